@@ -6,7 +6,8 @@ use log::*;
 use rand::{thread_rng, Rng};
 use rayon::prelude::*;
 use solana::gen_keys::GenKeys;
-use solana_client::perf_utils::{sample_txs, SampleStats};
+use solana_client::perf_report::{LatencyPercentiles, PerfReport};
+use solana_client::perf_utils::{sample_confirmations, sample_txs, SampleStats};
 use solana_drone::drone::request_airdrop_transaction;
 use solana_exchange_api::exchange_instruction;
 use solana_exchange_api::exchange_state::*;
@@ -15,7 +16,7 @@ use solana_metrics::datapoint_info;
 use solana_sdk::client::Client;
 use solana_sdk::client::SyncClient;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::{Keypair, KeypairUtil};
+use solana_sdk::signature::{Keypair, KeypairUtil, Signature};
 use solana_sdk::system_instruction;
 use solana_sdk::timing::{duration_as_ms, duration_as_s};
 use solana_sdk::transaction::Transaction;
@@ -98,7 +99,7 @@ pub fn create_client_accounts_file(
     file.write_all(&serialized.into_bytes()).unwrap();
 }
 
-pub fn do_bench_exchange<T>(clients: Vec<T>, config: Config)
+pub fn do_bench_exchange<T>(clients: Vec<T>, config: Config) -> PerfReport
 where
     T: 'static + Client + Send + Sync,
 {
@@ -192,18 +193,42 @@ where
     );
 
     let exit_signal = Arc::new(AtomicBool::new(false));
+
+    // Sample the confirmation latency of the first transaction of each chunk
+    let latencies = Arc::new(RwLock::new(Vec::new()));
+    let (confirmation_sender, confirmation_receiver) = channel();
+    let confirmation_thread = {
+        let exit_signal = exit_signal.clone();
+        let latencies = latencies.clone();
+        let client = clients[0].clone();
+        Builder::new()
+            .name("solana-exchange-confirmation".to_string())
+            .spawn(move || {
+                sample_confirmations(&exit_signal, &confirmation_receiver, &latencies, &client)
+            })
+            .unwrap()
+    };
+
     let shared_txs: SharedTransactions = Arc::new(RwLock::new(VecDeque::new()));
     let total_txs_sent_count = Arc::new(AtomicUsize::new(0));
+    let start = Instant::now();
     let s_threads: Vec<_> = (0..threads)
         .map(|_| {
             let exit_signal = exit_signal.clone();
             let shared_txs = shared_txs.clone();
             let total_txs_sent_count = total_txs_sent_count.clone();
+            let confirmation_sender = confirmation_sender.clone();
             let client = clients[0].clone();
             Builder::new()
                 .name("solana-exchange-transfer".to_string())
                 .spawn(move || {
-                    do_tx_transfers(&exit_signal, &shared_txs, &total_txs_sent_count, &client)
+                    do_tx_transfers(
+                        &exit_signal,
+                        &shared_txs,
+                        &total_txs_sent_count,
+                        &confirmation_sender,
+                        &client,
+                    )
                 })
                 .unwrap()
         })
@@ -287,17 +312,21 @@ where
     for t in sample_threads {
         let _ = t.join();
     }
+    let _ = confirmation_thread.join();
 
     compute_and_report_stats(
         &sample_stats,
+        &latencies.read().unwrap(),
+        &start.elapsed(),
         total_txs_sent_count.load(Ordering::Relaxed) as u64,
-    );
+    )
 }
 
 fn do_tx_transfers<T>(
     exit_signal: &Arc<AtomicBool>,
     shared_txs: &SharedTransactions,
     total_txs_sent_count: &Arc<AtomicUsize>,
+    confirmation_sender: &Sender<(Signature, Instant)>,
     client: &Arc<T>,
 ) where
     T: Client,
@@ -312,8 +341,12 @@ fn do_tx_transfers<T>(
             let n = txs0.len();
 
             let now = Instant::now();
-            for tx in txs0 {
-                client.async_send_transaction(tx).expect("Transfer");
+            for (i, tx) in txs0.into_iter().enumerate() {
+                let sent = Instant::now();
+                let signature = client.async_send_transaction(tx).expect("Transfer");
+                if i == 0 {
+                    let _ = confirmation_sender.send((signature, sent));
+                }
             }
             let duration = now.elapsed();
 
@@ -861,8 +894,15 @@ pub fn create_token_accounts(client: &Client, signers: &[Arc<Keypair>], accounts
     }
 }
 
-fn compute_and_report_stats(maxes: &Arc<RwLock<Vec<(String, SampleStats)>>>, total_txs_sent: u64) {
+fn compute_and_report_stats(
+    maxes: &Arc<RwLock<Vec<(String, SampleStats)>>>,
+    latencies: &[Duration],
+    elapsed: &Duration,
+    total_txs_sent: u64,
+) -> PerfReport {
     let mut max_txs = 0;
+    let mut max_tps = 0.0;
+    let mut tps_samples = vec![];
     let mut max_elapsed = Duration::new(0, 0);
     info!("|       Max TPS | Total Transactions");
     info!("+---------------+--------------------");
@@ -878,8 +918,12 @@ fn compute_and_report_stats(maxes: &Arc<RwLock<Vec<(String, SampleStats)>>>, tot
         if stats.elapsed > max_elapsed {
             max_elapsed = stats.elapsed;
         }
+        if stats.tps > max_tps {
+            max_tps = stats.tps;
+        }
         if stats.txs > max_txs {
             max_txs = stats.txs;
+            tps_samples = stats.tps_samples.clone();
         }
     }
     info!("+---------------+--------------------");
@@ -895,10 +939,30 @@ fn compute_and_report_stats(maxes: &Arc<RwLock<Vec<(String, SampleStats)>>>, tot
         total_txs_sent - max_txs,
         (total_txs_sent - max_txs) as f64 / total_txs_sent as f64 * 100_f64
     );
+    let average_tps = max_txs as f32 / max_elapsed.as_secs() as f32;
+    info!("\tAverage TPS: {:.2}", average_tps);
+
+    let confirmation_latency_ms = LatencyPercentiles::new(latencies);
     info!(
-        "\tAverage TPS: {:.2}",
-        max_txs as f32 / max_elapsed.as_secs() as f32
+        "\tConfirmation latency: p50 {}ms p90 {}ms p99 {}ms max {}ms, {} transactions sampled",
+        confirmation_latency_ms.p50,
+        confirmation_latency_ms.p90,
+        confirmation_latency_ms.p99,
+        confirmation_latency_ms.max,
+        confirmation_latency_ms.count,
     );
+
+    PerfReport {
+        name: "bench-exchange".to_string(),
+        duration_secs: duration_as_s(elapsed),
+        txs_sent: total_txs_sent,
+        txs_processed: max_txs,
+        txs_dropped: total_txs_sent - max_txs,
+        max_tps,
+        average_tps,
+        tps_samples,
+        confirmation_latency_ms,
+    }
 }
 
 fn generate_keypairs(num: u64) -> Vec<Keypair> {
@@ -1,8 +1,10 @@
 use clap::{crate_description, crate_name, crate_version, value_t, App, Arg, ArgMatches};
 use solana::gen_keys::GenKeys;
+use solana_client::perf_report::RegressionThresholds;
 use solana_drone::drone::DRONE_PORT;
 use solana_sdk::signature::{read_keypair, Keypair, KeypairUtil};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::process::exit;
 use std::time::Duration;
 
@@ -21,6 +23,9 @@ pub struct Config {
     pub client_ids_and_stake_file: String,
     pub write_to_client_file: bool,
     pub read_from_client_file: bool,
+    pub report_path: Option<PathBuf>,
+    pub baseline_path: Option<PathBuf>,
+    pub regression_thresholds: RegressionThresholds,
 }
 
 impl Default for Config {
@@ -40,6 +45,9 @@ impl Default for Config {
             client_ids_and_stake_file: String::new(),
             write_to_client_file: false,
             read_from_client_file: false,
+            report_path: None,
+            baseline_path: None,
+            regression_thresholds: RegressionThresholds::default(),
         }
     }
 }
@@ -147,6 +155,38 @@ pub fn build_args<'a, 'b>() -> App<'a, 'b> {
                 .default_value("10")
                 .help("Number of account groups to cycle for each batch"),
         )
+        .arg(
+            Arg::with_name("report")
+                .long("report")
+                .value_name("FILENAME")
+                .takes_value(true)
+                .help(
+                    "Write the results to FILENAME, as CSV if it ends in .csv and JSON otherwise",
+                ),
+        )
+        .arg(
+            Arg::with_name("baseline")
+                .long("baseline")
+                .value_name("FILENAME")
+                .takes_value(true)
+                .help("Exit with an error if the results regress from the JSON report in FILENAME"),
+        )
+        .arg(
+            Arg::with_name("max_tps_regression")
+                .long("max-tps-regression")
+                .value_name("PERCENT")
+                .takes_value(true)
+                .default_value("10")
+                .help("How far the average TPS may fall below the baseline's"),
+        )
+        .arg(
+            Arg::with_name("max_latency_regression")
+                .long("max-latency-regression")
+                .value_name("PERCENT")
+                .takes_value(true)
+                .default_value("25")
+                .help("How far the p99 confirmation latency may rise above the baseline's"),
+        )
         .arg(
             Arg::with_name("write-client-keys")
                 .long("write-client-keys")
@@ -214,5 +254,21 @@ pub fn extract_args<'a>(matches: &ArgMatches<'a>) -> Config {
         args.read_from_client_file = true;
         args.client_ids_and_stake_file = s.to_string();
     }
+
+    if let Some(s) = matches.value_of("report") {
+        args.report_path = Some(PathBuf::from(s));
+    }
+
+    if let Some(s) = matches.value_of("baseline") {
+        args.baseline_path = Some(PathBuf::from(s));
+    }
+
+    args.regression_thresholds = RegressionThresholds {
+        max_tps_regression: value_t!(matches, "max_tps_regression", f32)
+            .unwrap_or_else(|err| err.exit()),
+        max_latency_regression: value_t!(matches, "max_latency_regression", f32)
+            .unwrap_or_else(|err| err.exit()),
+    };
+
     args
 }
@@ -9,7 +9,9 @@ extern crate solana_exchange_program;
 use crate::bench::{airdrop_lamports, create_client_accounts_file, do_bench_exchange, Config};
 use log::*;
 use solana::gossip_service::{discover_cluster, get_multi_client};
+use solana_client::perf_report::PerfReport;
 use solana_sdk::signature::KeypairUtil;
+use std::process::exit;
 
 fn main() {
     solana_logger::setup();
@@ -33,6 +35,9 @@ fn main() {
         client_ids_and_stake_file,
         write_to_client_file,
         read_from_client_file,
+        report_path,
+        baseline_path,
+        regression_thresholds,
         ..
     } = cli_config;
 
@@ -82,6 +87,27 @@ fn main() {
                 fund_amount * (accounts_in_groups + 1) as u64 * NUM_SIGNERS,
             );
         }
-        do_bench_exchange(vec![client], config);
+        let report = do_bench_exchange(vec![client], config);
+
+        if let Some(report_path) = report_path {
+            report.write(&report_path).unwrap_or_else(|err| {
+                eprintln!("Failed to write report to {:?}: {}", report_path, err);
+                exit(1);
+            });
+        }
+
+        if let Some(baseline_path) = baseline_path {
+            let baseline = PerfReport::read(&baseline_path).unwrap_or_else(|err| {
+                eprintln!("Failed to read baseline from {:?}: {}", baseline_path, err);
+                exit(1);
+            });
+            let regressions = report.regressions(&baseline, &regression_thresholds);
+            for regression in &regressions {
+                eprintln!("Regression: {}", regression);
+            }
+            if !regressions.is_empty() {
+                exit(1);
+            }
+        }
     }
 }
@@ -1,19 +1,20 @@
+use crate::workload::{ramp_up_tx_count, Workload};
 use solana_metrics;
 
 use bincode;
 use log::*;
 use rayon::prelude::*;
 use solana::gen_keys::GenKeys;
-use solana_client::perf_utils::{sample_txs, SampleStats};
+use solana_client::perf_report::{LatencyPercentiles, PerfReport};
+use solana_client::perf_utils::{sample_confirmations, sample_txs, SampleStats};
 use solana_drone::drone::request_airdrop_transaction;
 use solana_measure::measure::Measure;
 use solana_metrics::datapoint_info;
 use solana_sdk::client::Client;
 use solana_sdk::hash::Hash;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::{Keypair, KeypairUtil};
+use solana_sdk::signature::{Keypair, KeypairUtil, Signature};
 use solana_sdk::system_instruction;
-use solana_sdk::system_transaction;
 use solana_sdk::timing::timestamp;
 use solana_sdk::timing::{duration_as_ms, duration_as_s};
 use solana_sdk::transaction::Transaction;
@@ -21,6 +22,7 @@ use std::cmp;
 use std::collections::VecDeque;
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, RwLock};
 use std::thread::sleep;
 use std::thread::Builder;
@@ -49,6 +51,9 @@ pub struct Config {
     pub tx_count: usize,
     pub sustained: bool,
     pub use_move: bool,
+    pub workload: Workload,
+    /// How long the batches take to grow to `tx_count` transactions
+    pub ramp_up: Duration,
 }
 
 impl Default for Config {
@@ -61,6 +66,8 @@ impl Default for Config {
             tx_count: 500_000,
             sustained: false,
             use_move: false,
+            workload: Workload::default(),
+            ramp_up: Duration::from_secs(0),
         }
     }
 }
@@ -72,7 +79,7 @@ pub fn do_bench_tps<T>(
     keypair0_balance: u64,
     program_id: &Pubkey,
     libra_mint_id: &Pubkey,
-) -> u64
+) -> PerfReport
 where
     T: 'static + Client + Send + Sync,
 {
@@ -84,6 +91,8 @@ where
         tx_count,
         use_move,
         sustained,
+        workload,
+        ramp_up,
     } = config;
 
     let clients: Vec<_> = clients.into_iter().map(Arc::new).collect();
@@ -117,6 +126,21 @@ where
         })
         .collect();
 
+    // Sample the confirmation latency of the first transaction of each batch
+    let latencies = Arc::new(RwLock::new(Vec::new()));
+    let (confirmation_sender, confirmation_receiver) = channel();
+    let confirmation_thread = {
+        let exit_signal = exit_signal.clone();
+        let latencies = latencies.clone();
+        let client = client.clone();
+        Builder::new()
+            .name("solana-client-confirmation".to_string())
+            .spawn(move || {
+                sample_confirmations(&exit_signal, &confirmation_receiver, &latencies, &client);
+            })
+            .unwrap()
+    };
+
     let shared_txs: SharedTransactions = Arc::new(RwLock::new(VecDeque::new()));
 
     let shared_tx_active_thread_count = Arc::new(AtomicIsize::new(0));
@@ -128,6 +152,7 @@ where
             let shared_txs = shared_txs.clone();
             let shared_tx_active_thread_count = shared_tx_active_thread_count.clone();
             let total_tx_sent_count = total_tx_sent_count.clone();
+            let confirmation_sender = confirmation_sender.clone();
             let client = client.clone();
             Builder::new()
                 .name("solana-client-sender".to_string())
@@ -137,6 +162,7 @@ where
                         &shared_txs,
                         &shared_tx_active_thread_count,
                         &total_tx_sent_count,
+                        &confirmation_sender,
                         thread_batch_sleep_ms,
                         &client,
                     );
@@ -155,7 +181,7 @@ where
         // ping-pong between source and destination accounts for each loop iteration
         // this seems to be faster than trying to determine the balance of individual
         // accounts
-        let len = tx_count as usize;
+        let len = ramp_up_tx_count(tx_count, ramp_up, start.elapsed());
         if let Ok((new_blockhash, _fee_calculator)) = client.get_new_blockhash(&blockhash) {
             blockhash = new_blockhash;
         } else {
@@ -172,10 +198,11 @@ where
             &shared_txs,
             &blockhash,
             &keypairs[..len],
-            &keypairs[len..],
+            &keypairs[tx_count..tx_count + len],
             threads,
             reclaim_lamports_back_to_source_account,
             use_move,
+            &workload,
             &program_id,
             &libra_mint_id,
         );
@@ -211,19 +238,20 @@ where
             println!("  join() failed with: {:?}", err);
         }
     }
+    if let Err(err) = confirmation_thread.join() {
+        println!("  join() failed with: {:?}", err);
+    }
 
     let balance = client.get_balance(&id.pubkey()).unwrap_or(0);
     metrics_submit_lamport_balance(balance);
 
     compute_and_report_stats(
         &maxes,
+        &latencies.read().unwrap(),
         sample_period,
         &start.elapsed(),
         total_tx_sent_count.load(Ordering::Relaxed),
-    );
-
-    let r_maxes = maxes.read().unwrap();
-    r_maxes.first().unwrap().1.txs
+    )
 }
 
 fn metrics_submit_lamport_balance(lamport_balance: u64) {
@@ -234,6 +262,7 @@ fn metrics_submit_lamport_balance(lamport_balance: u64) {
     );
 }
 
+#[allow(clippy::too_many_arguments)]
 fn generate_txs(
     shared_txs: &SharedTransactions,
     blockhash: &Hash,
@@ -242,6 +271,7 @@ fn generate_txs(
     threads: usize,
     reclaim: bool,
     use_move: bool,
+    workload: &Workload,
     libra_pay_program_id: &Pubkey,
     libra_mint_id: &Pubkey,
 ) {
@@ -249,11 +279,9 @@ fn generate_txs(
     println!("Signing transactions... {} (reclaim={})", tx_count, reclaim);
     let signing_start = Instant::now();
 
-    let pairs: Vec<_> = if !reclaim {
-        source.iter().zip(dest.iter()).collect()
-    } else {
-        dest.iter().zip(source.iter()).collect()
-    };
+    // Large data workloads allocate accounts at addresses derived from the batch's seed
+    let seed = timestamp().to_string();
+    let pairs = workload.pairs(source, dest, reclaim);
     let transactions: Vec<_> = pairs
         .par_iter()
         .map(|(id, keypair)| {
@@ -272,7 +300,7 @@ fn generate_txs(
                 )
             } else {
                 (
-                    system_transaction::create_user_account(id, &keypair.pubkey(), 1, *blockhash),
+                    workload.transaction(id, &keypair.pubkey(), &seed, *blockhash),
                     timestamp(),
                 )
             }
@@ -295,7 +323,7 @@ fn generate_txs(
         ("duration", duration_as_ms(&duration), i64)
     );
 
-    let sz = transactions.len() / threads;
+    let sz = cmp::max(1, transactions.len() / threads);
     let chunks: Vec<_> = transactions.chunks(sz).collect();
     {
         let mut shared_txs_wl = shared_txs.write().unwrap();
@@ -310,6 +338,7 @@ fn do_tx_transfers<T: Client>(
     shared_txs: &SharedTransactions,
     shared_tx_thread_count: &Arc<AtomicIsize>,
     total_tx_sent_count: &Arc<AtomicUsize>,
+    confirmation_sender: &Sender<(Signature, Instant)>,
     thread_batch_sleep_ms: usize,
     client: &Arc<T>,
 ) {
@@ -331,14 +360,20 @@ fn do_tx_transfers<T: Client>(
             );
             let tx_len = txs0.len();
             let transfer_start = Instant::now();
+            let mut sampled = false;
             for tx in txs0 {
                 let now = timestamp();
                 if now > tx.1 && now - tx.1 > 1000 * 30 {
                     continue;
                 }
-                client
+                let sent = Instant::now();
+                let signature = client
                     .async_send_transaction(tx.0)
                     .expect("async_send_transaction in do_tx_transfers");
+                if !sampled {
+                    let _ = confirmation_sender.send((signature, sent));
+                    sampled = true;
+                }
             }
             shared_tx_thread_count.fetch_add(-1, Ordering::Relaxed);
             total_tx_sent_count.fetch_add(tx_len, Ordering::Relaxed);
@@ -542,13 +577,15 @@ pub fn airdrop_lamports<T: Client>(
 
 fn compute_and_report_stats(
     maxes: &Arc<RwLock<Vec<(String, SampleStats)>>>,
+    latencies: &[Duration],
     sample_period: u64,
     tx_send_elapsed: &Duration,
     total_tx_send_count: usize,
-) {
+) -> PerfReport {
     // Compute/report stats
     let mut max_of_maxes = 0.0;
     let mut max_tx_count = 0;
+    let mut tps_samples = vec![];
     let mut nodes_with_zero_tps = 0;
     let mut total_maxes = 0.0;
     println!(" Node address        |       Max TPS | Total Transactions");
@@ -575,6 +612,7 @@ fn compute_and_report_stats(
         }
         if stats.txs > max_tx_count {
             max_tx_count = stats.txs;
+            tps_samples = stats.tps_samples.clone();
         }
     }
 
@@ -601,10 +639,30 @@ fn compute_and_report_stats(
         maxes.read().unwrap().len(),
         drop_rate,
     );
+    let average_tps = max_tx_count as f32 / duration_as_s(tx_send_elapsed);
+    println!("\tAverage TPS: {}", average_tps);
+
+    let confirmation_latency_ms = LatencyPercentiles::new(latencies);
     println!(
-        "\tAverage TPS: {}",
-        max_tx_count as f32 / duration_as_s(tx_send_elapsed)
+        "\tConfirmation latency: p50 {}ms p90 {}ms p99 {}ms max {}ms, {} transactions sampled",
+        confirmation_latency_ms.p50,
+        confirmation_latency_ms.p90,
+        confirmation_latency_ms.p99,
+        confirmation_latency_ms.max,
+        confirmation_latency_ms.count,
     );
+
+    PerfReport {
+        name: "bench-tps".to_string(),
+        duration_secs: duration_as_s(tx_send_elapsed),
+        txs_sent: total_tx_send_count,
+        txs_processed: max_tx_count,
+        txs_dropped: total_tx_send_count.saturating_sub(max_tx_count),
+        max_tps: max_of_maxes,
+        average_tps,
+        tps_samples,
+        confirmation_latency_ms,
+    }
 }
 
 // First transfer 3/4 of the lamports to the dest accounts
@@ -906,7 +964,7 @@ mod tests {
         )
         .unwrap();
 
-        let total = do_bench_tps(
+        let report = do_bench_tps(
             vec![client],
             config,
             keypairs,
@@ -914,7 +972,7 @@ mod tests {
             &libra_pay_program_id,
             &cluster.libra_mint_keypair.pubkey(),
         );
-        assert!(total > 100);
+        assert!(report.txs_processed > 100);
     }
 
     #[test]
@@ -962,6 +1020,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bench_tps_bank_client_contention() {
+        let (genesis_block, id) = create_genesis_block(10_000);
+        let bank = Bank::new(&genesis_block);
+        let clients = vec![BankClient::new(bank)];
+
+        let mut config = Config::default();
+        config.id = id;
+        config.tx_count = 10;
+        config.duration = Duration::from_secs(5);
+        config.workload = Workload::Contention { accounts: 2 };
+        config.ramp_up = Duration::from_secs(2);
+
+        let (keypairs, _keypair_balance) =
+            generate_and_fund_keypairs(&clients[0], None, &config.id, config.tx_count, 20, None)
+                .unwrap();
+
+        do_bench_tps(
+            clients,
+            config,
+            keypairs,
+            0,
+            &Pubkey::default(),
+            &Pubkey::default(),
+        );
+    }
+
     #[test]
     fn test_bench_tps_fund_keys() {
         let (genesis_block, id) = create_genesis_block(10_000);
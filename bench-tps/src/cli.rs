@@ -1,10 +1,14 @@
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::process::exit;
 use std::time::Duration;
 
-use clap::{crate_description, crate_name, crate_version, App, Arg, ArgMatches};
+use crate::workload::{Workload, WORKLOADS};
+use clap::{crate_description, crate_name, crate_version, value_t, App, Arg, ArgMatches};
+use solana_client::perf_report::RegressionThresholds;
 use solana_drone::drone::DRONE_PORT;
 use solana_sdk::fee_calculator::FeeCalculator;
+use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{read_keypair, Keypair, KeypairUtil};
 
 /// Holds the configuration for a single run of the benchmark
@@ -23,6 +27,11 @@ pub struct Config {
     pub read_from_client_file: bool,
     pub target_lamports_per_signature: u64,
     pub use_move: bool,
+    pub workload: Workload,
+    pub ramp_up: Duration,
+    pub report_path: Option<PathBuf>,
+    pub baseline_path: Option<PathBuf>,
+    pub regression_thresholds: RegressionThresholds,
 }
 
 impl Default for Config {
@@ -42,6 +51,11 @@ impl Default for Config {
             read_from_client_file: false,
             target_lamports_per_signature: FeeCalculator::default().target_lamports_per_signature,
             use_move: false,
+            workload: Workload::default(),
+            ramp_up: Duration::from_secs(0),
+            report_path: None,
+            baseline_path: None,
+            regression_thresholds: RegressionThresholds::default(),
         }
     }
 }
@@ -107,6 +121,48 @@ pub fn build_args<'a, 'b>() -> App<'a, 'b> {
                 .long("use-move")
                 .help("Use Move language transactions to perform transfers."),
         )
+        .arg(
+            Arg::with_name("workload")
+                .long("workload")
+                .value_name("WORKLOAD")
+                .takes_value(true)
+                .possible_values(WORKLOADS)
+                .default_value("transfer")
+                .help("Transactions to send: transfers between disjoint accounts, transfers \
+                       contending for --contention-accounts accounts, transfers invoking \
+                       --program-id, or transfers allocating --data-size bytes in a new account"),
+        )
+        .arg(
+            Arg::with_name("contention_accounts")
+                .long("contention-accounts")
+                .value_name("NUM")
+                .takes_value(true)
+                .default_value("1")
+                .help("Number of accounts the transactions of a contention workload write to"),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .required_if("workload", "program")
+                .help("Program a program workload invokes"),
+        )
+        .arg(
+            Arg::with_name("data_size")
+                .long("data-size")
+                .value_name("BYTES")
+                .takes_value(true)
+                .default_value("1024")
+                .help("Bytes each transaction of a large-data workload allocates"),
+        )
+        .arg(
+            Arg::with_name("ramp_up")
+                .long("ramp-up")
+                .value_name("SECS")
+                .takes_value(true)
+                .help("Grow the batches linearly to --tx_count transactions over SECS seconds"),
+        )
         .arg(
             Arg::with_name("tx_count")
                 .long("tx_count")
@@ -136,6 +192,36 @@ pub fn build_args<'a, 'b>() -> App<'a, 'b> {
                 .takes_value(true)
                 .help("Read client keys and stakes from the YAML file"),
         )
+        .arg(
+            Arg::with_name("report")
+                .long("report")
+                .value_name("FILENAME")
+                .takes_value(true)
+                .help("Write the results to FILENAME, as CSV if it ends in .csv and JSON otherwise"),
+        )
+        .arg(
+            Arg::with_name("baseline")
+                .long("baseline")
+                .value_name("FILENAME")
+                .takes_value(true)
+                .help("Exit with an error if the results regress from the JSON report in FILENAME"),
+        )
+        .arg(
+            Arg::with_name("max_tps_regression")
+                .long("max-tps-regression")
+                .value_name("PERCENT")
+                .takes_value(true)
+                .default_value("10")
+                .help("How far the average TPS may fall below the baseline's"),
+        )
+        .arg(
+            Arg::with_name("max_latency_regression")
+                .long("max-latency-regression")
+                .value_name("PERCENT")
+                .takes_value(true)
+                .default_value("25")
+                .help("How far the p99 confirmation latency may rise above the baseline's"),
+        )
         .arg(
             Arg::with_name("target_lamports_per_signature")
                 .long("target-lamports-per-signature")
@@ -220,5 +306,38 @@ pub fn extract_args<'a>(matches: &ArgMatches<'a>) -> Config {
 
     args.use_move = matches.is_present("use-move");
 
+    args.workload = match matches.value_of("workload").unwrap() {
+        "contention" => Workload::Contention {
+            accounts: value_t!(matches, "contention_accounts", usize)
+                .unwrap_or_else(|err| err.exit()),
+        },
+        "program" => Workload::Program {
+            program_id: value_t!(matches, "program_id", Pubkey).unwrap_or_else(|err| err.exit()),
+        },
+        "large-data" => Workload::LargeData {
+            space: value_t!(matches, "data_size", u64).unwrap_or_else(|err| err.exit()),
+        },
+        _ => Workload::Transfer,
+    };
+
+    if let Some(secs) = matches.value_of("ramp_up") {
+        args.ramp_up = Duration::new(secs.to_string().parse().expect("can't parse ramp-up"), 0);
+    }
+
+    if let Some(s) = matches.value_of("report") {
+        args.report_path = Some(PathBuf::from(s));
+    }
+
+    if let Some(s) = matches.value_of("baseline") {
+        args.baseline_path = Some(PathBuf::from(s));
+    }
+
+    args.regression_thresholds = RegressionThresholds {
+        max_tps_regression: value_t!(matches, "max_tps_regression", f32)
+            .unwrap_or_else(|err| err.exit()),
+        max_latency_regression: value_t!(matches, "max_latency_regression", f32)
+            .unwrap_or_else(|err| err.exit()),
+    };
+
     args
 }
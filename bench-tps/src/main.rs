@@ -1,10 +1,12 @@
 mod bench;
 mod cli;
+mod workload;
 
 use crate::bench::{
     do_bench_tps, generate_and_fund_keypairs, generate_keypairs, Config, NUM_LAMPORTS_PER_ACCOUNT,
 };
 use solana::gossip_service::{discover_cluster, get_multi_client};
+use solana_client::perf_report::PerfReport;
 use solana_sdk::fee_calculator::FeeCalculator;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, KeypairUtil};
@@ -39,6 +41,11 @@ fn main() {
         read_from_client_file,
         target_lamports_per_signature,
         use_move,
+        workload,
+        ramp_up,
+        report_path,
+        baseline_path,
+        regression_thresholds,
     } = cli_config;
 
     if write_to_client_file {
@@ -121,9 +128,11 @@ fn main() {
         tx_count,
         sustained,
         use_move,
+        workload,
+        ramp_up,
     };
 
-    do_bench_tps(
+    let report = do_bench_tps(
         vec![client],
         config,
         keypairs,
@@ -131,4 +140,25 @@ fn main() {
         &Pubkey::new_rand(),
         &Pubkey::new_rand(),
     );
+
+    if let Some(report_path) = report_path {
+        report.write(&report_path).unwrap_or_else(|err| {
+            eprintln!("Failed to write report to {:?}: {}", report_path, err);
+            exit(1);
+        });
+    }
+
+    if let Some(baseline_path) = baseline_path {
+        let baseline = PerfReport::read(&baseline_path).unwrap_or_else(|err| {
+            eprintln!("Failed to read baseline from {:?}: {}", baseline_path, err);
+            exit(1);
+        });
+        let regressions = report.regressions(&baseline, &regression_thresholds);
+        for regression in &regressions {
+            eprintln!("Regression: {}", regression);
+        }
+        if !regressions.is_empty() {
+            exit(1);
+        }
+    }
 }
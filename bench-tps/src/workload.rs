@@ -0,0 +1,168 @@
+//! The `workload` module builds the transactions bench-tps sends. Beyond transfers between
+//! disjoint pairs of accounts, a workload can make the transactions of a batch contend for a
+//! few accounts, invoke a program, or write large account data, to load the banking stage the
+//! way real traffic does.
+
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, KeypairUtil};
+use solana_sdk::system_instruction;
+use solana_sdk::system_program;
+use solana_sdk::transaction::Transaction;
+use std::cmp;
+use std::time::Duration;
+
+pub const WORKLOADS: &[&str] = &["transfer", "contention", "program", "large-data"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Workload {
+    /// Transfer a lamport between disjoint pairs of accounts
+    Transfer,
+    /// Transfer a lamport into one of `accounts` destinations, so every transaction of a batch
+    /// write-locks an account that `1 / accounts` of the others lock too
+    Contention { accounts: usize },
+    /// Transfer a lamport and invoke `program_id`, which is passed the source account
+    Program { program_id: Pubkey },
+    /// Transfer a lamport and allocate `space` bytes in a new account derived from the source.
+    /// The new account keeps a lamport of the source.
+    LargeData { space: u64 },
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Workload::Transfer
+    }
+}
+
+impl Workload {
+    /// The (from, to) keypairs of each transaction of a batch
+    pub fn pairs<'a>(
+        &self,
+        source: &'a [Keypair],
+        dest: &'a [Keypair],
+        reclaim: bool,
+    ) -> Vec<(&'a Keypair, &'a Keypair)> {
+        let pairs: Vec<_> = match self {
+            Workload::Contention { accounts } => {
+                let accounts = cmp::max(1, cmp::min(*accounts, dest.len()));
+                source
+                    .iter()
+                    .enumerate()
+                    .map(|(i, keypair)| (keypair, &dest[i % accounts]))
+                    .collect()
+            }
+            _ => source.iter().zip(dest.iter()).collect(),
+        };
+        if reclaim {
+            pairs.into_iter().map(|(from, to)| (to, from)).collect()
+        } else {
+            pairs
+        }
+    }
+
+    /// A transaction of the workload from `from` to `to`. `seed` must be unique to the batch, so
+    /// large data workloads allocate new accounts every batch.
+    pub fn transaction(
+        &self,
+        from: &Keypair,
+        to: &Pubkey,
+        seed: &str,
+        blockhash: Hash,
+    ) -> Transaction {
+        let mut instructions = vec![system_instruction::transfer(&from.pubkey(), to, 1)];
+        match self {
+            Workload::Transfer | Workload::Contention { .. } => (),
+            Workload::Program { program_id } => {
+                instructions.push(Instruction::new(
+                    *program_id,
+                    &(),
+                    vec![AccountMeta::new(from.pubkey(), true)],
+                ));
+            }
+            Workload::LargeData { space } => {
+                let program_id = system_program::id();
+                let address = Pubkey::create_with_seed(&from.pubkey(), seed, &program_id).unwrap();
+                instructions.push(system_instruction::transfer(&from.pubkey(), &address, 1));
+                instructions.push(system_instruction::allocate_with_seed(
+                    &address,
+                    &from.pubkey(),
+                    seed,
+                    *space,
+                    &program_id,
+                ));
+            }
+        }
+        Transaction::new_signed_instructions(&[from], instructions, blockhash)
+    }
+}
+
+/// The number of transactions in a batch sent `elapsed` into the benchmark, which grows linearly
+/// from 1 to `tx_count` over `ramp_up`
+pub fn ramp_up_tx_count(tx_count: usize, ramp_up: Duration, elapsed: Duration) -> usize {
+    if elapsed >= ramp_up {
+        return tx_count;
+    }
+    let ramped = tx_count as u128 * elapsed.as_millis() / ramp_up.as_millis();
+    cmp::max(1, ramped as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contention_pairs() {
+        let source: Vec<_> = (0..4).map(|_| Keypair::new()).collect();
+        let dest: Vec<_> = (0..4).map(|_| Keypair::new()).collect();
+        let workload = Workload::Contention { accounts: 2 };
+
+        let pairs = workload.pairs(&source, &dest, false);
+        let to: Vec<_> = pairs.iter().map(|(_, to)| to.pubkey()).collect();
+        assert_eq!(
+            to,
+            vec![
+                dest[0].pubkey(),
+                dest[1].pubkey(),
+                dest[0].pubkey(),
+                dest[1].pubkey()
+            ]
+        );
+
+        // Reclaiming returns the lamports to every source
+        let pairs = workload.pairs(&source, &dest, true);
+        let to: Vec<_> = pairs.iter().map(|(_, to)| to.pubkey()).collect();
+        let source_pubkeys: Vec<_> = source.iter().map(|keypair| keypair.pubkey()).collect();
+        assert_eq!(to, source_pubkeys);
+
+        // There can't be more hot accounts than destinations
+        let workload = Workload::Contention { accounts: 10 };
+        let pairs = workload.pairs(&source, &dest, false);
+        assert_eq!(pairs[3].1.pubkey(), dest[3].pubkey());
+    }
+
+    #[test]
+    fn test_large_data_transaction() {
+        let from = Keypair::new();
+        let to = Pubkey::new_rand();
+        let workload = Workload::LargeData { space: 1024 };
+        let tx = workload.transaction(&from, &to, "0", Hash::default());
+        let address = Pubkey::create_with_seed(&from.pubkey(), "0", &system_program::id()).unwrap();
+        assert_eq!(tx.message().instructions.len(), 3);
+        assert!(tx.message().account_keys.contains(&address));
+        assert_eq!(tx.signatures.len(), 1);
+    }
+
+    #[test]
+    fn test_ramp_up_tx_count() {
+        let ramp_up = Duration::from_secs(10);
+        assert_eq!(ramp_up_tx_count(100, ramp_up, Duration::from_secs(0)), 1);
+        assert_eq!(ramp_up_tx_count(100, ramp_up, Duration::from_secs(5)), 50);
+        assert_eq!(ramp_up_tx_count(100, ramp_up, Duration::from_secs(10)), 100);
+        assert_eq!(ramp_up_tx_count(100, ramp_up, Duration::from_secs(20)), 100);
+        assert_eq!(
+            ramp_up_tx_count(100, Duration::from_secs(0), Duration::from_secs(0)),
+            100
+        );
+    }
+}
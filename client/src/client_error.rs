@@ -9,6 +9,7 @@ pub enum ClientError {
     RpcError(rpc_request::RpcError),
     SerdeJson(serde_json::error::Error),
     TransactionError(TransactionError),
+    Ws(ws::Error),
 }
 
 impl fmt::Display for ClientError {
@@ -48,3 +49,9 @@ impl From<TransactionError> for ClientError {
         ClientError::TransactionError(err)
     }
 }
+
+impl From<ws::Error> for ClientError {
+    fn from(err: ws::Error) -> ClientError {
+        ClientError::Ws(err)
+    }
+}
@@ -1,8 +1,12 @@
 use crate::client_error::ClientError;
 use crate::generic_rpc_client_request::GenericRpcClientRequest;
 use crate::rpc_request::RpcRequest;
-use serde_json::{Number, Value};
+use bincode::serialize;
+use serde_json::{json, Number, Value};
 use solana_sdk::fee_calculator::FeeCalculator;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::{Keypair, KeypairUtil};
+use solana_sdk::system_transaction;
 use solana_sdk::transaction::{self, TransactionError};
 
 pub const PUBKEY: &str = "7RoSF9fUmdphVCpabEoefH81WwrW7orsWonXWqTXkKV8";
@@ -41,6 +45,27 @@ impl GenericRpcClientRequest for MockRpcClientRequest {
                     Value::Null
                 }
             }
+            RpcRequest::GetConfirmedSignaturesForAddress => {
+                Value::Array(vec![Value::String(SIGNATURE.to_string())])
+            }
+            // A transfer of 50 lamports to PUBKEY, from a payer that paid a fee of 5
+            RpcRequest::GetConfirmedTransaction => {
+                let from = Keypair::new();
+                let to = PUBKEY.parse().unwrap();
+                let transaction = system_transaction::transfer(&from, &to, 50, Hash::default());
+                json!({
+                    "slot": 1,
+                    "transaction": bs58::encode(serialize(&transaction).unwrap()).into_string(),
+                    "status": {"Ok": null},
+                    "meta": {
+                        "status": {"Ok": null},
+                        "fee": 5,
+                        "preBalances": [100, 0, 1],
+                        "postBalances": [45, 50, 1],
+                    },
+                    "returnData": null,
+                })
+            }
             RpcRequest::GetBalance => {
                 let n = if self.url == "airdrop" { 0 } else { 50 };
                 Value::Number(Number::from(n))
@@ -61,6 +86,7 @@ impl GenericRpcClientRequest for MockRpcClientRequest {
             }
             RpcRequest::GetTransactionCount => Value::Number(Number::from(1234)),
             RpcRequest::GetSlot => Value::Number(Number::from(0)),
+            RpcRequest::RequestAirdrop => Value::String(SIGNATURE.to_string()),
             RpcRequest::SendTransaction => Value::String(SIGNATURE.to_string()),
             _ => Value::Null,
         };
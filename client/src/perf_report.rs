@@ -0,0 +1,238 @@
+//! The `perf_report` module holds the results of a benchmark run in a form other tools can
+//! read, and compares them against the results of an earlier run to catch regressions.
+
+use serde_derive::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Confirmation latencies of the transactions a benchmark sampled, in milliseconds
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    pub count: usize,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+impl LatencyPercentiles {
+    pub fn new(latencies: &[Duration]) -> Self {
+        let mut latencies: Vec<_> = latencies
+            .iter()
+            .map(|latency| latency.as_millis() as u64)
+            .collect();
+        latencies.sort();
+        let percentile = |p: usize| {
+            if latencies.is_empty() {
+                0
+            } else {
+                latencies[(latencies.len() - 1) * p / 100]
+            }
+        };
+        Self {
+            count: latencies.len(),
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: percentile(100),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PerfReport {
+    /// The benchmark that produced the report
+    pub name: String,
+    pub duration_secs: f32,
+    /// Transactions the benchmark sent
+    pub txs_sent: u64,
+    /// Transactions the busiest node processed
+    pub txs_processed: u64,
+    /// Transactions sent that no node processed
+    pub txs_dropped: u64,
+    pub max_tps: f32,
+    pub average_tps: f32,
+    /// The TPS of the busiest node in each sample period
+    pub tps_samples: Vec<f32>,
+    pub confirmation_latency_ms: LatencyPercentiles,
+}
+
+/// How much worse than a baseline a run may be, in percent
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegressionThresholds {
+    pub max_tps_regression: f32,
+    pub max_latency_regression: f32,
+}
+
+impl Default for RegressionThresholds {
+    fn default() -> Self {
+        Self {
+            max_tps_regression: 10.0,
+            max_latency_regression: 25.0,
+        }
+    }
+}
+
+fn json_error(err: serde_json::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err)
+}
+
+impl PerfReport {
+    pub fn drop_rate(&self) -> f64 {
+        if self.txs_sent == 0 {
+            0.0
+        } else {
+            self.txs_dropped as f64 / self.txs_sent as f64
+        }
+    }
+
+    /// Writes the report as CSV if `path` ends in `.csv`, as JSON otherwise
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        if path
+            .extension()
+            .map_or(false, |extension| extension == "csv")
+        {
+            file.write_all(self.to_csv().as_bytes())
+        } else {
+            serde_json::to_writer_pretty(&mut file, self).map_err(json_error)?;
+            writeln!(file)
+        }
+    }
+
+    /// Reads a report written as JSON
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        serde_json::from_reader(file).map_err(json_error)
+    }
+
+    /// The report as `metric,value` rows, followed by a `tps_sample_<n>` row per sample period
+    pub fn to_csv(&self) -> String {
+        let latency = &self.confirmation_latency_ms;
+        let mut rows = vec![
+            "metric,value".to_string(),
+            format!("name,{}", self.name),
+            format!("duration_secs,{}", self.duration_secs),
+            format!("txs_sent,{}", self.txs_sent),
+            format!("txs_processed,{}", self.txs_processed),
+            format!("txs_dropped,{}", self.txs_dropped),
+            format!("max_tps,{}", self.max_tps),
+            format!("average_tps,{}", self.average_tps),
+            format!("confirmation_latency_count,{}", latency.count),
+            format!("confirmation_latency_p50_ms,{}", latency.p50),
+            format!("confirmation_latency_p90_ms,{}", latency.p90),
+            format!("confirmation_latency_p99_ms,{}", latency.p99),
+            format!("confirmation_latency_max_ms,{}", latency.max),
+        ];
+        rows.extend(
+            self.tps_samples
+                .iter()
+                .enumerate()
+                .map(|(i, tps)| format!("tps_sample_{},{}", i, tps)),
+        );
+        rows.join("\n") + "\n"
+    }
+
+    /// Describes each way this report regressed from `baseline` past `thresholds`
+    pub fn regressions(
+        &self,
+        baseline: &PerfReport,
+        thresholds: &RegressionThresholds,
+    ) -> Vec<String> {
+        let mut regressions = vec![];
+        let min_tps = baseline.average_tps * (1.0 - thresholds.max_tps_regression / 100.0);
+        if self.average_tps < min_tps {
+            regressions.push(format!(
+                "average TPS {:.2} is below {:.2}, {}% under the baseline's {:.2}",
+                self.average_tps, min_tps, thresholds.max_tps_regression, baseline.average_tps
+            ));
+        }
+        // Only compare latencies both runs measured
+        if self.confirmation_latency_ms.count > 0 && baseline.confirmation_latency_ms.count > 0 {
+            let max_p99 = baseline.confirmation_latency_ms.p99 as f32
+                * (1.0 + thresholds.max_latency_regression / 100.0);
+            if self.confirmation_latency_ms.p99 as f32 > max_p99 {
+                regressions.push(format!(
+                    "p99 confirmation latency {}ms is above {:.0}ms, {}% over the baseline's {}ms",
+                    self.confirmation_latency_ms.p99,
+                    max_p99,
+                    thresholds.max_latency_regression,
+                    baseline.confirmation_latency_ms.p99
+                ));
+            }
+        }
+        regressions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_percentiles() {
+        assert_eq!(LatencyPercentiles::new(&[]), LatencyPercentiles::default());
+
+        let latencies: Vec<_> = (1..=100).rev().map(Duration::from_millis).collect();
+        let percentiles = LatencyPercentiles::new(&latencies);
+        assert_eq!(percentiles.count, 100);
+        assert_eq!(percentiles.p50, 50);
+        assert_eq!(percentiles.p90, 90);
+        assert_eq!(percentiles.p99, 99);
+        assert_eq!(percentiles.max, 100);
+    }
+
+    #[test]
+    fn test_regressions() {
+        let baseline = PerfReport {
+            average_tps: 1000.0,
+            confirmation_latency_ms: LatencyPercentiles {
+                count: 10,
+                p99: 1000,
+                ..LatencyPercentiles::default()
+            },
+            ..PerfReport::default()
+        };
+        let thresholds = RegressionThresholds::default();
+        let mut report = baseline.clone();
+        assert!(report.regressions(&baseline, &thresholds).is_empty());
+
+        report.average_tps = 950.0;
+        report.confirmation_latency_ms.p99 = 1200;
+        assert!(report.regressions(&baseline, &thresholds).is_empty());
+
+        report.average_tps = 850.0;
+        assert_eq!(report.regressions(&baseline, &thresholds).len(), 1);
+        report.confirmation_latency_ms.p99 = 1300;
+        assert_eq!(report.regressions(&baseline, &thresholds).len(), 2);
+
+        // Latencies the baseline didn't measure aren't compared
+        report.average_tps = 1000.0;
+        report.confirmation_latency_ms.count = 0;
+        assert!(report.regressions(&baseline, &thresholds).is_empty());
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let report = PerfReport {
+            name: "bench".to_string(),
+            txs_sent: 10,
+            txs_processed: 8,
+            txs_dropped: 2,
+            tps_samples: vec![1.0, 2.5],
+            ..PerfReport::default()
+        };
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("perf-report-{}.json", std::process::id()));
+        report.write(&path).unwrap();
+        assert_eq!(PerfReport::read(&path).unwrap(), report);
+        std::fs::remove_file(&path).unwrap();
+
+        let csv = report.to_csv();
+        assert!(csv.starts_with("metric,value\nname,bench\n"));
+        assert!(csv.ends_with("tps_sample_0,1\ntps_sample_1,2.5\n"));
+        assert!((report.drop_rate() - 0.2).abs() < std::f64::EPSILON);
+    }
+}
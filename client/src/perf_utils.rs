@@ -1,7 +1,9 @@
 use log::*;
 use solana_sdk::client::Client;
+use solana_sdk::signature::Signature;
 use solana_sdk::timing::duration_as_s;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
 use std::sync::{Arc, RwLock};
 use std::thread::sleep;
 use std::time::{Duration, Instant};
@@ -14,8 +16,13 @@ pub struct SampleStats {
     pub elapsed: Duration,
     /// Total transactions reported by this node
     pub txs: u64,
+    /// TPS reported by this node in each sample period
+    pub tps_samples: Vec<f32>,
 }
 
+/// How long a sampled transaction has to be confirmed before it's left out of the latencies
+pub const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub fn sample_txs<T>(
     exit_signal: &Arc<AtomicBool>,
     sample_stats: &Arc<RwLock<Vec<(String, SampleStats)>>>,
@@ -25,6 +32,7 @@ pub fn sample_txs<T>(
     T: Client,
 {
     let mut max_tps = 0.0;
+    let mut tps_samples = vec![];
     let mut total_elapsed;
     let mut total_txs;
     let mut now = Instant::now();
@@ -61,6 +69,7 @@ pub fn sample_txs<T>(
         if tps > max_tps {
             max_tps = tps;
         }
+        tps_samples.push(tps);
 
         info!(
             "Sampler {:9.2} TPS, Transactions: {:6}, Total transactions: {} over {} s",
@@ -75,6 +84,7 @@ pub fn sample_txs<T>(
                 tps: max_tps,
                 elapsed: total_elapsed,
                 txs: total_txs,
+                tps_samples,
             };
             sample_stats
                 .write()
@@ -85,3 +95,38 @@ pub fn sample_txs<T>(
         sleep(Duration::from_secs(sample_period));
     }
 }
+
+/// Records how long the transactions a benchmark sends `receiver`, with the time each was sent,
+/// take to be confirmed. A benchmark only samples a few of the transactions it sends, as each
+/// one is polled for.
+pub fn sample_confirmations<T>(
+    exit_signal: &Arc<AtomicBool>,
+    receiver: &Receiver<(Signature, Instant)>,
+    latencies: &Arc<RwLock<Vec<Duration>>>,
+    client: &Arc<T>,
+) where
+    T: Client,
+{
+    let mut pending: Vec<(Signature, Instant)> = vec![];
+    while !exit_signal.load(Ordering::Relaxed) {
+        match receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok(sent) => pending.push(sent),
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => {
+                if pending.is_empty() {
+                    return;
+                }
+            }
+        }
+        pending.extend(receiver.try_iter());
+
+        pending.retain(|(signature, sent)| {
+            if let Ok(Some(_)) = client.get_signature_status(signature) {
+                latencies.write().unwrap().push(sent.elapsed());
+                false
+            } else {
+                sent.elapsed() < CONFIRMATION_TIMEOUT
+            }
+        });
+    }
+}
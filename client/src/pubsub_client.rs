@@ -0,0 +1,437 @@
+//! The `pubsub_client` module multiplexes account subscriptions over a single WebSocket to a
+//! node's pubsub service. Identical subscriptions share one subscription on the node, and the
+//! subscribe and unsubscribe requests of a batch are sent together in one JSON-RPC batch.
+
+use crate::client_error::ClientError;
+use log::*;
+use serde_json::{json, Value};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::io;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How long `PubsubClient::new` waits for the WebSocket to open
+const CONNECT_TIMEOUT_SECS: u64 = 10;
+
+pub type Confirmations = usize;
+
+/// Subscriptions to the same account at the same confirmation level are shared
+type SubscriptionKey = (Pubkey, Confirmations);
+
+/// A handle to one account subscription, to unsubscribe with
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AccountSubscription {
+    key: SubscriptionKey,
+    id: u64,
+}
+
+impl AccountSubscription {
+    pub fn pubkey(&self) -> &Pubkey {
+        &self.key.0
+    }
+}
+
+#[derive(Default)]
+struct NodeSubscription {
+    /// The node's id for the subscription, `None` until the node confirms it
+    node_id: Option<u64>,
+    /// Where to send the account's notifications, by `AccountSubscription` id
+    senders: HashMap<u64, Sender<Account>>,
+}
+
+/// Tracks the account subscriptions on the node and who is watching each one. Every method
+/// returns the frame to send to the node, if there is anything to send.
+#[derive(Default)]
+struct AccountMultiplexer {
+    last_id: u64,
+    /// Subscribe requests still waiting for the node's subscription id
+    pending: HashMap<u64, SubscriptionKey>,
+    subscriptions: HashMap<SubscriptionKey, NodeSubscription>,
+    node_ids: HashMap<u64, SubscriptionKey>,
+}
+
+/// Combines `requests` into a single frame
+fn batch_frame(mut requests: Vec<Value>) -> Option<String> {
+    match requests.len() {
+        0 => None,
+        1 => Some(requests.pop().unwrap().to_string()),
+        _ => Some(Value::Array(requests).to_string()),
+    }
+}
+
+impl AccountMultiplexer {
+    fn next_id(&mut self) -> u64 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn subscribe(
+        &mut self,
+        keys: &[SubscriptionKey],
+    ) -> (
+        Vec<(AccountSubscription, Receiver<Account>)>,
+        Option<String>,
+    ) {
+        let mut requests = vec![];
+        let mut subscriptions = vec![];
+        for key in keys {
+            if !self.subscriptions.contains_key(key) {
+                let request_id = self.next_id();
+                self.pending.insert(request_id, *key);
+                self.subscriptions.insert(*key, NodeSubscription::default());
+                requests.push(json!({
+                    "jsonrpc": "2.0",
+                    "id": request_id,
+                    "method": "accountSubscribe",
+                    "params": [key.0.to_string(), key.1],
+                }));
+            }
+
+            let id = self.next_id();
+            let (sender, receiver) = channel();
+            self.subscriptions
+                .get_mut(key)
+                .unwrap()
+                .senders
+                .insert(id, sender);
+            subscriptions.push((AccountSubscription { key: *key, id }, receiver));
+        }
+        (subscriptions, batch_frame(requests))
+    }
+
+    fn unsubscribe(&mut self, subscriptions: &[AccountSubscription]) -> Option<String> {
+        let mut requests = vec![];
+        for subscription in subscriptions {
+            if let Some(node_subscription) = self.subscriptions.get_mut(&subscription.key) {
+                node_subscription.senders.remove(&subscription.id);
+            }
+            requests.extend(self.unsubscribe_if_unused(&subscription.key));
+        }
+        batch_frame(requests)
+    }
+
+    /// Drops the node's subscription to `key` once nobody is watching it. A subscription the
+    /// node hasn't confirmed yet is dropped when it does.
+    fn unsubscribe_if_unused(&mut self, key: &SubscriptionKey) -> Option<Value> {
+        let node_id = match self.subscriptions.get(key) {
+            Some(node_subscription) if node_subscription.senders.is_empty() => {
+                node_subscription.node_id?
+            }
+            _ => return None,
+        };
+        self.subscriptions.remove(key);
+        self.node_ids.remove(&node_id);
+        Some(json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id(),
+            "method": "accountUnsubscribe",
+            "params": [node_id],
+        }))
+    }
+
+    fn handle_message(&mut self, text: &str) -> Option<String> {
+        let message: Value = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(err) => {
+                warn!("Invalid pubsub message {}: {}", text, err);
+                return None;
+            }
+        };
+        let messages = match message {
+            Value::Array(messages) => messages,
+            message => vec![message],
+        };
+        let requests = messages
+            .iter()
+            .filter_map(|message| self.process_message(message))
+            .collect();
+        batch_frame(requests)
+    }
+
+    fn process_message(&mut self, message: &Value) -> Option<Value> {
+        if message["method"] == "accountNotification" {
+            let node_id = message["params"]["subscription"].as_u64()?;
+            let account: Account =
+                serde_json::from_value(message["params"]["result"].clone()).ok()?;
+            let key = *self.node_ids.get(&node_id)?;
+            let node_subscription = self.subscriptions.get_mut(&key)?;
+            // Receivers that were dropped unsubscribe too
+            node_subscription
+                .senders
+                .retain(|_, sender| sender.send(account.clone()).is_ok());
+            return self.unsubscribe_if_unused(&key);
+        }
+
+        let request_id = message["id"].as_u64()?;
+        let key = self.pending.remove(&request_id)?;
+        match message["result"].as_u64() {
+            Some(node_id) => {
+                self.node_ids.insert(node_id, key);
+                self.subscriptions.get_mut(&key)?.node_id = Some(node_id);
+                self.unsubscribe_if_unused(&key)
+            }
+            None => {
+                warn!("Unable to subscribe to {}: {}", key.0, message["error"]);
+                // Dropping the senders tells the receivers there is nothing coming
+                self.subscriptions.remove(&key);
+                None
+            }
+        }
+    }
+}
+
+struct Handler {
+    out: ws::Sender,
+    multiplexer: Arc<Mutex<AccountMultiplexer>>,
+    connected: Sender<ws::Sender>,
+}
+
+impl ws::Handler for Handler {
+    fn on_open(&mut self, _: ws::Handshake) -> ws::Result<()> {
+        let _ = self.connected.send(self.out.clone());
+        Ok(())
+    }
+
+    fn on_message(&mut self, message: ws::Message) -> ws::Result<()> {
+        let frame = self
+            .multiplexer
+            .lock()
+            .unwrap()
+            .handle_message(message.as_text()?);
+        if let Some(frame) = frame {
+            self.out.send(frame)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct PubsubClient {
+    out: ws::Sender,
+    multiplexer: Arc<Mutex<AccountMultiplexer>>,
+    thread: JoinHandle<()>,
+}
+
+impl PubsubClient {
+    /// Connects to the pubsub service at `url`, such as `ws://127.0.0.1:8900`
+    pub fn new(url: &str) -> Result<Self, ClientError> {
+        let multiplexer = Arc::new(Mutex::new(AccountMultiplexer::default()));
+        let (connected_sender, connected_receiver) = channel();
+        let thread = {
+            let url = url.to_string();
+            let multiplexer = multiplexer.clone();
+            thread::Builder::new()
+                .name("solana-pubsub-client".to_string())
+                .spawn(move || {
+                    let result = ws::connect(url.as_str(), |out| Handler {
+                        out,
+                        multiplexer: multiplexer.clone(),
+                        connected: connected_sender.clone(),
+                    });
+                    if let Err(err) = result {
+                        warn!("pubsub connection to {} failed: {}", url, err);
+                    }
+                })?
+        };
+        let out = connected_receiver
+            .recv_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    format!("Unable to connect to {}", url),
+                )
+            })?;
+        Ok(Self {
+            out,
+            multiplexer,
+            thread,
+        })
+    }
+
+    /// Subscribes to changes of `pubkey`. Notifications arrive on the returned receiver until
+    /// the subscription is unsubscribed, or the receiver is dropped.
+    pub fn account_subscribe(
+        &self,
+        pubkey: &Pubkey,
+        confirmations: Option<Confirmations>,
+    ) -> Result<(AccountSubscription, Receiver<Account>), ClientError> {
+        Ok(self
+            .account_subscribe_batch(&[(*pubkey, confirmations)])?
+            .pop()
+            .unwrap())
+    }
+
+    /// Subscribes to changes of each of `accounts`, sending all the subscribe requests the node
+    /// needs at once. Accounts already subscribed to at the same confirmation level reuse the
+    /// existing subscription.
+    pub fn account_subscribe_batch(
+        &self,
+        accounts: &[(Pubkey, Option<Confirmations>)],
+    ) -> Result<Vec<(AccountSubscription, Receiver<Account>)>, ClientError> {
+        let keys: Vec<_> = accounts
+            .iter()
+            .map(|(pubkey, confirmations)| (*pubkey, confirmations.unwrap_or(0)))
+            .collect();
+        let mut multiplexer = self.multiplexer.lock().unwrap();
+        let (subscriptions, frame) = multiplexer.subscribe(&keys);
+        if let Some(frame) = frame {
+            self.out.send(frame)?;
+        }
+        Ok(subscriptions)
+    }
+
+    pub fn account_unsubscribe(
+        &self,
+        subscription: &AccountSubscription,
+    ) -> Result<(), ClientError> {
+        self.account_unsubscribe_batch(&[subscription.clone()])
+    }
+
+    /// Unsubscribes from each of `subscriptions`, sending the unsubscribe requests for the
+    /// accounts nobody watches anymore at once
+    pub fn account_unsubscribe_batch(
+        &self,
+        subscriptions: &[AccountSubscription],
+    ) -> Result<(), ClientError> {
+        let mut multiplexer = self.multiplexer.lock().unwrap();
+        if let Some(frame) = multiplexer.unsubscribe(subscriptions) {
+            self.out.send(frame)?;
+        }
+        Ok(())
+    }
+
+    /// Closes the connection, ending every subscription
+    pub fn shutdown(self) -> thread::Result<()> {
+        let _ = self.out.close(ws::CloseCode::Normal);
+        self.thread.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscribe_response(request_id: u64, node_id: u64) -> Value {
+        json!({"jsonrpc": "2.0", "result": node_id, "id": request_id})
+    }
+
+    fn notification(node_id: u64, account: &Account) -> String {
+        json!({
+            "jsonrpc": "2.0",
+            "method": "accountNotification",
+            "params": {"result": account, "subscription": node_id},
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_subscribe_dedupes_and_batches() {
+        let mut multiplexer = AccountMultiplexer::default();
+        let alice = Pubkey::new_rand();
+        let bob = Pubkey::new_rand();
+
+        let (subscriptions, frame) = multiplexer.subscribe(&[(alice, 0), (alice, 0), (bob, 0)]);
+        assert_eq!(subscriptions.len(), 3);
+        let requests: Value = serde_json::from_str(&frame.unwrap()).unwrap();
+        let requests = requests.as_array().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0]["method"], "accountSubscribe");
+        assert_eq!(requests[0]["params"], json!([alice.to_string(), 0]));
+        assert_eq!(requests[1]["params"], json!([bob.to_string(), 0]));
+
+        // Same account, other confirmations
+        let (_, frame) = multiplexer.subscribe(&[(alice, 0), (alice, 1)]);
+        let request: Value = serde_json::from_str(&frame.unwrap()).unwrap();
+        assert_eq!(request["params"], json!([alice.to_string(), 1]));
+    }
+
+    #[test]
+    fn test_notify_all_watchers() {
+        let mut multiplexer = AccountMultiplexer::default();
+        let alice = Pubkey::new_rand();
+        let (mut subscriptions, frame) = multiplexer.subscribe(&[(alice, 0), (alice, 0)]);
+        let request: Value = serde_json::from_str(&frame.unwrap()).unwrap();
+        let request_id = request["id"].as_u64().unwrap();
+
+        assert_eq!(
+            multiplexer.handle_message(&subscribe_response(request_id, 7).to_string()),
+            None
+        );
+        let account = Account::new(42, 0, &Pubkey::default());
+        assert_eq!(multiplexer.handle_message(&notification(7, &account)), None);
+        for (_, receiver) in &subscriptions {
+            assert_eq!(receiver.try_recv().unwrap(), account);
+        }
+
+        // The node's subscription is dropped with its last watcher
+        let (first, _) = subscriptions.remove(0);
+        assert_eq!(multiplexer.unsubscribe(&[first]), None);
+        let (second, _) = subscriptions.remove(0);
+        let request: Value =
+            serde_json::from_str(&multiplexer.unsubscribe(&[second]).unwrap()).unwrap();
+        assert_eq!(request["method"], "accountUnsubscribe");
+        assert_eq!(request["params"], json!([7]));
+
+        // Later notifications are ignored
+        assert_eq!(multiplexer.handle_message(&notification(7, &account)), None);
+    }
+
+    #[test]
+    fn test_unsubscribe_before_confirmed() {
+        let mut multiplexer = AccountMultiplexer::default();
+        let alice = Pubkey::new_rand();
+        let bob = Pubkey::new_rand();
+        let (subscriptions, frame) = multiplexer.subscribe(&[(alice, 0), (bob, 0)]);
+        let requests: Value = serde_json::from_str(&frame.unwrap()).unwrap();
+        let request_ids: Vec<_> = requests
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|request| request["id"].as_u64().unwrap())
+            .collect();
+
+        let subscriptions: Vec<_> = subscriptions
+            .into_iter()
+            .map(|(subscription, _)| subscription)
+            .collect();
+        assert_eq!(multiplexer.unsubscribe(&subscriptions), None);
+
+        // Both are unsubscribed, in one batch, once the node confirms them
+        let responses = json!([
+            subscribe_response(request_ids[0], 3),
+            subscribe_response(request_ids[1], 4),
+        ]);
+        let requests: Value =
+            serde_json::from_str(&multiplexer.handle_message(&responses.to_string()).unwrap())
+                .unwrap();
+        let requests = requests.as_array().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0]["params"], json!([3]));
+        assert_eq!(requests[1]["params"], json!([4]));
+        assert!(multiplexer.subscriptions.is_empty());
+    }
+
+    #[test]
+    fn test_dropped_receiver_unsubscribes() {
+        let mut multiplexer = AccountMultiplexer::default();
+        let alice = Pubkey::new_rand();
+        let (subscriptions, frame) = multiplexer.subscribe(&[(alice, 0)]);
+        let request: Value = serde_json::from_str(&frame.unwrap()).unwrap();
+        multiplexer
+            .handle_message(&subscribe_response(request["id"].as_u64().unwrap(), 9).to_string());
+        drop(subscriptions);
+
+        let account = Account::new(1, 0, &Pubkey::default());
+        let request: Value = serde_json::from_str(
+            &multiplexer
+                .handle_message(&notification(9, &account))
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(request["method"], "accountUnsubscribe");
+        assert_eq!(request["params"], json!([9]));
+    }
+}
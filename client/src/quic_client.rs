@@ -0,0 +1,220 @@
+//! The `quic_client` module sends transactions to a validator's QUIC TPU. Each transaction is
+//! written to a unidirectional stream of its own, over a connection that is reused until it
+//! fails.
+//!
+//! Both ends of a connection prove their node identity: each presents a self-signed certificate
+//! for its identity's Ed25519 key, and the TLS handshake proves it holds that key. The client
+//! checks the validator's certificate carries the identity it meant to reach.
+
+use bincode::serialize;
+use futures::Future;
+use log::*;
+use quinn::{ClientConfigBuilder, Connection, Endpoint};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::transaction::Transaction;
+use std::fmt;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Runtime;
+
+/// The server name clients connect with. Certificates are checked for the node identity they
+/// carry, not for this name.
+pub const QUIC_SERVER_NAME: &str = "solana-tpu";
+
+/// The DER encoding of a PKCS#8 Ed25519 private key, up to the 32 byte secret key
+const ED25519_PKCS8_PREFIX: [u8; 16] = [
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+
+/// The DER encoding of an Ed25519 SubjectPublicKeyInfo, up to the 32 byte public key
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+fn quic_error<E: fmt::Display>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
+
+/// A self-signed certificate for `keypair`'s key, along with the key, so a TLS handshake with
+/// it proves the node identity
+pub fn new_identity_cert(
+    keypair: &Keypair,
+) -> io::Result<(rustls::Certificate, rustls::PrivateKey)> {
+    let mut pkcs8 = ED25519_PKCS8_PREFIX.to_vec();
+    pkcs8.extend_from_slice(keypair.secret.as_bytes());
+    let mut params = rcgen::CertificateParams::new(vec![QUIC_SERVER_NAME.to_string()]);
+    params.alg = &rcgen::PKCS_ED25519;
+    params.key_pair = Some(rcgen::KeyPair::from_der(&pkcs8).map_err(quic_error)?);
+    let cert = rcgen::Certificate::from_params(params).map_err(quic_error)?;
+    Ok((
+        rustls::Certificate(cert.serialize_der().map_err(quic_error)?),
+        rustls::PrivateKey(pkcs8),
+    ))
+}
+
+/// The node identity a certificate from `new_identity_cert` is for
+pub fn identity_from_cert(cert: &rustls::Certificate) -> Option<Pubkey> {
+    let start = cert
+        .0
+        .windows(ED25519_SPKI_PREFIX.len())
+        .position(|window| window == ED25519_SPKI_PREFIX)?
+        + ED25519_SPKI_PREFIX.len();
+    cert.0
+        .get(start..start + 32)
+        .map(|pubkey| Pubkey::new(pubkey))
+}
+
+fn verify_identity_cert(
+    presented_certs: &[rustls::Certificate],
+    identity: Option<&Pubkey>,
+) -> Result<(), rustls::TLSError> {
+    let presented_identity = match presented_certs {
+        [cert] => identity_from_cert(cert),
+        _ => None,
+    }
+    .ok_or_else(|| rustls::TLSError::General("not a node identity certificate".to_string()))?;
+    match identity {
+        Some(identity) if *identity != presented_identity => Err(rustls::TLSError::General(
+            format!("expected node {}, got {}", identity, presented_identity),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Accepts only the certificate of the node the client meant to reach
+struct ServerIdentityVerification(Pubkey);
+
+impl rustls::ServerCertVerifier for ServerIdentityVerification {
+    fn verify_server_cert(
+        &self,
+        _roots: &rustls::RootCertStore,
+        presented_certs: &[rustls::Certificate],
+        _dns_name: webpki::DNSNameRef,
+        _ocsp_response: &[u8],
+    ) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
+        verify_identity_cert(presented_certs, Some(&self.0))?;
+        Ok(rustls::ServerCertVerified::assertion())
+    }
+}
+
+/// Requires clients to present a node identity certificate. Any identity is accepted; the TLS
+/// handshake still proves the client holds its key.
+pub struct ClientIdentityVerification;
+
+impl rustls::ClientCertVerifier for ClientIdentityVerification {
+    fn client_auth_root_subjects(&self) -> rustls::DistinguishedNames {
+        rustls::DistinguishedNames::new()
+    }
+
+    fn verify_client_cert(
+        &self,
+        presented_certs: &[rustls::Certificate],
+    ) -> Result<rustls::ClientCertVerified, rustls::TLSError> {
+        verify_identity_cert(presented_certs, None)?;
+        Ok(rustls::ClientCertVerified::assertion())
+    }
+}
+
+pub struct QuicTpuClient {
+    tpu_quic_addr: SocketAddr,
+    node_id: Pubkey,
+    endpoint: Endpoint,
+    connection: Mutex<Option<Connection>>,
+    runtime: Mutex<Runtime>,
+}
+
+impl QuicTpuClient {
+    /// A client for the QUIC TPU of node `node_id` at `tpu_quic_addr`, connecting as `identity`
+    pub fn new(
+        tpu_quic_addr: SocketAddr,
+        node_id: &Pubkey,
+        identity: &Keypair,
+    ) -> io::Result<Self> {
+        let (cert, key) = new_identity_cert(identity)?;
+        let mut client_config = ClientConfigBuilder::default().build();
+        let crypto = Arc::get_mut(&mut client_config.crypto).unwrap();
+        crypto
+            .dangerous()
+            .set_certificate_verifier(Arc::new(ServerIdentityVerification(*node_id)));
+        crypto.set_single_client_cert(vec![cert], key);
+
+        let mut builder = Endpoint::builder();
+        builder.default_client_config(client_config);
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        let (driver, endpoint, _incoming) = builder.with_socket(socket).map_err(quic_error)?;
+
+        let mut runtime = Runtime::new()?;
+        runtime.spawn(driver.map_err(|err| warn!("quic endpoint error: {}", err)));
+        Ok(Self {
+            tpu_quic_addr,
+            node_id: *node_id,
+            endpoint,
+            connection: Mutex::new(None),
+            runtime: Mutex::new(runtime),
+        })
+    }
+
+    pub fn tpu_quic_addr(&self) -> &SocketAddr {
+        &self.tpu_quic_addr
+    }
+
+    pub fn node_id(&self) -> &Pubkey {
+        &self.node_id
+    }
+
+    fn connect(&self, runtime: &mut Runtime) -> io::Result<Connection> {
+        let connecting = self
+            .endpoint
+            .connect(&self.tpu_quic_addr, QUIC_SERVER_NAME)
+            .map_err(quic_error)?;
+        let (driver, connection, _incoming) = runtime.block_on(connecting).map_err(quic_error)?;
+        runtime.spawn(driver.map_err(|err| debug!("quic connection closed: {}", err)));
+        Ok(connection)
+    }
+
+    /// Sends `transaction` on a stream of its own, connecting to the validator first if there's
+    /// no connection yet or the last one failed
+    pub fn send_transaction(&self, transaction: &Transaction) -> io::Result<()> {
+        let data = serialize(transaction).map_err(quic_error)?;
+        let mut runtime = self.runtime.lock().unwrap();
+        let mut connection = self.connection.lock().unwrap();
+        if connection.is_none() {
+            *connection = Some(self.connect(&mut runtime)?);
+        }
+
+        let send = connection
+            .as_ref()
+            .unwrap()
+            .open_uni()
+            .map_err(quic_error)
+            .and_then(move |stream| tokio::io::write_all(stream, data))
+            .and_then(|(stream, _data)| stream.finish().map_err(quic_error));
+        let result = runtime.block_on(send);
+        if result.is_err() {
+            // Reconnect on the next send
+            *connection = None;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::KeypairUtil;
+
+    #[test]
+    fn test_identity_cert() {
+        let keypair = Keypair::new();
+        let (cert, _key) = new_identity_cert(&keypair).unwrap();
+        assert_eq!(identity_from_cert(&cert), Some(keypair.pubkey()));
+        assert!(verify_identity_cert(&[cert.clone()], Some(&keypair.pubkey())).is_ok());
+        assert!(verify_identity_cert(&[cert.clone()], None).is_ok());
+        assert!(verify_identity_cert(&[cert.clone()], Some(&Pubkey::new_rand())).is_err());
+        assert!(verify_identity_cert(&[], None).is_err());
+        assert!(verify_identity_cert(&[cert.clone(), cert], None).is_err());
+        assert!(verify_identity_cert(&[rustls::Certificate(vec![0; 64])], None).is_err());
+    }
+}
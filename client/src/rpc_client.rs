@@ -3,22 +3,37 @@ use crate::generic_rpc_client_request::GenericRpcClientRequest;
 use crate::mock_rpc_client_request::MockRpcClientRequest;
 use crate::rpc_client_request::RpcClientRequest;
 use crate::rpc_request::RpcRequest;
-use bincode::serialize;
+use bincode::{deserialize, serialize};
 use log::*;
 use serde_json::{json, Value};
 use solana_sdk::account::Account;
 use solana_sdk::fee_calculator::FeeCalculator;
 use solana_sdk::hash::Hash;
+use solana_sdk::native_token::Sol;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{KeypairUtil, Signature};
 use solana_sdk::timing::{DEFAULT_NUM_TICKS_PER_SECOND, DEFAULT_TICKS_PER_SLOT};
-use solana_sdk::transaction::{self, Transaction, TransactionError};
+use solana_sdk::transaction::{self, Transaction, TransactionError, TransactionStatusMeta};
 use std::error;
 use std::io;
 use std::net::SocketAddr;
 use std::thread::sleep;
 use std::time::{Duration, Instant};
 
+/// Number of times `request_airdrop` retries a failed or unconfirmed airdrop
+const AIRDROP_RETRIES: usize = 5;
+/// Delay before the first airdrop retry, doubled after every attempt
+const AIRDROP_INITIAL_BACKOFF_MS: u64 = 250;
+
+/// A transaction `getConfirmedTransaction` returned, with the slot of its block
+#[derive(Debug, PartialEq)]
+pub struct ConfirmedTransaction {
+    pub slot: u64,
+    pub transaction: Transaction,
+    /// Its fee and balance changes, if the node recorded them
+    pub meta: Option<TransactionStatusMeta>,
+}
+
 pub struct RpcClient {
     client: Box<GenericRpcClientRequest + Send + Sync>,
 }
@@ -30,6 +45,12 @@ impl RpcClient {
         }
     }
 
+    pub fn new_with_client(url: String, client: reqwest::Client) -> Self {
+        Self {
+            client: Box::new(RpcClientRequest::new_with_client(url, client)),
+        }
+    }
+
     pub fn new_mock(url: String) -> Self {
         Self {
             client: Box::new(MockRpcClientRequest::new(url)),
@@ -48,6 +69,7 @@ impl RpcClient {
     }
 
     pub fn send_transaction(&self, transaction: &Transaction) -> Result<String, ClientError> {
+        transaction.check_packet_fit().map_err(io::Error::from)?;
         let serialized = serialize(transaction).unwrap();
         let params = json!([serialized]);
         let signature = self
@@ -151,6 +173,10 @@ impl RpcClient {
         mut transactions: Vec<Transaction>,
         signer_keys: &[&T],
     ) -> Result<(), Box<dyn error::Error>> {
+        // Fail before sending any of them, rather than leave the batch half done
+        for transaction in &transactions {
+            transaction.check_packet_fit()?;
+        }
         let mut send_retries = 5;
         loop {
             let mut status_retries = 4;
@@ -307,6 +333,133 @@ impl RpcClient {
         Ok(pubkey_accounts)
     }
 
+    /// Request the data blobs the node has stored for `slot` with indexes in
+    /// `start_index..end_index`. Missing blobs are left out of the result.
+    pub fn get_blobs(
+        &self,
+        slot: u64,
+        start_index: u64,
+        end_index: u64,
+    ) -> io::Result<Vec<Vec<u8>>> {
+        let params = json!([slot, start_index, end_index]);
+        let response = self
+            .client
+            .send(&RpcRequest::GetBlobs, Some(params), 0)
+            .map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("GetBlobs request failure: {:?}", err),
+                )
+            })?;
+
+        serde_json::from_value(response).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("GetBlobs parse failure: {}", err),
+            )
+        })
+    }
+
+    /// Request the signatures of the transactions that used `address` in the rooted slots
+    /// `start_slot..=end_slot`, following `after` and at most `limit` of them. A node searches
+    /// a limited number of slots, and returns a limited number of signatures, per request.
+    pub fn get_confirmed_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        start_slot: u64,
+        end_slot: u64,
+        after: Option<&Signature>,
+        limit: usize,
+    ) -> io::Result<Vec<Signature>> {
+        let params = json!([
+            address.to_string(),
+            start_slot,
+            end_slot,
+            {
+                "after": after.map(|signature| signature.to_string()),
+                "limit": limit,
+            }
+        ]);
+        let response = self
+            .client
+            .send(
+                &RpcRequest::GetConfirmedSignaturesForAddress,
+                Some(params),
+                0,
+            )
+            .map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!(
+                        "GetConfirmedSignaturesForAddress request failure: {:?}",
+                        err
+                    ),
+                )
+            })?;
+
+        let parse_error = |err: String| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("GetConfirmedSignaturesForAddress parse failure: {}", err),
+            )
+        };
+        let signatures: Vec<String> =
+            serde_json::from_value(response).map_err(|err| parse_error(err.to_string()))?;
+        signatures
+            .iter()
+            .map(|signature| {
+                signature
+                    .parse()
+                    .map_err(|err| parse_error(format!("{:?}", err)))
+            })
+            .collect()
+    }
+
+    /// Request the transaction with `signature`, with the slot of its block and its status
+    /// metadata. Returns `None` if the node doesn't know of the transaction.
+    pub fn get_confirmed_transaction(
+        &self,
+        signature: &Signature,
+    ) -> io::Result<Option<ConfirmedTransaction>> {
+        let params = json!([signature.to_string(), "binary"]);
+        let response = self
+            .client
+            .send(&RpcRequest::GetConfirmedTransaction, Some(params), 0)
+            .map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("GetConfirmedTransaction request failure: {:?}", err),
+                )
+            })?;
+        if response.is_null() {
+            return Ok(None);
+        }
+
+        let parse_error = |err: String| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("GetConfirmedTransaction parse failure: {}", err),
+            )
+        };
+        let slot = response["slot"]
+            .as_u64()
+            .ok_or_else(|| parse_error("missing slot".to_string()))?;
+        let transaction = response["transaction"]
+            .as_str()
+            .ok_or_else(|| parse_error("missing transaction".to_string()))?;
+        let transaction = bs58::decode(transaction)
+            .into_vec()
+            .map_err(|err| parse_error(format!("{:?}", err)))?;
+        let transaction = deserialize(&transaction).map_err(|err| parse_error(err.to_string()))?;
+        let meta = serde_json::from_value(response["meta"].clone())
+            .map_err(|err| parse_error(err.to_string()))?;
+        Ok(Some(ConfirmedTransaction {
+            slot,
+            transaction,
+            meta,
+        }))
+    }
+
     /// Request the transaction count.  If the response packet is dropped by the network,
     /// this method will try again 5 times.
     pub fn get_transaction_count(&self) -> io::Result<u64> {
@@ -554,6 +707,51 @@ impl RpcClient {
         })
     }
 
+    /// Request an airdrop through the RPC node, which forwards it to its own drone
+    /// or to one it discovered through gossip. Failures, including requests the
+    /// drone refuses because of its rate limit, are retried with exponential
+    /// backoff. Returns once the airdrop transaction is confirmed.
+    pub fn request_airdrop(&self, pubkey: &Pubkey, lamports: u64) -> io::Result<Signature> {
+        let params = json!([format!("{}", pubkey), lamports]);
+        let mut backoff = Duration::from_millis(AIRDROP_INITIAL_BACKOFF_MS);
+        for attempt in 0..=AIRDROP_RETRIES {
+            if attempt > 0 {
+                sleep(backoff);
+                backoff *= 2;
+            }
+            let signature = self
+                .client
+                .send(&RpcRequest::RequestAirdrop, Some(params.clone()), 0)
+                .map_err(|err| {
+                    io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("RequestAirdrop request failure: {:?}", err),
+                    )
+                })
+                .and_then(|response| {
+                    serde_json::from_value::<String>(response)
+                        .ok()
+                        .and_then(|signature| signature.parse::<Signature>().ok())
+                        .ok_or_else(|| {
+                            io::Error::new(io::ErrorKind::Other, "RequestAirdrop parse failure")
+                        })
+                });
+            match signature {
+                Ok(signature) => {
+                    if self.poll_for_signature(&signature).is_ok() {
+                        return Ok(signature);
+                    }
+                    debug!("airdrop {} was not confirmed", signature);
+                }
+                Err(err) => debug!("request_airdrop attempt {} failed: {}", attempt, err),
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("airdrop of {} to {} failed", Sol(lamports), pubkey),
+        ))
+    }
+
     pub fn fullnode_exit(&self) -> io::Result<bool> {
         let response = self
             .client
@@ -599,6 +797,8 @@ mod tests {
     use jsonrpc_http_server::{AccessControlAllowOrigin, DomainsValidation, ServerBuilder};
     use serde_json::Number;
     use solana_logger;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::packet::PACKET_DATA_SIZE;
     use solana_sdk::signature::{Keypair, KeypairUtil};
     use solana_sdk::system_transaction;
     use solana_sdk::transaction::TransactionError;
@@ -716,6 +916,14 @@ mod tests {
 
         let signature = rpc_client.send_transaction(&tx);
         assert!(signature.is_err());
+        // Transactions too large for a packet are rejected before they're sent
+        let rpc_client = RpcClient::new_mock("succeeds".to_string());
+        let ix = Instruction::new(Pubkey::new_rand(), &vec![0u8; PACKET_DATA_SIZE], vec![]);
+        let tx = Transaction::new_signed_instructions(&[&key], vec![ix], blockhash);
+        match rpc_client.send_transaction(&tx) {
+            Err(ClientError::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::InvalidInput),
+            result => panic!("unexpected result: {:?}", result),
+        }
     }
     #[test]
     fn test_get_recent_blockhash() {
@@ -792,6 +1000,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_request_airdrop() {
+        let rpc_client = RpcClient::new_mock("succeeds".to_string());
+        let signature = rpc_client.request_airdrop(&Pubkey::new_rand(), 50);
+        assert_eq!(signature.unwrap(), SIGNATURE.parse().unwrap());
+    }
+
     #[test]
     fn test_rpc_client_thread() {
         let rpc_client = RpcClient::new_mock("succeeds".to_string());
@@ -29,6 +29,11 @@ impl RpcClientRequest {
 
         Self { client, url }
     }
+
+    /// Sends requests with `client`, such as one configured with a proxy
+    pub fn new_with_client(url: String, client: reqwest::Client) -> Self {
+        Self { client, url }
+    }
 }
 
 impl GenericRpcClientRequest for RpcClientRequest {
@@ -8,23 +8,33 @@ pub enum RpcRequest {
     FullnodeExit,
     GetAccountInfo,
     GetBalance,
+    GetBlobs,
     GetClusterNodes,
+    GetConfirmedSignaturesForAddress,
+    GetConfirmedTransaction,
+    GetDuplicateSlotProofs,
+    GetInflation,
     GetNumBlocksSinceSignatureConfirmation,
+    GetPipelineLatencies,
     GetProgramAccounts,
     GetRecentBlockhash,
     GetSignatureStatus,
     GetSlot,
     GetSlotLeader,
+    GetStakeActivation,
     GetEpochVoteAccounts,
+    GetVoteAccounts,
     GetStorageTurn,
     GetStorageTurnRate,
     GetSlotsPerSegment,
     GetStoragePubkeysForSlot,
     GetTransactionCount,
+    MinimumLedgerSlot,
     RegisterNode,
     RequestAirdrop,
     SendTransaction,
     SignVote,
+    SimulateTransaction,
 }
 
 impl RpcRequest {
@@ -36,25 +46,35 @@ impl RpcRequest {
             RpcRequest::FullnodeExit => "fullnodeExit",
             RpcRequest::GetAccountInfo => "getAccountInfo",
             RpcRequest::GetBalance => "getBalance",
+            RpcRequest::GetBlobs => "getBlobs",
             RpcRequest::GetClusterNodes => "getClusterNodes",
+            RpcRequest::GetConfirmedSignaturesForAddress => "getConfirmedSignaturesForAddress",
+            RpcRequest::GetConfirmedTransaction => "getConfirmedTransaction",
+            RpcRequest::GetDuplicateSlotProofs => "getDuplicateSlotProofs",
+            RpcRequest::GetInflation => "getInflation",
             RpcRequest::GetNumBlocksSinceSignatureConfirmation => {
                 "getNumBlocksSinceSignatureConfirmation"
             }
+            RpcRequest::GetPipelineLatencies => "getPipelineLatencies",
             RpcRequest::GetProgramAccounts => "getProgramAccounts",
             RpcRequest::GetRecentBlockhash => "getRecentBlockhash",
             RpcRequest::GetSignatureStatus => "getSignatureStatus",
             RpcRequest::GetSlot => "getSlot",
             RpcRequest::GetSlotLeader => "getSlotLeader",
+            RpcRequest::GetStakeActivation => "getStakeActivation",
             RpcRequest::GetEpochVoteAccounts => "getEpochVoteAccounts",
+            RpcRequest::GetVoteAccounts => "getVoteAccounts",
             RpcRequest::GetStorageTurn => "getStorageTurn",
             RpcRequest::GetStorageTurnRate => "getStorageTurnRate",
             RpcRequest::GetSlotsPerSegment => "getSlotsPerSegment",
             RpcRequest::GetStoragePubkeysForSlot => "getStoragePubkeysForSlot",
             RpcRequest::GetTransactionCount => "getTransactionCount",
+            RpcRequest::MinimumLedgerSlot => "minimumLedgerSlot",
             RpcRequest::RegisterNode => "registerNode",
             RpcRequest::RequestAirdrop => "requestAirdrop",
             RpcRequest::SendTransaction => "sendTransaction",
             RpcRequest::SignVote => "signVote",
+            RpcRequest::SimulateTransaction => "simulateTransaction",
         };
         let mut request = json!({
            "jsonrpc": jsonrpc,
@@ -12,7 +12,6 @@ use solana_sdk::fee_calculator::FeeCalculator;
 use solana_sdk::hash::Hash;
 use solana_sdk::instruction::Instruction;
 use solana_sdk::message::Message;
-use solana_sdk::packet::PACKET_DATA_SIZE;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, KeypairUtil, Signature};
 use solana_sdk::system_instruction;
@@ -205,6 +204,7 @@ impl ThinClient {
         tries: usize,
         pending_confirmations: usize,
     ) -> io::Result<Signature> {
+        transaction.check_packet_fit()?;
         for x in 0..tries {
             let now = Instant::now();
             let mut buf = vec![0; serialized_size(&transaction).unwrap() as usize];
@@ -279,6 +279,11 @@ impl ThinClient {
         self.rpc_client().fullnode_exit()
     }
 
+    /// Request an airdrop from the cluster's drone and wait for it to be confirmed
+    pub fn request_airdrop(&self, pubkey: &Pubkey, lamports: u64) -> io::Result<Signature> {
+        self.rpc_client().request_airdrop(pubkey, lamports)
+    }
+
     pub fn get_num_blocks_since_signature_confirmation(
         &mut self,
         sig: &Signature,
@@ -415,11 +420,11 @@ impl SyncClient for ThinClient {
 
 impl AsyncClient for ThinClient {
     fn async_send_transaction(&self, transaction: Transaction) -> io::Result<Signature> {
+        transaction.check_packet_fit()?;
         let mut buf = vec![0; serialized_size(&transaction).unwrap() as usize];
         let mut wr = std::io::Cursor::new(&mut buf[..]);
         serialize_into(&mut wr, &transaction)
             .expect("serialize Transaction in pub fn transfer_signed");
-        assert!(buf.len() < PACKET_DATA_SIZE);
         self.transactions_socket
             .send_to(&buf[..], &self.transactions_addr())?;
         Ok(transaction.signatures[0])
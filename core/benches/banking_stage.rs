@@ -26,10 +26,9 @@ use solana_sdk::signature::Signature;
 use solana_sdk::system_instruction;
 use solana_sdk::system_transaction;
 use solana_sdk::timing::{duration_as_us, timestamp};
-use solana_sdk::transaction::Transaction;
-use std::iter;
+use solana_sdk::transaction::{SanitizedTransaction, Transaction};
 use std::sync::atomic::Ordering;
-use std::sync::mpsc::Receiver;
+use std::sync::mpsc::{channel, Receiver};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 use test::Bencher;
@@ -78,12 +77,14 @@ fn bench_consume_buffered(bencher: &mut Bencher) {
         }
         // This tests the performance of buffering packets.
         // If the packet buffers are copied, performance will be poor.
+        let (prefetch_sender, _) = channel();
         bencher.iter(move || {
             let _ignored = BankingStage::consume_buffered_packets(
                 &my_pubkey,
                 &poh_recorder,
                 &mut packets,
                 10_000,
+                &prefetch_sender,
             );
         });
 
@@ -179,18 +180,13 @@ fn bench_banking(bencher: &mut Bencher, tx_type: TransactionType) {
     });
     bank.clear_signatures();
     //sanity check, make sure all the transactions can execute in parallel
-    let res = bank.process_transactions(&transactions);
+    let res = bank
+        .process_transactions(&SanitizedTransaction::try_new_all(transactions.clone()).unwrap());
     for r in res {
         assert!(r.is_ok(), "sanity parallel execution");
     }
     bank.clear_signatures();
-    let verified: Vec<_> = to_packets_chunked(&transactions.clone(), PACKETS_PER_BATCH)
-        .into_iter()
-        .map(|x| {
-            let len = x.packets.len();
-            (x, iter::repeat(1).take(len).collect())
-        })
-        .collect();
+    let verified: Vec<_> = to_packets_chunked(&transactions.clone(), PACKETS_PER_BATCH);
     let ledger_path = get_tmp_ledger_path!();
     {
         let blocktree = Arc::new(
@@ -229,7 +225,7 @@ fn bench_banking(bencher: &mut Bencher, tx_type: TransactionType) {
                     v.len(),
                 );
                 for xv in v {
-                    sent += xv.0.packets.len();
+                    sent += xv.packets.len();
                 }
                 verified_sender.send(v.to_vec()).unwrap();
             }
@@ -3,6 +3,7 @@
 #![feature(test)]
 extern crate test;
 
+use solana::hash_chain::{hash_chains, scalar_hash_chain, HASH_CHAIN_LANES};
 use solana::poh::Poh;
 use solana::poh_service::NUM_HASHES_PER_BATCH;
 use solana_sdk::hash::Hash;
@@ -61,3 +62,21 @@ fn bench_poh_lock_time_per_batch(bencher: &mut Bencher) {
         poh.hash(NUM_HASHES_PER_BATCH);
     })
 }
+
+#[bench]
+// The hashing `Poh::hash` does, without the SHA extensions
+fn bench_poh_scalar_hash(bencher: &mut Bencher) {
+    let mut hash = Hash::default();
+    bencher.iter(|| {
+        hash = scalar_hash_chain(&hash, NUM_HASHES);
+    })
+}
+
+#[bench]
+// Verifying the chains of several entries at once
+fn bench_poh_hash_chains(bencher: &mut Bencher) {
+    let mut chains = vec![(Hash::default(), NUM_HASHES); HASH_CHAIN_LANES];
+    bencher.iter(|| {
+        hash_chains(&mut chains);
+    })
+}
@@ -15,6 +15,7 @@ use solana_sdk::signature::{Keypair, KeypairUtil};
 use solana_sdk::system_transaction;
 use solana_sdk::timing::duration_as_ms;
 use std::sync::mpsc::channel;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use test::Bencher;
 
@@ -24,7 +25,7 @@ fn bench_sigverify_stage(bencher: &mut Bencher) {
     let (packet_s, packet_r) = channel();
     let (verified_s, verified_r) = unbounded();
     let sigverify_disabled = false;
-    let stage = SigVerifyStage::new(packet_r, sigverify_disabled, verified_s);
+    let stage = SigVerifyStage::new(packet_r, sigverify_disabled, verified_s, Arc::default());
 
     let now = Instant::now();
     let len = 4096;
@@ -70,8 +71,8 @@ fn bench_sigverify_stage(bencher: &mut Bencher) {
         loop {
             if let Ok(mut verifieds) = verified_r.recv_timeout(Duration::from_millis(10)) {
                 while let Some(v) = verifieds.pop() {
-                    received += v.0.packets.len();
-                    batches.push(v.0);
+                    received += v.packets.len();
+                    batches.push(v);
                 }
                 if received >= sent_len {
                     break;
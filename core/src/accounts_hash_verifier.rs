@@ -0,0 +1,218 @@
+//! The `accounts_hash_verifier` module gossips this node's bank hash of the first rooted slot of
+//! each epoch, and checks it against the hashes gossiped by the node's trusted validators. A bank
+//! hash covers the accounts its slot changed and the hash of its parent, so a mismatch means this
+//! node's accounts diverged from the trusted validator's at or before that slot.
+
+use crate::cluster_info::ClusterInfo;
+use solana_metrics::{datapoint_error, datapoint_info};
+use solana_runtime::bank::Bank;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// How many of this node's most recent epoch boundary hashes to gossip and check
+pub const MAX_ACCOUNTS_HASHES: usize = 4;
+
+pub struct AccountsHashVerifier {
+    /// This node's (slot, hash) of its most recent epoch boundary slots, oldest first
+    hashes: VecDeque<(u64, Hash)>,
+    trusted_validators: HashSet<Pubkey>,
+    /// The (trusted validator, slot) pairs already compared, so each is reported once
+    checked: HashSet<(Pubkey, u64)>,
+    halt_on_mismatch: bool,
+    exit: Arc<AtomicBool>,
+}
+
+impl AccountsHashVerifier {
+    pub fn new(
+        trusted_validators: HashSet<Pubkey>,
+        halt_on_mismatch: bool,
+        exit: &Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            hashes: VecDeque::new(),
+            trusted_validators,
+            checked: HashSet::new(),
+            halt_on_mismatch,
+            exit: exit.clone(),
+        }
+    }
+
+    /// Whether `bank` is the first of its epoch on its fork. Must be called before the bank's
+    /// parent is squashed away.
+    fn is_epoch_boundary(bank: &Bank) -> bool {
+        bank.parent()
+            .map_or(false, |parent| parent.epoch() < bank.epoch())
+    }
+
+    /// Record and gossip the hashes of the epoch boundary slots among `rooted_banks`, then
+    /// compare this node's hashes with its trusted validators'. Trusted validators may root
+    /// a boundary slot after this node, so their hashes are compared again after every root
+    /// until they show up. Returns the (trusted validator, slot) pairs found to mismatch.
+    pub fn new_root(
+        &mut self,
+        rooted_banks: &[Arc<Bank>],
+        cluster_info: &Arc<RwLock<ClusterInfo>>,
+    ) -> Vec<(Pubkey, u64)> {
+        let mut boundary_banks: Vec<_> = rooted_banks
+            .iter()
+            .filter(|bank| Self::is_epoch_boundary(bank))
+            .collect();
+        boundary_banks.sort_by_key(|bank| bank.slot());
+        for bank in &boundary_banks {
+            datapoint_info!(
+                "accounts_hash_verifier-boundary",
+                ("slot", bank.slot(), i64),
+                ("epoch", bank.epoch(), i64),
+                ("hash", bank.hash().to_string(), String)
+            );
+            self.hashes.push_back((bank.slot(), bank.hash()));
+        }
+        while self.hashes.len() > MAX_ACCOUNTS_HASHES {
+            self.hashes.pop_front();
+        }
+        if !boundary_banks.is_empty() {
+            cluster_info
+                .write()
+                .unwrap()
+                .push_accounts_hashes(self.hashes.iter().cloned().collect());
+            let hashes = &self.hashes;
+            self.checked
+                .retain(|(_, slot)| hashes.iter().any(|(my_slot, _)| my_slot == slot));
+        }
+
+        let mismatches = self.check_trusted_validators(&cluster_info.read().unwrap());
+        if !mismatches.is_empty() && self.halt_on_mismatch {
+            error!("Accounts hash mismatch with trusted validators, halting");
+            self.exit.store(true, Ordering::Relaxed);
+        }
+        mismatches
+    }
+
+    fn check_trusted_validators(&mut self, cluster_info: &ClusterInfo) -> Vec<(Pubkey, u64)> {
+        let mut mismatches = vec![];
+        for trusted_validator in &self.trusted_validators {
+            let their_hashes = match cluster_info.get_accounts_hashes(trusted_validator) {
+                Some(hashes) => hashes,
+                None => continue,
+            };
+            for (slot, my_hash) in &self.hashes {
+                if self.checked.contains(&(*trusted_validator, *slot)) {
+                    continue;
+                }
+                let their_hash = match their_hashes.iter().find(|(s, _)| s == slot) {
+                    Some((_, hash)) => hash,
+                    None => continue,
+                };
+                self.checked.insert((*trusted_validator, *slot));
+                if their_hash == my_hash {
+                    info!(
+                        "accounts hash for slot {} matches trusted validator {}",
+                        slot, trusted_validator
+                    );
+                } else {
+                    error!(
+                        "accounts hash for slot {} is {}, but trusted validator {} has {}",
+                        slot, my_hash, trusted_validator, their_hash
+                    );
+                    datapoint_error!(
+                        "accounts_hash_verifier-mismatch",
+                        ("slot", *slot, i64),
+                        ("trusted_validator", trusted_validator.to_string(), String),
+                        ("hash", my_hash.to_string(), String),
+                        ("trusted_hash", their_hash.to_string(), String)
+                    );
+                    mismatches.push((*trusted_validator, *slot));
+                }
+            }
+        }
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contact_info::ContactInfo;
+    use crate::crds_value::{AccountsHashes, CrdsValue};
+    use crate::genesis_utils::create_genesis_block;
+    use solana_sdk::signature::{Keypair, KeypairUtil, Signable};
+    use solana_sdk::timing::timestamp;
+
+    fn insert_accounts_hashes(
+        cluster_info: &Arc<RwLock<ClusterInfo>>,
+        keypair: &Keypair,
+        hashes: Vec<(u64, Hash)>,
+    ) {
+        let mut value =
+            CrdsValue::AccountsHashes(AccountsHashes::new(&keypair.pubkey(), hashes, timestamp()));
+        value.sign(keypair);
+        cluster_info
+            .write()
+            .unwrap()
+            .gossip
+            .crds
+            .insert(value, timestamp())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_accounts_hash_verifier() {
+        let my_pubkey = Pubkey::new_rand();
+        let genesis_block = create_genesis_block(10_000).genesis_block;
+        let bank0 = Arc::new(Bank::new(&genesis_block));
+        let cluster_info = Arc::new(RwLock::new(ClusterInfo::new_with_invalid_keypair(
+            ContactInfo::new_localhost(&my_pubkey, 0),
+        )));
+
+        // Root the first slot of epoch 1, and a slot after it
+        let boundary_slot = bank0.epoch_schedule().get_first_slot_in_epoch(1);
+        let boundary_bank = Arc::new(Bank::new_from_parent(&bank0, &my_pubkey, boundary_slot));
+        boundary_bank.freeze();
+        let next_bank = Arc::new(Bank::new_from_parent(
+            &boundary_bank,
+            &my_pubkey,
+            boundary_slot + 1,
+        ));
+        next_bank.freeze();
+        let boundary_hash = boundary_bank.hash();
+
+        let good = Keypair::new();
+        let bad = Keypair::new();
+        let untrusted = Keypair::new();
+        let trusted_validators = vec![good.pubkey(), bad.pubkey()].into_iter().collect();
+        let exit = Arc::new(AtomicBool::new(false));
+        let mut verifier = AccountsHashVerifier::new(trusted_validators, true, &exit);
+
+        // Nothing to compare against until the trusted validators gossip their hashes
+        let rooted_banks = vec![next_bank.clone(), boundary_bank, bank0];
+        assert!(verifier.new_root(&rooted_banks, &cluster_info).is_empty());
+        assert_eq!(
+            cluster_info.read().unwrap().get_accounts_hashes(&my_pubkey),
+            Some(&[(boundary_slot, boundary_hash)][..])
+        );
+
+        insert_accounts_hashes(&cluster_info, &good, vec![(boundary_slot, boundary_hash)]);
+        insert_accounts_hashes(
+            &cluster_info,
+            &untrusted,
+            vec![(boundary_slot, Hash::default())],
+        );
+        assert!(verifier
+            .new_root(&[next_bank.clone()], &cluster_info)
+            .is_empty());
+        assert!(!exit.load(Ordering::Relaxed));
+
+        insert_accounts_hashes(&cluster_info, &bad, vec![(boundary_slot, Hash::default())]);
+        assert_eq!(
+            verifier.new_root(&[next_bank.clone()], &cluster_info),
+            vec![(bad.pubkey(), boundary_slot)]
+        );
+        assert!(exit.load(Ordering::Relaxed));
+
+        // A mismatch is only reported once
+        assert!(verifier.new_root(&[next_bank], &cluster_info).is_empty());
+    }
+}
@@ -1,7 +1,7 @@
 //! The `bank_forks` module implments BankForks a DAG of checkpointed Banks
 
 use bincode::{deserialize_from, serialize_into};
-use solana_metrics::inc_new_counter_info;
+use solana_metrics::{datapoint_info, inc_new_counter_info};
 use solana_runtime::bank::{Bank, BankRc, StatusCacheRc};
 use solana_sdk::genesis_block::GenesisBlock;
 use solana_sdk::timing;
@@ -9,6 +9,7 @@ use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Error, ErrorKind};
+use std::mem;
 use std::ops::Index;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -21,6 +22,30 @@ pub struct BankForks {
     slots: HashSet<u64>,
     snapshot_path: Option<String>,
     confidence: HashMap<u64, Confidence>,
+    pruning_config: ForkPruningConfig,
+    /// Slots past the root whose banks were evicted to make room for other forks
+    evicted_slots: HashSet<u64>,
+}
+
+/// Limits on the banks kept past the root, so that forks can't pile up without bound while
+/// the cluster fails to make roots
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ForkPruningConfig {
+    /// Banks more than this many slots past the root are pruned, unless on the heaviest or a
+    /// voted fork
+    pub max_depth: u64,
+    /// At most this many banks are kept past the root. Once reached, the lightest stale forks are
+    /// evicted to make room for new banks, and a new root prunes the lightest forks first.
+    pub max_active_banks: usize,
+}
+
+impl Default for ForkPruningConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: std::u64::MAX,
+            max_active_banks: std::usize::MAX,
+        }
+    }
 }
 
 #[derive(Debug, Default, PartialEq)]
@@ -74,6 +99,8 @@ impl BankForks {
             slots: HashSet::new(),
             snapshot_path: None,
             confidence: HashMap::new(),
+            pruning_config: ForkPruningConfig::default(),
+            evicted_slots: HashSet::new(),
         }
     }
 
@@ -126,6 +153,86 @@ impl BankForks {
         self.banks.get(&bank_slot)
     }
 
+    /// The number of banks held, frozen or not
+    pub fn active_bank_count(&self) -> usize {
+        self.banks.len()
+    }
+
+    /// A rough estimate of the memory the banks hold on to: each bank plus the account storage
+    /// written for its slot
+    pub fn estimated_memory_size(&self) -> u64 {
+        self.banks
+            .values()
+            .map(|bank| mem::size_of::<Bank>() as u64 + bank.account_storage_size())
+            .sum()
+    }
+
+    pub fn set_pruning_config(&mut self, pruning_config: ForkPruningConfig) {
+        self.pruning_config = pruning_config;
+    }
+
+    /// Whether there's room within the pruning limits for a new bank at `slot`
+    pub fn within_pruning_limits(&self, slot: u64) -> bool {
+        self.within_max_depth(slot) && self.has_room_for_bank()
+    }
+
+    /// Whether `slot` is close enough to the root to be given a bank
+    pub fn within_max_depth(&self, slot: u64) -> bool {
+        slot.saturating_sub(self.root) <= self.pruning_config.max_depth
+    }
+
+    /// Whether another bank past the root fits within `max_active_banks`
+    pub fn has_room_for_bank(&self) -> bool {
+        self.banks.keys().filter(|slot| **slot > self.root).count()
+            < self.pruning_config.max_active_banks
+    }
+
+    /// The banks past the root that no other bank descends from
+    fn fork_tips(&self) -> Vec<u64> {
+        let parents: HashSet<u64> = self
+            .banks
+            .values()
+            .filter_map(|bank| bank.parent().map(|parent| parent.slot()))
+            .collect();
+        self.banks
+            .keys()
+            .filter(|slot| **slot > self.root && !parents.contains(slot))
+            .cloned()
+            .collect()
+    }
+
+    /// The frozen fork tips: the tips of the forks replay is done with, which can be evicted to
+    /// make room for new banks
+    pub fn stale_fork_tips(&self) -> Vec<u64> {
+        self.fork_tips()
+            .into_iter()
+            .filter(|slot| self.banks[slot].is_frozen())
+            .collect()
+    }
+
+    /// Drops the bank of `slot`, one of the `stale_fork_tips`. The slot's blobs stay in the
+    /// blocktree, so it can still be repaired from this node, and replayed again once there's
+    /// room for it.
+    pub fn evict(&mut self, slot: u64) {
+        assert!(self.stale_fork_tips().contains(&slot));
+        self.remove_tip(slot);
+    }
+
+    fn remove_tip(&mut self, slot: u64) {
+        self.banks.remove(&slot);
+        self.confidence.remove(&slot);
+        self.evicted_slots.insert(slot);
+        if self.working_bank.slot() == slot {
+            if let Some(bank) = self.banks.values().max_by_key(|bank| bank.slot()) {
+                self.working_bank = bank.clone();
+            }
+        }
+    }
+
+    pub fn is_evicted(&self, slot: u64) -> bool {
+        self.evicted_slots.contains(&slot)
+    }
+
     pub fn new_from_banks(initial_banks: &[Arc<Bank>], root: u64) -> Self {
         let mut banks = HashMap::new();
         let working_bank = initial_banks[0].clone();
@@ -139,6 +246,8 @@ impl BankForks {
             slots: HashSet::new(),
             snapshot_path: None,
             confidence: HashMap::new(),
+            pruning_config: ForkPruningConfig::default(),
+            evicted_slots: HashSet::new(),
         }
     }
 
@@ -171,6 +280,7 @@ impl BankForks {
         root_bank.squash();
         let new_tx_count = root_bank.transaction_count();
         self.prune_non_root(root);
+        self.evicted_slots.retain(|slot| *slot > root);
 
         inc_new_counter_info!(
             "bank-forks_set_root_ms",
@@ -180,6 +290,13 @@ impl BankForks {
             "bank-forks_set_root_tx_count",
             (new_tx_count - root_tx_count) as usize
         );
+        datapoint_info!(
+            "bank-forks_banks",
+            ("root", root, i64),
+            ("active_bank_count", self.active_bank_count(), i64),
+            ("estimated_memory_size", self.estimated_memory_size(), i64),
+            ("evicted_count", self.evicted_slots.len(), i64)
+        );
     }
 
     pub fn root(&self) -> u64 {
@@ -212,6 +329,44 @@ impl BankForks {
         self.slots = slots.clone();
     }
 
+    /// Prunes the fork tips that `pruning_config` doesn't allow for, the tip with the least
+    /// `weight` first, until the banks past the root fit within `max_active_banks` and none is
+    /// past `max_depth`. The slots in `keep` are never pruned, nor their ancestors, which aren't
+    /// tips while they're kept, even if that leaves the limits exceeded. Returns the pruned
+    /// slots, in the order they were pruned, so each was a tip when it went.
+    pub fn prune_beyond_limits<F>(&mut self, keep: &HashSet<u64>, weight: F) -> Vec<u64>
+    where
+        F: Fn(u64) -> u64,
+    {
+        let mut pruned = vec![];
+        loop {
+            let has_room = self.banks.keys().filter(|slot| **slot > self.root).count()
+                <= self.pruning_config.max_active_banks;
+            let lightest_tip = self
+                .fork_tips()
+                .into_iter()
+                .filter(|slot| !keep.contains(slot))
+                .filter(|slot| !has_room || !self.within_max_depth(*slot))
+                .min_by_key(|slot| (weight(*slot), *slot));
+            match lightest_tip {
+                Some(slot) => {
+                    self.remove_tip(slot);
+                    pruned.push(slot);
+                }
+                None => break,
+            }
+        }
+        if !pruned.is_empty() {
+            warn!(
+                "pruned {} banks past root {} beyond {:?}",
+                pruned.len(),
+                self.root,
+                self.pruning_config
+            );
+        }
+        pruned
+    }
+
     pub fn cache_fork_confidence(
         &mut self,
         fork: u64,
@@ -429,6 +584,8 @@ impl BankForks {
             slots,
             snapshot_path: snapshot_path.clone(),
             confidence: HashMap::new(),
+            pruning_config: ForkPruningConfig::default(),
+            evicted_slots: HashSet::new(),
         })
     }
 }
@@ -456,6 +613,106 @@ mod tests {
         assert_eq!(bank_forks.working_bank().tick_height(), 1);
     }
 
+    #[test]
+    fn test_bank_forks_prune_beyond_limits() {
+        let GenesisBlockInfo { genesis_block, .. } = create_genesis_block(10_000);
+        let new_bank_forks = |pruning_config| {
+            let mut bank_forks = BankForks::new(0, Bank::new(&genesis_block));
+            for (slot, parent) in &[(1, 0), (2, 0), (3, 2), (5, 2), (6, 3)] {
+                let bank = Bank::new_from_parent(&bank_forks[*parent], &Pubkey::default(), *slot);
+                bank_forks.insert(bank);
+            }
+            bank_forks.set_pruning_config(pruning_config);
+            bank_forks
+        };
+        let sorted_slots = |bank_forks: &BankForks| {
+            let mut slots: Vec<_> = bank_forks.banks.keys().cloned().collect();
+            slots.sort();
+            slots
+        };
+
+        let no_weight = |_| 0;
+
+        let mut bank_forks = new_bank_forks(ForkPruningConfig::default());
+        assert_eq!(bank_forks.active_bank_count(), 6);
+        assert!(bank_forks.estimated_memory_size() >= 6 * mem::size_of::<Bank>() as u64);
+        assert!(bank_forks.within_pruning_limits(7));
+        assert!(bank_forks
+            .prune_beyond_limits(&HashSet::new(), no_weight)
+            .is_empty());
+        assert_eq!(sorted_slots(&bank_forks), vec![0, 1, 2, 3, 5, 6]);
+
+        // Too many banks, the lightest tips go, until the ones left fit
+        let mut bank_forks = new_bank_forks(ForkPruningConfig {
+            max_depth: 10,
+            max_active_banks: 3,
+        });
+        assert!(!bank_forks.within_pruning_limits(7));
+        let weight = |slot| if slot == 6 { 10 } else { 0 };
+        assert_eq!(
+            bank_forks.prune_beyond_limits(&HashSet::new(), weight),
+            vec![1, 5]
+        );
+        assert_eq!(sorted_slots(&bank_forks), vec![0, 2, 3, 6]);
+        assert_eq!(bank_forks.working_bank().slot(), 6);
+
+        // Kept slots and their ancestors stay, even past the limits
+        let mut bank_forks = new_bank_forks(ForkPruningConfig {
+            max_depth: 10,
+            max_active_banks: 1,
+        });
+        let keep = vec![1, 6].into_iter().collect();
+        assert_eq!(bank_forks.prune_beyond_limits(&keep, no_weight), vec![5]);
+        assert_eq!(sorted_slots(&bank_forks), vec![0, 1, 2, 3, 6]);
+
+        // Too far past the root
+        let mut bank_forks = new_bank_forks(ForkPruningConfig {
+            max_depth: 2,
+            max_active_banks: 10,
+        });
+        assert_eq!(
+            bank_forks.prune_beyond_limits(&HashSet::new(), no_weight),
+            vec![5, 6, 3]
+        );
+        assert_eq!(sorted_slots(&bank_forks), vec![0, 1, 2]);
+        assert!(bank_forks.within_pruning_limits(2));
+        assert!(!bank_forks.within_pruning_limits(3));
+    }
+
+    #[test]
+    fn test_bank_forks_evict() {
+        let GenesisBlockInfo { genesis_block, .. } = create_genesis_block(10_000);
+        let mut bank_forks = BankForks::new(0, Bank::new(&genesis_block));
+        for (slot, parent) in &[(1, 0), (2, 0), (3, 2), (4, 2)] {
+            let bank = Bank::new_from_parent(&bank_forks[*parent], &Pubkey::default(), *slot);
+            bank_forks.insert(bank);
+        }
+        bank_forks.set_pruning_config(ForkPruningConfig {
+            max_active_banks: 4,
+            ..ForkPruningConfig::default()
+        });
+        assert!(!bank_forks.has_room_for_bank());
+        assert!(bank_forks.within_max_depth(5));
+
+        // Only frozen banks are stale
+        bank_forks[3].freeze();
+        assert_eq!(bank_forks.stale_fork_tips(), vec![3]);
+        bank_forks[1].freeze();
+        let mut tips = bank_forks.stale_fork_tips();
+        tips.sort();
+        assert_eq!(tips, vec![1, 3]);
+
+        bank_forks.evict(3);
+        assert!(bank_forks.get(3).is_none());
+        assert!(bank_forks.is_evicted(3));
+        assert!(bank_forks.has_room_for_bank());
+        assert_eq!(bank_forks.working_bank().slot(), 4);
+
+        // Evicted slots are forgotten once rooted past
+        bank_forks.set_root(4);
+        assert!(!bank_forks.is_evicted(3));
+    }
+
     #[test]
     fn test_bank_forks_descendants() {
         let GenesisBlockInfo { genesis_block, .. } = create_genesis_block(10_000);
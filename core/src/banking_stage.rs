@@ -3,6 +3,7 @@
 //! can do its processing in parallel with signature verification on the GPU.
 use crate::blocktree::Blocktree;
 use crate::cluster_info::ClusterInfo;
+use crate::dedup_filter::DedupFilter;
 use crate::entry;
 use crate::entry::{hash_transactions, Entry};
 use crate::leader_schedule_cache::LeaderScheduleCache;
@@ -11,6 +12,7 @@ use crate::packet::PACKETS_PER_BLOB;
 use crate::packet::{Packet, Packets};
 use crate::poh_recorder::{PohRecorder, PohRecorderError, WorkingBankEntries};
 use crate::poh_service::PohService;
+use crate::prefetch_stage::{PrefetchSender, PrefetchStage};
 use crate::result::{Error, Result};
 use crate::service::Service;
 use crate::sigverify_stage::VerifiedPackets;
@@ -20,7 +22,7 @@ use itertools::Itertools;
 use solana_measure::measure::Measure;
 use solana_metrics::{inc_new_counter_debug, inc_new_counter_info, inc_new_counter_warn};
 use solana_runtime::accounts_db::ErrorCounters;
-use solana_runtime::bank::Bank;
+use solana_runtime::bank::{Bank, LoadAndExecuteTransactionsOutput};
 use solana_runtime::locked_accounts_results::LockedAccountsResults;
 use solana_sdk::poh_config::PohConfig;
 use solana_sdk::pubkey::Pubkey;
@@ -28,7 +30,7 @@ use solana_sdk::timing::{
     self, DEFAULT_NUM_TICKS_PER_SECOND, DEFAULT_TICKS_PER_SLOT, MAX_PROCESSING_AGE,
     MAX_TRANSACTION_FORWARDING_DELAY,
 };
-use solana_sdk::transaction::{self, Transaction, TransactionError};
+use solana_sdk::transaction::{self, SanitizedTransaction, Transaction, TransactionError};
 use std::cmp;
 use std::env;
 use std::net::UdpSocket;
@@ -53,6 +55,7 @@ const TOTAL_BUFFERED_PACKETS: usize = 500_000;
 /// Stores the stage's thread handle and output receiver.
 pub struct BankingStage {
     bank_thread_hdls: Vec<JoinHandle<()>>,
+    prefetch_stage: PrefetchStage,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -92,6 +95,10 @@ impl BankingStage {
         // This thread talks to poh_service and broadcasts the entries once they have been recorded.
         // Once an entry has been recorded, its blockhash is registered with the bank.
         let my_pubkey = cluster_info.read().unwrap().id();
+        // Warms the accounts of the next queued batch while a thread processes the current one
+        let (prefetch_stage, prefetch_sender) = PrefetchStage::new();
+        // Shared by the threads, so a transaction is dropped whichever thread saw it first
+        let dedup_filter = Arc::new(Mutex::new(DedupFilter::default()));
         // Many banks that process transactions in parallel.
         let bank_thread_hdls: Vec<JoinHandle<()>> = (0..num_threads)
             .map(|i| {
@@ -104,6 +111,8 @@ impl BankingStage {
 
                 let poh_recorder = poh_recorder.clone();
                 let cluster_info = cluster_info.clone();
+                let prefetch_sender = prefetch_sender.clone();
+                let dedup_filter = dedup_filter.clone();
                 let mut recv_start = Instant::now();
                 Builder::new()
                     .name("solana-banking-stage-tx".to_string())
@@ -117,12 +126,17 @@ impl BankingStage {
                             enable_forwarding,
                             i,
                             batch_limit,
+                            &prefetch_sender,
+                            &dedup_filter,
                         );
                     })
                     .unwrap()
             })
             .collect();
-        Self { bank_thread_hdls }
+        Self {
+            bank_thread_hdls,
+            prefetch_stage,
+        }
     }
 
     fn filter_valid_packets_for_forwarding(all_packets: &[PacketsAndOffsets]) -> Vec<&Packet> {
@@ -153,12 +167,13 @@ impl BankingStage {
         poh_recorder: &Arc<Mutex<PohRecorder>>,
         buffered_packets: &mut Vec<PacketsAndOffsets>,
         batch_limit: usize,
+        prefetch_sender: &PrefetchSender,
     ) -> Result<UnprocessedPackets> {
         let mut unprocessed_packets = vec![];
         let mut rebuffered_packets = 0;
         let mut new_tx_count = 0;
         let buffered_len = buffered_packets.len();
-        let mut buffered_packets_iter = buffered_packets.drain(..);
+        let mut buffered_packets_iter = buffered_packets.drain(..).peekable();
         let mut dropped_batches_count = 0;
 
         let mut proc_start = Measure::start("consume_buffered_process");
@@ -177,6 +192,11 @@ impl BankingStage {
             }
             let bank = bank.unwrap();
 
+            if let Some((next_msgs, next_unprocessed_indexes)) = buffered_packets_iter.peek() {
+                Self::prefetch_packets(prefetch_sender, &bank, next_msgs, next_unprocessed_indexes);
+            }
+
+            let process_start = Instant::now();
             let (processed, verified_txs_len, new_unprocessed_indexes) =
                 Self::process_received_packets(
                     &bank,
@@ -184,6 +204,7 @@ impl BankingStage {
                     &msgs,
                     unprocessed_indexes.to_owned(),
                 );
+            solana_metrics::record_latency("banking", process_start.elapsed());
 
             new_tx_count += processed;
 
@@ -274,6 +295,7 @@ impl BankingStage {
         buffered_packets: &mut Vec<PacketsAndOffsets>,
         enable_forwarding: bool,
         batch_limit: usize,
+        prefetch_sender: &PrefetchSender,
     ) -> Result<()> {
         let (poh_next_slot_leader, poh_has_bank, would_be_leader) = {
             let poh = poh_recorder.lock().unwrap();
@@ -300,6 +322,7 @@ impl BankingStage {
                     poh_recorder,
                     buffered_packets,
                     batch_limit,
+                    prefetch_sender,
                 )?;
                 buffered_packets.append(&mut unprocessed);
                 Ok(())
@@ -347,6 +370,8 @@ impl BankingStage {
         enable_forwarding: bool,
         id: u32,
         batch_limit: usize,
+        prefetch_sender: &PrefetchSender,
+        dedup_filter: &Mutex<DedupFilter>,
     ) {
         let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
         let mut buffered_packets = vec![];
@@ -360,6 +385,7 @@ impl BankingStage {
                     &mut buffered_packets,
                     enable_forwarding,
                     batch_limit,
+                    prefetch_sender,
                 )
                 .unwrap_or_else(|_| buffered_packets.clear());
             }
@@ -382,6 +408,8 @@ impl BankingStage {
                 recv_timeout,
                 id,
                 batch_limit,
+                prefetch_sender,
+                dedup_filter,
             ) {
                 Err(Error::CrossbeamRecvTimeoutError(RecvTimeoutError::Timeout)) => (),
                 Err(Error::CrossbeamRecvTimeoutError(RecvTimeoutError::Disconnected)) => break,
@@ -414,18 +442,23 @@ impl BankingStage {
         )
     }
 
-    /// Convert the transactions from a blob of binary data to a vector of transactions
-    fn deserialize_transactions(p: &Packets) -> Vec<Option<Transaction>> {
+    /// Convert the transactions from a blob of binary data to a vector of transactions. Packets
+    /// that don't hold a well-formed transaction are `None`.
+    fn deserialize_transactions(p: &Packets) -> Vec<Option<SanitizedTransaction>> {
         p.packets
             .iter()
-            .map(|x| deserialize(&x.data[0..x.meta.size]).ok())
+            .map(|x| {
+                deserialize::<Transaction>(&x.data[0..x.meta.size])
+                    .ok()
+                    .and_then(|tx| SanitizedTransaction::try_new(tx).ok())
+            })
             .collect()
     }
 
     #[allow(clippy::match_wild_err_arm)]
     fn record_transactions(
         bank_slot: u64,
-        txs: &[Transaction],
+        txs: &[SanitizedTransaction],
         results: &[transaction::Result<()>],
         poh: &Arc<Mutex<PohRecorder>>,
     ) -> (Result<()>, Vec<usize>) {
@@ -436,7 +469,7 @@ impl BankingStage {
             .enumerate()
             .filter_map(|(i, (r, x))| {
                 if Bank::can_commit(r) {
-                    Some((x.clone(), i))
+                    Some((Transaction::clone(x), i))
                 } else {
                     None
                 }
@@ -479,7 +512,7 @@ impl BankingStage {
 
     fn process_and_record_transactions_locked(
         bank: &Bank,
-        txs: &[Transaction],
+        txs: &[SanitizedTransaction],
         poh: &Arc<Mutex<PohRecorder>>,
         lock_results: &LockedAccountsResults,
     ) -> (Result<()>, Vec<usize>) {
@@ -488,8 +521,17 @@ impl BankingStage {
         // the likelihood of any single thread getting starved and processing old ids.
         // TODO: Banking stage threads should be prioritized to complete faster then this queue
         // expires.
-        let (mut loaded_accounts, results, mut retryable_txs, tx_count, signature_count) =
-            bank.load_and_execute_transactions(txs, lock_results, MAX_PROCESSING_AGE);
+        let LoadAndExecuteTransactionsOutput {
+            mut loaded_accounts,
+            executed: results,
+            return_data,
+            owner_changes,
+            pre_balances,
+            mut retryable_txs,
+            tx_count,
+            signature_count,
+            ..
+        } = bank.load_and_execute_transactions(txs, lock_results, MAX_PROCESSING_AGE);
         load_execute_time.stop();
 
         let freeze_lock = bank.freeze_lock();
@@ -512,6 +554,9 @@ impl BankingStage {
                 txs,
                 &mut loaded_accounts,
                 &results,
+                &return_data,
+                &owner_changes,
+                pre_balances,
                 tx_count,
                 signature_count,
             );
@@ -535,7 +580,7 @@ impl BankingStage {
 
     pub fn process_and_record_transactions(
         bank: &Bank,
-        txs: &[Transaction],
+        txs: &[SanitizedTransaction],
         poh: &Arc<Mutex<PohRecorder>>,
         chunk_offset: usize,
     ) -> (Result<()>, Vec<usize>) {
@@ -571,7 +616,7 @@ impl BankingStage {
     /// than the total number if max PoH height was reached and the bank halted
     fn process_transactions(
         bank: &Bank,
-        transactions: &[Transaction],
+        transactions: &[SanitizedTransaction],
         poh: &Arc<Mutex<PohRecorder>>,
     ) -> (usize, Vec<usize>) {
         let mut chunk_start = 0;
@@ -581,7 +626,9 @@ impl BankingStage {
                 + entry::num_will_fit(
                     &transactions[chunk_start..],
                     packet::BLOB_DATA_SIZE as u64,
-                    &Entry::serialized_to_blob_size,
+                    &|txs: &[SanitizedTransaction]| {
+                        Entry::serialized_to_blob_size(SanitizedTransaction::as_transactions(txs))
+                    },
                 );
 
             let (result, retryable_txs_in_chunk) = Self::process_and_record_transactions(
@@ -617,9 +664,9 @@ impl BankingStage {
     // This function returns a vector of transactions that are not None. It also returns a vector
     // with position of the transaction in the input list
     fn filter_transaction_indexes(
-        transactions: Vec<Option<Transaction>>,
+        transactions: Vec<Option<SanitizedTransaction>>,
         indexes: &[usize],
-    ) -> (Vec<Transaction>, Vec<usize>) {
+    ) -> (Vec<SanitizedTransaction>, Vec<usize>) {
         transactions
             .into_iter()
             .zip(indexes)
@@ -633,7 +680,7 @@ impl BankingStage {
     // This function creates a filter of transaction results with Ok() for every pending
     // transaction. The non-pending transactions are marked with TransactionError
     fn prepare_filter_for_pending_transactions(
-        transactions: &[Transaction],
+        transactions: &[SanitizedTransaction],
         pending_tx_indexes: &[usize],
     ) -> Vec<transaction::Result<()>> {
         let mut mask = vec![Err(TransactionError::BlockhashNotFound); transactions.len()];
@@ -659,11 +706,11 @@ impl BankingStage {
             .collect()
     }
 
-    // This function deserializes packets into transactions and returns non-None transactions
+    // This function deserializes packets into sanitized transactions, dropping malformed ones
     fn transactions_from_packets(
         msgs: &Packets,
         transaction_indexes: &[usize],
-    ) -> (Vec<Transaction>, Vec<usize>) {
+    ) -> (Vec<SanitizedTransaction>, Vec<usize>) {
         let packets = Packets::new(
             transaction_indexes
                 .iter()
@@ -683,7 +730,7 @@ impl BankingStage {
     /// * `pending_indexes` - identifies which indexes in the `transactions` list are still pending
     fn filter_pending_packets_from_pending_txs(
         bank: &Arc<Bank>,
-        transactions: &[Transaction],
+        transactions: &[SanitizedTransaction],
         transaction_to_packet_indexes: &[usize],
         pending_indexes: &[usize],
     ) -> Vec<usize> {
@@ -782,10 +829,18 @@ impl BankingStage {
         filtered_unprocessed_packet_indexes
     }
 
-    fn generate_packet_indexes(vers: Vec<u8>) -> Vec<usize> {
-        vers.iter()
+    // The indexes of the packets sigverify didn't mark to be discarded
+    fn generate_packet_indexes(packets: &[Packet]) -> Vec<usize> {
+        packets
+            .iter()
             .enumerate()
-            .filter_map(|(index, ver)| if *ver != 0 { Some(index) } else { None })
+            .filter_map(|(index, packet)| {
+                if !packet.meta.discard {
+                    Some(index)
+                } else {
+                    None
+                }
+            })
             .collect()
     }
 
@@ -798,13 +853,15 @@ impl BankingStage {
         recv_timeout: Duration,
         id: u32,
         batch_limit: usize,
+        prefetch_sender: &PrefetchSender,
+        dedup_filter: &Mutex<DedupFilter>,
     ) -> Result<UnprocessedPackets> {
         let mut recv_time = Measure::start("process_packets_recv");
         let mms = verified_receiver.recv_timeout(recv_timeout)?;
         recv_time.stop();
 
         let mms_len = mms.len();
-        let count: usize = mms.iter().map(|x| x.1.len()).sum();
+        let count: usize = mms.iter().map(|x| x.packets.len()).sum();
         debug!(
             "@{:?} process start stalled for: {:?}ms txs: {} id: {}",
             timing::timestamp(),
@@ -816,11 +873,31 @@ impl BankingStage {
         let mut proc_start = Measure::start("process_received_packets_process");
         let mut new_tx_count = 0;
 
-        let mut mms_iter = mms.into_iter();
+        // Drop the transactions already seen before spending any more time on them
+        let mut dedup_time = Measure::start("process_packets_dedup");
+        let mut duplicate_packets_count = 0;
+        let mms: Vec<_> = {
+            let mut dedup_filter = dedup_filter.lock().unwrap();
+            mms.into_iter()
+                .map(|msgs| {
+                    let (packet_indexes, num_duplicates) = dedup_filter
+                        .dedup_packets(&msgs, Self::generate_packet_indexes(&msgs.packets));
+                    duplicate_packets_count += num_duplicates;
+                    (msgs, packet_indexes)
+                })
+                .collect()
+        };
+        dedup_time.stop();
+        inc_new_counter_info!(
+            "banking_stage-dropped_duplicate_packets",
+            duplicate_packets_count
+        );
+        inc_new_counter_debug!("banking_stage-dedup_us", dedup_time.as_us() as usize);
+
+        let mut mms_iter = mms.into_iter().peekable();
         let mut unprocessed_packets = vec![];
         let mut dropped_batches_count = 0;
-        while let Some((msgs, vers)) = mms_iter.next() {
-            let packet_indexes = Self::generate_packet_indexes(vers);
+        while let Some((msgs, packet_indexes)) = mms_iter.next() {
             let bank = poh.lock().unwrap().bank();
             if bank.is_none() {
                 Self::push_unprocessed(
@@ -834,8 +911,14 @@ impl BankingStage {
             }
             let bank = bank.unwrap();
 
+            if let Some((next_msgs, next_packet_indexes)) = mms_iter.peek() {
+                Self::prefetch_packets(prefetch_sender, &bank, next_msgs, next_packet_indexes);
+            }
+
+            let process_start = Instant::now();
             let (processed, verified_txs_len, unprocessed_indexes) =
                 Self::process_received_packets(&bank, &poh, &msgs, packet_indexes);
+            solana_metrics::record_latency("banking", process_start.elapsed());
 
             new_tx_count += processed;
 
@@ -851,8 +934,7 @@ impl BankingStage {
             if processed < verified_txs_len {
                 let next_leader = poh.lock().unwrap().next_slot_leader();
                 // Walk thru rest of the transactions and filter out the invalid (e.g. too old) ones
-                while let Some((msgs, vers)) = mms_iter.next() {
-                    let packet_indexes = Self::generate_packet_indexes(vers);
+                while let Some((msgs, packet_indexes)) = mms_iter.next() {
                     let unprocessed_indexes = Self::filter_unprocessed_packets(
                         &bank,
                         &msgs,
@@ -893,6 +975,21 @@ impl BankingStage {
         Ok(unprocessed_packets)
     }
 
+    // Queue the transactions of the next batch to have their accounts warmed while the
+    // current batch is processed
+    fn prefetch_packets(
+        prefetch_sender: &PrefetchSender,
+        bank: &Arc<Bank>,
+        msgs: &Packets,
+        packet_indexes: &[usize],
+    ) {
+        let (transactions, _) = Self::transactions_from_packets(msgs, packet_indexes);
+        if !transactions.is_empty() {
+            // The stage exits with the banking threads, so a failed send can be ignored
+            let _ = prefetch_sender.send((bank.clone(), transactions));
+        }
+    }
+
     fn push_unprocessed(
         unprocessed_packets: &mut UnprocessedPackets,
         packets: Packets,
@@ -917,7 +1014,7 @@ impl Service for BankingStage {
         for bank_thread_hdl in self.bank_thread_hdls {
             bank_thread_hdl.join()?;
         }
-        Ok(())
+        self.prefetch_stage.join()
     }
 }
 
@@ -1100,15 +1197,13 @@ mod tests {
             let tx_anf = system_transaction::create_user_account(&keypair, &to3, 1, start_hash);
 
             // send 'em over
-            let packets = to_packets(&[tx_no_ver, tx_anf, tx]);
+            let mut packets = to_packets(&[tx_no_ver, tx_anf, tx]);
 
             // glad they all fit
             assert_eq!(packets.len(), 1);
 
-            let packets = packets
-                .into_iter()
-                .map(|packets| (packets, vec![0u8, 1u8, 1u8]))
-                .collect();
+            // tx_no_ver failed sigverify
+            packets[0].packets[0].meta.discard = true;
 
             verified_sender // no_ver, anf, tx
                 .send(packets)
@@ -1132,7 +1227,9 @@ mod tests {
 
                 for entries in &ventries {
                     for entry in entries {
-                        bank.process_transactions(&entry.transactions)
+                        let transactions =
+                            SanitizedTransaction::try_new_all(entry.transactions.clone()).unwrap();
+                        bank.process_transactions(&transactions)
                             .iter()
                             .for_each(|x| assert_eq!(*x, Ok(())));
                     }
@@ -1179,10 +1276,6 @@ mod tests {
         );
 
         let packets = to_packets(&[tx]);
-        let packets = packets
-            .into_iter()
-            .map(|packets| (packets, vec![1u8]))
-            .collect();
         verified_sender.send(packets).unwrap();
 
         // Process a second batch that spends one of those lamports.
@@ -1193,10 +1286,6 @@ mod tests {
             genesis_block.hash(),
         );
         let packets = to_packets(&[tx]);
-        let packets = packets
-            .into_iter()
-            .map(|packets| (packets, vec![1u8]))
-            .collect();
         verified_sender.send(packets).unwrap();
 
         let (vote_sender, vote_receiver) = unbounded();
@@ -1242,7 +1331,9 @@ mod tests {
 
             let bank = Bank::new(&genesis_block);
             for entry in &entries {
-                bank.process_transactions(&entry.transactions)
+                let transactions =
+                    SanitizedTransaction::try_new_all(entry.transactions.clone()).unwrap();
+                bank.process_transactions(&transactions)
                     .iter()
                     .for_each(|x| assert_eq!(*x, Ok(())));
             }
@@ -1290,10 +1381,11 @@ mod tests {
             let keypair2 = Keypair::new();
             let pubkey2 = Pubkey::new_rand();
 
-            let transactions = vec![
+            let transactions = SanitizedTransaction::try_new_all(vec![
                 system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
                 system_transaction::transfer(&keypair2, &pubkey2, 1, genesis_block.hash()),
-            ];
+            ])
+            .unwrap();
 
             let mut results = vec![Ok(()), Ok(())];
             BankingStage::record_transactions(bank.slot(), &transactions, &results, &poh_recorder);
@@ -1405,15 +1497,20 @@ mod tests {
             None,
             None,
         ];
+        let transactions: Vec<_> = transactions
+            .into_iter()
+            .map(|tx| tx.map(|tx| SanitizedTransaction::try_new(tx).unwrap()))
+            .collect();
 
-        let filtered_transactions = vec![
+        let filtered_transactions = SanitizedTransaction::try_new_all(vec![
             system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
             system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
             system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
             system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
             system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
             system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
-        ];
+        ])
+        .unwrap();
 
         assert_eq!(
             BankingStage::filter_transaction_indexes(
@@ -1441,14 +1538,15 @@ mod tests {
         } = create_genesis_block(10_000);
         let pubkey = Pubkey::new_rand();
 
-        let transactions = vec![
+        let transactions = SanitizedTransaction::try_new_all(vec![
             system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
             system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
             system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
             system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
             system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
             system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
-        ];
+        ])
+        .unwrap();
 
         assert_eq!(
             BankingStage::prepare_filter_for_pending_transactions(&transactions, &vec![2, 4, 5],),
@@ -1584,12 +1682,14 @@ mod tests {
         let bank = Arc::new(Bank::new(&genesis_block));
         let pubkey = Pubkey::new_rand();
 
-        let transactions = vec![system_transaction::create_user_account(
-            &mint_keypair,
-            &pubkey,
-            1,
-            genesis_block.hash(),
-        )];
+        let transactions =
+            SanitizedTransaction::try_new_all(vec![system_transaction::create_user_account(
+                &mint_keypair,
+                &pubkey,
+                1,
+                genesis_block.hash(),
+            )])
+            .unwrap();
 
         let working_bank = WorkingBank {
             bank: bank.clone(),
@@ -1639,12 +1739,14 @@ mod tests {
 
             assert_eq!(done, true);
 
-            let transactions = vec![system_transaction::transfer(
-                &mint_keypair,
-                &pubkey,
-                2,
-                genesis_block.hash(),
-            )];
+            let transactions =
+                SanitizedTransaction::try_new_all(vec![system_transaction::transfer(
+                    &mint_keypair,
+                    &pubkey,
+                    2,
+                    genesis_block.hash(),
+                )])
+                .unwrap();
 
             assert_matches!(
                 BankingStage::process_and_record_transactions(
@@ -1674,10 +1776,11 @@ mod tests {
         let pubkey = Pubkey::new_rand();
         let pubkey1 = Pubkey::new_rand();
 
-        let transactions = vec![
+        let transactions = SanitizedTransaction::try_new_all(vec![
             system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
             system_transaction::transfer(&mint_keypair, &pubkey1, 1, genesis_block.hash()),
-        ];
+        ])
+        .unwrap();
 
         let working_bank = WorkingBank {
             bank: bank.clone(),
@@ -1804,6 +1907,7 @@ mod tests {
             // record
             let poh_recorder = Arc::new(Mutex::new(poh_recorder));
 
+            let transactions = SanitizedTransaction::try_new_all(transactions).unwrap();
             let (processed_transactions_count, mut retryable_txs) =
                 BankingStage::process_transactions(&bank, &transactions, &poh_recorder);
 
@@ -27,6 +27,22 @@ impl BlobFetchStage {
 
         Self { thread_hdls }
     }
+
+    /// Also receives the responses to repair requests on `repair_socket`
+    pub fn new_with_repair_socket(
+        sockets: Vec<Arc<UdpSocket>>,
+        repair_socket: Arc<UdpSocket>,
+        sender: &BlobSender,
+        exit: &Arc<AtomicBool>,
+    ) -> Self {
+        let mut fetch_stage = Self::new_multi_socket(sockets, sender, exit);
+        fetch_stage.thread_hdls.push(streamer::repair_blob_receiver(
+            repair_socket,
+            &exit,
+            sender.clone(),
+        ));
+        fetch_stage
+    }
 }
 
 impl Service for BlobFetchStage {
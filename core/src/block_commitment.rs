@@ -0,0 +1,159 @@
+//! The `block_commitment` module tracks how much of the cluster's stake is committed to each
+//! recent slot, from the latest vote of every staked validator. A validator's vote carries its
+//! recent votes, so a slot it voted for deeper in that list has more confirmations behind it.
+
+use solana_sdk::pubkey::Pubkey;
+use solana_vote_api::vote_state::MAX_LOCKOUT_HISTORY;
+use std::collections::HashMap;
+
+/// The stake that voted for a slot, by how many confirmations the votes gave the slot
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BlockCommitment {
+    commitment: [u64; MAX_LOCKOUT_HISTORY],
+}
+
+impl BlockCommitment {
+    pub fn increase_confirmation_stake(&mut self, confirmation_count: usize, stake: u64) {
+        assert!(confirmation_count > 0 && confirmation_count <= MAX_LOCKOUT_HISTORY);
+        self.commitment[confirmation_count - 1] += stake;
+    }
+
+    pub fn get_confirmation_stake(&self, confirmation_count: usize) -> u64 {
+        assert!(confirmation_count > 0 && confirmation_count <= MAX_LOCKOUT_HISTORY);
+        self.commitment[confirmation_count - 1]
+    }
+
+    /// The stake that gave the slot at least `min_confirmation_count` confirmations
+    pub fn get_confirmed_stake(&self, min_confirmation_count: usize) -> u64 {
+        assert!(min_confirmation_count > 0 && min_confirmation_count <= MAX_LOCKOUT_HISTORY);
+        self.commitment[min_confirmation_count - 1..].iter().sum()
+    }
+
+    pub fn commitment(&self) -> &[u64; MAX_LOCKOUT_HISTORY] {
+        &self.commitment
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BlockCommitmentCache {
+    /// The slots of each vote account's latest vote, in ascending order, with the account's stake
+    latest_votes: HashMap<Pubkey, (Vec<u64>, u64)>,
+    block_commitment: HashMap<u64, BlockCommitment>,
+    total_stake: u64,
+    root: u64,
+}
+
+impl BlockCommitmentCache {
+    /// Records a vote for `slots` by `vote_pubkey`, replacing the account's previous vote unless
+    /// that one is newer. Votes can arrive out of order from gossip. Returns whether the vote was
+    /// recorded.
+    pub fn record_vote(&mut self, vote_pubkey: Pubkey, mut slots: Vec<u64>, stake: u64) -> bool {
+        slots.sort();
+        slots.dedup();
+        let last_slot = match slots.last() {
+            Some(last_slot) => *last_slot,
+            None => return false,
+        };
+        if let Some((latest_slots, _)) = self.latest_votes.get(&vote_pubkey) {
+            if latest_slots.last().map_or(false, |slot| *slot >= last_slot) {
+                return false;
+            }
+        }
+        self.latest_votes.insert(vote_pubkey, (slots, stake));
+        true
+    }
+
+    pub fn set_total_stake(&mut self, total_stake: u64) {
+        self.total_stake = total_stake;
+    }
+
+    /// Forgets the slots below `root`, along with the votes that only voted for them
+    pub fn set_root(&mut self, root: u64) {
+        self.root = root;
+        self.latest_votes
+            .retain(|_, (slots, _)| slots.last().map_or(false, |slot| *slot >= root));
+        self.block_commitment.retain(|slot, _| *slot >= root);
+    }
+
+    /// Recomputes the commitment of every slot from the latest votes
+    pub fn aggregate(&mut self) {
+        let mut block_commitment: HashMap<u64, BlockCommitment> = HashMap::new();
+        for (slots, stake) in self.latest_votes.values() {
+            for (i, slot) in slots.iter().enumerate() {
+                if *slot < self.root {
+                    continue;
+                }
+                let confirmation_count = (slots.len() - i).min(MAX_LOCKOUT_HISTORY);
+                block_commitment
+                    .entry(*slot)
+                    .or_default()
+                    .increase_confirmation_stake(confirmation_count, *stake);
+            }
+        }
+        self.block_commitment = block_commitment;
+    }
+
+    pub fn get_block_commitment(&self, slot: u64) -> Option<&BlockCommitment> {
+        self.block_commitment.get(&slot)
+    }
+
+    /// The stake of every staked vote account in the current epoch
+    pub fn total_stake(&self) -> u64 {
+        self.total_stake
+    }
+
+    pub fn root(&self) -> u64 {
+        self.root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_commitment() {
+        let mut commitment = BlockCommitment::default();
+        commitment.increase_confirmation_stake(1, 10);
+        commitment.increase_confirmation_stake(2, 20);
+        commitment.increase_confirmation_stake(2, 5);
+        assert_eq!(commitment.get_confirmation_stake(1), 10);
+        assert_eq!(commitment.get_confirmation_stake(2), 25);
+        assert_eq!(commitment.get_confirmed_stake(1), 35);
+        assert_eq!(commitment.get_confirmed_stake(2), 25);
+        assert_eq!(commitment.get_confirmed_stake(3), 0);
+    }
+
+    #[test]
+    fn test_block_commitment_cache() {
+        let mut cache = BlockCommitmentCache::default();
+        let pubkey0 = Pubkey::new_rand();
+        let pubkey1 = Pubkey::new_rand();
+        assert!(cache.record_vote(pubkey0, vec![3, 1, 2], 10));
+        assert!(cache.record_vote(pubkey1, vec![2], 20));
+        // A vote older than the account's latest is ignored
+        assert!(!cache.record_vote(pubkey0, vec![1, 2], 10));
+        assert!(!cache.record_vote(pubkey1, vec![], 20));
+        cache.aggregate();
+
+        let commitment = cache.get_block_commitment(1).unwrap();
+        assert_eq!(commitment.get_confirmation_stake(3), 10);
+        assert_eq!(commitment.get_confirmed_stake(1), 10);
+        let commitment = cache.get_block_commitment(2).unwrap();
+        assert_eq!(commitment.get_confirmation_stake(1), 20);
+        assert_eq!(commitment.get_confirmation_stake(2), 10);
+        assert_eq!(commitment.get_confirmed_stake(1), 30);
+        assert_eq!(cache.get_block_commitment(4), None);
+
+        // pubkey1 only voted below the new root
+        cache.set_root(3);
+        cache.aggregate();
+        assert_eq!(cache.get_block_commitment(2), None);
+        let commitment = cache.get_block_commitment(3).unwrap();
+        assert_eq!(commitment.get_confirmed_stake(1), 10);
+        assert!(cache.record_vote(pubkey1, vec![3, 4], 20));
+        cache.aggregate();
+        let commitment = cache.get_block_commitment(3).unwrap();
+        assert_eq!(commitment.get_confirmed_stake(1), 30);
+    }
+}
@@ -1,6 +1,7 @@
-//! The `blockstream` module provides a method for streaming entries out via a
-//! local unix socket, to provide client services such as a block explorer with
-//! real-time access to entries.
+//! The `blockstream` module provides a method for streaming entries and block metadata out to
+//! a `BlockstreamSink`, such as a local unix socket, a TCP connection or a set of rotating files,
+//! to provide client services such as a block explorer with real-time access to entries. Each
+//! event is a line of JSON whose schema is documented in the book's blockstreamer chapter.
 
 use crate::entry::Entry;
 use crate::result::Result;
@@ -9,27 +10,86 @@ use chrono::{SecondsFormat, Utc};
 use serde_json::json;
 use solana_sdk::hash::Hash;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction;
 use std::cell::RefCell;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::time::Duration;
 
-pub trait EntryWriter: std::fmt::Debug {
+/// The version of the event schema, bumped whenever a field is removed or changes meaning
+pub const BLOCKSTREAM_SCHEMA_VERSION: u64 = 1;
+
+/// The largest a `RotatingFileSink` file grows to by default before it's rotated
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 100 * 1024 * 1024;
+
+/// How many rotated files a `RotatingFileSink` keeps by default
+pub const DEFAULT_MAX_ROTATED_FILES: usize = 10;
+
+const MESSAGE_TERMINATOR: &[u8] = b"\n";
+
+const TCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Somewhere to send blockstream events, one line of JSON per event
+pub trait BlockstreamSink: std::fmt::Debug {
     fn write(&self, payload: String) -> Result<()>;
 }
 
+impl<T: BlockstreamSink + ?Sized> BlockstreamSink for Box<T> {
+    fn write(&self, payload: String) -> Result<()> {
+        (**self).write(payload)
+    }
+}
+
+/// Creates the sink for a blockstream `location`, which is one of:
+///
+/// * `unix:PATH`, or just `PATH`, a unix domain socket connected to for each event
+/// * `tcp:HOST:PORT`, a TCP connection kept open across events
+/// * `file:PATH`, a file rotated to `PATH.1`, `PATH.2`, ... as it fills up
+pub fn new_sink(location: &str) -> std::result::Result<Box<dyn BlockstreamSink + Send>, String> {
+    if location.starts_with("tcp:") {
+        let addr = location["tcp:".len()..]
+            .to_socket_addrs()
+            .map_err(|err| format!("Invalid blockstream address {}: {}", location, err))?
+            .next()
+            .ok_or_else(|| format!("Unable to resolve blockstream address {}", location))?;
+        Ok(Box::new(TcpSink::new(addr)))
+    } else if location.starts_with("file:") {
+        Ok(Box::new(RotatingFileSink::new(
+            &location["file:".len()..],
+            DEFAULT_MAX_FILE_BYTES,
+            DEFAULT_MAX_ROTATED_FILES,
+        )))
+    } else if location.starts_with("unix:") {
+        Ok(Box::new(UnixSocketSink::new(&location["unix:".len()..])))
+    } else {
+        Ok(Box::new(UnixSocketSink::new(location)))
+    }
+}
+
+fn write_line<W: Write>(writer: &mut W, payload: &str) -> Result<()> {
+    writer.write_all(payload.as_bytes())?;
+    writer.write_all(MESSAGE_TERMINATOR)?;
+    Ok(())
+}
+
 #[derive(Debug, Default)]
-pub struct EntryVec {
+pub struct VecSink {
     values: RefCell<Vec<String>>,
 }
 
-impl EntryWriter for EntryVec {
+impl BlockstreamSink for VecSink {
     fn write(&self, payload: String) -> Result<()> {
         self.values.borrow_mut().push(payload);
         Ok(())
     }
 }
 
-impl EntryVec {
+impl VecSink {
     pub fn new() -> Self {
-        EntryVec {
+        VecSink {
             values: RefCell::new(Vec::new()),
         }
     }
@@ -39,24 +99,26 @@ impl EntryVec {
     }
 }
 
+/// Connects to a unix domain socket for each event, so the listener may come and go
 #[derive(Debug)]
-pub struct EntrySocket {
-    socket: String,
+pub struct UnixSocketSink {
+    path: PathBuf,
 }
 
-impl EntryWriter for EntrySocket {
+impl UnixSocketSink {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl BlockstreamSink for UnixSocketSink {
     #[cfg(not(windows))]
     fn write(&self, payload: String) -> Result<()> {
-        use std::io::prelude::*;
         use std::net::Shutdown;
         use std::os::unix::net::UnixStream;
-        use std::path::Path;
-
-        const MESSAGE_TERMINATOR: &str = "\n";
 
-        let mut socket = UnixStream::connect(Path::new(&self.socket))?;
-        socket.write_all(payload.as_bytes())?;
-        socket.write_all(MESSAGE_TERMINATOR.as_bytes())?;
+        let mut socket = UnixStream::connect(&self.path)?;
+        write_line(&mut socket, &payload)?;
         socket.shutdown(Shutdown::Write)?;
         Ok(())
     }
@@ -64,11 +126,114 @@ impl EntryWriter for EntrySocket {
     fn write(&self, _payload: String) -> Result<()> {
         Err(crate::result::Error::from(std::io::Error::new(
             std::io::ErrorKind::Other,
-            "EntryWriter::write() not implemented for windows",
+            "UnixSocketSink::write() not implemented for windows",
         )))
     }
 }
 
+/// Streams events over a TCP connection, reconnecting on the next event once a write fails
+#[derive(Debug)]
+pub struct TcpSink {
+    addr: SocketAddr,
+    stream: RefCell<Option<TcpStream>>,
+}
+
+impl TcpSink {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            stream: RefCell::new(None),
+        }
+    }
+}
+
+impl BlockstreamSink for TcpSink {
+    fn write(&self, payload: String) -> Result<()> {
+        let mut stream = self.stream.borrow_mut();
+        if stream.is_none() {
+            *stream = Some(TcpStream::connect_timeout(&self.addr, TCP_CONNECT_TIMEOUT)?);
+        }
+        let result = write_line(stream.as_mut().unwrap(), &payload);
+        if result.is_err() {
+            *stream = None;
+        }
+        result
+    }
+}
+
+/// Appends events to a file, which is moved aside to `<path>.1` once it would grow past
+/// `max_file_bytes`. Older files shift to `<path>.2` and so on, keeping `max_rotated_files`.
+#[derive(Debug)]
+pub struct RotatingFileSink {
+    path: PathBuf,
+    max_file_bytes: u64,
+    max_rotated_files: usize,
+    /// The open file and its length
+    file: RefCell<Option<(File, u64)>>,
+}
+
+impl RotatingFileSink {
+    pub fn new<P: Into<PathBuf>>(path: P, max_file_bytes: u64, max_rotated_files: usize) -> Self {
+        Self {
+            path: path.into(),
+            max_file_bytes,
+            max_rotated_files,
+            file: RefCell::new(None),
+        }
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = OsString::from(&self.path);
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    fn rotate(&self) -> Result<()> {
+        if self.max_rotated_files == 0 {
+            fs::remove_file(&self.path)?;
+            return Ok(());
+        }
+        for index in (1..self.max_rotated_files).rev() {
+            let rotated_path = self.rotated_path(index);
+            if rotated_path.exists() {
+                fs::rename(rotated_path, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+        Ok(())
+    }
+
+    fn open(&self) -> Result<(File, u64)> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let len = file.metadata()?.len();
+        Ok((file, len))
+    }
+}
+
+impl BlockstreamSink for RotatingFileSink {
+    fn write(&self, payload: String) -> Result<()> {
+        let line_len = (payload.len() + MESSAGE_TERMINATOR.len()) as u64;
+        let mut file = self.file.borrow_mut();
+        if file.is_none() {
+            *file = Some(self.open()?);
+        }
+        let len = file.as_ref().unwrap().1;
+        if len > 0 && len + line_len > self.max_file_bytes {
+            *file = None;
+            self.rotate()?;
+            *file = Some(self.open()?);
+        }
+
+        let (file, len) = file.as_mut().unwrap();
+        write_line(file, &payload)?;
+        *len += line_len;
+        Ok(())
+    }
+}
+
 pub trait BlockstreamEvents {
     fn emit_entry_event(
         &self,
@@ -76,6 +241,7 @@ pub trait BlockstreamEvents {
         tick_height: u64,
         leader_pubkey: &Pubkey,
         entries: &Entry,
+        statuses: &[Option<transaction::Result<()>>],
     ) -> Result<()>;
     fn emit_block_event(
         &self,
@@ -87,20 +253,22 @@ pub trait BlockstreamEvents {
 }
 
 #[derive(Debug)]
-pub struct Blockstream<T: EntryWriter> {
+pub struct Blockstream<T: BlockstreamSink> {
     pub output: T,
 }
 
 impl<T> BlockstreamEvents for Blockstream<T>
 where
-    T: EntryWriter,
+    T: BlockstreamSink,
 {
+    /// `statuses` holds the status of each of the entry's transactions, or `None` if it's unknown
     fn emit_entry_event(
         &self,
         slot: u64,
         tick_height: u64,
         leader_pubkey: &Pubkey,
         entry: &Entry,
+        statuses: &[Option<transaction::Result<()>>],
     ) -> Result<()> {
         let transactions: Vec<Vec<u8>> = serialize_transactions(entry);
         let stream_entry = json!({
@@ -108,16 +276,17 @@ where
             "hash": entry.hash,
             "transactions": transactions
         });
-        let json_entry = serde_json::to_string(&stream_entry)?;
-        let payload = format!(
-            r#"{{"dt":"{}","t":"entry","s":{},"h":{},"l":"{:?}","entry":{}}}"#,
-            Utc::now().to_rfc3339_opts(SecondsFormat::Nanos, true),
-            slot,
-            tick_height,
-            leader_pubkey,
-            json_entry,
-        );
-        self.output.write(payload)?;
+        let event = json!({
+            "v": BLOCKSTREAM_SCHEMA_VERSION,
+            "dt": Utc::now().to_rfc3339_opts(SecondsFormat::Nanos, true),
+            "t": "entry",
+            "s": slot,
+            "h": tick_height,
+            "l": leader_pubkey.to_string(),
+            "entry": stream_entry,
+            "statuses": statuses,
+        });
+        self.output.write(serde_json::to_string(&event)?)?;
         Ok(())
     }
 
@@ -128,35 +297,37 @@ where
         leader_pubkey: &Pubkey,
         blockhash: Hash,
     ) -> Result<()> {
-        let payload = format!(
-            r#"{{"dt":"{}","t":"block","s":{},"h":{},"l":"{:?}","hash":"{:?}"}}"#,
-            Utc::now().to_rfc3339_opts(SecondsFormat::Nanos, true),
-            slot,
-            tick_height,
-            leader_pubkey,
-            blockhash,
-        );
-        self.output.write(payload)?;
+        let event = json!({
+            "v": BLOCKSTREAM_SCHEMA_VERSION,
+            "dt": Utc::now().to_rfc3339_opts(SecondsFormat::Nanos, true),
+            "t": "block",
+            "s": slot,
+            "h": tick_height,
+            "l": leader_pubkey.to_string(),
+            "hash": blockhash.to_string(),
+        });
+        self.output.write(serde_json::to_string(&event)?)?;
         Ok(())
     }
 }
 
-pub type SocketBlockstream = Blockstream<EntrySocket>;
+pub type SinkBlockstream = Blockstream<Box<dyn BlockstreamSink + Send>>;
 
-impl SocketBlockstream {
-    pub fn new(socket: String) -> Self {
+impl SinkBlockstream {
+    /// Panics if `location` isn't valid, see `new_sink`
+    pub fn new(location: String) -> Self {
         Blockstream {
-            output: EntrySocket { socket },
+            output: new_sink(&location).unwrap_or_else(|err| panic!("{}", err)),
         }
     }
 }
 
-pub type MockBlockstream = Blockstream<EntryVec>;
+pub type MockBlockstream = Blockstream<VecSink>;
 
 impl MockBlockstream {
     pub fn new(_: String) -> Self {
         Blockstream {
-            output: EntryVec::new(),
+            output: VecSink::new(),
         }
     }
 
@@ -176,6 +347,7 @@ fn serialize_transactions(entry: &Entry) -> Vec<Vec<u8>> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::blocktree::get_tmp_ledger_path;
     use crate::entry::Entry;
     use chrono::{DateTime, FixedOffset};
     use serde_json::Value;
@@ -183,6 +355,9 @@ mod test {
     use solana_sdk::signature::{Keypair, KeypairUtil};
     use solana_sdk::system_transaction;
     use std::collections::HashSet;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::path::Path;
 
     #[test]
     fn test_serialize_transactions() {
@@ -203,6 +378,59 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_new_sink() {
+        assert!(new_sink("tcp:127.0.0.1:8000").is_ok());
+        assert!(new_sink("tcp:not an address").is_err());
+        assert!(new_sink("file:blockstream.log").is_ok());
+        assert!(new_sink("unix:blockstream.sock").is_ok());
+        assert!(new_sink("blockstream.sock").is_ok());
+    }
+
+    #[test]
+    fn test_tcp_sink() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let sink = TcpSink::new(listener.local_addr().unwrap());
+        sink.write("first".to_string()).unwrap();
+        sink.write("second".to_string()).unwrap();
+
+        // both events arrive over the one connection
+        let (stream, _) = listener.accept().unwrap();
+        let lines: Vec<_> = BufReader::new(stream)
+            .lines()
+            .take(2)
+            .map(|line| line.unwrap())
+            .collect();
+        assert_eq!(lines, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_rotating_file_sink() {
+        let dir = get_tmp_ledger_path!();
+        fs::create_dir_all(&dir).unwrap();
+        let path = Path::new(&dir).join("blockstream.log");
+        let read = |path: PathBuf| fs::read_to_string(path).unwrap();
+
+        // each 5 byte event fills up a file
+        let sink = RotatingFileSink::new(&path, 8, 2);
+        sink.write("0000".to_string()).unwrap();
+        assert_eq!(read(path.clone()), "0000\n");
+        for event in &["1111", "2222", "3333"] {
+            sink.write(event.to_string()).unwrap();
+        }
+        assert_eq!(read(path.clone()), "3333\n");
+        assert_eq!(read(sink.rotated_path(1)), "2222\n");
+        assert_eq!(read(sink.rotated_path(2)), "1111\n");
+        assert!(!sink.rotated_path(3).exists());
+
+        // a new sink appends to the existing file
+        let sink = RotatingFileSink::new(&path, 20, 2);
+        sink.write("4444".to_string()).unwrap();
+        assert_eq!(read(path.clone()), "3333\n4444\n");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
     #[test]
     fn test_blockstream() -> () {
         let blockstream = MockBlockstream::new("test_stream".to_string());
@@ -227,7 +455,7 @@ mod test {
             let entry = Entry::new(&mut blockhash, 1, vec![]); // just ticks
             blockhash = entry.hash;
             blockstream
-                .emit_entry_event(curr_slot, tick_height, &leader_pubkey, &entry)
+                .emit_entry_event(curr_slot, tick_height, &leader_pubkey, &entry, &[])
                 .unwrap();
             expected_entries.push(entry.clone());
             entries.push(entry);
@@ -246,6 +474,7 @@ mod test {
 
         for item in blockstream.entries() {
             let json: Value = serde_json::from_str(&item).unwrap();
+            assert_eq!(json["v"].as_u64(), Some(BLOCKSTREAM_SCHEMA_VERSION));
             let dt_str = json["dt"].as_str().unwrap();
 
             // Ensure `ts` field parses as valid DateTime
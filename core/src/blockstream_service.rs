@@ -2,18 +2,19 @@
 //! using the `blockstream` module, providing client services such as a block explorer with
 //! real-time access to entries.
 
+use crate::bank_forks::BankForks;
 use crate::blockstream::BlockstreamEvents;
 #[cfg(test)]
 use crate::blockstream::MockBlockstream as Blockstream;
 #[cfg(not(test))]
-use crate::blockstream::SocketBlockstream as Blockstream;
+use crate::blockstream::SinkBlockstream as Blockstream;
 use crate::blocktree::Blocktree;
 use crate::result::{Error, Result};
 use crate::service::Service;
 use solana_sdk::pubkey::Pubkey;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{Receiver, RecvTimeoutError};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::thread::{self, Builder, JoinHandle};
 use std::time::Duration;
 
@@ -26,10 +27,11 @@ impl BlockstreamService {
     pub fn new(
         slot_full_receiver: Receiver<(u64, Pubkey)>,
         blocktree: Arc<Blocktree>,
-        blockstream_socket: String,
+        bank_forks: Arc<RwLock<BankForks>>,
+        blockstream_location: String,
         exit: &Arc<AtomicBool>,
     ) -> Self {
-        let mut blockstream = Blockstream::new(blockstream_socket);
+        let mut blockstream = Blockstream::new(blockstream_location);
         let exit = exit.clone();
         let t_blockstream = Builder::new()
             .name("solana-blockstream".to_string())
@@ -37,9 +39,12 @@ impl BlockstreamService {
                 if exit.load(Ordering::Relaxed) {
                     break;
                 }
-                if let Err(e) =
-                    Self::process_entries(&slot_full_receiver, &blocktree, &mut blockstream)
-                {
+                if let Err(e) = Self::process_entries(
+                    &slot_full_receiver,
+                    &blocktree,
+                    &bank_forks,
+                    &mut blockstream,
+                ) {
                     match e {
                         Error::RecvTimeoutError(RecvTimeoutError::Disconnected) => break,
                         Error::RecvTimeoutError(RecvTimeoutError::Timeout) => (),
@@ -53,10 +58,14 @@ impl BlockstreamService {
     fn process_entries(
         slot_full_receiver: &Receiver<(u64, Pubkey)>,
         blocktree: &Arc<Blocktree>,
+        bank_forks: &Arc<RwLock<BankForks>>,
         blockstream: &mut Blockstream,
     ) -> Result<()> {
         let timeout = Duration::new(1, 0);
         let (slot, slot_leader) = slot_full_receiver.recv_timeout(timeout)?;
+        // The slot's bank may already be gone if it was on a fork that's been pruned, in which
+        // case its transactions' statuses aren't known
+        let bank = bank_forks.read().unwrap().get(slot).cloned();
 
         let entries = blocktree.get_slot_entries(slot, 0, None).unwrap();
         let blocktree_meta = blocktree.meta(slot).unwrap().unwrap();
@@ -79,8 +88,16 @@ impl BlockstreamService {
             if entry.is_tick() {
                 tick_height += 1;
             }
+            let statuses: Vec<_> = entry
+                .transactions
+                .iter()
+                .map(|tx| {
+                    bank.as_ref()
+                        .and_then(|bank| bank.get_signature_status(&tx.signatures[0]))
+                })
+                .collect();
             blockstream
-                .emit_entry_event(slot, tick_height, &slot_leader, &entry)
+                .emit_entry_event(slot, tick_height, &slot_leader, &entry, &statuses)
                 .unwrap_or_else(|e| {
                     debug!("Blockstream error: {:?}, {:?}", e, blockstream.output);
                 });
@@ -112,7 +129,8 @@ mod test {
     use crate::genesis_utils::{create_genesis_block, GenesisBlockInfo};
     use bincode::{deserialize, serialize};
     use chrono::{DateTime, FixedOffset};
-    use serde_json::Value;
+    use serde_json::{json, Value};
+    use solana_runtime::bank::Bank;
     use solana_sdk::hash::Hash;
     use solana_sdk::signature::{Keypair, KeypairUtil};
     use solana_sdk::system_transaction;
@@ -125,9 +143,13 @@ mod test {
 
         // Set up genesis block and blocktree
         let GenesisBlockInfo {
-            mut genesis_block, ..
+            mut genesis_block,
+            mint_keypair,
+            ..
         } = create_genesis_block(1000);
         genesis_block.ticks_per_slot = ticks_per_slot;
+        let bank0 = Arc::new(Bank::new(&genesis_block));
+        let bank1 = Arc::new(Bank::new_from_parent(&bank0, &leader_pubkey, 1));
 
         let (ledger_path, _blockhash) = create_new_tmp_ledger!(&genesis_block);
         let blocktree = Blocktree::open(&ledger_path).unwrap();
@@ -144,11 +166,13 @@ mod test {
         let keypair = Keypair::new();
         let mut blockhash = entries[3].hash;
         let tx = system_transaction::create_user_account(
-            &keypair,
+            &mint_keypair,
             &keypair.pubkey(),
             1,
-            Hash::default(),
+            bank0.last_blockhash(),
         );
+        bank1.process_transaction(&tx).unwrap();
+        let bank_forks = Arc::new(RwLock::new(BankForks::new_from_banks(&[bank0, bank1], 0)));
         let entry = Entry::new(&mut blockhash, 1, vec![tx]);
         blockhash = entry.hash;
         entries.push(entry);
@@ -166,6 +190,7 @@ mod test {
         BlockstreamService::process_entries(
             &slot_full_receiver,
             &Arc::new(blocktree),
+            &bank_forks,
             &mut blockstream,
         )
         .unwrap();
@@ -190,6 +215,11 @@ mod test {
             assert_eq!(height, expected_tick_heights[i]);
             let entry_obj = json["entry"].clone();
             let tx = entry_obj["transactions"].as_array().unwrap();
+            let statuses = json["statuses"].as_array().unwrap();
+            assert_eq!(statuses.len(), tx.len());
+            assert!(statuses
+                .iter()
+                .all(|status| *status == json!({ "Ok": null })));
             let entry: Entry;
             if tx.len() == 0 {
                 entry = serde_json::from_value(entry_obj).unwrap();
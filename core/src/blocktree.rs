@@ -2,31 +2,36 @@
 //! Proof of History ledger as well as iterative read, append write, and random
 //! access read to a persistent file-based ledger.
 use crate::entry::Entry;
-use crate::erasure::{ErasureConfig, Session};
+use crate::erasure::Session;
 use crate::packet::{Blob, SharedBlob, BLOB_HEADER_SIZE};
 use crate::result::{Error, Result};
+use crate::slot_range::SlotRange;
 
 #[cfg(feature = "kvstore")]
 use solana_kvstore as kvstore;
 
 use bincode::deserialize;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[cfg(not(feature = "kvstore"))]
 use rocksdb;
 
 use solana_metrics::{datapoint_error, datapoint_info};
 
+use solana_runtime::bank::RewardInfo;
 use solana_sdk::genesis_block::GenesisBlock;
 use solana_sdk::hash::Hash;
-use solana_sdk::signature::{Keypair, KeypairUtil};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, KeypairUtil, Signable, Signature};
+use solana_sdk::transaction::TransactionStatusMeta;
 
 use std::borrow::{Borrow, Cow};
 use std::cell::RefCell;
 use std::cmp;
 use std::fs;
 use std::io;
+use std::ops::Range;
 use std::rc::Rc;
 use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
 use std::sync::{Arc, RwLock};
@@ -90,6 +95,14 @@ pub struct Blocktree {
     erasure_meta_cf: LedgerColumn<cf::ErasureMeta>,
     orphans_cf: LedgerColumn<cf::Orphans>,
     index_cf: LedgerColumn<cf::Index>,
+    duplicate_slots_cf: LedgerColumn<cf::DuplicateSlots>,
+    /// The slots in `duplicate_slots_cf`, cached since they're checked for every blob received
+    /// and every slot replayed
+    duplicate_slots: RwLock<HashSet<u64>>,
+    transaction_status_cf: LedgerColumn<cf::TransactionStatus>,
+    address_signatures_cf: LedgerColumn<cf::AddressSignatures>,
+    signature_slots_cf: LedgerColumn<cf::SignatureSlots>,
+    rewards_cf: LedgerColumn<cf::Rewards>,
     batch_processor: Arc<RwLock<BatchProcessor>>,
     pub new_blobs_signals: Vec<SyncSender<bool>>,
     pub completed_slots_senders: Vec<SyncSender<Vec<u64>>>,
@@ -110,6 +123,16 @@ pub const ORPHANS_CF: &str = "orphans";
 pub const ROOT_CF: &str = "root";
 /// Column family for indexes
 pub const INDEX_CF: &str = "index";
+/// Column family for proofs of leaders producing conflicting blobs
+pub const DUPLICATE_SLOTS_CF: &str = "duplicate_slots";
+/// Column family for the fees and balance changes of committed transactions
+pub const TRANSACTION_STATUS_CF: &str = "transaction_status";
+/// Column family for the signatures of committed transactions, by the addresses they use
+pub const ADDRESS_SIGNATURES_CF: &str = "address_signatures";
+/// Column family for the slots of committed transactions, by their signatures
+pub const SIGNATURE_SLOTS_CF: &str = "signature_slots";
+/// Column family for the inflation rewards paid in each slot
+pub const REWARDS_CF: &str = "rewards";
 
 impl Blocktree {
     /// Opens a Ledger in directory, provides "infinite" window of blobs
@@ -144,6 +167,26 @@ impl Blocktree {
         let orphans_cf = db.column();
         let index_cf = db.column();
 
+        // Create the duplicate slots column family, storing the first conflicting
+        // pair of blobs seen for a slot
+        let duplicate_slots_cf = db.column();
+        let duplicate_slots = db
+            .iter::<cf::DuplicateSlots>(None)?
+            .map(|(slot, _)| slot)
+            .collect();
+
+        // Create the transaction status column family, keyed by slot and signature
+        let transaction_status_cf = db.column();
+
+        // Create the address signatures column family, keyed by slot, address and signature
+        let address_signatures_cf = db.column();
+
+        // Create the signature slots column family, keyed by signature and slot
+        let signature_slots_cf = db.column();
+
+        // Create the rewards column family, keyed by slot and the address paid
+        let rewards_cf = db.column();
+
         let db = Arc::new(db);
 
         Ok(Blocktree {
@@ -155,6 +198,12 @@ impl Blocktree {
             erasure_meta_cf,
             orphans_cf,
             index_cf,
+            duplicate_slots_cf,
+            duplicate_slots: RwLock::new(duplicate_slots),
+            transaction_status_cf,
+            address_signatures_cf,
+            signature_slots_cf,
+            rewards_cf,
             new_blobs_signals: vec![],
             batch_processor,
             completed_slots_senders: vec![],
@@ -194,32 +243,39 @@ impl Blocktree {
         false
     }
 
-    /// Silently deletes all blocktree column families starting at the given slot until the `to` slot
+    /// Silently deletes all blocktree column families starting at the given slot until the `to` slot,
+    /// inclusive
     /// Dangerous; Use with care:
     /// Does not check for integrity and does not update slot metas that refer to deleted slots
     /// Modifies multiple column families simultaneously
-    pub fn purge_slots(&self, mut from_slot: Slot, to_slot: Option<Slot>) {
+    pub fn purge_slots(&self, from_slot: Slot, to_slot: Option<Slot>) {
         // split the purge request into batches of 1000 slots
         const PURGE_BATCH_SIZE: u64 = 1000;
-        let mut batch_end = to_slot
-            .unwrap_or(from_slot + PURGE_BATCH_SIZE)
-            .min(from_slot + PURGE_BATCH_SIZE);
-        while from_slot < batch_end {
-            if let Ok(end) = self.run_purge_batch(from_slot, batch_end) {
+        let mut remaining = SlotRange::new(from_slot, to_slot.unwrap_or(std::u64::MAX));
+        while let Some(range) = remaining {
+            let batch = range.truncate(PURGE_BATCH_SIZE);
+            if let Ok(end) = self.run_purge_batch(batch.start, batch.end) {
                 // no more slots to iter or reached the upper bound
                 if end {
                     break;
-                } else {
-                    // update the next batch bounds
-                    from_slot = batch_end;
-                    batch_end = to_slot
-                        .unwrap_or(batch_end + PURGE_BATCH_SIZE)
-                        .min(batch_end + PURGE_BATCH_SIZE);
                 }
+                // update the next batch bounds
+                remaining = batch
+                    .end_exclusive()
+                    .and_then(|next_slot| range.clamp_start(next_slot));
             }
         }
     }
 
+    /// The lowest slot still in the ledger, or 0 if the ledger is empty
+    pub fn lowest_slot(&self) -> Slot {
+        self.slot_meta_iterator(0)
+            .ok()
+            .and_then(|mut iter| iter.next())
+            .map(|(slot, _)| slot)
+            .unwrap_or(0)
+    }
+
     // Returns whether or not all iterators have reached their end
     fn run_purge_batch(&self, from_slot: Slot, batch_end: Slot) -> Result<bool> {
         let mut end = true;
@@ -321,6 +377,66 @@ impl Blocktree {
                     false
                 }
             };
+            end &= match self
+                .duplicate_slots_cf
+                .delete_slot(&mut write_batch, from_slot, batch_end)
+            {
+                Ok(finished) => finished,
+                Err(e) => {
+                    error!(
+                        "Error: {:?} while deleting duplicate_slots_cf for slot {:?}",
+                        e, from_slot
+                    );
+                    false
+                }
+            };
+            // The signature slots are keyed by signature, so they're found through the statuses
+            // being purged with them
+            let status_cf = &self.transaction_status_cf;
+            end &= match self.delete_signature_slots(&mut write_batch, from_slot, batch_end) {
+                Ok(finished) => finished,
+                Err(e) => {
+                    error!(
+                        "Error: {:?} while deleting signature_slots_cf for slot {:?}",
+                        e, from_slot
+                    );
+                    false
+                }
+            };
+            end &= match status_cf.delete_slot(&mut write_batch, from_slot, batch_end) {
+                Ok(finished) => finished,
+                Err(e) => {
+                    error!(
+                        "Error: {:?} while deleting transaction_status_cf for slot {:?}",
+                        e, from_slot
+                    );
+                    false
+                }
+            };
+            let address_cf = &self.address_signatures_cf;
+            end &= match address_cf.delete_slot(&mut write_batch, from_slot, batch_end) {
+                Ok(finished) => finished,
+                Err(e) => {
+                    error!(
+                        "Error: {:?} while deleting address_signatures_cf for slot {:?}",
+                        e, from_slot
+                    );
+                    false
+                }
+            };
+            end &= match self
+                .rewards_cf
+                .delete_slot(&mut write_batch, from_slot, batch_end)
+            {
+                Ok(finished) => finished,
+                Err(e) => {
+                    error!(
+                        "Error: {:?} while deleting rewards_cf for slot {:?}",
+                        e, from_slot
+                    );
+                    false
+                }
+            };
             let roots_cf = self.db.column::<cf::Root>();
             end &= match roots_cf.delete_slot(&mut write_batch, from_slot, batch_end) {
                 Ok(finished) => finished,
@@ -339,14 +455,64 @@ impl Blocktree {
                 );
                 Err(e)?;
             }
+            let purged = from_slot.unwrap()..=batch_end.unwrap();
+            self.duplicate_slots
+                .write()
+                .unwrap()
+                .retain(|slot| !purged.contains(slot));
             Ok(end)
         }
     }
 
+    /// Deletes the signature slots of the transactions with statuses in the slots from `from_slot`
+    /// to `to_slot`
+    fn delete_signature_slots(
+        &self,
+        batch: &mut WriteBatch,
+        from_slot: Option<Slot>,
+        to_slot: Option<Slot>,
+    ) -> Result<bool> {
+        let mut end = true;
+        let iter = self
+            .transaction_status_cf
+            .iter(from_slot.map(|slot| (slot, Signature::default())))?;
+        for ((slot, signature), _) in iter {
+            if to_slot.map_or(false, |to_slot| slot > to_slot) {
+                end = false;
+                break;
+            }
+            batch.delete::<cf::SignatureSlots>((signature, slot))?;
+        }
+        Ok(end)
+    }
+
     pub fn erasure_meta(&self, slot: u64, set_index: u64) -> Result<Option<ErasureMeta>> {
         self.erasure_meta_cf.get((slot, set_index))
     }
 
+    /// The status of each erasure set of `slot` a coding blob has been received for, by set
+    /// index: whether its data is full, can be recovered, or how many more blobs it needs
+    pub fn erasure_set_statuses(&self, slot: u64) -> Result<Vec<(u64, ErasureMetaStatus)>> {
+        let index = match self.index_cf.get(slot)? {
+            Some(index) => index,
+            None => return Ok(vec![]),
+        };
+        let meta_iter = self.db.iter::<cf::ErasureMeta>(Some((slot, 0)))?;
+        Ok(meta_iter
+            .take_while(|((meta_slot, _), _)| *meta_slot == slot)
+            .map(|((_, set_index), erasure_meta_bytes)| {
+                let erasure_meta: ErasureMeta =
+                    deserialize(&erasure_meta_bytes).unwrap_or_else(|_| {
+                        panic!(
+                            "Could not deserialize ErasureMeta for slot {} set {}",
+                            slot, set_index
+                        )
+                    });
+                (set_index, erasure_meta.status(&index))
+            })
+            .collect())
+    }
+
     pub fn orphan(&self, slot: u64) -> Result<Option<bool>> {
         self.orphans_cf.get(slot)
     }
@@ -473,20 +639,21 @@ impl Blocktree {
         let mut slot_meta_working_set = HashMap::new();
         let mut erasure_meta_working_set = HashMap::new();
         let mut index_working_set = HashMap::new();
-        let mut erasure_config_opt = None;
+        let mut num_data_opt = None;
 
         for blob in new_blobs.iter() {
             let blob = blob.borrow();
             assert!(!blob.is_coding());
 
-            match erasure_config_opt {
-                Some(config) => {
-                    if config != blob.erasure_config() {
+            let num_data = blob.erasure_config().num_data();
+            match num_data_opt {
+                Some(slot_num_data) => {
+                    if slot_num_data != num_data {
                         // ToDo: This is a potential slashing condition
-                        error!("Multiple erasure config for the same slot.");
+                        error!("Multiple erasure set sizes for the same slot.");
                     }
                 }
-                None => erasure_config_opt = Some(blob.erasure_config()),
+                None => num_data_opt = Some(num_data),
             }
 
             let blob_slot = blob.slot();
@@ -498,8 +665,7 @@ impl Blocktree {
                     .unwrap_or_else(|| Index::new(blob_slot))
             });
 
-            let set_index =
-                ErasureMeta::set_index_for(blob.index(), erasure_config_opt.unwrap().num_data());
+            let set_index = ErasureMeta::set_index_for(blob.index(), num_data);
             if let Some(erasure_meta) = self.erasure_meta_cf.get((blob_slot, set_index))? {
                 erasure_meta_working_set.insert((blob_slot, set_index), erasure_meta);
             }
@@ -512,7 +678,6 @@ impl Blocktree {
             &prev_inserted_blob_datas,
             &mut prev_inserted_coding,
             &mut write_batch,
-            &erasure_config_opt.unwrap_or_default(),
         )?;
 
         if let Some(recovered_data) = recovered_data_opt {
@@ -704,30 +869,15 @@ impl Blocktree {
         let mut prev_inserted_coding = HashMap::new();
         let mut prev_inserted_blob_datas = HashMap::new();
 
-        let mut erasure_config_opt = None;
-
         for blob_item in blobs {
             let blob = blob_item.borrow();
             assert!(blob.is_coding());
 
-            match erasure_config_opt {
-                Some(config) => {
-                    if config != blob.erasure_config() {
-                        // ToDo: This is a potential slashing condition
-                        error!("Multiple erasure config for the same slot.");
-                    }
-                }
-                None => erasure_config_opt = Some(blob.erasure_config()),
-            }
-
+            // Each erasure set has its own coding rate, carried by its coding blobs
+            let erasure_config = blob.erasure_config();
             let (blob_slot, blob_index, blob_size) =
                 (blob.slot(), blob.index(), blob.size() as usize);
-            let set_index = blob_index / blob.erasure_config().num_coding() as u64;
-
-            writebatch.put_bytes::<cf::Coding>(
-                (blob_slot, blob_index),
-                &blob.data[..BLOB_HEADER_SIZE + blob_size],
-            )?;
+            let set_index = ErasureMeta::set_index_for(blob_index, erasure_config.num_data());
 
             let index = index_working_set.entry(blob_slot).or_insert_with(|| {
                 self.index_cf
@@ -742,11 +892,20 @@ impl Blocktree {
                     self.erasure_meta_cf
                         .get((blob_slot, set_index))
                         .expect("Expect database get to succeed")
-                        .unwrap_or_else(|| {
-                            ErasureMeta::new(set_index, &erasure_config_opt.unwrap())
-                        })
+                        .unwrap_or_else(|| ErasureMeta::new(set_index, &erasure_config))
                 });
 
+            if erasure_meta.config() != erasure_config {
+                // ToDo: This is a potential slashing condition
+                error!("Multiple erasure config for the same erasure set.");
+                continue;
+            }
+
+            writebatch.put_bytes::<cf::Coding>(
+                (blob_slot, blob_index),
+                &blob.data[..BLOB_HEADER_SIZE + blob_size],
+            )?;
+
             // size should be the same for all coding blobs, else there's a bug
             erasure_meta.set_size(blob_size);
             index.coding_mut().set_present(blob_index, true);
@@ -764,7 +923,6 @@ impl Blocktree {
             &prev_inserted_blob_datas,
             &mut prev_inserted_coding,
             &mut writebatch,
-            &erasure_config_opt.unwrap_or_default(),
         )?;
 
         if let Some(recovered_data) = recovered_data_opt {
@@ -970,6 +1128,32 @@ impl Blocktree {
         Ok((blobs, num))
     }
 
+    /// Returns the entries of every slot in `slots` that has received data, in slot order.
+    /// Slots are found with a single sorted pass over the meta column, and only the consecutive
+    /// blobs of each slot are read, so range queries don't pay for a meta lookup per slot.
+    pub fn get_entries_for_slots(
+        &self,
+        slots: Range<Slot>,
+    ) -> Result<impl Iterator<Item = Result<(Slot, Vec<Entry>)>> + '_> {
+        let end_slot = slots.end;
+        let meta_iter = self.slot_meta_iterator(slots.start)?;
+        Ok(meta_iter
+            .take_while(move |(slot, _)| *slot < end_slot)
+            .filter(|(_, meta)| meta.consumed > 0)
+            .map(move |(slot, meta)| {
+                let consecutive_blobs = get_slot_consecutive_blobs(
+                    slot,
+                    &self.db,
+                    &HashMap::new(),
+                    0,
+                    Some(meta.consumed),
+                )?;
+                let entries = deserialize_blobs(&consecutive_blobs)
+                    .map_err(BlocktreeError::InvalidBlobData)?;
+                Ok((slot, entries))
+            }))
+    }
+
     // Returns slots connecting to any element of the list `slots`.
     pub fn get_slots_since(&self, slots: &[u64]) -> Result<HashMap<u64, Vec<u64>>> {
         // Return error if there was a database error during lookup of any of the
@@ -1039,6 +1223,222 @@ impl Blocktree {
         self.dead_slots_cf.put(slot, &true)
     }
 
+    /// Lets a dead slot be replayed again
+    pub fn remove_dead_slot(&self, slot: u64) -> Result<()> {
+        self.dead_slots_cf.delete(slot)
+    }
+
+    /// The dead slots, starting at `slot`
+    pub fn dead_slots_iterator(&self, slot: u64) -> Result<impl Iterator<Item = u64>> {
+        let iter = self.db.iter::<cf::DeadSlots>(Some(slot))?;
+        Ok(iter.filter_map(|(slot, value)| match deserialize(&value) {
+            Ok(true) => Some(slot),
+            _ => None,
+        }))
+    }
+
+    /// Returns the payload already stored for the blob's (slot, index) if
+    /// `slot_leader` signed both it and the given blob, and their signed data
+    /// differs, i.e. the leader has signed two different blobs for the same
+    /// position. Fields outside the signed data, like the forwarded flag, can
+    /// change on the way without making a blob a duplicate.
+    pub fn is_blob_duplicate(&self, blob: &Blob, slot_leader: &Pubkey) -> Option<Vec<u8>> {
+        let signed_by_leader = |blob: &Blob| blob.id() == *slot_leader && blob.verify();
+        if !signed_by_leader(blob) {
+            return None;
+        }
+        self.get_data_blob_bytes(blob.slot(), blob.index())
+            .expect("fetch from Data column family failed")
+            .filter(|existing| {
+                let existing = Blob::new(existing);
+                signed_by_leader(&existing) && existing.signable_data() != blob.signable_data()
+            })
+    }
+
+    pub fn has_duplicate_blobs_in_slot(&self, slot: u64) -> bool {
+        self.duplicate_slots.read().unwrap().contains(&slot)
+    }
+
+    /// Persists a conflict proof for `slot`. Only the first proof for a slot is
+    /// kept, one is enough to show the leader misbehaved.
+    pub fn store_duplicate_slot(
+        &self,
+        slot: u64,
+        index: u64,
+        blob1: Vec<u8>,
+        blob2: Vec<u8>,
+    ) -> Result<()> {
+        if self.has_duplicate_blobs_in_slot(slot) {
+            return Ok(());
+        }
+        let proof = DuplicateSlotProof {
+            index,
+            blob1,
+            blob2,
+        };
+        self.duplicate_slots_cf.put(slot, &proof)?;
+        self.duplicate_slots.write().unwrap().insert(slot);
+        Ok(())
+    }
+
+    pub fn get_duplicate_slot(&self, slot: u64) -> Option<DuplicateSlotProof> {
+        self.duplicate_slots_cf
+            .get(slot)
+            .expect("fetch from DuplicateSlots column family failed")
+    }
+
+    /// The slots with a duplicate blob proof, starting at `slot`
+    pub fn duplicate_slots_iterator(&self, slot: u64) -> Result<impl Iterator<Item = u64>> {
+        let iter = self.db.iter::<cf::DuplicateSlots>(Some(slot))?;
+        Ok(iter.map(|(slot, _)| slot))
+    }
+
+    /// Persists the fees and balance changes of the transactions committed in `slot`
+    pub fn write_transaction_statuses(
+        &self,
+        slot: u64,
+        statuses: &[(Signature, TransactionStatusMeta)],
+    ) -> Result<()> {
+        unsafe {
+            let mut batch_processor = self.db.batch_processor();
+            let mut write_batch = batch_processor.batch()?;
+            for (signature, meta) in statuses {
+                write_batch.put::<cf::TransactionStatus>((slot, *signature), meta)?;
+                write_batch.put::<cf::SignatureSlots>((*signature, slot), &())?;
+            }
+
+            batch_processor.write(write_batch)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_transaction_status(
+        &self,
+        slot: u64,
+        signature: &Signature,
+    ) -> Result<Option<TransactionStatusMeta>> {
+        self.transaction_status_cf.get((slot, *signature))
+    }
+
+    /// The rooted slot the transaction with `signature` was committed in, if the blocktree still
+    /// has its status
+    pub fn get_rooted_signature_slot(&self, signature: &Signature) -> Result<Option<u64>> {
+        let iter = self.signature_slots_cf.iter(Some((*signature, 0)))?;
+        for ((index_signature, slot), _) in iter {
+            if index_signature != *signature {
+                break;
+            }
+            if self.is_root(slot) {
+                return Ok(Some(slot));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Indexes the transactions committed in `slot` by the addresses they use, and whether they
+    /// could write them. `addresses` holds the addresses of each transaction together, in the
+    /// order the transactions were committed, which the index keeps.
+    pub fn write_address_signatures(
+        &self,
+        slot: u64,
+        addresses: &[(Pubkey, Signature, bool)],
+    ) -> Result<()> {
+        unsafe {
+            let mut batch_processor = self.db.batch_processor();
+            let mut write_batch = batch_processor.batch()?;
+            let mut position = 0;
+            let mut last_signature = None;
+            for (address, signature, writable) in addresses {
+                if last_signature.map_or(false, |last_signature| last_signature != signature) {
+                    position += 1;
+                }
+                last_signature = Some(signature);
+                write_batch.put::<cf::AddressSignatures>(
+                    (slot, *address, position, *signature),
+                    writable,
+                )?;
+            }
+
+            batch_processor.write(write_batch)?;
+        }
+        Ok(())
+    }
+
+    /// The first `limit` signatures of the transactions that used `address` in the rooted slots
+    /// from `start_slot` to `end_slot` inclusive, in the order they were committed. With
+    /// `after`, only the signatures following that one are returned, so a busy address can be
+    /// paged through by passing the last signature of each page.
+    pub fn get_confirmed_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        start_slot: u64,
+        end_slot: u64,
+        after: Option<Signature>,
+        limit: usize,
+    ) -> Result<Vec<Signature>> {
+        let mut signatures = vec![];
+        let mut past_after = after.is_none();
+        for slot in start_slot..=end_slot {
+            if signatures.len() >= limit {
+                break;
+            }
+            if !self.is_root(slot) {
+                continue;
+            }
+            let start = (slot, *address, 0, Signature::default());
+            let iter = self.db.iter::<cf::AddressSignatures>(Some(start))?;
+            for (_, _, _, signature) in
+                iter.map(|(index, _)| index)
+                    .take_while(|(index_slot, index_address, _, _)| {
+                        *index_slot == slot && index_address == address
+                    })
+            {
+                if signatures.len() >= limit {
+                    break;
+                }
+                if past_after {
+                    signatures.push(signature);
+                } else {
+                    past_after = after == Some(signature);
+                }
+            }
+        }
+        Ok(signatures)
+    }
+
+    /// Persists the inflation rewards paid in `slot`
+    pub fn write_rewards(&self, slot: u64, rewards: &[(Pubkey, RewardInfo)]) -> Result<()> {
+        unsafe {
+            let mut batch_processor = self.db.batch_processor();
+            let mut write_batch = batch_processor.batch()?;
+            for (address, reward) in rewards {
+                write_batch.put::<cf::Rewards>((slot, *address), reward)?;
+            }
+
+            batch_processor.write(write_batch)?;
+        }
+        Ok(())
+    }
+
+    /// The inflation rewards paid in the rooted slots from `start_slot` to `end_slot` inclusive,
+    /// with the slot each was paid in
+    pub fn get_rooted_rewards(
+        &self,
+        start_slot: u64,
+        end_slot: u64,
+    ) -> Result<Vec<(u64, Pubkey, RewardInfo)>> {
+        let iter = self
+            .db
+            .iter::<cf::Rewards>(Some((start_slot, Pubkey::default())))?;
+        let mut rewards = vec![];
+        for ((slot, address), value) in iter.take_while(|((slot, _), _)| *slot <= end_slot) {
+            if self.is_root(slot) {
+                rewards.push((slot, address, deserialize(&value)?));
+            }
+        }
+        Ok(rewards)
+    }
+
     pub fn get_orphans(&self, max: Option<usize>) -> Vec<u64> {
         let mut results = vec![];
 
@@ -1627,10 +2027,7 @@ fn handle_recovery(
     prev_inserted_blob_datas: &HashMap<(u64, u64), &[u8]>,
     prev_inserted_coding: &mut HashMap<(u64, u64), Blob>,
     writebatch: &mut WriteBatch,
-    erasure_config: &ErasureConfig,
 ) -> Result<Option<Vec<Blob>>> {
-    use solana_sdk::signature::Signable;
-
     let (mut recovered_data, mut recovered_coding) = (vec![], vec![]);
 
     for (&(slot, _), erasure_meta) in erasure_metas.iter() {
@@ -1643,7 +2040,6 @@ fn handle_recovery(
             slot,
             &prev_inserted_blob_datas,
             &prev_inserted_coding,
-            erasure_config,
         )? {
             for blob in data.iter() {
                 debug!(
@@ -1739,8 +2135,8 @@ fn try_erasure_recover(
     slot: u64,
     prev_inserted_blob_datas: &HashMap<(u64, u64), &[u8]>,
     prev_inserted_coding: &HashMap<(u64, u64), Blob>,
-    erasure_config: &ErasureConfig,
 ) -> Result<Option<(Vec<Blob>, Vec<Blob>)>> {
+    let erasure_config = erasure_meta.config();
     let set_index = erasure_meta.set_index;
     let start_index = erasure_meta.start_index();
     let (data_end_index, coding_end_idx) = erasure_meta.end_indexes();
@@ -1758,7 +2154,7 @@ fn try_erasure_recover(
 
     let blobs = match erasure_meta.status(index) {
         ErasureMetaStatus::CanRecover => {
-            let session = Session::new_from_config(erasure_config).unwrap();
+            let session = Session::new_from_config(&erasure_config).unwrap();
             let erasure_result = recover(
                 db,
                 &session,
@@ -1767,7 +2163,6 @@ fn try_erasure_recover(
                 index,
                 prev_inserted_blob_datas,
                 prev_inserted_coding,
-                erasure_config,
             );
 
             match erasure_result {
@@ -1841,8 +2236,8 @@ fn recover(
     index: &Index,
     prev_inserted_blob_datas: &HashMap<(u64, u64), &[u8]>,
     prev_inserted_coding: &HashMap<(u64, u64), Blob>,
-    erasure_config: &ErasureConfig,
 ) -> Result<(Vec<Blob>, Vec<Blob>)> {
+    let erasure_config = erasure_meta.config();
     let start_idx = erasure_meta.start_index();
     let size = erasure_meta.size();
     let data_cf = db.column::<cf::Data>();
@@ -2070,7 +2465,6 @@ pub mod tests {
     use rand::thread_rng;
     use rand::Rng;
     use solana_sdk::hash::Hash;
-    use solana_sdk::pubkey::Pubkey;
     use std::cmp::min;
     use std::collections::HashSet;
     use std::iter::once;
@@ -2489,6 +2883,336 @@ pub mod tests {
         Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    pub fn test_get_entries_for_slots() {
+        let blocktree_path = get_tmp_ledger_path("test_get_entries_for_slots");
+        {
+            let blocktree = Blocktree::open(&blocktree_path).unwrap();
+            let entries_per_slot = 5;
+            let mut expected = vec![];
+            // Leave slot 2 empty so the range has a gap
+            for slot in (0..5).filter(|slot| *slot != 2) {
+                let parent_slot = if slot == 0 { 0 } else { slot - 1 };
+                let (blobs, entries) = make_slot_entries(slot, parent_slot, entries_per_slot);
+                blocktree.write_blobs(&blobs).unwrap();
+                expected.push((slot, entries));
+            }
+
+            let fetched: Vec<_> = blocktree
+                .get_entries_for_slots(0..4)
+                .unwrap()
+                .map(|result| result.unwrap())
+                .collect();
+            assert_eq!(fetched[..], expected[..3]);
+
+            let fetched: Vec<_> = blocktree
+                .get_entries_for_slots(4..10)
+                .unwrap()
+                .map(|result| result.unwrap())
+                .collect();
+            assert_eq!(fetched[..], expected[3..]);
+
+            assert_eq!(blocktree.get_entries_for_slots(10..20).unwrap().count(), 0);
+        }
+        Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
+    }
+
+    #[test]
+    pub fn test_dead_slots() {
+        let blocktree_path = get_tmp_ledger_path("test_dead_slots");
+        {
+            let blocktree = Blocktree::open(&blocktree_path).unwrap();
+            blocktree.set_dead_slot(2).unwrap();
+            blocktree.set_dead_slot(5).unwrap();
+            blocktree.set_dead_slot(7).unwrap();
+            assert_eq!(
+                blocktree
+                    .dead_slots_iterator(0)
+                    .unwrap()
+                    .collect::<Vec<_>>(),
+                vec![2, 5, 7]
+            );
+            assert_eq!(
+                blocktree
+                    .dead_slots_iterator(3)
+                    .unwrap()
+                    .collect::<Vec<_>>(),
+                vec![5, 7]
+            );
+
+            blocktree.remove_dead_slot(5).unwrap();
+            assert!(!blocktree.is_dead(5));
+            assert!(blocktree.is_dead(7));
+            assert_eq!(
+                blocktree
+                    .dead_slots_iterator(0)
+                    .unwrap()
+                    .collect::<Vec<_>>(),
+                vec![2, 7]
+            );
+        }
+        Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
+    }
+
+    #[test]
+    pub fn test_duplicate_slot() {
+        let blocktree_path = get_tmp_ledger_path("test_duplicate_slot");
+        {
+            let blocktree = Blocktree::open(&blocktree_path).unwrap();
+            let slot = 0;
+            let leader = Keypair::new();
+            let (mut blobs, _) = make_slot_entries(slot, 0, 1);
+            blobs[0].set_id(&leader.pubkey());
+            blobs[0].sign(&leader);
+            let blob1 = blobs[0].clone();
+            let mut blob2 = blob1.clone();
+            blob2.data[BLOB_HEADER_SIZE] ^= 1;
+
+            // Nothing to conflict with yet
+            assert_eq!(blocktree.is_blob_duplicate(&blob1, &leader.pubkey()), None);
+            blocktree.write_blobs(&blobs).unwrap();
+
+            // Resending the same blob is not a conflict, even once forwarded
+            assert_eq!(blocktree.is_blob_duplicate(&blob1, &leader.pubkey()), None);
+            let mut forwarded = blob1.clone();
+            forwarded.set_forwarded(true);
+            assert_eq!(
+                blocktree.is_blob_duplicate(&forwarded, &leader.pubkey()),
+                None
+            );
+
+            // Neither is a blob the leader didn't sign
+            assert_eq!(blocktree.is_blob_duplicate(&blob2, &leader.pubkey()), None);
+            blob2.sign(&leader);
+            assert_eq!(
+                blocktree.is_blob_duplicate(&blob2, &Pubkey::new_rand()),
+                None
+            );
+            let existing = blocktree
+                .is_blob_duplicate(&blob2, &leader.pubkey())
+                .unwrap();
+            let payload2 = blob2.data[..BLOB_HEADER_SIZE + blob2.size()].to_vec();
+            assert!(!blocktree.has_duplicate_blobs_in_slot(slot));
+
+            blocktree
+                .store_duplicate_slot(slot, blob2.index(), existing.clone(), payload2.clone())
+                .unwrap();
+            assert!(blocktree.has_duplicate_blobs_in_slot(slot));
+            assert_eq!(
+                blocktree
+                    .duplicate_slots_iterator(0)
+                    .unwrap()
+                    .collect::<Vec<_>>(),
+                vec![slot]
+            );
+
+            // Only the first proof for a slot is kept
+            blocktree
+                .store_duplicate_slot(slot, blob2.index(), vec![], vec![])
+                .unwrap();
+            let proof = blocktree.get_duplicate_slot(slot).unwrap();
+            assert_eq!(proof.index, blob2.index());
+            assert_eq!(proof.blob1, existing);
+            assert_eq!(proof.blob2, payload2);
+
+            // The cached duplicate slots are reloaded with the ledger
+            drop(blocktree);
+            let blocktree = Blocktree::open(&blocktree_path).unwrap();
+            assert!(blocktree.has_duplicate_blobs_in_slot(slot));
+
+            blocktree.purge_slots(slot, None);
+            assert!(!blocktree.has_duplicate_blobs_in_slot(slot));
+        }
+        Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
+    }
+
+    #[test]
+    pub fn test_transaction_status() {
+        let blocktree_path = get_tmp_ledger_path("test_transaction_status");
+        {
+            let blocktree = Blocktree::open(&blocktree_path).unwrap();
+            let signature = Signature::new(&[1; 64]);
+            let meta = TransactionStatusMeta {
+                status: Ok(()),
+                fee: 5,
+                pre_balances: vec![100, 0],
+                post_balances: vec![85, 10],
+            };
+            assert_eq!(
+                blocktree.get_transaction_status(1, &signature).unwrap(),
+                None
+            );
+
+            blocktree
+                .write_transaction_statuses(1, &[(signature, meta.clone())])
+                .unwrap();
+            assert_eq!(
+                blocktree.get_transaction_status(1, &signature).unwrap(),
+                Some(meta)
+            );
+            // Statuses are kept per slot
+            assert_eq!(
+                blocktree.get_transaction_status(2, &signature).unwrap(),
+                None
+            );
+
+            // Only rooted slots are found by signature
+            assert_eq!(
+                blocktree.get_rooted_signature_slot(&signature).unwrap(),
+                None
+            );
+            blocktree
+                .write_transaction_statuses(2, &[(signature, meta.clone())])
+                .unwrap();
+            blocktree.set_roots(&[2]).unwrap();
+            assert_eq!(
+                blocktree.get_rooted_signature_slot(&signature).unwrap(),
+                Some(2)
+            );
+            assert_eq!(
+                blocktree
+                    .get_rooted_signature_slot(&Signature::new(&[2; 64]))
+                    .unwrap(),
+                None
+            );
+
+            blocktree.purge_slots(1, None);
+            assert_eq!(
+                blocktree.get_transaction_status(1, &signature).unwrap(),
+                None
+            );
+            assert_eq!(
+                blocktree.get_rooted_signature_slot(&signature).unwrap(),
+                None
+            );
+        }
+        Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
+    }
+
+    #[test]
+    pub fn test_rooted_rewards() {
+        let blocktree_path = get_tmp_ledger_path("test_rooted_rewards");
+        {
+            let blocktree = Blocktree::open(&blocktree_path).unwrap();
+            let address = Pubkey::new_rand();
+            let reward = RewardInfo {
+                lamports: 10,
+                post_balance: 110,
+                commission: 5,
+            };
+            blocktree.write_rewards(1, &[(address, reward)]).unwrap();
+            blocktree.write_rewards(2, &[(address, reward)]).unwrap();
+            blocktree.write_rewards(4, &[(address, reward)]).unwrap();
+
+            // Only rooted slots are confirmed
+            assert!(blocktree.get_rooted_rewards(0, 4).unwrap().is_empty());
+            blocktree.set_roots(&[1, 2, 4]).unwrap();
+            assert_eq!(
+                blocktree.get_rooted_rewards(2, 3).unwrap(),
+                vec![(2, address, reward)]
+            );
+
+            blocktree.purge_slots(2, None);
+            assert_eq!(
+                blocktree.get_rooted_rewards(0, 4).unwrap(),
+                vec![(1, address, reward)]
+            );
+        }
+        Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
+    }
+
+    #[test]
+    pub fn test_confirmed_signatures_for_address() {
+        let blocktree_path = get_tmp_ledger_path("test_confirmed_signatures_for_address");
+        {
+            let blocktree = Blocktree::open(&blocktree_path).unwrap();
+            let address = Pubkey::new_rand();
+            let other_address = Pubkey::new_rand();
+            // Committed in the opposite order of their signatures' bytes
+            let signatures: Vec<_> = (1..=4).rev().map(|i| Signature::new(&[i; 64])).collect();
+            blocktree
+                .write_address_signatures(
+                    1,
+                    &[
+                        (address, signatures[0], true),
+                        (other_address, signatures[0], false),
+                        (other_address, signatures[1], true),
+                    ],
+                )
+                .unwrap();
+            blocktree
+                .write_address_signatures(2, &[(address, signatures[2], false)])
+                .unwrap();
+            blocktree
+                .write_address_signatures(3, &[(address, signatures[3], true)])
+                .unwrap();
+
+            // Only rooted slots are confirmed
+            assert!(blocktree
+                .get_confirmed_signatures_for_address(&address, 0, 3, None, 10)
+                .unwrap()
+                .is_empty());
+            blocktree.set_roots(&[1, 2]).unwrap();
+            assert_eq!(
+                blocktree
+                    .get_confirmed_signatures_for_address(&address, 0, 3, None, 10)
+                    .unwrap(),
+                vec![signatures[0], signatures[2]]
+            );
+            assert_eq!(
+                blocktree
+                    .get_confirmed_signatures_for_address(&address, 2, 2, None, 10)
+                    .unwrap(),
+                vec![signatures[2]]
+            );
+            assert_eq!(
+                blocktree
+                    .get_confirmed_signatures_for_address(&other_address, 0, 3, None, 10)
+                    .unwrap(),
+                vec![signatures[0], signatures[1]]
+            );
+
+            // Paging through the signatures
+            assert_eq!(
+                blocktree
+                    .get_confirmed_signatures_for_address(&other_address, 0, 3, None, 1)
+                    .unwrap(),
+                vec![signatures[0]]
+            );
+            assert_eq!(
+                blocktree
+                    .get_confirmed_signatures_for_address(
+                        &other_address,
+                        0,
+                        3,
+                        Some(signatures[0]),
+                        1
+                    )
+                    .unwrap(),
+                vec![signatures[1]]
+            );
+            assert_eq!(
+                blocktree
+                    .get_confirmed_signatures_for_address(&address, 0, 3, Some(signatures[0]), 10)
+                    .unwrap(),
+                vec![signatures[2]]
+            );
+            assert!(blocktree
+                .get_confirmed_signatures_for_address(&address, 0, 3, None, 0)
+                .unwrap()
+                .is_empty());
+
+            blocktree.purge_slots(2, None);
+            assert_eq!(
+                blocktree
+                    .get_confirmed_signatures_for_address(&address, 0, 3, None, 10)
+                    .unwrap(),
+                vec![signatures[0]]
+            );
+        }
+        Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     pub fn test_insert_data_blobs_consecutive() {
         let blocktree_path = get_tmp_ledger_path("test_insert_data_blobs_consecutive");
@@ -3564,14 +4288,20 @@ pub mod tests {
         let blocktree = Blocktree::open(&blocktree_path).unwrap();
         let (blobs, _) = make_many_slot_entries(0, 50, 5);
         blocktree.write_blobs(blobs).unwrap();
+        assert_eq!(blocktree.lowest_slot(), 0);
 
         blocktree.purge_slots(0, Some(5));
+        assert_eq!(blocktree.lowest_slot(), 6);
+
+        // A single slot
+        blocktree.purge_slots(6, Some(6));
+        assert_eq!(blocktree.lowest_slot(), 7);
 
         blocktree
             .slot_meta_iterator(0)
             .unwrap()
             .for_each(|(slot, _)| {
-                assert!(slot > 5);
+                assert!(slot > 6);
             });
 
         blocktree.purge_slots(0, None);
@@ -3880,7 +4610,6 @@ pub mod tests {
                 &index,
                 &prev_data,
                 &prev_coding,
-                &erasure_config,
             )
             .expect("Successful recovery");
 
@@ -3970,6 +4699,74 @@ pub mod tests {
             );
         }
 
+        #[test]
+        fn test_recovery_variable_coding_rates() {
+            use ErasureMetaStatus::{DataFull, StillNeed};
+            solana_logger::setup();
+
+            let slot = 0;
+            let ledger_path = get_tmp_ledger_path!();
+            let blocktree = Blocktree::open(&ledger_path).unwrap();
+            let erasure_config = ErasureConfig::default();
+            let num_data = erasure_config.num_data();
+
+            // (coding blobs, data blobs lost) of each erasure set of the slot
+            let sets = [(2, 2), (num_data, 5), (1, 2)];
+            let data_blobs = make_slot_entries(slot, 0, (sets.len() * num_data) as u64)
+                .0
+                .into_iter()
+                .map(Blob::into)
+                .collect::<Vec<_>>();
+            let keypair = Keypair::new();
+            data_blobs.iter().for_each(|blob: &Arc<RwLock<Blob>>| {
+                let mut b = blob.write().unwrap();
+                b.set_id(&keypair.pubkey());
+                b.sign(&keypair);
+            });
+
+            let mut coding_generator = CodingGenerator::new_from_config(&erasure_config);
+            for ((num_coding, num_lost), data_blobs) in
+                sets.iter().zip(data_blobs.chunks_exact(num_data))
+            {
+                coding_generator.set_num_coding(*num_coding);
+                let coding_blobs = coding_generator.next(&data_blobs);
+                assert_eq!(coding_blobs.len(), *num_coding);
+
+                blocktree
+                    .write_shared_blobs(data_blobs.iter().skip(*num_lost))
+                    .unwrap();
+                blocktree
+                    .put_shared_coding_blobs(coding_blobs.iter())
+                    .unwrap();
+            }
+
+            for (set_index, (num_coding, _)) in sets.iter().enumerate() {
+                let erasure_meta = blocktree
+                    .erasure_meta(slot, set_index as u64)
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(
+                    erasure_meta.config(),
+                    ErasureConfig::new(num_data, *num_coding)
+                );
+            }
+
+            // The first two sets had enough blobs left to be recovered, the last is a blob short
+            assert_eq!(
+                blocktree.erasure_set_statuses(slot).unwrap(),
+                vec![(0, DataFull), (1, DataFull), (2, StillNeed(1))]
+            );
+            let index = blocktree.get_index(slot).unwrap().unwrap();
+            assert_eq!(
+                index.data().present_in_bounds(0..2 * num_data as u64),
+                2 * num_data
+            );
+            assert_eq!(blocktree.erasure_set_statuses(slot + 1).unwrap(), vec![]);
+
+            drop(blocktree);
+            Blocktree::destroy(&ledger_path).expect("Expect successful Blocktree destruction");
+        }
+
         #[test]
         fn test_recovery_fails_safely() {
             const SLOT: u64 = 0;
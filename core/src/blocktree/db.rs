@@ -44,6 +44,26 @@ pub mod columns {
     #[derive(Debug)]
     /// The index column
     pub struct Index;
+
+    #[derive(Debug)]
+    /// The duplicate slots column
+    pub struct DuplicateSlots;
+
+    #[derive(Debug)]
+    /// The transaction status column
+    pub struct TransactionStatus;
+
+    #[derive(Debug)]
+    /// The column indexing transaction signatures by the addresses they use
+    pub struct AddressSignatures;
+
+    #[derive(Debug)]
+    /// The column indexing the slots transactions were committed in by their signatures
+    pub struct SignatureSlots;
+
+    #[derive(Debug)]
+    /// The inflation rewards column
+    pub struct Rewards;
 }
 
 pub trait Backend: Sized + Send + Sync {
@@ -138,6 +138,25 @@ impl TypedColumn<Kvs> for cf::Root {
     type Type = bool;
 }
 
+impl Column<Kvs> for cf::DuplicateSlots {
+    const NAME: &'static str = super::DUPLICATE_SLOTS_CF;
+    type Index = u64;
+
+    fn key(slot: u64) -> Key {
+        let mut key = Key::default();
+        BigEndian::write_u64(&mut key.0[8..16], slot);
+        key
+    }
+
+    fn index(key: &Key) -> u64 {
+        BigEndian::read_u64(&key.0[8..16])
+    }
+}
+
+impl TypedColumn<Kvs> for cf::DuplicateSlots {
+    type Type = super::DuplicateSlotProof;
+}
+
 impl Column<Kvs> for cf::Orphans {
     const NAME: &'static str = super::ORPHANS_CF;
     type Index = u64;
@@ -48,6 +48,17 @@ pub struct CodingIndex {
     index: BTreeSet<u64>,
 }
 
+#[derive(Clone, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+/// Proof that the leader of a slot signed two different blobs for the same index
+pub struct DuplicateSlotProof {
+    /// Index of the conflicting blobs within the slot
+    pub index: u64,
+    /// The first payload received for the index, including the blob header
+    pub blob1: Vec<u8>,
+    /// The conflicting payload, including the blob header
+    pub blob2: Vec<u8>,
+}
+
 #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
 /// Erasure coding information
 pub struct ErasureMeta {
@@ -218,6 +229,10 @@ impl ErasureMeta {
         }
     }
 
+    pub fn config(&self) -> ErasureConfig {
+        self.config
+    }
+
     pub fn set_size(&mut self, size: usize) {
         self.size = size;
     }
@@ -2,6 +2,8 @@ use crate::blocktree::db::columns as cf;
 use crate::blocktree::db::{Backend, Column, DbCursor, IWriteBatch, TypedColumn};
 use crate::blocktree::BlocktreeError;
 use crate::result::{Error, Result};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
 use solana_sdk::timing::Slot;
 
 use byteorder::{BigEndian, ByteOrder};
@@ -33,7 +35,8 @@ impl Backend for Rocks {
 
     fn open(path: &Path) -> Result<Rocks> {
         use crate::blocktree::db::columns::{
-            Coding, Data, DeadSlots, ErasureMeta, Index, Orphans, Root, SlotMeta,
+            AddressSignatures, Coding, Data, DeadSlots, DuplicateSlots, ErasureMeta, Index,
+            Orphans, Rewards, Root, SignatureSlots, SlotMeta, TransactionStatus,
         };
 
         fs::create_dir_all(&path)?;
@@ -58,6 +61,20 @@ impl Backend for Rocks {
             ColumnFamilyDescriptor::new(Root::NAME, get_cf_options(Root::NAME));
         let index_cf_descriptor =
             ColumnFamilyDescriptor::new(Index::NAME, get_cf_options(Index::NAME));
+        let duplicate_slots_cf_descriptor =
+            ColumnFamilyDescriptor::new(DuplicateSlots::NAME, get_cf_options(DuplicateSlots::NAME));
+        let transaction_status_cf_descriptor = ColumnFamilyDescriptor::new(
+            TransactionStatus::NAME,
+            get_cf_options(TransactionStatus::NAME),
+        );
+        let address_signatures_cf_descriptor = ColumnFamilyDescriptor::new(
+            AddressSignatures::NAME,
+            get_cf_options(AddressSignatures::NAME),
+        );
+        let signature_slots_cf_descriptor =
+            ColumnFamilyDescriptor::new(SignatureSlots::NAME, get_cf_options(SignatureSlots::NAME));
+        let rewards_cf_descriptor =
+            ColumnFamilyDescriptor::new(Rewards::NAME, get_cf_options(Rewards::NAME));
 
         let cfs = vec![
             meta_cf_descriptor,
@@ -68,6 +85,11 @@ impl Backend for Rocks {
             orphans_cf_descriptor,
             root_cf_descriptor,
             index_cf_descriptor,
+            duplicate_slots_cf_descriptor,
+            transaction_status_cf_descriptor,
+            address_signatures_cf_descriptor,
+            signature_slots_cf_descriptor,
+            rewards_cf_descriptor,
         ];
 
         // Open the database
@@ -78,18 +100,24 @@ impl Backend for Rocks {
 
     fn columns(&self) -> Vec<&'static str> {
         use crate::blocktree::db::columns::{
-            Coding, Data, DeadSlots, ErasureMeta, Index, Orphans, Root, SlotMeta,
+            AddressSignatures, Coding, Data, DeadSlots, DuplicateSlots, ErasureMeta, Index,
+            Orphans, Rewards, Root, SignatureSlots, SlotMeta, TransactionStatus,
         };
 
         vec![
             Coding::NAME,
             ErasureMeta::NAME,
             DeadSlots::NAME,
+            DuplicateSlots::NAME,
             Data::NAME,
             Index::NAME,
             Orphans::NAME,
             Root::NAME,
             SlotMeta::NAME,
+            TransactionStatus::NAME,
+            AddressSignatures::NAME,
+            SignatureSlots::NAME,
+            Rewards::NAME,
         ]
     }
 
@@ -250,6 +278,33 @@ impl TypedColumn<Rocks> for cf::DeadSlots {
     type Type = bool;
 }
 
+impl Column<Rocks> for cf::DuplicateSlots {
+    const NAME: &'static str = super::DUPLICATE_SLOTS_CF;
+    type Index = u64;
+
+    fn key(slot: u64) -> Vec<u8> {
+        let mut key = vec![0; 8];
+        BigEndian::write_u64(&mut key[..], slot);
+        key
+    }
+
+    fn index(key: &[u8]) -> u64 {
+        BigEndian::read_u64(&key[..8])
+    }
+
+    fn slot(index: Self::Index) -> Slot {
+        index
+    }
+
+    fn as_index(slot: Slot) -> Self::Index {
+        slot
+    }
+}
+
+impl TypedColumn<Rocks> for cf::DuplicateSlots {
+    type Type = super::DuplicateSlotProof;
+}
+
 impl Column<Rocks> for cf::Orphans {
     const NAME: &'static str = super::ORPHANS_CF;
     type Index = u64;
@@ -331,6 +386,129 @@ impl TypedColumn<Rocks> for cf::SlotMeta {
     type Type = super::SlotMeta;
 }
 
+impl Column<Rocks> for cf::TransactionStatus {
+    const NAME: &'static str = super::TRANSACTION_STATUS_CF;
+    type Index = (u64, Signature);
+
+    fn key((slot, signature): (u64, Signature)) -> Vec<u8> {
+        let mut key = vec![0; 8];
+        BigEndian::write_u64(&mut key[..], slot);
+        key.extend_from_slice(signature.as_ref());
+        key
+    }
+
+    fn index(key: &[u8]) -> (u64, Signature) {
+        let slot = BigEndian::read_u64(&key[..8]);
+        let signature = Signature::new(&key[8..]);
+        (slot, signature)
+    }
+
+    fn slot(index: Self::Index) -> Slot {
+        index.0
+    }
+
+    fn as_index(slot: Slot) -> Self::Index {
+        (slot, Signature::default())
+    }
+}
+
+impl TypedColumn<Rocks> for cf::TransactionStatus {
+    type Type = solana_sdk::transaction::TransactionStatusMeta;
+}
+
+impl Column<Rocks> for cf::AddressSignatures {
+    const NAME: &'static str = super::ADDRESS_SIGNATURES_CF;
+    type Index = (u64, Pubkey, u64, Signature);
+
+    fn key((slot, address, position, signature): (u64, Pubkey, u64, Signature)) -> Vec<u8> {
+        let mut key = vec![0; 8];
+        BigEndian::write_u64(&mut key[..], slot);
+        key.extend_from_slice(address.as_ref());
+        key.extend_from_slice(&position.to_be_bytes());
+        key.extend_from_slice(signature.as_ref());
+        key
+    }
+
+    fn index(key: &[u8]) -> (u64, Pubkey, u64, Signature) {
+        let slot = BigEndian::read_u64(&key[..8]);
+        let address = Pubkey::new(&key[8..40]);
+        let position = BigEndian::read_u64(&key[40..48]);
+        let signature = Signature::new(&key[48..]);
+        (slot, address, position, signature)
+    }
+
+    fn slot(index: Self::Index) -> Slot {
+        index.0
+    }
+
+    fn as_index(slot: Slot) -> Self::Index {
+        (slot, Pubkey::default(), 0, Signature::default())
+    }
+}
+
+impl TypedColumn<Rocks> for cf::AddressSignatures {
+    type Type = bool;
+}
+
+impl Column<Rocks> for cf::SignatureSlots {
+    const NAME: &'static str = super::SIGNATURE_SLOTS_CF;
+    type Index = (Signature, u64);
+
+    fn key((signature, slot): (Signature, u64)) -> Vec<u8> {
+        let mut key = signature.as_ref().to_vec();
+        key.extend_from_slice(&slot.to_be_bytes());
+        key
+    }
+
+    fn index(key: &[u8]) -> (Signature, u64) {
+        let signature = Signature::new(&key[..64]);
+        let slot = BigEndian::read_u64(&key[64..]);
+        (signature, slot)
+    }
+
+    fn slot(index: Self::Index) -> Slot {
+        index.1
+    }
+
+    fn as_index(slot: Slot) -> Self::Index {
+        (Signature::default(), slot)
+    }
+}
+
+impl TypedColumn<Rocks> for cf::SignatureSlots {
+    type Type = ();
+}
+
+impl Column<Rocks> for cf::Rewards {
+    const NAME: &'static str = super::REWARDS_CF;
+    type Index = (u64, Pubkey);
+
+    fn key((slot, address): (u64, Pubkey)) -> Vec<u8> {
+        let mut key = vec![0; 8];
+        BigEndian::write_u64(&mut key[..], slot);
+        key.extend_from_slice(address.as_ref());
+        key
+    }
+
+    fn index(key: &[u8]) -> (u64, Pubkey) {
+        let slot = BigEndian::read_u64(&key[..8]);
+        let address = Pubkey::new(&key[8..40]);
+        (slot, address)
+    }
+
+    fn slot(index: Self::Index) -> Slot {
+        index.0
+    }
+
+    fn as_index(slot: Slot) -> Self::Index {
+        (slot, Pubkey::default())
+    }
+}
+
+impl TypedColumn<Rocks> for cf::Rewards {
+    type Type = solana_runtime::bank::RewardInfo;
+}
+
 impl Column<Rocks> for cf::ErasureMeta {
     const NAME: &'static str = super::ERASURE_META_CF;
     type Index = (u64, u64);
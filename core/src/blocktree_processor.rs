@@ -1,16 +1,19 @@
 use crate::bank_forks::BankForks;
-use crate::blocktree::Blocktree;
-use crate::entry::{Entry, EntrySlice};
+use crate::blocktree::{Blocktree, SlotMeta};
+use crate::entry::Entry;
+use crate::entry_verifier::{new_poh_verifier, PohVerifier};
 use crate::leader_schedule_cache::LeaderScheduleCache;
+use crate::packet::BLOB_DATA_SIZE;
 use rayon::prelude::*;
 use rayon::ThreadPool;
 use solana_metrics::{datapoint, datapoint_error, inc_new_counter_debug};
 use solana_runtime::bank::Bank;
 use solana_runtime::locked_accounts_results::LockedAccountsResults;
 use solana_sdk::genesis_block::GenesisBlock;
+use solana_sdk::hash::Hash;
 use solana_sdk::timing::duration_as_ms;
 use solana_sdk::timing::MAX_RECENT_BLOCKHASHES;
-use solana_sdk::transaction::Result;
+use solana_sdk::transaction::{Result, SanitizedTransaction};
 use std::result;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -32,54 +35,89 @@ fn first_err(results: &[Result<()>]) -> Result<()> {
     Ok(())
 }
 
-fn par_execute_entries(bank: &Bank, entries: &[(&Entry, LockedAccountsResults)]) -> Result<()> {
+// Warm the accounts of the entries up to the next tick, which are executed next
+fn prefetch_entries(bank: &Bank, upcoming_entries: &[(&Entry, &[SanitizedTransaction])]) {
+    for (_, transactions) in upcoming_entries
+        .iter()
+        .take_while(|(entry, _)| !entry.is_tick())
+    {
+        bank.prefetch_accounts(transactions);
+    }
+}
+
+/// Execute `entries` in parallel, while prefetching the accounts of `upcoming_entries`
+fn par_execute_entries(
+    bank: &Bank,
+    entries: &[LockedAccountsResults],
+    upcoming_entries: &[(&Entry, &[SanitizedTransaction])],
+) -> Result<()> {
     inc_new_counter_debug!("bank-par_execute_entries-count", entries.len());
-    let results: Vec<Result<()>> = PAR_THREAD_POOL.with(|thread_pool| {
-        thread_pool.borrow().install(|| {
-            entries
-                .into_par_iter()
-                .map(|(e, locked_accounts)| {
-                    let results = bank.load_execute_and_commit_transactions(
-                        &e.transactions,
-                        locked_accounts,
-                        MAX_RECENT_BLOCKHASHES,
-                    );
-                    let mut first_err = None;
-                    for (r, tx) in results.iter().zip(e.transactions.iter()) {
-                        if let Err(ref e) = r {
-                            if first_err.is_none() {
-                                first_err = Some(r.clone());
-                            }
-                            if !Bank::can_commit(&r) {
-                                warn!("Unexpected validator error: {:?}, tx: {:?}", e, tx);
-                                datapoint_error!(
-                                    "validator_process_entry_error",
-                                    ("error", format!("error: {:?}, tx: {:?}", e, tx), String)
-                                );
-                            }
-                        }
-                    }
-                    first_err.unwrap_or(Ok(()))
-                })
-                .collect()
-        })
-    });
+    let execute = || {
+        rayon::join(
+            || prefetch_entries(bank, upcoming_entries),
+            || execute_entries(bank, entries),
+        )
+    };
+    // Forks replayed in parallel already run on the pool, so their entries share its threads
+    let (_, results): (_, Vec<Result<()>>) = if rayon::current_thread_index().is_some() {
+        execute()
+    } else {
+        PAR_THREAD_POOL.with(|thread_pool| thread_pool.borrow().install(execute))
+    };
 
     first_err(&results)
 }
 
+fn execute_entries(bank: &Bank, entries: &[LockedAccountsResults]) -> Vec<Result<()>> {
+    entries
+        .into_par_iter()
+        .map(|locked_accounts| {
+            let transactions = locked_accounts.transactions();
+            let results = bank.load_execute_and_commit_transactions(
+                transactions,
+                locked_accounts,
+                MAX_RECENT_BLOCKHASHES,
+            );
+            let mut first_err = None;
+            for (r, tx) in results.iter().zip(transactions.iter()) {
+                if let Err(ref e) = r {
+                    if first_err.is_none() {
+                        first_err = Some(r.clone());
+                    }
+                    if !Bank::can_commit(&r) {
+                        warn!("Unexpected validator error: {:?}, tx: {:?}", e, tx);
+                        datapoint_error!(
+                            "validator_process_entry_error",
+                            ("error", format!("error: {:?}, tx: {:?}", e, tx), String)
+                        );
+                    }
+                }
+            }
+            first_err.unwrap_or(Ok(()))
+        })
+        .collect()
+}
+
 /// Process an ordered list of entries in parallel
 /// 1. In order lock accounts for each entry while the lock succeeds, up to a Tick entry
 /// 2. Process the locked group in parallel
 /// 3. Register the `Tick` if it's available
 /// 4. Update the leader scheduler, goto 1
 pub fn process_entries(bank: &Bank, entries: &[Entry]) -> Result<()> {
+    // Entries from the ledger are sanitized like any other transactions before the bank sees them
+    let entries = entries
+        .iter()
+        .map(|entry| {
+            SanitizedTransaction::try_from_slice(&entry.transactions)
+                .map(|transactions| (entry, transactions))
+        })
+        .collect::<Result<Vec<_>>>()?;
     // accumulator for entries that can be processed in parallel
     let mut mt_group = vec![];
-    for entry in entries {
+    for (i, (entry, transactions)) in entries.iter().enumerate() {
         if entry.is_tick() {
             // if its a tick, execute the group and register the tick
-            par_execute_entries(bank, &mt_group)?;
+            par_execute_entries(bank, &mt_group, &entries[i + 1..])?;
             mt_group = vec![];
             bank.register_tick(&entry.hash);
             continue;
@@ -87,14 +125,14 @@ pub fn process_entries(bank: &Bank, entries: &[Entry]) -> Result<()> {
         // else loop on processing the entry
         loop {
             // try to lock the accounts
-            let lock_results = bank.lock_accounts(&entry.transactions);
+            let lock_results = bank.lock_accounts(transactions);
 
             let first_lock_err = first_err(lock_results.locked_accounts_results());
 
             // if locking worked
             if first_lock_err.is_ok() {
                 // push the entry to the mt_group
-                mt_group.push((entry, lock_results));
+                mt_group.push(lock_results);
                 // done with this entry
                 break;
             }
@@ -118,15 +156,38 @@ pub fn process_entries(bank: &Bank, entries: &[Entry]) -> Result<()> {
             } else {
                 // else we have an entry that conflicts with a prior entry
                 // execute the current queue and try to process this entry again
-                par_execute_entries(bank, &mt_group)?;
+                par_execute_entries(bank, &mt_group, &entries[i..])?;
                 mt_group = vec![];
             }
         }
     }
-    par_execute_entries(bank, &mt_group)?;
+    par_execute_entries(bank, &mt_group, &[])?;
     Ok(())
 }
 
+/// Persists the statuses of the transactions a frozen `bank` committed, indexes them by the
+/// addresses they use, and persists the inflation rewards it paid. The bank forgets them once
+/// it's squashed. Failures are only logged, the history is served over RPC but replay doesn't
+/// need it.
+pub fn write_transaction_history(blocktree: &Blocktree, bank: &Bank) {
+    let slot = bank.slot();
+    if let Err(err) = blocktree.write_transaction_statuses(slot, &bank.transaction_status_metas()) {
+        warn!(
+            "Failed to write transaction statuses for slot {}: {:?}",
+            slot, err
+        );
+    }
+    if let Err(err) = blocktree.write_address_signatures(slot, &bank.take_transaction_addresses()) {
+        warn!(
+            "Failed to write address signatures for slot {}: {:?}",
+            slot, err
+        );
+    }
+    if let Err(err) = blocktree.write_rewards(slot, &bank.take_rewards()) {
+        warn!("Failed to write rewards for slot {}: {:?}", slot, err);
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct BankForksInfo {
     pub bank_slot: u64,
@@ -138,11 +199,73 @@ pub enum BlocktreeProcessorError {
     LedgerVerificationFailed,
 }
 
+/// Bounds the size of the entries of the slots `process_blocktree` replays at once
+const MAX_REPLAY_BATCH_BYTES: u64 = 512 * 1024 * 1024;
+
+/// A full slot whose bank is ready to replay its entries
+struct PendingSlot {
+    slot: u64,
+    meta: SlotMeta,
+    bank: Arc<Bank>,
+    entry_height: u64,
+    last_entry_hash: Hash,
+}
+
+impl PendingSlot {
+    /// An upper bound on the size of the slot's entries
+    fn max_entries_bytes(&self) -> u64 {
+        self.meta.consumed * BLOB_DATA_SIZE as u64
+    }
+}
+
+/// Loads the entries of `pending` and, with `verify_ledger`, checks they chain from its last
+/// entry hash. Returns them without slot 0's pseudo-tick, which the bank doesn't process.
+fn load_slot_entries(
+    blocktree: &Blocktree,
+    pending: &mut PendingSlot,
+    poh_verifier: &dyn PohVerifier,
+    verify_ledger: bool,
+) -> result::Result<Vec<Entry>, BlocktreeProcessorError> {
+    let slot = pending.slot;
+    let mut entries = blocktree.get_slot_entries(slot, 0, None).map_err(|err| {
+        warn!("Failed to load entries for slot {}: {:?}", slot, err);
+        BlocktreeProcessorError::LedgerVerificationFailed
+    })?;
+
+    if slot == 0 {
+        // The first entry in the ledger is a pseudo-tick used only to ensure the number of ticks
+        // in slot 0 is the same as the number of ticks in all subsequent slots.  It is not
+        // processed by the bank, skip over it.
+        if entries.is_empty() {
+            warn!("entry0 not present");
+            return Err(BlocktreeProcessorError::LedgerVerificationFailed);
+        }
+        let entry0 = entries.remove(0);
+        if !(entry0.is_tick() && entry0.verify(&pending.last_entry_hash)) {
+            warn!("Ledger proof of history failed at entry0");
+            return Err(BlocktreeProcessorError::LedgerVerificationFailed);
+        }
+        pending.last_entry_hash = entry0.hash;
+        pending.entry_height += 1;
+    }
+
+    if verify_ledger && !poh_verifier.verify(&entries, &pending.last_entry_hash) {
+        warn!(
+            "Ledger proof of history failed at slot: {}, entry: {}",
+            slot, pending.entry_height
+        );
+        return Err(BlocktreeProcessorError::LedgerVerificationFailed);
+    }
+    Ok(entries)
+}
+
+/// Replays the ledger into banks. With `dev_halt_at_slot`, slots past it are left unprocessed.
 pub fn process_blocktree(
     genesis_block: &GenesisBlock,
     blocktree: &Blocktree,
     account_paths: Option<String>,
     verify_ledger: bool,
+    dev_halt_at_slot: Option<u64>,
 ) -> result::Result<(BankForks, Vec<BankForksInfo>, LeaderScheduleCache), BlocktreeProcessorError> {
     let now = Instant::now();
     info!("processing ledger...");
@@ -162,130 +285,194 @@ pub fn process_blocktree(
             })?
             .unwrap();
 
-        vec![(slot, meta, bank, entry_height, last_entry_hash)]
+        vec![PendingSlot {
+            slot,
+            meta,
+            bank,
+            entry_height,
+            last_entry_hash,
+        }]
     };
 
     blocktree.set_roots(&[0]).expect("Couldn't set first root");
 
-    let leader_schedule_cache =
-        LeaderScheduleCache::new(*pending_slots[0].2.epoch_schedule(), &pending_slots[0].2);
+    let leader_schedule_cache = LeaderScheduleCache::new(
+        *pending_slots[0].bank.epoch_schedule(),
+        &pending_slots[0].bank,
+    );
 
+    let poh_verifier = new_poh_verifier();
     let mut fork_info = vec![];
+    let mut failed_slots = vec![];
     let mut last_status_report = Instant::now();
     let mut root = 0;
     while !pending_slots.is_empty() {
-        let (slot, meta, bank, mut entry_height, mut last_entry_hash) =
-            pending_slots.pop().unwrap();
+        // The pending slots are on independent forks, so they're replayed together, lowest slot
+        // first, as many as fit the memory budget
+        let mut batch = vec![];
+        let mut batch_bytes = 0;
+        while let Some(pending) = pending_slots.last() {
+            let slot_bytes = pending.max_entries_bytes();
+            if !batch.is_empty() && batch_bytes + slot_bytes > MAX_REPLAY_BATCH_BYTES {
+                break;
+            }
+            batch_bytes += slot_bytes;
+            batch.push(pending_slots.pop().unwrap());
+        }
 
         if last_status_report.elapsed() > Duration::from_secs(2) {
-            info!("processing ledger...block {}", slot);
+            info!(
+                "processing ledger...block {} ({} forks)",
+                batch[0].slot,
+                batch.len()
+            );
             last_status_report = Instant::now();
         }
 
-        // Fetch all entries for this slot
-        let mut entries = blocktree.get_slot_entries(slot, 0, None).map_err(|err| {
-            warn!("Failed to load entries for slot {}: {:?}", slot, err);
-            BlocktreeProcessorError::LedgerVerificationFailed
-        })?;
-
-        if slot == 0 {
-            // The first entry in the ledger is a pseudo-tick used only to ensure the number of ticks
-            // in slot 0 is the same as the number of ticks in all subsequent slots.  It is not
-            // processed by the bank, skip over it.
-            if entries.is_empty() {
-                warn!("entry0 not present");
-                return Err(BlocktreeProcessorError::LedgerVerificationFailed);
+        // Verification is already parallel within a slot
+        let replays: Vec<_> = batch
+            .into_iter()
+            .map(|mut pending| {
+                let entries =
+                    load_slot_entries(blocktree, &mut pending, &*poh_verifier, verify_ledger);
+                (pending, entries)
+            })
+            .collect();
+
+        let results: Vec<_> = PAR_THREAD_POOL.with(|thread_pool| {
+            thread_pool.borrow().install(|| {
+                replays
+                    .par_iter()
+                    .map(|(pending, entries)| {
+                        entries
+                            .as_ref()
+                            .ok()
+                            .map(|entries| process_entries(&pending.bank, entries))
+                    })
+                    .collect()
+            })
+        });
+
+        // Finish the slots in order, so roots and forks are found as if replayed one at a time
+        let mut batch_root = false;
+        for ((pending, entries), result) in replays.into_iter().zip(results) {
+            if batch_root {
+                // The rest of the batch is on forks the root left behind
+                break;
             }
-            let entry0 = entries.remove(0);
-            if !(entry0.is_tick() && entry0.verify(&last_entry_hash)) {
-                warn!("Ledger proof of history failed at entry0");
-                return Err(BlocktreeProcessorError::LedgerVerificationFailed);
+            let PendingSlot {
+                slot,
+                meta,
+                bank,
+                mut entry_height,
+                mut last_entry_hash,
+            } = pending;
+            let replayed = match (entries, result) {
+                (Ok(entries), Some(Ok(()))) => Some(entries),
+                (Ok(_), Some(Err(err))) => {
+                    warn!("Failed to process entries for slot {}: {:?}", slot, err);
+                    None
+                }
+                _ => None,
+            };
+            let entries = match replayed {
+                Some(entries) => entries,
+                None => {
+                    // The fork may still be left behind by a later root, so the failure only
+                    // fails the ledger if the slot is kept
+                    if blocktree.is_root(slot) {
+                        return Err(BlocktreeProcessorError::LedgerVerificationFailed);
+                    }
+                    failed_slots.push(slot);
+                    continue;
+                }
+            };
+            if let Some(last_entry) = entries.last() {
+                last_entry_hash = last_entry.hash;
+                entry_height += entries.len() as u64;
             }
-            last_entry_hash = entry0.hash;
-            entry_height += 1;
-        }
 
-        if !entries.is_empty() {
-            if verify_ledger && !entries.verify(&last_entry_hash) {
-                warn!(
-                    "Ledger proof of history failed at slot: {}, entry: {}",
-                    slot, entry_height
-                );
-                return Err(BlocktreeProcessorError::LedgerVerificationFailed);
+            bank.freeze(); // all banks handled by this routine are created from complete slots
+            write_transaction_history(blocktree, &bank);
+
+            if blocktree.is_root(slot) {
+                root = slot;
+                leader_schedule_cache.set_root(&bank);
+                bank.squash();
+                pending_slots.clear();
+                fork_info.clear();
+                failed_slots.clear();
+                batch_root = true;
             }
 
-            process_entries(&bank, &entries).map_err(|err| {
-                warn!("Failed to process entries for slot {}: {:?}", slot, err);
-                BlocktreeProcessorError::LedgerVerificationFailed
-            })?;
-
-            last_entry_hash = entries.last().unwrap().hash;
-            entry_height += entries.len() as u64;
-        }
-
-        bank.freeze(); // all banks handled by this routine are created from complete slots
-
-        if blocktree.is_root(slot) {
-            root = slot;
-            leader_schedule_cache.set_root(&bank);
-            bank.squash();
-            pending_slots.clear();
-            fork_info.clear();
-        }
-
-        if meta.next_slots.is_empty() {
-            // Reached the end of this fork.  Record the final entry height and last entry.hash
-            let bfi = BankForksInfo {
-                bank_slot: slot,
-                entry_height,
-            };
-            fork_info.push((bank, bfi));
-            continue;
-        }
-
-        // This is a fork point, create a new child bank for each fork
-        for next_slot in meta.next_slots {
-            let next_meta = blocktree
-                .meta(next_slot)
-                .map_err(|err| {
-                    warn!("Failed to load meta for slot {}: {:?}", slot, err);
-                    BlocktreeProcessorError::LedgerVerificationFailed
-                })?
-                .unwrap();
-
-            // only process full slots in blocktree_processor, replay_stage
-            // handles any partials
-            if next_meta.is_full() {
-                let next_bank = Arc::new(Bank::new_from_parent(
-                    &bank,
-                    &leader_schedule_cache
-                        .slot_leader_at(next_slot, Some(&bank))
-                        .unwrap(),
-                    next_slot,
-                ));
-                trace!("Add child bank for slot={}", next_slot);
-                // bank_forks.insert(*next_slot, child_bank);
-                pending_slots.push((
-                    next_slot,
-                    next_meta,
-                    next_bank,
-                    entry_height,
-                    last_entry_hash,
-                ));
-            } else {
+            let next_slots: Vec<u64> = meta
+                .next_slots
+                .into_iter()
+                .filter(|next_slot| {
+                    dev_halt_at_slot.map_or(true, |halt_slot| *next_slot <= halt_slot)
+                })
+                .collect();
+            if next_slots.is_empty() {
+                // Reached the end of this fork.  Record the final entry height and last entry.hash
                 let bfi = BankForksInfo {
                     bank_slot: slot,
                     entry_height,
                 };
-                fork_info.push((bank.clone(), bfi));
+                fork_info.push((bank, bfi));
+                continue;
+            }
+
+            // This is a fork point, create a new child bank for each fork
+            for next_slot in next_slots {
+                let next_meta = blocktree
+                    .meta(next_slot)
+                    .map_err(|err| {
+                        warn!("Failed to load meta for slot {}: {:?}", slot, err);
+                        BlocktreeProcessorError::LedgerVerificationFailed
+                    })?
+                    .unwrap();
+
+                // only process full slots in blocktree_processor, replay_stage
+                // handles any partials
+                if next_meta.is_full() {
+                    let next_bank = Arc::new(Bank::new_from_parent(
+                        &bank,
+                        &leader_schedule_cache
+                            .slot_leader_at(next_slot, Some(&bank))
+                            .unwrap(),
+                        next_slot,
+                    ));
+                    trace!("Add child bank for slot={}", next_slot);
+                    // bank_forks.insert(*next_slot, child_bank);
+                    pending_slots.push(PendingSlot {
+                        slot: next_slot,
+                        meta: next_meta,
+                        bank: next_bank,
+                        entry_height,
+                        last_entry_hash,
+                    });
+                } else {
+                    let bfi = BankForksInfo {
+                        bank_slot: slot,
+                        entry_height,
+                    };
+                    fork_info.push((bank.clone(), bfi));
+                }
             }
         }
 
         // reverse sort by slot, so the next slot to be processed can be pop()ed
         // TODO: remove me once leader_scheduler can hang with out-of-order slots?
-        pending_slots.sort_by(|a, b| b.0.cmp(&a.0));
+        pending_slots.sort_by(|a, b| b.slot.cmp(&a.slot));
     }
 
+    if !failed_slots.is_empty() {
+        warn!("Failed to replay slots {:?}", failed_slots);
+        return Err(BlocktreeProcessorError::LedgerVerificationFailed);
+    }
+
+    // Report the forks in slot order, as if every slot was replayed one at a time
+    fork_info.sort_by_key(|(_, bfi)| bfi.bank_slot);
     let (banks, bank_forks_info): (Vec<_>, Vec<_>) = fork_info.into_iter().unzip();
     let bank_forks = BankForks::new_from_banks(&banks, root);
     info!(
@@ -376,7 +563,7 @@ pub mod tests {
         fill_blocktree_slot_with_ticks(&blocktree, ticks_per_slot, 2, 1, blockhash);
 
         let (mut _bank_forks, bank_forks_info, _) =
-            process_blocktree(&genesis_block, &blocktree, None, true).unwrap();
+            process_blocktree(&genesis_block, &blocktree, None, true, None).unwrap();
 
         assert_eq!(bank_forks_info.len(), 1);
         assert_eq!(
@@ -435,7 +622,7 @@ pub mod tests {
         blocktree.set_roots(&[4, 1, 0]).unwrap();
 
         let (bank_forks, bank_forks_info, _) =
-            process_blocktree(&genesis_block, &blocktree, None, true).unwrap();
+            process_blocktree(&genesis_block, &blocktree, None, true, None).unwrap();
 
         assert_eq!(bank_forks_info.len(), 1); // One fork, other one is ignored b/c not a descendant of the root
 
@@ -509,7 +696,7 @@ pub mod tests {
         blocktree.set_roots(&[0, 1]).unwrap();
 
         let (bank_forks, bank_forks_info, _) =
-            process_blocktree(&genesis_block, &blocktree, None, true).unwrap();
+            process_blocktree(&genesis_block, &blocktree, None, true, None).unwrap();
 
         assert_eq!(bank_forks_info.len(), 2); // There are two forks
         assert_eq!(
@@ -550,6 +737,131 @@ pub mod tests {
             assert_eq!(bank_forks[info.bank_slot].slot(), info.bank_slot);
             assert!(bank_forks[info.bank_slot].is_frozen());
         }
+
+        // Halting at slot 2 leaves slots 3 and 4 unprocessed
+        let (bank_forks, bank_forks_info, _) =
+            process_blocktree(&genesis_block, &blocktree, None, true, Some(2)).unwrap();
+        assert_eq!(
+            bank_forks_info,
+            vec![BankForksInfo {
+                bank_slot: 2,
+                entry_height: ticks_per_slot * 3,
+            }]
+        );
+        assert!(bank_forks.get(3).is_none());
+        assert!(bank_forks.get(4).is_none());
+    }
+
+    #[test]
+    fn test_process_blocktree_with_many_forks() {
+        solana_logger::setup();
+
+        let GenesisBlockInfo { genesis_block, .. } = create_genesis_block(10_000);
+        let ticks_per_slot = genesis_block.ticks_per_slot;
+        let (ledger_path, blockhash) = create_new_tmp_ledger!(&genesis_block);
+        let blocktree =
+            Blocktree::open(&ledger_path).expect("Expected to successfully open database ledger");
+
+        /*
+            Build a blocktree with sibling forks that are replayed together:
+
+                        slot 0
+                          |
+                        slot 1  <-- set_root(true)
+                     /   /   \   \
+                slot 2 slot 3 slot 4 slot 5
+                         |
+                       slot 6
+        */
+        let last_slot1_entry_hash =
+            fill_blocktree_slot_with_ticks(&blocktree, ticks_per_slot, 1, 0, blockhash);
+        let mut last_slot3_entry_hash = last_slot1_entry_hash;
+        for slot in 2..=5 {
+            let last_entry_hash = fill_blocktree_slot_with_ticks(
+                &blocktree,
+                ticks_per_slot,
+                slot,
+                1,
+                last_slot1_entry_hash,
+            );
+            if slot == 3 {
+                last_slot3_entry_hash = last_entry_hash;
+            }
+        }
+        fill_blocktree_slot_with_ticks(&blocktree, ticks_per_slot, 6, 3, last_slot3_entry_hash);
+        blocktree.set_roots(&[0, 1]).unwrap();
+
+        let (bank_forks, bank_forks_info, _) =
+            process_blocktree(&genesis_block, &blocktree, None, true, None).unwrap();
+
+        let expected_forks = vec![(2, 3), (4, 3), (5, 3), (6, 4)];
+        assert_eq!(
+            bank_forks_info,
+            expected_forks
+                .iter()
+                .map(|(bank_slot, num_slots)| BankForksInfo {
+                    bank_slot: *bank_slot,
+                    entry_height: ticks_per_slot * num_slots,
+                })
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            &bank_forks[6]
+                .parents()
+                .iter()
+                .map(|bank| bank.slot())
+                .collect::<Vec<_>>(),
+            &[3, 1]
+        );
+        for info in bank_forks_info {
+            assert!(bank_forks[info.bank_slot].is_frozen());
+        }
+        assert_eq!(bank_forks.root(), 1);
+
+        // A bad fork fails the whole ledger, even when replayed alongside good ones
+        let bad_slot = 7;
+        fill_blocktree_slot_with_ticks(&blocktree, ticks_per_slot, bad_slot, 1, blockhash);
+        assert!(process_blocktree(&genesis_block, &blocktree, None, true, None).is_err());
+    }
+
+    #[test]
+    fn test_process_blocktree_bad_fork_left_behind_by_root() {
+        solana_logger::setup();
+
+        let GenesisBlockInfo { genesis_block, .. } = create_genesis_block(10_000);
+        let ticks_per_slot = genesis_block.ticks_per_slot;
+        let (ledger_path, blockhash) = create_new_tmp_ledger!(&genesis_block);
+        let blocktree =
+            Blocktree::open(&ledger_path).expect("Expected to successfully open database ledger");
+
+        /*
+            Build a blocktree where a bad fork is replayed in the same batch as the root that
+            leaves it behind:
+
+                   slot 0
+                   /   \
+              slot 1   slot 2 <-- set_root(true)
+              (bad)
+        */
+        fill_blocktree_slot_with_ticks(&blocktree, ticks_per_slot, 1, 0, Hash::default());
+        fill_blocktree_slot_with_ticks(&blocktree, ticks_per_slot, 2, 0, blockhash);
+        blocktree.set_roots(&[0, 2]).unwrap();
+
+        let (bank_forks, bank_forks_info, _) =
+            process_blocktree(&genesis_block, &blocktree, None, true, None).unwrap();
+        assert_eq!(
+            bank_forks_info,
+            vec![BankForksInfo {
+                bank_slot: 2,
+                entry_height: ticks_per_slot * 2,
+            }]
+        );
+        assert!(bank_forks.get(1).is_none());
+        assert_eq!(bank_forks.root(), 2);
+
+        // A bad slot that is itself a root fails the ledger
+        blocktree.set_roots(&[1]).unwrap();
+        assert!(process_blocktree(&genesis_block, &blocktree, None, true, None).is_err());
     }
 
     #[test]
@@ -590,7 +902,7 @@ pub mod tests {
 
         // Check that we can properly restart the ledger / leader scheduler doesn't fail
         let (bank_forks, bank_forks_info, _) =
-            process_blocktree(&genesis_block, &blocktree, None, true).unwrap();
+            process_blocktree(&genesis_block, &blocktree, None, true, None).unwrap();
 
         assert_eq!(bank_forks_info.len(), 1); // There is one fork
         assert_eq!(
@@ -726,7 +1038,7 @@ pub mod tests {
             .unwrap();
         let entry_height = genesis_block.ticks_per_slot + entries.len() as u64;
         let (bank_forks, bank_forks_info, _) =
-            process_blocktree(&genesis_block, &blocktree, None, true).unwrap();
+            process_blocktree(&genesis_block, &blocktree, None, true, None).unwrap();
 
         assert_eq!(bank_forks_info.len(), 1);
         assert_eq!(bank_forks.root(), 0);
@@ -757,7 +1069,7 @@ pub mod tests {
 
         let blocktree = Blocktree::open(&ledger_path).unwrap();
         let (bank_forks, bank_forks_info, _) =
-            process_blocktree(&genesis_block, &blocktree, None, true).unwrap();
+            process_blocktree(&genesis_block, &blocktree, None, true, None).unwrap();
 
         assert_eq!(bank_forks_info.len(), 1);
         assert_eq!(
@@ -942,15 +1254,15 @@ pub mod tests {
         assert_eq!(bank.get_balance(&keypair2.pubkey()), 4);
 
         // Check all accounts are unlocked
-        let txs1 = &entry_1_to_mint.transactions[..];
-        let txs2 = &entry_2_to_3_mint_to_1.transactions[..];
-        let locked_accounts1 = bank.lock_accounts(txs1);
+        let txs1 = SanitizedTransaction::try_new_all(entry_1_to_mint.transactions).unwrap();
+        let txs2 = SanitizedTransaction::try_new_all(entry_2_to_3_mint_to_1.transactions).unwrap();
+        let locked_accounts1 = bank.lock_accounts(&txs1);
         for result in locked_accounts1.locked_accounts_results() {
             assert!(result.is_ok());
         }
         // txs1 and txs2 have accounts that conflict, so we must drop txs1 first
         drop(locked_accounts1);
-        let locked_accounts2 = bank.lock_accounts(txs2);
+        let locked_accounts2 = bank.lock_accounts(&txs2);
         for result in locked_accounts2.locked_accounts_results() {
             assert!(result.is_ok());
         }
@@ -5,7 +5,7 @@ use self::fail_entry_verification_broadcast_run::FailEntryVerificationBroadcastR
 use self::standard_broadcast_run::StandardBroadcastRun;
 use crate::blocktree::Blocktree;
 use crate::cluster_info::{ClusterInfo, ClusterInfoError};
-use crate::erasure::{CodingGenerator, ErasureConfig};
+use crate::erasure::{CodingGenerator, CodingRateController, ErasureConfig};
 use crate::poh_recorder::WorkingBankEntries;
 use crate::result::{Error, Result};
 use crate::service::Service;
@@ -110,6 +110,8 @@ trait BroadcastRun {
 
 struct Broadcast {
     coding_generator: CodingGenerator,
+    /// Picks the coding rate of the erasure sets `coding_generator` builds
+    coding_rate: CodingRateController,
     thread_pool: ThreadPool,
 }
 
@@ -149,6 +151,7 @@ impl BroadcastStage {
 
         let mut broadcast = Broadcast {
             coding_generator,
+            coding_rate: CodingRateController::new(erasure_config),
             thread_pool: rayon::ThreadPoolBuilder::new()
                 .num_threads(sys_info::cpu_num().unwrap_or(NUM_THREADS) as usize)
                 .build()
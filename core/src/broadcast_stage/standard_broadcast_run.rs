@@ -10,12 +10,42 @@ struct BroadcastStats {
 
 pub(super) struct StandardBroadcastRun {
     stats: BroadcastStats,
+    /// `ClusterInfo::repaired_blobs()` as of the last run
+    repaired_blobs: usize,
 }
 
 impl StandardBroadcastRun {
     pub(super) fn new() -> Self {
         Self {
             stats: BroadcastStats::default(),
+            repaired_blobs: 0,
+        }
+    }
+
+    /// Sets the coding rate of the next erasure sets from the share of the blobs sent since the
+    /// last run that peers had to repair, never below the configured rate
+    fn update_coding_rate(
+        &mut self,
+        broadcast: &mut Broadcast,
+        cluster_info: &Arc<RwLock<ClusterInfo>>,
+        num_data_blobs: usize,
+    ) {
+        let repaired_blobs = cluster_info.read().unwrap().repaired_blobs();
+        broadcast.coding_rate.observe(
+            num_data_blobs,
+            repaired_blobs.saturating_sub(self.repaired_blobs),
+        );
+        self.repaired_blobs = repaired_blobs;
+
+        let num_coding = broadcast.coding_rate.num_coding();
+        if num_coding != broadcast.coding_generator.num_coding() {
+            broadcast.coding_generator.set_num_coding(num_coding);
+            let loss_rate = broadcast.coding_rate.loss_rate().unwrap_or(0.0);
+            datapoint!(
+                "broadcast-coding-rate",
+                ("num_coding", num_coding as i64, i64),
+                ("loss_rate", loss_rate, f64),
+            );
         }
     }
 
@@ -105,9 +135,16 @@ impl BroadcastRun for StandardBroadcastRun {
         );
 
         let broadcast_elapsed = broadcast_start.elapsed();
+
+        // 4) Adapt the coding rate of the next erasure sets to the loss peers repaired
+        self.update_coding_rate(broadcast, cluster_info, data_blobs.len());
+
+        let run_elapsed = receive_elapsed + to_blobs_elapsed + broadcast_elapsed;
+        // Like the other stages, the time waiting for entries isn't part of the latency
+        solana_metrics::record_latency("broadcast", to_blobs_elapsed + broadcast_elapsed);
         self.update_broadcast_stats(
             duration_as_ms(&broadcast_elapsed),
-            duration_as_ms(&(receive_elapsed + to_blobs_elapsed + broadcast_elapsed)),
+            duration_as_ms(&run_elapsed),
             num_entries,
             duration_as_ms(&to_blobs_elapsed),
             latest_blob_index,
@@ -5,4 +5,7 @@ pub trait Cluster {
     fn get_node_pubkeys(&self) -> Vec<Pubkey>;
     fn get_validator_client(&self, pubkey: &Pubkey) -> Option<ThinClient>;
     fn restart_node(&mut self, pubkey: Pubkey);
+    /// Stops a node, keeping its ledger so it can be restarted
+    fn exit_node(&mut self, pubkey: &Pubkey);
+    fn restart_exited_node(&mut self, pubkey: &Pubkey);
 }
@@ -13,15 +13,22 @@
 //!
 //! Bank needs to provide an interface for us to query the stake weight
 use crate::bank_forks::BankForks;
-use crate::blocktree::Blocktree;
+use crate::blocktree::{Blocktree, DuplicateSlotProof};
 use crate::contact_info::ContactInfo;
 use crate::crds_gossip::CrdsGossip;
 use crate::crds_gossip_error::CrdsGossipError;
 use crate::crds_gossip_pull::CRDS_GOSSIP_PULL_CRDS_TIMEOUT_MS;
-use crate::crds_value::{CrdsValue, CrdsValueLabel, EpochSlots, Vote};
+use crate::crds_value::{
+    compute_feature_set, AccountsHashes, CrdsValue, CrdsValueLabel, DuplicateShred, EpochSlots,
+    Faucet, LeaderSlots, NodeReachability, NodeVersion, QuicTpu, Reachability, Vote,
+};
+use crate::leader_slot_report::LeaderSlotTotals;
+use crate::nat_traversal::{EntrypointMessage, NatAdvice, NatTraversal};
 use crate::packet::{to_shared_blob, Blob, SharedBlob, BLOB_SIZE};
-use crate::repair_service::RepairType;
+use crate::partition_cfg::PartitionCfg;
+use crate::repair_service::{append_nonce, Nonce, RepairType};
 use crate::result::Result;
+use crate::slot_range::SlotRange;
 use crate::staking_utils;
 use crate::streamer::{BlobReceiver, BlobSender};
 use crate::weighted_shuffle::weighted_shuffle;
@@ -33,7 +40,8 @@ use rand::{thread_rng, Rng};
 use rand_chacha::ChaChaRng;
 use rayon::prelude::*;
 use solana_metrics::{
-    datapoint_debug, inc_new_counter_debug, inc_new_counter_error, inc_new_counter_warn,
+    datapoint_debug, datapoint_info, inc_new_counter_debug, inc_new_counter_error,
+    inc_new_counter_warn,
 };
 use solana_netutil::{
     bind_in_range, bind_to, find_available_port_in_range, multi_bind_in_range, PortRange,
@@ -49,8 +57,9 @@ use std::borrow::Cow;
 use std::cmp::min;
 use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt;
+use std::io;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
 use std::thread::{sleep, Builder, JoinHandle};
 use std::time::{Duration, Instant};
@@ -65,6 +74,10 @@ pub const GOSSIP_SLEEP_MILLIS: u64 = 100;
 /// the number of slots to respond with when responding to `Orphan` requests
 pub const MAX_ORPHAN_REPAIR_RESPONSES: usize = 10;
 
+/// How far a repair request's wallclock may be from the recipient's for it to be answered, in
+/// milliseconds
+pub const MAX_REPAIR_REQUEST_AGE_MS: u64 = 10_000;
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum ClusterInfoError {
     NoPeers,
@@ -80,6 +93,21 @@ pub struct ClusterInfo {
     pub(crate) keypair: Arc<Keypair>,
     /// The network entrypoint
     entrypoint: Option<ContactInfo>,
+    /// Hole punching state, for nodes behind a NAT and for the entrypoint introducing them
+    nat_traversal: NatTraversal,
+    /// Partitions a test splits the cluster into
+    partition_cfg: Option<PartitionCfg>,
+    /// Blobs this node has sent in answer to repair requests for missing blobs
+    repaired_blobs: Arc<AtomicUsize>,
+    /// The drone address this node advertises, re-pushed along with its ContactInfo
+    faucet_addr: Option<SocketAddr>,
+    /// Nodes besides itself and the entrypoint whose advertised drone this node will use
+    trusted_faucets: HashSet<Pubkey>,
+    /// The QUIC TPU address this node advertises, re-pushed along with its ContactInfo
+    quic_tpu_addr: Option<SocketAddr>,
+    /// The shred version of this node's cluster, re-pushed along with its ContactInfo. 0 until
+    /// the node knows its genesis block, and until then it takes any node for a peer.
+    shred_version: u16,
 }
 
 #[derive(Default, Clone)]
@@ -151,6 +179,55 @@ impl Signable for PruneData {
     }
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RepairRequest {
+    /// Contact info of the node that sent this request
+    pub from: ContactInfo,
+    /// The Pubkey of the node the request was sent to
+    pub recipient: Pubkey,
+    /// The blobs requested
+    pub repair_type: RepairType,
+    /// Echoed after the blobs of the response, to match them to this request
+    pub nonce: Nonce,
+    /// Wallclock of the node that generated this request
+    pub wallclock: u64,
+    /// Signature of this request, by `from`
+    pub signature: Signature,
+}
+
+impl Signable for RepairRequest {
+    fn pubkey(&self) -> Pubkey {
+        self.from.id
+    }
+
+    fn signable_data(&self) -> Cow<[u8]> {
+        #[derive(Serialize)]
+        struct SignData<'a> {
+            from: &'a ContactInfo,
+            recipient: Pubkey,
+            repair_type: RepairType,
+            nonce: Nonce,
+            wallclock: u64,
+        }
+        let data = SignData {
+            from: &self.from,
+            recipient: self.recipient,
+            repair_type: self.repair_type,
+            nonce: self.nonce,
+            wallclock: self.wallclock,
+        };
+        Cow::Owned(serialize(&data).expect("serialize RepairRequest"))
+    }
+
+    fn get_signature(&self) -> Signature {
+        self.signature
+    }
+
+    fn set_signature(&mut self, signature: Signature) {
+        self.signature = signature
+    }
+}
+
 // TODO These messages should go through the gpu pipeline for spam filtering
 #[derive(Serialize, Deserialize, Debug)]
 #[allow(clippy::large_enum_variant)]
@@ -163,9 +240,17 @@ enum Protocol {
 
     /// Window protocol messages
     /// TODO: move this message to a different module
-    RequestWindowIndex(ContactInfo, u64, u64),
-    RequestHighestWindowIndex(ContactInfo, u64, u64),
-    RequestOrphan(ContactInfo, u64),
+    RepairRequest(RepairRequest),
+
+    /// NAT traversal messages
+    /// The entrypoint's signed advice: the address it observed the receiver's pull requests
+    /// coming from, or an introduction to a peer
+    EntrypointMessage(EntrypointMessage),
+    /// Ask the entrypoint to introduce the sender (first) to a peer (second)
+    HolePunchRequest(Pubkey, Pubkey),
+    /// Sent by both sides of an introduction to open a path through their NATs, with the
+    /// introduction's token
+    HolePunch(Pubkey, u64),
 }
 
 impl ClusterInfo {
@@ -179,6 +264,13 @@ impl ClusterInfo {
             gossip: CrdsGossip::default(),
             keypair,
             entrypoint: None,
+            nat_traversal: NatTraversal::default(),
+            partition_cfg: None,
+            repaired_blobs: Arc::new(AtomicUsize::new(0)),
+            faucet_addr: None,
+            trusted_faucets: HashSet::new(),
+            quic_tpu_addr: None,
+            shred_version: 0,
         };
         let id = contact_info.id;
         me.gossip.set_self(&id);
@@ -201,9 +293,18 @@ impl ClusterInfo {
         my_data.wallclock = now;
         let mut entry = CrdsValue::ContactInfo(my_data);
         entry.sign(&self.keypair);
+        let mut entries = vec![entry];
+        if let Some(addr) = self.faucet_addr {
+            entries.push(self.new_faucet_entry(addr, now));
+        }
+        if let Some(addr) = self.quic_tpu_addr {
+            entries.push(self.new_quic_tpu_entry(addr, now));
+        }
+        if self.shred_version != 0 {
+            entries.push(self.new_version_entry(now));
+        }
         self.gossip.refresh_push_active_set(stakes);
-        self.gossip
-            .process_push_message(&self.id(), vec![entry], now);
+        self.gossip.process_push_message(&self.id(), entries, now);
     }
 
     // TODO kill insert_info, only used by tests
@@ -217,6 +318,30 @@ impl ClusterInfo {
         self.entrypoint = Some(entrypoint)
     }
 
+    pub fn set_partition_cfg(&mut self, partition_cfg: PartitionCfg) {
+        self.partition_cfg = Some(partition_cfg)
+    }
+
+    /// The number of blobs this node has sent to peers that were missing them, a measure of how
+    /// many of the blobs it broadcasts are lost
+    pub fn repaired_blobs(&self) -> usize {
+        self.repaired_blobs.load(Ordering::Relaxed)
+    }
+
+    /// The nodes this node can reach while a test partitions the cluster, None if it can reach
+    /// every node
+    fn reachable_nodes(&self) -> Option<HashSet<Pubkey>> {
+        self.partition_cfg
+            .as_ref()
+            .and_then(|partition_cfg| partition_cfg.reachable_nodes(&self.id(), timestamp()))
+    }
+
+    fn is_reachable(reachable_nodes: &Option<HashSet<Pubkey>>, pubkey: &Pubkey) -> bool {
+        reachable_nodes
+            .as_ref()
+            .map_or(true, |reachable_nodes| reachable_nodes.contains(pubkey))
+    }
+
     pub fn id(&self) -> Pubkey {
         self.gossip.id
     }
@@ -306,6 +431,225 @@ impl ClusterInfo {
             .process_push_message(&self.id(), vec![entry], now);
     }
 
+    /// Advertise the address of a drone that serves airdrops for this cluster, for as long as
+    /// this node runs
+    pub fn push_faucet(&mut self, addr: SocketAddr) {
+        if !ContactInfo::is_valid_address(&addr) {
+            warn!("Not advertising invalid drone address {}", addr);
+            return;
+        }
+        self.faucet_addr = Some(addr);
+        let now = timestamp();
+        let entry = self.new_faucet_entry(addr, now);
+        self.gossip
+            .process_push_message(&self.id(), vec![entry], now);
+    }
+
+    fn new_faucet_entry(&self, addr: SocketAddr, now: u64) -> CrdsValue {
+        let mut entry = CrdsValue::Faucet(Faucet::new(&self.id(), addr, now));
+        entry.sign(&self.keypair);
+        entry
+    }
+
+    /// Use the drones advertised by these nodes, as well as those of this node and the entrypoint
+    pub fn set_trusted_faucets(&mut self, trusted_faucets: HashSet<Pubkey>) {
+        self.trusted_faucets = trusted_faucets;
+    }
+
+    /// The most recently advertised drone address, if this node, the entrypoint, or a trusted
+    /// node has gossiped one. Any node can advertise a drone, so those of others are ignored.
+    pub fn get_faucet_addr(&self) -> Option<SocketAddr> {
+        let me = self.id();
+        let entrypoint_id = self.entrypoint_id();
+        self.gossip
+            .crds
+            .table
+            .values()
+            .filter_map(|x| x.value.faucet())
+            .filter(|faucet| {
+                faucet.from == me
+                    || Some(faucet.from) == entrypoint_id
+                    || self.trusted_faucets.contains(&faucet.from)
+            })
+            .filter(|faucet| ContactInfo::is_valid_address(&faucet.addr))
+            .max_by_key(|faucet| faucet.wallclock)
+            .map(|faucet| faucet.addr)
+    }
+
+    /// Advertise the address of this node's QUIC TPU, for as long as this node runs
+    pub fn push_quic_tpu(&mut self, addr: SocketAddr) {
+        if !ContactInfo::is_valid_address(&addr) {
+            warn!("Not advertising invalid QUIC TPU address {}", addr);
+            return;
+        }
+        self.quic_tpu_addr = Some(addr);
+        let now = timestamp();
+        let entry = self.new_quic_tpu_entry(addr, now);
+        self.gossip
+            .process_push_message(&self.id(), vec![entry], now);
+    }
+
+    fn new_quic_tpu_entry(&self, addr: SocketAddr, now: u64) -> CrdsValue {
+        let mut entry = CrdsValue::QuicTpu(QuicTpu::new(&self.id(), addr, now));
+        entry.sign(&self.keypair);
+        entry
+    }
+
+    /// The QUIC TPU address `pubkey` advertises, if it accepts transactions over QUIC. Its
+    /// certificate carries `pubkey`, see `QuicTpuClient`.
+    pub fn get_quic_tpu_addr(&self, pubkey: &Pubkey) -> Option<SocketAddr> {
+        self.gossip
+            .crds
+            .lookup(&CrdsValueLabel::QuicTpu(*pubkey))
+            .and_then(CrdsValue::quic_tpu)
+            .map(|quic_tpu| quic_tpu.addr)
+            .filter(ContactInfo::is_valid_address)
+    }
+
+    /// Advertise the shred version of this node's cluster, see `compute_shred_version`. From
+    /// then on this node only gossips and repairs with nodes that advertise the same version.
+    pub fn set_shred_version(&mut self, shred_version: u16) {
+        self.shred_version = shred_version;
+        if shred_version == 0 {
+            return;
+        }
+        let now = timestamp();
+        let entry = self.new_version_entry(now);
+        self.gossip
+            .process_push_message(&self.id(), vec![entry], now);
+    }
+
+    fn new_version_entry(&self, now: u64) -> CrdsValue {
+        let feature_set = compute_feature_set();
+        let version = NodeVersion::new(&self.id(), self.shred_version, feature_set, now);
+        let mut entry = CrdsValue::Version(version);
+        entry.sign(&self.keypair);
+        entry
+    }
+
+    /// The cluster and software `pubkey` advertises
+    pub fn get_node_version(&self, pubkey: &Pubkey) -> Option<NodeVersion> {
+        self.gossip
+            .crds
+            .lookup(&CrdsValueLabel::Version(*pubkey))
+            .and_then(CrdsValue::version)
+            .cloned()
+    }
+
+    /// Whether `pubkey` is known to be of this node's cluster. A node that doesn't advertise its
+    /// version yet isn't, unless this node doesn't know its own version either.
+    fn is_same_cluster(&self, pubkey: &Pubkey) -> bool {
+        self.shred_version == 0
+            || *pubkey == self.id()
+            || self
+                .get_node_version(pubkey)
+                .map_or(false, |version| version.shred_version == self.shred_version)
+    }
+
+    /// Advertise how many of this node's leader slots up to `root` were rooted or skipped
+    pub fn push_leader_slots(&mut self, root: u64, totals: LeaderSlotTotals) {
+        let now = timestamp();
+        let mut entry = CrdsValue::LeaderSlots(LeaderSlots::new(&self.id(), root, totals, now));
+        entry.sign(&self.keypair);
+        self.gossip
+            .process_push_message(&self.id(), vec![entry], now);
+    }
+
+    /// The root and leader slot totals most recently gossiped by `pubkey`
+    pub fn get_leader_slots(&self, pubkey: &Pubkey) -> Option<(u64, LeaderSlotTotals)> {
+        self.gossip
+            .crds
+            .lookup(&CrdsValueLabel::LeaderSlots(*pubkey))
+            .and_then(CrdsValue::leader_slots)
+            .map(|slots| (slots.root, slots.totals))
+    }
+
+    /// How reachable `pubkey` most recently gossiped its gossip address is
+    pub fn get_reachability(&self, pubkey: &Pubkey) -> Reachability {
+        self.gossip
+            .crds
+            .lookup(&CrdsValueLabel::Reachability(*pubkey))
+            .and_then(CrdsValue::reachability)
+            .map_or(Reachability::Unknown, |reachability| {
+                reachability.reachability
+            })
+    }
+
+    /// Pushes to a pull-only node would be dropped by its NAT
+    fn accepts_push(&self, pubkey: &Pubkey) -> bool {
+        self.get_reachability(pubkey) != Reachability::PullOnly
+    }
+
+    /// Gossip this node's hashes of its recent epoch boundary slots, as (slot, hash)
+    pub fn push_accounts_hashes(&mut self, hashes: Vec<(u64, Hash)>) {
+        let now = timestamp();
+        let mut entry = CrdsValue::AccountsHashes(AccountsHashes::new(&self.id(), hashes, now));
+        entry.sign(&self.keypair);
+        self.gossip
+            .process_push_message(&self.id(), vec![entry], now);
+    }
+
+    /// The epoch boundary slot hashes most recently gossiped by `pubkey`
+    pub fn get_accounts_hashes(&self, pubkey: &Pubkey) -> Option<&[(u64, Hash)]> {
+        self.gossip
+            .crds
+            .lookup(&CrdsValueLabel::AccountsHashes(*pubkey))
+            .and_then(CrdsValue::accounts_hashes)
+            .map(|hashes| &hashes.hashes[..])
+    }
+
+    /// Gossip a proof that the leader of `slot` signed conflicting blobs
+    pub fn push_duplicate_slot(&mut self, slot: u64, proof: &DuplicateSlotProof) {
+        let now = timestamp();
+        let chunks = match DuplicateShred::from_proof(self.id(), slot, proof, now) {
+            Some(chunks) => chunks,
+            None => {
+                warn!(
+                    "duplicate slot proof for slot {} is too large to gossip",
+                    slot
+                );
+                return;
+            }
+        };
+        let entries = chunks
+            .into_iter()
+            .map(|chunk| {
+                let mut entry = CrdsValue::DuplicateShred(chunk);
+                entry.sign(&self.keypair);
+                entry
+            })
+            .collect();
+        self.gossip.process_push_message(&self.id(), entries, now);
+    }
+
+    /// Reassemble the duplicate slot proofs gossiped by each node
+    /// * return - (reporting node, slot, proof) for every complete proof in the crds
+    pub fn get_duplicate_slot_proofs(&self) -> Vec<(Pubkey, u64, DuplicateSlotProof)> {
+        let mut chunks: HashMap<Pubkey, Vec<&DuplicateShred>> = HashMap::new();
+        for shred in self
+            .gossip
+            .crds
+            .table
+            .values()
+            .filter_map(|x| x.value.duplicate_shred())
+        {
+            chunks.entry(shred.from).or_default().push(shred);
+        }
+        chunks
+            .into_iter()
+            .filter_map(|(from, shreds)| {
+                // Chunks of an older, longer proof may linger under the higher chunk indexes
+                let latest = shreds.iter().map(|shred| shred.wallclock).max()?;
+                let shreds: Vec<_> = shreds
+                    .into_iter()
+                    .filter(|shred| shred.wallclock == latest)
+                    .collect();
+                let slot = shreds[0].slot;
+                DuplicateShred::into_proof(&shreds).map(|proof| (from, slot, proof))
+            })
+            .collect()
+    }
+
     /// Get votes in the crds
     /// * since - The timestamp of when the vote inserted must be greater than
     /// since. This allows the bank to query for new votes only.
@@ -329,6 +673,21 @@ impl ClusterInfo {
         (txs, max_ts)
     }
 
+    /// The roots other nodes gossiped in their epoch slots within the last `max_age_ms`
+    pub fn get_recent_peer_roots(&self, max_age_ms: u64) -> Vec<(Pubkey, u64)> {
+        let now = timestamp();
+        let my_id = self.id();
+        self.gossip
+            .crds
+            .table
+            .values()
+            .filter(|x| x.insert_timestamp.saturating_add(max_age_ms) >= now)
+            .filter_map(|x| x.value.epoch_slots())
+            .filter(|epoch_slots| epoch_slots.from != my_id)
+            .map(|epoch_slots| (epoch_slots.from, epoch_slots.root))
+            .collect()
+    }
+
     pub fn get_epoch_state_for_node(
         &self,
         pubkey: &Pubkey,
@@ -380,6 +739,7 @@ impl ClusterInfo {
             .filter_map(|x| x.value.contact_info())
             .filter(|x| x.id != me)
             .filter(|x| ContactInfo::is_valid_address(&x.rpc))
+            .filter(|x| self.is_same_cluster(&x.id))
             .cloned()
             .collect()
     }
@@ -407,6 +767,7 @@ impl ClusterInfo {
             .filter_map(|x| x.value.contact_info())
             .filter(|x| x.id != me)
             .filter(|x| ContactInfo::is_valid_address(&x.gossip))
+            .filter(|x| self.is_same_cluster(&x.id))
             .cloned()
             .collect()
     }
@@ -414,6 +775,7 @@ impl ClusterInfo {
     /// all peers that have a valid tvu port.
     pub fn tvu_peers(&self) -> Vec<ContactInfo> {
         let me = self.my_data().id;
+        let reachable_nodes = self.reachable_nodes();
         self.gossip
             .crds
             .table
@@ -421,6 +783,8 @@ impl ClusterInfo {
             .filter_map(|x| x.value.contact_info())
             .filter(|x| ContactInfo::is_valid_address(&x.tvu))
             .filter(|x| x.id != me)
+            .filter(|x| self.is_same_cluster(&x.id))
+            .filter(|x| Self::is_reachable(&reachable_nodes, &x.id))
             .cloned()
             .collect()
     }
@@ -435,6 +799,7 @@ impl ClusterInfo {
             .filter_map(|x| x.value.contact_info())
             .filter(|x| ContactInfo::is_valid_address(&x.storage_addr))
             .filter(|x| x.id != me)
+            .filter(|x| self.is_same_cluster(&x.id))
             .cloned()
             .collect()
     }
@@ -442,6 +807,7 @@ impl ClusterInfo {
     /// all peers that have a valid tvu
     pub fn retransmit_peers(&self) -> Vec<ContactInfo> {
         let me = self.my_data().id;
+        let reachable_nodes = self.reachable_nodes();
         self.gossip
             .crds
             .table
@@ -449,6 +815,8 @@ impl ClusterInfo {
             .filter_map(|x| x.value.contact_info())
             .filter(|x| x.id != me)
             .filter(|x| ContactInfo::is_valid_address(&x.tvu))
+            .filter(|x| self.is_same_cluster(&x.id))
+            .filter(|x| Self::is_reachable(&reachable_nodes, &x.id))
             .cloned()
             .collect()
     }
@@ -554,6 +922,7 @@ impl ClusterInfo {
             .filter_map(|x| x.value.contact_info())
             .filter(|x| x.id != me)
             .filter(|x| ContactInfo::is_valid_address(&x.tpu))
+            .filter(|x| self.is_same_cluster(&x.id))
             .cloned()
             .collect()
     }
@@ -774,25 +1143,30 @@ impl ClusterInfo {
         Ok(())
     }
 
-    pub fn window_index_request_bytes(&self, slot: u64, blob_index: u64) -> Result<Vec<u8>> {
-        let req = Protocol::RequestWindowIndex(self.my_data().clone(), slot, blob_index);
-        let out = serialize(&req)?;
-        Ok(out)
-    }
-
-    fn window_highest_index_request_bytes(&self, slot: u64, blob_index: u64) -> Result<Vec<u8>> {
-        let req = Protocol::RequestHighestWindowIndex(self.my_data().clone(), slot, blob_index);
-        let out = serialize(&req)?;
-        Ok(out)
-    }
-
-    fn orphan_bytes(&self, slot: u64) -> Result<Vec<u8>> {
-        let req = Protocol::RequestOrphan(self.my_data().clone(), slot);
-        let out = serialize(&req)?;
+    fn repair_request_bytes(
+        &self,
+        repair_type: &RepairType,
+        recipient: &Pubkey,
+        nonce: Nonce,
+    ) -> Result<Vec<u8>> {
+        let mut request = RepairRequest {
+            from: self.my_data(),
+            recipient: *recipient,
+            repair_type: *repair_type,
+            nonce,
+            wallclock: timestamp(),
+            signature: Signature::default(),
+        };
+        request.sign(&self.keypair);
+        let out = serialize(&Protocol::RepairRequest(request))?;
         Ok(out)
     }
 
-    pub fn repair_request(&self, repair_request: &RepairType) -> Result<(SocketAddr, Vec<u8>)> {
+    pub fn repair_request(
+        &self,
+        repair_request: &RepairType,
+        nonce: Nonce,
+    ) -> Result<(SocketAddr, Vec<u8>)> {
         // find a peer that appears to be accepting replication, as indicated
         //  by a valid tvu port location
         let valid: Vec<_> = self.repair_peers();
@@ -801,11 +1175,55 @@ impl ClusterInfo {
         }
         let n = thread_rng().gen::<usize>() % valid.len();
         let addr = valid[n].gossip; // send the request to the peer's gossip port
-        let out = self.map_repair_request(repair_request)?;
+        let out = self.map_repair_request(repair_request, &valid[n].id, nonce)?;
 
         Ok((addr, out))
     }
-    pub fn map_repair_request(&self, repair_request: &RepairType) -> Result<Vec<u8>> {
+
+    /// Repair peers that gossiped a root at or past `slot`, so should have every slot up to it
+    /// unless they cleaned it up
+    fn archival_repair_peers(&self, slot: u64) -> Vec<ContactInfo> {
+        let rooted: HashSet<Pubkey> = self
+            .gossip
+            .crds
+            .table
+            .values()
+            .filter_map(|x| x.value.epoch_slots())
+            .filter(|epoch_slots| epoch_slots.root >= slot)
+            .map(|epoch_slots| epoch_slots.from)
+            .collect();
+        self.repair_peers()
+            .into_iter()
+            .filter(|x| rooted.contains(&x.id))
+            .collect()
+    }
+
+    /// Like `repair_request`, but sent to a peer that rooted all of `slot_range` if there is
+    /// one, so that a replicator downloading an old segment asks nodes that have it
+    pub fn repair_request_in_range(
+        &self,
+        repair_request: &RepairType,
+        slot_range: &SlotRange,
+        nonce: Nonce,
+    ) -> Result<(SocketAddr, Vec<u8>)> {
+        let archival = self.archival_repair_peers(slot_range.end);
+        if archival.is_empty() {
+            return self.repair_request(repair_request, nonce);
+        }
+        let n = thread_rng().gen::<usize>() % archival.len();
+        let addr = archival[n].gossip;
+        let out = self.map_repair_request(repair_request, &archival[n].id, nonce)?;
+
+        Ok((addr, out))
+    }
+
+    /// The signed request for `repair_request` to send to `recipient`
+    pub fn map_repair_request(
+        &self,
+        repair_request: &RepairType,
+        recipient: &Pubkey,
+        nonce: Nonce,
+    ) -> Result<Vec<u8>> {
         match repair_request {
             RepairType::Blob(slot, blob_index) => {
                 datapoint_debug!(
@@ -813,7 +1231,7 @@ impl ClusterInfo {
                     ("repair-slot", *slot, i64),
                     ("repair-ix", *blob_index, i64)
                 );
-                Ok(self.window_index_request_bytes(*slot, *blob_index)?)
+                self.repair_request_bytes(repair_request, recipient, nonce)
             }
             RepairType::HighestBlob(slot, blob_index) => {
                 datapoint_debug!(
@@ -821,11 +1239,11 @@ impl ClusterInfo {
                     ("repair-highest-slot", *slot, i64),
                     ("repair-highest-ix", *blob_index, i64)
                 );
-                Ok(self.window_highest_index_request_bytes(*slot, *blob_index)?)
+                self.repair_request_bytes(repair_request, recipient, nonce)
             }
             RepairType::Orphan(slot) => {
                 datapoint_debug!("cluster_info-repair_orphan", ("repair-orphan", *slot, i64));
-                Ok(self.orphan_bytes(*slot)?)
+                self.repair_request_bytes(repair_request, recipient, nonce)
             }
         }
     }
@@ -867,13 +1285,15 @@ impl ClusterInfo {
                     .crds
                     .lookup(&peer_label)
                     .and_then(CrdsValue::contact_info)
-                    .map(|peer_info| (peer, filter, peer_info.gossip, self_info))
+                    .map(|peer_info| (peer, filter, self.gossip_addr(peer_info), self_info))
             })
             .collect();
         if pr.is_empty() {
             self.add_entrypoint(&mut pr);
         }
+        let reachable_nodes = self.reachable_nodes();
         pr.into_iter()
+            .filter(|(peer, _, _, _)| Self::is_reachable(&reachable_nodes, peer))
             .map(|(peer, filter, gossip, self_info)| {
                 self.gossip.mark_pull_request_creation_time(&peer, now);
                 (gossip, Protocol::PullRequest(filter, self_info))
@@ -883,24 +1303,90 @@ impl ClusterInfo {
     fn new_push_requests(&mut self) -> Vec<(SocketAddr, Protocol)> {
         let self_id = self.gossip.id;
         let (_, push_messages) = self.gossip.new_push_messages(timestamp());
+        let reachable_nodes = self.reachable_nodes();
         push_messages
             .into_iter()
+            .filter(|(peer, _)| Self::is_reachable(&reachable_nodes, peer))
             .filter_map(|(peer, messages)| {
                 let peer_label = CrdsValueLabel::ContactInfo(peer);
                 self.gossip
                     .crds
                     .lookup(&peer_label)
                     .and_then(CrdsValue::contact_info)
-                    .map(|p| (p.gossip, messages))
+                    .filter(|p| self.accepts_push(&p.id))
+                    .map(|p| (self.gossip_addr(p), messages))
             })
             .map(|(peer, msgs)| (peer, Protocol::PushMessage(self_id, msgs)))
             .collect()
     }
 
+    /// The address to gossip with `peer` at, which differs from its advertised gossip address
+    /// if a hole was punched to it
+    fn gossip_addr(&self, peer: &ContactInfo) -> SocketAddr {
+        self.nat_traversal
+            .punched_addr(&peer.id)
+            .unwrap_or(peer.gossip)
+    }
+
+    fn new_hole_punch_requests(&mut self) -> Vec<(SocketAddr, Protocol)> {
+        let entrypoint = match &self.entrypoint {
+            Some(entrypoint) => entrypoint.gossip,
+            None => return vec![],
+        };
+        let self_id = self.id();
+        let now = timestamp();
+        self.nat_traversal.purge(now);
+        let my_data = self.my_data();
+        let peers: Vec<_> = self
+            .gossip_peers()
+            .into_iter()
+            .filter(|peer| peer.gossip != entrypoint)
+            .collect();
+        let requests = self
+            .nat_traversal
+            .new_hole_punch_requests(&my_data, &peers, now)
+            .into_iter()
+            .map(|peer| (entrypoint, Protocol::HolePunchRequest(self_id, peer)));
+        let punches = self
+            .nat_traversal
+            .hole_punch_addrs()
+            .into_iter()
+            .map(|(addr, token)| (addr, Protocol::HolePunch(self_id, token)));
+        let reqs = requests.chain(punches).collect();
+        self.update_reachability();
+        reqs
+    }
+
+    /// Advertise how reachable this node is, if that changed
+    fn update_reachability(&mut self) {
+        let self_id = self.id();
+        let current = self.get_reachability(&self_id);
+        let reachability = self.nat_traversal.reachability(&self.my_data());
+        if reachability != current {
+            info!(
+                "{}: gossip reachability changed from {:?} to {:?}",
+                self_id, current, reachability
+            );
+            datapoint_info!(
+                "cluster_info-reachability",
+                ("reachability", format!("{:?}", reachability), String)
+            );
+            let now = timestamp();
+            let mut entry =
+                CrdsValue::Reachability(NodeReachability::new(&self_id, reachability, now));
+            entry.sign(&self.keypair);
+            self.gossip.process_push_message(&self_id, vec![entry], now);
+        }
+    }
+
     fn gossip_request(&mut self, stakes: &HashMap<Pubkey, u64>) -> Vec<(SocketAddr, Protocol)> {
         let pulls: Vec<_> = self.new_pull_requests(stakes);
         let pushes: Vec<_> = self.new_push_requests();
-        vec![pulls, pushes].into_iter().flat_map(|x| x).collect()
+        let hole_punches: Vec<_> = self.new_hole_punch_requests();
+        vec![pulls, pushes, hole_punches]
+            .into_iter()
+            .flat_map(|x| x)
+            .collect()
     }
 
     /// At random pick a node and try to get updated changes from them
@@ -1082,29 +1568,184 @@ impl ClusterInfo {
             return vec![];
         }
         let now = timestamp();
-        let data = me
-            .write()
-            .unwrap()
-            .gossip
-            .process_pull_request(caller, filter, now);
+        let from_id = from.id;
+        let mut me = me.write().unwrap();
+        let observed = me.nat_traversal.observe(&from_id, *from_addr, now);
+        // A node that doesn't advertise its version yet is answered, so it can find its peers,
+        // but it isn't gossiped with until it's known to be of this cluster
+        let data = if me.is_same_cluster(&from_id) {
+            me.gossip.process_pull_request(caller, filter, now)
+        } else {
+            inc_new_counter_debug!("cluster_info-pull_request-unknown_version", 1);
+            me.gossip.filter_pull_request(filter)
+        };
         let len = data.len();
         trace!("get updates since response {}", len);
         let rsp = Protocol::PullResponse(self_id, data);
         // The remote node may not know its public IP:PORT. Instead of responding to the caller's
         // gossip addr, respond to the origin addr.
         inc_new_counter_debug!("cluster_info-pull_request-rsp", len);
-        to_shared_blob(rsp, *from_addr).ok().into_iter().collect()
+        let mut rsps: Vec<_> = to_shared_blob(rsp, *from_addr).ok().into_iter().collect();
+        if observed {
+            // Also tell it what that origin addr is, so it can tell if it's behind a NAT
+            let advice = NatAdvice::ObservedAddr(*from_addr);
+            let message = me.entrypoint_message(&from_id, advice, now);
+            rsps.extend(to_shared_blob(message, *from_addr).ok());
+        }
+        rsps
+    }
+
+    /// The identity of the entrypoint, once its contact info has been gossiped
+    fn entrypoint_id(&self) -> Option<Pubkey> {
+        let entrypoint = self.entrypoint.as_ref()?;
+        if entrypoint.id != Pubkey::default() {
+            return Some(entrypoint.id);
+        }
+        self.gossip
+            .crds
+            .table
+            .values()
+            .filter_map(|v| v.value.contact_info())
+            .find(|info| info.gossip == entrypoint.gossip)
+            .map(|info| info.id)
+    }
+
+    /// A node that doesn't know its genesis block, like a replicator, joins the cluster of its
+    /// entrypoint once the entrypoint's version is gossiped
+    fn adopt_entrypoint_version(&mut self) {
+        if self.shred_version != 0 {
+            return;
+        }
+        let version = self
+            .entrypoint_id()
+            .and_then(|entrypoint_id| self.get_node_version(&entrypoint_id));
+        if let Some(version) = version {
+            info!(
+                "Joining the cluster of shred version {}",
+                version.shred_version
+            );
+            self.set_shred_version(version.shred_version);
+        }
+    }
+
+    /// `advice` for `to`, signed as this node
+    fn entrypoint_message(&self, to: &Pubkey, advice: NatAdvice, now: u64) -> Protocol {
+        let mut message = EntrypointMessage::new(&self.id(), to, advice, now);
+        message.sign(&self.keypair);
+        Protocol::EntrypointMessage(message)
+    }
+
+    fn handle_nat_traversal(
+        me: &Arc<RwLock<Self>>,
+        from_addr: &SocketAddr,
+        request: Protocol,
+    ) -> Vec<SharedBlob> {
+        let mut me = me.write().unwrap();
+        let self_id = me.id();
+        let now = timestamp();
+        let rsps = match request {
+            Protocol::EntrypointMessage(message) => {
+                let from_entrypoint = me.entrypoint_id().map_or(false, |entrypoint| {
+                    message.is_valid(&entrypoint, &self_id, now)
+                });
+                if !from_entrypoint {
+                    inc_new_counter_debug!("cluster_info-entrypoint_message-invalid", 1);
+                    return vec![];
+                }
+                match message.advice {
+                    NatAdvice::ObservedAddr(addr) => {
+                        me.nat_traversal.set_my_observed_addr(addr);
+                        vec![]
+                    }
+                    NatAdvice::Introduction(peer, addr, token) => {
+                        if peer == self_id {
+                            return vec![];
+                        }
+                        me.nat_traversal.introduce(&peer, addr, token, now);
+                        vec![(addr, Protocol::HolePunch(self_id, token))]
+                    }
+                }
+            }
+            Protocol::HolePunchRequest(from, peer) => {
+                // Only introduce a node from the address its signed pull requests came from, so
+                // that the request can't be spoofed to send peers elsewhere
+                if me.nat_traversal.observed_addr(&from) != Some(*from_addr) {
+                    inc_new_counter_debug!("cluster_info-hole_punch_request-unobserved", 1);
+                    return vec![];
+                }
+                let peer_addr = me
+                    .nat_traversal
+                    .observed_addr(&peer)
+                    .or_else(|| me.lookup(&peer).map(|peer| peer.gossip))
+                    .filter(ContactInfo::is_valid_address);
+                match peer_addr {
+                    Some(peer_addr) => {
+                        inc_new_counter_debug!("cluster_info-hole_punch_introduction", 1);
+                        let token = thread_rng().gen();
+                        let to_node = NatAdvice::Introduction(peer, peer_addr, token);
+                        let to_peer = NatAdvice::Introduction(from, *from_addr, token);
+                        vec![
+                            (*from_addr, me.entrypoint_message(&from, to_node, now)),
+                            (peer_addr, me.entrypoint_message(&peer, to_peer, now)),
+                        ]
+                    }
+                    None => vec![],
+                }
+            }
+            Protocol::HolePunch(peer, token) => {
+                if me.nat_traversal.hole_punched(&peer, *from_addr, token) {
+                    inc_new_counter_debug!("cluster_info-hole_punched", 1);
+                    vec![(*from_addr, Protocol::HolePunch(self_id, token))]
+                } else {
+                    vec![]
+                }
+            }
+            _ => panic!("Not a NAT traversal message"),
+        };
+        me.update_reachability();
+        rsps.into_iter()
+            .filter_map(|(addr, rsp)| to_shared_blob(rsp, addr).ok())
+            .collect()
     }
 
-    fn handle_pull_response(me: &Arc<RwLock<Self>>, from: &Pubkey, data: Vec<CrdsValue>) {
+    /// Drops the values of nodes not known to be of this node's cluster from `data`, so they
+    /// aren't gossiped with. A node is known by its version, which may come in `data` itself.
+    fn retain_same_cluster(&self, data: &mut Vec<CrdsValue>) {
+        if self.shred_version == 0 {
+            return;
+        }
+        let same_cluster: HashSet<Pubkey> = data
+            .iter()
+            .filter_map(CrdsValue::version)
+            .filter(|version| version.shred_version == self.shred_version)
+            .map(|version| version.from)
+            .collect();
         let len = data.len();
+        data.retain(|value| {
+            let origin = value.label().pubkey();
+            match value.version() {
+                Some(_) => same_cluster.contains(&origin),
+                None => same_cluster.contains(&origin) || self.is_same_cluster(&origin),
+            }
+        });
+        inc_new_counter_debug!(
+            "cluster_info-gossip-shred_version_mismatch",
+            len - data.len()
+        );
+    }
+
+    fn handle_pull_response(me: &Arc<RwLock<Self>>, from: &Pubkey, mut data: Vec<CrdsValue>) {
         let now = Instant::now();
         let self_id = me.read().unwrap().gossip.id;
-        trace!("PullResponse me: {} from: {} len={}", self_id, from, len);
-        me.write()
-            .unwrap()
-            .gossip
-            .process_pull_response(from, data, timestamp());
+        let len = {
+            let mut me = me.write().unwrap();
+            me.retain_same_cluster(&mut data);
+            let len = data.len();
+            trace!("PullResponse me: {} from: {} len={}", self_id, from, len);
+            me.gossip.process_pull_response(from, data, timestamp());
+            me.adopt_entrypoint_version();
+            len
+        };
         inc_new_counter_debug!("cluster_info-pull_request_response", 1);
         inc_new_counter_debug!("cluster_info-pull_request_response-size", len);
 
@@ -1114,17 +1755,17 @@ impl ClusterInfo {
     fn handle_push_message(
         me: &Arc<RwLock<Self>>,
         from: &Pubkey,
-        data: Vec<CrdsValue>,
+        mut data: Vec<CrdsValue>,
         stakes: &HashMap<Pubkey, u64>,
     ) -> Vec<SharedBlob> {
         let self_id = me.read().unwrap().gossip.id;
         inc_new_counter_debug!("cluster_info-push_message", 1, 0, 1000);
 
-        let updated: Vec<_> =
-            me.write()
-                .unwrap()
-                .gossip
-                .process_push_message(from, data, timestamp());
+        let updated: Vec<_> = {
+            let mut me = me.write().unwrap();
+            me.retain_same_cluster(&mut data);
+            me.gossip.process_push_message(from, data, timestamp())
+        };
 
         let updated_labels: Vec<_> = updated.into_iter().map(|u| u.value.label()).collect();
         let prunes_map: HashMap<Pubkey, HashSet<Pubkey>> = me
@@ -1169,30 +1810,20 @@ impl ClusterInfo {
         }
     }
 
-    fn get_repair_sender(request: &Protocol) -> &ContactInfo {
-        match request {
-            Protocol::RequestWindowIndex(ref from, _, _) => from,
-            Protocol::RequestHighestWindowIndex(ref from, _, _) => from,
-            Protocol::RequestOrphan(ref from, _) => from,
-            _ => panic!("Not a repair request"),
-        }
-    }
-
     fn handle_repair(
         me: &Arc<RwLock<Self>>,
         from_addr: &SocketAddr,
         blocktree: Option<&Arc<Blocktree>>,
-        request: Protocol,
+        request: RepairRequest,
     ) -> Vec<SharedBlob> {
         let now = Instant::now();
 
         //TODO this doesn't depend on cluster_info module, could be moved
         //but we are using the listen thread to service these request
-        //TODO verify from is signed
 
         let self_id = me.read().unwrap().gossip.id;
-        let from = Self::get_repair_sender(&request);
-        if from.id == me.read().unwrap().gossip.id {
+        let from = &request.from;
+        if from.id == self_id {
             warn!(
                 "{}: Ignored received repair request from ME {}",
                 self_id, from.id,
@@ -1200,53 +1831,73 @@ impl ClusterInfo {
             inc_new_counter_debug!("cluster_info-handle-repair--eq", 1);
             return vec![];
         }
+        // A request meant for another node, or an old one, may be replayed to make this node
+        // send blobs somewhere
+        if request.recipient != self_id {
+            inc_new_counter_debug!("cluster_info-handle-repair-wrong_recipient", 1);
+            return vec![];
+        }
+        let wallclock = timestamp();
+        if request.wallclock + MAX_REPAIR_REQUEST_AGE_MS < wallclock
+            || request.wallclock > wallclock + MAX_REPAIR_REQUEST_AGE_MS
+        {
+            inc_new_counter_debug!("cluster_info-handle-repair-expired", 1);
+            return vec![];
+        }
+        if !me.read().unwrap().is_same_cluster(&from.id) {
+            inc_new_counter_debug!("cluster_info-handle-repair-shred_version_mismatch", 1);
+            return vec![];
+        }
 
         me.write()
             .unwrap()
             .gossip
             .crds
-            .update_record_timestamp(&from.id, timestamp());
-        let my_info = me.read().unwrap().my_data().clone();
+            .update_record_timestamp(&from.id, wallclock);
+        let my_info = me.read().unwrap().my_data();
 
         let (res, label) = {
-            match &request {
-                Protocol::RequestWindowIndex(from, slot, blob_index) => {
+            match request.repair_type {
+                RepairType::Blob(slot, blob_index) => {
                     inc_new_counter_debug!("cluster_info-request-window-index", 1);
                     (
                         Self::run_window_request(
-                            from,
-                            &from_addr,
-                            blocktree,
-                            &my_info,
-                            *slot,
-                            *blob_index,
+                            from, &from_addr, blocktree, &my_info, slot, blob_index,
                         ),
                         "RequestWindowIndex",
                     )
                 }
 
-                Protocol::RequestHighestWindowIndex(_, slot, highest_index) => {
+                RepairType::HighestBlob(slot, highest_index) => {
                     inc_new_counter_debug!("cluster_info-request-highest-window-index", 1);
                     (
                         Self::run_highest_window_request(
                             &from_addr,
                             blocktree,
-                            *slot,
-                            *highest_index,
+                            slot,
+                            highest_index,
                         ),
                         "RequestHighestWindowIndex",
                     )
                 }
-                Protocol::RequestOrphan(_, slot) => {
+                RepairType::Orphan(slot) => {
                     inc_new_counter_debug!("cluster_info-request-orphan", 1);
                     (
-                        Self::run_orphan(&from_addr, blocktree, *slot, MAX_ORPHAN_REPAIR_RESPONSES),
+                        Self::run_orphan(&from_addr, blocktree, slot, MAX_ORPHAN_REPAIR_RESPONSES),
                         "RequestOrphan",
                     )
                 }
-                _ => panic!("Not a repair request"),
             }
         };
+        for blob in &res {
+            append_nonce(&mut blob.write().unwrap(), request.nonce);
+        }
+        if label != "RequestOrphan" {
+            me.read()
+                .unwrap()
+                .repaired_blobs
+                .fetch_add(res.len(), Ordering::Relaxed);
+        }
 
         trace!("{}: received repair request: {:?}", self_id, request);
         report_time_spent(label, &now.elapsed(), "");
@@ -1316,7 +1967,17 @@ impl ClusterInfo {
                 }
                 vec![]
             }
-            _ => Self::handle_repair(me, from_addr, blocktree, request),
+            Protocol::EntrypointMessage(_)
+            | Protocol::HolePunchRequest(_, _)
+            | Protocol::HolePunch(_, _) => Self::handle_nat_traversal(me, from_addr, request),
+            Protocol::RepairRequest(request) => {
+                if request.verify() {
+                    Self::handle_repair(me, from_addr, blocktree, request)
+                } else {
+                    inc_new_counter_error!("cluster_info-repair_request_verify_fail", 1);
+                    vec![]
+                }
+            }
         }
     }
 
@@ -1459,12 +2120,132 @@ pub fn compute_retransmit_peers(
     }
 }
 
+/// Where a node binds its sockets. The data plane's sockets (tvu, repair, retransmit and
+/// broadcast) bind in `data_port_range` if it's set, and the rest in `port_range`, except the
+/// services given ports of their own. Separate ranges let operators firewall the data plane's
+/// traffic apart from gossip and transactions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodePortConfig {
+    pub port_range: PortRange,
+    pub data_port_range: Option<PortRange>,
+    pub tvu_port: Option<u16>,
+    pub tpu_port: Option<u16>,
+    pub tpu_via_blobs_port: Option<u16>,
+    pub tpu_quic_port: Option<u16>,
+    pub repair_port: Option<u16>,
+    pub retransmit_port: Option<u16>,
+    pub broadcast_port: Option<u16>,
+}
+
+impl NodePortConfig {
+    pub fn new(port_range: PortRange) -> Self {
+        Self {
+            port_range,
+            data_port_range: None,
+            tvu_port: None,
+            tpu_port: None,
+            tpu_via_blobs_port: None,
+            tpu_quic_port: None,
+            repair_port: None,
+            retransmit_port: None,
+            broadcast_port: None,
+        }
+    }
+
+    fn data_port_range(&self) -> PortRange {
+        self.data_port_range.unwrap_or(self.port_range)
+    }
+
+    /// Checks that the port ranges aren't empty, and that no two services, counting gossip at
+    /// `gossip_port` unless it's 0, were given the same port
+    pub fn validate(&self, gossip_port: u16) -> std::result::Result<(), String> {
+        for (name, range) in &[
+            ("port", Some(self.port_range)),
+            ("data port", self.data_port_range),
+        ] {
+            if let Some((start, end)) = range {
+                if start >= end {
+                    return Err(format!("the {} range {}-{} is empty", name, start, end));
+                }
+            }
+        }
+
+        let mut ports: HashMap<u16, &str> = HashMap::new();
+        if gossip_port != 0 {
+            ports.insert(gossip_port, "gossip");
+        }
+        for (name, port) in &[
+            ("tvu", self.tvu_port),
+            ("tpu", self.tpu_port),
+            ("tpu via blobs", self.tpu_via_blobs_port),
+            ("tpu quic", self.tpu_quic_port),
+            ("repair", self.repair_port),
+            ("retransmit", self.retransmit_port),
+            ("broadcast", self.broadcast_port),
+        ] {
+            if let Some(port) = port {
+                if *port == 0 {
+                    return Err(format!("the {} port can't be 0", name));
+                }
+                if let Some(other) = ports.insert(*port, name) {
+                    return Err(format!(
+                        "the {} and {} services were both given port {}",
+                        other, name, port
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Binds the socket of the `name` service to `port`, or to a port of `range` if it's None
+fn bind_service(
+    name: &str,
+    port: Option<u16>,
+    range: PortRange,
+) -> std::result::Result<(u16, UdpSocket), String> {
+    let result = match port {
+        Some(port) => bind_to(port, false).map(|socket| (port, socket)),
+        None => bind_in_range(range),
+    };
+    result.map_err(|err| bind_service_error(name, port, range, err))
+}
+
+/// Binds `num` sockets of the `name` service to the same port, `port` or one of `range`
+fn multi_bind_service(
+    name: &str,
+    port: Option<u16>,
+    range: PortRange,
+    num: usize,
+) -> std::result::Result<(u16, Vec<UdpSocket>), String> {
+    let result = match port {
+        Some(port) => (0..num)
+            .map(|_| bind_to(port, true))
+            .collect::<io::Result<_>>()
+            .map(|sockets| (port, sockets)),
+        None => multi_bind_in_range(range, num),
+    };
+    result.map_err(|err| bind_service_error(name, port, range, err))
+}
+
+fn bind_service_error(name: &str, port: Option<u16>, range: PortRange, err: io::Error) -> String {
+    match port {
+        Some(port) => format!("failed to bind the {} port {}: {}", name, port, err),
+        None => format!(
+            "failed to bind a {} port in {}-{}: {}",
+            name, range.0, range.1, err
+        ),
+    }
+}
+
 #[derive(Debug)]
 pub struct Sockets {
     pub gossip: UdpSocket,
     pub tvu: Vec<UdpSocket>,
     pub tpu: Vec<UdpSocket>,
     pub tpu_via_blobs: Vec<UdpSocket>,
+    pub tpu_quic: Option<UdpSocket>,
     pub broadcast: UdpSocket,
     pub repair: UdpSocket,
     pub retransmit: UdpSocket,
@@ -1510,6 +2291,7 @@ impl Node {
                 tvu: vec![tvu],
                 tpu: vec![],
                 tpu_via_blobs: vec![],
+                tpu_quic: None,
                 broadcast,
                 repair,
                 retransmit,
@@ -1522,6 +2304,7 @@ impl Node {
         let gossip = UdpSocket::bind("127.0.0.1:0").unwrap();
         let tvu = UdpSocket::bind("127.0.0.1:0").unwrap();
         let tpu_via_blobs = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let tpu_quic = UdpSocket::bind("127.0.0.1:0").unwrap();
         let repair = UdpSocket::bind("127.0.0.1:0").unwrap();
         let rpc_port = find_available_port_in_range((1024, 65535)).unwrap();
         let rpc_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), rpc_port);
@@ -1550,6 +2333,7 @@ impl Node {
                 tvu: vec![tvu],
                 tpu: vec![tpu],
                 tpu_via_blobs: vec![tpu_via_blobs],
+                tpu_quic: Some(tpu_quic),
                 broadcast,
                 repair,
                 retransmit,
@@ -1577,18 +2361,41 @@ impl Node {
         gossip_addr: &SocketAddr,
         port_range: PortRange,
     ) -> Node {
+        Self::new_with_port_config(pubkey, gossip_addr, &NodePortConfig::new(port_range))
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+    /// Binds the node's sockets as `port_config` lays out, failing if it's invalid or a port
+    /// can't be bound
+    pub fn new_with_port_config(
+        pubkey: &Pubkey,
+        gossip_addr: &SocketAddr,
+        port_config: &NodePortConfig,
+    ) -> std::result::Result<Node, String> {
+        port_config.validate(gossip_addr.port())?;
+        let port_range = port_config.port_range;
+        let data_port_range = port_config.data_port_range();
+
         let (gossip_port, gossip) = Self::get_gossip_port(gossip_addr, port_range);
 
-        let (tvu_port, tvu_sockets) = multi_bind_in_range(port_range, 8).expect("tvu multi_bind");
+        let (tvu_port, tvu_sockets) =
+            multi_bind_service("tvu", port_config.tvu_port, data_port_range, 8)?;
 
-        let (tpu_port, tpu_sockets) = multi_bind_in_range(port_range, 32).expect("tpu multi_bind");
+        let (tpu_port, tpu_sockets) =
+            multi_bind_service("tpu", port_config.tpu_port, port_range, 32)?;
 
-        let (tpu_via_blobs_port, tpu_via_blobs_sockets) =
-            multi_bind_in_range(port_range, 8).expect("tpu multi_bind");
+        let (tpu_via_blobs_port, tpu_via_blobs_sockets) = multi_bind_service(
+            "tpu via blobs",
+            port_config.tpu_via_blobs_port,
+            port_range,
+            8,
+        )?;
 
-        let (_, repair) = Self::bind(port_range);
-        let (_, broadcast) = Self::bind(port_range);
-        let (_, retransmit) = Self::bind(port_range);
+        let (_, tpu_quic) = bind_service("tpu quic", port_config.tpu_quic_port, port_range)?;
+        let (_, repair) = bind_service("repair", port_config.repair_port, data_port_range)?;
+        let (_, broadcast) =
+            bind_service("broadcast", port_config.broadcast_port, data_port_range)?;
+        let (_, retransmit) =
+            bind_service("retransmit", port_config.retransmit_port, data_port_range)?;
 
         let info = ContactInfo::new(
             pubkey,
@@ -1603,19 +2410,20 @@ impl Node {
         );
         trace!("new ContactInfo: {:?}", info);
 
-        Node {
+        Ok(Node {
             info,
             sockets: Sockets {
                 gossip,
                 tvu: tvu_sockets,
                 tpu: tpu_sockets,
                 tpu_via_blobs: tpu_via_blobs_sockets,
+                tpu_quic: Some(tpu_quic),
                 broadcast,
                 repair,
                 retransmit,
                 storage: None,
             },
-        }
+        })
     }
     pub fn new_replicator_with_external_ip(
         pubkey: &Pubkey,
@@ -1633,6 +2441,7 @@ impl Node {
         new.info.tpu_via_blobs = empty;
         new.sockets.tpu = vec![];
         new.sockets.tpu_via_blobs = vec![];
+        new.sockets.tpu_quic = None;
 
         new
     }
@@ -1654,7 +2463,7 @@ mod tests {
     use crate::crds_value::CrdsValueLabel;
     use crate::erasure::ErasureConfig;
     use crate::packet::BLOB_HEADER_SIZE;
-    use crate::repair_service::RepairType;
+    use crate::repair_service::{strip_nonce, RepairType, SIZE_OF_NONCE};
     use crate::result::Error;
     use crate::test_tx::test_tx;
     use solana_sdk::signature::{Keypair, KeypairUtil};
@@ -1727,11 +2536,114 @@ mod tests {
         let label = CrdsValueLabel::ContactInfo(d.id);
         assert!(cluster_info.gossip.crds.lookup(&label).is_none());
     }
+    #[test]
+    fn test_shred_version_mismatch() {
+        let d = ContactInfo::new_localhost(&Pubkey::new_rand(), timestamp());
+        let cluster_info = Arc::new(RwLock::new(ClusterInfo::new_with_invalid_keypair(d)));
+        cluster_info.write().unwrap().set_shred_version(1);
+        let version = |contact_info: &ContactInfo, shred_version| {
+            let feature_set = compute_feature_set();
+            let version =
+                NodeVersion::new(&contact_info.id, shred_version, feature_set, timestamp());
+            CrdsValue::Version(version)
+        };
+        let unknown = ContactInfo::new_localhost(&Pubkey::new_rand(), timestamp());
+        let same = ContactInfo::new_localhost(&Pubkey::new_rand(), timestamp());
+        let other = ContactInfo::new_localhost(&Pubkey::new_rand(), timestamp());
+
+        // Only a node known to be of this cluster is a peer, whatever got into gossip
+        {
+            let mut me = cluster_info.write().unwrap();
+            me.insert_info(unknown.clone());
+            me.insert_info(same.clone());
+            me.insert_info(other.clone());
+            let _ = me.gossip.crds.insert(version(&same, 1), timestamp());
+            let _ = me.gossip.crds.insert(version(&other, 2), timestamp());
+        }
+        let tvu_peers = cluster_info.read().unwrap().tvu_peers();
+        assert_eq!(tvu_peers, vec![same.clone()]);
+        assert_eq!(cluster_info.read().unwrap().repair_peers(), tvu_peers);
+
+        // Gossip is only taken from nodes known to be of this cluster, or that tell so along
+        let another = ContactInfo::new_localhost(&Pubkey::new_rand(), timestamp());
+        let joining = ContactInfo::new_localhost(&Pubkey::new_rand(), timestamp());
+        let stranger = ContactInfo::new_localhost(&Pubkey::new_rand(), timestamp());
+        ClusterInfo::handle_push_message(
+            &cluster_info,
+            &same.id,
+            vec![
+                CrdsValue::ContactInfo(another.clone()),
+                version(&another, 2),
+                CrdsValue::ContactInfo(joining.clone()),
+                version(&joining, 1),
+            ],
+            &HashMap::new(),
+        );
+        ClusterInfo::handle_pull_response(
+            &cluster_info,
+            &same.id,
+            vec![CrdsValue::ContactInfo(stranger.clone())],
+        );
+        {
+            let me = cluster_info.read().unwrap();
+            let lookup = |label| me.gossip.crds.lookup(&label).is_some();
+            assert!(!lookup(CrdsValueLabel::ContactInfo(another.id)));
+            assert!(!lookup(CrdsValueLabel::Version(another.id)));
+            assert!(!lookup(CrdsValueLabel::ContactInfo(stranger.id)));
+            assert!(lookup(CrdsValueLabel::ContactInfo(joining.id)));
+            let joining_version = me.get_node_version(&joining.id).unwrap();
+            assert_eq!(joining_version.shred_version, 1);
+        }
+
+        // A node that doesn't tell its version is answered, so it can find its peers, but isn't
+        // taken in
+        let rsps = ClusterInfo::handle_pull_request(
+            &cluster_info,
+            Bloom::random(10, 0.1, 1024),
+            CrdsValue::ContactInfo(stranger.clone()),
+            &stranger.gossip,
+        );
+        assert!(!rsps.is_empty());
+        assert!(cluster_info
+            .read()
+            .unwrap()
+            .gossip
+            .crds
+            .lookup(&CrdsValueLabel::ContactInfo(stranger.id))
+            .is_none());
+    }
+
+    #[test]
+    fn test_partition_cfg() {
+        let me = ContactInfo::new_localhost(&Pubkey::new_rand(), timestamp());
+        let mut cluster_info = ClusterInfo::new_with_invalid_keypair(me.clone());
+        let reachable = ContactInfo::new_localhost(&Pubkey::new_rand(), timestamp());
+        let unreachable = ContactInfo::new_localhost(&Pubkey::new_rand(), timestamp());
+        cluster_info.insert_info(reachable.clone());
+        cluster_info.insert_info(unreachable.clone());
+        assert_eq!(cluster_info.tvu_peers().len(), 2);
+
+        let partition_cfg = PartitionCfg::default();
+        cluster_info.set_partition_cfg(partition_cfg.clone());
+        partition_cfg.partition(
+            vec![
+                [me.id, reachable.id].iter().cloned().collect(),
+                [unreachable.id].iter().cloned().collect(),
+            ],
+            Duration::from_secs(60),
+        );
+        assert_eq!(cluster_info.tvu_peers(), vec![reachable.clone()]);
+        assert_eq!(cluster_info.retransmit_peers(), vec![reachable]);
+
+        partition_cfg.heal();
+        assert_eq!(cluster_info.tvu_peers().len(), 2);
+    }
+
     #[test]
     fn window_index_request() {
         let me = ContactInfo::new_localhost(&Pubkey::new_rand(), timestamp());
         let mut cluster_info = ClusterInfo::new_with_invalid_keypair(me);
-        let rv = cluster_info.repair_request(&RepairType::Blob(0, 0));
+        let rv = cluster_info.repair_request(&RepairType::Blob(0, 0), 0);
         assert_matches!(rv, Err(Error::ClusterInfoError(ClusterInfoError::NoPeers)));
 
         let gossip_addr = socketaddr!([127, 0, 0, 1], 1234);
@@ -1748,7 +2660,7 @@ mod tests {
         );
         cluster_info.insert_info(nxt.clone());
         let rv = cluster_info
-            .repair_request(&RepairType::Blob(0, 0))
+            .repair_request(&RepairType::Blob(0, 0), 0)
             .unwrap();
         assert_eq!(nxt.gossip, gossip_addr);
         assert_eq!(rv.0, nxt.gossip);
@@ -1771,7 +2683,7 @@ mod tests {
         while !one || !two {
             //this randomly picks an option, so eventually it should pick both
             let rv = cluster_info
-                .repair_request(&RepairType::Blob(0, 0))
+                .repair_request(&RepairType::Blob(0, 0), 0)
                 .unwrap();
             if rv.0 == gossip_addr {
                 one = true;
@@ -1783,6 +2695,43 @@ mod tests {
         assert!(one && two);
     }
 
+    #[test]
+    fn window_index_request_in_range() {
+        let me = ContactInfo::new_localhost(&Pubkey::new_rand(), timestamp());
+        let mut cluster_info = ClusterInfo::new_with_invalid_keypair(me);
+        let slot_range = SlotRange::new(10, 19).unwrap();
+
+        let archival = Keypair::new();
+        let archival_info = ContactInfo::new_localhost(&archival.pubkey(), timestamp());
+        cluster_info.insert_info(archival_info.clone());
+        let recent = Keypair::new();
+        let recent_info = ContactInfo::new_localhost(&recent.pubkey(), timestamp());
+        cluster_info.insert_info(recent_info.clone());
+
+        // Without a peer that rooted the range, any repair peer is asked
+        let rv = cluster_info
+            .repair_request_in_range(&RepairType::Blob(10, 0), &slot_range, 0)
+            .unwrap();
+        assert!(rv.0 == archival_info.gossip || rv.0 == recent_info.gossip);
+
+        for (keypair, root) in &[(&archival, 19), (&recent, 18)] {
+            let mut value = CrdsValue::EpochSlots(EpochSlots::new(
+                keypair.pubkey(),
+                *root,
+                BTreeSet::new(),
+                timestamp(),
+            ));
+            value.sign(keypair);
+            cluster_info.gossip.crds.insert(value, timestamp()).unwrap();
+        }
+        for _ in 0..10 {
+            let rv = cluster_info
+                .repair_request_in_range(&RepairType::Blob(10, 0), &slot_range, 0)
+                .unwrap();
+            assert_eq!(rv.0, archival_info.gossip);
+        }
+    }
+
     /// test window requests respond with the right blob, and do not overrun
     #[test]
     fn run_window_request() {
@@ -1843,6 +2792,69 @@ mod tests {
         Blocktree::destroy(&ledger_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_handle_repair_request() {
+        let ledger_path = get_tmp_ledger_path!();
+        {
+            let blocktree = Arc::new(Blocktree::open(&ledger_path).unwrap());
+            let data_size = 1;
+            let mut blob = Blob::default();
+            blob.set_size(data_size);
+            blob.set_index(1);
+            blob.set_slot(2);
+            blob.set_erasure_config(&ErasureConfig::default());
+            blob.meta.size = data_size + BLOB_HEADER_SIZE;
+            blocktree.write_blobs(&[blob]).unwrap();
+
+            let me = ContactInfo::new_localhost(&Pubkey::new_rand(), timestamp());
+            let me = Arc::new(RwLock::new(ClusterInfo::new_with_invalid_keypair(me)));
+            let requester = ClusterInfo::new_with_invalid_keypair(ContactInfo::new_localhost(
+                &Pubkey::new_rand(),
+                timestamp(),
+            ));
+            let my_id = me.read().unwrap().id();
+            let request_bytes = requester
+                .map_repair_request(&RepairType::Blob(2, 1), &my_id, 42)
+                .unwrap();
+            let request = match deserialize(&request_bytes).unwrap() {
+                Protocol::RepairRequest(request) => request,
+                _ => panic!("Not a repair request"),
+            };
+            assert!(request.verify());
+
+            // The response carries the request's nonce
+            let from_addr = socketaddr!("127.0.0.1:1234");
+            let rv = ClusterInfo::handle_repair(&me, &from_addr, Some(&blocktree), request);
+            assert_eq!(rv.len(), 1);
+            let mut blob = rv[0].write().unwrap();
+            assert_eq!(blob.meta.size, BLOB_HEADER_SIZE + data_size + SIZE_OF_NONCE);
+            assert_eq!(strip_nonce(&mut blob), Some(42));
+            assert_eq!(blob.size(), data_size);
+
+            // A request for another node isn't answered
+            let request_bytes = requester
+                .map_repair_request(&RepairType::Blob(2, 1), &Pubkey::new_rand(), 42)
+                .unwrap();
+            if let Protocol::RepairRequest(request) = deserialize(&request_bytes).unwrap() {
+                assert!(
+                    ClusterInfo::handle_repair(&me, &from_addr, Some(&blocktree), request)
+                        .is_empty()
+                );
+            }
+
+            // Nor is a tampered one
+            let request_bytes = requester
+                .map_repair_request(&RepairType::Blob(2, 1), &my_id, 42)
+                .unwrap();
+            if let Protocol::RepairRequest(mut request) = deserialize(&request_bytes).unwrap() {
+                request.nonce = 43;
+                assert!(!request.verify());
+            }
+        }
+
+        Blocktree::destroy(&ledger_path).expect("Expected successful database destruction");
+    }
+
     /// test run_window_requestwindow requests respond with the right blob, and do not overrun
     #[test]
     fn run_highest_window_request() {
@@ -1954,6 +2966,7 @@ mod tests {
 
         check_sockets(&node.sockets.tvu, ip, range);
         check_sockets(&node.sockets.tpu, ip, range);
+        check_socket(node.sockets.tpu_quic.as_ref().unwrap(), ip, range);
     }
 
     #[test]
@@ -1987,6 +3000,57 @@ mod tests {
         assert_eq!(node.sockets.gossip.local_addr().unwrap().port(), port);
     }
 
+    #[test]
+    fn test_node_port_config_validate() {
+        let mut port_config = NodePortConfig::new(FULLNODE_PORT_RANGE);
+        assert_eq!(port_config.validate(0), Ok(()));
+        port_config.tvu_port = Some(9000);
+        port_config.repair_port = Some(9001);
+        assert_eq!(port_config.validate(8001), Ok(()));
+        assert!(port_config.validate(9000).is_err());
+        port_config.repair_port = Some(9000);
+        assert!(port_config.validate(0).is_err());
+        port_config.repair_port = Some(0);
+        assert!(port_config.validate(0).is_err());
+
+        let mut port_config = NodePortConfig::new(FULLNODE_PORT_RANGE);
+        port_config.data_port_range = Some((9000, 9000));
+        assert!(port_config.validate(0).is_err());
+    }
+
+    #[test]
+    fn test_new_with_port_config() {
+        let ip = IpAddr::V4(Ipv4Addr::from(0));
+        let data_port_range = (10_000, 12_000);
+        let tpu_port = bind_in_range((12_000, 14_000)).expect("Failed to bind").0;
+        let mut port_config = NodePortConfig::new(FULLNODE_PORT_RANGE);
+        port_config.data_port_range = Some(data_port_range);
+        port_config.tpu_port = Some(tpu_port);
+        let node =
+            Node::new_with_port_config(&Pubkey::new_rand(), &socketaddr!(0, 0), &port_config)
+                .unwrap();
+
+        check_socket(&node.sockets.gossip, ip, FULLNODE_PORT_RANGE);
+        check_sockets(&node.sockets.tvu, ip, data_port_range);
+        check_socket(&node.sockets.repair, ip, data_port_range);
+        check_socket(&node.sockets.retransmit, ip, data_port_range);
+        assert_eq!(node.sockets.tpu[0].local_addr().unwrap().port(), tpu_port);
+        assert_eq!(node.info.tpu.port(), tpu_port);
+        assert_eq!(
+            node.info.tvu.port(),
+            node.sockets.tvu[0].local_addr().unwrap().port()
+        );
+
+        // A port that's taken fails the node
+        let (taken_port, _taken) = bind_in_range((12_000, 14_000)).expect("Failed to bind");
+        port_config.tpu_port = None;
+        port_config.repair_port = Some(taken_port);
+        assert!(
+            Node::new_with_port_config(&Pubkey::new_rand(), &socketaddr!(0, 0), &port_config)
+                .is_err()
+        );
+    }
+
     #[test]
     fn new_replicator_external_ip_test() {
         let ip = Ipv4Addr::from(0);
@@ -2176,6 +3240,248 @@ mod tests {
         assert!(!broadcast_set.contains(&(layer_indices.last().unwrap())));
     }
 
+    #[test]
+    fn test_push_faucet() {
+        let keys = Keypair::new();
+        let contact_info = ContactInfo::new_localhost(&keys.pubkey(), 0);
+        let mut cluster_info = ClusterInfo::new_with_invalid_keypair(contact_info);
+        assert_eq!(cluster_info.get_faucet_addr(), None);
+
+        cluster_info.push_faucet(socketaddr!("0.0.0.0:9900"));
+        assert_eq!(cluster_info.get_faucet_addr(), None);
+
+        // Drones on this machine are fine, for local clusters
+        let addr = socketaddr!("127.0.0.1:9900");
+        cluster_info.push_faucet(addr);
+        assert_eq!(cluster_info.get_faucet_addr(), Some(addr));
+
+        // A drone some other node advertises is only used once that node is trusted
+        let other = Pubkey::new_rand();
+        let other_addr = socketaddr!("10.0.0.1:9900");
+        let faucet = CrdsValue::Faucet(Faucet::new(&other, other_addr, timestamp() + 1));
+        cluster_info
+            .gossip
+            .crds
+            .insert(faucet, timestamp())
+            .unwrap();
+        assert_eq!(cluster_info.get_faucet_addr(), Some(addr));
+        cluster_info.set_trusted_faucets(vec![other].into_iter().collect());
+        assert_eq!(cluster_info.get_faucet_addr(), Some(other_addr));
+
+        // The address is re-pushed with this node's ContactInfo, so peers don't purge it
+        let label = CrdsValueLabel::Faucet(keys.pubkey());
+        let wallclock = |cluster_info: &ClusterInfo| {
+            cluster_info.gossip.crds.lookup(&label).unwrap().wallclock()
+        };
+        let pushed = wallclock(&cluster_info);
+        sleep(Duration::from_millis(2));
+        cluster_info.push_self(&HashMap::new());
+        assert!(wallclock(&cluster_info) > pushed);
+    }
+
+    #[test]
+    fn test_push_quic_tpu() {
+        let keys = Keypair::new();
+        let contact_info = ContactInfo::new_localhost(&keys.pubkey(), 0);
+        let mut cluster_info = ClusterInfo::new_with_invalid_keypair(contact_info);
+        assert_eq!(cluster_info.get_quic_tpu_addr(&keys.pubkey()), None);
+
+        cluster_info.push_quic_tpu(socketaddr!("0.0.0.0:8009"));
+        assert_eq!(cluster_info.get_quic_tpu_addr(&keys.pubkey()), None);
+
+        let addr = socketaddr!("127.0.0.1:8009");
+        cluster_info.push_quic_tpu(addr);
+        assert_eq!(cluster_info.get_quic_tpu_addr(&keys.pubkey()), Some(addr));
+        assert_eq!(cluster_info.get_quic_tpu_addr(&Pubkey::new_rand()), None);
+    }
+
+    #[test]
+    fn test_push_leader_slots() {
+        let keys = Keypair::new();
+        let contact_info = ContactInfo::new_localhost(&keys.pubkey(), 0);
+        let mut cluster_info = ClusterInfo::new_with_invalid_keypair(contact_info);
+        assert_eq!(cluster_info.get_leader_slots(&keys.pubkey()), None);
+
+        let totals = LeaderSlotTotals {
+            rooted: 3,
+            late_start: 1,
+            no_votes: 0,
+            fork_abandoned: 2,
+        };
+        cluster_info.push_leader_slots(10, totals);
+        assert_eq!(
+            cluster_info.get_leader_slots(&keys.pubkey()),
+            Some((10, totals))
+        );
+        assert_eq!(cluster_info.get_leader_slots(&Pubkey::new_rand()), None);
+    }
+
+    #[test]
+    fn test_push_accounts_hashes() {
+        let keys = Keypair::new();
+        let contact_info = ContactInfo::new_localhost(&keys.pubkey(), 0);
+        let mut cluster_info = ClusterInfo::new_with_invalid_keypair(contact_info);
+        assert_eq!(cluster_info.get_accounts_hashes(&keys.pubkey()), None);
+
+        let hashes = vec![(32, Hash::new(&[1; 32])), (64, Hash::new(&[2; 32]))];
+        cluster_info.push_accounts_hashes(hashes.clone());
+        assert_eq!(
+            cluster_info.get_accounts_hashes(&keys.pubkey()),
+            Some(&hashes[..])
+        );
+        assert_eq!(cluster_info.get_accounts_hashes(&Pubkey::new_rand()), None);
+    }
+
+    #[test]
+    fn test_hole_punch_introduction() {
+        let entrypoint = Arc::new(RwLock::new(ClusterInfo::new_with_invalid_keypair(
+            ContactInfo::new_localhost(&Pubkey::new_rand(), 0),
+        )));
+        let node = Pubkey::new_rand();
+        let node_addr = socketaddr!("10.0.0.1:8001");
+        let peer = ContactInfo::new_with_socketaddr(&socketaddr!("10.0.0.2:8000"));
+        entrypoint.write().unwrap().insert_info(peer.clone());
+
+        // The node's pull requests haven't been seen from this address
+        let request = Protocol::HolePunchRequest(node, peer.id);
+        assert!(ClusterInfo::handle_nat_traversal(&entrypoint, &node_addr, request).is_empty());
+
+        entrypoint
+            .write()
+            .unwrap()
+            .nat_traversal
+            .observe(&node, node_addr, timestamp());
+        let request = Protocol::HolePunchRequest(node, peer.id);
+        let blobs = ClusterInfo::handle_nat_traversal(&entrypoint, &node_addr, request);
+        let entrypoint_id = entrypoint.read().unwrap().id();
+        let introductions: Vec<_> = blobs
+            .iter()
+            .map(|blob| {
+                let blob = blob.read().unwrap();
+                let introduction: Protocol = deserialize(&blob.data[..blob.meta.size]).unwrap();
+                match introduction {
+                    Protocol::EntrypointMessage(message) => {
+                        assert!(message.is_valid(&entrypoint_id, &message.to, timestamp()));
+                        match message.advice {
+                            NatAdvice::Introduction(pubkey, addr, token) => {
+                                (blob.meta.addr(), message.to, pubkey, addr, token)
+                            }
+                            advice => panic!("unexpected {:?}", advice),
+                        }
+                    }
+                    _ => panic!("unexpected {:?}", introduction),
+                }
+            })
+            .collect();
+        // Both sides get the same token
+        let token = introductions[0].4;
+        assert_eq!(
+            introductions,
+            vec![
+                (node_addr, node, peer.id, peer.gossip, token),
+                (peer.gossip, peer.id, node, node_addr, token),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_entrypoint_message_from_entrypoint_only() {
+        let keypair = Arc::new(Keypair::new());
+        let me = ContactInfo::new_localhost(&keypair.pubkey(), 0);
+        let cluster_info = Arc::new(RwLock::new(ClusterInfo::new(me.clone(), keypair)));
+        let entrypoint_keypair = Arc::new(Keypair::new());
+        let mut entrypoint_info = ContactInfo::new_with_socketaddr(&socketaddr!("10.0.0.9:8001"));
+        entrypoint_info.id = entrypoint_keypair.pubkey();
+        let entrypoint = ClusterInfo::new(entrypoint_info.clone(), entrypoint_keypair);
+        let observed_addr = socketaddr!("10.0.0.1:8001");
+        let advice = NatAdvice::ObservedAddr(observed_addr);
+        let is_behind_nat = || {
+            let cluster_info = cluster_info.read().unwrap();
+            cluster_info.nat_traversal.is_behind_nat(&me)
+        };
+
+        // Before the entrypoint is known, nothing is accepted from it
+        let request = entrypoint.entrypoint_message(&me.id, advice.clone(), timestamp());
+        let from_addr = entrypoint_info.gossip;
+        ClusterInfo::handle_nat_traversal(&cluster_info, &from_addr, request);
+        assert!(!is_behind_nat());
+
+        cluster_info
+            .write()
+            .unwrap()
+            .set_entrypoint(ContactInfo::new_gossip_entry_point(&entrypoint_info.gossip));
+        cluster_info
+            .write()
+            .unwrap()
+            .insert_info(entrypoint_info.clone());
+
+        // Signed by some other node, even one at the entrypoint's address
+        let impostor = ClusterInfo::new_with_invalid_keypair(ContactInfo::new_localhost(
+            &Pubkey::new_rand(),
+            0,
+        ));
+        let request = impostor.entrypoint_message(&me.id, advice.clone(), timestamp());
+        ClusterInfo::handle_nat_traversal(&cluster_info, &from_addr, request);
+        assert!(!is_behind_nat());
+
+        let request = entrypoint.entrypoint_message(&me.id, advice, timestamp());
+        ClusterInfo::handle_nat_traversal(&cluster_info, &from_addr, request);
+        assert!(is_behind_nat());
+    }
+
+    #[test]
+    fn test_no_push_to_pull_only() {
+        let mut cluster_info = ClusterInfo::new_with_invalid_keypair(ContactInfo::new_localhost(
+            &Pubkey::new_rand(),
+            0,
+        ));
+        let public_peer = ContactInfo::new_with_socketaddr(&socketaddr!("127.0.0.1:2000"));
+        let pull_only_peer = ContactInfo::new_with_socketaddr(&socketaddr!("127.0.0.1:3000"));
+        cluster_info.insert_info(public_peer.clone());
+        cluster_info.insert_info(pull_only_peer.clone());
+        let reachability = CrdsValue::Reachability(NodeReachability::new(
+            &pull_only_peer.id,
+            Reachability::PullOnly,
+            timestamp(),
+        ));
+        cluster_info
+            .gossip
+            .crds
+            .insert(reachability, timestamp())
+            .unwrap();
+        assert_eq!(
+            cluster_info.get_reachability(&pull_only_peer.id),
+            Reachability::PullOnly
+        );
+        cluster_info.push_self(&HashMap::new());
+
+        let addrs: Vec<_> = cluster_info
+            .new_push_requests()
+            .into_iter()
+            .map(|(addr, _)| addr)
+            .collect();
+        assert_eq!(addrs, vec![public_peer.gossip]);
+    }
+
+    #[test]
+    fn test_push_duplicate_slot() {
+        let keys = Keypair::new();
+        let contact_info = ContactInfo::new_localhost(&keys.pubkey(), 0);
+        let mut cluster_info = ClusterInfo::new_with_invalid_keypair(contact_info);
+        assert!(cluster_info.get_duplicate_slot_proofs().is_empty());
+
+        let proof = DuplicateSlotProof {
+            index: 7,
+            blob1: vec![1; BLOB_SIZE],
+            blob2: vec![2; BLOB_SIZE],
+        };
+        cluster_info.push_duplicate_slot(3, &proof);
+        assert_eq!(
+            cluster_info.get_duplicate_slot_proofs(),
+            vec![(cluster_info.id(), 3, proof)]
+        );
+    }
+
     #[test]
     fn test_push_vote() {
         let keys = Keypair::new();
@@ -1,16 +1,31 @@
+//! The `cluster_info_vote_listener` pulls the vote transactions validators gossip and verifies
+//! them. Verified votes feed the banking stage while this node is leader, and always feed the
+//! `BlockCommitmentCache`, which tracks the stake committed to each recent slot.
+
+use crate::bank_forks::BankForks;
+use crate::block_commitment::BlockCommitmentCache;
 use crate::cluster_info::{ClusterInfo, GOSSIP_SLEEP_MILLIS};
+use crate::packet::Packets;
 use crate::poh_recorder::PohRecorder;
 use crate::result::Result;
 use crate::service::Service;
 use crate::sigverify_stage::VerifiedPackets;
 use crate::{packet, sigverify};
+use bincode::deserialize;
 use crossbeam_channel::Sender as CrossbeamSender;
 use solana_metrics::inc_new_counter_debug;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::Transaction;
+use solana_vote_api::vote_instruction::VoteInstruction;
+use solana_vote_api::vote_state::VoteState;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
 use std::thread::{self, sleep, Builder, JoinHandle};
 use std::time::Duration;
 
+/// Where verified votes go while this node is leader
+type BankingVotes = (CrossbeamSender<VerifiedPackets>, Arc<Mutex<PohRecorder>>);
+
 pub struct ClusterInfoVoteListener {
     thread_hdls: Vec<JoinHandle<()>>,
 }
@@ -22,9 +37,48 @@ impl ClusterInfoVoteListener {
         sigverify_disabled: bool,
         sender: CrossbeamSender<VerifiedPackets>,
         poh_recorder: &Arc<Mutex<PohRecorder>>,
+        bank_forks: &Arc<RwLock<BankForks>>,
+        block_commitment_cache: &Arc<RwLock<BlockCommitmentCache>>,
+    ) -> Self {
+        Self::spawn(
+            exit,
+            cluster_info,
+            sigverify_disabled,
+            Some((sender, poh_recorder.clone())),
+            bank_forks,
+            block_commitment_cache,
+        )
+    }
+
+    /// A listener that only tracks commitment, for nodes without a banking stage
+    pub fn new_commitment_only(
+        exit: &Arc<AtomicBool>,
+        cluster_info: Arc<RwLock<ClusterInfo>>,
+        sigverify_disabled: bool,
+        bank_forks: &Arc<RwLock<BankForks>>,
+        block_commitment_cache: &Arc<RwLock<BlockCommitmentCache>>,
+    ) -> Self {
+        Self::spawn(
+            exit,
+            cluster_info,
+            sigverify_disabled,
+            None,
+            bank_forks,
+            block_commitment_cache,
+        )
+    }
+
+    fn spawn(
+        exit: &Arc<AtomicBool>,
+        cluster_info: Arc<RwLock<ClusterInfo>>,
+        sigverify_disabled: bool,
+        banking_votes: Option<BankingVotes>,
+        bank_forks: &Arc<RwLock<BankForks>>,
+        block_commitment_cache: &Arc<RwLock<BlockCommitmentCache>>,
     ) -> Self {
         let exit = exit.clone();
-        let poh_recorder = poh_recorder.clone();
+        let bank_forks = bank_forks.clone();
+        let block_commitment_cache = block_commitment_cache.clone();
         let thread = Builder::new()
             .name("solana-cluster_info_vote_listener".to_string())
             .spawn(move || {
@@ -32,8 +86,9 @@ impl ClusterInfoVoteListener {
                     exit,
                     &cluster_info,
                     sigverify_disabled,
-                    &sender,
-                    poh_recorder,
+                    banking_votes,
+                    &bank_forks,
+                    &block_commitment_cache,
                 );
             })
             .unwrap();
@@ -45,31 +100,123 @@ impl ClusterInfoVoteListener {
         exit: Arc<AtomicBool>,
         cluster_info: &Arc<RwLock<ClusterInfo>>,
         sigverify_disabled: bool,
-        sender: &CrossbeamSender<VerifiedPackets>,
-        poh_recorder: Arc<Mutex<PohRecorder>>,
+        banking_votes: Option<BankingVotes>,
+        bank_forks: &RwLock<BankForks>,
+        block_commitment_cache: &RwLock<BlockCommitmentCache>,
     ) -> Result<()> {
         let mut last_ts = 0;
+        let mut last_commitment_ts = 0;
         loop {
             if exit.load(Ordering::Relaxed) {
                 return Ok(());
             }
-            let (votes, new_ts) = cluster_info.read().unwrap().get_votes(last_ts);
-            if poh_recorder.lock().unwrap().has_bank() {
-                last_ts = new_ts;
-                inc_new_counter_debug!("cluster_info_vote_listener-recv_count", votes.len());
-                let msgs = packet::to_packets(&votes);
-                if !msgs.is_empty() {
-                    let r = if sigverify_disabled {
-                        sigverify::ed25519_verify_disabled(&msgs)
-                    } else {
-                        sigverify::ed25519_verify_cpu(&msgs)
-                    };
-                    sender.send(msgs.into_iter().zip(r).collect())?;
+            // Every vote counts towards commitment, whether or not this node is leader
+            let (votes, new_ts) = cluster_info.read().unwrap().get_votes(last_commitment_ts);
+            last_commitment_ts = new_ts;
+            if !votes.is_empty() {
+                let msgs = Self::verify_votes(&votes, sigverify_disabled);
+                Self::track_votes(&votes, &msgs, bank_forks, block_commitment_cache);
+            }
+
+            if let Some((sender, poh_recorder)) = &banking_votes {
+                let (votes, new_ts) = cluster_info.read().unwrap().get_votes(last_ts);
+                if poh_recorder.lock().unwrap().has_bank() {
+                    last_ts = new_ts;
+                    inc_new_counter_debug!("cluster_info_vote_listener-recv_count", votes.len());
+                    let msgs = Self::verify_votes(&votes, sigverify_disabled);
+                    if !msgs.is_empty() {
+                        sender.send(msgs)?;
+                    }
                 }
             }
             sleep(Duration::from_millis(GOSSIP_SLEEP_MILLIS));
         }
     }
+
+    /// Packets of `votes`, with the packets of votes that fail signature verification marked
+    /// to be discarded
+    fn verify_votes(votes: &[Transaction], sigverify_disabled: bool) -> Vec<Packets> {
+        let mut msgs = packet::to_packets(votes);
+        if !msgs.is_empty() {
+            let r = if sigverify_disabled {
+                sigverify::ed25519_verify_disabled(&msgs)
+            } else {
+                sigverify::ed25519_verify_cpu(&msgs)
+            };
+            sigverify::mark_packets(&mut msgs, &r);
+        }
+        msgs
+    }
+
+    /// The vote accounts and voted slots of the vote instructions in `tx`
+    fn parse_votes(tx: &Transaction) -> Vec<(Pubkey, Vec<u64>)> {
+        let message = tx.message();
+        message
+            .instructions
+            .iter()
+            .filter(|ix| {
+                message.account_keys.get(ix.program_id_index as usize)
+                    == Some(&solana_vote_api::id())
+            })
+            .filter_map(|ix| {
+                let vote_pubkey = message.account_keys.get(*ix.accounts.get(0)? as usize)?;
+                match deserialize::<VoteInstruction>(&ix.data).ok()? {
+                    VoteInstruction::Vote(votes) => Some((
+                        *vote_pubkey,
+                        votes.into_iter().map(|vote| vote.slot).collect(),
+                    )),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Records the verified votes of `votes`, weighted by the stake of their vote accounts in the
+    /// root bank's epoch, and recomputes the commitment of each slot
+    fn track_votes(
+        votes: &[Transaction],
+        msgs: &[Packets],
+        bank_forks: &RwLock<BankForks>,
+        block_commitment_cache: &RwLock<BlockCommitmentCache>,
+    ) {
+        let root_bank = {
+            let bank_forks = bank_forks.read().unwrap();
+            bank_forks[bank_forks.root()].clone()
+        };
+        let vote_accounts = match root_bank.epoch_vote_accounts(root_bank.epoch()) {
+            Some(vote_accounts) => vote_accounts,
+            None => return,
+        };
+        let packets = msgs.iter().flat_map(|msgs| msgs.packets.iter());
+
+        let mut block_commitment_cache = block_commitment_cache.write().unwrap();
+        let mut recorded = 0;
+        for (tx, packet) in votes.iter().zip(packets) {
+            if packet.meta.discard {
+                continue;
+            }
+            let message = tx.message();
+            let signers = &message.account_keys[..message.header.num_required_signatures as usize];
+            for (vote_pubkey, slots) in Self::parse_votes(tx) {
+                let (stake, account) = match vote_accounts.get(&vote_pubkey) {
+                    Some((stake, account)) if *stake > 0 => (*stake, account),
+                    _ => continue,
+                };
+                // Only the vote account's authorized voter can vote for it
+                let authorized = VoteState::from(account).map_or(false, |vote_state| {
+                    signers.contains(&vote_state.authorized_voter_pubkey)
+                });
+                if authorized && block_commitment_cache.record_vote(vote_pubkey, slots, stake) {
+                    recorded += 1;
+                }
+            }
+        }
+        inc_new_counter_debug!("cluster_info_vote_listener-tracked_votes", recorded);
+        block_commitment_cache
+            .set_total_stake(vote_accounts.values().map(|(stake, _)| stake).sum());
+        block_commitment_cache.set_root(root_bank.slot());
+        block_commitment_cache.aggregate();
+    }
 }
 
 impl Service for ClusterInfoVoteListener {
@@ -85,13 +232,19 @@ impl Service for ClusterInfoVoteListener {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use crate::consensus::MAX_RECENT_VOTES;
+    use crate::contact_info::ContactInfo;
+    use crate::genesis_utils::{create_genesis_block, GenesisBlockInfo, BOOTSTRAP_LEADER_LAMPORTS};
     use crate::packet;
+    use solana_runtime::bank::Bank;
     use solana_sdk::hash::Hash;
-    use solana_sdk::signature::{Keypair, KeypairUtil};
+    use solana_sdk::signature::{Keypair, KeypairUtil, Signature};
+    use solana_sdk::timing::timestamp;
     use solana_sdk::transaction::Transaction;
     use solana_vote_api::vote_instruction;
     use solana_vote_api::vote_state::Vote;
+    use std::time::Instant;
 
     #[test]
     fn test_max_vote_tx_fits() {
@@ -115,4 +268,106 @@ mod tests {
 
         assert_eq!(msgs.len(), 1);
     }
+
+    #[test]
+    fn test_track_votes() {
+        let GenesisBlockInfo {
+            genesis_block,
+            voting_keypair,
+            ..
+        } = create_genesis_block(10_000);
+        let bank_forks = RwLock::new(BankForks::new(0, Bank::new(&genesis_block)));
+        let block_commitment_cache = RwLock::new(BlockCommitmentCache::default());
+        let node_keypair = Keypair::new();
+        let vote_tx = |voter: &Keypair| {
+            let votes = vec![Vote::new(1, Hash::default()), Vote::new(2, Hash::default())];
+            let vote_ix = vote_instruction::vote(&voting_keypair.pubkey(), &voter.pubkey(), votes);
+            let mut vote_tx =
+                Transaction::new_with_payer(vec![vote_ix], Some(&node_keypair.pubkey()));
+            vote_tx.partial_sign(&[&node_keypair], Hash::default());
+            vote_tx.partial_sign(&[voter], Hash::default());
+            vote_tx
+        };
+        let track = |votes: &[Transaction]| {
+            let msgs = ClusterInfoVoteListener::verify_votes(votes, false);
+            ClusterInfoVoteListener::track_votes(
+                votes,
+                &msgs,
+                &bank_forks,
+                &block_commitment_cache,
+            );
+        };
+
+        // Votes that aren't signed by the vote account's authorized voter don't count
+        let mut forged_vote_tx = vote_tx(&voting_keypair);
+        forged_vote_tx.signatures[1] = Signature::default();
+        track(&[vote_tx(&Keypair::new()), forged_vote_tx]);
+        let cache = block_commitment_cache.read().unwrap();
+        assert_eq!(cache.total_stake(), BOOTSTRAP_LEADER_LAMPORTS);
+        assert_eq!(cache.get_block_commitment(1), None);
+        drop(cache);
+
+        track(&[vote_tx(&voting_keypair)]);
+        let cache = block_commitment_cache.read().unwrap();
+        let commitment = cache.get_block_commitment(1).unwrap();
+        assert_eq!(
+            commitment.get_confirmation_stake(2),
+            BOOTSTRAP_LEADER_LAMPORTS
+        );
+        let commitment = cache.get_block_commitment(2).unwrap();
+        assert_eq!(
+            commitment.get_confirmation_stake(1),
+            BOOTSTRAP_LEADER_LAMPORTS
+        );
+    }
+
+    #[test]
+    fn test_commitment_only_tracks_gossiped_votes() {
+        let GenesisBlockInfo {
+            genesis_block,
+            voting_keypair,
+            ..
+        } = create_genesis_block(10_000);
+        let exit = Arc::new(AtomicBool::new(false));
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(0, Bank::new(&genesis_block))));
+        let block_commitment_cache = Arc::new(RwLock::new(BlockCommitmentCache::default()));
+        let node_keypair = Keypair::new();
+        let cluster_info = Arc::new(RwLock::new(ClusterInfo::new_with_invalid_keypair(
+            ContactInfo::new_localhost(&node_keypair.pubkey(), timestamp()),
+        )));
+
+        let votes = vec![Vote::new(1, Hash::default())];
+        let vote_ix =
+            vote_instruction::vote(&voting_keypair.pubkey(), &voting_keypair.pubkey(), votes);
+        let mut vote_tx = Transaction::new_with_payer(vec![vote_ix], Some(&node_keypair.pubkey()));
+        vote_tx.partial_sign(&[&node_keypair], Hash::default());
+        vote_tx.partial_sign(&[&voting_keypair], Hash::default());
+        cluster_info.write().unwrap().push_vote(vote_tx);
+
+        // Without a banking stage, gossiped votes still fill the cache
+        let listener = ClusterInfoVoteListener::new_commitment_only(
+            &exit,
+            cluster_info,
+            false,
+            &bank_forks,
+            &block_commitment_cache,
+        );
+        let start = Instant::now();
+        while block_commitment_cache
+            .read()
+            .unwrap()
+            .get_block_commitment(1)
+            .is_none()
+        {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            sleep(Duration::from_millis(GOSSIP_SLEEP_MILLIS));
+        }
+        assert_eq!(
+            block_commitment_cache.read().unwrap().total_stake(),
+            BOOTSTRAP_LEADER_LAMPORTS
+        );
+
+        exit.store(true, Ordering::Relaxed);
+        listener.join().unwrap();
+    }
 }
@@ -127,6 +127,31 @@ pub fn verify_ledger_ticks(ledger_path: &str, ticks_per_slot: usize) {
     }
 }
 
+/// Checks that the ledgers of stopped nodes rooted the same fork, one possibly further along
+/// than another, and returns the last slot every ledger rooted
+pub fn verify_rooted_forks_agree(ledger_paths: &[&str]) -> u64 {
+    let mut rooted_forks = vec![];
+    for ledger_path in ledger_paths {
+        let blocktree = Blocktree::open(ledger_path).unwrap();
+        let rooted_fork: Vec<_> = blocktree
+            .rooted_slot_iterator(0)
+            .unwrap()
+            .map(|(slot, _)| slot)
+            .collect();
+        rooted_forks.push(rooted_fork);
+    }
+    let shortest_fork = rooted_forks.iter().min_by_key(|fork| fork.len()).unwrap();
+    for (ledger_path, rooted_fork) in ledger_paths.iter().zip(&rooted_forks) {
+        assert_eq!(
+            &rooted_fork[..shortest_fork.len()],
+            &shortest_fork[..],
+            "{} rooted another fork",
+            ledger_path
+        );
+    }
+    *shortest_fork.last().unwrap()
+}
+
 pub fn sleep_n_epochs(
     num_epochs: f64,
     config: &PohConfig,
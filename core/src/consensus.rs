@@ -1,5 +1,7 @@
 use crate::bank_forks::BankForks;
+use crate::result::{Error, Result};
 use crate::staking_utils;
+use bincode::{deserialize_from, serialize_into};
 use solana_metrics::datapoint_info;
 use solana_runtime::bank::Bank;
 use solana_sdk::account::Account;
@@ -7,6 +9,9 @@ use solana_sdk::hash::Hash;
 use solana_sdk::pubkey::Pubkey;
 use solana_vote_api::vote_state::{Lockout, Vote, VoteState, MAX_LOCKOUT_HISTORY};
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 pub const VOTE_THRESHOLD_DEPTH: usize = 8;
@@ -44,6 +49,21 @@ pub struct Tower {
     threshold_size: f64,
     lockouts: VoteState,
     recent_votes: VecDeque<Vote>,
+    /// Where the tower is saved after every vote, if anywhere
+    tower_path: Option<PathBuf>,
+}
+
+/// The votes of a tower, as saved to disk so a restarted validator keeps its lockouts
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+struct SavedTower {
+    node_pubkey: Pubkey,
+    lockouts: VoteState,
+    recent_votes: Vec<Vote>,
+}
+
+/// The file a validator's tower is saved to in its ledger
+pub fn tower_path(ledger_path: &Path, node_pubkey: &Pubkey) -> PathBuf {
+    ledger_path.join(format!("tower-{}.bin", node_pubkey))
 }
 
 impl EpochStakes {
@@ -78,14 +98,28 @@ impl EpochStakes {
 }
 
 impl Tower {
-    pub fn new_from_forks(bank_forks: &BankForks, my_pubkey: &Pubkey) -> Self {
+    /// Builds the tower from the votes on chain, or from the tower saved at `tower_path` when
+    /// that one is at least as recent. The tower is then saved there after every vote.
+    pub fn new_from_forks(
+        bank_forks: &BankForks,
+        my_pubkey: &Pubkey,
+        tower_path: Option<&Path>,
+    ) -> Self {
+        let tower_path = tower_path.map(Path::to_path_buf);
         let mut frozen_banks: Vec<_> = bank_forks.frozen_banks().values().cloned().collect();
         frozen_banks.sort_by_key(|b| (b.parents().len(), b.slot()));
         let epoch_stakes = {
             if let Some(bank) = frozen_banks.last() {
                 EpochStakes::new_from_bank(bank, my_pubkey)
             } else {
-                return Self::default();
+                return Self {
+                    epoch_stakes: EpochStakes {
+                        delegate_pubkey: *my_pubkey,
+                        ..EpochStakes::default()
+                    },
+                    tower_path,
+                    ..Self::default()
+                };
             }
         };
 
@@ -95,10 +129,18 @@ impl Tower {
             threshold_size: VOTE_THRESHOLD_SIZE,
             lockouts: VoteState::default(),
             recent_votes: VecDeque::default(),
+            tower_path,
         };
 
         let bank = tower.find_heaviest_bank(bank_forks).unwrap();
         tower.lockouts = Self::initialize_lockouts_from_bank(&bank, tower.epoch_stakes.epoch);
+        if let Some(tower_path) = tower.tower_path.clone() {
+            match Self::restore(&tower_path, my_pubkey) {
+                Ok(saved_tower) => tower.reconcile(saved_tower),
+                Err(err) => warn!("Unable to restore tower from {:?}: {:?}", tower_path, err),
+            }
+        }
+        tower.adjust_to_root(bank_forks.root());
         tower
     }
     pub fn new(epoch_stakes: EpochStakes, threshold_depth: usize, threshold_size: f64) -> Self {
@@ -108,6 +150,7 @@ impl Tower {
             threshold_size,
             lockouts: VoteState::default(),
             recent_votes: VecDeque::default(),
+            tower_path: None,
         }
     }
     pub fn collect_vote_lockouts<F>(
@@ -253,6 +296,37 @@ impl Tower {
 
         // vote_state doesn't keep around the hashes, so we save them in recent_votes
         self.recent_votes.push_back(vote);
+        self.retain_recent_votes();
+
+        datapoint_info!(
+            "tower-vote",
+            ("latest", slot, i64),
+            ("root", self.lockouts.root_slot.unwrap_or(0), i64)
+        );
+        if root_slot != self.lockouts.root_slot {
+            Some(self.lockouts.root_slot.unwrap())
+        } else {
+            None
+        }
+    }
+
+    /// Records a vote like `record_vote`, but keeps it only once the tower holding it is saved.
+    /// When saving fails the tower is left as it was, so the vote can't be sent or counted in
+    /// the lockouts without being on disk.
+    pub fn record_vote_and_save(&mut self, slot: u64, hash: Hash) -> Result<Option<u64>> {
+        let lockouts = self.lockouts.clone();
+        let recent_votes = self.recent_votes.clone();
+        let new_root = self.record_vote(slot, hash);
+        if let Err(err) = self.save() {
+            self.lockouts = lockouts;
+            self.recent_votes = recent_votes;
+            return Err(err);
+        }
+        Ok(new_root)
+    }
+
+    /// Keeps only the recent votes for the last `MAX_RECENT_VOTES` slots still locked out
+    fn retain_recent_votes(&mut self) {
         let slots = self
             .lockouts
             .votes
@@ -262,17 +336,93 @@ impl Tower {
             .collect::<Vec<_>>();
         self.recent_votes
             .retain(|vote| slots.iter().any(|slot| vote.slot == *slot));
+    }
+
+    /// Saves the tower to its `tower_path`. The file is replaced atomically, so a crash while
+    /// saving leaves the previous tower in place.
+    pub fn save(&self) -> Result<()> {
+        let tower_path = match &self.tower_path {
+            Some(tower_path) => tower_path,
+            None => return Ok(()),
+        };
+        let saved_tower = SavedTower {
+            node_pubkey: self.epoch_stakes.delegate_pubkey,
+            lockouts: self.lockouts.clone(),
+            recent_votes: self.recent_votes(),
+        };
+        let new_tower_path = tower_path.with_extension("new");
+        {
+            let mut file = BufWriter::new(File::create(&new_tower_path)?);
+            serialize_into(&mut file, &saved_tower)?;
+            file.flush()?;
+            file.get_ref().sync_all()?;
+        }
+        fs::rename(&new_tower_path, tower_path)?;
+        Ok(())
+    }
 
+    fn restore(tower_path: &Path, my_pubkey: &Pubkey) -> Result<SavedTower> {
+        let file = BufReader::new(File::open(tower_path)?);
+        let saved_tower: SavedTower = deserialize_from(file)?;
+        if saved_tower.node_pubkey != *my_pubkey {
+            return Err(Error::IO(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("tower saved by {}", saved_tower.node_pubkey),
+            )));
+        }
+        Ok(saved_tower)
+    }
+
+    /// Applies the votes of `saved_tower` that came after the last vote on chain. The saved
+    /// tower normally has votes that haven't landed on chain yet, and follows from the vote
+    /// state on chain: applying those votes then gives exactly the saved lockouts. When it
+    /// doesn't, the file is stale or from another ledger, and the lockouts on chain are kept
+    /// along with whatever later votes the file holds.
+    fn reconcile(&mut self, saved_tower: SavedTower) {
+        let saved_slot = saved_tower.lockouts.votes.back().map(|vote| vote.slot);
+        let on_chain_slot = self.last_voted_slot();
+        for vote in &saved_tower.lockouts.votes {
+            if Some(vote.slot) > on_chain_slot {
+                self.lockouts.process_slot_vote_unchecked(vote.slot);
+            }
+        }
+        self.recent_votes.extend(
+            saved_tower
+                .recent_votes
+                .into_iter()
+                .filter(|vote| Some(vote.slot) > on_chain_slot),
+        );
+        self.retain_recent_votes();
+
+        let follows_on_chain = self.lockouts.votes == saved_tower.lockouts.votes
+            && self.lockouts.root_slot == saved_tower.lockouts.root_slot;
+        if !follows_on_chain {
+            warn!(
+                "Saved tower (last vote {:?}) doesn't follow from the votes on chain ({:?})",
+                saved_slot, on_chain_slot
+            );
+        }
         datapoint_info!(
-            "tower-vote",
-            ("latest", slot, i64),
-            ("root", self.lockouts.root_slot.unwrap_or(0), i64)
+            "tower-restore",
+            ("saved_slot", saved_slot.unwrap_or(0), i64),
+            ("on_chain_slot", on_chain_slot.unwrap_or(0), i64),
+            ("follows_on_chain", follows_on_chain, bool)
         );
-        if root_slot != self.lockouts.root_slot {
-            Some(self.lockouts.root_slot.unwrap())
-        } else {
-            None
+    }
+
+    /// Drops the votes at or below `root`, the root of the forks the tower votes on, and roots
+    /// the tower there if it isn't rooted later already. Those slots may no longer be in the
+    /// forks to check lockouts against, and having been rooted they can't be switched from.
+    fn adjust_to_root(&mut self, root: u64) {
+        if self
+            .lockouts
+            .root_slot
+            .map_or(true, |root_slot| root_slot < root)
+        {
+            self.lockouts.root_slot = Some(root);
         }
+        self.lockouts.votes.retain(|vote| vote.slot > root);
+        self.recent_votes.retain(|vote| vote.slot > root);
     }
 
     pub fn recent_votes(&self) -> Vec<Vote> {
@@ -283,10 +433,28 @@ impl Tower {
         self.lockouts.root_slot
     }
 
+    /// The slots voted on and still locked out, along with the ones in `recent_votes`
+    pub fn voted_slots(&self) -> Vec<u64> {
+        self.lockouts
+            .votes
+            .iter()
+            .map(|lockout| lockout.slot)
+            .chain(self.recent_votes.iter().map(|vote| vote.slot))
+            .collect()
+    }
+
+    pub fn last_voted_slot(&self) -> Option<u64> {
+        self.lockouts.votes.back().map(|vote| vote.slot)
+    }
+
     pub fn total_epoch_stakes(&self) -> u64 {
         self.epoch_stakes.total_staked
     }
 
+    pub fn epoch_stake(&self, pubkey: &Pubkey) -> u64 {
+        *self.epoch_stakes.stakes.get(pubkey).unwrap_or(&0)
+    }
+
     pub fn calculate_weight(&self, stake_lockouts: &HashMap<u64, StakeLockout>) -> u128 {
         let mut sum = 0u128;
         let root_slot = self.lockouts.root_slot.unwrap_or(0);
@@ -315,12 +483,19 @@ impl Tower {
             if vote.slot == slot {
                 continue;
             }
-            if !descendants[&vote.slot].contains(&slot) {
+            // A voted slot that's been pruned from the forks keeps its lockout
+            if !descendants
+                .get(&vote.slot)
+                .map_or(false, |descendants| descendants.contains(&slot))
+            {
                 return true;
             }
         }
         if let Some(root) = lockouts.root_slot {
-            !descendants[&root].contains(&slot)
+            // A root that isn't in the forks yet can't be told to be an ancestor
+            !descendants
+                .get(&root)
+                .map_or(false, |descendants| descendants.contains(&slot))
         } else {
             false
         }
@@ -439,6 +614,7 @@ impl Tower {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::blocktree::get_tmp_ledger_path;
 
     fn gen_stakes(stake_votes: &[(u64, &[u64])]) -> Vec<(Pubkey, (u64, Account))> {
         let mut stakes = vec![];
@@ -928,4 +1104,104 @@ mod test {
     fn test_recent_votes_exact() {
         vote_and_check_recent(MAX_RECENT_VOTES)
     }
+
+    #[test]
+    fn test_save_and_restore_tower() {
+        let ledger_path = get_tmp_ledger_path!();
+        fs::create_dir_all(&ledger_path).unwrap();
+        let path = tower_path(Path::new(&ledger_path), &Pubkey::default());
+        let mut tower = Tower::new(EpochStakes::new_for_tests(2), 1, 0.67);
+
+        // Without a path there's nothing to save
+        tower.record_vote(0, Hash::default());
+        tower.save().unwrap();
+        assert!(Tower::restore(&path, &Pubkey::default()).is_err());
+
+        tower.tower_path = Some(path.clone());
+        assert_eq!(
+            tower.record_vote_and_save(1, Hash::default()).unwrap(),
+            None
+        );
+        let saved_tower = Tower::restore(&path, &Pubkey::default()).unwrap();
+        assert_eq!(saved_tower.lockouts, tower.lockouts);
+        assert_eq!(saved_tower.recent_votes, tower.recent_votes());
+
+        // A vote that can't be saved isn't kept
+        tower.tower_path = Some(Path::new(&ledger_path).join("missing").join("tower.bin"));
+        assert!(tower.record_vote_and_save(2, Hash::default()).is_err());
+        assert_eq!(tower.last_voted_slot(), Some(1));
+        assert_eq!(tower.recent_votes(), saved_tower.recent_votes);
+
+        // A tower saved by another node isn't restored
+        assert!(Tower::restore(&path, &Pubkey::new_rand()).is_err());
+        let _ignored = fs::remove_dir_all(&ledger_path);
+    }
+
+    #[test]
+    fn test_reconcile_saved_tower() {
+        let mut saved = Tower::new(EpochStakes::new_for_tests(2), 1, 0.67);
+        for slot in 0..3 {
+            saved.record_vote(slot, Hash::default());
+        }
+        let saved_tower = || SavedTower {
+            node_pubkey: Pubkey::default(),
+            lockouts: saved.lockouts.clone(),
+            recent_votes: saved.recent_votes(),
+        };
+
+        // The saved tower has votes that haven't landed yet
+        let mut tower = Tower::new(EpochStakes::new_for_tests(2), 1, 0.67);
+        tower.record_vote(0, Hash::default());
+        tower.reconcile(saved_tower());
+        assert_eq!(tower.last_voted_slot(), Some(2));
+        assert_eq!(tower.recent_votes(), saved.recent_votes());
+
+        assert_eq!(tower.lockouts.votes, saved.lockouts.votes);
+
+        // The saved tower is stale
+        let mut tower = Tower::new(EpochStakes::new_for_tests(2), 1, 0.67);
+        tower.record_vote(4, Hash::default());
+        tower.reconcile(saved_tower());
+        assert_eq!(tower.last_voted_slot(), Some(4));
+        assert_eq!(tower.recent_votes().len(), 1);
+
+        // The saved tower's last vote is later, but the vote on chain isn't in it: both are kept
+        let mut tower = Tower::new(EpochStakes::new_for_tests(2), 1, 0.67);
+        tower.record_vote(1, Hash::default());
+        tower.record_vote(2, Hash::default());
+        let mut saved = Tower::new(EpochStakes::new_for_tests(2), 1, 0.67);
+        saved.record_vote(3, Hash::default());
+        tower.reconcile(SavedTower {
+            node_pubkey: Pubkey::default(),
+            lockouts: saved.lockouts.clone(),
+            recent_votes: saved.recent_votes(),
+        });
+        assert_eq!(tower.voted_slots(), vec![1, 2, 3, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_adjust_to_root() {
+        let mut tower = Tower::new(EpochStakes::new_for_tests(2), 1, 0.67);
+        for slot in 0..4 {
+            tower.record_vote(slot, Hash::default());
+        }
+        tower.adjust_to_root(1);
+        assert_eq!(tower.root(), Some(1));
+        assert_eq!(tower.voted_slots(), vec![2, 3, 2, 3]);
+
+        // A later root in the tower is kept
+        tower.lockouts.root_slot = Some(5);
+        tower.adjust_to_root(1);
+        assert_eq!(tower.root(), Some(5));
+    }
+
+    #[test]
+    fn test_is_locked_out_root_not_in_forks() {
+        let mut tower = Tower::new(EpochStakes::new_for_tests(2), 0, 0.67);
+        let descendants = vec![(0, vec![1].into_iter().collect())]
+            .into_iter()
+            .collect();
+        tower.lockouts.root_slot = Some(3);
+        assert!(tower.is_locked_out(1, &descendants));
+    }
 }
@@ -155,6 +155,10 @@ impl CrdsGossip {
         self.pull
             .process_pull_request(&mut self.crds, caller, filter, now)
     }
+    /// answer a pull request without storing the caller's value
+    pub fn filter_pull_request(&self, mut filter: Bloom<Hash>) -> Vec<CrdsValue> {
+        self.pull.filter_crds_values(&self.crds, &mut filter)
+    }
     /// process a pull response
     pub fn process_pull_response(
         &mut self,
@@ -163,7 +163,7 @@ impl CrdsGossipPull {
         bloom
     }
     /// filter values that fail the bloom filter up to max_bytes
-    fn filter_crds_values(&self, crds: &Crds, filter: &mut Bloom<Hash>) -> Vec<CrdsValue> {
+    pub fn filter_crds_values(&self, crds: &Crds, filter: &mut Bloom<Hash>) -> Vec<CrdsValue> {
         let mut max_bytes = self.max_bytes as isize;
         let mut ret = vec![];
         for v in crds.table.values() {
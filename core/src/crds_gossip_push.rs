@@ -12,7 +12,7 @@ use crate::contact_info::ContactInfo;
 use crate::crds::{Crds, VersionedCrdsValue};
 use crate::crds_gossip::{get_stake, get_weight, CRDS_GOSSIP_BLOOM_SIZE};
 use crate::crds_gossip_error::CrdsGossipError;
-use crate::crds_value::{CrdsValue, CrdsValueLabel};
+use crate::crds_value::{CrdsValue, CrdsValueLabel, Reachability};
 use crate::packet::BLOB_DATA_SIZE;
 use crate::weighted_shuffle::weighted_shuffle;
 use bincode::serialized_size;
@@ -277,6 +277,15 @@ impl CrdsGossipPush {
         }
     }
 
+    /// Pushes to a pull-only node would be dropped by its NAT
+    fn is_pull_only(crds: &Crds, pubkey: &Pubkey) -> bool {
+        crds.lookup(&CrdsValueLabel::Reachability(*pubkey))
+            .and_then(CrdsValue::reachability)
+            .map_or(false, |reachability| {
+                reachability.reachability == Reachability::PullOnly
+            })
+    }
+
     fn push_options<'a>(
         &self,
         crds: &'a Crds,
@@ -287,7 +296,11 @@ impl CrdsGossipPush {
             .values()
             .filter(|v| v.value.contact_info().is_some())
             .map(|v| (v.value.contact_info().unwrap(), v))
-            .filter(|(info, _)| info.id != *self_id && ContactInfo::is_valid_address(&info.gossip))
+            .filter(|(info, _)| {
+                info.id != *self_id
+                    && ContactInfo::is_valid_address(&info.gossip)
+                    && !Self::is_pull_only(crds, &info.id)
+            })
             .map(|(info, value)| {
                 let max_weight = f32::from(u16::max_value()) - 1.0;
                 let last_updated: u64 = value.local_timestamp;
@@ -1,11 +1,17 @@
+use crate::blocktree::DuplicateSlotProof;
 use crate::contact_info::ContactInfo;
-use bincode::serialize;
+use crate::leader_slot_report::LeaderSlotTotals;
+use crate::packet::BLOB_DATA_SIZE;
+use bincode::{deserialize, serialize};
+use solana_sdk::hash::{hash, Hash};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, Signable, Signature};
 use solana_sdk::transaction::Transaction;
 use std::borrow::Cow;
+use std::cmp;
 use std::collections::BTreeSet;
 use std::fmt;
+use std::net::SocketAddr;
 
 /// CrdsValue that is replicated across the cluster
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -16,8 +22,27 @@ pub enum CrdsValue {
     Vote(Vote),
     /// * Merge Strategy - Latest wallclock is picked
     EpochSlots(EpochSlots),
+    /// * Merge Strategy - Latest wallclock is picked
+    DuplicateShred(DuplicateShred),
+    /// * Merge Strategy - Latest wallclock is picked
+    Faucet(Faucet),
+    /// * Merge Strategy - Latest wallclock is picked
+    LeaderSlots(LeaderSlots),
+    /// * Merge Strategy - Latest wallclock is picked
+    AccountsHashes(AccountsHashes),
+    /// * Merge Strategy - Latest wallclock is picked
+    Reachability(NodeReachability),
+    /// * Merge Strategy - Latest wallclock is picked
+    QuicTpu(QuicTpu),
+    /// * Merge Strategy - Latest wallclock is picked
+    Version(NodeVersion),
 }
 
+/// Largest number of chunks a duplicate slot proof is split into
+pub const MAX_DUPLICATE_SHRED_CHUNKS: u8 = 16;
+/// Size of each chunk, small enough that a few fit in one gossip push blob
+pub const DUPLICATE_SHRED_CHUNK_SIZE: usize = BLOB_DATA_SIZE / 4;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct EpochSlots {
     pub from: Pubkey,
@@ -68,6 +93,450 @@ impl Signable for EpochSlots {
     }
 }
 
+/// One chunk of a serialized `DuplicateSlotProof`. A proof holds two full blobs,
+/// more than fits in a single gossip message, so it is gossiped as
+/// `num_chunks` values that share `slot` and `wallclock`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DuplicateShred {
+    pub from: Pubkey,
+    pub slot: u64,
+    pub num_chunks: u8,
+    pub chunk_index: u8,
+    pub chunk: Vec<u8>,
+    pub signature: Signature,
+    pub wallclock: u64,
+}
+
+impl DuplicateShred {
+    /// Splits `proof` into unsigned chunks, or returns None if the proof
+    /// needs more than MAX_DUPLICATE_SHRED_CHUNKS chunks
+    pub fn from_proof(
+        from: Pubkey,
+        slot: u64,
+        proof: &DuplicateSlotProof,
+        wallclock: u64,
+    ) -> Option<Vec<Self>> {
+        let data = serialize(proof).expect("unable to serialize DuplicateSlotProof");
+        let chunks: Vec<_> = data.chunks(DUPLICATE_SHRED_CHUNK_SIZE).collect();
+        if chunks.len() > MAX_DUPLICATE_SHRED_CHUNKS as usize {
+            return None;
+        }
+        let num_chunks = chunks.len() as u8;
+        Some(
+            chunks
+                .into_iter()
+                .enumerate()
+                .map(|(chunk_index, chunk)| Self {
+                    from,
+                    slot,
+                    num_chunks,
+                    chunk_index: chunk_index as u8,
+                    chunk: chunk.to_vec(),
+                    signature: Signature::default(),
+                    wallclock,
+                })
+                .collect(),
+        )
+    }
+
+    /// Reassembles a proof from the chunks of a single `from_proof` call, in
+    /// any order. Returns None if chunks are missing or don't belong together.
+    pub fn into_proof(chunks: &[&DuplicateShred]) -> Option<DuplicateSlotProof> {
+        let first = chunks.first()?;
+        if chunks.len() != first.num_chunks as usize {
+            return None;
+        }
+        let mut ordered: Vec<Option<&DuplicateShred>> = vec![None; chunks.len()];
+        for chunk in chunks {
+            if chunk.from != first.from
+                || chunk.slot != first.slot
+                || chunk.wallclock != first.wallclock
+                || chunk.num_chunks != first.num_chunks
+            {
+                return None;
+            }
+            let entry = ordered.get_mut(chunk.chunk_index as usize)?;
+            if entry.is_some() {
+                return None;
+            }
+            *entry = Some(chunk);
+        }
+        let data: Vec<u8> = ordered
+            .into_iter()
+            .flat_map(|chunk| chunk.unwrap().chunk.iter().cloned())
+            .collect();
+        deserialize(&data).ok()
+    }
+}
+
+impl Signable for DuplicateShred {
+    fn pubkey(&self) -> Pubkey {
+        self.from
+    }
+
+    fn signable_data(&self) -> Cow<[u8]> {
+        #[derive(Serialize)]
+        struct SignData<'a> {
+            slot: u64,
+            num_chunks: u8,
+            chunk_index: u8,
+            chunk: &'a [u8],
+            wallclock: u64,
+        }
+        let data = SignData {
+            slot: self.slot,
+            num_chunks: self.num_chunks,
+            chunk_index: self.chunk_index,
+            chunk: &self.chunk,
+            wallclock: self.wallclock,
+        };
+        Cow::Owned(serialize(&data).expect("unable to serialize DuplicateShred"))
+    }
+
+    fn get_signature(&self) -> Signature {
+        self.signature
+    }
+
+    fn set_signature(&mut self, signature: Signature) {
+        self.signature = signature;
+    }
+}
+
+/// Address of an airdrop drone reachable through the node that gossips it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Faucet {
+    pub from: Pubkey,
+    pub addr: SocketAddr,
+    pub signature: Signature,
+    pub wallclock: u64,
+}
+
+impl Faucet {
+    pub fn new(from: &Pubkey, addr: SocketAddr, wallclock: u64) -> Self {
+        Self {
+            from: *from,
+            addr,
+            signature: Signature::default(),
+            wallclock,
+        }
+    }
+}
+
+impl Signable for Faucet {
+    fn pubkey(&self) -> Pubkey {
+        self.from
+    }
+
+    fn signable_data(&self) -> Cow<[u8]> {
+        #[derive(Serialize)]
+        struct SignData {
+            addr: SocketAddr,
+            wallclock: u64,
+        }
+        let data = SignData {
+            addr: self.addr,
+            wallclock: self.wallclock,
+        };
+        Cow::Owned(serialize(&data).expect("unable to serialize Faucet"))
+    }
+
+    fn get_signature(&self) -> Signature {
+        self.signature
+    }
+
+    fn set_signature(&mut self, signature: Signature) {
+        self.signature = signature;
+    }
+}
+
+/// How many of the node's leader slots up to `root` were rooted or skipped
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LeaderSlots {
+    pub from: Pubkey,
+    pub root: u64,
+    pub totals: LeaderSlotTotals,
+    pub signature: Signature,
+    pub wallclock: u64,
+}
+
+impl LeaderSlots {
+    pub fn new(from: &Pubkey, root: u64, totals: LeaderSlotTotals, wallclock: u64) -> Self {
+        Self {
+            from: *from,
+            root,
+            totals,
+            signature: Signature::default(),
+            wallclock,
+        }
+    }
+}
+
+impl Signable for LeaderSlots {
+    fn pubkey(&self) -> Pubkey {
+        self.from
+    }
+
+    fn signable_data(&self) -> Cow<[u8]> {
+        #[derive(Serialize)]
+        struct SignData {
+            root: u64,
+            totals: LeaderSlotTotals,
+            wallclock: u64,
+        }
+        let data = SignData {
+            root: self.root,
+            totals: self.totals,
+            wallclock: self.wallclock,
+        };
+        Cow::Owned(serialize(&data).expect("unable to serialize LeaderSlots"))
+    }
+
+    fn get_signature(&self) -> Signature {
+        self.signature
+    }
+
+    fn set_signature(&mut self, signature: Signature) {
+        self.signature = signature;
+    }
+}
+
+/// The node's bank hashes of its most recent epoch boundary slots, as (slot, hash)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AccountsHashes {
+    pub from: Pubkey,
+    pub hashes: Vec<(u64, Hash)>,
+    pub signature: Signature,
+    pub wallclock: u64,
+}
+
+impl AccountsHashes {
+    pub fn new(from: &Pubkey, hashes: Vec<(u64, Hash)>, wallclock: u64) -> Self {
+        Self {
+            from: *from,
+            hashes,
+            signature: Signature::default(),
+            wallclock,
+        }
+    }
+}
+
+impl Signable for AccountsHashes {
+    fn pubkey(&self) -> Pubkey {
+        self.from
+    }
+
+    fn signable_data(&self) -> Cow<[u8]> {
+        #[derive(Serialize)]
+        struct SignData<'a> {
+            hashes: &'a [(u64, Hash)],
+            wallclock: u64,
+        }
+        let data = SignData {
+            hashes: &self.hashes,
+            wallclock: self.wallclock,
+        };
+        Cow::Owned(serialize(&data).expect("unable to serialize AccountsHashes"))
+    }
+
+    fn get_signature(&self) -> Signature {
+        self.signature
+    }
+
+    fn set_signature(&mut self, signature: Signature) {
+        self.signature = signature;
+    }
+}
+
+/// Whether other nodes can reach a node's gossip address unprompted
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reachability {
+    /// The node hasn't learned how the cluster sees its gossip address yet
+    Unknown,
+    /// The gossip address is the one the cluster sees, the node isn't behind a NAT
+    Public,
+    /// The node is behind a NAT, but hole punching opened a path from some peers
+    HolePunched,
+    /// The node is behind a NAT that hole punching couldn't get through, so it only
+    /// receives gossip in response to its own pull requests
+    PullOnly,
+}
+
+impl Default for Reachability {
+    fn default() -> Self {
+        Reachability::Unknown
+    }
+}
+
+/// How reachable the node's gossip address is, see `Reachability`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NodeReachability {
+    pub from: Pubkey,
+    pub reachability: Reachability,
+    pub signature: Signature,
+    pub wallclock: u64,
+}
+
+impl NodeReachability {
+    pub fn new(from: &Pubkey, reachability: Reachability, wallclock: u64) -> Self {
+        Self {
+            from: *from,
+            reachability,
+            signature: Signature::default(),
+            wallclock,
+        }
+    }
+}
+
+impl Signable for NodeReachability {
+    fn pubkey(&self) -> Pubkey {
+        self.from
+    }
+
+    fn signable_data(&self) -> Cow<[u8]> {
+        #[derive(Serialize)]
+        struct SignData {
+            reachability: Reachability,
+            wallclock: u64,
+        }
+        let data = SignData {
+            reachability: self.reachability,
+            wallclock: self.wallclock,
+        };
+        Cow::Owned(serialize(&data).expect("unable to serialize NodeReachability"))
+    }
+
+    fn get_signature(&self) -> Signature {
+        self.signature
+    }
+
+    fn set_signature(&mut self, signature: Signature) {
+        self.signature = signature;
+    }
+}
+
+/// Address of the node's QUIC TPU, which proves the node's identity with its certificate
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QuicTpu {
+    pub from: Pubkey,
+    pub addr: SocketAddr,
+    pub signature: Signature,
+    pub wallclock: u64,
+}
+
+impl QuicTpu {
+    pub fn new(from: &Pubkey, addr: SocketAddr, wallclock: u64) -> Self {
+        Self {
+            from: *from,
+            addr,
+            signature: Signature::default(),
+            wallclock,
+        }
+    }
+}
+
+impl Signable for QuicTpu {
+    fn pubkey(&self) -> Pubkey {
+        self.from
+    }
+
+    fn signable_data(&self) -> Cow<[u8]> {
+        #[derive(Serialize)]
+        struct SignData {
+            addr: SocketAddr,
+            wallclock: u64,
+        }
+        let data = SignData {
+            addr: self.addr,
+            wallclock: self.wallclock,
+        };
+        Cow::Owned(serialize(&data).expect("unable to serialize QuicTpu"))
+    }
+
+    fn get_signature(&self) -> Signature {
+        self.signature
+    }
+
+    fn set_signature(&mut self, signature: Signature) {
+        self.signature = signature;
+    }
+}
+
+/// The shred version of a cluster with genesis blockhash `genesis_blockhash`. Nodes of clusters
+/// from different genesis blocks don't gossip or repair with each other. Never 0, which stands
+/// for an unknown version.
+pub fn compute_shred_version(genesis_blockhash: &Hash) -> u16 {
+    let version = genesis_blockhash
+        .as_ref()
+        .chunks(2)
+        .fold(0, |version, chunk| {
+            version ^ u16::from_le_bytes([chunk[0], chunk[1]])
+        });
+    cmp::max(version, 1)
+}
+
+/// Identifies the wire features of this software. Releases of the same major and minor version
+/// support the same features, so only those parts of the version go into it.
+pub fn compute_feature_set() -> u32 {
+    let release: Vec<_> = env!("CARGO_PKG_VERSION").split('.').take(2).collect();
+    let digest = hash(release.join(".").as_bytes());
+    let bytes = digest.as_ref();
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// The cluster and the software a node runs. Nodes only gossip and repair with nodes they know
+/// to be of their own cluster, see `ClusterInfo::set_shred_version`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NodeVersion {
+    pub from: Pubkey,
+    /// See `compute_shred_version`
+    pub shred_version: u16,
+    /// See `compute_feature_set`
+    pub feature_set: u32,
+    pub signature: Signature,
+    pub wallclock: u64,
+}
+
+impl NodeVersion {
+    pub fn new(from: &Pubkey, shred_version: u16, feature_set: u32, wallclock: u64) -> Self {
+        Self {
+            from: *from,
+            shred_version,
+            feature_set,
+            signature: Signature::default(),
+            wallclock,
+        }
+    }
+}
+
+impl Signable for NodeVersion {
+    fn pubkey(&self) -> Pubkey {
+        self.from
+    }
+
+    fn signable_data(&self) -> Cow<[u8]> {
+        #[derive(Serialize)]
+        struct SignData {
+            shred_version: u16,
+            feature_set: u32,
+            wallclock: u64,
+        }
+        let data = SignData {
+            shred_version: self.shred_version,
+            feature_set: self.feature_set,
+            wallclock: self.wallclock,
+        };
+        Cow::Owned(serialize(&data).expect("unable to serialize NodeVersion"))
+    }
+
+    fn get_signature(&self) -> Signature {
+        self.signature
+    }
+
+    fn set_signature(&mut self, signature: Signature) {
+        self.signature = signature;
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Vote {
     pub from: Pubkey,
@@ -121,6 +590,13 @@ pub enum CrdsValueLabel {
     ContactInfo(Pubkey),
     Vote(Pubkey),
     EpochSlots(Pubkey),
+    DuplicateShred(Pubkey, u8),
+    Faucet(Pubkey),
+    LeaderSlots(Pubkey),
+    AccountsHashes(Pubkey),
+    Reachability(Pubkey),
+    QuicTpu(Pubkey),
+    Version(Pubkey),
 }
 
 impl fmt::Display for CrdsValueLabel {
@@ -129,6 +605,15 @@ impl fmt::Display for CrdsValueLabel {
             CrdsValueLabel::ContactInfo(_) => write!(f, "ContactInfo({})", self.pubkey()),
             CrdsValueLabel::Vote(_) => write!(f, "Vote({})", self.pubkey()),
             CrdsValueLabel::EpochSlots(_) => write!(f, "EpochSlots({})", self.pubkey()),
+            CrdsValueLabel::DuplicateShred(_, i) => {
+                write!(f, "DuplicateShred({}, {})", self.pubkey(), i)
+            }
+            CrdsValueLabel::Faucet(_) => write!(f, "Faucet({})", self.pubkey()),
+            CrdsValueLabel::LeaderSlots(_) => write!(f, "LeaderSlots({})", self.pubkey()),
+            CrdsValueLabel::AccountsHashes(_) => write!(f, "AccountsHashes({})", self.pubkey()),
+            CrdsValueLabel::Reachability(_) => write!(f, "Reachability({})", self.pubkey()),
+            CrdsValueLabel::QuicTpu(_) => write!(f, "QuicTpu({})", self.pubkey()),
+            CrdsValueLabel::Version(_) => write!(f, "Version({})", self.pubkey()),
         }
     }
 }
@@ -139,6 +624,13 @@ impl CrdsValueLabel {
             CrdsValueLabel::ContactInfo(p) => *p,
             CrdsValueLabel::Vote(p) => *p,
             CrdsValueLabel::EpochSlots(p) => *p,
+            CrdsValueLabel::DuplicateShred(p, _) => *p,
+            CrdsValueLabel::Faucet(p) => *p,
+            CrdsValueLabel::LeaderSlots(p) => *p,
+            CrdsValueLabel::AccountsHashes(p) => *p,
+            CrdsValueLabel::Reachability(p) => *p,
+            CrdsValueLabel::QuicTpu(p) => *p,
+            CrdsValueLabel::Version(p) => *p,
         }
     }
 }
@@ -152,6 +644,13 @@ impl CrdsValue {
             CrdsValue::ContactInfo(contact_info) => contact_info.wallclock,
             CrdsValue::Vote(vote) => vote.wallclock,
             CrdsValue::EpochSlots(vote) => vote.wallclock,
+            CrdsValue::DuplicateShred(shred) => shred.wallclock,
+            CrdsValue::Faucet(faucet) => faucet.wallclock,
+            CrdsValue::LeaderSlots(slots) => slots.wallclock,
+            CrdsValue::AccountsHashes(hashes) => hashes.wallclock,
+            CrdsValue::Reachability(reachability) => reachability.wallclock,
+            CrdsValue::QuicTpu(quic_tpu) => quic_tpu.wallclock,
+            CrdsValue::Version(version) => version.wallclock,
         }
     }
     pub fn label(&self) -> CrdsValueLabel {
@@ -161,6 +660,17 @@ impl CrdsValue {
             }
             CrdsValue::Vote(vote) => CrdsValueLabel::Vote(vote.pubkey()),
             CrdsValue::EpochSlots(slots) => CrdsValueLabel::EpochSlots(slots.pubkey()),
+            CrdsValue::DuplicateShred(shred) => {
+                CrdsValueLabel::DuplicateShred(shred.pubkey(), shred.chunk_index)
+            }
+            CrdsValue::Faucet(faucet) => CrdsValueLabel::Faucet(faucet.pubkey()),
+            CrdsValue::LeaderSlots(slots) => CrdsValueLabel::LeaderSlots(slots.pubkey()),
+            CrdsValue::AccountsHashes(hashes) => CrdsValueLabel::AccountsHashes(hashes.pubkey()),
+            CrdsValue::Reachability(reachability) => {
+                CrdsValueLabel::Reachability(reachability.pubkey())
+            }
+            CrdsValue::QuicTpu(quic_tpu) => CrdsValueLabel::QuicTpu(quic_tpu.pubkey()),
+            CrdsValue::Version(version) => CrdsValueLabel::Version(version.pubkey()),
         }
     }
     pub fn contact_info(&self) -> Option<&ContactInfo> {
@@ -181,13 +691,65 @@ impl CrdsValue {
             _ => None,
         }
     }
+    pub fn duplicate_shred(&self) -> Option<&DuplicateShred> {
+        match self {
+            CrdsValue::DuplicateShred(shred) => Some(shred),
+            _ => None,
+        }
+    }
+    pub fn faucet(&self) -> Option<&Faucet> {
+        match self {
+            CrdsValue::Faucet(faucet) => Some(faucet),
+            _ => None,
+        }
+    }
+    pub fn leader_slots(&self) -> Option<&LeaderSlots> {
+        match self {
+            CrdsValue::LeaderSlots(slots) => Some(slots),
+            _ => None,
+        }
+    }
+    pub fn accounts_hashes(&self) -> Option<&AccountsHashes> {
+        match self {
+            CrdsValue::AccountsHashes(hashes) => Some(hashes),
+            _ => None,
+        }
+    }
+    pub fn reachability(&self) -> Option<&NodeReachability> {
+        match self {
+            CrdsValue::Reachability(reachability) => Some(reachability),
+            _ => None,
+        }
+    }
+    pub fn quic_tpu(&self) -> Option<&QuicTpu> {
+        match self {
+            CrdsValue::QuicTpu(quic_tpu) => Some(quic_tpu),
+            _ => None,
+        }
+    }
+    pub fn version(&self) -> Option<&NodeVersion> {
+        match self {
+            CrdsValue::Version(version) => Some(version),
+            _ => None,
+        }
+    }
     /// Return all the possible labels for a record identified by Pubkey.
-    pub fn record_labels(key: &Pubkey) -> [CrdsValueLabel; 3] {
-        [
+    pub fn record_labels(key: &Pubkey) -> Vec<CrdsValueLabel> {
+        let mut labels = vec![
             CrdsValueLabel::ContactInfo(*key),
             CrdsValueLabel::Vote(*key),
             CrdsValueLabel::EpochSlots(*key),
-        ]
+            CrdsValueLabel::Faucet(*key),
+            CrdsValueLabel::LeaderSlots(*key),
+            CrdsValueLabel::AccountsHashes(*key),
+            CrdsValueLabel::Reachability(*key),
+            CrdsValueLabel::QuicTpu(*key),
+            CrdsValueLabel::Version(*key),
+        ];
+        labels.extend(
+            (0..MAX_DUPLICATE_SHRED_CHUNKS).map(|i| CrdsValueLabel::DuplicateShred(*key, i)),
+        );
+        labels
     }
 }
 
@@ -197,6 +759,13 @@ impl Signable for CrdsValue {
             CrdsValue::ContactInfo(contact_info) => contact_info.sign(keypair),
             CrdsValue::Vote(vote) => vote.sign(keypair),
             CrdsValue::EpochSlots(epoch_slots) => epoch_slots.sign(keypair),
+            CrdsValue::DuplicateShred(shred) => shred.sign(keypair),
+            CrdsValue::Faucet(faucet) => faucet.sign(keypair),
+            CrdsValue::LeaderSlots(slots) => slots.sign(keypair),
+            CrdsValue::AccountsHashes(hashes) => hashes.sign(keypair),
+            CrdsValue::Reachability(reachability) => reachability.sign(keypair),
+            CrdsValue::QuicTpu(quic_tpu) => quic_tpu.sign(keypair),
+            CrdsValue::Version(version) => version.sign(keypair),
         };
     }
 
@@ -205,6 +774,13 @@ impl Signable for CrdsValue {
             CrdsValue::ContactInfo(contact_info) => contact_info.verify(),
             CrdsValue::Vote(vote) => vote.verify(),
             CrdsValue::EpochSlots(epoch_slots) => epoch_slots.verify(),
+            CrdsValue::DuplicateShred(shred) => shred.verify(),
+            CrdsValue::Faucet(faucet) => faucet.verify(),
+            CrdsValue::LeaderSlots(slots) => slots.verify(),
+            CrdsValue::AccountsHashes(hashes) => hashes.verify(),
+            CrdsValue::Reachability(reachability) => reachability.verify(),
+            CrdsValue::QuicTpu(quic_tpu) => quic_tpu.verify(),
+            CrdsValue::Version(version) => version.verify(),
         }
     }
 
@@ -213,6 +789,13 @@ impl Signable for CrdsValue {
             CrdsValue::ContactInfo(contact_info) => contact_info.pubkey(),
             CrdsValue::Vote(vote) => vote.pubkey(),
             CrdsValue::EpochSlots(epoch_slots) => epoch_slots.pubkey(),
+            CrdsValue::DuplicateShred(shred) => shred.pubkey(),
+            CrdsValue::Faucet(faucet) => faucet.pubkey(),
+            CrdsValue::LeaderSlots(slots) => slots.pubkey(),
+            CrdsValue::AccountsHashes(hashes) => hashes.pubkey(),
+            CrdsValue::Reachability(reachability) => reachability.pubkey(),
+            CrdsValue::QuicTpu(quic_tpu) => quic_tpu.pubkey(),
+            CrdsValue::Version(version) => version.pubkey(),
         }
     }
 
@@ -225,6 +808,13 @@ impl Signable for CrdsValue {
             CrdsValue::ContactInfo(contact_info) => contact_info.get_signature(),
             CrdsValue::Vote(vote) => vote.get_signature(),
             CrdsValue::EpochSlots(epoch_slots) => epoch_slots.get_signature(),
+            CrdsValue::DuplicateShred(shred) => shred.get_signature(),
+            CrdsValue::Faucet(faucet) => faucet.get_signature(),
+            CrdsValue::LeaderSlots(slots) => slots.get_signature(),
+            CrdsValue::AccountsHashes(hashes) => hashes.get_signature(),
+            CrdsValue::Reachability(reachability) => reachability.get_signature(),
+            CrdsValue::QuicTpu(quic_tpu) => quic_tpu.get_signature(),
+            CrdsValue::Version(version) => version.get_signature(),
         }
     }
 
@@ -238,19 +828,25 @@ mod test {
     use super::*;
     use crate::contact_info::ContactInfo;
     use crate::test_tx::test_tx;
-    use bincode::deserialize;
     use solana_sdk::signature::{Keypair, KeypairUtil};
     use solana_sdk::timing::timestamp;
 
     #[test]
     fn test_labels() {
-        let mut hits = [false; 3];
+        let mut hits = [false; 10];
         // this method should cover all the possible labels
         for v in &CrdsValue::record_labels(&Pubkey::default()) {
             match v {
                 CrdsValueLabel::ContactInfo(_) => hits[0] = true,
                 CrdsValueLabel::Vote(_) => hits[1] = true,
                 CrdsValueLabel::EpochSlots(_) => hits[2] = true,
+                CrdsValueLabel::DuplicateShred(_, _) => hits[3] = true,
+                CrdsValueLabel::Faucet(_) => hits[4] = true,
+                CrdsValueLabel::LeaderSlots(_) => hits[5] = true,
+                CrdsValueLabel::AccountsHashes(_) => hits[6] = true,
+                CrdsValueLabel::Reachability(_) => hits[7] = true,
+                CrdsValueLabel::QuicTpu(_) => hits[8] = true,
+                CrdsValueLabel::Version(_) => hits[9] = true,
             }
         }
         assert!(hits.iter().all(|x| *x));
@@ -271,6 +867,42 @@ mod test {
         assert_eq!(v.wallclock(), 0);
         let key = v.clone().epoch_slots().unwrap().from;
         assert_eq!(v.label(), CrdsValueLabel::EpochSlots(key));
+
+        let addr = socketaddr!("127.0.0.1:9900");
+        let v = CrdsValue::Faucet(Faucet::new(&Pubkey::default(), addr, 0));
+        assert_eq!(v.wallclock(), 0);
+        let key = v.clone().faucet().unwrap().from;
+        assert_eq!(v.label(), CrdsValueLabel::Faucet(key));
+
+        let totals = LeaderSlotTotals::default();
+        let v = CrdsValue::LeaderSlots(LeaderSlots::new(&Pubkey::default(), 0, totals, 0));
+        assert_eq!(v.wallclock(), 0);
+        let key = v.clone().leader_slots().unwrap().from;
+        assert_eq!(v.label(), CrdsValueLabel::LeaderSlots(key));
+
+        let v = CrdsValue::AccountsHashes(AccountsHashes::new(&Pubkey::default(), vec![], 0));
+        assert_eq!(v.wallclock(), 0);
+        let key = v.clone().accounts_hashes().unwrap().from;
+        assert_eq!(v.label(), CrdsValueLabel::AccountsHashes(key));
+
+        let v = CrdsValue::Reachability(NodeReachability::new(
+            &Pubkey::default(),
+            Reachability::PullOnly,
+            0,
+        ));
+        assert_eq!(v.wallclock(), 0);
+        let key = v.clone().reachability().unwrap().from;
+        assert_eq!(v.label(), CrdsValueLabel::Reachability(key));
+
+        let v = CrdsValue::QuicTpu(QuicTpu::new(&Pubkey::default(), addr, 0));
+        assert_eq!(v.wallclock(), 0);
+        let key = v.clone().quic_tpu().unwrap().from;
+        assert_eq!(v.label(), CrdsValueLabel::QuicTpu(key));
+
+        let v = CrdsValue::Version(NodeVersion::new(&Pubkey::default(), 1, 0, 0));
+        assert_eq!(v.wallclock(), 0);
+        let key = v.clone().version().unwrap().from;
+        assert_eq!(v.label(), CrdsValueLabel::Version(key));
     }
     #[test]
     fn test_signature() {
@@ -284,6 +916,77 @@ mod test {
         let btreeset: BTreeSet<u64> = vec![1, 2, 3, 6, 8].into_iter().collect();
         v = CrdsValue::EpochSlots(EpochSlots::new(keypair.pubkey(), 0, btreeset, timestamp()));
         verify_signatures(&mut v, &keypair, &wrong_keypair);
+        let proof = DuplicateSlotProof::default();
+        let shred = DuplicateShred::from_proof(keypair.pubkey(), 0, &proof, timestamp()).unwrap();
+        v = CrdsValue::DuplicateShred(shred[0].clone());
+        verify_signatures(&mut v, &keypair, &wrong_keypair);
+        let addr = socketaddr!("127.0.0.1:9900");
+        v = CrdsValue::Faucet(Faucet::new(&keypair.pubkey(), addr, timestamp()));
+        verify_signatures(&mut v, &keypair, &wrong_keypair);
+        let totals = LeaderSlotTotals::default();
+        v = CrdsValue::LeaderSlots(LeaderSlots::new(&keypair.pubkey(), 0, totals, timestamp()));
+        verify_signatures(&mut v, &keypair, &wrong_keypair);
+        let hashes = vec![(0, Hash::default())];
+        v = CrdsValue::AccountsHashes(AccountsHashes::new(&keypair.pubkey(), hashes, timestamp()));
+        verify_signatures(&mut v, &keypair, &wrong_keypair);
+        v = CrdsValue::Reachability(NodeReachability::new(
+            &keypair.pubkey(),
+            Reachability::Public,
+            timestamp(),
+        ));
+        verify_signatures(&mut v, &keypair, &wrong_keypair);
+        v = CrdsValue::QuicTpu(QuicTpu::new(&keypair.pubkey(), addr, timestamp()));
+        verify_signatures(&mut v, &keypair, &wrong_keypair);
+        let feature_set = compute_feature_set();
+        v = CrdsValue::Version(NodeVersion::new(
+            &keypair.pubkey(),
+            1,
+            feature_set,
+            timestamp(),
+        ));
+        verify_signatures(&mut v, &keypair, &wrong_keypair);
+    }
+
+    #[test]
+    fn test_compute_shred_version() {
+        let version = compute_shred_version(&hash(&[1, 2, 3]));
+        assert_ne!(version, 0);
+        assert_eq!(version, compute_shred_version(&hash(&[1, 2, 3])));
+        assert_eq!(compute_shred_version(&Hash::default()), 1);
+    }
+
+    #[test]
+    fn test_duplicate_shred_chunks() {
+        let from = Pubkey::new_rand();
+        let proof = DuplicateSlotProof {
+            index: 3,
+            blob1: vec![1; DUPLICATE_SHRED_CHUNK_SIZE],
+            blob2: vec![2; DUPLICATE_SHRED_CHUNK_SIZE],
+        };
+        let chunks = DuplicateShred::from_proof(from, 5, &proof, 0).unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.chunk.len() <= DUPLICATE_SHRED_CHUNK_SIZE));
+
+        let mut refs: Vec<_> = chunks.iter().rev().collect();
+        assert_eq!(DuplicateShred::into_proof(&refs), Some(proof.clone()));
+
+        // A missing chunk can't be reassembled
+        refs.pop();
+        assert_eq!(DuplicateShred::into_proof(&refs), None);
+
+        // Neither can chunks from different proofs
+        let other = DuplicateShred::from_proof(from, 5, &proof, 1).unwrap();
+        refs.push(&other[0]);
+        assert_eq!(DuplicateShred::into_proof(&refs), None);
+
+        let too_big = DuplicateSlotProof {
+            index: 0,
+            blob1: vec![0; DUPLICATE_SHRED_CHUNK_SIZE * MAX_DUPLICATE_SHRED_CHUNKS as usize],
+            blob2: vec![],
+        };
+        assert_eq!(DuplicateShred::from_proof(from, 5, &too_big, 0), None);
     }
 
     fn test_serialize_deserialize_value(value: &mut CrdsValue, keypair: &Keypair) {
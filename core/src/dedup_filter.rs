@@ -0,0 +1,144 @@
+//! The `dedup_filter` module lets the banking stage cheaply drop packets holding a transaction it
+//! has recently seen. A transaction sent to several nodes, or forwarded among them, would
+//! otherwise be executed again and only rejected by the status cache late in the pipeline.
+//!
+//! Signatures are kept in a pair of bloom filters that take turns being cleared, so a signature
+//! is forgotten after a while and a client can retry a transaction that was dropped elsewhere.
+//! A false positive drops a new transaction, which its client then has to retry.
+
+use crate::packet::Packets;
+use crate::sigverify;
+use solana_runtime::bloom::Bloom;
+use solana_sdk::signature::Signature;
+use solana_sdk::timing::timestamp;
+use std::mem;
+
+/// Signatures each filter holds before the filters rotate
+pub const DEDUP_FILTER_MAX_SIGNATURES: usize = 100_000;
+
+/// How long the filters hold signatures before they rotate, in milliseconds
+pub const DEDUP_FILTER_ROTATE_MS: u64 = 2_000;
+
+const DEDUP_FILTER_FALSE_RATE: f64 = 0.001;
+
+const DEDUP_FILTER_MAX_BITS: usize = 8 * 1024 * 1024;
+
+pub struct DedupFilter {
+    current: Bloom<Signature>,
+    /// The signatures seen before the last rotation
+    previous: Bloom<Signature>,
+    current_signatures: usize,
+    rotated_at: u64,
+    max_signatures: usize,
+    rotate_ms: u64,
+}
+
+impl DedupFilter {
+    pub fn new(max_signatures: usize, rotate_ms: u64) -> Self {
+        Self {
+            current: Self::new_bloom(max_signatures),
+            previous: Self::new_bloom(max_signatures),
+            current_signatures: 0,
+            rotated_at: timestamp(),
+            max_signatures,
+            rotate_ms,
+        }
+    }
+
+    fn new_bloom(max_signatures: usize) -> Bloom<Signature> {
+        Bloom::random(
+            max_signatures,
+            DEDUP_FILTER_FALSE_RATE,
+            DEDUP_FILTER_MAX_BITS,
+        )
+    }
+
+    fn maybe_rotate(&mut self, now: u64) {
+        if self.current_signatures >= self.max_signatures
+            || now.saturating_sub(self.rotated_at) >= self.rotate_ms
+        {
+            let current = mem::replace(&mut self.current, Self::new_bloom(self.max_signatures));
+            self.previous = current;
+            self.current_signatures = 0;
+            self.rotated_at = now;
+        }
+    }
+
+    /// Records `signature`, returning whether it was probably seen already
+    pub fn check_and_insert(&mut self, signature: &Signature, now: u64) -> bool {
+        self.maybe_rotate(now);
+        if self.current.contains(signature) || self.previous.contains(signature) {
+            return true;
+        }
+        self.current.add(signature);
+        self.current_signatures += 1;
+        false
+    }
+
+    /// The `packet_indexes` of `msgs` whose transactions weren't seen already, along with how
+    /// many were dropped
+    pub fn dedup_packets(
+        &mut self,
+        msgs: &Packets,
+        packet_indexes: Vec<usize>,
+    ) -> (Vec<usize>, usize) {
+        let now = timestamp();
+        let num_packets = packet_indexes.len();
+        let packet_indexes: Vec<_> = packet_indexes
+            .into_iter()
+            .filter(
+                |index| match sigverify::packet_signature(&msgs.packets[*index]) {
+                    Some(signature) => !self.check_and_insert(&signature, now),
+                    // Leave malformed packets for deserialization to drop
+                    None => true,
+                },
+            )
+            .collect();
+        let num_dropped = num_packets - packet_indexes.len();
+        (packet_indexes, num_dropped)
+    }
+}
+
+impl Default for DedupFilter {
+    fn default() -> Self {
+        Self::new(DEDUP_FILTER_MAX_SIGNATURES, DEDUP_FILTER_ROTATE_MS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::to_packets;
+    use crate::test_tx::test_tx;
+
+    #[test]
+    fn test_check_and_insert() {
+        let mut filter = DedupFilter::new(DEDUP_FILTER_MAX_SIGNATURES, 1_000);
+        // Keep the blooms large enough to make false positives unlikely
+        filter.max_signatures = 2;
+        let now = filter.rotated_at;
+        let a = Signature::new(&[1; 64]);
+        let b = Signature::new(&[2; 64]);
+        let c = Signature::new(&[3; 64]);
+        assert!(!filter.check_and_insert(&a, now));
+        assert!(filter.check_and_insert(&a, now));
+        assert!(!filter.check_and_insert(&b, now));
+
+        // A full filter rotates, but still remembers its signatures until the next rotation
+        assert!(!filter.check_and_insert(&c, now));
+        assert!(filter.check_and_insert(&a, now));
+
+        // Signatures are forgotten two rotations after they were seen
+        assert!(filter.check_and_insert(&c, now + 1_000));
+        assert!(!filter.check_and_insert(&a, now + 2_000));
+    }
+
+    #[test]
+    fn test_dedup_packets() {
+        let mut filter = DedupFilter::default();
+        let tx = test_tx();
+        let msgs = to_packets(&[tx.clone(), test_tx(), tx]).remove(0);
+        assert_eq!(filter.dedup_packets(&msgs, vec![0, 1, 2]), (vec![0, 1], 1));
+        assert_eq!(filter.dedup_packets(&msgs, vec![0, 1]), (vec![], 2));
+    }
+}
@@ -2,6 +2,7 @@
 //! unique ID that is the hash of the Entry before it, plus the hash of the
 //! transactions within it. Entries cannot be reordered, and its field `num_hashes`
 //! represents an approximate amount of time since the last Entry was created.
+use crate::hash_chain::{hash_chain, hash_chains, HASH_CHAIN_LANES};
 use crate::packet::{Blob, SharedBlob, BLOB_DATA_SIZE};
 use crate::poh::Poh;
 use crate::result::Result;
@@ -21,7 +22,7 @@ use std::sync::mpsc::{Receiver, Sender};
 use std::sync::{Arc, RwLock};
 
 #[cfg(feature = "cuda")]
-use crate::sigverify::poh_verify_many;
+use crate::sigverify::{self, poh_verify_many};
 use solana_sdk::timing;
 #[cfg(feature = "cuda")]
 use std::sync::Mutex;
@@ -190,12 +191,18 @@ pub fn hash_transactions(transactions: &[Transaction]) -> Hash {
 /// the signature.  If num_hashes is zero and there's no transaction data,
 ///  start_hash is returned.
 fn next_hash(start_hash: &Hash, num_hashes: u64, transactions: &[Transaction]) -> Hash {
+    let chain_hash = hash_chain(start_hash, num_hashes.saturating_sub(1));
+    last_hash(&chain_hash, num_hashes, transactions)
+}
+
+/// Creates the last hash of an entry from `chain_hash`, the hash `num_hashes - 1` after the
+/// entry's start hash
+fn last_hash(chain_hash: &Hash, num_hashes: u64, transactions: &[Transaction]) -> Hash {
     if num_hashes == 0 && transactions.is_empty() {
-        return *start_hash;
+        return *chain_hash;
     }
 
-    let mut poh = Poh::new(*start_hash, None);
-    poh.hash(num_hashes.saturating_sub(1));
+    let mut poh = Poh::new(*chain_hash, None);
     if transactions.is_empty() {
         poh.tick().unwrap().hash
     } else {
@@ -228,6 +235,10 @@ where
 pub trait EntrySlice {
     /// Verifies the hashes and counts of a slice of transactions are all consistent.
     fn verify_cpu(&self, start_hash: &Hash) -> bool;
+    /// Like `verify_cpu`, but hashes large slices on the GPU `sigverify::init` set up
+    #[cfg(feature = "cuda")]
+    fn verify_cuda(&self, start_hash: &Hash) -> bool;
+    /// Verifies on the GPU if there is one, on the CPU otherwise
     fn verify(&self, start_hash: &Hash) -> bool;
     fn to_shared_blobs(&self) -> Vec<SharedBlob>;
     fn to_blobs(&self) -> Vec<Blob>;
@@ -243,11 +254,22 @@ impl EntrySlice for [Entry] {
             hash: *start_hash,
             transactions: vec![],
         }];
+        // The chains of hashes leading up to the last hash of each entry are independent, so
+        // they're hashed several at a time
+        let mut chains: Vec<_> = genesis
+            .iter()
+            .chain(self)
+            .zip(self)
+            .map(|(x0, x1)| (x0.hash, x1.num_hashes.saturating_sub(1)))
+            .collect();
         let entry_pairs = genesis.par_iter().chain(self).zip(self);
         let res = PAR_THREAD_POOL.with(|thread_pool| {
             thread_pool.borrow().install(|| {
-                entry_pairs.all(|(x0, x1)| {
-                    let r = x1.verify(&x0.hash);
+                chains
+                    .par_chunks_mut(HASH_CHAIN_LANES)
+                    .for_each(hash_chains);
+                entry_pairs.zip(&chains).all(|((x0, x1), (chain_hash, _))| {
+                    let r = x1.hash == last_hash(chain_hash, x1.num_hashes, &x1.transactions);
                     if !r {
                         warn!(
                             "entry invalid!: x0: {:?}, x1: {:?} num txs: {}",
@@ -267,13 +289,18 @@ impl EntrySlice for [Entry] {
         res
     }
 
-    #[cfg(not(feature = "cuda"))]
     fn verify(&self, start_hash: &Hash) -> bool {
+        #[cfg(feature = "cuda")]
+        {
+            if sigverify::gpu_ready() {
+                return self.verify_cuda(start_hash);
+            }
+        }
         self.verify_cpu(start_hash)
     }
 
     #[cfg(feature = "cuda")]
-    fn verify(&self, start_hash: &Hash) -> bool {
+    fn verify_cuda(&self, start_hash: &Hash) -> bool {
         inc_new_counter_warn!("entry_verify-num_entries", self.len() as usize);
 
         // Use CPU verify if the batch length is < 1K
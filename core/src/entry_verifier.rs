@@ -0,0 +1,179 @@
+//! The `entry_verifier` module verifies the PoH hash chain of entries on a thread of its own, so
+//! that replay can process a batch of entries' transactions while the batch is being verified.
+//! Verification goes through a `PohVerifier`: the GPU poh-verify kernel for large batches on
+//! nodes built with the `cuda` feature that have a card, rayon across the batch otherwise.
+
+use crate::entry::{Entry, EntrySlice};
+#[cfg(feature = "cuda")]
+use crate::sigverify;
+use solana_metrics::inc_new_counter_info;
+use solana_sdk::hash::Hash;
+use solana_sdk::timing::duration_as_ms;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{Builder, JoinHandle};
+use std::time::Instant;
+
+/// Verifies that a batch of entries chains from a hash
+pub trait PohVerifier: Send + Sync {
+    fn verify(&self, entries: &[Entry], start_hash: &Hash) -> bool;
+}
+
+/// Hashes on the CPU cores with rayon
+pub struct CpuPohVerifier;
+
+impl PohVerifier for CpuPohVerifier {
+    fn verify(&self, entries: &[Entry], start_hash: &Hash) -> bool {
+        entries.verify_cpu(start_hash)
+    }
+}
+
+/// Hashes large batches on the GPU, in the CUDA context sigverify set up
+#[cfg(feature = "cuda")]
+pub struct CudaPohVerifier;
+
+#[cfg(feature = "cuda")]
+impl PohVerifier for CudaPohVerifier {
+    fn verify(&self, entries: &[Entry], start_hash: &Hash) -> bool {
+        entries.verify_cuda(start_hash)
+    }
+}
+
+/// The GPU verifier if this node has a card sigverify could set up, the CPU verifier otherwise
+pub fn new_poh_verifier() -> Arc<dyn PohVerifier> {
+    #[cfg(feature = "cuda")]
+    {
+        sigverify::init();
+        if sigverify::gpu_ready() {
+            return Arc::new(CudaPohVerifier);
+        }
+    }
+    Arc::new(CpuPohVerifier)
+}
+
+struct VerifyRequest {
+    entries: Arc<Vec<Entry>>,
+    start_hash: Hash,
+    result_sender: Sender<bool>,
+}
+
+/// The result of a verification started with `EntryVerifier::start_verify`
+pub struct PendingVerification {
+    result_receiver: Receiver<bool>,
+}
+
+impl PendingVerification {
+    /// Waits for the verification to finish, returning whether the entries are valid
+    pub fn finish(self) -> bool {
+        let now = Instant::now();
+        // The verifier thread only goes away with the `EntryVerifier`, so a disconnect means the
+        // verification panicked
+        let valid = self.result_receiver.recv().unwrap_or(false);
+        inc_new_counter_info!(
+            "entry_verifier-wait_ms",
+            duration_as_ms(&now.elapsed()) as usize
+        );
+        valid
+    }
+}
+
+pub struct EntryVerifier {
+    request_sender: Sender<VerifyRequest>,
+    _t_verify: JoinHandle<()>,
+}
+
+impl EntryVerifier {
+    /// Starts the verifier thread, which exits once the `EntryVerifier` is dropped
+    pub fn new() -> Self {
+        Self::new_with_poh_verifier(new_poh_verifier())
+    }
+
+    pub fn new_with_poh_verifier(poh_verifier: Arc<dyn PohVerifier>) -> Self {
+        let (request_sender, request_receiver) = channel::<VerifyRequest>();
+        let t_verify = Builder::new()
+            .name("solana-entry-verifier".to_string())
+            .spawn(move || {
+                for request in request_receiver.iter() {
+                    let valid = poh_verifier.verify(&request.entries, &request.start_hash);
+                    // The caller may have dropped its `PendingVerification`
+                    let _ = request.result_sender.send(valid);
+                }
+            })
+            .unwrap();
+        Self {
+            request_sender,
+            _t_verify: t_verify,
+        }
+    }
+
+    /// Starts verifying that `entries` chain from `start_hash`. Batches are verified in the order
+    /// they're started.
+    pub fn start_verify(
+        &self,
+        entries: &Arc<Vec<Entry>>,
+        start_hash: &Hash,
+    ) -> PendingVerification {
+        let (result_sender, result_receiver) = channel();
+        let request = VerifyRequest {
+            entries: entries.clone(),
+            start_hash: *start_hash,
+            result_sender,
+        };
+        // If the thread is gone, `finish` sees the dropped sender and fails the verification
+        let _ = self.request_sender.send(request);
+        PendingVerification { result_receiver }
+    }
+}
+
+impl Default for EntryVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::{create_ticks, next_entry};
+    use solana_sdk::hash::hash;
+
+    #[test]
+    fn test_entry_verifier() {
+        let verifier = EntryVerifier::new();
+        let start_hash = Hash::default();
+        let entries = Arc::new(create_ticks(10, start_hash));
+        let pending = verifier.start_verify(&entries, &start_hash);
+        let bad_pending = verifier.start_verify(&entries, &hash(b"bad"));
+        assert!(pending.finish());
+        assert!(!bad_pending.finish());
+
+        let mut entries = create_ticks(10, start_hash);
+        entries[5] = next_entry(&hash(b"bad"), 1, vec![]);
+        assert!(!verifier
+            .start_verify(&Arc::new(entries), &start_hash)
+            .finish());
+        assert!(verifier
+            .start_verify(&Arc::new(vec![]), &start_hash)
+            .finish());
+    }
+
+    #[test]
+    fn test_poh_verifiers_agree() {
+        let start_hash = Hash::default();
+        let mut entries = create_ticks(100, start_hash);
+        let poh_verifiers = vec![
+            Arc::new(CpuPohVerifier) as Arc<dyn PohVerifier>,
+            new_poh_verifier(),
+        ];
+        for poh_verifier in &poh_verifiers {
+            assert!(poh_verifier.verify(&entries, &start_hash));
+            assert!(!poh_verifier.verify(&entries, &hash(b"bad")));
+        }
+
+        entries[50] = next_entry(&hash(b"bad"), 1, vec![]);
+        let verifier = EntryVerifier::new_with_poh_verifier(Arc::new(CpuPohVerifier));
+        assert!(!verifier
+            .start_verify(&Arc::new(entries), &start_hash)
+            .finish());
+    }
+}
@@ -1,16 +1,19 @@
 //! # Erasure Coding and Recovery
 //!
-//! Blobs are logically grouped into erasure sets or blocks. Each set contains 16 sequential data
-//! blobs and 4 sequential coding blobs.
+//! Blobs are logically grouped into erasure sets or blocks. Each set contains `num_data`
+//! sequential data blobs and `num_coding` sequential coding blobs. `num_data` is the same for
+//! every set of a slot, while `num_coding` is picked per set by the broadcaster, from 1 up to
+//! `num_data`, to match the loss it observes. Every coding blob carries the `ErasureConfig` of
+//! its set, and any `num_data` blobs of a set are enough to recover the rest of it.
 //!
 //! Coding blobs in each set starting from `start_idx`:
 //!   For each erasure set:
-//!     generate `NUM_CODING` coding_blobs.
-//!     index the coding blobs from `start_idx` to `start_idx + NUM_CODING - 1`.
+//!     generate `num_coding` coding_blobs.
+//!     index the coding blobs from `start_idx` to `start_idx + num_coding - 1`.
 //!
 //!  model of an erasure set, with top row being data blobs and second being coding
-//!  |<======================= NUM_DATA ==============================>|
-//!  |<==== NUM_CODING ===>|
+//!  |<======================= num_data ==============================>|
+//!  |<==== num_coding ===>|
 //!  +---+ +---+ +---+ +---+ +---+         +---+ +---+ +---+ +---+ +---+
 //!  | D | | D | | D | | D | | D |         | D | | D | | D | | D | | D |
 //!  +---+ +---+ +---+ +---+ +---+  . . .  +---+ +---+ +---+ +---+ +---+
@@ -89,6 +92,67 @@ impl ErasureConfig {
 
 type Result<T> = std::result::Result<T, reed_solomon_erasure::Error>;
 
+/// Weight of the latest sample in the running loss rate of a `CodingRateController`
+const LOSS_RATE_SAMPLE_WEIGHT: f64 = 0.25;
+/// How many times over the coding blobs of a set should cover the losses it can expect
+const LOSS_MARGIN: f64 = 2.0;
+
+/// Picks how many coding blobs each erasure set gets from the blob loss the broadcaster
+/// observes. The loss is only estimated, from the repairs this node serves, so it never takes a
+/// set below the configured number of coding blobs; it only adds coding when sets would need
+/// more to be recovered without repair.
+#[derive(Debug, Clone)]
+pub struct CodingRateController {
+    config: ErasureConfig,
+    /// Running fraction of data blobs lost, None until the first sample
+    loss_rate: Option<f64>,
+}
+
+impl CodingRateController {
+    /// `config` sets the number of data blobs of a set and the fewest coding blobs a set gets,
+    /// which is also what sets get until loss has been observed
+    pub fn new(config: &ErasureConfig) -> Self {
+        CodingRateController {
+            config: *config,
+            loss_rate: None,
+        }
+    }
+
+    pub fn loss_rate(&self) -> Option<f64> {
+        self.loss_rate
+    }
+
+    /// Records that `lost` of `sent` data blobs didn't reach the cluster
+    pub fn observe(&mut self, sent: usize, lost: usize) {
+        if sent == 0 {
+            return;
+        }
+        let loss = (lost as f64 / sent as f64).min(1.0);
+        self.loss_rate = Some(match self.loss_rate {
+            Some(loss_rate) => loss_rate + LOSS_RATE_SAMPLE_WEIGHT * (loss - loss_rate),
+            None => loss,
+        });
+    }
+
+    /// The number of coding blobs the next erasure set should get
+    pub fn num_coding(&self) -> usize {
+        // A set can't have more coding blobs than data blobs
+        let max_coding = self.config.num_data;
+        let min_coding = cmp::min(self.config.num_coding, max_coding);
+        match self.loss_rate {
+            Some(loss_rate) if loss_rate < 1.0 => {
+                // A set of `d` data and `c` coding blobs loses `(d + c) * p` of them on average,
+                // and can be recovered as long as that's no more than `c`
+                let needed =
+                    LOSS_MARGIN * self.config.num_data as f64 * loss_rate / (1.0 - loss_rate);
+                cmp::max(min_coding, cmp::min(max_coding, needed.ceil() as usize))
+            }
+            Some(_) => max_coding,
+            None => min_coding,
+        }
+    }
+}
+
 /// Represents an erasure "session" with a particular configuration and number of data and coding
 /// blobs
 #[derive(Debug, Clone)]
@@ -162,6 +226,7 @@ impl Session {
 
         let mut recovered_data = vec![];
         let mut recovered_coding = vec![];
+        let (num_data, num_coding) = self.dimensions();
 
         let erasures = present
             .iter()
@@ -174,7 +239,7 @@ impl Session {
             let idx;
             let first_byte;
 
-            if n < self.0.data_shard_count() {
+            if n < num_data {
                 let mut blob = Blob::new(&blocks[n]);
                 blob.meta.size = blob.data_size() as usize;
 
@@ -189,12 +254,13 @@ impl Session {
                 blob.meta.size = size;
 
                 data_size = size;
-                idx = n as u64 + block_start_idx - NUM_DATA as u64;
+                idx = n as u64 + block_start_idx - num_data as u64;
                 first_byte = blob.data[0];
 
                 blob.set_slot(slot);
                 blob.set_index(idx);
                 blob.set_coding();
+                blob.set_erasure_config(&ErasureConfig::new(num_data, num_coding));
                 recovered_coding.push(blob);
             }
 
@@ -226,6 +292,20 @@ impl CodingGenerator {
         }
     }
 
+    pub fn num_coding(&self) -> usize {
+        self.session.dimensions().1
+    }
+
+    /// Sets how many coding blobs the following erasure sets get, from 1 up to the number of
+    /// data blobs in a set. Data blobs left over from earlier calls are coded at the new rate.
+    pub fn set_num_coding(&mut self, num_coding: usize) {
+        let (num_data, current_num_coding) = self.session.dimensions();
+        let num_coding = cmp::max(1, cmp::min(num_coding, num_data));
+        if num_coding != current_num_coding {
+            self.session = Arc::new(Session::new(num_data, num_coding).unwrap());
+        }
+    }
+
     /// Yields next set of coding blobs, if any.
     /// Must be called with consecutive data blobs within a slot.
     ///
@@ -237,6 +317,7 @@ impl CodingGenerator {
     /// error.
     pub fn next(&mut self, next_data: &[SharedBlob]) -> Vec<SharedBlob> {
         let (num_data, num_coding) = self.session.dimensions();
+        let erasure_config = ErasureConfig::new(num_data, num_coding);
         let mut next_coding =
             Vec::with_capacity((self.leftover.len() + next_data.len()) / num_data * num_coding);
 
@@ -282,7 +363,7 @@ impl CodingGenerator {
                 coding_blob.set_version(version);
                 coding_blob.set_size(max_data_size);
                 coding_blob.set_coding();
-                coding_blob.set_erasure_config(&data_blob.erasure_config());
+                coding_blob.set_erasure_config(&erasure_config);
 
                 coding_blobs.push(coding_blob);
             }
@@ -542,6 +623,96 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn test_generate_coding_variable_rate() {
+        solana_logger::setup();
+
+        let mut coding_generator = CodingGenerator::default();
+        let data_blobs = generate_test_blobs(0, NUM_DATA * 3);
+
+        for (set_index, num_coding) in [2, NUM_DATA * 2, 0].iter().enumerate() {
+            coding_generator.set_num_coding(*num_coding);
+            let expected_num_coding = cmp::max(1, cmp::min(*num_coding, NUM_DATA));
+            assert_eq!(coding_generator.num_coding(), expected_num_coding);
+
+            let start_index = set_index * NUM_DATA;
+            let coding_blobs =
+                coding_generator.next(&data_blobs[start_index..start_index + NUM_DATA]);
+            assert_eq!(coding_blobs.len(), expected_num_coding);
+
+            for coding_blob in &coding_blobs {
+                assert_eq!(
+                    coding_blob.read().unwrap().erasure_config(),
+                    ErasureConfig::new(NUM_DATA, expected_num_coding)
+                );
+            }
+
+            // Lose as many data blobs as there are coding blobs, and recover them from the rest
+            let size = coding_blobs[0].read().unwrap().size();
+            let mut blobs: Vec<_> = data_blobs[start_index..start_index + NUM_DATA]
+                .iter()
+                .chain(coding_blobs.iter())
+                .map(|blob| blob.read().unwrap().data[..BLOB_HEADER_SIZE + size].to_vec())
+                .collect();
+            for blob in &mut blobs[..NUM_DATA] {
+                blob.truncate(size);
+            }
+            for blob in &mut blobs[NUM_DATA..] {
+                blob.drain(..BLOB_HEADER_SIZE);
+            }
+            let mut present = vec![true; NUM_DATA + expected_num_coding];
+            for (present, blob) in present.iter_mut().zip(&mut blobs).take(expected_num_coding) {
+                *present = false;
+                *blob = vec![0; size];
+            }
+
+            let (recovered_data, recovered_coding) = coding_generator
+                .session
+                .reconstruct_blobs(&mut blobs, &present, size, start_index as u64, 0)
+                .expect("reconstruction must succeed");
+            assert_eq!(recovered_data.len(), expected_num_coding);
+            assert!(recovered_coding.is_empty());
+            for (i, recovered) in recovered_data.iter().enumerate() {
+                assert_eq!(
+                    recovered.data(),
+                    data_blobs[start_index + i].read().unwrap().data()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_coding_rate_controller() {
+        let config = ErasureConfig::new(16, 4);
+        let mut coding_rate = CodingRateController::new(&config);
+        assert_eq!(coding_rate.loss_rate(), None);
+        assert_eq!(coding_rate.num_coding(), 4);
+
+        // Nothing sent, nothing learned
+        coding_rate.observe(0, 0);
+        assert_eq!(coding_rate.num_coding(), 4);
+
+        // No loss never takes a set below the configured rate
+        coding_rate.observe(100, 0);
+        assert_eq!(coding_rate.num_coding(), 4);
+
+        // 10% loss needs ceil(2 * 16 * 0.1 / 0.9) = 4 coding blobs, which the configured rate
+        // already covers
+        let mut coding_rate = CodingRateController::new(&config);
+        coding_rate.observe(100, 10);
+        assert_eq!(coding_rate.num_coding(), 4);
+
+        // The loss rate moves a quarter of the way to each new sample, and 15% loss needs
+        // ceil(2 * 16 * 0.15 / 0.85) = 6 coding blobs
+        coding_rate.observe(100, 30);
+        assert!((coding_rate.loss_rate().unwrap() - 0.15).abs() < 1e-9);
+        assert_eq!(coding_rate.num_coding(), 6);
+
+        // Heavy loss is capped at a coding blob per data blob
+        coding_rate.observe(10, 100);
+        assert_eq!(coding_rate.num_coding(), 16);
+    }
+
     #[test]
     fn test_erasure_generate_blocktree_with_coding() {
         let cases = vec![
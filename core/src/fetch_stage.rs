@@ -1,12 +1,15 @@
-//! The `fetch_stage` batches input from a UDP socket and sends it to a channel.
+//! The `fetch_stage` batches input from UDP sockets, and the QUIC TPU if it's enabled, and sends
+//! it to a channel.
 
 use crate::banking_stage::FORWARD_TRANSACTIONS_TO_LEADER_AT_SLOT_OFFSET;
 use crate::poh_recorder::PohRecorder;
+use crate::quic_streamer::{QuicServer, QuicTpuConfig};
 use crate::recycler::Recycler;
 use crate::result::{Error, Result};
 use crate::service::Service;
-use crate::streamer::{self, PacketReceiver, PacketSender};
+use crate::streamer::{self, PacketCoalescer, PacketReceiver, PacketSender};
 use solana_metrics::{inc_new_counter_debug, inc_new_counter_info};
+use solana_sdk::signature::Keypair;
 use solana_sdk::timing::DEFAULT_TICKS_PER_SLOT;
 use std::net::UdpSocket;
 use std::sync::atomic::AtomicBool;
@@ -16,6 +19,7 @@ use std::thread::{self, Builder, JoinHandle};
 
 pub struct FetchStage {
     thread_hdls: Vec<JoinHandle<()>>,
+    quic_server: Option<QuicServer>,
 }
 
 impl FetchStage {
@@ -23,31 +27,49 @@ impl FetchStage {
     pub fn new(
         sockets: Vec<UdpSocket>,
         tpu_via_blobs_sockets: Vec<UdpSocket>,
+        tpu_quic: Option<(UdpSocket, &Keypair, &QuicTpuConfig)>,
+        coalescer: &PacketCoalescer,
         exit: &Arc<AtomicBool>,
         poh_recorder: &Arc<Mutex<PohRecorder>>,
     ) -> (Self, PacketReceiver) {
         let (sender, receiver) = channel();
         (
-            Self::new_with_sender(sockets, tpu_via_blobs_sockets, exit, &sender, &poh_recorder),
+            Self::new_with_sender(
+                sockets,
+                tpu_via_blobs_sockets,
+                tpu_quic,
+                coalescer,
+                exit,
+                &sender,
+                &poh_recorder,
+            ),
             receiver,
         )
     }
     pub fn new_with_sender(
         sockets: Vec<UdpSocket>,
         tpu_via_blobs_sockets: Vec<UdpSocket>,
+        tpu_quic: Option<(UdpSocket, &Keypair, &QuicTpuConfig)>,
+        coalescer: &PacketCoalescer,
         exit: &Arc<AtomicBool>,
         sender: &PacketSender,
         poh_recorder: &Arc<Mutex<PohRecorder>>,
     ) -> Self {
         let tx_sockets = sockets.into_iter().map(Arc::new).collect();
         let tpu_via_blobs_sockets = tpu_via_blobs_sockets.into_iter().map(Arc::new).collect();
-        Self::new_multi_socket(
+        let mut fetch_stage = Self::new_multi_socket(
             tx_sockets,
             tpu_via_blobs_sockets,
+            coalescer,
             exit,
             &sender,
             &poh_recorder,
-        )
+        );
+        fetch_stage.quic_server = tpu_quic.map(|(socket, identity, config)| {
+            QuicServer::new(socket, identity, config, sender, exit)
+                .expect("Failed to start the QUIC TPU")
+        });
+        fetch_stage
     }
 
     fn handle_forwarded_packets(
@@ -84,6 +106,7 @@ impl FetchStage {
     fn new_multi_socket(
         sockets: Vec<Arc<UdpSocket>>,
         tpu_via_blobs_sockets: Vec<Arc<UdpSocket>>,
+        coalescer: &PacketCoalescer,
         exit: &Arc<AtomicBool>,
         sender: &PacketSender,
         poh_recorder: &Arc<Mutex<PohRecorder>>,
@@ -95,6 +118,7 @@ impl FetchStage {
                 &exit,
                 sender.clone(),
                 recycler.clone(),
+                coalescer.clone(),
                 "fetch_stage",
             )
         });
@@ -126,7 +150,10 @@ impl FetchStage {
 
         let mut thread_hdls: Vec<_> = tpu_threads.chain(tpu_via_blobs_threads).collect();
         thread_hdls.push(fwd_thread_hdl);
-        Self { thread_hdls }
+        Self {
+            thread_hdls,
+            quic_server: None,
+        }
     }
 }
 
@@ -137,6 +164,9 @@ impl Service for FetchStage {
         for thread_hdl in self.thread_hdls {
             thread_hdl.join()?;
         }
+        if let Some(quic_server) = self.quic_server {
+            quic_server.join()?;
+        }
         Ok(())
     }
 }
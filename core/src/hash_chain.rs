@@ -0,0 +1,325 @@
+//! The `hash_chain` module computes the chains of SHA-256 hashes PoH is made of, where each
+//! hash is the hash of the one before it. On x86_64, a single chain is hashed with the SHA
+//! extensions and independent chains are hashed eight at a time with AVX2, when the CPU has
+//! them. Building with the `scalar-poh` feature always hashes one hash at a time with the
+//! `sha2` crate, like the rest of the sdk does.
+
+use solana_sdk::hash::{hash, Hash};
+
+/// The number of chains `hash_chains` hashes at once
+pub const HASH_CHAIN_LANES: usize = 8;
+
+/// Hashes `start` `num_hashes` times, one hash at a time
+pub fn scalar_hash_chain(start: &Hash, num_hashes: u64) -> Hash {
+    let mut end = *start;
+    for _ in 0..num_hashes {
+        end = hash(end.as_ref());
+    }
+    end
+}
+
+/// Hashes `start` `num_hashes` times
+pub fn hash_chain(start: &Hash, num_hashes: u64) -> Hash {
+    #[cfg(all(target_arch = "x86_64", not(feature = "scalar-poh")))]
+    {
+        if num_hashes > 0 && x86::has_sha() {
+            let mut state = x86::to_words(start);
+            unsafe { x86::sha_ni_chain(&mut state, num_hashes) };
+            return x86::from_words(&state);
+        }
+    }
+    scalar_hash_chain(start, num_hashes)
+}
+
+/// Hashes the hash of each chain as many times as the chain says, leaving the last hash of the
+/// chain in its place
+pub fn hash_chains(chains: &mut [(Hash, u64)]) {
+    #[cfg(all(target_arch = "x86_64", not(feature = "scalar-poh")))]
+    {
+        if x86::has_avx2() {
+            for lanes in chains.chunks_mut(HASH_CHAIN_LANES) {
+                x86::avx2_chains(lanes);
+            }
+            return;
+        }
+    }
+    for (hash, num_hashes) in chains.iter_mut() {
+        *hash = hash_chain(hash, *num_hashes);
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", not(feature = "scalar-poh")))]
+mod x86 {
+    use super::HASH_CHAIN_LANES;
+    use solana_sdk::hash::Hash;
+    use std::arch::x86_64::*;
+
+    const IV: [u32; 8] = [
+        0x6a09_e667,
+        0xbb67_ae85,
+        0x3c6e_f372,
+        0xa54f_f53a,
+        0x510e_527f,
+        0x9b05_688c,
+        0x1f83_d9ab,
+        0x5be0_cd19,
+    ];
+
+    #[rustfmt::skip]
+    const K: [u32; 64] = [
+        0x428a_2f98, 0x7137_4491, 0xb5c0_fbcf, 0xe9b5_dba5, 0x3956_c25b, 0x59f1_11f1, 0x923f_82a4,
+        0xab1c_5ed5, 0xd807_aa98, 0x1283_5b01, 0x2431_85be, 0x550c_7dc3, 0x72be_5d74, 0x80de_b1fe,
+        0x9bdc_06a7, 0xc19b_f174, 0xe49b_69c1, 0xefbe_4786, 0x0fc1_9dc6, 0x240c_a1cc, 0x2de9_2c6f,
+        0x4a74_84aa, 0x5cb0_a9dc, 0x76f9_88da, 0x983e_5152, 0xa831_c66d, 0xb003_27c8, 0xbf59_7fc7,
+        0xc6e0_0bf3, 0xd5a7_9147, 0x06ca_6351, 0x1429_2967, 0x27b7_0a85, 0x2e1b_2138, 0x4d2c_6dfc,
+        0x5338_0d13, 0x650a_7354, 0x766a_0abb, 0x81c2_c92e, 0x9272_2c85, 0xa2bf_e8a1, 0xa81a_664b,
+        0xc24b_8b70, 0xc76c_51a3, 0xd192_e819, 0xd699_0624, 0xf40e_3585, 0x106a_a070, 0x19a4_c116,
+        0x1e37_6c08, 0x2748_774c, 0x34b0_bcb5, 0x391c_0cb3, 0x4ed8_aa4a, 0x5b9c_ca4f, 0x682e_6ff3,
+        0x748f_82ee, 0x78a5_636f, 0x84c8_7814, 0x8cc7_0208, 0x90be_fffa, 0xa450_6ceb, 0xbef9_a3f7,
+        0xc671_78f2,
+    ];
+
+    /// The message words after the 32 byte hash in its single padded block: the padding bit
+    /// and the message length in bits
+    const PADDING: [u32; 8] = [0x8000_0000, 0, 0, 0, 0, 0, 0, 256];
+
+    pub fn has_sha() -> bool {
+        is_x86_feature_detected!("sha") && is_x86_feature_detected!("sse4.1")
+    }
+
+    pub fn has_avx2() -> bool {
+        is_x86_feature_detected!("avx2")
+    }
+
+    pub fn to_words(hash: &Hash) -> [u32; 8] {
+        let mut words = [0; 8];
+        for (word, bytes) in words.iter_mut().zip(hash.as_ref().chunks(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        words
+    }
+
+    pub fn from_words(words: &[u32; 8]) -> Hash {
+        let mut bytes = [0; 32];
+        for (bytes, word) in bytes.chunks_mut(4).zip(words.iter()) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        Hash::new(&bytes)
+    }
+
+    /// Hashes the hash in `state` `num_hashes` times with the SHA extensions. The words of each
+    /// hash are the first half of the block the next hash compresses, so the chain never leaves
+    /// the registers.
+    #[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+    pub unsafe fn sha_ni_chain(state: &mut [u32; 8], num_hashes: u64) {
+        let padding = [
+            _mm_loadu_si128(PADDING.as_ptr() as *const __m128i),
+            _mm_loadu_si128(PADDING[4..].as_ptr() as *const __m128i),
+        ];
+        // The rounds take the state as ABEF and CDGH
+        let iv0 = _mm_loadu_si128(IV.as_ptr() as *const __m128i);
+        let iv1 = _mm_loadu_si128(IV[4..].as_ptr() as *const __m128i);
+        let iv0 = _mm_shuffle_epi32(iv0, 0xB1);
+        let iv1 = _mm_shuffle_epi32(iv1, 0x1B);
+        let iv_abef = _mm_alignr_epi8(iv0, iv1, 8);
+        let iv_cdgh = _mm_blend_epi16(iv1, iv0, 0xF0);
+
+        let mut words = [
+            _mm_loadu_si128(state.as_ptr() as *const __m128i),
+            _mm_loadu_si128(state[4..].as_ptr() as *const __m128i),
+        ];
+        for _ in 0..num_hashes {
+            let mut msg = [words[0], words[1], padding[0], padding[1]];
+            let mut abef = iv_abef;
+            let mut cdgh = iv_cdgh;
+            for group in 0..16 {
+                let k = _mm_loadu_si128(K[group * 4..].as_ptr() as *const __m128i);
+                let w = _mm_add_epi32(msg[group % 4], k);
+                cdgh = _mm_sha256rnds2_epu32(cdgh, abef, w);
+                if (3..=14).contains(&group) {
+                    let next = (group + 1) % 4;
+                    let tmp = _mm_alignr_epi8(msg[group % 4], msg[(group + 3) % 4], 4);
+                    msg[next] = _mm_add_epi32(msg[next], tmp);
+                    msg[next] = _mm_sha256msg2_epu32(msg[next], msg[group % 4]);
+                }
+                abef = _mm_sha256rnds2_epu32(abef, cdgh, _mm_shuffle_epi32(w, 0x0E));
+                if (1..=12).contains(&group) {
+                    let prev = (group + 3) % 4;
+                    msg[prev] = _mm_sha256msg1_epu32(msg[prev], msg[group % 4]);
+                }
+            }
+            abef = _mm_add_epi32(abef, iv_abef);
+            cdgh = _mm_add_epi32(cdgh, iv_cdgh);
+
+            // Back to ABCD and EFGH
+            let feba = _mm_shuffle_epi32(abef, 0x1B);
+            let dchg = _mm_shuffle_epi32(cdgh, 0xB1);
+            words = [
+                _mm_blend_epi16(feba, dchg, 0xF0),
+                _mm_alignr_epi8(dchg, feba, 8),
+            ];
+        }
+        _mm_storeu_si128(state.as_mut_ptr() as *mut __m128i, words[0]);
+        _mm_storeu_si128(state[4..].as_mut_ptr() as *mut __m128i, words[1]);
+    }
+
+    macro_rules! rotr {
+        ($x:expr, $n:expr) => {
+            _mm256_or_si256(_mm256_srli_epi32($x, $n), _mm256_slli_epi32($x, 32 - $n))
+        };
+    }
+
+    /// Compresses the single padded block of each lane's hash
+    #[target_feature(enable = "avx2")]
+    unsafe fn compress_8way(state: &mut [__m256i; 8]) {
+        let mut w = [_mm256_setzero_si256(); 64];
+        w[..8].copy_from_slice(state);
+        for (w, padding) in w[8..16].iter_mut().zip(PADDING.iter()) {
+            *w = _mm256_set1_epi32(*padding as i32);
+        }
+        for i in 16..64 {
+            let s0 = _mm256_xor_si256(
+                _mm256_xor_si256(rotr!(w[i - 15], 7), rotr!(w[i - 15], 18)),
+                _mm256_srli_epi32(w[i - 15], 3),
+            );
+            let s1 = _mm256_xor_si256(
+                _mm256_xor_si256(rotr!(w[i - 2], 17), rotr!(w[i - 2], 19)),
+                _mm256_srli_epi32(w[i - 2], 10),
+            );
+            w[i] = _mm256_add_epi32(
+                _mm256_add_epi32(w[i - 16], s0),
+                _mm256_add_epi32(w[i - 7], s1),
+            );
+        }
+
+        let mut v = [_mm256_setzero_si256(); 8];
+        for (v, iv) in v.iter_mut().zip(IV.iter()) {
+            *v = _mm256_set1_epi32(*iv as i32);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = v;
+        for i in 0..64 {
+            let s1 = _mm256_xor_si256(_mm256_xor_si256(rotr!(e, 6), rotr!(e, 11)), rotr!(e, 25));
+            let ch = _mm256_xor_si256(_mm256_and_si256(e, f), _mm256_andnot_si256(e, g));
+            let t1 = _mm256_add_epi32(
+                _mm256_add_epi32(_mm256_add_epi32(h, s1), _mm256_add_epi32(ch, w[i])),
+                _mm256_set1_epi32(K[i] as i32),
+            );
+            let s0 = _mm256_xor_si256(_mm256_xor_si256(rotr!(a, 2), rotr!(a, 13)), rotr!(a, 22));
+            let maj = _mm256_xor_si256(
+                _mm256_xor_si256(_mm256_and_si256(a, b), _mm256_and_si256(a, c)),
+                _mm256_and_si256(b, c),
+            );
+            let t2 = _mm256_add_epi32(s0, maj);
+            h = g;
+            g = f;
+            f = e;
+            e = _mm256_add_epi32(d, t1);
+            d = c;
+            c = b;
+            b = a;
+            a = _mm256_add_epi32(t1, t2);
+        }
+        for (state, (v, x)) in state
+            .iter_mut()
+            .zip(v.iter().zip([a, b, c, d, e, f, g, h].iter()))
+        {
+            *state = _mm256_add_epi32(*v, *x);
+        }
+    }
+
+    /// Hashes `num_hashes` times the hash of every lane, words of a lane at a time
+    #[target_feature(enable = "avx2")]
+    unsafe fn avx2_chain(words: &mut [[u32; HASH_CHAIN_LANES]; 8], num_hashes: u64) {
+        let mut state = [_mm256_setzero_si256(); 8];
+        for (state, words) in state.iter_mut().zip(words.iter()) {
+            *state = _mm256_loadu_si256(words.as_ptr() as *const __m256i);
+        }
+        for _ in 0..num_hashes {
+            compress_8way(&mut state);
+        }
+        for (state, words) in state.iter().zip(words.iter_mut()) {
+            _mm256_storeu_si256(words.as_mut_ptr() as *mut __m256i, *state);
+        }
+    }
+
+    /// Hashes up to `HASH_CHAIN_LANES` chains together for as long as they all have hashes
+    /// left, then finishes the longer ones alone
+    pub fn avx2_chains(chains: &mut [(Hash, u64)]) {
+        let num_hashes = chains.iter().map(|(_, num_hashes)| *num_hashes).min();
+        let num_hashes = num_hashes.unwrap_or(0);
+        if num_hashes > 0 {
+            let mut words = [[0; HASH_CHAIN_LANES]; 8];
+            for (lane, (hash, _)) in chains.iter().enumerate() {
+                for (j, word) in to_words(hash).iter().enumerate() {
+                    words[j][lane] = *word;
+                }
+            }
+            unsafe { avx2_chain(&mut words, num_hashes) };
+            for (lane, (hash, _)) in chains.iter_mut().enumerate() {
+                let mut lane_words = [0; 8];
+                for (j, word) in lane_words.iter_mut().enumerate() {
+                    *word = words[j][lane];
+                }
+                *hash = from_words(&lane_words);
+            }
+        }
+        for (hash, chain_num_hashes) in chains.iter_mut() {
+            *hash = super::hash_chain(hash, *chain_num_hashes - num_hashes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_chain() {
+        let zero = Hash::default();
+        assert_eq!(hash_chain(&zero, 0), zero);
+        assert_eq!(hash_chain(&zero, 1), hash(zero.as_ref()));
+        assert_eq!(hash_chain(&zero, 2), hash(hash(zero.as_ref()).as_ref()));
+        for &num_hashes in &[3u64, 64, 1000] {
+            let start = hash(&num_hashes.to_le_bytes());
+            assert_eq!(
+                hash_chain(&start, num_hashes),
+                scalar_hash_chain(&start, num_hashes)
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash_chains() {
+        // More chains than lanes, of different lengths
+        let chains: Vec<_> = (0..HASH_CHAIN_LANES as u64 + 3)
+            .map(|i| (hash(&i.to_le_bytes()), 100 + i % 4))
+            .collect();
+        let mut hashed = chains.clone();
+        hash_chains(&mut hashed);
+        for ((start, num_hashes), (end, _)) in chains.iter().zip(hashed.iter()) {
+            assert_eq!(*end, scalar_hash_chain(start, *num_hashes));
+        }
+
+        let mut empty = vec![(Hash::default(), 0)];
+        hash_chains(&mut empty);
+        assert_eq!(empty[0].0, Hash::default());
+    }
+
+    #[cfg(all(target_arch = "x86_64", not(feature = "scalar-poh")))]
+    #[test]
+    fn test_accelerated_paths() {
+        let start = hash(&[1, 2, 3]);
+        if x86::has_sha() {
+            let mut state = x86::to_words(&start);
+            unsafe { x86::sha_ni_chain(&mut state, 500) };
+            assert_eq!(x86::from_words(&state), scalar_hash_chain(&start, 500));
+        }
+        if x86::has_avx2() {
+            let mut chains = vec![(start, 500); HASH_CHAIN_LANES];
+            x86::avx2_chains(&mut chains);
+            assert!(chains
+                .iter()
+                .all(|(hash, _)| *hash == scalar_hash_chain(&start, 500)));
+        }
+    }
+}
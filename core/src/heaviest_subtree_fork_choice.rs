@@ -0,0 +1,515 @@
+//! The `heaviest_subtree_fork_choice` module tracks the stake voted on every slot
+//! descended from the root, so the heaviest fork can be found without
+//! recomputing the weight of every frozen bank.
+//!
+//! Each validator counts only toward its latest vote. The stake voted on a
+//! subtree is the sum of the stake voted on all of its slots, and each slot
+//! caches the leaf at the end of its heaviest path. A new vote moves the
+//! validator's stake from its previous slot to the new one, which updates only
+//! the slots between those two and the root.
+
+use crate::bank_forks::BankForks;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Debug, Default, PartialEq)]
+struct ForkInfo {
+    // Stake of validators whose latest vote is this slot
+    stake_voted_at: u64,
+    // Stake of validators whose latest vote is this slot or a descendant
+    stake_voted_subtree: u64,
+    // The leaf at the end of the heaviest path starting at this slot
+    best_slot: u64,
+    parent: Option<u64>,
+    children: Vec<u64>,
+}
+
+#[derive(Debug, Default)]
+pub struct HeaviestSubtreeForkChoice {
+    fork_infos: HashMap<u64, ForkInfo>,
+    // Latest vote slot and stake counted for each validator
+    latest_votes: HashMap<Pubkey, (u64, u64)>,
+    root: u64,
+}
+
+impl HeaviestSubtreeForkChoice {
+    pub fn new(root: u64) -> Self {
+        let mut heaviest_subtree_fork_choice = Self {
+            root,
+            ..Self::default()
+        };
+        heaviest_subtree_fork_choice.add_new_leaf_slot(root, None);
+        heaviest_subtree_fork_choice
+    }
+
+    /// Build the tree from the frozen banks descended from the root of `bank_forks`
+    pub fn new_from_bank_forks(bank_forks: &BankForks) -> Self {
+        let root = bank_forks.root();
+        let mut frozen_banks: Vec<_> = bank_forks
+            .frozen_banks()
+            .into_iter()
+            .filter(|(slot, _)| *slot > root)
+            .map(|(_, bank)| bank)
+            .collect();
+        frozen_banks.sort_by_key(|bank| bank.slot());
+
+        let mut heaviest_subtree_fork_choice = Self::new(root);
+        for bank in frozen_banks {
+            heaviest_subtree_fork_choice
+                .add_new_leaf_slot(bank.slot(), bank.parent().map(|parent| parent.slot()));
+        }
+        heaviest_subtree_fork_choice
+    }
+
+    pub fn root(&self) -> u64 {
+        self.root
+    }
+
+    pub fn contains_slot(&self, slot: u64) -> bool {
+        self.fork_infos.contains_key(&slot)
+    }
+
+    /// The leaf at the end of the heaviest fork
+    pub fn best_overall_slot(&self) -> u64 {
+        self.fork_infos[&self.root].best_slot
+    }
+
+    /// The leaf at the end of the heaviest path starting at `slot`
+    pub fn best_slot(&self, slot: u64) -> Option<u64> {
+        self.fork_infos.get(&slot).map(|info| info.best_slot)
+    }
+
+    /// The heaviest leaf a validator that last voted on `last_voted_slot` may build on. That's
+    /// the best overall slot, unless `is_locked_out` of it, in which case it's the heaviest leaf
+    /// of the fork the validator voted on. Returns None if that fork has been pruned from the
+    /// tree too, since every leaf left may be locked out.
+    pub fn best_unlocked_slot<F>(
+        &self,
+        last_voted_slot: Option<u64>,
+        is_locked_out: F,
+    ) -> Option<u64>
+    where
+        F: Fn(u64) -> bool,
+    {
+        let best_overall_slot = self.best_overall_slot();
+        if !is_locked_out(best_overall_slot) {
+            return Some(best_overall_slot);
+        }
+        last_voted_slot
+            .and_then(|slot| self.best_slot(slot))
+            .filter(|slot| !is_locked_out(*slot))
+    }
+
+    /// Whether `slot` lies on the path from the root to the best overall slot
+    pub fn is_on_heaviest_fork(&self, slot: u64) -> bool {
+        self.best_slot(slot) == Some(self.best_overall_slot())
+    }
+
+    pub fn stake_voted_at(&self, slot: u64) -> Option<u64> {
+        self.fork_infos.get(&slot).map(|info| info.stake_voted_at)
+    }
+
+    pub fn stake_voted_subtree(&self, slot: u64) -> Option<u64> {
+        self.fork_infos
+            .get(&slot)
+            .map(|info| info.stake_voted_subtree)
+    }
+
+    pub fn latest_vote(&self, pubkey: &Pubkey) -> Option<u64> {
+        self.latest_votes.get(pubkey).map(|(slot, _)| *slot)
+    }
+
+    /// Add `slot` as a child of `parent`. Slots whose parent isn't in the tree
+    /// are ignored.
+    pub fn add_new_leaf_slot(&mut self, slot: u64, parent: Option<u64>) {
+        if self.fork_infos.contains_key(&slot) {
+            return;
+        }
+        if let Some(parent) = parent {
+            if let Some(parent_info) = self.fork_infos.get_mut(&parent) {
+                parent_info.children.push(slot);
+            } else {
+                trace!(
+                    "fork choice ignoring slot {} with unknown parent {}",
+                    slot,
+                    parent
+                );
+                return;
+            }
+        }
+        self.fork_infos.insert(
+            slot,
+            ForkInfo {
+                best_slot: slot,
+                parent,
+                ..ForkInfo::default()
+            },
+        );
+        if let Some(parent) = parent {
+            self.propagate_best_slot(parent);
+        }
+    }
+
+    /// Apply `(validator, vote slot, stake)` votes. A vote only counts if it's
+    /// newer than the validator's latest counted vote and its slot is in the tree.
+    pub fn add_votes<I>(&mut self, votes: I)
+    where
+        I: IntoIterator<Item = (Pubkey, u64, u64)>,
+    {
+        for (pubkey, slot, stake) in votes {
+            if !self.fork_infos.contains_key(&slot) {
+                continue;
+            }
+            if let Some((old_slot, old_stake)) = self.latest_votes.get(&pubkey).cloned() {
+                if old_slot >= slot {
+                    continue;
+                }
+                self.subtract_stake(old_slot, old_stake);
+            }
+            self.add_stake(slot, stake);
+            self.latest_votes.insert(pubkey, (slot, stake));
+        }
+    }
+
+    /// Drop `slot`, a leaf other than the root, along with the votes on it
+    pub fn remove_leaf_slot(&mut self, slot: u64) {
+        let stake_voted_at = match self.fork_infos.get(&slot) {
+            Some(info) => {
+                assert!(info.children.is_empty() && slot != self.root);
+                info.stake_voted_at
+            }
+            None => return,
+        };
+        self.subtract_stake(slot, stake_voted_at);
+        self.latest_votes.retain(|_, vote| vote.0 != slot);
+        let parent = self.fork_infos.remove(&slot).unwrap().parent.unwrap();
+        self.fork_infos
+            .get_mut(&parent)
+            .unwrap()
+            .children
+            .retain(|child| *child != slot);
+        self.propagate_best_slot(parent);
+    }
+
+    /// Drop every slot that doesn't descend from `new_root`, along with the
+    /// votes on them
+    pub fn set_root(&mut self, new_root: u64) {
+        if !self.fork_infos.contains_key(&new_root) {
+            warn!("fork choice has no slot for new root {}", new_root);
+            return;
+        }
+        let mut keep = HashMap::new();
+        let mut pending = VecDeque::new();
+        pending.push_back(new_root);
+        while let Some(slot) = pending.pop_front() {
+            let info = self.fork_infos.remove(&slot).unwrap();
+            pending.extend(info.children.iter().cloned());
+            keep.insert(slot, info);
+        }
+        self.fork_infos = keep;
+        self.fork_infos.get_mut(&new_root).unwrap().parent = None;
+        self.root = new_root;
+
+        let fork_infos = &self.fork_infos;
+        self.latest_votes
+            .retain(|_, vote| fork_infos.contains_key(&vote.0));
+    }
+
+    fn add_stake(&mut self, slot: u64, stake: u64) {
+        self.fork_infos.get_mut(&slot).unwrap().stake_voted_at += stake;
+        let mut next = Some(slot);
+        while let Some(slot) = next {
+            let info = self.fork_infos.get_mut(&slot).unwrap();
+            info.stake_voted_subtree += stake;
+            next = info.parent;
+        }
+        self.propagate_best_slot(slot);
+    }
+
+    fn subtract_stake(&mut self, slot: u64, stake: u64) {
+        self.fork_infos.get_mut(&slot).unwrap().stake_voted_at -= stake;
+        let mut next = Some(slot);
+        while let Some(slot) = next {
+            let info = self.fork_infos.get_mut(&slot).unwrap();
+            info.stake_voted_subtree -= stake;
+            next = info.parent;
+        }
+        self.propagate_best_slot(slot);
+    }
+
+    /// Recompute the best slot of `slot` and each of its ancestors. Only the
+    /// children on that path can have changed, so each slot picks again from its
+    /// children's cached values.
+    fn propagate_best_slot(&mut self, slot: u64) {
+        let mut next = Some(slot);
+        while let Some(slot) = next {
+            let fork_infos = &self.fork_infos;
+            let best_slot = fork_infos[&slot]
+                .children
+                .iter()
+                .max_by(|a, b| {
+                    // The heavier subtree wins, ties go to the lower slot
+                    fork_infos[*a]
+                        .stake_voted_subtree
+                        .cmp(&fork_infos[*b].stake_voted_subtree)
+                        .then_with(|| b.cmp(a))
+                })
+                .map(|child| fork_infos[child].best_slot)
+                .unwrap_or(slot);
+            let info = self.fork_infos.get_mut(&slot).unwrap();
+            info.best_slot = best_slot;
+            next = info.parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{thread_rng, Rng};
+    use std::collections::HashSet;
+
+    // 0
+    // ├── 1
+    // │   └── 3
+    // │       └── 5
+    // └── 2
+    //     └── 4
+    fn setup_forks() -> HeaviestSubtreeForkChoice {
+        let mut fork_choice = HeaviestSubtreeForkChoice::new(0);
+        for (slot, parent) in &[(1, 0), (2, 0), (3, 1), (4, 2), (5, 3)] {
+            fork_choice.add_new_leaf_slot(*slot, Some(*parent));
+        }
+        fork_choice
+    }
+
+    #[test]
+    fn test_best_slot_without_votes() {
+        let fork_choice = setup_forks();
+        // Ties go to the lower child
+        assert_eq!(fork_choice.best_overall_slot(), 5);
+        assert_eq!(fork_choice.best_slot(2), Some(4));
+        assert_eq!(fork_choice.best_slot(6), None);
+        assert!(fork_choice.is_on_heaviest_fork(3));
+        assert!(!fork_choice.is_on_heaviest_fork(2));
+    }
+
+    #[test]
+    fn test_add_votes() {
+        let mut fork_choice = setup_forks();
+        let validator0 = Pubkey::new_rand();
+        let validator1 = Pubkey::new_rand();
+
+        fork_choice.add_votes(vec![(validator0, 4, 10)]);
+        assert_eq!(fork_choice.best_overall_slot(), 4);
+        assert_eq!(fork_choice.stake_voted_subtree(0), Some(10));
+        assert_eq!(fork_choice.stake_voted_subtree(2), Some(10));
+        assert_eq!(fork_choice.stake_voted_subtree(1), Some(0));
+
+        fork_choice.add_votes(vec![(validator1, 3, 20)]);
+        assert_eq!(fork_choice.best_overall_slot(), 5);
+        assert_eq!(fork_choice.stake_voted_at(3), Some(20));
+        assert_eq!(fork_choice.stake_voted_subtree(0), Some(30));
+
+        // An older vote doesn't count
+        fork_choice.add_votes(vec![(validator1, 2, 20)]);
+        assert_eq!(fork_choice.latest_vote(&validator1), Some(3));
+        assert_eq!(fork_choice.stake_voted_subtree(2), Some(10));
+
+        // A newer vote moves the stake
+        fork_choice.add_votes(vec![(validator1, 4, 20)]);
+        assert_eq!(fork_choice.best_overall_slot(), 4);
+        assert_eq!(fork_choice.stake_voted_at(3), Some(0));
+        assert_eq!(fork_choice.stake_voted_subtree(1), Some(0));
+        assert_eq!(fork_choice.stake_voted_subtree(4), Some(30));
+
+        // Votes on unknown slots are ignored
+        fork_choice.add_votes(vec![(validator0, 7, 10)]);
+        assert_eq!(fork_choice.latest_vote(&validator0), Some(4));
+    }
+
+    #[test]
+    fn test_best_unlocked_slot() {
+        let mut fork_choice = setup_forks();
+        fork_choice.add_votes(vec![(Pubkey::new_rand(), 3, 20)]);
+        let fork_2: HashSet<u64> = [2, 4].iter().cloned().collect();
+        let locked_out_of_fork_1 = |slot| !fork_2.contains(&slot);
+
+        assert_eq!(fork_choice.best_unlocked_slot(Some(2), |_| false), Some(5));
+        // A validator locked out of the heaviest fork builds on its own
+        assert_eq!(
+            fork_choice.best_unlocked_slot(Some(2), locked_out_of_fork_1),
+            Some(4)
+        );
+        // Never fall back to a locked out slot, even if the voted fork was pruned
+        assert_eq!(
+            fork_choice.best_unlocked_slot(None, locked_out_of_fork_1),
+            None
+        );
+        fork_choice.remove_leaf_slot(4);
+        assert_eq!(
+            fork_choice.best_unlocked_slot(Some(4), locked_out_of_fork_1),
+            None
+        );
+    }
+
+    #[test]
+    fn test_add_new_leaf_slot() {
+        let mut fork_choice = setup_forks();
+        fork_choice.add_votes(vec![(Pubkey::new_rand(), 4, 10)]);
+        fork_choice.add_new_leaf_slot(6, Some(4));
+        assert_eq!(fork_choice.best_overall_slot(), 6);
+
+        // Unknown parent
+        fork_choice.add_new_leaf_slot(8, Some(7));
+        assert!(!fork_choice.contains_slot(8));
+    }
+
+    #[test]
+    fn test_set_root() {
+        let mut fork_choice = setup_forks();
+        let validator0 = Pubkey::new_rand();
+        let validator1 = Pubkey::new_rand();
+        fork_choice.add_votes(vec![(validator0, 4, 10), (validator1, 5, 5)]);
+        assert_eq!(fork_choice.best_overall_slot(), 4);
+
+        fork_choice.set_root(1);
+        assert_eq!(fork_choice.root(), 1);
+        assert!(!fork_choice.contains_slot(0));
+        assert!(!fork_choice.contains_slot(2));
+        assert!(!fork_choice.contains_slot(4));
+        assert_eq!(fork_choice.best_overall_slot(), 5);
+        assert_eq!(fork_choice.stake_voted_subtree(1), Some(5));
+        assert_eq!(fork_choice.latest_vote(&validator0), None);
+        assert_eq!(fork_choice.latest_vote(&validator1), Some(5));
+    }
+
+    #[test]
+    fn test_remove_leaf_slot() {
+        let mut fork_choice = setup_forks();
+        let validator0 = Pubkey::new_rand();
+        let validator1 = Pubkey::new_rand();
+        fork_choice.add_votes(vec![(validator0, 4, 10), (validator1, 5, 5)]);
+        assert_eq!(fork_choice.best_overall_slot(), 4);
+
+        fork_choice.remove_leaf_slot(4);
+        assert!(!fork_choice.contains_slot(4));
+        assert_eq!(fork_choice.latest_vote(&validator0), None);
+        assert_eq!(fork_choice.stake_voted_subtree(2), Some(0));
+        assert_eq!(fork_choice.stake_voted_subtree(0), Some(5));
+        assert_eq!(fork_choice.best_overall_slot(), 5);
+
+        // Slot 2 is now a leaf
+        fork_choice.remove_leaf_slot(2);
+        assert!(!fork_choice.contains_slot(2));
+        assert_eq!(fork_choice.best_overall_slot(), 5);
+
+        // Unknown slots are ignored
+        fork_choice.remove_leaf_slot(7);
+        assert_eq!(fork_choice.stake_voted_subtree(0), Some(5));
+
+        // The removed slot's validator can vote again
+        fork_choice.add_votes(vec![(validator0, 3, 10)]);
+        assert_eq!(fork_choice.stake_voted_subtree(0), Some(15));
+    }
+
+    // Recompute every subtree weight and the best slot from scratch
+    fn brute_force(
+        parents: &HashMap<u64, Option<u64>>,
+        latest_votes: &HashMap<Pubkey, (u64, u64)>,
+        root: u64,
+    ) -> (HashMap<u64, u64>, u64) {
+        let mut subtree_stakes: HashMap<u64, u64> = parents.keys().map(|s| (*s, 0)).collect();
+        for (slot, stake) in latest_votes.values() {
+            let mut next = Some(*slot);
+            while let Some(slot) = next {
+                *subtree_stakes.get_mut(&slot).unwrap() += stake;
+                next = parents[&slot];
+            }
+        }
+
+        let mut best_slot = root;
+        loop {
+            let best_child = parents
+                .iter()
+                .filter(|(_, parent)| **parent == Some(best_slot))
+                .map(|(child, _)| *child)
+                .max_by(|a, b| {
+                    subtree_stakes[a]
+                        .cmp(&subtree_stakes[b])
+                        .then_with(|| b.cmp(a))
+                });
+            match best_child {
+                Some(child) => best_slot = child,
+                None => break,
+            }
+        }
+        (subtree_stakes, best_slot)
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut rng = thread_rng();
+        for _ in 0..50 {
+            let validators: Vec<_> = (0..8)
+                .map(|_| (Pubkey::new_rand(), rng.gen_range(1, 100)))
+                .collect();
+            let mut fork_choice = HeaviestSubtreeForkChoice::new(0);
+            let mut parents: HashMap<u64, Option<u64>> = HashMap::new();
+            parents.insert(0, None);
+            let mut latest_votes: HashMap<Pubkey, (u64, u64)> = HashMap::new();
+            let mut root = 0;
+
+            for slot in 1..100 {
+                let slots: Vec<u64> = parents.keys().cloned().collect();
+                let parent = slots[rng.gen_range(0, slots.len())];
+                fork_choice.add_new_leaf_slot(slot, Some(parent));
+                parents.insert(slot, Some(parent));
+
+                for _ in 0..rng.gen_range(0, 4) {
+                    let (pubkey, stake) = validators[rng.gen_range(0, validators.len())];
+                    let slots: Vec<u64> = parents.keys().cloned().collect();
+                    let vote_slot = slots[rng.gen_range(0, slots.len())];
+                    fork_choice.add_votes(vec![(pubkey, vote_slot, stake)]);
+                    if latest_votes
+                        .get(&pubkey)
+                        .map(|(old_slot, _)| *old_slot < vote_slot)
+                        .unwrap_or(true)
+                    {
+                        latest_votes.insert(pubkey, (vote_slot, stake));
+                    }
+                }
+
+                if rng.gen_range(0, 10) == 0 {
+                    let slots: Vec<u64> = parents.keys().cloned().collect();
+                    let new_root = slots[rng.gen_range(0, slots.len())];
+                    fork_choice.set_root(new_root);
+
+                    let mut descendants = HashSet::new();
+                    for slot in &slots {
+                        let mut next = Some(*slot);
+                        while let Some(ancestor) = next {
+                            if ancestor == new_root {
+                                descendants.insert(*slot);
+                                break;
+                            }
+                            next = parents[&ancestor];
+                        }
+                    }
+                    parents.retain(|slot, _| descendants.contains(slot));
+                    parents.insert(new_root, None);
+                    latest_votes.retain(|_, vote| descendants.contains(&vote.0));
+                    root = new_root;
+                }
+
+                let (subtree_stakes, best_slot) = brute_force(&parents, &latest_votes, root);
+                assert_eq!(fork_choice.root(), root);
+                assert_eq!(fork_choice.best_overall_slot(), best_slot);
+                for (slot, stake) in &subtree_stakes {
+                    assert_eq!(fork_choice.stake_voted_subtree(*slot), Some(*stake));
+                }
+                assert_eq!(fork_choice.fork_infos.len(), parents.len());
+            }
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! The `io_probe` module measures how fast a directory's disk can write, so that a validator
+//! can warn at startup when its accounts or ledger are on a disk too slow to keep up.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Instant;
+
+const PROBE_FILE_NAME: &str = ".io_probe";
+const SYNCED_WRITE_SIZE: usize = 4 * 1024;
+const SYNCED_WRITES: usize = 256;
+const THROUGHPUT_CHUNK_SIZE: usize = 1024 * 1024;
+const THROUGHPUT_CHUNKS: usize = 16;
+
+/// The minimum write performance recommended for a directory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IoRecommendation {
+    /// Synced 4 KiB writes per second
+    pub min_iops: f64,
+    /// Sequential writes, in megabytes per second
+    pub min_throughput_mb: f64,
+}
+
+/// The accounts are written with every transaction, and want NVMe class disks
+pub const ACCOUNTS_IO_RECOMMENDATION: IoRecommendation = IoRecommendation {
+    min_iops: 1000.0,
+    min_throughput_mb: 500.0,
+};
+
+/// The ledger is mostly appended to, a large spinning disk keeps up
+pub const LEDGER_IO_RECOMMENDATION: IoRecommendation = IoRecommendation {
+    min_iops: 200.0,
+    min_throughput_mb: 100.0,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IoProbe {
+    pub iops: f64,
+    pub throughput_mb: f64,
+}
+
+impl IoProbe {
+    /// Measure the write performance of the disk `dir` is on, creating `dir` if needed
+    pub fn new(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let probe_file = dir.join(PROBE_FILE_NAME);
+        let result = Self::measure(&probe_file);
+        let _ = fs::remove_file(&probe_file);
+        result
+    }
+
+    fn measure(probe_file: &Path) -> io::Result<Self> {
+        let mut file = File::create(probe_file)?;
+
+        let block = vec![0xa5; SYNCED_WRITE_SIZE];
+        let now = Instant::now();
+        for _ in 0..SYNCED_WRITES {
+            file.write_all(&block)?;
+            file.sync_data()?;
+        }
+        let iops = SYNCED_WRITES as f64 / as_secs_f64(now);
+
+        let chunk = vec![0x5a; THROUGHPUT_CHUNK_SIZE];
+        let now = Instant::now();
+        for _ in 0..THROUGHPUT_CHUNKS {
+            file.write_all(&chunk)?;
+        }
+        file.sync_all()?;
+        let throughput_mb = (THROUGHPUT_CHUNKS * THROUGHPUT_CHUNK_SIZE) as f64
+            / (1024 * 1024) as f64
+            / as_secs_f64(now);
+
+        Ok(Self {
+            iops,
+            throughput_mb,
+        })
+    }
+
+    /// Describe how this probe falls short of `recommendation`, if it does
+    pub fn shortfalls(&self, recommendation: &IoRecommendation) -> Vec<String> {
+        let mut shortfalls = vec![];
+        if self.iops < recommendation.min_iops {
+            shortfalls.push(format!(
+                "{:.0} IOPS, {:.0} recommended",
+                self.iops, recommendation.min_iops
+            ));
+        }
+        if self.throughput_mb < recommendation.min_throughput_mb {
+            shortfalls.push(format!(
+                "{:.0} MB/s, {:.0} MB/s recommended",
+                self.throughput_mb, recommendation.min_throughput_mb
+            ));
+        }
+        shortfalls
+    }
+}
+
+// Guard against a zero elapsed time on coarse clocks
+fn as_secs_f64(start: Instant) -> f64 {
+    let elapsed = start.elapsed();
+    (elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1_000_000_000.0).max(1e-9)
+}
+
+/// Probe the disk of each of `dirs`, and warn about any that are slower than `recommendation`
+pub fn warn_if_slow<P: AsRef<Path>>(name: &str, dirs: &[P], recommendation: &IoRecommendation) {
+    for dir in dirs {
+        let dir = dir.as_ref();
+        match IoProbe::new(dir) {
+            Ok(probe) => {
+                info!(
+                    "{} path {:?}: {:.0} IOPS, {:.0} MB/s",
+                    name, dir, probe.iops, probe.throughput_mb
+                );
+                let shortfalls = probe.shortfalls(recommendation);
+                if !shortfalls.is_empty() {
+                    warn!(
+                        "{} path {:?} is on a slow disk: {}",
+                        name,
+                        dir,
+                        shortfalls.join(", ")
+                    );
+                }
+            }
+            Err(err) => warn!("Unable to probe {} path {:?}: {:?}", name, dir, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocktree::get_tmp_ledger_path;
+
+    #[test]
+    fn test_io_probe() {
+        let dir = get_tmp_ledger_path("test_io_probe");
+        let probe = IoProbe::new(Path::new(&dir)).unwrap();
+        assert!(probe.iops > 0.0);
+        assert!(probe.throughput_mb > 0.0);
+        assert!(!Path::new(&dir).join(PROBE_FILE_NAME).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_io_probe_shortfalls() {
+        let probe = IoProbe {
+            iops: 300.0,
+            throughput_mb: 150.0,
+        };
+        assert!(probe.shortfalls(&LEDGER_IO_RECOMMENDATION).is_empty());
+        assert_eq!(
+            probe.shortfalls(&ACCOUNTS_IO_RECOMMENDATION),
+            vec![
+                "300 IOPS, 1000 recommended".to_string(),
+                "150 MB/s, 500 MB/s recommended".to_string(),
+            ]
+        );
+    }
+}
@@ -115,6 +115,35 @@ impl LeaderScheduleCache {
         first_slot.and_then(|slot| Some((slot, last_slot)))
     }
 
+    /// Return each (slot, leader) of `epoch`, or None if the epoch's schedule isn't known yet
+    pub fn epoch_slot_leaders(
+        &self,
+        epoch: u64,
+        bank: &Bank,
+    ) -> Option<impl Iterator<Item = (u64, Pubkey)>> {
+        // Forbid asking for an unconfirmed epoch, like `slot_leader_at`
+        if epoch > *self.max_epoch.read().unwrap() {
+            debug!("Requested leader schedule of unconfirmed epoch: {}", epoch);
+            return None;
+        }
+        let leader_schedule = self.get_epoch_schedule_else_compute(epoch, bank)?;
+        let first_slot = self.epoch_schedule.get_first_slot_in_epoch(epoch);
+        let slots_in_epoch = self.epoch_schedule.get_slots_in_epoch(epoch);
+        Some((0..slots_in_epoch).map(move |i| (first_slot + i, leader_schedule[i])))
+    }
+
+    /// Return every slot of `epoch` that `pubkey` is scheduled to lead
+    pub fn leader_slots_for_pubkey(&self, pubkey: &Pubkey, epoch: u64, bank: &Bank) -> Vec<u64> {
+        self.epoch_slot_leaders(epoch, bank)
+            .map(|slot_leaders| {
+                slot_leaders
+                    .filter(|(_, leader)| leader == pubkey)
+                    .map(|(slot, _)| slot)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     fn slot_leader_at_no_compute(&self, slot: u64) -> Option<Pubkey> {
         let (epoch, slot_index) = self.epoch_schedule.get_epoch_and_slot_index(slot);
         self.cached_schedules
@@ -200,6 +229,7 @@ mod tests {
     use crate::staking_utils::tests::setup_vote_and_stake_accounts;
     use solana_runtime::bank::Bank;
     use solana_runtime::epoch_schedule::{EpochSchedule, MINIMUM_SLOTS_PER_EPOCH};
+    use solana_sdk::signature::Keypair;
     use std::sync::mpsc::channel;
     use std::sync::Arc;
     use std::thread::Builder;
@@ -353,6 +383,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_leader_slots_for_pubkey() {
+        let pubkey = Pubkey::new_rand();
+        let mut genesis_block = create_genesis_block_with_leader(
+            BOOTSTRAP_LEADER_LAMPORTS,
+            &pubkey,
+            BOOTSTRAP_LEADER_LAMPORTS,
+        )
+        .genesis_block;
+        genesis_block.epoch_warmup = false;
+
+        let bank = Bank::new(&genesis_block);
+        let cache = LeaderScheduleCache::new_from_bank(&bank);
+        let slots_per_epoch = genesis_block.slots_per_epoch;
+
+        let slot_leaders: Vec<_> = cache.epoch_slot_leaders(1, &bank).unwrap().collect();
+        assert_eq!(slot_leaders.len() as u64, slots_per_epoch);
+        assert_eq!(slot_leaders[0], (slots_per_epoch, pubkey));
+        assert!(slot_leaders.iter().all(|(_, leader)| *leader == pubkey));
+
+        assert_eq!(
+            cache.leader_slots_for_pubkey(&pubkey, 1, &bank),
+            (slots_per_epoch..2 * slots_per_epoch).collect::<Vec<_>>()
+        );
+        assert!(cache
+            .leader_slots_for_pubkey(&Pubkey::new_rand(), 1, &bank)
+            .is_empty());
+
+        // No schedule generated for epoch 2
+        assert!(cache.epoch_slot_leaders(2, &bank).is_none());
+        assert!(cache.leader_slots_for_pubkey(&pubkey, 2, &bank).is_empty());
+    }
+
     #[test]
     fn test_next_leader_slot_blocktree() {
         let pubkey = Pubkey::new_rand();
@@ -448,11 +511,11 @@ mod tests {
 
         // Create new vote account
         let node_pubkey = Pubkey::new_rand();
-        let vote_pubkey = Pubkey::new_rand();
+        let vote_keypair = Keypair::new();
         setup_vote_and_stake_accounts(
             &bank,
             &mint_keypair,
-            &vote_pubkey,
+            &vote_keypair,
             &node_pubkey,
             BOOTSTRAP_LEADER_LAMPORTS,
         );
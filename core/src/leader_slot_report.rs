@@ -0,0 +1,211 @@
+//! The `leader_slot_report` module tracks whether the blocks this node produced as leader were
+//! rooted by the cluster, and why they were skipped if they weren't.
+
+use crate::bank_forks::BankForks;
+use crate::cluster_info::ClusterInfo;
+use crate::leader_schedule_cache::LeaderScheduleCache;
+use solana_metrics::datapoint_info;
+use solana_runtime::bank::Bank;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeSet;
+use std::sync::{Arc, RwLock};
+
+/// Why one of this node's leader slots didn't make it into the rooted fork
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The node never started a bank for the slot, so it produced no block
+    LateStart,
+    /// The node produced a block, but no other block was built on it
+    NoVotes,
+    /// Blocks were built on the node's block, but the cluster rooted another fork
+    ForkAbandoned,
+}
+
+impl SkipReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            SkipReason::LateStart => "late_start",
+            SkipReason::NoVotes => "no_votes",
+            SkipReason::ForkAbandoned => "fork_abandoned",
+        }
+    }
+}
+
+/// How many of this node's leader slots were rooted, and skipped for each reason
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LeaderSlotTotals {
+    pub rooted: u64,
+    pub late_start: u64,
+    pub no_votes: u64,
+    pub fork_abandoned: u64,
+}
+
+impl LeaderSlotTotals {
+    fn add(&mut self, skip_reason: Option<SkipReason>) {
+        match skip_reason {
+            None => self.rooted += 1,
+            Some(SkipReason::LateStart) => self.late_start += 1,
+            Some(SkipReason::NoVotes) => self.no_votes += 1,
+            Some(SkipReason::ForkAbandoned) => self.fork_abandoned += 1,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LeaderSlotReport {
+    /// Leader slots this node started a bank for, that haven't been reported yet
+    started_slots: BTreeSet<u64>,
+    totals: LeaderSlotTotals,
+    /// Also publish the totals to gossip after each new root
+    gossip: bool,
+}
+
+impl LeaderSlotReport {
+    pub fn new(gossip: bool) -> Self {
+        Self {
+            gossip,
+            ..Self::default()
+        }
+    }
+
+    /// Record that this node started producing a block for `slot`
+    pub fn start_slot(&mut self, slot: u64) {
+        self.started_slots.insert(slot);
+    }
+
+    pub fn totals(&self) -> LeaderSlotTotals {
+        self.totals
+    }
+
+    /// Report this node's leader slots after `prev_root` up to the new root, the last of
+    /// `rooted_banks`. Must be called before `bank_forks` prunes the forks the new root
+    /// abandons. Returns each reported slot, with the reason it was skipped if it wasn't rooted.
+    pub fn new_root(
+        &mut self,
+        my_pubkey: &Pubkey,
+        prev_root: u64,
+        rooted_banks: &[Arc<Bank>],
+        bank_forks: &BankForks,
+        leader_schedule_cache: &LeaderScheduleCache,
+        cluster_info: &Arc<RwLock<ClusterInfo>>,
+    ) -> Vec<(u64, Option<SkipReason>)> {
+        let root_bank: &Bank = rooted_banks.last().expect("rooted_banks is empty");
+        let rooted_slots: BTreeSet<_> = rooted_banks.iter().map(|bank| bank.slot()).collect();
+        let descendants = bank_forks.descendants();
+
+        let reported: Vec<_> = (prev_root + 1..=root_bank.slot())
+            .filter(|slot| {
+                leader_schedule_cache.slot_leader_at(*slot, Some(root_bank)) == Some(*my_pubkey)
+            })
+            .map(|slot| {
+                let skip_reason = if rooted_slots.contains(&slot) {
+                    None
+                } else if !self.started_slots.contains(&slot) {
+                    Some(SkipReason::LateStart)
+                } else if descendants
+                    .get(&slot)
+                    .map_or(true, |descendants| descendants.is_empty())
+                {
+                    Some(SkipReason::NoVotes)
+                } else {
+                    Some(SkipReason::ForkAbandoned)
+                };
+                (slot, skip_reason)
+            })
+            .collect();
+
+        for (slot, skip_reason) in &reported {
+            self.totals.add(*skip_reason);
+            datapoint_info!(
+                "leader_slot_report",
+                ("slot", *slot, i64),
+                ("rooted", skip_reason.is_none(), bool),
+                (
+                    "skip_reason",
+                    skip_reason.map_or("", SkipReason::as_str).to_string(),
+                    String
+                )
+            );
+        }
+        self.started_slots = self.started_slots.split_off(&(root_bank.slot() + 1));
+
+        if self.gossip && !reported.is_empty() {
+            cluster_info
+                .write()
+                .unwrap()
+                .push_leader_slots(root_bank.slot(), self.totals);
+        }
+        reported
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contact_info::ContactInfo;
+    use crate::genesis_utils::create_genesis_block_with_leader;
+
+    #[test]
+    fn test_leader_slot_report() {
+        let my_pubkey = Pubkey::new_rand();
+        let genesis_block = create_genesis_block_with_leader(10_000, &my_pubkey, 42).genesis_block;
+        let bank0 = Bank::new(&genesis_block);
+        let leader_schedule_cache = LeaderScheduleCache::new_from_bank(&bank0);
+        let mut bank_forks = BankForks::new(0, bank0);
+        let cluster_info = Arc::new(RwLock::new(ClusterInfo::new_with_invalid_keypair(
+            ContactInfo::new_localhost(&my_pubkey, 0),
+        )));
+
+        // The only staked node leads every slot. Slot 1 is rooted, slot 2 was built on by slot 3
+        // on an abandoned fork, slot 4 was never built on, and slot 5 was never started. Slot 6
+        // is rooted, on slot 1.
+        //
+        //  0 - 1 - 2 - 3
+        //       \- 4
+        //       \- 6
+        let bank0 = bank_forks[0].clone();
+        bank_forks.insert(Bank::new_from_parent(&bank0, &my_pubkey, 1));
+        for (slot, parent) in &[(2, 1), (3, 2), (4, 1), (6, 1)] {
+            let parent = bank_forks[*parent].clone();
+            bank_forks.insert(Bank::new_from_parent(&parent, &my_pubkey, *slot));
+        }
+        let mut leader_slot_report = LeaderSlotReport::new(true);
+        for slot in &[1, 2, 3, 4, 6] {
+            leader_slot_report.start_slot(*slot);
+        }
+
+        let root_bank = bank_forks[6].clone();
+        let mut rooted_banks = root_bank.parents();
+        rooted_banks.push(root_bank);
+        assert_eq!(
+            leader_slot_report.new_root(
+                &my_pubkey,
+                0,
+                &rooted_banks,
+                &bank_forks,
+                &leader_schedule_cache,
+                &cluster_info,
+            ),
+            vec![
+                (1, None),
+                (2, Some(SkipReason::ForkAbandoned)),
+                (3, Some(SkipReason::NoVotes)),
+                (4, Some(SkipReason::NoVotes)),
+                (5, Some(SkipReason::LateStart)),
+                (6, None),
+            ]
+        );
+        let totals = LeaderSlotTotals {
+            rooted: 2,
+            late_start: 1,
+            no_votes: 2,
+            fork_abandoned: 1,
+        };
+        assert_eq!(leader_slot_report.totals(), totals);
+        assert!(leader_slot_report.started_slots.is_empty());
+        assert_eq!(
+            cluster_info.read().unwrap().get_leader_slots(&my_pubkey),
+            Some((6, totals))
+        );
+    }
+}
@@ -2,7 +2,9 @@
 
 use crate::blocktree::Blocktree;
 use crate::result::{Error, Result};
+use crate::rpc_subscriptions::{RpcSubscriptions, SlotsPurgedInfo};
 use crate::service::Service;
+use crate::slot_range::SlotRange;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::timing::DEFAULT_SLOTS_PER_EPOCH;
 use std::string::ToString;
@@ -24,6 +26,7 @@ impl LedgerCleanupService {
         slot_full_receiver: Receiver<(u64, Pubkey)>,
         blocktree: Arc<Blocktree>,
         max_ledger_slots: u64,
+        subscriptions: &Arc<RpcSubscriptions>,
         exit: &Arc<AtomicBool>,
     ) -> Self {
         info!(
@@ -31,15 +34,19 @@ impl LedgerCleanupService {
             max_ledger_slots
         );
         let exit = exit.clone();
+        let subscriptions = subscriptions.clone();
         let t_cleanup = Builder::new()
             .name("solana-ledger-cleanup".to_string())
             .spawn(move || loop {
                 if exit.load(Ordering::Relaxed) {
                     break;
                 }
-                if let Err(e) =
-                    Self::cleanup_ledger(&slot_full_receiver, &blocktree, max_ledger_slots)
-                {
+                if let Err(e) = Self::cleanup_ledger(
+                    &slot_full_receiver,
+                    &blocktree,
+                    max_ledger_slots,
+                    &subscriptions,
+                ) {
                     match e {
                         Error::RecvTimeoutError(RecvTimeoutError::Disconnected) => break,
                         Error::RecvTimeoutError(RecvTimeoutError::Timeout) => (),
@@ -55,11 +62,22 @@ impl LedgerCleanupService {
         slot_full_receiver: &Receiver<(u64, Pubkey)>,
         blocktree: &Arc<Blocktree>,
         max_ledger_slots: u64,
+        subscriptions: &RpcSubscriptions,
     ) -> Result<()> {
         let (slot, _) = slot_full_receiver.recv_timeout(Duration::from_secs(1))?;
-        if slot > max_ledger_slots {
+        // Keep the `max_ledger_slots` slots after `slot - max_ledger_slots`, up to `slot`
+        if let Some(last_slot) = slot.checked_sub(max_ledger_slots) {
             //cleanup
-            blocktree.purge_slots(0, Some(slot - max_ledger_slots));
+            let first_slot = blocktree.lowest_slot();
+            blocktree.purge_slots(0, Some(last_slot));
+            if let Some(purged) = SlotRange::new(first_slot, last_slot) {
+                // Let subscribers know which slots they need to find elsewhere
+                subscriptions.notify_slots_purged(&SlotsPurgedInfo {
+                    first_slot: purged.start,
+                    last_slot: purged.end,
+                    minimum_ledger_slot: blocktree.lowest_slot(),
+                });
+            }
         }
         Ok(())
     }
@@ -90,13 +108,39 @@ mod tests {
 
         //send a signal to kill slots 0-40
         sender.send((50, Pubkey::default())).unwrap();
-        LedgerCleanupService::cleanup_ledger(&receiver, &blocktree, 10).unwrap();
+        let subscriptions = RpcSubscriptions::default();
+        LedgerCleanupService::cleanup_ledger(&receiver, &blocktree, 10, &subscriptions).unwrap();
 
         //check that 0-40 don't exist
         blocktree
             .slot_meta_iterator(0)
             .unwrap()
             .for_each(|(slot, _)| assert!(slot > 40));
+        assert_eq!(blocktree.lowest_slot(), 41);
+
+        drop(blocktree);
+        Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
+    }
+
+    #[test]
+    fn test_cleanup_keeps_max_ledger_slots() {
+        let blocktree_path = get_tmp_ledger_path!();
+        let blocktree = Blocktree::open(&blocktree_path).unwrap();
+        let (blobs, _) = make_many_slot_entries(0, 11, 5);
+        blocktree.write_blobs(blobs).unwrap();
+        let blocktree = Arc::new(blocktree);
+        let (sender, receiver) = channel();
+        let subscriptions = RpcSubscriptions::default();
+
+        // Slots 0-9 fit
+        sender.send((9, Pubkey::default())).unwrap();
+        LedgerCleanupService::cleanup_ledger(&receiver, &blocktree, 10, &subscriptions).unwrap();
+        assert_eq!(blocktree.lowest_slot(), 0);
+
+        // Slots 0-10 don't, so slot 0 goes
+        sender.send((10, Pubkey::default())).unwrap();
+        LedgerCleanupService::cleanup_ledger(&receiver, &blocktree, 10, &subscriptions).unwrap();
+        assert_eq!(blocktree.lowest_slot(), 1);
 
         drop(blocktree);
         Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
@@ -0,0 +1,316 @@
+//! The `ledger_storage` module keeps confirmed blocks and the statuses of their transactions in
+//! a long-term store, so that their history outlives the blocktree's, which drops older slots.
+//! Stores are reached through the `LedgerStorage` trait, a key-value interface that object
+//! storage or a Bigtable-like database can sit behind.
+
+use crate::blocktree::Blocktree;
+use crate::entry::Entry;
+use crate::result::Result;
+use bincode::{deserialize, serialize};
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{Transaction, TransactionStatusMeta};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// The key of the last slot uploaded, so uploads resume where they left off
+const LAST_UPLOADED_SLOT_KEY: &str = "last_uploaded_slot";
+
+/// A key-value store for the ledger's history. Values are only ever written once per key, so
+/// an eventually consistent store is fine.
+pub trait LedgerStorage: std::fmt::Debug + Send + Sync {
+    fn put(&self, key: &str, value: &[u8]) -> io::Result<()>;
+
+    /// The value of `key`, or `None` if it was never put
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+}
+
+/// Keeps each value in a file of its own under `root`, laid out the way it would be in an
+/// object storage bucket. The directory can be synced to, or mounted from, such a bucket.
+#[derive(Debug)]
+pub struct FileLedgerStorage {
+    root: PathBuf,
+}
+
+impl FileLedgerStorage {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl LedgerStorage for FileLedgerStorage {
+    fn put(&self, key: &str, value: &[u8]) -> io::Result<()> {
+        let path = self.root.join(key);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write to a temporary file first so readers never see part of a value
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, value)?;
+        fs::rename(tmp_path, path)
+    }
+
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.root.join(key)) {
+            Ok(value) => Ok(Some(value)),
+            Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryLedgerStorage {
+    values: RwLock<HashMap<String, Vec<u8>>>,
+}
+
+impl LedgerStorage for MemoryLedgerStorage {
+    fn put(&self, key: &str, value: &[u8]) -> io::Result<()> {
+        self.values
+            .write()
+            .unwrap()
+            .insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.values.read().unwrap().get(key).cloned())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TransactionWithStatus {
+    pub transaction: Transaction,
+    /// The transaction's result, fee and balance changes, `None` if they weren't recorded
+    pub meta: Option<TransactionStatusMeta>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConfirmedBlock {
+    pub slot: u64,
+    pub parent_slot: u64,
+    /// The hash of the block's last entry
+    pub blockhash: Hash,
+    /// The hash of the parent block's last entry
+    pub previous_blockhash: Hash,
+    pub transactions: Vec<TransactionWithStatus>,
+}
+
+impl ConfirmedBlock {
+    /// Reads the block of `slot` from `blocktree`, with the transaction statuses it recorded.
+    /// Returns `None` if the slot isn't full.
+    pub fn from_blocktree(blocktree: &Blocktree, slot: u64) -> Result<Option<Self>> {
+        let entries = blocktree.get_slot_entries(slot, 0, None)?;
+        Self::from_entries(blocktree, slot, &entries, None)
+    }
+
+    /// Builds the block of `slot` from its `entries`, already read from `blocktree`. `previous`
+    /// is the slot and last entry hash of a block read before this one, which saves reading the
+    /// parent's entries again when it is this block's parent.
+    pub fn from_entries(
+        blocktree: &Blocktree,
+        slot: u64,
+        entries: &[Entry],
+        previous: Option<(u64, Hash)>,
+    ) -> Result<Option<Self>> {
+        let meta = match blocktree.meta(slot)? {
+            Some(meta) if meta.is_full() => meta,
+            _ => return Ok(None),
+        };
+        let last_hash = |slot| -> Result<Hash> {
+            match previous {
+                Some((previous_slot, hash)) if previous_slot == slot => Ok(hash),
+                _ => {
+                    let entries = blocktree.get_slot_entries(slot, 0, None)?;
+                    Ok(entries.last().map(|entry| entry.hash).unwrap_or_default())
+                }
+            }
+        };
+        let (parent_slot, previous_blockhash) = if slot == 0 {
+            (0, Hash::default())
+        } else {
+            (meta.parent_slot, last_hash(meta.parent_slot)?)
+        };
+        let transactions = entries
+            .iter()
+            .flat_map(|entry| entry.transactions.iter())
+            .map(|transaction| {
+                Ok(TransactionWithStatus {
+                    transaction: transaction.clone(),
+                    meta: blocktree.get_transaction_status(slot, &transaction.signatures[0])?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Some(Self {
+            slot,
+            parent_slot,
+            blockhash: entries.last().map(|entry| entry.hash).unwrap_or_default(),
+            previous_blockhash,
+            transactions,
+        }))
+    }
+}
+
+fn block_key(slot: u64) -> String {
+    // Zero padded, so keys list in slot order
+    format!("blocks/{:016x}", slot)
+}
+
+fn transaction_key(signature: &Signature) -> String {
+    format!("transactions/{}", signature)
+}
+
+fn io_error(err: bincode::Error) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, err.to_string())
+}
+
+/// Stores `block`, and the slot and status metadata of each of its transactions
+pub fn upload_block(storage: &dyn LedgerStorage, block: &ConfirmedBlock) -> io::Result<()> {
+    for transaction in &block.transactions {
+        let value = serialize(&(block.slot, &transaction.meta)).map_err(io_error)?;
+        storage.put(
+            &transaction_key(&transaction.transaction.signatures[0]),
+            &value,
+        )?;
+    }
+    // Stored last, so a block that's found has all of its transactions indexed
+    storage.put(&block_key(block.slot), &serialize(block).map_err(io_error)?)
+}
+
+pub fn get_block(storage: &dyn LedgerStorage, slot: u64) -> io::Result<Option<ConfirmedBlock>> {
+    storage
+        .get(&block_key(slot))?
+        .map(|value| deserialize(&value).map_err(io_error))
+        .transpose()
+}
+
+/// The slot and status metadata of the transaction with `signature`
+pub fn get_transaction_status(
+    storage: &dyn LedgerStorage,
+    signature: &Signature,
+) -> io::Result<Option<(u64, Option<TransactionStatusMeta>)>> {
+    storage
+        .get(&transaction_key(signature))?
+        .map(|value| deserialize(&value).map_err(io_error))
+        .transpose()
+}
+
+pub fn get_last_uploaded_slot(storage: &dyn LedgerStorage) -> io::Result<Option<u64>> {
+    storage
+        .get(LAST_UPLOADED_SLOT_KEY)?
+        .map(|value| deserialize(&value).map_err(io_error))
+        .transpose()
+}
+
+pub fn set_last_uploaded_slot(storage: &dyn LedgerStorage, slot: u64) -> io::Result<()> {
+    storage.put(LAST_UPLOADED_SLOT_KEY, &serialize(&slot).map_err(io_error)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocktree::{create_new_tmp_ledger, get_tmp_ledger_path};
+    use crate::entry::{create_ticks, Entry};
+    use crate::genesis_utils::{create_genesis_block, GenesisBlockInfo};
+    use solana_runtime::bank::Bank;
+    use solana_sdk::signature::{Keypair, KeypairUtil};
+    use solana_sdk::system_transaction;
+
+    #[test]
+    fn test_file_ledger_storage() {
+        let root = get_tmp_ledger_path!();
+        let storage = FileLedgerStorage::new(&root);
+        assert_eq!(storage.get("blocks/1").unwrap(), None);
+        storage.put("blocks/1", &[1, 2, 3]).unwrap();
+        assert_eq!(storage.get("blocks/1").unwrap(), Some(vec![1, 2, 3]));
+        storage.put("blocks/1", &[4]).unwrap();
+        assert_eq!(storage.get("blocks/1").unwrap(), Some(vec![4]));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_confirmed_block() {
+        let GenesisBlockInfo {
+            genesis_block,
+            mint_keypair,
+            ..
+        } = create_genesis_block(1000);
+        let bank = Bank::new(&genesis_block);
+        let (ledger_path, genesis_blockhash) = create_new_tmp_ledger!(&genesis_block);
+        let blocktree = Blocktree::open(&ledger_path).unwrap();
+
+        // Slot 1 has a transaction the bank recorded the status of, and one it never saw
+        let tx = system_transaction::create_user_account(
+            &mint_keypair,
+            &Keypair::new().pubkey(),
+            1,
+            bank.last_blockhash(),
+        );
+        bank.process_transaction(&tx).unwrap();
+        let unknown_tx = system_transaction::create_user_account(
+            &mint_keypair,
+            &Keypair::new().pubkey(),
+            2,
+            bank.last_blockhash(),
+        );
+        let mut entries = create_ticks(1, genesis_blockhash);
+        entries.push(Entry::new(
+            &entries[0].hash,
+            1,
+            vec![tx.clone(), unknown_tx.clone()],
+        ));
+        entries.extend(create_ticks(
+            genesis_block.ticks_per_slot - 1,
+            entries[1].hash,
+        ));
+        blocktree
+            .write_entries(1, 0, 0, genesis_block.ticks_per_slot, &entries)
+            .unwrap();
+        blocktree
+            .write_transaction_statuses(1, &bank.transaction_status_metas())
+            .unwrap();
+        let meta = bank.get_transaction_status_meta(&tx.signatures[0]);
+        assert!(meta.is_some());
+
+        let block = ConfirmedBlock::from_blocktree(&blocktree, 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(block.parent_slot, 0);
+        assert_eq!(block.blockhash, entries.last().unwrap().hash);
+        assert_eq!(block.previous_blockhash, genesis_blockhash);
+        assert_eq!(
+            block.transactions,
+            vec![
+                TransactionWithStatus {
+                    transaction: tx.clone(),
+                    meta: meta.clone(),
+                },
+                TransactionWithStatus {
+                    transaction: unknown_tx,
+                    meta: None,
+                },
+            ]
+        );
+        assert_eq!(ConfirmedBlock::from_blocktree(&blocktree, 2).unwrap(), None);
+
+        let storage = MemoryLedgerStorage::default();
+        assert_eq!(get_block(&storage, 1).unwrap(), None);
+        upload_block(&storage, &block).unwrap();
+        assert_eq!(get_block(&storage, 1).unwrap(), Some(block));
+        assert_eq!(
+            get_transaction_status(&storage, &tx.signatures[0]).unwrap(),
+            Some((1, meta))
+        );
+        assert_eq!(get_last_uploaded_slot(&storage).unwrap(), None);
+        set_last_uploaded_slot(&storage, 1).unwrap();
+        assert_eq!(get_last_uploaded_slot(&storage).unwrap(), Some(1));
+
+        drop(blocktree);
+        Blocktree::destroy(&ledger_path).unwrap();
+    }
+}
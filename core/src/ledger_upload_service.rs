@@ -0,0 +1,156 @@
+//! The `ledger_upload_service` uploads rooted blocks to a `LedgerStorage`, so that their history
+//! stays available over RPC after the blocktree drops them
+
+use crate::bank_forks::BankForks;
+use crate::blocktree::Blocktree;
+use crate::ledger_storage::{self, ConfirmedBlock, LedgerStorage};
+use crate::result::Result;
+use crate::service::Service;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, sleep, Builder, JoinHandle};
+use std::time::Duration;
+
+pub struct LedgerUploadService {
+    t_upload: JoinHandle<()>,
+}
+
+impl LedgerUploadService {
+    pub fn new(
+        storage: Arc<dyn LedgerStorage>,
+        blocktree: Arc<Blocktree>,
+        bank_forks: Arc<RwLock<BankForks>>,
+        exit: &Arc<AtomicBool>,
+    ) -> Self {
+        let exit = exit.clone();
+        let t_upload = Builder::new()
+            .name("solana-ledger-upload".to_string())
+            .spawn(move || loop {
+                if exit.load(Ordering::Relaxed) {
+                    break;
+                }
+                if let Err(e) = Self::upload_rooted_blocks(&*storage, &blocktree, &bank_forks) {
+                    info!("Error from upload_rooted_blocks: {:?}", e);
+                }
+                sleep(Duration::from_secs(1));
+            })
+            .unwrap();
+        Self { t_upload }
+    }
+
+    /// Uploads the rooted blocks after the last one uploaded, up to the current root. Returns
+    /// how many were uploaded.
+    pub fn upload_rooted_blocks(
+        storage: &dyn LedgerStorage,
+        blocktree: &Blocktree,
+        bank_forks: &RwLock<BankForks>,
+    ) -> Result<usize> {
+        let root = bank_forks.read().unwrap().root();
+        let lowest_slot = blocktree.lowest_slot();
+        let mut start_slot = match ledger_storage::get_last_uploaded_slot(storage)? {
+            Some(last_uploaded_slot) => last_uploaded_slot + 1,
+            None => lowest_slot,
+        };
+        if start_slot < lowest_slot {
+            warn!(
+                "slots {} to {} were purged before being uploaded",
+                start_slot,
+                lowest_slot - 1
+            );
+            start_slot = lowest_slot;
+        }
+
+        let mut uploaded = 0;
+        if start_slot <= root {
+            let mut previous = None;
+            for slot_entries in blocktree.get_entries_for_slots(start_slot..root + 1)? {
+                let (slot, entries) = slot_entries?;
+                if blocktree.is_root(slot) {
+                    if let Some(block) =
+                        ConfirmedBlock::from_entries(blocktree, slot, &entries, previous)?
+                    {
+                        ledger_storage::upload_block(storage, &block)?;
+                        ledger_storage::set_last_uploaded_slot(storage, slot)?;
+                        uploaded += 1;
+                    }
+                }
+                previous = Some((
+                    slot,
+                    entries.last().map(|entry| entry.hash).unwrap_or_default(),
+                ));
+            }
+            ledger_storage::set_last_uploaded_slot(storage, root)?;
+        }
+        if uploaded > 0 {
+            datapoint_info!(
+                "ledger_upload_service",
+                ("uploaded", uploaded, i64),
+                ("root", root, i64)
+            );
+        }
+        Ok(uploaded)
+    }
+}
+
+impl Service for LedgerUploadService {
+    type JoinReturnType = ();
+
+    fn join(self) -> thread::Result<()> {
+        self.t_upload.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocktree::create_new_tmp_ledger;
+    use crate::entry::create_ticks;
+    use crate::genesis_utils::create_genesis_block;
+    use crate::ledger_storage::MemoryLedgerStorage;
+    use solana_runtime::bank::Bank;
+    use solana_sdk::pubkey::Pubkey;
+
+    #[test]
+    fn test_upload_rooted_blocks() {
+        let genesis_block = create_genesis_block(1000).genesis_block;
+        let (ledger_path, genesis_blockhash) = create_new_tmp_ledger!(&genesis_block);
+        let blocktree = Blocktree::open(&ledger_path).unwrap();
+        let ticks_per_slot = genesis_block.ticks_per_slot;
+        let entries = create_ticks(ticks_per_slot, genesis_blockhash);
+        blocktree
+            .write_entries(1, 0, 0, ticks_per_slot, &entries)
+            .unwrap();
+        blocktree.set_roots(&[0, 1]).unwrap();
+
+        let bank0 = Bank::new(&genesis_block);
+        let bank_forks = RwLock::new(BankForks::new(0, bank0));
+        let storage = MemoryLedgerStorage::default();
+        assert_eq!(
+            LedgerUploadService::upload_rooted_blocks(&storage, &blocktree, &bank_forks).unwrap(),
+            1
+        );
+        assert_eq!(
+            ledger_storage::get_last_uploaded_slot(&storage).unwrap(),
+            Some(0)
+        );
+
+        // Slot 1 is uploaded once the bank forks root it
+        let bank1 = Bank::new_from_parent(&bank_forks.read().unwrap()[0], &Pubkey::default(), 1);
+        bank_forks.write().unwrap().insert(bank1);
+        bank_forks.write().unwrap().set_root(1);
+        assert_eq!(
+            LedgerUploadService::upload_rooted_blocks(&storage, &blocktree, &bank_forks).unwrap(),
+            1
+        );
+        let block = ledger_storage::get_block(&storage, 1).unwrap().unwrap();
+        assert_eq!(block.blockhash, entries.last().unwrap().hash);
+        assert_eq!(block.previous_blockhash, genesis_blockhash);
+        assert_eq!(
+            LedgerUploadService::upload_rooted_blocks(&storage, &blocktree, &bank_forks).unwrap(),
+            0
+        );
+
+        drop(blocktree);
+        Blocktree::destroy(&ledger_path).unwrap();
+    }
+}
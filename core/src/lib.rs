@@ -5,9 +5,11 @@
 //! command-line tools to spin up fullnodes and a Rust library
 //!
 
+pub mod accounts_hash_verifier;
 pub mod bank_forks;
 pub mod banking_stage;
 pub mod blob_fetch_stage;
+pub mod block_commitment;
 pub mod broadcast_stage;
 pub mod chacha;
 #[cfg(cuda)]
@@ -33,22 +35,35 @@ pub mod cluster_info_repair_listener;
 pub mod cluster_tests;
 pub mod consensus;
 pub mod cuda_runtime;
+pub mod dedup_filter;
 pub mod entry;
+pub mod entry_verifier;
 pub mod erasure;
 pub mod fetch_stage;
 pub mod gen_keys;
 pub mod genesis_utils;
 pub mod gossip_service;
+pub mod hash_chain;
+pub mod heaviest_subtree_fork_choice;
+pub mod io_probe;
 pub mod leader_schedule;
 pub mod leader_schedule_cache;
 pub mod leader_schedule_utils;
+pub mod leader_slot_report;
 pub mod ledger_cleanup_service;
+pub mod ledger_storage;
+pub mod ledger_upload_service;
 pub mod local_cluster;
 pub mod local_vote_signer_service;
+pub mod nat_traversal;
+pub mod owner_change_auditor;
 pub mod packet;
+pub mod partition_cfg;
 pub mod poh;
 pub mod poh_recorder;
 pub mod poh_service;
+pub mod prefetch_stage;
+pub mod quic_streamer;
 pub mod recvmmsg;
 pub mod repair_service;
 pub mod replay_stage;
@@ -63,6 +78,7 @@ pub mod rpc_subscriptions;
 pub mod service;
 pub mod sigverify;
 pub mod sigverify_stage;
+pub mod slot_range;
 pub mod staking_utils;
 pub mod storage_stage;
 pub mod streamer;
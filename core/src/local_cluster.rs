@@ -4,6 +4,7 @@ use crate::cluster_info::{Node, FULLNODE_PORT_RANGE};
 use crate::contact_info::ContactInfo;
 use crate::genesis_utils::{create_genesis_block_with_leader, GenesisBlockInfo};
 use crate::gossip_service::discover_cluster;
+use crate::partition_cfg::PartitionCfg;
 use crate::replicator::Replicator;
 use crate::service::Service;
 use crate::validator::{Validator, ValidatorConfig};
@@ -24,10 +25,11 @@ use solana_storage_api::storage_contract;
 use solana_storage_api::storage_instruction;
 use solana_vote_api::vote_instruction;
 use solana_vote_api::vote_state::VoteState;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::remove_dir_all;
 use std::io::{Error, ErrorKind, Result};
 use std::sync::Arc;
+use std::time::Duration;
 
 use solana_librapay_api::librapay_transaction;
 use solana_move_loader_api;
@@ -119,6 +121,8 @@ pub struct LocalCluster {
     replicators: Vec<Replicator>,
     pub replicator_infos: HashMap<Pubkey, ReplicatorInfo>,
     pub libra_mint_keypair: Arc<Keypair>,
+    /// Shared by every node, so partitions apply as soon as they're added
+    partition_cfg: PartitionCfg,
 }
 
 impl LocalCluster {
@@ -182,6 +186,11 @@ impl LocalCluster {
         let leader_storage_keypair = Arc::new(storage_keypair);
         let libra_mint_keypair = Arc::new(libra_mint_keypair);
         let leader_voting_keypair = Arc::new(voting_keypair);
+        let partition_cfg = PartitionCfg::default();
+        let leader_config = ValidatorConfig {
+            partition_cfg: Some(partition_cfg.clone()),
+            ..config.validator_configs[0].clone()
+        };
         let leader_server = Validator::new(
             leader_node,
             &leader_keypair,
@@ -191,7 +200,7 @@ impl LocalCluster {
             &leader_storage_keypair,
             None,
             true,
-            &config.validator_configs[0],
+            &leader_config,
         );
 
         let mut fullnodes = HashMap::new();
@@ -205,8 +214,7 @@ impl LocalCluster {
             contact_info: leader_contact_info.clone(),
         };
 
-        let cluster_leader =
-            ClusterValidatorInfo::new(leader_info, config.validator_configs[0].clone());
+        let cluster_leader = ClusterValidatorInfo::new(leader_info, leader_config);
 
         fullnode_infos.insert(leader_pubkey, cluster_leader);
 
@@ -220,6 +228,7 @@ impl LocalCluster {
             replicator_infos: HashMap::new(),
             listener_infos: HashMap::new(),
             libra_mint_keypair,
+            partition_cfg,
         };
 
         for (stake, validator_config) in (&config.node_stakes[1..])
@@ -271,7 +280,31 @@ impl LocalCluster {
         }
     }
 
+    /// Splits the nodes into `groups` that can't reach each other for `duration`. Nodes left out
+    /// of every group can still reach, and be reached by, any node.
+    pub fn partition(&self, groups: &[&[Pubkey]], duration: Duration) {
+        info!(
+            "partitioning the cluster into {:?} for {:?}",
+            groups, duration
+        );
+        let groups = groups
+            .iter()
+            .map(|group| group.iter().cloned().collect::<HashSet<_>>())
+            .collect();
+        self.partition_cfg.partition(groups, duration);
+    }
+
+    /// Ends every partition
+    pub fn heal_partitions(&self) {
+        info!("healing the cluster's partitions");
+        self.partition_cfg.heal();
+    }
+
     pub fn add_validator(&mut self, validator_config: &ValidatorConfig, stake: u64) {
+        let validator_config = &ValidatorConfig {
+            partition_cfg: Some(self.partition_cfg.clone()),
+            ..validator_config.clone()
+        };
         let client = create_client(
             self.entry_point_info.client_facing_addr(),
             FULLNODE_PORT_RANGE,
@@ -449,7 +482,7 @@ impl LocalCluster {
             // 1) Create vote account
 
             let mut transaction = Transaction::new_signed_instructions(
-                &[from_account.as_ref()],
+                &[from_account.as_ref(), vote_account],
                 vote_instruction::create_account(
                     &from_account.pubkey(),
                     &vote_account_pubkey,
@@ -557,18 +590,34 @@ impl Cluster for LocalCluster {
     }
 
     fn restart_node(&mut self, pubkey: Pubkey) {
-        // Shut down the fullnode
-        let node = self.fullnodes.remove(&pubkey).unwrap();
+        self.exit_node(&pubkey);
+        self.restart_exited_node(&pubkey);
+    }
+
+    fn exit_node(&mut self, pubkey: &Pubkey) {
+        let node = self.fullnodes.remove(pubkey).unwrap();
         node.exit();
         node.join().unwrap();
+    }
 
-        // Restart the node
-        let fullnode_info = &self.fullnode_infos[&pubkey].info;
-        let config = &self.fullnode_infos[&pubkey].config;
-        let node = Node::new_localhost_with_pubkey(&fullnode_info.keypair.pubkey());
-        if pubkey == self.entry_point_info.id {
-            self.entry_point_info = node.info.clone();
-        }
+    fn restart_exited_node(&mut self, pubkey: &Pubkey) {
+        assert!(!self.fullnodes.contains_key(pubkey));
+        let node = Node::new_localhost_with_pubkey(pubkey);
+        let contact_info = node.info.clone();
+        // Rejoin through the entry point, or through any running node if this is the entry point
+        let is_entry_point = *pubkey == self.entry_point_info.id;
+        let entry_point_info = if is_entry_point {
+            self.fullnodes.keys().next().map(|running_pubkey| {
+                self.fullnode_infos[running_pubkey]
+                    .info
+                    .contact_info
+                    .clone()
+            })
+        } else {
+            Some(self.entry_point_info.clone())
+        };
+        let cluster_validator_info = self.fullnode_infos.get_mut(pubkey).unwrap();
+        let fullnode_info = &mut cluster_validator_info.info;
         let restarted_node = Validator::new(
             node,
             &fullnode_info.keypair,
@@ -576,12 +625,16 @@ impl Cluster for LocalCluster {
             &fullnode_info.voting_keypair.pubkey(),
             &fullnode_info.voting_keypair,
             &fullnode_info.storage_keypair,
-            None,
+            entry_point_info.as_ref(),
             true,
-            config,
+            &cluster_validator_info.config,
         );
+        fullnode_info.contact_info = contact_info.clone();
+        if is_entry_point {
+            self.entry_point_info = contact_info;
+        }
 
-        self.fullnodes.insert(pubkey, restarted_node);
+        self.fullnodes.insert(*pubkey, restarted_node);
     }
 }
 
@@ -0,0 +1,405 @@
+//! The `nat_traversal` module helps nodes behind a NAT receive gossip pushes. The entrypoint
+//! tells each node the address it observed the node's pull requests coming from, and
+//! introduces a NAT'd node to its peers so that both sides can send to each other at the same
+//! time, opening a path through the NAT. A node that no peer could reach this way marks itself
+//! pull-only, so that peers stop pushing to it.
+//!
+//! The entrypoint signs what it tells a node, and both sides of an introduction echo its random
+//! token in their hole punch packets, so none of these can be spoofed from another address.
+
+use crate::contact_info::ContactInfo;
+use crate::crds_value::Reachability;
+use bincode::serialize;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Signable, Signature};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+/// How long to wait for a peer's hole punch packets before giving up on it
+pub const HOLE_PUNCH_TIMEOUT_MS: u64 = 5_000;
+/// Hole punch attempts to keep in flight at once
+pub const MAX_PENDING_HOLE_PUNCHES: usize = 4;
+/// Failed attempts, without a single success, before the node marks itself pull-only
+pub const MAX_HOLE_PUNCH_FAILURES: usize = 8;
+/// How often to remind a node of its observed address, if it hasn't changed
+pub const OBSERVED_ADDR_REFRESH_MS: u64 = 15_000;
+/// Most nodes the entrypoint keeps an observed address for, the least recently told are
+/// forgotten first
+pub const MAX_OBSERVED_ADDRS: usize = 8_192;
+/// How old a message from the entrypoint may be before it is ignored as a replay
+pub const ENTRYPOINT_MESSAGE_TIMEOUT_MS: u64 = HOLE_PUNCH_TIMEOUT_MS;
+
+/// What the entrypoint tells a node
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum NatAdvice {
+    /// The address the node's pull requests were observed coming from
+    ObservedAddr(SocketAddr),
+    /// A peer, the address to punch towards it at, and the token both sides' hole punches echo
+    Introduction(Pubkey, SocketAddr, u64),
+}
+
+/// `NatAdvice` for the node `to`, signed by the entrypoint `from`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EntrypointMessage {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub advice: NatAdvice,
+    pub signature: Signature,
+    pub wallclock: u64,
+}
+
+impl EntrypointMessage {
+    pub fn new(from: &Pubkey, to: &Pubkey, advice: NatAdvice, wallclock: u64) -> Self {
+        Self {
+            from: *from,
+            to: *to,
+            advice,
+            signature: Signature::default(),
+            wallclock,
+        }
+    }
+
+    /// Whether the message was signed by `entrypoint` for `me`, recently enough as of `now`
+    pub fn is_valid(&self, entrypoint: &Pubkey, me: &Pubkey, now: u64) -> bool {
+        self.from == *entrypoint
+            && self.to == *me
+            && now.saturating_sub(self.wallclock) <= ENTRYPOINT_MESSAGE_TIMEOUT_MS
+            && self.wallclock.saturating_sub(now) <= ENTRYPOINT_MESSAGE_TIMEOUT_MS
+            && self.verify()
+    }
+}
+
+impl Signable for EntrypointMessage {
+    fn pubkey(&self) -> Pubkey {
+        self.from
+    }
+
+    fn signable_data(&self) -> Cow<[u8]> {
+        #[derive(Serialize)]
+        struct SignData<'a> {
+            to: Pubkey,
+            advice: &'a NatAdvice,
+            wallclock: u64,
+        }
+        let data = SignData {
+            to: self.to,
+            advice: &self.advice,
+            wallclock: self.wallclock,
+        };
+        Cow::Owned(serialize(&data).expect("unable to serialize EntrypointMessage"))
+    }
+
+    fn get_signature(&self) -> Signature {
+        self.signature
+    }
+
+    fn set_signature(&mut self, signature: Signature) {
+        self.signature = signature;
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct PendingHolePunch {
+    /// Where the peer's NAT was last seen sending from, and the token of the introduction,
+    /// `None` until the entrypoint introduces it
+    introduction: Option<(SocketAddr, u64)>,
+    started: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct NatTraversal {
+    /// As the entrypoint: the address each node's pull requests came from, and when the node
+    /// was last told about it
+    observed_addrs: HashMap<Pubkey, (SocketAddr, u64)>,
+    /// The address the entrypoint observed this node's pull requests coming from
+    my_observed_addr: Option<SocketAddr>,
+    pending: HashMap<Pubkey, PendingHolePunch>,
+    /// Peers a hole was punched to, the address to reach them at, and the introduction's token
+    punched: HashMap<Pubkey, (SocketAddr, u64)>,
+    failed: HashMap<Pubkey, u64>,
+}
+
+impl NatTraversal {
+    /// Record that `pubkey`'s pull request came from `addr`. Returns true if `pubkey` should
+    /// be told about it, because it changed or hasn't been repeated in a while.
+    pub fn observe(&mut self, pubkey: &Pubkey, addr: SocketAddr, now: u64) -> bool {
+        match self.observed_addrs.get(pubkey) {
+            Some((observed, told))
+                if *observed == addr && now < told + OBSERVED_ADDR_REFRESH_MS =>
+            {
+                false
+            }
+            Some(_) => {
+                self.observed_addrs.insert(*pubkey, (addr, now));
+                true
+            }
+            None => {
+                if self.observed_addrs.len() >= MAX_OBSERVED_ADDRS {
+                    let oldest = self
+                        .observed_addrs
+                        .iter()
+                        .min_by_key(|(_, (_, told))| *told)
+                        .map(|(pubkey, _)| *pubkey);
+                    if let Some(oldest) = oldest {
+                        self.observed_addrs.remove(&oldest);
+                    }
+                }
+                self.observed_addrs.insert(*pubkey, (addr, now));
+                true
+            }
+        }
+    }
+
+    pub fn observed_addr(&self, pubkey: &Pubkey) -> Option<SocketAddr> {
+        self.observed_addrs.get(pubkey).map(|(addr, _)| *addr)
+    }
+
+    /// Record the address the entrypoint observed this node at
+    pub fn set_my_observed_addr(&mut self, addr: SocketAddr) {
+        self.my_observed_addr = Some(addr);
+    }
+
+    /// Whether this node has learned that the cluster can't reach its advertised address
+    pub fn is_behind_nat(&self, me: &ContactInfo) -> bool {
+        self.my_observed_addr
+            .map_or(false, |observed| observed != me.gossip)
+    }
+
+    /// Pick peers to ask the entrypoint for introductions to, up to `MAX_PENDING_HOLE_PUNCHES`
+    /// attempts in flight. Only a node behind a NAT asks.
+    pub fn new_hole_punch_requests(
+        &mut self,
+        me: &ContactInfo,
+        peers: &[ContactInfo],
+        now: u64,
+    ) -> Vec<Pubkey> {
+        if !self.is_behind_nat(me) {
+            return vec![];
+        }
+        let room = MAX_PENDING_HOLE_PUNCHES.saturating_sub(self.pending.len());
+        let requests: Vec<_> = peers
+            .iter()
+            .map(|peer| peer.id)
+            .filter(|id| {
+                !self.pending.contains_key(id)
+                    && !self.punched.contains_key(id)
+                    && !self.failed.contains_key(id)
+            })
+            .take(room)
+            .collect();
+        for id in &requests {
+            self.pending.insert(
+                *id,
+                PendingHolePunch {
+                    introduction: None,
+                    started: now,
+                },
+            );
+        }
+        requests
+    }
+
+    /// The entrypoint introduced `peer` at `addr`: start, or keep, punching towards it with
+    /// the introduction's `token`
+    pub fn introduce(&mut self, peer: &Pubkey, addr: SocketAddr, token: u64, now: u64) {
+        if self.punched.contains_key(peer) {
+            return;
+        }
+        let pending = self.pending.entry(*peer).or_insert(PendingHolePunch {
+            introduction: None,
+            started: now,
+        });
+        pending.introduction = Some((addr, token));
+    }
+
+    /// The addresses of the peers to keep sending hole punch packets to, with the token to send
+    pub fn hole_punch_addrs(&self) -> Vec<(SocketAddr, u64)> {
+        self.pending
+            .values()
+            .filter_map(|p| p.introduction)
+            .collect()
+    }
+
+    /// A hole punch packet from `peer` carrying `token` arrived from `addr`. Only a packet
+    /// echoing the token of `peer`'s introduction counts. Returns true if this is news, so the
+    /// caller should answer it to open the path from the peer's side too.
+    pub fn hole_punched(&mut self, peer: &Pubkey, addr: SocketAddr, token: u64) -> bool {
+        let introduced = self
+            .pending
+            .get(peer)
+            .and_then(|pending| pending.introduction)
+            .map(|(_, pending_token)| pending_token)
+            .or_else(|| self.punched.get(peer).map(|(_, token)| *token));
+        if introduced != Some(token) {
+            return false;
+        }
+        self.pending.remove(peer);
+        self.failed.remove(peer);
+        self.punched.insert(*peer, (addr, token)) != Some((addr, token))
+    }
+
+    /// The address a hole was punched to `peer` at, to use in place of its gossip address
+    pub fn punched_addr(&self, peer: &Pubkey) -> Option<SocketAddr> {
+        self.punched.get(peer).map(|(addr, _)| *addr)
+    }
+
+    /// Give up on attempts that haven't heard from their peer within `HOLE_PUNCH_TIMEOUT_MS`
+    pub fn purge(&mut self, now: u64) {
+        let timed_out: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| now.saturating_sub(pending.started) > HOLE_PUNCH_TIMEOUT_MS)
+            .map(|(peer, _)| *peer)
+            .collect();
+        for peer in timed_out {
+            self.pending.remove(&peer);
+            self.failed.insert(peer, now);
+        }
+    }
+
+    /// How reachable `me` is, from what has been learned so far
+    pub fn reachability(&self, me: &ContactInfo) -> Reachability {
+        match self.my_observed_addr {
+            None => Reachability::Unknown,
+            Some(_) if !self.is_behind_nat(me) => Reachability::Public,
+            Some(_) if !self.punched.is_empty() => Reachability::HolePunched,
+            Some(_) if self.failed.len() >= MAX_HOLE_PUNCH_FAILURES => Reachability::PullOnly,
+            Some(_) => Reachability::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::{Keypair, KeypairUtil};
+
+    #[test]
+    fn test_observe() {
+        let mut nat_traversal = NatTraversal::default();
+        let pubkey = Pubkey::new_rand();
+        let addr = socketaddr!("10.0.0.1:8001");
+        assert!(nat_traversal.observe(&pubkey, addr, 0));
+        assert!(!nat_traversal.observe(&pubkey, addr, 1));
+        assert!(nat_traversal.observe(&pubkey, socketaddr!("10.0.0.1:9001"), 2));
+        assert!(nat_traversal.observe(
+            &pubkey,
+            socketaddr!("10.0.0.1:9001"),
+            2 + OBSERVED_ADDR_REFRESH_MS
+        ));
+        assert_eq!(
+            nat_traversal.observed_addr(&pubkey),
+            Some(socketaddr!("10.0.0.1:9001"))
+        );
+    }
+
+    #[test]
+    fn test_observe_forgets_oldest() {
+        let mut nat_traversal = NatTraversal::default();
+        let addr = socketaddr!("10.0.0.1:8001");
+        let pubkeys: Vec<_> = (0..MAX_OBSERVED_ADDRS)
+            .map(|_| Pubkey::new_rand())
+            .collect();
+        for (now, pubkey) in pubkeys.iter().enumerate() {
+            assert!(nat_traversal.observe(pubkey, addr, now as u64));
+        }
+        let pubkey = Pubkey::new_rand();
+        assert!(nat_traversal.observe(&pubkey, addr, MAX_OBSERVED_ADDRS as u64));
+        assert_eq!(nat_traversal.observed_addrs.len(), MAX_OBSERVED_ADDRS);
+        assert_eq!(nat_traversal.observed_addr(&pubkeys[0]), None);
+        assert_eq!(nat_traversal.observed_addr(&pubkeys[1]), Some(addr));
+        assert_eq!(nat_traversal.observed_addr(&pubkey), Some(addr));
+    }
+
+    #[test]
+    fn test_entrypoint_message() {
+        let entrypoint = Keypair::new();
+        let me = Pubkey::new_rand();
+        let advice = NatAdvice::ObservedAddr(socketaddr!("10.0.0.1:8001"));
+        let mut message = EntrypointMessage::new(&entrypoint.pubkey(), &me, advice, 1_000);
+        // Unsigned
+        assert!(!message.is_valid(&entrypoint.pubkey(), &me, 1_000));
+        message.sign(&entrypoint);
+        assert!(message.is_valid(&entrypoint.pubkey(), &me, 1_000));
+        // Signed by some other node, for some other node, or stale
+        assert!(!message.is_valid(&Pubkey::new_rand(), &me, 1_000));
+        assert!(!message.is_valid(&entrypoint.pubkey(), &Pubkey::new_rand(), 1_000));
+        assert!(!message.is_valid(
+            &entrypoint.pubkey(),
+            &me,
+            1_001 + ENTRYPOINT_MESSAGE_TIMEOUT_MS
+        ));
+        // Tampered with
+        message.advice = NatAdvice::ObservedAddr(socketaddr!("10.0.0.2:8001"));
+        assert!(!message.is_valid(&entrypoint.pubkey(), &me, 1_000));
+    }
+
+    #[test]
+    fn test_public_node_does_not_punch() {
+        let me = ContactInfo::new_localhost(&Pubkey::new_rand(), 0);
+        let peer = ContactInfo::new_localhost(&Pubkey::new_rand(), 0);
+        let mut nat_traversal = NatTraversal::default();
+        assert_eq!(nat_traversal.reachability(&me), Reachability::Unknown);
+        nat_traversal.set_my_observed_addr(me.gossip);
+        assert_eq!(nat_traversal.reachability(&me), Reachability::Public);
+        assert!(nat_traversal
+            .new_hole_punch_requests(&me, &[peer], 0)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_hole_punch() {
+        let me = ContactInfo::new_localhost(&Pubkey::new_rand(), 0);
+        let peer = ContactInfo::new_localhost(&Pubkey::new_rand(), 0);
+        let peer_addr = socketaddr!("10.0.0.2:8001");
+        let mut nat_traversal = NatTraversal::default();
+        nat_traversal.set_my_observed_addr(socketaddr!("10.0.0.1:8001"));
+
+        assert_eq!(
+            nat_traversal.new_hole_punch_requests(&me, &[peer.clone()], 0),
+            vec![peer.id]
+        );
+        // Already pending
+        assert!(nat_traversal
+            .new_hole_punch_requests(&me, &[peer.clone()], 1)
+            .is_empty());
+        assert!(nat_traversal.hole_punch_addrs().is_empty());
+        nat_traversal.introduce(&peer.id, peer_addr, 42, 1);
+        assert_eq!(nat_traversal.hole_punch_addrs(), vec![(peer_addr, 42)]);
+
+        // Neither a packet from an unknown peer nor one without the introduction's token counts
+        assert!(!nat_traversal.hole_punched(&Pubkey::new_rand(), peer_addr, 42));
+        assert!(!nat_traversal.hole_punched(&peer.id, peer_addr, 43));
+        assert!(nat_traversal.hole_punched(&peer.id, peer_addr, 42));
+        assert!(!nat_traversal.hole_punched(&peer.id, peer_addr, 42));
+        assert!(!nat_traversal.hole_punched(&peer.id, socketaddr!("10.0.0.3:8001"), 43));
+        assert_eq!(nat_traversal.punched_addr(&peer.id), Some(peer_addr));
+        assert!(nat_traversal.hole_punch_addrs().is_empty());
+        assert_eq!(nat_traversal.reachability(&me), Reachability::HolePunched);
+    }
+
+    #[test]
+    fn test_pull_only_fallback() {
+        let me = ContactInfo::new_localhost(&Pubkey::new_rand(), 0);
+        let peers: Vec<_> = (0..MAX_HOLE_PUNCH_FAILURES)
+            .map(|_| ContactInfo::new_localhost(&Pubkey::new_rand(), 0))
+            .collect();
+        let mut nat_traversal = NatTraversal::default();
+        nat_traversal.set_my_observed_addr(socketaddr!("10.0.0.1:8001"));
+
+        let mut now = 0;
+        while nat_traversal.reachability(&me) == Reachability::Unknown {
+            let requests = nat_traversal.new_hole_punch_requests(&me, &peers, now);
+            assert!(requests.len() <= MAX_PENDING_HOLE_PUNCHES);
+            assert!(!requests.is_empty());
+            now += HOLE_PUNCH_TIMEOUT_MS + 1;
+            nat_traversal.purge(now);
+        }
+        assert_eq!(nat_traversal.reachability(&me), Reachability::PullOnly);
+        // Every peer has been tried
+        assert!(nat_traversal
+            .new_hole_punch_requests(&me, &peers, now)
+            .is_empty());
+    }
+}
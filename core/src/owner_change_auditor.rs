@@ -0,0 +1,23 @@
+//! The `owner_change_auditor` module reports every account owner change the validator's banks
+//! commit to the metrics pipeline, so that reassignments can be audited downstream
+
+use solana_metrics::datapoint_info;
+use solana_runtime::execution_observer::{AccountOwnerChange, ExecutionObserver};
+use solana_sdk::signature::Signature;
+
+#[derive(Default)]
+pub struct OwnerChangeAuditor {}
+
+impl ExecutionObserver for OwnerChangeAuditor {
+    fn account_owner_changed(&self, slot: u64, signature: &Signature, change: &AccountOwnerChange) {
+        datapoint_info!(
+            "account-owner-change",
+            ("slot", slot, i64),
+            ("signature", signature.to_string(), String),
+            ("instruction_index", change.instruction_index, i64),
+            ("pubkey", change.pubkey.to_string(), String),
+            ("old_owner", change.old_owner.to_string(), String),
+            ("new_owner", change.new_owner.to_string(), String)
+        );
+    }
+}
@@ -44,6 +44,15 @@ pub struct Meta {
     pub addr: [u16; 8],
     pub port: u16,
     pub v6: bool,
+    /// Set by sigverify on packets that failed verification, for later stages to skip
+    pub discard: bool,
+    /// The number of signatures the packet's transaction requires, as parsed by sigverify
+    pub num_required_signatures: u8,
+    /// Whether the packet's transaction only holds vote instructions, as parsed by sigverify
+    pub is_vote: bool,
+    /// Set on blobs received on a repair socket, which carry the nonce of the repair request
+    /// they answer
+    pub repair: bool,
 }
 
 #[derive(Clone)]
@@ -261,6 +270,17 @@ pub enum BlobError {
 
 impl Packets {
     pub fn recv_from(&mut self, socket: &UdpSocket) -> Result<usize> {
+        self.recv_from_coalesced(socket, 1, usize::max_value())
+    }
+
+    /// Receives the packets that arrive within `coalesce_ms` of the first one, up to
+    /// `max_packets` of them
+    pub fn recv_from_coalesced(
+        &mut self,
+        socket: &UdpSocket,
+        coalesce_ms: u64,
+        max_packets: usize,
+    ) -> Result<usize> {
         let mut i = 0;
         //DOCUMENTED SIDE-EFFECT
         //Performance out of the IO without poll
@@ -273,10 +293,13 @@ impl Packets {
         let start = Instant::now();
         let mut total_size = 0;
         loop {
-            self.packets.resize(i + NUM_RCVMMSGS, Packet::default());
+            self.packets.resize(
+                i + cmp::min(NUM_RCVMMSGS, max_packets - i),
+                Packet::default(),
+            );
             match recv_mmsg(socket, &mut self.packets[i..]) {
                 Err(_) if i > 0 => {
-                    if start.elapsed().as_millis() > 1 {
+                    if start.elapsed().as_millis() as u64 > coalesce_ms {
                         break;
                     }
                 }
@@ -293,7 +316,10 @@ impl Packets {
                     total_size += size;
                     // Try to batch into blob-sized buffers
                     // will cause less re-shuffling later on.
-                    if start.elapsed().as_millis() > 1 || total_size >= (BLOB_DATA_SIZE - 4096) {
+                    if start.elapsed().as_millis() as u64 > coalesce_ms
+                        || total_size >= (BLOB_DATA_SIZE - 4096)
+                        || i >= max_packets
+                    {
                         break;
                     }
                 }
@@ -747,6 +773,26 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn packet_recv_from_coalesced() {
+        let recv_socket = UdpSocket::bind("127.0.0.1:0").expect("bind");
+        let addr = recv_socket.local_addr().unwrap();
+        let send_socket = UdpSocket::bind("127.0.0.1:0").expect("bind");
+        let mut p = Packets::default();
+        p.packets.resize(10, Packet::default());
+        for m in p.packets.iter_mut() {
+            m.meta.set_addr(&addr);
+            m.meta.size = PACKET_DATA_SIZE;
+        }
+        p.send_to(&send_socket).unwrap();
+
+        // The batch stops at max_packets, leaving the rest for the next one
+        let mut recvd = Packets::default();
+        assert_eq!(recvd.recv_from_coalesced(&recv_socket, 1, 4).unwrap(), 4);
+        assert_eq!(recvd.packets.len(), 4);
+        assert_eq!(recvd.recv_from_coalesced(&recv_socket, 1, 100).unwrap(), 6);
+    }
+
     #[test]
     fn test_to_packets() {
         let keypair = Keypair::new();
@@ -0,0 +1,121 @@
+//! The `partition_cfg` module lets tests split a cluster into groups of nodes that can't reach
+//! each other for a while. A node in a group only sends gossip and blobs to the other nodes of
+//! its group, so the groups build their own forks until the partition ends. Nodes outside every
+//! group are unaffected.
+
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::timing::timestamp;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+#[derive(Clone, Debug)]
+pub struct Partition {
+    /// When the partition starts, in milliseconds since the UNIX epoch
+    pub start_ms: u64,
+    /// When the partition ends, in milliseconds since the UNIX epoch
+    pub end_ms: u64,
+    pub groups: Vec<HashSet<Pubkey>>,
+}
+
+impl Partition {
+    fn is_active(&self, now: u64) -> bool {
+        self.start_ms <= now && now < self.end_ms
+    }
+}
+
+/// The partitions of a cluster, shared by its nodes so a test can change them as it runs
+#[derive(Clone, Debug, Default)]
+pub struct PartitionCfg {
+    partitions: Arc<RwLock<Vec<Partition>>>,
+}
+
+impl PartitionCfg {
+    pub fn add_partition(&self, partition: Partition) {
+        self.partitions.write().unwrap().push(partition);
+    }
+
+    /// Splits the cluster into `groups` from now on, for `duration`
+    pub fn partition(&self, groups: Vec<HashSet<Pubkey>>, duration: Duration) {
+        let now = timestamp();
+        self.add_partition(Partition {
+            start_ms: now,
+            end_ms: now + duration.as_millis() as u64,
+            groups,
+        });
+    }
+
+    /// Ends every partition, including those that haven't started yet
+    pub fn heal(&self) {
+        self.partitions.write().unwrap().clear();
+    }
+
+    /// The nodes `pubkey` can reach at `now`, or None if it can reach every node. A node in
+    /// several active partitions only reaches the nodes it's grouped with in all of them.
+    pub fn reachable_nodes(&self, pubkey: &Pubkey, now: u64) -> Option<HashSet<Pubkey>> {
+        let partitions = self.partitions.read().unwrap();
+        let mut reachable_nodes: Option<HashSet<Pubkey>> = None;
+        for partition in partitions
+            .iter()
+            .filter(|partition| partition.is_active(now))
+        {
+            if let Some(group) = partition.groups.iter().find(|group| group.contains(pubkey)) {
+                reachable_nodes = Some(match reachable_nodes {
+                    Some(nodes) => nodes.intersection(group).cloned().collect(),
+                    None => group.clone(),
+                });
+            }
+        }
+        reachable_nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reachable_nodes() {
+        let nodes: Vec<_> = (0..4).map(|_| Pubkey::new_rand()).collect();
+        let group =
+            |indexes: &[usize]| -> HashSet<Pubkey> { indexes.iter().map(|i| nodes[*i]).collect() };
+        let partition_cfg = PartitionCfg::default();
+        assert_eq!(partition_cfg.reachable_nodes(&nodes[0], 0), None);
+
+        partition_cfg.add_partition(Partition {
+            start_ms: 10,
+            end_ms: 20,
+            groups: vec![group(&[0, 1]), group(&[2])],
+        });
+        assert_eq!(partition_cfg.reachable_nodes(&nodes[0], 9), None);
+        assert_eq!(
+            partition_cfg.reachable_nodes(&nodes[0], 10),
+            Some(group(&[0, 1]))
+        );
+        assert_eq!(
+            partition_cfg.reachable_nodes(&nodes[2], 19),
+            Some(group(&[2]))
+        );
+        assert_eq!(partition_cfg.reachable_nodes(&nodes[0], 20), None);
+        // Nodes outside every group are unaffected
+        assert_eq!(partition_cfg.reachable_nodes(&nodes[3], 15), None);
+
+        // Overlapping partitions
+        partition_cfg.add_partition(Partition {
+            start_ms: 15,
+            end_ms: 25,
+            groups: vec![group(&[0, 2, 3]), group(&[1])],
+        });
+        assert_eq!(
+            partition_cfg.reachable_nodes(&nodes[0], 15),
+            Some(group(&[0]))
+        );
+        assert_eq!(
+            partition_cfg.reachable_nodes(&nodes[0], 20),
+            Some(group(&[0, 2, 3]))
+        );
+
+        partition_cfg.heal();
+        assert_eq!(partition_cfg.reachable_nodes(&nodes[1], 15), None);
+    }
+}
@@ -1,4 +1,5 @@
 //! The `Poh` module provides an object for generating a Proof of History.
+use crate::hash_chain::hash_chain;
 use solana_sdk::hash::{hash, hashv, Hash};
 
 pub struct Poh {
@@ -33,9 +34,7 @@ impl Poh {
 
     pub fn hash(&mut self, max_num_hashes: u64) -> bool {
         let num_hashes = std::cmp::min(self.remaining_hashes - 1, max_num_hashes);
-        for _ in 0..num_hashes {
-            self.hash = hash(&self.hash.as_ref());
-        }
+        self.hash = hash_chain(&self.hash, num_hashes);
         self.num_hashes += num_hashes;
         self.remaining_hashes -= num_hashes;
 
@@ -115,6 +115,11 @@ impl PohRecorder {
         self.working_bank.is_some()
     }
 
+    /// The slot PoH was last reset to, or the slot of the last working bank
+    pub fn start_slot(&self) -> Slot {
+        self.start_slot
+    }
+
     pub fn tick_height(&self) -> u64 {
         self.tick_height
     }
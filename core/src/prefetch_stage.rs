@@ -0,0 +1,95 @@
+//! The `prefetch_stage` warms the accounts of transactions that are queued for a bank, so that
+//! by the time the transactions are processed their accounts are already in memory.
+
+use crate::service::Service;
+use solana_metrics::inc_new_counter_debug;
+use solana_runtime::bank::Bank;
+use solana_sdk::transaction::SanitizedTransaction;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, Builder, JoinHandle};
+use std::time::Duration;
+
+pub type PrefetchSender = Sender<(Arc<Bank>, Vec<SanitizedTransaction>)>;
+pub type PrefetchReceiver = Receiver<(Arc<Bank>, Vec<SanitizedTransaction>)>;
+
+pub struct PrefetchStage {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl PrefetchStage {
+    /// Create the stage and the sender that queues transactions for it. The stage exits once
+    /// every clone of the sender is dropped.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> (Self, PrefetchSender) {
+        let (prefetch_sender, prefetch_receiver) = channel();
+        let thread_hdl = Builder::new()
+            .name("solana-prefetch-stage".to_string())
+            .spawn(move || loop {
+                match Self::prefetch(&prefetch_receiver) {
+                    Err(RecvTimeoutError::Disconnected) => break,
+                    Err(RecvTimeoutError::Timeout) | Ok(()) => (),
+                }
+            })
+            .unwrap();
+        (Self { thread_hdl }, prefetch_sender)
+    }
+
+    fn prefetch(prefetch_receiver: &PrefetchReceiver) -> Result<(), RecvTimeoutError> {
+        let (mut bank, mut transactions) =
+            prefetch_receiver.recv_timeout(Duration::from_secs(1))?;
+        // Skip ahead to the most recently queued transactions, the earlier ones are likely
+        // being processed already
+        while let Ok((next_bank, next_transactions)) = prefetch_receiver.try_recv() {
+            inc_new_counter_debug!("prefetch_stage-skipped_txs", transactions.len());
+            bank = next_bank;
+            transactions = next_transactions;
+        }
+        let prefetched = bank.prefetch_accounts(&transactions);
+        inc_new_counter_debug!("prefetch_stage-prefetched_accounts", prefetched);
+        Ok(())
+    }
+}
+
+impl Service for PrefetchStage {
+    type JoinReturnType = ();
+
+    fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genesis_utils::create_genesis_block;
+    use solana_sdk::signature::KeypairUtil;
+    use solana_sdk::system_transaction;
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn test_prefetch_stage() {
+        let genesis_block_info = create_genesis_block(10_000);
+        let bank = Arc::new(Bank::new(&genesis_block_info.genesis_block));
+        let tx = system_transaction::transfer(
+            &genesis_block_info.mint_keypair,
+            &genesis_block_info.mint_keypair.pubkey(),
+            1,
+            bank.last_blockhash(),
+        );
+
+        let (prefetch_stage, prefetch_sender) = PrefetchStage::new();
+        prefetch_sender
+            .send((
+                bank.clone(),
+                vec![SanitizedTransaction::try_new(tx.clone()).unwrap()],
+            ))
+            .unwrap();
+        drop(prefetch_sender);
+        prefetch_stage.join().unwrap();
+
+        bank.process_transaction(&tx).unwrap();
+        let stats = bank.prefetch_stats();
+        assert!(stats.hits.load(Ordering::Relaxed) > 0);
+    }
+}
@@ -0,0 +1,392 @@
+//! The `quic_streamer` module receives transactions over QUIC, each on a unidirectional stream
+//! of its own. Unlike a UDP packet, a QUIC connection only opens once the client has proven it
+//! owns its address, and its node identity, so transactions can't be sent from spoofed
+//! addresses, and each client's connections and streams can be limited.
+
+use crate::packet::{Packet, Packets, PACKETS_PER_BLOB, PACKET_DATA_SIZE};
+use crate::result::{Error, Result};
+use crate::service::Service;
+use crate::streamer::PacketSender;
+use futures::{Future, Stream};
+use quinn::{Endpoint, NewStream, ServerConfig, ServerConfigBuilder, TransportConfig};
+use solana_client::quic_client::{new_identity_cert, ClientIdentityVerification};
+use solana_metrics::inc_new_counter_debug;
+use solana_sdk::signature::Keypair;
+use solana_sdk::timing::timestamp;
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, Builder, JoinHandle};
+use std::time::{Duration, Instant};
+use tokio::runtime::current_thread::{self, Runtime};
+use tokio::timer::Interval;
+
+/// Streams a connection may have open at once
+const MAX_CONCURRENT_UNI_STREAMS: u64 = 128;
+
+/// Transactions received but not yet batched for the sigverify stage. Once this many are
+/// queued, new ones are dropped rather than buffered without bound.
+const MAX_QUEUED_PACKETS: usize = 16 * PACKETS_PER_BLOB;
+
+#[derive(Clone, Debug)]
+pub struct QuicTpuConfig {
+    /// Connections each IP address may have open at once
+    pub max_connections_per_ip: usize,
+    /// Transactions each connection may send per second
+    pub max_streams_per_second: u64,
+}
+
+impl Default for QuicTpuConfig {
+    fn default() -> Self {
+        Self {
+            max_connections_per_ip: 8,
+            max_streams_per_second: 1_000,
+        }
+    }
+}
+
+/// Counts the open connections of each IP address
+#[derive(Debug, Default)]
+pub struct ConnectionTable {
+    connections: HashMap<IpAddr, usize>,
+}
+
+impl ConnectionTable {
+    /// Records a new connection from `ip`, unless it already has `max_connections` open
+    pub fn try_add(&mut self, ip: IpAddr, max_connections: usize) -> bool {
+        let connections = self.connections.entry(ip).or_insert(0);
+        if *connections >= max_connections {
+            return false;
+        }
+        *connections += 1;
+        true
+    }
+
+    pub fn remove(&mut self, ip: &IpAddr) {
+        if let Some(connections) = self.connections.get_mut(ip) {
+            *connections -= 1;
+            if *connections == 0 {
+                self.connections.remove(ip);
+            }
+        }
+    }
+}
+
+/// Limits the streams a connection opens with a bucket of tokens, refilled at
+/// `max_streams_per_second` and holding up to a second's worth
+#[derive(Debug)]
+pub struct StreamLimiter {
+    max_streams_per_second: u64,
+    tokens: u64,
+    last_refill: u64,
+}
+
+impl StreamLimiter {
+    pub fn new(max_streams_per_second: u64, now: u64) -> Self {
+        Self {
+            max_streams_per_second,
+            tokens: max_streams_per_second,
+            last_refill: now,
+        }
+    }
+
+    /// Takes a token for a new stream at `now`, in milliseconds, if one is left
+    pub fn allow(&mut self, now: u64) -> bool {
+        let refill = now.saturating_sub(self.last_refill) * self.max_streams_per_second / 1000;
+        // Leave the refill time alone until a whole token is due, so slow refills add up
+        if refill > 0 {
+            self.tokens = (self.tokens + refill).min(self.max_streams_per_second);
+            self.last_refill = now;
+        }
+        if self.tokens == 0 {
+            return false;
+        }
+        self.tokens -= 1;
+        true
+    }
+}
+
+fn quic_error<E: std::fmt::Display>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
+
+/// A server config with the certificate of the node `identity`, which clients check is the
+/// node they meant to reach. Clients must present a node identity certificate of their own.
+fn new_server_config(identity: &Keypair) -> io::Result<ServerConfig> {
+    let (cert, key) = new_identity_cert(identity)?;
+
+    let mut transport = TransportConfig::default();
+    transport.stream_window_uni = MAX_CONCURRENT_UNI_STREAMS;
+    transport.stream_window_bidi = 0;
+    let mut server_config = ServerConfig::default();
+    server_config.transport = Arc::new(transport);
+
+    let mut builder = ServerConfigBuilder::new(server_config);
+    builder
+        .certificate(
+            quinn::CertificateChain::from_certs(vec![
+                quinn::Certificate::from_der(&cert.0).map_err(quic_error)?
+            ]),
+            quinn::PrivateKey::from_der(&key.0).map_err(quic_error)?,
+        )
+        .map_err(quic_error)?;
+    let mut server_config = builder.build();
+    Arc::get_mut(&mut server_config.crypto)
+        .unwrap()
+        .set_client_certificate_verifier(Arc::new(ClientIdentityVerification));
+    Ok(server_config)
+}
+
+fn to_packet(data: &[u8], addr: &SocketAddr) -> Option<Packet> {
+    if data.len() > PACKET_DATA_SIZE {
+        return None;
+    }
+    let mut packet = Packet::default();
+    packet.data[..data.len()].copy_from_slice(data);
+    packet.meta.size = data.len();
+    packet.meta.set_addr(addr);
+    Some(packet)
+}
+
+/// Batches the packets of single transactions for the sigverify stage
+fn batch_packets(receiver: &Receiver<Packet>, sender: &PacketSender) -> Result<()> {
+    let packet = receiver.recv_timeout(Duration::from_secs(1))?;
+    let mut packets = vec![packet];
+    while packets.len() < PACKETS_PER_BLOB {
+        match receiver.try_recv() {
+            Ok(packet) => packets.push(packet),
+            Err(_) => break,
+        }
+    }
+    inc_new_counter_debug!("quic_streamer-packets", packets.len());
+    sender.send(Packets::new(packets))?;
+    Ok(())
+}
+
+fn handle_connection(
+    connecting: quinn::Connecting,
+    packet_sender: SyncSender<Packet>,
+    connection_table: Arc<Mutex<ConnectionTable>>,
+    config: QuicTpuConfig,
+) {
+    current_thread::spawn(
+        connecting
+            .map_err(|err| debug!("quic handshake failed: {}", err))
+            .and_then(move |(driver, connection, streams)| {
+                let addr = connection.remote_address();
+                // The driver runs the connection, and sends its close if it's refused
+                current_thread::spawn(
+                    driver.map_err(move |err| {
+                        debug!("quic connection from {} closed: {}", addr, err)
+                    }),
+                );
+                if !connection_table
+                    .lock()
+                    .unwrap()
+                    .try_add(addr.ip(), config.max_connections_per_ip)
+                {
+                    inc_new_counter_debug!("quic_streamer-connections_refused", 1);
+                    connection.close(0u32.into(), b"too many connections");
+                    return futures::future::Either::A(futures::future::ok(()));
+                }
+
+                let mut limiter = StreamLimiter::new(config.max_streams_per_second, timestamp());
+                let streams = streams
+                    .map_err(|err| debug!("quic streams closed: {}", err))
+                    .for_each(move |stream| {
+                        let stream = match stream {
+                            NewStream::Uni(stream) => stream,
+                            // Bidirectional streams aren't allowed by the transport config
+                            NewStream::Bi(_, _) => return Ok(()),
+                        };
+                        if !limiter.allow(timestamp()) {
+                            inc_new_counter_debug!("quic_streamer-streams_limited", 1);
+                            return Ok(());
+                        }
+                        let packet_sender = packet_sender.clone();
+                        current_thread::spawn(
+                            stream
+                                .read_to_end(PACKET_DATA_SIZE)
+                                .map_err(|err| debug!("quic stream failed: {}", err))
+                                .map(move |(_stream, data)| {
+                                    if let Some(packet) = to_packet(&data, &addr) {
+                                        if let Err(TrySendError::Full(_)) =
+                                            packet_sender.try_send(packet)
+                                        {
+                                            inc_new_counter_debug!(
+                                                "quic_streamer-packets_dropped",
+                                                1
+                                            );
+                                        }
+                                    }
+                                }),
+                        );
+                        Ok(())
+                    })
+                    .then(move |_| {
+                        connection_table.lock().unwrap().remove(&addr.ip());
+                        Ok(())
+                    });
+                futures::future::Either::B(streams)
+            }),
+    );
+}
+
+/// Serves the QUIC TPU, sending the transactions it receives on to the sigverify stage
+pub struct QuicServer {
+    thread_hdls: Vec<JoinHandle<()>>,
+}
+
+impl QuicServer {
+    pub fn new(
+        socket: UdpSocket,
+        identity: &Keypair,
+        config: &QuicTpuConfig,
+        sender: &PacketSender,
+        exit: &Arc<AtomicBool>,
+    ) -> io::Result<Self> {
+        let server_config = new_server_config(identity)?;
+        let mut builder = Endpoint::builder();
+        builder.listen(server_config);
+        let (driver, _endpoint, incoming) = builder.with_socket(socket).map_err(quic_error)?;
+
+        let (packet_sender, packet_receiver) = sync_channel(MAX_QUEUED_PACKETS);
+        let config = config.clone();
+        let server_exit = exit.clone();
+        let t_server = Builder::new()
+            .name("solana-quic-server".to_string())
+            .spawn(move || {
+                let mut runtime = Runtime::new().expect("Failed to create quic runtime");
+                let connection_table = Arc::new(Mutex::new(ConnectionTable::default()));
+                runtime.spawn(
+                    incoming
+                        .map_err(|err| warn!("quic incoming error: {:?}", err))
+                        .for_each(move |connecting| {
+                            handle_connection(
+                                connecting,
+                                packet_sender.clone(),
+                                connection_table.clone(),
+                                config.clone(),
+                            );
+                            Ok(())
+                        }),
+                );
+                let exit_poll = Interval::new(Instant::now(), Duration::from_millis(100))
+                    .map_err(|err| warn!("quic exit timer error: {}", err))
+                    .take_while(move |_| Ok(!server_exit.load(Ordering::Relaxed)))
+                    .for_each(|_| Ok(()));
+                let driver = driver.map_err(|err| warn!("quic endpoint error: {}", err));
+                let _ = runtime.block_on(driver.select(exit_poll));
+            })
+            .unwrap();
+
+        let exit = exit.clone();
+        let sender = sender.clone();
+        let t_batch = Builder::new()
+            .name("solana-quic-batch".to_string())
+            .spawn(move || loop {
+                if exit.load(Ordering::Relaxed) {
+                    break;
+                }
+                if let Err(e) = batch_packets(&packet_receiver, &sender) {
+                    match e {
+                        Error::RecvTimeoutError(RecvTimeoutError::Disconnected) => break,
+                        Error::RecvTimeoutError(RecvTimeoutError::Timeout) => (),
+                        Error::SendError => break,
+                        _ => error!("{:?}", e),
+                    }
+                }
+            })
+            .unwrap();
+
+        Ok(Self {
+            thread_hdls: vec![t_server, t_batch],
+        })
+    }
+}
+
+impl Service for QuicServer {
+    type JoinReturnType = ();
+
+    fn join(self) -> thread::Result<()> {
+        for thread_hdl in self.thread_hdls {
+            thread_hdl.join()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_tx::test_tx;
+    use bincode::serialize;
+    use solana_client::quic_client::QuicTpuClient;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::KeypairUtil;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_connection_table() {
+        let mut table = ConnectionTable::default();
+        let ip0 = IpAddr::from([127, 0, 0, 1]);
+        let ip1 = IpAddr::from([127, 0, 0, 2]);
+        assert!(table.try_add(ip0, 2));
+        assert!(table.try_add(ip0, 2));
+        assert!(!table.try_add(ip0, 2));
+        assert!(table.try_add(ip1, 2));
+        table.remove(&ip0);
+        assert!(table.try_add(ip0, 2));
+        table.remove(&ip1);
+        assert!(!table.connections.contains_key(&ip1));
+    }
+
+    #[test]
+    fn test_stream_limiter() {
+        let mut limiter = StreamLimiter::new(2, 0);
+        assert!(limiter.allow(0));
+        assert!(limiter.allow(0));
+        assert!(!limiter.allow(0));
+        // Half a second refills one token
+        assert!(!limiter.allow(400));
+        assert!(limiter.allow(500));
+        assert!(!limiter.allow(500));
+        // The bucket never holds more than a second's worth
+        assert!(limiter.allow(10_000));
+        assert!(limiter.allow(10_000));
+        assert!(!limiter.allow(10_000));
+    }
+
+    #[test]
+    fn test_quic_server() {
+        solana_logger::setup();
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+        let exit = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = channel();
+        let identity = Keypair::new();
+        let server =
+            QuicServer::new(socket, &identity, &QuicTpuConfig::default(), &sender, &exit).unwrap();
+
+        // The server must be the node the client meant to reach
+        let tx = test_tx();
+        let client = QuicTpuClient::new(addr, &Pubkey::new_rand(), &Keypair::new()).unwrap();
+        assert!(client.send_transaction(&tx).is_err());
+
+        let client = QuicTpuClient::new(addr, &identity.pubkey(), &Keypair::new()).unwrap();
+        client.send_transaction(&tx).unwrap();
+        let packets = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(packets.packets.len(), 1);
+        let packet = &packets.packets[0];
+        assert_eq!(
+            &packet.data[..packet.meta.size],
+            &serialize(&tx).unwrap()[..]
+        );
+
+        exit.store(true, Ordering::Relaxed);
+        server.join().unwrap();
+    }
+}
@@ -1,39 +1,68 @@
 //! The `repair_service` module implements the tools necessary to generate a thread which
-//! regularly finds missing blobs in the ledger and sends repair requests for those blobs
+//! regularly finds missing blobs in the ledger and sends repair requests for those blobs.
+//! If UDP repair stops making progress, missing blobs can also be fetched over JSON RPC from
+//! trusted nodes.
 
 use crate::bank_forks::BankForks;
 use crate::blocktree::{Blocktree, CompletedSlotsReceiver, SlotMeta};
 use crate::cluster_info::ClusterInfo;
 use crate::cluster_info_repair_listener::ClusterInfoRepairListener;
+use crate::packet::{Blob, BLOB_SIZE};
 use crate::result::Result;
+use crate::rpc::MAX_GET_BLOBS_RANGE;
 use crate::service::Service;
+use crate::slot_range::SlotRange;
+use crate::streamer::BlobSender;
+use byteorder::{ByteOrder, LittleEndian};
+use rand::{thread_rng, Rng};
+use solana_client::rpc_client::RpcClient;
 use solana_metrics::datapoint_info;
 use solana_runtime::epoch_schedule::EpochSchedule;
 use solana_sdk::pubkey::Pubkey;
-use std::collections::BTreeSet;
-use std::net::UdpSocket;
+use solana_sdk::timing::timestamp;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::{SocketAddr, UdpSocket};
 use std::ops::Bound::{Excluded, Unbounded};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
 use std::sync::{Arc, RwLock};
 use std::thread::sleep;
 use std::thread::{self, Builder, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub const MAX_REPAIR_LENGTH: usize = 16;
 pub const REPAIR_MS: u64 = 100;
 pub const MAX_REPAIR_TRIES: u64 = 128;
 pub const NUM_FORKS_TO_REPAIR: usize = 5;
 pub const MAX_ORPHANS: usize = 5;
+pub const DEFAULT_RPC_REPAIR_TIMEOUT_SECS: u64 = 10;
+const RPC_REPAIR_REQUEST_TIMEOUT_SECS: u64 = 5;
 
 pub enum RepairStrategy {
-    RepairRange(RepairSlotRange),
+    RepairRange(SlotRange),
     RepairAll {
         bank_forks: Arc<RwLock<BankForks>>,
         completed_slots_receiver: CompletedSlotsReceiver,
         epoch_schedule: EpochSchedule,
+        rpc_repair: Option<RpcRepair>,
     },
 }
 
+/// Trusted nodes to fetch missing blobs from over JSON RPC, for hosts whose inbound UDP is
+/// blocked and so never see responses to repair requests
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcRepairConfig {
+    pub rpc_addrs: Vec<SocketAddr>,
+    /// How long repair must go without progress before blobs are fetched over RPC
+    pub timeout: Duration,
+}
+
+pub struct RpcRepair {
+    pub config: RpcRepairConfig,
+    /// Blobs fetched over RPC are sent here to be verified and stored like any other blob
+    pub blob_sender: BlobSender,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RepairType {
     Orphan(u64),
@@ -41,32 +70,116 @@ pub enum RepairType {
     Blob(u64, u64),
 }
 
-pub struct RepairSlotRange {
-    pub start: u64,
-    pub end: u64,
+/// A random number sent with a repair request, and echoed after the blobs of the response
+pub type Nonce = u32;
+
+pub const SIZE_OF_NONCE: usize = 4;
+
+/// How long a repair request waits for its responses, in milliseconds
+pub const REPAIR_RESPONSE_TIMEOUT_MS: u64 = 10_000;
+
+/// Appends `nonce` to `blob`, past the size in its header, to answer the repair request it came
+/// with. There's always room, since a blob's data never fills its buffer.
+pub fn append_nonce(blob: &mut Blob, nonce: Nonce) {
+    let size = blob.meta.size;
+    LittleEndian::write_u32(&mut blob.data[size..size + SIZE_OF_NONCE], nonce);
+    blob.meta.size += SIZE_OF_NONCE;
+}
+
+/// Removes the nonce a repair response's sender appended to `blob`
+pub fn strip_nonce(blob: &mut Blob) -> Option<Nonce> {
+    let size = blob.meta.size.checked_sub(SIZE_OF_NONCE)?;
+    let nonce = LittleEndian::read_u32(&blob.data[size..size + SIZE_OF_NONCE]);
+    blob.meta.size = size;
+    Some(nonce)
+}
+
+/// The repair requests awaiting responses, by nonce. Only blobs that answer one of them are
+/// accepted from the repair socket, so that a peer can't feed a node blobs it didn't ask for.
+#[derive(Default)]
+pub struct OutstandingRepairs {
+    requests: HashMap<Nonce, (RepairType, u64)>,
 }
 
-impl Default for RepairSlotRange {
-    fn default() -> Self {
-        RepairSlotRange {
-            start: 0,
-            end: std::u64::MAX,
+impl OutstandingRepairs {
+    /// Records `request`, returning the nonce to send it with
+    pub fn add_request(&mut self, request: RepairType, now: u64) -> Nonce {
+        let nonce = loop {
+            let nonce = thread_rng().gen();
+            if !self.requests.contains_key(&nonce) {
+                break nonce;
+            }
+        };
+        self.requests.insert(nonce, (request, now));
+        nonce
+    }
+
+    /// Strips the nonce from `blob`, and checks that the blob is what the request with that
+    /// nonce asked for. An orphan request is answered with several blobs, so it stays outstanding
+    /// until it expires; other requests are answered by one.
+    pub fn register_response(&mut self, blob: &mut Blob, now: u64) -> bool {
+        let nonce = match strip_nonce(blob) {
+            Some(nonce) => nonce,
+            None => return false,
+        };
+        let request = match self.requests.get(&nonce) {
+            Some((request, sent)) if now.saturating_sub(*sent) <= REPAIR_RESPONSE_TIMEOUT_MS => {
+                *request
+            }
+            _ => return false,
+        };
+        let (slot, index) = (blob.slot(), blob.index());
+        let answers_request = match request {
+            RepairType::Blob(request_slot, request_index) => {
+                slot == request_slot && index == request_index
+            }
+            RepairType::HighestBlob(request_slot, request_index) => {
+                slot == request_slot && index >= request_index
+            }
+            RepairType::Orphan(request_slot) => return slot <= request_slot,
+        };
+        if answers_request {
+            self.requests.remove(&nonce);
         }
+        answers_request
+    }
+
+    /// Forgets the requests that didn't get a response in time
+    pub fn purge(&mut self, now: u64) {
+        self.requests
+            .retain(|_, (_, sent)| now.saturating_sub(*sent) <= REPAIR_RESPONSE_TIMEOUT_MS);
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
     }
 }
 
 pub struct RepairService {
     t_repair: JoinHandle<()>,
+    t_rpc_repair: Option<JoinHandle<()>>,
     cluster_info_repair_listener: Option<ClusterInfoRepairListener>,
 }
 
+/// The repair thread's handle on the RPC repair thread: stuck repairs are sent to it once
+/// repair makes no progress for `timeout`
+struct RpcRepairSender {
+    sender: SyncSender<Vec<RepairType>>,
+    timeout: Duration,
+}
+
 impl RepairService {
     pub fn new(
         blocktree: Arc<Blocktree>,
         exit: Arc<AtomicBool>,
         repair_socket: Arc<UdpSocket>,
         cluster_info: Arc<RwLock<ClusterInfo>>,
-        repair_strategy: RepairStrategy,
+        mut repair_strategy: RepairStrategy,
+        outstanding_repairs: Arc<RwLock<OutstandingRepairs>>,
     ) -> Self {
         let cluster_info_repair_listener = match repair_strategy {
             RepairStrategy::RepairAll {
@@ -81,6 +194,30 @@ impl RepairService {
             _ => None,
         };
 
+        // RPC requests can take seconds to time out, so they're made on their own thread
+        let rpc_repair = match repair_strategy {
+            RepairStrategy::RepairAll {
+                ref mut rpc_repair, ..
+            } => rpc_repair.take(),
+            _ => None,
+        };
+        let (rpc_repair_sender, t_rpc_repair) = match rpc_repair {
+            Some(rpc_repair) => {
+                let (sender, receiver) = sync_channel(1);
+                let rpc_repair_sender = RpcRepairSender {
+                    sender,
+                    timeout: rpc_repair.config.timeout,
+                };
+                let id = cluster_info.read().unwrap().id();
+                let t_rpc_repair = Builder::new()
+                    .name("solana-rpc-repair".to_string())
+                    .spawn(move || Self::run_rpc_repair(&id, &rpc_repair, &receiver))
+                    .unwrap();
+                (Some(rpc_repair_sender), Some(t_rpc_repair))
+            }
+            None => (None, None),
+        };
+
         let t_repair = Builder::new()
             .name("solana-repair-service".to_string())
             .spawn(move || {
@@ -90,12 +227,15 @@ impl RepairService {
                     &repair_socket,
                     &cluster_info,
                     repair_strategy,
+                    rpc_repair_sender,
+                    &outstanding_repairs,
                 )
             })
             .unwrap();
 
         RepairService {
             t_repair,
+            t_rpc_repair,
             cluster_info_repair_listener,
         }
     }
@@ -106,10 +246,14 @@ impl RepairService {
         repair_socket: &Arc<UdpSocket>,
         cluster_info: &Arc<RwLock<ClusterInfo>>,
         repair_strategy: RepairStrategy,
+        rpc_repair_sender: Option<RpcRepairSender>,
+        outstanding_repairs: &RwLock<OutstandingRepairs>,
     ) {
         let mut epoch_slots: BTreeSet<u64> = BTreeSet::new();
         let id = cluster_info.read().unwrap().id();
         let mut current_root = 0;
+        let mut last_repairs = vec![];
+        let mut last_progress = Instant::now();
         if let RepairStrategy::RepairAll {
             ref bank_forks,
             ref epoch_schedule,
@@ -162,17 +306,39 @@ impl RepairService {
             };
 
             if let Ok(repairs) = repairs {
+                if let Some(ref rpc_repair_sender) = rpc_repair_sender {
+                    // Repair is stuck if it keeps asking for the same blobs
+                    if repairs.is_empty() || repairs != last_repairs {
+                        last_repairs = repairs.clone();
+                        last_progress = Instant::now();
+                    } else if last_progress.elapsed() > rpc_repair_sender.timeout {
+                        // If the RPC repair thread is still fetching the last stuck repairs,
+                        // these are asked for again the next time repair is stuck
+                        let _ = rpc_repair_sender.sender.try_send(repairs.clone());
+                        last_progress = Instant::now();
+                    }
+                }
+
+                let now = timestamp();
+                let mut outstanding_repairs = outstanding_repairs.write().unwrap();
+                outstanding_repairs.purge(now);
                 let reqs: Vec<_> = repairs
                     .into_iter()
                     .filter_map(|repair_request| {
-                        cluster_info
-                            .read()
-                            .unwrap()
-                            .repair_request(&repair_request)
-                            .map(|result| (result, repair_request))
-                            .ok()
+                        let nonce = outstanding_repairs.add_request(repair_request, now);
+                        let cluster_info = cluster_info.read().unwrap();
+                        let result = match repair_strategy {
+                            // Replicators download old segments, so ask nodes that have them
+                            RepairStrategy::RepairRange(ref repair_slot_range) => cluster_info
+                                .repair_request_in_range(&repair_request, repair_slot_range, nonce),
+                            RepairStrategy::RepairAll { .. } => {
+                                cluster_info.repair_request(&repair_request, nonce)
+                            }
+                        };
+                        result.map(|result| (result, repair_request)).ok()
                     })
                     .collect();
+                drop(outstanding_repairs);
 
                 for ((to, req), repair_request) in reqs {
                     if let Ok(local_addr) = repair_socket.local_addr() {
@@ -194,15 +360,94 @@ impl RepairService {
         }
     }
 
+    // Fetch the stuck repairs sent by the repair thread over RPC, until the repair thread exits
+    fn run_rpc_repair(id: &Pubkey, rpc_repair: &RpcRepair, receiver: &Receiver<Vec<RepairType>>) {
+        for repairs in receiver.iter() {
+            Self::repair_over_rpc(id, &repairs, rpc_repair);
+        }
+    }
+
+    // Fetch the blobs for `repairs` from the first trusted node that has them. Orphans are
+    // left to UDP repair, since the missing parent slot isn't known.
+    fn repair_over_rpc(id: &Pubkey, repairs: &[RepairType], rpc_repair: &RpcRepair) {
+        let rpc_clients: Vec<_> = rpc_repair
+            .config
+            .rpc_addrs
+            .iter()
+            .map(|rpc_addr| {
+                let timeout = Duration::from_secs(RPC_REPAIR_REQUEST_TIMEOUT_SECS);
+                (
+                    rpc_addr,
+                    RpcClient::new_socket_with_timeout(*rpc_addr, timeout),
+                )
+            })
+            .collect();
+
+        for (slot, indexes) in Self::rpc_repair_ranges(repairs) {
+            let start_index = indexes.start;
+            let end_index = indexes.end_exclusive().unwrap_or(std::u64::MAX);
+            for (rpc_addr, rpc_client) in &rpc_clients {
+                let blobs = match rpc_client.get_blobs(slot, start_index, end_index) {
+                    Ok(blobs) => blobs,
+                    Err(err) => {
+                        info!("{} rpc repair from {} error {:?}", id, rpc_addr, err);
+                        continue;
+                    }
+                };
+                if blobs.is_empty() {
+                    continue;
+                }
+
+                datapoint_info!(
+                    "repair_service-rpc_repair",
+                    ("slot", slot, i64),
+                    ("blobs", blobs.len(), i64),
+                    ("from", rpc_addr.to_string(), String),
+                    ("id", id.to_string(), String)
+                );
+                let blobs = blobs
+                    .iter()
+                    .filter(|bytes| bytes.len() <= BLOB_SIZE)
+                    .map(|bytes| Arc::new(RwLock::new(Blob::new(bytes))))
+                    .collect();
+                if rpc_repair.blob_sender.send(blobs).is_err() {
+                    return;
+                }
+                break;
+            }
+        }
+    }
+
+    // Merge `repairs` into one range of blob indexes to request per slot
+    fn rpc_repair_ranges(repairs: &[RepairType]) -> BTreeMap<u64, SlotRange> {
+        let mut ranges: BTreeMap<u64, SlotRange> = BTreeMap::new();
+        for repair in repairs {
+            let (slot, indexes) = match *repair {
+                RepairType::Orphan(_) => continue,
+                RepairType::HighestBlob(slot, index) => (
+                    slot,
+                    SlotRange::with_len(index, MAX_REPAIR_LENGTH as u64).unwrap(),
+                ),
+                RepairType::Blob(slot, index) => (slot, SlotRange::with_len(index, 1).unwrap()),
+            };
+            let range = ranges.entry(slot).or_insert(indexes);
+            *range = range.union(&indexes);
+        }
+        for range in ranges.values_mut() {
+            *range = range.truncate(MAX_GET_BLOBS_RANGE);
+        }
+        ranges
+    }
+
     // Generate repairs for all slots `x` in the repair_range.start <= x <= repair_range.end
     pub fn generate_repairs_in_range(
         blocktree: &Blocktree,
         max_repairs: usize,
-        repair_range: &RepairSlotRange,
+        repair_range: &SlotRange,
     ) -> Result<(Vec<RepairType>)> {
         // Slot height and blob indexes for blobs we want to repair
         let mut repairs: Vec<RepairType> = vec![];
-        for slot in repair_range.start..=repair_range.end {
+        for slot in repair_range.iter() {
             if repairs.len() >= max_repairs {
                 break;
             }
@@ -392,6 +637,9 @@ impl Service for RepairService {
 
     fn join(self) -> thread::Result<()> {
         let mut results = vec![self.t_repair.join()];
+        if let Some(t_rpc_repair) = self.t_rpc_repair {
+            results.push(t_rpc_repair.join());
+        }
         if let Some(cluster_info_repair_listener) = self.cluster_info_repair_listener {
             results.push(cluster_info_repair_listener.join());
         }
@@ -416,6 +664,62 @@ mod test {
     use std::sync::mpsc::channel;
     use std::thread::Builder;
 
+    #[test]
+    fn test_append_and_strip_nonce() {
+        let mut blob = Blob::default();
+        blob.set_size(10);
+        append_nonce(&mut blob, 7);
+        assert_eq!(blob.size(), 0);
+        assert_eq!(strip_nonce(&mut blob), Some(7));
+        assert_eq!(blob.size(), 10);
+
+        blob.meta.size = SIZE_OF_NONCE - 1;
+        assert_eq!(strip_nonce(&mut blob), None);
+    }
+
+    #[test]
+    fn test_outstanding_repairs() {
+        let response = |slot, index, nonce| {
+            let mut blob = Blob::default();
+            blob.set_slot(slot);
+            blob.set_index(index);
+            blob.set_size(1);
+            append_nonce(&mut blob, nonce);
+            blob
+        };
+        let mut outstanding = OutstandingRepairs::default();
+        let now = timestamp();
+        let blob_nonce = outstanding.add_request(RepairType::Blob(2, 1), now);
+        let highest_nonce = outstanding.add_request(RepairType::HighestBlob(2, 3), now);
+        let orphan_nonce = outstanding.add_request(RepairType::Orphan(5), now);
+        assert_eq!(outstanding.len(), 3);
+
+        // Blobs other than the ones requested are dropped
+        assert!(!outstanding.register_response(&mut response(2, 2, blob_nonce), now));
+        assert!(!outstanding.register_response(&mut response(3, 1, blob_nonce), now));
+        assert!(!outstanding.register_response(&mut response(2, 2, highest_nonce), now));
+        assert!(!outstanding.register_response(&mut response(6, 0, orphan_nonce), now));
+        let mut blob = response(2, 1, blob_nonce);
+        blob.meta.size -= SIZE_OF_NONCE;
+        assert!(!outstanding.register_response(&mut blob, now));
+
+        // A request is answered once, except for orphan requests
+        let mut blob = response(2, 1, blob_nonce);
+        assert!(outstanding.register_response(&mut blob, now));
+        assert_eq!(blob.size(), 1);
+        assert!(!outstanding.register_response(&mut response(2, 1, blob_nonce), now));
+        assert!(outstanding.register_response(&mut response(2, 4, highest_nonce), now));
+        assert!(outstanding.register_response(&mut response(5, 0, orphan_nonce), now));
+        assert!(outstanding.register_response(&mut response(4, 0, orphan_nonce), now));
+        assert_eq!(outstanding.len(), 1);
+
+        // Late responses are dropped
+        let late = now + REPAIR_RESPONSE_TIMEOUT_MS + 1;
+        assert!(!outstanding.register_response(&mut response(3, 0, orphan_nonce), late));
+        outstanding.purge(late);
+        assert!(outstanding.is_empty());
+    }
+
     #[test]
     pub fn test_repair_orphan() {
         let blocktree_path = get_tmp_ledger_path!();
@@ -550,11 +854,9 @@ mod test {
             // sides of the range)
             for start in 0..slots.len() {
                 for end in start..slots.len() {
-                    let mut repair_slot_range = RepairSlotRange::default();
-                    repair_slot_range.start = slots[start];
-                    repair_slot_range.end = slots[end];
-                    let expected: Vec<RepairType> = (repair_slot_range.start
-                        ..=repair_slot_range.end)
+                    let repair_slot_range = SlotRange::new(slots[start], slots[end]).unwrap();
+                    let expected: Vec<RepairType> = repair_slot_range
+                        .iter()
                         .map(|slot_index| {
                             if slots.contains(&(slot_index as u64)) {
                                 RepairType::Blob(slot_index as u64, 0)
@@ -605,9 +907,7 @@ mod test {
                 RepairType::HighestBlob(end, 0),
             ];
 
-            let mut repair_slot_range = RepairSlotRange::default();
-            repair_slot_range.start = 2;
-            repair_slot_range.end = end;
+            let repair_slot_range = SlotRange::new(2, end).unwrap();
 
             assert_eq!(
                 RepairService::generate_repairs_in_range(
@@ -622,6 +922,32 @@ mod test {
         Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_rpc_repair_ranges() {
+        let repairs = vec![
+            RepairType::Orphan(1),
+            RepairType::Blob(2, 5),
+            RepairType::Blob(2, 3),
+            RepairType::HighestBlob(4, 10),
+            RepairType::Blob(6, 0),
+            RepairType::Blob(6, MAX_GET_BLOBS_RANGE + 10),
+        ];
+        let ranges: Vec<_> = RepairService::rpc_repair_ranges(&repairs)
+            .into_iter()
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (2, SlotRange::new(3, 5).unwrap()),
+                (
+                    4,
+                    SlotRange::new(10, 10 + MAX_REPAIR_LENGTH as u64 - 1).unwrap()
+                ),
+                (6, SlotRange::new(0, MAX_GET_BLOBS_RANGE - 1).unwrap()),
+            ]
+        );
+    }
+
     #[test]
     pub fn test_get_completed_slots_past_root() {
         let blocktree_path = get_tmp_ledger_path!();
@@ -1,18 +1,23 @@
 //! The `replay_stage` replays transactions broadcast by the leader.
 
+use crate::accounts_hash_verifier::AccountsHashVerifier;
 use crate::bank_forks::BankForks;
+use crate::block_commitment::BlockCommitmentCache;
 use crate::blocktree::{Blocktree, BlocktreeError};
 use crate::blocktree_processor;
 use crate::cluster_info::ClusterInfo;
-use crate::consensus::{StakeLockout, Tower};
-use crate::entry::{Entry, EntrySlice};
+use crate::consensus::{StakeLockout, Tower, VOTE_THRESHOLD_SIZE};
+use crate::entry::Entry;
+use crate::entry_verifier::EntryVerifier;
+use crate::heaviest_subtree_fork_choice::HeaviestSubtreeForkChoice;
 use crate::leader_schedule_cache::LeaderScheduleCache;
+use crate::leader_slot_report::LeaderSlotReport;
 use crate::packet::BlobError;
 use crate::poh_recorder::PohRecorder;
 use crate::result::{Error, Result};
 use crate::rpc_subscriptions::RpcSubscriptions;
 use crate::service::Service;
-use solana_metrics::{datapoint_warn, inc_new_counter_info};
+use solana_metrics::{datapoint_info, datapoint_warn, inc_new_counter_info};
 use solana_runtime::bank::Bank;
 use solana_sdk::hash::Hash;
 use solana_sdk::pubkey::Pubkey;
@@ -20,7 +25,9 @@ use solana_sdk::signature::KeypairUtil;
 use solana_sdk::timing::{self, duration_as_ms};
 use solana_sdk::transaction::Transaction;
 use solana_vote_api::vote_instruction;
-use std::collections::HashMap;
+use solana_vote_api::vote_state::VoteState;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
 use std::sync::{Arc, Mutex, RwLock};
@@ -87,9 +94,16 @@ impl ReplayStage {
         exit: &Arc<AtomicBool>,
         ledger_signal_receiver: Receiver<bool>,
         subscriptions: &Arc<RpcSubscriptions>,
+        block_commitment_cache: &Arc<RwLock<BlockCommitmentCache>>,
         poh_recorder: &Arc<Mutex<PohRecorder>>,
         leader_schedule_cache: &Arc<LeaderScheduleCache>,
         slot_full_senders: Vec<Sender<(u64, Pubkey)>>,
+        gossip_leader_slots: bool,
+        trusted_validators: HashSet<Pubkey>,
+        halt_on_trusted_validators_accounts_hash_mismatch: bool,
+        tower_path: Option<&Path>,
+        dev_halt_at_slot: Option<u64>,
+        leader_disabled: bool,
     ) -> (Self, Receiver<Vec<Arc<Bank>>>)
     where
         T: 'static + KeypairUtil + Send + Sync,
@@ -98,10 +112,19 @@ impl ReplayStage {
         trace!("replay stage");
         let exit_ = exit.clone();
         let subscriptions = subscriptions.clone();
+        let block_commitment_cache = block_commitment_cache.clone();
         let bank_forks = bank_forks.clone();
         let poh_recorder = poh_recorder.clone();
         let my_pubkey = *my_pubkey;
-        let mut tower = Tower::new_from_forks(&bank_forks.read().unwrap(), &my_pubkey);
+        if let Some(halt_slot) = dev_halt_at_slot {
+            warn!("{} replay halts at slot {}", my_pubkey, halt_slot);
+        }
+        let mut tower = Tower::new_from_forks(&bank_forks.read().unwrap(), &my_pubkey, tower_path);
+        let mut fork_choice =
+            HeaviestSubtreeForkChoice::new_from_bank_forks(&bank_forks.read().unwrap());
+        for bank in bank_forks.read().unwrap().frozen_banks().values() {
+            Self::add_fork_choice_votes(bank, &tower, &mut fork_choice);
+        }
         // Start the replay stage loop
         let leader_schedule_cache = leader_schedule_cache.clone();
         let vote_account = *vote_account;
@@ -115,6 +138,13 @@ impl ReplayStage {
                 let _exit = Finalizer::new(exit_.clone());
                 let mut progress = HashMap::new();
                 let mut current_leader = None;
+                let mut leader_slot_report = LeaderSlotReport::new(gossip_leader_slots);
+                let mut accounts_hash_verifier = AccountsHashVerifier::new(
+                    trusted_validators,
+                    halt_on_trusted_validators_accounts_hash_mismatch,
+                    &exit_,
+                );
+                let entry_verifier = EntryVerifier::new();
 
                 loop {
                     let now = Instant::now();
@@ -127,6 +157,9 @@ impl ReplayStage {
                         &blocktree,
                         &mut bank_forks.write().unwrap(),
                         &leader_schedule_cache,
+                        &mut fork_choice,
+                        &tower,
+                        dev_halt_at_slot,
                     );
 
                     let mut tpu_has_bank = poh_recorder.lock().unwrap().has_bank();
@@ -135,13 +168,19 @@ impl ReplayStage {
                         &blocktree,
                         &bank_forks,
                         &my_pubkey,
+                        &block_commitment_cache,
                         &mut progress,
                         &slot_full_senders,
+                        &tower,
+                        &mut fork_choice,
+                        &entry_verifier,
                     );
 
-                    let votable = Self::generate_votable_banks(&bank_forks, &tower, &mut progress);
+                    let (vote_fork, reset_bank) =
+                        Self::select_forks(&bank_forks, &tower, &fork_choice, &mut progress);
 
-                    if let Some((_, bank, lockouts)) = votable.into_iter().last() {
+                    let vote_fork_slot = vote_fork.as_ref().map(|(bank, _)| bank.slot());
+                    if let Some((bank, lockouts)) = vote_fork {
                         subscriptions.notify_subscribers(bank.slot(), &bank_forks);
 
                         if let Some(new_leader) =
@@ -156,9 +195,11 @@ impl ReplayStage {
                         }
 
                         Self::handle_votable_bank(
+                            &my_pubkey,
                             &bank,
                             &bank_forks,
                             &mut tower,
+                            &mut fork_choice,
                             &mut progress,
                             &vote_account,
                             &voting_keypair,
@@ -168,27 +209,42 @@ impl ReplayStage {
                             &root_bank_sender,
                             lockouts,
                             &lockouts_sender,
+                            &mut leader_slot_report,
+                            &mut accounts_hash_verifier,
                         )?;
+                        Self::report_block_commitment(&bank, &block_commitment_cache);
+                    }
 
-                        Self::reset_poh_recorder(
-                            &my_pubkey,
-                            &blocktree,
-                            &bank,
-                            &poh_recorder,
-                            &leader_schedule_cache,
-                        );
-                        tpu_has_bank = false;
+                    // Build on the heaviest fork even when it can't be voted on yet, but don't
+                    // interrupt a leader slot in progress to switch forks without a vote
+                    if let Some(bank) = reset_bank {
+                        let voted = vote_fork_slot == Some(bank.slot());
+                        if (voted || !tpu_has_bank)
+                            && bank.slot() != poh_recorder.lock().unwrap().start_slot()
+                        {
+                            Self::reset_poh_recorder(
+                                &my_pubkey,
+                                &blocktree,
+                                &bank,
+                                &poh_recorder,
+                                &leader_schedule_cache,
+                                leader_disabled,
+                            );
+                            tpu_has_bank = false;
+                        }
                     }
 
-                    if !tpu_has_bank {
+                    if !tpu_has_bank && !leader_disabled {
                         Self::maybe_start_leader(
                             &my_pubkey,
                             &bank_forks,
                             &poh_recorder,
                             &leader_schedule_cache,
+                            dev_halt_at_slot,
                         );
 
                         if let Some(bank) = poh_recorder.lock().unwrap().bank() {
+                            leader_slot_report.start_slot(bank.slot());
                             Self::log_leader_change(
                                 &my_pubkey,
                                 bank.slot(),
@@ -255,6 +311,7 @@ impl ReplayStage {
         bank_forks: &Arc<RwLock<BankForks>>,
         poh_recorder: &Arc<Mutex<PohRecorder>>,
         leader_schedule_cache: &Arc<LeaderScheduleCache>,
+        dev_halt_at_slot: Option<u64>,
     ) {
         // all the individual calls to poh_recorder.lock() are designed to
         // increase granularity, decrease contention
@@ -270,6 +327,11 @@ impl ReplayStage {
         }
         trace!("{} reached_leader_tick", my_pubkey,);
 
+        if dev_halt_at_slot.map_or(false, |halt_slot| poh_slot > halt_slot) {
+            trace!("{} poh slot {} is past the halt slot", my_pubkey, poh_slot);
+            return;
+        }
+
         let parent = bank_forks
             .read()
             .unwrap()
@@ -339,10 +401,18 @@ impl ReplayStage {
         bank: &Bank,
         blocktree: &Blocktree,
         progress: &mut HashMap<u64, ForkProgress>,
+        entry_verifier: &EntryVerifier,
     ) -> Result<()> {
+        let replay_start = Instant::now();
         let result =
             Self::load_blocktree_entries(bank, blocktree, progress).and_then(|(entries, num)| {
-                Self::replay_entries_into_bank(bank, entries, progress, num)
+                let replayed_entries = !entries.is_empty();
+                let result =
+                    Self::replay_entries_into_bank(bank, entries, progress, num, entry_verifier);
+                if replayed_entries {
+                    solana_metrics::record_latency("replay", replay_start.elapsed());
+                }
+                result
             });
 
         if Self::is_replay_result_fatal(&result) {
@@ -358,6 +428,7 @@ impl ReplayStage {
     }
 
     fn mark_dead_slot(slot: u64, blocktree: &Blocktree, progress: &mut HashMap<u64, ForkProgress>) {
+        inc_new_counter_info!("replay_stage-mark_dead_slot", 1);
         // Remove from progress map so we no longer try to replay this bank
         let mut progress_entry = progress
             .get_mut(&slot)
@@ -368,11 +439,61 @@ impl ReplayStage {
             .expect("Failed to mark slot as dead in blocktree");
     }
 
+    /// Whether to hold off replaying `bank`'s slot. A slot found dead before this node restarted
+    /// is marked dead in `progress`. A slot whose leader signed duplicate blobs waits until a
+    /// supermajority of the stake votes for it, so the node can follow the cluster if the
+    /// cluster settles on it.
+    fn skip_unreplayable_slot(
+        bank: &Bank,
+        blocktree: &Blocktree,
+        block_commitment_cache: &RwLock<BlockCommitmentCache>,
+        progress: &mut HashMap<u64, ForkProgress>,
+    ) -> bool {
+        let slot = bank.slot();
+        let first_seen = !progress.contains_key(&slot);
+        let is_dead = first_seen && blocktree.is_dead(slot);
+        if !is_dead
+            && (!blocktree.has_duplicate_blobs_in_slot(slot)
+                || Self::is_supermajority_confirmed(slot, block_commitment_cache))
+        {
+            return false;
+        }
+        if first_seen {
+            let reason = if is_dead { "dead" } else { "duplicate" };
+            warn!("Not replaying {} slot: {}", reason, slot);
+            datapoint_warn!(
+                "replay_stage-skip_slot",
+                ("slot", slot, i64),
+                ("reason", reason.to_string(), String)
+            );
+        }
+        progress
+            .entry(slot)
+            .or_insert_with(|| ForkProgress::new(bank.last_blockhash()))
+            .is_dead = is_dead;
+        true
+    }
+
+    fn is_supermajority_confirmed(
+        slot: u64,
+        block_commitment_cache: &RwLock<BlockCommitmentCache>,
+    ) -> bool {
+        let block_commitment_cache = block_commitment_cache.read().unwrap();
+        let min_stake = block_commitment_cache.total_stake() as f64 * VOTE_THRESHOLD_SIZE;
+        block_commitment_cache
+            .get_block_commitment(slot)
+            .map_or(false, |commitment| {
+                commitment.get_confirmed_stake(1) as f64 > min_stake
+            })
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn handle_votable_bank<T>(
+        my_pubkey: &Pubkey,
         bank: &Arc<Bank>,
         bank_forks: &Arc<RwLock<BankForks>>,
         tower: &mut Tower,
+        fork_choice: &mut HeaviestSubtreeForkChoice,
         progress: &mut HashMap<u64, ForkProgress>,
         vote_account: &Pubkey,
         voting_keypair: &Option<Arc<T>>,
@@ -382,12 +503,26 @@ impl ReplayStage {
         root_bank_sender: &Sender<Vec<Arc<Bank>>>,
         lockouts: HashMap<u64, StakeLockout>,
         lockouts_sender: &Sender<LockoutAggregationData>,
+        leader_slot_report: &mut LeaderSlotReport,
+        accounts_hash_verifier: &mut AccountsHashVerifier,
     ) -> Result<()>
     where
         T: 'static + KeypairUtil + Send + Sync,
     {
         trace!("handle votable bank {}", bank.slot());
-        if let Some(new_root) = tower.record_vote(bank.slot(), bank.hash()) {
+        // The vote must be on disk before the cluster sees it, or a restart could forget it
+        let new_root = match tower.record_vote_and_save(bank.slot(), bank.hash()) {
+            Ok(new_root) => new_root,
+            Err(err) => {
+                error!("{} Unable to save tower: {:?}", my_pubkey, err);
+                datapoint_error!(
+                    "replay-stage-tower_save_failure",
+                    ("error", format!("{:?}", err), String)
+                );
+                return Ok(());
+            }
+        };
+        if let Some(new_root) = new_root {
             // get the root bank before squash
             let root_bank = bank_forks
                 .read()
@@ -405,7 +540,22 @@ impl ReplayStage {
             // is consumed by repair_service to update gossip, so we don't want to get blobs for
             // repair on gossip before we update leader schedule, otherwise they may get dropped.
             leader_schedule_cache.set_root(rooted_banks.last().unwrap());
+            // Report on the leader slots before the forks that didn't make it are pruned
+            let prev_root = bank_forks.read().unwrap().root();
+            leader_slot_report.new_root(
+                my_pubkey,
+                prev_root,
+                &rooted_banks,
+                &bank_forks.read().unwrap(),
+                leader_schedule_cache,
+                cluster_info,
+            );
+            // Epoch boundaries are found through the rooted banks' parents, so this too must
+            // happen before they're squashed
+            accounts_hash_verifier.new_root(&rooted_banks, cluster_info);
             bank_forks.write().unwrap().set_root(new_root);
+            fork_choice.set_root(new_root);
+            Self::prune_beyond_limits(&mut bank_forks.write().unwrap(), fork_choice, tower);
             Self::handle_new_root(&bank_forks, progress);
             trace!("new root {}", new_root);
             if let Err(e) = root_bank_sender.send(rooted_banks) {
@@ -463,15 +613,35 @@ impl ReplayStage {
         }
     }
 
+    /// Reports how much of the cluster's stake has been seen voting for `bank` over gossip
+    fn report_block_commitment(bank: &Bank, block_commitment_cache: &RwLock<BlockCommitmentCache>) {
+        let block_commitment_cache = block_commitment_cache.read().unwrap();
+        let confirmed_stake = block_commitment_cache
+            .get_block_commitment(bank.slot())
+            .map(|commitment| commitment.get_confirmed_stake(1))
+            .unwrap_or(0);
+        datapoint_info!(
+            "replay_stage-block_commitment",
+            ("slot", bank.slot(), i64),
+            ("confirmed_stake", confirmed_stake, i64),
+            ("total_stake", block_commitment_cache.total_stake(), i64)
+        );
+    }
+
     fn reset_poh_recorder(
         my_pubkey: &Pubkey,
         blocktree: &Blocktree,
         bank: &Arc<Bank>,
         poh_recorder: &Arc<Mutex<PohRecorder>>,
         leader_schedule_cache: &Arc<LeaderScheduleCache>,
+        leader_disabled: bool,
     ) {
-        let next_leader_slot =
-            leader_schedule_cache.next_leader_slot(&my_pubkey, bank.slot(), &bank, Some(blocktree));
+        // Without a leader slot to wait for, PoH only ticks
+        let next_leader_slot = if leader_disabled {
+            None
+        } else {
+            leader_schedule_cache.next_leader_slot(&my_pubkey, bank.slot(), &bank, Some(blocktree))
+        };
         poh_recorder
             .lock()
             .unwrap()
@@ -484,8 +654,9 @@ impl ReplayStage {
         };
 
         info!(
-            "{} voted and reset poh at {}. {}",
+            "{} reset poh to slot {} at {}. {}",
             my_pubkey,
+            bank.slot(),
             bank.tick_height(),
             next_leader_msg,
         );
@@ -495,8 +666,12 @@ impl ReplayStage {
         blocktree: &Arc<Blocktree>,
         bank_forks: &Arc<RwLock<BankForks>>,
         my_pubkey: &Pubkey,
+        block_commitment_cache: &RwLock<BlockCommitmentCache>,
         progress: &mut HashMap<u64, ForkProgress>,
         slot_full_senders: &[Sender<(u64, Pubkey)>],
+        tower: &Tower,
+        fork_choice: &mut HeaviestSubtreeForkChoice,
+        entry_verifier: &EntryVerifier,
     ) -> bool {
         let mut did_complete_bank = false;
         let active_banks = bank_forks.read().unwrap().active_banks();
@@ -509,20 +684,35 @@ impl ReplayStage {
             }
 
             let bank = bank_forks.read().unwrap().get(*bank_slot).unwrap().clone();
-            if bank.collector_id() != my_pubkey
-                && Self::is_replay_result_fatal(&Self::replay_blocktree_into_bank(
-                    &bank, &blocktree, progress,
-                ))
-            {
-                trace!("replay_result_fatal slot {}", bank_slot);
-                // If the bank was corrupted, don't try to run the below logic to check if the
-                // bank is completed
-                continue;
+            if bank.collector_id() != my_pubkey {
+                if Self::skip_unreplayable_slot(&bank, &blocktree, block_commitment_cache, progress)
+                {
+                    continue;
+                }
+                if Self::is_replay_result_fatal(&Self::replay_blocktree_into_bank(
+                    &bank,
+                    &blocktree,
+                    progress,
+                    entry_verifier,
+                )) {
+                    trace!("replay_result_fatal slot {}", bank_slot);
+                    // If the bank was corrupted, don't try to run the below logic to check if the
+                    // bank is completed
+                    continue;
+                }
             }
             assert_eq!(*bank_slot, bank.slot());
             if bank.tick_height() == bank.max_tick_height() {
                 did_complete_bank = true;
-                Self::process_completed_bank(my_pubkey, bank, slot_full_senders);
+                Self::process_completed_bank(
+                    my_pubkey,
+                    bank.clone(),
+                    &blocktree,
+                    slot_full_senders,
+                );
+                fork_choice
+                    .add_new_leaf_slot(bank.slot(), bank.parent().map(|parent| parent.slot()));
+                Self::add_fork_choice_votes(&bank, tower, fork_choice);
             } else {
                 trace!(
                     "bank {} not completed tick_height: {}, max_tick_height: {}",
@@ -535,11 +725,55 @@ impl ReplayStage {
         did_complete_bank
     }
 
-    fn generate_votable_banks(
+    /// Feed the latest vote of every staked vote account in `bank` to `fork_choice`
+    fn add_fork_choice_votes(
+        bank: &Bank,
+        tower: &Tower,
+        fork_choice: &mut HeaviestSubtreeForkChoice,
+    ) {
+        let votes: Vec<_> = bank
+            .vote_accounts()
+            .into_iter()
+            .filter_map(|(key, (_, account))| {
+                let stake = tower.epoch_stake(&key);
+                if stake == 0 {
+                    return None;
+                }
+                VoteState::from(&account)
+                    .and_then(|vote_state| vote_state.nth_recent_vote(0).map(|vote| vote.slot))
+                    .map(|slot| (key, slot, stake))
+            })
+            .collect();
+        fork_choice.add_votes(votes);
+    }
+
+    // Candidates on the heaviest fork come first, the deepest of them first. The
+    // rest are ordered by the stake voted on their subtree.
+    fn fork_choice_key(fork_choice: &HeaviestSubtreeForkChoice, slot: u64) -> (bool, u64, u64) {
+        if fork_choice.is_on_heaviest_fork(slot) {
+            (true, 0, slot)
+        } else {
+            (
+                false,
+                fork_choice.stake_voted_subtree(slot).unwrap_or(0),
+                slot,
+            )
+        }
+    }
+
+    /// Selects the bank to vote on, if any passes the tower's checks, and the bank to reset PoH
+    /// to. PoH builds on the vote bank when there is one, otherwise on the heaviest fork the
+    /// tower isn't locked out of, so a leader can keep extending it while votes catch up. If the
+    /// tower is locked out of every fork left, PoH isn't reset at all.
+    fn select_forks(
         bank_forks: &Arc<RwLock<BankForks>>,
         tower: &Tower,
+        fork_choice: &HeaviestSubtreeForkChoice,
         progress: &mut HashMap<u64, ForkProgress>,
-    ) -> Vec<(u128, Arc<Bank>, HashMap<u64, StakeLockout>)> {
+    ) -> (
+        Option<(Arc<Bank>, HashMap<u64, StakeLockout>)>,
+        Option<Arc<Bank>>,
+    ) {
         let tower_start = Instant::now();
         // Tower voting
         let descendants = bank_forks.read().unwrap().descendants();
@@ -547,7 +781,7 @@ impl ReplayStage {
         let frozen_banks = bank_forks.read().unwrap().frozen_banks();
 
         trace!("frozen_banks {}", frozen_banks.len());
-        let mut votable: Vec<(u128, Arc<Bank>, HashMap<u64, StakeLockout>)> = frozen_banks
+        let votable: Vec<Arc<Bank>> = frozen_banks
             .values()
             .filter(|b| {
                 let is_votable = b.is_votable();
@@ -569,48 +803,54 @@ impl ReplayStage {
                 trace!("bank is is_locked_out: {} {}", b.slot(), is_locked_out);
                 !is_locked_out
             })
-            .map(|bank| {
-                (
-                    bank,
-                    tower.collect_vote_lockouts(
-                        bank.slot(),
-                        bank.vote_accounts().into_iter(),
-                        &ancestors,
-                    ),
-                )
-            })
-            .filter(|(b, stake_lockouts)| {
+            .cloned()
+            .collect();
+
+        // Every candidate's lockouts are collected, so each confirms the forks it can, and the
+        // best of those passing the vote threshold is voted on
+        trace!("votable_banks {}", votable.len());
+        let fork = votable
+            .into_iter()
+            .filter_map(|b| {
+                let stake_lockouts = tower.collect_vote_lockouts(
+                    b.slot(),
+                    b.vote_accounts().into_iter(),
+                    &ancestors,
+                );
                 let vote_threshold = tower.check_vote_stake_threshold(b.slot(), &stake_lockouts);
-                Self::confirm_forks(tower, stake_lockouts, progress, bank_forks);
+                Self::confirm_forks(tower, &stake_lockouts, progress, bank_forks);
                 debug!("bank vote_threshold: {} {}", b.slot(), vote_threshold);
-                vote_threshold
-            })
-            .map(|(b, stake_lockouts)| {
-                (
-                    tower.calculate_weight(&stake_lockouts),
-                    b.clone(),
-                    stake_lockouts,
-                )
+                if vote_threshold {
+                    Some((b, stake_lockouts))
+                } else {
+                    None
+                }
             })
-            .collect();
+            .max_by_key(|(b, _)| Self::fork_choice_key(fork_choice, b.slot()));
 
-        votable.sort_by_key(|b| b.0);
         let ms = timing::duration_as_ms(&tower_start.elapsed());
-
-        trace!("votable_banks {}", votable.len());
-        if !votable.is_empty() {
-            let weights: Vec<u128> = votable.iter().map(|x| x.0).collect();
+        if let Some((bank, _)) = &fork {
             info!(
-                "@{:?} tower duration: {:?} len: {} weights: {:?}",
+                "@{:?} tower duration: {:?} fork: {} best slot: {} stake: {:?}",
                 timing::timestamp(),
                 ms,
-                votable.len(),
-                weights
+                bank.slot(),
+                fork_choice.best_overall_slot(),
+                fork_choice.stake_voted_subtree(bank.slot())
             );
         }
         inc_new_counter_info!("replay_stage-tower_duration", ms as usize);
 
-        votable
+        let reset_bank = match &fork {
+            Some((bank, _)) => Some(bank.clone()),
+            None => {
+                let reset_slot = fork_choice.best_unlocked_slot(tower.last_voted_slot(), |slot| {
+                    tower.is_locked_out(slot, &descendants)
+                });
+                reset_slot.and_then(|slot| frozen_banks.get(&slot).cloned())
+            }
+        };
+        (fork, reset_bank)
     }
 
     fn confirm_forks(
@@ -661,11 +901,18 @@ impl ReplayStage {
         entries: Vec<Entry>,
         progress: &mut HashMap<u64, ForkProgress>,
         num: usize,
+        entry_verifier: &EntryVerifier,
     ) -> Result<()> {
         let bank_progress = &mut progress
             .entry(bank.slot())
             .or_insert_with(|| ForkProgress::new(bank.last_blockhash()));
-        let result = Self::verify_and_process_entries(&bank, &entries, &bank_progress.last_entry);
+        let entries = Arc::new(entries);
+        let result = Self::verify_and_process_entries(
+            &bank,
+            &entries,
+            &bank_progress.last_entry,
+            entry_verifier,
+        );
         bank_progress.num_blobs += num;
         if let Some(last_entry) = entries.last() {
             bank_progress.last_entry = last_entry.hash;
@@ -674,12 +921,18 @@ impl ReplayStage {
         result
     }
 
+    /// Processes `entries` while `entry_verifier` checks their hash chain, failing if either
+    /// does. The bank may have processed some of the entries even if their verification fails,
+    /// so the caller must mark it dead.
     pub fn verify_and_process_entries(
         bank: &Bank,
-        entries: &[Entry],
+        entries: &Arc<Vec<Entry>>,
         last_entry: &Hash,
+        entry_verifier: &EntryVerifier,
     ) -> Result<()> {
-        if !entries.verify(last_entry) {
+        let pending_verification = entry_verifier.start_verify(entries, last_entry);
+        let process_result = blocktree_processor::process_entries(bank, entries);
+        if !pending_verification.finish() {
             trace!(
                 "entry verification failed {} {} {} {}",
                 entries.len(),
@@ -695,7 +948,7 @@ impl ReplayStage {
             );
             return Err(Error::BlobError(BlobError::VerificationFailed));
         }
-        blocktree_processor::process_entries(bank, entries)?;
+        process_result?;
 
         Ok(())
     }
@@ -711,10 +964,12 @@ impl ReplayStage {
     fn process_completed_bank(
         my_pubkey: &Pubkey,
         bank: Arc<Bank>,
+        blocktree: &Blocktree,
         slot_full_senders: &[Sender<(u64, Pubkey)>],
     ) {
         bank.freeze();
         info!("bank frozen {}", bank.slot());
+        blocktree_processor::write_transaction_history(blocktree, &bank);
         slot_full_senders.iter().for_each(|sender| {
             if let Err(e) = sender.send((bank.slot(), *bank.collector_id())) {
                 trace!("{} slot_full alert failed: {:?}", my_pubkey, e);
@@ -722,10 +977,66 @@ impl ReplayStage {
         });
     }
 
+    /// The slots of the heaviest fork and of the forks voted on, with their ancestors, and the
+    /// banks still being replayed on top of them. These forks are never evicted or pruned, so
+    /// the node can always keep replaying, and voting, on them.
+    fn slots_to_keep(
+        forks: &BankForks,
+        fork_choice: &HeaviestSubtreeForkChoice,
+        tower: &Tower,
+    ) -> HashSet<u64> {
+        let mut keep: HashSet<u64> = tower
+            .voted_slots()
+            .into_iter()
+            .chain(Some(fork_choice.best_overall_slot()))
+            .filter_map(|slot| forks.get(slot))
+            .flat_map(|bank| bank.ancestors.keys().cloned().collect::<Vec<_>>())
+            .collect();
+        let replaying: Vec<u64> = forks
+            .active_banks()
+            .into_iter()
+            .filter(|slot| {
+                forks[*slot]
+                    .parent()
+                    .map_or(false, |parent| keep.contains(&parent.slot()))
+            })
+            .collect();
+        keep.extend(replaying);
+        keep
+    }
+
+    /// Prunes the lightest forks past the new root that don't fit within the pruning limits,
+    /// along with their fork choice state. Their progress goes in `handle_new_root`.
+    fn prune_beyond_limits(
+        forks: &mut BankForks,
+        fork_choice: &mut HeaviestSubtreeForkChoice,
+        tower: &Tower,
+    ) {
+        let keep = Self::slots_to_keep(forks, fork_choice, tower);
+        let pruned = forks.prune_beyond_limits(&keep, |slot| {
+            fork_choice.stake_voted_subtree(slot).unwrap_or(0)
+        });
+        if pruned.is_empty() {
+            return;
+        }
+        for slot in &pruned {
+            fork_choice.remove_leaf_slot(*slot);
+        }
+        datapoint_warn!(
+            "replay_stage-prune_forks",
+            ("root", forks.root(), i64),
+            ("pruned_count", pruned.len(), i64),
+            ("active_bank_count", forks.active_bank_count(), i64)
+        );
+    }
+
     fn generate_new_bank_forks(
         blocktree: &Blocktree,
         forks: &mut BankForks,
         leader_schedule_cache: &Arc<LeaderScheduleCache>,
+        fork_choice: &mut HeaviestSubtreeForkChoice,
+        tower: &Tower,
+        dev_halt_at_slot: Option<u64>,
     ) {
         // Find the next slot that chains to the old slot
         let frozen_banks = forks.frozen_banks();
@@ -739,7 +1050,19 @@ impl ReplayStage {
             next_slots.sort();
             next_slots
         });
+        // The heaviest and last voted forks grow past the limits rather than stall replay,
+        // until the next root prunes the forks around them
+        let kept_fork_tips: HashSet<u64> = tower
+            .last_voted_slot()
+            .into_iter()
+            .chain(Some(fork_choice.best_overall_slot()))
+            .collect();
+        let keep = Self::slots_to_keep(forks, fork_choice, tower);
         for (parent_id, children) in next_slots {
+            if forks.get(parent_id).is_none() {
+                trace!("parent {} was evicted", parent_id);
+                continue;
+            }
             let parent_bank = frozen_banks
                 .get(&parent_id)
                 .expect("missing parent in bank forks")
@@ -749,6 +1072,23 @@ impl ReplayStage {
                     trace!("child already active or frozen {}", child_id);
                     continue;
                 }
+                if dev_halt_at_slot.map_or(false, |halt_slot| child_id > halt_slot) {
+                    trace!("child past the halt slot {}", child_id);
+                    continue;
+                }
+                let extends_kept_fork = kept_fork_tips.contains(&parent_id);
+                if !forks.within_max_depth(child_id) && !extends_kept_fork {
+                    trace!("child beyond the maximum fork depth {}", child_id);
+                    continue;
+                }
+                if !forks.has_room_for_bank()
+                    && (forks.is_evicted(child_id)
+                        || !Self::evict_stale_fork(forks, fork_choice, &keep, parent_id, child_id))
+                    && !extends_kept_fork
+                {
+                    trace!("no room for child {}", child_id);
+                    continue;
+                }
                 let leader = leader_schedule_cache
                     .slot_leader_at(child_id, Some(&parent_bank))
                     .unwrap();
@@ -757,6 +1097,45 @@ impl ReplayStage {
             }
         }
     }
+
+    /// Evicts the stale fork tip with the least stake voted on it to make room for `child`, a
+    /// new child of `parent`. Tips that outweigh `parent`, and the slots in `keep` (see
+    /// `slots_to_keep`), are kept, so a fork this node voted on is never evicted from under
+    /// its lockouts. Returns whether a tip was evicted.
+    fn evict_stale_fork(
+        forks: &mut BankForks,
+        fork_choice: &mut HeaviestSubtreeForkChoice,
+        keep: &HashSet<u64>,
+        parent: u64,
+        child: u64,
+    ) -> bool {
+        let parent_weight = fork_choice.stake_voted_subtree(parent).unwrap_or(0);
+        let lightest_tip = forks
+            .stale_fork_tips()
+            .into_iter()
+            .filter(|slot| *slot != parent && !keep.contains(slot))
+            .map(|slot| (fork_choice.stake_voted_subtree(slot).unwrap_or(0), slot))
+            .min();
+        match lightest_tip {
+            Some((weight, slot)) if weight <= parent_weight => {
+                warn!(
+                    "evicting fork tip {} with stake {} for new bank {}",
+                    slot, weight, child
+                );
+                forks.evict(slot);
+                fork_choice.remove_leaf_slot(slot);
+                datapoint_warn!(
+                    "replay_stage-evict_fork",
+                    ("slot", slot, i64),
+                    ("stake", weight, i64),
+                    ("new_slot", child, i64),
+                    ("active_bank_count", forks.active_bank_count(), i64)
+                );
+                true
+            }
+            _ => false,
+        }
+    }
 }
 
 impl Service for ReplayStage {
@@ -811,7 +1190,7 @@ fn aggregate_stake_lockouts(
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::bank_forks::Confidence;
+    use crate::bank_forks::{Confidence, ForkPruningConfig};
     use crate::blocktree::get_tmp_ledger_path;
     use crate::entry;
     use crate::erasure::ErasureConfig;
@@ -840,6 +1219,7 @@ mod test {
             let leader_schedule_cache = Arc::new(LeaderScheduleCache::new_from_bank(&bank0));
             let mut bank_forks = BankForks::new(0, bank0);
             bank_forks.working_bank().freeze();
+            let mut fork_choice = HeaviestSubtreeForkChoice::new_from_bank_forks(&bank_forks);
 
             // Insert blob for slot 1, generate new forks, check result
             let mut blob_slot_1 = Blob::default();
@@ -852,6 +1232,18 @@ mod test {
                 &blocktree,
                 &mut bank_forks,
                 &leader_schedule_cache,
+                &mut fork_choice,
+                &Tower::default(),
+                Some(0),
+            );
+            assert!(bank_forks.get(1).is_none());
+            ReplayStage::generate_new_bank_forks(
+                &blocktree,
+                &mut bank_forks,
+                &leader_schedule_cache,
+                &mut fork_choice,
+                &Tower::default(),
+                None,
             );
             assert!(bank_forks.get(1).is_some());
 
@@ -866,14 +1258,144 @@ mod test {
                 &blocktree,
                 &mut bank_forks,
                 &leader_schedule_cache,
+                &mut fork_choice,
+                &Tower::default(),
+                None,
             );
             assert!(bank_forks.get(1).is_some());
             assert!(bank_forks.get(2).is_some());
+
+            // With no room for slot 3, the lightest stale fork not on the heaviest fork is
+            // evicted for it
+            bank_forks.set_pruning_config(ForkPruningConfig {
+                max_active_banks: 2,
+                ..ForkPruningConfig::default()
+            });
+            for slot in 1..=2 {
+                bank_forks[slot].freeze();
+                fork_choice.add_new_leaf_slot(slot, Some(0));
+            }
+            assert_eq!(fork_choice.best_overall_slot(), 1);
+            let mut blob_slot_3 = Blob::default();
+            blob_slot_3.set_slot(3);
+            blob_slot_3.set_parent(0);
+            blob_slot_3.set_erasure_config(&ErasureConfig::default());
+            blocktree.insert_data_blobs(&vec![blob_slot_3]).unwrap();
+            ReplayStage::generate_new_bank_forks(
+                &blocktree,
+                &mut bank_forks,
+                &leader_schedule_cache,
+                &mut fork_choice,
+                &Tower::default(),
+                None,
+            );
+            assert!(bank_forks.get(1).is_some());
+            assert!(bank_forks.get(2).is_none());
+            assert!(bank_forks.is_evicted(2));
+            assert!(!fork_choice.contains_slot(2));
+            assert!(bank_forks.get(3).is_some());
+
+            // The evicted slot isn't given a bank again while there's no room for it
+            ReplayStage::generate_new_bank_forks(
+                &blocktree,
+                &mut bank_forks,
+                &leader_schedule_cache,
+                &mut fork_choice,
+                &Tower::default(),
+                None,
+            );
+            assert!(bank_forks.get(2).is_none());
+
+            // The heaviest fork still grows when nothing can be evicted for it
+            let mut blob_slot_4 = Blob::default();
+            blob_slot_4.set_slot(4);
+            blob_slot_4.set_parent(1);
+            blob_slot_4.set_erasure_config(&ErasureConfig::default());
+            blocktree.insert_data_blobs(&vec![blob_slot_4]).unwrap();
+            ReplayStage::generate_new_bank_forks(
+                &blocktree,
+                &mut bank_forks,
+                &leader_schedule_cache,
+                &mut fork_choice,
+                &Tower::default(),
+                None,
+            );
+            assert!(bank_forks.get(4).is_some());
+            assert!(!bank_forks.has_room_for_bank());
         }
 
         let _ignored = remove_dir_all(&ledger_path);
     }
 
+    #[test]
+    fn test_evict_stale_fork_keeps_voted_forks() {
+        let genesis_block = create_genesis_block(10_000).genesis_block;
+        let mut bank_forks = BankForks::new(0, Bank::new(&genesis_block));
+        bank_forks.working_bank().freeze();
+        let mut fork_choice = HeaviestSubtreeForkChoice::new_from_bank_forks(&bank_forks);
+        for (slot, parent) in &[(1, 0), (2, 0), (3, 2)] {
+            let bank = Bank::new_from_parent(&bank_forks[*parent], &Pubkey::default(), *slot);
+            bank_forks.insert(bank).freeze();
+            fork_choice.add_new_leaf_slot(*slot, Some(*parent));
+        }
+        assert_eq!(fork_choice.best_overall_slot(), 1);
+
+        // Neither the tip of the heaviest fork nor the tip voted on is evicted
+        let mut tower = Tower::default();
+        tower.record_vote(3, bank_forks[3].hash());
+        let keep = ReplayStage::slots_to_keep(&bank_forks, &fork_choice, &tower);
+        assert_eq!(keep, vec![0, 1, 2, 3].into_iter().collect::<HashSet<_>>());
+        assert!(!ReplayStage::evict_stale_fork(
+            &mut bank_forks,
+            &mut fork_choice,
+            &keep,
+            0,
+            4
+        ));
+        assert!(bank_forks.get(3).is_some());
+
+        // Without the votes, the lightest tip off the heaviest fork goes
+        let keep = ReplayStage::slots_to_keep(&bank_forks, &fork_choice, &Tower::default());
+        assert!(ReplayStage::evict_stale_fork(
+            &mut bank_forks,
+            &mut fork_choice,
+            &keep,
+            0,
+            4
+        ));
+        assert!(bank_forks.get(3).is_none());
+        assert!(!fork_choice.contains_slot(3));
+    }
+
+    #[test]
+    fn test_prune_beyond_limits() {
+        let genesis_block = create_genesis_block(10_000).genesis_block;
+        let mut bank_forks = BankForks::new(0, Bank::new(&genesis_block));
+        bank_forks.working_bank().freeze();
+        let mut fork_choice = HeaviestSubtreeForkChoice::new_from_bank_forks(&bank_forks);
+        for (slot, parent) in &[(1, 0), (2, 0), (3, 1)] {
+            let bank = Bank::new_from_parent(&bank_forks[*parent], &Pubkey::default(), *slot);
+            bank_forks.insert(bank).freeze();
+            fork_choice.add_new_leaf_slot(*slot, Some(*parent));
+        }
+        bank_forks.set_pruning_config(ForkPruningConfig {
+            max_active_banks: 2,
+            ..ForkPruningConfig::default()
+        });
+        assert_eq!(fork_choice.best_overall_slot(), 3);
+
+        // Slot 2 is lighter than the heaviest fork, which is kept, so it goes from both
+        let mut progress = HashMap::new();
+        progress.insert(2, ForkProgress::new(Hash::default()));
+        ReplayStage::prune_beyond_limits(&mut bank_forks, &mut fork_choice, &Tower::default());
+        assert!(bank_forks.get(2).is_none());
+        assert!(!fork_choice.contains_slot(2));
+        assert_eq!(bank_forks.active_bank_count(), 3);
+        let bank_forks = Arc::new(RwLock::new(bank_forks));
+        ReplayStage::handle_new_root(&bank_forks, &mut progress);
+        assert!(progress.is_empty());
+    }
+
     #[test]
     fn test_handle_new_root() {
         let genesis_block = create_genesis_block(10_000).genesis_block;
@@ -971,6 +1493,62 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_skip_unreplayable_slot() {
+        let ledger_path = get_tmp_ledger_path!();
+        {
+            let blocktree =
+                Blocktree::open(&ledger_path).expect("Expected to be able to open database ledger");
+            let GenesisBlockInfo { genesis_block, .. } = create_genesis_block(1000);
+            let bank0 = Arc::new(Bank::new(&genesis_block));
+            let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+            let bank2 = Bank::new_from_parent(&bank0, &Pubkey::default(), 2);
+            let block_commitment_cache = RwLock::new(BlockCommitmentCache::default());
+            let mut progress = HashMap::new();
+            let skip = |bank: &Bank, progress: &mut HashMap<u64, ForkProgress>| {
+                ReplayStage::skip_unreplayable_slot(
+                    bank,
+                    &blocktree,
+                    &block_commitment_cache,
+                    progress,
+                )
+            };
+
+            assert!(!skip(&bank1, &mut progress));
+            assert!(progress.is_empty());
+
+            // A slot found dead before a restart
+            blocktree.set_dead_slot(1).unwrap();
+            assert!(skip(&bank1, &mut progress));
+            assert!(progress[&1].is_dead);
+
+            // A slot its leader equivocated on waits for the cluster, without being marked dead
+            blocktree
+                .store_duplicate_slot(2, 0, vec![1], vec![2])
+                .unwrap();
+            assert!(skip(&bank2, &mut progress));
+            assert!(!progress[&2].is_dead);
+            assert!(!blocktree.is_dead(2));
+
+            // Once a supermajority votes for it, the slot is replayed
+            {
+                let mut cache = block_commitment_cache.write().unwrap();
+                cache.set_total_stake(3);
+                cache.record_vote(Pubkey::new_rand(), vec![2], 1);
+                cache.record_vote(Pubkey::new_rand(), vec![2], 1);
+                cache.aggregate();
+            }
+            assert!(skip(&bank2, &mut progress));
+            block_commitment_cache
+                .write()
+                .unwrap()
+                .record_vote(Pubkey::new_rand(), vec![2], 1);
+            block_commitment_cache.write().unwrap().aggregate();
+            assert!(!skip(&bank2, &mut progress));
+        }
+        let _ignored = remove_dir_all(&ledger_path);
+    }
+
     // Given a blob and a fatal expected error, check that replaying that blob causes causes the fork to be
     // marked as dead. Returns the error for caller to verify.
     fn check_dead_fork<F>(blob_to_insert: F) -> Result<()>
@@ -989,7 +1567,12 @@ mod test {
             progress.insert(bank0.slot(), ForkProgress::new(last_blockhash));
             let blob = blob_to_insert(&last_blockhash);
             blocktree.insert_data_blobs(&[blob]).unwrap();
-            let res = ReplayStage::replay_blocktree_into_bank(&bank0, &blocktree, &mut progress);
+            let res = ReplayStage::replay_blocktree_into_bank(
+                &bank0,
+                &blocktree,
+                &mut progress,
+                &EntryVerifier::new(),
+            );
 
             // Check that the erroring bank was marked as dead in the progress map
             assert!(progress
@@ -1036,12 +1619,14 @@ mod test {
             0,
         )));
         let pubkey = Pubkey::new_rand();
-        let mut tower = Tower::new_from_forks(&bank_forks.read().unwrap(), &pubkey);
+        let mut tower = Tower::new_from_forks(&bank_forks.read().unwrap(), &pubkey, None);
+        let mut fork_choice = HeaviestSubtreeForkChoice::new(0);
         let mut progress = HashMap::new();
 
         leader_vote(&arc_bank0, &leader_voting_pubkey);
-        let votable = ReplayStage::generate_votable_banks(&bank_forks, &tower, &mut progress);
-        if let Some((_, _, lockouts)) = votable.into_iter().last() {
+        ReplayStage::add_fork_choice_votes(&arc_bank0, &tower, &mut fork_choice);
+        let (fork, _) = ReplayStage::select_forks(&bank_forks, &tower, &fork_choice, &mut progress);
+        if let Some((_, lockouts)) = fork {
             ReplayStage::update_confidence_cache(&bank_forks, &tower, lockouts, &lockouts_sender);
         }
 
@@ -1062,8 +1647,10 @@ mod test {
         bank_forks.write().unwrap().insert(bank1);
         let arc_bank1 = bank_forks.read().unwrap().get(1).unwrap().clone();
         leader_vote(&arc_bank1, &leader_voting_pubkey);
-        let votable = ReplayStage::generate_votable_banks(&bank_forks, &tower, &mut progress);
-        if let Some((_, _, lockouts)) = votable.into_iter().last() {
+        fork_choice.add_new_leaf_slot(1, Some(0));
+        ReplayStage::add_fork_choice_votes(&arc_bank1, &tower, &mut fork_choice);
+        let (fork, _) = ReplayStage::select_forks(&bank_forks, &tower, &fork_choice, &mut progress);
+        if let Some((_, lockouts)) = fork {
             ReplayStage::update_confidence_cache(&bank_forks, &tower, lockouts, &lockouts_sender);
         }
 
@@ -1078,8 +1665,10 @@ mod test {
         bank_forks.write().unwrap().insert(bank2);
         let arc_bank2 = bank_forks.read().unwrap().get(2).unwrap().clone();
         leader_vote(&arc_bank2, &leader_voting_pubkey);
-        let votable = ReplayStage::generate_votable_banks(&bank_forks, &tower, &mut progress);
-        if let Some((_, _, lockouts)) = votable.into_iter().last() {
+        fork_choice.add_new_leaf_slot(2, Some(1));
+        ReplayStage::add_fork_choice_votes(&arc_bank2, &tower, &mut fork_choice);
+        let (fork, _) = ReplayStage::select_forks(&bank_forks, &tower, &fork_choice, &mut progress);
+        if let Some((_, lockouts)) = fork {
             ReplayStage::update_confidence_cache(&bank_forks, &tower, lockouts, &lockouts_sender);
         }
         thread::sleep(Duration::from_millis(200));
@@ -1097,4 +1686,78 @@ mod test {
             &Confidence::new_with_stake_weighted(0, 1, 2, 0)
         );
     }
+
+    #[test]
+    fn test_select_forks_locked_out_of_heaviest_fork() {
+        let GenesisBlockInfo {
+            mut genesis_block,
+            mint_keypair,
+            ..
+        } = create_genesis_block(10_000);
+        genesis_block.ticks_per_slot = 4;
+        let bank0 = Bank::new(&genesis_block);
+        for _ in 1..genesis_block.ticks_per_slot {
+            bank0.register_tick(&Hash::default());
+        }
+        bank0.freeze();
+        let bank_forks = Arc::new(RwLock::new(BankForks::new_from_banks(
+            &[Arc::new(bank0)],
+            0,
+        )));
+        let mut fork_choice = HeaviestSubtreeForkChoice::new(0);
+        let add_votable_bank = |fork_choice: &mut HeaviestSubtreeForkChoice, slot, parent| {
+            let parent = bank_forks.read().unwrap().get(parent).unwrap().clone();
+            let bank = Bank::new_from_parent(&parent, &Pubkey::default(), slot);
+            bank.transfer(1, &mint_keypair, &Pubkey::new_rand())
+                .unwrap();
+            for _ in 0..genesis_block.ticks_per_slot {
+                bank.register_tick(&Hash::default());
+            }
+            bank.freeze();
+            let hash = bank.hash();
+            bank_forks.write().unwrap().insert(bank);
+            fork_choice.add_new_leaf_slot(slot, Some(parent.slot()));
+            hash
+        };
+
+        // 0
+        // ├── 1
+        // │   └── 3
+        // └── 2
+        //     └── 4
+        let mut tower =
+            Tower::new_from_forks(&bank_forks.read().unwrap(), &Pubkey::new_rand(), None);
+        for (slot, parent) in &[(1, 0), (2, 0), (3, 1), (4, 2)] {
+            let hash = add_votable_bank(&mut fork_choice, *slot, *parent);
+            if *slot % 2 == 1 {
+                tower.record_vote(*slot, hash);
+            }
+        }
+        fork_choice.add_votes(vec![(Pubkey::new_rand(), 4, 100)]);
+        assert_eq!(fork_choice.best_overall_slot(), 4);
+        assert!(tower.is_locked_out(4, &bank_forks.read().unwrap().descendants()));
+
+        // Locked out of the heaviest fork, PoH keeps building on the fork the tower voted on
+        let mut progress = HashMap::new();
+        let (vote_fork, reset_bank) =
+            ReplayStage::select_forks(&bank_forks, &tower, &fork_choice, &mut progress);
+        assert!(vote_fork.is_none());
+        assert_eq!(reset_bank.map(|bank| bank.slot()), Some(3));
+
+        // Until that fork grows a slot the tower can vote on
+        let hash = add_votable_bank(&mut fork_choice, 5, 3);
+        let (vote_fork, reset_bank) =
+            ReplayStage::select_forks(&bank_forks, &tower, &fork_choice, &mut progress);
+        assert_eq!(vote_fork.map(|(bank, _)| bank.slot()), Some(5));
+        assert_eq!(reset_bank.map(|bank| bank.slot()), Some(5));
+
+        // If the voted slot is pruned, PoH isn't reset onto the locked out heaviest fork
+        tower.record_vote(5, hash);
+        bank_forks.write().unwrap().evict(5);
+        fork_choice.remove_leaf_slot(5);
+        let (vote_fork, reset_bank) =
+            ReplayStage::select_forks(&bank_forks, &tower, &fork_choice, &mut progress);
+        assert!(vote_fork.is_none());
+        assert!(reset_bank.is_none());
+    }
 }
@@ -3,14 +3,16 @@ use crate::blocktree::Blocktree;
 use crate::chacha::{chacha_cbc_encrypt_ledger, CHACHA_BLOCK_SIZE};
 use crate::cluster_info::{ClusterInfo, Node, FULLNODE_PORT_RANGE};
 use crate::contact_info::ContactInfo;
+use crate::entry::EntrySlice;
 use crate::gossip_service::GossipService;
 use crate::packet::to_shared_blob;
 use crate::recycler::Recycler;
-use crate::repair_service::{RepairService, RepairSlotRange, RepairStrategy};
+use crate::repair_service::{OutstandingRepairs, RepairService, RepairStrategy};
 use crate::result::{Error, Result};
 use crate::service::Service;
+use crate::slot_range::SlotRange;
 use crate::storage_stage::NUM_STORAGE_SAMPLES;
-use crate::streamer::{blob_receiver, receiver, responder, BlobReceiver};
+use crate::streamer::{receiver, repair_blob_receiver, responder, BlobReceiver};
 use crate::window_service::WindowService;
 use crate::{repair_service, window_service};
 use bincode::deserialize;
@@ -33,7 +35,8 @@ use solana_sdk::transaction::Transaction;
 use solana_sdk::transport::TransportError;
 use solana_storage_api::storage_contract::StorageContract;
 use solana_storage_api::storage_instruction;
-use std::fs::File;
+use std::collections::HashMap;
+use std::fs::{self, File};
 use std::io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom};
 use std::mem::size_of;
 use std::net::{SocketAddr, UdpSocket};
@@ -46,6 +49,7 @@ use std::thread::{sleep, spawn, JoinHandle};
 use std::time::Duration;
 
 static ENCRYPTED_FILENAME: &'static str = "ledger.enc";
+static PROGRESS_FILENAME: &'static str = "segment.json";
 
 #[derive(Serialize, Deserialize)]
 pub enum ReplicatorRequest {
@@ -71,6 +75,47 @@ struct ReplicatorMeta {
     num_chacha_blocks: usize,
 }
 
+/// The segment a replicator stores, persisted in its ledger directory so that a restarted
+/// replicator resumes downloading it instead of picking a new segment and starting over
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+struct SegmentProgress {
+    slot: u64,
+    slots_per_segment: u64,
+    /// The storage blockhash the segment was picked with, which the replicator signs to make
+    /// its encryption key
+    blockhash: Hash,
+    /// How many of the segment's slots were downloaded in full
+    num_downloaded_slots: u64,
+    /// Whether the downloaded segment was verified, after which it's not downloaded again
+    sealed: bool,
+}
+
+impl SegmentProgress {
+    fn path(ledger_path: &str) -> PathBuf {
+        Path::new(ledger_path).join(PROGRESS_FILENAME)
+    }
+
+    fn load(ledger_path: &str) -> Option<Self> {
+        let file = File::open(Self::path(ledger_path)).ok()?;
+        serde_json::from_reader(BufReader::new(file))
+            .map_err(|err| warn!("Ignoring unreadable segment progress: {:?}", err))
+            .ok()
+    }
+
+    /// Writes the progress to a temporary file first, so a crash can't leave it half written
+    fn save(&self, ledger_path: &str) -> io::Result<()> {
+        let path = Self::path(ledger_path);
+        let tmp_path = path.with_extension("json.tmp");
+        let file = File::create(&tmp_path)?;
+        serde_json::to_writer(file, self).map_err(|err| io::Error::new(ErrorKind::Other, err))?;
+        fs::rename(tmp_path, path)
+    }
+
+    fn segment(&self) -> SlotRange {
+        SlotRange::with_len(self.slot, self.slots_per_segment).unwrap()
+    }
+}
+
 pub(crate) fn sample_file(in_path: &Path, sample_offsets: &[u64]) -> io::Result<Hash> {
     let in_file = File::open(in_path)?;
     let metadata = in_file.metadata()?;
@@ -106,6 +151,18 @@ pub(crate) fn sample_file(in_path: &Path, sample_offsets: &[u64]) -> io::Result<
     Ok(hasher.result())
 }
 
+/// The offsets, in chacha blocks, to sample an encrypted segment of `num_chacha_blocks` blocks
+/// at for a proof seeded with `blockhash`. Validators sample at the same offsets to check a
+/// replicator's proof.
+pub(crate) fn sampling_offsets(blockhash: &Hash, num_chacha_blocks: usize) -> Vec<u64> {
+    let mut rng_seed = [0u8; 32];
+    rng_seed.copy_from_slice(&blockhash.as_ref());
+    let mut rng = ChaChaRng::from_seed(rng_seed);
+    (0..NUM_STORAGE_SAMPLES)
+        .map(|_| rng.gen_range(0, num_chacha_blocks) as u64)
+        .collect()
+}
+
 fn get_slot_from_signature(
     signature: &ed25519_dalek::Signature,
     storage_turn: u64,
@@ -250,11 +307,15 @@ impl Replicator {
         };
 
         let repair_socket = Arc::new(node.sockets.repair);
-        let mut blob_sockets: Vec<Arc<UdpSocket>> =
+        let blob_sockets: Vec<Arc<UdpSocket>> =
             node.sockets.tvu.into_iter().map(Arc::new).collect();
-        blob_sockets.push(repair_socket.clone());
         let (blob_fetch_sender, blob_fetch_receiver) = channel();
-        let fetch_stage = BlobFetchStage::new_multi_socket(blob_sockets, &blob_fetch_sender, &exit);
+        let fetch_stage = BlobFetchStage::new_with_repair_socket(
+            blob_sockets,
+            repair_socket.clone(),
+            &blob_fetch_sender,
+            &exit,
+        );
         let (slot_sender, slot_receiver) = channel();
         let request_processor =
             create_request_processor(node.sockets.storage.unwrap(), &exit, slot_receiver);
@@ -426,31 +487,49 @@ impl Replicator {
                 return Err(e);
             }
         };
-        let (segment_blockhash, segment_slot) = match Self::poll_for_segment(
-            &cluster_info,
-            slots_per_segment,
-            &Hash::default(),
-            exit,
-        ) {
-            Ok(blockhash_and_slot) => blockhash_and_slot,
-            Err(e) => {
-                //shutdown services before exiting
-                exit.store(true, Ordering::Relaxed);
-                return Err(e);
+        let mut progress = match SegmentProgress::load(&meta.ledger_path) {
+            Some(progress) if progress.slots_per_segment == slots_per_segment => {
+                info!(
+                    "resuming segment at slot {}: {} of {} slots downloaded",
+                    progress.slot, progress.num_downloaded_slots, slots_per_segment
+                );
+                progress
+            }
+            _ => {
+                let (segment_blockhash, segment_slot) = match Self::poll_for_segment(
+                    &cluster_info,
+                    slots_per_segment,
+                    &Hash::default(),
+                    exit,
+                ) {
+                    Ok(blockhash_and_slot) => blockhash_and_slot,
+                    Err(e) => {
+                        //shutdown services before exiting
+                        exit.store(true, Ordering::Relaxed);
+                        return Err(e);
+                    }
+                };
+                let signature = storage_keypair.sign(segment_blockhash.as_ref());
+                SegmentProgress {
+                    slot: get_slot_from_signature(&signature, segment_slot, slots_per_segment),
+                    slots_per_segment,
+                    blockhash: segment_blockhash,
+                    ..SegmentProgress::default()
+                }
             }
         };
-        let signature = storage_keypair.sign(segment_blockhash.as_ref());
-        let slot = get_slot_from_signature(&signature, segment_slot, slots_per_segment);
-        info!("replicating slot: {}", slot);
-        slot_sender.send(slot)?;
-        meta.slot = slot;
+        progress.save(&meta.ledger_path)?;
+        let signature = storage_keypair.sign(progress.blockhash.as_ref());
+        info!("replicating slot: {}", progress.slot);
+        slot_sender.send(progress.slot)?;
+        meta.slot = progress.slot;
         meta.slots_per_segment = slots_per_segment;
         meta.signature = Signature::new(&signature.to_bytes());
-        meta.blockhash = segment_blockhash;
+        meta.blockhash = progress.blockhash;
 
-        let mut repair_slot_range = RepairSlotRange::default();
-        repair_slot_range.end = slot + slots_per_segment;
-        repair_slot_range.start = slot;
+        // The segment is `slots_per_segment` slots, so its last slot is one short of the next
+        // segment's first
+        let repair_slot_range = progress.segment();
 
         let (retransmit_sender, _) = channel();
 
@@ -466,8 +545,8 @@ impl Replicator {
         );
         info!("waiting for ledger download");
         Self::wait_for_segment_download(
-            slot,
-            slots_per_segment,
+            &mut progress,
+            &meta.ledger_path,
             &blocktree,
             &exit,
             &node_info,
@@ -476,9 +555,12 @@ impl Replicator {
         Ok(window_service)
     }
 
+    /// Waits until the segment is downloaded and verified, recording the progress so that a
+    /// restart picks up where the download left off. Slots that fail verification are purged
+    /// to be downloaded again.
     fn wait_for_segment_download(
-        start_slot: u64,
-        slots_per_segment: u64,
+        progress: &mut SegmentProgress,
+        ledger_path: &str,
         blocktree: &Arc<Blocktree>,
         exit: &Arc<AtomicBool>,
         node_info: &ContactInfo,
@@ -486,18 +568,36 @@ impl Replicator {
     ) {
         info!(
             "window created, waiting for ledger download starting at slot {:?}",
-            start_slot
+            progress.slot
         );
-        let mut current_slot = start_slot;
-        'outer: loop {
-            while blocktree.is_full(current_slot) {
-                current_slot += 1;
-                info!("current slot: {}", current_slot);
-                if current_slot >= start_slot + slots_per_segment {
-                    break 'outer;
+        let segment = progress.segment();
+        while !progress.sealed {
+            let num_downloaded_slots = segment
+                .iter()
+                .filter(|slot| blocktree.is_full(*slot))
+                .count() as u64;
+            if num_downloaded_slots != progress.num_downloaded_slots {
+                info!(
+                    "downloaded {} of {} slots",
+                    num_downloaded_slots,
+                    segment.num_slots()
+                );
+                progress.num_downloaded_slots = num_downloaded_slots;
+            }
+            if num_downloaded_slots == segment.num_slots() {
+                match Self::verify_segment(&segment, blocktree) {
+                    Ok(()) => progress.sealed = true,
+                    Err(slot) => {
+                        warn!("segment failed verification at slot {}", slot);
+                        blocktree.purge_slots(slot, Some(segment.end));
+                        progress.num_downloaded_slots = slot - segment.start;
+                    }
                 }
             }
-            if exit.load(Ordering::Relaxed) {
+            progress
+                .save(ledger_path)
+                .unwrap_or_else(|err| warn!("unable to save segment progress: {:?}", err));
+            if progress.sealed || exit.load(Ordering::Relaxed) {
                 break;
             }
             sleep(Duration::from_secs(1));
@@ -515,6 +615,34 @@ impl Replicator {
         }
     }
 
+    /// Checks that the entries of each slot of `segment` chain from the last entry of the slot's
+    /// parent, returning the first slot that doesn't. Nothing before the segment is downloaded,
+    /// so slots whose parent is outside it are only checked from their own first entry.
+    fn verify_segment(segment: &SlotRange, blocktree: &Blocktree) -> result::Result<(), u64> {
+        let mut last_hashes = HashMap::new();
+        for slot in segment.iter() {
+            let parent_slot = match blocktree.meta(slot) {
+                Ok(Some(meta)) => meta.parent_slot,
+                _ => return Err(slot),
+            };
+            let entries = blocktree
+                .get_slot_entries(slot, 0, None)
+                .map_err(|_| slot)?;
+            let (start_hash, entries) = match last_hashes.get(&parent_slot) {
+                Some(last_hash) if parent_slot != slot => (*last_hash, &entries[..]),
+                _ => match entries.split_first() {
+                    Some((first, rest)) => (first.hash, rest),
+                    None => return Err(slot),
+                },
+            };
+            if !entries.verify(&start_hash) {
+                return Err(slot);
+            }
+            last_hashes.insert(slot, entries.last().map_or(start_hash, |entry| entry.hash));
+        }
+        Ok(())
+    }
+
     fn encrypt_ledger(meta: &mut ReplicatorMeta, blocktree: &Arc<Blocktree>) -> Result<()> {
         let ledger_path = Path::new(&meta.ledger_path);
         meta.ledger_data_file_encrypted = ledger_path.join(ENCRYPTED_FILENAME);
@@ -542,14 +670,7 @@ impl Replicator {
     }
 
     fn create_sampling_offsets(meta: &mut ReplicatorMeta) {
-        meta.sampling_offsets.clear();
-        let mut rng_seed = [0u8; 32];
-        rng_seed.copy_from_slice(&meta.blockhash.as_ref());
-        let mut rng = ChaChaRng::from_seed(rng_seed);
-        for _ in 0..NUM_STORAGE_SAMPLES {
-            meta.sampling_offsets
-                .push(rng.gen_range(0, meta.num_chacha_blocks) as u64);
-        }
+        meta.sampling_offsets = sampling_offsets(&meta.blockhash, meta.num_chacha_blocks);
     }
 
     fn sample_file_to_create_mining_hash(
@@ -795,17 +916,15 @@ impl Replicator {
         let exit = Arc::new(AtomicBool::new(false));
         let (s_reader, r_reader) = channel();
         let repair_socket = Arc::new(bind_in_range(FULLNODE_PORT_RANGE).unwrap().1);
-        let t_receiver = blob_receiver(repair_socket.clone(), &exit, s_reader);
+        let t_receiver = repair_blob_receiver(repair_socket.clone(), &exit, s_reader);
+        let outstanding_repairs = RwLock::new(OutstandingRepairs::default());
         let id = cluster_info.read().unwrap().id();
         info!(
             "Sending repair requests from: {} to: {}",
             cluster_info.read().unwrap().my_data().id,
             replicator_info.gossip
         );
-        let repair_slot_range = RepairSlotRange {
-            start: start_slot,
-            end: start_slot + slots_per_segment,
-        };
+        let repair_slot_range = SlotRange::with_len(start_slot, slots_per_segment).unwrap();
         // try for upto 180 seconds //TODO needs tuning if segments are huge
         for _ in 0..120 {
             // Strategy used by replicators
@@ -816,13 +935,19 @@ impl Replicator {
             );
             //iter over the repairs and send them
             if let Ok(repairs) = repairs {
+                let now = timestamp();
+                outstanding_repairs.write().unwrap().purge(now);
                 let reqs: Vec<_> = repairs
                     .into_iter()
                     .filter_map(|repair_request| {
+                        let nonce = outstanding_repairs
+                            .write()
+                            .unwrap()
+                            .add_request(repair_request, now);
                         cluster_info
                             .read()
                             .unwrap()
-                            .map_repair_request(&repair_request)
+                            .map_repair_request(&repair_request, &replicator_info.id, nonce)
                             .map(|result| ((replicator_info.gossip, result), repair_request))
                             .ok()
                     })
@@ -848,10 +973,11 @@ impl Replicator {
             }
             let res = r_reader.recv_timeout(Duration::new(1, 0));
             if let Ok(blobs) = res {
+                let blobs = window_service::filter_repair_responses(blobs, &outstanding_repairs);
                 window_service::process_blobs(&blobs, blocktree)?;
             }
             // check if all the slots in the segment are complete
-            if Self::segment_complete(start_slot, slots_per_segment, blocktree) {
+            if Self::segment_complete(&repair_slot_range, blocktree) {
                 break;
             }
             sleep(Duration::from_millis(500));
@@ -860,7 +986,7 @@ impl Replicator {
         t_receiver.join().unwrap();
 
         // check if all the slots in the segment are complete
-        if !Self::segment_complete(start_slot, slots_per_segment, blocktree) {
+        if !Self::segment_complete(&repair_slot_range, blocktree) {
             Err(io::Error::new(
                 ErrorKind::Other,
                 "Unable to download the full segment",
@@ -869,17 +995,8 @@ impl Replicator {
         Ok(start_slot)
     }
 
-    fn segment_complete(
-        start_slot: u64,
-        slots_per_segment: u64,
-        blocktree: &Arc<Blocktree>,
-    ) -> bool {
-        for slot in start_slot..(start_slot + slots_per_segment) {
-            if !blocktree.is_full(slot) {
-                return false;
-            }
-        }
-        true
+    fn segment_complete(segment: &SlotRange, blocktree: &Arc<Blocktree>) -> bool {
+        segment.iter().all(|slot| blocktree.is_full(slot))
     }
 
     fn get_replicator_segment_slot(to: SocketAddr) -> u64 {
@@ -905,6 +1022,9 @@ impl Replicator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::blocktree::get_tmp_ledger_path;
+    use crate::entry::create_ticks;
+    use solana_sdk::hash::hash;
     use std::fs::{create_dir_all, remove_file};
     use std::io::Write;
 
@@ -922,6 +1042,77 @@ mod tests {
         path
     }
 
+    #[test]
+    fn test_segment_progress() {
+        let ledger_path = tmp_file_path("test_segment_progress");
+        create_dir_all(&ledger_path).unwrap();
+        let ledger_path = ledger_path.to_str().unwrap();
+        assert_eq!(SegmentProgress::load(ledger_path), None);
+
+        let progress = SegmentProgress {
+            slot: 16,
+            slots_per_segment: 8,
+            blockhash: hash(b"segment"),
+            num_downloaded_slots: 3,
+            sealed: false,
+        };
+        progress.save(ledger_path).unwrap();
+        assert_eq!(SegmentProgress::load(ledger_path), Some(progress));
+        assert_eq!(
+            SegmentProgress::load(ledger_path).unwrap().segment(),
+            SlotRange::new(16, 23).unwrap()
+        );
+
+        fs::write(SegmentProgress::path(ledger_path), "garbage").unwrap();
+        assert_eq!(SegmentProgress::load(ledger_path), None);
+        fs::remove_dir_all(ledger_path).unwrap();
+    }
+
+    #[test]
+    fn test_verify_segment() {
+        let ledger_path = get_tmp_ledger_path!();
+        let blocktree = Blocktree::open(&ledger_path).unwrap();
+        let ticks_per_slot = 4;
+        let segment = SlotRange::new(0, 3).unwrap();
+
+        // Slots 0 and 1 chain, slot 2 doesn't chain from its parent, slot 1
+        let ticks = create_ticks(ticks_per_slot * 2, Hash::default());
+        blocktree
+            .write_entries(0, 0, 0, ticks_per_slot, &ticks)
+            .unwrap();
+        assert_eq!(
+            Replicator::verify_segment(&SlotRange::new(0, 1).unwrap(), &blocktree),
+            Ok(())
+        );
+        let bad_ticks = create_ticks(ticks_per_slot * 2, hash(b"fork"));
+        blocktree
+            .write_entries(2, 0, 0, ticks_per_slot, &bad_ticks)
+            .unwrap();
+        assert_eq!(Replicator::verify_segment(&segment, &blocktree), Err(2));
+
+        // Once slot 2 is replaced by one that chains, the segment verifies
+        blocktree.purge_slots(2, Some(segment.end));
+        let last_hash = ticks.last().unwrap().hash;
+        blocktree
+            .write_entries(
+                2,
+                0,
+                0,
+                ticks_per_slot,
+                &create_ticks(ticks_per_slot * 2, last_hash),
+            )
+            .unwrap();
+        assert_eq!(Replicator::verify_segment(&segment, &blocktree), Ok(()));
+        // A segment starting after its first slot's parent trusts that slot's first entry
+        assert_eq!(
+            Replicator::verify_segment(&SlotRange::new(1, 3).unwrap(), &blocktree),
+            Ok(())
+        );
+
+        drop(blocktree);
+        Blocktree::destroy(&ledger_path).unwrap();
+    }
+
     #[test]
     fn test_sample_file() {
         solana_logger::setup();
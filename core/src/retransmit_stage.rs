@@ -4,7 +4,7 @@ use crate::bank_forks::BankForks;
 use crate::blocktree::{Blocktree, CompletedSlotsReceiver};
 use crate::cluster_info::{compute_retransmit_peers, ClusterInfo, DATA_PLANE_FANOUT};
 use crate::leader_schedule_cache::LeaderScheduleCache;
-use crate::repair_service::RepairStrategy;
+use crate::repair_service::{RepairStrategy, RpcRepair};
 use crate::result::{Error, Result};
 use crate::service::Service;
 use crate::staking_utils;
@@ -123,6 +123,7 @@ impl RetransmitStage {
         exit: &Arc<AtomicBool>,
         completed_slots_receiver: CompletedSlotsReceiver,
         epoch_schedule: EpochSchedule,
+        rpc_repair: Option<RpcRepair>,
     ) -> Self {
         let (retransmit_sender, retransmit_receiver) = channel();
 
@@ -138,6 +139,7 @@ impl RetransmitStage {
             bank_forks,
             completed_slots_receiver,
             epoch_schedule,
+            rpc_repair,
         };
         let leader_schedule_cache = leader_schedule_cache.clone();
         let window_service = WindowService::new(
@@ -1,31 +1,76 @@
 //! The `rpc` module implements the Solana RPC interface.
 
 use crate::bank_forks::BankForks;
+use crate::block_commitment::BlockCommitmentCache;
+use crate::blocktree::Blocktree;
 use crate::cluster_info::ClusterInfo;
 use crate::contact_info::ContactInfo;
+use crate::ledger_storage::{
+    self, ConfirmedBlock, FileLedgerStorage, LedgerStorage, TransactionWithStatus,
+};
 use crate::packet::PACKET_DATA_SIZE;
+use crate::slot_range::SlotRange;
+use crate::staking_utils;
 use crate::storage_stage::StorageState;
 use bincode::{deserialize, serialize};
 use jsonrpc_core::{Error, Metadata, Result};
 use jsonrpc_derive::rpc;
 use solana_drone::drone::request_airdrop_transaction;
 use solana_runtime::bank::Bank;
+use solana_runtime::message_processor::InstructionTraceEntry;
 use solana_sdk::account::Account;
 use solana_sdk::fee_calculator::FeeCalculator;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signature;
-use solana_sdk::transaction::{self, Transaction};
-use solana_vote_api::vote_state::VoteState;
+use solana_sdk::timing::duration_as_ms;
+use solana_sdk::transaction::{
+    self, SanitizedTransaction, Transaction, TransactionError, TransactionReturnData,
+    TransactionStatusMeta,
+};
+use solana_stake_api::stake_state::StakeState;
+use solana_vote_api::vote_state::{VoteState, MAX_LOCKOUT_HISTORY};
+use std::collections::HashMap;
+use std::fmt;
 use std::net::{SocketAddr, UdpSocket};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread::sleep;
 use std::time::{Duration, Instant};
 
+/// Most blobs that one `getBlobs` request can ask for
+pub const MAX_GET_BLOBS_RANGE: u64 = 128;
+
+/// Most slots that one `getConfirmedSignaturesForAddress` request can search
+pub const MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE: u64 = 10_000;
+
+/// Most signatures that one `getConfirmedSignaturesForAddress` request can return
+pub const MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_LIMIT: usize = 1_000;
+
+/// A node whose root is more than this many slots below the highest root recently gossiped
+/// by another node is behind
+pub const HEALTH_CHECK_SLOT_DISTANCE: u64 = 150;
+
+/// Roots gossiped longer ago than this aren't compared against
+pub const HEALTH_GOSSIP_FRESHNESS_MS: u64 = 60_000;
+
+/// A node whose working bank hasn't advanced for this long is stalled
+pub const HEALTH_SLOT_STALL_MS: u64 = 60_000;
+
+/// A validator whose last vote is more than this many slots behind the bank is delinquent
+pub const DELINQUENT_VALIDATOR_SLOT_DISTANCE: u64 = 128;
+
+/// A validator whose root is more than this many slots behind the bank is delinquent, which
+/// allows for the votes it can have outstanding past its root
+pub const DELINQUENT_VALIDATOR_ROOT_DISTANCE: u64 =
+    DELINQUENT_VALIDATOR_SLOT_DISTANCE + MAX_LOCKOUT_HISTORY as u64 + 1;
+
 #[derive(Debug, Clone)]
 pub struct JsonRpcConfig {
     pub enable_fullnode_exit: bool, // Enable the 'fullnodeExit' command
     pub drone_addr: Option<SocketAddr>,
+    /// Directory of the long-term ledger storage to serve blocks and transactions from once
+    /// the blocktree no longer has them
+    pub ledger_storage_path: Option<String>,
 }
 
 impl Default for JsonRpcConfig {
@@ -33,6 +78,7 @@ impl Default for JsonRpcConfig {
         Self {
             enable_fullnode_exit: false,
             drone_addr: None,
+            ledger_storage_path: None,
         }
     }
 }
@@ -40,9 +86,14 @@ impl Default for JsonRpcConfig {
 #[derive(Clone)]
 pub struct JsonRpcRequestProcessor {
     bank_forks: Arc<RwLock<BankForks>>,
+    block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+    blocktree: Arc<Blocktree>,
+    ledger_storage: Option<Arc<dyn LedgerStorage>>,
     storage_state: StorageState,
     config: JsonRpcConfig,
     fullnode_exit: Arc<AtomicBool>,
+    /// The working bank's slot when health was last checked, and when that slot was first seen
+    slot_progress: Arc<Mutex<(u64, Instant)>>,
 }
 
 impl JsonRpcRequestProcessor {
@@ -54,13 +105,24 @@ impl JsonRpcRequestProcessor {
         storage_state: StorageState,
         config: JsonRpcConfig,
         bank_forks: Arc<RwLock<BankForks>>,
+        block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+        blocktree: Arc<Blocktree>,
         fullnode_exit: &Arc<AtomicBool>,
     ) -> Self {
+        let slot = bank_forks.read().unwrap().working_bank().slot();
+        let ledger_storage = config
+            .ledger_storage_path
+            .as_ref()
+            .map(|path| Arc::new(FileLedgerStorage::new(path)) as Arc<dyn LedgerStorage>);
         JsonRpcRequestProcessor {
             bank_forks,
+            block_commitment_cache,
+            blocktree,
+            ledger_storage,
             storage_state,
             config,
             fullnode_exit: fullnode_exit.clone(),
+            slot_progress: Arc::new(Mutex::new((slot, Instant::now()))),
         }
     }
 
@@ -105,10 +167,196 @@ impl JsonRpcRequestProcessor {
         self.bank().get_signature_confirmation_status(&signature)
     }
 
+    /// Execute `tx` against the working bank without committing it
+    pub fn simulate_transaction(&self, tx: &SanitizedTransaction) -> RpcSimulatedTransaction {
+        let (result, return_data, instruction_trace) = self.bank().simulate_transaction(tx);
+        RpcSimulatedTransaction {
+            err: result.err(),
+            return_data: return_data.map(RpcReturnData::from),
+            instruction_trace: instruction_trace
+                .into_iter()
+                .map(RpcInstructionTraceEntry::from)
+                .collect(),
+        }
+    }
+
+    /// The transaction with `signature`, read from the blocktree while the bank or the
+    /// blocktree's signature index still knows of it, otherwise from the long-term storage
+    pub fn get_confirmed_transaction(
+        &self,
+        signature: Signature,
+        encoding: RpcTransactionEncoding,
+    ) -> Option<RpcConfirmedTransaction> {
+        let bank = self.bank();
+        if let Some((slot, status)) = bank.get_signature_slot(&signature) {
+            if let Some(transaction) = self.get_slot_transaction(slot, &signature) {
+                // Banks that are gone from the fork persisted their metas to the blocktree
+                let meta = bank.get_transaction_status_meta(&signature).or_else(|| {
+                    self.blocktree
+                        .get_transaction_status(slot, &signature)
+                        .unwrap_or_else(|err| {
+                            warn!("failed to read the status of {}: {:?}", signature, err);
+                            None
+                        })
+                });
+                return Some(RpcConfirmedTransaction {
+                    slot,
+                    transaction: RpcEncodedTransaction::encode(&transaction, encoding),
+                    status,
+                    meta,
+                    return_data: bank.get_return_data(&signature).map(RpcReturnData::from),
+                });
+            }
+        }
+        // Older transactions are found through the blocktree's index while it still has their slot,
+        // neither it nor the long-term storage keeps return data
+        if let Some(transaction) = self.get_rooted_transaction(&signature, encoding) {
+            return Some(transaction);
+        }
+        let ledger_storage = self.ledger_storage.as_ref()?;
+        let block = ledger_storage::get_transaction_status(&**ledger_storage, &signature)
+            .and_then(|status| match status {
+                Some((slot, _)) => ledger_storage::get_block(&**ledger_storage, slot),
+                None => Ok(None),
+            })
+            .unwrap_or_else(|err| {
+                warn!("failed to read transaction {}: {:?}", signature, err);
+                None
+            })?;
+        let slot = block.slot;
+        let TransactionWithStatus { transaction, meta } = block
+            .transactions
+            .into_iter()
+            .find(|transaction| transaction.transaction.signatures[0] == signature)?;
+        let meta = meta?;
+        Some(RpcConfirmedTransaction {
+            slot,
+            transaction: RpcEncodedTransaction::encode(&transaction, encoding),
+            status: meta.status.clone(),
+            meta: Some(meta),
+            return_data: None,
+        })
+    }
+
+    /// The transaction with `signature` from the rooted slot the blocktree indexed it in
+    fn get_rooted_transaction(
+        &self,
+        signature: &Signature,
+        encoding: RpcTransactionEncoding,
+    ) -> Option<RpcConfirmedTransaction> {
+        let slot = self
+            .blocktree
+            .get_rooted_signature_slot(signature)
+            .unwrap_or_else(|err| {
+                warn!("failed to find the slot of {}: {:?}", signature, err);
+                None
+            })?;
+        let transaction = self.get_slot_transaction(slot, signature)?;
+        let meta = self
+            .blocktree
+            .get_transaction_status(slot, signature)
+            .unwrap_or_else(|err| {
+                warn!("failed to read the status of {}: {:?}", signature, err);
+                None
+            })?;
+        Some(RpcConfirmedTransaction {
+            slot,
+            transaction: RpcEncodedTransaction::encode(&transaction, encoding),
+            status: meta.status.clone(),
+            meta: Some(meta),
+            return_data: None,
+        })
+    }
+
+    /// The transaction with `signature` in the entries the blocktree has of `slot`
+    fn get_slot_transaction(&self, slot: u64, signature: &Signature) -> Option<Transaction> {
+        let entries = self
+            .blocktree
+            .get_slot_entries(slot, 0, None)
+            .map_err(|err| warn!("failed to read the entries of slot {}: {:?}", slot, err))
+            .ok()?;
+        entries
+            .into_iter()
+            .flat_map(|entry| entry.transactions)
+            .find(|transaction| transaction.signatures[0] == *signature)
+    }
+
+    /// The rooted block of `slot`, from the blocktree if it still has the slot, otherwise from
+    /// the long-term storage
+    pub fn get_confirmed_block(&self, slot: u64) -> Result<Option<RpcConfirmedBlock>> {
+        if slot >= self.blocktree.lowest_slot() && self.blocktree.is_root(slot) {
+            let block = ConfirmedBlock::from_blocktree(&self.blocktree, slot)
+                .map_err(|_| Error::internal_error())?;
+            if let Some(block) = block {
+                return Ok(Some(block.into()));
+            }
+        }
+        match &self.ledger_storage {
+            Some(ledger_storage) => Ok(ledger_storage::get_block(&**ledger_storage, slot)
+                .map_err(|_| Error::internal_error())?
+                .map(RpcConfirmedBlock::from)),
+            None => Ok(None),
+        }
+    }
+
+    /// The signatures of the transactions that used `address` in the rooted slots from
+    /// `start_slot` to `end_slot`, following `after` and at most `limit` of them. Longer
+    /// histories are paged through a range of slots at a time.
+    pub fn get_confirmed_signatures_for_address(
+        &self,
+        address: Pubkey,
+        start_slot: u64,
+        end_slot: u64,
+        after: Option<Signature>,
+        limit: usize,
+    ) -> Result<Vec<Signature>> {
+        let slots = SlotRange::new(start_slot, end_slot).ok_or_else(Error::invalid_request)?;
+        if slots.num_slots() > MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE
+            || limit > MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_LIMIT
+        {
+            return Err(Error::invalid_request());
+        }
+        self.blocktree
+            .get_confirmed_signatures_for_address(&address, start_slot, end_slot, after, limit)
+            .map_err(|_| Error::internal_error())
+    }
+
+    /// The stake that has been seen voting for the block of `slot`
+    pub fn get_block_commitment(&self, slot: u64) -> RpcBlockCommitment {
+        let block_commitment_cache = self.block_commitment_cache.read().unwrap();
+        RpcBlockCommitment {
+            commitment: block_commitment_cache
+                .get_block_commitment(slot)
+                .map(|commitment| commitment.commitment().to_vec()),
+            total_stake: block_commitment_cache.total_stake(),
+        }
+    }
+
     fn get_slot(&self) -> Result<u64> {
         Ok(self.bank().slot())
     }
 
+    fn minimum_ledger_slot(&self) -> Result<u64> {
+        Ok(self.blocktree.lowest_slot())
+    }
+
+    fn get_blobs(&self, slot: u64, start_index: u64, end_index: u64) -> Result<Vec<Vec<u8>>> {
+        let indexes = match SlotRange::new_exclusive(start_index, end_index) {
+            Some(indexes) if indexes.num_slots() <= MAX_GET_BLOBS_RANGE => indexes,
+            None if start_index == end_index => return Ok(vec![]),
+            _ => return Err(Error::invalid_request()),
+        };
+        let mut blobs = vec![];
+        for index in indexes.iter() {
+            let blob = self
+                .blocktree
+                .get_data_blob_bytes(slot, index)
+                .map_err(|_| Error::internal_error())?;
+            blobs.extend(blob);
+        }
+        Ok(blobs)
+    }
+
     fn get_slot_leader(&self) -> Result<String> {
         Ok(self.bank().collector_id().to_string())
     }
@@ -139,6 +387,140 @@ impl JsonRpcRequestProcessor {
             .collect::<Vec<_>>())
     }
 
+    /// The vote accounts with activated stake or a stake in the current epoch, split into those
+    /// that are keeping up with the bank and those that are delinquent
+    fn get_vote_accounts(&self) -> Result<RpcVoteAccountStatus> {
+        let bank = self.bank();
+        let slot = bank.slot();
+        let epoch_vote_accounts = bank
+            .epoch_vote_accounts(bank.get_epoch_and_slot_index(slot).0)
+            .ok_or_else(Error::invalid_request)?;
+        let (mut current, mut delinquent): (Vec<_>, Vec<_>) = bank
+            .vote_accounts()
+            .into_iter()
+            .filter(|(vote_pubkey, (activated_stake, _))| {
+                *activated_stake > 0 || epoch_vote_accounts.contains_key(vote_pubkey)
+            })
+            .filter_map(|(vote_pubkey, (activated_stake, account))| {
+                let vote_state = VoteState::from(&account)?;
+                Some(RpcVoteAccount {
+                    vote_pubkey: vote_pubkey.to_string(),
+                    node_pubkey: vote_state.node_pubkey.to_string(),
+                    activated_stake,
+                    commission: vote_state.commission,
+                    epoch_vote_account: epoch_vote_accounts.contains_key(&vote_pubkey),
+                    epoch_credits: vote_state.epoch_credits().cloned().collect(),
+                    last_vote: vote_state.votes.back().map_or(0, |vote| vote.slot),
+                    root_slot: vote_state.root_slot,
+                })
+            })
+            .partition(|vote_account| !vote_account.is_delinquent(slot));
+        current.sort_by(|a, b| b.activated_stake.cmp(&a.activated_stake));
+        delinquent.sort_by(|a, b| b.activated_stake.cmp(&a.activated_stake));
+        Ok(RpcVoteAccountStatus {
+            current,
+            delinquent,
+        })
+    }
+
+    /// The inflation schedule and the rates it sets for the current epoch
+    fn get_inflation(&self) -> Result<RpcInflation> {
+        let bank = self.bank();
+        let inflation = bank.inflation();
+        let epoch = bank.epoch();
+        // The same point in the schedule the epoch's rewards are computed from
+        let year =
+            bank.epoch_schedule().get_last_slot_in_epoch(epoch) as f64 / bank.slots_per_year();
+        Ok(RpcInflation {
+            initial: inflation.initial,
+            terminal: inflation.terminal,
+            taper: inflation.taper,
+            foundation: inflation.foundation,
+            foundation_term: inflation.foundation_term,
+            grant: inflation.grant,
+            grant_term: inflation.grant_term,
+            storage: inflation.storage,
+            epoch,
+            total_rate: inflation.total(year),
+            validator_rate: inflation.validator(year),
+            foundation_rate: inflation.foundation(year),
+            grant_rate: inflation.grant(year),
+            storage_rate: inflation.storage(year),
+        })
+    }
+
+    /// How much of the stake in `pubkey` is active, warming up and cooling down in `epoch`, the
+    /// current epoch if None
+    pub fn get_stake_activation(
+        &self,
+        pubkey: &Pubkey,
+        epoch: Option<u64>,
+    ) -> Result<RpcStakeActivation> {
+        let bank = self.bank();
+        let epoch = epoch.unwrap_or_else(|| bank.epoch());
+        let stake = bank
+            .get_account(pubkey)
+            .and_then(|account| StakeState::stake_from(&account))
+            .ok_or_else(Error::invalid_request)?;
+        let (active, activating, deactivating) = stake.stake_activating_and_deactivating(epoch);
+        let state = if deactivating > 0 {
+            StakeActivationState::Deactivating
+        } else if activating > 0 {
+            StakeActivationState::Activating
+        } else if active > 0 {
+            StakeActivationState::Active
+        } else {
+            StakeActivationState::Inactive
+        };
+        Ok(RpcStakeActivation {
+            state,
+            active,
+            activating,
+            deactivating,
+        })
+    }
+
+    /// The inflation rewards each of `pubkeys` was paid in the rooted slots of `epoch`, as
+    /// recorded when they were paid, or None for those that weren't paid any
+    pub fn get_inflation_reward(
+        &self,
+        pubkeys: &[Pubkey],
+        epoch: u64,
+    ) -> Result<Vec<Option<RpcInflationReward>>> {
+        let bank = self.bank();
+        if epoch > bank.epoch() {
+            return Err(Error::invalid_request());
+        }
+        let epoch_schedule = bank.epoch_schedule();
+        let rewards = self
+            .blocktree
+            .get_rooted_rewards(
+                epoch_schedule.get_first_slot_in_epoch(epoch),
+                epoch_schedule.get_last_slot_in_epoch(epoch),
+            )
+            .map_err(|_| Error::internal_error())?;
+        Ok(pubkeys
+            .iter()
+            .map(|pubkey| {
+                rewards
+                    .iter()
+                    .filter(|(_, address, _)| address == pubkey)
+                    .fold(
+                        None,
+                        |paid: Option<RpcInflationReward>, (slot, _, reward)| {
+                            Some(RpcInflationReward {
+                                epoch,
+                                effective_slot: *slot,
+                                amount: paid.map_or(0, |paid| paid.amount) + reward.lamports,
+                                post_balance: reward.post_balance,
+                                commission: reward.commission,
+                            })
+                        },
+                    )
+            })
+            .collect())
+    }
+
     fn get_storage_turn_rate(&self) -> Result<u64> {
         Ok(self.storage_state.get_storage_turn_rate())
     }
@@ -160,6 +542,43 @@ impl JsonRpcRequestProcessor {
             .get_pubkeys_for_slot(slot, &self.bank_forks))
     }
 
+    /// Whether this node keeps up with the cluster: its working bank must keep advancing, and
+    /// its root must be near the highest root recently gossiped by a third of the stake
+    pub fn get_health(&self, cluster_info: &ClusterInfo) -> RpcHealth {
+        let bank = self.bank();
+        let slot = bank.slot();
+        let root = self.bank_forks.read().unwrap().root();
+        let ms_since_slot_progress = {
+            let mut slot_progress = self.slot_progress.lock().unwrap();
+            if slot_progress.0 != slot {
+                *slot_progress = (slot, Instant::now());
+            }
+            duration_as_ms(&slot_progress.1.elapsed())
+        };
+        let peer_roots = cluster_info.get_recent_peer_roots(HEALTH_GOSSIP_FRESHNESS_MS);
+        let cluster_root = stake_weighted_root(&peer_roots, &staking_utils::staked_nodes(&bank));
+
+        let status = if ms_since_slot_progress > HEALTH_SLOT_STALL_MS {
+            RpcHealthStatus::Stalled
+        } else {
+            match cluster_root {
+                None => RpcHealthStatus::Unknown,
+                Some(cluster_root) if cluster_root > root + HEALTH_CHECK_SLOT_DISTANCE => {
+                    RpcHealthStatus::Behind
+                }
+                Some(_) => RpcHealthStatus::Ok,
+            }
+        };
+        RpcHealth {
+            status,
+            slot,
+            root,
+            cluster_root,
+            fresh_peers: peer_roots.len(),
+            ms_since_slot_progress,
+        }
+    }
+
     pub fn fullnode_exit(&self) -> Result<bool> {
         if self.config.enable_fullnode_exit {
             warn!("fullnode_exit request...");
@@ -172,6 +591,29 @@ impl JsonRpcRequestProcessor {
     }
 }
 
+/// The highest root reached by peers that hold over a third of the stake, so nodes without
+/// stake can't make this node look behind by claiming a root far ahead
+fn stake_weighted_root(
+    peer_roots: &[(Pubkey, u64)],
+    staked_nodes: &HashMap<Pubkey, u64>,
+) -> Option<u64> {
+    let total_stake: u64 = staked_nodes.values().sum();
+    let mut staked_roots: Vec<_> = peer_roots
+        .iter()
+        .filter_map(|(id, root)| staked_nodes.get(id).map(|stake| (*root, *stake)))
+        .collect();
+    staked_roots.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut stake = 0;
+    for (root, node_stake) in staked_roots {
+        stake += node_stake;
+        if stake > total_stake / 3 {
+            return Some(root);
+        }
+    }
+    None
+}
+
 fn get_tpu_addr(cluster_info: &Arc<RwLock<ClusterInfo>>) -> Result<SocketAddr> {
     let contact_info = cluster_info.read().unwrap().my_data();
     Ok(contact_info.tpu)
@@ -204,6 +646,309 @@ pub struct RpcContactInfo {
     pub rpc: Option<SocketAddr>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcDuplicateSlotProof {
+    /// Pubkey of the node that gossiped the proof, as a base-58 string
+    pub reporter: String,
+
+    /// The slot whose leader signed conflicting blobs
+    pub slot: u64,
+
+    /// Index of the conflicting blobs within the slot
+    pub index: u64,
+
+    /// The two conflicting blob payloads, including their headers
+    pub blob1: Vec<u8>,
+    pub blob2: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcHealthStatus {
+    Ok,
+    /// The node's root is far below the cluster's
+    Behind,
+    /// The node's working bank stopped advancing
+    Stalled,
+    /// No other node gossiped its root recently, so the node can't tell if it's behind
+    Unknown,
+}
+
+impl RpcHealthStatus {
+    /// Whether a liveness check should pass. A node that can't compare itself with the cluster,
+    /// such as the only node of a cluster, passes as long as it makes progress.
+    pub fn is_healthy(self) -> bool {
+        match self {
+            RpcHealthStatus::Ok | RpcHealthStatus::Unknown => true,
+            RpcHealthStatus::Behind | RpcHealthStatus::Stalled => false,
+        }
+    }
+}
+
+impl fmt::Display for RpcHealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            RpcHealthStatus::Ok => "ok",
+            RpcHealthStatus::Behind => "behind",
+            RpcHealthStatus::Stalled => "stalled",
+            RpcHealthStatus::Unknown => "unknown",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcHealth {
+    pub status: RpcHealthStatus,
+
+    /// Slot of the node's working bank
+    pub slot: u64,
+
+    /// The node's root
+    pub root: u64,
+
+    /// The highest root recently gossiped by other nodes holding over a third of the stake, if any
+    pub cluster_root: Option<u64>,
+
+    /// Number of other nodes that gossiped their root recently
+    pub fresh_peers: usize,
+
+    /// Milliseconds since the node's working bank last advanced, as of the previous check
+    pub ms_since_slot_progress: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcReturnData {
+    /// Program that set the data, as a base-58 string
+    pub program_id: String,
+
+    pub data: Vec<u8>,
+}
+
+impl From<TransactionReturnData> for RpcReturnData {
+    fn from(return_data: TransactionReturnData) -> Self {
+        Self {
+            program_id: return_data.program_id.to_string(),
+            data: return_data.data,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcInstructionTraceEntry {
+    /// Index of the transaction instruction the program was invoked for
+    pub instruction_index: u8,
+
+    /// 1 for the outermost loader, increasing through the loaders to the instruction's program
+    pub depth: u8,
+
+    /// Program invoked, as a base-58 string
+    pub program_id: String,
+}
+
+impl From<InstructionTraceEntry> for RpcInstructionTraceEntry {
+    fn from(entry: InstructionTraceEntry) -> Self {
+        Self {
+            instruction_index: entry.instruction_index,
+            depth: entry.depth,
+            program_id: entry.program_id.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSimulatedTransaction {
+    /// Why the transaction would fail, or None if it would succeed
+    pub err: Option<TransactionError>,
+
+    /// Data the transaction's programs returned, if any
+    pub return_data: Option<RpcReturnData>,
+
+    /// The programs the transaction invoked, up to where it failed
+    pub instruction_trace: Vec<RpcInstructionTraceEntry>,
+}
+
+/// How transactions are encoded in RPC responses
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcTransactionEncoding {
+    /// As a JSON object, with its keys, hashes and instruction data base-58 encoded
+    Json,
+    /// As its base-58 encoded binary form, the way it's sent to the cluster
+    Binary,
+}
+
+impl Default for RpcTransactionEncoding {
+    fn default() -> Self {
+        RpcTransactionEncoding::Json
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum RpcEncodedTransaction {
+    Binary(String),
+    Json(RpcTransaction),
+}
+
+impl RpcEncodedTransaction {
+    pub fn encode(transaction: &Transaction, encoding: RpcTransactionEncoding) -> Self {
+        match encoding {
+            RpcTransactionEncoding::Json => RpcEncodedTransaction::Json(transaction.into()),
+            RpcTransactionEncoding::Binary => RpcEncodedTransaction::Binary(
+                bs58::encode(serialize(transaction).unwrap()).into_string(),
+            ),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTransaction {
+    /// The transaction's signatures, as base-58 encoded strings
+    pub signatures: Vec<String>,
+
+    pub message: RpcMessage,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcMessage {
+    pub num_required_signatures: u8,
+
+    pub num_credit_only_signed_accounts: u8,
+
+    pub num_credit_only_unsigned_accounts: u8,
+
+    /// The accounts the transaction uses, as base-58 encoded strings
+    pub account_keys: Vec<String>,
+
+    /// The blockhash the transaction was signed with, as base-58 encoded string
+    pub recent_blockhash: String,
+
+    pub instructions: Vec<RpcCompiledInstruction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcCompiledInstruction {
+    /// Index of the program in the message's account keys
+    pub program_id_index: u8,
+
+    /// Indexes of the instruction's accounts in the message's account keys
+    pub accounts: Vec<u8>,
+
+    /// The instruction's data, as base-58 encoded string
+    pub data: String,
+}
+
+impl From<&Transaction> for RpcTransaction {
+    fn from(transaction: &Transaction) -> Self {
+        let message = transaction.message();
+        let header = &message.header;
+        Self {
+            signatures: transaction
+                .signatures
+                .iter()
+                .map(|signature| signature.to_string())
+                .collect(),
+            message: RpcMessage {
+                num_required_signatures: header.num_required_signatures,
+                num_credit_only_signed_accounts: header.num_credit_only_signed_accounts,
+                num_credit_only_unsigned_accounts: header.num_credit_only_unsigned_accounts,
+                account_keys: message
+                    .account_keys
+                    .iter()
+                    .map(|pubkey| pubkey.to_string())
+                    .collect(),
+                recent_blockhash: message.recent_blockhash.to_string(),
+                instructions: message
+                    .instructions
+                    .iter()
+                    .map(|instruction| RpcCompiledInstruction {
+                        program_id_index: instruction.program_id_index,
+                        accounts: instruction.accounts.clone(),
+                        data: bs58::encode(&instruction.data).into_string(),
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcConfirmedTransaction {
+    /// The slot of the block the transaction is in
+    pub slot: u64,
+
+    pub transaction: RpcEncodedTransaction,
+
+    /// The result of processing the transaction
+    pub status: transaction::Result<()>,
+
+    /// The transaction's fee and the balances of its accounts before and after it, if recorded
+    pub meta: Option<TransactionStatusMeta>,
+
+    /// Data the transaction's programs returned, if any
+    pub return_data: Option<RpcReturnData>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcConfirmedBlock {
+    pub slot: u64,
+
+    pub parent_slot: u64,
+
+    /// The hash of the block's last entry, as base-58 encoded string
+    pub blockhash: String,
+
+    /// The hash of the parent block's last entry, as base-58 encoded string
+    pub previous_blockhash: String,
+
+    /// The block's transactions, each with its status metadata if it was recorded
+    pub transactions: Vec<TransactionWithStatus>,
+}
+
+impl From<ConfirmedBlock> for RpcConfirmedBlock {
+    fn from(block: ConfirmedBlock) -> Self {
+        Self {
+            slot: block.slot,
+            parent_slot: block.parent_slot,
+            blockhash: block.blockhash.to_string(),
+            previous_blockhash: block.previous_blockhash.to_string(),
+            transactions: block.transactions,
+        }
+    }
+}
+
+/// Pages through the results of `getConfirmedSignaturesForAddress`
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSignaturesForAddressConfig {
+    /// Only return the signatures following this one, as base-58 encoded string
+    pub after: Option<String>,
+    /// The most signatures to return, `MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_LIMIT` if unset
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlockCommitment {
+    /// The stake that voted for the block, by the number of confirmations the votes gave it,
+    /// starting from one confirmation. `None` if no staked votes for the block were seen.
+    pub commitment: Option<Vec<u64>>,
+
+    /// The stake of every staked vote account in the current epoch
+    pub total_stake: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcVoteAccountInfo {
@@ -220,6 +965,161 @@ pub struct RpcVoteAccountInfo {
     pub commission: u8,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcVoteAccount {
+    /// Vote account pubkey as base-58 encoded string
+    pub vote_pubkey: String,
+
+    /// The pubkey of the node that votes using this account
+    pub node_pubkey: String,
+
+    /// The stake, in lamports, delegated to this vote account and active in the current epoch
+    pub activated_stake: u64,
+
+    /// An 8-bit integer used as a fraction (commission/MAX_U8) for rewards payout
+    pub commission: u8,
+
+    /// Whether the vote account is staked for the current epoch
+    pub epoch_vote_account: bool,
+
+    /// The credits earned by the end of each of the latest epochs, as (epoch, credits,
+    /// previous credits)
+    pub epoch_credits: Vec<(u64, u64, u64)>,
+
+    /// The most recent slot voted on by this vote account, 0 if it hasn't voted
+    pub last_vote: u64,
+
+    /// The root slot of this vote account, if it has one
+    pub root_slot: Option<u64>,
+}
+
+impl RpcVoteAccount {
+    /// Whether the validator has fallen too far behind a bank at `slot`
+    pub fn is_delinquent(&self, slot: u64) -> bool {
+        slot.saturating_sub(self.last_vote) > DELINQUENT_VALIDATOR_SLOT_DISTANCE
+            || slot.saturating_sub(self.root_slot.unwrap_or(0)) > DELINQUENT_VALIDATOR_ROOT_DISTANCE
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcVoteAccountStatus {
+    /// Vote accounts that are keeping up with the bank, by descending stake
+    pub current: Vec<RpcVoteAccount>,
+
+    /// Vote accounts that have fallen behind, by descending stake
+    pub delinquent: Vec<RpcVoteAccount>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcInflation {
+    /// Initial inflation percentage, from time=0
+    pub initial: f64,
+
+    /// Terminal inflation percentage, to time=INF
+    pub terminal: f64,
+
+    /// Rate per year at which inflation is lowered until reaching terminal
+    pub taper: f64,
+
+    /// Percentage of total inflation allocated to the foundation
+    pub foundation: f64,
+
+    /// Duration of foundation pool inflation, in years
+    pub foundation_term: f64,
+
+    /// Percentage of total inflation allocated to grant pools
+    pub grant: f64,
+
+    /// Duration of grant pool inflation, in years
+    pub grant_term: f64,
+
+    /// Percentage of total inflation allocated to storage rewards
+    pub storage: f64,
+
+    /// The epoch the rates below apply to
+    pub epoch: u64,
+
+    /// The total inflation rate for the epoch, per year
+    pub total_rate: f64,
+
+    /// The portion of the total rate paid to validators and their stakers
+    pub validator_rate: f64,
+
+    /// The portion of the total rate paid to the foundation
+    pub foundation_rate: f64,
+
+    /// The portion of the total rate paid to grant pools
+    pub grant_rate: f64,
+
+    /// The portion of the total rate paid to storage miners
+    pub storage_rate: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum StakeActivationState {
+    /// Some of the stake is still warming up
+    Activating,
+    /// All of the stake is effective
+    Active,
+    /// The stake is cooling down after being deactivated
+    Deactivating,
+    /// None of the stake is effective
+    Inactive,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcStakeActivation {
+    pub state: StakeActivationState,
+
+    /// The stake, in lamports, that is effective in the epoch
+    pub active: u64,
+
+    /// The stake, in lamports, that is still warming up
+    pub activating: u64,
+
+    /// The effective stake, in lamports, that is cooling down
+    pub deactivating: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcInflationReward {
+    /// The epoch the reward was paid in
+    pub epoch: u64,
+
+    /// The slot the reward was last paid in
+    pub effective_slot: u64,
+
+    /// The reward, in lamports, after the vote account's commission
+    pub amount: u64,
+
+    /// The balance of the account, in lamports, once paid
+    pub post_balance: u64,
+
+    /// The commission of the vote account the credits were redeemed from
+    pub commission: u8,
+}
+
+/// How long a stage of this node's pipeline takes to process a batch, in microseconds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcStageLatency {
+    pub stage: String,
+
+    /// The number of batches the stage has processed since the node started
+    pub count: u64,
+
+    /// The quantiles of the latencies of the most recent batches
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcEpochInfo {
@@ -249,15 +1149,67 @@ pub trait RpcSol {
     #[rpc(meta, name = "getBalance")]
     fn get_balance(&self, _: Self::Metadata, _: String) -> Result<u64>;
 
+    #[rpc(meta, name = "getBlobs")]
+    fn get_blobs(&self, _: Self::Metadata, _: u64, _: u64, _: u64) -> Result<Vec<Vec<u8>>>;
+
+    #[rpc(meta, name = "getBlockCommitment")]
+    fn get_block_commitment(&self, _: Self::Metadata, _: u64) -> Result<RpcBlockCommitment>;
+
+    #[rpc(meta, name = "getConfirmedBlock")]
+    fn get_confirmed_block(&self, _: Self::Metadata, _: u64) -> Result<Option<RpcConfirmedBlock>>;
+
+    #[rpc(meta, name = "getConfirmedSignaturesForAddress")]
+    fn get_confirmed_signatures_for_address(
+        &self,
+        _: Self::Metadata,
+        _: String,
+        _: u64,
+        _: u64,
+        _: Option<RpcSignaturesForAddressConfig>,
+    ) -> Result<Vec<String>>;
+
+    #[rpc(meta, name = "getConfirmedTransaction")]
+    fn get_confirmed_transaction(
+        &self,
+        _: Self::Metadata,
+        _: String,
+        _: Option<RpcTransactionEncoding>,
+    ) -> Result<Option<RpcConfirmedTransaction>>;
+
     #[rpc(meta, name = "getClusterNodes")]
     fn get_cluster_nodes(&self, _: Self::Metadata) -> Result<Vec<RpcContactInfo>>;
 
+    #[rpc(meta, name = "getDuplicateSlotProofs")]
+    fn get_duplicate_slot_proofs(&self, _: Self::Metadata) -> Result<Vec<RpcDuplicateSlotProof>>;
+
     #[rpc(meta, name = "getEpochInfo")]
     fn get_epoch_info(&self, _: Self::Metadata) -> Result<RpcEpochInfo>;
 
+    #[rpc(meta, name = "getHealth")]
+    fn get_health(&self, _: Self::Metadata) -> Result<RpcHealth>;
+
+    #[rpc(meta, name = "getInflation")]
+    fn get_inflation(&self, _: Self::Metadata) -> Result<RpcInflation>;
+
+    #[rpc(meta, name = "getInflationReward")]
+    fn get_inflation_reward(
+        &self,
+        _: Self::Metadata,
+        _: Vec<String>,
+        _: u64,
+    ) -> Result<Vec<Option<RpcInflationReward>>>;
+
     #[rpc(meta, name = "getLeaderSchedule")]
     fn get_leader_schedule(&self, _: Self::Metadata) -> Result<Option<Vec<String>>>;
 
+    #[rpc(meta, name = "getStakeActivation")]
+    fn get_stake_activation(
+        &self,
+        _: Self::Metadata,
+        _: String,
+        _: Option<u64>,
+    ) -> Result<RpcStakeActivation>;
+
     #[rpc(meta, name = "getRecentBlockhash")]
     fn get_recent_blockhash(&self, _: Self::Metadata) -> Result<(String, FeeCalculator)>;
 
@@ -271,24 +1223,40 @@ pub trait RpcSol {
     #[rpc(meta, name = "getSlot")]
     fn get_slot(&self, _: Self::Metadata) -> Result<u64>;
 
+    #[rpc(meta, name = "minimumLedgerSlot")]
+    fn minimum_ledger_slot(&self, _: Self::Metadata) -> Result<u64>;
+
     #[rpc(meta, name = "getTransactionCount")]
     fn get_transaction_count(&self, _: Self::Metadata) -> Result<u64>;
 
     #[rpc(meta, name = "getTotalSupply")]
     fn get_total_supply(&self, _: Self::Metadata) -> Result<u64>;
 
+    #[rpc(meta, name = "getPipelineLatencies")]
+    fn get_pipeline_latencies(&self, _: Self::Metadata) -> Result<Vec<RpcStageLatency>>;
+
     #[rpc(meta, name = "requestAirdrop")]
     fn request_airdrop(&self, _: Self::Metadata, _: String, _: u64) -> Result<String>;
 
     #[rpc(meta, name = "sendTransaction")]
     fn send_transaction(&self, _: Self::Metadata, _: Vec<u8>) -> Result<String>;
 
+    #[rpc(meta, name = "simulateTransaction")]
+    fn simulate_transaction(
+        &self,
+        _: Self::Metadata,
+        _: Vec<u8>,
+    ) -> Result<RpcSimulatedTransaction>;
+
     #[rpc(meta, name = "getSlotLeader")]
     fn get_slot_leader(&self, _: Self::Metadata) -> Result<String>;
 
     #[rpc(meta, name = "getEpochVoteAccounts")]
     fn get_epoch_vote_accounts(&self, _: Self::Metadata) -> Result<Vec<RpcVoteAccountInfo>>;
 
+    #[rpc(meta, name = "getVoteAccounts")]
+    fn get_vote_accounts(&self, _: Self::Metadata) -> Result<RpcVoteAccountStatus>;
+
     #[rpc(meta, name = "getStorageTurnRate")]
     fn get_storage_turn_rate(&self, _: Self::Metadata) -> Result<u64>;
 
@@ -388,6 +1356,93 @@ impl RpcSol for RpcSolImpl {
             .collect())
     }
 
+    fn get_block_commitment(&self, meta: Self::Metadata, slot: u64) -> Result<RpcBlockCommitment> {
+        debug!("get_block_commitment rpc request received: {:?}", slot);
+        Ok(meta
+            .request_processor
+            .read()
+            .unwrap()
+            .get_block_commitment(slot))
+    }
+
+    fn get_confirmed_block(
+        &self,
+        meta: Self::Metadata,
+        slot: u64,
+    ) -> Result<Option<RpcConfirmedBlock>> {
+        debug!("get_confirmed_block rpc request received: {:?}", slot);
+        meta.request_processor
+            .read()
+            .unwrap()
+            .get_confirmed_block(slot)
+    }
+
+    fn get_confirmed_signatures_for_address(
+        &self,
+        meta: Self::Metadata,
+        address: String,
+        start_slot: u64,
+        end_slot: u64,
+        config: Option<RpcSignaturesForAddressConfig>,
+    ) -> Result<Vec<String>> {
+        debug!(
+            "get_confirmed_signatures_for_address rpc request received: {:?} {}-{} {:?}",
+            address, start_slot, end_slot, config
+        );
+        let address = verify_pubkey(address)?;
+        let config = config.unwrap_or_default();
+        let after = config
+            .after
+            .as_ref()
+            .map(|after| verify_signature(after))
+            .transpose()?;
+        let limit = config
+            .limit
+            .unwrap_or(MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_LIMIT);
+        let signatures = meta
+            .request_processor
+            .read()
+            .unwrap()
+            .get_confirmed_signatures_for_address(address, start_slot, end_slot, after, limit)?;
+        Ok(signatures
+            .iter()
+            .map(|signature| signature.to_string())
+            .collect())
+    }
+
+    fn get_confirmed_transaction(
+        &self,
+        meta: Self::Metadata,
+        id: String,
+        encoding: Option<RpcTransactionEncoding>,
+    ) -> Result<Option<RpcConfirmedTransaction>> {
+        debug!("get_confirmed_transaction rpc request received: {:?}", id);
+        let signature = verify_signature(&id)?;
+        Ok(meta
+            .request_processor
+            .read()
+            .unwrap()
+            .get_confirmed_transaction(signature, encoding.unwrap_or_default()))
+    }
+
+    fn get_duplicate_slot_proofs(
+        &self,
+        meta: Self::Metadata,
+    ) -> Result<Vec<RpcDuplicateSlotProof>> {
+        let cluster_info = meta.cluster_info.read().unwrap();
+        Ok(cluster_info
+            .get_duplicate_slot_proofs()
+            .into_iter()
+            .map(|(reporter, slot, proof)| RpcDuplicateSlotProof {
+                reporter: reporter.to_string(),
+                slot,
+                index: proof.index,
+                blob1: proof.blob1,
+                blob2: proof.blob2,
+            })
+            .collect())
+    }
+
     fn get_epoch_info(&self, meta: Self::Metadata) -> Result<RpcEpochInfo> {
         let bank = meta.request_processor.read().unwrap().bank();
         let epoch_schedule = bank.epoch_schedule();
@@ -399,6 +1454,41 @@ impl RpcSol for RpcSolImpl {
         })
     }
 
+    fn get_inflation(&self, meta: Self::Metadata) -> Result<RpcInflation> {
+        meta.request_processor.read().unwrap().get_inflation()
+    }
+
+    fn get_inflation_reward(
+        &self,
+        meta: Self::Metadata,
+        ids: Vec<String>,
+        epoch: u64,
+    ) -> Result<Vec<Option<RpcInflationReward>>> {
+        debug!("get_inflation_reward rpc request received: {:?}", ids);
+        let pubkeys = ids
+            .into_iter()
+            .map(verify_pubkey)
+            .collect::<Result<Vec<_>>>()?;
+        meta.request_processor
+            .read()
+            .unwrap()
+            .get_inflation_reward(&pubkeys, epoch)
+    }
+
+    fn get_stake_activation(
+        &self,
+        meta: Self::Metadata,
+        id: String,
+        epoch: Option<u64>,
+    ) -> Result<RpcStakeActivation> {
+        debug!("get_stake_activation rpc request received: {:?}", id);
+        let pubkey = verify_pubkey(id)?;
+        meta.request_processor
+            .read()
+            .unwrap()
+            .get_stake_activation(&pubkey, epoch)
+    }
+
     fn get_leader_schedule(&self, meta: Self::Metadata) -> Result<Option<Vec<String>>> {
         let bank = meta.request_processor.read().unwrap().bank();
         Ok(
@@ -436,6 +1526,23 @@ impl RpcSol for RpcSolImpl {
         meta.request_processor.read().unwrap().get_slot()
     }
 
+    fn minimum_ledger_slot(&self, meta: Self::Metadata) -> Result<u64> {
+        meta.request_processor.read().unwrap().minimum_ledger_slot()
+    }
+
+    fn get_blobs(
+        &self,
+        meta: Self::Metadata,
+        slot: u64,
+        start_index: u64,
+        end_index: u64,
+    ) -> Result<Vec<Vec<u8>>> {
+        meta.request_processor
+            .read()
+            .unwrap()
+            .get_blobs(slot, start_index, end_index)
+    }
+
     fn get_num_blocks_since_signature_confirmation(
         &self,
         meta: Self::Metadata,
@@ -472,15 +1579,31 @@ impl RpcSol for RpcSolImpl {
         meta.request_processor.read().unwrap().get_total_supply()
     }
 
+    fn get_pipeline_latencies(&self, _meta: Self::Metadata) -> Result<Vec<RpcStageLatency>> {
+        debug!("get_pipeline_latencies rpc request received");
+        Ok(solana_metrics::stage_latencies()
+            .into_iter()
+            .map(|latency| RpcStageLatency {
+                stage: latency.stage,
+                count: latency.count,
+                p50: latency.p50_us,
+                p90: latency.p90_us,
+                p99: latency.p99_us,
+            })
+            .collect())
+    }
+
     fn request_airdrop(&self, meta: Self::Metadata, id: String, lamports: u64) -> Result<String> {
         trace!("request_airdrop id={} lamports={}", id, lamports);
 
+        // Fall back to a drone discovered through gossip
         let drone_addr = meta
             .request_processor
             .read()
             .unwrap()
             .config
             .drone_addr
+            .or_else(|| meta.cluster_info.read().unwrap().get_faucet_addr())
             .ok_or_else(Error::invalid_request)?;
         let pubkey = verify_pubkey(id)?;
 
@@ -495,7 +1618,7 @@ impl RpcSol for RpcSolImpl {
             .map_err(|err| {
                 info!("request_airdrop_transaction failed: {:?}", err);
                 Error::internal_error()
-            })?;;
+            })?;
 
         let data = serialize(&transaction).map_err(|err| {
             info!("request_airdrop: serialize error: {:?}", err);
@@ -537,6 +1660,11 @@ impl RpcSol for RpcSolImpl {
             info!("send_transaction: deserialize error: {:?}", err);
             Error::invalid_request()
         })?;
+        // Malformed transactions would only be dropped by the TPU
+        let tx = SanitizedTransaction::try_new(tx).map_err(|err| {
+            info!("send_transaction: malformed transaction: {:?}", err);
+            Error::invalid_request()
+        })?;
         if data.len() >= PACKET_DATA_SIZE {
             info!(
                 "send_transaction: transaction too large: {} bytes (max: {} bytes)",
@@ -563,6 +1691,26 @@ impl RpcSol for RpcSolImpl {
         Ok(signature)
     }
 
+    fn simulate_transaction(
+        &self,
+        meta: Self::Metadata,
+        data: Vec<u8>,
+    ) -> Result<RpcSimulatedTransaction> {
+        let tx: Transaction = deserialize(&data).map_err(|err| {
+            info!("simulate_transaction: deserialize error: {:?}", err);
+            Error::invalid_request()
+        })?;
+        let tx = SanitizedTransaction::try_new(tx).map_err(|err| {
+            info!("simulate_transaction: malformed transaction: {:?}", err);
+            Error::invalid_request()
+        })?;
+        Ok(meta
+            .request_processor
+            .read()
+            .unwrap()
+            .simulate_transaction(&tx))
+    }
+
     fn get_slot_leader(&self, meta: Self::Metadata) -> Result<String> {
         meta.request_processor.read().unwrap().get_slot_leader()
     }
@@ -574,6 +1722,10 @@ impl RpcSol for RpcSolImpl {
             .get_epoch_vote_accounts()
     }
 
+    fn get_vote_accounts(&self, meta: Self::Metadata) -> Result<RpcVoteAccountStatus> {
+        meta.request_processor.read().unwrap().get_vote_accounts()
+    }
+
     fn get_storage_turn_rate(&self, meta: Self::Metadata) -> Result<u64> {
         meta.request_processor
             .read()
@@ -599,6 +1751,14 @@ impl RpcSol for RpcSolImpl {
             .get_storage_pubkeys_for_slot(slot)
     }
 
+    fn get_health(&self, meta: Self::Metadata) -> Result<RpcHealth> {
+        Ok(meta
+            .request_processor
+            .read()
+            .unwrap()
+            .get_health(&meta.cluster_info.read().unwrap()))
+    }
+
     fn fullnode_exit(&self, meta: Self::Metadata) -> Result<bool> {
         meta.request_processor.read().unwrap().fullnode_exit()
     }
@@ -607,14 +1767,22 @@ impl RpcSol for RpcSolImpl {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::blocktree::tests::make_many_slot_entries;
+    use crate::blocktree::{get_tmp_ledger_path, DuplicateSlotProof};
     use crate::contact_info::ContactInfo;
+    use crate::crds_value::{CrdsValue, EpochSlots};
+    use crate::entry::Entry;
     use crate::genesis_utils::{create_genesis_block, GenesisBlockInfo};
     use jsonrpc_core::{MetaIoHandler, Output, Response, Value};
+    use solana_runtime::bank::RewardInfo;
     use solana_sdk::hash::{hash, Hash};
     use solana_sdk::instruction::InstructionError;
     use solana_sdk::signature::{Keypair, KeypairUtil};
+    use solana_sdk::system_program;
     use solana_sdk::system_transaction;
+    use solana_sdk::timing::timestamp;
     use solana_sdk::transaction::TransactionError;
+    use std::collections::BTreeSet;
     use std::thread;
 
     const TEST_MINT_LAMPORTS: u64 = 10_000;
@@ -638,6 +1806,8 @@ mod tests {
             StorageState::default(),
             JsonRpcConfig::default(),
             bank_forks,
+            Arc::new(RwLock::new(BlockCommitmentCache::default())),
+            new_blocktree(),
             &exit,
         )));
         let cluster_info = Arc::new(RwLock::new(ClusterInfo::new_with_invalid_keypair(
@@ -672,6 +1842,8 @@ mod tests {
             StorageState::default(),
             JsonRpcConfig::default(),
             bank_forks,
+            Arc::new(RwLock::new(BlockCommitmentCache::default())),
+            new_blocktree(),
             &exit,
         );
         thread::spawn(move || {
@@ -694,50 +1866,437 @@ mod tests {
             r#"{{"jsonrpc":"2.0","id":1,"method":"getBalance","params":["{}"]}}"#,
             bob_pubkey
         );
-        let res = io.handle_request_sync(&req, meta);
-        let expected = format!(r#"{{"jsonrpc":"2.0","result":20,"id":1}}"#);
-        let expected: Response =
-            serde_json::from_str(&expected).expect("expected response deserialization");
-        let result: Response = serde_json::from_str(&res.expect("actual response"))
-            .expect("actual response deserialization");
-        assert_eq!(expected, result);
+        let res = io.handle_request_sync(&req, meta);
+        let expected = format!(r#"{{"jsonrpc":"2.0","result":20,"id":1}}"#);
+        let expected: Response =
+            serde_json::from_str(&expected).expect("expected response deserialization");
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_rpc_get_cluster_nodes() {
+        let bob_pubkey = Pubkey::new_rand();
+        let (io, meta, _bank, _blockhash, _alice, leader_pubkey) =
+            start_rpc_handler_with_tx(&bob_pubkey);
+
+        let req = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"getClusterNodes"}}"#);
+        let res = io.handle_request_sync(&req, meta);
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+
+        let expected = format!(
+            r#"{{"jsonrpc":"2.0","result":[{{"pubkey": "{}", "gossip": "127.0.0.1:1235", "tpu": "127.0.0.1:1234", "rpc": "127.0.0.1:8899"}}],"id":1}}"#,
+            leader_pubkey,
+        );
+
+        let expected: Response =
+            serde_json::from_str(&expected).expect("expected response deserialization");
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_rpc_get_duplicate_slot_proofs() {
+        let bob_pubkey = Pubkey::new_rand();
+        let (io, meta, _bank, _blockhash, _alice, _leader_pubkey) =
+            start_rpc_handler_with_tx(&bob_pubkey);
+        let reporter = meta.cluster_info.read().unwrap().id();
+
+        let proof = DuplicateSlotProof {
+            index: 2,
+            blob1: vec![1, 2],
+            blob2: vec![3, 4],
+        };
+        meta.cluster_info
+            .write()
+            .unwrap()
+            .push_duplicate_slot(5, &proof);
+
+        let req = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"getDuplicateSlotProofs"}}"#);
+        let res = io.handle_request_sync(&req, meta);
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+
+        let expected = format!(
+            r#"{{"jsonrpc":"2.0","result":[{{"reporter": "{}", "slot": 5, "index": 2, "blob1": [1, 2], "blob2": [3, 4]}}],"id":1}}"#,
+            reporter,
+        );
+        let expected: Response =
+            serde_json::from_str(&expected).expect("expected response deserialization");
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_rpc_get_slot_leader() {
+        let bob_pubkey = Pubkey::new_rand();
+        let (io, meta, _bank, _blockhash, _alice, leader_pubkey) =
+            start_rpc_handler_with_tx(&bob_pubkey);
+
+        let req = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"getSlotLeader"}}"#);
+        let res = io.handle_request_sync(&req, meta);
+        let expected = format!(r#"{{"jsonrpc":"2.0","result":"{}","id":1}}"#, leader_pubkey);
+        let expected: Response =
+            serde_json::from_str(&expected).expect("expected response deserialization");
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_rpc_get_health() {
+        let bob_pubkey = Pubkey::new_rand();
+        let (io, meta, _bank, _blockhash, _alice, leader_pubkey) =
+            start_rpc_handler_with_tx(&bob_pubkey);
+        let get_health = |meta| {
+            let req = r#"{"jsonrpc":"2.0","id":1,"method":"getHealth"}"#;
+            let res = io.handle_request_sync(&req, meta);
+            let result: Response = serde_json::from_str(&res.expect("actual response"))
+                .expect("actual response deserialization");
+            match result {
+                Response::Single(Output::Success(success)) => {
+                    serde_json::from_value::<RpcHealth>(success.result).unwrap()
+                }
+                _ => panic!("Expected success"),
+            }
+        };
+
+        // No other node has gossiped its root
+        let health = get_health(meta.clone());
+        assert_eq!(health.status, RpcHealthStatus::Unknown);
+        assert!(health.status.is_healthy());
+        assert_eq!(health.cluster_root, None);
+        assert_eq!(health.fresh_peers, 0);
+
+        let cluster_root = health.root + HEALTH_CHECK_SLOT_DISTANCE + 1;
+        let gossip_root = |from: Pubkey, root: u64| {
+            let value =
+                CrdsValue::EpochSlots(EpochSlots::new(from, root, BTreeSet::new(), timestamp()));
+            meta.cluster_info
+                .write()
+                .unwrap()
+                .gossip
+                .crds
+                .insert(value, timestamp())
+                .unwrap();
+        };
+
+        // A node without stake can't make this one look behind
+        gossip_root(Pubkey::new_rand(), std::u64::MAX);
+        let health = get_health(meta.clone());
+        assert_eq!(health.status, RpcHealthStatus::Unknown);
+        assert_eq!(health.cluster_root, None);
+        assert_eq!(health.fresh_peers, 1);
+
+        // The leader holds all the stake
+        gossip_root(leader_pubkey, cluster_root);
+        let health = get_health(meta);
+        assert_eq!(health.status, RpcHealthStatus::Behind);
+        assert!(!health.status.is_healthy());
+        assert_eq!(health.cluster_root, Some(cluster_root));
+        assert_eq!(health.fresh_peers, 2);
+        assert_eq!(health.status.to_string(), "behind");
+    }
+
+    #[test]
+    fn test_stake_weighted_root() {
+        let staked: Vec<_> = (0..3).map(|_| Pubkey::new_rand()).collect();
+        let staked_nodes: HashMap<_, _> = staked
+            .iter()
+            .zip(&[10, 20, 70])
+            .map(|(id, stake)| (*id, *stake))
+            .collect();
+
+        assert_eq!(stake_weighted_root(&[], &staked_nodes), None);
+        // A third of the stake or less isn't enough
+        assert_eq!(
+            stake_weighted_root(
+                &[(staked[0], 5), (staked[1], 4), (Pubkey::new_rand(), 9)],
+                &staked_nodes
+            ),
+            None
+        );
+        assert_eq!(
+            stake_weighted_root(
+                &[(staked[0], 5), (staked[1], 4), (staked[2], 3)],
+                &staked_nodes
+            ),
+            Some(3)
+        );
+        assert_eq!(
+            stake_weighted_root(&[(staked[0], 5), (staked[2], 3)], &staked_nodes),
+            Some(3)
+        );
+        assert_eq!(
+            stake_weighted_root(&[(staked[1], 4), (staked[2], 5)], &staked_nodes),
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn test_rpc_minimum_ledger_slot() {
+        let exit = Arc::new(AtomicBool::new(false));
+        let blocktree = new_blocktree();
+        let (blobs, _) = make_many_slot_entries(0, 10, 1);
+        blocktree.write_blobs(blobs).unwrap();
+        blocktree.purge_slots(0, Some(4));
+        let request_processor = JsonRpcRequestProcessor::new(
+            StorageState::default(),
+            JsonRpcConfig::default(),
+            new_bank_forks().0,
+            Arc::new(RwLock::new(BlockCommitmentCache::default())),
+            blocktree,
+            &exit,
+        );
+        assert_eq!(request_processor.minimum_ledger_slot(), Ok(5));
+    }
+
+    #[test]
+    fn test_rpc_get_blobs() {
+        let exit = Arc::new(AtomicBool::new(false));
+        let blocktree = new_blocktree();
+        let (blobs, _) = make_many_slot_entries(1, 1, 4);
+        let num_blobs = blobs.len() as u64;
+        blocktree.write_blobs(blobs).unwrap();
+        let expected: Vec<_> = (0..num_blobs)
+            .map(|index| blocktree.get_data_blob_bytes(1, index).unwrap().unwrap())
+            .collect();
+        let request_processor = JsonRpcRequestProcessor::new(
+            StorageState::default(),
+            JsonRpcConfig::default(),
+            new_bank_forks().0,
+            Arc::new(RwLock::new(BlockCommitmentCache::default())),
+            blocktree,
+            &exit,
+        );
+
+        // Blobs past the end of the slot are left out
+        assert_eq!(
+            request_processor.get_blobs(1, 0, num_blobs + 2),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            request_processor.get_blobs(1, 1, 2),
+            Ok(expected[1..2].to_vec())
+        );
+        assert_eq!(request_processor.get_blobs(2, 0, 4), Ok(vec![]));
+
+        assert!(request_processor.get_blobs(1, 2, 1).is_err());
+        assert!(request_processor
+            .get_blobs(1, 0, MAX_GET_BLOBS_RANGE + 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_rpc_get_vote_accounts() {
+        let GenesisBlockInfo {
+            genesis_block,
+            voting_keypair,
+            ..
+        } = create_genesis_block(TEST_MINT_LAMPORTS);
+        let bank = Arc::new(Bank::new(&genesis_block));
+        let vote_pubkey = voting_keypair.pubkey();
+        let exit = Arc::new(AtomicBool::new(false));
+        let new_request_processor = |bank: Arc<Bank>| {
+            JsonRpcRequestProcessor::new(
+                StorageState::default(),
+                JsonRpcConfig::default(),
+                Arc::new(RwLock::new(BankForks::new(bank.slot(), bank))),
+                Arc::new(RwLock::new(BlockCommitmentCache::default())),
+                new_blocktree(),
+                &exit,
+            )
+        };
+
+        // The bootstrap leader is current until its votes fall behind
+        let vote_accounts = new_request_processor(bank.clone())
+            .get_vote_accounts()
+            .unwrap();
+        assert_eq!(vote_accounts.current.len(), 1);
+        assert!(vote_accounts.delinquent.is_empty());
+        let vote_account = &vote_accounts.current[0];
+        assert_eq!(vote_account.vote_pubkey, vote_pubkey.to_string());
+        assert!(vote_account.activated_stake > 0);
+        assert!(vote_account.epoch_vote_account);
+        assert_eq!(vote_account.last_vote, 0);
+        assert_eq!(vote_account.root_slot, None);
+
+        let slot = DELINQUENT_VALIDATOR_SLOT_DISTANCE + 1;
+        let bank = Arc::new(Bank::new_from_parent(&bank, &Pubkey::default(), slot));
+        let vote_accounts = new_request_processor(bank).get_vote_accounts().unwrap();
+        assert!(vote_accounts.current.is_empty());
+        assert_eq!(vote_accounts.delinquent.len(), 1);
+    }
+
+    #[test]
+    fn test_rpc_vote_account_is_delinquent() {
+        let mut vote_account = RpcVoteAccount {
+            vote_pubkey: Pubkey::new_rand().to_string(),
+            node_pubkey: Pubkey::new_rand().to_string(),
+            activated_stake: 42,
+            commission: 0,
+            epoch_vote_account: true,
+            epoch_credits: vec![],
+            last_vote: 1_000,
+            root_slot: Some(968),
+        };
+        assert!(!vote_account.is_delinquent(1_000));
+        assert!(!vote_account.is_delinquent(1_000 + DELINQUENT_VALIDATOR_SLOT_DISTANCE));
+        assert!(vote_account.is_delinquent(1_000 + DELINQUENT_VALIDATOR_SLOT_DISTANCE + 1));
+
+        // A validator that votes without rooting is delinquent too
+        vote_account.root_slot = Some(1_000 - DELINQUENT_VALIDATOR_ROOT_DISTANCE - 1);
+        assert!(vote_account.is_delinquent(1_000));
+        vote_account.root_slot = None;
+        assert!(vote_account.is_delinquent(1_000));
+        assert!(!vote_account.is_delinquent(DELINQUENT_VALIDATOR_ROOT_DISTANCE));
+    }
+
+    #[test]
+    fn test_rpc_get_inflation_reward() {
+        let GenesisBlockInfo { genesis_block, .. } = create_genesis_block(TEST_MINT_LAMPORTS);
+        let bank = Arc::new(Bank::new(&genesis_block));
+        let bank1 = Bank::new_from_parent(
+            &bank,
+            &Pubkey::default(),
+            bank.epoch_schedule().get_first_slot_in_epoch(1),
+        );
+
+        // a stake account paid twice in epoch 0, once in a slot that wasn't rooted
+        let stake_pubkey = Pubkey::new_rand();
+        let reward = |lamports, post_balance| RewardInfo {
+            lamports,
+            post_balance,
+            commission: 10,
+        };
+        let blocktree = new_blocktree();
+        blocktree
+            .write_rewards(1, &[(stake_pubkey, reward(20, 3_020))])
+            .unwrap();
+        blocktree
+            .write_rewards(2, &[(stake_pubkey, reward(30, 3_050))])
+            .unwrap();
+        blocktree
+            .write_rewards(3, &[(stake_pubkey, reward(40, 3_090))])
+            .unwrap();
+        blocktree.set_roots(&[1, 2]).unwrap();
+
+        let exit = Arc::new(AtomicBool::new(false));
+        let request_processor = JsonRpcRequestProcessor::new(
+            StorageState::default(),
+            JsonRpcConfig::default(),
+            Arc::new(RwLock::new(BankForks::new(bank1.slot(), bank1))),
+            Arc::new(RwLock::new(BlockCommitmentCache::default())),
+            blocktree,
+            &exit,
+        );
+
+        let bob_pubkey = Pubkey::new_rand();
+        assert_eq!(
+            request_processor
+                .get_inflation_reward(&[stake_pubkey, bob_pubkey], 0)
+                .unwrap(),
+            vec![
+                Some(RpcInflationReward {
+                    epoch: 0,
+                    effective_slot: 2,
+                    amount: 50,
+                    post_balance: 3_050,
+                    commission: 10,
+                }),
+                None,
+            ]
+        );
+        assert_eq!(
+            request_processor
+                .get_inflation_reward(&[stake_pubkey], 1)
+                .unwrap(),
+            vec![None]
+        );
+
+        // future epochs haven't paid anything yet
+        assert!(request_processor
+            .get_inflation_reward(&[stake_pubkey], 2)
+            .is_err());
     }
 
     #[test]
-    fn test_rpc_get_cluster_nodes() {
+    fn test_rpc_get_inflation() {
         let bob_pubkey = Pubkey::new_rand();
-        let (io, meta, _bank, _blockhash, _alice, leader_pubkey) =
+        let (io, meta, bank, _blockhash, _alice, _leader_pubkey) =
             start_rpc_handler_with_tx(&bob_pubkey);
 
-        let req = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"getClusterNodes"}}"#);
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"getInflation"}"#;
         let res = io.handle_request_sync(&req, meta);
         let result: Response = serde_json::from_str(&res.expect("actual response"))
             .expect("actual response deserialization");
-
-        let expected = format!(
-            r#"{{"jsonrpc":"2.0","result":[{{"pubkey": "{}", "gossip": "127.0.0.1:1235", "tpu": "127.0.0.1:1234", "rpc": "127.0.0.1:8899"}}],"id":1}}"#,
-            leader_pubkey,
-        );
-
-        let expected: Response =
-            serde_json::from_str(&expected).expect("expected response deserialization");
-        assert_eq!(expected, result);
+        let inflation: RpcInflation = if let Response::Single(res) = result {
+            if let Output::Success(res) = res {
+                serde_json::from_value(res.result).unwrap()
+            } else {
+                panic!("Expected success");
+            }
+        } else {
+            panic!("Expected single response");
+        };
+        let expected_inflation = bank.inflation();
+        assert_eq!(inflation.initial, expected_inflation.initial);
+        assert_eq!(inflation.grant_term, expected_inflation.grant_term);
+        assert_eq!(inflation.epoch, bank.epoch());
+        assert!(inflation.total_rate <= expected_inflation.initial);
+        assert!(inflation.total_rate >= expected_inflation.terminal);
+        assert!(inflation.validator_rate < inflation.total_rate);
     }
 
     #[test]
-    fn test_rpc_get_slot_leader() {
-        let bob_pubkey = Pubkey::new_rand();
-        let (io, meta, _bank, _blockhash, _alice, leader_pubkey) =
-            start_rpc_handler_with_tx(&bob_pubkey);
+    fn test_rpc_get_stake_activation() {
+        let GenesisBlockInfo { genesis_block, .. } = create_genesis_block(TEST_MINT_LAMPORTS);
+        let bank = Arc::new(Bank::new(&genesis_block));
+        let vote_pubkey = Pubkey::new_rand();
+        let vote_account =
+            solana_vote_api::vote_state::create_account(&vote_pubkey, &Pubkey::new_rand(), 0, 1);
+        let vote_state = VoteState::from(&vote_account).unwrap();
+        let stake_pubkey = Pubkey::new_rand();
+        let stake_account =
+            solana_stake_api::stake_state::create_stake_account(&vote_pubkey, &vote_state, 3_000);
+        bank.store_account(&stake_pubkey, &stake_account);
 
-        let req = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"getSlotLeader"}}"#);
-        let res = io.handle_request_sync(&req, meta);
-        let expected = format!(r#"{{"jsonrpc":"2.0","result":"{}","id":1}}"#, leader_pubkey);
-        let expected: Response =
-            serde_json::from_str(&expected).expect("expected response deserialization");
-        let result: Response = serde_json::from_str(&res.expect("actual response"))
-            .expect("actual response deserialization");
-        assert_eq!(expected, result);
+        let exit = Arc::new(AtomicBool::new(false));
+        let request_processor = JsonRpcRequestProcessor::new(
+            StorageState::default(),
+            JsonRpcConfig::default(),
+            Arc::new(RwLock::new(BankForks::new(bank.slot(), bank))),
+            Arc::new(RwLock::new(BlockCommitmentCache::default())),
+            new_blocktree(),
+            &exit,
+        );
+
+        // a third of the stake is active in the first epoch of warmup
+        assert_eq!(
+            request_processor
+                .get_stake_activation(&stake_pubkey, None)
+                .unwrap(),
+            RpcStakeActivation {
+                state: StakeActivationState::Activating,
+                active: 1_000,
+                activating: 2_000,
+                deactivating: 0,
+            }
+        );
+        assert_eq!(
+            request_processor
+                .get_stake_activation(&stake_pubkey, Some(42))
+                .unwrap(),
+            RpcStakeActivation {
+                state: StakeActivationState::Active,
+                active: 3_000,
+                activating: 0,
+                deactivating: 0,
+            }
+        );
+        assert!(request_processor
+            .get_stake_activation(&Pubkey::new_rand(), None)
+            .is_err());
     }
 
     #[test]
@@ -782,6 +2341,35 @@ mod tests {
         assert!(supply >= TEST_MINT_LAMPORTS);
     }
 
+    #[test]
+    fn test_rpc_get_pipeline_latencies() {
+        let bob_pubkey = Pubkey::new_rand();
+        let (io, meta, _bank, _blockhash, _alice, _leader_pubkey) =
+            start_rpc_handler_with_tx(&bob_pubkey);
+        solana_metrics::record_latency("rpc-test", Duration::from_micros(1500));
+
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"getPipelineLatencies"}"#;
+        let rep = io.handle_request_sync(&req, meta);
+        let res: Response = serde_json::from_str(&rep.expect("actual response"))
+            .expect("actual response deserialization");
+        let latencies: Vec<RpcStageLatency> = if let Response::Single(res) = res {
+            if let Output::Success(res) = res {
+                serde_json::from_value(res.result).unwrap()
+            } else {
+                panic!("Expected success");
+            }
+        } else {
+            panic!("Expected single response");
+        };
+        assert!(latencies.contains(&RpcStageLatency {
+            stage: "rpc-test".to_string(),
+            count: 1,
+            p50: 1500,
+            p90: 1500,
+            p99: 1500,
+        }));
+    }
+
     #[test]
     fn test_rpc_get_account_info() {
         let bob_pubkey = Pubkey::new_rand();
@@ -978,6 +2566,243 @@ mod tests {
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn test_rpc_simulate_transaction() {
+        let bob_pubkey = Pubkey::new_rand();
+        let (io, meta, bank, blockhash, alice, _leader_pubkey) =
+            start_rpc_handler_with_tx(&bob_pubkey);
+        let simulate = |tx: &Transaction| {
+            let req = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "simulateTransaction",
+                "params": [serialize(tx).unwrap()],
+            });
+            let res = io.handle_request_sync(&req.to_string(), meta.clone());
+            let result: Value = serde_json::from_str(&res.expect("actual response"))
+                .expect("actual response deserialization");
+            result["result"].clone()
+        };
+
+        let instruction_trace = json!([{
+            "instructionIndex": 0,
+            "depth": 1,
+            "programId": system_program::id().to_string(),
+        }]);
+        let tx = system_transaction::transfer(&alice, &bob_pubkey, 10, blockhash);
+        assert_eq!(
+            simulate(&tx),
+            json!({"err": null, "returnData": null, "instructionTrace": instruction_trace})
+        );
+        // Nothing was committed
+        assert_eq!(bank.get_balance(&bob_pubkey), 20);
+        assert_eq!(bank.get_signature_status(&tx.signatures[0]), None);
+
+        let tx = system_transaction::transfer(&alice, &bob_pubkey, TEST_MINT_LAMPORTS, blockhash);
+        let expected_err = TransactionError::InstructionError(
+            0,
+            InstructionError::new_result_with_negative_lamports(),
+        );
+        assert_eq!(
+            simulate(&tx),
+            json!({"err": expected_err, "returnData": null, "instructionTrace": instruction_trace})
+        );
+
+        // Malformed transactions are rejected before reaching the bank
+        let mut tx = system_transaction::transfer(&alice, &bob_pubkey, 10, blockhash);
+        tx.message.account_keys[1] = tx.message.account_keys[0];
+        assert_eq!(simulate(&tx), Value::Null);
+    }
+
+    #[test]
+    fn test_rpc_get_confirmed_transaction() {
+        let bob_pubkey = Pubkey::new_rand();
+        let (io, meta, bank, blockhash, alice, _leader_pubkey) =
+            start_rpc_handler_with_tx(&bob_pubkey);
+
+        let tx = system_transaction::transfer(&alice, &bob_pubkey, 20, blockhash);
+        let tx_meta = bank.get_transaction_status_meta(&tx.signatures[0]).unwrap();
+        let entries = vec![Entry::new(&blockhash, 1, vec![tx.clone()])];
+        meta.request_processor
+            .read()
+            .unwrap()
+            .blocktree
+            .write_entries(0, 0, 0, bank.ticks_per_slot(), &entries)
+            .unwrap();
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getConfirmedTransaction","params":["{}"]}}"#,
+            tx.signatures[0]
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let expected = json!({
+            "jsonrpc": "2.0",
+            "result": {
+                "slot": 0,
+                "transaction": RpcTransaction::from(&tx),
+                "status": {"Ok": null},
+                "meta": {
+                    "status": {"Ok": null},
+                    "fee": tx_meta.fee,
+                    "preBalances": tx_meta.pre_balances,
+                    "postBalances": tx_meta.post_balances,
+                },
+                "returnData": null,
+            },
+            "id": 1
+        });
+        let expected: Response =
+            serde_json::from_value(expected).expect("expected response deserialization");
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(expected, result);
+
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getConfirmedTransaction","params":["{}","binary"]}}"#,
+            tx.signatures[0]
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        let encoded = result["result"]["transaction"].as_str().unwrap();
+        let decoded: Transaction = deserialize(&bs58::decode(encoded).into_vec().unwrap()).unwrap();
+        assert_eq!(decoded, tx);
+
+        // Unprocessed transactions aren't found
+        let tx = system_transaction::transfer(&alice, &bob_pubkey, 10, blockhash);
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getConfirmedTransaction","params":["{}"]}}"#,
+            tx.signatures[0]
+        );
+        let res = io.handle_request_sync(&req, meta);
+        let expected = json!({
+            "jsonrpc": "2.0",
+            "result": null,
+            "id": 1
+        });
+        let expected: Response =
+            serde_json::from_value(expected).expect("expected response deserialization");
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_rpc_get_confirmed_signatures_for_address() {
+        let bob_pubkey = Pubkey::new_rand();
+        let (io, meta, bank, blockhash, alice, _leader_pubkey) =
+            start_rpc_handler_with_tx(&bob_pubkey);
+
+        let tx = system_transaction::transfer(&alice, &bob_pubkey, 20, blockhash);
+        let tx2 = system_transaction::transfer(&alice, &bob_pubkey, 21, blockhash);
+        bank.process_transaction(&tx2).unwrap();
+        {
+            let request_processor = meta.request_processor.read().unwrap();
+            let blocktree = &request_processor.blocktree;
+            blocktree
+                .write_address_signatures(1, &bank.take_transaction_addresses())
+                .unwrap();
+            blocktree.set_roots(&[1]).unwrap();
+        }
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getConfirmedSignaturesForAddress","params":["{}", 0, 1]}}"#,
+            bob_pubkey
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let expected = json!({
+            "jsonrpc": "2.0",
+            "result": [tx.signatures[0].to_string(), tx2.signatures[0].to_string()],
+            "id": 1
+        });
+        let expected: Response =
+            serde_json::from_value(expected).expect("expected response deserialization");
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(expected, result);
+
+        // One page at a time
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getConfirmedSignaturesForAddress","params":["{}", 0, 1, {{"after": "{}", "limit": 1}}]}}"#,
+            bob_pubkey, tx.signatures[0]
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let expected = json!({
+            "jsonrpc": "2.0",
+            "result": [tx2.signatures[0].to_string()],
+            "id": 1
+        });
+        let expected: Response =
+            serde_json::from_value(expected).expect("expected response deserialization");
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(expected, result);
+
+        // Too many slots at once
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getConfirmedSignaturesForAddress","params":["{}", 0, {}]}}"#,
+            bob_pubkey, MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE
+        );
+        let res = io.handle_request_sync(&req, meta);
+        let expected =
+            r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":1}"#;
+        let expected: Response =
+            serde_json::from_str(expected).expect("expected response deserialization");
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_rpc_encoded_transaction() {
+        let keypair = Keypair::new();
+        let to = Pubkey::new_rand();
+        let tx = system_transaction::transfer(&keypair, &to, 20, Hash::default());
+        let message = tx.message();
+        let instruction = &message.instructions[0];
+        assert_eq!(
+            serde_json::to_value(&RpcEncodedTransaction::encode(
+                &tx,
+                RpcTransactionEncoding::Json
+            ))
+            .unwrap(),
+            json!({
+                "signatures": [tx.signatures[0].to_string()],
+                "message": {
+                    "numRequiredSignatures": 1,
+                    "numCreditOnlySignedAccounts": 0,
+                    "numCreditOnlyUnsignedAccounts": 1,
+                    "accountKeys": [
+                        keypair.pubkey().to_string(),
+                        to.to_string(),
+                        system_program::id().to_string(),
+                    ],
+                    "recentBlockhash": Hash::default().to_string(),
+                    "instructions": [{
+                        "programIdIndex": instruction.program_id_index,
+                        "accounts": instruction.accounts,
+                        "data": bs58::encode(&instruction.data).into_string(),
+                    }],
+                },
+            })
+        );
+        assert_eq!(
+            RpcEncodedTransaction::encode(&tx, RpcTransactionEncoding::Binary),
+            RpcEncodedTransaction::Binary(bs58::encode(serialize(&tx).unwrap()).into_string())
+        );
+    }
+
+    #[test]
+    fn test_rpc_return_data() {
+        let program_id = Pubkey::new_rand();
+        let return_data = RpcReturnData::from(TransactionReturnData {
+            program_id,
+            data: vec![1, 2, 3],
+        });
+        assert_eq!(
+            serde_json::to_value(&return_data).unwrap(),
+            json!({"programId": program_id.to_string(), "data": [1, 2, 3]})
+        );
+    }
+
     #[test]
     fn test_rpc_send_bad_tx() {
         let exit = Arc::new(AtomicBool::new(false));
@@ -991,6 +2816,8 @@ mod tests {
                     StorageState::default(),
                     JsonRpcConfig::default(),
                     new_bank_forks().0,
+                    Arc::new(RwLock::new(BlockCommitmentCache::default())),
+                    new_blocktree(),
                     &exit,
                 );
                 Arc::new(RwLock::new(request_processor))
@@ -1048,6 +2875,11 @@ mod tests {
         );
     }
 
+    fn new_blocktree() -> Arc<Blocktree> {
+        let ledger_path = get_tmp_ledger_path!();
+        Arc::new(Blocktree::open(&ledger_path).unwrap())
+    }
+
     fn new_bank_forks() -> (Arc<RwLock<BankForks>>, Keypair) {
         let GenesisBlockInfo {
             genesis_block,
@@ -1068,6 +2900,8 @@ mod tests {
             StorageState::default(),
             JsonRpcConfig::default(),
             new_bank_forks().0,
+            Arc::new(RwLock::new(BlockCommitmentCache::default())),
+            new_blocktree(),
             &exit,
         );
         assert_eq!(request_processor.fullnode_exit(), Ok(false));
@@ -1083,9 +2917,163 @@ mod tests {
             StorageState::default(),
             config,
             new_bank_forks().0,
+            Arc::new(RwLock::new(BlockCommitmentCache::default())),
+            new_blocktree(),
             &exit,
         );
         assert_eq!(request_processor.fullnode_exit(), Ok(true));
         assert_eq!(exit.load(Ordering::Relaxed), true);
     }
+
+    #[test]
+    fn test_rpc_request_processor_ledger_storage_fallback() {
+        let exit = Arc::new(AtomicBool::new(false));
+        let (bank_forks, alice) = new_bank_forks();
+        let blockhash = bank_forks.read().unwrap().working_bank().last_blockhash();
+        let ledger_storage_path = get_tmp_ledger_path!();
+        let mut config = JsonRpcConfig::default();
+        config.ledger_storage_path = Some(ledger_storage_path.clone());
+        let request_processor = JsonRpcRequestProcessor::new(
+            StorageState::default(),
+            config,
+            bank_forks,
+            Arc::new(RwLock::new(BlockCommitmentCache::default())),
+            new_blocktree(),
+            &exit,
+        );
+        let tx = system_transaction::transfer(&alice, &Pubkey::new_rand(), 20, blockhash);
+        assert_eq!(request_processor.get_confirmed_block(5), Ok(None));
+        assert_eq!(
+            request_processor
+                .get_confirmed_transaction(tx.signatures[0], RpcTransactionEncoding::default()),
+            None
+        );
+
+        // A block the blocktree no longer has, uploaded by an archival node
+        let tx_meta = TransactionStatusMeta {
+            status: Err(TransactionError::InstructionError(
+                0,
+                InstructionError::InsufficientFunds,
+            )),
+            fee: 10,
+            pre_balances: vec![30, 0, 1],
+            post_balances: vec![20, 0, 1],
+        };
+        let block = ConfirmedBlock {
+            slot: 5,
+            parent_slot: 4,
+            blockhash: hash(b"5"),
+            previous_blockhash: hash(b"4"),
+            transactions: vec![TransactionWithStatus {
+                transaction: tx.clone(),
+                meta: Some(tx_meta.clone()),
+            }],
+        };
+        ledger_storage::upload_block(&FileLedgerStorage::new(&ledger_storage_path), &block)
+            .unwrap();
+        assert_eq!(
+            request_processor.get_confirmed_block(5),
+            Ok(Some(RpcConfirmedBlock::from(block)))
+        );
+        assert_eq!(
+            request_processor
+                .get_confirmed_transaction(tx.signatures[0], RpcTransactionEncoding::Binary),
+            Some(RpcConfirmedTransaction {
+                slot: 5,
+                transaction: RpcEncodedTransaction::encode(&tx, RpcTransactionEncoding::Binary),
+                status: tx_meta.status.clone(),
+                meta: Some(tx_meta),
+                return_data: None,
+            })
+        );
+        std::fs::remove_dir_all(ledger_storage_path).unwrap();
+    }
+
+    #[test]
+    fn test_rpc_request_processor_rooted_transaction() {
+        let exit = Arc::new(AtomicBool::new(false));
+        let (bank_forks, alice) = new_bank_forks();
+        let bank = bank_forks.read().unwrap().working_bank();
+        let blocktree = new_blocktree();
+        let request_processor = JsonRpcRequestProcessor::new(
+            StorageState::default(),
+            JsonRpcConfig::default(),
+            bank_forks,
+            Arc::new(RwLock::new(BlockCommitmentCache::default())),
+            blocktree.clone(),
+            &exit,
+        );
+
+        // A transaction the bank's status cache has forgotten, still in the blocktree
+        let tx =
+            system_transaction::transfer(&alice, &Pubkey::new_rand(), 20, bank.last_blockhash());
+        let tx_meta = TransactionStatusMeta {
+            status: Ok(()),
+            fee: 10,
+            pre_balances: vec![30, 0, 1],
+            post_balances: vec![0, 20, 1],
+        };
+        let entries = vec![Entry::new(&bank.last_blockhash(), 1, vec![tx.clone()])];
+        blocktree
+            .write_entries(1, 0, 0, bank.ticks_per_slot(), &entries)
+            .unwrap();
+        blocktree
+            .write_transaction_statuses(1, &[(tx.signatures[0], tx_meta.clone())])
+            .unwrap();
+        assert_eq!(
+            request_processor
+                .get_confirmed_transaction(tx.signatures[0], RpcTransactionEncoding::Binary),
+            None
+        );
+
+        blocktree.set_roots(&[1]).unwrap();
+        assert_eq!(
+            request_processor
+                .get_confirmed_transaction(tx.signatures[0], RpcTransactionEncoding::Binary),
+            Some(RpcConfirmedTransaction {
+                slot: 1,
+                transaction: RpcEncodedTransaction::encode(&tx, RpcTransactionEncoding::Binary),
+                status: tx_meta.status.clone(),
+                meta: Some(tx_meta),
+                return_data: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_rpc_request_processor_block_commitment() {
+        let exit = Arc::new(AtomicBool::new(false));
+        let block_commitment_cache = Arc::new(RwLock::new(BlockCommitmentCache::default()));
+        let request_processor = JsonRpcRequestProcessor::new(
+            StorageState::default(),
+            JsonRpcConfig::default(),
+            new_bank_forks().0,
+            block_commitment_cache.clone(),
+            new_blocktree(),
+            &exit,
+        );
+        assert_eq!(
+            request_processor.get_block_commitment(2),
+            RpcBlockCommitment {
+                commitment: None,
+                total_stake: 0,
+            }
+        );
+
+        {
+            let mut block_commitment_cache = block_commitment_cache.write().unwrap();
+            block_commitment_cache.set_total_stake(30);
+            block_commitment_cache.record_vote(Pubkey::new_rand(), vec![1, 2], 10);
+            block_commitment_cache.aggregate();
+        }
+        let mut commitment = vec![0; MAX_LOCKOUT_HISTORY];
+        commitment[0] = 10;
+        assert_eq!(
+            request_processor.get_block_commitment(2),
+            RpcBlockCommitment {
+                commitment: Some(commitment),
+                total_stake: 30,
+            }
+        );
+    }
 }
@@ -1,16 +1,35 @@
 //! The `pubsub` module implements a threaded subscription service on client RPC request
 
-use crate::rpc_subscriptions::{Confirmations, RpcSubscriptions};
+use crate::rpc_subscriptions::{Commitment, Confirmations, RpcSubscriptions, SlotsPurgedInfo};
 use jsonrpc_core::{Error, ErrorCode, Result};
 use jsonrpc_derive::rpc;
-use jsonrpc_pubsub::typed::Subscriber;
-use jsonrpc_pubsub::{Session, SubscriptionId};
+use jsonrpc_pubsub::typed::{Sink, Subscriber};
+use jsonrpc_pubsub::{PubSubMetadata, Session, SubscriptionId};
 use solana_sdk::account::Account;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signature;
 use solana_sdk::transaction;
 use std::sync::{atomic, Arc};
 
+/// The most subscriptions a single connection may have open at once
+pub const MAX_SUBSCRIPTIONS_PER_CONNECTION: usize = 1_000;
+
+/// The metadata of a request: the session of the connection it came in on, and an id the
+/// connection keeps for as long as it's open
+#[derive(Clone)]
+pub struct ConnectionMetadata {
+    pub session: Arc<Session>,
+    pub connection_id: u64,
+}
+
+impl jsonrpc_core::Metadata for ConnectionMetadata {}
+
+impl PubSubMetadata for ConnectionMetadata {
+    fn session(&self) -> Option<Arc<Session>> {
+        Some(self.session.clone())
+    }
+}
+
 #[rpc(server)]
 pub trait RpcSolPubSub {
     type Metadata;
@@ -28,6 +47,7 @@ pub trait RpcSolPubSub {
         _: Subscriber<Account>,
         _: String,
         _: Option<Confirmations>,
+        _: Option<Commitment>,
     );
 
     // Unsubscribe from account notification subscription.
@@ -51,6 +71,7 @@ pub trait RpcSolPubSub {
         _: Subscriber<(String, Account)>,
         _: String,
         _: Option<Confirmations>,
+        _: Option<Commitment>,
     );
 
     // Unsubscribe from account notification subscription.
@@ -74,6 +95,7 @@ pub trait RpcSolPubSub {
         _: Subscriber<transaction::Result<()>>,
         _: String,
         _: Option<Confirmations>,
+        _: Option<Commitment>,
     );
 
     // Unsubscribe from signature notification subscription.
@@ -83,18 +105,85 @@ pub trait RpcSolPubSub {
         name = "signatureUnsubscribe"
     )]
     fn signature_unsubscribe(&self, _: Option<Self::Metadata>, _: SubscriptionId) -> Result<bool>;
+
+    // Get notification when the ledger purges old slots
+    #[pubsub(
+        subscription = "slotsPurgedNotification",
+        subscribe,
+        name = "slotsPurgedSubscribe"
+    )]
+    fn slots_purged_subscribe(&self, _: Self::Metadata, _: Subscriber<SlotsPurgedInfo>);
+
+    // Unsubscribe from slots purged notification subscription.
+    #[pubsub(
+        subscription = "slotsPurgedNotification",
+        unsubscribe,
+        name = "slotsPurgedUnsubscribe"
+    )]
+    fn slots_purged_unsubscribe(
+        &self,
+        _: Option<Self::Metadata>,
+        _: SubscriptionId,
+    ) -> Result<bool>;
 }
 
-#[derive(Default)]
 pub struct RpcSolPubSubImpl {
     uid: Arc<atomic::AtomicUsize>,
     subscriptions: Arc<RpcSubscriptions>,
+    max_subscriptions_per_connection: usize,
+}
+
+impl Default for RpcSolPubSubImpl {
+    fn default() -> Self {
+        Self::new(Arc::new(RpcSubscriptions::default()))
+    }
 }
 
 impl RpcSolPubSubImpl {
     pub fn new(subscriptions: Arc<RpcSubscriptions>) -> Self {
         let uid = Arc::new(atomic::AtomicUsize::default());
-        Self { uid, subscriptions }
+        Self {
+            uid,
+            subscriptions,
+            max_subscriptions_per_connection: MAX_SUBSCRIPTIONS_PER_CONNECTION,
+        }
+    }
+
+    /// Assigns the next subscription id to `subscriber`, or rejects it if its connection already
+    /// has as many subscriptions open as it may
+    fn assign_id<T>(
+        &self,
+        meta: &ConnectionMetadata,
+        subscriber: Subscriber<T>,
+    ) -> Option<(SubscriptionId, Sink<T>)> {
+        let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
+        let sub_id = SubscriptionId::Number(id as u64);
+        if !self.subscriptions.add_connection_subscription(
+            meta.connection_id,
+            &sub_id,
+            self.max_subscriptions_per_connection,
+        ) {
+            inc_new_counter_info!("rpc-pubsub-subscriptions-rejected", 1);
+            subscriber
+                .reject(Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: format!(
+                        "Invalid Request: Connection already has {} subscriptions",
+                        self.max_subscriptions_per_connection
+                    ),
+                    data: None,
+                })
+                .unwrap();
+            return None;
+        }
+        let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+        Some((sub_id, sink))
+    }
+
+    /// Frees the connection's slot for `id`. A closed connection unsubscribes from everything,
+    /// so this is called whether or not the subscription still exists.
+    fn release_id(&self, id: &SubscriptionId) {
+        self.subscriptions.release_subscription_id(id);
     }
 }
 
@@ -109,24 +198,31 @@ fn param<T: FromStr>(param_str: &str, thing: &str) -> Result<T> {
 }
 
 impl RpcSolPubSub for RpcSolPubSubImpl {
-    type Metadata = Arc<Session>;
+    type Metadata = ConnectionMetadata;
 
     fn account_subscribe(
         &self,
-        _meta: Self::Metadata,
+        meta: Self::Metadata,
         subscriber: Subscriber<Account>,
         pubkey_str: String,
         confirmations: Option<Confirmations>,
+        commitment: Option<Commitment>,
     ) {
         match param::<Pubkey>(&pubkey_str, "pubkey") {
             Ok(pubkey) => {
-                let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
-                let sub_id = SubscriptionId::Number(id as u64);
+                let (sub_id, sink) = match self.assign_id(&meta, subscriber) {
+                    Some(assigned) => assigned,
+                    None => return,
+                };
                 info!("account_subscribe: account={:?} id={:?}", pubkey, sub_id);
-                let sink = subscriber.assign_id(sub_id.clone()).unwrap();
 
-                self.subscriptions
-                    .add_account_subscription(&pubkey, confirmations, &sub_id, &sink)
+                self.subscriptions.add_account_subscription(
+                    &pubkey,
+                    confirmations,
+                    commitment,
+                    &sub_id,
+                    &sink,
+                )
             }
             Err(e) => subscriber.reject(e).unwrap(),
         }
@@ -138,6 +234,7 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
         id: SubscriptionId,
     ) -> Result<bool> {
         info!("account_unsubscribe: id={:?}", id);
+        self.release_id(&id);
         if self.subscriptions.remove_account_subscription(&id) {
             Ok(true)
         } else {
@@ -151,20 +248,27 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
 
     fn program_subscribe(
         &self,
-        _meta: Self::Metadata,
+        meta: Self::Metadata,
         subscriber: Subscriber<(String, Account)>,
         pubkey_str: String,
         confirmations: Option<Confirmations>,
+        commitment: Option<Commitment>,
     ) {
         match param::<Pubkey>(&pubkey_str, "pubkey") {
             Ok(pubkey) => {
-                let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
-                let sub_id = SubscriptionId::Number(id as u64);
+                let (sub_id, sink) = match self.assign_id(&meta, subscriber) {
+                    Some(assigned) => assigned,
+                    None => return,
+                };
                 info!("program_subscribe: account={:?} id={:?}", pubkey, sub_id);
-                let sink = subscriber.assign_id(sub_id.clone()).unwrap();
 
-                self.subscriptions
-                    .add_program_subscription(&pubkey, confirmations, &sub_id, &sink)
+                self.subscriptions.add_program_subscription(
+                    &pubkey,
+                    confirmations,
+                    commitment,
+                    &sub_id,
+                    &sink,
+                )
             }
             Err(e) => subscriber.reject(e).unwrap(),
         }
@@ -176,6 +280,7 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
         id: SubscriptionId,
     ) -> Result<bool> {
         info!("program_unsubscribe: id={:?}", id);
+        self.release_id(&id);
         if self.subscriptions.remove_program_subscription(&id) {
             Ok(true)
         } else {
@@ -189,25 +294,28 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
 
     fn signature_subscribe(
         &self,
-        _meta: Self::Metadata,
+        meta: Self::Metadata,
         subscriber: Subscriber<transaction::Result<()>>,
         signature_str: String,
         confirmations: Option<Confirmations>,
+        commitment: Option<Commitment>,
     ) {
         info!("signature_subscribe");
         match param::<Signature>(&signature_str, "signature") {
             Ok(signature) => {
-                let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
-                let sub_id = SubscriptionId::Number(id as u64);
+                let (sub_id, sink) = match self.assign_id(&meta, subscriber) {
+                    Some(assigned) => assigned,
+                    None => return,
+                };
                 info!(
                     "signature_subscribe: signature={:?} id={:?}",
                     signature, sub_id
                 );
-                let sink = subscriber.assign_id(sub_id.clone()).unwrap();
 
                 self.subscriptions.add_signature_subscription(
                     &signature,
                     confirmations,
+                    commitment,
                     &sub_id,
                     &sink,
                 );
@@ -222,6 +330,7 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
         id: SubscriptionId,
     ) -> Result<bool> {
         info!("signature_unsubscribe");
+        self.release_id(&id);
         if self.subscriptions.remove_signature_subscription(&id) {
             Ok(true)
         } else {
@@ -232,6 +341,39 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
             })
         }
     }
+
+    fn slots_purged_subscribe(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<SlotsPurgedInfo>,
+    ) {
+        let (sub_id, sink) = match self.assign_id(&meta, subscriber) {
+            Some(assigned) => assigned,
+            None => return,
+        };
+        info!("slots_purged_subscribe: id={:?}", sub_id);
+
+        self.subscriptions
+            .add_slots_purged_subscription(&sub_id, &sink);
+    }
+
+    fn slots_purged_unsubscribe(
+        &self,
+        _meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool> {
+        info!("slots_purged_unsubscribe: id={:?}", id);
+        self.release_id(&id);
+        if self.subscriptions.remove_slots_purged_subscription(&id) {
+            Ok(true)
+        } else {
+            Err(Error {
+                code: ErrorCode::InvalidParams,
+                message: "Invalid Request: Subscription id does not exist".into(),
+                data: None,
+            })
+        }
+    }
 }
 
 #[cfg(test)]
@@ -240,6 +382,7 @@ mod tests {
     use crate::bank_forks::BankForks;
     use crate::genesis_utils::{create_genesis_block, GenesisBlockInfo};
     use jsonrpc_core::futures::sync::mpsc;
+    use jsonrpc_core::futures::Future;
     use jsonrpc_core::Response;
     use jsonrpc_pubsub::{PubSubHandler, Session};
     use solana_budget_api;
@@ -270,8 +413,12 @@ mod tests {
         Ok(())
     }
 
-    fn create_session() -> Arc<Session> {
-        Arc::new(Session::new(mpsc::channel(1).0))
+    fn create_session() -> ConnectionMetadata {
+        static NEXT_CONNECTION_ID: atomic::AtomicUsize = atomic::AtomicUsize::new(0);
+        ConnectionMetadata {
+            session: Arc::new(Session::new(mpsc::channel(1).0)),
+            connection_id: NEXT_CONNECTION_ID.fetch_add(1, atomic::Ordering::Relaxed) as u64,
+        }
     }
 
     #[test]
@@ -295,7 +442,13 @@ mod tests {
         let session = create_session();
         let (subscriber, _id_receiver, mut receiver) =
             Subscriber::new_test("signatureNotification");
-        rpc.signature_subscribe(session, subscriber, tx.signatures[0].to_string(), None);
+        rpc.signature_subscribe(
+            session,
+            subscriber,
+            tx.signatures[0].to_string(),
+            None,
+            None,
+        );
 
         process_transaction_and_notify(&bank_forks, &tx, &rpc.subscriptions).unwrap();
         sleep(Duration::from_millis(200));
@@ -306,7 +459,10 @@ mod tests {
             let expected_res: Option<transaction::Result<()>> = Some(Ok(()));
             let expected_res_str =
                 serde_json::to_string(&serde_json::to_value(expected_res).unwrap()).unwrap();
-            let expected = format!(r#"{{"jsonrpc":"2.0","method":"signatureNotification","params":{{"result":{},"subscription":0}}}}"#, expected_res_str);
+            let expected = format!(
+                r#"{{"jsonrpc":"2.0","method":"signatureNotification","params":{{"result":{},"subscription":0}}}}"#,
+                expected_res_str
+            );
             assert_eq!(expected, response);
         }
     }
@@ -350,7 +506,9 @@ mod tests {
         let req =
             format!(r#"{{"jsonrpc":"2.0","id":1,"method":"signatureUnsubscribe","params":[1]}}"#);
         let res = io.handle_request_sync(&req, session.clone());
-        let expected = format!(r#"{{"jsonrpc":"2.0","error":{{"code":-32602,"message":"Invalid Request: Subscription id does not exist"}},"id":1}}"#);
+        let expected = format!(
+            r#"{{"jsonrpc":"2.0","error":{{"code":-32602,"message":"Invalid Request: Subscription id does not exist"}},"id":1}}"#
+        );
         let expected: Response = serde_json::from_str(&expected).unwrap();
 
         let result: Response = serde_json::from_str(&res.unwrap()).unwrap();
@@ -388,6 +546,7 @@ mod tests {
             subscriber,
             contract_state.pubkey().to_string(),
             None,
+            None,
         );
 
         let tx = system_transaction::create_user_account(
@@ -491,7 +650,9 @@ mod tests {
         let req =
             format!(r#"{{"jsonrpc":"2.0","id":1,"method":"accountUnsubscribe","params":[1]}}"#);
         let res = io.handle_request_sync(&req, session.clone());
-        let expected = format!(r#"{{"jsonrpc":"2.0","error":{{"code":-32602,"message":"Invalid Request: Subscription id does not exist"}},"id":1}}"#);
+        let expected = format!(
+            r#"{{"jsonrpc":"2.0","error":{{"code":-32602,"message":"Invalid Request: Subscription id does not exist"}},"id":1}}"#
+        );
         let expected: Response = serde_json::from_str(&expected).unwrap();
 
         let result: Response = serde_json::from_str(&res.unwrap()).unwrap();
@@ -514,7 +675,7 @@ mod tests {
         let rpc = RpcSolPubSubImpl::default();
         let session = create_session();
         let (subscriber, _id_receiver, mut receiver) = Subscriber::new_test("accountNotification");
-        rpc.account_subscribe(session, subscriber, bob.pubkey().to_string(), Some(2));
+        rpc.account_subscribe(session, subscriber, bob.pubkey().to_string(), Some(2), None);
 
         let tx = system_transaction::transfer(&alice, &bob.pubkey(), 100, blockhash);
         bank_forks
@@ -543,7 +704,7 @@ mod tests {
         let rpc = RpcSolPubSubImpl::default();
         let session = create_session();
         let (subscriber, _id_receiver, mut receiver) = Subscriber::new_test("accountNotification");
-        rpc.account_subscribe(session, subscriber, bob.pubkey().to_string(), Some(2));
+        rpc.account_subscribe(session, subscriber, bob.pubkey().to_string(), Some(2), None);
 
         let tx = system_transaction::transfer(&alice, &bob.pubkey(), 100, blockhash);
         bank_forks
@@ -581,4 +742,58 @@ mod tests {
             assert_eq!(serde_json::to_string(&expected).unwrap(), response);
         }
     }
+
+    #[test]
+    fn test_slots_purged_subscribe() {
+        let rpc = RpcSolPubSubImpl::default();
+        let session = create_session();
+        let (subscriber, _id_receiver, mut receiver) =
+            Subscriber::new_test("slotsPurgedNotification");
+        rpc.slots_purged_subscribe(session, subscriber);
+
+        rpc.subscriptions.notify_slots_purged(&SlotsPurgedInfo {
+            first_slot: 0,
+            last_slot: 9,
+            minimum_ledger_slot: 10,
+        });
+        let string = receiver.poll();
+        if let Async::Ready(Some(response)) = string.unwrap() {
+            let expected = r#"{"jsonrpc":"2.0","method":"slotsPurgedNotification","params":{"result":{"firstSlot":0,"lastSlot":9,"minimumLedgerSlot":10},"subscription":0}}"#;
+            assert_eq!(expected, response);
+        }
+
+        let sub_id = SubscriptionId::Number(0);
+        assert!(rpc.slots_purged_unsubscribe(None, sub_id.clone()).unwrap());
+        assert!(rpc.slots_purged_unsubscribe(None, sub_id).is_err());
+    }
+
+    #[test]
+    fn test_max_subscriptions_per_connection() {
+        let rpc = RpcSolPubSubImpl {
+            max_subscriptions_per_connection: 2,
+            ..RpcSolPubSubImpl::default()
+        };
+        let session = create_session();
+        let subscribe = |session: &ConnectionMetadata| {
+            let (subscriber, id_receiver, _receiver) = Subscriber::new_test("accountNotification");
+            rpc.account_subscribe(
+                session.clone(),
+                subscriber,
+                Pubkey::new_rand().to_string(),
+                None,
+                None,
+            );
+            id_receiver.wait().unwrap()
+        };
+
+        let sub_id = subscribe(&session).unwrap();
+        assert!(subscribe(&session).is_ok());
+        assert!(subscribe(&session).is_err());
+        // Other connections have subscriptions of their own
+        assert!(subscribe(&create_session()).is_ok());
+
+        assert!(rpc.account_unsubscribe(None, sub_id).unwrap());
+        assert!(subscribe(&session).is_ok());
+        assert!(subscribe(&session).is_err());
+    }
 }
@@ -1,16 +1,21 @@
 //! The `pubsub` module implements a threaded subscription service on client RPC request
 
-use crate::rpc_pubsub::{RpcSolPubSub, RpcSolPubSubImpl};
+use crate::rpc_pubsub::{ConnectionMetadata, RpcSolPubSub, RpcSolPubSubImpl};
 use crate::rpc_subscriptions::RpcSubscriptions;
 use crate::service::Service;
 use jsonrpc_pubsub::{PubSubHandler, Session};
-use jsonrpc_ws_server::{RequestContext, ServerBuilder};
+use jsonrpc_ws_server::ws::CloseCode;
+use jsonrpc_ws_server::{RequestContext, Sender, ServerBuilder};
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::thread::{self, sleep, Builder, JoinHandle};
 use std::time::Duration;
 
+/// The most websocket connections the service accepts at once
+pub const MAX_CONNECTIONS: usize = 1_000;
+
 pub struct PubSubService {
     thread_hdl: JoinHandle<()>,
 }
@@ -31,6 +36,9 @@ impl PubSubService {
     ) -> Self {
         info!("rpc_pubsub bound to {:?}", pubsub_addr);
         let rpc = RpcSolPubSubImpl::new(subscriptions.clone());
+        let subscriptions = subscriptions.clone();
+        // The open connections, by the ids the server gave their sessions
+        let connections: Arc<RwLock<HashMap<u64, Sender>>> = Arc::default();
         let exit_ = exit.clone();
         let thread_hdl = Builder::new()
             .name("solana-pubsub".to_string())
@@ -38,14 +46,29 @@ impl PubSubService {
                 let mut io = PubSubHandler::default();
                 io.extend_with(rpc.to_delegate());
 
-                let server = ServerBuilder::with_meta_extractor(io, |context: &RequestContext| {
+                let extractor_subscriptions = subscriptions.clone();
+                let extractor_connections = connections.clone();
+                let server = ServerBuilder::with_meta_extractor(io, move |context: &RequestContext| {
                         info!("New pubsub connection");
+                        let connection_id = context.session_id;
+                        extractor_connections
+                            .write()
+                            .unwrap()
+                            .insert(connection_id, context.out.clone());
                         let session = Arc::new(Session::new(context.sender().clone()));
-                        session.on_drop(|| {
+                        let subscriptions = extractor_subscriptions.clone();
+                        let connections = extractor_connections.clone();
+                        session.on_drop(move || {
                             info!("Pubsub connection dropped");
+                            connections.write().unwrap().remove(&connection_id);
+                            subscriptions.remove_connection(connection_id);
                         });
-                        session
+                        ConnectionMetadata {
+                            session,
+                            connection_id,
+                        }
                 })
+                .max_connections(MAX_CONNECTIONS)
                 .start(&pubsub_addr);
 
                 if let Err(e) = server {
@@ -53,6 +76,14 @@ impl PubSubService {
                     return;
                 }
                 while !exit_.load(Ordering::Relaxed) {
+                    // A client that stopped reading notifications is presumed gone, so its
+                    // connection is closed rather than left holding the server's resources
+                    for connection_id in subscriptions.take_stalled_connections() {
+                        if let Some(out) = connections.read().unwrap().get(&connection_id) {
+                            info!("closing stalled pubsub connection {}", connection_id);
+                            let _ = out.close(CloseCode::Away);
+                        }
+                    }
                     sleep(Duration::from_millis(100));
                 }
                 server.unwrap().close();
@@ -1,12 +1,17 @@
 //! The `rpc_service` module implements the Solana JSON RPC service.
 
 use crate::bank_forks::BankForks;
+use crate::block_commitment::BlockCommitmentCache;
+use crate::blocktree::Blocktree;
 use crate::cluster_info::ClusterInfo;
 use crate::rpc::*;
 use crate::service::Service;
 use crate::storage_stage::StorageState;
 use jsonrpc_core::MetaIoHandler;
-use jsonrpc_http_server::{hyper, AccessControlAllowOrigin, DomainsValidation, ServerBuilder};
+use jsonrpc_http_server::{
+    hyper, AccessControlAllowOrigin, DomainsValidation, RequestMiddleware, RequestMiddlewareAction,
+    ServerBuilder,
+};
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
@@ -20,6 +25,40 @@ pub struct JsonRpcService {
     pub request_processor: Arc<RwLock<JsonRpcRequestProcessor>>, // Used only by test_rpc_new()...
 }
 
+/// Answers `GET /health` with the node's health status, for load balancers and process
+/// supervisors that can't make a JSON RPC request. Healthy nodes answer 200, others 503.
+struct RpcRequestMiddleware {
+    request_processor: Arc<RwLock<JsonRpcRequestProcessor>>,
+    cluster_info: Arc<RwLock<ClusterInfo>>,
+}
+
+impl RequestMiddleware for RpcRequestMiddleware {
+    fn on_request(&self, request: hyper::Request<hyper::Body>) -> RequestMiddlewareAction {
+        if request.uri().path() != "/health" {
+            return RequestMiddlewareAction::Proceed {
+                should_continue_on_invalid_cors: false,
+                request,
+            };
+        }
+        let status = self
+            .request_processor
+            .read()
+            .unwrap()
+            .get_health(&self.cluster_info.read().unwrap())
+            .status;
+        let status_code = if status.is_healthy() {
+            hyper::StatusCode::OK
+        } else {
+            hyper::StatusCode::SERVICE_UNAVAILABLE
+        };
+        hyper::Response::builder()
+            .status(status_code)
+            .body(hyper::Body::from(status.to_string()))
+            .unwrap()
+            .into()
+    }
+}
+
 impl JsonRpcService {
     pub fn new(
         cluster_info: &Arc<RwLock<ClusterInfo>>,
@@ -27,6 +66,8 @@ impl JsonRpcService {
         storage_state: StorageState,
         config: JsonRpcConfig,
         bank_forks: Arc<RwLock<BankForks>>,
+        block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+        blocktree: Arc<Blocktree>,
         exit: &Arc<AtomicBool>,
     ) -> Self {
         info!("rpc bound to {:?}", rpc_addr);
@@ -35,9 +76,15 @@ impl JsonRpcService {
             storage_state,
             config,
             bank_forks,
+            block_commitment_cache,
+            blocktree,
             exit,
         )));
         let request_processor_ = request_processor.clone();
+        let middleware = RpcRequestMiddleware {
+            request_processor: request_processor.clone(),
+            cluster_info: cluster_info.clone(),
+        };
 
         let cluster_info = cluster_info.clone();
         let exit_ = exit.clone();
@@ -57,6 +104,7 @@ impl JsonRpcService {
                         .cors(DomainsValidation::AllowOnly(vec![
                             AccessControlAllowOrigin::Any,
                         ]))
+                        .request_middleware(middleware)
                         .start_http(&rpc_addr);
                 if let Err(e) = server {
                     warn!("JSON RPC service unavailable error: {:?}. \nAlso, check that port {} is not already in use by another application", e, rpc_addr.port());
@@ -87,6 +135,7 @@ impl Service for JsonRpcService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::blocktree::get_tmp_ledger_path;
     use crate::contact_info::ContactInfo;
     use crate::genesis_utils::{create_genesis_block, GenesisBlockInfo};
     use solana_runtime::bank::Bank;
@@ -110,12 +159,16 @@ mod tests {
             solana_netutil::find_available_port_in_range((10000, 65535)).unwrap(),
         );
         let bank_forks = Arc::new(RwLock::new(BankForks::new(bank.slot(), bank)));
+        let ledger_path = get_tmp_ledger_path!();
+        let blocktree = Arc::new(Blocktree::open(&ledger_path).unwrap());
         let rpc_service = JsonRpcService::new(
             &cluster_info,
             rpc_addr,
             StorageState::default(),
             JsonRpcConfig::default(),
             bank_forks,
+            Arc::new(RwLock::new(BlockCommitmentCache::default())),
+            blocktree,
             &exit,
         );
         let thread = rpc_service.thread_hdl.thread();
@@ -131,5 +184,6 @@ mod tests {
         );
         exit.store(true, Ordering::Relaxed);
         rpc_service.join().unwrap();
+        Blocktree::destroy(&ledger_path).unwrap();
     }
 }
@@ -1,8 +1,11 @@
 //! The `pubsub` module implements a threaded subscription service on client RPC request
 
 use crate::bank_forks::BankForks;
+use crate::block_commitment::BlockCommitmentCache;
+use crate::consensus::VOTE_THRESHOLD_SIZE;
 use core::hash::Hash;
-use jsonrpc_core::futures::Future;
+use jsonrpc_core::futures::sink::Sink as _;
+use jsonrpc_core::futures::{future, AsyncSink, Future};
 use jsonrpc_pubsub::typed::Sink;
 use jsonrpc_pubsub::SubscriptionId;
 use serde::Serialize;
@@ -10,30 +13,135 @@ use solana_runtime::bank::Bank;
 use solana_sdk::account::Account;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signature;
+use solana_sdk::timing::timestamp;
 use solana_sdk::transaction;
 use solana_vote_api::vote_state::MAX_LOCKOUT_HISTORY;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, RwLock};
 
 pub type Confirmations = usize;
 
-type RpcAccountSubscriptions =
-    RwLock<HashMap<Pubkey, HashMap<SubscriptionId, (Sink<Account>, Confirmations)>>>;
-type RpcProgramSubscriptions =
-    RwLock<HashMap<Pubkey, HashMap<SubscriptionId, (Sink<(String, Account)>, Confirmations)>>>;
-type RpcSignatureSubscriptions = RwLock<
-    HashMap<Signature, HashMap<SubscriptionId, (Sink<transaction::Result<()>>, Confirmations)>>,
->;
+/// How many notifications a subscription holds for a client that isn't reading them, beyond
+/// what its connection buffers. The oldest are dropped first.
+pub const MAX_QUEUED_NOTIFICATIONS: usize = 256;
+
+/// A client that hasn't taken a notification for this long while some are queued for it is
+/// presumed gone, and its subscriptions are dropped
+pub const CLIENT_TIMEOUT_MS: u64 = 60_000;
+
+/// How committed the cluster must be to a bank before a subscription is notified from it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum Commitment {
+    /// The bank `confirmations` slots behind the latest bank the node voted on, which a fork
+    /// switch can still roll back
+    Processed,
+    /// The newest bank on the node's fork that a supermajority of the stake voted for
+    Confirmed,
+    /// The node's root bank, which can't be rolled back
+    Rooted,
+}
+
+impl Default for Commitment {
+    fn default() -> Self {
+        Commitment::Processed
+    }
+}
+
+struct Subscription<S> {
+    sink: Sink<S>,
+    confirmations: Confirmations,
+    commitment: Commitment,
+    /// The slot of the bank the subscription was last checked against
+    last_slot: Option<u64>,
+    /// The latest value the subscription saw, which it won't be notified of again
+    last_value: Option<S>,
+    /// Notifications the client's connection hasn't taken yet
+    pending: VecDeque<S>,
+    /// When the client last took a notification, or subscribed
+    last_delivery_ms: u64,
+}
+
+impl<S> Subscription<S>
+where
+    S: Clone + Serialize,
+{
+    fn new(sink: &Sink<S>, confirmations: Confirmations, commitment: Commitment) -> Self {
+        Self {
+            sink: sink.clone(),
+            confirmations,
+            commitment,
+            last_slot: None,
+            last_value: None,
+            pending: VecDeque::new(),
+            last_delivery_ms: timestamp(),
+        }
+    }
+
+    /// Queues `value` for the client and sends what its connection will take, without waiting
+    /// on a client that isn't reading
+    fn notify(&mut self, value: S) {
+        if self.pending.len() >= MAX_QUEUED_NOTIFICATIONS {
+            self.pending.pop_front();
+            inc_new_counter_info!("rpc-pubsub-notifications-dropped", 1);
+        }
+        self.pending.push_back(value);
+        self.flush();
+    }
+
+    /// Sends queued notifications until the client's connection stops taking them
+    fn flush(&mut self) {
+        while let Some(value) = self.pending.pop_front() {
+            let sink = &mut self.sink;
+            // Sending needs a task to park if the connection's buffer is full
+            let sent = future::lazy(|| sink.start_send(Ok(value))).wait();
+            match sent {
+                Ok(AsyncSink::Ready) => self.last_delivery_ms = timestamp(),
+                Ok(AsyncSink::NotReady(Ok(value))) => {
+                    self.pending.push_front(value);
+                    break;
+                }
+                Ok(AsyncSink::NotReady(Err(_))) => (),
+                Err(_) => {
+                    // The connection is closed, and its subscriptions are about to be removed
+                    self.pending.clear();
+                }
+            }
+        }
+    }
+
+    /// Whether the client stopped taking notifications so long ago that it's presumed gone
+    fn is_stalled(&self, now: u64) -> bool {
+        !self.pending.is_empty() && now.saturating_sub(self.last_delivery_ms) > CLIENT_TIMEOUT_MS
+    }
+}
+
+type SubscriptionMap<K, S> = HashMap<K, HashMap<SubscriptionId, Subscription<S>>>;
+type RpcAccountSubscriptions = RwLock<SubscriptionMap<Pubkey, Account>>;
+type RpcProgramSubscriptions = RwLock<SubscriptionMap<Pubkey, (String, Account)>>;
+type RpcSignatureSubscriptions = RwLock<SubscriptionMap<Signature, transaction::Result<()>>>;
+type RpcSlotsPurgedSubscriptions = RwLock<HashMap<SubscriptionId, Sink<SlotsPurgedInfo>>>;
+
+/// Slots the ledger no longer holds. Purging always starts at the lowest slot, so every slot
+/// below `minimum_ledger_slot` is gone.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotsPurgedInfo {
+    pub first_slot: u64,
+    pub last_slot: u64,
+    pub minimum_ledger_slot: u64,
+}
 
 fn add_subscription<K, S>(
-    subscriptions: &mut HashMap<K, HashMap<SubscriptionId, (Sink<S>, Confirmations)>>,
+    subscriptions: &mut SubscriptionMap<K, S>,
     hashmap_key: &K,
     confirmations: Option<Confirmations>,
+    commitment: Option<Commitment>,
     sub_id: &SubscriptionId,
     sink: &Sink<S>,
 ) where
     K: Eq + Hash + Clone + Copy,
-    S: Clone,
+    S: Clone + Serialize,
 {
     let confirmations = confirmations.unwrap_or(0);
     let confirmations = if confirmations > MAX_LOCKOUT_HISTORY {
@@ -41,22 +149,20 @@ fn add_subscription<K, S>(
     } else {
         confirmations
     };
-    if let Some(current_hashmap) = subscriptions.get_mut(hashmap_key) {
-        current_hashmap.insert(sub_id.clone(), (sink.clone(), confirmations));
-        return;
-    }
-    let mut hashmap = HashMap::new();
-    hashmap.insert(sub_id.clone(), (sink.clone(), confirmations));
-    subscriptions.insert(*hashmap_key, hashmap);
+    let subscription = Subscription::new(sink, confirmations, commitment.unwrap_or_default());
+    subscriptions
+        .entry(*hashmap_key)
+        .or_insert_with(HashMap::new)
+        .insert(sub_id.clone(), subscription);
 }
 
 fn remove_subscription<K, S>(
-    subscriptions: &mut HashMap<K, HashMap<SubscriptionId, (Sink<S>, Confirmations)>>,
+    subscriptions: &mut SubscriptionMap<K, S>,
     sub_id: &SubscriptionId,
 ) -> bool
 where
     K: Eq + Hash + Clone + Copy,
-    S: Clone,
+    S: Clone + Serialize,
 {
     let mut found = false;
     subscriptions.retain(|_, v| {
@@ -71,81 +177,250 @@ where
     found
 }
 
-fn check_confirmations_and_notify<K, S, F, N, X>(
-    subscriptions: &HashMap<K, HashMap<SubscriptionId, (Sink<S>, Confirmations)>>,
+/// Sends the notifications queued for slow clients, and drops the subscriptions of clients that
+/// stopped reading altogether. Returns the ids of the dropped subscriptions.
+fn flush_subscriptions<K, S>(
+    subscriptions: &mut SubscriptionMap<K, S>,
+    now: u64,
+) -> Vec<SubscriptionId>
+where
+    K: Eq + Hash + Clone + Copy,
+    S: Clone + Serialize,
+{
+    let mut stalled = vec![];
+    subscriptions.retain(|_, hashmap| {
+        hashmap.retain(|sub_id, subscription| {
+            subscription.flush();
+            if subscription.is_stalled(now) {
+                warn!(
+                    "dropping subscription {:?}, its client stopped reading notifications",
+                    sub_id
+                );
+                inc_new_counter_info!("rpc-pubsub-stalled-subscriptions", 1);
+                stalled.push(sub_id.clone());
+                false
+            } else {
+                true
+            }
+        });
+        !hashmap.is_empty()
+    });
+    stalled
+}
+
+/// The slot of the bank on the fork of `current_ancestors` that a subscription waiting for
+/// `commitment` is notified from, if the fork has reached it
+fn commitment_slot(
+    commitment: Commitment,
+    confirmations: Confirmations,
+    current_ancestors: &HashMap<u64, usize>,
+    root: u64,
+    block_commitment_cache: &BlockCommitmentCache,
+) -> Option<u64> {
+    let root = Some(root).filter(|root| current_ancestors.contains_key(root));
+    match commitment {
+        Commitment::Processed => current_ancestors
+            .iter()
+            .find(|(_, depth)| **depth == confirmations)
+            .map(|(slot, _)| *slot),
+        Commitment::Confirmed => {
+            let min_stake = block_commitment_cache.total_stake() as f64 * VOTE_THRESHOLD_SIZE;
+            current_ancestors
+                .keys()
+                .filter(|slot| root.map_or(true, |root| **slot >= root))
+                .filter(|slot| {
+                    block_commitment_cache
+                        .get_block_commitment(**slot)
+                        .map_or(false, |commitment| {
+                            commitment.get_confirmed_stake(1) as f64 > min_stake
+                        })
+                })
+                .max()
+                .cloned()
+                // Everything up to the root is confirmed
+                .or(root)
+        }
+        Commitment::Rooted => root,
+    }
+}
+
+/// Checks each subscription to `hashmap_key` against the bank its commitment calls for on the
+/// fork of `current_slot`
+fn check_commitment_and_notify<K, S, F, N, X>(
+    subscriptions: &mut SubscriptionMap<K, S>,
     hashmap_key: &K,
     current_slot: u64,
     bank_forks: &Arc<RwLock<BankForks>>,
+    block_commitment_cache: &RwLock<BlockCommitmentCache>,
     bank_method: F,
     notify: N,
 ) where
     K: Eq + Hash + Clone + Copy,
     S: Clone + Serialize,
-    F: Fn(&Bank, &K) -> X,
-    N: Fn(X, &Sink<S>, u64),
-    X: Clone + Serialize,
+    F: Fn(&Bank, Option<u64>, &K) -> X,
+    N: Fn(X, &mut Subscription<S>),
+    X: Clone,
 {
-    let current_ancestors = bank_forks
-        .read()
-        .unwrap()
-        .get(current_slot)
-        .unwrap()
-        .ancestors
-        .clone();
-    if let Some(hashmap) = subscriptions.get(hashmap_key) {
-        for (_bank_sub_id, (sink, confirmations)) in hashmap.iter() {
-            let desired_slot: Vec<u64> = current_ancestors
-                .iter()
-                .filter(|(_, &v)| v == *confirmations)
-                .map(|(k, _)| k)
-                .cloned()
-                .collect();
-            let root: Vec<u64> = current_ancestors
-                .iter()
-                .filter(|(_, &v)| v == 32)
-                .map(|(k, _)| k)
-                .cloned()
-                .collect();
-            let root = if root.len() == 1 { root[0] } else { 0 };
-            if desired_slot.len() == 1 {
-                let desired_bank = bank_forks
-                    .read()
-                    .unwrap()
-                    .get(desired_slot[0])
-                    .unwrap()
-                    .clone();
-                let result = bank_method(&desired_bank, hashmap_key);
-                notify(result, &sink, root);
-            }
+    let hashmap = match subscriptions.get_mut(hashmap_key) {
+        Some(hashmap) => hashmap,
+        None => return,
+    };
+    let bank_forks = bank_forks.read().unwrap();
+    let current_ancestors = &bank_forks.get(current_slot).unwrap().ancestors;
+    let root = bank_forks.root();
+    let block_commitment_cache = block_commitment_cache.read().unwrap();
+
+    // Subscriptions notified from the same bank, and last notified from the same bank, share a
+    // single look at it
+    let mut results: HashMap<(u64, Option<u64>), X> = HashMap::new();
+    for subscription in hashmap.values_mut() {
+        let desired_slot = commitment_slot(
+            subscription.commitment,
+            subscription.confirmations,
+            current_ancestors,
+            root,
+            &block_commitment_cache,
+        );
+        let desired_bank = match desired_slot.and_then(|slot| bank_forks.get(slot)) {
+            Some(desired_bank) => desired_bank,
+            None => continue,
+        };
+        // Nothing is new until the subscription's commitment reaches another bank
+        if subscription.last_slot == Some(desired_bank.slot()) {
+            continue;
         }
+        let last_slot = subscription.last_slot.replace(desired_bank.slot());
+
+        let result = results
+            .entry((desired_bank.slot(), last_slot))
+            .or_insert_with(|| bank_method(desired_bank, last_slot, hashmap_key))
+            .clone();
+        notify(result, subscription);
     }
 }
 
-fn notify_account<S>(result: Option<(S, u64)>, sink: &Sink<S>, root: u64)
-where
-    S: Clone + Serialize,
-{
-    if let Some((account, fork)) = result {
-        if fork >= root {
-            sink.notify(Ok(account)).wait().unwrap();
-        }
+/// The account as of `bank`, and whether `bank` modified it
+fn get_account_and_modified(
+    bank: &Bank,
+    _last_slot: Option<u64>,
+    pubkey: &Pubkey,
+) -> Option<(Account, bool)> {
+    let account = bank.get_account(pubkey)?;
+    Some((
+        account,
+        bank.get_account_modified_since_parent(pubkey).is_some(),
+    ))
+}
+
+fn notify_account(result: Option<(Account, bool)>, subscription: &mut Subscription<Account>) {
+    let (account, modified) = match result {
+        Some(result) => result,
+        None => return,
+    };
+    // A subscription that hasn't seen the account yet only hears of changes made in this bank;
+    // after that, of every change since the value it last saw, however many banks ago
+    let changed = match &subscription.last_value {
+        Some(last_account) => *last_account != account,
+        None => modified,
+    };
+    if changed {
+        subscription.notify(account.clone());
     }
+    subscription.last_value = Some(account);
 }
 
-fn notify_signature<S>(result: Option<S>, sink: &Sink<S>, _root: u64)
+fn notify_signature<S>(result: Option<S>, subscription: &mut Subscription<S>)
 where
     S: Clone + Serialize,
 {
+    // A signature is only notified once
+    if subscription.last_value.is_some() {
+        return;
+    }
     if let Some(result) = result {
-        sink.notify(Ok(result)).wait().unwrap();
+        subscription.notify(result.clone());
+        subscription.last_value = Some(result);
     }
 }
 
-fn notify_program(accounts: Vec<(Pubkey, Account)>, sink: &Sink<(String, Account)>, _root: u64) {
-    for (pubkey, account) in accounts.iter() {
-        sink.notify(Ok((pubkey.to_string(), account.clone())))
-            .wait()
-            .unwrap();
+/// Ends the signature subscriptions whose clients have taken their notification. Returns the ids
+/// of the ended subscriptions.
+fn remove_notified_signatures(
+    subscriptions: &mut SubscriptionMap<Signature, transaction::Result<()>>,
+) -> Vec<SubscriptionId> {
+    let mut notified = vec![];
+    subscriptions.retain(|_, hashmap| {
+        hashmap.retain(|sub_id, subscription| {
+            if subscription.last_value.is_none() || !subscription.pending.is_empty() {
+                true
+            } else {
+                notified.push(sub_id.clone());
+                false
+            }
+        });
+        !hashmap.is_empty()
+    });
+    notified
+}
+
+/// The program's accounts modified after bank `last_slot`, up to and including `bank`, oldest
+/// change first. A subscription whose commitment skips banks hears of the changes made in each
+/// of them. If the banks between can't be walked, because `last_slot` was squashed into the root
+/// or is on another fork, every account of the program is returned.
+fn get_program_accounts_modified_since(
+    bank: &Bank,
+    last_slot: Option<u64>,
+    program_id: &Pubkey,
+) -> Vec<(Pubkey, Account)> {
+    let last_slot = match last_slot {
+        Some(last_slot) => last_slot,
+        // A new subscription only hears of changes made in this bank
+        None => return bank.get_program_accounts_modified_since_parent(program_id),
+    };
+    let mut skipped = vec![];
+    let mut reached_last_slot = false;
+    for parent in bank.parents() {
+        if parent.slot() <= last_slot {
+            reached_last_slot = parent.slot() == last_slot;
+            break;
+        }
+        skipped.push(parent);
+    }
+    if !reached_last_slot {
+        return bank.get_program_accounts(program_id);
+    }
+
+    // Only the latest change to each account is of interest
+    let mut accounts: Vec<(Pubkey, Account)> = vec![];
+    let mut positions: HashMap<Pubkey, usize> = HashMap::new();
+    let modified = skipped
+        .iter()
+        .rev()
+        .flat_map(|parent| parent.get_program_accounts_modified_since_parent(program_id))
+        .chain(bank.get_program_accounts_modified_since_parent(program_id));
+    for (pubkey, account) in modified {
+        match positions.get(&pubkey) {
+            Some(position) => accounts[*position].1 = account,
+            None => {
+                positions.insert(pubkey, accounts.len());
+                accounts.push((pubkey, account));
+            }
+        }
+    }
+    accounts
+}
+
+fn notify_program(
+    accounts: Vec<(Pubkey, Account)>,
+    subscription: &mut Subscription<(String, Account)>,
+) {
+    for (pubkey, account) in accounts {
+        let notification = (pubkey.to_string(), account);
+        if subscription.last_value.as_ref() == Some(&notification) {
+            continue;
+        }
+        subscription.notify(notification.clone());
+        subscription.last_value = Some(notification);
     }
 }
 
@@ -153,32 +428,107 @@ pub struct RpcSubscriptions {
     account_subscriptions: RpcAccountSubscriptions,
     program_subscriptions: RpcProgramSubscriptions,
     signature_subscriptions: RpcSignatureSubscriptions,
+    slots_purged_subscriptions: RpcSlotsPurgedSubscriptions,
+    block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+    /// The subscriptions open on each connection, by the connection's id
+    connection_subscriptions: RwLock<HashMap<u64, HashSet<SubscriptionId>>>,
+    /// Connections whose clients stopped reading notifications, which are to be closed
+    stalled_connections: RwLock<HashSet<u64>>,
 }
 
 impl Default for RpcSubscriptions {
     fn default() -> Self {
+        Self::new(Arc::new(RwLock::new(BlockCommitmentCache::default())))
+    }
+}
+
+impl RpcSubscriptions {
+    pub fn new(block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>) -> Self {
         RpcSubscriptions {
             account_subscriptions: RpcAccountSubscriptions::default(),
             program_subscriptions: RpcProgramSubscriptions::default(),
             signature_subscriptions: RpcSignatureSubscriptions::default(),
+            slots_purged_subscriptions: RpcSlotsPurgedSubscriptions::default(),
+            block_commitment_cache,
+            connection_subscriptions: RwLock::default(),
+            stalled_connections: RwLock::default(),
         }
     }
-}
 
-impl RpcSubscriptions {
+    /// Counts `sub_id` against the subscriptions connection `connection_id` may have open.
+    /// Returns false, without counting it, if the connection already has `max_subscriptions`.
+    pub fn add_connection_subscription(
+        &self,
+        connection_id: u64,
+        sub_id: &SubscriptionId,
+        max_subscriptions: usize,
+    ) -> bool {
+        let mut connection_subscriptions = self.connection_subscriptions.write().unwrap();
+        let sub_ids = connection_subscriptions
+            .entry(connection_id)
+            .or_insert_with(HashSet::new);
+        if sub_ids.len() >= max_subscriptions {
+            return false;
+        }
+        sub_ids.insert(sub_id.clone());
+        true
+    }
+
+    /// Frees the slot `sub_id` took on its connection, whether or not the subscription still
+    /// exists
+    pub fn release_subscription_id(&self, sub_id: &SubscriptionId) {
+        self.release_subscription_ids(&[sub_id.clone()]);
+    }
+
+    /// Frees the slots `sub_ids` took on their connections. Returns the connections they were on.
+    fn release_subscription_ids(&self, sub_ids: &[SubscriptionId]) -> HashSet<u64> {
+        let mut connections = HashSet::new();
+        if sub_ids.is_empty() {
+            return connections;
+        }
+        let mut connection_subscriptions = self.connection_subscriptions.write().unwrap();
+        connection_subscriptions.retain(|connection_id, connection_sub_ids| {
+            for sub_id in sub_ids {
+                if connection_sub_ids.remove(sub_id) {
+                    connections.insert(*connection_id);
+                }
+            }
+            !connection_sub_ids.is_empty()
+        });
+        connections
+    }
+
+    /// Forgets a closed connection
+    pub fn remove_connection(&self, connection_id: u64) {
+        self.connection_subscriptions
+            .write()
+            .unwrap()
+            .remove(&connection_id);
+        self.stalled_connections
+            .write()
+            .unwrap()
+            .remove(&connection_id);
+    }
+
+    /// The connections whose clients stopped reading notifications since the last call
+    pub fn take_stalled_connections(&self) -> Vec<u64> {
+        self.stalled_connections.write().unwrap().drain().collect()
+    }
+
     pub fn check_account(
         &self,
         pubkey: &Pubkey,
         current_slot: u64,
         bank_forks: &Arc<RwLock<BankForks>>,
     ) {
-        let subscriptions = self.account_subscriptions.read().unwrap();
-        check_confirmations_and_notify(
-            &subscriptions,
+        let mut subscriptions = self.account_subscriptions.write().unwrap();
+        check_commitment_and_notify(
+            &mut subscriptions,
             pubkey,
             current_slot,
             bank_forks,
-            Bank::get_account_modified_since_parent,
+            &self.block_commitment_cache,
+            get_account_and_modified,
             notify_account,
         );
     }
@@ -189,13 +539,14 @@ impl RpcSubscriptions {
         current_slot: u64,
         bank_forks: &Arc<RwLock<BankForks>>,
     ) {
-        let subscriptions = self.program_subscriptions.write().unwrap();
-        check_confirmations_and_notify(
-            &subscriptions,
+        let mut subscriptions = self.program_subscriptions.write().unwrap();
+        check_commitment_and_notify(
+            &mut subscriptions,
             program_id,
             current_slot,
             bank_forks,
-            Bank::get_program_accounts_modified_since_parent,
+            &self.block_commitment_cache,
+            get_program_accounts_modified_since,
             notify_program,
         );
     }
@@ -207,26 +558,37 @@ impl RpcSubscriptions {
         bank_forks: &Arc<RwLock<BankForks>>,
     ) {
         let mut subscriptions = self.signature_subscriptions.write().unwrap();
-        check_confirmations_and_notify(
-            &subscriptions,
+        check_commitment_and_notify(
+            &mut subscriptions,
             signature,
             current_slot,
             bank_forks,
-            Bank::get_signature_status,
+            &self.block_commitment_cache,
+            |bank, _last_slot, signature| bank.get_signature_status(signature),
             notify_signature,
         );
-        subscriptions.remove(&signature);
+        let notified = remove_notified_signatures(&mut subscriptions);
+        drop(subscriptions);
+        self.release_subscription_ids(&notified);
     }
 
     pub fn add_account_subscription(
         &self,
         pubkey: &Pubkey,
         confirmations: Option<Confirmations>,
+        commitment: Option<Commitment>,
         sub_id: &SubscriptionId,
         sink: &Sink<Account>,
     ) {
         let mut subscriptions = self.account_subscriptions.write().unwrap();
-        add_subscription(&mut subscriptions, pubkey, confirmations, sub_id, sink);
+        add_subscription(
+            &mut subscriptions,
+            pubkey,
+            confirmations,
+            commitment,
+            sub_id,
+            sink,
+        );
     }
 
     pub fn remove_account_subscription(&self, id: &SubscriptionId) -> bool {
@@ -238,11 +600,19 @@ impl RpcSubscriptions {
         &self,
         program_id: &Pubkey,
         confirmations: Option<Confirmations>,
+        commitment: Option<Commitment>,
         sub_id: &SubscriptionId,
         sink: &Sink<(String, Account)>,
     ) {
         let mut subscriptions = self.program_subscriptions.write().unwrap();
-        add_subscription(&mut subscriptions, program_id, confirmations, sub_id, sink);
+        add_subscription(
+            &mut subscriptions,
+            program_id,
+            confirmations,
+            commitment,
+            sub_id,
+            sink,
+        );
     }
 
     pub fn remove_program_subscription(&self, id: &SubscriptionId) -> bool {
@@ -254,11 +624,19 @@ impl RpcSubscriptions {
         &self,
         signature: &Signature,
         confirmations: Option<Confirmations>,
+        commitment: Option<Commitment>,
         sub_id: &SubscriptionId,
         sink: &Sink<transaction::Result<()>>,
     ) {
         let mut subscriptions = self.signature_subscriptions.write().unwrap();
-        add_subscription(&mut subscriptions, signature, confirmations, sub_id, sink);
+        add_subscription(
+            &mut subscriptions,
+            signature,
+            confirmations,
+            commitment,
+            sub_id,
+            sink,
+        );
     }
 
     pub fn remove_signature_subscription(&self, id: &SubscriptionId) -> bool {
@@ -266,6 +644,28 @@ impl RpcSubscriptions {
         remove_subscription(&mut subscriptions, id)
     }
 
+    pub fn add_slots_purged_subscription(
+        &self,
+        sub_id: &SubscriptionId,
+        sink: &Sink<SlotsPurgedInfo>,
+    ) {
+        let mut subscriptions = self.slots_purged_subscriptions.write().unwrap();
+        subscriptions.insert(sub_id.clone(), sink.clone());
+    }
+
+    pub fn remove_slots_purged_subscription(&self, id: &SubscriptionId) -> bool {
+        let mut subscriptions = self.slots_purged_subscriptions.write().unwrap();
+        subscriptions.remove(id).is_some()
+    }
+
+    /// Notify subscribers that the ledger purged `slots_purged`
+    pub fn notify_slots_purged(&self, slots_purged: &SlotsPurgedInfo) {
+        let subscriptions = self.slots_purged_subscriptions.read().unwrap();
+        for (_, sink) in subscriptions.iter() {
+            sink.notify(Ok(slots_purged.clone())).wait().unwrap();
+        }
+    }
+
     /// Notify subscribers of changes to any accounts or new signatures since
     /// the bank's last checkpoint.
     pub fn notify_subscribers(&self, current_slot: u64, bank_forks: &Arc<RwLock<BankForks>>) {
@@ -292,6 +692,25 @@ impl RpcSubscriptions {
         for signature in &signatures {
             self.check_signature(signature, current_slot, bank_forks);
         }
+
+        let now = timestamp();
+        let mut stalled =
+            flush_subscriptions(&mut self.account_subscriptions.write().unwrap(), now);
+        stalled.extend(flush_subscriptions(
+            &mut self.program_subscriptions.write().unwrap(),
+            now,
+        ));
+        let notified = {
+            let mut signature_subscriptions = self.signature_subscriptions.write().unwrap();
+            stalled.extend(flush_subscriptions(&mut signature_subscriptions, now));
+            remove_notified_signatures(&mut signature_subscriptions)
+        };
+        self.release_subscription_ids(&notified);
+        let stalled_connections = self.release_subscription_ids(&stalled);
+        self.stalled_connections
+            .write()
+            .unwrap()
+            .extend(stalled_connections);
     }
 }
 
@@ -305,6 +724,35 @@ mod tests {
     use solana_sdk::system_transaction;
     use tokio::prelude::{Async, Stream};
 
+    /// The next notification sent to a test subscriber, if there is one
+    fn next_notification<R: Stream<Item = String>>(receiver: &mut R) -> Option<String> {
+        match future::lazy(|| receiver.poll()).wait() {
+            Ok(Async::Ready(Some(response))) => Some(response),
+            _ => None,
+        }
+    }
+
+    /// Bank forks rooted at slot 0 with a transfer to `to` in slot 1. Returns the bank forks, the
+    /// mint keypair and the signature of the transfer.
+    fn new_bank_forks_with_transfer(
+        to: &Pubkey,
+        lamports: u64,
+    ) -> (Arc<RwLock<BankForks>>, Keypair, Signature) {
+        let GenesisBlockInfo {
+            genesis_block,
+            mint_keypair,
+            ..
+        } = create_genesis_block(100);
+        let bank0 = Bank::new(&genesis_block);
+        let blockhash = bank0.last_blockhash();
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(0, bank0)));
+        let bank1 = Bank::new_from_parent(&bank_forks.read().unwrap()[0], &Pubkey::default(), 1);
+        let tx = system_transaction::transfer(&mint_keypair, to, lamports, blockhash);
+        bank1.process_transaction(&tx).unwrap();
+        bank_forks.write().unwrap().insert(bank1);
+        (bank_forks, mint_keypair, tx.signatures[0])
+    }
+
     #[test]
     fn test_check_account_subscribe() {
         let GenesisBlockInfo {
@@ -337,7 +785,7 @@ mod tests {
         let sub_id = SubscriptionId::Number(0 as u64);
         let sink = subscriber.assign_id(sub_id.clone()).unwrap();
         let subscriptions = RpcSubscriptions::default();
-        subscriptions.add_account_subscription(&alice.pubkey(), None, &sub_id, &sink);
+        subscriptions.add_account_subscription(&alice.pubkey(), None, None, &sub_id, &sink);
 
         assert!(subscriptions
             .account_subscriptions
@@ -348,7 +796,9 @@ mod tests {
         subscriptions.check_account(&alice.pubkey(), 0, &bank_forks);
         let string = transport_receiver.poll();
         if let Async::Ready(Some(response)) = string.unwrap() {
-            let expected = format!(r#"{{"jsonrpc":"2.0","method":"accountNotification","params":{{"result":{{"data":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0],"executable":false,"lamports":1,"owner":[2,203,81,223,225,24,34,35,203,214,138,130,144,208,35,77,63,16,87,51,47,198,115,123,98,188,19,160,0,0,0,0]}},"subscription":0}}}}"#);
+            let expected = format!(
+                r#"{{"jsonrpc":"2.0","method":"accountNotification","params":{{"result":{{"data":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0],"executable":false,"lamports":1,"owner":[2,203,81,223,225,24,34,35,203,214,138,130,144,208,35,77,63,16,87,51,47,198,115,123,98,188,19,160,0,0,0,0]}},"subscription":0}}}}"#
+            );
             assert_eq!(expected, response);
         }
 
@@ -360,6 +810,54 @@ mod tests {
             .contains_key(&alice.pubkey()));
     }
 
+    #[test]
+    fn test_check_account_subscribe_multiple_watchers() {
+        let GenesisBlockInfo {
+            genesis_block,
+            mint_keypair,
+            ..
+        } = create_genesis_block(100);
+        let bank = Bank::new(&genesis_block);
+        let blockhash = bank.last_blockhash();
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(0, bank)));
+        let alice = Keypair::new();
+        let tx = system_transaction::transfer(&mint_keypair, &alice.pubkey(), 20, blockhash);
+        bank_forks
+            .write()
+            .unwrap()
+            .get(0)
+            .unwrap()
+            .process_transaction(&tx)
+            .unwrap();
+
+        let subscriptions = RpcSubscriptions::default();
+        let mut transport_receivers = vec![];
+        for i in 0..3 {
+            let (subscriber, _id_receiver, transport_receiver) =
+                Subscriber::new_test("accountNotification");
+            let sub_id = SubscriptionId::Number(i as u64);
+            let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+            subscriptions.add_account_subscription(&alice.pubkey(), None, None, &sub_id, &sink);
+            transport_receivers.push((i, transport_receiver));
+        }
+        assert_eq!(
+            subscriptions.account_subscriptions.read().unwrap()[&alice.pubkey()].len(),
+            3
+        );
+
+        subscriptions.check_account(&alice.pubkey(), 0, &bank_forks);
+        for (i, mut transport_receiver) in transport_receivers {
+            let string = transport_receiver.poll();
+            if let Async::Ready(Some(response)) = string.unwrap() {
+                let expected = format!(
+                    r#"{{"jsonrpc":"2.0","method":"accountNotification","params":{{"result":{{"data":[],"executable":false,"lamports":20,"owner":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}},"subscription":{}}}}}"#,
+                    i
+                );
+                assert_eq!(expected, response);
+            }
+        }
+    }
+
     #[test]
     fn test_check_program_subscribe() {
         let GenesisBlockInfo {
@@ -392,7 +890,13 @@ mod tests {
         let sub_id = SubscriptionId::Number(0 as u64);
         let sink = subscriber.assign_id(sub_id.clone()).unwrap();
         let subscriptions = RpcSubscriptions::default();
-        subscriptions.add_program_subscription(&solana_budget_api::id(), None, &sub_id, &sink);
+        subscriptions.add_program_subscription(
+            &solana_budget_api::id(),
+            None,
+            None,
+            &sub_id,
+            &sink,
+        );
 
         assert!(subscriptions
             .program_subscriptions
@@ -403,7 +907,10 @@ mod tests {
         subscriptions.check_program(&solana_budget_api::id(), 0, &bank_forks);
         let string = transport_receiver.poll();
         if let Async::Ready(Some(response)) = string.unwrap() {
-            let expected = format!(r#"{{"jsonrpc":"2.0","method":"programNotification","params":{{"result":["{:?}",{{"data":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0],"executable":false,"lamports":1,"owner":[2,203,81,223,225,24,34,35,203,214,138,130,144,208,35,77,63,16,87,51,47,198,115,123,98,188,19,160,0,0,0,0]}}],"subscription":0}}}}"#, alice.pubkey());
+            let expected = format!(
+                r#"{{"jsonrpc":"2.0","method":"programNotification","params":{{"result":["{:?}",{{"data":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0],"executable":false,"lamports":1,"owner":[2,203,81,223,225,24,34,35,203,214,138,130,144,208,35,77,63,16,87,51,47,198,115,123,98,188,19,160,0,0,0,0]}}],"subscription":0}}}}"#,
+                alice.pubkey()
+            );
             assert_eq!(expected, response);
         }
 
@@ -440,7 +947,7 @@ mod tests {
         let sub_id = SubscriptionId::Number(0 as u64);
         let sink = subscriber.assign_id(sub_id.clone()).unwrap();
         let subscriptions = RpcSubscriptions::default();
-        subscriptions.add_signature_subscription(&signature, None, &sub_id, &sink);
+        subscriptions.add_signature_subscription(&signature, None, None, &sub_id, &sink);
 
         assert!(subscriptions
             .signature_subscriptions
@@ -454,7 +961,10 @@ mod tests {
             let expected_res: Option<transaction::Result<()>> = Some(Ok(()));
             let expected_res_str =
                 serde_json::to_string(&serde_json::to_value(expected_res).unwrap()).unwrap();
-            let expected = format!(r#"{{"jsonrpc":"2.0","method":"signatureNotification","params":{{"result":{},"subscription":0}}}}"#, expected_res_str);
+            let expected = format!(
+                r#"{{"jsonrpc":"2.0","method":"signatureNotification","params":{{"result":{},"subscription":0}}}}"#,
+                expected_res_str
+            );
             assert_eq!(expected, response);
         }
 
@@ -465,4 +975,285 @@ mod tests {
             .unwrap()
             .contains_key(&signature));
     }
+
+    #[test]
+    fn test_notify_slots_purged() {
+        let (subscriber, _id_receiver, mut transport_receiver) =
+            Subscriber::new_test("slotsPurgedNotification");
+        let sub_id = SubscriptionId::Number(0 as u64);
+        let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+        let subscriptions = RpcSubscriptions::default();
+        subscriptions.add_slots_purged_subscription(&sub_id, &sink);
+
+        assert!(subscriptions
+            .slots_purged_subscriptions
+            .read()
+            .unwrap()
+            .contains_key(&sub_id));
+
+        subscriptions.notify_slots_purged(&SlotsPurgedInfo {
+            first_slot: 0,
+            last_slot: 40,
+            minimum_ledger_slot: 41,
+        });
+        let string = transport_receiver.poll();
+        if let Async::Ready(Some(response)) = string.unwrap() {
+            let expected = r#"{"jsonrpc":"2.0","method":"slotsPurgedNotification","params":{"result":{"firstSlot":0,"lastSlot":40,"minimumLedgerSlot":41},"subscription":0}}"#;
+            assert_eq!(expected, response);
+        }
+
+        assert!(subscriptions.remove_slots_purged_subscription(&sub_id));
+        assert!(!subscriptions
+            .slots_purged_subscriptions
+            .read()
+            .unwrap()
+            .contains_key(&sub_id));
+    }
+
+    #[test]
+    fn test_check_account_subscribe_deduplicates() {
+        let alice = Keypair::new();
+        let (bank_forks, mint_keypair, _signature) =
+            new_bank_forks_with_transfer(&alice.pubkey(), 20);
+        let (subscriber, _id_receiver, mut transport_receiver) =
+            Subscriber::new_test("accountNotification");
+        let sub_id = SubscriptionId::Number(0 as u64);
+        let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+        let subscriptions = RpcSubscriptions::default();
+        subscriptions.add_account_subscription(&alice.pubkey(), None, None, &sub_id, &sink);
+
+        subscriptions.check_account(&alice.pubkey(), 1, &bank_forks);
+        assert!(next_notification(&mut transport_receiver).is_some());
+        // The same bank doesn't notify twice
+        subscriptions.check_account(&alice.pubkey(), 1, &bank_forks);
+        assert!(next_notification(&mut transport_receiver).is_none());
+
+        // A bank that leaves the account as it was doesn't notify either
+        let bank1 = bank_forks.read().unwrap()[1].clone();
+        let bank2 = Bank::new_from_parent(&bank1, &Pubkey::default(), 2);
+        let tx =
+            system_transaction::transfer(&mint_keypair, &alice.pubkey(), 0, bank2.last_blockhash());
+        bank2.process_transaction(&tx).unwrap();
+        bank_forks.write().unwrap().insert(bank2);
+        subscriptions.check_account(&alice.pubkey(), 2, &bank_forks);
+        assert!(next_notification(&mut transport_receiver).is_none());
+
+        let bank2 = bank_forks.read().unwrap()[2].clone();
+        let bank3 = Bank::new_from_parent(&bank2, &Pubkey::default(), 3);
+        let tx =
+            system_transaction::transfer(&mint_keypair, &alice.pubkey(), 5, bank3.last_blockhash());
+        bank3.process_transaction(&tx).unwrap();
+        bank_forks.write().unwrap().insert(bank3);
+        subscriptions.check_account(&alice.pubkey(), 3, &bank_forks);
+        let response = next_notification(&mut transport_receiver).unwrap();
+        assert!(response.contains(r#""lamports":25"#));
+    }
+
+    #[test]
+    fn test_check_account_subscribe_commitment() {
+        let alice = Keypair::new();
+        let (bank_forks, _mint_keypair, _signature) =
+            new_bank_forks_with_transfer(&alice.pubkey(), 20);
+        let block_commitment_cache = Arc::new(RwLock::new(BlockCommitmentCache::default()));
+        let subscriptions = RpcSubscriptions::new(block_commitment_cache.clone());
+        let mut transport_receivers = vec![];
+        for (i, commitment) in [Commitment::Confirmed, Commitment::Rooted]
+            .iter()
+            .enumerate()
+        {
+            let (subscriber, _id_receiver, transport_receiver) =
+                Subscriber::new_test("accountNotification");
+            let sub_id = SubscriptionId::Number(i as u64);
+            let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+            subscriptions.add_account_subscription(
+                &alice.pubkey(),
+                None,
+                Some(*commitment),
+                &sub_id,
+                &sink,
+            );
+            transport_receivers.push(transport_receiver);
+        }
+
+        // Slot 1 is neither confirmed nor rooted, so its transfer waits
+        subscriptions.check_account(&alice.pubkey(), 1, &bank_forks);
+        assert!(next_notification(&mut transport_receivers[0]).is_none());
+        assert!(next_notification(&mut transport_receivers[1]).is_none());
+
+        {
+            let mut block_commitment_cache = block_commitment_cache.write().unwrap();
+            block_commitment_cache.set_total_stake(10);
+            block_commitment_cache.record_vote(Pubkey::new_rand(), vec![0, 1], 10);
+            block_commitment_cache.aggregate();
+        }
+        subscriptions.check_account(&alice.pubkey(), 1, &bank_forks);
+        assert!(next_notification(&mut transport_receivers[0]).is_some());
+        assert!(next_notification(&mut transport_receivers[1]).is_none());
+
+        bank_forks.write().unwrap().set_root(1);
+        subscriptions.check_account(&alice.pubkey(), 1, &bank_forks);
+        assert!(next_notification(&mut transport_receivers[0]).is_none());
+        assert!(next_notification(&mut transport_receivers[1]).is_some());
+    }
+
+    #[test]
+    fn test_get_program_accounts_modified_since() {
+        let GenesisBlockInfo {
+            genesis_block,
+            mint_keypair,
+            ..
+        } = create_genesis_block(100);
+        let bank0 = Arc::new(Bank::new(&genesis_block));
+        let bank1 = Arc::new(Bank::new_from_parent(&bank0, &Pubkey::default(), 1));
+        let alice = Keypair::new();
+        let tx = system_transaction::create_account(
+            &mint_keypair,
+            &alice.pubkey(),
+            bank1.last_blockhash(),
+            1,
+            16,
+            &solana_budget_api::id(),
+        );
+        bank1.process_transaction(&tx).unwrap();
+        let bank2 = Bank::new_from_parent(&bank1, &Pubkey::default(), 2);
+        let program_id = solana_budget_api::id();
+
+        // A new subscription only hears of this bank's changes
+        assert!(get_program_accounts_modified_since(&bank2, None, &program_id).is_empty());
+        // One that skipped a bank hears of its changes too
+        let accounts = get_program_accounts_modified_since(&bank2, Some(0), &program_id);
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].0, alice.pubkey());
+        assert!(get_program_accounts_modified_since(&bank2, Some(1), &program_id).is_empty());
+        // A bank that's not an ancestor can't be diffed against, so everything is returned
+        assert_eq!(
+            get_program_accounts_modified_since(&bank2, Some(3), &program_id).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_check_signature_subscribe_rooted() {
+        let alice = Keypair::new();
+        let (bank_forks, _mint_keypair, signature) =
+            new_bank_forks_with_transfer(&alice.pubkey(), 20);
+        let (subscriber, _id_receiver, mut transport_receiver) =
+            Subscriber::new_test("signatureNotification");
+        let sub_id = SubscriptionId::Number(0 as u64);
+        let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+        let subscriptions = RpcSubscriptions::default();
+        subscriptions.add_signature_subscription(
+            &signature,
+            None,
+            Some(Commitment::Rooted),
+            &sub_id,
+            &sink,
+        );
+
+        // The subscription waits for the transaction's slot to be rooted
+        subscriptions.check_signature(&signature, 1, &bank_forks);
+        assert!(next_notification(&mut transport_receiver).is_none());
+        assert!(subscriptions
+            .signature_subscriptions
+            .read()
+            .unwrap()
+            .contains_key(&signature));
+
+        bank_forks.write().unwrap().set_root(1);
+        subscriptions.check_signature(&signature, 1, &bank_forks);
+        assert!(next_notification(&mut transport_receiver).is_some());
+        assert!(!subscriptions
+            .signature_subscriptions
+            .read()
+            .unwrap()
+            .contains_key(&signature));
+    }
+
+    #[test]
+    fn test_subscription_drops_oldest_notifications() {
+        let (subscriber, _id_receiver, mut transport_receiver) =
+            Subscriber::new_test("accountNotification");
+        let sink = subscriber
+            .assign_id(SubscriptionId::Number(0 as u64))
+            .unwrap();
+        let mut subscription = Subscription::new(&sink, 0, Commitment::Processed);
+
+        // The client doesn't read while many more notifications than can be queued are sent
+        let num_notifications = MAX_QUEUED_NOTIFICATIONS as u64 * 4;
+        for lamports in 0..num_notifications {
+            subscription.notify(Account::new(lamports, 0, &Pubkey::default()));
+        }
+        assert_eq!(subscription.pending.len(), MAX_QUEUED_NOTIFICATIONS);
+        assert!(!subscription.is_stalled(subscription.last_delivery_ms + CLIENT_TIMEOUT_MS));
+        assert!(subscription.is_stalled(subscription.last_delivery_ms + CLIENT_TIMEOUT_MS + 1));
+
+        let mut responses = vec![];
+        while !subscription.pending.is_empty() {
+            while let Some(response) = next_notification(&mut transport_receiver) {
+                responses.push(response);
+            }
+            subscription.flush();
+        }
+        while let Some(response) = next_notification(&mut transport_receiver) {
+            responses.push(response);
+        }
+        // The oldest were dropped, the newest all arrived
+        assert!(responses.len() < num_notifications as usize);
+        assert!(responses.len() >= MAX_QUEUED_NOTIFICATIONS);
+        let expected_lamports = format!(r#""lamports":{}"#, num_notifications - 1);
+        assert!(responses.last().unwrap().contains(&expected_lamports));
+        assert!(!subscription.is_stalled(timestamp() + CLIENT_TIMEOUT_MS + 1));
+    }
+
+    #[test]
+    fn test_stalled_subscription_frees_its_connection_slot() {
+        let GenesisBlockInfo { genesis_block, .. } = create_genesis_block(100);
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(0, Bank::new(&genesis_block))));
+        let subscriptions = RpcSubscriptions::default();
+        let pubkey = Pubkey::new_rand();
+        let connection_id = 7;
+
+        let (subscriber, _id_receiver, _transport_receiver) =
+            Subscriber::new_test("accountNotification");
+        let sub_id = SubscriptionId::Number(0 as u64);
+        let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+        assert!(subscriptions.add_connection_subscription(connection_id, &sub_id, 1));
+        subscriptions.add_account_subscription(&pubkey, None, None, &sub_id, &sink);
+        assert!(!subscriptions.add_connection_subscription(
+            connection_id,
+            &SubscriptionId::Number(1 as u64),
+            1
+        ));
+
+        // The client stops reading while notifications are queued for it
+        {
+            let mut account_subscriptions = subscriptions.account_subscriptions.write().unwrap();
+            let subscription = account_subscriptions
+                .get_mut(&pubkey)
+                .unwrap()
+                .get_mut(&sub_id)
+                .unwrap();
+            for lamports in 0..MAX_QUEUED_NOTIFICATIONS as u64 * 4 {
+                subscription.notify(Account::new(lamports, 0, &Pubkey::default()));
+            }
+            subscription.last_delivery_ms = 0;
+        }
+        subscriptions.notify_subscribers(0, &bank_forks);
+
+        assert!(subscriptions
+            .account_subscriptions
+            .read()
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            subscriptions.take_stalled_connections(),
+            vec![connection_id]
+        );
+        assert!(subscriptions.take_stalled_connections().is_empty());
+        assert!(subscriptions.add_connection_subscription(
+            connection_id,
+            &SubscriptionId::Number(1 as u64),
+            1
+        ));
+    }
 }
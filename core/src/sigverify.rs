@@ -11,9 +11,10 @@ use crate::result::Result;
 use bincode::serialized_size;
 use rayon::ThreadPool;
 use solana_metrics::inc_new_counter_debug;
+use solana_sdk::hash::Hash;
 use solana_sdk::message::MessageHeader;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::short_vec::decode_len;
+use solana_sdk::short_vec::{decode_len, ShortU16};
 use solana_sdk::signature::Signature;
 #[cfg(test)]
 use solana_sdk::transaction::Transaction;
@@ -25,6 +26,11 @@ use std::os::raw::{c_int, c_uint};
 #[cfg(feature = "cuda")]
 use core::ffi::c_void;
 
+#[cfg(feature = "cuda")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "cuda")]
+use std::sync::Once;
+
 pub const NUM_THREADS: u32 = 10;
 use std::cell::RefCell;
 
@@ -35,6 +41,35 @@ thread_local!(static PAR_THREAD_POOL: RefCell<ThreadPool> = RefCell::new(rayon::
 
 pub type TxOffset = PinnedVec<u32>;
 
+/// Why sigverify marked a packet to be discarded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscardReason {
+    /// The packet doesn't hold a well formed transaction
+    Malformed,
+    /// A signature of the packet's transaction doesn't verify
+    InvalidSignature,
+}
+
+/// The number of packets sigverify marked to be discarded, by reason
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DiscardStats {
+    pub malformed: usize,
+    pub invalid_signature: usize,
+}
+
+impl DiscardStats {
+    pub fn total(&self) -> usize {
+        self.malformed + self.invalid_signature
+    }
+
+    fn add(&mut self, reason: DiscardReason) {
+        match reason {
+            DiscardReason::Malformed => self.malformed += 1,
+            DiscardReason::InvalidSignature => self.invalid_signature += 1,
+        }
+    }
+}
+
 type TxOffsets = (TxOffset, TxOffset, TxOffset, TxOffset, Vec<Vec<u32>>);
 
 #[cfg(feature = "cuda")]
@@ -95,6 +130,12 @@ pub fn init() {
     // stub
 }
 
+/// Whether `init` set up a GPU to verify on
+#[cfg(not(feature = "cuda"))]
+pub fn gpu_ready() -> bool {
+    false
+}
+
 fn verify_packet(packet: &Packet) -> u8 {
     let (sig_len, sig_start, msg_start, pubkey_start) = get_packet_offsets(packet, 0);
     let mut sig_start = sig_start as usize;
@@ -236,15 +277,118 @@ pub fn ed25519_verify_disabled(batches: &[Packets]) -> Vec<Vec<u8>> {
     rv
 }
 
+fn decode_len_checked(bytes: &[u8]) -> Option<(usize, usize)> {
+    let short_len: ShortU16 = bincode::deserialize(bytes).ok()?;
+    let num_bytes = serialized_size(&short_len).ok()? as usize;
+    Some((short_len.0 as usize, num_bytes))
+}
+
+/// The first signature of the packet's transaction, which identifies the transaction, or None if
+/// the packet doesn't hold one
+pub fn packet_signature(packet: &Packet) -> Option<Signature> {
+    let data = packet.data.get(..packet.meta.size)?;
+    let (sig_len, sig_size) = decode_len_checked(data)?;
+    if sig_len == 0 {
+        return None;
+    }
+    data.get(sig_size..sig_size + size_of::<Signature>())
+        .map(Signature::new)
+}
+
+/// The number of signatures the packet's transaction requires and whether it only holds vote
+/// instructions, or None if the packet doesn't hold a well formed transaction
+pub fn parse_packet_metadata(packet: &Packet) -> Option<(u8, bool)> {
+    let data = packet.data.get(..packet.meta.size)?;
+    let (sig_len, sig_size) = decode_len_checked(data)?;
+    let msg_start = sig_size.checked_add(sig_len.checked_mul(size_of::<Signature>())?)?;
+    let message = data.get(msg_start..)?;
+
+    let num_required_signatures = *message.get(0)?;
+    if num_required_signatures as usize != sig_len {
+        return None;
+    }
+    let mut offset = serialized_size(&MessageHeader::default()).unwrap() as usize;
+    let (num_keys, size) = decode_len_checked(message.get(offset..)?)?;
+    let keys_start = offset + size;
+    offset = keys_start + num_keys * size_of::<Pubkey>() + size_of::<Hash>();
+
+    let (num_instructions, size) = decode_len_checked(message.get(offset..)?)?;
+    offset += size;
+    let vote_program_id = solana_vote_api::id();
+    let mut is_vote = num_instructions > 0;
+    for _ in 0..num_instructions {
+        let program_id_index = *message.get(offset)? as usize;
+        offset += 1;
+        if program_id_index >= num_keys {
+            return None;
+        }
+        let program_id_start = keys_start + program_id_index * size_of::<Pubkey>();
+        is_vote &= message.get(program_id_start..program_id_start + size_of::<Pubkey>())?
+            == vote_program_id.as_ref();
+        // The instruction's account indexes, then its data
+        for _ in 0..2 {
+            let (len, size) = decode_len_checked(message.get(offset..)?)?;
+            offset += size + len;
+        }
+    }
+    if offset > message.len() {
+        return None;
+    }
+    Some((num_required_signatures, is_vote))
+}
+
+/// Fills in the metadata of the packets in `batches`, and marks the ones to be discarded:
+/// those that don't hold a well formed transaction, and those `verified`, as returned by
+/// `ed25519_verify`, found invalid
+pub fn mark_packets(batches: &mut [Packets], verified: &[Vec<u8>]) -> DiscardStats {
+    let mut stats = DiscardStats::default();
+    for (packets, verified) in batches.iter_mut().zip(verified) {
+        for (packet, verified) in packets.packets.iter_mut().zip(verified) {
+            let reason = match parse_packet_metadata(packet) {
+                Some((num_required_signatures, is_vote)) => {
+                    packet.meta.num_required_signatures = num_required_signatures;
+                    packet.meta.is_vote = is_vote;
+                    if *verified == 0 {
+                        Some(DiscardReason::InvalidSignature)
+                    } else {
+                        None
+                    }
+                }
+                None => Some(DiscardReason::Malformed),
+            };
+            packet.meta.discard = reason.is_some();
+            if let Some(reason) = reason {
+                stats.add(reason);
+            }
+        }
+    }
+    stats
+}
+
+#[cfg(feature = "cuda")]
+static GPU_INIT: Once = Once::new();
+#[cfg(feature = "cuda")]
+static GPU_READY: AtomicBool = AtomicBool::new(false);
+
+/// Sets up the CUDA context shared by every GPU verification of the process. Nodes without a
+/// usable card verify on the CPU instead.
 #[cfg(feature = "cuda")]
 pub fn init() {
-    unsafe {
+    GPU_INIT.call_once(|| unsafe {
         ed25519_set_verbose(true);
-        if !ed25519_init() {
-            panic!("ed25519_init() failed");
+        if ed25519_init() {
+            GPU_READY.store(true, Ordering::Relaxed);
+        } else {
+            warn!("ed25519_init() failed, verifying on the CPU");
         }
         ed25519_set_verbose(false);
-    }
+    });
+}
+
+/// Whether `init` set up a GPU to verify on
+#[cfg(feature = "cuda")]
+pub fn gpu_ready() -> bool {
+    GPU_READY.load(Ordering::Relaxed)
 }
 
 #[cfg(feature = "cuda")]
@@ -261,7 +405,7 @@ pub fn ed25519_verify(
     // power-of-two number around that accounting for the fact that the CPU
     // may be busy doing other things while being a real fullnode
     // TODO: dynamically adjust this crossover
-    if count < 64 {
+    if count < 64 || !gpu_ready() {
         return ed25519_verify_cpu(batches);
     }
 
@@ -354,7 +498,11 @@ mod tests {
     use crate::sigverify;
     use crate::test_tx::{test_multisig_tx, test_tx};
     use bincode::{deserialize, serialize};
+    use solana_sdk::hash::Hash;
+    use solana_sdk::signature::{Keypair, KeypairUtil};
     use solana_sdk::transaction::Transaction;
+    use solana_vote_api::vote_instruction;
+    use solana_vote_api::vote_state::Vote;
 
     const SIG_OFFSET: usize = 1;
 
@@ -548,4 +696,69 @@ mod tests {
     fn test_verify_fail() {
         test_verify_n(5, true);
     }
+
+    #[test]
+    fn test_parse_packet_metadata() {
+        let packet = sigverify::make_packet_from_transaction(test_tx());
+        assert_eq!(sigverify::parse_packet_metadata(&packet), Some((1, false)));
+        let packet = sigverify::make_packet_from_transaction(test_multisig_tx());
+        assert_eq!(sigverify::parse_packet_metadata(&packet), Some((2, false)));
+
+        let node_keypair = Keypair::new();
+        let vote_keypair = Keypair::new();
+        let vote_ix = vote_instruction::vote(
+            &vote_keypair.pubkey(),
+            &vote_keypair.pubkey(),
+            vec![Vote::new(1, Hash::default())],
+        );
+        let mut vote_tx = Transaction::new_with_payer(vec![vote_ix], Some(&node_keypair.pubkey()));
+        vote_tx.sign(&[&node_keypair, &vote_keypair], Hash::default());
+        let packet = sigverify::make_packet_from_transaction(vote_tx);
+        assert_eq!(sigverify::parse_packet_metadata(&packet), Some((2, true)));
+
+        // Cut short, or with a program id index past the account keys
+        let mut packet = sigverify::make_packet_from_transaction(test_tx());
+        packet.meta.size -= 1;
+        assert_eq!(sigverify::parse_packet_metadata(&packet), None);
+        let mut tx = test_tx();
+        tx.message.instructions[0].program_id_index = 10;
+        let packet = sigverify::make_packet_from_transaction(tx);
+        assert_eq!(sigverify::parse_packet_metadata(&packet), None);
+        assert_eq!(sigverify::parse_packet_metadata(&Packet::default()), None);
+    }
+
+    #[test]
+    fn test_packet_signature() {
+        let tx = test_multisig_tx();
+        let packet = sigverify::make_packet_from_transaction(tx.clone());
+        assert_eq!(sigverify::packet_signature(&packet), Some(tx.signatures[0]));
+
+        let mut packet = sigverify::make_packet_from_transaction(tx);
+        packet.meta.size = 10;
+        assert_eq!(sigverify::packet_signature(&packet), None);
+        assert_eq!(sigverify::packet_signature(&Packet::default()), None);
+    }
+
+    #[test]
+    fn test_mark_packets() {
+        let packet = sigverify::make_packet_from_transaction(test_multisig_tx());
+        let mut batches = generate_packet_vec(&packet, 3, 2);
+        batches[1].packets[2].meta.size = 10;
+        let verified = vec![vec![1u8, 0u8, 1u8], vec![1u8, 1u8, 1u8]];
+
+        let stats = sigverify::mark_packets(&mut batches, &verified);
+        assert_eq!(stats.malformed, 1);
+        assert_eq!(stats.invalid_signature, 1);
+        assert_eq!(stats.total(), 2);
+        let discards: Vec<Vec<_>> = batches
+            .iter()
+            .map(|packets| packets.packets.iter().map(|p| p.meta.discard).collect())
+            .collect();
+        assert_eq!(
+            discards,
+            vec![vec![false, true, false], vec![false, false, true]]
+        );
+        assert_eq!(batches[0].packets[0].meta.num_required_signatures, 2);
+        assert!(!batches[0].packets[0].meta.is_vote);
+    }
 }
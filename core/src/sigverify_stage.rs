@@ -1,7 +1,7 @@
 //! The `sigverify_stage` implements the signature verification stage of the TPU. It
-//! receives a list of lists of packets and outputs the same list, but tags each
-//! top-level list with a list of booleans, telling the next stage whether the
-//! signature in that packet is valid. It assumes each packet contains one
+//! receives a list of lists of packets and outputs the same list, but marks the
+//! packets whose signatures are invalid, or that don't hold a well formed transaction,
+//! to be discarded by the next stage. It assumes each packet contains one
 //! transaction. All processing is done on the CPU by default and on a GPU
 //! if the `cuda` feature is enabled with `--features=cuda`.
 
@@ -11,15 +11,17 @@ use crate::recycler::Recycler;
 use crate::result::{Error, Result};
 use crate::service::Service;
 use crate::sigverify;
-use crate::sigverify::TxOffset;
+use crate::sigverify::{DiscardStats, TxOffset};
 use crate::streamer::{self, PacketReceiver};
 use crossbeam_channel::Sender as CrossbeamSender;
 use solana_measure::measure::Measure;
 use solana_metrics::{datapoint_info, inc_new_counter_info};
 use solana_sdk::timing;
+use std::sync::atomic::AtomicUsize;
 use std::sync::mpsc::{Receiver, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, Builder, JoinHandle};
+use std::time::Instant;
 
 #[cfg(feature = "cuda")]
 const RECV_BATCH_MAX: usize = 5_000;
@@ -27,7 +29,7 @@ const RECV_BATCH_MAX: usize = 5_000;
 #[cfg(not(feature = "cuda"))]
 const RECV_BATCH_MAX: usize = 1000;
 
-pub type VerifiedPackets = Vec<(Packets, Vec<u8>)>;
+pub type VerifiedPackets = Vec<Packets>;
 
 pub struct SigVerifyStage {
     thread_hdls: Vec<JoinHandle<()>>,
@@ -39,31 +41,38 @@ impl SigVerifyStage {
         packet_receiver: Receiver<Packets>,
         sigverify_disabled: bool,
         verified_sender: CrossbeamSender<VerifiedPackets>,
+        packet_queue_depth: Arc<AtomicUsize>,
     ) -> Self {
         sigverify::init();
-        let thread_hdls =
-            Self::verifier_services(packet_receiver, verified_sender, sigverify_disabled);
+        let thread_hdls = Self::verifier_services(
+            packet_receiver,
+            verified_sender,
+            sigverify_disabled,
+            packet_queue_depth,
+        );
         Self { thread_hdls }
     }
 
     fn verify_batch(
-        batch: Vec<Packets>,
+        mut batch: Vec<Packets>,
         sigverify_disabled: bool,
         recycler: &Recycler<TxOffset>,
         recycler_out: &Recycler<PinnedVec<u8>>,
-    ) -> VerifiedPackets {
+    ) -> (VerifiedPackets, DiscardStats) {
         let r = if sigverify_disabled {
             sigverify::ed25519_verify_disabled(&batch)
         } else {
             sigverify::ed25519_verify(&batch, recycler, recycler_out)
         };
-        batch.into_iter().zip(r).collect()
+        let discard_stats = sigverify::mark_packets(&mut batch, &r);
+        (batch, discard_stats)
     }
 
     fn verifier(
         recvr: &Arc<Mutex<PacketReceiver>>,
         sendr: &CrossbeamSender<VerifiedPackets>,
         sigverify_disabled: bool,
+        packet_queue_depth: &AtomicUsize,
         id: usize,
         recycler: &Recycler<TxOffset>,
         recycler_out: &Recycler<PinnedVec<u8>>,
@@ -71,6 +80,7 @@ impl SigVerifyStage {
         let (batch, len, recv_time) = streamer::recv_batch(
             &recvr.lock().expect("'recvr' lock in fn verifier"),
             RECV_BATCH_MAX,
+            packet_queue_depth,
         )?;
         inc_new_counter_info!("sigverify_stage-packets_received", len);
 
@@ -83,8 +93,18 @@ impl SigVerifyStage {
             id
         );
 
-        let verified_batch = Self::verify_batch(batch, sigverify_disabled, recycler, recycler_out);
+        let verify_start = Instant::now();
+        let (verified_batch, discard_stats) =
+            Self::verify_batch(batch, sigverify_disabled, recycler, recycler_out);
+        solana_metrics::record_latency("sigverify", verify_start.elapsed());
         inc_new_counter_info!("sigverify_stage-verified_packets_send", len);
+        datapoint_info!(
+            "sigverify_stage-discards",
+            ("packets", len, i64),
+            ("discarded", discard_stats.total(), i64),
+            ("malformed", discard_stats.malformed, i64),
+            ("invalid_signature", discard_stats.invalid_signature, i64)
+        );
 
         for v in verified_batch {
             if sendr.send(vec![v]).is_err() {
@@ -122,6 +142,7 @@ impl SigVerifyStage {
         packet_receiver: Arc<Mutex<PacketReceiver>>,
         verified_sender: CrossbeamSender<VerifiedPackets>,
         sigverify_disabled: bool,
+        packet_queue_depth: Arc<AtomicUsize>,
         id: usize,
     ) -> JoinHandle<()> {
         Builder::new()
@@ -134,6 +155,7 @@ impl SigVerifyStage {
                         &packet_receiver,
                         &verified_sender,
                         sigverify_disabled,
+                        &packet_queue_depth,
                         id,
                         &recycler,
                         &recycler_out,
@@ -156,6 +178,7 @@ impl SigVerifyStage {
         packet_receiver: PacketReceiver,
         verified_sender: CrossbeamSender<VerifiedPackets>,
         sigverify_disabled: bool,
+        packet_queue_depth: Arc<AtomicUsize>,
     ) -> Vec<JoinHandle<()>> {
         let receiver = Arc::new(Mutex::new(packet_receiver));
         (0..4)
@@ -164,6 +187,7 @@ impl SigVerifyStage {
                     receiver.clone(),
                     verified_sender.clone(),
                     sigverify_disabled,
+                    packet_queue_depth.clone(),
                     id,
                 )
             })
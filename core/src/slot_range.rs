@@ -0,0 +1,261 @@
+//! The `slot_range` module provides `SlotRange`, a non-empty, inclusive range of slots (or of
+//! blob indexes within a slot). Keeping the bounds inclusive lets a range reach `u64::MAX`, and
+//! constructing one through the helpers below keeps the `<` vs `<=` decisions in one place.
+
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SlotRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Default for SlotRange {
+    /// Every slot
+    fn default() -> Self {
+        Self {
+            start: 0,
+            end: std::u64::MAX,
+        }
+    }
+}
+
+impl SlotRange {
+    /// The slots `start..=end`, or `None` if `end < start`
+    pub fn new(start: u64, end: u64) -> Option<Self> {
+        if end < start {
+            None
+        } else {
+            Some(Self { start, end })
+        }
+    }
+
+    /// The slots `start..end`, or `None` if that's empty
+    pub fn new_exclusive(start: u64, end: u64) -> Option<Self> {
+        if end <= start {
+            None
+        } else {
+            Some(Self {
+                start,
+                end: end - 1,
+            })
+        }
+    }
+
+    /// The `len` slots starting at `start`, up to `u64::MAX`, or `None` if `len` is 0
+    pub fn with_len(start: u64, len: u64) -> Option<Self> {
+        if len == 0 {
+            None
+        } else {
+            Some(Self {
+                start,
+                end: start.saturating_add(len - 1),
+            })
+        }
+    }
+
+    /// The number of slots in the range, saturating at `u64::MAX` for the range of every slot
+    pub fn num_slots(&self) -> u64 {
+        (self.end - self.start).saturating_add(1)
+    }
+
+    /// The first slot after the range, for APIs that take an exclusive end, or `None` if the
+    /// range ends at `u64::MAX`
+    pub fn end_exclusive(&self) -> Option<u64> {
+        self.end.checked_add(1)
+    }
+
+    pub fn contains(&self, slot: u64) -> bool {
+        self.start <= slot && slot <= self.end
+    }
+
+    /// The slots in both ranges, if any
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        Self::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    /// The smallest range containing both ranges, including any slots between them
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    /// The slots of the range at or after `min`, such as the current root, if any
+    pub fn clamp_start(&self, min: u64) -> Option<Self> {
+        Self::new(self.start.max(min), self.end)
+    }
+
+    /// The slots of the range at or before `max`, such as the highest known slot, if any
+    pub fn clamp_end(&self, max: u64) -> Option<Self> {
+        Self::new(self.start, self.end.min(max))
+    }
+
+    /// The first `max_len` slots of the range, or the whole range if it's no longer than that.
+    /// `max_len` must be nonzero.
+    pub fn truncate(&self, max_len: u64) -> Self {
+        assert!(max_len > 0);
+        Self {
+            start: self.start,
+            end: self.end.min(self.start.saturating_add(max_len - 1)),
+        }
+    }
+
+    pub fn iter(&self) -> RangeInclusive<u64> {
+        self.start..=self.end
+    }
+}
+
+impl IntoIterator for SlotRange {
+    type Item = u64;
+    type IntoIter = RangeInclusive<u64>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::u64::MAX;
+
+    fn range(start: u64, end: u64) -> SlotRange {
+        SlotRange::new(start, end).unwrap()
+    }
+
+    #[test]
+    fn test_new() {
+        assert_eq!(SlotRange::new(0, 0), Some(SlotRange { start: 0, end: 0 }));
+        assert_eq!(SlotRange::new(3, 5), Some(SlotRange { start: 3, end: 5 }));
+        assert_eq!(SlotRange::new(5, 3), None);
+        assert_eq!(SlotRange::new(0, MAX), Some(SlotRange::default()));
+        assert_eq!(SlotRange::new(MAX, MAX).unwrap().num_slots(), 1);
+    }
+
+    #[test]
+    fn test_new_exclusive() {
+        assert_eq!(SlotRange::new_exclusive(3, 5), Some(range(3, 4)));
+        assert_eq!(SlotRange::new_exclusive(3, 4), Some(range(3, 3)));
+        assert_eq!(SlotRange::new_exclusive(3, 3), None);
+        assert_eq!(SlotRange::new_exclusive(5, 3), None);
+        assert_eq!(SlotRange::new_exclusive(0, MAX), Some(range(0, MAX - 1)));
+        assert_eq!(SlotRange::new_exclusive(MAX, MAX), None);
+    }
+
+    #[test]
+    fn test_with_len() {
+        assert_eq!(SlotRange::with_len(3, 0), None);
+        assert_eq!(SlotRange::with_len(3, 1), Some(range(3, 3)));
+        assert_eq!(SlotRange::with_len(3, 4), Some(range(3, 6)));
+        assert_eq!(SlotRange::with_len(MAX - 1, 4), Some(range(MAX - 1, MAX)));
+        for len in 1..10 {
+            assert_eq!(SlotRange::with_len(7, len).unwrap().num_slots(), len);
+        }
+    }
+
+    #[test]
+    fn test_num_slots_and_end_exclusive() {
+        assert_eq!(range(3, 3).num_slots(), 1);
+        assert_eq!(range(3, 5).num_slots(), 3);
+        assert_eq!(SlotRange::default().num_slots(), MAX);
+        assert_eq!(range(1, MAX).num_slots(), MAX);
+        assert_eq!(range(3, 5).end_exclusive(), Some(6));
+        assert_eq!(range(3, MAX).end_exclusive(), None);
+        for start in 0..5 {
+            for end in start..5 {
+                let r = range(start, end);
+                assert_eq!(r.num_slots(), r.iter().count() as u64);
+                assert_eq!(
+                    SlotRange::new_exclusive(r.start, r.end_exclusive().unwrap()),
+                    Some(r)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_contains() {
+        let r = range(3, 5);
+        assert!(!r.contains(2));
+        assert!(r.contains(3));
+        assert!(r.contains(5));
+        assert!(!r.contains(6));
+        assert!(SlotRange::default().contains(0));
+        assert!(SlotRange::default().contains(MAX));
+    }
+
+    #[test]
+    fn test_intersect_and_union() {
+        // Check every pair of ranges within 0..6 against the slots they contain
+        for a_start in 0..6 {
+            for a_end in a_start..6 {
+                for b_start in 0..6 {
+                    for b_end in b_start..6 {
+                        let a = range(a_start, a_end);
+                        let b = range(b_start, b_end);
+                        let both: Vec<_> = a.iter().filter(|slot| b.contains(*slot)).collect();
+                        let intersection = a.intersect(&b);
+                        assert_eq!(intersection, b.intersect(&a));
+                        assert_eq!(
+                            intersection.map(|r| r.iter().collect()).unwrap_or_default(),
+                            both
+                        );
+
+                        let union = a.union(&b);
+                        assert_eq!(union, b.union(&a));
+                        assert_eq!(union.start, a_start.min(b_start));
+                        assert_eq!(union.end, a_end.max(b_end));
+                    }
+                }
+            }
+        }
+        assert_eq!(
+            range(0, MAX).intersect(&range(MAX, MAX)),
+            Some(range(MAX, MAX))
+        );
+    }
+
+    #[test]
+    fn test_clamp() {
+        let r = range(3, 5);
+        assert_eq!(r.clamp_start(0), Some(r));
+        assert_eq!(r.clamp_start(3), Some(r));
+        assert_eq!(r.clamp_start(4), Some(range(4, 5)));
+        assert_eq!(r.clamp_start(5), Some(range(5, 5)));
+        assert_eq!(r.clamp_start(6), None);
+        assert_eq!(r.clamp_end(MAX), Some(r));
+        assert_eq!(r.clamp_end(5), Some(r));
+        assert_eq!(r.clamp_end(4), Some(range(3, 4)));
+        assert_eq!(r.clamp_end(3), Some(range(3, 3)));
+        assert_eq!(r.clamp_end(2), None);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let r = range(3, 5);
+        assert_eq!(r.truncate(1), range(3, 3));
+        assert_eq!(r.truncate(2), range(3, 4));
+        assert_eq!(r.truncate(3), r);
+        assert_eq!(r.truncate(MAX), r);
+        assert_eq!(range(MAX - 1, MAX).truncate(MAX), range(MAX - 1, MAX));
+        assert_eq!(SlotRange::default().truncate(10), range(0, 9));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_truncate_zero() {
+        range(3, 5).truncate(0);
+    }
+
+    #[test]
+    fn test_iter() {
+        assert_eq!(range(3, 5).iter().collect::<Vec<_>>(), vec![3, 4, 5]);
+        assert_eq!(range(3, 3).into_iter().collect::<Vec<_>>(), vec![3]);
+        assert_eq!(
+            range(MAX - 1, MAX).into_iter().collect::<Vec<_>>(),
+            vec![MAX - 1, MAX]
+        );
+    }
+}
@@ -162,10 +162,12 @@ pub(crate) mod tests {
     pub(crate) fn setup_vote_and_stake_accounts(
         bank: &Bank,
         from_account: &Keypair,
-        vote_pubkey: &Pubkey,
+        vote_account: &Keypair,
         node_pubkey: &Pubkey,
         amount: u64,
     ) {
+        let vote_pubkey = &vote_account.pubkey();
+
         fn process_instructions<T: KeypairUtil>(
             bank: &Bank,
             keypairs: &[&T],
@@ -182,7 +184,7 @@ pub(crate) mod tests {
 
         process_instructions(
             bank,
-            &[from_account],
+            &[from_account, vote_account],
             vote_instruction::create_account(
                 &from_account.pubkey(),
                 vote_pubkey,
@@ -224,7 +226,8 @@ pub(crate) mod tests {
         } = create_genesis_block(10_000);
 
         let bank = Bank::new(&genesis_block);
-        let vote_pubkey = Pubkey::new_rand();
+        let vote_keypair = Keypair::new();
+        let vote_pubkey = vote_keypair.pubkey();
 
         // Give the validator some stake but don't setup a staking account
         // Validator has no lamports staked, so they get filtered out. Only the bootstrap leader
@@ -237,7 +240,7 @@ pub(crate) mod tests {
         setup_vote_and_stake_accounts(
             &bank,
             &mint_keypair,
-            &vote_pubkey,
+            &vote_keypair,
             &mint_keypair.pubkey(),
             stake,
         );
@@ -1,14 +1,17 @@
 // A stage that handles generating the keys used to encrypt the ledger and sample it
-// for storage mining. Replicators submit storage proofs, validator then bundles them
-// to submit its proof for mining to be rewarded.
+// for storage mining. Replicators submit storage proofs, validator then checks them
+// against its own copy of the ledger and submits the results to be rewarded.
 
 use crate::bank_forks::BankForks;
 use crate::blocktree::Blocktree;
+use crate::chacha::{chacha_cbc_encrypt_ledger, CHACHA_BLOCK_SIZE};
 #[cfg(cuda)]
 use crate::chacha_cuda::chacha_cbc_encrypt_file_many_keys;
 use crate::cluster_info::ClusterInfo;
+use crate::replicator::{sample_file, sampling_offsets};
 use crate::result::{Error, Result};
 use crate::service::Service;
+use crate::slot_range::SlotRange;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
 use solana_runtime::bank::Bank;
@@ -24,10 +27,14 @@ use solana_sdk::timing::get_segment_from_slot;
 use solana_sdk::transaction::Transaction;
 use solana_storage_api::storage_contract::{Proof, ProofStatus, StorageContract};
 use solana_storage_api::storage_instruction;
-use solana_storage_api::storage_instruction::proof_validation;
-use std::collections::HashMap;
+use solana_storage_api::storage_instruction::{
+    challenge_proof, proof_validation, resolve_challenge,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
 use std::mem::size_of;
 use std::net::UdpSocket;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
 use std::sync::{Arc, RwLock};
@@ -63,6 +70,8 @@ struct StorageSlots {
 #[derive(Clone, Default)]
 pub struct StorageState {
     state: Arc<RwLock<StorageStateInner>>,
+    /// Pays for and signs the storage transactions, None if storage mining is disabled
+    identity_keypair: Option<Arc<Keypair>>,
 }
 
 pub struct StorageStage {
@@ -89,7 +98,12 @@ fn get_identity_index_from_signature(key: &Signature) -> usize {
 }
 
 impl StorageState {
-    pub fn new(hash: &Hash, slots_per_turn: u64, slots_per_segment: u64) -> Self {
+    pub fn new(
+        hash: &Hash,
+        slots_per_turn: u64,
+        slots_per_segment: u64,
+        identity_keypair: Option<Arc<Keypair>>,
+    ) -> Self {
         let storage_keys = vec![0u8; KEY_SIZE * NUM_IDENTITIES];
         let storage_results = vec![Hash::default(); NUM_IDENTITIES];
         let replicator_map = vec![];
@@ -106,6 +120,7 @@ impl StorageState {
 
         StorageState {
             state: Arc::new(RwLock::new(state)),
+            identity_keypair,
         }
     }
 
@@ -173,26 +188,37 @@ impl StorageStage {
         storage_state: &StorageState,
         bank_receiver: Receiver<Vec<Arc<Bank>>>,
         blocktree: Option<Arc<Blocktree>>,
-        keypair: &Arc<Keypair>,
         storage_keypair: &Arc<Keypair>,
         exit: &Arc<AtomicBool>,
         bank_forks: &Arc<RwLock<BankForks>>,
         cluster_info: &Arc<RwLock<ClusterInfo>>,
     ) -> Self {
         let (instruction_sender, instruction_receiver) = channel();
+        let identity_keypair = storage_state.identity_keypair.clone();
+        if identity_keypair.is_none() {
+            info!("storage mining is disabled");
+        }
 
         let t_storage_mining_verifier = {
             let slots_per_turn = storage_state.state.read().unwrap().slots_per_turn;
             let storage_state_inner = storage_state.state.clone();
             let exit = exit.clone();
             let storage_keypair = storage_keypair.clone();
+            let mining_enabled = identity_keypair.is_some();
             Builder::new()
                 .name("solana-storage-mining-verify-stage".to_string())
                 .spawn(move || {
                     let mut current_key = 0;
                     let mut storage_slots = StorageSlots::default();
                     loop {
-                        if let Some(ref some_blocktree) = blocktree {
+                        if !mining_enabled {
+                            // Only keep the rooted banks from piling up in the channel
+                            if let Err(RecvTimeoutError::Disconnected) =
+                                bank_receiver.recv_timeout(Duration::from_secs(1))
+                            {
+                                break;
+                            }
+                        } else if let Some(ref some_blocktree) = blocktree {
                             if let Err(e) = Self::process_entries(
                                 &storage_keypair,
                                 &storage_state_inner,
@@ -223,12 +249,15 @@ impl StorageStage {
         let t_storage_create_accounts = {
             let cluster_info = cluster_info.clone();
             let exit = exit.clone();
-            let keypair = keypair.clone();
             let storage_keypair = storage_keypair.clone();
             let bank_forks = bank_forks.clone();
             Builder::new()
                 .name("solana-storage-create-accounts".to_string())
                 .spawn(move || {
+                    let identity_keypair = match identity_keypair {
+                        Some(identity_keypair) => identity_keypair,
+                        None => return,
+                    };
                     let transactions_socket = UdpSocket::bind("0.0.0.0:0").unwrap();
 
                     {
@@ -246,7 +275,7 @@ impl StorageStage {
                                     &bank_forks,
                                     &cluster_info,
                                     instruction,
-                                    &keypair,
+                                    &identity_keypair,
                                     &storage_keypair,
                                     &transactions_socket,
                                 )
@@ -279,21 +308,24 @@ impl StorageStage {
         bank_forks: &Arc<RwLock<BankForks>>,
         cluster_info: &Arc<RwLock<ClusterInfo>>,
         instruction: Instruction,
-        keypair: &Arc<Keypair>,
+        identity_keypair: &Arc<Keypair>,
         storage_keypair: &Arc<Keypair>,
         transactions_socket: &UdpSocket,
     ) -> io::Result<()> {
         let working_bank = bank_forks.read().unwrap().working_bank();
         let blockhash = working_bank.confirmed_last_blockhash().0;
-        let keypair_balance = working_bank.get_balance(&keypair.pubkey());
+        let identity_balance = working_bank.get_balance(&identity_keypair.pubkey());
 
-        if keypair_balance == 0 {
-            warn!("keypair account balance empty: {}", keypair.pubkey(),);
+        if identity_balance == 0 {
+            warn!(
+                "storage identity account balance empty: {}",
+                identity_keypair.pubkey()
+            );
         } else {
             debug!(
-                "keypair account balance: {}: {}",
-                keypair.pubkey(),
-                keypair_balance
+                "storage identity account balance: {}: {}",
+                identity_keypair.pubkey(),
+                identity_balance
             );
         }
         if working_bank
@@ -306,7 +338,7 @@ impl StorageStage {
             );
         }
 
-        let signer_keys = vec![keypair.as_ref(), storage_keypair.as_ref()];
+        let signer_keys = vec![identity_keypair.as_ref(), storage_keypair.as_ref()];
         let message = Message::new_with_payer(vec![instruction], Some(&signer_keys[0].pubkey()));
         let transaction = Transaction::new(&signer_keys, message, blockhash);
         // try sending the transaction upto 5 times
@@ -474,12 +506,14 @@ impl StorageStage {
                     }
                     let proof_segment_index = proof.segment_index as usize;
                     if proof_segment_index < statew.replicator_map.len() {
-                        // TODO randomly select and verify the proof first
-                        // Copy the submitted proof
-                        statew.replicator_map[proof_segment_index]
+                        // Record who stores the segment; the proof is checked once the
+                        // segment it was filed in ends
+                        let account_proofs = statew.replicator_map[proof_segment_index]
                             .entry(account_id)
-                            .or_default()
-                            .push(proof.clone());
+                            .or_default();
+                        if !account_proofs.contains(proof) {
+                            account_proofs.push(proof.clone());
+                        }
                     }
                 }
                 debug!("storage proof: slot: {}", slot);
@@ -536,8 +570,8 @@ impl StorageStage {
                         instruction_sender,
                     );
                     Self::submit_verifications(
-                        get_segment_from_slot(bank.slot(), bank.slots_per_segment()),
-                        &storage_state,
+                        &bank,
+                        &blocktree,
                         &storage_keypair,
                         instruction_sender,
                     )?
@@ -547,74 +581,177 @@ impl StorageStage {
         Ok(())
     }
 
+    /// Checks the proofs replicators filed in segments that ended by `bank`, and that this
+    /// validator hasn't validated yet, then submits the results. A replicator's proofs are only
+    /// validated once per segment, so they're checked once no more can be filed in it.
+    /// Proofs this validator found not valid are challenged, and its challenges resolved once
+    /// their windows close.
     fn submit_verifications(
-        current_segment: u64,
-        storage_state: &Arc<RwLock<StorageStateInner>>,
+        bank: &Bank,
+        blocktree: &Arc<Blocktree>,
         storage_keypair: &Arc<Keypair>,
-        ix_sender: &Sender<Instruction>,
+        ix_sender: &InstructionSender,
     ) -> Result<()> {
-        // bundle up mining submissions from replicators
-        // and submit them in a tx to the leader to get rewarded.
-        let mut w_state = storage_state.write().unwrap();
-        let mut max_proof_mask = 0;
+        let current_segment = get_segment_from_slot(bank.slot(), bank.slots_per_segment());
+        let my_pubkey = storage_keypair.pubkey();
         let proof_mask_limit = storage_instruction::proof_mask_limit();
-        let instructions: Vec<_> = w_state
-            .replicator_map
-            .iter_mut()
-            .enumerate()
-            .flat_map(|(_, proof_map)| {
-                let checked_proofs = proof_map
-                    .iter_mut()
-                    .filter_map(|(id, proofs)| {
-                        if !proofs.is_empty() {
-                            if (proofs.len() as u64) >= proof_mask_limit {
-                                proofs.clear();
-                                None
-                            } else {
-                                max_proof_mask = cmp::max(max_proof_mask, proofs.len());
-                                Some((
-                                    *id,
-                                    proofs
-                                        .drain(..)
-                                        .map(|_| ProofStatus::Valid)
-                                        .collect::<Vec<_>>(),
-                                ))
+        // The checked proofs of each replicator account, by the segment they were filed in
+        let mut checked_proofs: BTreeMap<u64, Vec<(Pubkey, Vec<ProofStatus>)>> = BTreeMap::new();
+        for (account_id, account) in replicator_accounts(bank) {
+            if let Ok(StorageContract::ReplicatorStorage {
+                proofs,
+                validations,
+                challenges,
+                ..
+            }) = account.state()
+            {
+                for ((segment, proof_index), challenge) in &challenges {
+                    if challenge.challenger == my_pubkey
+                        && !challenge.resolved
+                        && challenge.window_end < current_segment
+                    {
+                        ix_sender.send(resolve_challenge(
+                            &account_id,
+                            &my_pubkey,
+                            *segment,
+                            *proof_index,
+                        ))?;
+                    }
+                }
+                // Challenge the proofs this validator found not valid once its results landed
+                for (segment, proof_masks) in &validations {
+                    if let Some(proof_mask) = proof_masks.get(&my_pubkey) {
+                        for (proof_index, status) in proof_mask.iter().enumerate() {
+                            let proof_index = proof_index as u64;
+                            if *status == ProofStatus::NotValid
+                                && !challenges.contains_key(&(*segment, proof_index))
+                            {
+                                ix_sender.send(challenge_proof(
+                                    &my_pubkey,
+                                    &account_id,
+                                    *segment,
+                                    proof_index,
+                                ))?;
                             }
-                        } else {
-                            None
                         }
-                    })
-                    .collect::<Vec<(_, _)>>();
-
-                if !checked_proofs.is_empty() {
-                    let max_accounts_per_ix =
-                        storage_instruction::validation_account_limit(max_proof_mask);
-                    let ixs = checked_proofs
-                        .chunks(max_accounts_per_ix as usize)
-                        .map(|checked_proofs| {
-                            proof_validation(
-                                &storage_keypair.pubkey(),
-                                current_segment,
-                                checked_proofs.to_vec(),
-                            )
-                        })
-                        .collect::<Vec<_>>();
-                    Some(ixs)
-                } else {
-                    None
+                    }
                 }
-            })
-            .flatten()
-            .collect();
-        let res: std::result::Result<_, _> = instructions
-            .into_iter()
-            .map(|ix| {
+                for (segment, segment_proofs) in proofs {
+                    let validated = validations
+                        .get(&segment)
+                        .map_or(false, |proof_masks| proof_masks.contains_key(&my_pubkey));
+                    if segment >= current_segment
+                        || validated
+                        || segment_proofs.is_empty()
+                        || segment_proofs.len() as u64 >= proof_mask_limit
+                    {
+                        continue;
+                    }
+                    let proof_mask =
+                        Self::verify_proofs(blocktree, bank.slots_per_segment(), &segment_proofs);
+                    checked_proofs
+                        .entry(segment)
+                        .or_default()
+                        .push((account_id, proof_mask));
+                }
+            }
+        }
+
+        for (segment, checked_proofs) in checked_proofs {
+            let max_proof_mask = checked_proofs
+                .iter()
+                .map(|(_, proof_mask)| proof_mask.len())
+                .max()
+                .unwrap_or(0);
+            let max_accounts_per_ix = storage_instruction::validation_account_limit(max_proof_mask);
+            for checked_proofs in checked_proofs.chunks(cmp::max(max_accounts_per_ix, 1) as usize) {
                 sleep(Duration::from_millis(100));
-                ix_sender.send(ix)
+                ix_sender.send(proof_validation(
+                    &my_pubkey,
+                    segment,
+                    checked_proofs.to_vec(),
+                ))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks each proof by encrypting the segment it's for with the proof's signature, the way
+    /// the replicator did, and sampling the encrypted segment at the offsets seeded by the proof's
+    /// blockhash. Proofs for segments this validator doesn't have all of are skipped.
+    fn verify_proofs(
+        blocktree: &Arc<Blocktree>,
+        slots_per_segment: u64,
+        proofs: &[Proof],
+    ) -> Vec<ProofStatus> {
+        // Each replicator encrypts a segment once, so encrypt once per (signature, segment)
+        let mut encrypted_segments: HashMap<(Signature, u64), Option<(PathBuf, usize)>> =
+            HashMap::new();
+        let proof_mask = proofs
+            .iter()
+            .map(|proof| {
+                let encrypted_segment = encrypted_segments
+                    .entry((proof.signature, proof.segment_index))
+                    .or_insert_with(|| {
+                        Self::encrypt_segment(
+                            blocktree,
+                            slots_per_segment,
+                            &proof.signature,
+                            proof.segment_index,
+                        )
+                    });
+                match encrypted_segment {
+                    None => ProofStatus::Skipped,
+                    Some((path, num_chacha_blocks)) => {
+                        let offsets = sampling_offsets(&proof.blockhash, *num_chacha_blocks);
+                        match sample_file(path, &offsets) {
+                            Ok(sha_state) if sha_state == proof.sha_state => ProofStatus::Valid,
+                            _ => ProofStatus::NotValid,
+                        }
+                    }
+                }
             })
             .collect();
-        res?;
-        Ok(())
+        for (path, _) in encrypted_segments.values().flatten() {
+            let _ = fs::remove_file(path);
+        }
+        proof_mask
+    }
+
+    /// Encrypts `segment` with `signature` into a temporary file, returning the file and the
+    /// number of chacha blocks in it, or None if the segment isn't complete in `blocktree`
+    fn encrypt_segment(
+        blocktree: &Arc<Blocktree>,
+        slots_per_segment: u64,
+        signature: &Signature,
+        segment: u64,
+    ) -> Option<(PathBuf, usize)> {
+        let start_slot = segment * slots_per_segment;
+        let segment_slots = SlotRange::with_len(start_slot, slots_per_segment)?;
+        if !segment_slots.iter().all(|slot| blocktree.is_full(slot)) {
+            debug!("storage proof for missing segment: {}", segment);
+            return None;
+        }
+
+        let path = std::env::temp_dir().join(format!(
+            "solana-storage-proof-{}-{}-{}.enc",
+            std::process::id(),
+            signature,
+            segment
+        ));
+        let mut ivec = [0u8; CHACHA_BLOCK_SIZE];
+        ivec.copy_from_slice(signature.as_ref());
+        match chacha_cbc_encrypt_ledger(blocktree, start_slot, slots_per_segment, &path, &mut ivec)
+        {
+            Ok(num_encrypted_bytes) if num_encrypted_bytes >= CHACHA_BLOCK_SIZE => {
+                Some((path, num_encrypted_bytes / CHACHA_BLOCK_SIZE))
+            }
+            result => {
+                info!("unable to encrypt segment {}: {:?}", segment, result);
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
     }
 }
 
@@ -630,7 +767,8 @@ impl Service for StorageStage {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::blocktree::{create_new_tmp_ledger, Blocktree};
+    use crate::blocktree::tests::make_many_slot_entries;
+    use crate::blocktree::{create_new_tmp_ledger, get_tmp_ledger_path, Blocktree};
     use crate::cluster_info::ClusterInfo;
     use crate::contact_info::ContactInfo;
     use crate::genesis_utils::{create_genesis_block, GenesisBlockInfo};
@@ -638,7 +776,7 @@ mod tests {
     use crate::{blocktree_processor, entry};
     use rayon::prelude::*;
     use solana_runtime::bank::Bank;
-    use solana_sdk::hash::{Hash, Hasher};
+    use solana_sdk::hash::{hash, Hash, Hasher};
     use solana_sdk::pubkey::Pubkey;
     use solana_sdk::signature::{Keypair, KeypairUtil};
     use solana_sdk::timing::DEFAULT_TICKS_PER_SLOT;
@@ -665,12 +803,12 @@ mod tests {
             &bank.last_blockhash(),
             SLOTS_PER_TURN_TEST,
             bank.slots_per_segment(),
+            Some(keypair.clone()),
         );
         let storage_stage = StorageStage::new(
             &storage_state,
             slot_receiver,
             None,
-            &keypair,
             &storage_keypair,
             &exit.clone(),
             &bank_forks,
@@ -680,6 +818,50 @@ mod tests {
         storage_stage.join().unwrap();
     }
 
+    #[test]
+    fn test_storage_stage_mining_disabled() {
+        let keypair = Arc::new(Keypair::new());
+        let storage_keypair = Arc::new(Keypair::new());
+        let exit = Arc::new(AtomicBool::new(false));
+
+        let GenesisBlockInfo { genesis_block, .. } = create_genesis_block(1000);
+        let (ledger_path, _blockhash) = create_new_tmp_ledger!(&genesis_block);
+        let blocktree = Arc::new(Blocktree::open(&ledger_path).unwrap());
+        let cluster_info = test_cluster_info(&keypair.pubkey());
+        let bank = Arc::new(Bank::new(&genesis_block));
+        let bank_forks = Arc::new(RwLock::new(BankForks::new_from_banks(&[bank.clone()], 0)));
+        let (bank_sender, bank_receiver) = channel();
+        let storage_state = StorageState::new(
+            &bank.last_blockhash(),
+            SLOTS_PER_TURN_TEST,
+            bank.slots_per_segment(),
+            None,
+        );
+        let storage_stage = StorageStage::new(
+            &storage_state,
+            bank_receiver,
+            Some(blocktree.clone()),
+            &storage_keypair,
+            &exit.clone(),
+            &bank_forks,
+            &cluster_info,
+        );
+
+        // Rooted banks are still taken off the channel, but no turn is taken on them
+        for i in 1..=SLOTS_PER_TURN_TEST {
+            let bank = Arc::new(Bank::new_from_parent(&bank, &keypair.pubkey(), i));
+            bank_sender.send(vec![bank]).unwrap();
+        }
+        sleep(Duration::from_millis(500));
+        assert_eq!(storage_state.get_slot(), 0);
+        assert_eq!(storage_state.get_storage_blockhash(), bank.last_blockhash());
+
+        exit.store(true, Ordering::Relaxed);
+        storage_stage.join().unwrap();
+        drop(blocktree);
+        Blocktree::destroy(&ledger_path).unwrap();
+    }
+
     fn test_cluster_info(id: &Pubkey) -> Arc<RwLock<ClusterInfo>> {
         let contact_info = ContactInfo::new_localhost(id, 0);
         let cluster_info = ClusterInfo::new_with_invalid_keypair(contact_info);
@@ -707,12 +889,12 @@ mod tests {
             &bank.last_blockhash(),
             SLOTS_PER_TURN_TEST,
             bank.slots_per_segment(),
+            Some(keypair.clone()),
         );
         let storage_stage = StorageStage::new(
             &storage_state,
             bank_receiver,
             Some(blocktree.clone()),
-            &keypair,
             &storage_keypair,
             &exit.clone(),
             &bank_forks,
@@ -799,12 +981,12 @@ mod tests {
             &bank.last_blockhash(),
             SLOTS_PER_TURN_TEST,
             bank.slots_per_segment(),
+            Some(keypair.clone()),
         );
         let storage_stage = StorageStage::new(
             &storage_state,
             bank_receiver,
             Some(blocktree.clone()),
-            &keypair,
             &storage_keypair,
             &exit.clone(),
             &bank_forks,
@@ -890,6 +1072,59 @@ mod tests {
         remove_dir_all(ledger_path).unwrap();
     }
 
+    #[test]
+    fn test_verify_proofs() {
+        let slots_per_segment = 4;
+        let ledger_path = get_tmp_ledger_path!();
+        let blocktree = Arc::new(Blocktree::open(&ledger_path).unwrap());
+        let (blobs, _) = make_many_slot_entries(0, slots_per_segment, 2);
+        blocktree.write_blobs(blobs).unwrap();
+
+        // Make a proof for segment 0 the way a replicator does
+        let storage_keypair = Keypair::new();
+        let signature = storage_keypair.sign_message(Hash::default().as_ref());
+        let blockhash = hash(b"storage blockhash");
+        let out_path = std::env::temp_dir().join("test_verify_proofs.enc");
+        let mut ivec = [0u8; CHACHA_BLOCK_SIZE];
+        ivec.copy_from_slice(signature.as_ref());
+        let num_encrypted_bytes =
+            chacha_cbc_encrypt_ledger(&blocktree, 0, slots_per_segment, &out_path, &mut ivec)
+                .unwrap();
+        let offsets = sampling_offsets(&blockhash, num_encrypted_bytes / CHACHA_BLOCK_SIZE);
+        let sha_state = sample_file(&out_path, &offsets).unwrap();
+        fs::remove_file(&out_path).unwrap();
+        let proof = Proof {
+            signature,
+            blockhash,
+            sha_state,
+            segment_index: 0,
+        };
+
+        let wrong_sha_state = Proof {
+            sha_state: Hash::default(),
+            ..proof.clone()
+        };
+        let missing_segment = Proof {
+            segment_index: 1,
+            ..proof.clone()
+        };
+        assert_eq!(
+            StorageStage::verify_proofs(
+                &blocktree,
+                slots_per_segment,
+                &[proof, wrong_sha_state, missing_segment],
+            ),
+            vec![
+                ProofStatus::Valid,
+                ProofStatus::NotValid,
+                ProofStatus::Skipped
+            ]
+        );
+
+        drop(blocktree);
+        Blocktree::destroy(&ledger_path).unwrap();
+    }
+
     #[test]
     fn test_signature_distribution() {
         // See that signatures have an even-ish distribution..
@@ -5,7 +5,7 @@ use crate::packet::{Blob, Packets, PacketsRecycler, SharedBlobs, PACKETS_PER_BLO
 use crate::result::{Error, Result};
 use solana_sdk::timing::duration_as_ms;
 use std::net::UdpSocket;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
 use std::sync::Arc;
 use std::thread::{Builder, JoinHandle};
@@ -16,11 +16,69 @@ pub type PacketSender = Sender<Packets>;
 pub type BlobSender = Sender<SharedBlobs>;
 pub type BlobReceiver = Receiver<SharedBlobs>;
 
+/// How receivers coalesce packets into batches. The coalescing window and the batch size grow
+/// from their minimums while nothing is queued downstream to their maximums once
+/// `target_queue_depth` packets are, so an idle sigverify stage gets packets as soon as they
+/// arrive and a busy one gets fewer, larger batches.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CoalesceConfig {
+    pub min_coalesce_ms: u64,
+    pub max_coalesce_ms: u64,
+    pub min_batch_packets: usize,
+    pub max_batch_packets: usize,
+    pub target_queue_depth: usize,
+}
+
+impl Default for CoalesceConfig {
+    fn default() -> Self {
+        Self {
+            min_coalesce_ms: 1,
+            max_coalesce_ms: 5,
+            min_batch_packets: 64,
+            max_batch_packets: PACKETS_PER_BLOB,
+            target_queue_depth: 1000,
+        }
+    }
+}
+
+/// Picks the coalescing window and batch size of receivers from the packets queued downstream,
+/// as last seen by `recv_batch`
+#[derive(Clone, Debug, Default)]
+pub struct PacketCoalescer {
+    config: CoalesceConfig,
+    queue_depth: Arc<AtomicUsize>,
+}
+
+impl PacketCoalescer {
+    pub fn new(config: CoalesceConfig, queue_depth: Arc<AtomicUsize>) -> Self {
+        Self {
+            config,
+            queue_depth,
+        }
+    }
+
+    /// The coalescing window, in milliseconds, and the most packets to batch
+    pub fn limits(&self) -> (u64, usize) {
+        let config = &self.config;
+        let target = config.target_queue_depth.max(1) as u64;
+        let depth = (self.queue_depth.load(Ordering::Relaxed) as u64).min(target);
+        let scale = |min: u64, max: u64| min + max.saturating_sub(min) * depth / target;
+        (
+            scale(config.min_coalesce_ms, config.max_coalesce_ms),
+            scale(
+                config.min_batch_packets as u64,
+                config.max_batch_packets as u64,
+            ) as usize,
+        )
+    }
+}
+
 fn recv_loop(
     sock: &UdpSocket,
     exit: Arc<AtomicBool>,
     channel: &PacketSender,
     recycler: &PacketsRecycler,
+    coalescer: &PacketCoalescer,
     name: &'static str,
 ) -> Result<()> {
     loop {
@@ -31,7 +89,8 @@ fn recv_loop(
             if exit.load(Ordering::Relaxed) {
                 return Ok(());
             }
-            if let Ok(_len) = msgs.recv_from(sock) {
+            let (coalesce_ms, max_packets) = coalescer.limits();
+            if let Ok(_len) = msgs.recv_from_coalesced(sock, coalesce_ms, max_packets) {
                 channel.send(msgs)?;
                 break;
             }
@@ -44,6 +103,7 @@ pub fn receiver(
     exit: &Arc<AtomicBool>,
     packet_sender: PacketSender,
     recycler: PacketsRecycler,
+    coalescer: PacketCoalescer,
     name: &'static str,
 ) -> JoinHandle<()> {
     let res = sock.set_read_timeout(Some(Duration::new(1, 0)));
@@ -54,7 +114,14 @@ pub fn receiver(
     Builder::new()
         .name("solana-receiver".to_string())
         .spawn(move || {
-            let _ = recv_loop(&sock, exit, &packet_sender, &recycler.clone(), name);
+            let _ = recv_loop(
+                &sock,
+                exit,
+                &packet_sender,
+                &recycler.clone(),
+                &coalescer,
+                name,
+            );
         })
         .unwrap()
 }
@@ -66,7 +133,13 @@ fn recv_send(sock: &UdpSocket, r: &BlobReceiver) -> Result<()> {
     Ok(())
 }
 
-pub fn recv_batch(recvr: &PacketReceiver, max_batch: usize) -> Result<(Vec<Packets>, usize, u64)> {
+/// Receives up to about `max_batch` packets, and records how many were queued in `queue_depth`
+/// for the receivers' `PacketCoalescer`
+pub fn recv_batch(
+    recvr: &PacketReceiver,
+    max_batch: usize,
+    queue_depth: &AtomicUsize,
+) -> Result<(Vec<Packets>, usize, u64)> {
     let timer = Duration::new(1, 0);
     let msgs = recvr.recv_timeout(timer)?;
     let recv_start = Instant::now();
@@ -83,6 +156,7 @@ pub fn recv_batch(recvr: &PacketReceiver, max_batch: usize) -> Result<(Vec<Packe
         }
     }
     trace!("batch len {}", batch.len());
+    queue_depth.store(len, Ordering::Relaxed);
     Ok((batch, len, duration_as_ms(&recv_start.elapsed())))
 }
 
@@ -103,9 +177,14 @@ pub fn responder(name: &'static str, sock: Arc<UdpSocket>, r: BlobReceiver) -> J
 
 //TODO, we would need to stick block authentication before we create the
 //window.
-fn recv_blobs(sock: &UdpSocket, s: &BlobSender) -> Result<()> {
+fn recv_blobs(sock: &UdpSocket, s: &BlobSender, repair: bool) -> Result<()> {
     trace!("recv_blobs: receiving on {}", sock.local_addr().unwrap());
     let dq = Blob::recv_from(sock)?;
+    if repair {
+        for blob in &dq {
+            blob.write().unwrap().meta.repair = true;
+        }
+    }
     if !dq.is_empty() {
         s.send(dq)?;
     }
@@ -116,6 +195,25 @@ pub fn blob_receiver(
     sock: Arc<UdpSocket>,
     exit: &Arc<AtomicBool>,
     s: BlobSender,
+) -> JoinHandle<()> {
+    spawn_blob_receiver(sock, exit, s, false)
+}
+
+/// Like `blob_receiver`, but marks the blobs as responses to repair requests, to be checked
+/// against the requests before they're used
+pub fn repair_blob_receiver(
+    sock: Arc<UdpSocket>,
+    exit: &Arc<AtomicBool>,
+    s: BlobSender,
+) -> JoinHandle<()> {
+    spawn_blob_receiver(sock, exit, s, true)
+}
+
+fn spawn_blob_receiver(
+    sock: Arc<UdpSocket>,
+    exit: &Arc<AtomicBool>,
+    s: BlobSender,
+    repair: bool,
 ) -> JoinHandle<()> {
     //DOCUMENTED SIDE-EFFECT
     //1 second timeout on socket read
@@ -129,7 +227,7 @@ pub fn blob_receiver(
             if exit.load(Ordering::Relaxed) {
                 break;
             }
-            let _ = recv_blobs(&sock, &s);
+            let _ = recv_blobs(&sock, &s, repair);
         })
         .unwrap()
 }
@@ -183,7 +281,7 @@ mod test {
     use std::io;
     use std::io::Write;
     use std::net::UdpSocket;
-    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
     use std::sync::mpsc::channel;
     use std::sync::Arc;
     use std::time::Duration;
@@ -210,6 +308,38 @@ mod test {
         write!(io::sink(), "{:?}", Packets::default()).unwrap();
         write!(io::sink(), "{:?}", Blob::default()).unwrap();
     }
+    #[test]
+    fn test_packet_coalescer() {
+        let queue_depth = Arc::new(AtomicUsize::new(0));
+        let config = CoalesceConfig {
+            min_coalesce_ms: 1,
+            max_coalesce_ms: 5,
+            min_batch_packets: 100,
+            max_batch_packets: 500,
+            target_queue_depth: 1000,
+        };
+        let coalescer = PacketCoalescer::new(config, queue_depth.clone());
+        assert_eq!(coalescer.limits(), (1, 100));
+        queue_depth.store(500, Ordering::Relaxed);
+        assert_eq!(coalescer.limits(), (3, 300));
+        queue_depth.store(5000, Ordering::Relaxed);
+        assert_eq!(coalescer.limits(), (5, 500));
+    }
+
+    #[test]
+    fn test_recv_batch_queue_depth() {
+        let (sender, receiver) = channel();
+        let queue_depth = AtomicUsize::new(0);
+        for _ in 0..3 {
+            let mut packets = Packets::default();
+            packets.packets.resize(10, Packet::default());
+            sender.send(packets).unwrap();
+        }
+        let (batch, len, _) = recv_batch(&receiver, 1000, &queue_depth).unwrap();
+        assert_eq!((batch.len(), len), (3, 30));
+        assert_eq!(queue_depth.load(Ordering::Relaxed), 30);
+    }
+
     #[test]
     fn streamer_send_test() {
         let read = UdpSocket::bind("127.0.0.1:0").expect("bind");
@@ -219,7 +349,14 @@ mod test {
         let send = UdpSocket::bind("127.0.0.1:0").expect("bind");
         let exit = Arc::new(AtomicBool::new(false));
         let (s_reader, r_reader) = channel();
-        let t_receiver = receiver(Arc::new(read), &exit, s_reader, Recycler::default(), "test");
+        let t_receiver = receiver(
+            Arc::new(read),
+            &exit,
+            s_reader,
+            Recycler::default(),
+            PacketCoalescer::default(),
+            "test",
+        );
         let t_responder = {
             let (s_responder, r_responder) = channel();
             let t_responder = responder("streamer_send_test", Arc::new(send), r_responder);
@@ -1,7 +1,9 @@
 //! The `tpu` module implements the Transaction Processing Unit, a
 //! multi-stage transaction processing pipeline in software.
 
+use crate::bank_forks::BankForks;
 use crate::banking_stage::BankingStage;
+use crate::block_commitment::BlockCommitmentCache;
 use crate::blocktree::Blocktree;
 use crate::broadcast_stage::{BroadcastStage, BroadcastStageType};
 use crate::cluster_info::ClusterInfo;
@@ -9,11 +11,14 @@ use crate::cluster_info_vote_listener::ClusterInfoVoteListener;
 use crate::erasure::ErasureConfig;
 use crate::fetch_stage::FetchStage;
 use crate::poh_recorder::{PohRecorder, WorkingBankEntries};
+use crate::quic_streamer::QuicTpuConfig;
 use crate::service::Service;
 use crate::sigverify_stage::SigVerifyStage;
+use crate::streamer::{CoalesceConfig, PacketCoalescer};
 use crossbeam_channel::unbounded;
+use solana_sdk::signature::Keypair;
 use std::net::UdpSocket;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
 use std::sync::mpsc::{channel, Receiver};
 use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
@@ -31,9 +36,13 @@ impl Tpu {
     pub fn new(
         cluster_info: &Arc<RwLock<ClusterInfo>>,
         poh_recorder: &Arc<Mutex<PohRecorder>>,
+        bank_forks: &Arc<RwLock<BankForks>>,
+        block_commitment_cache: &Arc<RwLock<BlockCommitmentCache>>,
         entry_receiver: Receiver<WorkingBankEntries>,
         transactions_sockets: Vec<UdpSocket>,
         tpu_via_blobs_sockets: Vec<UdpSocket>,
+        tpu_quic: Option<(UdpSocket, &Keypair, &QuicTpuConfig)>,
+        coalesce_config: &CoalesceConfig,
         broadcast_socket: UdpSocket,
         sigverify_disabled: bool,
         blocktree: &Arc<Blocktree>,
@@ -42,17 +51,26 @@ impl Tpu {
         exit: &Arc<AtomicBool>,
     ) -> Self {
         let (packet_sender, packet_receiver) = channel();
+        // The packets queued for the sigverify stage, which the fetch stage batches by
+        let packet_queue_depth = Arc::new(AtomicUsize::new(0));
+        let coalescer = PacketCoalescer::new(*coalesce_config, packet_queue_depth.clone());
         let fetch_stage = FetchStage::new_with_sender(
             transactions_sockets,
             tpu_via_blobs_sockets,
+            tpu_quic,
+            &coalescer,
             &exit,
             &packet_sender,
             &poh_recorder,
         );
         let (verified_sender, verified_receiver) = unbounded();
 
-        let sigverify_stage =
-            SigVerifyStage::new(packet_receiver, sigverify_disabled, verified_sender.clone());
+        let sigverify_stage = SigVerifyStage::new(
+            packet_receiver,
+            sigverify_disabled,
+            verified_sender.clone(),
+            packet_queue_depth,
+        );
 
         let (verified_vote_sender, verified_vote_receiver) = unbounded();
         let cluster_info_vote_listener = ClusterInfoVoteListener::new(
@@ -61,6 +79,8 @@ impl Tpu {
             sigverify_disabled,
             verified_vote_sender,
             &poh_recorder,
+            bank_forks,
+            block_commitment_cache,
         );
 
         let banking_stage = BankingStage::new(
@@ -14,12 +14,14 @@
 
 use crate::bank_forks::BankForks;
 use crate::blob_fetch_stage::BlobFetchStage;
+use crate::block_commitment::BlockCommitmentCache;
 use crate::blockstream_service::BlockstreamService;
 use crate::blocktree::{Blocktree, CompletedSlotsReceiver};
 use crate::cluster_info::ClusterInfo;
 use crate::leader_schedule_cache::LeaderScheduleCache;
 use crate::ledger_cleanup_service::LedgerCleanupService;
 use crate::poh_recorder::PohRecorder;
+use crate::repair_service::{RpcRepair, RpcRepairConfig};
 use crate::replay_stage::ReplayStage;
 use crate::retransmit_stage::RetransmitStage;
 use crate::rpc_subscriptions::RpcSubscriptions;
@@ -27,7 +29,9 @@ use crate::service::Service;
 use crate::storage_stage::{StorageStage, StorageState};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, KeypairUtil};
+use std::collections::HashSet;
 use std::net::UdpSocket;
+use std::path::Path;
 use std::sync::atomic::AtomicBool;
 use std::sync::mpsc::{channel, Receiver};
 use std::sync::{Arc, Mutex, RwLock};
@@ -67,8 +71,16 @@ impl Tvu {
         storage_state: &StorageState,
         blockstream: Option<&String>,
         max_ledger_slots: Option<u64>,
+        rpc_repair_config: Option<&RpcRepairConfig>,
+        gossip_leader_slots: bool,
+        trusted_validators: Option<&HashSet<Pubkey>>,
+        halt_on_trusted_validators_accounts_hash_mismatch: bool,
+        tower_path: Option<&Path>,
+        dev_halt_at_slot: Option<u64>,
+        leader_disabled: bool,
         ledger_signal_receiver: Receiver<bool>,
         subscriptions: &Arc<RpcSubscriptions>,
+        block_commitment_cache: &Arc<RwLock<BlockCommitmentCache>>,
         poh_recorder: &Arc<Mutex<PohRecorder>>,
         leader_schedule_cache: &Arc<LeaderScheduleCache>,
         exit: &Arc<AtomicBool>,
@@ -92,10 +104,17 @@ impl Tvu {
         let (blob_fetch_sender, blob_fetch_receiver) = channel();
 
         let repair_socket = Arc::new(repair_socket);
-        let mut blob_sockets: Vec<Arc<UdpSocket>> =
-            fetch_sockets.into_iter().map(Arc::new).collect();
-        blob_sockets.push(repair_socket.clone());
-        let fetch_stage = BlobFetchStage::new_multi_socket(blob_sockets, &blob_fetch_sender, &exit);
+        let blob_sockets: Vec<Arc<UdpSocket>> = fetch_sockets.into_iter().map(Arc::new).collect();
+        let fetch_stage = BlobFetchStage::new_with_repair_socket(
+            blob_sockets,
+            repair_socket.clone(),
+            &blob_fetch_sender,
+            &exit,
+        );
+        let rpc_repair = rpc_repair_config.map(|rpc_repair_config| RpcRepair {
+            config: rpc_repair_config.clone(),
+            blob_sender: blob_fetch_sender.clone(),
+        });
 
         //TODO
         //the packets coming out of blob_receiver need to be sent to the GPU and verified
@@ -111,6 +130,7 @@ impl Tvu {
             &exit,
             completed_slots_receiver,
             *bank_forks.read().unwrap().working_bank().epoch_schedule(),
+            rpc_repair,
         );
 
         let (blockstream_slot_sender, blockstream_slot_receiver) = channel();
@@ -126,15 +146,23 @@ impl Tvu {
             &exit,
             ledger_signal_receiver,
             subscriptions,
+            block_commitment_cache,
             poh_recorder,
             leader_schedule_cache,
             vec![blockstream_slot_sender, ledger_cleanup_slot_sender],
+            gossip_leader_slots,
+            trusted_validators.cloned().unwrap_or_default(),
+            halt_on_trusted_validators_accounts_hash_mismatch,
+            tower_path,
+            dev_halt_at_slot,
+            leader_disabled,
         );
 
         let blockstream_service = if blockstream.is_some() {
             let blockstream_service = BlockstreamService::new(
                 blockstream_slot_receiver,
                 blocktree.clone(),
+                bank_forks.clone(),
                 blockstream.unwrap().to_string(),
                 &exit,
             );
@@ -148,6 +176,7 @@ impl Tvu {
                 ledger_cleanup_slot_receiver,
                 blocktree.clone(),
                 max_ledger_slots,
+                subscriptions,
                 &exit,
             )
         });
@@ -156,7 +185,6 @@ impl Tvu {
             storage_state,
             root_bank_receiver,
             Some(blocktree),
-            &keypair,
             storage_keypair,
             &exit,
             &bank_forks,
@@ -247,8 +275,16 @@ pub mod tests {
             &StorageState::default(),
             None,
             None,
+            None,
+            false,
+            None,
+            false,
+            None,
+            None,
+            false,
             l_receiver,
             &Arc::new(RpcSubscriptions::default()),
+            &Arc::new(RwLock::new(BlockCommitmentCache::default())),
             &poh_recorder,
             &leader_schedule_cache,
             &exit,
@@ -1,33 +1,47 @@
 //! The `fullnode` module hosts all the fullnode microservices.
 
-use crate::bank_forks::BankForks;
+use crate::bank_forks::{BankForks, ForkPruningConfig};
+use crate::block_commitment::BlockCommitmentCache;
 use crate::blocktree::{Blocktree, CompletedSlotsReceiver};
 use crate::blocktree_processor::{self, BankForksInfo};
 use crate::broadcast_stage::BroadcastStageType;
 use crate::cluster_info::{ClusterInfo, Node};
+use crate::cluster_info_vote_listener::ClusterInfoVoteListener;
+use crate::consensus;
 use crate::contact_info::ContactInfo;
+use crate::crds_value::compute_shred_version;
 use crate::erasure::ErasureConfig;
 use crate::gossip_service::{discover_cluster, GossipService};
 use crate::leader_schedule_cache::LeaderScheduleCache;
+use crate::ledger_storage::FileLedgerStorage;
+use crate::ledger_upload_service::LedgerUploadService;
+use crate::owner_change_auditor::OwnerChangeAuditor;
+use crate::partition_cfg::PartitionCfg;
 use crate::poh_recorder::PohRecorder;
 use crate::poh_service::PohService;
+use crate::quic_streamer::QuicTpuConfig;
+use crate::repair_service::RpcRepairConfig;
 use crate::rpc::JsonRpcConfig;
 use crate::rpc_pubsub_service::PubSubService;
 use crate::rpc_service::JsonRpcService;
 use crate::rpc_subscriptions::RpcSubscriptions;
 use crate::service::Service;
 use crate::storage_stage::StorageState;
+use crate::streamer::CoalesceConfig;
 use crate::tpu::Tpu;
 use crate::tvu::{Sockets, Tvu};
+use solana_drone::drone::run_local_drone;
 use solana_metrics::datapoint_info;
+use solana_runtime::plugin_manager::PluginManager;
 use solana_sdk::genesis_block::GenesisBlock;
-use solana_sdk::poh_config::PohConfig;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::{Keypair, KeypairUtil};
+use solana_sdk::signature::{read_keypair, Keypair, KeypairUtil};
 use solana_sdk::timing::{timestamp, DEFAULT_SLOTS_PER_TURN};
+use std::collections::HashSet;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::Receiver;
+use std::sync::mpsc::{channel, Receiver};
 use std::sync::{Arc, Mutex, RwLock};
 use std::thread::Result;
 
@@ -37,12 +51,35 @@ pub struct ValidatorConfig {
     pub voting_disabled: bool,
     pub blockstream: Option<String>,
     pub storage_slots_per_turn: u64,
+    /// Keypair file of the account that pays for storage transactions, the node identity if None
+    pub storage_identity_keypair: Option<String>,
+    pub storage_mining_disabled: bool,
     pub account_paths: Option<String>,
     pub rpc_config: JsonRpcConfig,
     pub snapshot_path: Option<String>,
     pub max_ledger_slots: Option<u64>,
     pub broadcast_stage_type: BroadcastStageType,
     pub erasure_config: ErasureConfig,
+    pub rpc_repair_config: Option<RpcRepairConfig>,
+    pub gossip_leader_slots: bool,
+    /// Check this node's epoch boundary accounts hashes against these validators'
+    pub trusted_validators: Option<HashSet<Pubkey>>,
+    pub halt_on_trusted_validators_accounts_hash_mismatch: bool,
+    pub fork_pruning_config: ForkPruningConfig,
+    /// Config files of the account update plugins to load
+    pub account_update_plugin_config_files: Vec<String>,
+    /// Upload rooted blocks to the RPC config's long-term ledger storage
+    pub upload_ledger: bool,
+    /// Also accept transactions over QUIC. Experimental.
+    pub quic_tpu_config: Option<QuicTpuConfig>,
+    /// How the TPU batches the transactions it receives for signature verification
+    pub coalesce_config: CoalesceConfig,
+    /// Stop processing the ledger past this slot, for debugging
+    pub dev_halt_at_slot: Option<u64>,
+    /// Replay the ledger and serve RPC without a TPU, never voting or producing blocks
+    pub rpc_only: bool,
+    /// Partitions a test splits the cluster into
+    pub partition_cfg: Option<PartitionCfg>,
 }
 
 impl Default for ValidatorConfig {
@@ -52,12 +89,26 @@ impl Default for ValidatorConfig {
             voting_disabled: false,
             blockstream: None,
             storage_slots_per_turn: DEFAULT_SLOTS_PER_TURN,
+            storage_identity_keypair: None,
+            storage_mining_disabled: false,
             max_ledger_slots: None,
             account_paths: None,
             rpc_config: JsonRpcConfig::default(),
             snapshot_path: None,
             broadcast_stage_type: BroadcastStageType::Standard,
             erasure_config: ErasureConfig::default(),
+            rpc_repair_config: None,
+            gossip_leader_slots: false,
+            trusted_validators: None,
+            halt_on_trusted_validators_accounts_hash_mismatch: false,
+            fork_pruning_config: ForkPruningConfig::default(),
+            account_update_plugin_config_files: vec![],
+            upload_ledger: false,
+            quic_tpu_config: None,
+            coalesce_config: CoalesceConfig::default(),
+            dev_halt_at_slot: None,
+            rpc_only: false,
+            partition_cfg: None,
         }
     }
 }
@@ -67,10 +118,12 @@ pub struct Validator {
     exit: Arc<AtomicBool>,
     rpc_service: Option<JsonRpcService>,
     rpc_pubsub_service: Option<PubSubService>,
+    ledger_upload_service: Option<LedgerUploadService>,
     gossip_service: GossipService,
     poh_recorder: Arc<Mutex<PohRecorder>>,
     poh_service: PohService,
-    tpu: Tpu,
+    tpu: Option<Tpu>,
+    cluster_info_vote_listener: Option<ClusterInfoVoteListener>,
     tvu: Tvu,
     ip_echo_server: solana_netutil::IpEchoServer,
 }
@@ -94,25 +147,40 @@ impl Validator {
 
         info!("creating bank...");
         let (
-            bank_forks,
+            mut bank_forks,
             bank_forks_info,
             blocktree,
             ledger_signal_receiver,
             completed_slots_receiver,
             leader_schedule_cache,
-            poh_config,
+            genesis_block,
         ) = new_banks_from_blocktree(
             ledger_path,
             config.account_paths.clone(),
             config.snapshot_path.clone(),
             verify_ledger,
+            config.dev_halt_at_slot,
         );
+        let genesis_hash = genesis_block.hash();
+        solana_metrics::set_node_labels(&id, &genesis_hash);
 
         let leader_schedule_cache = Arc::new(leader_schedule_cache);
         let exit = Arc::new(AtomicBool::new(false));
         let bank_info = &bank_forks_info[0];
         let bank = bank_forks[bank_info.bank_slot].clone();
 
+        if !config.account_update_plugin_config_files.is_empty() {
+            let mut plugin_manager = PluginManager::new();
+            for config_file in &config.account_update_plugin_config_files {
+                plugin_manager
+                    .load_plugin(config_file)
+                    .unwrap_or_else(|err| panic!("Unable to load plugin {}: {}", config_file, err));
+            }
+            bank.set_plugin_manager(Arc::new(plugin_manager));
+        }
+        // Every bank replayed or produced from here on descends from this one
+        bank.set_execution_observer(Arc::new(OwnerChangeAuditor::default()));
+
         info!(
             "starting PoH... {} {}",
             bank.tick_height(),
@@ -120,12 +188,17 @@ impl Validator {
         );
         let blocktree = Arc::new(blocktree);
 
-        let poh_config = Arc::new(poh_config);
+        let poh_config = Arc::new(genesis_block.poh_config.clone());
+        let next_leader_slot = if config.rpc_only {
+            None
+        } else {
+            leader_schedule_cache.next_leader_slot(&id, bank.slot(), &bank, Some(&blocktree))
+        };
         let (mut poh_recorder, entry_receiver) = PohRecorder::new_with_clear_signal(
             bank.tick_height(),
             bank.last_blockhash(),
             bank.slot(),
-            leader_schedule_cache.next_leader_slot(&id, bank.slot(), &bank, Some(&blocktree)),
+            next_leader_slot,
             bank.ticks_per_slot(),
             &id,
             &blocktree,
@@ -133,7 +206,7 @@ impl Validator {
             &leader_schedule_cache,
             &poh_config,
         );
-        if config.snapshot_path.is_some() {
+        if config.snapshot_path.is_some() && !config.rpc_only {
             poh_recorder.set_bank(&bank);
         }
 
@@ -152,20 +225,68 @@ impl Validator {
             node.sockets.gossip.local_addr().unwrap()
         );
 
+        bank_forks.set_pruning_config(config.fork_pruning_config);
         let bank_forks = Arc::new(RwLock::new(bank_forks));
 
         node.info.wallclock = timestamp();
+        if config.quic_tpu_config.is_none() || config.rpc_only {
+            node.sockets.tpu_quic = None;
+        }
+        if config.rpc_only {
+            // Clients shouldn't send transactions to a node that won't process them
+            node.info.tpu = socketaddr_any!();
+            node.info.tpu_via_blobs = socketaddr_any!();
+        }
         let cluster_info = Arc::new(RwLock::new(ClusterInfo::new(
             node.info.clone(),
             keypair.clone(),
         )));
+        cluster_info
+            .write()
+            .unwrap()
+            .set_shred_version(compute_shred_version(&genesis_hash));
+        if let Some(partition_cfg) = &config.partition_cfg {
+            cluster_info
+                .write()
+                .unwrap()
+                .set_partition_cfg(partition_cfg.clone());
+        }
+        if let Some(drone_addr) = config.rpc_config.drone_addr {
+            // Let nodes without a drone of their own discover this one
+            cluster_info.write().unwrap().push_faucet(drone_addr);
+        }
+        if let Some(trusted_validators) = &config.trusted_validators {
+            cluster_info
+                .write()
+                .unwrap()
+                .set_trusted_faucets(trusted_validators.clone());
+        }
+        if let Some(tpu_quic) = &node.sockets.tpu_quic {
+            let port = tpu_quic.local_addr().unwrap().port();
+            cluster_info
+                .write()
+                .unwrap()
+                .push_quic_tpu(SocketAddr::new(node.info.tpu.ip(), port));
+        }
 
+        let storage_identity_keypair = if config.storage_mining_disabled {
+            None
+        } else if let Some(path) = &config.storage_identity_keypair {
+            Some(Arc::new(read_keypair(path).unwrap_or_else(|err| {
+                panic!("Unable to read storage identity keypair {}: {}", path, err)
+            })))
+        } else {
+            Some(keypair.clone())
+        };
         let storage_state = StorageState::new(
             &bank.last_blockhash(),
             config.storage_slots_per_turn,
             bank.slots_per_segment(),
+            storage_identity_keypair,
         );
 
+        let block_commitment_cache = Arc::new(RwLock::new(BlockCommitmentCache::default()));
+
         let rpc_service = if node.info.rpc.port() == 0 {
             None
         } else {
@@ -175,14 +296,32 @@ impl Validator {
                 storage_state.clone(),
                 config.rpc_config.clone(),
                 bank_forks.clone(),
+                block_commitment_cache.clone(),
+                blocktree.clone(),
                 &exit,
             ))
         };
 
+        let ledger_upload_service = if config.upload_ledger {
+            let path = config
+                .rpc_config
+                .ledger_storage_path
+                .as_ref()
+                .expect("uploading the ledger requires a ledger storage path");
+            Some(LedgerUploadService::new(
+                Arc::new(FileLedgerStorage::new(path)),
+                blocktree.clone(),
+                bank_forks.clone(),
+                &exit,
+            ))
+        } else {
+            None
+        };
+
         let ip_echo_server =
             solana_netutil::ip_echo_server(node.sockets.gossip.local_addr().unwrap().port());
 
-        let subscriptions = Arc::new(RpcSubscriptions::default());
+        let subscriptions = Arc::new(RpcSubscriptions::new(block_commitment_cache.clone()));
         let rpc_pubsub_service = if node.info.rpc_pubsub.port() == 0 {
             None
         } else {
@@ -233,12 +372,13 @@ impl Validator {
                 .collect(),
         };
 
-        let voting_keypair = if config.voting_disabled {
+        let voting_keypair = if config.voting_disabled || config.rpc_only {
             None
         } else {
             Some(voting_keypair)
         };
-
+        // A node that doesn't vote has no lockouts to keep
+        let tower_path = voting_keypair.map(|_| consensus::tower_path(Path::new(ledger_path), &id));
         let tvu = Tvu::new(
             vote_account,
             voting_keypair,
@@ -250,8 +390,16 @@ impl Validator {
             &storage_state,
             config.blockstream.as_ref(),
             config.max_ledger_slots,
+            config.rpc_repair_config.as_ref(),
+            config.gossip_leader_slots,
+            config.trusted_validators.as_ref(),
+            config.halt_on_trusted_validators_accounts_hash_mismatch,
+            tower_path.as_ref().map(PathBuf::as_path),
+            config.dev_halt_at_slot,
+            config.rpc_only,
             ledger_signal_receiver,
             &subscriptions,
+            &block_commitment_cache,
             &poh_recorder,
             &leader_schedule_cache,
             &exit,
@@ -262,19 +410,43 @@ impl Validator {
             warn!("signature verification disabled");
         }
 
-        let tpu = Tpu::new(
-            &cluster_info,
-            &poh_recorder,
-            entry_receiver,
-            node.sockets.tpu,
-            node.sockets.tpu_via_blobs,
-            node.sockets.broadcast,
-            config.sigverify_disabled,
-            &blocktree,
-            &config.broadcast_stage_type,
-            &config.erasure_config,
-            &exit,
-        );
+        let (tpu, cluster_info_vote_listener) = if config.rpc_only {
+            info!("RPC-only mode, not starting the TPU");
+            // Gossiped votes still feed the commitment served over RPC
+            let cluster_info_vote_listener = ClusterInfoVoteListener::new_commitment_only(
+                &exit,
+                cluster_info.clone(),
+                config.sigverify_disabled,
+                &bank_forks,
+                &block_commitment_cache,
+            );
+            (None, Some(cluster_info_vote_listener))
+        } else {
+            let tpu = Tpu::new(
+                &cluster_info,
+                &poh_recorder,
+                &bank_forks,
+                &block_commitment_cache,
+                entry_receiver,
+                node.sockets.tpu,
+                node.sockets.tpu_via_blobs,
+                node.sockets.tpu_quic.map(|socket| {
+                    (
+                        socket,
+                        keypair.as_ref(),
+                        config.quic_tpu_config.as_ref().unwrap(),
+                    )
+                }),
+                &config.coalesce_config,
+                node.sockets.broadcast,
+                config.sigverify_disabled,
+                &blocktree,
+                &config.broadcast_stage_type,
+                &config.erasure_config,
+                &exit,
+            );
+            (Some(tpu), None)
+        };
 
         datapoint_info!("validator-new");
         Self {
@@ -282,7 +454,9 @@ impl Validator {
             gossip_service,
             rpc_service,
             rpc_pubsub_service,
+            ledger_upload_service,
             tpu,
+            cluster_info_vote_listener,
             tvu,
             exit,
             poh_service,
@@ -308,6 +482,7 @@ fn get_bank_forks(
     account_paths: Option<String>,
     snapshot_path: Option<String>,
     verify_ledger: bool,
+    dev_halt_at_slot: Option<u64>,
 ) -> (BankForks, Vec<BankForksInfo>, LeaderScheduleCache) {
     if snapshot_path.is_some() {
         let bank_forks =
@@ -330,6 +505,7 @@ fn get_bank_forks(
             &blocktree,
             account_paths,
             verify_ledger,
+            dev_halt_at_slot,
         )
         .expect("process_blocktree failed");
     if snapshot_path.is_some() {
@@ -344,6 +520,7 @@ pub fn new_banks_from_blocktree(
     account_paths: Option<String>,
     snapshot_path: Option<String>,
     verify_ledger: bool,
+    dev_halt_at_slot: Option<u64>,
 ) -> (
     BankForks,
     Vec<BankForksInfo>,
@@ -351,7 +528,7 @@ pub fn new_banks_from_blocktree(
     Receiver<bool>,
     CompletedSlotsReceiver,
     LeaderScheduleCache,
-    PohConfig,
+    GenesisBlock,
 ) {
     let genesis_block =
         GenesisBlock::load(blocktree_path).expect("Expected to successfully open genesis block");
@@ -366,6 +543,7 @@ pub fn new_banks_from_blocktree(
         account_paths,
         snapshot_path,
         verify_ledger,
+        dev_halt_at_slot,
     );
 
     (
@@ -375,7 +553,7 @@ pub fn new_banks_from_blocktree(
         ledger_signal_receiver,
         completed_slots_receiver,
         leader_schedule_cache,
-        genesis_block.poh_config,
+        genesis_block,
     )
 }
 
@@ -391,9 +569,17 @@ impl Service for Validator {
         if let Some(rpc_pubsub_service) = self.rpc_pubsub_service {
             rpc_pubsub_service.join()?;
         }
+        if let Some(ledger_upload_service) = self.ledger_upload_service {
+            ledger_upload_service.join()?;
+        }
 
         self.gossip_service.join()?;
-        self.tpu.join()?;
+        if let Some(tpu) = self.tpu {
+            tpu.join()?;
+        }
+        if let Some(cluster_info_vote_listener) = self.cluster_info_vote_listener {
+            cluster_info_vote_listener.join()?;
+        }
         self.tvu.join()?;
         self.ip_echo_server.shutdown_now();
 
@@ -402,6 +588,29 @@ impl Service for Validator {
 }
 
 pub fn new_validator_for_tests() -> (Validator, ContactInfo, Keypair, String) {
+    new_validator_for_tests_with_config(|_| ValidatorConfig::default())
+}
+
+/// A validator whose RPC service forwards airdrop requests to a drone funded by the mint
+pub fn new_validator_with_drone_for_tests() -> (Validator, ContactInfo, String) {
+    let (node, contact_info, _mint_keypair, ledger_path) =
+        new_validator_for_tests_with_config(|mint_keypair| {
+            let (sender, receiver) = channel();
+            let drone_keypair = Keypair::from_bytes(&mint_keypair.to_bytes()).unwrap();
+            run_local_drone(drone_keypair, sender, None);
+            let mut config = ValidatorConfig::default();
+            config.rpc_config.drone_addr = Some(receiver.recv().unwrap());
+            config
+        });
+    (node, contact_info, ledger_path)
+}
+
+fn new_validator_for_tests_with_config<F>(
+    validator_config: F,
+) -> (Validator, ContactInfo, Keypair, String)
+where
+    F: FnOnce(&Keypair) -> ValidatorConfig,
+{
     use crate::blocktree::create_new_tmp_ledger;
     use crate::genesis_utils::{create_genesis_block_with_leader, GenesisBlockInfo};
 
@@ -422,6 +631,7 @@ pub fn new_validator_for_tests() -> (Validator, ContactInfo, Keypair, String) {
 
     let voting_keypair = Arc::new(Keypair::new());
     let storage_keypair = Arc::new(Keypair::new());
+    let config = validator_config(&mint_keypair);
     let node = Validator::new(
         node,
         &node_keypair,
@@ -431,7 +641,7 @@ pub fn new_validator_for_tests() -> (Validator, ContactInfo, Keypair, String) {
         &storage_keypair,
         None,
         true,
-        &ValidatorConfig::default(),
+        &config,
     );
     discover_cluster(&contact_info.gossip, 1).expect("Node startup failed");
     (node, contact_info, mint_keypair, ledger_path)
@@ -4,8 +4,8 @@
 use crate::blocktree::Blocktree;
 use crate::cluster_info::ClusterInfo;
 use crate::leader_schedule_cache::LeaderScheduleCache;
-use crate::packet::{Blob, SharedBlob};
-use crate::repair_service::{RepairService, RepairStrategy};
+use crate::packet::{Blob, SharedBlob, BLOB_HEADER_SIZE};
+use crate::repair_service::{OutstandingRepairs, RepairService, RepairStrategy};
 use crate::result::{Error, Result};
 use crate::service::Service;
 use crate::streamer::{BlobReceiver, BlobSender};
@@ -15,7 +15,7 @@ use solana_metrics::{inc_new_counter_debug, inc_new_counter_error};
 use solana_runtime::bank::Bank;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signable;
-use solana_sdk::timing::duration_as_ms;
+use solana_sdk::timing::{duration_as_ms, timestamp};
 use std::net::UdpSocket;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::RecvTimeoutError;
@@ -67,6 +67,37 @@ pub fn process_blobs(blobs: &[SharedBlob], blocktree: &Arc<Blocktree>) -> Result
     Ok(())
 }
 
+/// Persist and gossip a proof for any data blob that conflicts with the blob
+/// already stored at the same slot and index. Blobs have passed the check
+/// that they come from the slot's leader by this point, so both blobs must
+/// carry the signature of the blob's id for a conflict to show the leader
+/// equivocated.
+pub fn check_duplicate_blobs(
+    blobs: &[SharedBlob],
+    blocktree: &Arc<Blocktree>,
+    cluster_info: &Arc<RwLock<ClusterInfo>>,
+) -> Result<()> {
+    for blob in blobs {
+        let blob = blob.read().unwrap();
+        let slot = blob.slot();
+        if blob.is_coding() || blocktree.has_duplicate_blobs_in_slot(slot) {
+            continue;
+        }
+        if let Some(existing) = blocktree.is_blob_duplicate(&blob, &blob.id()) {
+            inc_new_counter_error!("streamer-recv_window-duplicate_blob", 1);
+            let payload = blob.data[..BLOB_HEADER_SIZE + blob.size()].to_vec();
+            blocktree.store_duplicate_slot(slot, blob.index(), existing, payload)?;
+            if let Some(proof) = blocktree.get_duplicate_slot(slot) {
+                cluster_info
+                    .write()
+                    .unwrap()
+                    .push_duplicate_slot(slot, &proof);
+            }
+        }
+    }
+    Ok(())
+}
+
 /// drop blobs that are from myself or not from the correct leader for the
 /// blob's slot
 pub fn should_retransmit_and_persist(
@@ -99,12 +130,38 @@ pub fn should_retransmit_and_persist(
     }
 }
 
+/// Drops the blobs from the repair socket that don't answer an outstanding repair request, and
+/// strips the nonces from the ones that do
+pub fn filter_repair_responses(
+    blobs: Vec<SharedBlob>,
+    outstanding_repairs: &RwLock<OutstandingRepairs>,
+) -> Vec<SharedBlob> {
+    let now = timestamp();
+    let mut outstanding_repairs = outstanding_repairs.write().unwrap();
+    let num_blobs = blobs.len();
+    let blobs: Vec<_> = blobs
+        .into_iter()
+        .filter(|blob| {
+            let mut blob = blob.write().unwrap();
+            !blob.meta.repair || outstanding_repairs.register_response(&mut blob, now)
+        })
+        .collect();
+    inc_new_counter_debug!(
+        "streamer-recv_window-unsolicited_repair",
+        num_blobs - blobs.len()
+    );
+    blobs
+}
+
+#[allow(clippy::too_many_arguments)]
 fn recv_window<F>(
     blocktree: &Arc<Blocktree>,
+    cluster_info: &Arc<RwLock<ClusterInfo>>,
     my_pubkey: &Pubkey,
     r: &BlobReceiver,
     retransmit: &BlobSender,
     blob_filter: F,
+    outstanding_repairs: &RwLock<OutstandingRepairs>,
     thread_pool: &ThreadPool,
 ) -> Result<()>
 where
@@ -120,6 +177,7 @@ where
     let now = Instant::now();
     inc_new_counter_debug!("streamer-recv_window-recv", blobs.len(), 0, 1000);
 
+    let blobs = filter_repair_responses(blobs, outstanding_repairs);
     let blobs: Vec<_> = thread_pool.install(|| {
         blobs
             .into_par_iter()
@@ -135,6 +193,7 @@ where
 
     trace!("{} num blobs received: {}", my_pubkey, blobs.len());
 
+    check_duplicate_blobs(&blobs, blocktree, cluster_info)?;
     process_blobs(&blobs, blocktree)?;
 
     trace!(
@@ -192,12 +251,14 @@ impl WindowService {
             RepairStrategy::RepairAll { ref bank_forks, .. } => Some(bank_forks.clone()),
         };
 
+        let outstanding_repairs = Arc::new(RwLock::new(OutstandingRepairs::default()));
         let repair_service = RepairService::new(
             blocktree.clone(),
             exit.clone(),
             repair_socket,
             cluster_info.clone(),
             repair_strategy,
+            outstanding_repairs.clone(),
         );
         let exit = exit.clone();
         let blob_filter = Arc::new(blob_filter);
@@ -222,6 +283,7 @@ impl WindowService {
 
                     if let Err(e) = recv_window(
                         &blocktree,
+                        &cluster_info,
                         &id,
                         &r,
                         &retransmit,
@@ -234,6 +296,7 @@ impl WindowService {
                                     .map(|bank_forks| bank_forks.read().unwrap().working_bank()),
                             )
                         },
+                        &outstanding_repairs,
                         &thread_pool,
                     ) {
                         match e {
@@ -316,6 +379,42 @@ mod test {
         Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_check_duplicate_blobs() {
+        let blocktree_path = get_tmp_ledger_path!();
+        let blocktree = Arc::new(Blocktree::open(&blocktree_path).unwrap());
+        let node = Node::new_localhost();
+        let cluster_info = Arc::new(RwLock::new(ClusterInfo::new_with_invalid_keypair(
+            node.info.clone(),
+        )));
+        let leader = Keypair::new();
+        let shared_blobs = make_tiny_test_entries(1).to_shared_blobs();
+        index_blobs(&shared_blobs, &leader.pubkey(), 0, 0, 0);
+        shared_blobs[0].write().unwrap().sign(&leader);
+        process_blobs(&shared_blobs, &blocktree).unwrap();
+
+        // The same blob again is not a conflict
+        check_duplicate_blobs(&shared_blobs, &blocktree, &cluster_info).unwrap();
+        assert!(!blocktree.has_duplicate_blobs_in_slot(0));
+
+        let mut conflicting = shared_blobs[0].read().unwrap().clone();
+        conflicting.data[BLOB_HEADER_SIZE] ^= 1;
+        conflicting.sign(&leader);
+        let conflicting = Arc::new(RwLock::new(conflicting));
+        check_duplicate_blobs(&[conflicting], &blocktree, &cluster_info).unwrap();
+
+        let proof = blocktree.get_duplicate_slot(0).unwrap();
+        assert_eq!(proof.index, 0);
+        assert_ne!(proof.blob1, proof.blob2);
+        assert_eq!(
+            cluster_info.read().unwrap().get_duplicate_slot_proofs(),
+            vec![(node.info.id, 0, proof)]
+        );
+
+        drop(blocktree);
+        Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     fn test_should_retransmit_and_persist() {
         let me_id = Pubkey::new_rand();
@@ -390,6 +489,7 @@ mod test {
                 .working_bank()
                 .epoch_schedule()
                 .clone(),
+            rpc_repair: None,
         };
         let t_window = WindowService::new(
             blocktree,
@@ -472,6 +572,7 @@ mod test {
             bank_forks,
             completed_slots_receiver,
             epoch_schedule,
+            rpc_repair: None,
         };
         let t_window = WindowService::new(
             blocktree,
@@ -3,13 +3,14 @@ extern crate solana;
 use hashbrown::HashSet;
 use log::*;
 use serial_test_derive::serial;
-use solana::blocktree::Blocktree;
+use solana::bank_forks::BankForks;
+use solana::blocktree::{get_tmp_ledger_path, Blocktree};
 use solana::broadcast_stage::BroadcastStageType;
 use solana::cluster::Cluster;
 use solana::cluster_tests;
 use solana::gossip_service::discover_cluster;
 use solana::local_cluster::{ClusterConfig, LocalCluster};
-use solana::validator::ValidatorConfig;
+use solana::validator::{self, ValidatorConfig};
 use solana_runtime::epoch_schedule::{EpochSchedule, MINIMUM_SLOTS_PER_EPOCH};
 use solana_sdk::client::SyncClient;
 use solana_sdk::poh_config::PohConfig;
@@ -258,6 +259,150 @@ fn test_restart_node() {
     cluster_tests::send_many_transactions(&cluster.entry_point_info, &cluster.funding_keypair, 1);
 }
 
+#[test]
+#[serial]
+fn test_snapshot_restart_matches_full_replay() {
+    solana_logger::setup();
+    let slots_per_epoch = MINIMUM_SLOTS_PER_EPOCH as u64;
+    let ticks_per_slot = 16;
+    let mut validator_config = ValidatorConfig::default();
+    // The accounts must outlive the node for its snapshots to be loaded
+    validator_config.account_paths = Some(get_tmp_ledger_path("snapshot_restart_accounts"));
+    validator_config.snapshot_path = Some(get_tmp_ledger_path("snapshot_restart_snapshots"));
+    let mut cluster = LocalCluster::new(&ClusterConfig {
+        node_stakes: vec![3],
+        cluster_lamports: 100,
+        validator_configs: vec![validator_config.clone()],
+        ticks_per_slot,
+        slots_per_epoch,
+        ..ClusterConfig::default()
+    });
+    let node_pubkey = cluster.get_node_pubkeys()[0];
+    cluster_tests::sleep_n_epochs(
+        1.0,
+        &cluster.genesis_block.poh_config,
+        ticks_per_slot,
+        slots_per_epoch,
+    );
+
+    // Restart the node from its snapshot, and let it root more slots
+    let restart_slot = cluster
+        .get_validator_client(&node_pubkey)
+        .unwrap()
+        .get_slot()
+        .unwrap();
+    cluster.restart_node(node_pubkey);
+    cluster_tests::sleep_n_epochs(
+        2.0,
+        &cluster.genesis_block.poh_config,
+        ticks_per_slot,
+        slots_per_epoch,
+    );
+    cluster_tests::send_many_transactions(&cluster.entry_point_info, &cluster.funding_keypair, 1);
+    cluster.close_preserve_ledgers();
+
+    // The banks the restarted node snapshotted
+    let snapshot_bank_forks = BankForks::load_from_snapshot(
+        &cluster.genesis_block,
+        validator_config.account_paths.clone(),
+        &validator_config.snapshot_path,
+    )
+    .unwrap();
+    assert!(
+        snapshot_bank_forks.root() > restart_slot,
+        "no root after the restart at slot {}",
+        restart_slot
+    );
+
+    // The roots the node made after the restart, which it built on the snapshot's accounts
+    let ledger_path = &cluster.fullnode_infos[&node_pubkey].info.ledger_path;
+    let new_roots: Vec<_> = {
+        let blocktree = Blocktree::open(ledger_path).unwrap();
+        let mut new_roots: Vec<_> = snapshot_bank_forks
+            .frozen_banks()
+            .into_iter()
+            .filter(|(slot, _)| *slot > restart_slot && blocktree.is_root(*slot))
+            .collect();
+        new_roots.sort_by_key(|(slot, _)| *slot);
+        new_roots
+    };
+    assert!(!new_roots.is_empty());
+
+    // Replay the node's entire ledger into fresh accounts up to each new root, and check the
+    // replayed bank matches
+    for (slot, bank) in new_roots {
+        let (replayed_bank_forks, ..) =
+            validator::new_banks_from_blocktree(ledger_path, None, None, true, Some(slot));
+        let replayed_bank = replayed_bank_forks
+            .get(slot)
+            .unwrap_or_else(|| panic!("full replay has no bank for root {}", slot));
+        assert_eq!(replayed_bank.hash(), bank.hash(), "root {}", slot);
+    }
+}
+
+#[test]
+#[serial]
+fn test_partition_heal_and_restart() {
+    solana_logger::setup();
+    let slots_per_epoch = MINIMUM_SLOTS_PER_EPOCH as u64;
+    let num_nodes = 4;
+    let mut cluster = LocalCluster::new(&ClusterConfig {
+        node_stakes: vec![100; num_nodes],
+        cluster_lamports: 10_000,
+        validator_configs: vec![ValidatorConfig::default(); num_nodes],
+        slots_per_epoch,
+        ..ClusterConfig::default()
+    });
+    let nodes = cluster.get_node_pubkeys();
+
+    // Split the stake in half so neither side can root, and let each side build its own forks
+    cluster.partition(&[&nodes[..2], &nodes[2..]], Duration::from_secs(600));
+    cluster_tests::sleep_n_epochs(
+        1.0,
+        &cluster.genesis_block.poh_config,
+        timing::DEFAULT_TICKS_PER_SLOT,
+        slots_per_epoch,
+    );
+    cluster.heal_partitions();
+    cluster_tests::spend_and_verify_all_nodes(
+        &cluster.entry_point_info,
+        &cluster.funding_keypair,
+        num_nodes,
+        HashSet::new(),
+    );
+
+    // The rest of the cluster keeps confirming while a node is down, and the node catches up
+    // from its ledger once restarted
+    let restarted_node = *nodes
+        .iter()
+        .find(|pubkey| **pubkey != cluster.entry_point_info.id)
+        .unwrap();
+    cluster.exit_node(&restarted_node);
+    let mut ignore_nodes = HashSet::new();
+    ignore_nodes.insert(restarted_node);
+    cluster_tests::spend_and_verify_all_nodes(
+        &cluster.entry_point_info,
+        &cluster.funding_keypair,
+        num_nodes - 1,
+        ignore_nodes,
+    );
+    cluster.restart_exited_node(&restarted_node);
+    cluster_tests::spend_and_verify_all_nodes(
+        &cluster.entry_point_info,
+        &cluster.funding_keypair,
+        num_nodes,
+        HashSet::new(),
+    );
+
+    cluster.close_preserve_ledgers();
+    let ledger_paths: Vec<_> = cluster
+        .fullnode_infos
+        .values()
+        .map(|fullnode_info| fullnode_info.info.ledger_path.as_str())
+        .collect();
+    assert!(cluster_tests::verify_rooted_forks_agree(&ledger_paths) > 0);
+}
+
 #[test]
 #[serial]
 fn test_listener_startup() {
@@ -3,6 +3,7 @@ extern crate solana;
 
 use log::*;
 use solana::banking_stage::create_test_recorder;
+use solana::block_commitment::BlockCommitmentCache;
 use solana::blocktree::{create_new_tmp_ledger, Blocktree};
 use solana::cluster_info::{ClusterInfo, Node};
 use solana::entry::next_entry_mut;
@@ -97,7 +98,7 @@ fn test_replay() {
         completed_slots_receiver,
         leader_schedule_cache,
         _,
-    ) = validator::new_banks_from_blocktree(&blocktree_path, None, None, true);
+    ) = validator::new_banks_from_blocktree(&blocktree_path, None, None, true, None);
     let working_bank = bank_forks.working_bank();
     assert_eq!(
         working_bank.get_balance(&mint_keypair.pubkey()),
@@ -135,8 +136,16 @@ fn test_replay() {
             &StorageState::default(),
             None,
             None,
+            None,
+            false,
+            None,
+            false,
+            None,
+            None,
+            false,
             ledger_signal_receiver,
             &Arc::new(RpcSubscriptions::default()),
+            &Arc::new(RwLock::new(BlockCommitmentCache::default())),
             &poh_recorder,
             &leader_schedule_cache,
             &exit,
@@ -0,0 +1,376 @@
+//! The `airdrop_log` module keeps an append-only record of every airdrop the drone
+//! grants, along with running and per-day totals, so that faucet operators can
+//! account for the lamports a drone has given away.
+
+use bincode::{deserialize, serialize};
+use byteorder::{ByteOrder, LittleEndian};
+use rand::{thread_rng, Rng};
+use serde_derive::{Deserialize, Serialize};
+use solana_sdk::hash::{hashv, Hash};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// Each record is stored as its length (u32) followed by its bincode encoding
+const RECORD_HEADER_LEN: usize = 4;
+
+const IP_HASH_KEY_LEN: usize = 32;
+
+/// The drone's secret key for hashing requester IP addresses. There are few enough
+/// addresses that an unkeyed hash could be reversed by hashing every one of them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IpHashKey([u8; IP_HASH_KEY_LEN]);
+
+impl IpHashKey {
+    pub fn new_rand() -> Self {
+        IpHashKey(thread_rng().gen())
+    }
+
+    /// Load the key at `path`, creating a new one there if it doesn't exist, so that
+    /// the hashes of a log stay comparable across restarts
+    pub fn load_or_create(path: &Path) -> io::Result<Self> {
+        match fs::read(path) {
+            Ok(bytes) => {
+                if bytes.len() != IP_HASH_KEY_LEN {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{:?} is not an IP hash key", path),
+                    ));
+                }
+                let mut key = [0; IP_HASH_KEY_LEN];
+                key.copy_from_slice(&bytes);
+                Ok(IpHashKey(key))
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                let key = Self::new_rand();
+                OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(path)?
+                    .write_all(&key.0)?;
+                Ok(key)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn hash(&self, ip: IpAddr) -> Hash {
+        match ip {
+            IpAddr::V4(ip) => hashv(&[&self.0, &ip.octets()]),
+            IpAddr::V6(ip) => hashv(&[&self.0, &ip.octets()]),
+        }
+    }
+}
+
+impl Default for IpHashKey {
+    fn default() -> Self {
+        Self::new_rand()
+    }
+}
+
+/// A granted airdrop. Only a keyed hash of the requester's IP address is kept.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AirdropRecord {
+    pub to: Pubkey,
+    pub lamports: u64,
+    /// Seconds since the UNIX epoch
+    pub timestamp: u64,
+    pub ip_hash: Hash,
+}
+
+impl AirdropRecord {
+    pub fn new(to: Pubkey, lamports: u64, ip_hash: Hash) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        Self {
+            to,
+            lamports,
+            timestamp,
+            ip_hash,
+        }
+    }
+
+    /// The first second of the UTC day this airdrop was granted in
+    pub fn day_start(&self) -> u64 {
+        self.timestamp - self.timestamp % SECONDS_PER_DAY
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct AirdropTotals {
+    pub airdrops: u64,
+    pub lamports: u64,
+}
+
+impl AirdropTotals {
+    fn add(&mut self, lamports: u64) {
+        self.airdrops += 1;
+        self.lamports += lamports;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyAirdropTotals {
+    /// The first second of the UTC day, since the UNIX epoch
+    pub day_start: u64,
+    pub airdrops: u64,
+    pub lamports: u64,
+}
+
+/// The totals served by the drone's stats endpoint
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AirdropStats {
+    pub total: AirdropTotals,
+    pub daily: Vec<DailyAirdropTotals>,
+}
+
+/// Totals of the granted airdrops, persisted to a file if the log was opened with one
+#[derive(Default)]
+pub struct AirdropLog {
+    file: Option<File>,
+    ip_hash_key: IpHashKey,
+    total: AirdropTotals,
+    daily: BTreeMap<u64, AirdropTotals>,
+}
+
+/// Where the IP hash key of the log at `path` is kept
+pub fn ip_hash_key_path(path: &Path) -> PathBuf {
+    let mut key_path = path.as_os_str().to_owned();
+    key_path.push(".key");
+    PathBuf::from(key_path)
+}
+
+impl AirdropLog {
+    /// Open the log at `path`, creating it and its IP hash key if they don't exist, and
+    /// total the airdrops already recorded in it
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let (records, valid_len) = read_records_and_len(path)?;
+        let mut airdrop_log = Self {
+            ip_hash_key: IpHashKey::load_or_create(&ip_hash_key_path(path))?,
+            ..Self::default()
+        };
+        for record in &records {
+            airdrop_log.add_to_totals(record);
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        // Drop a record cut short by a crash, so that new records can be read back
+        file.set_len(valid_len)?;
+        airdrop_log.file = Some(file);
+        Ok(airdrop_log)
+    }
+
+    /// Hash `ip` with this log's key, for a record of an airdrop it requested
+    pub fn hash_ip(&self, ip: IpAddr) -> Hash {
+        self.ip_hash_key.hash(ip)
+    }
+
+    pub fn record(&mut self, record: &AirdropRecord) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            let data = serialize(record)
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{:?}", err)))?;
+            let mut bytes = vec![0; RECORD_HEADER_LEN];
+            LittleEndian::write_u32(&mut bytes, data.len() as u32);
+            bytes.extend_from_slice(&data);
+            file.write_all(&bytes)?;
+        }
+        self.add_to_totals(record);
+        Ok(())
+    }
+
+    fn add_to_totals(&mut self, record: &AirdropRecord) {
+        self.total.add(record.lamports);
+        self.daily
+            .entry(record.day_start())
+            .or_default()
+            .add(record.lamports);
+    }
+
+    pub fn total(&self) -> AirdropTotals {
+        self.total
+    }
+
+    pub fn stats(&self) -> AirdropStats {
+        AirdropStats {
+            total: self.total,
+            daily: self
+                .daily
+                .iter()
+                .map(|(day_start, totals)| DailyAirdropTotals {
+                    day_start: *day_start,
+                    airdrops: totals.airdrops,
+                    lamports: totals.lamports,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Read every complete record in the log at `path`
+pub fn read_records(path: &Path) -> io::Result<Vec<AirdropRecord>> {
+    read_records_and_len(path).map(|(records, _valid_len)| records)
+}
+
+// Also return the length of the file up to the end of the last complete record
+fn read_records_and_len(path: &Path) -> io::Result<(Vec<AirdropRecord>, u64)> {
+    let mut bytes = vec![];
+    match File::open(path) {
+        Ok(mut file) => {
+            file.read_to_end(&mut bytes)?;
+        }
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok((vec![], 0)),
+        Err(err) => return Err(err),
+    }
+
+    let mut records = vec![];
+    let mut offset = 0;
+    while bytes.len() - offset >= RECORD_HEADER_LEN {
+        let len = LittleEndian::read_u32(&bytes[offset..]) as usize;
+        let start = offset + RECORD_HEADER_LEN;
+        if bytes.len() - start < len {
+            break;
+        }
+        let record = deserialize(&bytes[start..start + len])
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", err)))?;
+        records.push(record);
+        offset = start + len;
+    }
+    Ok((records, offset as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::{Keypair, KeypairUtil};
+    use std::fs::remove_file;
+    use std::net::Ipv4Addr;
+    use std::path::PathBuf;
+
+    fn tmp_log_path(name: &str) -> PathBuf {
+        let out_dir = std::env::var("FARF_DIR").unwrap_or_else(|_| "farf".to_string());
+        let path = PathBuf::from(format!(
+            "{}/tmp/{}-{}",
+            out_dir,
+            name,
+            Keypair::new().pubkey()
+        ));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        path
+    }
+
+    fn new_record(lamports: u64, timestamp: u64) -> AirdropRecord {
+        AirdropRecord {
+            timestamp,
+            ..AirdropRecord::new(
+                Pubkey::new_rand(),
+                lamports,
+                IpHashKey::new_rand().hash(Ipv4Addr::LOCALHOST.into()),
+            )
+        }
+    }
+
+    #[test]
+    fn test_ip_hash_key() {
+        let ip = Ipv4Addr::new(10, 0, 0, 1).into();
+        let key = IpHashKey::new_rand();
+        assert_eq!(key.hash(ip), key.hash(ip));
+        assert_ne!(key.hash(ip), key.hash(Ipv4Addr::new(10, 0, 0, 2).into()));
+        assert_ne!(key.hash(ip), IpHashKey::new_rand().hash(ip));
+        assert_ne!(key.hash(ip), solana_sdk::hash::hash(&[10, 0, 0, 1]));
+
+        let path = tmp_log_path("test_ip_hash_key");
+        let key = IpHashKey::load_or_create(&path).unwrap();
+        assert_eq!(IpHashKey::load_or_create(&path).unwrap(), key);
+        remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_airdrop_log_totals() {
+        let mut airdrop_log = AirdropLog::default();
+        airdrop_log.record(&new_record(1, 10)).unwrap();
+        airdrop_log.record(&new_record(2, 20)).unwrap();
+        airdrop_log
+            .record(&new_record(4, SECONDS_PER_DAY + 5))
+            .unwrap();
+
+        assert_eq!(
+            airdrop_log.total(),
+            AirdropTotals {
+                airdrops: 3,
+                lamports: 7
+            }
+        );
+        assert_eq!(
+            airdrop_log.stats().daily,
+            vec![
+                DailyAirdropTotals {
+                    day_start: 0,
+                    airdrops: 2,
+                    lamports: 3,
+                },
+                DailyAirdropTotals {
+                    day_start: SECONDS_PER_DAY,
+                    airdrops: 1,
+                    lamports: 4,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_airdrop_log_persists() {
+        let path = tmp_log_path("test_airdrop_log_persists");
+        let records = vec![new_record(1, 10), new_record(2, SECONDS_PER_DAY)];
+        {
+            let mut airdrop_log = AirdropLog::open(&path).unwrap();
+            for record in &records {
+                airdrop_log.record(record).unwrap();
+            }
+        }
+        assert_eq!(read_records(&path).unwrap(), records);
+
+        let ip = Ipv4Addr::LOCALHOST.into();
+        let ip_hash = AirdropLog::open(&path).unwrap().hash_ip(ip);
+        let airdrop_log = AirdropLog::open(&path).unwrap();
+        assert_eq!(airdrop_log.total().lamports, 3);
+        assert_eq!(airdrop_log.stats().daily.len(), 2);
+        // The key is kept with the log, so its hashes are comparable across restarts
+        assert_eq!(airdrop_log.hash_ip(ip), ip_hash);
+        remove_file(&path).unwrap();
+        remove_file(ip_hash_key_path(&path)).unwrap();
+    }
+
+    #[test]
+    fn test_airdrop_log_truncated_record() {
+        let path = tmp_log_path("test_airdrop_log_truncated_record");
+        let record = new_record(1, 10);
+        {
+            let mut airdrop_log = AirdropLog::open(&path).unwrap();
+            airdrop_log.record(&record).unwrap();
+        }
+        // Simulate a crash partway through writing a record
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(&[100, 0, 0, 0, 1, 2])
+            .unwrap();
+        assert_eq!(read_records(&path).unwrap(), vec![record.clone()]);
+
+        let mut airdrop_log = AirdropLog::open(&path).unwrap();
+        let next_record = new_record(2, 20);
+        airdrop_log.record(&next_record).unwrap();
+        assert_eq!(read_records(&path).unwrap(), vec![record, next_record]);
+        remove_file(&path).unwrap();
+        remove_file(ip_hash_key_path(&path)).unwrap();
+    }
+}
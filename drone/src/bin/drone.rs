@@ -1,18 +1,61 @@
-use clap::{crate_description, crate_name, crate_version, App, Arg};
-use solana_drone::drone::{run_drone, Drone, DRONE_PORT};
+use chrono::prelude::*;
+use clap::{
+    crate_description, crate_name, crate_version, App, AppSettings, Arg, ArgMatches, SubCommand,
+};
+use solana_drone::airdrop_log::{self, AirdropLog};
+use solana_drone::drone::{run_drone, run_drone_stats, AirdropLimits, Drone, DRONE_PORT};
 use solana_drone::socketaddr;
 use solana_sdk::signature::read_keypair;
 use std::error;
 use std::net::{Ipv4Addr, SocketAddr};
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::thread;
 
+// Print the airdrops recorded in an airdrop log, by day
+fn report(matches: &ArgMatches<'_>) -> Result<(), Box<error::Error>> {
+    let path = Path::new(matches.value_of("airdrop_log").unwrap());
+    let mut airdrop_log = AirdropLog::default();
+    for record in airdrop_log::read_records(path)? {
+        airdrop_log.record(&record)?;
+    }
+    let stats = airdrop_log.stats();
+
+    println!("{:<12} {:>10} {:>24}", "Day", "Airdrops", "Lamports");
+    for day in &stats.daily {
+        println!(
+            "{:<12} {:>10} {:>24}",
+            Utc.timestamp(day.day_start as i64, 0).format("%Y-%m-%d"),
+            day.airdrops,
+            day.lamports
+        );
+    }
+    println!(
+        "{:<12} {:>10} {:>24}",
+        "Total", stats.total.airdrops, stats.total.lamports
+    );
+    Ok(())
+}
+
 fn main() -> Result<(), Box<error::Error>> {
     solana_logger::setup();
     solana_metrics::set_panic_hook("drone");
     let matches = App::new(crate_name!())
         .about(crate_description!())
         .version(crate_version!())
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .subcommand(
+            SubCommand::with_name("report")
+                .about("Report the airdrops recorded in an airdrop log")
+                .arg(
+                    Arg::with_name("airdrop_log")
+                        .long("airdrop-log")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Airdrop log written by the drone"),
+                ),
+        )
         .arg(
             Arg::with_name("keypair")
                 .short("k")
@@ -36,8 +79,53 @@ fn main() -> Result<(), Box<error::Error>> {
                 .takes_value(true)
                 .help("Request limit for time slice"),
         )
+        .arg(
+            Arg::with_name("per_request_cap")
+                .long("per-request-cap")
+                .value_name("NUM")
+                .takes_value(true)
+                .help("Largest single airdrop"),
+        )
+        .arg(
+            Arg::with_name("per_ip_cap")
+                .long("per-ip-cap")
+                .value_name("NUM")
+                .takes_value(true)
+                .help("Request limit for time slice for each requesting IP address"),
+        )
+        .arg(
+            Arg::with_name("per_pubkey_cap")
+                .long("per-pubkey-cap")
+                .value_name("NUM")
+                .takes_value(true)
+                .help("Request limit for time slice for each recipient"),
+        )
+        .arg(
+            Arg::with_name("require_signed_requests")
+                .long("require-signed-requests")
+                .takes_value(false)
+                .help("Deny airdrop requests that aren't signed by the recipient"),
+        )
+        .arg(
+            Arg::with_name("airdrop_log")
+                .long("airdrop-log")
+                .value_name("PATH")
+                .takes_value(true)
+                .help("Record every airdrop in this file"),
+        )
+        .arg(
+            Arg::with_name("stats_port")
+                .long("stats-port")
+                .value_name("PORT")
+                .takes_value(true)
+                .help("Serve airdrop totals as JSON over HTTP on this port"),
+        )
         .get_matches();
 
+    if let Some(matches) = matches.subcommand_matches("report") {
+        return report(matches);
+    }
+
     let mint_keypair =
         read_keypair(matches.value_of("keypair").unwrap()).expect("failed to read client keypair");
 
@@ -54,13 +142,33 @@ fn main() -> Result<(), Box<error::Error>> {
         request_cap = None;
     }
 
+    let parse_cap = |name: &str| {
+        matches.value_of(name).map(|cap| {
+            cap.parse::<u64>()
+                .unwrap_or_else(|_| panic!("failed to parse {}", name))
+        })
+    };
+    let limits = AirdropLimits {
+        per_request_cap: parse_cap("per_request_cap"),
+        per_ip_cap: parse_cap("per_ip_cap"),
+        per_pubkey_cap: parse_cap("per_pubkey_cap"),
+        require_signature: matches.is_present("require_signed_requests"),
+    };
+
     let drone_addr = socketaddr!(0, DRONE_PORT);
 
-    let drone = Arc::new(Mutex::new(Drone::new(
-        mint_keypair,
-        time_slice,
-        request_cap,
-    )));
+    let mut drone = Drone::new(mint_keypair, time_slice, request_cap);
+    drone.set_limits(limits);
+    if let Some(path) = matches.value_of("airdrop_log") {
+        drone.set_airdrop_log(AirdropLog::open(Path::new(path))?);
+    }
+    let drone = Arc::new(Mutex::new(drone));
+
+    if let Some(port) = matches.value_of("stats_port") {
+        let stats_addr = socketaddr!(0, port.parse::<u16>().expect("failed to parse stats port"));
+        let stats_drone = drone.clone();
+        thread::spawn(move || run_drone_stats(stats_drone, stats_addr, None));
+    }
 
     let drone1 = drone.clone();
     thread::spawn(move || loop {
@@ -1,9 +1,11 @@
 //! The `drone` module provides an object for launching a Solana Drone,
 //! which is the custodian of any remaining lamports in a mint.
 //! The Solana Drone builds and send airdrop transactions,
-//! checking requests against a request cap for a given time time_slice
-//! and (to come) an IP rate limit.
+//! checking requests against a request cap for a given time time_slice,
+//! and against caps per requesting IP address and per recipient.  Requests
+//! signed by the recipient prove the requester holds its key.
 
+use crate::airdrop_log::{AirdropLog, AirdropRecord, AirdropStats};
 use bincode::{deserialize, serialize};
 use byteorder::{ByteOrder, LittleEndian};
 use bytes::{Bytes, BytesMut};
@@ -12,14 +14,18 @@ use serde_derive::{Deserialize, Serialize};
 use solana_metrics::datapoint_info;
 use solana_sdk::hash::Hash;
 use solana_sdk::message::Message;
+use solana_sdk::native_token::Sol;
 use solana_sdk::packet::PACKET_DATA_SIZE;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::{Keypair, KeypairUtil};
+use solana_sdk::signature::{Keypair, KeypairUtil, Signature};
 use solana_sdk::system_instruction;
 use solana_sdk::transaction::Transaction;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
 use std::io;
 use std::io::{Error, ErrorKind};
-use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener as StdTcpListener, TcpStream};
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -51,14 +57,127 @@ pub enum DroneRequest {
         to: Pubkey,
         blockhash: Hash,
     },
+    /// An airdrop request signed by the recipient, over `signed_airdrop_data()`
+    GetSignedAirdrop {
+        lamports: u64,
+        to: Pubkey,
+        blockhash: Hash,
+        signature: Signature,
+    },
+}
+
+/// The data the recipient signs to request an airdrop. The blockhash keeps the signature
+/// from being replayed once the blockhash expires.
+pub fn signed_airdrop_data(lamports: u64, to: &Pubkey, blockhash: &Hash) -> Vec<u8> {
+    serialize(&(lamports, to, blockhash)).unwrap()
+}
+
+/// Caps on airdrops beyond the drone-wide request cap. The per-IP and per-recipient caps
+/// are on the lamports granted within the current time slice.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct AirdropLimits {
+    pub per_request_cap: Option<u64>,
+    pub per_ip_cap: Option<u64>,
+    pub per_pubkey_cap: Option<u64>,
+    /// Deny requests that aren't signed by the recipient, so that nobody can use up the
+    /// per-recipient cap of a key they don't hold
+    pub require_signature: bool,
+}
+
+/// Why the drone turned down an airdrop request. Sent back to the requester in place of
+/// the airdrop transaction.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum AirdropDenial {
+    RequestTooLarge {
+        lamports: u64,
+        cap: u64,
+    },
+    DroneCapReached {
+        lamports: u64,
+        current: u64,
+        cap: u64,
+    },
+    IpCapReached {
+        ip: IpAddr,
+        lamports: u64,
+        current: u64,
+        cap: u64,
+    },
+    PubkeyCapReached {
+        to: Pubkey,
+        lamports: u64,
+        current: u64,
+        cap: u64,
+    },
+    SignatureRequired,
+    InvalidSignature,
+}
+
+impl fmt::Display for AirdropDenial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AirdropDenial::RequestTooLarge { lamports, cap } => write!(
+                f,
+                "request of {} exceeds the per-request cap of {}",
+                Sol(*lamports),
+                Sol(*cap)
+            ),
+            AirdropDenial::DroneCapReached {
+                lamports,
+                current,
+                cap,
+            } => write!(
+                f,
+                "token limit reached; req: {} current: {} cap: {}",
+                Sol(*lamports),
+                Sol(*current),
+                Sol(*cap)
+            ),
+            AirdropDenial::IpCapReached {
+                ip,
+                lamports,
+                current,
+                cap,
+            } => write!(
+                f,
+                "limit for {} reached; req: {} current: {} cap: {}",
+                ip,
+                Sol(*lamports),
+                Sol(*current),
+                Sol(*cap)
+            ),
+            AirdropDenial::PubkeyCapReached {
+                to,
+                lamports,
+                current,
+                cap,
+            } => write!(
+                f,
+                "limit for {} reached; req: {} current: {} cap: {}",
+                to,
+                Sol(*lamports),
+                Sol(*current),
+                Sol(*cap)
+            ),
+            AirdropDenial::SignatureRequired => {
+                write!(f, "requests must be signed by the recipient")
+            }
+            AirdropDenial::InvalidSignature => write!(f, "request signature is invalid"),
+        }
+    }
 }
 
+impl error::Error for AirdropDenial {}
+
 pub struct Drone {
     mint_keypair: Keypair,
-    ip_cache: Vec<IpAddr>,
     pub time_slice: Duration,
     request_cap: u64,
     pub request_current: u64,
+    limits: AirdropLimits,
+    ip_current: HashMap<IpAddr, u64>,
+    pubkey_current: HashMap<Pubkey, u64>,
+    airdrop_log: AirdropLog,
 }
 
 impl Drone {
@@ -77,69 +196,139 @@ impl Drone {
         };
         Drone {
             mint_keypair,
-            ip_cache: Vec::new(),
             time_slice,
             request_cap,
             request_current: 0,
+            limits: AirdropLimits::default(),
+            ip_current: HashMap::new(),
+            pubkey_current: HashMap::new(),
+            airdrop_log: AirdropLog::default(),
         }
     }
 
-    pub fn check_request_limit(&mut self, request_amount: u64) -> bool {
-        (self.request_current + request_amount) <= self.request_cap
+    /// Record granted airdrops in `airdrop_log` instead of only in memory
+    pub fn set_airdrop_log(&mut self, airdrop_log: AirdropLog) {
+        self.airdrop_log = airdrop_log;
     }
 
-    pub fn clear_request_count(&mut self) {
-        self.request_current = 0;
+    pub fn set_limits(&mut self, limits: AirdropLimits) {
+        self.limits = limits;
+    }
+
+    pub fn airdrop_stats(&self) -> AirdropStats {
+        self.airdrop_log.stats()
     }
 
-    pub fn add_ip_to_cache(&mut self, ip: IpAddr) {
-        self.ip_cache.push(ip);
+    pub fn check_request_limit(&mut self, request_amount: u64) -> bool {
+        self.request_current.saturating_add(request_amount) <= self.request_cap
+    }
+
+    /// Check an airdrop of `lamports` to `to`, requested from `ip`, against every cap
+    pub fn check_airdrop_limits(
+        &self,
+        lamports: u64,
+        to: &Pubkey,
+        ip: &IpAddr,
+    ) -> Result<(), AirdropDenial> {
+        if let Some(cap) = self.limits.per_request_cap {
+            if lamports > cap {
+                return Err(AirdropDenial::RequestTooLarge { lamports, cap });
+            }
+        }
+        if !self.check_request_limit(lamports) {
+            return Err(AirdropDenial::DroneCapReached {
+                lamports,
+                current: self.request_current,
+                cap: self.request_cap,
+            });
+        }
+        if let Some(cap) = self.limits.per_ip_cap {
+            let current = self.ip_current.get(ip).cloned().unwrap_or(0);
+            if current.saturating_add(lamports) > cap {
+                return Err(AirdropDenial::IpCapReached {
+                    ip: *ip,
+                    lamports,
+                    current,
+                    cap,
+                });
+            }
+        }
+        if let Some(cap) = self.limits.per_pubkey_cap {
+            let current = self.pubkey_current.get(to).cloned().unwrap_or(0);
+            if current.saturating_add(lamports) > cap {
+                return Err(AirdropDenial::PubkeyCapReached {
+                    to: *to,
+                    lamports,
+                    current,
+                    cap,
+                });
+            }
+        }
+        Ok(())
     }
 
-    pub fn clear_ip_cache(&mut self) {
-        self.ip_cache.clear();
+    /// Start a new time slice
+    pub fn clear_request_count(&mut self) {
+        self.request_current = 0;
+        self.ip_current.clear();
+        self.pubkey_current.clear();
     }
 
     pub fn build_airdrop_transaction(
         &mut self,
         req: DroneRequest,
-    ) -> Result<Transaction, io::Error> {
+        ip: IpAddr,
+    ) -> Result<Transaction, AirdropDenial> {
         trace!("build_airdrop_transaction: {:?}", req);
-        match req {
+        let (lamports, to, blockhash) = match req {
             DroneRequest::GetAirdrop {
                 lamports,
                 to,
                 blockhash,
             } => {
-                if self.check_request_limit(lamports) {
-                    self.request_current += lamports;
-                    datapoint_info!(
-                        "drone-airdrop",
-                        ("request_amount", lamports, i64),
-                        ("request_current", self.request_current, i64)
-                    );
-                    info!("Requesting airdrop of {} to {:?}", lamports, to);
-
-                    let create_instruction = system_instruction::create_user_account(
-                        &self.mint_keypair.pubkey(),
-                        &to,
-                        lamports,
-                    );
-                    let message = Message::new(vec![create_instruction]);
-                    Ok(Transaction::new(&[&self.mint_keypair], message, blockhash))
-                } else {
-                    Err(Error::new(
-                        ErrorKind::Other,
-                        format!(
-                            "token limit reached; req: {} current: {} cap: {}",
-                            lamports, self.request_current, self.request_cap
-                        ),
-                    ))
+                if self.limits.require_signature {
+                    return Err(AirdropDenial::SignatureRequired);
                 }
+                (lamports, to, blockhash)
             }
-        }
+            DroneRequest::GetSignedAirdrop {
+                lamports,
+                to,
+                blockhash,
+                signature,
+            } => {
+                let data = signed_airdrop_data(lamports, &to, &blockhash);
+                if !signature.verify(to.as_ref(), &data) {
+                    return Err(AirdropDenial::InvalidSignature);
+                }
+                (lamports, to, blockhash)
+            }
+        };
+        self.check_airdrop_limits(lamports, &to, &ip)?;
+        self.request_current = self.request_current.saturating_add(lamports);
+        let ip_current = self.ip_current.entry(ip).or_insert(0);
+        *ip_current = ip_current.saturating_add(lamports);
+        let pubkey_current = self.pubkey_current.entry(to).or_insert(0);
+        *pubkey_current = pubkey_current.saturating_add(lamports);
+        datapoint_info!(
+            "drone-airdrop",
+            ("request_amount", lamports, i64),
+            ("request_current", self.request_current, i64)
+        );
+        info!("Requesting airdrop of {} to {:?}", Sol(lamports), to);
+
+        let create_instruction =
+            system_instruction::create_user_account(&self.mint_keypair.pubkey(), &to, lamports);
+        let message = Message::new(vec![create_instruction]);
+        Ok(Transaction::new(&[&self.mint_keypair], message, blockhash))
     }
-    pub fn process_drone_request(&mut self, bytes: &BytesMut) -> Result<Bytes, io::Error> {
+
+    /// Responds to a request with the airdrop transaction, or with why it was denied
+    pub fn process_drone_request(
+        &mut self,
+        bytes: &BytesMut,
+        ip: IpAddr,
+    ) -> Result<Bytes, io::Error> {
         let req: DroneRequest = deserialize(bytes).or_else(|err| {
             Err(io::Error::new(
                 io::ErrorKind::Other,
@@ -148,7 +337,7 @@ impl Drone {
         })?;
 
         info!("Airdrop transaction requested...{:?}", req);
-        let res = self.build_airdrop_transaction(req);
+        let res = self.build_airdrop_transaction(req, ip);
         match res {
             Ok(tx) => {
                 let response_vec = bincode::serialize(&tx).or_else(|err| {
@@ -158,22 +347,73 @@ impl Drone {
                     ))
                 })?;
 
-                let mut response_vec_with_length = vec![0; 2];
-                LittleEndian::write_u16(&mut response_vec_with_length, response_vec.len() as u16);
-                response_vec_with_length.extend_from_slice(&response_vec);
-
-                let response_bytes = Bytes::from(response_vec_with_length);
+                let response_bytes = Bytes::from(with_length(&response_vec));
                 info!("Airdrop transaction granted");
+                self.record_airdrop(req, ip);
                 Ok(response_bytes)
             }
-            Err(err) => {
-                warn!("Airdrop transaction failed: {:?}", err);
-                Err(err)
+            Err(denial) => {
+                warn!("Airdrop transaction denied: {}", denial);
+                datapoint_info!(
+                    "drone-airdrop-denied",
+                    ("reason", denial.to_string(), String)
+                );
+                let denial_vec = bincode::serialize(&denial).or_else(|err| {
+                    Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("serialize denial in drone: {:?}", err),
+                    ))
+                })?;
+
+                // A zero transaction length tells the requester a denial follows
+                let mut response_vec = vec![0; 2];
+                response_vec.extend_from_slice(&with_length(&denial_vec));
+                Ok(Bytes::from(response_vec))
+            }
+        }
+    }
+
+    fn record_airdrop(&mut self, req: DroneRequest, ip: IpAddr) {
+        match req {
+            DroneRequest::GetAirdrop { lamports, to, .. }
+            | DroneRequest::GetSignedAirdrop { lamports, to, .. } => {
+                let record = AirdropRecord::new(to, lamports, self.airdrop_log.hash_ip(ip));
+                if let Err(err) = self.airdrop_log.record(&record) {
+                    warn!("Failed to record airdrop {:?}: {:?}", record, err);
+                }
+                let total = self.airdrop_log.total();
+                datapoint_info!(
+                    "drone-airdrop-total",
+                    ("airdrops", total.airdrops, i64),
+                    ("lamports", total.lamports, i64)
+                );
             }
         }
     }
 }
 
+/// `bytes` prefixed with their length, as the drone frames its responses
+fn with_length(bytes: &[u8]) -> Vec<u8> {
+    let mut vec_with_length = vec![0; 2];
+    LittleEndian::write_u16(&mut vec_with_length, bytes.len() as u16);
+    vec_with_length.extend_from_slice(bytes);
+    vec_with_length
+}
+
+/// Whether `transaction` is a fully signed airdrop to `to` for `blockhash`
+fn verify_airdrop_transaction(transaction: &Transaction, to: &Pubkey, blockhash: &Hash) -> bool {
+    let message = transaction.message();
+    let message_data = transaction.message_data();
+    message.recent_blockhash == *blockhash
+        && message.account_keys.contains(to)
+        && transaction.signatures.len() == message.header.num_required_signatures as usize
+        && transaction
+            .signatures
+            .iter()
+            .zip(&message.account_keys)
+            .all(|(signature, pubkey)| signature.verify(pubkey.as_ref(), &message_data))
+}
+
 impl Drop for Drone {
     fn drop(&mut self) {
         solana_metrics::flush();
@@ -187,18 +427,55 @@ pub fn request_airdrop_transaction(
     blockhash: Hash,
 ) -> Result<Transaction, Error> {
     info!(
-        "request_airdrop_transaction: drone_addr={} id={} lamports={} blockhash={}",
-        drone_addr, id, lamports, blockhash
+        "request_airdrop_transaction: drone_addr={} id={} amount={} blockhash={}",
+        drone_addr,
+        id,
+        Sol(lamports),
+        blockhash
     );
-    // TODO: make this async tokio client
-    let mut stream = TcpStream::connect_timeout(drone_addr, Duration::new(3, 0))?;
-    stream.set_read_timeout(Some(Duration::new(10, 0)))?;
     let req = DroneRequest::GetAirdrop {
         lamports,
         blockhash,
         to: *id,
     };
-    let req = serialize(&req).expect("serialize drone request");
+    send_drone_request(drone_addr, &req, id, &blockhash)
+}
+
+/// Like `request_airdrop_transaction()`, for an airdrop to `keypair` that it signs the request for
+pub fn request_signed_airdrop_transaction(
+    drone_addr: &SocketAddr,
+    keypair: &Keypair,
+    lamports: u64,
+    blockhash: Hash,
+) -> Result<Transaction, Error> {
+    let to = keypair.pubkey();
+    info!(
+        "request_signed_airdrop_transaction: drone_addr={} id={} amount={} blockhash={}",
+        drone_addr,
+        to,
+        Sol(lamports),
+        blockhash
+    );
+    let signature = keypair.sign_message(&signed_airdrop_data(lamports, &to, &blockhash));
+    let req = DroneRequest::GetSignedAirdrop {
+        lamports,
+        to,
+        blockhash,
+        signature,
+    };
+    send_drone_request(drone_addr, &req, &to, &blockhash)
+}
+
+fn send_drone_request(
+    drone_addr: &SocketAddr,
+    req: &DroneRequest,
+    id: &Pubkey,
+    blockhash: &Hash,
+) -> Result<Transaction, Error> {
+    // TODO: make this async tokio client
+    let mut stream = TcpStream::connect_timeout(drone_addr, Duration::new(3, 0))?;
+    stream.set_read_timeout(Some(Duration::new(10, 0)))?;
+    let req = serialize(req).expect("serialize drone request");
     stream.write_all(&req)?;
 
     // Read length of transaction
@@ -211,6 +488,9 @@ pub fn request_airdrop_transaction(
         Err(Error::new(ErrorKind::Other, "Airdrop failed"))
     })?;
     let transaction_length = LittleEndian::read_u16(&buffer) as usize;
+    if transaction_length == 0 {
+        return Err(read_airdrop_denial(&mut stream));
+    }
     if transaction_length >= PACKET_DATA_SIZE {
         Err(Error::new(
             ErrorKind::Other,
@@ -238,9 +518,34 @@ pub fn request_airdrop_transaction(
             format!("request_airdrop_transaction deserialize failure: {:?}", err),
         ))
     })?;
+    if !verify_airdrop_transaction(&transaction, id, blockhash) {
+        Err(Error::new(
+            ErrorKind::Other,
+            "request_airdrop_transaction: drone sent an invalid transaction",
+        ))?;
+    }
     Ok(transaction)
 }
 
+/// Read the denial that follows a zero-length response. The denial is the source of the
+/// returned error, for requesters that want to tell why they were turned down.
+fn read_airdrop_denial(stream: &mut TcpStream) -> Error {
+    let mut buffer = [0; 2];
+    let denial = stream
+        .read_exact(&mut buffer)
+        .and_then(|_| {
+            let mut buffer = vec![0; LittleEndian::read_u16(&buffer) as usize];
+            stream.read_exact(&mut buffer)?;
+            Ok(buffer)
+        })
+        .ok()
+        .and_then(|buffer| deserialize::<AirdropDenial>(&buffer).ok());
+    match denial {
+        Some(denial) => Error::new(ErrorKind::Other, denial),
+        None => Error::new(ErrorKind::Other, "Airdrop failed"),
+    }
+}
+
 // For integration tests. Listens on random open port and reports port to Sender.
 pub fn run_local_drone(
     mint_keypair: Keypair,
@@ -276,11 +581,15 @@ pub fn run_drone(
         .map_err(|e| debug!("failed to accept socket; error = {:?}", e))
         .for_each(move |socket| {
             let drone2 = drone.clone();
+            let ip = socket
+                .peer_addr()
+                .map(|addr| addr.ip())
+                .unwrap_or_else(|_| Ipv4Addr::UNSPECIFIED.into());
             let framed = BytesCodec::new().framed(socket);
             let (writer, reader) = framed.split();
 
             let processor = reader.and_then(move |bytes| {
-                match drone2.lock().unwrap().process_drone_request(&bytes) {
+                match drone2.lock().unwrap().process_drone_request(&bytes, ip) {
                     Ok(response_bytes) => {
                         trace!("Airdrop response_bytes: {:?}", response_bytes.to_vec());
                         Ok(response_bytes)
@@ -304,11 +613,50 @@ pub fn run_drone(
     tokio::run(done);
 }
 
+/// Serve the drone's airdrop totals as JSON to any HTTP request on `stats_addr`
+pub fn run_drone_stats(
+    drone: Arc<Mutex<Drone>>,
+    stats_addr: SocketAddr,
+    send_addr: Option<Sender<SocketAddr>>,
+) {
+    let listener = StdTcpListener::bind(&stats_addr).unwrap();
+    if let Some(send_addr) = send_addr {
+        send_addr.send(listener.local_addr().unwrap()).unwrap();
+    }
+    info!("Drone stats listening on: {}", stats_addr);
+    for stream in listener.incoming() {
+        let result = stream.and_then(|mut stream| {
+            let stats = drone.lock().unwrap().airdrop_stats();
+            write_stats_response(&mut stream, &stats)
+        });
+        if let Err(err) = result {
+            debug!("failed to serve drone stats; error = {:?}", err);
+        }
+    }
+}
+
+fn write_stats_response(stream: &mut TcpStream, stats: &AirdropStats) -> io::Result<()> {
+    // Every request gets the same response, so only read enough to let the client finish sending
+    stream.set_read_timeout(Some(Duration::new(1, 0)))?;
+    let mut request = [0; 1024];
+    let _ = stream.read(&mut request);
+
+    let body = serde_json::to_string(stats)?;
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use bytes::BufMut;
+    use solana_sdk::signature::Signature;
     use solana_sdk::system_instruction::SystemInstruction;
+    use solana_sdk::system_transaction;
     use std::time::Duration;
 
     #[test]
@@ -331,27 +679,131 @@ mod tests {
     }
 
     #[test]
-    fn test_add_ip_to_cache() {
+    fn test_check_airdrop_limits() {
         let keypair = Keypair::new();
-        let mut drone = Drone::new(keypair, None, None);
-        let ip = "127.0.0.1".parse().expect("create IpAddr from string");
-        assert_eq!(drone.ip_cache.len(), 0);
-        drone.add_ip_to_cache(ip);
-        assert_eq!(drone.ip_cache.len(), 1);
-        assert!(drone.ip_cache.contains(&ip));
+        let mut drone = Drone::new(keypair, None, Some(10));
+        drone.set_limits(AirdropLimits {
+            per_request_cap: Some(4),
+            per_ip_cap: Some(6),
+            per_pubkey_cap: Some(5),
+            require_signature: false,
+        });
+        let to = Pubkey::new_rand();
+        let other_to = Pubkey::new_rand();
+        let ip: IpAddr = "127.0.0.1".parse().expect("create IpAddr from string");
+        let other_ip: IpAddr = "127.0.0.2".parse().expect("create IpAddr from string");
+        let request = |lamports, to| DroneRequest::GetAirdrop {
+            lamports,
+            to,
+            blockhash: Hash::default(),
+        };
+
+        assert_eq!(
+            drone.check_airdrop_limits(5, &to, &ip),
+            Err(AirdropDenial::RequestTooLarge {
+                lamports: 5,
+                cap: 4
+            })
+        );
+        assert!(drone.build_airdrop_transaction(request(4, to), ip).is_ok());
+        assert_eq!(
+            drone.check_airdrop_limits(2, &to, &other_ip),
+            Err(AirdropDenial::PubkeyCapReached {
+                to,
+                lamports: 2,
+                current: 4,
+                cap: 5
+            })
+        );
+        assert_eq!(
+            drone.check_airdrop_limits(3, &other_to, &ip),
+            Err(AirdropDenial::IpCapReached {
+                ip,
+                lamports: 3,
+                current: 4,
+                cap: 6
+            })
+        );
+        assert!(drone
+            .build_airdrop_transaction(request(2, other_to), ip)
+            .is_ok());
+        assert!(drone
+            .build_airdrop_transaction(request(4, Pubkey::new_rand()), other_ip)
+            .is_ok());
+        assert_eq!(
+            drone.check_airdrop_limits(1, &Pubkey::new_rand(), &other_ip),
+            Err(AirdropDenial::DroneCapReached {
+                lamports: 1,
+                current: 10,
+                cap: 10
+            })
+        );
+
+        // Every cap resets with the time slice
+        drone.clear_request_count();
+        assert_eq!(drone.check_airdrop_limits(4, &to, &ip), Ok(()));
+
+        // Requests too large to add to the totals are denied, not wrapped around
+        drone.request_current = 1;
+        assert!(!drone.check_request_limit(std::u64::MAX));
     }
 
     #[test]
-    fn test_clear_ip_cache() {
+    fn test_signed_airdrop_request() {
+        let mut drone = Drone::new(Keypair::new(), None, None);
+        drone.set_limits(AirdropLimits {
+            require_signature: true,
+            ..AirdropLimits::default()
+        });
+        let ip = Ipv4Addr::LOCALHOST.into();
         let keypair = Keypair::new();
-        let mut drone = Drone::new(keypair, None, None);
-        let ip = "127.0.0.1".parse().expect("create IpAddr from string");
-        assert_eq!(drone.ip_cache.len(), 0);
-        drone.add_ip_to_cache(ip);
-        assert_eq!(drone.ip_cache.len(), 1);
-        drone.clear_ip_cache();
-        assert_eq!(drone.ip_cache.len(), 0);
-        assert!(drone.ip_cache.is_empty());
+        let to = keypair.pubkey();
+        let blockhash = Hash::new(&to.as_ref());
+        let signed_request = |lamports, signer: &Keypair| DroneRequest::GetSignedAirdrop {
+            lamports,
+            to,
+            blockhash,
+            signature: signer.sign_message(&signed_airdrop_data(lamports, &to, &blockhash)),
+        };
+
+        let unsigned_request = DroneRequest::GetAirdrop {
+            lamports: 2,
+            to,
+            blockhash,
+        };
+        assert_eq!(
+            drone
+                .build_airdrop_transaction(unsigned_request, ip)
+                .unwrap_err(),
+            AirdropDenial::SignatureRequired
+        );
+        assert_eq!(
+            drone
+                .build_airdrop_transaction(signed_request(2, &Keypair::new()), ip)
+                .unwrap_err(),
+            AirdropDenial::InvalidSignature
+        );
+
+        // The signature covers the amount
+        let mut altered_request = signed_request(2, &keypair);
+        if let DroneRequest::GetSignedAirdrop {
+            ref mut lamports, ..
+        } = altered_request
+        {
+            *lamports = 3;
+        }
+        assert_eq!(
+            drone
+                .build_airdrop_transaction(altered_request, ip)
+                .unwrap_err(),
+            AirdropDenial::InvalidSignature
+        );
+
+        let tx = drone
+            .build_airdrop_transaction(signed_request(2, &keypair), ip)
+            .unwrap();
+        assert!(verify_airdrop_transaction(&tx, &to, &blockhash));
+        assert_eq!(drone.request_current, 2);
     }
 
     #[test]
@@ -378,7 +830,8 @@ mod tests {
         let mint_pubkey = mint.pubkey();
         let mut drone = Drone::new(mint, None, None);
 
-        let tx = drone.build_airdrop_transaction(request).unwrap();
+        let ip = Ipv4Addr::LOCALHOST.into();
+        let tx = drone.build_airdrop_transaction(request, ip).unwrap();
         let message = tx.message();
 
         assert_eq!(tx.signatures.len(), 1);
@@ -401,7 +854,7 @@ mod tests {
 
         let mint = Keypair::new();
         drone = Drone::new(mint, None, Some(1));
-        let tx = drone.build_airdrop_transaction(request);
+        let tx = drone.build_airdrop_transaction(request, ip);
         assert!(tx.is_err());
     }
 
@@ -429,13 +882,75 @@ mod tests {
         LittleEndian::write_u16(&mut expected_vec_with_length, expected_bytes.len() as u16);
         expected_vec_with_length.extend_from_slice(&expected_bytes);
 
+        let ip = Ipv4Addr::LOCALHOST.into();
         let mut drone = Drone::new(keypair, None, None);
-        let response = drone.process_drone_request(&bytes);
+        let response = drone.process_drone_request(&bytes, ip);
         let response_vec = response.unwrap().to_vec();
         assert_eq!(expected_vec_with_length, response_vec);
+        assert_eq!(drone.airdrop_stats().total.airdrops, 1);
+        assert_eq!(drone.airdrop_stats().total.lamports, lamports);
 
         let mut bad_bytes = BytesMut::with_capacity(9);
         bad_bytes.put("bad bytes");
-        assert!(drone.process_drone_request(&bad_bytes).is_err());
+        assert!(drone.process_drone_request(&bad_bytes, ip).is_err());
+        assert_eq!(drone.airdrop_stats().total.airdrops, 1);
+
+        drone.set_limits(AirdropLimits {
+            per_request_cap: Some(lamports - 1),
+            ..AirdropLimits::default()
+        });
+        let denial = AirdropDenial::RequestTooLarge {
+            lamports,
+            cap: lamports - 1,
+        };
+        let mut expected_vec = vec![0; 2];
+        expected_vec.extend_from_slice(&with_length(&serialize(&denial).unwrap()));
+        let response = drone.process_drone_request(&bytes, ip).unwrap();
+        assert_eq!(response.to_vec(), expected_vec);
+        assert_eq!(drone.airdrop_stats().total.airdrops, 1);
+    }
+
+    #[test]
+    fn test_verify_airdrop_transaction() {
+        let keypair = Keypair::new();
+        let to = Pubkey::new_rand();
+        let blockhash = Hash::new(&to.as_ref());
+        let tx = system_transaction::create_user_account(&keypair, &to, 1, blockhash);
+        assert!(verify_airdrop_transaction(&tx, &to, &blockhash));
+        assert!(!verify_airdrop_transaction(
+            &tx,
+            &Pubkey::new_rand(),
+            &blockhash
+        ));
+        assert!(!verify_airdrop_transaction(&tx, &to, &Hash::default()));
+
+        let mut unsigned_tx = tx.clone();
+        unsigned_tx.signatures[0] = Signature::default();
+        assert!(!verify_airdrop_transaction(&unsigned_tx, &to, &blockhash));
+    }
+
+    #[test]
+    fn test_run_drone_stats() {
+        let mut drone = Drone::new(Keypair::new(), None, None);
+        let req = DroneRequest::GetAirdrop {
+            lamports: 42,
+            to: Pubkey::new_rand(),
+            blockhash: Hash::default(),
+        };
+        drone.record_airdrop(req, Ipv4Addr::LOCALHOST.into());
+        let stats = drone.airdrop_stats();
+        let drone = Arc::new(Mutex::new(drone));
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        thread::spawn(move || run_drone_stats(drone, socketaddr!(0, 0), Some(sender)));
+        let stats_addr = receiver.recv().unwrap();
+
+        let mut stream = TcpStream::connect(&stats_addr).unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        assert_eq!(serde_json::from_str::<AirdropStats>(body).unwrap(), stats);
     }
 }
@@ -1,2 +1,3 @@
+pub mod airdrop_log;
 pub mod drone;
 pub mod drone_mock;
@@ -1,4 +1,6 @@
-use solana_drone::drone::{request_airdrop_transaction, run_local_drone};
+use solana_drone::drone::{
+    request_airdrop_transaction, request_signed_airdrop_transaction, run_local_drone, AirdropDenial,
+};
 use solana_sdk::hash::Hash;
 use solana_sdk::message::Message;
 use solana_sdk::pubkey::Pubkey;
@@ -25,3 +27,38 @@ fn test_local_drone() {
     let result = request_airdrop_transaction(&drone_addr, &to, lamports, blockhash);
     assert_eq!(expected_tx, result.unwrap());
 }
+
+#[test]
+fn test_local_drone_signed_request() {
+    let (sender, receiver) = channel();
+    run_local_drone(Keypair::new(), sender, None);
+    let drone_addr = receiver.recv().unwrap();
+
+    let keypair = Keypair::new();
+    let blockhash = Hash::new(&keypair.pubkey().as_ref());
+    let tx = request_signed_airdrop_transaction(&drone_addr, &keypair, 50, blockhash).unwrap();
+    assert!(tx.message().account_keys.contains(&keypair.pubkey()));
+    assert_eq!(tx.message().recent_blockhash, blockhash);
+}
+
+#[test]
+fn test_local_drone_denial() {
+    let to = Pubkey::new_rand();
+    let (sender, receiver) = channel();
+    run_local_drone(Keypair::new(), sender, Some(10));
+    let drone_addr = receiver.recv().unwrap();
+
+    let err = request_airdrop_transaction(&drone_addr, &to, 50, Hash::default()).unwrap_err();
+    let denial = err
+        .get_ref()
+        .and_then(|err| err.downcast_ref::<AirdropDenial>())
+        .unwrap();
+    assert_eq!(
+        *denial,
+        AirdropDenial::DroneCapReached {
+            lamports: 50,
+            current: 0,
+            cap: 10
+        }
+    );
+}
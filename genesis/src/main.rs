@@ -14,14 +14,21 @@ extern crate solana_config_program;
 #[macro_use]
 extern crate solana_exchange_program;
 #[macro_use]
+extern crate solana_memo_program;
+#[macro_use]
 extern crate solana_storage_program;
 
-use clap::{crate_description, crate_name, crate_version, value_t_or_exit, App, Arg};
+use clap::{
+    crate_description, crate_name, crate_version, value_t_or_exit, values_t_or_exit, App,
+    AppSettings, Arg, ArgMatches, SubCommand,
+};
+use serde_derive::{Deserialize, Serialize};
 use solana::blocktree::create_new_ledger;
 use solana_sdk::account::Account;
-use solana_sdk::fee_calculator::FeeCalculator;
-use solana_sdk::genesis_block::Builder;
+use solana_sdk::fee_calculator::{FeeCalculator, FeeRateGovernor};
+use solana_sdk::genesis_block::{Builder, GenesisBlock};
 use solana_sdk::hash::{hash, Hash};
+use solana_sdk::inflation::Inflation;
 use solana_sdk::poh_config::PohConfig;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{read_keypair, Keypair, KeypairUtil};
@@ -29,11 +36,12 @@ use solana_sdk::system_program;
 use solana_sdk::timing;
 use solana_stake_api::stake_state;
 use solana_storage_api::storage_contract;
-use solana_vote_api::vote_state;
+use solana_vote_api::vote_state::{self, VoteState};
 use std::collections::HashMap;
 use std::error;
 use std::fs::File;
 use std::io;
+use std::path::Path;
 use std::str::FromStr;
 use std::time::{Duration, Instant};
 
@@ -44,31 +52,222 @@ pub enum AccountFileFormat {
     Keypair,
 }
 
+/// An entry of a primordial accounts file, keyed by the account's pubkey (or keypair)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum PrimordialAccount {
+    /// A system account holding this many lamports
+    Balance(u64),
+    /// A validator's node account, along with its vote account and a stake account delegated to
+    /// it
+    Validator(PrimordialValidator),
+    /// An account with an arbitrary owner and data
+    Account(PrimordialAccountDetails),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PrimordialAccountDetails {
+    pub balance: u64,
+    /// Defaults to the system program
+    pub owner: Option<String>,
+    /// Base64 encoded account data
+    pub data: Option<String>,
+    #[serde(default)]
+    pub executable: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PrimordialValidator {
+    /// Lamports of the node account, which pays for the validator's votes
+    pub balance: u64,
+    pub vote_pubkey: String,
+    pub vote_balance: u64,
+    #[serde(default)]
+    pub commission: u8,
+    pub stake_pubkey: String,
+    pub stake_balance: u64,
+    /// Slot before which nothing can be withdrawn from the stake account
+    #[serde(default)]
+    pub lockup: u64,
+}
+
+fn invalid_data<E: std::fmt::Debug>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", err))
+}
+
+fn parse_pubkey(pubkey: &str) -> io::Result<Pubkey> {
+    Pubkey::from_str(pubkey).map_err(|err| invalid_data((pubkey, err)))
+}
+
+// Reads a CSV file of `pubkey,balance[,owner[,data]]` rows, skipping blank lines and `#` comments
+fn read_primordial_accounts_csv(file: &str) -> io::Result<Vec<(String, PrimordialAccount)>> {
+    let contents = std::fs::read_to_string(file)?;
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let fields: Vec<_> = line.split(',').map(str::trim).collect();
+            if fields.len() < 2 || fields.len() > 4 {
+                return Err(invalid_data(format!("malformed row: {}", line)));
+            }
+            let balance = fields[1]
+                .parse::<u64>()
+                .map_err(|err| invalid_data((line, err)))?;
+            let optional = |i: usize| {
+                fields
+                    .get(i)
+                    .filter(|field| !field.is_empty())
+                    .map(|field| field.to_string())
+            };
+            let account = if fields.len() == 2 {
+                PrimordialAccount::Balance(balance)
+            } else {
+                PrimordialAccount::Account(PrimordialAccountDetails {
+                    balance,
+                    owner: optional(2),
+                    data: optional(3),
+                    executable: false,
+                })
+            };
+            Ok((fields[0].to_string(), account))
+        })
+        .collect()
+}
+
+/// Reads the primordial accounts in `file`, a YAML map of accounts to `PrimordialAccount`s, or
+/// if it ends in `.csv`, rows of `pubkey,balance[,owner[,data]]`
+pub fn read_primordial_accounts(file: &str) -> io::Result<Vec<(String, PrimordialAccount)>> {
+    if file.ends_with(".csv") {
+        return read_primordial_accounts_csv(file);
+    }
+    let accounts_file = File::open(file.to_string())?;
+    let primordial_accounts: HashMap<String, PrimordialAccount> =
+        serde_yaml::from_reader(accounts_file)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{:?}", err)))?;
+    Ok(primordial_accounts.into_iter().collect())
+}
+
 pub fn append_primordial_accounts(
     file: &str,
     file_format: AccountFileFormat,
     mut builder: Builder,
 ) -> io::Result<(Builder)> {
-    let accounts_file = File::open(file.to_string())?;
-
-    let primordial_accounts: HashMap<String, u64> = serde_yaml::from_reader(accounts_file)
-        .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{:?}", err)))?;
-
-    for (account, balance) in primordial_accounts {
+    for (account, primordial_account) in read_primordial_accounts(file)? {
         let pubkey = match file_format {
-            AccountFileFormat::Pubkey => Pubkey::from_str(account.as_str()).unwrap(),
+            AccountFileFormat::Pubkey => parse_pubkey(&account)?,
             AccountFileFormat::Keypair => {
-                let bytes: Vec<u8> = serde_json::from_str(account.as_str()).unwrap();
-                Keypair::from_bytes(&bytes).unwrap().pubkey()
+                let bytes: Vec<u8> =
+                    serde_json::from_str(account.as_str()).map_err(invalid_data)?;
+                Keypair::from_bytes(&bytes).map_err(invalid_data)?.pubkey()
             }
         };
 
-        builder = builder.account(pubkey, Account::new(balance, 0, &system_program::id()));
+        match primordial_account {
+            PrimordialAccount::Balance(balance) => {
+                builder = builder.account(pubkey, Account::new(balance, 0, &system_program::id()));
+            }
+            PrimordialAccount::Account(details) => {
+                let owner = match details.owner {
+                    Some(owner) => parse_pubkey(&owner)?,
+                    None => system_program::id(),
+                };
+                let data = match details.data {
+                    Some(data) => base64::decode(&data).map_err(invalid_data)?,
+                    None => vec![],
+                };
+                builder = builder.account(
+                    pubkey,
+                    Account {
+                        lamports: details.balance,
+                        data,
+                        owner,
+                        executable: details.executable,
+                    },
+                );
+            }
+            PrimordialAccount::Validator(validator) => {
+                let vote_pubkey = parse_pubkey(&validator.vote_pubkey)?;
+                let stake_pubkey = parse_pubkey(&validator.stake_pubkey)?;
+                let vote_account = vote_state::create_account(
+                    &vote_pubkey,
+                    &pubkey,
+                    validator.commission,
+                    validator.vote_balance,
+                );
+                let stake_account = stake_state::create_stake_account_with_lockup(
+                    &vote_pubkey,
+                    &VoteState::from(&vote_account).unwrap(),
+                    validator.stake_balance,
+                    validator.lockup,
+                );
+                builder = builder.accounts(&[
+                    (
+                        pubkey,
+                        Account::new(validator.balance, 0, &system_program::id()),
+                    ),
+                    (vote_pubkey, vote_account),
+                    (stake_pubkey, stake_account),
+                ]);
+            }
+        }
     }
 
     Ok(builder)
 }
 
+fn print_accounts(accounts: &[(Pubkey, Account)]) {
+    for (pubkey, account) in accounts {
+        println!(
+            "  {}: {} lamports, owner {}, {} bytes of data{}",
+            pubkey,
+            account.lamports,
+            account.owner,
+            account.data.len(),
+            if account.executable {
+                ", executable"
+            } else {
+                ""
+            },
+        );
+    }
+}
+
+// Print the genesis block in `ledger_path`, whatever version wrote it
+fn inspect(matches: &ArgMatches<'_>) -> Result<(), Box<dyn error::Error>> {
+    let ledger_path = matches.value_of("ledger_path").unwrap();
+    let bytes = std::fs::read(Path::new(ledger_path).join("genesis.bin"))?;
+    let version = GenesisBlock::version(&bytes)?;
+    let genesis_block = GenesisBlock::from_bytes(&bytes)?;
+
+    println!("version: {}", version);
+    println!("hash: {}", genesis_block.hash());
+    println!("accounts:");
+    print_accounts(&genesis_block.accounts);
+    println!("native_instruction_processors:");
+    for (name, program_id) in &genesis_block.native_instruction_processors {
+        println!("  {}: {}", name, program_id);
+    }
+    println!("rewards_pools:");
+    print_accounts(&genesis_block.rewards_pools);
+    println!("slots_per_epoch: {}", genesis_block.slots_per_epoch);
+    println!("stakers_slot_offset: {}", genesis_block.stakers_slot_offset);
+    println!("epoch_warmup: {}", genesis_block.epoch_warmup);
+    println!("ticks_per_slot: {}", genesis_block.ticks_per_slot);
+    println!("slots_per_segment: {}", genesis_block.slots_per_segment);
+    println!("poh_config: {:?}", genesis_block.poh_config);
+    println!("fee_calculator: {:?}", genesis_block.fee_calculator);
+    println!("fee_rate_governor: {:?}", genesis_block.fee_rate_governor);
+    println!("inflation: {:?}", genesis_block.inflation);
+    println!("rent: {:?}", genesis_block.rent);
+    for (tag, value) in &genesis_block.unknown_fields {
+        println!("unknown field {}: {} bytes", tag, value.len());
+    }
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn error::Error>> {
     let default_bootstrap_leader_lamports = &BOOTSTRAP_LEADER_LAMPORTS.to_string();
     let default_target_lamports_per_signature = &FeeCalculator::default()
@@ -77,6 +276,21 @@ fn main() -> Result<(), Box<dyn error::Error>> {
     let default_target_signatures_per_slot = &FeeCalculator::default()
         .target_signatures_per_slot
         .to_string();
+    let default_burn_percent = &FeeCalculator::default().burn_percent.to_string();
+    let default_fee_rate_governor = FeeRateGovernor::default();
+    let default_fee_rate_min_percent = &default_fee_rate_governor.min_percent.to_string();
+    let default_fee_rate_max_percent = &default_fee_rate_governor.max_percent.to_string();
+    let default_fee_rate_adjustment_percent =
+        &default_fee_rate_governor.adjustment_percent.to_string();
+    let default_inflation = Inflation::default();
+    let default_inflation_initial = &default_inflation.initial.to_string();
+    let default_inflation_terminal = &default_inflation.terminal.to_string();
+    let default_inflation_taper = &default_inflation.taper.to_string();
+    let default_inflation_foundation = &default_inflation.foundation.to_string();
+    let default_inflation_foundation_term = &default_inflation.foundation_term.to_string();
+    let default_inflation_grant = &default_inflation.grant.to_string();
+    let default_inflation_grant_term = &default_inflation.grant_term.to_string();
+    let default_inflation_storage = &default_inflation.storage.to_string();
     let default_target_tick_duration =
         &timing::duration_as_ms(&PohConfig::default().target_tick_duration).to_string();
     let default_ticks_per_slot = &timing::DEFAULT_TICKS_PER_SLOT.to_string();
@@ -85,6 +299,20 @@ fn main() -> Result<(), Box<dyn error::Error>> {
     let matches = App::new(crate_name!())
         .about(crate_description!())
         .version(crate_version!())
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .subcommand(
+            SubCommand::with_name("inspect")
+                .about("Print a genesis block written by any version")
+                .arg(
+                    Arg::with_name("ledger_path")
+                        .short("l")
+                        .long("ledger")
+                        .value_name("DIR")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Ledger directory containing the genesis block"),
+                ),
+        )
         .arg(
             Arg::with_name("bootstrap_leader_keypair_file")
                 .short("b")
@@ -189,6 +417,113 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                     the next slot. A value of 0 disables signature-based fee adjustments",
                 ),
         )
+        .arg(
+            Arg::with_name("fee_burn_percent")
+                .long("fee-burn-percent")
+                .value_name("PERCENT")
+                .takes_value(true)
+                .default_value(default_burn_percent)
+                .help("Percentage of collected transaction fees that are destroyed"),
+        )
+        .arg(
+            Arg::with_name("fee_rate_min_percent")
+                .long("fee-rate-min-percent")
+                .value_name("PERCENT")
+                .takes_value(true)
+                .default_value(default_fee_rate_min_percent)
+                .help(
+                    "The lowest lamports-per-signature fee, as a percentage of \
+                     target-lamports-per-signature",
+                ),
+        )
+        .arg(
+            Arg::with_name("fee_rate_max_percent")
+                .long("fee-rate-max-percent")
+                .value_name("PERCENT")
+                .takes_value(true)
+                .default_value(default_fee_rate_max_percent)
+                .help(
+                    "The highest lamports-per-signature fee, as a percentage of \
+                     target-lamports-per-signature",
+                ),
+        )
+        .arg(
+            Arg::with_name("fee_rate_adjustment_percent")
+                .long("fee-rate-adjustment-percent")
+                .value_name("PERCENT")
+                .takes_value(true)
+                .default_value(default_fee_rate_adjustment_percent)
+                .help(
+                    "How much the lamports-per-signature fee may change from one slot to the \
+                     next, as a percentage of target-lamports-per-signature",
+                ),
+        )
+        .arg(
+            Arg::with_name("inflation_initial")
+                .long("inflation-initial")
+                .value_name("RATE")
+                .takes_value(true)
+                .default_value(default_inflation_initial)
+                .help("Yearly inflation rate at genesis"),
+        )
+        .arg(
+            Arg::with_name("inflation_terminal")
+                .long("inflation-terminal")
+                .value_name("RATE")
+                .takes_value(true)
+                .default_value(default_inflation_terminal)
+                .help("Yearly inflation rate that inflation tapers down to"),
+        )
+        .arg(
+            Arg::with_name("inflation_taper")
+                .long("inflation-taper")
+                .value_name("RATE")
+                .takes_value(true)
+                .default_value(default_inflation_taper)
+                .help(
+                    "Rate per year at which inflation is lowered until reaching the terminal rate",
+                ),
+        )
+        .arg(
+            Arg::with_name("inflation_foundation")
+                .long("inflation-foundation")
+                .value_name("FRACTION")
+                .takes_value(true)
+                .default_value(default_inflation_foundation)
+                .help("Fraction of inflation allocated to the foundation"),
+        )
+        .arg(
+            Arg::with_name("inflation_foundation_term")
+                .long("inflation-foundation-term")
+                .value_name("YEARS")
+                .takes_value(true)
+                .default_value(default_inflation_foundation_term)
+                .help("Years that inflation is allocated to the foundation"),
+        )
+        .arg(
+            Arg::with_name("inflation_grant")
+                .long("inflation-grant")
+                .value_name("FRACTION")
+                .takes_value(true)
+                .default_value(default_inflation_grant)
+                .help("Fraction of inflation allocated to grant pools"),
+        )
+        .arg(
+            Arg::with_name("inflation_grant_term")
+                .long("inflation-grant-term")
+                .value_name("YEARS")
+                .takes_value(true)
+                .default_value(default_inflation_grant_term)
+                .help("Years that inflation is allocated to grant pools"),
+        )
+        .arg(
+            Arg::with_name("inflation_storage")
+                .long("inflation-storage")
+                .value_name("FRACTION")
+                .takes_value(true)
+                .default_value(default_inflation_storage)
+                .help("Fraction of inflation allocated to storage mining rewards"),
+        )
         .arg(
             Arg::with_name("target_tick_duration")
                 .long("target-tick-duration")
@@ -226,12 +561,36 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 .default_value(default_slots_per_epoch)
                 .help("The number of slots in an epoch"),
         )
+        .arg(
+            Arg::with_name("storage_min_stake")
+                .long("storage-min-stake")
+                .value_name("LAMPORTS")
+                .takes_value(true)
+                .default_value("0")
+                .help(
+                    "Lamports of active stake a replicator must reference to submit \
+                     storage mining proofs. A value of 0 disables the requirement",
+                ),
+        )
+        .arg(
+            Arg::with_name("storage_whitelist")
+                .long("storage-whitelist")
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .multiple(true)
+                .help("Replicator storage account allowed to submit mining proofs without stake"),
+        )
         .arg(
             Arg::with_name("primordial_accounts_file")
                 .long("primordial-accounts-file")
                 .value_name("FILENAME")
                 .takes_value(true)
-                .help("The location of pubkey for primordial accounts and balance"),
+                .help(
+                    "The location of a YAML file mapping primordial account pubkeys to a \
+                     balance, to an account's balance, owner and base64 data, or to a \
+                     validator's vote and stake accounts and stake lockup slot. A file ending \
+                     in .csv holds pubkey,balance[,owner[,data]] rows instead",
+                ),
         )
         .arg(
             Arg::with_name("primordial_keypairs_file")
@@ -242,6 +601,10 @@ fn main() -> Result<(), Box<dyn error::Error>> {
         )
         .get_matches();
 
+    if let Some(matches) = matches.subcommand_matches("inspect") {
+        return inspect(matches);
+    }
+
     let bootstrap_leader_keypair_file = matches.value_of("bootstrap_leader_keypair_file").unwrap();
     let bootstrap_vote_keypair_file = matches.value_of("bootstrap_vote_keypair_file").unwrap();
     let bootstrap_stake_keypair_file = matches.value_of("bootstrap_stake_keypair_file").unwrap();
@@ -307,6 +670,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             solana_config_program!(),
             solana_exchange_program!(),
             solana_storage_program!(),
+            solana_memo_program!(),
         ])
         .ticks_per_slot(value_t_or_exit!(matches, "ticks_per_slot", u64))
         .slots_per_epoch(value_t_or_exit!(matches, "slots_per_epoch", u64));
@@ -316,7 +680,40 @@ fn main() -> Result<(), Box<dyn error::Error>> {
         value_t_or_exit!(matches, "target_lamports_per_signature", u64);
     fee_calculator.target_signatures_per_slot =
         value_t_or_exit!(matches, "target_signatures_per_slot", usize);
-    builder = builder.fee_calculator(FeeCalculator::new_derived(&fee_calculator, 0));
+    fee_calculator.burn_percent = value_t_or_exit!(matches, "fee_burn_percent", u8);
+    if fee_calculator.burn_percent > 100 {
+        Err("--fee-burn-percent must be at most 100")?;
+    }
+    let fee_rate_governor = FeeRateGovernor {
+        min_percent: value_t_or_exit!(matches, "fee_rate_min_percent", u64),
+        max_percent: value_t_or_exit!(matches, "fee_rate_max_percent", u64),
+        adjustment_percent: value_t_or_exit!(matches, "fee_rate_adjustment_percent", u64),
+    };
+    if fee_rate_governor.min_percent > fee_rate_governor.max_percent {
+        Err("--fee-rate-min-percent must not exceed --fee-rate-max-percent")?;
+    }
+    builder = builder
+        .fee_calculator(FeeCalculator::new_derived_with_governor(
+            &fee_calculator,
+            0,
+            &fee_rate_governor,
+        ))
+        .fee_rate_governor(fee_rate_governor);
+
+    let inflation = Inflation {
+        initial: value_t_or_exit!(matches, "inflation_initial", f64),
+        terminal: value_t_or_exit!(matches, "inflation_terminal", f64),
+        taper: value_t_or_exit!(matches, "inflation_taper", f64),
+        foundation: value_t_or_exit!(matches, "inflation_foundation", f64),
+        foundation_term: value_t_or_exit!(matches, "inflation_foundation_term", f64),
+        grant: value_t_or_exit!(matches, "inflation_grant", f64),
+        grant_term: value_t_or_exit!(matches, "inflation_grant_term", f64),
+        storage: value_t_or_exit!(matches, "inflation_storage", f64),
+    };
+    if inflation.foundation + inflation.grant + inflation.storage > 1.0 {
+        Err("inflation allocated to the foundation, grants and storage exceeds 100%")?;
+    }
+    builder = builder.inflation(inflation);
 
     let mut poh_config = PohConfig::default();
     poh_config.target_tick_duration =
@@ -355,6 +752,16 @@ fn main() -> Result<(), Box<dyn error::Error>> {
         builder = append_primordial_accounts(file, AccountFileFormat::Keypair, builder)?;
     }
 
+    let storage_config = solana_storage_api::config::Config {
+        min_stake: value_t_or_exit!(matches, "storage_min_stake", u64),
+        whitelist: if matches.is_present("storage_whitelist") {
+            values_t_or_exit!(matches, "storage_whitelist", Pubkey)
+        } else {
+            vec![]
+        },
+    };
+    builder = solana_storage_api::config::genesis(builder, &mint_keypair.pubkey(), &storage_config);
+
     // add the reward pools
     builder = solana_storage_api::rewards_pools::genesis(builder);
     builder = solana_stake_api::rewards_pools::genesis(builder);
@@ -561,4 +968,137 @@ mod tests {
             );
         });
     }
+
+    #[test]
+    fn test_append_primordial_accounts_manifest() {
+        let node_pubkey = Pubkey::new_rand();
+        let vote_pubkey = Pubkey::new_rand();
+        let stake_pubkey = Pubkey::new_rand();
+        let program_account_pubkey = Pubkey::new_rand();
+        let owner = Pubkey::new_rand();
+
+        let mut primordial_accounts = HashMap::new();
+        primordial_accounts.insert(
+            program_account_pubkey.to_string(),
+            PrimordialAccount::Account(PrimordialAccountDetails {
+                balance: 3,
+                owner: Some(owner.to_string()),
+                data: Some(base64::encode(&[1, 2, 3])),
+                executable: true,
+            }),
+        );
+        primordial_accounts.insert(
+            node_pubkey.to_string(),
+            PrimordialAccount::Validator(PrimordialValidator {
+                balance: 10,
+                vote_pubkey: vote_pubkey.to_string(),
+                vote_balance: 1,
+                commission: 5,
+                stake_pubkey: stake_pubkey.to_string(),
+                stake_balance: 100,
+                lockup: 1_000,
+            }),
+        );
+
+        let path = Path::new("test_append_primordial_accounts_manifest.yml");
+        let mut file = File::create(path).unwrap();
+        file.write_all(
+            &serde_yaml::to_string(&primordial_accounts)
+                .unwrap()
+                .into_bytes(),
+        )
+        .unwrap();
+        let builder = append_primordial_accounts(
+            "test_append_primordial_accounts_manifest.yml",
+            AccountFileFormat::Pubkey,
+            Builder::new(),
+        );
+        remove_file(path).unwrap();
+
+        let accounts: HashMap<_, _> = builder.unwrap().build().accounts.into_iter().collect();
+        assert_eq!(accounts.len(), 4);
+        assert_eq!(
+            accounts[&program_account_pubkey],
+            Account {
+                lamports: 3,
+                data: vec![1, 2, 3],
+                owner,
+                executable: true,
+            }
+        );
+        assert_eq!(
+            accounts[&node_pubkey],
+            Account::new(10, 0, &system_program::id())
+        );
+        let vote_state = VoteState::from(&accounts[&vote_pubkey]).unwrap();
+        assert_eq!(vote_state.node_pubkey, node_pubkey);
+        assert_eq!(vote_state.commission, 5);
+        let (stake, lockup) = stake_state::StakeState::from(&accounts[&stake_pubkey])
+            .and_then(|stake_state| stake_state.stake_and_lockup())
+            .unwrap();
+        assert_eq!(stake.voter_pubkey, vote_pubkey);
+        assert_eq!(stake.stake, 100);
+        assert_eq!(lockup, 1_000);
+
+        // Unknown fields are rejected rather than silently creating a different account
+        let path = Path::new("test_append_primordial_accounts_manifest_bad.yml");
+        let mut file = File::create(path).unwrap();
+        file.write_all(format!("{}:\n  balance: 1\n  lockup: 2\n", Pubkey::new_rand()).as_bytes())
+            .unwrap();
+        assert!(append_primordial_accounts(
+            "test_append_primordial_accounts_manifest_bad.yml",
+            AccountFileFormat::Pubkey,
+            Builder::new()
+        )
+        .is_err());
+        remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_append_primordial_accounts_csv() {
+        let system_pubkey = Pubkey::new_rand();
+        let owned_pubkey = Pubkey::new_rand();
+        let owner = Pubkey::new_rand();
+
+        let path = Path::new("test_append_primordial_accounts_csv.csv");
+        let mut file = File::create(path).unwrap();
+        file.write_all(
+            format!(
+                "# pubkey,balance,owner,data\n{},42\n\n{},7,{},{}\n",
+                system_pubkey,
+                owned_pubkey,
+                owner,
+                base64::encode(&[4, 5])
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+        let builder = append_primordial_accounts(
+            "test_append_primordial_accounts_csv.csv",
+            AccountFileFormat::Pubkey,
+            Builder::new(),
+        );
+        remove_file(path).unwrap();
+
+        let accounts: HashMap<_, _> = builder.unwrap().build().accounts.into_iter().collect();
+        assert_eq!(
+            accounts[&system_pubkey],
+            Account::new(42, 0, &system_program::id())
+        );
+        assert_eq!(accounts[&owned_pubkey].lamports, 7);
+        assert_eq!(accounts[&owned_pubkey].owner, owner);
+        assert_eq!(accounts[&owned_pubkey].data, vec![4, 5]);
+
+        let path = Path::new("test_append_primordial_accounts_csv_bad.csv");
+        let mut file = File::create(path).unwrap();
+        file.write_all(format!("{},not a balance\n", system_pubkey).as_bytes())
+            .unwrap();
+        assert!(append_primordial_accounts(
+            "test_append_primordial_accounts_csv_bad.csv",
+            AccountFileFormat::Pubkey,
+            Builder::new()
+        )
+        .is_err());
+        remove_file(path).unwrap();
+    }
 }
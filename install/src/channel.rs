@@ -0,0 +1,101 @@
+use semver::{Version, VersionReq};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+const GITHUB_RELEASES_URL: &str = "https://api.github.com/repos/solana-labs/solana/releases";
+
+/// A named stream of releases to follow
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseChannel {
+    /// Whatever the update manifest points at, usually the latest build of master
+    Edge,
+    /// The newest GitHub release, including pre-releases
+    Beta,
+    /// The newest GitHub release that isn't a pre-release
+    Stable,
+}
+
+pub const RELEASE_CHANNELS: &[&str] = &["edge", "beta", "stable"];
+
+impl ReleaseChannel {
+    /// Whether the channel's releases are found on GitHub rather than in the update manifest
+    pub fn uses_github(self) -> bool {
+        self != ReleaseChannel::Edge
+    }
+}
+
+impl fmt::Display for ReleaseChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ReleaseChannel::Edge => "edge",
+            ReleaseChannel::Beta => "beta",
+            ReleaseChannel::Stable => "stable",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for ReleaseChannel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "edge" => Ok(ReleaseChannel::Edge),
+            "beta" => Ok(ReleaseChannel::Beta),
+            "stable" => Ok(ReleaseChannel::Stable),
+            _ => Err(format!("Unknown release channel: {}", s)),
+        }
+    }
+}
+
+/// The fields of a GitHub release used to pick a channel's release
+#[derive(Deserialize, Debug)]
+struct GithubRelease {
+    tag_name: String,
+    draft: bool,
+    prerelease: bool,
+}
+
+fn fetch_github_releases(
+    http_client: &reqwest::Client,
+) -> Result<Vec<GithubRelease>, Box<dyn std::error::Error>> {
+    let releases = http_client
+        .get(GITHUB_RELEASES_URL)
+        .header(reqwest::header::USER_AGENT, clap::crate_name!())
+        .send()?
+        .error_for_status()?
+        .json()?;
+    Ok(releases)
+}
+
+/// The newest GitHub release on `channel` that satisfies `version_req`, if any
+pub fn latest_github_release(
+    http_client: &reqwest::Client,
+    channel: ReleaseChannel,
+    version_req: Option<&str>,
+) -> Result<Option<Version>, String> {
+    assert!(channel.uses_github());
+    let version_req = version_req
+        .map(VersionReq::parse)
+        .transpose()
+        .map_err(|err| format!("Invalid version requirement: {:?}", err))?;
+    let releases = fetch_github_releases(http_client)
+        .map_err(|err| format!("Unable to fetch releases from GitHub: {}", err))?;
+
+    Ok(releases
+        .into_iter()
+        .filter(|release| !release.draft)
+        .filter(|release| channel == ReleaseChannel::Beta || !release.prerelease)
+        .filter_map(|release| Version::parse(release.tag_name.trim_start_matches('v')).ok())
+        .filter(|version| channel == ReleaseChannel::Beta || !version.is_prerelease())
+        .filter(|version| {
+            // Match pre-releases by the release they lead up to, which `VersionReq` won't do
+            // on its own
+            version_req.as_ref().map_or(true, |version_req| {
+                version_req.matches(&Version::new(version.major, version.minor, version.patch))
+            })
+        })
+        .max())
+}
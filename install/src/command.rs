@@ -1,9 +1,12 @@
-use crate::config::Config;
+use crate::channel::{self, ReleaseChannel};
+use crate::config::{Config, InstalledRelease};
+use crate::defaults;
+use crate::http_client;
 use crate::stop_process::stop_process;
 use crate::update_manifest::{SignedUpdateManifest, UpdateManifest};
 use chrono::{Local, TimeZone};
 use console::{style, Emoji};
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
 use sha2::{Digest, Sha256};
 use solana_client::rpc_client::RpcClient;
 use solana_config_api::config_instruction::{self, ConfigKeys};
@@ -49,6 +52,7 @@ fn println_name_value(name: &str, value: &str) {
 /// * String  - SHA256 of the release
 ///
 fn download_to_temp_archive(
+    http_client: &reqwest::Client,
     url: &str,
     expected_sha256: Option<&str>,
 ) -> Result<(TempDir, PathBuf, String), Box<dyn std::error::Error>> {
@@ -73,12 +77,10 @@ fn download_to_temp_archive(
     let temp_dir = TempDir::new(clap::crate_name!())?;
     let temp_file = temp_dir.path().join("release.tar.bz2");
 
-    let client = reqwest::Client::new();
-
     let progress_bar = new_spinner_progress_bar();
     progress_bar.set_message(&format!("{}Downloading...", TRUCK));
 
-    let response = client.get(url.as_str()).send()?;
+    let response = http_client.get(url.as_str()).send()?;
     let download_size = {
         response
             .headers()
@@ -486,6 +488,7 @@ fn add_to_path(new_path: &str) -> Result<bool, String> {
     Ok(modified_rcfiles)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn init(
     config_file: &str,
     data_dir: &str,
@@ -493,18 +496,37 @@ pub fn init(
     update_manifest_pubkey: &Pubkey,
     no_modify_path: bool,
     release_semver: Option<&str>,
+    channel: Option<ReleaseChannel>,
+    channel_version_req: Option<&str>,
+    cacert: Option<&str>,
 ) -> Result<(), String> {
+    // `run` and `update` may run from another directory
+    let cacert = match cacert {
+        Some(cacert) => Some(
+            fs::canonicalize(cacert)
+                .map_err(|err| format!("Unable to read {}: {}", cacert, err))?
+                .to_str()
+                .unwrap()
+                .to_string(),
+        ),
+        None => None,
+    };
     let config = {
         // Write new config file only if different, so that running |solana-install init|
         // repeatedly doesn't unnecessarily re-download
         let mut current_config = Config::load(config_file).unwrap_or_default();
         current_config.current_update_manifest = None;
-        let config = Config::new(
+        let mut config = Config::new(
             data_dir,
             json_rpc_url,
             update_manifest_pubkey,
             release_semver,
+            channel,
+            channel_version_req,
         );
+        config.cacert = cacert;
+        // Keep each channel's downgrade protection across re-initializations
+        config.channel_versions = current_config.channel_versions.clone();
         if current_config != config {
             config.save(config_file)?;
         }
@@ -541,6 +563,21 @@ pub fn info(config_file: &str, local_info_only: bool) -> Result<Option<UpdateMan
         "Active release directory:",
         &config.active_release_dir().to_str().unwrap_or("?"),
     );
+    if !config.release_history.is_empty() {
+        println_name_value("Installed releases:", "");
+        for installed in &config.release_history {
+            let active = if Some(&installed.release_id) == config.active_release_id.as_ref() {
+                " (active)"
+            } else {
+                ""
+            };
+            let when = Local.timestamp(installed.installed_secs as i64, 0);
+            println_name_value(
+                &format!("{}{}{}", BULLET, installed.release_id, active),
+                &format!("installed {}", when),
+            );
+        }
+    }
     if let Some(release_semver) = &config.release_semver {
         println_name_value(&format!("{}Release version:", BULLET), &release_semver);
         println_name_value(
@@ -550,6 +587,33 @@ pub fn info(config_file: &str, local_info_only: bool) -> Result<Option<UpdateMan
         return Ok(None);
     }
 
+    if let Some(channel) = config.channel {
+        println_name_value("Release channel:", &channel.to_string());
+        if let Some(version_req) = &config.channel_version_req {
+            println_name_value(&format!("{}Version requirement:", BULLET), version_req);
+        }
+    }
+    if let Some(channel) = config.github_channel() {
+        if let Some(version) = config.channel_versions.get(&channel) {
+            println_name_value(&format!("{}Installed version:", BULLET), version);
+        }
+        if !local_info_only {
+            let progress_bar = new_spinner_progress_bar();
+            progress_bar.set_message(&format!("{}Checking for updates...", LOOKING_GLASS));
+            let latest = channel::latest_github_release(
+                &config.http_client()?,
+                channel,
+                config.channel_version_req.as_ref().map(String::as_str),
+            )?;
+            progress_bar.finish_and_clear();
+            println_name_value(
+                &format!("{}Latest version:", BULLET),
+                &latest.map_or("None".to_string(), |version| version.to_string()),
+            );
+        }
+        return Ok(None);
+    }
+
     println_name_value("JSON RPC URL:", &config.json_rpc_url);
     println_name_value(
         "Update manifest pubkey:",
@@ -580,7 +644,8 @@ pub fn info(config_file: &str, local_info_only: bool) -> Result<Option<UpdateMan
     } else {
         let progress_bar = new_spinner_progress_bar();
         progress_bar.set_message(&format!("{}Checking for updates...", LOOKING_GLASS));
-        let rpc_client = RpcClient::new(config.json_rpc_url.clone());
+        let rpc_client =
+            RpcClient::new_with_client(config.json_rpc_url.clone(), config.http_client()?);
         let manifest = get_update_manifest(&rpc_client, &config.update_manifest_pubkey)?;
         progress_bar.finish_and_clear();
 
@@ -600,14 +665,16 @@ pub fn deploy(
     from_keypair_file: &str,
     download_url: &str,
     update_manifest_keypair_file: &str,
+    cacert: Option<&str>,
 ) -> Result<(), String> {
+    let http_client = http_client::new(cacert)?;
     let from_keypair = read_keypair(from_keypair_file)
         .map_err(|err| format!("Unable to read {}: {}", from_keypair_file, err))?;
     let update_manifest_keypair = read_keypair(update_manifest_keypair_file)
         .map_err(|err| format!("Unable to read {}: {}", update_manifest_keypair_file, err))?;
 
     // Confirm the `json_rpc_url` is good and that `from_keypair` is a valid account
-    let rpc_client = RpcClient::new(json_rpc_url.to_string());
+    let rpc_client = RpcClient::new_with_client(json_rpc_url.to_string(), http_client.clone());
     let progress_bar = new_spinner_progress_bar();
     progress_bar.set_message(&format!("{}Checking cluster...", LOOKING_GLASS));
     let balance = rpc_client
@@ -625,7 +692,7 @@ pub fn deploy(
 
     // Download the release
     let (temp_dir, temp_archive, temp_archive_sha256) =
-        download_to_temp_archive(download_url, None)
+        download_to_temp_archive(&http_client, download_url, None)
             .map_err(|err| format!("Unable to download {}: {}", download_url, err))?;
 
     // Extract it and load the release version metadata
@@ -692,29 +759,116 @@ fn symlink_dir<P: AsRef<Path>, Q: AsRef<Path>>(src: P, dst: Q) -> std::io::Resul
     std::os::unix::fs::symlink(src, dst)
 }
 
+/// Removes a symlink created by `symlink_dir()`, leaving the directory it points to intact
+#[cfg(windows)]
+fn remove_symlink_dir<P: AsRef<Path>>(link: P) -> std::io::Result<()> {
+    fs::remove_dir(link)
+}
+#[cfg(not(windows))]
+fn remove_symlink_dir<P: AsRef<Path>>(link: P) -> std::io::Result<()> {
+    fs::remove_file(link)
+}
+
+/// Points the active release symlink at the installed release `release_id`
+fn activate_release(config: &Config, release_id: &str) -> Result<(), String> {
+    let release_dir = config.release_dir(release_id);
+    let release_target = load_release_target(&release_dir).map_err(|err| {
+        format!(
+            "Unable to load release target from {:?}: {}",
+            release_dir, err
+        )
+    })?;
+
+    if release_target != crate::build_env::TARGET {
+        Err(format!("Incompatible update target: {}", release_target))?;
+    }
+
+    let _ = remove_symlink_dir(config.active_release_dir());
+    symlink_dir(
+        release_dir.join("solana-release"),
+        config.active_release_dir(),
+    )
+    .map_err(|err| {
+        format!(
+            "Unable to symlink {:?} to {:?}: {}",
+            release_dir,
+            config.active_release_dir(),
+            err
+        )
+    })
+}
+
+/// Downloads and extracts the GitHub release `release_semver`, unless it's already installed
+fn download_github_release(
+    http_client: &reqwest::Client,
+    config: &Config,
+    release_semver: &str,
+) -> Result<(), String> {
+    let download_url = github_download_url(release_semver);
+    let release_dir = config.release_dir(release_semver);
+    let ok_dir = release_dir.join(".ok");
+    if ok_dir.exists() {
+        return Ok(());
+    }
+    let (_temp_dir, temp_archive, _temp_archive_sha256) =
+        download_to_temp_archive(http_client, &download_url, None)
+            .map_err(|err| format!("Unable to download {}: {}", download_url, err))?;
+    extract_release_archive(&temp_archive, &release_dir).map_err(|err| {
+        format!(
+            "Unable to extract {:?} to {:?}: {}",
+            temp_archive, release_dir, err
+        )
+    })?;
+    let _ = fs::create_dir_all(ok_dir);
+    Ok(())
+}
+
 pub fn update(config_file: &str) -> Result<bool, String> {
     let mut config = Config::load(config_file)?;
-    let update_manifest = info(config_file, false)?;
+    // A GitHub channel's releases are checked for below, only once to go easy on its rate limit
+    let update_manifest = info(config_file, config.github_channel().is_some())?;
+    let http_client = config.http_client()?;
 
-    let release_dir = if let Some(release_semver) = &config.release_semver {
-        let download_url = github_download_url(release_semver);
-        let release_dir = config.release_dir(&release_semver);
-        let ok_dir = release_dir.join(".ok");
-        if ok_dir.exists() {
+    let (release_id, update_manifest) = if let Some(release_semver) = &config.release_semver {
+        if config.release_dir(release_semver).join(".ok").exists() {
             return Ok(false);
         }
-        let (_temp_dir, temp_archive, _temp_archive_sha256) =
-            download_to_temp_archive(&download_url, None)
-                .map_err(|err| format!("Unable to download {}: {}", download_url, err))?;
-        extract_release_archive(&temp_archive, &release_dir).map_err(|err| {
-            format!(
-                "Unable to extract {:?} to {:?}: {}",
-                temp_archive, release_dir, err
-            )
-        })?;
-        let _ = fs::create_dir_all(ok_dir);
+        download_github_release(&http_client, &config, release_semver)?;
+
+        (release_semver.clone(), None)
+    } else if let Some(channel) = config.github_channel() {
+        let version = channel::latest_github_release(
+            &http_client,
+            channel,
+            config.channel_version_req.as_ref().map(String::as_str),
+        )?
+        .ok_or_else(|| format!("No release found on the {} channel", channel))?;
+
+        if let Some(installed) = config.channel_versions.get(&channel) {
+            let installed = semver::Version::parse(installed)
+                .map_err(|err| format!("Invalid installed version {}: {:?}", installed, err))?;
+            if version < installed {
+                Err(format!(
+                    "Unable to update the {} channel to {}, older than the already installed {}",
+                    channel, version, installed
+                ))?
+            }
+        }
+
+        let release_id = version.to_string();
+        if config.active_release_id.as_ref() == Some(&release_id)
+            && config.release_dir(&release_id).join(".ok").exists()
+        {
+            return Ok(false);
+        }
+        println_name_value(
+            &format!("Updating the {} channel to:", channel),
+            &release_id,
+        );
+        download_github_release(&http_client, &config, &release_id)?;
+        config.channel_versions.insert(channel, release_id.clone());
 
-        release_dir
+        (release_id, None)
     } else {
         if update_manifest.is_none() {
             return Ok(false);
@@ -734,6 +888,7 @@ pub fn update(config_file: &str) -> Result<bool, String> {
         }
         let release_dir = config.release_dir(&update_manifest.download_sha256);
         let (_temp_dir, temp_archive, _temp_archive_sha256) = download_to_temp_archive(
+            &http_client,
             &update_manifest.download_url,
             Some(&update_manifest.download_sha256),
         )
@@ -750,45 +905,229 @@ pub fn update(config_file: &str) -> Result<bool, String> {
             )
         })?;
 
-        config.current_update_manifest = Some(update_manifest);
-        release_dir
+        config.current_update_manifest = Some(update_manifest.clone());
+        (
+            update_manifest.download_sha256.clone(),
+            Some(update_manifest),
+        )
     };
 
-    let release_target = load_release_target(&release_dir).map_err(|err| {
-        format!(
-            "Unable to load release target from {:?}: {}",
-            release_dir, err
-        )
-    })?;
+    activate_release(&config, &release_id)?;
 
-    if release_target != crate::build_env::TARGET {
-        Err(format!("Incompatible update target: {}", release_target))?;
+    let dropped_release_dirs = config.add_release(
+        InstalledRelease {
+            release_id,
+            update_manifest,
+            installed_secs: timestamp_secs(),
+        },
+        defaults::RELEASES_TO_KEEP,
+    );
+    for release_dir in dropped_release_dirs {
+        let _ = fs::remove_dir_all(release_dir);
     }
 
-    let _ = fs::remove_dir_all(config.active_release_dir());
-    symlink_dir(
-        release_dir.join("solana-release"),
-        config.active_release_dir(),
-    )
-    .map_err(|err| {
-        format!(
-            "Unable to symlink {:?} to {:?}: {}",
-            release_dir,
-            config.active_release_dir(),
-            err
-        )
-    })?;
-
     config.save(config_file)?;
 
     println!("  {}{}", SPARKLE, style("Update successful").bold());
     Ok(true)
 }
 
+/// Re-activates an installed release, the one before the active release if `release_id` is
+/// `None`.  The release rolled back from is not re-applied by `update`, but newer updates are.
+pub fn rollback(config_file: &str, release_id: Option<&str>) -> Result<(), String> {
+    let mut config = Config::load(config_file)?;
+    let release_id = config.rollback_release(release_id)?.release_id.clone();
+
+    activate_release(&config, &release_id)?;
+    config.active_release_id = Some(release_id.clone());
+    config.save(config_file)?;
+
+    println!(
+        "  {}{}",
+        SPARKLE,
+        style(format!("Rolled back to {}", release_id)).bold()
+    );
+    Ok(())
+}
+
+/// Switches to following `channel`, and installs its newest release.  A GitHub channel won't be
+/// updated to a release older than one it installed before.
+pub fn set_channel(
+    config_file: &str,
+    channel: ReleaseChannel,
+    channel_version_req: Option<&str>,
+) -> Result<(), String> {
+    let mut config = Config::load(config_file)?;
+    if !channel.uses_github() && channel_version_req.is_some() {
+        Err(format!(
+            "The {} channel follows the update manifest, which has no versions to require",
+            channel
+        ))?;
+    }
+    config.release_semver = None;
+    config.set_channel(Some(channel), channel_version_req);
+    let manifest_release_id = config
+        .current_update_manifest
+        .as_ref()
+        .map(|update_manifest| update_manifest.download_sha256.clone());
+    if let (false, Some(release_id)) = (channel.uses_github(), manifest_release_id) {
+        // Switch back to the release the update manifest last installed
+        if config.active_release_id.as_ref() != Some(&release_id) {
+            if config
+                .release_history
+                .iter()
+                .any(|installed| installed.release_id == release_id)
+            {
+                activate_release(&config, &release_id)?;
+                config.active_release_id = Some(release_id);
+            } else {
+                // It has been removed since, so have `update` install it again
+                config.current_update_manifest = None;
+            }
+        }
+    }
+    config.save(config_file)?;
+
+    println_name_value("Release channel:", &channel.to_string());
+    update(config_file).map(|_| ())
+}
+
+/// When `path`, or anything under it, was last modified, not following symlinks
+fn last_modified(path: &Path) -> Option<SystemTime> {
+    let metadata = fs::symlink_metadata(path).ok()?;
+    let modified = metadata.modified().ok();
+    if !metadata.is_dir() {
+        return modified;
+    }
+    fs::read_dir(path)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|entry| last_modified(&entry.path()))
+        .chain(modified)
+        .max()
+}
+
+/// The size in bytes of `path` and everything under it, not following symlinks
+fn disk_usage(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(ref metadata) if metadata.is_dir() => fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .map(|entry| disk_usage(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or(0),
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Removes the installed releases beyond the newest `releases_to_keep`, except the active one,
+/// and the archives left behind by interrupted downloads.  Safe to run while `run` is active:
+/// anything `gc` can't account for is left alone until it's `GC_MIN_AGE_SECS` old.
+pub fn gc(config_file: &str, releases_to_keep: usize) -> Result<(), String> {
+    let config = Config::load(config_file)?;
+    let retained_release_ids = config.retained_release_ids(releases_to_keep);
+    let min_age = Duration::from_secs(defaults::GC_MIN_AGE_SECS);
+    let is_stale = |path: &Path| {
+        last_modified(path)
+            .and_then(|modified| modified.elapsed().ok())
+            .map_or(false, |age| age >= min_age)
+    };
+
+    let mut garbage = vec![];
+    if let Ok(entries) = fs::read_dir(config.releases_dir()) {
+        for entry in entries.filter_map(Result::ok) {
+            let release_id = entry.file_name().to_string_lossy().to_string();
+            if retained_release_ids.contains(&release_id.as_str()) {
+                continue;
+            }
+            let installed = config
+                .release_history
+                .iter()
+                .any(|installed| installed.release_id == release_id);
+            // A release missing from the history may be the one being installed right now
+            if installed || is_stale(&entry.path()) {
+                garbage.push(entry.path());
+            }
+        }
+    }
+
+    // Downloads go to a `TempDir` named after this crate, which is normally removed once the
+    // release is extracted
+    let temp_prefix = format!("{}.", clap::crate_name!());
+    if let Ok(entries) = fs::read_dir(std::env::temp_dir()) {
+        garbage.extend(
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| {
+                    entry
+                        .file_name()
+                        .to_string_lossy()
+                        .starts_with(&temp_prefix)
+                })
+                .map(|entry| entry.path())
+                .filter(|path| is_stale(path)),
+        );
+    }
+
+    let mut reclaimed_bytes = 0;
+    for path in garbage {
+        let bytes = disk_usage(&path);
+        match fs::remove_dir_all(&path) {
+            Ok(()) => {
+                println_name_value(
+                    &format!("{}Removed:", BULLET),
+                    &path.to_str().unwrap_or("?"),
+                );
+                reclaimed_bytes += bytes;
+            }
+            Err(err) => eprintln!("Unable to remove {:?}: {}", path, err),
+        }
+    }
+
+    // Reload the config, in case `run` installed an update in the meantime
+    let mut config = Config::load(config_file)?;
+    let releases_dir = config.releases_dir().clone();
+    let history_len = config.release_history.len();
+    config
+        .release_history
+        .retain(|installed| releases_dir.join(&installed.release_id).exists());
+    if config.release_history.len() != history_len {
+        config.save(config_file)?;
+    }
+
+    println!(
+        "  {}{}",
+        SPARKLE,
+        style(format!("Reclaimed {}", HumanBytes(reclaimed_bytes))).bold()
+    );
+    Ok(())
+}
+
+/// Rotates the previous log files of `program_name` in `logs_dir`, and creates a new one
+fn create_log_file(logs_dir: &Path, program_name: &str) -> io::Result<File> {
+    fs::create_dir_all(logs_dir)?;
+    let log_path = |n: usize| {
+        if n == 0 {
+            logs_dir.join(format!("{}.log", program_name))
+        } else {
+            logs_dir.join(format!("{}.log.{}", program_name, n))
+        }
+    };
+    for n in (0..defaults::LOG_FILES_TO_KEEP - 1).rev() {
+        let _ = fs::rename(log_path(n), log_path(n + 1));
+    }
+    File::create(log_path(0))
+}
+
 pub fn run(
     config_file: &str,
     program_name: &str,
     program_arguments: Vec<&str>,
+    max_restarts: usize,
+    capture_logs: bool,
 ) -> Result<(), String> {
     let config = Config::load(config_file)?;
 
@@ -803,10 +1142,23 @@ pub fn run(
             full_program_path.to_str().unwrap()
         ))?;
     }
+    let log_name = full_program_path
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or(program_name)
+        .to_string();
 
     let mut child_option: Option<std::process::Child> = None;
     let mut now = Instant::now();
 
+    // A program that keeps exiting soon after it starts is restarted less and less often, and
+    // given up on after `max_restarts` such exits in a row
+    let initial_backoff = Duration::from_secs(defaults::RESTART_INITIAL_BACKOFF_SECS);
+    let mut restart_backoff = initial_backoff;
+    let mut next_start = Instant::now();
+    let mut started = Instant::now();
+    let mut crashes = 0;
+
     let (signal_sender, signal_receiver) = mpsc::channel();
     ctrlc::set_handler(move || {
         let _ = signal_sender.send(());
@@ -829,11 +1181,26 @@ pub fn run(
                     None
                 }
             },
+            None if Instant::now() < next_start => None,
             None => {
-                match std::process::Command::new(&full_program_path)
-                    .args(&program_arguments)
-                    .spawn()
-                {
+                let mut command = std::process::Command::new(&full_program_path);
+                command.args(&program_arguments);
+                if capture_logs {
+                    let log_file =
+                        create_log_file(&config.logs_dir(), &log_name).map_err(|err| {
+                            format!(
+                                "Unable to create log file in {:?}: {}",
+                                config.logs_dir(),
+                                err
+                            )
+                        })?;
+                    let stderr_file = log_file
+                        .try_clone()
+                        .map_err(|err| format!("Unable to clone log file: {}", err))?;
+                    command.stdout(log_file).stderr(stderr_file);
+                }
+                started = Instant::now();
+                match command.spawn() {
                     Ok(child) => Some(child),
                     Err(err) => {
                         eprintln!("Failed to spawn {}: {:?}", program_name, err);
@@ -843,6 +1210,31 @@ pub fn run(
             }
         };
 
+        if child_option.is_none() && next_start <= started {
+            // The program just exited, or failed to start
+            if started.elapsed() < Duration::from_secs(defaults::RESTART_HEALTHY_SECS) {
+                crashes += 1;
+                if crashes > max_restarts {
+                    Err(format!(
+                        "{} exited {} times in a row within {}s of starting, giving up",
+                        program_name,
+                        crashes,
+                        defaults::RESTART_HEALTHY_SECS
+                    ))?;
+                }
+                println_name_value("Restarting in:", &format!("{}s", restart_backoff.as_secs()));
+                next_start = Instant::now() + restart_backoff;
+                restart_backoff = std::cmp::min(
+                    restart_backoff * 2,
+                    Duration::from_secs(defaults::RESTART_MAX_BACKOFF_SECS),
+                );
+            } else {
+                crashes = 0;
+                restart_backoff = initial_backoff;
+                next_start = Instant::now();
+            }
+        }
+
         if now.elapsed().as_secs() > config.update_poll_secs {
             match update(config_file) {
                 Ok(true) => {
@@ -852,6 +1244,11 @@ pub fn run(
                             eprintln!("Failed to stop child: {:?}", err);
                         });
                     }
+                    // The new release gets a fresh start
+                    child_option = None;
+                    crashes = 0;
+                    restart_backoff = initial_backoff;
+                    next_start = Instant::now();
                 }
                 Ok(false) => {} // No update available
                 Err(err) => {
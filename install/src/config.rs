@@ -1,10 +1,25 @@
+use crate::channel::ReleaseChannel;
+use crate::defaults;
+use crate::http_client;
 use crate::update_manifest::UpdateManifest;
 use serde_derive::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
 use std::fs::{create_dir_all, File};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
+/// A release that was installed into the releases directory
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InstalledRelease {
+    /// Name of the release's directory: its semver, or the SHA256 of its archive
+    pub release_id: String,
+    /// The update manifest the release was installed from, if any
+    pub update_manifest: Option<UpdateManifest>,
+    /// When the release was installed in seconds since UNIX EPOCH
+    pub installed_secs: u64,
+}
+
 #[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
 pub struct Config {
     pub json_rpc_url: String,
@@ -14,6 +29,25 @@ pub struct Config {
     pub release_semver: Option<String>,
     releases_dir: PathBuf,
     active_release_dir: PathBuf,
+    /// Releases still in the releases directory, oldest first
+    #[serde(default)]
+    pub release_history: Vec<InstalledRelease>,
+    /// The `release_id` of the release `active_release_dir` links to
+    #[serde(default)]
+    pub active_release_id: Option<String>,
+    /// The release channel followed, unless `release_semver` pins a release
+    #[serde(default)]
+    pub channel: Option<ReleaseChannel>,
+    /// Semver requirement the channel's releases must meet, such as `~0.18`
+    #[serde(default)]
+    pub channel_version_req: Option<String>,
+    /// The newest semver installed from each GitHub release channel, which updates on that
+    /// channel won't go back from
+    #[serde(default)]
+    pub channel_versions: BTreeMap<ReleaseChannel, String>,
+    /// PEM encoded CA certificate to trust, besides the system's, for downloads and RPC requests
+    #[serde(default)]
+    pub cacert: Option<String>,
 }
 
 impl Config {
@@ -22,16 +56,50 @@ impl Config {
         json_rpc_url: &str,
         update_manifest_pubkey: &Pubkey,
         release_semver: Option<&str>,
+        channel: Option<ReleaseChannel>,
+        channel_version_req: Option<&str>,
     ) -> Self {
-        Self {
+        let mut config = Self {
             json_rpc_url: json_rpc_url.to_string(),
             update_manifest_pubkey: *update_manifest_pubkey,
             current_update_manifest: None,
-            update_poll_secs: 60, // check for updates once a minute
+            update_poll_secs: 0,
             release_semver: release_semver.map(|s| s.to_string()),
             releases_dir: PathBuf::from(data_dir).join("releases"),
             active_release_dir: PathBuf::from(data_dir).join("active_release"),
+            release_history: vec![],
+            active_release_id: None,
+            channel: None,
+            channel_version_req: None,
+            channel_versions: BTreeMap::new(),
+            cacert: None,
+        };
+        config.set_channel(channel, channel_version_req);
+        config
+    }
+
+    /// Follow `channel`, or the update manifest if `None`
+    pub fn set_channel(
+        &mut self,
+        channel: Option<ReleaseChannel>,
+        channel_version_req: Option<&str>,
+    ) {
+        self.channel = channel;
+        self.channel_version_req = channel_version_req.map(|s| s.to_string());
+        self.update_poll_secs = if channel.map_or(false, ReleaseChannel::uses_github) {
+            defaults::GITHUB_UPDATE_POLL_SECS
+        } else {
+            defaults::UPDATE_POLL_SECS
+        };
+    }
+
+    /// The GitHub release channel followed, if the release isn't pinned and the channel
+    /// doesn't use the update manifest
+    pub fn github_channel(&self) -> Option<ReleaseChannel> {
+        if self.release_semver.is_some() {
+            return None;
         }
+        self.channel.filter(|channel| channel.uses_github())
     }
 
     fn _load(config_file: &str) -> Result<Self, io::Error> {
@@ -63,6 +131,11 @@ impl Config {
             .map_err(|err| format!("Unable to save {}: {:?}", config_file, err))
     }
 
+    /// The client to download releases and make RPC requests with
+    pub fn http_client(&self) -> Result<reqwest::Client, String> {
+        http_client::new(self.cacert.as_ref().map(String::as_str))
+    }
+
     pub fn active_release_dir(&self) -> &PathBuf {
         &self.active_release_dir
     }
@@ -71,7 +144,78 @@ impl Config {
         self.active_release_dir.join("bin")
     }
 
+    pub fn releases_dir(&self) -> &PathBuf {
+        &self.releases_dir
+    }
+
     pub fn release_dir(&self, release_id: &str) -> PathBuf {
         self.releases_dir.join(release_id)
     }
+
+    /// Where `run` writes the logs of the programs it runs
+    pub fn logs_dir(&self) -> PathBuf {
+        self.releases_dir.with_file_name("logs")
+    }
+
+    /// Record that `release` was installed and is now active. Only the newest `releases_to_keep`
+    /// releases are kept in the history; returns the directories of the releases dropped from it.
+    pub fn add_release(
+        &mut self,
+        release: InstalledRelease,
+        releases_to_keep: usize,
+    ) -> Vec<PathBuf> {
+        self.release_history
+            .retain(|installed| installed.release_id != release.release_id);
+        self.active_release_id = Some(release.release_id.clone());
+        self.release_history.push(release);
+
+        let excess = self
+            .release_history
+            .len()
+            .saturating_sub(releases_to_keep.max(1));
+        let releases_dir = &self.releases_dir;
+        self.release_history
+            .drain(..excess)
+            .map(|dropped| releases_dir.join(dropped.release_id))
+            .collect()
+    }
+
+    /// The releases `gc` keeps: the active release and the newest `releases_to_keep` releases
+    /// in the history
+    pub fn retained_release_ids(&self, releases_to_keep: usize) -> Vec<&str> {
+        let mut release_ids: Vec<_> = self
+            .release_history
+            .iter()
+            .rev()
+            .take(releases_to_keep)
+            .map(|installed| installed.release_id.as_str())
+            .collect();
+        if let Some(active_release_id) = &self.active_release_id {
+            if !release_ids.contains(&active_release_id.as_str()) {
+                release_ids.push(active_release_id);
+            }
+        }
+        release_ids
+    }
+
+    /// The release to roll back to: the one named `release_id`, or the release installed before
+    /// the active one if `None`
+    pub fn rollback_release(&self, release_id: Option<&str>) -> Result<&InstalledRelease, String> {
+        match release_id {
+            Some(release_id) => self
+                .release_history
+                .iter()
+                .find(|installed| installed.release_id == release_id)
+                .ok_or_else(|| format!("Release {} is not installed", release_id)),
+            None => {
+                let active = self.release_history.iter().position(|installed| {
+                    Some(&installed.release_id) == self.active_release_id.as_ref()
+                });
+                match active {
+                    Some(active) if active > 0 => Ok(&self.release_history[active - 1]),
+                    _ => Err("No earlier release to roll back to".to_string()),
+                }
+            }
+        }
+    }
 }
@@ -1,5 +1,29 @@
 pub const JSON_RPC_URL: &str = "http://testnet.solana.com:8899";
 
+/// How often to check the update manifest for updates
+pub const UPDATE_POLL_SECS: u64 = 60;
+/// How often to check GitHub for a channel's updates, which is rate limited
+pub const GITHUB_UPDATE_POLL_SECS: u64 = 60 * 60;
+
+/// How many installed releases to keep in the data directory, so that `rollback` has something
+/// to roll back to
+pub const RELEASES_TO_KEEP: usize = 3;
+
+/// Release directories and temporary archives that `gc` finds but knows nothing about are left
+/// alone until they are this old, as `run` may still be downloading or extracting them
+pub const GC_MIN_AGE_SECS: u64 = 60 * 60;
+
+/// How many times in a row `run` restarts a program that exits soon after starting
+pub const MAX_RESTARTS: usize = 10;
+/// A program that ran at least this long before exiting is restarted right away
+pub const RESTART_HEALTHY_SECS: u64 = 60;
+/// Restarts of a program that exited sooner are delayed by this much, doubling each time
+pub const RESTART_INITIAL_BACKOFF_SECS: u64 = 1;
+pub const RESTART_MAX_BACKOFF_SECS: u64 = 60;
+
+/// How many log files `run --log` keeps for each program
+pub const LOG_FILES_TO_KEEP: usize = 5;
+
 lazy_static! {
     pub static ref CONFIG_FILE: Option<String> = {
         dirs::home_dir().map(|mut path| {
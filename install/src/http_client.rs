@@ -0,0 +1,88 @@
+use std::env;
+use std::fs;
+
+/// The value of the first of the environment variables `names` that is set, trying each name in
+/// upper and lower case
+fn env_var(names: &[&str]) -> Option<(String, String)> {
+    names
+        .iter()
+        .flat_map(|name| vec![name.to_string(), name.to_lowercase()])
+        .filter_map(|name| match env::var(&name) {
+            Ok(ref value) if !value.is_empty() => Some((name, value.clone())),
+            _ => None,
+        })
+        .next()
+}
+
+fn proxy_from_env(names: &[&str]) -> Result<Option<reqwest::Url>, String> {
+    match env_var(names) {
+        Some((name, value)) => {
+            // A proxy is often given as just `host:port`
+            let value = if value.contains("://") {
+                value
+            } else {
+                format!("http://{}", value)
+            };
+            reqwest::Url::parse(&value)
+                .map(Some)
+                .map_err(|err| format!("Invalid {} {}: {}", name, value, err))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Whether `host` matches one of the `NO_PROXY` patterns: a domain, which also matches its
+/// subdomains, or `*` for every host
+fn is_no_proxy(no_proxy: &[String], host: &str) -> bool {
+    no_proxy.iter().any(|pattern| {
+        let domain = pattern.trim_start_matches('.');
+        pattern == "*" || host == domain || host.ends_with(&format!(".{}", domain))
+    })
+}
+
+/// Creates the client for downloads and RPC requests.  Requests go through the proxy in the
+/// `HTTPS_PROXY`, `HTTP_PROXY` or `ALL_PROXY` environment variable, except to the hosts in
+/// `NO_PROXY`, and servers may also present a certificate signed by the PEM encoded CA
+/// certificate in the `cacert` file.
+pub fn new(cacert: Option<&str>) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder();
+
+    let http_proxy = proxy_from_env(&["HTTP_PROXY", "ALL_PROXY"])?;
+    let https_proxy = proxy_from_env(&["HTTPS_PROXY", "ALL_PROXY"])?;
+    if http_proxy.is_some() || https_proxy.is_some() {
+        let no_proxy: Vec<_> = env_var(&["NO_PROXY"])
+            .map(|(_, value)| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|pattern| !pattern.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        builder = builder.proxy(reqwest::Proxy::custom(move |url| {
+            if url
+                .host_str()
+                .map_or(false, |host| is_no_proxy(&no_proxy, host))
+            {
+                return None;
+            }
+            match url.scheme() {
+                "http" => http_proxy.clone(),
+                "https" => https_proxy.clone(),
+                _ => None,
+            }
+        }));
+    }
+
+    if let Some(cacert) = cacert {
+        let pem = fs::read(cacert).map_err(|err| format!("Unable to read {}: {}", cacert, err))?;
+        let certificate = reqwest::Certificate::from_pem(&pem)
+            .map_err(|err| format!("Invalid CA certificate {}: {}", cacert, err))?;
+        builder = builder.add_root_certificate(certificate);
+    }
+
+    builder
+        .build()
+        .map_err(|err| format!("Unable to create HTTP client: {}", err))
+}
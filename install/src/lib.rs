@@ -5,9 +5,12 @@ use clap::{crate_description, crate_name, crate_version, App, AppSettings, Arg,
 use solana_sdk::pubkey::Pubkey;
 
 mod build_env;
+mod channel;
 mod command;
 mod config;
 mod defaults;
+mod http_client;
+mod service;
 mod stop_process;
 mod update_manifest;
 
@@ -33,6 +36,13 @@ fn is_pubkey(string: String) -> Result<(), String> {
     }
 }
 
+fn is_usize(string: String) -> Result<(), String> {
+    match string.parse::<usize>() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("{:?}", err)),
+    }
+}
+
 fn is_semver(string: String) -> Result<(), String> {
     match semver::Version::parse(&string) {
         Ok(_) => Ok(()),
@@ -40,8 +50,17 @@ fn is_semver(string: String) -> Result<(), String> {
     }
 }
 
+fn is_version_req(string: String) -> Result<(), String> {
+    match semver::VersionReq::parse(&string) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("{:?}", err)),
+    }
+}
+
 pub fn main() -> Result<(), String> {
     solana_logger::setup();
+    let default_max_restarts = &defaults::MAX_RESTARTS.to_string();
+    let default_releases_to_keep = &defaults::RELEASES_TO_KEEP.to_string();
 
     let matches = App::new(crate_name!())
         .about(crate_description!())
@@ -113,6 +132,31 @@ pub fn main() -> Result<(), String> {
                         .conflicts_with_all(&["json_rpc_url", "update_manifest_pubkey"])
                         .validator(is_semver)
                         .help("The exact version to install.  Updates will not be available if this argument is used"),
+                )
+                .arg(
+                    Arg::with_name("channel")
+                        .long("channel")
+                        .value_name("CHANNEL")
+                        .takes_value(true)
+                        .possible_values(channel::RELEASE_CHANNELS)
+                        .conflicts_with("release_semver")
+                        .help("The release channel to follow: edge follows the update manifest, beta and stable follow GitHub releases"),
+                )
+                .arg(
+                    Arg::with_name("version_req")
+                        .long("version-req")
+                        .value_name("REQ")
+                        .takes_value(true)
+                        .requires("channel")
+                        .validator(is_version_req)
+                        .help("Only install releases of the channel that meet this semver requirement, such as ~0.18"),
+                )
+                .arg(
+                    Arg::with_name("cacert")
+                        .long("cacert")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("PEM encoded CA certificate to trust for downloads and RPC requests, besides the system's"),
                 ),
         )
         .subcommand(
@@ -167,6 +211,13 @@ pub fn main() -> Result<(), String> {
                         .index(2)
                         .required(true)
                         .help("Keypair file for the update manifest (/path/to/keypair.json)"),
+                )
+                .arg(
+                    Arg::with_name("cacert")
+                        .long("cacert")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("PEM encoded CA certificate to trust for downloads and RPC requests, besides the system's"),
                 ),
         )
         .subcommand(
@@ -174,6 +225,92 @@ pub fn main() -> Result<(), String> {
                 .about("checks for an update, and if available downloads and applies it")
                 .setting(AppSettings::DisableVersion),
         )
+        .subcommand(
+            SubCommand::with_name("set-channel")
+                .about("switches to another release channel, and installs its latest release")
+                .setting(AppSettings::DisableVersion)
+                .arg(
+                    Arg::with_name("channel")
+                        .index(1)
+                        .value_name("CHANNEL")
+                        .required(true)
+                        .possible_values(channel::RELEASE_CHANNELS)
+                        .help("The release channel to follow"),
+                )
+                .arg(
+                    Arg::with_name("version_req")
+                        .long("version-req")
+                        .value_name("REQ")
+                        .takes_value(true)
+                        .validator(is_version_req)
+                        .help("Only install releases of the channel that meet this semver requirement, such as ~0.18"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("rollback")
+                .about("re-activates a previously installed release")
+                .setting(AppSettings::DisableVersion)
+                .arg(
+                    Arg::with_name("release")
+                        .index(1)
+                        .value_name("RELEASE")
+                        .help("The release to activate, as listed by `info` [default: the release installed before the active one]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("gc")
+                .about("removes old releases and interrupted downloads to reclaim disk space")
+                .setting(AppSettings::DisableVersion)
+                .arg(
+                    Arg::with_name("releases_to_keep")
+                        .long("keep")
+                        .value_name("NUM")
+                        .takes_value(true)
+                        .default_value(default_releases_to_keep)
+                        .validator(is_usize)
+                        .help("how many of the most recently installed releases to keep, besides the active one"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("service")
+                .about("manages a service that runs a program with the run command")
+                .setting(AppSettings::DisableVersion)
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("install")
+                        .about("installs a systemd unit on Linux, or a scheduled task that starts at boot on Windows")
+                        .setting(AppSettings::DisableVersion)
+                        .arg(
+                            Arg::with_name("program_name")
+                                .index(1)
+                                .required(true)
+                                .help("program to run"),
+                        )
+                        .arg(
+                            Arg::with_name("program_arguments")
+                                .index(2)
+                                .multiple(true)
+                                .help("arguments to supply to the program"),
+                        )
+                        .arg(
+                            Arg::with_name("name")
+                                .long("name")
+                                .value_name("NAME")
+                                .takes_value(true)
+                                .help("name of the service [default: the program name]"),
+                        )
+                        .arg(
+                            Arg::with_name("log")
+                                .long("log")
+                                .help("write the program's output to rotated log files in the data directory"),
+                        )
+                        .arg(
+                            Arg::with_name("user")
+                                .long("user")
+                                .help("install a systemd user unit rather than a system unit"),
+                        ),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("run")
                 .about("Runs a program while periodically checking and applying software updates")
@@ -190,6 +327,20 @@ pub fn main() -> Result<(), String> {
                         .index(2)
                         .multiple(true)
                         .help("arguments to supply to the program"),
+                )
+                .arg(
+                    Arg::with_name("max_restarts")
+                        .long("max-restarts")
+                        .value_name("NUM")
+                        .takes_value(true)
+                        .default_value(default_max_restarts)
+                        .validator(is_usize)
+                        .help("give up after the program exits this many times in a row soon after starting"),
+                )
+                .arg(
+                    Arg::with_name("log")
+                        .long("log")
+                        .help("write the program's output to rotated log files in the data directory"),
                 ),
         )
         .get_matches();
@@ -207,6 +358,11 @@ pub fn main() -> Result<(), String> {
             let data_dir = matches.value_of("data_dir").unwrap();
             let no_modify_path = matches.is_present("no_modify_path");
             let release_semver = matches.value_of("release_semver");
+            let channel = matches
+                .value_of("channel")
+                .map(|channel| channel.parse().unwrap());
+            let version_req = matches.value_of("version_req");
+            let cacert = matches.value_of("cacert");
 
             command::init(
                 config_file,
@@ -215,6 +371,9 @@ pub fn main() -> Result<(), String> {
                 &update_manifest_pubkey,
                 no_modify_path,
                 release_semver,
+                channel,
+                version_req,
+                cacert,
             )
         }
         ("info", Some(matches)) => {
@@ -227,14 +386,52 @@ pub fn main() -> Result<(), String> {
             let download_url = matches.value_of("download_url").unwrap();
             let update_manifest_keypair_file =
                 matches.value_of("update_manifest_keypair_file").unwrap();
+            let cacert = matches.value_of("cacert");
             command::deploy(
                 json_rpc_url,
                 from_keypair_file,
                 download_url,
                 update_manifest_keypair_file,
+                cacert,
             )
         }
         ("update", Some(_matches)) => command::update(config_file).map(|_| ()),
+        ("set-channel", Some(matches)) => {
+            let channel = matches.value_of("channel").unwrap().parse().unwrap();
+            let version_req = matches.value_of("version_req");
+            command::set_channel(config_file, channel, version_req)
+        }
+        ("rollback", Some(matches)) => command::rollback(config_file, matches.value_of("release")),
+        ("gc", Some(matches)) => {
+            let releases_to_keep = matches
+                .value_of("releases_to_keep")
+                .unwrap()
+                .parse()
+                .unwrap();
+            command::gc(config_file, releases_to_keep)
+        }
+        ("service", Some(matches)) => match matches.subcommand() {
+            ("install", Some(matches)) => {
+                let program_name = matches.value_of("program_name").unwrap();
+                let program_arguments: Vec<_> = matches
+                    .values_of("program_arguments")
+                    .map(Iterator::collect)
+                    .unwrap_or_else(|| vec![]);
+                let name = matches.value_of("name").unwrap_or(program_name);
+                let capture_logs = matches.is_present("log");
+                let user_unit = matches.is_present("user");
+
+                service::install(
+                    config_file,
+                    name,
+                    program_name,
+                    &program_arguments,
+                    capture_logs,
+                    user_unit,
+                )
+            }
+            _ => unreachable!(),
+        },
         ("run", Some(matches)) => {
             let program_name = matches.value_of("program_name").unwrap();
             let program_arguments = matches
@@ -242,7 +439,16 @@ pub fn main() -> Result<(), String> {
                 .map(Iterator::collect)
                 .unwrap_or_else(|| vec![]);
 
-            command::run(config_file, program_name, program_arguments)
+            let max_restarts = matches.value_of("max_restarts").unwrap().parse().unwrap();
+            let capture_logs = matches.is_present("log");
+
+            command::run(
+                config_file,
+                program_name,
+                program_arguments,
+                max_restarts,
+                capture_logs,
+            )
         }
         _ => unreachable!(),
     }
@@ -317,6 +523,31 @@ pub fn main_init() -> Result<(), String> {
                 .validator(is_semver)
                 .help("The exact version to install.  Updates will not be available if this argument is used"),
         )
+        .arg(
+            Arg::with_name("channel")
+                .long("channel")
+                .value_name("CHANNEL")
+                .takes_value(true)
+                .possible_values(channel::RELEASE_CHANNELS)
+                .conflicts_with("release_semver")
+                .help("The release channel to follow: edge follows the update manifest, beta and stable follow GitHub releases"),
+        )
+        .arg(
+            Arg::with_name("version_req")
+                .long("version-req")
+                .value_name("REQ")
+                .takes_value(true)
+                .requires("channel")
+                .validator(is_version_req)
+                .help("Only install releases of the channel that meet this semver requirement, such as ~0.18"),
+        )
+        .arg(
+            Arg::with_name("cacert")
+                .long("cacert")
+                .value_name("PATH")
+                .takes_value(true)
+                .help("PEM encoded CA certificate to trust for downloads and RPC requests, besides the system's"),
+        )
         .get_matches();
 
     let config_file = matches.value_of("config_file").unwrap();
@@ -330,6 +561,11 @@ pub fn main_init() -> Result<(), String> {
     let data_dir = matches.value_of("data_dir").unwrap();
     let no_modify_path = matches.is_present("no_modify_path");
     let release_semver = matches.value_of("release_semver");
+    let channel = matches
+        .value_of("channel")
+        .map(|channel| channel.parse().unwrap());
+    let version_req = matches.value_of("version_req");
+    let cacert = matches.value_of("cacert");
 
     command::init(
         config_file,
@@ -338,5 +574,8 @@ pub fn main_init() -> Result<(), String> {
         &update_manifest_pubkey,
         no_modify_path,
         release_semver,
+        channel,
+        version_req,
+        cacert,
     )
 }
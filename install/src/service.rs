@@ -0,0 +1,193 @@
+use crate::config::Config;
+use std::fs;
+use std::path::Path;
+
+/// The command line that runs `program_name` through `solana-install run`.  The
+/// `solana-install` of the active release is used, so that it's kept up to date too.
+fn run_command_line(
+    config: &Config,
+    config_file: &Path,
+    program_name: &str,
+    program_arguments: &[&str],
+    capture_logs: bool,
+) -> Vec<String> {
+    let mut solana_install = config.active_release_bin_dir().join("solana-install");
+    if cfg!(windows) {
+        solana_install.set_extension("exe");
+    }
+
+    let mut args = vec![
+        solana_install.to_str().unwrap().to_string(),
+        "--config".to_string(),
+        config_file.to_str().unwrap().to_string(),
+        "run".to_string(),
+    ];
+    if capture_logs {
+        args.push("--log".to_string());
+    }
+    args.push(program_name.to_string());
+    if !program_arguments.is_empty() {
+        args.push("--".to_string());
+        args.extend(program_arguments.iter().map(|arg| arg.to_string()));
+    }
+    args
+}
+
+/// Quotes `arg` for an `ExecStart=` line, escaping what systemd would otherwise expand
+#[cfg(not(windows))]
+fn systemd_quote(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    if escaped.is_empty() || escaped.contains(char::is_whitespace) || escaped != arg {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+#[cfg(not(windows))]
+fn systemd_unit(name: &str, command_line: &[String], user_unit: bool) -> String {
+    let exec_start: Vec<_> = command_line.iter().map(|arg| systemd_quote(arg)).collect();
+    let user = if user_unit {
+        String::new()
+    } else {
+        // Run as whoever installed the unit, even through `sudo`
+        std::env::var("SUDO_USER")
+            .or_else(|_| std::env::var("USER"))
+            .map(|user| format!("User={}\n", user))
+            .unwrap_or_default()
+    };
+    format!(
+        "[Unit]\n\
+         Description={name}, kept up to date by solana-install\n\
+         Wants=network-online.target\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         {user}\
+         ExecStart={exec_start}\n\
+         Restart=on-failure\n\
+         RestartSec=10\n\
+         LimitNOFILE=500000\n\
+         \n\
+         [Install]\n\
+         WantedBy={wanted_by}\n",
+        name = name,
+        user = user,
+        exec_start = exec_start.join(" "),
+        wanted_by = if user_unit {
+            "default.target"
+        } else {
+            "multi-user.target"
+        },
+    )
+}
+
+#[cfg(not(windows))]
+fn systemd_unit_dir(user_unit: bool) -> Result<std::path::PathBuf, String> {
+    if user_unit {
+        dirs::config_dir()
+            .map(|dir| dir.join("systemd").join("user"))
+            .ok_or_else(|| "Unable to determine the user's config directory".to_string())
+    } else {
+        Ok("/etc/systemd/system".into())
+    }
+}
+
+/// Runs `program_name` with `solana-install run` as a service named `name`.  On Linux a systemd
+/// unit is written, a system unit or, if `user_unit`, one of the user's units.
+#[cfg(not(windows))]
+pub fn install(
+    config_file: &str,
+    name: &str,
+    program_name: &str,
+    program_arguments: &[&str],
+    capture_logs: bool,
+    user_unit: bool,
+) -> Result<(), String> {
+    let config = Config::load(config_file)?;
+    let config_file = fs::canonicalize(config_file)
+        .map_err(|err| format!("Unable to read {}: {}", config_file, err))?;
+    let command_line = run_command_line(
+        &config,
+        &config_file,
+        program_name,
+        program_arguments,
+        capture_logs,
+    );
+
+    let unit_dir = systemd_unit_dir(user_unit)?;
+    let unit_file = unit_dir.join(format!("{}.service", name));
+    fs::create_dir_all(&unit_dir)
+        .and_then(|_| fs::write(&unit_file, systemd_unit(name, &command_line, user_unit)))
+        .map_err(|err| format!("Unable to write {:?}: {}", unit_file, err))?;
+
+    let systemctl = if user_unit {
+        "systemctl --user"
+    } else {
+        "sudo systemctl"
+    };
+    println!("Wrote {:?}", unit_file);
+    println!("To start {} now and at every boot, run:", name);
+    println!("  {} daemon-reload", systemctl);
+    println!("  {} enable --now {}", systemctl, name);
+    Ok(())
+}
+
+/// Runs `program_name` with `solana-install run` as a service named `name`.  On Windows a
+/// scheduled task that starts at boot is registered, as `run` doesn't answer the service control
+/// manager the way a service registered with `sc.exe` must.
+#[cfg(windows)]
+pub fn install(
+    config_file: &str,
+    name: &str,
+    program_name: &str,
+    program_arguments: &[&str],
+    capture_logs: bool,
+    _user_unit: bool,
+) -> Result<(), String> {
+    use std::process::Command;
+
+    let config = Config::load(config_file)?;
+    let config_file = fs::canonicalize(config_file)
+        .map_err(|err| format!("Unable to read {}: {}", config_file, err))?;
+    let command_line: Vec<_> = run_command_line(
+        &config,
+        &config_file,
+        program_name,
+        program_arguments,
+        capture_logs,
+    )
+    .into_iter()
+    .map(|arg| {
+        if arg.contains(char::is_whitespace) {
+            format!("\"{}\"", arg)
+        } else {
+            arg
+        }
+    })
+    .collect();
+
+    let status = Command::new("schtasks.exe")
+        .args(&[
+            "/Create", "/F", "/SC", "ONSTART", "/RU", "SYSTEM", "/TN", name,
+        ])
+        .arg("/TR")
+        .arg(command_line.join(" "))
+        .status()
+        .map_err(|err| format!("Unable to run schtasks.exe: {}", err))?;
+    if !status.success() {
+        Err(format!(
+            "Unable to register {}: schtasks.exe {}",
+            name, status
+        ))?;
+    }
+    println!("Registered {}, which starts at every boot", name);
+    println!("To start it now, run:");
+    println!("  schtasks.exe /Run /TN {}", name);
+    Ok(())
+}
@@ -1,11 +1,23 @@
 use clap::{
-    crate_description, crate_name, crate_version, App, AppSettings, Arg, ArgMatches, SubCommand,
+    crate_description, crate_name, crate_version, value_t_or_exit, values_t, App, AppSettings, Arg,
+    ArgGroup, ArgMatches, SubCommand,
 };
 use solana_sdk::pubkey::write_pubkey;
-use solana_sdk::signature::{gen_keypair_file, read_keypair, KeypairUtil};
+use solana_sdk::signature::{gen_keypair_file, read_keypair, write_keypair, Keypair, KeypairUtil};
 use std::error;
 use std::path::Path;
 use std::process::exit;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, sleep};
+use std::time::{Duration, Instant};
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// The longest base58 encoding of a pubkey
+const MAX_PATTERN_LEN: usize = 44;
+
+const GRIND_PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
 
 fn check_for_overwrite(outfile: &str, matches: &ArgMatches) {
     let force = matches.is_present("force");
@@ -15,6 +27,189 @@ fn check_for_overwrite(outfile: &str, matches: &ArgMatches) {
     }
 }
 
+fn is_base58_pattern(pattern: String) -> Result<(), String> {
+    if pattern.len() > MAX_PATTERN_LEN {
+        return Err(format!(
+            "{} is longer than a pubkey, {} characters",
+            pattern, MAX_PATTERN_LEN
+        ));
+    }
+    match pattern.chars().find(|c| !BASE58_ALPHABET.contains(*c)) {
+        Some(c) => Err(format!("{} contains {:?}, which is not base58", pattern, c)),
+        None => Ok(()),
+    }
+}
+
+fn is_positive_integer(value: String) -> Result<(), String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be greater than zero".to_string()),
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// The pubkeys a `grind` is searching for
+struct GrindPattern {
+    starts_with: Vec<String>,
+    ends_with: Vec<String>,
+    ignore_case: bool,
+}
+
+impl GrindPattern {
+    fn new(starts_with: Vec<String>, ends_with: Vec<String>, ignore_case: bool) -> Self {
+        let normalize = |patterns: Vec<String>| {
+            patterns
+                .into_iter()
+                .map(|pattern| {
+                    if ignore_case {
+                        pattern.to_lowercase()
+                    } else {
+                        pattern
+                    }
+                })
+                .collect()
+        };
+        Self {
+            starts_with: normalize(starts_with),
+            ends_with: normalize(ends_with),
+            ignore_case,
+        }
+    }
+
+    /// Whether `pubkey` starts with any of the prefixes and ends with any of the suffixes
+    fn is_match(&self, pubkey: &str) -> bool {
+        let pubkey = if self.ignore_case {
+            pubkey.to_lowercase()
+        } else {
+            pubkey.to_string()
+        };
+        (self.starts_with.is_empty()
+            || self
+                .starts_with
+                .iter()
+                .any(|prefix| pubkey.starts_with(prefix)))
+            && (self.ends_with.is_empty()
+                || self.ends_with.iter().any(|suffix| pubkey.ends_with(suffix)))
+    }
+
+    /// The chance of matching a single character of a pattern
+    fn char_probability(&self, c: char) -> f64 {
+        let matches = if self.ignore_case {
+            BASE58_ALPHABET
+                .chars()
+                .filter(|a| a.to_lowercase().eq(c.to_lowercase()))
+                .count()
+        } else {
+            1
+        };
+        matches as f64 / BASE58_ALPHABET.len() as f64
+    }
+
+    fn any_probability(&self, patterns: &[String]) -> f64 {
+        if patterns.is_empty() {
+            return 1.0;
+        }
+        let probability: f64 = patterns
+            .iter()
+            .map(|pattern| {
+                pattern
+                    .chars()
+                    .map(|c| self.char_probability(c))
+                    .product::<f64>()
+            })
+            .sum();
+        probability.min(1.0)
+    }
+
+    /// Roughly how many keypairs are generated for each match. Base58 digits of a pubkey aren't
+    /// quite uniformly distributed, the leading one in particular, so this is only an estimate.
+    fn expected_attempts(&self) -> f64 {
+        1.0 / (self.any_probability(&self.starts_with) * self.any_probability(&self.ends_with))
+    }
+}
+
+/// Generates keypairs on `num_threads` threads until `count` of them match `pattern`, writing
+/// each match to `<outdir>/<PUBKEY>.json`
+fn grind(
+    pattern: GrindPattern,
+    count: usize,
+    num_threads: usize,
+    outdir: &str,
+) -> Result<(), Box<dyn error::Error>> {
+    let expected_attempts = pattern.expected_attempts();
+    println!(
+        "Searching with {} threads for {} keypair(s), about {:.0} keypairs apart",
+        num_threads, count, expected_attempts
+    );
+
+    let pattern = Arc::new(pattern);
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let found = Arc::new(AtomicUsize::new(0));
+    let done = Arc::new(AtomicBool::new(false));
+    let threads: Vec<_> = (0..num_threads)
+        .map(|_| {
+            let pattern = pattern.clone();
+            let attempts = attempts.clone();
+            let found = found.clone();
+            let done = done.clone();
+            let outdir = outdir.to_string();
+            thread::spawn(move || -> Result<(), String> {
+                while !done.load(Ordering::Relaxed) {
+                    let keypair = Keypair::new();
+                    attempts.fetch_add(1, Ordering::Relaxed);
+                    let pubkey = keypair.pubkey().to_string();
+                    if !pattern.is_match(&pubkey) {
+                        continue;
+                    }
+                    // Other threads may find a match at the same time
+                    let found_before = found.fetch_add(1, Ordering::Relaxed);
+                    if found_before >= count {
+                        break;
+                    }
+                    if found_before + 1 == count {
+                        done.store(true, Ordering::Relaxed);
+                    }
+                    let outfile = Path::new(&outdir).join(format!("{}.json", pubkey));
+                    let outfile = outfile.to_str().unwrap();
+                    if let Err(err) = write_keypair(&keypair, outfile) {
+                        done.store(true, Ordering::Relaxed);
+                        return Err(err.to_string());
+                    }
+                    println!("Wrote {}", outfile);
+                }
+                Ok(())
+            })
+        })
+        .collect();
+
+    let start = Instant::now();
+    let mut last_progress = start;
+    while !done.load(Ordering::Relaxed) {
+        sleep(Duration::from_millis(100));
+        if last_progress.elapsed() < GRIND_PROGRESS_INTERVAL {
+            continue;
+        }
+        last_progress = Instant::now();
+        let elapsed = start.elapsed().as_millis() as f64 / 1000.0;
+        let attempts = attempts.load(Ordering::Relaxed);
+        let rate = attempts as f64 / elapsed;
+        eprintln!(
+            "Searched {} keypairs in {:.0}s ({:.0}/s), found {} of {}, expect a match every {:.0}s",
+            attempts,
+            elapsed,
+            rate,
+            found.load(Ordering::Relaxed).min(count),
+            count,
+            expected_attempts / rate,
+        );
+    }
+
+    for thread in threads {
+        thread.join().unwrap()?;
+    }
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn error::Error>> {
     let matches = App::new(crate_name!())
         .about(crate_description!())
@@ -65,6 +260,66 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                         .help("Overwrite the output file if it exists"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("grind")
+                .about("Grind for vanity keypairs, whose pubkeys start or end with a pattern")
+                .setting(AppSettings::DisableVersion)
+                .arg(
+                    Arg::with_name("starts_with")
+                        .long("starts-with")
+                        .value_name("PREFIX")
+                        .takes_value(true)
+                        .multiple(true)
+                        .validator(is_base58_pattern)
+                        .help("Find pubkeys that start with any of these prefixes"),
+                )
+                .arg(
+                    Arg::with_name("ends_with")
+                        .long("ends-with")
+                        .value_name("SUFFIX")
+                        .takes_value(true)
+                        .multiple(true)
+                        .validator(is_base58_pattern)
+                        .help("Find pubkeys that end with any of these suffixes"),
+                )
+                .group(
+                    ArgGroup::with_name("pattern")
+                        .args(&["starts_with", "ends_with"])
+                        .multiple(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("ignore_case")
+                        .long("ignore-case")
+                        .help("Match the prefixes and suffixes case-insensitively"),
+                )
+                .arg(
+                    Arg::with_name("count")
+                        .short("n")
+                        .long("count")
+                        .value_name("NUMBER")
+                        .takes_value(true)
+                        .default_value("1")
+                        .validator(is_positive_integer)
+                        .help("Number of matching keypairs to find"),
+                )
+                .arg(
+                    Arg::with_name("num_threads")
+                        .long("num-threads")
+                        .value_name("NUMBER")
+                        .takes_value(true)
+                        .validator(is_positive_integer)
+                        .help("Number of threads to grind with [default: one per CPU]"),
+                )
+                .arg(
+                    Arg::with_name("outdir")
+                        .long("outdir")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .default_value(".")
+                        .help("Directory to write the <PUBKEY>.json keypair files to"),
+                ),
+        )
         .get_matches();
 
     match matches.subcommand() {
@@ -105,8 +360,55 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 println!("Wrote {}", outfile);
             }
         }
+        ("grind", Some(matches)) => {
+            let starts_with = values_t!(matches, "starts_with", String).unwrap_or_default();
+            let ends_with = values_t!(matches, "ends_with", String).unwrap_or_default();
+            let pattern =
+                GrindPattern::new(starts_with, ends_with, matches.is_present("ignore_case"));
+            let count = value_t_or_exit!(matches, "count", usize);
+            let num_threads = if matches.is_present("num_threads") {
+                value_t_or_exit!(matches, "num_threads", usize)
+            } else {
+                sys_info::cpu_num().unwrap_or(1) as usize
+            };
+            let outdir = matches.value_of("outdir").unwrap();
+            grind(pattern, count, num_threads, outdir)?;
+        }
         _ => unreachable!(),
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_base58_pattern() {
+        assert!(is_base58_pattern("Sol".to_string()).is_ok());
+        assert!(is_base58_pattern("S0l".to_string()).is_err());
+        assert!(is_base58_pattern("1".repeat(MAX_PATTERN_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_grind_pattern() {
+        let pattern = GrindPattern::new(
+            vec!["AB".to_string(), "C".to_string()],
+            vec!["z".to_string()],
+            false,
+        );
+        assert!(pattern.is_match("ABxz"));
+        assert!(pattern.is_match("Cxz"));
+        assert!(!pattern.is_match("abxz"));
+        assert!(!pattern.is_match("ABxy"));
+        let expected_attempts = 1.0 / ((1.0 / (58.0 * 58.0) + 1.0 / 58.0) / 58.0);
+        assert!((pattern.expected_attempts() - expected_attempts).abs() < 1e-6);
+
+        // 'o' has no uppercase in base58, 'a' does
+        let pattern = GrindPattern::new(vec!["Ao".to_string()], vec![], true);
+        assert!(pattern.is_match("aOx"));
+        assert!(!pattern.is_match("bOx"));
+        assert!((pattern.expected_attempts() - 58.0 * 58.0 / 2.0).abs() < 1e-6);
+    }
+}
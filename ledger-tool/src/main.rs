@@ -1,12 +1,19 @@
 use clap::{crate_description, crate_name, crate_version, value_t, App, Arg, SubCommand};
-use solana::blocktree::Blocktree;
-use solana::blocktree_processor::process_blocktree;
+use serde_derive::Serialize;
+use solana::blocktree::{Blocktree, SlotMeta};
+use solana::blocktree_processor::{process_blocktree, process_entries};
+use solana::leader_schedule_cache::LeaderScheduleCache;
+use solana_runtime::bank::Bank;
+use solana_sdk::account::Account;
 use solana_sdk::genesis_block::GenesisBlock;
-use std::collections::BTreeMap;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::{self, Transaction};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
-use std::io::{stdout, Write};
+use std::io::{stdin, stdout, Write};
 use std::process::exit;
 use std::str::FromStr;
+use std::sync::Arc;
 
 #[derive(PartialEq)]
 enum LedgerOutputMethod {
@@ -60,6 +67,283 @@ fn output_ledger(blocktree: Blocktree, starting_slot: u64, method: LedgerOutputM
     }
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedInstruction {
+    /// Program invoked, as a base-58 string
+    program_id: String,
+    /// Accounts passed to the program, as base-58 strings
+    accounts: Vec<String>,
+    data: Vec<u8>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedTransaction {
+    signatures: Vec<String>,
+    /// The result of processing the transaction, or None if the bank didn't record one
+    status: Option<transaction::Result<()>>,
+    num_required_signatures: u8,
+    account_keys: Vec<String>,
+    recent_blockhash: String,
+    instructions: Vec<ExportedInstruction>,
+}
+
+impl ExportedTransaction {
+    fn new(tx: &Transaction, status: Option<transaction::Result<()>>) -> Self {
+        let message = tx.message();
+        Self {
+            signatures: tx.signatures.iter().map(ToString::to_string).collect(),
+            status,
+            num_required_signatures: message.header.num_required_signatures,
+            account_keys: message
+                .account_keys
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            recent_blockhash: message.recent_blockhash.to_string(),
+            instructions: message
+                .instructions
+                .iter()
+                .map(|ix| ExportedInstruction {
+                    program_id: ix.program_id(&message.account_keys).to_string(),
+                    accounts: ix
+                        .accounts
+                        .iter()
+                        .map(|i| message.account_keys[*i as usize].to_string())
+                        .collect(),
+                    data: ix.data.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedAccount {
+    pubkey: String,
+    lamports: u64,
+    owner: String,
+    executable: bool,
+    data: Vec<u8>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedSlot {
+    slot: u64,
+    parent_slot: u64,
+    blockhash: String,
+    bank_hash: String,
+    transactions: Vec<ExportedTransaction>,
+    /// The state after the slot of every account its transactions changed, if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    accounts: Option<Vec<ExportedAccount>>,
+}
+
+/// Replays the rooted slots from genesis, printing each rooted slot from `starting_slot` through
+/// `ending_slot` as a line of JSON with its decoded transactions and their statuses, and if
+/// `include_accounts`, the accounts its transactions changed
+fn export_ledger(
+    genesis_block: &GenesisBlock,
+    blocktree: &Blocktree,
+    starting_slot: u64,
+    ending_slot: u64,
+    include_accounts: bool,
+) -> Result<(), String> {
+    let rooted_slot_iterator = blocktree
+        .rooted_slot_iterator(0)
+        .map_err(|err| format!("Failed to load rooted slots: {:?}", err))?;
+    let bank0 = Arc::new(Bank::new(genesis_block));
+    let leader_schedule_cache = LeaderScheduleCache::new(*bank0.epoch_schedule(), &bank0);
+
+    let mut parent: Option<Arc<Bank>> = None;
+    for (slot, slot_meta) in rooted_slot_iterator {
+        if slot > ending_slot {
+            break;
+        }
+        let bank = match parent {
+            None => bank0.clone(),
+            Some(parent) => {
+                let leader = leader_schedule_cache
+                    .slot_leader_at(slot, Some(&parent))
+                    .ok_or_else(|| format!("No leader for slot {}", slot))?;
+                Arc::new(Bank::new_from_parent(&parent, &leader, slot))
+            }
+        };
+
+        let mut entries = blocktree
+            .get_slot_entries(slot, 0, None)
+            .map_err(|err| format!("Failed to load entries for slot {}: {:?}", slot, err))?;
+        if slot == 0 && !entries.is_empty() {
+            // The pseudo-tick that starts the ledger isn't processed by the bank
+            entries.remove(0);
+        }
+        let transactions: Vec<_> = entries
+            .iter()
+            .flat_map(|entry| entry.transactions.iter())
+            .collect();
+
+        let export = slot >= starting_slot;
+        let pre_accounts: Vec<(Pubkey, Option<Account>)> = if export && include_accounts {
+            let pubkeys: BTreeSet<_> = transactions
+                .iter()
+                .flat_map(|tx| tx.message().account_keys.iter())
+                .collect();
+            pubkeys
+                .into_iter()
+                .map(|pubkey| (*pubkey, bank.get_account(pubkey)))
+                .collect()
+        } else {
+            vec![]
+        };
+
+        process_entries(&bank, &entries)
+            .map_err(|err| format!("Failed to process slot {}: {:?}", slot, err))?;
+        bank.freeze();
+        leader_schedule_cache.set_root(&bank);
+        bank.squash();
+
+        if export {
+            let accounts = if include_accounts {
+                Some(
+                    pre_accounts
+                        .into_iter()
+                        .filter_map(|(pubkey, pre_account)| {
+                            let account = bank.get_account(&pubkey).unwrap_or_default();
+                            if pre_account.unwrap_or_default() == account {
+                                return None;
+                            }
+                            Some(ExportedAccount {
+                                pubkey: pubkey.to_string(),
+                                lamports: account.lamports,
+                                owner: account.owner.to_string(),
+                                executable: account.executable,
+                                data: account.data,
+                            })
+                        })
+                        .collect(),
+                )
+            } else {
+                None
+            };
+            let exported_slot = ExportedSlot {
+                slot,
+                parent_slot: slot_meta.parent_slot,
+                blockhash: bank.last_blockhash().to_string(),
+                bank_hash: bank.hash().to_string(),
+                transactions: transactions
+                    .iter()
+                    .map(|tx| {
+                        ExportedTransaction::new(tx, bank.get_signature_status(&tx.signatures[0]))
+                    })
+                    .collect(),
+                accounts,
+            };
+            serde_json::to_writer(stdout(), &exported_slot).expect("serialize slot");
+            stdout().write_all(b"\n").expect("newline");
+        }
+        parent = Some(bank);
+    }
+    Ok(())
+}
+
+/// Makes the user type `expected` back before modifying the ledger
+fn confirm(warning: &str, expected: &str) -> bool {
+    eprintln!("WARNING: {}", warning);
+    eprintln!("Stop the validator and back up the ledger first, this can't be undone.");
+    eprint!("Type `{}` to continue: ", expected);
+    let mut input = String::new();
+    stdin().read_line(&mut input).is_ok() && input.trim() == expected
+}
+
+/// Most missing blob indexes listed for a slot by `repair-analysis`
+const MAX_LISTED_MISSING_BLOBS: usize = 100;
+
+/// What keeps `meta`'s slot from being replayed, if anything
+fn slot_problem(blocktree: &Blocktree, meta: &SlotMeta) -> Option<String> {
+    if blocktree.is_dead(meta.slot) {
+        return Some("dead".to_string());
+    }
+    if !meta.is_full() {
+        // Without the last blob, only the blobs up to the highest one received are known missing
+        let (expected, end_index) = if meta.last_index == std::u64::MAX {
+            ("?".to_string(), meta.received)
+        } else {
+            ((meta.last_index + 1).to_string(), meta.last_index + 1)
+        };
+        let missing = blocktree.find_missing_data_indexes(
+            meta.slot,
+            meta.consumed,
+            end_index,
+            MAX_LISTED_MISSING_BLOBS,
+        );
+        return Some(format!(
+            "incomplete, {} of {} blobs received, missing indexes {:?}{}",
+            meta.consumed,
+            expected,
+            missing,
+            if meta.is_parent_set() { "" } else { ", orphan" },
+        ));
+    }
+    if !meta.is_connected {
+        return Some("full, but an ancestor is incomplete".to_string());
+    }
+    None
+}
+
+/// A DOT graph of the slots from `starting_slot` and the forks between them. Roots are filled,
+/// dead slots red, and slots that can't be replayed yet dashed.
+fn slot_graph(blocktree: &Blocktree, starting_slot: u64) -> Result<String, String> {
+    let slot_metas = blocktree
+        .slot_meta_iterator(starting_slot)
+        .map_err(|err| format!("Failed to load slots: {:?}", err))?;
+
+    let mut dot = vec!["digraph Slots {".to_string()];
+    for (slot, meta) in slot_metas {
+        let mut attributes = vec![format!("label=\"{}\"", slot)];
+        if blocktree.is_root(slot) {
+            attributes.push("style=filled".to_string());
+        } else if blocktree.is_dead(slot) {
+            attributes.push("color=red".to_string());
+        } else if !meta.is_full() || !meta.is_connected {
+            attributes.push("style=dashed".to_string());
+        }
+        dot.push(format!("  {} [{}];", slot, attributes.join(",")));
+        for next_slot in &meta.next_slots {
+            dot.push(format!("  {} -> {};", slot, next_slot));
+        }
+    }
+    dot.push("}".to_string());
+    Ok(dot.join("\n"))
+}
+
+/// The slots to root so that `slot` is a root: `slot` and its ancestors back to the closest
+/// existing root, which must all be complete and not dead
+fn slots_to_root(blocktree: &Blocktree, slot: u64) -> Result<Vec<u64>, String> {
+    let mut slots = vec![];
+    let mut slot = slot;
+    while !blocktree.is_root(slot) {
+        let meta = blocktree
+            .meta(slot)
+            .map_err(|err| format!("Failed to load slot {}: {:?}", slot, err))?
+            .ok_or_else(|| format!("Slot {} is not in the ledger", slot))?;
+        if !meta.is_full() {
+            Err(format!("Slot {} is incomplete", slot))?;
+        }
+        if blocktree.is_dead(slot) {
+            Err(format!("Slot {} is dead", slot))?;
+        }
+        if slot == 0 || !meta.is_parent_set() {
+            Err(format!("Slot {} doesn't descend from a root", slot))?;
+        }
+        slots.push(slot);
+        slot = meta.parent_slot;
+    }
+    Ok(slots)
+}
+
 fn main() {
     const DEFAULT_ROOT_COUNT: &str = "1";
     solana_logger::setup();
@@ -81,10 +365,21 @@ fn main() {
                 .value_name("NUM")
                 .takes_value(true)
                 .default_value("0")
-                .help("Start at this slot (only applies to print and json commands)"),
+                .help("Start at this slot (only applies to print, json, export and list commands)"),
         )
         .subcommand(SubCommand::with_name("print").about("Print the ledger"))
         .subcommand(SubCommand::with_name("json").about("Print the ledger in JSON format"))
+        .subcommand(SubCommand::with_name("export").about("Replay the rooted slots and print each one from --starting-slot as a line of JSON, with its transactions and their statuses").arg(
+            Arg::with_name("ending_slot")
+                .long("ending-slot")
+                .value_name("SLOT")
+                .takes_value(true)
+                .help("Stop after this slot"),
+        ).arg(
+            Arg::with_name("accounts")
+                .long("accounts")
+                .help("Include the accounts each slot's transactions changed"),
+        ))
         .subcommand(SubCommand::with_name("verify").about("Verify the ledger's PoH"))
         .subcommand(SubCommand::with_name("prune").about("Prune the ledger at the block height").arg(
             Arg::with_name("slot_list")
@@ -117,6 +412,25 @@ fn main() {
                 .required(false)
                 .help("Number of roots in the output"),
         ))
+        .subcommand(SubCommand::with_name("list-dead-slots").about("Output the slots that failed to replay, starting at --starting-slot"))
+        .subcommand(SubCommand::with_name("unmark-dead-slot").about("Let a dead slot be replayed again").arg(
+            Arg::with_name("slot")
+                .index(1)
+                .value_name("SLOT")
+                .required(true)
+                .help("The dead slot"),
+        ))
+        .subcommand(SubCommand::with_name("bounds").about("Print the lowest and highest slots in the ledger, and the latest root"))
+        .subcommand(SubCommand::with_name("repair-analysis").about("Output the slots from --starting-slot that can't be replayed, with the blobs they're missing"))
+        .subcommand(SubCommand::with_name("graph").about("Output a DOT graph of the slots from --starting-slot and their forks"))
+        .subcommand(SubCommand::with_name("list-duplicate-slots").about("Output the slots their leader sent conflicting blobs for, starting at --starting-slot"))
+        .subcommand(SubCommand::with_name("set-root").about("Root a slot, and its ancestors back to the latest root, abandoning the forks that don't descend from it").arg(
+            Arg::with_name("slot")
+                .index(1)
+                .value_name("SLOT")
+                .required(true)
+                .help("The slot to root"),
+        ))
         .get_matches();
 
     let ledger_path = matches.value_of("ledger").unwrap();
@@ -146,9 +460,27 @@ fn main() {
         ("json", _) => {
             output_ledger(blocktree, starting_slot, LedgerOutputMethod::Json);
         }
+        ("export", Some(args_matches)) => {
+            let ending_slot = if args_matches.is_present("ending_slot") {
+                value_t!(args_matches, "ending_slot", u64).unwrap_or_else(|e| e.exit())
+            } else {
+                std::u64::MAX
+            };
+            export_ledger(
+                &genesis_block,
+                &blocktree,
+                starting_slot,
+                ending_slot,
+                args_matches.is_present("accounts"),
+            )
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to export the ledger: {}", err);
+                exit(1);
+            });
+        }
         ("verify", _) => {
             println!("Verifying ledger...");
-            match process_blocktree(&genesis_block, &blocktree, None, true) {
+            match process_blocktree(&genesis_block, &blocktree, None, true, None) {
                 Ok((_bank_forks, bank_forks_info, _)) => {
                     println!("{:?}", bank_forks_info);
                 }
@@ -249,6 +581,130 @@ fn main() {
                     }
                 });
         }
+        ("list-dead-slots", _) => {
+            let dead_slots = blocktree
+                .dead_slots_iterator(starting_slot)
+                .unwrap_or_else(|err| {
+                    eprintln!("Failed to load dead slots: {:?}", err);
+                    exit(1);
+                });
+            for slot in dead_slots {
+                println!("{}", slot);
+            }
+        }
+        ("unmark-dead-slot", Some(args_matches)) => {
+            let slot = value_t!(args_matches, "slot", u64).unwrap_or_else(|e| e.exit());
+            if !blocktree.is_dead(slot) {
+                eprintln!("Slot {} is not dead", slot);
+                exit(1);
+            }
+            if !confirm(
+                &format!(
+                    "Slot {} will be replayed again the next time the validator starts",
+                    slot
+                ),
+                &format!("unmark-dead-slot {}", slot),
+            ) {
+                eprintln!("Aborted");
+                exit(1);
+            }
+            blocktree.remove_dead_slot(slot).unwrap_or_else(|err| {
+                eprintln!("Failed to unmark slot {}: {:?}", slot, err);
+                exit(1);
+            });
+            println!("Slot {} is no longer dead", slot);
+        }
+        ("bounds", _) => {
+            let slot_metas = blocktree.slot_meta_iterator(0).unwrap_or_else(|err| {
+                eprintln!("Failed to load slots: {:?}", err);
+                exit(1);
+            });
+            let mut num_slots = 0;
+            let mut highest_slot = None;
+            let mut latest_root = None;
+            for (slot, _) in slot_metas {
+                num_slots += 1;
+                highest_slot = Some(slot);
+                if blocktree.is_root(slot) {
+                    latest_root = Some(slot);
+                }
+            }
+            match highest_slot {
+                Some(highest_slot) => {
+                    println!(
+                        "Ledger has data for {} slots {} to {}",
+                        num_slots,
+                        blocktree.lowest_slot(),
+                        highest_slot
+                    );
+                    match latest_root {
+                        Some(root) => println!("Latest root: {}", root),
+                        None => println!("No roots"),
+                    }
+                }
+                None => println!("Ledger is empty"),
+            }
+        }
+        ("repair-analysis", _) => {
+            let slot_metas = blocktree
+                .slot_meta_iterator(starting_slot)
+                .unwrap_or_else(|err| {
+                    eprintln!("Failed to load slots: {:?}", err);
+                    exit(1);
+                });
+            for (slot, meta) in slot_metas {
+                if let Some(problem) = slot_problem(&blocktree, &meta) {
+                    println!("{}: {}", slot, problem);
+                }
+            }
+        }
+        ("graph", _) => match slot_graph(&blocktree, starting_slot) {
+            Ok(dot) => println!("{}", dot),
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(1);
+            }
+        },
+        ("list-duplicate-slots", _) => {
+            let duplicate_slots = blocktree
+                .duplicate_slots_iterator(starting_slot)
+                .unwrap_or_else(|err| {
+                    eprintln!("Failed to load duplicate slots: {:?}", err);
+                    exit(1);
+                });
+            for slot in duplicate_slots {
+                match blocktree.get_duplicate_slot(slot) {
+                    Some(proof) => println!("{}: conflicting blobs at index {}", slot, proof.index),
+                    None => println!("{}", slot),
+                }
+            }
+        }
+        ("set-root", Some(args_matches)) => {
+            let slot = value_t!(args_matches, "slot", u64).unwrap_or_else(|e| e.exit());
+            let slots = slots_to_root(&blocktree, slot).unwrap_or_else(|err| {
+                eprintln!("Unable to root slot {}: {}", slot, err);
+                exit(1);
+            });
+            if slots.is_empty() {
+                println!("Slot {} is already a root", slot);
+                return;
+            }
+            if !confirm(
+                &format!(
+                    "Slots {:?} will be rooted, and the forks that don't descend from slot {} abandoned",
+                    slots, slot
+                ),
+                &format!("set-root {}", slot),
+            ) {
+                eprintln!("Aborted");
+                exit(1);
+            }
+            blocktree.set_roots(&slots).unwrap_or_else(|err| {
+                eprintln!("Failed to root slot {}: {:?}", slot, err);
+                exit(1);
+            });
+            println!("Slot {} is now a root", slot);
+        }
         ("", _) => {
             eprintln!("{}", matches.usage());
             exit(1);
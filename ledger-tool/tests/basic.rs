@@ -2,10 +2,15 @@
 extern crate solana;
 
 use assert_cmd::prelude::*;
-use solana::blocktree::create_new_tmp_ledger;
+use solana::blocktree::{create_new_tmp_ledger, Blocktree};
+use solana::entry::{create_ticks, next_entry};
 use solana::genesis_utils::create_genesis_block;
+use solana_sdk::signature::{Keypair, KeypairUtil};
+use solana_sdk::system_transaction;
+use std::io::Write;
 use std::process::Command;
 use std::process::Output;
+use std::process::Stdio;
 
 fn run_ledger_tool(args: &[&str]) -> Output {
     Command::cargo_bin(env!("CARGO_PKG_NAME"))
@@ -15,6 +20,24 @@ fn run_ledger_tool(args: &[&str]) -> Output {
         .unwrap()
 }
 
+fn run_ledger_tool_with_input(args: &[&str], input: &str) -> Output {
+    let mut child = Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
 fn count_newlines(chars: &[u8]) -> usize {
     chars.iter().filter(|&c| *c == '\n' as u8).count()
 }
@@ -47,3 +70,153 @@ fn nominal() {
     assert!(output.status.success());
     assert_eq!(count_newlines(&output.stdout), ticks + 1);
 }
+
+#[test]
+fn fork_surgery() {
+    let genesis_block = create_genesis_block(100).genesis_block;
+    let ticks_per_slot = genesis_block.ticks_per_slot;
+
+    let (ledger_path, blockhash) = create_new_tmp_ledger!(&genesis_block);
+    {
+        // Slots 1 and 2, with 2 dead
+        let blocktree = Blocktree::open(&ledger_path).unwrap();
+        let ticks = create_ticks(ticks_per_slot * 2, blockhash);
+        blocktree
+            .write_entries(1, 0, 0, ticks_per_slot, &ticks)
+            .unwrap();
+        blocktree.set_dead_slot(2).unwrap();
+    }
+
+    let output = run_ledger_tool(&["-l", &ledger_path, "list-dead-slots"]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"2\n");
+
+    // A dead slot can't be rooted
+    let output = run_ledger_tool_with_input(&["-l", &ledger_path, "set-root", "2"], "set-root 2\n");
+    assert!(!output.status.success());
+
+    // Nothing changes without the exact confirmation
+    let output = run_ledger_tool_with_input(&["-l", &ledger_path, "unmark-dead-slot", "2"], "y\n");
+    assert!(!output.status.success());
+    let output = run_ledger_tool(&["-l", &ledger_path, "list-dead-slots"]);
+    assert_eq!(output.stdout, b"2\n");
+
+    let output = run_ledger_tool_with_input(
+        &["-l", &ledger_path, "unmark-dead-slot", "2"],
+        "unmark-dead-slot 2\n",
+    );
+    assert!(output.status.success());
+    let output = run_ledger_tool(&["-l", &ledger_path, "list-dead-slots"]);
+    assert!(output.stdout.is_empty());
+
+    let output = run_ledger_tool_with_input(&["-l", &ledger_path, "set-root", "2"], "set-root 2\n");
+    assert!(output.status.success());
+    let blocktree = Blocktree::open(&ledger_path).unwrap();
+    assert!(blocktree.is_root(1));
+    assert!(blocktree.is_root(2));
+}
+
+#[test]
+fn export() {
+    let genesis_info = create_genesis_block(100);
+    let genesis_block = genesis_info.genesis_block;
+    let mint_keypair = genesis_info.mint_keypair;
+    let ticks_per_slot = genesis_block.ticks_per_slot;
+
+    let (ledger_path, blockhash) = create_new_tmp_ledger!(&genesis_block);
+    let to = Keypair::new().pubkey();
+    {
+        // Slot 1 transfers from the mint
+        let blocktree = Blocktree::open(&ledger_path).unwrap();
+        let tx = system_transaction::transfer(&mint_keypair, &to, 1, blockhash);
+        let entry = next_entry(&blockhash, 1, vec![tx]);
+        let mut entries = create_ticks(ticks_per_slot, entry.hash);
+        entries.insert(0, entry);
+        blocktree
+            .write_entries(1, 0, 0, ticks_per_slot, &entries)
+            .unwrap();
+        blocktree.set_roots(&[1]).unwrap();
+    }
+
+    let output = run_ledger_tool(&["-l", &ledger_path, "export"]);
+    assert!(output.status.success());
+    assert_eq!(count_newlines(&output.stdout), 2);
+
+    let output = run_ledger_tool(&[
+        "-l",
+        &ledger_path,
+        "--starting-slot",
+        "1",
+        "export",
+        "--accounts",
+    ]);
+    assert!(output.status.success());
+    let slot: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(slot["slot"], 1);
+    assert_eq!(slot["parentSlot"], 0);
+    let transactions = slot["transactions"].as_array().unwrap();
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0]["status"], serde_json::json!({ "Ok": null }));
+    let accounts = slot["accounts"].as_array().unwrap();
+    assert!(accounts
+        .iter()
+        .any(|account| account["pubkey"] == to.to_string() && account["lamports"] == 1));
+    assert!(accounts
+        .iter()
+        .any(|account| account["pubkey"] == mint_keypair.pubkey().to_string()));
+
+    // Nothing past the ending slot
+    let output = run_ledger_tool(&["-l", &ledger_path, "export", "--ending-slot", "0"]);
+    assert!(output.status.success());
+    assert_eq!(count_newlines(&output.stdout), 1);
+}
+
+#[test]
+fn diagnostics() {
+    let genesis_block = create_genesis_block(100).genesis_block;
+    let ticks_per_slot = genesis_block.ticks_per_slot;
+
+    let (ledger_path, blockhash) = create_new_tmp_ledger!(&genesis_block);
+    {
+        // Slots 1 and 2, with 2 dead, and slot 3 missing its first two blobs
+        let blocktree = Blocktree::open(&ledger_path).unwrap();
+        blocktree.set_roots(&[0]).unwrap();
+        let ticks = create_ticks(ticks_per_slot * 2, blockhash);
+        blocktree
+            .write_entries(1, 0, 0, ticks_per_slot, &ticks)
+            .unwrap();
+        blocktree.set_dead_slot(2).unwrap();
+        let ticks = create_ticks(ticks_per_slot, ticks.last().unwrap().hash);
+        blocktree
+            .write_entries(3, 0, 2, ticks_per_slot, &ticks)
+            .unwrap();
+    }
+
+    let output = run_ledger_tool(&["-l", &ledger_path, "bounds"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Ledger has data for 4 slots 0 to 3\nLatest root: 0\n"
+    );
+
+    let output = run_ledger_tool(&["-l", &ledger_path, "repair-analysis"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "2: dead\n3: incomplete, 0 of {} blobs received, missing indexes [0, 1]\n",
+            ticks_per_slot + 2
+        )
+    );
+
+    let output = run_ledger_tool(&["-l", &ledger_path, "graph"]);
+    assert!(output.status.success());
+    let dot = String::from_utf8(output.stdout).unwrap();
+    assert!(dot.starts_with("digraph Slots {"));
+    assert!(dot.contains("0 [label=\"0\",style=filled];"));
+    assert!(dot.contains("2 [label=\"2\",color=red];"));
+    assert!(dot.contains("3 [label=\"3\",style=dashed];"));
+    for edge in &["0 -> 1;", "1 -> 2;", "2 -> 3;"] {
+        assert!(dot.contains(edge));
+    }
+}
@@ -1,8 +1,10 @@
-use crate::{influxdb, submit};
+use crate::influxdb;
+use crate::metrics::submit_counter;
 use log::*;
 use solana_sdk::timing;
 use std::env;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 const DEFAULT_LOG_RATE: usize = 1000;
 const DEFAULT_METRICS_RATE: usize = 1;
@@ -17,6 +19,8 @@ pub struct Counter {
     pub lograte: AtomicUsize,
     pub metricsrate: AtomicUsize,
     pub point: Option<influxdb::Point>,
+    /// The counter's series in the Prometheus registry, once the endpoint is started
+    pub prometheus_count: Option<Arc<AtomicUsize>>,
 }
 
 #[macro_export]
@@ -30,6 +34,7 @@ macro_rules! create_counter {
             lograte: std::sync::atomic::AtomicUsize::new($lograte),
             metricsrate: std::sync::atomic::AtomicUsize::new($metricsrate),
             point: None,
+            prometheus_count: None,
         }
     };
 }
@@ -144,6 +149,12 @@ impl Counter {
         );
     }
     pub fn inc(&mut self, level: log::Level, events: usize) {
+        if self.prometheus_count.is_none() {
+            self.prometheus_count = crate::prometheus::register_counter(self.name);
+        }
+        if let Some(prometheus_count) = &self.prometheus_count {
+            prometheus_count.fetch_add(events, Ordering::Relaxed);
+        }
         let counts = self.counts.fetch_add(events, Ordering::Relaxed);
         let times = self.times.fetch_add(1, Ordering::Relaxed);
         let mut lograte = self.lograte.load(Ordering::Relaxed);
@@ -183,7 +194,7 @@ impl Counter {
                         .or_insert(influxdb::Value::Integer(0));
                 }
                 if let Some(ref mut point) = self.point {
-                    submit(point.to_owned(), level);
+                    submit_counter(point.to_owned(), level);
                 }
             }
         }
@@ -0,0 +1,134 @@
+//! The `latency` module keeps the recent latencies of each pipeline stage, the time the stage
+//! took to process each batch it was handed.  They're recorded whether or not metrics are
+//! exported, so that a validator can always report them.
+
+use lazy_static::lazy_static;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How many of each stage's most recent latencies its quantiles are computed from
+const MAX_LATENCY_SAMPLES: usize = 1024;
+
+lazy_static! {
+    static ref LATENCIES: Mutex<BTreeMap<&'static str, LatencySamples>> =
+        Mutex::new(BTreeMap::new());
+}
+
+#[derive(Default)]
+struct LatencySamples {
+    recent: VecDeque<u64>,
+    count: u64,
+    sum: u64,
+}
+
+impl LatencySamples {
+    fn add(&mut self, micros: u64) {
+        if self.recent.len() == MAX_LATENCY_SAMPLES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(micros);
+        self.count += 1;
+        self.sum += micros;
+    }
+
+    fn summary(&self, stage: &str) -> StageLatency {
+        let mut sorted: Vec<_> = self.recent.iter().cloned().collect();
+        sorted.sort();
+        let quantile = |quantile: f64| {
+            let index = ((sorted.len() - 1) as f64 * quantile).round() as usize;
+            sorted[index]
+        };
+        StageLatency {
+            stage: stage.to_string(),
+            count: self.count,
+            sum_us: self.sum,
+            p50_us: quantile(0.5),
+            p90_us: quantile(0.9),
+            p99_us: quantile(0.99),
+        }
+    }
+}
+
+/// A summary of the latencies recorded for one pipeline stage, in microseconds. The quantiles
+/// are of the most recent latencies only.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageLatency {
+    pub stage: String,
+    /// How many latencies have been recorded since the validator started
+    pub count: u64,
+    pub sum_us: u64,
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+}
+
+/// Record that `stage` of the pipeline took `latency` to process a batch
+pub fn record_latency(stage: &'static str, latency: Duration) {
+    let micros = latency.as_secs() * 1_000_000 + u64::from(latency.subsec_micros());
+    LATENCIES
+        .lock()
+        .unwrap()
+        .entry(stage)
+        .or_default()
+        .add(micros);
+}
+
+/// The latencies recorded so far, by stage
+pub fn stage_latencies() -> Vec<StageLatency> {
+    LATENCIES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(stage, samples)| samples.summary(stage))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_latency_samples_summary() {
+        let mut samples = LatencySamples::default();
+        for micros in 1..=100 {
+            samples.add(micros);
+        }
+        assert_eq!(
+            samples.summary("banking"),
+            StageLatency {
+                stage: "banking".to_string(),
+                count: 100,
+                sum_us: 5050,
+                p50_us: 51,
+                p90_us: 90,
+                p99_us: 99,
+            }
+        );
+    }
+
+    #[test]
+    fn test_latency_samples_window() {
+        let mut samples = LatencySamples::default();
+        for micros in 0..MAX_LATENCY_SAMPLES as u64 + 10 {
+            samples.add(micros);
+        }
+        assert_eq!(samples.recent.len(), MAX_LATENCY_SAMPLES);
+        assert_eq!(
+            samples.summary("replay").count,
+            MAX_LATENCY_SAMPLES as u64 + 10
+        );
+        assert_eq!(samples.recent.front(), Some(&10));
+    }
+
+    #[test]
+    fn test_record_latency() {
+        record_latency("test-stage", Duration::from_millis(3));
+        let latency = stage_latencies()
+            .into_iter()
+            .find(|latency| latency.stage == "test-stage")
+            .unwrap();
+        assert_eq!(latency.count, 1);
+        assert_eq!(latency.p50_us, 3000);
+    }
+}
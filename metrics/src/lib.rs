@@ -1,10 +1,15 @@
 #[macro_use]
 pub mod counter;
 
+mod latency;
 mod metrics;
+mod prometheus;
 
 pub use crate::metrics::flush;
 pub use crate::metrics::query;
+pub use crate::metrics::set_node_labels;
 pub use crate::metrics::set_panic_hook;
 pub use crate::metrics::submit;
+pub use crate::latency::{record_latency, stage_latencies, StageLatency};
+pub use crate::prometheus::serve_prometheus;
 pub use influx_db_client as influxdb;
@@ -4,11 +4,12 @@ use influx_db_client as influxdb;
 use influx_db_client::Point;
 use lazy_static::lazy_static;
 use log::*;
-use solana_sdk::hash::hash;
+use solana_sdk::hash::{hash, Hash};
+use solana_sdk::pubkey::Pubkey;
 use solana_sdk::timing;
 use std::collections::HashMap;
 use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
-use std::sync::{Arc, Barrier, Mutex, Once};
+use std::sync::{Arc, Barrier, Mutex, Once, RwLock};
 use std::thread;
 use std::time::{Duration, Instant};
 use std::{cmp, env};
@@ -36,6 +37,11 @@ macro_rules! datapoint {
                     $name,
                     $crate::influxdb::Value::Boolean($value as bool));
     };
+    (@field $point:ident $name:expr, $value:expr, tag) => {
+            $point.add_tag(
+                    $name,
+                    $crate::influxdb::Value::String($value.to_string()));
+    };
 
     (@fields $point:ident) => {};
     (@fields $point:ident ($name:expr, $value:expr, $type:ident) , $($rest:tt)*) => {
@@ -131,6 +137,42 @@ lazy_static! {
             format!("host-{}", hash(hostname.as_bytes())).to_string()
         })
     };
+    static ref NODE_LABELS: RwLock<Option<NodeLabels>> = RwLock::new(None);
+}
+
+/// Tags identifying the node and cluster a datapoint came from
+#[derive(Clone, Debug)]
+struct NodeLabels {
+    node_id: String,
+    cluster: String,
+}
+
+impl NodeLabels {
+    fn new(node_id: &Pubkey, genesis_hash: &Hash) -> Self {
+        Self {
+            node_id: node_id.to_string(),
+            cluster: genesis_hash.to_string(),
+        }
+    }
+
+    fn add_to(&self, point: &mut Point) {
+        point
+            .add_tag("node_id", influxdb::Value::String(self.node_id.clone()))
+            .add_tag("cluster", influxdb::Value::String(self.cluster.clone()));
+    }
+}
+
+/// Tags every datapoint submitted after this call with the node identity pubkey and
+/// the cluster's genesis hash, so series from several nodes reporting to the same
+/// database stay separate
+pub fn set_node_labels(node_id: &Pubkey, genesis_hash: &Hash) {
+    *NODE_LABELS.write().unwrap() = Some(NodeLabels::new(node_id, genesis_hash));
+}
+
+fn add_node_labels(point: &mut Point) {
+    if let Some(labels) = NODE_LABELS.read().unwrap().as_ref() {
+        labels.add_to(point);
+    }
 }
 
 #[derive(Debug)]
@@ -243,7 +285,7 @@ impl MetricsAgent {
         }
         let points_written = cmp::min(num_points, max_points - 1);
 
-        let extra = influxdb::Point::new("metrics")
+        let mut extra = influxdb::Point::new("metrics")
             .add_timestamp(timing::timestamp() as i64)
             .add_field("host_id", influxdb::Value::String(HOST_ID.to_string()))
             .add_field(
@@ -260,6 +302,7 @@ impl MetricsAgent {
                 influxdb::Value::Integer(now.duration_since(last_write_time).as_secs() as i64),
             )
             .to_owned();
+        add_node_labels(&mut extra);
 
         writer.write(points[0..points_written].to_vec());
         writer.write([extra].to_vec());
@@ -343,6 +386,7 @@ impl MetricsAgent {
 
     pub fn submit(&self, mut point: influxdb::Point, level: log::Level) {
         point.add_field("host_id", influxdb::Value::String(HOST_ID.to_string()));
+        add_node_labels(&mut point);
         if point.timestamp.is_none() {
             point.timestamp = Some(timing::timestamp() as i64);
         }
@@ -384,6 +428,13 @@ fn get_singleton_agent() -> Arc<Mutex<MetricsAgent>> {
 /// Submits a new point from any thread.  Note that points are internally queued
 /// and transmitted periodically in batches.
 pub fn submit(point: influxdb::Point, level: log::Level) {
+    crate::prometheus::set_gauges(&point);
+    submit_counter(point, level);
+}
+
+/// Submits a counter's point, which unlike a datapoint is mirrored to Prometheus as the
+/// counter is incremented rather than as a gauge
+pub(crate) fn submit_counter(point: influxdb::Point, level: log::Level) {
     let agent_mutex = get_singleton_agent();
     let agent = agent_mutex.lock().unwrap();
     agent.submit(point, level);
@@ -616,6 +667,7 @@ mod test {
         datapoint!("name", ("field name", true, bool));
         datapoint!("name", ("field name", 1, i64));
         datapoint!("name", ("field name", 1, i64),);
+        datapoint!("name", ("tag name", "tag", tag), ("field name", 1, i64));
         datapoint!("name", ("field1 name", 2, i64), ("field2 name", 2, i64));
         datapoint!("name", ("field1 name", 2, i64), ("field2 name", 2, i64),);
         datapoint!(
@@ -661,6 +713,30 @@ mod test {
             point.fields.get("bool").unwrap(),
             influxdb::Value::Boolean(true)
         ));
+
+        let point = datapoint!(@point "name", ("slot", 42, tag), ("i64", 1, i64));
+        assert!(match point.tags.get("slot").unwrap() {
+            influxdb::Value::String(ref s) => s == "42",
+            _ => false,
+        });
+        assert!(point.fields.get("slot").is_none());
+    }
+
+    #[test]
+    fn test_node_labels() {
+        let node_id = Pubkey::new_rand();
+        let genesis_hash = hash(&[42]);
+
+        let mut point = influxdb::Point::new("labeled");
+        NodeLabels::new(&node_id, &genesis_hash).add_to(&mut point);
+        assert!(match point.tags.get("node_id").unwrap() {
+            influxdb::Value::String(ref s) => *s == node_id.to_string(),
+            _ => false,
+        });
+        assert!(match point.tags.get("cluster").unwrap() {
+            influxdb::Value::String(ref s) => *s == genesis_hash.to_string(),
+            _ => false,
+        });
     }
 
 }
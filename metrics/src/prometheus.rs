@@ -0,0 +1,307 @@
+//! The `prometheus` module mirrors the counters and datapoints submitted through this crate
+//! into an in-memory registry, and serves it in the Prometheus text exposition format for
+//! operators that scrape metrics rather than run an `InfluxDB` instance.  The pipeline stage
+//! latencies are served as a summary of their quantiles.  Counters and datapoints aren't
+//! mirrored until the endpoint is started.
+
+use crate::influxdb;
+use crate::latency::{stage_latencies, StageLatency};
+use lazy_static::lazy_static;
+use log::*;
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::Builder;
+use std::time::Duration;
+
+/// Measurement name prefixes and the pipeline stage their series are labeled with
+const STAGES: &[(&str, &str)] = &[
+    ("banking_stage", "banking"),
+    ("replay_stage", "replay"),
+    ("replay-stage", "replay"),
+    ("retransmit", "retransmit"),
+    ("streamer-retransmit", "retransmit"),
+    ("cluster_info-retransmit", "retransmit"),
+    ("repair", "repair"),
+    ("cluster_info-repair", "repair"),
+];
+
+/// The name of the summary of pipeline stage latencies, in microseconds
+const LATENCY_METRIC: &str = "solana_pipeline_latency_us";
+
+lazy_static! {
+    static ref ENABLED: AtomicBool = AtomicBool::new(false);
+    static ref REGISTRY: Mutex<Registry> = Mutex::new(Registry::default());
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Gauge {
+    stage: Option<&'static str>,
+    value: f64,
+}
+
+/// A counter's total, which the counters of this crate add to without locking the registry
+#[derive(Debug)]
+struct Counter {
+    stage: Option<&'static str>,
+    count: Arc<AtomicUsize>,
+}
+
+/// The latest value of every mirrored series, by Prometheus metric name
+#[derive(Default)]
+struct Registry {
+    counters: BTreeMap<String, Counter>,
+    gauges: BTreeMap<String, Gauge>,
+}
+
+impl Registry {
+    /// The total of the counter `name`, shared by every counter of that name
+    fn register_counter(&mut self, name: &str) -> Arc<AtomicUsize> {
+        self.counters
+            .entry(format!("{}_total", metric_name(name)))
+            .or_insert_with(|| Counter {
+                stage: stage(name),
+                count: Arc::default(),
+            })
+            .count
+            .clone()
+    }
+
+    fn set_gauges(&mut self, point: &influxdb::Point) {
+        for (field, value) in &point.fields {
+            let value = match value {
+                influxdb::Value::Integer(value) => *value as f64,
+                influxdb::Value::Float(value) => *value,
+                influxdb::Value::Boolean(value) => {
+                    if *value {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                }
+                influxdb::Value::String(_) => continue,
+            };
+            self.gauges.insert(
+                metric_name(&format!("{}_{}", point.measurement, field)),
+                Gauge {
+                    stage: stage(&point.measurement),
+                    value,
+                },
+            );
+        }
+    }
+
+    fn render(&self, latencies: &[StageLatency]) -> String {
+        // Counters and gauges are rendered together, in order of name
+        let mut series: BTreeMap<&str, (&str, Option<&str>, f64)> = BTreeMap::new();
+        for (name, counter) in &self.counters {
+            let count = counter.count.load(Ordering::Relaxed) as f64;
+            series.insert(name, ("counter", counter.stage, count));
+        }
+        for (name, gauge) in &self.gauges {
+            series.insert(name, ("gauge", gauge.stage, gauge.value));
+        }
+
+        let mut text = String::new();
+        for (name, (kind, stage, value)) in series {
+            let labels = stage
+                .map(|stage| format!("{{stage=\"{}\"}}", stage))
+                .unwrap_or_default();
+            text.push_str(&format!("# TYPE {} {}\n", name, kind));
+            text.push_str(&format!("{}{} {}\n", name, labels, value));
+        }
+        if !latencies.is_empty() {
+            text.push_str(&format!("# TYPE {} summary\n", LATENCY_METRIC));
+        }
+        for latency in latencies {
+            let quantiles = [
+                ("0.5", latency.p50_us),
+                ("0.9", latency.p90_us),
+                ("0.99", latency.p99_us),
+            ];
+            for (quantile, value) in &quantiles {
+                text.push_str(&format!(
+                    "{}{{stage=\"{}\",quantile=\"{}\"}} {}\n",
+                    LATENCY_METRIC, latency.stage, quantile, value
+                ));
+            }
+            text.push_str(&format!(
+                "{}_sum{{stage=\"{}\"}} {}\n",
+                LATENCY_METRIC, latency.stage, latency.sum_us
+            ));
+            text.push_str(&format!(
+                "{}_count{{stage=\"{}\"}} {}\n",
+                LATENCY_METRIC, latency.stage, latency.count
+            ));
+        }
+        text
+    }
+}
+
+/// `name` with the characters Prometheus doesn't allow in metric names replaced
+fn metric_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("solana_{}", name)
+}
+
+fn stage(measurement: &str) -> Option<&'static str> {
+    STAGES
+        .iter()
+        .find(|(prefix, _)| measurement.starts_with(prefix))
+        .map(|(_, stage)| *stage)
+}
+
+/// The total the counter `name` adds its events to, or `None` if the endpoint isn't started.
+/// Counters register once and add to the total directly from then on.
+pub(crate) fn register_counter(name: &str) -> Option<Arc<AtomicUsize>> {
+    if ENABLED.load(Ordering::Relaxed) {
+        Some(REGISTRY.lock().unwrap().register_counter(name))
+    } else {
+        None
+    }
+}
+
+pub(crate) fn set_gauges(point: &influxdb::Point) {
+    if ENABLED.load(Ordering::Relaxed) {
+        REGISTRY.lock().unwrap().set_gauges(point);
+    }
+}
+
+/// Start mirroring metrics and serve them at `/metrics` over HTTP on `addr`.  Returns the
+/// address the endpoint is listening on.
+pub fn serve_prometheus(addr: SocketAddr) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(&addr)?;
+    let local_addr = listener.local_addr()?;
+    ENABLED.store(true, Ordering::Relaxed);
+    Builder::new()
+        .name("solana-metrics-prometheus".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                if let Err(err) = stream.and_then(|mut stream| write_response(&mut stream)) {
+                    debug!("failed to serve prometheus metrics; error = {:?}", err);
+                }
+            }
+        })?;
+    info!("Prometheus metrics listening on: {}", local_addr);
+    Ok(local_addr)
+}
+
+fn write_response(stream: &mut TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::new(1, 0)))?;
+    let mut request = [0; 1024];
+    let len = stream.read(&mut request)?;
+    let request = String::from_utf8_lossy(&request[..len]);
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+
+    if path == "/metrics" {
+        let latencies = stage_latencies();
+        let body = REGISTRY.lock().unwrap().render(&latencies);
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    } else {
+        write!(
+            stream,
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_metric_name_and_stage() {
+        assert_eq!(
+            metric_name("banking_stage-process_packets"),
+            "solana_banking_stage_process_packets"
+        );
+        assert_eq!(stage("banking_stage-process_packets"), Some("banking"));
+        assert_eq!(
+            stage("replay-stage-entry_verification_failure"),
+            Some("replay")
+        );
+        assert_eq!(stage("streamer-retransmit-error"), Some("retransmit"));
+        assert_eq!(stage("repair_service"), Some("repair"));
+        assert_eq!(stage("validator-confirmation"), None);
+    }
+
+    #[test]
+    fn test_registry_render() {
+        let mut registry = Registry::default();
+        registry
+            .register_counter("banking_stage-process_packets")
+            .fetch_add(3, Ordering::Relaxed);
+        registry
+            .register_counter("banking_stage-process_packets")
+            .fetch_add(4, Ordering::Relaxed);
+        let point = influxdb::Point::new("validator-new")
+            .add_field("count", influxdb::Value::Integer(2))
+            .add_field("ratio", influxdb::Value::Float(0.5))
+            .add_field("host_id", influxdb::Value::String("host".to_string()))
+            .to_owned();
+        registry.set_gauges(&point);
+
+        assert_eq!(
+            registry.render(&[]),
+            "# TYPE solana_banking_stage_process_packets_total counter\n\
+             solana_banking_stage_process_packets_total{stage=\"banking\"} 7\n\
+             # TYPE solana_validator_new_count gauge\n\
+             solana_validator_new_count 2\n\
+             # TYPE solana_validator_new_ratio gauge\n\
+             solana_validator_new_ratio 0.5\n"
+        );
+    }
+
+    #[test]
+    fn test_registry_render_latencies() {
+        let registry = Registry::default();
+        let latency = StageLatency {
+            stage: "banking".to_string(),
+            count: 100,
+            sum_us: 5050,
+            p50_us: 51,
+            p90_us: 90,
+            p99_us: 99,
+        };
+        assert_eq!(
+            registry.render(&[latency]),
+            "# TYPE solana_pipeline_latency_us summary\n\
+             solana_pipeline_latency_us{stage=\"banking\",quantile=\"0.5\"} 51\n\
+             solana_pipeline_latency_us{stage=\"banking\",quantile=\"0.9\"} 90\n\
+             solana_pipeline_latency_us{stage=\"banking\",quantile=\"0.99\"} 99\n\
+             solana_pipeline_latency_us_sum{stage=\"banking\"} 5050\n\
+             solana_pipeline_latency_us_count{stage=\"banking\"} 100\n"
+        );
+    }
+
+    #[test]
+    fn test_serve_prometheus() {
+        let addr = serve_prometheus("127.0.0.1:0".parse().unwrap()).unwrap();
+        register_counter("repair-slot")
+            .unwrap()
+            .fetch_add(1, Ordering::Relaxed);
+
+        let mut stream = TcpStream::connect(&addr).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("solana_repair_slot_total{stage=\"repair\"} "));
+
+        let mut stream = TcpStream::connect(&addr).unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+    }
+}
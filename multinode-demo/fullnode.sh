@@ -26,9 +26,14 @@ Start a validator
   --stake LAMPORTS          - Number of lamports to stake
   --node-lamports LAMPORTS  - Number of lamports this node has been funded from the genesis block
   --no-voting               - start node without vote signer
+  --rpc-only                - replay the ledger and serve RPC without a TPU, implies --no-voting
+  --dev-halt-at-slot SLOT   - stop processing the ledger past SLOT, for debugging
   --rpc-port port           - custom RPC port for this node
   --no-restart              - do not restart the node if it exits
   --no-airdrop              - The genesis block has an account for the node. Airdrops are not required.
+  --accounts-path DIR       - store the accounts in DIR instead of under the config dir. Repeat to spread
+                              the accounts across disks
+  --no-io-probe             - skip measuring the speed of the accounts and ledger disks at startup
 
 EOF
   exit 1
@@ -98,12 +103,14 @@ setup_validator_accounts() {
     (
       set -x
       $solana_wallet --keypair "$identity_keypair_path" --url "http://$entrypoint_ip:8899" \
-      create-vote-account "$vote_pubkey" "$identity_pubkey" 1 --commission 127
+      create-vote-account "$voting_keypair_path" "$identity_pubkey" 1 --commission 127
     ) || return $?
 
-    echo "Delegate the stake account to the node's vote account"
+    echo "Create the stake account and delegate it to the node's vote account"
     (
       set -x
+      $solana_wallet --keypair "$identity_keypair_path" --url "http://$entrypoint_ip:8899" \
+        create-stake-account "$stake_keypair_path" "$stake_lamports"
       $solana_wallet --keypair "$identity_keypair_path" --url "http://$entrypoint_ip:8899" \
         delegate-stake "$stake_keypair_path" "$vote_pubkey" "$stake_lamports"
     ) || return $?
@@ -144,6 +151,7 @@ generate_snapshots=0
 boot_from_snapshot=1
 reset_ledger=0
 config_dir=
+accounts_paths=()
 
 positional_args=()
 while [[ -n $1 ]]; do
@@ -194,6 +202,12 @@ while [[ -n $1 ]]; do
     elif [[ $1 = --no-voting ]]; then
       args+=("$1")
       shift
+    elif [[ $1 = --rpc-only ]]; then
+      args+=("$1")
+      shift
+    elif [[ $1 = --dev-halt-at-slot ]]; then
+      args+=("$1" "$2")
+      shift 2
     elif [[ $1 = --skip-ledger-verify ]]; then
       args+=("$1")
       shift
@@ -203,6 +217,13 @@ while [[ -n $1 ]]; do
     elif [[ $1 = --limit-ledger-size ]]; then
       args+=("$1")
       shift
+    elif [[ $1 = --accounts-path ]]; then
+      accounts_paths+=("$2")
+      args+=("$1" "$2")
+      shift 2
+    elif [[ $1 = --no-io-probe ]]; then
+      args+=("$1")
+      shift
     elif [[ $1 = --rpc-port ]]; then
       args+=("$1" "$2")
       shift 2
@@ -286,7 +307,11 @@ default_arg --identity "$identity_keypair_path"
 default_arg --voting-keypair "$voting_keypair_path"
 default_arg --storage-keypair "$storage_keypair_path"
 default_arg --ledger "$ledger_config_dir"
-default_arg --accounts "$accounts_config_dir"
+if [[ ${#accounts_paths[@]} -gt 0 ]]; then
+  accounts_config_dir="${accounts_paths[*]}"
+else
+  default_arg --accounts "$accounts_config_dir"
+fi
 default_arg --snapshot-path "$snapshot_config_dir"
 
 if [[ -n $SOLANA_CUDA ]]; then
@@ -0,0 +1,274 @@
+//! The `solana_program_test` crate runs BPF programs against an in-process Bank so
+//! that programs can be unit tested with `cargo test`, without a local cluster.
+//!
+//! ```ignore
+//! let mut program_test = ProgramTest::default();
+//! program_test.add_program("my_program", program_id);
+//! let mut context = program_test.start();
+//! context.process_instructions(vec![instruction], &[]).unwrap();
+//! context.warp_to_slot(100);
+//! ```
+
+#[macro_use]
+extern crate solana_bpf_loader_program;
+
+use log::*;
+use solana_runtime::bank::Bank;
+use solana_sdk::account::Account;
+use solana_sdk::bpf_loader;
+use solana_sdk::fee_calculator::FeeCalculator;
+use solana_sdk::genesis_block::Builder;
+use solana_sdk::hash::{hash, Hash};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, KeypairUtil};
+use solana_sdk::system_program;
+use solana_sdk::transaction::{self, Transaction};
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Lamports the payer starts with
+pub const PAYER_LAMPORTS: u64 = 1_000_000_000;
+
+/// Environment variable naming an extra directory to search for program files
+pub const BPF_OUT_DIR: &str = "BPF_OUT_DIR";
+
+/// BPF program file extension
+const PLATFORM_FILE_EXTENSION_BPF: &str = "so";
+
+/// Describes the programs and accounts a test starts with
+pub struct ProgramTest {
+    accounts: Vec<(Pubkey, Account)>,
+    search_path: Vec<PathBuf>,
+}
+
+impl Default for ProgramTest {
+    fn default() -> Self {
+        let mut search_path = vec![];
+        if let Ok(bpf_out_dir) = env::var(BPF_OUT_DIR) {
+            search_path.push(PathBuf::from(bpf_out_dir));
+        }
+        // Where the workspace build puts BPF programs, next to the test executable
+        if let Ok(current_exe) = env::current_exe() {
+            if let Some(target_dir) = current_exe.parent().and_then(Path::parent) {
+                search_path.push(target_dir.join("bpf"));
+            }
+        }
+        search_path.push(PathBuf::from("tests/fixtures"));
+        search_path.push(PathBuf::from("."));
+        Self {
+            accounts: vec![],
+            search_path,
+        }
+    }
+}
+
+impl ProgramTest {
+    /// Add a directory to search for program files, ahead of the defaults
+    pub fn add_search_path<P: AsRef<Path>>(&mut self, path: P) {
+        self.search_path.insert(0, path.as_ref().to_path_buf());
+    }
+
+    /// Load `<program_name>.so` from the search path and deploy it at `program_id`
+    pub fn add_program(&mut self, program_name: &str, program_id: Pubkey) {
+        let path = self
+            .find_program(program_name)
+            .unwrap_or_else(|| panic!("Program file {}.so not found", program_name));
+        let mut file = File::open(&path)
+            .unwrap_or_else(|err| panic!("Failed to open {}: {}", path.display(), err));
+        let mut elf = vec![];
+        file.read_to_end(&mut elf)
+            .unwrap_or_else(|err| panic!("Failed to read {}: {}", path.display(), err));
+        info!("Loaded {} from {}", program_name, path.display());
+        self.add_program_elf(program_id, elf);
+    }
+
+    /// Deploy an already loaded program at `program_id`
+    pub fn add_program_elf(&mut self, program_id: Pubkey, elf: Vec<u8>) {
+        let mut account = Account::new(1, 0, &bpf_loader::id());
+        account.data = elf;
+        account.executable = true;
+        self.add_account(program_id, account);
+    }
+
+    /// Add an account that exists when the test starts
+    pub fn add_account(&mut self, pubkey: Pubkey, account: Account) {
+        self.accounts.push((pubkey, account));
+    }
+
+    fn find_program(&self, program_name: &str) -> Option<PathBuf> {
+        self.search_path
+            .iter()
+            .map(|dir| {
+                let mut path = dir.join(program_name);
+                path.set_extension(PLATFORM_FILE_EXTENSION_BPF);
+                path
+            })
+            .find(|path| path.exists())
+    }
+
+    /// Create the genesis Bank, with the BPF loader that runs the test's programs, and a
+    /// funded payer
+    pub fn start(self) -> ProgramTestContext {
+        solana_logger::setup();
+        let payer = Keypair::new();
+        let genesis_block = Builder::new()
+            .account(
+                payer.pubkey(),
+                Account::new(PAYER_LAMPORTS, 0, &system_program::id()),
+            )
+            .accounts(&self.accounts)
+            .native_instruction_processors(&[solana_bpf_loader_program!()])
+            .fee_calculator(FeeCalculator::new(0))
+            .build();
+        ProgramTestContext {
+            bank: Arc::new(Bank::new(&genesis_block)),
+            payer,
+        }
+    }
+}
+
+/// A running test: the current Bank and the payer funding transactions
+pub struct ProgramTestContext {
+    bank: Arc<Bank>,
+    pub payer: Keypair,
+}
+
+impl ProgramTestContext {
+    pub fn bank(&self) -> &Arc<Bank> {
+        &self.bank
+    }
+
+    pub fn last_blockhash(&self) -> Hash {
+        self.bank.last_blockhash()
+    }
+
+    pub fn get_account(&self, pubkey: &Pubkey) -> Option<Account> {
+        self.bank.get_account(pubkey)
+    }
+
+    pub fn get_balance(&self, pubkey: &Pubkey) -> u64 {
+        self.bank.get_balance(pubkey)
+    }
+
+    /// Overwrite an account, bypassing the programs that own it
+    pub fn set_account(&self, pubkey: &Pubkey, account: &Account) {
+        self.bank.store_account(pubkey, account);
+    }
+
+    pub fn process_transaction(&self, transaction: &Transaction) -> transaction::Result<()> {
+        self.bank.process_transaction(transaction)
+    }
+
+    /// Process `instructions` in one transaction paid for by the payer. `signers`
+    /// are any keypairs the instructions need besides the payer.
+    pub fn process_instructions(
+        &self,
+        instructions: Vec<Instruction>,
+        signers: &[&Keypair],
+    ) -> transaction::Result<()> {
+        let message = Message::new_with_payer(instructions, Some(&self.payer.pubkey()));
+        let mut keypairs = vec![&self.payer];
+        keypairs.extend_from_slice(signers);
+        let transaction = Transaction::new(&keypairs, message, self.last_blockhash());
+        self.process_transaction(&transaction)
+    }
+
+    /// Complete the current slot and continue on a child Bank at `slot`. The
+    /// completed slot registers a new blockhash, so identical transactions can be
+    /// sent again once they're re-signed.
+    pub fn warp_to_slot(&mut self, slot: u64) {
+        assert!(
+            slot > self.bank.slot(),
+            "Can't warp from slot {} back to {}",
+            self.bank.slot(),
+            slot
+        );
+        let mut tick_hash = self.bank.last_blockhash();
+        while self.bank.tick_height() < self.bank.max_tick_height() {
+            tick_hash = hash(tick_hash.as_ref());
+            self.bank.register_tick(&tick_hash);
+        }
+        self.bank = Arc::new(Bank::new_from_parent(&self.bank, &Pubkey::default(), slot));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::native_loader;
+    use solana_sdk::system_instruction;
+
+    #[test]
+    fn test_process_instructions() {
+        let context = ProgramTest::default().start();
+        let to = Pubkey::new_rand();
+        let instruction = system_instruction::transfer(&context.payer.pubkey(), &to, 42);
+        context
+            .process_instructions(vec![instruction], &[])
+            .unwrap();
+        assert_eq!(context.get_balance(&to), 42);
+        assert_eq!(
+            context.get_balance(&context.payer.pubkey()),
+            PAYER_LAMPORTS - 42
+        );
+    }
+
+    #[test]
+    fn test_accounts() {
+        let pubkey = Pubkey::new_rand();
+        let mut program_test = ProgramTest::default();
+        program_test.add_account(pubkey, Account::new(7, 0, &system_program::id()));
+        let context = program_test.start();
+        assert_eq!(context.get_balance(&pubkey), 7);
+
+        context.set_account(&pubkey, &Account::new(9, 0, &system_program::id()));
+        assert_eq!(context.get_balance(&pubkey), 9);
+    }
+
+    #[test]
+    fn test_add_program_elf() {
+        let program_id = Pubkey::new_rand();
+        let mut program_test = ProgramTest::default();
+        program_test.add_program_elf(program_id, vec![1, 2, 3]);
+        let context = program_test.start();
+        let account = context.get_account(&program_id).unwrap();
+        assert!(account.executable);
+        assert_eq!(account.owner, bpf_loader::id());
+        assert_eq!(account.data, vec![1, 2, 3]);
+
+        // The loader the program is owned by is registered too
+        let loader = context.get_account(&bpf_loader::id()).unwrap();
+        assert!(loader.executable);
+        assert_eq!(loader.owner, native_loader::id());
+    }
+
+    #[test]
+    #[should_panic(expected = "not found")]
+    fn test_add_program_missing() {
+        ProgramTest::default().add_program("does_not_exist", Pubkey::new_rand());
+    }
+
+    #[test]
+    fn test_warp_to_slot() {
+        let mut context = ProgramTest::default().start();
+        let payer = context.payer.pubkey();
+        let to = Pubkey::new_rand();
+        let blockhash = context.last_blockhash();
+        let transfer = || system_instruction::transfer(&payer, &to, 1);
+        context.process_instructions(vec![transfer()], &[]).unwrap();
+
+        context.warp_to_slot(100);
+        assert_eq!(context.bank().slot(), 100);
+        assert_ne!(context.last_blockhash(), blockhash);
+        assert_eq!(context.get_balance(&to), 1);
+
+        // The new blockhash lets the same transfer be sent again
+        context.process_instructions(vec![transfer()], &[]).unwrap();
+        assert_eq!(context.get_balance(&to), 2);
+    }
+}
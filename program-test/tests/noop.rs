@@ -0,0 +1,50 @@
+use solana_program_test::ProgramTest;
+use solana_sdk::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::KeypairUtil;
+use solana_sdk::transaction::TransactionError;
+
+#[test]
+fn test_noop_program() {
+    // tests/fixtures/noop.so, built by tests/fixtures/build.sh
+    let program_id = Pubkey::new_rand();
+    let mut program_test = ProgramTest::default();
+    program_test.add_program("noop", program_id);
+    let mut context = program_test.start();
+
+    let account = Pubkey::new_rand();
+    let instruction = || {
+        Instruction::new(
+            program_id,
+            &[1u8, 2, 3],
+            vec![AccountMeta::new_credit_only(account, false)],
+        )
+    };
+    context
+        .process_instructions(vec![instruction()], &[])
+        .unwrap();
+
+    // The program still runs on the Bank of a later slot
+    context.warp_to_slot(10);
+    context
+        .process_instructions(vec![instruction()], &[])
+        .unwrap();
+}
+
+#[test]
+fn test_invalid_program() {
+    let program_id = Pubkey::new_rand();
+    let mut program_test = ProgramTest::default();
+    program_test.add_program_elf(program_id, vec![1, 2, 3]);
+    let context = program_test.start();
+
+    let instruction = Instruction::new(
+        program_id,
+        &0u8,
+        vec![AccountMeta::new(context.payer.pubkey(), true)],
+    );
+    match context.process_instructions(vec![instruction], &[]) {
+        Err(TransactionError::InstructionError(0, InstructionError::GenericError)) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
@@ -4,6 +4,7 @@ extern crate test;
 
 use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
 use solana_rbpf::EbpfVmRaw;
+use solana_sdk::pubkey::Pubkey;
 use std::env;
 use std::fs::File;
 use std::io::Error;
@@ -76,7 +77,7 @@ fn bench_program_alu(bencher: &mut Bencher) {
     inner_iter.write_u64::<LittleEndian>(0).unwrap();
 
     let elf = load_elf().unwrap();
-    let (mut vm, _) = solana_bpf_loader_api::create_vm(&elf).unwrap();
+    let (mut vm, _) = solana_bpf_loader_api::create_vm(&Pubkey::default(), &elf).unwrap();
 
     println!("Interpreted:");
     assert_eq!(
@@ -0,0 +1,23 @@
+/**
+ * @brief Example C-based BPF program that returns its instruction data
+ */
+#include <solana_sdk.h>
+
+extern bool entrypoint(const uint8_t *input) {
+  SolKeyedAccount ka[1];
+  SolParameters params = (SolParameters) { .ka = ka };
+
+  if (!sol_deserialize(input, &params, SOL_ARRAY_SIZE(ka))) {
+    return false;
+  }
+
+  sol_set_return_data(params.data, params.data_len);
+
+  // Read it back to check the loader kept it
+  uint8_t data[8];
+  SolPubkey program_id;
+  uint64_t len = sol_get_return_data(data, sizeof(data), program_id.x);
+  sol_assert(len == params.data_len);
+  sol_assert(SolPubkey_same(&program_id, params.program_id));
+  return true;
+}
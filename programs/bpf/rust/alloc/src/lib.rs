@@ -105,6 +105,31 @@ pub extern "C" fn entrypoint(_input: *mut u8) -> bool {
         assert_eq!(v.len(), ITERS);
     }
 
+    #[cfg(not(test))]
+    unsafe {
+        // Test heap size request, the default heap can't hold this allocation
+
+        const SIZE: usize = 64 * 1024;
+        let layout = Layout::from_size_align(SIZE, mem::align_of::<u8>()).unwrap();
+        let ptr = alloc::alloc::alloc(layout);
+        if !ptr.is_null() {
+            info!("Error: Alloc larger than the default heap should fail");
+            panic!();
+        }
+        if !solana_sdk_bpf_no_std::allocator::sol_request_heap(2 * SIZE) {
+            info!("Error: Heap request failed");
+            panic!();
+        }
+        let ptr = alloc::alloc::alloc(layout);
+        if ptr.is_null() {
+            info!("Error: Alloc after heap request failed");
+            alloc::alloc::handle_alloc_error(layout);
+        }
+        *ptr.add(SIZE - 1) = 42;
+        assert_eq!(*ptr.add(SIZE - 1), 42);
+        alloc::alloc::dealloc(ptr, layout);
+    }
+
     info!("Success");
     true
 }
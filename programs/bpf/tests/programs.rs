@@ -23,6 +23,13 @@ mod bpf {
         pathbuf
     }
 
+    /// Print the execution profile of all programs run, if SOLANA_BPF_PROFILE is set
+    fn print_profile() {
+        if let Some(profiler) = solana_bpf_loader_api::profiler::get_profiler() {
+            println!("{}", profiler.lock().unwrap().report());
+        }
+    }
+
     #[cfg(feature = "bpf_c")]
     mod bpf_c {
         use super::*;
@@ -30,7 +37,9 @@ mod bpf {
         use solana_sdk::bpf_loader;
         use solana_sdk::client::SyncClient;
         use solana_sdk::signature::KeypairUtil;
+        use solana_sdk::transaction::TransactionReturnData;
         use std::io::Read;
+        use std::sync::Arc;
 
         #[test]
         fn test_program_bpf_c() {
@@ -43,6 +52,7 @@ mod bpf {
                 ("noop++", true),
                 ("panic", false),
                 ("relative_call", true),
+                ("return_data", true),
                 ("struct_pass", true),
                 ("struct_ret", true),
             ];
@@ -71,6 +81,37 @@ mod bpf {
                     assert!(result.is_err());
                 }
             }
+            print_profile();
+        }
+
+        #[test]
+        fn test_program_bpf_c_return_data() {
+            solana_logger::setup();
+
+            let mut file = File::open(create_bpf_path("return_data")).expect("file open failed");
+            let mut elf = Vec::new();
+            file.read_to_end(&mut elf).unwrap();
+
+            let GenesisBlockInfo {
+                genesis_block,
+                mint_keypair,
+                ..
+            } = create_genesis_block(50);
+            let bank = Arc::new(Bank::new(&genesis_block));
+            let bank_client = BankClient::new_shared(&bank);
+
+            let program_id = load_program(&bank_client, &mint_keypair, &bpf_loader::id(), elf);
+            let instruction = create_invoke_instruction(mint_keypair.pubkey(), program_id, &7u8);
+            let signature = bank_client
+                .send_instruction(&mint_keypair, instruction)
+                .unwrap();
+            assert_eq!(
+                bank.get_return_data(&signature),
+                Some(TransactionReturnData {
+                    program_id,
+                    data: vec![7],
+                })
+            );
         }
     }
 
@@ -132,6 +173,7 @@ mod bpf {
                     assert!(result.is_err());
                 }
             }
+            print_profile();
         }
     }
 }
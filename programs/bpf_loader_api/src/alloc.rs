@@ -5,6 +5,12 @@ use std::fmt;
 pub trait Alloc {
     fn alloc(&mut self, layout: Layout) -> Result<*mut u8, AllocErr>;
     fn dealloc(&mut self, ptr: *mut u8, layout: Layout);
+    /// Changes the number of bytes the program may allocate, fails if the
+    /// new size is larger than the backing heap or smaller than what is
+    /// already allocated
+    fn set_heap_size(&mut self, size: usize) -> Result<(), AllocErr>;
+    /// Number of bytes the program may currently allocate
+    fn heap_size(&self) -> usize;
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
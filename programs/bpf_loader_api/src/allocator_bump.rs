@@ -7,26 +7,74 @@ use std::alloc::Layout;
 pub struct BPFAllocator {
     heap: Vec<u8>,
     pos: usize,
+    size: usize,
 }
 
 impl BPFAllocator {
     pub fn new(heap: Vec<u8>) -> Self {
-        Self { heap, pos: 0 }
+        let size = heap.len();
+        Self { heap, pos: 0, size }
     }
 }
 
 impl Alloc for BPFAllocator {
     fn alloc(&mut self, layout: Layout) -> Result<*mut u8, AllocErr> {
-        if self.pos + layout.size() <= self.heap.len() {
-            let ptr = unsafe { self.heap.as_mut_ptr().add(self.pos) };
-            self.pos += layout.size();
-            Ok(ptr)
-        } else {
-            Err(AllocErr)
+        let base = self.heap.as_ptr() as usize;
+        // Align the host address, the program sees the same pointer
+        let start = (base + self.pos + layout.align() - 1) & !(layout.align() - 1);
+        let offset = start - base;
+        match offset.checked_add(layout.size()) {
+            Some(end) if end <= self.size => {
+                self.pos = end;
+                Ok(unsafe { self.heap.as_mut_ptr().add(offset) })
+            }
+            _ => Err(AllocErr),
         }
     }
 
     fn dealloc(&mut self, _ptr: *mut u8, _layout: Layout) {
         // It's a bump allocator, free not supported
     }
+
+    fn set_heap_size(&mut self, size: usize) -> Result<(), AllocErr> {
+        if size > self.heap.len() || size < self.pos {
+            return Err(AllocErr);
+        }
+        self.size = size;
+        Ok(())
+    }
+
+    fn heap_size(&self) -> usize {
+        self.size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bump_allocator() {
+        let mut allocator = BPFAllocator::new(vec![0; 64]);
+        allocator.set_heap_size(32).unwrap();
+
+        let layout = Layout::from_size_align(3, 1).unwrap();
+        let first = allocator.alloc(layout).unwrap();
+        let layout = Layout::from_size_align(8, 8).unwrap();
+        let second = allocator.alloc(layout).unwrap();
+        assert_eq!(second as usize % 8, 0);
+        assert!(second as usize >= first as usize + 3);
+
+        // Limited by the requested heap size, not the backing heap
+        let layout = Layout::from_size_align(32, 1).unwrap();
+        assert_eq!(allocator.alloc(layout), Err(AllocErr));
+        allocator.set_heap_size(64).unwrap();
+        assert!(allocator.alloc(layout).is_ok());
+
+        assert_eq!(allocator.set_heap_size(8), Err(AllocErr));
+        assert_eq!(allocator.set_heap_size(65), Err(AllocErr));
+
+        let layout = Layout::from_size_align(core::isize::MAX as usize, 1).unwrap();
+        assert_eq!(allocator.alloc(layout), Err(AllocErr));
+    }
 }
@@ -30,6 +30,18 @@ impl Alloc for BPFAllocator {
         Err(AllocErr)
     }
 
+    fn set_heap_size(&mut self, size: usize) -> Result<(), AllocErr> {
+        if size < self.allocated {
+            return Err(AllocErr);
+        }
+        self.size = size;
+        Ok(())
+    }
+
+    fn heap_size(&self) -> usize {
+        self.size
+    }
+
     #[allow(clippy::not_unsafe_ptr_arg_deref)]
     fn dealloc(&mut self, ptr: *mut u8, layout: Layout) {
         self.allocated -= layout.size();
@@ -1,13 +1,20 @@
+use crate::return_data;
 use crate::Alloc;
 use libc::c_char;
 use log::*;
+use sha3::{Digest, Keccak256};
 use solana_rbpf::{EbpfVmRaw, MemoryRegion};
+use solana_sdk::hash::hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::MAX_RETURN_DATA;
 use std::alloc::Layout;
 use std::any::Any;
+use std::cell::RefCell;
 use std::ffi::CStr;
 use std::io::{Error, ErrorKind};
 use std::mem;
-use std::slice::from_raw_parts;
+use std::rc::Rc;
+use std::slice::{from_raw_parts, from_raw_parts_mut};
 use std::str::from_utf8;
 
 /// Program heap allocators are intended to allocate/free from a given
@@ -24,41 +31,157 @@ use crate::allocator_bump::BPFAllocator;
 // use allocator_system::BPFAllocator;
 
 /// Default program heap size, allocators
-/// are expected to enforce this
-const DEFAULT_HEAP_SIZE: usize = 32 * 1024;
+/// are expected to enforce this.  Programs that need more pay for it with
+/// `sol_request_heap_()`
+pub const DEFAULT_HEAP_SIZE: usize = 32 * 1024;
 
-pub fn register_helpers(vm: &mut EbpfVmRaw) -> Result<(MemoryRegion), Error> {
-    vm.register_helper_ex("abort", Some(helper_abort_verify), helper_abort, None)?;
-    vm.register_helper_ex(
+/// Largest heap a program may request via `sol_request_heap_()`.  The
+/// whole region is allocated and zeroed for every invocation and mapped for
+/// the program up front, only the requested portion is handed out by the
+/// allocator, so the cap bounds the memory each invocation costs the
+/// validator
+pub const MAX_HEAP_SIZE: usize = 256 * 1024;
+
+/// Heap requests are charged per page above `DEFAULT_HEAP_SIZE`
+pub const HEAP_PAGE_SIZE: usize = 32 * 1024;
+/// Compute units charged for every heap page above `DEFAULT_HEAP_SIZE`, a
+/// program requesting `MAX_HEAP_SIZE` gives up a tenth of its compute budget
+pub const HEAP_COST_PER_PAGE: u64 = 500;
+
+/// Returned by `sol_request_heap_()` when the request could not be satisfied.
+/// No heap is ever this large, so it can't be mistaken for a granted size
+pub const HEAP_REQUEST_DENIED: u64 = std::u64::MAX;
+
+/// Size in bytes of the result written by the hashing helpers
+const HASH_RESULT_SIZE: u64 = 32;
+
+/// Compute units available to the hashing and heap request helpers for a
+/// single program invocation
+pub const COMPUTE_BUDGET: u64 = 36_000;
+/// Compute units charged for every call to a hashing helper
+pub const HASH_BASE_COST: u64 = 85;
+/// Number of input bytes hashed per additional compute unit
+pub const HASH_BYTES_PER_UNIT: u64 = 2;
+
+/// Size in bytes of the program id written by `sol_get_return_data_()`
+const PUBKEY_SIZE: u64 = 32;
+
+/// Verifies the arguments of a helper call before the helper is called, an
+/// error halts the program
+pub type HelperVerifier = fn(
+    u64,
+    u64,
+    u64,
+    u64,
+    u64,
+    &mut Context,
+    &[MemoryRegion],
+    &[MemoryRegion],
+) -> Result<(()), Error>;
+
+/// A helper function, its return value is handed back to the program in r0
+pub type Helper = fn(u64, u64, u64, u64, u64, &mut Context) -> u64;
+
+/// Anything the helpers can be registered with, the VM itself or the
+/// profiler's tracer that replays programs outside of it
+pub trait HelperRegistry {
+    fn add_helper(
+        &mut self,
+        name: &str,
+        verifier: Option<HelperVerifier>,
+        helper: Helper,
+        context: Context,
+    ) -> Result<(()), Error>;
+}
+
+impl<'a> HelperRegistry for EbpfVmRaw<'a> {
+    fn add_helper(
+        &mut self,
+        name: &str,
+        verifier: Option<HelperVerifier>,
+        helper: Helper,
+        context: Context,
+    ) -> Result<(()), Error> {
+        self.register_helper_ex(name, verifier, helper, context)
+    }
+}
+
+pub fn register_helpers<R: HelperRegistry>(
+    vm: &mut R,
+    program_id: &Pubkey,
+) -> Result<(MemoryRegion), Error> {
+    vm.add_helper("abort", Some(helper_abort_verify), helper_abort, None)?;
+    vm.add_helper(
         "sol_panic",
         Some(helper_sol_panic_verify),
         helper_sol_panic,
         None,
     )?;
-    vm.register_helper_ex(
+    vm.add_helper(
         "sol_panic_",
         Some(helper_sol_panic_verify),
         helper_sol_panic,
         None,
     )?;
-    vm.register_helper_ex("sol_log", Some(helper_sol_log_verify), helper_sol_log, None)?;
-    vm.register_helper_ex(
+    vm.add_helper("sol_log", Some(helper_sol_log_verify), helper_sol_log, None)?;
+    vm.add_helper(
         "sol_log_",
         Some(helper_sol_log_verify_),
         helper_sol_log_,
         None,
     )?;
-    vm.register_helper_ex("sol_log_64", None, helper_sol_log_u64, None)?;
-    vm.register_helper_ex("sol_log_64_", None, helper_sol_log_u64, None)?;
+    vm.add_helper("sol_log_64", None, helper_sol_log_u64, None)?;
+    vm.add_helper("sol_log_64_", None, helper_sol_log_u64, None)?;
+
+    // The hashing helpers and heap requests draw from the same compute budget
+    let compute_meter = Rc::new(RefCell::new(ComputeMeter::new(COMPUTE_BUDGET)));
+    vm.add_helper(
+        "sol_sha256_",
+        Some(helper_sol_hash_verify),
+        helper_sol_sha256,
+        Some(Box::new(compute_meter.clone())),
+    )?;
+    vm.add_helper(
+        "sol_keccak256_",
+        Some(helper_sol_hash_verify),
+        helper_sol_keccak256,
+        Some(Box::new(compute_meter.clone())),
+    )?;
+
+    vm.add_helper(
+        "sol_set_return_data_",
+        Some(helper_sol_set_return_data_verify),
+        helper_sol_set_return_data,
+        Some(Box::new(*program_id)),
+    )?;
+    vm.add_helper(
+        "sol_get_return_data_",
+        Some(helper_sol_get_return_data_verify),
+        helper_sol_get_return_data,
+        None,
+    )?;
 
-    let heap = vec![0_u8; DEFAULT_HEAP_SIZE];
+    let heap = vec![0_u8; MAX_HEAP_SIZE];
     let heap_region = MemoryRegion::new_from_slice(&heap);
-    let context = Box::new(BPFAllocator::new(heap));
-    vm.register_helper_ex(
+    let mut allocator = BPFAllocator::new(heap);
+    allocator
+        .set_heap_size(DEFAULT_HEAP_SIZE)
+        .map_err(|e| Error::new(ErrorKind::Other, e.to_string()))?;
+    let allocator = Rc::new(RefCell::new(allocator));
+    vm.add_helper(
         "sol_alloc_free_",
         None,
         helper_sol_alloc_free,
-        Some(context),
+        Some(Box::new(allocator.clone())),
+    )?;
+    vm.add_helper(
+        "sol_request_heap_",
+        None,
+        helper_sol_request_heap,
+        Some(Box::new(HeapRequestContext {
+            allocator,
+            compute_meter,
+        })),
     )?;
 
     Ok(heap_region)
@@ -86,7 +209,17 @@ fn verify_string(addr: u64, ro_regions: &[MemoryRegion]) -> Result<(()), Error>
     ))
 }
 
-type Context = Option<Box<dyn Any + 'static>>;
+/// Returns true if `[addr, addr + len)` lies entirely within one of `regions`
+fn is_in_regions(addr: u64, len: u64, regions: &[MemoryRegion]) -> bool {
+    regions.iter().any(|region| {
+        region.addr <= addr
+            && addr
+                .checked_add(len)
+                .map_or(false, |end| end <= region.addr + region.len)
+    })
+}
+
+pub type Context = Option<Box<dyn Any + 'static>>;
 
 /// Abort helper functions, called when the BPF program calls `abort()`
 /// The verify function returns an error which will cause the BPF program
@@ -236,6 +369,203 @@ pub fn helper_sol_log_u64(
     0
 }
 
+/// Tracks the compute units left for the hashing and heap request helpers
+#[derive(Debug)]
+pub struct ComputeMeter {
+    remaining: u64,
+}
+
+impl ComputeMeter {
+    pub fn new(budget: u64) -> Self {
+        Self { remaining: budget }
+    }
+
+    /// Charges `cost` compute units, failing if the budget is exhausted
+    pub fn consume(&mut self, cost: u64) -> Result<(()), Error> {
+        if cost > self.remaining {
+            self.remaining = 0;
+            return Err(Error::new(
+                ErrorKind::Other,
+                "Error: Exceeded maximum compute budget",
+            ));
+        }
+        self.remaining -= cost;
+        Ok(())
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+}
+
+/// Hashing helper functions, called when the BPF program calls `sol_sha256_()`
+/// or `sol_keccak256_()`.  The verify function checks that the input is
+/// readable, that the 32 byte result is writable, and charges the hash
+/// against the invocation's compute budget
+pub fn helper_sol_hash_verify(
+    addr: u64,
+    len: u64,
+    result_addr: u64,
+    _arg4: u64,
+    _arg5: u64,
+    context: &mut Context,
+    ro_regions: &[MemoryRegion],
+    rw_regions: &[MemoryRegion],
+) -> Result<(()), Error> {
+    if !is_in_regions(addr, len, ro_regions) && !is_in_regions(addr, len, rw_regions) {
+        return Err(Error::new(
+            ErrorKind::Other,
+            "Error: Load segfault, bad hash input pointer",
+        ));
+    }
+    if !is_in_regions(result_addr, HASH_RESULT_SIZE, rw_regions) {
+        return Err(Error::new(
+            ErrorKind::Other,
+            "Error: Store segfault, bad hash result pointer",
+        ));
+    }
+    if let Some(context) = context {
+        if let Some(meter) = context.downcast_mut::<Rc<RefCell<ComputeMeter>>>() {
+            return meter
+                .borrow_mut()
+                .consume(HASH_BASE_COST + len / HASH_BYTES_PER_UNIT);
+        }
+    }
+    panic!("Failed to get hash context");
+}
+pub fn helper_sol_sha256(
+    addr: u64,
+    len: u64,
+    result_addr: u64,
+    _arg4: u64,
+    _arg5: u64,
+    _context: &mut Context,
+) -> u64 {
+    let input = unsafe { from_raw_parts(addr as *const u8, len as usize) };
+    let result = unsafe { from_raw_parts_mut(result_addr as *mut u8, HASH_RESULT_SIZE as usize) };
+    result.copy_from_slice(hash(input).as_ref());
+    0
+}
+pub fn helper_sol_keccak256(
+    addr: u64,
+    len: u64,
+    result_addr: u64,
+    _arg4: u64,
+    _arg5: u64,
+    _context: &mut Context,
+) -> u64 {
+    let input = unsafe { from_raw_parts(addr as *const u8, len as usize) };
+    let result = unsafe { from_raw_parts_mut(result_addr as *mut u8, HASH_RESULT_SIZE as usize) };
+    result.copy_from_slice(Keccak256::digest(input).as_slice());
+    0
+}
+
+/// Return data helper functions, called when the BPF program calls
+/// `sol_set_return_data_()`.  The verify function checks that the data is
+/// readable and no larger than `MAX_RETURN_DATA`
+pub fn helper_sol_set_return_data_verify(
+    addr: u64,
+    len: u64,
+    _arg3: u64,
+    _arg4: u64,
+    _arg5: u64,
+    _context: &mut Context,
+    ro_regions: &[MemoryRegion],
+    rw_regions: &[MemoryRegion],
+) -> Result<(()), Error> {
+    if len > MAX_RETURN_DATA as u64 {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "Error: Return data of {} bytes exceeds the maximum of {}",
+                len, MAX_RETURN_DATA
+            ),
+        ));
+    }
+    if len > 0 && !is_in_regions(addr, len, ro_regions) && !is_in_regions(addr, len, rw_regions) {
+        return Err(Error::new(
+            ErrorKind::Other,
+            "Error: Load segfault, bad return data pointer",
+        ));
+    }
+    Ok(())
+}
+pub fn helper_sol_set_return_data(
+    addr: u64,
+    len: u64,
+    _arg3: u64,
+    _arg4: u64,
+    _arg5: u64,
+    context: &mut Context,
+) -> u64 {
+    if let Some(context) = context {
+        if let Some(program_id) = context.downcast_ref::<Pubkey>() {
+            let data: &[u8] = if len == 0 {
+                &[]
+            } else {
+                unsafe { from_raw_parts(addr as *const u8, len as usize) }
+            };
+            // The verify function already rejected oversized data
+            return_data::set(program_id, data).unwrap();
+            return 0;
+        }
+    }
+    panic!("Failed to get set_return_data context");
+}
+
+/// Return data helper functions, called when the BPF program calls
+/// `sol_get_return_data_()`.  Copies up to `len` bytes of the current return
+/// data to `addr`, writes the id of the program that set it to
+/// `program_id_addr`, and returns the full length of the data, or 0 if there
+/// is none
+pub fn helper_sol_get_return_data_verify(
+    addr: u64,
+    len: u64,
+    program_id_addr: u64,
+    _arg4: u64,
+    _arg5: u64,
+    _context: &mut Context,
+    _ro_regions: &[MemoryRegion],
+    rw_regions: &[MemoryRegion],
+) -> Result<(()), Error> {
+    if len > 0 && !is_in_regions(addr, len, rw_regions) {
+        return Err(Error::new(
+            ErrorKind::Other,
+            "Error: Store segfault, bad return data pointer",
+        ));
+    }
+    if !is_in_regions(program_id_addr, PUBKEY_SIZE, rw_regions) {
+        return Err(Error::new(
+            ErrorKind::Other,
+            "Error: Store segfault, bad program id pointer",
+        ));
+    }
+    Ok(())
+}
+pub fn helper_sol_get_return_data(
+    addr: u64,
+    len: u64,
+    program_id_addr: u64,
+    _arg4: u64,
+    _arg5: u64,
+    _context: &mut Context,
+) -> u64 {
+    match return_data::get() {
+        Some(return_data) => {
+            let copy_len = return_data.data.len().min(len as usize);
+            if copy_len > 0 {
+                let buffer = unsafe { from_raw_parts_mut(addr as *mut u8, copy_len) };
+                buffer.copy_from_slice(&return_data.data[..copy_len]);
+            }
+            let program_id =
+                unsafe { from_raw_parts_mut(program_id_addr as *mut u8, PUBKEY_SIZE as usize) };
+            program_id.copy_from_slice(return_data.program_id.as_ref());
+            return_data.data.len() as u64
+        }
+        None => 0,
+    }
+}
+
 /// Dynamic memory allocation helper called when the BPF program calls
 /// `sol_alloc_free_()`.  The allocator is expected to allocate/free
 /// from/to a given chunk of memory and enforce size restrictions.  The
@@ -251,9 +581,11 @@ pub fn helper_sol_alloc_free(
     context: &mut Context,
 ) -> u64 {
     if let Some(context) = context {
-        if let Some(allocator) = context.downcast_mut::<BPFAllocator>() {
+        if let Some(allocator) = context.downcast_mut::<Rc<RefCell<BPFAllocator>>>() {
+            let mut allocator = allocator.borrow_mut();
             return {
-                let layout = Layout::from_size_align(size as usize, mem::align_of::<u8>()).unwrap();
+                let layout =
+                    Layout::from_size_align(size as usize, mem::align_of::<u64>()).unwrap();
                 if free_ptr == 0 {
                     match allocator.alloc(layout) {
                         Ok(ptr) => ptr as u64,
@@ -268,3 +600,283 @@ pub fn helper_sol_alloc_free(
     }
     panic!("Failed to get alloc_free context");
 }
+
+/// Context of `sol_request_heap_()`, the program's allocator and the compute
+/// budget larger heaps are charged against
+pub struct HeapRequestContext {
+    pub allocator: Rc<RefCell<BPFAllocator>>,
+    pub compute_meter: Rc<RefCell<ComputeMeter>>,
+}
+
+/// Number of heap pages above `DEFAULT_HEAP_SIZE` a heap of `size` bytes uses
+fn extra_heap_pages(size: usize) -> u64 {
+    let extra = size.saturating_sub(DEFAULT_HEAP_SIZE);
+    ((extra + HEAP_PAGE_SIZE - 1) / HEAP_PAGE_SIZE) as u64
+}
+
+/// Heap size request helper called when the BPF program calls
+/// `sol_request_heap_()`.  Resizes the program's heap to `size` bytes, up
+/// to `MAX_HEAP_SIZE`, charging `HEAP_COST_PER_PAGE` for every page the
+/// heap grows by above `DEFAULT_HEAP_SIZE`.  Returns the new size or
+/// `HEAP_REQUEST_DENIED` if the request could not be satisfied or paid for
+pub fn helper_sol_request_heap(
+    size: u64,
+    _arg2: u64,
+    _arg3: u64,
+    _arg4: u64,
+    _arg5: u64,
+    context: &mut Context,
+) -> u64 {
+    if let Some(context) = context {
+        if let Some(context) = context.downcast_mut::<HeapRequestContext>() {
+            if size > MAX_HEAP_SIZE as u64 {
+                warn!(
+                    "Heap request of {} bytes denied, the maximum is {} bytes",
+                    size, MAX_HEAP_SIZE
+                );
+                return HEAP_REQUEST_DENIED;
+            }
+            let size = size as usize;
+            let mut allocator = context.allocator.borrow_mut();
+            let cost = extra_heap_pages(size)
+                .saturating_sub(extra_heap_pages(allocator.heap_size()))
+                * HEAP_COST_PER_PAGE;
+            let mut compute_meter = context.compute_meter.borrow_mut();
+            if cost > compute_meter.remaining() {
+                warn!(
+                    "Heap request of {} bytes denied, it costs {} compute units",
+                    size, cost
+                );
+                return HEAP_REQUEST_DENIED;
+            }
+            return match allocator.set_heap_size(size) {
+                Ok(()) => {
+                    compute_meter.consume(cost).unwrap();
+                    size as u64
+                }
+                Err(e) => {
+                    warn!("Heap request of {} bytes failed: {}", size, e);
+                    HEAP_REQUEST_DENIED
+                }
+            };
+        };
+    }
+    panic!("Failed to get request_heap context");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_context(budget: u64) -> Context {
+        Some(Box::new(Rc::new(RefCell::new(ComputeMeter::new(budget)))))
+    }
+
+    #[test]
+    fn test_helper_sol_sha256() {
+        let input = b"gaggablaghblagh!";
+        let mut result = [0u8; 32];
+        let ro_regions = [MemoryRegion::new_from_slice(input)];
+        let rw_regions = [MemoryRegion::new_from_slice(&result)];
+        let mut context = hash_context(COMPUTE_BUDGET);
+
+        let addr = input.as_ptr() as u64;
+        let result_addr = result.as_mut_ptr() as u64;
+        helper_sol_hash_verify(
+            addr,
+            input.len() as u64,
+            result_addr,
+            0,
+            0,
+            &mut context,
+            &ro_regions,
+            &rw_regions,
+        )
+        .unwrap();
+        helper_sol_sha256(addr, input.len() as u64, result_addr, 0, 0, &mut context);
+        assert_eq!(result[..], hash(input).as_ref()[..]);
+
+        helper_sol_keccak256(addr, input.len() as u64, result_addr, 0, 0, &mut context);
+        assert_eq!(result[..], Keccak256::digest(input)[..]);
+    }
+
+    #[test]
+    fn test_helper_sol_hash_verify_bad_pointers() {
+        let input = [1u8; 16];
+        let result = [0u8; 32];
+        let ro_regions = [MemoryRegion::new_from_slice(&input)];
+        let rw_regions = [MemoryRegion::new_from_slice(&result)];
+        let mut context = hash_context(COMPUTE_BUDGET);
+
+        let addr = input.as_ptr() as u64;
+        let result_addr = result.as_ptr() as u64;
+        // Input runs past the end of its region
+        assert!(helper_sol_hash_verify(
+            addr,
+            17,
+            result_addr,
+            0,
+            0,
+            &mut context,
+            &ro_regions,
+            &rw_regions,
+        )
+        .is_err());
+        // Result is not writable
+        assert!(helper_sol_hash_verify(
+            addr,
+            16,
+            addr,
+            0,
+            0,
+            &mut context,
+            &ro_regions,
+            &rw_regions,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_helper_sol_return_data() {
+        return_data::clear();
+        let program_id = Pubkey::new_rand();
+        let mut context: Context = Some(Box::new(program_id));
+        let input = b"return me";
+        let mut buffer = [0u8; 4];
+        let mut returned_id = [0u8; 32];
+        let ro_regions = [MemoryRegion::new_from_slice(input)];
+        let rw_regions = [
+            MemoryRegion::new_from_slice(&buffer),
+            MemoryRegion::new_from_slice(&returned_id),
+        ];
+
+        let addr = buffer.as_mut_ptr() as u64;
+        let program_id_addr = returned_id.as_mut_ptr() as u64;
+        assert_eq!(
+            helper_sol_get_return_data(addr, 4, program_id_addr, 0, 0, &mut context),
+            0
+        );
+
+        let input_addr = input.as_ptr() as u64;
+        let input_len = input.len() as u64;
+        helper_sol_set_return_data_verify(
+            input_addr,
+            input_len,
+            0,
+            0,
+            0,
+            &mut context,
+            &ro_regions,
+            &rw_regions,
+        )
+        .unwrap();
+        helper_sol_set_return_data(input_addr, input_len, 0, 0, 0, &mut context);
+
+        helper_sol_get_return_data_verify(
+            addr,
+            4,
+            program_id_addr,
+            0,
+            0,
+            &mut context,
+            &ro_regions,
+            &rw_regions,
+        )
+        .unwrap();
+        // The full length is returned even though only 4 bytes fit
+        assert_eq!(
+            helper_sol_get_return_data(addr, 4, program_id_addr, 0, 0, &mut context),
+            input_len
+        );
+        assert_eq!(&buffer, b"retu");
+        assert_eq!(returned_id, program_id.as_ref()[..]);
+        return_data::clear();
+    }
+
+    #[test]
+    fn test_helper_sol_return_data_verify_bad_pointers() {
+        let mut context: Context = None;
+        let input = [1u8; 16];
+        let ro_regions = [MemoryRegion::new_from_slice(&input)];
+        let addr = input.as_ptr() as u64;
+        assert!(helper_sol_set_return_data_verify(
+            addr,
+            17,
+            0,
+            0,
+            0,
+            &mut context,
+            &ro_regions,
+            &[],
+        )
+        .is_err());
+        assert!(helper_sol_set_return_data_verify(
+            addr,
+            MAX_RETURN_DATA as u64 + 1,
+            0,
+            0,
+            0,
+            &mut context,
+            &ro_regions,
+            &ro_regions,
+        )
+        .is_err());
+        // Return data can't be copied into read-only memory
+        assert!(helper_sol_get_return_data_verify(
+            addr,
+            16,
+            addr,
+            0,
+            0,
+            &mut context,
+            &ro_regions,
+            &[],
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_helper_sol_request_heap() {
+        let mut allocator = BPFAllocator::new(vec![0_u8; MAX_HEAP_SIZE]);
+        allocator.set_heap_size(DEFAULT_HEAP_SIZE).unwrap();
+        let compute_meter = Rc::new(RefCell::new(ComputeMeter::new(3 * HEAP_COST_PER_PAGE)));
+        let mut context: Context = Some(Box::new(HeapRequestContext {
+            allocator: Rc::new(RefCell::new(allocator)),
+            compute_meter: compute_meter.clone(),
+        }));
+
+        // Growing by one page and a byte costs two pages
+        let size = (DEFAULT_HEAP_SIZE + HEAP_PAGE_SIZE + 1) as u64;
+        assert_eq!(
+            helper_sol_request_heap(size, 0, 0, 0, 0, &mut context),
+            size
+        );
+        assert_eq!(compute_meter.borrow().remaining(), HEAP_COST_PER_PAGE);
+        // Shrinking is free, an empty heap is granted, not mistaken for a denial
+        assert_eq!(helper_sol_request_heap(0, 0, 0, 0, 0, &mut context), 0);
+        assert_eq!(
+            helper_sol_request_heap(DEFAULT_HEAP_SIZE as u64, 0, 0, 0, 0, &mut context),
+            DEFAULT_HEAP_SIZE as u64
+        );
+        assert_eq!(compute_meter.borrow().remaining(), HEAP_COST_PER_PAGE);
+        // A heap the remaining budget can't pay for is denied
+        assert_eq!(
+            helper_sol_request_heap(size, 0, 0, 0, 0, &mut context),
+            HEAP_REQUEST_DENIED
+        );
+        assert_eq!(compute_meter.borrow().remaining(), HEAP_COST_PER_PAGE);
+        assert_eq!(
+            helper_sol_request_heap(MAX_HEAP_SIZE as u64 + 1, 0, 0, 0, 0, &mut context),
+            HEAP_REQUEST_DENIED
+        );
+    }
+
+    #[test]
+    fn test_compute_meter() {
+        let mut meter = ComputeMeter::new(HASH_BASE_COST * 2 + 10);
+        meter.consume(HASH_BASE_COST + 10).unwrap();
+        assert_eq!(meter.remaining(), HASH_BASE_COST);
+        assert!(meter.consume(HASH_BASE_COST + 1).is_err());
+        assert_eq!(meter.remaining(), 0);
+    }
+}
@@ -3,6 +3,9 @@ pub mod allocator_bump;
 pub mod allocator_system;
 pub mod bpf_verifier;
 pub mod helpers;
+pub mod profiler;
+pub mod return_data;
+pub mod tracer;
 
 #[macro_export]
 macro_rules! solana_bpf_loader {
@@ -26,13 +29,16 @@ use std::io::prelude::*;
 use std::io::Error;
 use std::mem;
 
-pub fn create_vm(prog: &[u8]) -> Result<(EbpfVmRaw, MemoryRegion), Error> {
+/// Most instructions a single program invocation may execute
+pub const MAX_INSTRUCTION_COUNT: u64 = 36000;
+
+pub fn create_vm(program_id: &Pubkey, prog: &[u8]) -> Result<(EbpfVmRaw, MemoryRegion), Error> {
     let mut vm = EbpfVmRaw::new(None)?;
     vm.set_verifier(bpf_verifier::check)?;
-    vm.set_max_instruction_count(36000)?;
+    vm.set_max_instruction_count(MAX_INSTRUCTION_COUNT)?;
     vm.set_elf(&prog)?;
 
-    let heap_region = helpers::register_helpers(&mut vm)?;
+    let heap_region = helpers::register_helpers(&mut vm, program_id)?;
 
     Ok((vm, heap_region))
 }
@@ -128,7 +134,7 @@ pub fn process_instruction(
                 let (progs, params) = keyed_accounts.split_at_mut(1);
                 let prog = &progs[0].account.data;
                 info!("Call BPF program");
-                let (mut vm, heap_region) = match create_vm(prog) {
+                let (mut vm, heap_region) = match create_vm(progs[0].unsigned_key(), prog) {
                     Ok(info) => info,
                     Err(e) => {
                         warn!("Failed to create BPF VM: {}", e);
@@ -137,6 +143,7 @@ pub fn process_instruction(
                 };
                 let mut v = serialize_parameters(program_id, params, &data);
 
+                profiler::profile_program(progs[0].unsigned_key(), prog, &v);
                 match vm.execute_program(v.as_mut_slice(), &[], &[heap_region]) {
                     Ok(status) => {
                         if 0 == status {
@@ -0,0 +1,348 @@
+//! Optional execution profiling of BPF programs, enabled by setting the
+//! `SOLANA_BPF_PROFILE` environment variable.  Before a program runs, the
+//! tracer replays it on a copy of its input and reports every call into and
+//! return out of a function, so each instruction executed is attributed to
+//! the function that executed it.  A running report is kept per program id
+//! so program developers can see which functions bring them close to the
+//! instruction budget.
+
+use crate::return_data;
+use crate::tracer;
+use lazy_static::lazy_static;
+use log::*;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// Symbol the BPF loader enters every program through
+pub const ENTRYPOINT_SYMBOL: &str = "entrypoint";
+
+lazy_static! {
+    static ref PROFILER: Option<Mutex<Profiler>> = if env::var("SOLANA_BPF_PROFILE").is_ok() {
+        Some(Mutex::new(Profiler::default()))
+    } else {
+        None
+    };
+}
+
+/// How a call handed control back to its caller
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Exit {
+    /// Returned successfully
+    Success,
+    /// Returned an error status
+    Failure,
+    /// Halted by the VM before it could return
+    Aborted,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SymbolProfile {
+    /// Number of times the symbol was called
+    pub calls: u64,
+    /// Number of calls that returned to the caller
+    pub returns: u64,
+    /// Number of returns that reported an error
+    pub failures: u64,
+    /// Instructions executed across all calls, including those executed by
+    /// the functions it called
+    pub total_instructions: u64,
+    /// Instructions executed by the symbol itself across all calls
+    pub self_instructions: u64,
+    /// Largest number of instructions executed by a single call
+    pub max_instructions: u64,
+}
+
+impl SymbolProfile {
+    pub fn average_instructions(&self) -> u64 {
+        if self.calls == 0 {
+            0
+        } else {
+            self.total_instructions / self.calls
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Profiler {
+    profiles: HashMap<(Pubkey, String), SymbolProfile>,
+}
+
+impl Profiler {
+    fn profile_mut(&mut self, program_id: &Pubkey, symbol: &str) -> &mut SymbolProfile {
+        self.profiles
+            .entry((*program_id, symbol.to_string()))
+            .or_default()
+    }
+
+    /// Records a call into `symbol` by `program_id`
+    pub fn record_call(&mut self, program_id: &Pubkey, symbol: &str) {
+        self.profile_mut(program_id, symbol).calls += 1;
+    }
+
+    /// Records the end of a call into `symbol` that executed
+    /// `instructions` instructions, `self_instructions` of them outside
+    /// the functions it called
+    pub fn record_exit(
+        &mut self,
+        program_id: &Pubkey,
+        symbol: &str,
+        instructions: u64,
+        self_instructions: u64,
+        exit: Exit,
+    ) -> &SymbolProfile {
+        let profile = self.profile_mut(program_id, symbol);
+        match exit {
+            Exit::Success => profile.returns += 1,
+            Exit::Failure => {
+                profile.returns += 1;
+                profile.failures += 1;
+            }
+            Exit::Aborted => (),
+        }
+        profile.total_instructions += instructions;
+        profile.self_instructions += self_instructions;
+        profile.max_instructions = profile.max_instructions.max(instructions);
+        profile
+    }
+
+    pub fn get(&self, program_id: &Pubkey, symbol: &str) -> Option<&SymbolProfile> {
+        self.profiles.get(&(*program_id, symbol.to_string()))
+    }
+
+    /// Formats every recorded symbol, the one that executed the most
+    /// instructions itself first
+    pub fn report(&self) -> String {
+        let mut profiles: Vec<_> = self.profiles.iter().collect();
+        profiles.sort_by(|(_, a), (_, b)| {
+            b.self_instructions
+                .cmp(&a.self_instructions)
+                .then(b.total_instructions.cmp(&a.total_instructions))
+                .then(b.calls.cmp(&a.calls))
+        });
+
+        let mut report = format!(
+            "{:<44} {:<20} {:>8} {:>8} {:>8} {:>12} {:>12} {:>12} {:>12}\n",
+            "program", "symbol", "calls", "returns", "failures", "self", "total", "average", "max"
+        );
+        for ((program_id, symbol), profile) in profiles {
+            writeln!(
+                report,
+                "{:<44} {:<20} {:>8} {:>8} {:>8} {:>12} {:>12} {:>12} {:>12}",
+                program_id.to_string(),
+                symbol,
+                profile.calls,
+                profile.returns,
+                profile.failures,
+                profile.self_instructions,
+                profile.total_instructions,
+                profile.average_instructions(),
+                profile.max_instructions
+            )
+            .unwrap();
+        }
+        report
+    }
+}
+
+struct Frame {
+    symbol: String,
+    /// Instructions the run had executed when the call was made
+    entry_count: u64,
+    /// Instructions executed by the functions this one called
+    callee_instructions: u64,
+}
+
+/// The functions called by one traced run of a program, innermost last
+pub struct CallStack<'a> {
+    profiler: &'a mut Profiler,
+    program_id: Pubkey,
+    frames: Vec<Frame>,
+}
+
+impl<'a> CallStack<'a> {
+    pub fn new(profiler: &'a mut Profiler, program_id: &Pubkey) -> Self {
+        Self {
+            profiler,
+            program_id: *program_id,
+            frames: vec![],
+        }
+    }
+
+    /// Records a call into `symbol` once the run has executed
+    /// `instruction_count` instructions
+    pub fn call(&mut self, symbol: &str, instruction_count: u64) {
+        self.profiler.record_call(&self.program_id, symbol);
+        self.frames.push(Frame {
+            symbol: symbol.to_string(),
+            entry_count: instruction_count,
+            callee_instructions: 0,
+        });
+    }
+
+    /// Records the innermost call returning once the run has executed
+    /// `instruction_count` instructions
+    pub fn ret(&mut self, instruction_count: u64, exit: Exit) {
+        if let Some(frame) = self.frames.pop() {
+            let instructions = instruction_count - frame.entry_count;
+            if let Some(caller) = self.frames.last_mut() {
+                caller.callee_instructions += instructions;
+            }
+            self.profiler.record_exit(
+                &self.program_id,
+                &frame.symbol,
+                instructions,
+                instructions - frame.callee_instructions,
+                exit,
+            );
+        }
+    }
+
+    /// Records the run being halted, none of the calls in progress return
+    pub fn abort(&mut self, instruction_count: u64) {
+        while !self.frames.is_empty() {
+            self.ret(instruction_count, Exit::Aborted);
+        }
+    }
+}
+
+/// Returns the process-wide profiler, or None if profiling is disabled
+pub fn get_profiler() -> Option<&'static Mutex<Profiler>> {
+    PROFILER.as_ref()
+}
+
+/// Replays the program `prog` of `program_id` on a copy of `input` and
+/// attributes the instructions it executes to the functions it calls.  Does
+/// nothing unless profiling is enabled.  The replay makes real helper calls,
+/// so a profiled program logs everything twice, but it neither sees nor
+/// leaves behind return data of its own
+pub fn profile_program(program_id: &Pubkey, prog: &[u8], input: &[u8]) {
+    if let Some(profiler) = get_profiler() {
+        let mut input = input.to_vec();
+        let return_data = return_data::get();
+        let mut profiler = profiler.lock().unwrap();
+        let result = tracer::trace_elf(
+            program_id,
+            prog,
+            &mut input,
+            &mut CallStack::new(&mut profiler, program_id),
+        );
+        return_data::swap(return_data);
+
+        match result {
+            Ok(instruction_count) => {
+                let profile = profiler.get(program_id, ENTRYPOINT_SYMBOL).unwrap();
+                info!(
+                    "BPF profile: {} {}: {} instructions, {} calls, {} average, {} max",
+                    program_id,
+                    ENTRYPOINT_SYMBOL,
+                    instruction_count,
+                    profile.calls,
+                    profile.average_instructions(),
+                    profile.max_instructions
+                );
+            }
+            Err(e) => warn!("BPF profile: {} failed to trace: {}", program_id, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiler_record() {
+        let program_id = Pubkey::new_rand();
+        let mut profiler = Profiler::default();
+        profiler.record_call(&program_id, ENTRYPOINT_SYMBOL);
+        profiler.record_exit(&program_id, ENTRYPOINT_SYMBOL, 10, 4, Exit::Success);
+        profiler.record_call(&program_id, ENTRYPOINT_SYMBOL);
+        profiler.record_exit(&program_id, ENTRYPOINT_SYMBOL, 30, 30, Exit::Failure);
+        profiler.record_call(&program_id, ENTRYPOINT_SYMBOL);
+        profiler.record_exit(&program_id, ENTRYPOINT_SYMBOL, 20, 20, Exit::Aborted);
+
+        assert_eq!(
+            profiler.get(&program_id, ENTRYPOINT_SYMBOL),
+            Some(&SymbolProfile {
+                calls: 3,
+                returns: 2,
+                failures: 1,
+                total_instructions: 60,
+                self_instructions: 54,
+                max_instructions: 30,
+            })
+        );
+        assert_eq!(
+            profiler
+                .get(&program_id, ENTRYPOINT_SYMBOL)
+                .unwrap()
+                .average_instructions(),
+            20
+        );
+        assert_eq!(profiler.get(&Pubkey::new_rand(), ENTRYPOINT_SYMBOL), None);
+    }
+
+    #[test]
+    fn test_call_stack() {
+        let program_id = Pubkey::new_rand();
+        let mut profiler = Profiler::default();
+        {
+            let mut call_stack = CallStack::new(&mut profiler, &program_id);
+            call_stack.call(ENTRYPOINT_SYMBOL, 0);
+            call_stack.call("helper_function", 5);
+            call_stack.call("sol_log_", 7);
+            call_stack.ret(7, Exit::Success);
+            call_stack.ret(12, Exit::Success);
+            call_stack.call("helper_function", 15);
+            call_stack.ret(19, Exit::Success);
+            call_stack.ret(20, Exit::Success);
+
+            call_stack.call(ENTRYPOINT_SYMBOL, 0);
+            call_stack.call("abort", 3);
+            call_stack.abort(3);
+        }
+
+        assert_eq!(
+            profiler.get(&program_id, ENTRYPOINT_SYMBOL),
+            Some(&SymbolProfile {
+                calls: 2,
+                returns: 1,
+                failures: 0,
+                total_instructions: 23,
+                self_instructions: 12,
+                max_instructions: 20,
+            })
+        );
+        // instructions executed by callees are attributed to them
+        assert_eq!(
+            profiler.get(&program_id, "helper_function"),
+            Some(&SymbolProfile {
+                calls: 2,
+                returns: 2,
+                failures: 0,
+                total_instructions: 11,
+                self_instructions: 11,
+                max_instructions: 7,
+            })
+        );
+        assert_eq!(profiler.get(&program_id, "sol_log_").unwrap().returns, 1);
+        // a call without a return means the helper halted the program
+        assert_eq!(
+            profiler.get(&program_id, "abort"),
+            Some(&SymbolProfile {
+                calls: 1,
+                ..SymbolProfile::default()
+            })
+        );
+
+        let report = profiler.report();
+        let lines: Vec<_> = report.lines().collect();
+        assert_eq!(lines.len(), 5);
+        // the symbol that executed the most instructions itself first
+        assert!(lines[1].contains(ENTRYPOINT_SYMBOL));
+        assert!(lines[2].contains("helper_function"));
+    }
+}
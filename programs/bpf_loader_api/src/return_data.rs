@@ -0,0 +1,87 @@
+//! Return data handed back by BPF programs.  Programs set it with
+//! `sol_set_return_data_()` and read it back, along with whatever an earlier
+//! instruction left behind, with `sol_get_return_data_()`.  The data is kept
+//! per thread and per copy of this crate, so the runtime swaps the
+//! transaction's return data in before each instruction and back out
+//! afterwards, in-process or through the loader library's
+//! `swap_return_data()` export.
+
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::{TransactionReturnData, MAX_RETURN_DATA};
+use std::cell::RefCell;
+
+thread_local! {
+    static RETURN_DATA: RefCell<Option<TransactionReturnData>> = RefCell::new(None);
+}
+
+/// Replaces the current return data, fails if `data` is larger than `MAX_RETURN_DATA`
+pub fn set(program_id: &Pubkey, data: &[u8]) -> Result<(), String> {
+    if data.len() > MAX_RETURN_DATA {
+        return Err(format!(
+            "Return data of {} bytes exceeds the maximum of {}",
+            data.len(),
+            MAX_RETURN_DATA
+        ));
+    }
+    RETURN_DATA.with(|return_data| {
+        *return_data.borrow_mut() = Some(TransactionReturnData {
+            program_id: *program_id,
+            data: data.to_vec(),
+        })
+    });
+    Ok(())
+}
+
+pub fn get() -> Option<TransactionReturnData> {
+    RETURN_DATA.with(|return_data| return_data.borrow().clone())
+}
+
+/// Removes and returns the current return data
+pub fn take() -> Option<TransactionReturnData> {
+    RETURN_DATA.with(|return_data| return_data.borrow_mut().take())
+}
+
+/// Replaces the current return data with `return_data`, returning what it replaced
+pub fn swap(return_data: Option<TransactionReturnData>) -> Option<TransactionReturnData> {
+    RETURN_DATA.with(|current| current.replace(return_data))
+}
+
+pub fn clear() {
+    RETURN_DATA.with(|return_data| *return_data.borrow_mut() = None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_return_data() {
+        clear();
+        assert_eq!(get(), None);
+
+        let program_id = Pubkey::new_rand();
+        set(&program_id, &[1, 2, 3]).unwrap();
+        let expected = TransactionReturnData {
+            program_id,
+            data: vec![1, 2, 3],
+        };
+        assert_eq!(get(), Some(expected.clone()));
+
+        // Oversized data leaves the previous data in place
+        assert!(set(&program_id, &[0; MAX_RETURN_DATA + 1]).is_err());
+        assert_eq!(take(), Some(expected));
+        assert_eq!(get(), None);
+
+        set(&program_id, &[]).unwrap();
+        clear();
+        assert_eq!(take(), None);
+
+        let expected = Some(TransactionReturnData {
+            program_id,
+            data: vec![4, 5],
+        });
+        assert_eq!(swap(expected.clone()), None);
+        assert_eq!(swap(None), expected);
+        assert_eq!(get(), None);
+    }
+}
@@ -0,0 +1,599 @@
+//! Replays a BPF program one instruction at a time on behalf of the
+//! profiler.  The VM only reports how many instructions a whole run
+//! executed, so the tracer follows the VM's semantics itself and reports
+//! every call into and return out of a function to a `CallStack`.  The
+//! relocated text, read-only sections and BPF-to-BPF call targets come from
+//! the VM's own ELF loader, and helper calls go to the same helpers the VM
+//! registers.
+
+use crate::helpers::{self, Context, Helper, HelperRegistry, HelperVerifier};
+use crate::profiler::{CallStack, Exit, ENTRYPOINT_SYMBOL};
+use crate::MAX_INSTRUCTION_COUNT;
+use goblin::elf::{sym, Elf};
+use solana_rbpf::elf::EBpfElf;
+use solana_rbpf::{ebpf, MemoryRegion};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::ptr;
+
+/// Deepest chain of BPF-to-BPF calls the VM allows
+const MAX_CALL_DEPTH: usize = 20;
+
+struct HelperInfo {
+    name: String,
+    verifier: Option<HelperVerifier>,
+    helper: Helper,
+    context: Context,
+}
+
+struct Frame {
+    saved_registers: [u64; 4],
+    return_pc: usize,
+}
+
+#[derive(Default)]
+struct Tracer<'a> {
+    helpers: HashMap<u32, HelperInfo>,
+    /// Resolves BPF-to-BPF calls, raw programs have none
+    elf: Option<&'a EBpfElf>,
+    /// Names of the program's functions by the instruction they start at
+    functions: HashMap<usize, String>,
+}
+
+impl<'a> HelperRegistry for Tracer<'a> {
+    fn add_helper(
+        &mut self,
+        name: &str,
+        verifier: Option<HelperVerifier>,
+        helper: Helper,
+        context: Context,
+    ) -> Result<(()), Error> {
+        self.helpers.insert(
+            ebpf::hash_symbol_name(name.as_bytes()),
+            HelperInfo {
+                name: name.to_string(),
+                verifier,
+                helper,
+                context,
+            },
+        );
+        Ok(())
+    }
+}
+
+fn trace_error<S: AsRef<str>>(msg: S, pc: usize) -> Error {
+    Error::new(
+        ErrorKind::Other,
+        format!("[Tracer] Error: {} (insn #{})", msg.as_ref(), pc),
+    )
+}
+
+/// Returns the host address of `[addr, addr + len)` if it lies within one of
+/// `regions`
+fn check_mem(addr: u64, len: u64, regions: &[MemoryRegion], pc: usize) -> Result<u64, Error> {
+    if regions.iter().any(|region| {
+        region.addr <= addr
+            && addr
+                .checked_add(len)
+                .map_or(false, |end| end <= region.addr + region.len)
+    }) {
+        Ok(addr)
+    } else {
+        Err(trace_error(
+            format!(
+                "out of bounds memory access of {} bytes at {:#x}",
+                len, addr
+            ),
+            pc,
+        ))
+    }
+}
+
+/// Returns the name of every function in `elf_bytes` by the instruction of
+/// the text section it starts at
+fn function_symbols(elf_bytes: &[u8]) -> Result<HashMap<usize, String>, Error> {
+    let elf = Elf::parse(elf_bytes).map_err(|e| Error::new(ErrorKind::Other, e.to_string()))?;
+    let text = elf
+        .section_headers
+        .iter()
+        .find(|header| {
+            elf.shdr_strtab
+                .get(header.sh_name)
+                .and_then(|name| name.ok())
+                .map_or(false, |name| name == ".text")
+        })
+        .ok_or_else(|| Error::new(ErrorKind::Other, "ELF has no text section"))?;
+
+    let mut functions = HashMap::new();
+    for symbol in elf.syms.iter() {
+        if symbol.st_type() != sym::STT_FUNC
+            || symbol.st_value < text.sh_addr
+            || symbol.st_value >= text.sh_addr + text.sh_size
+        {
+            continue;
+        }
+        if let Some(Ok(name)) = elf.strtab.get(symbol.st_name) {
+            let pc = (symbol.st_value - text.sh_addr) as usize / ebpf::INSN_SIZE;
+            functions.insert(pc, name.to_string());
+        }
+    }
+    Ok(functions)
+}
+
+/// Replays the ELF program `elf_bytes` of `program_id` on `input`, reporting
+/// its calls and returns to `call_stack`, and returns the number of
+/// instructions it executed
+pub fn trace_elf(
+    program_id: &Pubkey,
+    elf_bytes: &[u8],
+    input: &mut [u8],
+    call_stack: &mut CallStack,
+) -> Result<u64, Error> {
+    let elf = EBpfElf::load(elf_bytes)?;
+    let mut tracer = Tracer {
+        elf: Some(&elf),
+        functions: function_symbols(elf_bytes)?,
+        ..Tracer::default()
+    };
+    let ro_regions: Vec<_> = elf
+        .get_ro_sections()?
+        .iter()
+        .map(|section| MemoryRegion::new_from_slice(section))
+        .collect();
+    tracer.run(
+        program_id,
+        elf.get_text_bytes()?,
+        elf.get_entrypoint_instruction_offset()?,
+        &ro_regions,
+        input,
+        call_stack,
+    )
+}
+
+/// Replays the raw program `prog`, which starts at its first instruction
+pub fn trace_raw(
+    program_id: &Pubkey,
+    prog: &[u8],
+    input: &mut [u8],
+    call_stack: &mut CallStack,
+) -> Result<u64, Error> {
+    Tracer::default().run(program_id, prog, 0, &[], input, call_stack)
+}
+
+impl<'a> Tracer<'a> {
+    fn function_name(&self, pc: usize) -> String {
+        self.functions
+            .get(&pc)
+            .cloned()
+            .unwrap_or_else(|| format!("function_{}", pc))
+    }
+
+    fn run(
+        &mut self,
+        program_id: &Pubkey,
+        prog: &[u8],
+        entry_pc: usize,
+        ro_sections: &[MemoryRegion],
+        input: &mut [u8],
+        call_stack: &mut CallStack,
+    ) -> Result<u64, Error> {
+        let heap_region = helpers::register_helpers(self, program_id)?;
+        let stack = vec![0u8; ebpf::STACK_SIZE * MAX_CALL_DEPTH];
+        let stack_addr = stack.as_ptr() as u64;
+        let input_addr = input.as_ptr() as u64;
+
+        let rw_regions = [
+            MemoryRegion::new_from_slice(input),
+            MemoryRegion::new_from_slice(&stack),
+            heap_region,
+        ];
+        let mut ro_regions = rw_regions.to_vec();
+        ro_regions.extend_from_slice(ro_sections);
+
+        let mut instruction_count = 0;
+        call_stack.call(ENTRYPOINT_SYMBOL, instruction_count);
+        let result = self.execute(
+            prog,
+            entry_pc,
+            input_addr,
+            stack_addr,
+            &ro_regions,
+            &rw_regions,
+            &mut instruction_count,
+            call_stack,
+        );
+        match result {
+            Ok(0) => call_stack.ret(instruction_count, Exit::Failure),
+            Ok(_) => call_stack.ret(instruction_count, Exit::Success),
+            Err(_) => call_stack.abort(instruction_count),
+        }
+        result.map(|_| instruction_count)
+    }
+
+    #[allow(clippy::cognitive_complexity)]
+    #[allow(clippy::too_many_arguments)]
+    fn execute(
+        &mut self,
+        prog: &[u8],
+        entry_pc: usize,
+        input_addr: u64,
+        stack_addr: u64,
+        ro_regions: &[MemoryRegion],
+        rw_regions: &[MemoryRegion],
+        instruction_count: &mut u64,
+        call_stack: &mut CallStack,
+    ) -> Result<u64, Error> {
+        let mut reg = [0u64; 11];
+        reg[1] = input_addr;
+        reg[10] = stack_addr + ebpf::STACK_SIZE as u64;
+        let mut frames: Vec<Frame> = vec![];
+
+        let mut pc = entry_pc;
+        while pc * ebpf::INSN_SIZE < prog.len() {
+            let insn_pc = pc;
+            let insn = ebpf::get_insn(prog, pc);
+            pc += 1;
+            if *instruction_count >= MAX_INSTRUCTION_COUNT {
+                return Err(trace_error(
+                    "exceeded maximum number of instructions",
+                    insn_pc,
+                ));
+            }
+            *instruction_count += 1;
+
+            let dst = insn.dst as usize;
+            let src = insn.src as usize;
+            let imm = insn.imm as u64;
+            let load_addr = |base: u64, len: u64| {
+                check_mem(
+                    (base as i64).wrapping_add(i64::from(insn.off)) as u64,
+                    len,
+                    ro_regions,
+                    insn_pc,
+                )
+            };
+            let store_addr = |base: u64, len: u64| {
+                check_mem(
+                    (base as i64).wrapping_add(i64::from(insn.off)) as u64,
+                    len,
+                    rw_regions,
+                    insn_pc,
+                )
+            };
+            let divisor = |divisor: u64| {
+                if divisor == 0 {
+                    Err(trace_error("division by 0", insn_pc))
+                } else {
+                    Ok(divisor)
+                }
+            };
+
+            // Every access has been bounds checked against the regions the
+            // VM would grant the program
+            unsafe {
+                match insn.opc {
+                    ebpf::LD_DW_IMM => {
+                        let next_insn = ebpf::get_insn(prog, pc);
+                        pc += 1;
+                        reg[dst] = u64::from(insn.imm as u32) + ((next_insn.imm as u64) << 32);
+                    }
+
+                    ebpf::LD_B_REG => {
+                        reg[dst] = u64::from(ptr::read(load_addr(reg[src], 1)? as *const u8))
+                    }
+                    ebpf::LD_H_REG => {
+                        reg[dst] =
+                            u64::from(ptr::read_unaligned(load_addr(reg[src], 2)? as *const u16))
+                    }
+                    ebpf::LD_W_REG => {
+                        reg[dst] =
+                            u64::from(ptr::read_unaligned(load_addr(reg[src], 4)? as *const u32))
+                    }
+                    ebpf::LD_DW_REG => {
+                        reg[dst] = ptr::read_unaligned(load_addr(reg[src], 8)? as *const u64)
+                    }
+
+                    ebpf::ST_B_IMM => ptr::write(store_addr(reg[dst], 1)? as *mut u8, imm as u8),
+                    ebpf::ST_H_IMM => {
+                        ptr::write_unaligned(store_addr(reg[dst], 2)? as *mut u16, imm as u16)
+                    }
+                    ebpf::ST_W_IMM => {
+                        ptr::write_unaligned(store_addr(reg[dst], 4)? as *mut u32, imm as u32)
+                    }
+                    ebpf::ST_DW_IMM => {
+                        ptr::write_unaligned(store_addr(reg[dst], 8)? as *mut u64, imm)
+                    }
+
+                    ebpf::ST_B_REG => {
+                        ptr::write(store_addr(reg[dst], 1)? as *mut u8, reg[src] as u8)
+                    }
+                    ebpf::ST_H_REG => {
+                        ptr::write_unaligned(store_addr(reg[dst], 2)? as *mut u16, reg[src] as u16)
+                    }
+                    ebpf::ST_W_REG => {
+                        ptr::write_unaligned(store_addr(reg[dst], 4)? as *mut u32, reg[src] as u32)
+                    }
+                    ebpf::ST_DW_REG => {
+                        ptr::write_unaligned(store_addr(reg[dst], 8)? as *mut u64, reg[src])
+                    }
+
+                    ebpf::ADD32_IMM => {
+                        reg[dst] = u64::from((reg[dst] as u32).wrapping_add(imm as u32))
+                    }
+                    ebpf::ADD32_REG => {
+                        reg[dst] = u64::from((reg[dst] as u32).wrapping_add(reg[src] as u32))
+                    }
+                    ebpf::SUB32_IMM => {
+                        reg[dst] = u64::from((reg[dst] as u32).wrapping_sub(imm as u32))
+                    }
+                    ebpf::SUB32_REG => {
+                        reg[dst] = u64::from((reg[dst] as u32).wrapping_sub(reg[src] as u32))
+                    }
+                    ebpf::MUL32_IMM => {
+                        reg[dst] = u64::from((reg[dst] as u32).wrapping_mul(imm as u32))
+                    }
+                    ebpf::MUL32_REG => {
+                        reg[dst] = u64::from((reg[dst] as u32).wrapping_mul(reg[src] as u32))
+                    }
+                    ebpf::DIV32_IMM => reg[dst] = u64::from(reg[dst] as u32 / divisor(imm)? as u32),
+                    ebpf::DIV32_REG => {
+                        reg[dst] =
+                            u64::from(reg[dst] as u32 / divisor(u64::from(reg[src] as u32))? as u32)
+                    }
+                    ebpf::OR32_IMM => reg[dst] = u64::from(reg[dst] as u32 | imm as u32),
+                    ebpf::OR32_REG => reg[dst] = u64::from(reg[dst] as u32 | reg[src] as u32),
+                    ebpf::AND32_IMM => reg[dst] = u64::from(reg[dst] as u32 & imm as u32),
+                    ebpf::AND32_REG => reg[dst] = u64::from(reg[dst] as u32 & reg[src] as u32),
+                    ebpf::LSH32_IMM => {
+                        reg[dst] = u64::from((reg[dst] as u32).wrapping_shl(imm as u32))
+                    }
+                    ebpf::LSH32_REG => {
+                        reg[dst] = u64::from((reg[dst] as u32).wrapping_shl(reg[src] as u32))
+                    }
+                    ebpf::RSH32_IMM => {
+                        reg[dst] = u64::from((reg[dst] as u32).wrapping_shr(imm as u32))
+                    }
+                    ebpf::RSH32_REG => {
+                        reg[dst] = u64::from((reg[dst] as u32).wrapping_shr(reg[src] as u32))
+                    }
+                    ebpf::NEG32 => reg[dst] = u64::from((reg[dst] as i32).wrapping_neg() as u32),
+                    ebpf::MOD32_IMM => reg[dst] = u64::from(reg[dst] as u32 % divisor(imm)? as u32),
+                    ebpf::MOD32_REG => {
+                        reg[dst] =
+                            u64::from(reg[dst] as u32 % divisor(u64::from(reg[src] as u32))? as u32)
+                    }
+                    ebpf::XOR32_IMM => reg[dst] = u64::from(reg[dst] as u32 ^ imm as u32),
+                    ebpf::XOR32_REG => reg[dst] = u64::from(reg[dst] as u32 ^ reg[src] as u32),
+                    ebpf::MOV32_IMM => reg[dst] = u64::from(imm as u32),
+                    ebpf::MOV32_REG => reg[dst] = u64::from(reg[src] as u32),
+                    ebpf::ARSH32_IMM => {
+                        reg[dst] = u64::from((reg[dst] as i32).wrapping_shr(imm as u32) as u32)
+                    }
+                    ebpf::ARSH32_REG => {
+                        reg[dst] = u64::from((reg[dst] as i32).wrapping_shr(reg[src] as u32) as u32)
+                    }
+                    ebpf::LE => {
+                        reg[dst] = match insn.imm {
+                            16 => u64::from((reg[dst] as u16).to_le()),
+                            32 => u64::from((reg[dst] as u32).to_le()),
+                            _ => reg[dst].to_le(),
+                        }
+                    }
+                    ebpf::BE => {
+                        reg[dst] = match insn.imm {
+                            16 => u64::from((reg[dst] as u16).to_be()),
+                            32 => u64::from((reg[dst] as u32).to_be()),
+                            _ => reg[dst].to_be(),
+                        }
+                    }
+
+                    ebpf::ADD64_IMM => reg[dst] = reg[dst].wrapping_add(imm),
+                    ebpf::ADD64_REG => reg[dst] = reg[dst].wrapping_add(reg[src]),
+                    ebpf::SUB64_IMM => reg[dst] = reg[dst].wrapping_sub(imm),
+                    ebpf::SUB64_REG => reg[dst] = reg[dst].wrapping_sub(reg[src]),
+                    ebpf::MUL64_IMM => reg[dst] = reg[dst].wrapping_mul(imm),
+                    ebpf::MUL64_REG => reg[dst] = reg[dst].wrapping_mul(reg[src]),
+                    ebpf::DIV64_IMM => reg[dst] /= divisor(imm)?,
+                    ebpf::DIV64_REG => reg[dst] /= divisor(reg[src])?,
+                    ebpf::OR64_IMM => reg[dst] |= imm,
+                    ebpf::OR64_REG => reg[dst] |= reg[src],
+                    ebpf::AND64_IMM => reg[dst] &= imm,
+                    ebpf::AND64_REG => reg[dst] &= reg[src],
+                    ebpf::LSH64_IMM => reg[dst] = reg[dst].wrapping_shl(imm as u32),
+                    ebpf::LSH64_REG => reg[dst] = reg[dst].wrapping_shl(reg[src] as u32),
+                    ebpf::RSH64_IMM => reg[dst] = reg[dst].wrapping_shr(imm as u32),
+                    ebpf::RSH64_REG => reg[dst] = reg[dst].wrapping_shr(reg[src] as u32),
+                    ebpf::NEG64 => reg[dst] = (reg[dst] as i64).wrapping_neg() as u64,
+                    ebpf::MOD64_IMM => reg[dst] %= divisor(imm)?,
+                    ebpf::MOD64_REG => reg[dst] %= divisor(reg[src])?,
+                    ebpf::XOR64_IMM => reg[dst] ^= imm,
+                    ebpf::XOR64_REG => reg[dst] ^= reg[src],
+                    ebpf::MOV64_IMM => reg[dst] = imm,
+                    ebpf::MOV64_REG => reg[dst] = reg[src],
+                    ebpf::ARSH64_IMM => {
+                        reg[dst] = (reg[dst] as i64).wrapping_shr(imm as u32) as u64
+                    }
+                    ebpf::ARSH64_REG => {
+                        reg[dst] = (reg[dst] as i64).wrapping_shr(reg[src] as u32) as u64
+                    }
+
+                    ebpf::JA
+                    | ebpf::JEQ_IMM
+                    | ebpf::JEQ_REG
+                    | ebpf::JGT_IMM
+                    | ebpf::JGT_REG
+                    | ebpf::JGE_IMM
+                    | ebpf::JGE_REG
+                    | ebpf::JLT_IMM
+                    | ebpf::JLT_REG
+                    | ebpf::JLE_IMM
+                    | ebpf::JLE_REG
+                    | ebpf::JSET_IMM
+                    | ebpf::JSET_REG
+                    | ebpf::JNE_IMM
+                    | ebpf::JNE_REG
+                    | ebpf::JSGT_IMM
+                    | ebpf::JSGT_REG
+                    | ebpf::JSGE_IMM
+                    | ebpf::JSGE_REG
+                    | ebpf::JSLT_IMM
+                    | ebpf::JSLT_REG
+                    | ebpf::JSLE_IMM
+                    | ebpf::JSLE_REG => {
+                        let a = reg[dst];
+                        let b = if insn.opc & ebpf::BPF_X != 0 {
+                            reg[src]
+                        } else {
+                            imm
+                        };
+                        let taken = match insn.opc {
+                            ebpf::JA => true,
+                            ebpf::JEQ_IMM | ebpf::JEQ_REG => a == b,
+                            ebpf::JGT_IMM | ebpf::JGT_REG => a > b,
+                            ebpf::JGE_IMM | ebpf::JGE_REG => a >= b,
+                            ebpf::JLT_IMM | ebpf::JLT_REG => a < b,
+                            ebpf::JLE_IMM | ebpf::JLE_REG => a <= b,
+                            ebpf::JSET_IMM | ebpf::JSET_REG => a & b != 0,
+                            ebpf::JNE_IMM | ebpf::JNE_REG => a != b,
+                            ebpf::JSGT_IMM | ebpf::JSGT_REG => a as i64 > b as i64,
+                            ebpf::JSGE_IMM | ebpf::JSGE_REG => a as i64 >= b as i64,
+                            ebpf::JSLT_IMM | ebpf::JSLT_REG => (a as i64) < b as i64,
+                            _ => a as i64 <= b as i64,
+                        };
+                        if taken {
+                            pc = (pc as isize + isize::from(insn.off)) as usize;
+                        }
+                    }
+
+                    ebpf::CALL => {
+                        let hash = insn.imm as u32;
+                        if let Some(info) = self.helpers.get_mut(&hash) {
+                            call_stack.call(&info.name, *instruction_count);
+                            if let Some(verifier) = info.verifier {
+                                verifier(
+                                    reg[1],
+                                    reg[2],
+                                    reg[3],
+                                    reg[4],
+                                    reg[5],
+                                    &mut info.context,
+                                    ro_regions,
+                                    rw_regions,
+                                )?;
+                            }
+                            reg[0] = (info.helper)(
+                                reg[1],
+                                reg[2],
+                                reg[3],
+                                reg[4],
+                                reg[5],
+                                &mut info.context,
+                            );
+                            call_stack.ret(*instruction_count, Exit::Success);
+                        } else if let Some(target_pc) =
+                            self.elf.and_then(|elf| elf.lookup_bpf_call(hash))
+                        {
+                            if frames.len() + 1 >= MAX_CALL_DEPTH {
+                                return Err(trace_error("exceeded maximum call depth", insn_pc));
+                            }
+                            call_stack.call(&self.function_name(*target_pc), *instruction_count);
+                            frames.push(Frame {
+                                saved_registers: [reg[6], reg[7], reg[8], reg[9]],
+                                return_pc: pc,
+                            });
+                            reg[10] += ebpf::STACK_SIZE as u64;
+                            pc = *target_pc;
+                        } else {
+                            return Err(trace_error(
+                                format!("unresolved function {:#x}", hash),
+                                insn_pc,
+                            ));
+                        }
+                    }
+
+                    ebpf::EXIT => match frames.pop() {
+                        Some(frame) => {
+                            call_stack.ret(*instruction_count, Exit::Success);
+                            reg[6..10].copy_from_slice(&frame.saved_registers);
+                            reg[10] -= ebpf::STACK_SIZE as u64;
+                            pc = frame.return_pc;
+                        }
+                        None => return Ok(reg[0]),
+                    },
+
+                    opc => {
+                        return Err(trace_error(
+                            format!("unsupported instruction {:#x}", opc),
+                            insn_pc,
+                        ))
+                    }
+                }
+            }
+        }
+        Err(trace_error("ran off the end of the program", pc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiler::Profiler;
+    use solana_rbpf::EbpfVmRaw;
+
+    #[test]
+    fn test_trace_matches_vm_instruction_count() {
+        #[rustfmt::skip]
+        let prog = &[
+            0xb7, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // r0 = 0
+            0x07, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, // r0 += 1
+            0x55, 0x00, 0xfe, 0xff, 0x05, 0x00, 0x00, 0x00, // if r0 != 5 goto -2
+            0x95, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // exit
+        ];
+        let program_id = Pubkey::new_rand();
+
+        let mut vm = EbpfVmRaw::new(None).unwrap();
+        vm.set_max_instruction_count(MAX_INSTRUCTION_COUNT).unwrap();
+        vm.set_program(prog).unwrap();
+        assert_eq!(vm.execute_program(&mut [0], &[], &[]).unwrap(), 5);
+
+        let mut profiler = Profiler::default();
+        let instruction_count = trace_raw(
+            &program_id,
+            prog,
+            &mut [0],
+            &mut CallStack::new(&mut profiler, &program_id),
+        )
+        .unwrap();
+        assert_eq!(instruction_count, vm.get_last_instruction_count());
+
+        let profile = profiler.get(&program_id, ENTRYPOINT_SYMBOL).unwrap();
+        assert_eq!(profile.returns, 1);
+        assert_eq!(profile.total_instructions, instruction_count);
+        assert_eq!(profile.self_instructions, instruction_count);
+    }
+
+    #[test]
+    fn test_trace_out_of_bounds() {
+        #[rustfmt::skip]
+        let prog = &[
+            0x71, 0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // r2 = *(u8 *)(r1 + 0)
+            0x71, 0x12, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, // r2 = *(u8 *)(r1 + 8)
+            0x95, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // exit
+        ];
+        let program_id = Pubkey::new_rand();
+        let mut profiler = Profiler::default();
+        assert!(trace_raw(
+            &program_id,
+            prog,
+            &mut [0],
+            &mut CallStack::new(&mut profiler, &program_id),
+        )
+        .is_err());
+
+        let profile = profiler.get(&program_id, ENTRYPOINT_SYMBOL).unwrap();
+        assert_eq!(profile.calls, 1);
+        assert_eq!(profile.returns, 0);
+        assert_eq!(profile.total_instructions, 2);
+    }
+}
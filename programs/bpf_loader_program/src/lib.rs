@@ -9,4 +9,12 @@ macro_rules! solana_bpf_loader_program {
 }
 
 use solana_bpf_loader_api::process_instruction;
+use solana_sdk::transaction::TransactionReturnData;
 solana_sdk::solana_entrypoint!(process_instruction);
+
+#[no_mangle]
+pub extern "C" fn swap_return_data(
+    return_data: Option<TransactionReturnData>,
+) -> Option<TransactionReturnData> {
+    solana_bpf_loader_api::return_data::swap(return_data)
+}
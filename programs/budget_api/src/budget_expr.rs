@@ -7,6 +7,7 @@ use chrono::prelude::*;
 use serde_derive::{Deserialize, Serialize};
 use solana_sdk::hash::Hash;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::timing::Slot;
 use std::mem;
 
 /// The types of events a payment plan can process.
@@ -60,6 +61,12 @@ pub enum Condition {
 
     /// Wait for the account with the given constraints.
     AccountData(AccountConstraints),
+
+    /// Wait for every one of the conditions, in any order.
+    All(Vec<Condition>),
+
+    /// Wait for any one of the conditions.
+    Any(Vec<Condition>),
 }
 
 impl Condition {
@@ -78,9 +85,73 @@ impl Condition {
                     && constraints.key == *from
                     && constraints.data_hash == *actual_hash
             }
+            (Condition::All(conditions), _) => conditions
+                .iter()
+                .all(|condition| condition.is_satisfied(witness, from)),
+            (Condition::Any(conditions), _) => conditions
+                .iter()
+                .any(|condition| condition.is_satisfied(witness, from)),
             _ => false,
         }
     }
+
+    /// Apply a witness to the condition, dropping whichever of an `All`'s conditions it
+    /// satisfies. Return true once the condition is satisfied.
+    pub fn apply_witness(&mut self, witness: &Witness, from: &Pubkey) -> bool {
+        match self {
+            Condition::All(conditions) => {
+                *conditions = conditions
+                    .drain(..)
+                    .filter_map(|mut condition| {
+                        if condition.apply_witness(witness, from) {
+                            None
+                        } else {
+                            Some(condition)
+                        }
+                    })
+                    .collect();
+                conditions.is_empty()
+            }
+            Condition::Any(conditions) => conditions
+                .iter_mut()
+                .any(|condition| condition.apply_witness(witness, from)),
+            _ => self.is_satisfied(witness, from),
+        }
+    }
+}
+
+/// Equal payments made every `interval` slots, starting at `next_slot`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct RecurringPayment {
+    /// Each of the payments.
+    pub payment: Payment,
+
+    /// The slot at which the next payment comes due.
+    pub next_slot: Slot,
+
+    /// The number of slots between payments.
+    pub interval: u64,
+
+    /// The number of payments yet to be made.
+    pub remaining: u64,
+}
+
+impl RecurringPayment {
+    /// Return the number of payments due by `slot`, and schedule the ones after them.
+    pub fn take_due_payments(&mut self, slot: Slot) -> u64 {
+        if slot < self.next_slot {
+            return 0;
+        }
+        let due = (slot - self.next_slot)
+            .checked_div(self.interval)
+            .map_or(self.remaining, |intervals| intervals + 1)
+            .min(self.remaining);
+        self.next_slot = self
+            .next_slot
+            .saturating_add(due.saturating_mul(self.interval));
+        self.remaining -= due;
+        due
+    }
 }
 
 /// A data type representing a payment plan.
@@ -99,6 +170,9 @@ pub enum BudgetExpr {
 
     /// Make a payment after both of two conditions are satisfied
     And(Condition, Condition, Box<BudgetExpr>),
+
+    /// Make equal payments on a schedule of slots, as the `Clock` sysvar reaches them.
+    Recurring(RecurringPayment),
 }
 
 impl BudgetExpr {
@@ -210,6 +284,44 @@ impl BudgetExpr {
         )
     }
 
+    /// Create a budget that pays `lamports` to `to` once `condition` is satisfied, unless
+    /// canceled with a signature from `from`.
+    pub fn new_cancelable_conditional_payment(
+        condition: Condition,
+        lamports: u64,
+        to: &Pubkey,
+        from: Option<Pubkey>,
+    ) -> Self {
+        let payment = Box::new(Self::new_payment(lamports, to));
+        match from {
+            None => BudgetExpr::After(condition, payment),
+            Some(from) => BudgetExpr::Or(
+                (condition, payment),
+                (
+                    Condition::Signature(from),
+                    Box::new(Self::new_payment(lamports, &from)),
+                ),
+            ),
+        }
+    }
+
+    /// Create a budget that pays `lamports` to `to` `count` times, once every `interval`
+    /// slots starting at `start_slot`.
+    pub fn new_recurring_payment(
+        lamports: u64,
+        to: &Pubkey,
+        start_slot: Slot,
+        interval: u64,
+        count: u64,
+    ) -> Self {
+        BudgetExpr::Recurring(RecurringPayment {
+            payment: Payment { lamports, to: *to },
+            next_slot: start_slot,
+            interval,
+            remaining: count,
+        })
+    }
+
     /// Return Payment if the budget requires no additional Witnesses.
     pub fn final_payment(&self) -> Option<Payment> {
         match self {
@@ -228,6 +340,11 @@ impl BudgetExpr {
             BudgetExpr::Or(a, b) => {
                 a.1.verify(spendable_lamports) && b.1.verify(spendable_lamports)
             }
+            BudgetExpr::Recurring(recurring) => {
+                recurring.interval > 0
+                    && recurring.payment.lamports.checked_mul(recurring.remaining)
+                        == Some(spendable_lamports)
+            }
         }
     }
 
@@ -235,19 +352,26 @@ impl BudgetExpr {
     /// If so, modify the budget in-place.
     pub fn apply_witness(&mut self, witness: &Witness, from: &Pubkey) {
         let new_expr = match self {
-            BudgetExpr::After(cond, sub_expr) if cond.is_satisfied(witness, from) => {
-                Some(sub_expr.clone())
-            }
-            BudgetExpr::Or((cond, sub_expr), _) if cond.is_satisfied(witness, from) => {
-                Some(sub_expr.clone())
+            BudgetExpr::After(cond, sub_expr) => {
+                if cond.apply_witness(witness, from) {
+                    Some(sub_expr.clone())
+                } else {
+                    None
+                }
             }
-            BudgetExpr::Or(_, (cond, sub_expr)) if cond.is_satisfied(witness, from) => {
-                Some(sub_expr.clone())
+            BudgetExpr::Or((cond0, sub_expr0), (cond1, sub_expr1)) => {
+                if cond0.apply_witness(witness, from) {
+                    Some(sub_expr0.clone())
+                } else if cond1.apply_witness(witness, from) {
+                    Some(sub_expr1.clone())
+                } else {
+                    None
+                }
             }
             BudgetExpr::And(cond0, cond1, sub_expr) => {
-                if cond0.is_satisfied(witness, from) {
+                if cond0.apply_witness(witness, from) {
                     Some(Box::new(BudgetExpr::After(cond1.clone(), sub_expr.clone())))
-                } else if cond1.is_satisfied(witness, from) {
+                } else if cond1.apply_witness(witness, from) {
                     Some(Box::new(BudgetExpr::After(cond0.clone(), sub_expr.clone())))
                 } else {
                     None
@@ -388,4 +512,89 @@ mod tests {
         expr.apply_witness(&Witness::Signature, &from0);
         assert_eq!(expr, BudgetExpr::new_authorized_payment(&from1, 42, &to));
     }
+
+    #[test]
+    fn test_all_conditions_payment() {
+        let dt = Utc.ymd(2014, 11, 11).and_hms(7, 7, 7);
+        let from0 = Pubkey::new_rand();
+        let from1 = Pubkey::new_rand();
+        let to = Pubkey::new_rand();
+
+        let condition = Condition::All(vec![
+            Condition::Timestamp(dt, from0),
+            Condition::Signature(from1),
+        ]);
+        let mut expr = BudgetExpr::new_cancelable_conditional_payment(condition, 42, &to, None);
+        assert!(expr.verify(42));
+
+        expr.apply_witness(&Witness::Signature, &from1);
+        assert_eq!(
+            expr,
+            BudgetExpr::After(
+                Condition::All(vec![Condition::Timestamp(dt, from0)]),
+                Box::new(BudgetExpr::new_payment(42, &to))
+            )
+        );
+
+        // A second signature from the same key doesn't satisfy the timestamp
+        let orig_expr = expr.clone();
+        expr.apply_witness(&Witness::Signature, &from1);
+        assert_eq!(expr, orig_expr);
+
+        expr.apply_witness(&Witness::Timestamp(dt), &from0);
+        assert_eq!(expr, BudgetExpr::new_payment(42, &to));
+    }
+
+    #[test]
+    fn test_any_condition_payment() {
+        let dt = Utc.ymd(2014, 11, 11).and_hms(7, 7, 7);
+        let from0 = Pubkey::new_rand();
+        let from1 = Pubkey::new_rand();
+        let to = Pubkey::new_rand();
+
+        let condition = Condition::Any(vec![
+            Condition::Timestamp(dt, from0),
+            Condition::Signature(from1),
+        ]);
+        let expr = BudgetExpr::new_cancelable_conditional_payment(condition, 42, &to, Some(from0));
+        assert!(expr.verify(42));
+
+        let mut sig_expr = expr.clone();
+        sig_expr.apply_witness(&Witness::Signature, &from1);
+        assert_eq!(sig_expr, BudgetExpr::new_payment(42, &to));
+
+        let mut ts_expr = expr.clone();
+        ts_expr.apply_witness(&Witness::Timestamp(dt), &from0);
+        assert_eq!(ts_expr, BudgetExpr::new_payment(42, &to));
+
+        // A signature from `from0` cancels the payment
+        let mut cancel_expr = expr.clone();
+        cancel_expr.apply_witness(&Witness::Signature, &from0);
+        assert_eq!(cancel_expr, BudgetExpr::new_payment(42, &from0));
+    }
+
+    #[test]
+    fn test_recurring_payment() {
+        let to = Pubkey::new_rand();
+        assert!(BudgetExpr::new_recurring_payment(10, &to, 5, 2, 3).verify(30));
+        assert!(!BudgetExpr::new_recurring_payment(10, &to, 5, 2, 3).verify(20));
+        assert!(!BudgetExpr::new_recurring_payment(10, &to, 5, 0, 3).verify(30));
+        assert!(!BudgetExpr::new_recurring_payment(std::u64::MAX, &to, 5, 2, 2).verify(0));
+
+        let mut recurring = RecurringPayment {
+            payment: Payment { lamports: 10, to },
+            next_slot: 5,
+            interval: 2,
+            remaining: 3,
+        };
+        assert_eq!(recurring.take_due_payments(4), 0);
+        assert_eq!(recurring.take_due_payments(5), 1);
+        assert_eq!(recurring.next_slot, 7);
+        assert_eq!(recurring.take_due_payments(6), 0);
+
+        // Payments missed since the last one are all due at once, up to those remaining
+        assert_eq!(recurring.take_due_payments(9), 2);
+        assert_eq!(recurring.remaining, 0);
+        assert_eq!(recurring.take_due_payments(100), 0);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::budget_expr::BudgetExpr;
+use crate::budget_expr::{BudgetExpr, Condition};
 use crate::budget_state::BudgetState;
 use crate::id;
 use bincode::serialized_size;
@@ -8,6 +8,8 @@ use solana_sdk::hash::Hash;
 use solana_sdk::instruction::{AccountMeta, Instruction};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::system_instruction;
+use solana_sdk::sysvar;
+use solana_sdk::timing::Slot;
 
 /// A smart contract.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -32,6 +34,9 @@ pub enum BudgetInstruction {
 
     /// Load an account and pass its data to the budget for inspection.
     ApplyAccountData,
+
+    /// Make the payments of a recurring payment plan that are due by the `Clock` sysvar's slot.
+    ApplyClock,
 }
 
 fn initialize_account(contract: &Pubkey, expr: BudgetExpr) -> Instruction {
@@ -117,6 +122,33 @@ pub fn when_account_data(
     create_account(from, contract, lamports, expr)
 }
 
+/// Make a payment once `condition` is satisfied.
+pub fn on_conditions(
+    from: &Pubkey,
+    to: &Pubkey,
+    contract: &Pubkey,
+    condition: Condition,
+    cancelable: Option<Pubkey>,
+    lamports: u64,
+) -> Vec<Instruction> {
+    let expr = BudgetExpr::new_cancelable_conditional_payment(condition, lamports, to, cancelable);
+    create_account(from, contract, lamports, expr)
+}
+
+/// Pay `lamports` to `to` `count` times, once every `interval` slots starting at `start_slot`.
+pub fn recurring(
+    from: &Pubkey,
+    to: &Pubkey,
+    contract: &Pubkey,
+    lamports: u64,
+    start_slot: Slot,
+    interval: u64,
+    count: u64,
+) -> Vec<Instruction> {
+    let expr = BudgetExpr::new_recurring_payment(lamports, to, start_slot, interval, count);
+    create_account(from, contract, lamports * count, expr)
+}
+
 pub fn apply_timestamp(
     from: &Pubkey,
     contract: &Pubkey,
@@ -154,6 +186,16 @@ pub fn apply_account_data(witness_pubkey: &Pubkey, contract: &Pubkey, to: &Pubke
     Instruction::new(id(), &BudgetInstruction::ApplyAccountData, account_metas)
 }
 
+/// Make the due payments of a recurring payment plan. Anyone may send it.
+pub fn apply_clock(contract: &Pubkey, to: &Pubkey) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new_credit_only(sysvar::clock::id(), false),
+        AccountMeta::new(*contract, false),
+        AccountMeta::new_credit_only(*to, false),
+    ];
+    Instruction::new(id(), &BudgetInstruction::ApplyClock, account_metas)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
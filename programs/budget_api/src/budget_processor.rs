@@ -1,5 +1,5 @@
 //! budget program
-use crate::budget_expr::Witness;
+use crate::budget_expr::{BudgetExpr, Witness};
 use crate::budget_instruction::BudgetInstruction;
 use crate::budget_state::{BudgetError, BudgetState};
 use bincode::deserialize;
@@ -9,13 +9,14 @@ use solana_sdk::account::KeyedAccount;
 use solana_sdk::hash::hash;
 use solana_sdk::instruction::InstructionError;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar;
 
 /// Process a Witness Signature. Any payment plans waiting on this signature
 /// will progress one step.
 fn apply_signature(
     budget_state: &mut BudgetState,
     keyed_accounts: &mut [KeyedAccount],
-) -> Result<(), BudgetError> {
+) -> Result<(), InstructionError> {
     let mut final_payment = None;
     if let Some(ref mut expr) = budget_state.pending_budget {
         let key = keyed_accounts[0].signer_key().unwrap();
@@ -27,18 +28,28 @@ fn apply_signature(
         if let Some(key) = keyed_accounts[0].signer_key() {
             if &payment.to == key {
                 budget_state.pending_budget = None;
-                keyed_accounts[1].account.lamports -= payment.lamports;
-                keyed_accounts[0].account.lamports += payment.lamports;
+                keyed_accounts[1]
+                    .account
+                    .checked_sub_lamports(payment.lamports)?;
+                keyed_accounts[0]
+                    .account
+                    .checked_add_lamports(payment.lamports)?;
                 return Ok(());
             }
         }
         if &payment.to != keyed_accounts[2].unsigned_key() {
             trace!("destination missing");
-            return Err(BudgetError::DestinationMissing);
+            return Err(InstructionError::CustomError(
+                BudgetError::DestinationMissing as u32,
+            ));
         }
         budget_state.pending_budget = None;
-        keyed_accounts[1].account.lamports -= payment.lamports;
-        keyed_accounts[2].account.lamports += payment.lamports;
+        keyed_accounts[1]
+            .account
+            .checked_sub_lamports(payment.lamports)?;
+        keyed_accounts[2]
+            .account
+            .checked_add_lamports(payment.lamports)?;
     }
     Ok(())
 }
@@ -49,7 +60,7 @@ fn apply_timestamp(
     budget_state: &mut BudgetState,
     keyed_accounts: &mut [KeyedAccount],
     dt: DateTime<Utc>,
-) -> Result<(), BudgetError> {
+) -> Result<(), InstructionError> {
     // Check to see if any timelocked transactions can be completed.
     let mut final_payment = None;
 
@@ -62,11 +73,17 @@ fn apply_timestamp(
     if let Some(payment) = final_payment {
         if &payment.to != keyed_accounts[2].unsigned_key() {
             trace!("destination missing");
-            return Err(BudgetError::DestinationMissing);
+            return Err(InstructionError::CustomError(
+                BudgetError::DestinationMissing as u32,
+            ));
         }
         budget_state.pending_budget = None;
-        keyed_accounts[1].account.lamports -= payment.lamports;
-        keyed_accounts[2].account.lamports += payment.lamports;
+        keyed_accounts[1]
+            .account
+            .checked_sub_lamports(payment.lamports)?;
+        keyed_accounts[2]
+            .account
+            .checked_add_lamports(payment.lamports)?;
     }
     Ok(())
 }
@@ -75,7 +92,7 @@ fn apply_timestamp(
 fn apply_account_data(
     budget_state: &mut BudgetState,
     keyed_accounts: &mut [KeyedAccount],
-) -> Result<(), BudgetError> {
+) -> Result<(), InstructionError> {
     // Check to see if any timelocked transactions can be completed.
     let mut final_payment = None;
 
@@ -91,11 +108,43 @@ fn apply_account_data(
     if let Some(payment) = final_payment {
         if &payment.to != keyed_accounts[2].unsigned_key() {
             trace!("destination missing");
-            return Err(BudgetError::DestinationMissing);
+            return Err(InstructionError::CustomError(
+                BudgetError::DestinationMissing as u32,
+            ));
         }
         budget_state.pending_budget = None;
-        keyed_accounts[1].account.lamports -= payment.lamports;
-        keyed_accounts[2].account.lamports += payment.lamports;
+        keyed_accounts[1]
+            .account
+            .checked_sub_lamports(payment.lamports)?;
+        keyed_accounts[2]
+            .account
+            .checked_add_lamports(payment.lamports)?;
+    }
+    Ok(())
+}
+
+/// Make the payments of a recurring payment plan that are due by the `Clock` sysvar's slot.
+fn apply_clock(
+    budget_state: &mut BudgetState,
+    keyed_accounts: &mut [KeyedAccount],
+) -> Result<(), InstructionError> {
+    let clock = sysvar::clock::from_keyed_account(&keyed_accounts[0])?;
+    if let Some(BudgetExpr::Recurring(recurring)) = &mut budget_state.pending_budget {
+        let payments = recurring.take_due_payments(clock.slot);
+        if payments > 0 {
+            if &recurring.payment.to != keyed_accounts[2].unsigned_key() {
+                trace!("destination missing");
+                return Err(InstructionError::CustomError(
+                    BudgetError::DestinationMissing as u32,
+                ));
+            }
+            let lamports = recurring.payment.lamports.saturating_mul(payments);
+            keyed_accounts[1].account.checked_sub_lamports(lamports)?;
+            keyed_accounts[2].account.checked_add_lamports(lamports)?;
+        }
+        if recurring.remaining == 0 {
+            budget_state.pending_budget = None;
+        }
     }
     Ok(())
 }
@@ -117,7 +166,9 @@ pub fn process_instruction(
             let expr = expr.clone();
             if let Some(payment) = expr.final_payment() {
                 keyed_accounts[1].account.lamports = 0;
-                keyed_accounts[0].account.lamports += payment.lamports;
+                keyed_accounts[0]
+                    .account
+                    .checked_add_lamports(payment.lamports)?;
                 return Ok(());
             }
             let existing = BudgetState::deserialize(&keyed_accounts[0].account.data).ok();
@@ -143,8 +194,7 @@ pub fn process_instruction(
                 return Err(InstructionError::MissingRequiredSignature);
             }
             trace!("apply timestamp");
-            apply_timestamp(&mut budget_state, keyed_accounts, dt)
-                .map_err(|e| InstructionError::CustomError(e as u32))?;
+            apply_timestamp(&mut budget_state, keyed_accounts, dt)?;
             trace!("apply timestamp committed");
             budget_state.serialize(&mut keyed_accounts[1].account.data)
         }
@@ -161,8 +211,7 @@ pub fn process_instruction(
                 return Err(InstructionError::MissingRequiredSignature);
             }
             trace!("apply signature");
-            apply_signature(&mut budget_state, keyed_accounts)
-                .map_err(|e| InstructionError::CustomError(e as u32))?;
+            apply_signature(&mut budget_state, keyed_accounts)?;
             trace!("apply signature committed");
             budget_state.serialize(&mut keyed_accounts[1].account.data)
         }
@@ -175,11 +224,23 @@ pub fn process_instruction(
                 trace!("contract is uninitialized");
                 return Err(InstructionError::UninitializedAccount);
             }
-            apply_account_data(&mut budget_state, keyed_accounts)
-                .map_err(|e| InstructionError::CustomError(e as u32))?;
+            apply_account_data(&mut budget_state, keyed_accounts)?;
             trace!("apply account data committed");
             budget_state.serialize(&mut keyed_accounts[1].account.data)
         }
+        BudgetInstruction::ApplyClock => {
+            let mut budget_state = BudgetState::deserialize(&keyed_accounts[1].account.data)?;
+            if !budget_state.is_pending() {
+                return Ok(()); // Nothing to do here.
+            }
+            if !budget_state.initialized {
+                trace!("contract is uninitialized");
+                return Err(InstructionError::UninitializedAccount);
+            }
+            apply_clock(&mut budget_state, keyed_accounts)?;
+            trace!("apply clock committed");
+            budget_state.serialize(&mut keyed_accounts[1].account.data)
+        }
     }
 }
 
@@ -198,6 +259,7 @@ mod tests {
     use solana_sdk::message::Message;
     use solana_sdk::signature::{Keypair, KeypairUtil};
     use solana_sdk::transaction::TransactionError;
+    use std::sync::Arc;
 
     fn create_bank(lamports: u64) -> (Bank, Keypair) {
         let (genesis_block, mint_keypair) = create_genesis_block(lamports);
@@ -206,6 +268,12 @@ mod tests {
         (bank, mint_keypair)
     }
 
+    fn create_child_bank(parent: &Arc<Bank>, slot: u64) -> Arc<Bank> {
+        let mut bank = Bank::new_from_parent(parent, &Pubkey::default(), slot);
+        bank.add_instruction_processor(id(), process_instruction);
+        Arc::new(bank)
+    }
+
     #[test]
     fn test_budget_payment() {
         let (bank, alice_keypair) = create_bank(10_000);
@@ -509,4 +577,60 @@ mod tests {
         assert_eq!(bank_client.get_balance(&bob_pubkey).unwrap(), 42);
         assert_eq!(bank_client.get_account_data(&budget_pubkey).unwrap(), None);
     }
+
+    #[test]
+    fn test_recurring_payment() {
+        let (bank, alice_keypair) = create_bank(30);
+        let bank = Arc::new(bank);
+        let bank_client = BankClient::new_shared(&bank);
+        let alice_pubkey = alice_keypair.pubkey();
+        let budget_pubkey = Pubkey::new_rand();
+        let bob_keypair = Keypair::new();
+        let bob_pubkey = bob_keypair.pubkey();
+        let mallory_pubkey = Pubkey::new_rand();
+
+        // Pay Bob 10 lamports every 2 slots from slot 2, 3 times
+        let instructions =
+            budget_instruction::recurring(&alice_pubkey, &bob_pubkey, &budget_pubkey, 10, 2, 2, 3);
+        let message = Message::new(instructions);
+        bank_client
+            .send_message(&[&alice_keypair], message)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&alice_pubkey).unwrap(), 0);
+        assert_eq!(bank_client.get_balance(&budget_pubkey).unwrap(), 30);
+
+        let bank = create_child_bank(&bank, 3);
+        let bank_client = BankClient::new_shared(&bank);
+
+        // Attack! Try to pay the due installment to mallory_pubkey
+        let instruction = budget_instruction::apply_clock(&budget_pubkey, &mallory_pubkey);
+        assert_eq!(
+            bank_client
+                .send_instruction(&alice_keypair, instruction)
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::CustomError(BudgetError::DestinationMissing as u32)
+            )
+        );
+
+        let instruction = budget_instruction::apply_clock(&budget_pubkey, &bob_pubkey);
+        bank_client
+            .send_instruction(&alice_keypair, instruction)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&budget_pubkey).unwrap(), 20);
+        assert_eq!(bank_client.get_balance(&bob_pubkey).unwrap(), 10);
+
+        // Both remaining installments are due by slot 10. Anyone can send the instruction,
+        // so Bob does.
+        let bank = create_child_bank(&bank, 10);
+        let bank_client = BankClient::new_shared(&bank);
+        let instruction = budget_instruction::apply_clock(&budget_pubkey, &bob_pubkey);
+        let message = Message::new_with_payer(vec![instruction], Some(&bob_pubkey));
+        bank_client.send_message(&[&bob_keypair], message).unwrap();
+        assert_eq!(bank_client.get_balance(&budget_pubkey).unwrap(), 0);
+        assert_eq!(bank_client.get_balance(&bob_pubkey).unwrap(), 30);
+        assert_eq!(bank_client.get_account_data(&budget_pubkey).unwrap(), None);
+    }
 }
@@ -8,7 +8,7 @@ use solana_sdk::short_vec;
 use solana_sdk::system_instruction;
 
 /// A collection of keys to be stored in Config account data.
-#[derive(Debug, Default, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
 pub struct ConfigKeys {
     // Each key tuple comprises a unique `Pubkey` identifier,
     // and `bool` whether that key is a signer of the data
@@ -22,9 +22,46 @@ impl ConfigKeys {
             .unwrap_or_else(|_| vec![])
             .len()
     }
+
+    /// The keys authorized to sign updates
+    pub fn signer_keys(&self) -> Vec<Pubkey> {
+        self.keys
+            .iter()
+            .filter(|(_, is_signer)| *is_signer)
+            .map(|(pubkey, _)| *pubkey)
+            .collect()
+    }
+}
+
+/// Marks instruction data as a serialized `ConfigInstruction`.  Any other data stores new
+/// data in a configuration account, as it always has: the serialized `ConfigKeys`, followed
+/// by the serialized config state, see `store`.  A `ConfigKeys` length never takes four bytes
+/// with the top bit set, so no store instruction begins with this prefix.
+pub const CONFIG_INSTRUCTION_PREFIX: [u8; 4] = [0xff; 4];
+
+/// Instructions to manage the signer keys of a configuration account, serialized after
+/// `CONFIG_INSTRUCTION_PREFIX`
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum ConfigInstruction {
+    /// Authorize another key to sign updates to a configuration account
+    /// * Instruction::keys[0] - the config account, signing if the account has no signer keys
+    /// * Instruction::keys[1..] - at least `signer_threshold()` of the current signer keys
+    AddSigner { signer: Pubkey },
+    /// Revoke a key's authority to sign updates to a configuration account.  Once the last
+    /// signer key is removed, the config account itself signs updates again.
+    /// * Instruction::keys[0] - the config account, signing if the account has no signer keys
+    /// * Instruction::keys[1..] - at least `signer_threshold()` of the current signer keys
+    RemoveSigner { signer: Pubkey },
+}
+
+/// How many of an account's `num_signer_keys` signer keys must sign to add or remove one: a
+/// majority of them
+pub fn signer_threshold(num_signer_keys: usize) -> usize {
+    num_signer_keys / 2 + 1
 }
 
-/// Create a new, empty configuration account
+/// Create a new, empty configuration account, with room for `keys`, which should include the
+/// signer keys it may later be given with `add_signer`
 pub fn create_account<T: ConfigState>(
     from_account_pubkey: &Pubkey,
     config_account_pubkey: &Pubkey,
@@ -42,6 +79,9 @@ pub fn create_account<T: ConfigState>(
 }
 
 /// Store new data in a configuration account
+/// * Instruction::keys[0] - the config account, signing if it's one of the signer keys or the
+///   account has no signer keys yet
+/// * Instruction::keys[1..] - the signer keys of `data` other than the config account
 pub fn store<T: ConfigState>(
     config_account_pubkey: &Pubkey,
     is_config_signer: bool,
@@ -57,3 +97,58 @@ pub fn store<T: ConfigState>(
     let account_data = (ConfigKeys { keys }, data);
     Instruction::new(id(), &account_data, account_metas)
 }
+
+fn config_instruction(instruction: &ConfigInstruction, accounts: Vec<AccountMeta>) -> Instruction {
+    let mut data = CONFIG_INSTRUCTION_PREFIX.to_vec();
+    data.extend(serialize(instruction).unwrap());
+    Instruction {
+        program_id: id(),
+        accounts,
+        data,
+    }
+}
+
+fn signer_account_metas(config_account_pubkey: &Pubkey, signers: &[Pubkey]) -> Vec<AccountMeta> {
+    let mut account_metas = vec![AccountMeta::new(
+        *config_account_pubkey,
+        signers.is_empty() || signers.contains(config_account_pubkey),
+    )];
+    for signer_pubkey in signers {
+        if signer_pubkey != config_account_pubkey {
+            account_metas.push(AccountMeta::new(*signer_pubkey, true));
+        }
+    }
+    account_metas
+}
+
+/// Authorize `new_signer_pubkey` to sign updates, with the signatures of `signers`, at least
+/// `signer_threshold()` of the current signer keys.  An account without any signer keys needs
+/// no `signers`, but its own signature.  The account must have been created with zeroed space
+/// after its config state for the new key, see `create_account`.
+pub fn add_signer(
+    config_account_pubkey: &Pubkey,
+    signers: &[Pubkey],
+    new_signer_pubkey: &Pubkey,
+) -> Instruction {
+    config_instruction(
+        &ConfigInstruction::AddSigner {
+            signer: *new_signer_pubkey,
+        },
+        signer_account_metas(config_account_pubkey, signers),
+    )
+}
+
+/// Revoke the authority of `signer_pubkey` to sign updates, with the signatures of `signers`,
+/// at least `signer_threshold()` of the current signer keys
+pub fn remove_signer(
+    config_account_pubkey: &Pubkey,
+    signers: &[Pubkey],
+    signer_pubkey: &Pubkey,
+) -> Instruction {
+    config_instruction(
+        &ConfigInstruction::RemoveSigner {
+            signer: *signer_pubkey,
+        },
+        signer_account_metas(config_account_pubkey, signers),
+    )
+}
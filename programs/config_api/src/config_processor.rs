@@ -1,32 +1,32 @@
 //! Config program
 
-use crate::config_instruction::ConfigKeys;
-use bincode::deserialize;
+use crate::config_instruction::{
+    signer_threshold, ConfigInstruction, ConfigKeys, CONFIG_INSTRUCTION_PREFIX,
+};
+use bincode::{deserialize, serialize};
 use log::*;
 use solana_sdk::account::KeyedAccount;
 use solana_sdk::instruction::InstructionError;
 use solana_sdk::pubkey::Pubkey;
 
-pub fn process_instruction(
-    _program_id: &Pubkey,
-    keyed_accounts: &mut [KeyedAccount],
-    data: &[u8],
-) -> Result<(), InstructionError> {
+fn get_config_keys(keyed_accounts: &[KeyedAccount]) -> Result<ConfigKeys, InstructionError> {
+    deserialize(&keyed_accounts[0].account.data).map_err(|err| {
+        error!(
+            "Invalid data in account[0]: {:?} {:?}",
+            keyed_accounts[0].account.data, err
+        );
+        InstructionError::InvalidAccountData
+    })
+}
+
+fn store(keyed_accounts: &mut [KeyedAccount], data: &[u8]) -> Result<(), InstructionError> {
     let key_list: ConfigKeys = deserialize(data).map_err(|err| {
         error!("Invalid ConfigKeys data: {:?} {:?}", data, err);
         InstructionError::InvalidInstructionData
     })?;
 
-    let current_data: ConfigKeys = deserialize(&keyed_accounts[0].account.data).map_err(|err| {
-        error!("Invalid data in account[0]: {:?} {:?}", data, err);
-        InstructionError::InvalidAccountData
-    })?;
-    let current_signer_keys: Vec<Pubkey> = current_data
-        .keys
-        .iter()
-        .filter(|(_, is_signer)| *is_signer)
-        .map(|(pubkey, _)| *pubkey)
-        .collect();
+    let current_data = get_config_keys(keyed_accounts)?;
+    let current_signer_keys = current_data.signer_keys();
 
     if current_signer_keys.is_empty() {
         // Config account keypair must be a signer on account initilization,
@@ -99,11 +99,121 @@ pub fn process_instruction(
     Ok(())
 }
 
+/// Apply `update` to the keys of the config account, once a threshold of its current signer keys
+/// have signed.  The config state is moved to follow the updated keys.
+fn update_signer_keys<F>(
+    keyed_accounts: &mut [KeyedAccount],
+    update: F,
+) -> Result<(), InstructionError>
+where
+    F: FnOnce(&mut Vec<(Pubkey, bool)>) -> Result<(), InstructionError>,
+{
+    let mut config_keys = get_config_keys(keyed_accounts)?;
+    let current_signer_keys = config_keys.signer_keys();
+
+    if current_signer_keys.is_empty() {
+        if keyed_accounts[0].signer_key().is_none() {
+            error!("account[0].signer_key().is_none()");
+            Err(InstructionError::MissingRequiredSignature)?;
+        }
+    } else {
+        let num_signed = current_signer_keys
+            .iter()
+            .filter(|pubkey| {
+                keyed_accounts
+                    .iter()
+                    .any(|keyed_account| keyed_account.signer_key() == Some(*pubkey))
+            })
+            .count();
+        let threshold = signer_threshold(current_signer_keys.len());
+        if num_signed < threshold {
+            error!(
+                "too few signers: {:?}; expected: {:?}",
+                num_signed, threshold
+            );
+            Err(InstructionError::MissingRequiredSignature)?;
+        }
+    }
+
+    let meta_length = ConfigKeys::serialized_size(config_keys.keys.clone());
+    let state = keyed_accounts[0].account.data[meta_length..].to_vec();
+    update(&mut config_keys.keys)?;
+
+    let mut data = serialize(&config_keys).unwrap();
+    data.extend(state);
+    // A new key takes space from the end of the account, which must be unused
+    let len = keyed_accounts[0].account.data.len();
+    if data.len() > len {
+        if data[len..].iter().any(|byte| *byte != 0) {
+            error!("account[0] has no room for another key");
+            Err(InstructionError::AccountDataTooSmall)?;
+        }
+        data.truncate(len);
+    } else {
+        data.resize(len, 0);
+    }
+    keyed_accounts[0].account.data = data;
+    Ok(())
+}
+
+fn add_signer(keyed_accounts: &mut [KeyedAccount], signer: Pubkey) -> Result<(), InstructionError> {
+    update_signer_keys(keyed_accounts, |keys| {
+        if keys
+            .iter()
+            .any(|(pubkey, is_signer)| *pubkey == signer && *is_signer)
+        {
+            error!("{:?} is already a signer key", signer);
+            Err(InstructionError::InvalidArgument)?;
+        }
+        match keys.iter_mut().find(|(pubkey, _)| *pubkey == signer) {
+            Some((_, is_signer)) => *is_signer = true,
+            None => keys.push((signer, true)),
+        }
+        Ok(())
+    })
+}
+
+fn remove_signer(
+    keyed_accounts: &mut [KeyedAccount],
+    signer: Pubkey,
+) -> Result<(), InstructionError> {
+    update_signer_keys(keyed_accounts, |keys| {
+        let num_keys = keys.len();
+        keys.retain(|(pubkey, is_signer)| *pubkey != signer || !*is_signer);
+        if keys.len() == num_keys {
+            error!("{:?} is not a signer key", signer);
+            Err(InstructionError::InvalidArgument)?;
+        }
+        Ok(())
+    })
+}
+
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    keyed_accounts: &mut [KeyedAccount],
+    data: &[u8],
+) -> Result<(), InstructionError> {
+    if !data.starts_with(&CONFIG_INSTRUCTION_PREFIX) {
+        return store(keyed_accounts, data);
+    }
+    let instruction_data = &data[CONFIG_INSTRUCTION_PREFIX.len()..];
+    let instruction: ConfigInstruction = deserialize(instruction_data).map_err(|err| {
+        error!("Invalid instruction data: {:?} {:?}", instruction_data, err);
+        InstructionError::InvalidInstructionData
+    })?;
+    trace!("process_instruction: {:?}", instruction);
+
+    match instruction {
+        ConfigInstruction::AddSigner { signer } => add_signer(keyed_accounts, signer),
+        ConfigInstruction::RemoveSigner { signer } => remove_signer(keyed_accounts, signer),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{config_instruction, id, ConfigState};
-    use bincode::{deserialize, serialized_size};
+    use crate::{config_instruction, id, ConfigState, VersionedConfig};
+    use bincode::serialized_size;
     use serde_derive::{Deserialize, Serialize};
     use solana_runtime::bank::Bank;
     use solana_runtime::bank_client::BankClient;
@@ -476,4 +586,164 @@ mod tests {
             .send_message(&[&mint_keypair, &config_keypair], message)
             .unwrap_err();
     }
+
+    fn get_config_keys_and_state(
+        bank_client: &BankClient,
+        config_pubkey: &Pubkey,
+    ) -> (ConfigKeys, MyConfig) {
+        let config_account_data = bank_client
+            .get_account_data(config_pubkey)
+            .unwrap()
+            .unwrap();
+        let meta_data: ConfigKeys = deserialize(&config_account_data).unwrap();
+        let meta_length = ConfigKeys::serialized_size(meta_data.keys.clone());
+        let my_config = MyConfig::deserialize(&config_account_data[meta_length..]).unwrap();
+        (meta_data, my_config)
+    }
+
+    #[test]
+    fn test_add_and_remove_signer() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let pubkey = Pubkey::new_rand();
+        let signer0 = Keypair::new();
+        let signer1 = Keypair::new();
+        let keys = vec![
+            (pubkey, false),
+            (signer0.pubkey(), true),
+            (signer1.pubkey(), true),
+        ]; // Dummy keys for account sizing
+        let (bank_client, config_keypair) =
+            create_config_account(bank, &mint_keypair, keys.clone());
+        let config_pubkey = config_keypair.pubkey();
+
+        let keys = vec![(pubkey, false), (signer0.pubkey(), true)];
+        let my_config = MyConfig::new(42);
+        let instruction = config_instruction::store(&config_pubkey, true, keys.clone(), &my_config);
+        let message = Message::new_with_payer(vec![instruction], Some(&mint_keypair.pubkey()));
+        bank_client
+            .send_message(&[&mint_keypair, &config_keypair, &signer0], message)
+            .unwrap();
+
+        // Only a signer key can add another
+        let instruction =
+            config_instruction::add_signer(&config_pubkey, &[signer1.pubkey()], &signer1.pubkey());
+        let message = Message::new_with_payer(vec![instruction], Some(&mint_keypair.pubkey()));
+        bank_client
+            .send_message(&[&mint_keypair, &signer1], message)
+            .unwrap_err();
+
+        let instruction =
+            config_instruction::add_signer(&config_pubkey, &[signer0.pubkey()], &signer1.pubkey());
+        let message = Message::new_with_payer(vec![instruction], Some(&mint_keypair.pubkey()));
+        bank_client
+            .send_message(&[&mint_keypair, &signer0], message)
+            .unwrap();
+        let (meta_data, config) = get_config_keys_and_state(&bank_client, &config_pubkey);
+        assert_eq!(
+            meta_data.keys,
+            vec![
+                (pubkey, false),
+                (signer0.pubkey(), true),
+                (signer1.pubkey(), true)
+            ]
+        );
+        assert_eq!(config, my_config);
+
+        // Removing a key takes a majority of the two signer keys
+        let instruction = config_instruction::remove_signer(
+            &config_pubkey,
+            &[signer1.pubkey()],
+            &signer0.pubkey(),
+        );
+        let message = Message::new_with_payer(vec![instruction], Some(&mint_keypair.pubkey()));
+        bank_client
+            .send_message(&[&mint_keypair, &signer1], message)
+            .unwrap_err();
+
+        let instruction = config_instruction::remove_signer(
+            &config_pubkey,
+            &[signer0.pubkey(), signer1.pubkey()],
+            &signer0.pubkey(),
+        );
+        let message = Message::new_with_payer(vec![instruction], Some(&mint_keypair.pubkey()));
+        bank_client
+            .send_message(&[&mint_keypair, &signer0, &signer1], message)
+            .unwrap();
+        let (meta_data, config) = get_config_keys_and_state(&bank_client, &config_pubkey);
+        assert_eq!(
+            meta_data.keys,
+            vec![(pubkey, false), (signer1.pubkey(), true)]
+        );
+        assert_eq!(config, my_config);
+
+        // The rotated key signs updates, without the removed one
+        let new_config = MyConfig::new(84);
+        let instruction =
+            config_instruction::store(&config_pubkey, false, meta_data.keys, &new_config);
+        let message = Message::new_with_payer(vec![instruction], Some(&mint_keypair.pubkey()));
+        bank_client
+            .send_message(&[&mint_keypair, &signer1], message)
+            .unwrap();
+        let (_, config) = get_config_keys_and_state(&bank_client, &config_pubkey);
+        assert_eq!(config, new_config);
+
+        let instruction = config_instruction::store(
+            &config_pubkey,
+            false,
+            vec![(signer0.pubkey(), true)],
+            &my_config,
+        );
+        let message = Message::new_with_payer(vec![instruction], Some(&mint_keypair.pubkey()));
+        bank_client
+            .send_message(&[&mint_keypair, &signer0], message)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_add_signer_without_room() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let (bank_client, config_keypair) = create_config_account(bank, &mint_keypair, vec![]);
+        let config_pubkey = config_keypair.pubkey();
+        let my_config = MyConfig::new(42);
+        let instruction = config_instruction::store(&config_pubkey, true, vec![], &my_config);
+        let message = Message::new_with_payer(vec![instruction], Some(&mint_keypair.pubkey()));
+        bank_client
+            .send_message(&[&mint_keypair, &config_keypair], message)
+            .unwrap();
+
+        let signer0 = Keypair::new();
+        let instruction = config_instruction::add_signer(&config_pubkey, &[], &signer0.pubkey());
+        let message = Message::new_with_payer(vec![instruction], Some(&mint_keypair.pubkey()));
+        bank_client
+            .send_message(&[&mint_keypair, &config_keypair], message)
+            .unwrap_err();
+        let (meta_data, config) = get_config_keys_and_state(&bank_client, &config_pubkey);
+        assert_eq!(meta_data, ConfigKeys::default());
+        assert_eq!(config, my_config);
+    }
+
+    #[test]
+    fn test_config_instruction_prefix_is_not_config_keys() {
+        let mut data = CONFIG_INSTRUCTION_PREFIX.to_vec();
+        data.extend(vec![0; 64]);
+        assert!(deserialize::<ConfigKeys>(&data).is_err());
+
+        let keys: Vec<_> = (0..200).map(|_| (Pubkey::new_rand(), true)).collect();
+        let data = serialize(&ConfigKeys { keys }).unwrap();
+        assert!(!data.starts_with(&CONFIG_INSTRUCTION_PREFIX));
+    }
+
+    #[test]
+    fn test_versioned_config() {
+        let versioned_config = VersionedConfig {
+            version: 1,
+            state: MyConfig::new(42),
+        };
+        assert_eq!(
+            VersionedConfig::<MyConfig>::max_space(),
+            serialized_size(&versioned_config).unwrap()
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde_derive::{Deserialize, Serialize};
 
 pub mod config_instruction;
 pub mod config_processor;
@@ -13,7 +13,21 @@ solana_sdk::solana_name_id!(
     "Config1111111111111111111111111111111111111"
 );
 
-pub trait ConfigState: Serialize {
+pub trait ConfigState: serde::Serialize {
     /// Maximum space that the serialized representation will require
     fn max_space() -> u64;
 }
+
+/// Config state prefixed with the version of its format, so that readers can tell the formats
+/// of a config apart as it evolves
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct VersionedConfig<T> {
+    pub version: u32,
+    pub state: T,
+}
+
+impl<T: ConfigState> ConfigState for VersionedConfig<T> {
+    fn max_space() -> u64 {
+        std::mem::size_of::<u32>() as u64 + T::max_space()
+    }
+}
@@ -41,7 +41,8 @@ pub enum ExchangeInstruction {
     /// key 2 - Token account to source tokens from
     OrderRequest(OrderRequestInfo),
 
-    /// Order cancellation
+    /// Order cancellation, turning the order into a token account holding the tokens left in
+    /// escrow and the tokens settled so far
     /// key 0 - Signer
     /// key 1 - Order to cancel
     OrderCancellation,
@@ -52,6 +53,18 @@ pub enum ExchangeInstruction {
     /// key 3 - `From` trade order
     /// key 6 - Token account in which to deposit the brokers profit from the swap.
     SwapRequest,
+
+    /// Cancellation of several orders at once.  Either every order is cancelled or none are.
+    /// key 0 - Signer
+    /// key 1.. - Orders to cancel
+    BatchOrderCancellation,
+
+    /// Settlement of the tokens an order has been filled with so far, leaving the rest of the
+    /// order open
+    /// key 0 - Signer
+    /// key 1 - Order to settle
+    /// key 2 - Token account to deposit the settled tokens in
+    SettlementRequest,
 }
 
 pub fn account_request(owner: &Pubkey, new: &Pubkey) -> Instruction {
@@ -129,3 +142,22 @@ pub fn swap_request(
     ];
     Instruction::new(id(), &ExchangeInstruction::SwapRequest, account_metas)
 }
+
+pub fn batch_order_cancellation(owner: &Pubkey, orders: &[Pubkey]) -> Instruction {
+    let mut account_metas = vec![AccountMeta::new(*owner, true)];
+    account_metas.extend(orders.iter().map(|order| AccountMeta::new(*order, false)));
+    Instruction::new(
+        id(),
+        &ExchangeInstruction::BatchOrderCancellation,
+        account_metas,
+    )
+}
+
+pub fn settlement_request(owner: &Pubkey, order: &Pubkey, account: &Pubkey) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(*order, false),
+        AccountMeta::new(*account, false),
+    ];
+    Instruction::new(id(), &ExchangeInstruction::SettlementRequest, account_metas)
+}
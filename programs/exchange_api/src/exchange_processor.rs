@@ -61,15 +61,12 @@ impl ExchangeProcessor {
     }
 
     fn trade_to_token_account(trade: &OrderInfo) -> TokenAccountInfo {
-        // Turn trade order into token account
-
-        let token = match trade.direction {
-            Direction::To => trade.pair.Quote,
-            Direction::From => trade.pair.Base,
-        };
+        // Turn trade order into token account, holding what's left in escrow and what's been
+        // settled
 
         let mut account = TokenAccountInfo::default().owner(&trade.owner);
-        account.tokens[token] = trade.tokens_settled;
+        account.tokens[trade.escrow_token()] = trade.tokens;
+        account.tokens[trade.settlement_token()] += trade.tokens_settled;
         account
     }
 
@@ -224,11 +221,7 @@ impl ExchangeProcessor {
                         Err(InstructionError::GenericError)?
                     }
 
-                    let from_token = match from_trade.direction {
-                        Direction::To => from_trade.pair.Quote,
-                        Direction::From => from_trade.pair.Base,
-                    };
-                    if token != from_token {
+                    if token != from_trade.settlement_token() {
                         error!("Trade to transfer from does not hold correct token");
                         Err(InstructionError::GenericError)?
                     }
@@ -290,7 +283,8 @@ impl ExchangeProcessor {
         }
 
         if let Err(e) = check_trade(info.direction, info.tokens, info.price) {
-            bincode::serialize(&e).unwrap();
+            error!("{}", e);
+            Err(InstructionError::InvalidArgument)?
         }
 
         // Trade holds the tokens in escrow
@@ -324,26 +318,78 @@ impl ExchangeProcessor {
             Err(InstructionError::InvalidArgument)?
         }
 
-        let order = Self::deserialize_order(&keyed_accounts[ORDER_INDEX].account.data)?;
+        Self::cancel_order(keyed_accounts, OWNER_INDEX, ORDER_INDEX)
+    }
 
-        if &order.owner != keyed_accounts[OWNER_INDEX].unsigned_key() {
+    fn cancel_order(
+        keyed_accounts: &mut [KeyedAccount],
+        owner_index: usize,
+        order_index: usize,
+    ) -> Result<(), InstructionError> {
+        let order = Self::deserialize_order(&keyed_accounts[order_index].account.data)?;
+
+        if &order.owner != keyed_accounts[owner_index].unsigned_key() {
             error!("Signer does not own trade");
             Err(InstructionError::GenericError)?
         }
 
-        let token = match order.direction {
-            Direction::To => order.pair.Base,
-            Direction::From => order.pair.Quote,
-        };
+        // Turn trade order into a token account
+        Self::serialize(
+            &ExchangeState::Account(Self::trade_to_token_account(&order)),
+            &mut keyed_accounts[order_index].account.data,
+        )
+    }
+
+    fn do_batch_order_cancellation(
+        keyed_accounts: &mut [KeyedAccount],
+    ) -> Result<(), InstructionError> {
+        const OWNER_INDEX: usize = 0;
+        const FIRST_ORDER_INDEX: usize = 1;
 
-        let mut account = TokenAccountInfo::default().owner(&order.owner);
-        account.tokens[token] = order.tokens;
-        account.tokens[token] += order.tokens_settled;
+        if keyed_accounts.len() < 2 {
+            error!("Not enough accounts");
+            Err(InstructionError::InvalidArgument)?
+        }
+
+        // The runtime discards every cancellation if any of them fails
+        for order_index in FIRST_ORDER_INDEX..keyed_accounts.len() {
+            Self::cancel_order(keyed_accounts, OWNER_INDEX, order_index)?;
+        }
+        Ok(())
+    }
+
+    fn do_settlement_request(keyed_accounts: &mut [KeyedAccount]) -> Result<(), InstructionError> {
+        const OWNER_INDEX: usize = 0;
+        const ORDER_INDEX: usize = 1;
+        const ACCOUNT_INDEX: usize = 2;
+
+        if keyed_accounts.len() < 3 {
+            error!("Not enough accounts");
+            Err(InstructionError::InvalidArgument)?
+        }
+
+        let mut order = Self::deserialize_order(&keyed_accounts[ORDER_INDEX].account.data)?;
+        let mut account = Self::deserialize_account(&keyed_accounts[ACCOUNT_INDEX].account.data)?;
+
+        if &order.owner != keyed_accounts[OWNER_INDEX].unsigned_key() {
+            error!("Signer does not own trade");
+            Err(InstructionError::GenericError)?
+        }
+        if &account.owner != keyed_accounts[OWNER_INDEX].unsigned_key() {
+            error!("Signer does not own account");
+            Err(InstructionError::GenericError)?
+        }
+
+        account.tokens[order.settlement_token()] += order.tokens_settled;
+        order.tokens_settled = 0;
 
-        // Turn trade order into a token account
         Self::serialize(
-            &ExchangeState::Account(account),
+            &ExchangeState::Trade(order),
             &mut keyed_accounts[ORDER_INDEX].account.data,
+        )?;
+        Self::serialize(
+            &ExchangeState::Account(account),
+            &mut keyed_accounts[ACCOUNT_INDEX].account.data,
         )
     }
 
@@ -395,7 +441,7 @@ impl ExchangeProcessor {
         if to_order.tokens == 0 {
             // Turn into token account
             Self::serialize(
-                &ExchangeState::Account(Self::trade_to_token_account(&from_order)),
+                &ExchangeState::Account(Self::trade_to_token_account(&to_order)),
                 &mut keyed_accounts[TO_ORDER_INDEX].account.data,
             )?;
         } else {
@@ -439,6 +485,11 @@ pub fn process_instruction(
 
     trace!("{:?}", command);
 
+    if keyed_accounts.is_empty() || keyed_accounts[0].signer_key().is_none() {
+        error!("account[0] is unsigned");
+        Err(InstructionError::MissingRequiredSignature)?
+    }
+
     match command {
         ExchangeInstruction::AccountRequest => {
             ExchangeProcessor::do_account_request(keyed_accounts)
@@ -453,6 +504,12 @@ pub fn process_instruction(
             ExchangeProcessor::do_order_cancellation(keyed_accounts)
         }
         ExchangeInstruction::SwapRequest => ExchangeProcessor::do_swap_request(keyed_accounts),
+        ExchangeInstruction::BatchOrderCancellation => {
+            ExchangeProcessor::do_batch_order_cancellation(keyed_accounts)
+        }
+        ExchangeInstruction::SettlementRequest => {
+            ExchangeProcessor::do_settlement_request(keyed_accounts)
+        }
     }
 }
 
@@ -462,6 +519,7 @@ mod test {
     use crate::{exchange_instruction, id};
     use solana_runtime::bank::Bank;
     use solana_runtime::bank_client::BankClient;
+    use solana_sdk::account::Account;
     use solana_sdk::client::SyncClient;
     use solana_sdk::genesis_block::create_genesis_block;
     use solana_sdk::signature::{Keypair, KeypairUtil};
@@ -858,4 +916,344 @@ mod test {
             ExchangeProcessor::deserialize_account(&new_account_data).unwrap()
         );
     }
+
+    #[test]
+    fn test_exchange_order_cancellation() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let (client, owner) = create_client(bank, mint_keypair);
+
+        let profit = create_token_account(&client, &owner);
+        let (to_trade, _) = trade(
+            &client,
+            &owner,
+            Direction::To,
+            AssetPair::default(),
+            Token::A,
+            3,
+            3,
+            2000,
+        );
+        let (from_trade, _) = trade(
+            &client,
+            &owner,
+            Direction::From,
+            AssetPair::default(),
+            Token::B,
+            3,
+            3,
+            3000,
+        );
+        let instruction =
+            exchange_instruction::swap_request(&owner.pubkey(), &to_trade, &from_trade, &profit);
+        client
+            .send_instruction(&owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+
+        // The partially filled order returns its escrow and its settled tokens
+        let instruction = exchange_instruction::order_cancellation(&owner.pubkey(), &to_trade);
+        client
+            .send_instruction(&owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+        let to_trade_account_data = client.get_account_data(&to_trade).unwrap().unwrap();
+        assert_eq!(
+            TokenAccountInfo::default()
+                .owner(&owner.pubkey())
+                .tokens(2, 2, 0, 0),
+            ExchangeProcessor::deserialize_account(&to_trade_account_data).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_exchange_batch_order_cancellation() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let (client, owner) = create_client(bank, mint_keypair);
+
+        let (trade0, _) = trade(
+            &client,
+            &owner,
+            Direction::To,
+            AssetPair::default(),
+            Token::A,
+            3,
+            3,
+            2000,
+        );
+        let (trade1, _) = trade(
+            &client,
+            &owner,
+            Direction::From,
+            AssetPair::default(),
+            Token::B,
+            4,
+            4,
+            3000,
+        );
+        let token_account = create_token_account(&client, &owner);
+
+        // Nothing is cancelled if any of the accounts isn't an order
+        let instruction = exchange_instruction::batch_order_cancellation(
+            &owner.pubkey(),
+            &[trade0, token_account, trade1],
+        );
+        client
+            .send_instruction(&owner, instruction)
+            .expect_err(&format!("{}:{}", line!(), file!()));
+        let trade0_account_data = client.get_account_data(&trade0).unwrap().unwrap();
+        ExchangeProcessor::deserialize_order(&trade0_account_data).unwrap();
+
+        let instruction =
+            exchange_instruction::batch_order_cancellation(&owner.pubkey(), &[trade0, trade1]);
+        client
+            .send_instruction(&owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+        let trade0_account_data = client.get_account_data(&trade0).unwrap().unwrap();
+        let trade1_account_data = client.get_account_data(&trade1).unwrap().unwrap();
+        assert_eq!(
+            TokenAccountInfo::default()
+                .owner(&owner.pubkey())
+                .tokens(3, 0, 0, 0),
+            ExchangeProcessor::deserialize_account(&trade0_account_data).unwrap()
+        );
+        assert_eq!(
+            TokenAccountInfo::default()
+                .owner(&owner.pubkey())
+                .tokens(0, 4, 0, 0),
+            ExchangeProcessor::deserialize_account(&trade1_account_data).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_exchange_settlement_request() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let (client, owner) = create_client(bank, mint_keypair);
+
+        let profit = create_token_account(&client, &owner);
+        let (to_trade, src) = trade(
+            &client,
+            &owner,
+            Direction::To,
+            AssetPair::default(),
+            Token::A,
+            3,
+            3,
+            2000,
+        );
+        let (from_trade, _) = trade(
+            &client,
+            &owner,
+            Direction::From,
+            AssetPair::default(),
+            Token::B,
+            3,
+            3,
+            3000,
+        );
+        let instruction =
+            exchange_instruction::swap_request(&owner.pubkey(), &to_trade, &from_trade, &profit);
+        client
+            .send_instruction(&owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+
+        let instruction =
+            exchange_instruction::settlement_request(&owner.pubkey(), &to_trade, &src);
+        client
+            .send_instruction(&owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+
+        let to_trade_account_data = client.get_account_data(&to_trade).unwrap().unwrap();
+        let src_account_data = client.get_account_data(&src).unwrap().unwrap();
+        assert_eq!(
+            OrderInfo {
+                owner: owner.pubkey(),
+                direction: Direction::To,
+                pair: AssetPair::default(),
+                tokens: 2,
+                price: 2000,
+                tokens_settled: 0,
+            },
+            ExchangeProcessor::deserialize_order(&to_trade_account_data).unwrap()
+        );
+        assert_eq!(
+            TokenAccountInfo::default()
+                .owner(&owner.pubkey())
+                .tokens(100_000, 100_002, 100_000, 100_000),
+            ExchangeProcessor::deserialize_account(&src_account_data).unwrap()
+        );
+    }
+
+    /// A small, seeded generator, so that failures can be reproduced
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    /// Runs `instruction` over the `accounts` at `indexes`, keeping the changes only if it
+    /// succeeds, like the runtime
+    fn process_at(
+        accounts: &mut [(Pubkey, Account)],
+        indexes: &[usize],
+        instruction: &ExchangeInstruction,
+    ) -> Result<(), InstructionError> {
+        let mut instruction_accounts: Vec<_> =
+            indexes.iter().map(|i| accounts[*i].clone()).collect();
+        let mut keyed_accounts: Vec<_> = instruction_accounts
+            .iter_mut()
+            .enumerate()
+            .map(|(i, (pubkey, account))| KeyedAccount::new(pubkey, i == 0, account))
+            .collect();
+        process_instruction(
+            &id(),
+            &mut keyed_accounts,
+            &bincode::serialize(instruction).unwrap(),
+        )?;
+        for (i, instruction_account) in indexes.iter().zip(instruction_accounts) {
+            accounts[*i] = instruction_account;
+        }
+        Ok(())
+    }
+
+    /// Every token held by token accounts, or held or settled by orders
+    fn total_tokens(accounts: &[(Pubkey, Account)]) -> Tokens {
+        let mut total = Tokens::default();
+        for (_, account) in accounts {
+            match bincode::deserialize(&account.data) {
+                Ok(ExchangeState::Account(info)) => {
+                    for token in &[Token::A, Token::B, Token::C, Token::D] {
+                        total[*token] += info.tokens[*token];
+                    }
+                }
+                Ok(ExchangeState::Trade(order)) => {
+                    total[order.escrow_token()] += order.tokens;
+                    total[order.settlement_token()] += order.tokens_settled;
+                }
+                _ => (),
+            }
+        }
+        total
+    }
+
+    #[test]
+    fn test_exchange_conservation_of_funds() {
+        solana_logger::setup();
+        let mut rng = XorShift(0x5eed_0f_e8c4_a96e);
+        let pairs = [
+            AssetPair::default(),
+            AssetPair {
+                Base: Token::C,
+                Quote: Token::D,
+            },
+        ];
+        let num_owners = 4;
+
+        // Each owner is followed by its token account
+        let mut accounts = vec![];
+        for _ in 0..num_owners {
+            let owner = Pubkey::new_rand();
+            let mut token_account = Account::new(1, mem::size_of::<ExchangeState>(), &id());
+            ExchangeProcessor::serialize(
+                &ExchangeState::Account(
+                    TokenAccountInfo::default()
+                        .owner(&owner)
+                        .tokens(10_000, 10_000, 10_000, 10_000),
+                ),
+                &mut token_account.data,
+            )
+            .unwrap();
+            accounts.push((owner, Account::new(1, 0, &Pubkey::default())));
+            accounts.push((Pubkey::new_rand(), token_account));
+        }
+        let total = total_tokens(&accounts);
+
+        let mut orders: Vec<(usize, usize)> = vec![];
+        let mut num_swaps = 0;
+        for _ in 0..2_000 {
+            let owner_index = 2 * rng.below(num_owners) as usize;
+            let result = match rng.below(8) {
+                0..=3 => {
+                    let direction = if rng.below(2) == 0 {
+                        Direction::To
+                    } else {
+                        Direction::From
+                    };
+                    let info = OrderRequestInfo {
+                        direction,
+                        pair: pairs[rng.below(2) as usize],
+                        tokens: 1 + rng.below(1_000),
+                        price: 500 + rng.below(1_500),
+                    };
+                    accounts.push((
+                        Pubkey::new_rand(),
+                        Account::new(1, mem::size_of::<ExchangeState>(), &id()),
+                    ));
+                    let order_index = accounts.len() - 1;
+                    let result = process_at(
+                        &mut accounts,
+                        &[owner_index, order_index, owner_index + 1],
+                        &ExchangeInstruction::OrderRequest(info),
+                    );
+                    if result.is_ok() {
+                        orders.push((owner_index, order_index));
+                    }
+                    result
+                }
+                4 | 5 if orders.len() >= 2 => {
+                    let (_, to_index) = orders[rng.below(orders.len() as u64) as usize];
+                    let (_, from_index) = orders[rng.below(orders.len() as u64) as usize];
+                    if to_index == from_index {
+                        continue;
+                    }
+                    let result = process_at(
+                        &mut accounts,
+                        &[owner_index, to_index, from_index, owner_index + 1],
+                        &ExchangeInstruction::SwapRequest,
+                    );
+                    if result.is_ok() {
+                        num_swaps += 1;
+                    }
+                    result
+                }
+                6 if !orders.is_empty() => {
+                    let (owner_index, order_index) =
+                        orders[rng.below(orders.len() as u64) as usize];
+                    process_at(
+                        &mut accounts,
+                        &[owner_index, order_index, owner_index + 1],
+                        &ExchangeInstruction::SettlementRequest,
+                    )
+                }
+                7 if !orders.is_empty() => {
+                    let (owner_index, order_index) =
+                        orders.remove(rng.below(orders.len() as u64) as usize);
+                    let mut indexes = vec![owner_index, order_index];
+                    if let Some(position) =
+                        orders.iter().position(|(owner, _)| *owner == owner_index)
+                    {
+                        indexes.push(orders.remove(position).1);
+                    }
+                    process_at(
+                        &mut accounts,
+                        &indexes,
+                        &ExchangeInstruction::BatchOrderCancellation,
+                    )
+                }
+                _ => continue,
+            };
+            trace!("{:?}", result);
+            assert_eq!(total_tokens(&accounts), total);
+        }
+        assert!(num_swaps > 0);
+    }
 }
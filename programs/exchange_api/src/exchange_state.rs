@@ -184,9 +184,29 @@ impl OrderInfo {
         self.price = price;
         self
     }
+    /// The token the order holds in escrow until it's filled
+    pub fn escrow_token(&self) -> Token {
+        match self.direction {
+            Direction::To => self.pair.Base,
+            Direction::From => self.pair.Quote,
+        }
+    }
+    /// The token the order settles in as it's filled
+    pub fn settlement_token(&self) -> Token {
+        match self.direction {
+            Direction::To => self.pair.Quote,
+            Direction::From => self.pair.Base,
+        }
+    }
 }
 
 pub fn check_trade(direction: Direction, tokens: u64, price: u64) -> Result<(), ExchangeError> {
+    if price == 0 {
+        Err(ExchangeError::InvalidTrade(format!(
+            "Trade of {} at a price of 0",
+            tokens
+        )))?
+    }
     match direction {
         Direction::To => {
             if tokens * price / SCALER == 0 {
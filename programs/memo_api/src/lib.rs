@@ -0,0 +1,12 @@
+pub mod memo_instruction;
+pub mod memo_processor;
+
+const MEMO_PROGRAM_ID: [u8; 32] = [
+    5, 74, 83, 80, 201, 237, 149, 176, 165, 18, 197, 150, 155, 137, 8, 230, 245, 248, 181, 88, 237,
+    178, 111, 88, 95, 71, 65, 0, 0, 0, 0, 0,
+];
+
+solana_sdk::solana_name_id!(
+    MEMO_PROGRAM_ID,
+    "Memo111111111111111111111111111111111111111"
+);
@@ -0,0 +1,12 @@
+use crate::id;
+use solana_sdk::instruction::Instruction;
+
+/// Attach `memo` to a transaction. The memo is stored as is in the instruction data, so that
+/// anyone reading the transaction can find it without knowing about this program.
+pub fn memo(memo: &str) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![],
+        data: memo.as_bytes().to_vec(),
+    }
+}
@@ -0,0 +1,64 @@
+//! Memo program
+
+use log::*;
+use solana_sdk::account::KeyedAccount;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use std::str;
+
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    _keyed_accounts: &mut [KeyedAccount],
+    data: &[u8],
+) -> Result<(), InstructionError> {
+    solana_logger::setup();
+
+    let memo = str::from_utf8(data).map_err(|err| {
+        error!("Invalid UTF-8 memo: {:?} {:?}", data, err);
+        InstructionError::InvalidInstructionData
+    })?;
+    debug!("memo: {}", memo);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{id, memo_instruction};
+    use solana_runtime::bank::Bank;
+    use solana_runtime::bank_client::BankClient;
+    use solana_sdk::client::SyncClient;
+    use solana_sdk::genesis_block::create_genesis_block;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::message::Message;
+    use solana_sdk::signature::KeypairUtil;
+    use solana_sdk::system_instruction;
+
+    #[test]
+    fn test_process_memo() {
+        let (genesis_block, mint_keypair) = create_genesis_block(10_000);
+        let mut bank = Bank::new(&genesis_block);
+        bank.add_instruction_processor(id(), process_instruction);
+        let bank_client = BankClient::new(bank);
+        let mint_pubkey = mint_keypair.pubkey();
+        let to = Pubkey::new_rand();
+
+        let message = Message::new(vec![
+            system_instruction::transfer(&mint_pubkey, &to, 1),
+            memo_instruction::memo("thanks for lunch"),
+        ]);
+        bank_client.send_message(&[&mint_keypair], message).unwrap();
+        assert_eq!(bank_client.get_balance(&to).unwrap(), 1);
+
+        let invalid_memo = Instruction {
+            data: vec![0xff, 0xfe],
+            ..memo_instruction::memo("")
+        };
+        let message = Message::new(vec![
+            system_instruction::transfer(&mint_pubkey, &to, 1),
+            invalid_memo,
+        ]);
+        assert!(bank_client.send_message(&[&mint_keypair], message).is_err());
+        assert_eq!(bank_client.get_balance(&to).unwrap(), 1);
+    }
+}
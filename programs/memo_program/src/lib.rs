@@ -0,0 +1,9 @@
+#[macro_export]
+macro_rules! solana_memo_program {
+    () => {
+        ("solana_memo_program".to_string(), solana_memo_api::id())
+    };
+}
+use solana_memo_api::memo_processor::process_instruction;
+
+solana_sdk::solana_entrypoint!(process_instruction);
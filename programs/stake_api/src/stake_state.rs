@@ -10,7 +10,7 @@ use solana_sdk::account_utils::State;
 use solana_sdk::instruction::InstructionError;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::sysvar;
-use solana_sdk::timing::Epoch;
+use solana_sdk::timing::{Epoch, Slot};
 use solana_vote_api::vote_state::VoteState;
 use std::cmp;
 
@@ -19,6 +19,10 @@ pub enum StakeState {
     Uninitialized,
     Stake(Stake),
     RewardsPool,
+    /// A stake that can't be withdrawn from before the given slot. The lockup is kept out of
+    /// `Stake`, in a variant of its own, so stake accounts created before lockups keep their
+    /// layout, and their size.
+    LockedStake(Stake, Slot),
 }
 
 impl Default for StakeState {
@@ -38,11 +42,26 @@ impl StakeState {
     }
 
     pub fn stake(&self) -> Option<Stake> {
+        self.stake_and_lockup().map(|(stake, _)| stake)
+    }
+
+    /// The stake and the slot before which it can't be withdrawn from, 0 if never locked up
+    pub fn stake_and_lockup(&self) -> Option<(Stake, Slot)> {
         match self {
-            StakeState::Stake(stake) => Some(stake.clone()),
+            StakeState::Stake(stake) => Some((stake.clone(), 0)),
+            StakeState::LockedStake(stake, lockup) => Some((stake.clone(), *lockup)),
             _ => None,
         }
     }
+
+    /// The state of `stake` locked up until `lockup`, in the legacy layout if there's no lockup
+    pub fn new_stake(stake: Stake, lockup: Slot) -> Self {
+        if lockup == 0 {
+            StakeState::Stake(stake)
+        } else {
+            StakeState::LockedStake(stake, lockup)
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -96,6 +115,21 @@ impl Stake {
         }
     }
 
+    /// the stake that is (effective, activating, deactivating) at `epoch`.  activating stake
+    ///  is still warming up, deactivating stake is effective but cooling down
+    pub fn stake_activating_and_deactivating(&self, epoch: u64) -> (u64, u64, u64) {
+        let effective = self.stake(epoch);
+        if epoch < self.activated || epoch >= self.deactivated {
+            (0, 0, 0)
+        } else if epoch - self.activated < STAKE_WARMUP_EPOCHS {
+            (effective, self.stake - effective, 0)
+        } else if self.deactivated - epoch < STAKE_WARMUP_EPOCHS {
+            (effective, 0, effective)
+        } else {
+            (effective, 0, 0)
+        }
+    }
+
     /// for a given stake and vote_state, calculate what distributions and what updates should be made
     /// returns a tuple in the case of a payout of:
     ///   * voter_rewards to be distributed
@@ -230,10 +264,11 @@ impl<'a> StakeAccount for KeyedAccount<'a> {
             return Err(InstructionError::MissingRequiredSignature);
         }
 
-        if let StakeState::Stake(mut stake) = self.state()? {
+        let stake_state: StakeState = self.state()?;
+        if let Some((mut stake, lockup)) = stake_state.stake_and_lockup() {
             stake.deactivate(clock.epoch);
 
-            self.set_state(&StakeState::Stake(stake))
+            self.set_state(&StakeState::new_stake(stake, lockup))
         } else {
             Err(InstructionError::InvalidAccountData)
         }
@@ -244,8 +279,9 @@ impl<'a> StakeAccount for KeyedAccount<'a> {
         rewards_account: &mut KeyedAccount,
         rewards: &sysvar::rewards::Rewards,
     ) -> Result<(), InstructionError> {
-        if let (StakeState::Stake(mut stake), StakeState::RewardsPool) =
-            (self.state()?, rewards_account.state()?)
+        let stake_state: StakeState = self.state()?;
+        if let (Some((mut stake, lockup)), StakeState::RewardsPool) =
+            (stake_state.stake_and_lockup(), rewards_account.state()?)
         {
             let vote_state: VoteState = vote_account.state()?;
 
@@ -256,17 +292,20 @@ impl<'a> StakeAccount for KeyedAccount<'a> {
             if let Some((stakers_reward, voters_reward, credits_observed)) =
                 stake.calculate_rewards(rewards.validator_point_value, &vote_state)
             {
-                if rewards_account.account.lamports < (stakers_reward + voters_reward) {
+                let total_reward = stakers_reward
+                    .checked_add(voters_reward)
+                    .ok_or(InstructionError::ArithmeticOverflow)?;
+                if rewards_account.account.lamports < total_reward {
                     return Err(InstructionError::UnbalancedInstruction);
                 }
-                rewards_account.account.lamports -= stakers_reward + voters_reward;
+                rewards_account.account.checked_sub_lamports(total_reward)?;
 
-                self.account.lamports += stakers_reward;
-                vote_account.account.lamports += voters_reward;
+                self.account.checked_add_lamports(stakers_reward)?;
+                vote_account.account.checked_add_lamports(voters_reward)?;
 
                 stake.credits_observed = credits_observed;
 
-                self.set_state(&StakeState::Stake(stake))
+                self.set_state(&StakeState::new_stake(stake, lockup))
             } else {
                 // not worth collecting
                 Err(InstructionError::CustomError(1))
@@ -286,7 +325,21 @@ impl<'a> StakeAccount for KeyedAccount<'a> {
         }
 
         match self.state()? {
-            StakeState::Stake(mut stake) => {
+            StakeState::Uninitialized => {
+                if lamports > self.account.lamports {
+                    return Err(InstructionError::InsufficientFunds);
+                }
+                self.account.checked_sub_lamports(lamports)?;
+                to.account.checked_add_lamports(lamports)?;
+                Ok(())
+            }
+            stake_state => {
+                let (mut stake, lockup) = stake_state
+                    .stake_and_lockup()
+                    .ok_or(InstructionError::InvalidAccountData)?;
+                if clock.slot < lockup {
+                    return Err(InstructionError::InsufficientFunds);
+                }
                 let staked = if stake.stake(clock.epoch) == 0 {
                     0
                 } else {
@@ -296,21 +349,12 @@ impl<'a> StakeAccount for KeyedAccount<'a> {
                 if lamports > self.account.lamports.saturating_sub(staked) {
                     return Err(InstructionError::InsufficientFunds);
                 }
-                self.account.lamports -= lamports;
-                to.account.lamports += lamports;
+                self.account.checked_sub_lamports(lamports)?;
+                to.account.checked_add_lamports(lamports)?;
                 // Adjust the stake (in case balance dropped below stake)
                 stake.stake = cmp::min(stake.stake, self.account.lamports);
-                self.set_state(&StakeState::Stake(stake))
-            }
-            StakeState::Uninitialized => {
-                if lamports > self.account.lamports {
-                    return Err(InstructionError::InsufficientFunds);
-                }
-                self.account.lamports -= lamports;
-                to.account.lamports += lamports;
-                Ok(())
+                self.set_state(&StakeState::new_stake(stake, lockup))
             }
-            _ => Err(InstructionError::InvalidAccountData),
         }
     }
 }
@@ -320,17 +364,30 @@ pub fn create_stake_account(
     voter_pubkey: &Pubkey,
     vote_state: &VoteState,
     lamports: u64,
+) -> Account {
+    create_stake_account_with_lockup(voter_pubkey, vote_state, lamports, 0)
+}
+
+// utility function, used by genesis to create stake that can't be withdrawn until `lockup`
+pub fn create_stake_account_with_lockup(
+    voter_pubkey: &Pubkey,
+    vote_state: &VoteState,
+    lamports: u64,
+    lockup: Slot,
 ) -> Account {
     let mut stake_account = Account::new(lamports, std::mem::size_of::<StakeState>(), &id());
 
     stake_account
-        .set_state(&StakeState::Stake(Stake {
-            voter_pubkey: *voter_pubkey,
-            credits_observed: vote_state.credits(),
-            stake: lamports,
-            activated: 0,
-            deactivated: std::u64::MAX,
-        }))
+        .set_state(&StakeState::new_stake(
+            Stake {
+                voter_pubkey: *voter_pubkey,
+                credits_observed: vote_state.credits(),
+                stake: lamports,
+                activated: 0,
+                deactivated: std::u64::MAX,
+            },
+            lockup,
+        ))
         .expect("set_state");
 
     stake_account
@@ -449,6 +506,41 @@ mod tests {
         assert_eq!(stake.stake(STAKE_WARMUP_EPOCHS * 42), 0);
     }
 
+    #[test]
+    fn test_stake_activating_and_deactivating() {
+        let mut stake = Stake::default();
+        let staked = STAKE_WARMUP_EPOCHS * 10;
+        stake.delegate(staked, &Pubkey::default(), &VoteState::default(), 1);
+        assert_eq!(stake.stake_activating_and_deactivating(0), (0, 0, 0));
+
+        // warmup
+        for i in 0..STAKE_WARMUP_EPOCHS {
+            let effective = stake.stake(1 + i);
+            assert_eq!(
+                stake.stake_activating_and_deactivating(1 + i),
+                (effective, staked - effective, 0)
+            );
+        }
+        assert_eq!(
+            stake.stake_activating_and_deactivating(1 + STAKE_WARMUP_EPOCHS),
+            (staked, 0, 0)
+        );
+
+        // cooldown
+        stake.deactivate(STAKE_WARMUP_EPOCHS * 3);
+        for i in stake.deactivated - STAKE_WARMUP_EPOCHS..stake.deactivated {
+            let effective = stake.stake(i);
+            assert_eq!(
+                stake.stake_activating_and_deactivating(i),
+                (effective, 0, effective)
+            );
+        }
+        assert_eq!(
+            stake.stake_activating_and_deactivating(stake.deactivated),
+            (0, 0, 0)
+        );
+    }
+
     #[test]
     fn test_deactivate_stake() {
         let stake_pubkey = Pubkey::new_rand();
@@ -596,6 +688,77 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_stake_state_pre_lockup_layout() {
+        // The stake state as laid out before lockups
+        #[allow(dead_code)]
+        #[derive(Serialize)]
+        enum StakeStateV0 {
+            Uninitialized,
+            Stake(Stake),
+            RewardsPool,
+        }
+        let stake_pubkey = Pubkey::new_rand();
+        let stake = Stake {
+            stake: 42,
+            ..Stake::default()
+        };
+        let mut stake_account =
+            Account::new_data(42, &StakeStateV0::Stake(stake.clone()), &id()).unwrap();
+        assert_eq!(
+            StakeState::from(&stake_account),
+            Some(StakeState::Stake(stake.clone()))
+        );
+
+        // Still fits its account once updated, since a stake without lockup keeps its layout
+        let clock = sysvar::clock::Clock::default();
+        let mut stake_keyed_account = KeyedAccount::new(&stake_pubkey, true, &mut stake_account);
+        assert_eq!(stake_keyed_account.deactivate_stake(&clock), Ok(()));
+        assert!(StakeState::stake_from(&stake_account).unwrap().deactivated < std::u64::MAX);
+
+        // Stakes with a lockup round-trip through their own variant
+        let stake_account =
+            create_stake_account_with_lockup(&Pubkey::default(), &VoteState::default(), 42, 10);
+        assert_eq!(
+            StakeState::from(&stake_account).unwrap().stake_and_lockup(),
+            Some((stake, 10))
+        );
+    }
+
+    #[test]
+    fn test_withdraw_stake_lockup() {
+        let stake_pubkey = Pubkey::new_rand();
+        let vote_pubkey = Pubkey::new_rand();
+        let total_lamports = 100;
+        let mut stake_account = create_stake_account_with_lockup(
+            &vote_pubkey,
+            &VoteState::default(),
+            total_lamports,
+            10,
+        );
+
+        // fully cooled down, but still locked up
+        let mut clock = sysvar::clock::Clock::default();
+        clock.epoch = std::u64::MAX;
+        clock.slot = 9;
+
+        let to = Pubkey::new_rand();
+        let mut to_account = Account::new(1, 0, &system_program::id());
+        let mut to_keyed_account = KeyedAccount::new(&to, false, &mut to_account);
+
+        let mut stake_keyed_account = KeyedAccount::new(&stake_pubkey, true, &mut stake_account);
+        assert_eq!(
+            stake_keyed_account.withdraw(1, &mut to_keyed_account, &clock),
+            Err(InstructionError::InsufficientFunds)
+        );
+
+        clock.slot = 10;
+        assert_eq!(
+            stake_keyed_account.withdraw(total_lamports, &mut to_keyed_account, &clock),
+            Ok(())
+        );
+    }
+
     #[test]
     fn test_withdraw_stake_invalid_state() {
         let stake_pubkey = Pubkey::new_rand();
@@ -801,5 +964,4 @@ mod tests {
             Err(InstructionError::InvalidArgument)
         );
     }
-
 }
@@ -15,6 +15,7 @@ use solana_stake_api::stake_instruction::process_instruction;
 use solana_stake_api::stake_state::StakeState;
 use solana_vote_api::vote_instruction;
 use solana_vote_api::vote_state::{Vote, VoteState};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 fn fill_epoch_with_votes(
@@ -81,7 +82,7 @@ fn test_stake_account_delegate() {
         10,
     ));
     bank_client
-        .send_message(&[&mint_keypair], message)
+        .send_message(&[&mint_keypair, &vote_keypair], message)
         .expect("failed to create vote account");
 
     // Create stake account and delegate to vote account
@@ -173,6 +174,13 @@ fn test_stake_account_delegate() {
         assert!(false, "wrong account type found")
     }
 
+    // The bank records the reward it paid
+    let paid_rewards: HashMap<_, _> = bank.take_rewards().into_iter().collect();
+    let stake_reward = paid_rewards[&staker_pubkey];
+    assert_eq!(stake_reward.lamports, rewards);
+    assert_eq!(stake_reward.post_balance, 20000 + rewards);
+    assert_eq!(stake_reward.commission, std::u8::MAX / 2);
+
     // Deactivate the stake
     let message = Message::new_with_payer(
         vec![stake_instruction::deactivate_stake(&staker_pubkey)],
@@ -0,0 +1,252 @@
+//! config for the storage program
+//! * lives in a config program account created at genesis
+//! * decides who may submit mining proofs: replicators must either reference a
+//!   stake account holding at least `min_stake` or be on the whitelist
+//! * decides whose proof validations count: validators must reference a stake
+//!   account holding at least `min_stake`
+
+use crate::storage_contract::StorageError;
+use bincode::{deserialize, serialize, serialized_size};
+use serde_derive::{Deserialize, Serialize};
+use solana_config_api::{config_instruction::ConfigKeys, ConfigState};
+use solana_sdk::account::{Account, KeyedAccount};
+use solana_sdk::genesis_block::Builder;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_stake_api::stake_state::StakeState;
+
+// storage config account ID
+const ID: [u8; 32] = [
+    6, 162, 25, 123, 127, 70, 106, 206, 155, 23, 57, 41, 99, 133, 118, 24, 11, 222, 164, 66, 242,
+    33, 67, 172, 91, 99, 194, 210, 64, 0, 0, 0,
+];
+
+solana_sdk::solana_name_id!(ID, "StorageConfig111111111111111111111111111111");
+
+/// Maximum number of replicator storage accounts on the whitelist
+pub const MAX_WHITELIST_LEN: usize = 64;
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Clone)]
+pub struct Config {
+    /// Lamports of active stake a replicator must reference to submit mining proofs
+    pub min_stake: u64,
+    /// Replicator storage accounts that may submit mining proofs without stake
+    pub whitelist: Vec<Pubkey>,
+}
+
+impl ConfigState for Config {
+    fn max_space() -> u64 {
+        serialized_size(&Config {
+            min_stake: 0,
+            whitelist: vec![Pubkey::default(); MAX_WHITELIST_LEN],
+        })
+        .unwrap()
+    }
+}
+
+impl Config {
+    /// Checks that `storage_pubkey` may submit a mining proof, either because it's
+    /// whitelisted or because `stake_account` is a signed stake account holding
+    /// at least `min_stake` active lamports at `epoch`
+    pub fn check_proof_submitter(
+        &self,
+        storage_pubkey: &Pubkey,
+        stake_account: Option<&KeyedAccount>,
+        epoch: u64,
+    ) -> Result<(), InstructionError> {
+        if self.min_stake == 0 || self.whitelist.contains(storage_pubkey) {
+            return Ok(());
+        }
+        self.check_stake(stake_account, epoch).map(|_| ())
+    }
+
+    /// The stake a validator's proof validations count with: the active lamports at
+    /// `epoch` of `stake_account`, a signed stake account holding at least
+    /// `min_stake`. Without a minimum stake every validator counts the same, as 1.
+    pub fn validator_stake(
+        &self,
+        stake_account: Option<&KeyedAccount>,
+        epoch: u64,
+    ) -> Result<u64, InstructionError> {
+        if self.min_stake == 0 {
+            return Ok(1);
+        }
+        self.check_stake(stake_account, epoch)
+    }
+
+    fn check_stake(
+        &self,
+        stake_account: Option<&KeyedAccount>,
+        epoch: u64,
+    ) -> Result<u64, InstructionError> {
+        let stake_account = stake_account.ok_or(InstructionError::CustomError(
+            StorageError::InsufficientStake as u32,
+        ))?;
+        if stake_account.signer_key().is_none() {
+            Err(InstructionError::MissingRequiredSignature)?;
+        }
+        if stake_account.account.owner != solana_stake_api::id() {
+            Err(InstructionError::IncorrectProgramId)?;
+        }
+        let stake = StakeState::stake_from(&stake_account.account)
+            .map(|stake| stake.stake(epoch))
+            .unwrap_or(0);
+        if stake < self.min_stake {
+            Err(InstructionError::CustomError(
+                StorageError::InsufficientStake as u32,
+            ))?;
+        }
+        Ok(stake)
+    }
+}
+
+/// Creates the config account, `authority` signs later whitelist and minimum
+/// stake updates through the config program
+pub fn create_account(lamports: u64, authority: &Pubkey, config: &Config) -> Account {
+    let keys = vec![(*authority, true)];
+    let space = ConfigKeys::serialized_size(keys.clone()) as u64 + Config::max_space();
+    let mut account = Account::new(lamports, space as usize, &solana_config_api::id());
+    let data = serialize(&(ConfigKeys { keys }, config)).unwrap();
+    account.data[..data.len()].copy_from_slice(&data);
+    account
+}
+
+pub fn from(account: &Account) -> Option<Config> {
+    deserialize::<(ConfigKeys, Config)>(&account.data)
+        .ok()
+        .map(|(_, config)| config)
+}
+
+pub fn from_keyed_account(account: &KeyedAccount) -> Result<Config, InstructionError> {
+    if !check_id(account.unsigned_key()) {
+        Err(InstructionError::InvalidArgument)?;
+    }
+    // clusters created without a storage config don't restrict proof submission
+    if account.account.data.is_empty() {
+        return Ok(Config::default());
+    }
+    if account.account.owner != solana_config_api::id() {
+        Err(InstructionError::InvalidAccountData)?;
+    }
+    from(&account.account).ok_or(InstructionError::InvalidAccountData)
+}
+
+pub fn genesis(builder: Builder, authority: &Pubkey, config: &Config) -> Builder {
+    builder.account(id(), create_account(1, authority, config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_stake_api::stake_state::create_stake_account;
+    use solana_vote_api::vote_state::VoteState;
+
+    #[test]
+    fn test_create_account() {
+        let config = Config {
+            min_stake: 42,
+            whitelist: vec![Pubkey::new_rand()],
+        };
+        let account = create_account(1, &Pubkey::new_rand(), &config);
+        assert_eq!(from(&account), Some(config));
+    }
+
+    #[test]
+    fn test_from_keyed_account() {
+        let pubkey = id();
+        let mut account = Account::default();
+        let keyed_account = KeyedAccount::new(&pubkey, false, &mut account);
+        assert_eq!(from_keyed_account(&keyed_account), Ok(Config::default()));
+
+        let wrong_pubkey = Pubkey::new_rand();
+        let mut account = create_account(1, &Pubkey::new_rand(), &Config::default());
+        let keyed_account = KeyedAccount::new(&wrong_pubkey, false, &mut account);
+        assert_eq!(
+            from_keyed_account(&keyed_account),
+            Err(InstructionError::InvalidArgument)
+        );
+
+        let mut account = Account::new(1, 8, &Pubkey::new_rand());
+        let keyed_account = KeyedAccount::new(&pubkey, false, &mut account);
+        assert_eq!(
+            from_keyed_account(&keyed_account),
+            Err(InstructionError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_validator_stake() {
+        let stake_pubkey = Pubkey::new_rand();
+        let epoch = 10;
+        assert_eq!(Config::default().validator_stake(None, epoch), Ok(1));
+
+        let config = Config {
+            min_stake: 100,
+            whitelist: vec![],
+        };
+        assert_eq!(
+            config.validator_stake(None, epoch),
+            Err(InstructionError::CustomError(
+                StorageError::InsufficientStake as u32,
+            ))
+        );
+        let mut stake_account =
+            create_stake_account(&Pubkey::new_rand(), &VoteState::default(), 150);
+        let keyed_account = KeyedAccount::new(&stake_pubkey, true, &mut stake_account);
+        assert_eq!(config.validator_stake(Some(&keyed_account), epoch), Ok(150));
+    }
+
+    #[test]
+    fn test_check_proof_submitter() {
+        let storage_pubkey = Pubkey::new_rand();
+        let stake_pubkey = Pubkey::new_rand();
+        // past stake warmup
+        let epoch = 10;
+        let config = Config {
+            min_stake: 100,
+            whitelist: vec![],
+        };
+        let insufficient_stake = Err(InstructionError::CustomError(
+            StorageError::InsufficientStake as u32,
+        ));
+
+        // no stake account
+        assert_eq!(
+            config.check_proof_submitter(&storage_pubkey, None, epoch),
+            insufficient_stake
+        );
+
+        // not enough stake
+        let vote_state = VoteState::default();
+        let mut stake_account = create_stake_account(&Pubkey::new_rand(), &vote_state, 99);
+        let keyed_account = KeyedAccount::new(&stake_pubkey, true, &mut stake_account);
+        assert_eq!(
+            config.check_proof_submitter(&storage_pubkey, Some(&keyed_account), epoch),
+            insufficient_stake
+        );
+
+        // enough stake, but not signed
+        let mut stake_account = create_stake_account(&Pubkey::new_rand(), &vote_state, 100);
+        let keyed_account = KeyedAccount::new(&stake_pubkey, false, &mut stake_account);
+        assert_eq!(
+            config.check_proof_submitter(&storage_pubkey, Some(&keyed_account), epoch),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+
+        let keyed_account = KeyedAccount::new(&stake_pubkey, true, &mut stake_account);
+        assert_eq!(
+            config.check_proof_submitter(&storage_pubkey, Some(&keyed_account), epoch),
+            Ok(())
+        );
+
+        // whitelisted replicators don't need stake
+        let config = Config {
+            min_stake: 100,
+            whitelist: vec![storage_pubkey],
+        };
+        assert_eq!(
+            config.check_proof_submitter(&storage_pubkey, None, epoch),
+            Ok(())
+        );
+    }
+}
@@ -14,6 +14,10 @@ use std::collections::BTreeMap;
 // Todo Tune this for actual use cases when PoRep is feature complete
 pub const STORAGE_ACCOUNT_SPACE: u64 = 1024 * 8;
 pub const MAX_PROOFS_PER_SEGMENT: usize = 80;
+/// How many segments after a challenge is opened validators have to check the challenged proof
+pub const CHALLENGE_WINDOW_SEGMENTS: u64 = 2;
+/// The percentage of a replicator's unclaimed credits burned when a challenge against it holds
+pub const SLASHED_CREDITS_PERCENT: u64 = 50;
 
 #[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Credits {
@@ -29,10 +33,24 @@ impl Credits {
     pub fn update_epoch(&mut self, current_epoch: u64) {
         if self.epoch != current_epoch {
             self.epoch = current_epoch;
-            self.redeemable += self.current_epoch;
+            self.redeemable = self.redeemable.saturating_add(self.current_epoch);
             self.current_epoch = 0;
         }
     }
+
+    /// Takes back `amount` credits not claimed yet, pending credits first
+    pub fn revoke(&mut self, amount: u64) {
+        let from_current_epoch = amount.min(self.current_epoch);
+        self.current_epoch -= from_current_epoch;
+        self.redeemable = self.redeemable.saturating_sub(amount - from_current_epoch);
+    }
+
+    /// Burns `percent` of the credits not claimed yet, pending credits first
+    pub fn slash(&mut self, percent: u64) {
+        let unclaimed = u128::from(self.current_epoch) + u128::from(self.redeemable);
+        let burned = unclaimed * u128::from(percent.min(100)) / 100;
+        self.revoke(burned.min(u128::from(std::u64::MAX)) as u64);
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, FromPrimitive)]
@@ -44,6 +62,10 @@ pub enum StorageError {
     RewardPoolDepleted,
     InvalidOwner,
     ProofLimitReached,
+    InsufficientStake,
+    InvalidChallenge,
+    DuplicateChallenge,
+    ChallengeWindowOpen,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -71,6 +93,20 @@ pub struct Proof {
     pub segment_index: u64,
 }
 
+/// A validator's claim that one of a replicator's proofs isn't valid. It's arbitrated by the
+/// results of every validator that checked the proof by the time its window closes.
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Challenge {
+    /// The validator storage account that opened the challenge
+    pub challenger: Pubkey,
+    /// The last segment during which the challenge can't be resolved yet
+    pub window_end: u64,
+    /// Resolved challenges are kept so a proof is only ever slashed for once
+    pub resolved: bool,
+    /// Whether the resolved challenge held, the proof having been found not valid
+    pub upheld: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum StorageContract {
     Uninitialized, // Must be first (aka, 0)
@@ -81,8 +117,7 @@ pub enum StorageContract {
         segment: u64,
         // Most recently advertised blockhash
         hash: Hash,
-        // Lockouts and Rewards are per segment per replicator. It needs to remain this way until
-        // the challenge stage is added.
+        // Lockouts and Rewards are per segment per replicator
         lockout_validations: BTreeMap<u64, BTreeMap<Pubkey, Vec<ProofStatus>>>,
         // Used to keep track of ongoing credits
         credits: Credits,
@@ -94,8 +129,13 @@ pub enum StorageContract {
         // Map of Proofs per segment, in a Vec
         proofs: BTreeMap<u64, Vec<Proof>>,
         // Map of Rewards per segment, in a BTreeMap based on the validator account that verified
-        // the proof. This can be used for challenge stage when its added
+        // the proof. A proof is only rewarded if no validator found it not valid
         validations: BTreeMap<u64, BTreeMap<Pubkey, Vec<ProofStatus>>>,
+        // Open challenges, by the segment and index of the challenged proof
+        challenges: BTreeMap<(u64, u64), Challenge>,
+        // The stake the results of each validator in `validations` count with in challenges,
+        // as of its latest results
+        validator_stakes: BTreeMap<Pubkey, u64>,
         // Used to keep track of ongoing credits
         credits: Credits,
     },
@@ -137,6 +177,8 @@ impl<'a> StorageAccount<'a> {
                 owner,
                 proofs: BTreeMap::new(),
                 validations: BTreeMap::new(),
+                challenges: BTreeMap::new(),
+                validator_stakes: BTreeMap::new(),
                 credits: Credits::default(),
             };
             self.account.set_state(storage_contract)
@@ -173,6 +215,8 @@ impl<'a> StorageAccount<'a> {
         if let StorageContract::ReplicatorStorage {
             proofs,
             validations,
+            challenges,
+            validator_stakes,
             credits,
             ..
         } = &mut storage_contract
@@ -191,6 +235,13 @@ impl<'a> StorageAccount<'a> {
                 .filter(|(segment, _)| **segment >= current_segment.saturating_sub(10))
                 .map(|(segment, rewards)| (*segment, rewards.clone()))
                 .collect();
+            // challenges can't be arbitrated once the validations of their proofs are gone
+            challenges.retain(|(segment, _), _| *segment >= current_segment.saturating_sub(10));
+            validator_stakes.retain(|validator, _| {
+                validations
+                    .values()
+                    .any(|proof_masks| proof_masks.contains_key(validator))
+            });
 
             if segment_index >= current_segment {
                 // attempt to submit proof for unconfirmed segment
@@ -263,11 +314,12 @@ impl<'a> StorageAccount<'a> {
             *state_segment = segment;
             *state_hash = hash;
 
-            // storage epoch updated, move the lockout_validations to credits
-            let (_num_valid, total_validations) = count_valid_proofs(&lockout_validations);
+            // storage epoch updated, move the lockout_validations to credits. Validators are
+            // rewarded for the proofs they checked, whether they found them valid or not
+            let checked_proofs = count_checked_proofs(&lockout_validations);
             lockout_validations.clear();
             credits.update_epoch(clock.epoch);
-            credits.current_epoch += total_validations;
+            credits.current_epoch += checked_proofs;
             self.account.set_state(storage_contract)
         } else {
             Err(InstructionError::InvalidArgument)?
@@ -277,6 +329,7 @@ impl<'a> StorageAccount<'a> {
     pub fn proof_validation(
         &mut self,
         me: &Pubkey,
+        stake: u64,
         clock: sysvar::clock::Clock,
         segment_index: u64,
         proofs_per_account: Vec<Vec<ProofStatus>>,
@@ -338,8 +391,15 @@ impl<'a> StorageAccount<'a> {
                 .into_iter()
                 .zip(accounts.into_iter())
                 .filter_map(|(checked_proofs, account)| {
-                    if store_validation_result(me, &clock, account, segment_index, &checked_proofs)
-                        .is_ok()
+                    if store_validation_result(
+                        me,
+                        stake,
+                        &clock,
+                        account,
+                        segment_index,
+                        &checked_proofs,
+                    )
+                    .is_ok()
                     {
                         Some((account.id, checked_proofs))
                     } else {
@@ -364,6 +424,147 @@ impl<'a> StorageAccount<'a> {
         }
     }
 
+    /// Opens a challenge against the replicator's proof `proof_index` of `segment`, which this
+    /// validator must have found not valid
+    pub fn challenge_proof(
+        &mut self,
+        segment: u64,
+        proof_index: u64,
+        clock: sysvar::clock::Clock,
+        replicator_account: &mut StorageAccount,
+    ) -> Result<(), InstructionError> {
+        if let StorageContract::ValidatorStorage { .. } = self.account.state()? {
+        } else {
+            Err(InstructionError::InvalidArgument)?
+        }
+
+        let mut storage_contract = replicator_account.account.state()?;
+        if let StorageContract::ReplicatorStorage {
+            validations,
+            challenges,
+            ..
+        } = &mut storage_contract
+        {
+            let found_not_valid = validations
+                .get(&segment)
+                .and_then(|proof_masks| proof_masks.get(&self.id))
+                .and_then(|proof_mask| proof_mask.get(proof_index as usize))
+                == Some(&ProofStatus::NotValid);
+            if !found_not_valid {
+                Err(InstructionError::CustomError(
+                    StorageError::InvalidChallenge as u32,
+                ))?
+            }
+            if challenges.contains_key(&(segment, proof_index)) {
+                Err(InstructionError::CustomError(
+                    StorageError::DuplicateChallenge as u32,
+                ))?
+            }
+
+            challenges.insert(
+                (segment, proof_index),
+                Challenge {
+                    challenger: self.id,
+                    window_end: clock.segment.saturating_add(CHALLENGE_WINDOW_SEGMENTS),
+                    resolved: false,
+                    upheld: false,
+                },
+            );
+            replicator_account.account.set_state(&storage_contract)
+        } else {
+            Err(InstructionError::InvalidArgument)?
+        }
+    }
+
+    /// Settles the challenge against the replicator's proof `proof_index` of `segment` once its
+    /// window closed. If more of the stake of the validators that checked the proof found it not
+    /// valid than valid, `SLASHED_CREDITS_PERCENT` of the replicator's unclaimed credits are
+    /// burned.
+    /// Otherwise the proof is credited after all, and `challenger` loses the credit for its
+    /// result.
+    pub fn resolve_challenge(
+        &mut self,
+        segment: u64,
+        proof_index: u64,
+        clock: sysvar::clock::Clock,
+        challenger: &mut StorageAccount,
+    ) -> Result<(), InstructionError> {
+        let mut storage_contract = self.account.state()?;
+        if let StorageContract::ReplicatorStorage {
+            validations,
+            challenges,
+            validator_stakes,
+            credits,
+            ..
+        } = &mut storage_contract
+        {
+            let recorded_validations = count_valid_proofs(&validations, &challenges);
+            let challenge = challenges
+                .get_mut(&(segment, proof_index))
+                .filter(|challenge| !challenge.resolved)
+                .ok_or_else(|| {
+                    InstructionError::CustomError(StorageError::InvalidChallenge as u32)
+                })?;
+            if clock.segment <= challenge.window_end {
+                Err(InstructionError::CustomError(
+                    StorageError::ChallengeWindowOpen as u32,
+                ))?
+            }
+            if challenge.challenger != challenger.id {
+                Err(InstructionError::InvalidArgument)?
+            }
+            challenge.resolved = true;
+
+            let (valid_stake, not_valid_stake) = validations
+                .get(&segment)
+                .map(|proof_masks| {
+                    proof_masks
+                        .iter()
+                        .filter_map(|(validator, proof_mask)| {
+                            let stake = validator_stakes.get(validator).cloned().unwrap_or(0);
+                            proof_mask
+                                .get(proof_index as usize)
+                                .map(|status| (status, stake))
+                        })
+                        .fold(
+                            (0u64, 0u64),
+                            |(valid, not_valid), (status, stake)| match status {
+                                ProofStatus::Valid => (valid.saturating_add(stake), not_valid),
+                                ProofStatus::NotValid => (valid, not_valid.saturating_add(stake)),
+                                ProofStatus::Skipped => (valid, not_valid),
+                            },
+                        )
+                })
+                .unwrap_or((0, 0));
+            debug!(
+                "challenge of segment {} proof {}: {} stake found it valid, {} not valid",
+                segment, proof_index, valid_stake, not_valid_stake
+            );
+            challenge.upheld = not_valid_stake > valid_stake;
+            let upheld = challenge.upheld;
+
+            credits.update_epoch(clock.epoch);
+            let total_validations = count_valid_proofs(&validations, &challenges);
+            update_credits(credits, recorded_validations, total_validations);
+            if upheld {
+                credits.slash(SLASHED_CREDITS_PERCENT);
+            } else {
+                let mut challenger_contract = challenger.account.state()?;
+                if let StorageContract::ValidatorStorage { credits, .. } = &mut challenger_contract
+                {
+                    credits.update_epoch(clock.epoch);
+                    credits.revoke(1);
+                } else {
+                    Err(InstructionError::InvalidArgument)?
+                }
+                challenger.account.set_state(&challenger_contract)?;
+            }
+            self.account.set_state(&storage_contract)
+        } else {
+            Err(InstructionError::InvalidArgument)?
+        }
+    }
+
     pub fn claim_storage_reward(
         &mut self,
         rewards_pool: &mut KeyedAccount,
@@ -391,7 +592,6 @@ impl<'a> StorageAccount<'a> {
             self.account.set_state(storage_contract)
         } else if let StorageContract::ReplicatorStorage {
             owner: account_owner,
-            validations,
             credits,
             ..
         } = &mut storage_contract
@@ -401,10 +601,8 @@ impl<'a> StorageAccount<'a> {
                     StorageError::InvalidOwner as u32,
                 ))?
             }
+            // Validated proofs were credited as their validations were stored
             credits.update_epoch(clock.epoch);
-            let (num_validations, _total_proofs) = count_valid_proofs(&validations);
-            credits.current_epoch += num_validations;
-            validations.clear();
             check_redeemable(credits, rewards.storage_point_value, rewards_pool, owner)?;
 
             self.account.set_state(storage_contract)
@@ -427,8 +625,8 @@ fn check_redeemable(
         ))?
     }
     if rewards >= 1 {
-        rewards_pool.account.lamports -= rewards;
-        owner.account.lamports += rewards;
+        rewards_pool.account.checked_sub_lamports(rewards)?;
+        owner.account.checked_add_lamports(rewards)?;
         //clear credits
         credits.redeemable = 0;
     }
@@ -439,9 +637,11 @@ pub fn create_rewards_pool() -> Account {
     Account::new_data(std::u64::MAX, &StorageContract::RewardsPool, &crate::id()).unwrap()
 }
 
-/// Store the result of a proof validation into the replicator account
+/// Store the result of a proof validation into the replicator account, along with the stake it
+/// counts with
 fn store_validation_result(
     me: &Pubkey,
+    stake: u64,
     clock: &sysvar::clock::Clock,
     storage_account: &mut StorageAccount,
     segment: u64,
@@ -452,6 +652,8 @@ fn store_validation_result(
         StorageContract::ReplicatorStorage {
             proofs,
             validations,
+            challenges,
+            validator_stakes,
             credits,
             ..
         } => {
@@ -463,39 +665,71 @@ fn store_validation_result(
                 return Err(InstructionError::InvalidAccountData);
             }
 
-            let (recorded_validations, _) = count_valid_proofs(&validations);
+            let recorded_validations = count_valid_proofs(&validations, &challenges);
             let entry = validations.entry(segment).or_default();
             if !entry.contains_key(me) {
                 entry.insert(*me, proof_mask.to_vec());
+                validator_stakes.insert(*me, stake);
             }
-            let (total_validations, _) = count_valid_proofs(&validations);
+            let total_validations = count_valid_proofs(&validations, &challenges);
             credits.update_epoch(clock.epoch);
-            credits.current_epoch += total_validations - recorded_validations;
+            update_credits(credits, recorded_validations, total_validations);
         }
         _ => return Err(InstructionError::InvalidAccountData),
     }
     storage_account.account.set_state(&storage_contract)
 }
 
+/// Credits a replicator for the change from `recorded_validations` to `total_validations` valid
+/// proofs. A validator that found a proof not valid disputes it, taking back the credit for it
+/// if that wasn't redeemed yet.
+fn update_credits(credits: &mut Credits, recorded_validations: u64, total_validations: u64) {
+    if total_validations >= recorded_validations {
+        credits.current_epoch = credits
+            .current_epoch
+            .saturating_add(total_validations - recorded_validations);
+    } else {
+        credits.current_epoch = credits
+            .current_epoch
+            .saturating_sub(recorded_validations - total_validations);
+    }
+}
+
+/// The number of proofs the validators checked, whether they found them valid or not
+fn count_checked_proofs(validations: &BTreeMap<u64, BTreeMap<Pubkey, Vec<ProofStatus>>>) -> u64 {
+    validations
+        .values()
+        .flat_map(|proof_masks| proof_masks.values())
+        .flatten()
+        .filter(|status| **status != ProofStatus::Skipped)
+        .count() as u64
+}
+
+/// The number of a replicator's proofs that a validator found valid, and no validator found
+/// not valid, unless a challenge over the proof was since resolved in its favor
 fn count_valid_proofs(
     validations: &BTreeMap<u64, BTreeMap<Pubkey, Vec<ProofStatus>>>,
-) -> (u64, u64) {
-    let proofs = validations
+    challenges: &BTreeMap<(u64, u64), Challenge>,
+) -> u64 {
+    validations
         .iter()
-        .flat_map(|(_, proofs)| {
-            proofs
-                .iter()
-                .flat_map(|(_, proofs)| proofs)
-                .collect::<Vec<_>>()
+        .map(|(segment, proof_masks)| {
+            let num_proofs = proof_masks.values().map(Vec::len).max().unwrap_or(0);
+            (0..num_proofs)
+                .filter(|i| {
+                    let statuses: Vec<_> = proof_masks
+                        .values()
+                        .filter_map(|proof_mask| proof_mask.get(*i))
+                        .collect();
+                    let challenge_failed = challenges
+                        .get(&(*segment, *i as u64))
+                        .map_or(false, |challenge| challenge.resolved && !challenge.upheld);
+                    statuses.contains(&&ProofStatus::Valid)
+                        && (challenge_failed || !statuses.contains(&&ProofStatus::NotValid))
+                })
+                .count() as u64
         })
-        .collect::<Vec<_>>();
-    let mut num = 0;
-    for proof in proofs.iter() {
-        if let ProofStatus::Valid = proof {
-            num += 1;
-        }
-    }
-    (num, proofs.len() as u64)
+        .sum()
 }
 
 #[cfg(test)]
@@ -534,6 +768,8 @@ mod tests {
             owner: Pubkey::default(),
             proofs: BTreeMap::new(),
             validations: BTreeMap::new(),
+            challenges: BTreeMap::new(),
+            validator_stakes: BTreeMap::new(),
             credits: Credits::default(),
         };
         storage_account.account.set_state(&contract).unwrap();
@@ -562,6 +798,7 @@ mod tests {
         // account has no space
         store_validation_result(
             &Pubkey::default(),
+            1,
             &sysvar::clock::Clock::default(),
             &mut account,
             segment_index,
@@ -581,6 +818,8 @@ mod tests {
                 owner: Pubkey::default(),
                 proofs,
                 validations: BTreeMap::new(),
+                challenges: BTreeMap::new(),
+                validator_stakes: BTreeMap::new(),
                 credits: Credits::default(),
             };
         };
@@ -589,6 +828,7 @@ mod tests {
         // proof is valid
         store_validation_result(
             &Pubkey::default(),
+            1,
             &sysvar::clock::Clock::default(),
             &mut account,
             segment_index,
@@ -599,6 +839,7 @@ mod tests {
         // proof failed verification but we should still be able to store it
         store_validation_result(
             &Pubkey::default(),
+            1,
             &sysvar::clock::Clock::default(),
             &mut account,
             segment_index,
@@ -607,6 +848,306 @@ mod tests {
         .unwrap();
     }
 
+    #[test]
+    fn test_count_proofs() {
+        let validator_1 = Pubkey::new_rand();
+        let validator_2 = Pubkey::new_rand();
+        let mut validations: BTreeMap<u64, BTreeMap<Pubkey, Vec<ProofStatus>>> = BTreeMap::new();
+        validations.entry(0).or_default().insert(
+            validator_1,
+            vec![ProofStatus::Valid, ProofStatus::Skipped, ProofStatus::Valid],
+        );
+        let mut challenges = BTreeMap::new();
+        assert_eq!(count_checked_proofs(&validations), 2);
+        assert_eq!(count_valid_proofs(&validations, &challenges), 2);
+
+        // The second validator challenges the first proof, and is the only one to check the
+        // second
+        validations.entry(0).or_default().insert(
+            validator_2,
+            vec![
+                ProofStatus::NotValid,
+                ProofStatus::Valid,
+                ProofStatus::Skipped,
+            ],
+        );
+        validations
+            .entry(1)
+            .or_default()
+            .insert(validator_2, vec![ProofStatus::Valid]);
+        assert_eq!(count_checked_proofs(&validations), 5);
+        assert_eq!(count_valid_proofs(&validations, &challenges), 3);
+
+        // The challenge of the first proof is open, then fails
+        let mut challenge = Challenge {
+            challenger: validator_2,
+            ..Challenge::default()
+        };
+        challenges.insert((0, 0), challenge.clone());
+        assert_eq!(count_valid_proofs(&validations, &challenges), 3);
+        challenge.resolved = true;
+        challenges.insert((0, 0), challenge.clone());
+        assert_eq!(count_valid_proofs(&validations, &challenges), 4);
+
+        // An upheld challenge doesn't credit the proof
+        challenge.upheld = true;
+        challenges.insert((0, 0), challenge);
+        assert_eq!(count_valid_proofs(&validations, &challenges), 3);
+    }
+
+    #[test]
+    fn test_challenged_proof_credits() {
+        let mut account = Account::new(0, STORAGE_ACCOUNT_SPACE as usize, &id());
+        let mut storage_account = StorageAccount::new(Pubkey::new_rand(), &mut account);
+        storage_account
+            .initialize_replicator_storage(Pubkey::default())
+            .unwrap();
+        let mut contract = storage_account.account.state().unwrap();
+        if let StorageContract::ReplicatorStorage { proofs, .. } = &mut contract {
+            proofs.insert(0, vec![Proof::default()]);
+        }
+        storage_account.account.set_state(&contract).unwrap();
+
+        let clock = sysvar::clock::Clock::default();
+        let get_credits =
+            |storage_account: &StorageAccount| match storage_account.account.state().unwrap() {
+                StorageContract::ReplicatorStorage { credits, .. } => credits.current_epoch,
+                _ => panic!("Wrong contract type"),
+            };
+        store_validation_result(
+            &Pubkey::new_rand(),
+            1,
+            &clock,
+            &mut storage_account,
+            0,
+            &[ProofStatus::Valid],
+        )
+        .unwrap();
+        assert_eq!(get_credits(&storage_account), 1);
+
+        store_validation_result(
+            &Pubkey::new_rand(),
+            1,
+            &clock,
+            &mut storage_account,
+            0,
+            &[ProofStatus::NotValid],
+        )
+        .unwrap();
+        assert_eq!(get_credits(&storage_account), 0);
+    }
+
+    #[test]
+    fn test_credits_slash() {
+        let mut credits = Credits {
+            epoch: 0,
+            current_epoch: 10,
+            redeemable: 30,
+        };
+        credits.slash(50);
+        assert_eq!(credits.current_epoch, 0);
+        assert_eq!(credits.redeemable, 20);
+        credits.slash(100);
+        assert_eq!(credits.redeemable, 0);
+
+        // slashing can't overflow
+        let mut credits = Credits {
+            epoch: 0,
+            current_epoch: std::u64::MAX,
+            redeemable: std::u64::MAX,
+        };
+        credits.slash(50);
+        assert_eq!(credits.current_epoch, 0);
+        assert_eq!(credits.redeemable, std::u64::MAX);
+    }
+
+    #[test]
+    fn test_credits_revoke() {
+        let mut credits = Credits {
+            epoch: 0,
+            current_epoch: 1,
+            redeemable: 2,
+        };
+        credits.revoke(2);
+        assert_eq!(credits.current_epoch, 0);
+        assert_eq!(credits.redeemable, 1);
+        credits.revoke(2);
+        assert_eq!(credits.redeemable, 0);
+    }
+
+    #[test]
+    fn test_challenge_proof() {
+        let mut account = Account::new(0, STORAGE_ACCOUNT_SPACE as usize, &id());
+        let mut replicator = StorageAccount::new(Pubkey::new_rand(), &mut account);
+        replicator
+            .initialize_replicator_storage(Pubkey::default())
+            .unwrap();
+        let mut contract = replicator.account.state().unwrap();
+        if let StorageContract::ReplicatorStorage {
+            proofs, credits, ..
+        } = &mut contract
+        {
+            proofs.insert(0, vec![Proof::default(), Proof::default()]);
+            credits.redeemable = 10;
+        }
+        replicator.account.set_state(&contract).unwrap();
+
+        let mut validator_account = Account::new(0, STORAGE_ACCOUNT_SPACE as usize, &id());
+        let mut validator = StorageAccount::new(Pubkey::new_rand(), &mut validator_account);
+        validator
+            .initialize_validator_storage(Pubkey::default())
+            .unwrap();
+        let mut contract = validator.account.state().unwrap();
+        if let StorageContract::ValidatorStorage { credits, .. } = &mut contract {
+            credits.redeemable = 3;
+        }
+        validator.account.set_state(&contract).unwrap();
+
+        let clock = |segment| sysvar::clock::Clock {
+            segment,
+            ..sysvar::clock::Clock::default()
+        };
+        let challenge_error = |error: StorageError| -> Result<(), InstructionError> {
+            Err(InstructionError::CustomError(error as u32))
+        };
+        let credits = |account: &StorageAccount| match account.account.state().unwrap() {
+            StorageContract::ReplicatorStorage { credits, .. } => credits,
+            StorageContract::ValidatorStorage { credits, .. } => credits,
+            _ => panic!("Wrong contract type"),
+        };
+
+        // a validator can only challenge proofs it found not valid
+        assert_eq!(
+            validator.challenge_proof(0, 0, clock(0), &mut replicator),
+            challenge_error(StorageError::InvalidChallenge)
+        );
+        store_validation_result(
+            &validator.id,
+            1,
+            &clock(0),
+            &mut replicator,
+            0,
+            &[ProofStatus::NotValid, ProofStatus::NotValid],
+        )
+        .unwrap();
+        for proof_index in 0..2 {
+            validator
+                .challenge_proof(0, proof_index, clock(0), &mut replicator)
+                .unwrap();
+        }
+        assert_eq!(
+            validator.challenge_proof(0, 0, clock(0), &mut replicator),
+            challenge_error(StorageError::DuplicateChallenge)
+        );
+
+        // another validator vouches for the first proof while the window is open
+        store_validation_result(
+            &Pubkey::new_rand(),
+            1,
+            &clock(0),
+            &mut replicator,
+            0,
+            &[ProofStatus::Valid, ProofStatus::NotValid],
+        )
+        .unwrap();
+        assert_eq!(
+            replicator.resolve_challenge(0, 0, clock(CHALLENGE_WINDOW_SEGMENTS), &mut validator),
+            challenge_error(StorageError::ChallengeWindowOpen)
+        );
+
+        // a tie doesn't slash the replicator, whose proof is credited after all, while the
+        // challenger loses the credit for its result
+        let window_closed = CHALLENGE_WINDOW_SEGMENTS + 1;
+        let mut other_account = Account::new(0, STORAGE_ACCOUNT_SPACE as usize, &id());
+        let mut other = StorageAccount::new(Pubkey::new_rand(), &mut other_account);
+        assert_eq!(
+            replicator.resolve_challenge(0, 0, clock(window_closed), &mut other),
+            Err(InstructionError::InvalidArgument)
+        );
+        replicator
+            .resolve_challenge(0, 0, clock(window_closed), &mut validator)
+            .unwrap();
+        assert_eq!(credits(&replicator).redeemable, 10);
+        assert_eq!(credits(&replicator).current_epoch, 1);
+        assert_eq!(credits(&validator).redeemable, 2);
+
+        // but a majority finding the proof not valid does, once
+        replicator
+            .resolve_challenge(0, 1, clock(window_closed), &mut validator)
+            .unwrap();
+        assert_eq!(credits(&replicator).current_epoch, 0);
+        assert_eq!(credits(&replicator).redeemable, 6);
+        assert_eq!(credits(&validator).redeemable, 2);
+        assert_eq!(
+            replicator.resolve_challenge(0, 1, clock(window_closed), &mut validator),
+            challenge_error(StorageError::InvalidChallenge)
+        );
+        assert_eq!(
+            validator.challenge_proof(0, 1, clock(window_closed), &mut replicator),
+            challenge_error(StorageError::DuplicateChallenge)
+        );
+    }
+
+    #[test]
+    fn test_resolve_challenge_by_stake() {
+        let mut account = Account::new(0, STORAGE_ACCOUNT_SPACE as usize, &id());
+        let mut replicator = StorageAccount::new(Pubkey::new_rand(), &mut account);
+        replicator
+            .initialize_replicator_storage(Pubkey::default())
+            .unwrap();
+        let mut contract = replicator.account.state().unwrap();
+        if let StorageContract::ReplicatorStorage {
+            proofs, credits, ..
+        } = &mut contract
+        {
+            proofs.insert(0, vec![Proof::default()]);
+            credits.redeemable = 10;
+        }
+        replicator.account.set_state(&contract).unwrap();
+
+        let mut validator_account = Account::new(0, STORAGE_ACCOUNT_SPACE as usize, &id());
+        let mut validator = StorageAccount::new(Pubkey::new_rand(), &mut validator_account);
+        validator
+            .initialize_validator_storage(Pubkey::default())
+            .unwrap();
+        let clock = |segment| sysvar::clock::Clock {
+            segment,
+            ..sysvar::clock::Clock::default()
+        };
+
+        // the challenger outweighs the two validators that found the proof valid
+        store_validation_result(
+            &validator.id,
+            10,
+            &clock(0),
+            &mut replicator,
+            0,
+            &[ProofStatus::NotValid],
+        )
+        .unwrap();
+        validator
+            .challenge_proof(0, 0, clock(0), &mut replicator)
+            .unwrap();
+        for _ in 0..2 {
+            store_validation_result(
+                &Pubkey::new_rand(),
+                1,
+                &clock(0),
+                &mut replicator,
+                0,
+                &[ProofStatus::Valid],
+            )
+            .unwrap();
+        }
+        replicator
+            .resolve_challenge(0, 0, clock(CHALLENGE_WINDOW_SEGMENTS + 1), &mut validator)
+            .unwrap();
+        match replicator.account.state().unwrap() {
+            StorageContract::ReplicatorStorage { credits, .. } => assert_eq!(credits.redeemable, 5),
+            _ => panic!("Wrong contract type"),
+        }
+    }
+
     #[test]
     fn test_redeemable() {
         let mut credits = Credits {
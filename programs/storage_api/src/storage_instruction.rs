@@ -1,5 +1,5 @@
 use crate::storage_contract::{ProofStatus, STORAGE_ACCOUNT_SPACE};
-use crate::{id, rewards_pools};
+use crate::{config, id, rewards_pools};
 use serde_derive::{Deserialize, Serialize};
 use solana_sdk::hash::Hash;
 use solana_sdk::instruction::{AccountMeta, Instruction};
@@ -21,6 +21,13 @@ pub enum StorageInstruction {
         owner: Pubkey,
     },
 
+    /// Submit a mining proof
+    ///
+    /// Expects 3 or 4 Accounts:
+    ///    0 - Replicator storage account, must be signed
+    ///    1 - Clock Syscall to figure out the current segment
+    ///    2 - Storage config account, deciding who may submit proofs
+    ///    3 - (optional) Signed stake account backing the replicator
     SubmitMiningProof {
         sha_state: Hash,
         segment_index: u64,
@@ -40,12 +47,44 @@ pub enum StorageInstruction {
     ///    3 - MiningPool account to redeem credits from
     ///    4 - Rewards Syscall to figure out point values
     ClaimStorageReward,
+    /// Store the results of checking replicators' proofs
+    ///
+    /// Expects at least 4 Accounts:
+    ///    0 - Validator storage account, must be signed
+    ///    1 - Clock Syscall to figure out the current segment
+    ///    2 - Storage config account, deciding whose results count
+    ///    3 - (optional) Signed stake account backing the validator
+    ///    N - Replicator storage accounts, one per proof mask
     ProofValidation {
         /// The segment during which this proof was generated
         segment: u64,
         /// A Vec of proof masks per keyed replicator account loaded by the instruction
         proofs: Vec<Vec<ProofStatus>>,
     },
+    /// Challenge a replicator's proof this validator found not valid
+    ///
+    /// Expects 3 Accounts:
+    ///    0 - Validator storage account, must be signed
+    ///    1 - Clock Syscall to figure out the current segment
+    ///    2 - Replicator storage account holding the proof
+    ChallengeProof {
+        /// The segment during which the proof was generated
+        segment: u64,
+        /// The index of the proof within the segment
+        proof_index: u64,
+    },
+    /// Settle a challenge whose window has closed, slashing the replicator's credits if the
+    /// validators that checked the proof mostly found it not valid, and taking back the
+    /// challenger's credit for its result otherwise
+    ///
+    /// Expects 3 Accounts:
+    ///    0 - Replicator storage account holding the challenged proof
+    ///    1 - Clock Syscall to figure out the current segment
+    ///    2 - Validator storage account that opened the challenge
+    ResolveChallenge {
+        segment: u64,
+        proof_index: u64,
+    },
 }
 
 fn get_ratios() -> (u64, u64) {
@@ -145,10 +184,35 @@ pub fn mining_proof(
     let account_metas = vec![
         AccountMeta::new(*storage_pubkey, true),
         AccountMeta::new(clock::id(), false),
+        AccountMeta::new(config::id(), false),
     ];
     Instruction::new(id(), &storage_instruction, account_metas)
 }
 
+/// A mining proof backed by the stake in `stake_pubkey`, required when the
+/// cluster's storage config sets a minimum stake and the replicator isn't
+/// whitelisted
+pub fn mining_proof_with_stake(
+    storage_pubkey: &Pubkey,
+    stake_pubkey: &Pubkey,
+    sha_state: Hash,
+    segment_index: u64,
+    signature: Signature,
+    blockhash: Hash,
+) -> Instruction {
+    let mut instruction = mining_proof(
+        storage_pubkey,
+        sha_state,
+        segment_index,
+        signature,
+        blockhash,
+    );
+    instruction
+        .accounts
+        .push(AccountMeta::new(*stake_pubkey, true));
+    instruction
+}
+
 pub fn advertise_recent_blockhash(
     storage_pubkey: &Pubkey,
     storage_hash: Hash,
@@ -173,6 +237,7 @@ pub fn proof_validation(
     let mut account_metas = vec![
         AccountMeta::new(*storage_pubkey, true),
         AccountMeta::new(clock::id(), false),
+        AccountMeta::new(config::id(), false),
     ];
     let mut proofs = vec![];
     checked_proofs.into_iter().for_each(|(id, p)| {
@@ -183,6 +248,57 @@ pub fn proof_validation(
     Instruction::new(id(), &storage_instruction, account_metas)
 }
 
+/// A proof validation backed by the stake in `stake_pubkey`, required for the
+/// results to count when the cluster's storage config sets a minimum stake
+pub fn proof_validation_with_stake(
+    storage_pubkey: &Pubkey,
+    stake_pubkey: &Pubkey,
+    segment: u64,
+    checked_proofs: Vec<(Pubkey, Vec<ProofStatus>)>,
+) -> Instruction {
+    let mut instruction = proof_validation(storage_pubkey, segment, checked_proofs);
+    instruction
+        .accounts
+        .insert(3, AccountMeta::new(*stake_pubkey, true));
+    instruction
+}
+
+pub fn challenge_proof(
+    storage_pubkey: &Pubkey,
+    replicator_pubkey: &Pubkey,
+    segment: u64,
+    proof_index: u64,
+) -> Instruction {
+    let storage_instruction = StorageInstruction::ChallengeProof {
+        segment,
+        proof_index,
+    };
+    let account_metas = vec![
+        AccountMeta::new(*storage_pubkey, true),
+        AccountMeta::new(clock::id(), false),
+        AccountMeta::new(*replicator_pubkey, false),
+    ];
+    Instruction::new(id(), &storage_instruction, account_metas)
+}
+
+pub fn resolve_challenge(
+    replicator_pubkey: &Pubkey,
+    challenger_pubkey: &Pubkey,
+    segment: u64,
+    proof_index: u64,
+) -> Instruction {
+    let storage_instruction = StorageInstruction::ResolveChallenge {
+        segment,
+        proof_index,
+    };
+    let account_metas = vec![
+        AccountMeta::new(*replicator_pubkey, false),
+        AccountMeta::new(clock::id(), false),
+        AccountMeta::new(*challenger_pubkey, false),
+    ];
+    Instruction::new(id(), &storage_instruction, account_metas)
+}
+
 pub fn claim_reward(owner_pubkey: &Pubkey, storage_pubkey: &Pubkey) -> Instruction {
     let storage_instruction = StorageInstruction::ClaimStorageReward;
     let account_metas = vec![
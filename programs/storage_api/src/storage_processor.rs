@@ -1,6 +1,7 @@
 //! storage program
 //!  Receive mining proofs from miners, validate the answers
 //!  and give reward for good proofs.
+use crate::config;
 use crate::storage_contract::StorageAccount;
 use crate::storage_instruction::StorageInstruction;
 use solana_sdk::account::KeyedAccount;
@@ -38,11 +39,15 @@ pub fn process_instruction(
             signature,
             blockhash,
         } => {
-            if me_unsigned || rest.len() != 1 {
+            if me_unsigned || rest.len() < 2 || rest.len() > 3 {
                 // This instruction must be signed by `me`
                 Err(InstructionError::InvalidArgument)?;
             }
-            let clock = sysvar::clock::from_keyed_account(&rest[0])?;
+            let (clock, rest) = rest.split_at_mut(1);
+            let (config, stake) = rest.split_at_mut(1);
+            let clock = sysvar::clock::from_keyed_account(&clock[0])?;
+            let config = config::from_keyed_account(&config[0])?;
+            config.check_proof_submitter(&storage_account.id, stake.first(), clock.epoch)?;
             storage_account.submit_mining_proof(
                 sha_state,
                 segment_index,
@@ -79,19 +84,56 @@ pub fn process_instruction(
             }
 
             let (clock, rest) = rest.split_at_mut(1);
-            if me_unsigned || rest.is_empty() {
-                // This instruction must be signed by `me` and `rest` cannot be empty
+            if me_unsigned || rest.len() < 2 {
+                // This instruction must be signed by `me` and name the config account
+                Err(InstructionError::InvalidArgument)?;
+            }
+            let (config, rest) = rest.split_at_mut(1);
+            // The validator's stake account, if any, comes before the replicator accounts
+            let has_stake = rest[0].account.owner == solana_stake_api::id();
+            let (stake, rest) = rest.split_at_mut(if has_stake { 1 } else { 0 });
+            if rest.is_empty() {
                 Err(InstructionError::InvalidArgument)?;
             }
             let me_id = storage_account.id;
             let clock = sysvar::clock::from_keyed_account(&clock[0])?;
+            let config = config::from_keyed_account(&config[0])?;
+            // Only the results of validators with enough stake count, weighted by it
+            let stake = config.validator_stake(stake.first(), clock.epoch)?;
             let mut rest: Vec<_> = rest
                 .iter_mut()
                 .map(|keyed_account| {
                     StorageAccount::new(*keyed_account.unsigned_key(), &mut keyed_account.account)
                 })
                 .collect();
-            storage_account.proof_validation(&me_id, clock, segment, proofs, &mut rest)
+            storage_account.proof_validation(&me_id, stake, clock, segment, proofs, &mut rest)
+        }
+        StorageInstruction::ChallengeProof {
+            segment,
+            proof_index,
+        } => {
+            if me_unsigned || rest.len() != 2 {
+                // This instruction must be signed by `me`
+                Err(InstructionError::InvalidArgument)?;
+            }
+            let (clock, replicator) = rest.split_at_mut(1);
+            let clock = sysvar::clock::from_keyed_account(&clock[0])?;
+            let mut replicator =
+                StorageAccount::new(*replicator[0].unsigned_key(), &mut replicator[0].account);
+            storage_account.challenge_proof(segment, proof_index, clock, &mut replicator)
+        }
+        StorageInstruction::ResolveChallenge {
+            segment,
+            proof_index,
+        } => {
+            if rest.len() != 2 {
+                Err(InstructionError::InvalidArgument)?;
+            }
+            let (clock, challenger) = rest.split_at_mut(1);
+            let clock = sysvar::clock::from_keyed_account(&clock[0])?;
+            let mut challenger =
+                StorageAccount::new(*challenger[0].unsigned_key(), &mut challenger[0].account);
+            storage_account.resolve_challenge(segment, proof_index, clock, &mut challenger)
         }
     }
 }
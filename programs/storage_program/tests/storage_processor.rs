@@ -19,11 +19,16 @@ use solana_sdk::sysvar::{clock, rewards};
 use solana_sdk::timing::{
     get_segment_from_slot, DEFAULT_SLOTS_PER_SEGMENT, DEFAULT_TICKS_PER_SLOT,
 };
+use solana_stake_api::stake_state::create_stake_account;
+use solana_storage_api::config::{self, Config};
 use solana_storage_api::id;
 use solana_storage_api::storage_contract::StorageAccount;
-use solana_storage_api::storage_contract::{ProofStatus, StorageContract, STORAGE_ACCOUNT_SPACE};
+use solana_storage_api::storage_contract::{
+    ProofStatus, StorageContract, StorageError, STORAGE_ACCOUNT_SPACE,
+};
 use solana_storage_api::storage_instruction;
 use solana_storage_api::storage_processor::process_instruction;
+use solana_vote_api::vote_state::VoteState;
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -137,7 +142,10 @@ fn test_proof_bounds() {
         &mut clock_account,
     );
 
-    assert_eq!(test_instruction(&ix, &mut [account, clock_account]), Ok(()));
+    assert_eq!(
+        test_instruction(&ix, &mut [account, clock_account, Account::default()]),
+        Ok(())
+    );
 }
 
 #[test]
@@ -192,7 +200,13 @@ fn test_invalid_accounts_len() {
 
     assert!(test_instruction(&ix, &mut accounts).is_err());
 
-    let mut accounts = [Account::default(), clock_account, Account::default()];
+    let mut accounts = [
+        Account::default(),
+        clock_account,
+        Account::default(),
+        Account::default(),
+        Account::default(),
+    ];
 
     assert!(test_instruction(&ix, &mut accounts).is_err());
 }
@@ -201,7 +215,7 @@ fn test_invalid_accounts_len() {
 fn test_submit_mining_invalid_slot() {
     solana_logger::setup();
     let pubkey = Pubkey::new_rand();
-    let mut accounts = [Account::default(), Account::default()];
+    let mut accounts = [Account::default(), Account::default(), Account::default()];
     accounts[0].data.resize(STORAGE_ACCOUNT_SPACE as usize, 0);
     accounts[1].data.resize(STORAGE_ACCOUNT_SPACE as usize, 0);
 
@@ -250,7 +264,136 @@ fn test_submit_mining_ok() {
         &mut clock_account,
     );
 
-    assert_matches!(test_instruction(&ix, &mut [account, clock_account]), Ok(_));
+    assert_matches!(
+        test_instruction(&ix, &mut [account, clock_account, Account::default()]),
+        Ok(_)
+    );
+}
+
+#[test]
+fn test_submit_mining_requires_stake() {
+    solana_logger::setup();
+    let account_owner = Pubkey::new_rand();
+    let pubkey = Pubkey::new_rand();
+    let stake_pubkey = Pubkey::new_rand();
+    let mut account = Account::default();
+    account.data.resize(STORAGE_ACCOUNT_SPACE as usize, 0);
+    {
+        let mut storage_account = StorageAccount::new(pubkey, &mut account);
+        storage_account
+            .initialize_replicator_storage(account_owner)
+            .unwrap();
+    }
+    // move slot into segment 1, past stake warmup
+    let mut clock_account = clock::create_account(1, 0, 0, 0, 0);
+    Clock::to(
+        &Clock {
+            slot: DEFAULT_SLOTS_PER_SEGMENT,
+            segment: 1,
+            epoch: 10,
+            stakers_epoch: 10,
+        },
+        &mut clock_account,
+    );
+    let config = Config {
+        min_stake: 100,
+        whitelist: vec![],
+    };
+    let config_account = config::create_account(1, &Pubkey::new_rand(), &config);
+
+    // no stake account
+    let ix = storage_instruction::mining_proof(
+        &pubkey,
+        Hash::default(),
+        0,
+        Signature::default(),
+        Hash::default(),
+    );
+    assert_eq!(
+        test_instruction(
+            &ix,
+            &mut [
+                account.clone(),
+                clock_account.clone(),
+                config_account.clone()
+            ]
+        ),
+        Err(InstructionError::CustomError(
+            StorageError::InsufficientStake as u32
+        ))
+    );
+
+    // too little stake
+    let ix = storage_instruction::mining_proof_with_stake(
+        &pubkey,
+        &stake_pubkey,
+        Hash::default(),
+        0,
+        Signature::default(),
+        Hash::default(),
+    );
+    let stake_account = create_stake_account(&Pubkey::new_rand(), &VoteState::default(), 99);
+    assert_eq!(
+        test_instruction(
+            &ix,
+            &mut [
+                account.clone(),
+                clock_account.clone(),
+                config_account.clone(),
+                stake_account
+            ]
+        ),
+        Err(InstructionError::CustomError(
+            StorageError::InsufficientStake as u32
+        ))
+    );
+
+    // a stake account that isn't owned by the stake program
+    let stake_account = Account::new(1000, 0, &Pubkey::new_rand());
+    assert_eq!(
+        test_instruction(
+            &ix,
+            &mut [
+                account.clone(),
+                clock_account.clone(),
+                config_account.clone(),
+                stake_account
+            ]
+        ),
+        Err(InstructionError::IncorrectProgramId)
+    );
+
+    let stake_account = create_stake_account(&Pubkey::new_rand(), &VoteState::default(), 100);
+    assert_eq!(
+        test_instruction(
+            &ix,
+            &mut [
+                account.clone(),
+                clock_account.clone(),
+                config_account,
+                stake_account
+            ]
+        ),
+        Ok(())
+    );
+
+    // whitelisted replicators don't need stake
+    let config = Config {
+        min_stake: 100,
+        whitelist: vec![pubkey],
+    };
+    let config_account = config::create_account(1, &Pubkey::new_rand(), &config);
+    let ix = storage_instruction::mining_proof(
+        &pubkey,
+        Hash::default(),
+        0,
+        Signature::new(&[1; 64]),
+        Hash::default(),
+    );
+    assert_eq!(
+        test_instruction(&ix, &mut [account, clock_account, config_account]),
+        Ok(())
+    );
 }
 
 #[test]
@@ -0,0 +1,251 @@
+use crate::id;
+use crate::token_state::{
+    MultisigInfo, TokenAccountDelegateInfo, TokenAccountInfo, TokenInfo, TokenState,
+};
+use serde_derive::{Deserialize, Serialize};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_instruction;
+
+/// Instructions that take an owner accept a multisig account in its place, followed by the
+/// signers that authorize the instruction.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub enum TokenInstruction {
+    /// Create a token and credit its whole supply to an account
+    /// key 0 - New token, signer
+    /// key 1 - Account of the token to credit
+    NewToken(TokenInfo),
+
+    /// Create an account for a token
+    /// key 0 - New account, signer
+    /// key 1 - Owner of the account
+    /// key 2 - Token the account holds
+    /// key 3 - Source account that the new account is a delegate for (optional)
+    NewTokenAccount,
+
+    /// Transfer tokens between accounts
+    /// key 0 - Owner of the source account
+    /// key 1 - Source account
+    /// key 2 - Destination account
+    /// key 3 - Account the source account is a delegate for, if it is one
+    Transfer(u64),
+
+    /// Allow a delegate account to transfer up to an amount from its source account
+    /// key 0 - Owner of the source account
+    /// key 1 - Source account
+    /// key 2 - Delegate account
+    Approve(u64),
+
+    /// Change the owner of an account
+    /// key 0 - Owner of the account
+    /// key 1 - Account
+    /// key 2 - New owner
+    SetOwner,
+
+    /// Create an owner that acts with the signatures of some number of its signers
+    /// key 0 - New multisig account, signer
+    NewMultisig(MultisigInfo),
+
+    /// Mint new tokens into an account
+    /// key 0 - Mint authority of the token
+    /// key 1 - Token
+    /// key 2 - Destination account
+    MintTo(u64),
+
+    /// Destroy tokens held by an account, reducing the token's supply
+    /// key 0 - Owner of the account
+    /// key 1 - Account
+    /// key 2 - Token of the account
+    Burn(u64),
+}
+
+/// The account metas of an owner, and of the signers that sign for it when it's a multisig
+fn owner_metas(owner: &Pubkey, signers: &[Pubkey]) -> (AccountMeta, Vec<AccountMeta>) {
+    let owner_meta = AccountMeta::new(*owner, signers.is_empty());
+    let signer_metas = signers
+        .iter()
+        .map(|signer| AccountMeta::new(*signer, true))
+        .collect();
+    (owner_meta, signer_metas)
+}
+
+fn token_account_size() -> u64 {
+    TokenState::Account(TokenAccountInfo {
+        delegate: Some(TokenAccountDelegateInfo::default()),
+        ..TokenAccountInfo::default()
+    })
+    .size_of() as u64
+}
+
+/// Create a token, and credit its supply to `account`, an account of the token created
+/// beforehand
+pub fn create_token(
+    from: &Pubkey,
+    token: &Pubkey,
+    account: &Pubkey,
+    token_info: TokenInfo,
+    lamports: u64,
+) -> Vec<Instruction> {
+    let space = TokenState::Token(token_info.clone()).size_of() as u64;
+    vec![
+        system_instruction::create_account(from, token, lamports, space, &id()),
+        Instruction::new(
+            id(),
+            &TokenInstruction::NewToken(token_info),
+            vec![
+                AccountMeta::new(*token, true),
+                AccountMeta::new(*account, false),
+            ],
+        ),
+    ]
+}
+
+pub fn create_token_account(
+    from: &Pubkey,
+    account: &Pubkey,
+    owner: &Pubkey,
+    token: &Pubkey,
+    lamports: u64,
+) -> Vec<Instruction> {
+    vec![
+        system_instruction::create_account(from, account, lamports, token_account_size(), &id()),
+        Instruction::new(
+            id(),
+            &TokenInstruction::NewTokenAccount,
+            vec![
+                AccountMeta::new(*account, true),
+                AccountMeta::new_credit_only(*owner, false),
+                AccountMeta::new_credit_only(*token, false),
+            ],
+        ),
+    ]
+}
+
+/// Create an account that can be approved to transfer tokens from `source`
+pub fn create_delegate_account(
+    from: &Pubkey,
+    account: &Pubkey,
+    owner: &Pubkey,
+    token: &Pubkey,
+    source: &Pubkey,
+    lamports: u64,
+) -> Vec<Instruction> {
+    let mut instructions = create_token_account(from, account, owner, token, lamports);
+    instructions[1]
+        .accounts
+        .push(AccountMeta::new_credit_only(*source, false));
+    instructions
+}
+
+/// Create an owner that acts once `m` of `signers` sign
+pub fn create_multisig(
+    from: &Pubkey,
+    multisig: &Pubkey,
+    m: u8,
+    signers: &[Pubkey],
+    lamports: u64,
+) -> Vec<Instruction> {
+    let multisig_info = MultisigInfo {
+        m,
+        signers: signers.to_vec(),
+    };
+    let space = TokenState::Multisig(multisig_info.clone()).size_of() as u64;
+    vec![
+        system_instruction::create_account(from, multisig, lamports, space, &id()),
+        Instruction::new(
+            id(),
+            &TokenInstruction::NewMultisig(multisig_info),
+            vec![AccountMeta::new(*multisig, true)],
+        ),
+    ]
+}
+
+/// Transfer `amount` from `source` to `dest`. If `source` is a delegate account,
+/// `delegate_source` is the account it's a delegate for. `signers` sign for a multisig `owner`.
+pub fn transfer(
+    owner: &Pubkey,
+    source: &Pubkey,
+    dest: &Pubkey,
+    delegate_source: Option<&Pubkey>,
+    signers: &[Pubkey],
+    amount: u64,
+) -> Instruction {
+    let (owner_meta, signer_metas) = owner_metas(owner, signers);
+    let mut account_metas = vec![
+        owner_meta,
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*dest, false),
+    ];
+    if let Some(delegate_source) = delegate_source {
+        account_metas.push(AccountMeta::new(*delegate_source, false));
+    }
+    account_metas.extend(signer_metas);
+    Instruction::new(id(), &TokenInstruction::Transfer(amount), account_metas)
+}
+
+pub fn approve(
+    owner: &Pubkey,
+    source: &Pubkey,
+    delegate: &Pubkey,
+    signers: &[Pubkey],
+    amount: u64,
+) -> Instruction {
+    let (owner_meta, signer_metas) = owner_metas(owner, signers);
+    let mut account_metas = vec![
+        owner_meta,
+        AccountMeta::new_credit_only(*source, false),
+        AccountMeta::new(*delegate, false),
+    ];
+    account_metas.extend(signer_metas);
+    Instruction::new(id(), &TokenInstruction::Approve(amount), account_metas)
+}
+
+pub fn set_owner(
+    owner: &Pubkey,
+    account: &Pubkey,
+    new_owner: &Pubkey,
+    signers: &[Pubkey],
+) -> Instruction {
+    let (owner_meta, signer_metas) = owner_metas(owner, signers);
+    let mut account_metas = vec![
+        owner_meta,
+        AccountMeta::new(*account, false),
+        AccountMeta::new_credit_only(*new_owner, false),
+    ];
+    account_metas.extend(signer_metas);
+    Instruction::new(id(), &TokenInstruction::SetOwner, account_metas)
+}
+
+pub fn mint_to(
+    mint_authority: &Pubkey,
+    token: &Pubkey,
+    dest: &Pubkey,
+    signers: &[Pubkey],
+    amount: u64,
+) -> Instruction {
+    let (owner_meta, signer_metas) = owner_metas(mint_authority, signers);
+    let mut account_metas = vec![
+        owner_meta,
+        AccountMeta::new(*token, false),
+        AccountMeta::new(*dest, false),
+    ];
+    account_metas.extend(signer_metas);
+    Instruction::new(id(), &TokenInstruction::MintTo(amount), account_metas)
+}
+
+pub fn burn(
+    owner: &Pubkey,
+    account: &Pubkey,
+    token: &Pubkey,
+    signers: &[Pubkey],
+    amount: u64,
+) -> Instruction {
+    let (owner_meta, signer_metas) = owner_metas(owner, signers);
+    let mut account_metas = vec![
+        owner_meta,
+        AccountMeta::new(*account, false),
+        AccountMeta::new(*token, false),
+    ];
+    account_metas.extend(signer_metas);
+    Instruction::new(id(), &TokenInstruction::Burn(amount), account_metas)
+}
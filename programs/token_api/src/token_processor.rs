@@ -16,3 +16,367 @@ pub fn process_instruction(
         InstructionError::CustomError(e as u32)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id;
+    use crate::token_instruction;
+    use crate::token_state::{TokenAccountInfo, TokenError, TokenInfo};
+    use solana_runtime::bank::Bank;
+    use solana_runtime::bank_client::BankClient;
+    use solana_sdk::client::SyncClient;
+    use solana_sdk::genesis_block::create_genesis_block;
+    use solana_sdk::message::Message;
+    use solana_sdk::signature::{Keypair, KeypairUtil};
+    use solana_sdk::transaction::TransactionError;
+
+    fn create_bank(lamports: u64) -> (Bank, Keypair) {
+        let (genesis_block, mint_keypair) = create_genesis_block(lamports);
+        let mut bank = Bank::new(&genesis_block);
+        bank.add_instruction_processor(id(), process_instruction);
+        (bank, mint_keypair)
+    }
+
+    fn get_token_state(bank_client: &BankClient, pubkey: &Pubkey) -> TokenState {
+        let data = bank_client.get_account_data(pubkey).unwrap().unwrap();
+        TokenState::deserialize(&data).unwrap()
+    }
+
+    fn get_token_amount(bank_client: &BankClient, pubkey: &Pubkey) -> u64 {
+        get_token_state(bank_client, pubkey).amount().unwrap()
+    }
+
+    fn get_token_supply(bank_client: &BankClient, pubkey: &Pubkey) -> u64 {
+        match get_token_state(bank_client, pubkey) {
+            TokenState::Token(token_info) => token_info.supply,
+            _ => panic!("not a token"),
+        }
+    }
+
+    fn create_token_account(
+        bank_client: &BankClient,
+        payer_keypair: &Keypair,
+        owner: &Pubkey,
+        token: &Pubkey,
+    ) -> Pubkey {
+        let account_keypair = Keypair::new();
+        let instructions = token_instruction::create_token_account(
+            &payer_keypair.pubkey(),
+            &account_keypair.pubkey(),
+            owner,
+            token,
+            1,
+        );
+        bank_client
+            .send_message(
+                &[payer_keypair, &account_keypair],
+                Message::new(instructions),
+            )
+            .unwrap();
+        account_keypair.pubkey()
+    }
+
+    /// Create a token with `supply` credited to an account owned by `owner`. Returns the
+    /// token and the account.
+    fn create_token(
+        bank_client: &BankClient,
+        payer_keypair: &Keypair,
+        owner: &Pubkey,
+        supply: u64,
+        mint_authority: Option<Pubkey>,
+    ) -> (Pubkey, Pubkey) {
+        let token_keypair = Keypair::new();
+        let token = token_keypair.pubkey();
+        let account = create_token_account(bank_client, payer_keypair, owner, &token);
+        let token_info = TokenInfo {
+            supply,
+            decimals: 2,
+            name: "A test token".to_string(),
+            symbol: "TEST".to_string(),
+            mint_authority,
+        };
+        let instructions = token_instruction::create_token(
+            &payer_keypair.pubkey(),
+            &token,
+            &account,
+            token_info,
+            1,
+        );
+        bank_client
+            .send_message(&[payer_keypair, &token_keypair], Message::new(instructions))
+            .unwrap();
+        (token, account)
+    }
+
+    fn token_error(error: TokenError) -> TransactionError {
+        TransactionError::InstructionError(0, InstructionError::CustomError(error as u32))
+    }
+
+    #[test]
+    fn test_token_transfer_mint_and_burn() {
+        let (bank, alice_keypair) = create_bank(10_000);
+        let bank_client = BankClient::new(bank);
+        let alice_pubkey = alice_keypair.pubkey();
+        let bob_keypair = Keypair::new();
+        let bob_pubkey = bob_keypair.pubkey();
+        bank_client
+            .transfer(1, &alice_keypair, &bob_pubkey)
+            .unwrap();
+
+        let (token, alice_account) = create_token(
+            &bank_client,
+            &alice_keypair,
+            &alice_pubkey,
+            100,
+            Some(alice_pubkey),
+        );
+        let bob_account = create_token_account(&bank_client, &alice_keypair, &bob_pubkey, &token);
+        assert_eq!(get_token_amount(&bank_client, &alice_account), 100);
+        assert_eq!(get_token_amount(&bank_client, &bob_account), 0);
+
+        let instruction =
+            token_instruction::transfer(&alice_pubkey, &alice_account, &bob_account, None, &[], 40);
+        bank_client
+            .send_instruction(&alice_keypair, instruction)
+            .unwrap();
+        assert_eq!(get_token_amount(&bank_client, &alice_account), 60);
+        assert_eq!(get_token_amount(&bank_client, &bob_account), 40);
+
+        // Only the owner may transfer out of an account
+        let instruction =
+            token_instruction::transfer(&bob_pubkey, &alice_account, &bob_account, None, &[], 1);
+        assert_eq!(
+            bank_client
+                .send_instruction(&bob_keypair, instruction)
+                .unwrap_err()
+                .unwrap(),
+            token_error(TokenError::NotOwner)
+        );
+
+        // Only the mint authority may mint
+        let instruction = token_instruction::mint_to(&bob_pubkey, &token, &bob_account, &[], 10);
+        assert_eq!(
+            bank_client
+                .send_instruction(&bob_keypair, instruction)
+                .unwrap_err()
+                .unwrap(),
+            token_error(TokenError::NotOwner)
+        );
+        let instruction = token_instruction::mint_to(&alice_pubkey, &token, &bob_account, &[], 10);
+        bank_client
+            .send_instruction(&alice_keypair, instruction)
+            .unwrap();
+        assert_eq!(get_token_amount(&bank_client, &bob_account), 50);
+        assert_eq!(get_token_supply(&bank_client, &token), 110);
+
+        let instruction = token_instruction::burn(&bob_pubkey, &bob_account, &token, &[], 51);
+        assert_eq!(
+            bank_client
+                .send_instruction(&bob_keypair, instruction)
+                .unwrap_err()
+                .unwrap(),
+            token_error(TokenError::InsufficentFunds)
+        );
+        let instruction = token_instruction::burn(&bob_pubkey, &bob_account, &token, &[], 20);
+        bank_client
+            .send_instruction(&bob_keypair, instruction)
+            .unwrap();
+        assert_eq!(get_token_amount(&bank_client, &bob_account), 30);
+        assert_eq!(get_token_supply(&bank_client, &token), 90);
+    }
+
+    #[test]
+    fn test_token_fixed_supply() {
+        let (bank, alice_keypair) = create_bank(10_000);
+        let bank_client = BankClient::new(bank);
+        let alice_pubkey = alice_keypair.pubkey();
+        let (token, alice_account) =
+            create_token(&bank_client, &alice_keypair, &alice_pubkey, 100, None);
+
+        let instruction =
+            token_instruction::mint_to(&alice_pubkey, &token, &alice_account, &[], 10);
+        assert_eq!(
+            bank_client
+                .send_instruction(&alice_keypair, instruction)
+                .unwrap_err()
+                .unwrap(),
+            token_error(TokenError::InvalidArgument)
+        );
+        assert_eq!(get_token_supply(&bank_client, &token), 100);
+    }
+
+    #[test]
+    fn test_token_delegate() {
+        let (bank, alice_keypair) = create_bank(10_000);
+        let bank_client = BankClient::new(bank);
+        let alice_pubkey = alice_keypair.pubkey();
+        let bob_keypair = Keypair::new();
+        let bob_pubkey = bob_keypair.pubkey();
+        bank_client
+            .transfer(1, &alice_keypair, &bob_pubkey)
+            .unwrap();
+
+        let (token, alice_account) =
+            create_token(&bank_client, &alice_keypair, &alice_pubkey, 100, None);
+        let bob_account = create_token_account(&bank_client, &alice_keypair, &bob_pubkey, &token);
+
+        // Let Bob spend up to 30 of Alice's tokens
+        let delegate_keypair = Keypair::new();
+        let delegate_account = delegate_keypair.pubkey();
+        let instructions = token_instruction::create_delegate_account(
+            &alice_pubkey,
+            &delegate_account,
+            &bob_pubkey,
+            &token,
+            &alice_account,
+            1,
+        );
+        bank_client
+            .send_message(
+                &[&alice_keypair, &delegate_keypair],
+                Message::new(instructions),
+            )
+            .unwrap();
+        let instruction =
+            token_instruction::approve(&alice_pubkey, &alice_account, &delegate_account, &[], 30);
+        bank_client
+            .send_instruction(&alice_keypair, instruction)
+            .unwrap();
+        assert_eq!(get_token_amount(&bank_client, &delegate_account), 30);
+
+        let instruction = token_instruction::transfer(
+            &bob_pubkey,
+            &delegate_account,
+            &bob_account,
+            Some(&alice_account),
+            &[],
+            20,
+        );
+        bank_client
+            .send_instruction(&bob_keypair, instruction)
+            .unwrap();
+        assert_eq!(get_token_amount(&bank_client, &alice_account), 80);
+        assert_eq!(get_token_amount(&bank_client, &delegate_account), 10);
+        assert_eq!(get_token_amount(&bank_client, &bob_account), 20);
+
+        // The rest of the allowance is all Bob can spend
+        let instruction = token_instruction::transfer(
+            &bob_pubkey,
+            &delegate_account,
+            &bob_account,
+            Some(&alice_account),
+            &[],
+            11,
+        );
+        assert_eq!(
+            bank_client
+                .send_instruction(&bob_keypair, instruction)
+                .unwrap_err()
+                .unwrap(),
+            token_error(TokenError::InsufficentFunds)
+        );
+    }
+
+    #[test]
+    fn test_token_multisig_owner() {
+        let (bank, alice_keypair) = create_bank(10_000);
+        let bank_client = BankClient::new(bank);
+        let alice_pubkey = alice_keypair.pubkey();
+        let signer_keypairs = vec![Keypair::new(), Keypair::new(), Keypair::new()];
+        let signers: Vec<_> = signer_keypairs.iter().map(|k| k.pubkey()).collect();
+
+        // A 2 of 3 multisig owns the token's supply
+        let multisig_keypair = Keypair::new();
+        let multisig = multisig_keypair.pubkey();
+        let instructions =
+            token_instruction::create_multisig(&alice_pubkey, &multisig, 2, &signers, 1);
+        bank_client
+            .send_message(
+                &[&alice_keypair, &multisig_keypair],
+                Message::new(instructions),
+            )
+            .unwrap();
+        let (token, multisig_account) =
+            create_token(&bank_client, &alice_keypair, &multisig, 100, None);
+        let alice_account =
+            create_token_account(&bank_client, &alice_keypair, &alice_pubkey, &token);
+
+        let instruction = token_instruction::transfer(
+            &multisig,
+            &multisig_account,
+            &alice_account,
+            None,
+            &signers[..1],
+            10,
+        );
+        let message = Message::new_with_payer(vec![instruction], Some(&alice_pubkey));
+        assert_eq!(
+            bank_client
+                .send_message(&[&alice_keypair, &signer_keypairs[0]], message)
+                .unwrap_err()
+                .unwrap(),
+            token_error(TokenError::NotOwner)
+        );
+
+        let instruction = token_instruction::transfer(
+            &multisig,
+            &multisig_account,
+            &alice_account,
+            None,
+            &signers[1..],
+            10,
+        );
+        let message = Message::new_with_payer(vec![instruction], Some(&alice_pubkey));
+        bank_client
+            .send_message(
+                &[&alice_keypair, &signer_keypairs[1], &signer_keypairs[2]],
+                message,
+            )
+            .unwrap();
+        assert_eq!(get_token_amount(&bank_client, &multisig_account), 90);
+        assert_eq!(get_token_amount(&bank_client, &alice_account), 10);
+        assert_eq!(
+            get_token_state(&bank_client, &alice_account),
+            TokenState::Account(TokenAccountInfo {
+                token,
+                owner: alice_pubkey,
+                amount: 10,
+                delegate: None,
+            })
+        );
+
+        // A signer listed twice doesn't make up for a missing one
+        let multisig_keypair = Keypair::new();
+        let multisig = multisig_keypair.pubkey();
+        let duplicate_signers = vec![signers[0], signers[0], signers[1]];
+        let instructions =
+            token_instruction::create_multisig(&alice_pubkey, &multisig, 2, &duplicate_signers, 1);
+        bank_client
+            .send_message(
+                &[&alice_keypair, &multisig_keypair],
+                Message::new(instructions),
+            )
+            .unwrap();
+        let (token, multisig_account) =
+            create_token(&bank_client, &alice_keypair, &multisig, 100, None);
+        let alice_account =
+            create_token_account(&bank_client, &alice_keypair, &alice_pubkey, &token);
+        let instruction = token_instruction::transfer(
+            &multisig,
+            &multisig_account,
+            &alice_account,
+            None,
+            &signers[..1],
+            10,
+        );
+        let message = Message::new_with_payer(vec![instruction], Some(&alice_pubkey));
+        assert_eq!(
+            bank_client
+                .send_message(&[&alice_keypair, &signer_keypairs[0]], message)
+                .unwrap_err()
+                .unwrap(),
+            token_error(TokenError::NotOwner)
+        );
+    }
+}
@@ -1,15 +1,18 @@
+use crate::token_instruction::TokenInstruction;
 use log::*;
 use num_derive::FromPrimitive;
 use serde_derive::{Deserialize, Serialize};
 use solana_sdk::account::KeyedAccount;
 use solana_sdk::instruction_processor_utils::DecodeError;
 use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
 
 #[derive(Serialize, Debug, PartialEq, FromPrimitive)]
 pub enum TokenError {
     InvalidArgument,
     InsufficentFunds,
     NotOwner,
+    Overflow,
 }
 
 impl<T> DecodeError<T> for TokenError {
@@ -27,54 +30,99 @@ impl std::error::Error for TokenError {}
 
 pub type Result<T> = std::result::Result<T, TokenError>;
 
-#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+/// The most signers a multisig owner can have
+pub const MAX_SIGNERS: usize = 11;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TokenInfo {
     /// Total supply of tokens
-    supply: u64,
+    pub supply: u64,
 
     /// Number of base 10 digits to the right of the decimal place in the total supply
-    decimals: u8,
+    pub decimals: u8,
 
     /// Descriptive name of this token
-    name: String,
+    pub name: String,
 
     /// Symbol for this token
+    pub symbol: String,
+
+    /// The key allowed to mint more tokens. If None, the supply is fixed.
+    pub mint_authority: Option<Pubkey>,
+}
+
+/// The layout of `TokenInfo` from before tokens had a mint authority. Tokens with a fixed supply
+/// are still stored in it, so accounts sized for it keep fitting.
+#[derive(Serialize, Deserialize)]
+struct TokenInfoV0 {
+    supply: u64,
+    decimals: u8,
+    name: String,
     symbol: String,
 }
 
+impl From<TokenInfoV0> for TokenInfo {
+    fn from(token_info: TokenInfoV0) -> Self {
+        Self {
+            supply: token_info.supply,
+            decimals: token_info.decimals,
+            name: token_info.name,
+            symbol: token_info.symbol,
+            mint_authority: None,
+        }
+    }
+}
+
+impl TokenInfo {
+    /// The layout this token is stored in, if it's the one from before tokens had a mint
+    /// authority
+    fn to_v0(&self) -> Option<TokenInfoV0> {
+        if self.mint_authority.is_some() {
+            return None;
+        }
+        Some(TokenInfoV0 {
+            supply: self.supply,
+            decimals: self.decimals,
+            name: self.name.clone(),
+            symbol: self.symbol.clone(),
+        })
+    }
+}
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TokenAccountDelegateInfo {
     /// The source account for the tokens
-    source: Pubkey,
+    pub source: Pubkey,
 
     /// The original amount that this delegate account was authorized to spend up to
-    original_amount: u64,
+    pub original_amount: u64,
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TokenAccountInfo {
     /// The kind of token this account holds
-    token: Pubkey,
+    pub token: Pubkey,
 
     /// Owner of this account
-    owner: Pubkey,
+    pub owner: Pubkey,
 
     /// Amount of tokens this account holds
-    amount: u64,
+    pub amount: u64,
 
     /// If `delegate` None, `amount` belongs to this account.
     /// If `delegate` is Option<_>, `amount` represents the remaining allowance
     /// of tokens that may be transferred from the `source` account.
-    delegate: Option<TokenAccountDelegateInfo>,
+    pub delegate: Option<TokenAccountDelegateInfo>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
-enum TokenInstruction {
-    NewToken(TokenInfo),
-    NewTokenAccount,
-    Transfer(u64),
-    Approve(u64),
-    SetOwner,
+/// An owner that acts once `m` of its `signers` sign
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MultisigInfo {
+    /// The number of signers required
+    pub m: u8,
+
+    /// The keys that may sign for the owner
+    pub signers: Vec<Pubkey>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -83,6 +131,7 @@ pub enum TokenState {
     Token(TokenInfo),
     Account(TokenAccountInfo),
     Invalid,
+    Multisig(MultisigInfo),
 }
 impl Default for TokenState {
     fn default() -> TokenState {
@@ -104,11 +153,19 @@ impl TokenState {
         match input[0] {
             0 => Ok(TokenState::Unallocated),
             1 => Ok(TokenState::Token(
-                bincode::deserialize(&input[1..]).map_err(Self::map_to_invalid_args)?,
+                bincode::deserialize::<TokenInfoV0>(&input[1..])
+                    .map_err(Self::map_to_invalid_args)?
+                    .into(),
             )),
             2 => Ok(TokenState::Account(
                 bincode::deserialize(&input[1..]).map_err(Self::map_to_invalid_args)?,
             )),
+            3 => Ok(TokenState::Multisig(
+                bincode::deserialize(&input[1..]).map_err(Self::map_to_invalid_args)?,
+            )),
+            4 => Ok(TokenState::Token(
+                bincode::deserialize(&input[1..]).map_err(Self::map_to_invalid_args)?,
+            )),
             _ => Err(TokenError::InvalidArgument),
         }
     }
@@ -121,18 +178,46 @@ impl TokenState {
         match self {
             TokenState::Unallocated | TokenState::Invalid => Err(TokenError::InvalidArgument),
             TokenState::Token(token_info) => {
-                output[0] = 1;
                 let writer = std::io::BufWriter::new(&mut output[1..]);
-                bincode::serialize_into(writer, &token_info).map_err(Self::map_to_invalid_args)
+                match token_info.to_v0() {
+                    Some(token_info_v0) => {
+                        output[0] = 1;
+                        bincode::serialize_into(writer, &token_info_v0)
+                    }
+                    None => {
+                        output[0] = 4;
+                        bincode::serialize_into(writer, &token_info)
+                    }
+                }
+                .map_err(Self::map_to_invalid_args)
             }
             TokenState::Account(account_info) => {
                 output[0] = 2;
                 let writer = std::io::BufWriter::new(&mut output[1..]);
                 bincode::serialize_into(writer, &account_info).map_err(Self::map_to_invalid_args)
             }
+            TokenState::Multisig(multisig_info) => {
+                output[0] = 3;
+                let writer = std::io::BufWriter::new(&mut output[1..]);
+                bincode::serialize_into(writer, &multisig_info).map_err(Self::map_to_invalid_args)
+            }
         }
     }
 
+    /// The size of an account holding this state
+    pub fn size_of(&self) -> usize {
+        let size = match self {
+            TokenState::Unallocated | TokenState::Invalid => 0,
+            TokenState::Token(token_info) => match token_info.to_v0() {
+                Some(token_info_v0) => bincode::serialized_size(&token_info_v0).unwrap(),
+                None => bincode::serialized_size(token_info).unwrap(),
+            },
+            TokenState::Account(account_info) => bincode::serialized_size(account_info).unwrap(),
+            TokenState::Multisig(multisig_info) => bincode::serialized_size(multisig_info).unwrap(),
+        };
+        1 + size as usize
+    }
+
     #[allow(dead_code)]
     pub fn amount(&self) -> Result<u64> {
         if let TokenState::Account(account_info) = self {
@@ -142,6 +227,41 @@ impl TokenState {
         }
     }
 
+    /// Succeeds if `owner` is the key of `info[0]` and has authorized the instruction, either
+    /// by signing it or, for a multisig owner, with the signatures of enough of its signers
+    /// among `info[signers_index..]`
+    fn check_owner(
+        info: &[KeyedAccount],
+        input_accounts: &[TokenState],
+        owner: &Pubkey,
+        signers_index: usize,
+    ) -> Result<()> {
+        if info[0].unsigned_key() != owner {
+            error!("owner of account not present");
+            Err(TokenError::NotOwner)?;
+        }
+        if info[0].signer_key().is_some() {
+            return Ok(());
+        }
+        if let TokenState::Multisig(multisig_info) = &input_accounts[0] {
+            // A signer listed more than once still only counts once
+            let signers: HashSet<_> = multisig_info.signers.iter().collect();
+            let signed = signers
+                .into_iter()
+                .filter(|signer| {
+                    info.iter()
+                        .skip(signers_index)
+                        .any(|keyed_account| keyed_account.signer_key() == Some(*signer))
+                })
+                .count();
+            if signed >= multisig_info.m as usize {
+                return Ok(());
+            }
+            error!("{} of {} multisig signers present", signed, multisig_info.m);
+        }
+        Err(TokenError::NotOwner)
+    }
+
     #[allow(dead_code)]
     pub fn only_owner(&self, key: &Pubkey) -> Result<()> {
         if *key != Pubkey::default() {
@@ -167,7 +287,7 @@ impl TokenState {
         }
 
         if let TokenState::Account(dest_account) = &input_accounts[1] {
-            if info[0].signer_key().unwrap() != &dest_account.token {
+            if info[0].signer_key() != Some(&dest_account.token) {
                 error!("account 1 token mismatch");
                 Err(TokenError::InvalidArgument)?;
             }
@@ -250,10 +370,12 @@ impl TokenState {
                 Err(TokenError::InvalidArgument)?;
             }
 
-            if info[0].signer_key().unwrap() != &source_account.owner {
-                error!("owner of account 1 not present");
-                Err(TokenError::InvalidArgument)?;
-            }
+            let signers_index = if source_account.delegate.is_some() {
+                4
+            } else {
+                3
+            };
+            Self::check_owner(info, input_accounts, &source_account.owner, signers_index)?;
 
             if source_account.amount < amount {
                 Err(TokenError::InsufficentFunds)?;
@@ -264,7 +386,7 @@ impl TokenState {
             output_accounts.push((1, TokenState::Account(output_source_account)));
 
             if let Some(ref delegate_info) = source_account.delegate {
-                if input_accounts.len() != 4 {
+                if input_accounts.len() < 4 {
                     error!("Expected 4 accounts");
                     Err(TokenError::InvalidArgument)?;
                 }
@@ -294,7 +416,10 @@ impl TokenState {
             }
 
             let mut output_dest_account = dest_account.clone();
-            output_dest_account.amount += amount;
+            output_dest_account.amount = dest_account
+                .amount
+                .checked_add(amount)
+                .ok_or(TokenError::Overflow)?;
             output_accounts.push((2, TokenState::Account(output_dest_account)));
         } else {
             error!("account 1 and/or 2 are invalid accounts");
@@ -309,7 +434,7 @@ impl TokenState {
         input_accounts: &[TokenState],
         output_accounts: &mut Vec<(usize, TokenState)>,
     ) -> Result<()> {
-        if input_accounts.len() != 3 {
+        if input_accounts.len() < 3 {
             error!("Expected 3 accounts");
             Err(TokenError::InvalidArgument)?;
         }
@@ -322,10 +447,7 @@ impl TokenState {
                 Err(TokenError::InvalidArgument)?;
             }
 
-            if info[0].signer_key().unwrap() != &source_account.owner {
-                error!("owner of account 1 not present");
-                Err(TokenError::InvalidArgument)?;
-            }
+            Self::check_owner(info, input_accounts, &source_account.owner, 3)?;
 
             if source_account.delegate.is_some() {
                 error!("account 1 is a delegate");
@@ -370,10 +492,7 @@ impl TokenState {
         }
 
         if let TokenState::Account(source_account) = &input_accounts[1] {
-            if info[0].signer_key().unwrap() != &source_account.owner {
-                info!("owner of account 1 not present");
-                Err(TokenError::InvalidArgument)?;
-            }
+            Self::check_owner(info, input_accounts, &source_account.owner, 3)?;
 
             let mut output_source_account = source_account.clone();
             output_source_account.owner = *info[2].unsigned_key();
@@ -385,12 +504,140 @@ impl TokenState {
         Ok(())
     }
 
+    pub fn process_newmultisig(
+        info: &mut [KeyedAccount],
+        multisig_info: MultisigInfo,
+        input_accounts: &[TokenState],
+        output_accounts: &mut Vec<(usize, TokenState)>,
+    ) -> Result<()> {
+        // key 0 - Destination new multisig account
+        if input_accounts[0] != TokenState::Unallocated {
+            error!("account 0 is already allocated");
+            Err(TokenError::InvalidArgument)?;
+        }
+        let signers = multisig_info.signers.len();
+        if multisig_info.m == 0 || multisig_info.m as usize > signers || signers > MAX_SIGNERS {
+            error!("invalid {} of {} multisig", multisig_info.m, signers);
+            Err(TokenError::InvalidArgument)?;
+        }
+        if info[0].signer_key().is_none() {
+            error!("account 0 not signed");
+            Err(TokenError::InvalidArgument)?;
+        }
+        output_accounts.push((0, TokenState::Multisig(multisig_info)));
+        Ok(())
+    }
+
+    pub fn process_mintto(
+        info: &mut [KeyedAccount],
+        amount: u64,
+        input_accounts: &[TokenState],
+        output_accounts: &mut Vec<(usize, TokenState)>,
+    ) -> Result<()> {
+        // key 0 - Mint authority of the token
+        // key 1 - Token to mint
+        // key 2 - Destination account
+        if input_accounts.len() < 3 {
+            error!("Expected 3 accounts");
+            Err(TokenError::InvalidArgument)?;
+        }
+
+        if let (TokenState::Token(token_info), TokenState::Account(dest_account)) =
+            (&input_accounts[1], &input_accounts[2])
+        {
+            match &token_info.mint_authority {
+                Some(mint_authority) => {
+                    Self::check_owner(info, input_accounts, mint_authority, 3)?;
+                }
+                None => {
+                    error!("token 1 has a fixed supply");
+                    Err(TokenError::InvalidArgument)?;
+                }
+            }
+
+            if info[1].unsigned_key() != &dest_account.token {
+                error!("account 1/2 token mismatch");
+                Err(TokenError::InvalidArgument)?;
+            }
+
+            if dest_account.delegate.is_some() {
+                error!("account 2 is a delegate and cannot accept tokens");
+                Err(TokenError::InvalidArgument)?;
+            }
+
+            let mut output_token_info = token_info.clone();
+            output_token_info.supply = token_info
+                .supply
+                .checked_add(amount)
+                .ok_or(TokenError::Overflow)?;
+            output_accounts.push((1, TokenState::Token(output_token_info)));
+
+            let mut output_dest_account = dest_account.clone();
+            output_dest_account.amount = dest_account
+                .amount
+                .checked_add(amount)
+                .ok_or(TokenError::Overflow)?;
+            output_accounts.push((2, TokenState::Account(output_dest_account)));
+        } else {
+            error!("account 1 and/or 2 are invalid accounts");
+            Err(TokenError::InvalidArgument)?;
+        }
+        Ok(())
+    }
+
+    pub fn process_burn(
+        info: &mut [KeyedAccount],
+        amount: u64,
+        input_accounts: &[TokenState],
+        output_accounts: &mut Vec<(usize, TokenState)>,
+    ) -> Result<()> {
+        // key 0 - Owner of the account
+        // key 1 - Account to burn tokens from
+        // key 2 - Token of the account
+        if input_accounts.len() < 3 {
+            error!("Expected 3 accounts");
+            Err(TokenError::InvalidArgument)?;
+        }
+
+        if let (TokenState::Account(source_account), TokenState::Token(token_info)) =
+            (&input_accounts[1], &input_accounts[2])
+        {
+            if info[2].unsigned_key() != &source_account.token {
+                error!("account 1/2 token mismatch");
+                Err(TokenError::InvalidArgument)?;
+            }
+
+            if source_account.delegate.is_some() {
+                error!("account 1 is a delegate");
+                Err(TokenError::InvalidArgument)?;
+            }
+
+            Self::check_owner(info, input_accounts, &source_account.owner, 3)?;
+
+            if source_account.amount < amount {
+                Err(TokenError::InsufficentFunds)?;
+            }
+
+            let mut output_source_account = source_account.clone();
+            output_source_account.amount -= amount;
+            output_accounts.push((1, TokenState::Account(output_source_account)));
+
+            let mut output_token_info = token_info.clone();
+            output_token_info.supply = token_info.supply.saturating_sub(amount);
+            output_accounts.push((2, TokenState::Token(output_token_info)));
+        } else {
+            error!("account 1 and/or 2 are invalid accounts");
+            Err(TokenError::InvalidArgument)?;
+        }
+        Ok(())
+    }
+
     pub fn process(program_id: &Pubkey, info: &mut [KeyedAccount], input: &[u8]) -> Result<()> {
         let command =
             bincode::deserialize::<TokenInstruction>(input).map_err(Self::map_to_invalid_args)?;
         info!("process_transaction: command={:?}", command);
 
-        if info[0].signer_key().is_none() {
+        if info.is_empty() {
             Err(TokenError::InvalidArgument)?;
         }
 
@@ -423,6 +670,9 @@ impl TokenState {
                 Self::process_newtoken(info, token_info, &input_accounts, &mut output_accounts)?
             }
             TokenInstruction::NewTokenAccount => {
+                if info[0].signer_key().is_none() {
+                    Err(TokenError::InvalidArgument)?;
+                }
                 Self::process_newaccount(info, &input_accounts, &mut output_accounts)?
             }
 
@@ -437,6 +687,21 @@ impl TokenState {
             TokenInstruction::SetOwner => {
                 Self::process_setowner(info, &input_accounts, &mut output_accounts)?
             }
+
+            TokenInstruction::NewMultisig(multisig_info) => Self::process_newmultisig(
+                info,
+                multisig_info,
+                &input_accounts,
+                &mut output_accounts,
+            )?,
+
+            TokenInstruction::MintTo(amount) => {
+                Self::process_mintto(info, amount, &input_accounts, &mut output_accounts)?
+            }
+
+            TokenInstruction::Burn(amount) => {
+                Self::process_burn(info, amount, &input_accounts, &mut output_accounts)?
+            }
         }
 
         for (index, account) in &output_accounts {
@@ -465,16 +730,50 @@ mod test {
         account.serialize(&mut data).unwrap();
         assert_eq!(TokenState::deserialize(&data), Ok(account));
 
-        let account = TokenState::Token(TokenInfo {
+        let token_info = TokenInfo {
             supply: 12345,
             decimals: 2,
             name: "A test token".to_string(),
             symbol: "TEST".to_string(),
+            mint_authority: None,
+        };
+        let account = TokenState::Token(token_info.clone());
+        account.serialize(&mut data).unwrap();
+        assert_eq!(TokenState::deserialize(&data), Ok(account));
+
+        let account = TokenState::Token(TokenInfo {
+            mint_authority: Some(Pubkey::new(&[3; 32])),
+            ..token_info
         });
         account.serialize(&mut data).unwrap();
+        assert_eq!(data[0], 4);
         assert_eq!(TokenState::deserialize(&data), Ok(account));
     }
 
+    #[test]
+    pub fn serde_fixed_supply_token_keeps_its_layout() {
+        let token_info = TokenInfo {
+            supply: 12345,
+            decimals: 2,
+            name: "A test token".to_string(),
+            symbol: "TEST".to_string(),
+            mint_authority: None,
+        };
+        // A token stored before tokens had a mint authority
+        let mut data = vec![1];
+        data.extend(bincode::serialize(&token_info.to_v0().unwrap()).unwrap());
+        assert_eq!(
+            TokenState::deserialize(&data),
+            Ok(TokenState::Token(token_info.clone()))
+        );
+        let account = TokenState::Token(token_info);
+        assert_eq!(account.size_of(), data.len());
+
+        let mut output = vec![0; data.len()];
+        account.serialize(&mut output).unwrap();
+        assert_eq!(output, data);
+    }
+
     #[test]
     pub fn serde_expect_fail() {
         let mut data = vec![0; 256];
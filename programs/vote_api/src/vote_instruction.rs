@@ -17,6 +17,7 @@ use solana_sdk::sysvar;
 pub enum VoteInstruction {
     /// Initialize the VoteState for this `vote account`
     /// takes a node_pubkey and commission
+    /// requires the `vote account` to sign, see `create_account()`
     InitializeAccount(Pubkey, u8),
 
     /// Authorize a voter to send signed votes.
@@ -30,7 +31,7 @@ pub enum VoteInstruction {
 }
 
 fn initialize_account(vote_pubkey: &Pubkey, node_pubkey: &Pubkey, commission: u8) -> Instruction {
-    let account_metas = vec![AccountMeta::new(*vote_pubkey, false)];
+    let account_metas = vec![AccountMeta::new(*vote_pubkey, true)];
     Instruction::new(
         id(),
         &VoteInstruction::InitializeAccount(*node_pubkey, commission),
@@ -44,6 +45,9 @@ pub fn minimum_balance() -> u64 {
     rent.minimum_balance(VoteState::size_of())
 }
 
+/// Create and initialize a vote account in one transaction. The transaction must be signed by
+/// both `from_pubkey` and `vote_pubkey`, so the funded account can't be initialized by anyone
+/// else between the two instructions.
 pub fn create_account(
     from_pubkey: &Pubkey,
     vote_pubkey: &Pubkey,
@@ -312,19 +312,21 @@ pub fn withdraw(
     if vote_account.account.lamports < lamports {
         return Err(InstructionError::InsufficientFunds);
     }
-    vote_account.account.lamports -= lamports;
-    to_account.account.lamports += lamports;
-    Ok(())
+    vote_account.account.checked_sub_lamports(lamports)?;
+    to_account.account.checked_add_lamports(lamports)
 }
 
 /// Initialize the vote_state for a vote account
-/// Assumes that the account is being init as part of a account creation or balance transfer and
-/// that the transaction must be signed by the staker's keys
+/// The vote account must sign, so that once its lamports are moved in, nobody but its
+/// owner can initialize it with their own node
 pub fn initialize_account(
     vote_account: &mut KeyedAccount,
     node_pubkey: &Pubkey,
     commission: u8,
 ) -> Result<(), InstructionError> {
+    if vote_account.signer_key().is_none() {
+        return Err(InstructionError::MissingRequiredSignature);
+    }
     let vote_state: VoteState = vote_account.state()?;
 
     if vote_state.authorized_voter_pubkey != Pubkey::default() {
@@ -416,8 +418,13 @@ mod tests {
 
         let node_pubkey = Pubkey::new_rand();
 
+        // init without the vote account's signature should fail
+        let mut keyed_account = KeyedAccount::new(&vote_account_pubkey, false, &mut vote_account);
+        let res = initialize_account(&mut keyed_account, &node_pubkey, 0);
+        assert_eq!(res, Err(InstructionError::MissingRequiredSignature));
+
         //init should pass
-        let mut vote_account = KeyedAccount::new(&vote_account_pubkey, false, &mut vote_account);
+        let mut vote_account = KeyedAccount::new(&vote_account_pubkey, true, &mut vote_account);
         let res = initialize_account(&mut vote_account, &node_pubkey, 0);
         assert_eq!(res, Ok(()));
 
@@ -848,6 +855,16 @@ mod tests {
         );
         assert_eq!(res, Err(InstructionError::InsufficientFunds));
 
+        // the recipient's balance would overflow
+        let mut full_account = Account::new(std::u64::MAX, 0, &Pubkey::default());
+        let res = withdraw(
+            &mut KeyedAccount::new(&vote_pubkey, true, &mut vote_account.clone()),
+            1,
+            &mut KeyedAccount::new(&Pubkey::new_rand(), false, &mut full_account),
+        );
+        assert_eq!(res, Err(InstructionError::ArithmeticOverflow));
+        assert_eq!(full_account.lamports, std::u64::MAX);
+
         // all good
         let mut to_account = Account::default();
         let lamports = vote_account.lamports;
@@ -2,6 +2,7 @@ use clap::{crate_description, crate_name, crate_version, App, Arg};
 use solana::cluster_info::{Node, FULLNODE_PORT_RANGE};
 use solana::contact_info::ContactInfo;
 use solana::replicator::Replicator;
+use solana_netutil::parse_port_range;
 use solana_sdk::signature::{read_keypair, Keypair, KeypairUtil};
 use std::net::SocketAddr;
 use std::process::exit;
@@ -10,6 +11,8 @@ use std::sync::Arc;
 fn main() {
     solana_logger::setup();
 
+    let default_dynamic_port_range =
+        &format!("{}-{}", FULLNODE_PORT_RANGE.0, FULLNODE_PORT_RANGE.1);
     let matches = App::new(crate_name!())
         .about(crate_description!())
         .version(crate_version!())
@@ -48,6 +51,19 @@ fn main() {
                 .required(true)
                 .help("File containing the storage account keypair"),
         )
+        .arg(
+            Arg::with_name("dynamic_port_range")
+                .long("dynamic-port-range")
+                .value_name("MIN_PORT-MAX_PORT")
+                .takes_value(true)
+                .default_value(default_dynamic_port_range)
+                .validator(|port_range| {
+                    parse_port_range(&port_range)
+                        .map(|_| ())
+                        .ok_or_else(|| "Invalid port range".to_string())
+                })
+                .help("Range to use for dynamically assigned ports"),
+        )
         .get_matches();
 
     let ledger_path = matches.value_of("ledger").unwrap();
@@ -82,8 +98,10 @@ fn main() {
         addr.set_ip(solana_netutil::get_public_ip_addr(&entrypoint_addr).unwrap());
         addr
     };
+    let dynamic_port_range = parse_port_range(matches.value_of("dynamic_port_range").unwrap())
+        .expect("invalid dynamic_port_range");
     let node =
-        Node::new_replicator_with_external_ip(&keypair.pubkey(), &gossip_addr, FULLNODE_PORT_RANGE);
+        Node::new_replicator_with_external_ip(&keypair.pubkey(), &gossip_addr, dynamic_port_range);
 
     println!(
         "replicating the data with keypair={:?} gossip_addr={:?}",
@@ -13,7 +13,7 @@ use solana_sdk::genesis_block::create_genesis_block;
 use solana_sdk::instruction::InstructionError;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, KeypairUtil};
-use solana_sdk::transaction::Transaction;
+use solana_sdk::transaction::{SanitizedTransaction, Transaction};
 use std::sync::Arc;
 use std::thread::sleep;
 use std::time::Duration;
@@ -81,14 +81,24 @@ pub fn create_native_loader_transactions(
         .collect()
 }
 
-fn sync_bencher(bank: &Arc<Bank>, _bank_client: &BankClient, transactions: &Vec<Transaction>) {
+fn sync_bencher(
+    bank: &Arc<Bank>,
+    _bank_client: &BankClient,
+    transactions: &Vec<SanitizedTransaction>,
+) {
     let results = bank.process_transactions(&transactions);
     assert!(results.iter().all(Result::is_ok));
 }
 
-fn async_bencher(bank: &Arc<Bank>, bank_client: &BankClient, transactions: &Vec<Transaction>) {
+fn async_bencher(
+    bank: &Arc<Bank>,
+    bank_client: &BankClient,
+    transactions: &Vec<SanitizedTransaction>,
+) {
     for transaction in transactions.clone() {
-        bank_client.async_send_transaction(transaction).unwrap();
+        bank_client
+            .async_send_transaction(transaction.into_transaction())
+            .unwrap();
     }
     for _ in 0..1_000_000_000_u64 {
         if bank
@@ -115,7 +125,7 @@ fn async_bencher(bank: &Arc<Bank>, bank_client: &BankClient, transactions: &Vec<
 
 fn do_bench_transactions(
     bencher: &mut Bencher,
-    bench_work: &Fn(&Arc<Bank>, &BankClient, &Vec<Transaction>),
+    bench_work: &Fn(&Arc<Bank>, &BankClient, &Vec<SanitizedTransaction>),
     create_transactions: &Fn(&BankClient, &Keypair) -> Vec<Transaction>,
 ) {
     solana_logger::setup();
@@ -130,7 +140,9 @@ fn do_bench_transactions(
     );
     let bank = Arc::new(bank);
     let bank_client = BankClient::new_shared(&bank);
-    let transactions = create_transactions(&bank_client, &mint_keypair);
+    let transactions =
+        SanitizedTransaction::try_new_all(create_transactions(&bank_client, &mint_keypair))
+            .unwrap();
 
     // Do once to fund accounts, load modules, etc...
     let results = bank.process_transactions(&transactions);
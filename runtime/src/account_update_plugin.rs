@@ -0,0 +1,93 @@
+//! The `account_update_plugin` module defines the interface of plugins that are told about every
+//! account write, slot status change and committed transaction, so that they can mirror chain
+//! state into an external database without polling RPC. A plugin is a dynamic library exporting
+//! a constructor, see `declare_plugin!`, that's loaded by the `PluginManager`.
+
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::{self, Transaction};
+use std::any::Any;
+use std::{error, fmt, io};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlotStatus {
+    /// All of the slot's transactions were processed. The slot may still be on a fork that's
+    /// abandoned, in which case it's never rooted.
+    Processed,
+    /// The slot was rooted, so its accounts won't be rolled back
+    Rooted,
+}
+
+#[derive(Debug)]
+pub enum PluginError {
+    ConfigFileReadError(io::Error),
+    InvalidConfig(String),
+    LoadError(io::Error),
+    /// An error of the plugin's own
+    Custom(Box<dyn error::Error + Send + Sync>),
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PluginError::ConfigFileReadError(err) => write!(f, "unable to read config: {}", err),
+            PluginError::InvalidConfig(err) => write!(f, "invalid config: {}", err),
+            PluginError::LoadError(err) => write!(f, "unable to load plugin: {}", err),
+            PluginError::Custom(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl error::Error for PluginError {}
+
+pub type Result<T> = std::result::Result<T, PluginError>;
+
+/// Notifications are made on the threads writing accounts and processing transactions, so
+/// plugins should hand them off rather than wait on their database. An account may be written
+/// many times in a slot, and slots on forks that are abandoned are never rooted, so a plugin
+/// mirroring confirmed state should only keep a slot's last write of each account once the slot
+/// is rooted.
+pub trait AccountUpdatePlugin: Any + Send + Sync + fmt::Debug {
+    fn name(&self) -> &'static str;
+
+    /// Called once the plugin is loaded, with the path of the config file it was loaded from
+    fn on_load(&mut self, _config_file: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called before the plugin is unloaded
+    fn on_unload(&mut self) {}
+
+    /// Called for each account written to `slot`, and, once the plugin is installed on a bank,
+    /// for each of the accounts the bank already holds, with the slot they were written to
+    fn update_account(&self, slot: u64, pubkey: &Pubkey, account: &Account) -> Result<()>;
+
+    fn update_slot_status(&self, slot: u64, parent: Option<u64>, status: SlotStatus) -> Result<()>;
+
+    /// Called for each transaction committed to `slot`, after its account writes. Failed
+    /// transactions are committed too, to charge their fees.
+    fn notify_transaction(
+        &self,
+        slot: u64,
+        transaction: &Transaction,
+        result: &transaction::Result<()>,
+    ) -> Result<()>;
+}
+
+/// The type of the `_create_plugin` function a plugin library exports. Plugins must be built
+/// with the same compiler as the validator, since trait objects have no stable ABI.
+pub type PluginConstructor = unsafe fn() -> *mut dyn AccountUpdatePlugin;
+
+/// Exports the `_create_plugin` function of a plugin library, which creates the plugin with
+/// `$constructor`
+#[macro_export]
+macro_rules! declare_plugin {
+    ($constructor:path) => {
+        #[no_mangle]
+        pub fn _create_plugin() -> *mut dyn $crate::account_update_plugin::AccountUpdatePlugin {
+            let plugin: Box<dyn $crate::account_update_plugin::AccountUpdatePlugin> =
+                Box::new($constructor());
+            Box::into_raw(plugin)
+        }
+    };
+}
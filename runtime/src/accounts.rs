@@ -5,11 +5,12 @@ use crate::accounts_db::{
 use crate::accounts_index::{AccountsIndex, Fork};
 use crate::append_vec::StoredAccount;
 use crate::blockhash_queue::BlockhashQueue;
+use crate::compute_budget::MAX_INVOKE_DEPTH;
 use crate::message_processor::has_duplicates;
 use bincode::serialize;
 use log::*;
 use rayon::slice::ParallelSliceMut;
-use solana_metrics::inc_new_counter_error;
+use solana_metrics::{inc_new_counter_error, inc_new_counter_info};
 use solana_sdk::account::Account;
 use solana_sdk::hash::{Hash, Hasher};
 use solana_sdk::message::Message;
@@ -24,6 +25,9 @@ use std::io::{BufReader, Read};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
+/// Prefetched accounts to remember, for counting prefetch hits
+const PREFETCHED_ACCOUNTS_SLOTS: usize = 16_384;
+
 #[derive(Default, Debug)]
 struct CreditOnlyLock {
     credits: AtomicU64,
@@ -43,6 +47,59 @@ pub struct Accounts {
     /// and number of locks. On commit_credits(), we do a take() on the option so that the hashmap
     /// is no longer available to be written to.
     credit_only_account_locks: Arc<RwLock<Option<HashMap<Pubkey, CreditOnlyLock>>>>,
+
+    /// Accounts prefetched by `prefetch_accounts()` that haven't been loaded yet
+    prefetched_accounts: PrefetchedAccounts,
+    prefetch_stats: PrefetchStats,
+}
+
+/// A fixed-size table of prefetched accounts, updated with atomics so that threads loading
+/// accounts don't wait on each other to count prefetch hits. An account prefetched into the
+/// slot of another replaces it, and the other counts as a miss when it's loaded.
+#[derive(Debug)]
+struct PrefetchedAccounts {
+    slots: Vec<AtomicU64>,
+}
+
+impl Default for PrefetchedAccounts {
+    fn default() -> Self {
+        Self {
+            slots: (0..PREFETCHED_ACCOUNTS_SLOTS)
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+        }
+    }
+}
+
+impl PrefetchedAccounts {
+    /// The slot for `key`, and the nonzero tag that marks `key` as being in it
+    fn slot(&self, key: &Pubkey) -> (&AtomicU64, u64) {
+        let bytes = key.as_ref();
+        let mut tag = [0u8; 8];
+        tag.copy_from_slice(&bytes[..8]);
+        let mut index = [0u8; 8];
+        index.copy_from_slice(&bytes[8..16]);
+        let index = u64::from_le_bytes(index) as usize % self.slots.len();
+        (&self.slots[index], u64::from_le_bytes(tag).max(1))
+    }
+
+    fn insert(&self, key: &Pubkey) {
+        let (slot, tag) = self.slot(key);
+        slot.store(tag, Ordering::Relaxed);
+    }
+
+    /// Whether `key` was prefetched, forgetting it so that it only counts once
+    fn remove(&self, key: &Pubkey) -> bool {
+        let (slot, tag) = self.slot(key);
+        slot.compare_and_swap(tag, 0, Ordering::Relaxed) == tag
+    }
+}
+
+/// How many loaded accounts had been prefetched
+#[derive(Default, Debug)]
+pub struct PrefetchStats {
+    pub hits: AtomicU64,
+    pub misses: AtomicU64,
 }
 
 impl Accounts {
@@ -53,6 +110,8 @@ impl Accounts {
             accounts_db,
             account_locks: Mutex::new(HashSet::new()),
             credit_only_account_locks: Arc::new(RwLock::new(Some(HashMap::new()))),
+            prefetched_accounts: PrefetchedAccounts::default(),
+            prefetch_stats: PrefetchStats::default(),
         }
     }
     pub fn new_from_parent(parent: &Accounts) -> Self {
@@ -61,6 +120,8 @@ impl Accounts {
             accounts_db,
             account_locks: Mutex::new(HashSet::new()),
             credit_only_account_locks: Arc::new(RwLock::new(Some(HashMap::new()))),
+            prefetched_accounts: PrefetchedAccounts::default(),
+            prefetch_stats: PrefetchStats::default(),
         }
     }
 
@@ -136,7 +197,7 @@ impl Accounts {
                 break;
             }
 
-            if depth >= 5 {
+            if depth >= MAX_INVOKE_DEPTH {
                 error_counters.call_chain_too_deep += 1;
                 return Err(TransactionError::CallChainTooDeep);
             }
@@ -192,6 +253,54 @@ impl Accounts {
             .collect()
     }
 
+    /// Warm the index entries and storage pages of the accounts that `txs` will load, so that
+    /// a later `load_accounts()` of them doesn't wait on the disk. Returns the number of
+    /// accounts found.
+    pub fn prefetch_accounts(
+        &self,
+        ancestors: &HashMap<Fork, usize>,
+        txs: &[Transaction],
+    ) -> usize {
+        let accounts_index = self.accounts_db.accounts_index.read().unwrap();
+        let storage = self.accounts_db.storage.read().unwrap();
+        let mut num_prefetched = 0;
+        for key in txs.iter().flat_map(|tx| tx.message().account_keys.iter()) {
+            if AccountsDB::prefetch(&storage, ancestors, &accounts_index, key) {
+                self.prefetched_accounts.insert(key);
+                num_prefetched += 1;
+            }
+        }
+        num_prefetched
+    }
+
+    pub fn prefetch_stats(&self) -> &PrefetchStats {
+        &self.prefetch_stats
+    }
+
+    fn record_prefetch_hits(&self, txs: &[Transaction], lock_results: &[Result<()>]) {
+        let mut hits = 0;
+        let mut misses = 0;
+        for (tx, _) in txs
+            .iter()
+            .zip(lock_results)
+            .filter(|(_, lock_result)| lock_result.is_ok())
+        {
+            for key in &tx.message().account_keys {
+                if self.prefetched_accounts.remove(key) {
+                    hits += 1;
+                } else {
+                    misses += 1;
+                }
+            }
+        }
+        self.prefetch_stats.hits.fetch_add(hits, Ordering::Relaxed);
+        self.prefetch_stats
+            .misses
+            .fetch_add(misses, Ordering::Relaxed);
+        inc_new_counter_info!("accounts-prefetch_hits", hits as usize);
+        inc_new_counter_info!("accounts-prefetch_misses", misses as usize);
+    }
+
     pub fn load_accounts(
         &self,
         ancestors: &HashMap<Fork, usize>,
@@ -200,6 +309,8 @@ impl Accounts {
         hash_queue: &BlockhashQueue,
         error_counters: &mut ErrorCounters,
     ) -> Vec<Result<(InstructionAccounts, InstructionLoaders, InstructionCredits)>> {
+        self.record_prefetch_hits(txs, &lock_results);
+
         //PERF: hold the lock to scan for the references, but not to clone the accounts
         //TODO: two locks usually leads to deadlocks, should this be one structure?
         let accounts_index = self.accounts_db.accounts_index.read().unwrap();
@@ -495,6 +606,11 @@ impl Accounts {
         self.accounts_db.has_accounts(fork)
     }
 
+    /// The number of bytes written to `fork`'s account storage
+    pub fn fork_storage_size(&self, fork: Fork) -> u64 {
+        self.accounts_db.fork_storage_size(fork)
+    }
+
     /// Store the accounts into the DB
     pub fn store_accounts(
         &self,
@@ -574,7 +690,7 @@ impl Accounts {
                     .load_slow(ancestors, &pubkey)
                     .map(|(account, _)| account)
                     .unwrap_or_default();
-                account.lamports += credit;
+                account.lamports = account.lamports.saturating_add(credit);
                 self.store_slow(fork, &pubkey, &account);
             }
         }
@@ -1129,6 +1245,73 @@ mod tests {
         assert_eq!(error_counters.account_not_found, 1);
     }
 
+    #[test]
+    fn test_prefetch_accounts() {
+        let keypair = Keypair::new();
+        let to = Pubkey::new_rand();
+        let accounts = Accounts::new(None);
+        let account = Account::new(10, 1, &Pubkey::default());
+        accounts.store_slow(0, &keypair.pubkey(), &account);
+        let ancestors = vec![(0, 0)].into_iter().collect();
+
+        let instructions = vec![CompiledInstruction::new(2, &(), vec![0, 1])];
+        let tx = Transaction::new_with_compiled_instructions(
+            &[&keypair],
+            &[to],
+            Hash::default(),
+            vec![native_loader::id()],
+            instructions,
+        );
+        let txs = vec![tx];
+
+        // Only the stored account is found
+        assert_eq!(accounts.prefetch_accounts(&ancestors, &txs), 1);
+
+        let mut hash_queue = BlockhashQueue::new(100);
+        hash_queue.register_hash(&Hash::default(), &FeeCalculator::default());
+        let mut error_counters = ErrorCounters::default();
+        accounts.load_accounts(
+            &ancestors,
+            &txs,
+            vec![Ok(())],
+            &hash_queue,
+            &mut error_counters,
+        );
+        let stats = accounts.prefetch_stats();
+        assert_eq!(stats.hits.load(Ordering::Relaxed), 1);
+        assert_eq!(stats.misses.load(Ordering::Relaxed), 2);
+
+        // Each prefetched account only counts as a hit once
+        accounts.load_accounts(
+            &ancestors,
+            &txs,
+            vec![Ok(())],
+            &hash_queue,
+            &mut error_counters,
+        );
+        assert_eq!(stats.hits.load(Ordering::Relaxed), 1);
+        assert_eq!(stats.misses.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn test_prefetched_accounts() {
+        let prefetched_accounts = PrefetchedAccounts::default();
+        let key = Pubkey::new(&[1; 32]);
+        assert!(!prefetched_accounts.remove(&key));
+        prefetched_accounts.insert(&key);
+        assert!(prefetched_accounts.remove(&key));
+        assert!(!prefetched_accounts.remove(&key));
+
+        // A key that goes in the same slot replaces the one there
+        let mut other = [1; 32];
+        other[0] = 2;
+        let other = Pubkey::new(&other);
+        prefetched_accounts.insert(&key);
+        prefetched_accounts.insert(&other);
+        assert!(!prefetched_accounts.remove(&key));
+        assert!(prefetched_accounts.remove(&other));
+    }
+
     #[test]
     fn test_accounts_empty_hash_internal_state() {
         let accounts = Accounts::new(None);
@@ -18,8 +18,10 @@
 //! tracks the number of commits to the entire data store. So the latest
 //! commit for each fork entry would be indexed.
 
+use crate::account_update_plugin::SlotStatus;
 use crate::accounts_index::{AccountsIndex, Fork};
 use crate::append_vec::{AppendVec, StorageMeta, StoredAccount};
+use crate::plugin_manager::PluginManager;
 use bincode::{deserialize_from, serialize_into, serialized_size};
 use log::*;
 use rand::{thread_rng, Rng};
@@ -313,6 +315,9 @@ pub struct AccountsDB {
     pub thread_pool: ThreadPool,
 
     min_num_stores: usize,
+
+    /// Told about every account stored and fork rooted
+    plugin_manager: RwLock<Option<Arc<PluginManager>>>,
 }
 
 impl Default for AccountsDB {
@@ -334,6 +339,7 @@ impl Default for AccountsDB {
                 .build()
                 .unwrap(),
             min_num_stores: num_threads,
+            plugin_manager: RwLock::new(None),
         }
     }
 }
@@ -366,6 +372,14 @@ impl AccountsDB {
         }
     }
 
+    pub fn set_plugin_manager(&self, plugin_manager: Arc<PluginManager>) {
+        *self.plugin_manager.write().unwrap() = Some(plugin_manager);
+    }
+
+    pub fn plugin_manager(&self) -> Option<Arc<PluginManager>> {
+        self.plugin_manager.read().unwrap().clone()
+    }
+
     pub fn paths(&self) -> String {
         self.paths.read().unwrap().join(",")
     }
@@ -429,6 +443,22 @@ impl AccountsDB {
         false
     }
 
+    /// The number of bytes written to `fork`'s account storage
+    pub fn fork_storage_size(&self, fork: Fork) -> u64 {
+        self.storage
+            .read()
+            .unwrap()
+            .0
+            .get(&fork)
+            .map(|stores| {
+                stores
+                    .values()
+                    .map(|store| store.accounts.len() as u64)
+                    .sum()
+            })
+            .unwrap_or(0)
+    }
+
     pub fn scan_accounts<F, A>(&self, ancestors: &HashMap<Fork, usize>, scan_func: F) -> A
     where
         F: Fn(&mut A, Option<(&Pubkey, Account, Fork)>) -> (),
@@ -512,6 +542,29 @@ impl AccountsDB {
         }
     }
 
+    /// Look up `pubkey` in the index and touch the storage holding it, without cloning the
+    /// account. Returns whether the account was found.
+    pub fn prefetch(
+        storage: &AccountStorage,
+        ancestors: &HashMap<Fork, usize>,
+        accounts_index: &AccountsIndex<AccountInfo>,
+        pubkey: &Pubkey,
+    ) -> bool {
+        if let Some((lock, index)) = accounts_index.get(pubkey, ancestors) {
+            let (fork, info) = &lock[index];
+            let stored_account = storage
+                .0
+                .get(fork)
+                .and_then(|fork_storage| fork_storage.get(&info.id))
+                .and_then(|store| store.accounts.get_account(info.offset));
+            if let Some((stored_account, _)) = stored_account {
+                stored_account.touch();
+                return true;
+            }
+        }
+        false
+    }
+
     pub fn load_slow(
         &self,
         ancestors: &HashMap<Fork, usize>,
@@ -729,10 +782,19 @@ impl AccountsDB {
             self.purge_fork(fork);
         }
         purge_forks.stop();
+
+        if let Some(plugin_manager) = self.plugin_manager() {
+            for (pubkey, account) in accounts {
+                plugin_manager.notify_account_update(fork_id, pubkey, account);
+            }
+        }
     }
 
     pub fn add_root(&self, fork: Fork) {
-        self.accounts_index.write().unwrap().add_root(fork)
+        self.accounts_index.write().unwrap().add_root(fork);
+        if let Some(plugin_manager) = self.plugin_manager() {
+            plugin_manager.notify_slot_status(fork, None, SlotStatus::Rooted);
+        }
     }
 
     fn merge(
@@ -837,6 +899,24 @@ mod tests {
         assert_eq!(db.load_slow(&ancestors, &key), Some((account0, 0)));
     }
 
+    #[test]
+    fn test_accountsdb_fork_storage_size() {
+        let db = AccountsDB::new(None);
+        let key = Pubkey::default();
+        let account0 = Account::new(1, 0, &key);
+        assert_eq!(db.fork_storage_size(0), 0);
+
+        db.store(0, &hashmap!(&key => &account0));
+        let size = db.fork_storage_size(0);
+        assert!(size > 0);
+        assert_eq!(db.fork_storage_size(1), 0);
+
+        db.store(0, &hashmap!(&key => &account0));
+        assert_eq!(db.fork_storage_size(0), 2 * size);
+        db.purge_fork(0);
+        assert_eq!(db.fork_storage_size(0), 0);
+    }
+
     #[test]
     fn test_accountsdb_latest_ancestor() {
         solana_logger::setup();
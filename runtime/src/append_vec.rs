@@ -11,6 +11,9 @@ use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Mutex;
 
+// Stride for touching mapped memory; smaller than or equal to the page size on supported platforms
+const PAGE_SIZE: usize = 4096;
+
 //Data is aligned at the next 64 byte offset. Without alignment loading the memory may
 //crash on some architectures.
 macro_rules! align_up {
@@ -59,6 +62,18 @@ impl<'a> StoredAccount<'a> {
             data: self.data.to_vec(),
         }
     }
+
+    /// Read a byte from every page the account is stored in, faulting the pages into memory
+    /// without copying the account out
+    pub fn touch(&self) {
+        //UNSAFE: Volatile reads of valid references, so that the reads aren't optimized away
+        unsafe {
+            std::ptr::read_volatile(&self.balance.lamports);
+            for offset in (0..self.data.len()).step_by(PAGE_SIZE) {
+                std::ptr::read_volatile(&self.data[offset]);
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
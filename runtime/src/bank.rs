@@ -2,15 +2,19 @@
 //! programs. It offers a high-level API that signs transactions
 //! on behalf of the caller, and a low-level API for when they have
 //! already been signed and verified.
-use crate::accounts::Accounts;
+use crate::account_update_plugin::SlotStatus;
+use crate::accounts::{Accounts, PrefetchStats};
 use crate::accounts_db::{
     AppendVecId, ErrorCounters, InstructionAccounts, InstructionCredits, InstructionLoaders,
 };
 use crate::accounts_index::Fork;
 use crate::blockhash_queue::BlockhashQueue;
+use crate::compute_budget::ComputeBudget;
 use crate::epoch_schedule::EpochSchedule;
+use crate::execution_observer::{AccountOwnerChanges, ExecutionObserver};
 use crate::locked_accounts_results::LockedAccountsResults;
-use crate::message_processor::{MessageProcessor, ProcessInstruction};
+use crate::message_processor::{InstructionTrace, MessageProcessor, ProcessInstruction};
+use crate::plugin_manager::PluginManager;
 use crate::serde_utils::{
     deserialize_atomicbool, deserialize_atomicusize, serialize_atomicbool, serialize_atomicusize,
 };
@@ -18,7 +22,7 @@ use crate::stakes::Stakes;
 use crate::status_cache::StatusCache;
 use crate::storage_utils;
 use crate::storage_utils::StorageAccounts;
-use bincode::{deserialize_from, serialize_into, serialized_size};
+use bincode::{deserialize, deserialize_from, serialize_into, serialized_size};
 use byteorder::{ByteOrder, LittleEndian};
 use log::*;
 use serde::{Deserialize, Serialize};
@@ -27,22 +31,29 @@ use solana_metrics::{
     datapoint_info, inc_new_counter_debug, inc_new_counter_error, inc_new_counter_info,
 };
 use solana_sdk::account::Account;
-use solana_sdk::fee_calculator::FeeCalculator;
+use solana_sdk::fee_calculator::{FeeCalculator, FeeRateGovernor};
 use solana_sdk::genesis_block::GenesisBlock;
 use solana_sdk::hash::{hashv, Hash};
 use solana_sdk::inflation::Inflation;
+use solana_sdk::message::Message;
 use solana_sdk::native_loader;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
 use solana_sdk::signature::{Keypair, Signature};
 use solana_sdk::system_transaction;
 use solana_sdk::sysvar::{
-    clock, fees, rewards,
+    self, clock, epoch_schedule as epoch_schedule_sysvar, fees, recent_blockhashes, rent, rewards,
     slot_hashes::{self, SlotHashes},
 };
 use solana_sdk::timing::{duration_as_ns, get_segment_from_slot, MAX_RECENT_BLOCKHASHES};
-use solana_sdk::transaction::{Result, Transaction, TransactionError};
+use solana_sdk::transaction::{
+    Result, SanitizedTransaction, Transaction, TransactionError, TransactionReturnData,
+    TransactionStatusMeta,
+};
+use solana_stake_api::stake_instruction::StakeInstruction;
+use solana_vote_api::vote_state::VoteState;
 use std::cmp;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::io::{BufReader, Cursor, Read};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
@@ -52,6 +63,14 @@ pub const SECONDS_PER_YEAR: f64 = (365.0 * 24.0 * 60.0 * 60.0);
 
 type BankStatusCache = StatusCache<Result<()>>;
 
+/// Return data of executed transactions by signature, along with the slot of
+/// the Bank that executed each one
+#[derive(Default)]
+struct ReturnDataCache {
+    return_data: HashMap<Signature, Vec<(u64, TransactionReturnData)>>,
+    roots: HashSet<u64>,
+}
+
 #[derive(Default)]
 pub struct BankRc {
     /// where all the Accounts are stored
@@ -123,7 +142,7 @@ impl Serialize for StatusCacheRc {
         {
             let mut status_cache = self.status_cache.write().unwrap();
             serialize_into(&mut wr, &*status_cache).map_err(Error::custom)?;
-            status_cache.merge_caches();
+            status_cache.mark_snapshotted();
         }
         let len = wr.position() as usize;
         serializer.serialize_bytes(&wr.into_inner()[..len])
@@ -240,9 +259,15 @@ pub struct Bank {
     /// Latest transaction fees for transactions processed by this bank
     fee_calculator: FeeCalculator,
 
+    /// initialized from genesis, bounds how fee_calculator moves from one bank to the next
+    fee_rate_governor: FeeRateGovernor,
+
     /// initialized from genesis
     epoch_schedule: EpochSchedule,
 
+    /// initialized from genesis
+    rent: Rent,
+
     /// inflation specs
     inflation: Inflation,
 
@@ -264,6 +289,60 @@ pub struct Bank {
 
     /// The Message processor
     message_processor: MessageProcessor,
+
+    /// Limits on the program execution of each transaction
+    #[serde(skip)]
+    compute_budget: ComputeBudget,
+
+    /// Return data of executed transactions, shared with the Bank's descendants
+    #[serde(skip)]
+    return_data_cache: Arc<RwLock<ReturnDataCache>>,
+
+    /// Fees and balance changes of the transactions this Bank committed, by signature
+    #[serde(skip)]
+    transaction_status_metas: RwLock<HashMap<Signature, TransactionStatusMeta>>,
+
+    /// The accounts of the transactions this Bank committed, with whether they were writable,
+    /// in the order the transactions were committed. Programs and sysvars are left out.
+    #[serde(skip)]
+    transaction_addresses: RwLock<Vec<(Pubkey, Signature, bool)>>,
+
+    /// The inflation rewards this Bank paid out of the rewards pools, by the account paid
+    #[serde(skip)]
+    rewards: RwLock<HashMap<Pubkey, RewardInfo>>,
+
+    /// Notified of the changes committed by this Bank and the Banks it shares it with, its
+    /// ancestors and descendants
+    #[serde(skip)]
+    execution_observer: Arc<RwLock<Option<Arc<dyn ExecutionObserver>>>>,
+}
+
+/// What `Bank::load_and_execute_transactions` did with each of the transactions it was given,
+/// in order, and counts over all of them
+pub struct LoadAndExecuteTransactionsOutput {
+    pub loaded_accounts: Vec<Result<(InstructionAccounts, InstructionLoaders, InstructionCredits)>>,
+    pub executed: Vec<Result<()>>,
+    pub return_data: Vec<Option<TransactionReturnData>>,
+    pub instruction_traces: Vec<InstructionTrace>,
+    pub owner_changes: Vec<AccountOwnerChanges>,
+    /// The lamports of each account of the loaded transactions before they executed, after the
+    /// fee was withdrawn from the payer, empty for the others
+    pub pre_balances: Vec<Vec<u64>>,
+    /// Indexes of the transactions that couldn't lock their accounts, which may be retried
+    pub retryable_txs: Vec<usize>,
+    /// How many transactions executed successfully
+    pub tx_count: usize,
+    pub signature_count: usize,
+}
+
+/// An inflation reward the stake program paid an account when its vote credits were redeemed
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct RewardInfo {
+    pub lamports: u64,
+    /// The balance of the account once paid
+    pub post_balance: u64,
+    /// The commission of the vote account the credits were redeemed from
+    pub commission: u8,
 }
 
 impl Default for BlockhashQueue {
@@ -314,15 +393,23 @@ impl Bank {
         self.slots_per_segment = parent.slots_per_segment;
         self.slots_per_year = parent.slots_per_year;
         self.epoch_schedule = parent.epoch_schedule;
+        self.rent = parent.rent.clone();
+        self.fee_rate_governor = parent.fee_rate_governor;
+        self.compute_budget = parent.compute_budget;
 
         self.slot = slot;
         self.max_tick_height = (self.slot + 1) * self.ticks_per_slot - 1;
 
         self.blockhash_queue = RwLock::new(parent.blockhash_queue.read().unwrap().clone());
         self.src.status_cache = parent.src.status_cache.clone();
+        self.return_data_cache = parent.return_data_cache.clone();
+        self.execution_observer = parent.execution_observer.clone();
         self.bank_height = parent.bank_height + 1;
-        self.fee_calculator =
-            FeeCalculator::new_derived(&parent.fee_calculator, parent.signature_count());
+        self.fee_calculator = FeeCalculator::new_derived_with_governor(
+            &parent.fee_calculator,
+            parent.signature_count(),
+            &self.fee_rate_governor,
+        );
 
         self.capitalization
             .store(parent.capitalization() as usize, Ordering::Relaxed);
@@ -434,6 +521,29 @@ impl Bank {
         self.store_account(&slot_hashes::id(), &account);
     }
 
+    fn update_recent_blockhashes(&self) {
+        let blockhashes = self
+            .blockhash_queue
+            .read()
+            .unwrap()
+            .get_recent_blockhashes();
+        self.store_account(
+            &recent_blockhashes::id(),
+            &recent_blockhashes::create_account(1, &blockhashes),
+        );
+    }
+
+    fn update_epoch_schedule(&self) {
+        self.store_account(
+            &epoch_schedule_sysvar::id(),
+            &epoch_schedule_sysvar::create_account(1, &self.epoch_schedule),
+        );
+    }
+
+    fn update_rent(&self) {
+        self.store_account(&rent::id(), &rent::create_account(1, &self.rent));
+    }
+
     fn update_fees(&self) {
         self.store_account(&fees::id(), &fees::create_account(1, &self.fee_calculator));
     }
@@ -528,6 +638,13 @@ impl Bank {
     pub fn freeze(&self) {
         if self.set_hash() {
             self.update_slot_hashes();
+            if let Some(plugin_manager) = self.rc.accounts.accounts_db.plugin_manager() {
+                plugin_manager.notify_slot_status(
+                    self.slot(),
+                    self.parent().map(|parent| parent.slot()),
+                    SlotStatus::Processed,
+                );
+            }
         }
     }
 
@@ -551,9 +668,14 @@ impl Bank {
         squash_accounts_time.stop();
 
         let mut squash_cache_time = Measure::start("squash_cache_time");
-        parents
-            .iter()
-            .for_each(|p| self.src.status_cache.write().unwrap().add_root(p.slot()));
+        {
+            // The status cache takes its roots oldest first
+            let mut status_cache = self.src.status_cache.write().unwrap();
+            for p in parents.iter().rev() {
+                status_cache.add_root(p.slot());
+            }
+        }
+        self.add_return_data_roots(&parents);
         squash_cache_time.stop();
 
         datapoint_info!(
@@ -571,6 +693,7 @@ impl Bank {
     fn process_genesis_block(&mut self, genesis_block: &GenesisBlock) {
         // Bootstrap leader collects fees until `new_from_parent` is called.
         self.fee_calculator = genesis_block.fee_calculator.clone();
+        self.fee_rate_governor = genesis_block.fee_rate_governor;
         self.update_fees();
 
         for (pubkey, account) in genesis_block.accounts.iter() {
@@ -615,6 +738,11 @@ impl Bank {
         );
 
         self.inflation = genesis_block.inflation.clone();
+        self.rent = genesis_block.rent.clone();
+
+        self.update_epoch_schedule();
+        self.update_rent();
+        self.update_recent_blockhashes();
 
         // Add native programs mandatory for the MessageProcessor to function
         self.register_native_instruction_processor(
@@ -676,7 +804,173 @@ impl Bank {
         }
     }
 
-    fn update_transaction_statuses(&self, txs: &[Transaction], res: &[Result<()>]) {
+    /// Mark `roots` so their return data stays visible once they are squashed,
+    /// and drop return data from slots too old for their transactions to be
+    /// processed again
+    fn add_return_data_roots(&self, roots: &[Arc<Bank>]) {
+        let min_slot = self.slot().saturating_sub(MAX_RECENT_BLOCKHASHES as u64);
+        let mut return_data_cache = self.return_data_cache.write().unwrap();
+        return_data_cache
+            .roots
+            .extend(roots.iter().map(|root| root.slot()));
+        return_data_cache.roots.retain(|slot| *slot >= min_slot);
+        return_data_cache.return_data.retain(|_, forks| {
+            forks.retain(|(slot, _)| *slot >= min_slot);
+            !forks.is_empty()
+        });
+    }
+
+    fn update_return_data(
+        &self,
+        txs: &[SanitizedTransaction],
+        res: &[Result<()>],
+        return_data: &[Option<TransactionReturnData>],
+    ) {
+        let mut return_data_cache = self.return_data_cache.write().unwrap();
+        for ((tx, res), return_data) in txs.iter().zip(res).zip(return_data) {
+            if let (Ok(()), Some(return_data), Some(signature)) =
+                (res, return_data, tx.signatures.get(0))
+            {
+                return_data_cache
+                    .return_data
+                    .entry(*signature)
+                    .or_default()
+                    .push((self.slot(), return_data.clone()));
+            }
+        }
+    }
+
+    /// The lamports of each account of `message`, from the accounts loaded for it. Programs
+    /// aren't loaded with the other accounts, but with the loaders of the instructions calling
+    /// them.
+    fn account_balances(
+        message: &Message,
+        accounts: &[Account],
+        loaders: &[Vec<(Pubkey, Account)>],
+    ) -> Vec<u64> {
+        let program_ids = message.program_ids();
+        let mut accounts = accounts.iter();
+        message
+            .account_keys
+            .iter()
+            .map(|pubkey| {
+                if program_ids.contains(&pubkey) {
+                    loaders
+                        .iter()
+                        .flatten()
+                        .find(|(program_id, _)| program_id == pubkey)
+                        .map_or(0, |(_, account)| account.lamports)
+                } else {
+                    accounts.next().map_or(0, |account| account.lamports)
+                }
+            })
+            .collect()
+    }
+
+    fn update_transaction_status_metas(
+        &self,
+        txs: &[SanitizedTransaction],
+        executed: &[Result<()>],
+        loaded_accounts: &[Result<(InstructionAccounts, InstructionLoaders, InstructionCredits)>],
+        pre_balances: Vec<Vec<u64>>,
+    ) {
+        let hash_queue = self.blockhash_queue.read().unwrap();
+        let mut transaction_status_metas = self.transaction_status_metas.write().unwrap();
+        let mut transaction_addresses = self.transaction_addresses.write().unwrap();
+        let mut rewards = self.rewards.write().unwrap();
+        for (((tx, res), loaded), mut pre_balances) in txs
+            .iter()
+            .zip(executed)
+            .zip(loaded_accounts)
+            .zip(pre_balances)
+        {
+            if !Self::can_commit(res) {
+                continue;
+            }
+            let fee = hash_queue
+                .get_fee_calculator(&tx.message().recent_blockhash)
+                .map_or(0, |fee_calculator| {
+                    fee_calculator.calculate_fee(tx.message())
+                });
+            // Failed transactions only paid their fee, which was withdrawn when they were loaded
+            let post_balances = match (res, loaded) {
+                (Ok(()), Ok((accounts, loaders, _))) => {
+                    Self::account_balances(tx.message(), accounts, loaders)
+                }
+                _ => pre_balances.clone(),
+            };
+            if let Some(payer_balance) = pre_balances.first_mut() {
+                *payer_balance += fee;
+            }
+            let message = tx.message();
+            // Nearly every transaction uses a program and many a sysvar, indexing those would
+            // only bloat the index
+            transaction_addresses.extend(
+                message
+                    .account_keys
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, pubkey)| {
+                        message.program_position(*i).is_none() && !sysvar::is_sysvar_id(pubkey)
+                    })
+                    .map(|(i, pubkey)| (*pubkey, tx.signatures[0], message.is_debitable(i))),
+            );
+            if res.is_ok() {
+                self.record_rewards(&mut rewards, message, &pre_balances, &post_balances);
+            }
+            transaction_status_metas.insert(
+                tx.signatures[0],
+                TransactionStatusMeta {
+                    status: res.clone(),
+                    fee,
+                    pre_balances,
+                    post_balances,
+                },
+            );
+        }
+    }
+
+    /// Records what the RedeemVoteCredits instructions of a successful transaction paid the stake
+    /// accounts and the vote accounts they're delegated to, from the transaction's balance changes
+    fn record_rewards(
+        &self,
+        rewards: &mut HashMap<Pubkey, RewardInfo>,
+        message: &Message,
+        pre_balances: &[u64],
+        post_balances: &[u64],
+    ) {
+        // Both accounts of every redemption, with the commission they were paid at
+        let mut paid = HashMap::new();
+        for instruction in &message.instructions {
+            if *instruction.program_id(&message.account_keys) != solana_stake_api::id() {
+                continue;
+            }
+            if let Ok(StakeInstruction::RedeemVoteCredits) = deserialize(&instruction.data) {
+                let (stake_index, vote_index) = (
+                    instruction.accounts[0] as usize,
+                    instruction.accounts[1] as usize,
+                );
+                let commission = self
+                    .get_account(&message.account_keys[vote_index])
+                    .and_then(|account| VoteState::from(&account))
+                    .map_or(0, |vote_state| vote_state.commission);
+                paid.insert(stake_index, commission);
+                paid.insert(vote_index, commission);
+            }
+        }
+        for (index, commission) in paid {
+            let lamports = post_balances[index].saturating_sub(pre_balances[index]);
+            if lamports == 0 {
+                continue;
+            }
+            let reward = rewards.entry(message.account_keys[index]).or_default();
+            reward.lamports += lamports;
+            reward.post_balance = post_balances[index];
+            reward.commission = commission;
+        }
+    }
+
+    fn update_transaction_statuses(&self, txs: &[SanitizedTransaction], res: &[Result<()>]) {
         let mut status_cache = self.src.status_cache.write().unwrap();
         for (i, tx) in txs.iter().enumerate() {
             if Self::can_commit(&res[i]) && !tx.signatures.is_empty() {
@@ -744,13 +1038,14 @@ impl Bank {
                 .write()
                 .unwrap()
                 .register_hash(hash, &self.fee_calculator);
+            self.update_recent_blockhashes();
         }
     }
 
-    /// Process a Transaction. This is used for unit tests and simply calls the vector
-    /// Bank::process_transactions method, and commits credit-only credits.
+    /// Process a Transaction. This is used for unit tests and simply sanitizes it, calls the
+    /// vector Bank::process_transactions method, and commits credit-only credits.
     pub fn process_transaction(&self, tx: &Transaction) -> Result<()> {
-        let txs = vec![tx.clone()];
+        let txs = vec![SanitizedTransaction::try_new(tx.clone())?];
         self.process_transactions(&txs)[0].clone()?;
         // Call this instead of commit_credits(), so that the credit-only locks hashmap on this
         // bank isn't deleted
@@ -764,14 +1059,17 @@ impl Bank {
 
     pub fn lock_accounts<'a, 'b>(
         &'a self,
-        txs: &'b [Transaction],
+        txs: &'b [SanitizedTransaction],
     ) -> LockedAccountsResults<'a, 'b> {
         if self.is_frozen() {
             warn!("=========== FIXME: lock_accounts() working on a frozen bank! ================");
         }
         // TODO: put this assert back in
         // assert!(!self.is_frozen());
-        let results = self.rc.accounts.lock_accounts(txs);
+        let results = self
+            .rc
+            .accounts
+            .lock_accounts(SanitizedTransaction::as_transactions(txs));
         LockedAccountsResults::new(results, &self, txs)
     }
 
@@ -779,47 +1077,41 @@ impl Bank {
         if locked_accounts_results.needs_unlock {
             locked_accounts_results.needs_unlock = false;
             self.rc.accounts.unlock_accounts(
-                locked_accounts_results.transactions(),
+                SanitizedTransaction::as_transactions(locked_accounts_results.transactions()),
                 locked_accounts_results.locked_accounts_results(),
             )
         }
     }
 
+    /// Warm the accounts `txs` will load, ahead of processing them. Returns the number of
+    /// accounts found.
+    pub fn prefetch_accounts(&self, txs: &[SanitizedTransaction]) -> usize {
+        self.rc
+            .accounts
+            .prefetch_accounts(&self.ancestors, SanitizedTransaction::as_transactions(txs))
+    }
+
+    pub fn prefetch_stats(&self) -> &PrefetchStats {
+        self.rc.accounts.prefetch_stats()
+    }
+
     fn load_accounts(
         &self,
-        txs: &[Transaction],
+        txs: &[SanitizedTransaction],
         results: Vec<Result<()>>,
         error_counters: &mut ErrorCounters,
     ) -> Vec<Result<(InstructionAccounts, InstructionLoaders, InstructionCredits)>> {
         self.rc.accounts.load_accounts(
             &self.ancestors,
-            txs,
+            SanitizedTransaction::as_transactions(txs),
             results,
             &self.blockhash_queue.read().unwrap(),
             error_counters,
         )
     }
-    fn check_refs(
-        &self,
-        txs: &[Transaction],
-        lock_results: &[Result<()>],
-        error_counters: &mut ErrorCounters,
-    ) -> Vec<Result<()>> {
-        txs.iter()
-            .zip(lock_results)
-            .map(|(tx, lock_res)| {
-                if lock_res.is_ok() && !tx.verify_refs() {
-                    error_counters.invalid_account_index += 1;
-                    Err(TransactionError::InvalidAccountIndex)
-                } else {
-                    lock_res.clone()
-                }
-            })
-            .collect()
-    }
     fn check_age(
         &self,
-        txs: &[Transaction],
+        txs: &[SanitizedTransaction],
         lock_results: Vec<Result<()>>,
         max_age: usize,
         error_counters: &mut ErrorCounters,
@@ -841,7 +1133,7 @@ impl Bank {
     }
     fn check_signatures(
         &self,
-        txs: &[Transaction],
+        txs: &[SanitizedTransaction],
         lock_results: Vec<Result<()>>,
         error_counters: &mut ErrorCounters,
     ) -> Vec<Result<()>> {
@@ -879,13 +1171,12 @@ impl Bank {
 
     pub fn check_transactions(
         &self,
-        txs: &[Transaction],
+        txs: &[SanitizedTransaction],
         lock_results: &[Result<()>],
         max_age: usize,
         mut error_counters: &mut ErrorCounters,
     ) -> Vec<Result<()>> {
-        let refs_results = self.check_refs(txs, lock_results, &mut error_counters);
-        let age_results = self.check_age(txs, refs_results, max_age, &mut error_counters);
+        let age_results = self.check_age(txs, lock_results.to_vec(), max_age, &mut error_counters);
         self.check_signatures(txs, age_results, &mut error_counters)
     }
 
@@ -898,14 +1189,6 @@ impl Bank {
                 1000
             );
         }
-        if 0 != error_counters.invalid_account_index {
-            inc_new_counter_error!(
-                "bank-process_transactions-error-invalid_account_index",
-                error_counters.invalid_account_index,
-                0,
-                1000
-            );
-        }
         if 0 != error_counters.reserve_blockhash {
             inc_new_counter_error!(
                 "bank-process_transactions-error-reserve_blockhash",
@@ -948,19 +1231,12 @@ impl Bank {
         }
     }
 
-    #[allow(clippy::type_complexity)]
     pub fn load_and_execute_transactions(
         &self,
-        txs: &[Transaction],
+        txs: &[SanitizedTransaction],
         lock_results: &LockedAccountsResults,
         max_age: usize,
-    ) -> (
-        Vec<Result<(InstructionAccounts, InstructionLoaders, InstructionCredits)>>,
-        Vec<Result<()>>,
-        Vec<usize>,
-        usize,
-        usize,
-    ) {
+    ) -> LoadAndExecuteTransactionsOutput {
         debug!("processing transactions: {}", txs.len());
         let mut error_counters = ErrorCounters::default();
         let mut load_time = Measure::start("accounts_load");
@@ -987,15 +1263,41 @@ impl Bank {
 
         let mut execution_time = Measure::start("execution_time");
         let mut signature_count = 0;
+        let mut return_data = Vec::with_capacity(txs.len());
+        let mut instruction_traces = Vec::with_capacity(txs.len());
+        let mut owner_changes = Vec::with_capacity(txs.len());
+        let mut pre_balances = Vec::with_capacity(txs.len());
         let executed: Vec<Result<()>> = loaded_accounts
             .iter_mut()
             .zip(txs.iter())
             .map(|(accs, tx)| match accs {
-                Err(e) => Err(e.clone()),
+                Err(e) => {
+                    return_data.push(None);
+                    instruction_traces.push(vec![]);
+                    owner_changes.push(vec![]);
+                    pre_balances.push(vec![]);
+                    Err(e.clone())
+                }
                 Ok((ref mut accounts, ref mut loaders, ref mut credits)) => {
                     signature_count += tx.message().header.num_required_signatures as usize;
-                    self.message_processor
-                        .process_message(tx.message(), loaders, accounts, credits)
+                    pre_balances.push(Self::account_balances(tx.message(), accounts, loaders));
+                    let mut tx_return_data = None;
+                    let mut instruction_trace = vec![];
+                    let mut tx_owner_changes = vec![];
+                    let result = self.message_processor.process_message(
+                        tx.message(),
+                        loaders,
+                        accounts,
+                        credits,
+                        &self.compute_budget,
+                        &mut instruction_trace,
+                        &mut tx_return_data,
+                        &mut tx_owner_changes,
+                    );
+                    return_data.push(tx_return_data);
+                    instruction_traces.push(instruction_trace);
+                    owner_changes.push(tx_owner_changes);
+                    result
                 }
             })
             .collect();
@@ -1032,18 +1334,22 @@ impl Bank {
         }
 
         Self::update_error_counters(&error_counters);
-        (
+        LoadAndExecuteTransactionsOutput {
             loaded_accounts,
             executed,
+            return_data,
+            instruction_traces,
+            owner_changes,
+            pre_balances,
             retryable_txs,
             tx_count,
             signature_count,
-        )
+        }
     }
 
     fn filter_program_errors_and_collect_fee(
         &self,
-        txs: &[Transaction],
+        txs: &[SanitizedTransaction],
         executed: &[Result<()>],
     ) -> Vec<Result<()>> {
         let hash_queue = self.blockhash_queue.read().unwrap();
@@ -1064,11 +1370,11 @@ impl Bank {
                         // necessary to withdraw from account[0] here because previous
                         // work of doing so (in accounts.load()) is ignored by store_account()
                         self.withdraw(&message.account_keys[0], fee)?;
-                        fees += fee;
+                        fees = fees.saturating_add(fee);
                         Ok(())
                     }
                     Ok(()) => {
-                        fees += fee;
+                        fees = fees.saturating_add(fee);
                         Ok(())
                     }
                     _ => res.clone(),
@@ -1083,13 +1389,16 @@ impl Bank {
 
     pub fn commit_transactions(
         &self,
-        txs: &[Transaction],
+        txs: &[SanitizedTransaction],
         loaded_accounts: &mut [Result<(
             InstructionAccounts,
             InstructionLoaders,
             InstructionCredits,
         )>],
         executed: &[Result<()>],
+        return_data: &[Option<TransactionReturnData>],
+        owner_changes: &[AccountOwnerChanges],
+        pre_balances: Vec<Vec<u64>>,
         tx_count: usize,
         signature_count: usize,
     ) -> Vec<Result<()>> {
@@ -1110,9 +1419,12 @@ impl Bank {
         // TODO: put this assert back in
         // assert!(!self.is_frozen());
         let mut write_time = Measure::start("write_time");
-        self.rc
-            .accounts
-            .store_accounts(self.slot(), txs, executed, loaded_accounts);
+        self.rc.accounts.store_accounts(
+            self.slot(),
+            SanitizedTransaction::as_transactions(txs),
+            executed,
+            loaded_accounts,
+        );
 
         self.update_cached_accounts(txs, executed, loaded_accounts);
 
@@ -1120,31 +1432,146 @@ impl Bank {
         write_time.stop();
         debug!("store: {}us txs_len={}", write_time.as_us(), txs.len(),);
         self.update_transaction_statuses(txs, &executed);
-        self.filter_program_errors_and_collect_fee(txs, executed)
+        self.update_return_data(txs, &executed, return_data);
+        self.notify_owner_changes(txs, &executed, owner_changes);
+        // Fees of failed transactions are withdrawn here, so notify plugins after
+        let results = self.filter_program_errors_and_collect_fee(txs, executed);
+        self.update_transaction_status_metas(txs, executed, loaded_accounts, pre_balances);
+        self.notify_committed_transactions(txs, executed);
+        results
+    }
+
+    fn notify_committed_transactions(&self, txs: &[SanitizedTransaction], executed: &[Result<()>]) {
+        if let Some(plugin_manager) = self.rc.accounts.accounts_db.plugin_manager() {
+            for (tx, res) in txs.iter().zip(executed) {
+                if Self::can_commit(res) {
+                    plugin_manager.notify_transaction(self.slot(), tx, res);
+                }
+            }
+        }
+    }
+
+    fn notify_owner_changes(
+        &self,
+        txs: &[SanitizedTransaction],
+        executed: &[Result<()>],
+        owner_changes: &[AccountOwnerChanges],
+    ) {
+        let execution_observer = self.execution_observer.read().unwrap().clone();
+        for ((tx, res), tx_owner_changes) in txs.iter().zip(executed).zip(owner_changes) {
+            // Only successful transactions commit their account changes
+            if res.is_err() {
+                continue;
+            }
+            for change in tx_owner_changes {
+                debug!(
+                    "account {} reassigned from {} to {} in slot {}",
+                    change.pubkey,
+                    change.old_owner,
+                    change.new_owner,
+                    self.slot()
+                );
+                if let Some(execution_observer) = &execution_observer {
+                    execution_observer.account_owner_changed(
+                        self.slot(),
+                        &tx.signatures[0],
+                        change,
+                    );
+                }
+            }
+        }
     }
 
     /// Process a batch of transactions.
     #[must_use]
     pub fn load_execute_and_commit_transactions(
         &self,
-        txs: &[Transaction],
+        txs: &[SanitizedTransaction],
         lock_results: &LockedAccountsResults,
         max_age: usize,
     ) -> Vec<Result<()>> {
-        let (mut loaded_accounts, executed, _, tx_count, signature_count) =
-            self.load_and_execute_transactions(txs, lock_results, max_age);
+        let LoadAndExecuteTransactionsOutput {
+            mut loaded_accounts,
+            executed,
+            return_data,
+            owner_changes,
+            pre_balances,
+            tx_count,
+            signature_count,
+            ..
+        } = self.load_and_execute_transactions(txs, lock_results, max_age);
 
         self.commit_transactions(
             txs,
             &mut loaded_accounts,
             &executed,
+            &return_data,
+            &owner_changes,
+            pre_balances,
             tx_count,
             signature_count,
         )
     }
 
+    /// Execute a transaction against this Bank without committing it, returning
+    /// its result, return data and instruction trace
+    pub fn simulate_transaction(
+        &self,
+        tx: &SanitizedTransaction,
+    ) -> (Result<()>, Option<TransactionReturnData>, InstructionTrace) {
+        let txs = [tx.clone()];
+        let lock_results = self.lock_accounts(&txs);
+        let LoadAndExecuteTransactionsOutput {
+            mut executed,
+            mut return_data,
+            mut instruction_traces,
+            ..
+        } = self.load_and_execute_transactions(&txs, &lock_results, MAX_RECENT_BLOCKHASHES);
+        (
+            executed.remove(0),
+            return_data.remove(0),
+            instruction_traces.remove(0),
+        )
+    }
+
+    pub fn compute_budget(&self) -> ComputeBudget {
+        self.compute_budget
+    }
+
+    /// Limit the program execution of this Bank's transactions, and its descendants'
+    pub fn set_compute_budget(&mut self, compute_budget: ComputeBudget) {
+        self.compute_budget = compute_budget;
+    }
+
+    /// Notify `execution_observer` of the changes committed by this Bank and the Banks it shares
+    /// its observer with, its ancestors and descendants
+    pub fn set_execution_observer(&self, execution_observer: Arc<dyn ExecutionObserver>) {
+        *self.execution_observer.write().unwrap() = Some(execution_observer);
+    }
+
+    /// Notify `plugin_manager`'s plugins of the account writes, slot status changes and
+    /// committed transactions of every Bank sharing this Bank's accounts, starting with the
+    /// accounts this Bank already holds
+    pub fn set_plugin_manager(&self, plugin_manager: Arc<PluginManager>) {
+        let accounts_db = &self.rc.accounts.accounts_db;
+        // Install the plugins first, so accounts written during the scan are sent again rather
+        // than missed
+        accounts_db.set_plugin_manager(plugin_manager.clone());
+        let accounts = accounts_db.scan_accounts(
+            &self.ancestors,
+            |collector: &mut Vec<(Pubkey, Account, Fork)>, option| {
+                if let Some((pubkey, account, fork)) = option {
+                    collector.push((*pubkey, account, fork));
+                }
+            },
+        );
+        for (pubkey, account, fork) in &accounts {
+            plugin_manager.notify_account_update(*fork, pubkey, account);
+        }
+    }
+
     #[must_use]
-    pub fn process_transactions(&self, txs: &[Transaction]) -> Vec<Result<()>> {
+    pub fn process_transactions(&self, txs: &[SanitizedTransaction]) -> Vec<Result<()>> {
         let lock_results = self.lock_accounts(txs);
         self.load_execute_and_commit_transactions(txs, &lock_results, MAX_RECENT_BLOCKHASHES)
     }
@@ -1211,7 +1638,8 @@ impl Bank {
 
     pub fn deposit(&self, pubkey: &Pubkey, lamports: u64) {
         let mut account = self.get_account(pubkey).unwrap_or_default();
-        account.lamports += lamports;
+        // No transaction is there to fail, and the total supply is far below u64::MAX
+        account.lamports = account.lamports.saturating_add(lamports);
         self.store_account(pubkey, &account);
     }
 
@@ -1259,6 +1687,11 @@ impl Bank {
         self.transaction_count.load(Ordering::Relaxed) as u64
     }
 
+    /// The number of bytes of account storage holding the accounts this bank's slot stored
+    pub fn account_storage_size(&self) -> u64 {
+        self.rc.accounts.fork_storage_size(self.slot())
+    }
+
     fn increment_transaction_count(&self, tx_count: usize) {
         self.transaction_count
             .fetch_add(tx_count, Ordering::Relaxed);
@@ -1281,11 +1714,79 @@ impl Bank {
         rcache.get_signature_status_slow(signature, &self.ancestors)
     }
 
+    /// The slot of the Bank on this fork that committed the transaction with `signature`, and
+    /// its status
+    pub fn get_signature_slot(&self, signature: &Signature) -> Option<(u64, Result<()>)> {
+        let rcache = self.src.status_cache.read().unwrap();
+        rcache.get_signature_fork(signature, &self.ancestors)
+    }
+
     pub fn get_signature_status(&self, signature: &Signature) -> Option<Result<()>> {
         self.get_signature_confirmation_status(signature)
             .map(|v| v.1)
     }
 
+    /// Return data of a transaction executed on this fork
+    pub fn get_return_data(&self, signature: &Signature) -> Option<TransactionReturnData> {
+        let return_data_cache = self.return_data_cache.read().unwrap();
+        return_data_cache
+            .return_data
+            .get(signature)
+            .and_then(|forks| {
+                forks
+                    .iter()
+                    .find(|(slot, _)| {
+                        self.ancestors.contains_key(slot) || return_data_cache.roots.contains(slot)
+                    })
+                    .map(|(_, return_data)| return_data.clone())
+            })
+    }
+
+    /// Fees and balance changes of a transaction committed by this Bank or one of its parents.
+    /// Banks persist theirs once frozen, since they're gone from here once squashed.
+    pub fn get_transaction_status_meta(
+        &self,
+        signature: &Signature,
+    ) -> Option<TransactionStatusMeta> {
+        let meta = self
+            .transaction_status_metas
+            .read()
+            .unwrap()
+            .get(signature)
+            .cloned();
+        meta.or_else(|| {
+            self.parents().iter().find_map(|bank| {
+                let transaction_status_metas = bank.transaction_status_metas.read().unwrap();
+                transaction_status_metas.get(signature).cloned()
+            })
+        })
+    }
+
+    /// Fees and balance changes of every transaction this Bank committed
+    pub fn transaction_status_metas(&self) -> Vec<(Signature, TransactionStatusMeta)> {
+        self.transaction_status_metas
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(signature, meta)| (*signature, meta.clone()))
+            .collect()
+    }
+
+    /// Removes and returns the accounts of every transaction this Bank committed, and whether
+    /// the transaction could write them
+    pub fn take_transaction_addresses(&self) -> Vec<(Pubkey, Signature, bool)> {
+        self.transaction_addresses
+            .write()
+            .unwrap()
+            .drain(..)
+            .collect()
+    }
+
+    /// Removes and returns the inflation rewards this Bank paid
+    pub fn take_rewards(&self) -> Vec<(Pubkey, RewardInfo)> {
+        self.rewards.write().unwrap().drain().collect()
+    }
+
     pub fn has_signature(&self, signature: &Signature) -> bool {
         self.get_signature_confirmation_status(signature).is_some()
     }
@@ -1327,6 +1828,16 @@ impl Bank {
         self.tick_height.load(Ordering::Relaxed) as u64
     }
 
+    /// Return the number of slots per year, used for inflation
+    pub fn slots_per_year(&self) -> f64 {
+        self.slots_per_year
+    }
+
+    /// Return the inflation schedule of the Bank
+    pub fn inflation(&self) -> Inflation {
+        self.inflation.clone()
+    }
+
     /// Return the total capititalization of the Bank
     pub fn capitalization(&self) -> u64 {
         // capitalization is using an AtomicUSize because AtomicU64 is not yet a stable API.
@@ -1354,7 +1865,7 @@ impl Bank {
     /// a bank-level cache of vote accounts
     fn update_cached_accounts(
         &self,
-        txs: &[Transaction],
+        txs: &[SanitizedTransaction],
         res: &[Result<()>],
         loaded: &[Result<(InstructionAccounts, InstructionLoaders, InstructionCredits)>],
     ) {
@@ -1482,23 +1993,29 @@ impl Drop for Bank {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::account_update_plugin::{AccountUpdatePlugin, Result as PluginResult};
     use crate::epoch_schedule::MINIMUM_SLOTS_PER_EPOCH;
+    use crate::execution_observer::AccountOwnerChange;
     use crate::genesis_utils::{
         create_genesis_block_with_leader, GenesisBlockInfo, BOOTSTRAP_LEADER_LAMPORTS,
     };
+    use crate::message_processor::InstructionTraceEntry;
     use bincode::{deserialize_from, serialize_into, serialized_size};
+    use solana_sdk::account::KeyedAccount;
     use solana_sdk::genesis_block::create_genesis_block;
     use solana_sdk::hash;
-    use solana_sdk::instruction::InstructionError;
+    use solana_sdk::instruction::{Instruction, InstructionError};
     use solana_sdk::poh_config::PohConfig;
     use solana_sdk::signature::{Keypair, KeypairUtil};
     use solana_sdk::system_instruction;
+    use solana_sdk::system_program;
     use solana_sdk::system_transaction;
     use solana_sdk::sysvar::{fees::Fees, rewards::Rewards};
     use solana_sdk::timing::DEFAULT_TICKS_PER_SLOT;
     use solana_vote_api::vote_instruction;
     use solana_vote_api::vote_state::{VoteState, MAX_LOCKOUT_HISTORY};
     use std::io::Cursor;
+    use std::sync::Mutex;
     use std::time::Duration;
 
     #[test]
@@ -1634,7 +2151,8 @@ mod tests {
 
         let t1 = system_transaction::transfer(&mint_keypair, &key1, 1, genesis_block.hash());
         let t2 = system_transaction::transfer(&mint_keypair, &key2, 1, genesis_block.hash());
-        let res = bank.process_transactions(&vec![t1.clone(), t2.clone()]);
+        let txs = SanitizedTransaction::try_new_all(vec![t1.clone(), t2.clone()]).unwrap();
+        let res = bank.process_transactions(&txs);
         bank.commit_credits();
 
         assert_eq!(res.len(), 2);
@@ -1888,6 +2406,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bank_fee_rate_governor() {
+        let leader = Pubkey::new_rand();
+        let mut genesis_block =
+            create_genesis_block_with_leader(1_000_000, &leader, 3).genesis_block;
+        genesis_block.fee_calculator.target_lamports_per_signature = 1000;
+        genesis_block.fee_calculator.target_signatures_per_slot = 1;
+        genesis_block.fee_rate_governor = FeeRateGovernor {
+            min_percent: 90,
+            max_percent: 110,
+            adjustment_percent: 10,
+        };
+
+        let bank0 = Arc::new(Bank::new(&genesis_block));
+        assert_eq!(bank0.fee_rate_governor, genesis_block.fee_rate_governor);
+        let bank1 = Arc::new(Bank::new_from_parent(&bank0, &leader, 1));
+        assert_eq!(bank1.fee_rate_governor, genesis_block.fee_rate_governor);
+        assert_eq!(bank1.fee_calculator.min_lamports_per_signature, 900);
+        assert_eq!(bank1.fee_calculator.max_lamports_per_signature, 1100);
+        assert_eq!(bank1.fee_calculator.lamports_per_signature, 900);
+    }
+
     #[test]
     fn test_bank_blockhash_fee_schedule() {
         //solana_logger::setup();
@@ -1996,7 +2536,7 @@ mod tests {
             1,
             genesis_block.hash(),
         );
-        let txs = vec![tx0, tx1];
+        let txs = SanitizedTransaction::try_new_all(vec![tx0, tx1]).unwrap();
         let results = bank.process_transactions(&txs);
         assert!(results[1].is_err());
 
@@ -2024,7 +2564,7 @@ mod tests {
             system_transaction::transfer(&payer0, &recipient.pubkey(), 1, genesis_block.hash());
         let tx2 =
             system_transaction::transfer(&payer1, &recipient.pubkey(), 1, genesis_block.hash());
-        let txs = vec![tx0, tx1, tx2];
+        let txs = SanitizedTransaction::try_new_all(vec![tx0, tx1, tx2]).unwrap();
         let results = bank.process_transactions(&txs);
         bank.rc
             .accounts
@@ -2045,7 +2585,7 @@ mod tests {
         );
         let tx1 =
             system_transaction::transfer(&recipient, &payer0.pubkey(), 1, genesis_block.hash());
-        let txs = vec![tx0, tx1];
+        let txs = SanitizedTransaction::try_new_all(vec![tx0, tx1]).unwrap();
         let results = bank.process_transactions(&txs);
         bank.rc
             .accounts
@@ -2068,7 +2608,7 @@ mod tests {
             1,
             genesis_block.hash(),
         );
-        let pay_alice = vec![tx1];
+        let pay_alice = vec![SanitizedTransaction::try_new(tx1).unwrap()];
 
         let lock_result = bank.lock_accounts(&pay_alice);
         let results_alice = bank.load_execute_and_commit_transactions(
@@ -2117,7 +2657,7 @@ mod tests {
             instructions: vec![],
         };
         let tx = Transaction::new(&[&key0], message, genesis_block.hash());
-        let txs = vec![tx];
+        let txs = vec![SanitizedTransaction::try_new(tx).unwrap()];
 
         let lock_result0 = bank.lock_accounts(&txs);
         assert!(lock_result0.locked_accounts_results()[0].is_ok());
@@ -2135,7 +2675,7 @@ mod tests {
             instructions: vec![],
         };
         let tx = Transaction::new(&[&key1], message, genesis_block.hash());
-        let txs = vec![tx];
+        let txs = vec![SanitizedTransaction::try_new(tx).unwrap()];
 
         let lock_result1 = bank.lock_accounts(&txs);
         assert!(lock_result1.locked_accounts_results()[0].is_err());
@@ -2152,7 +2692,7 @@ mod tests {
             instructions: vec![],
         };
         let tx = Transaction::new(&[&key2], message, genesis_block.hash());
-        let txs = vec![tx];
+        let txs = vec![SanitizedTransaction::try_new(tx).unwrap()];
 
         let lock_result2 = bank.lock_accounts(&txs);
         assert!(lock_result2.locked_accounts_results()[0].is_ok());
@@ -2647,7 +3187,7 @@ mod tests {
         );
 
         let transaction = Transaction::new_signed_instructions(
-            &[&mint_keypair],
+            &[&mint_keypair, &vote_keypair],
             instructions,
             bank.last_blockhash(),
         );
@@ -2822,4 +3362,394 @@ mod tests {
         assert_eq!(bank1.get_program_accounts(&program_id).len(), 2);
         assert_eq!(bank3.get_program_accounts(&program_id).len(), 2);
     }
+
+    #[test]
+    fn test_bank_sysvar_accounts() {
+        let (mut genesis_block, _) = create_genesis_block(500);
+        genesis_block.rent = Rent {
+            lamports_per_byte_year: 5,
+            exemption_threshold: 1.5,
+        };
+        let mut bank0 = Bank::new(&genesis_block);
+
+        let account = bank0.get_account(&epoch_schedule_sysvar::id()).unwrap();
+        assert_eq!(
+            EpochSchedule::from(&account).as_ref(),
+            Some(bank0.epoch_schedule())
+        );
+        let account = bank0.get_account(&rent::id()).unwrap();
+        assert_eq!(Rent::from(&account), Some(genesis_block.rent.clone()));
+        let account = bank0.get_account(&recent_blockhashes::id()).unwrap();
+        let blockhashes = recent_blockhashes::RecentBlockhashes::from(&account).unwrap();
+        assert_eq!(blockhashes[..], [genesis_block.hash()]);
+
+        // Completing the slot registers a new blockhash
+        goto_end_of_slot(&mut bank0);
+        let account = bank0.get_account(&recent_blockhashes::id()).unwrap();
+        let blockhashes = recent_blockhashes::RecentBlockhashes::from(&account).unwrap();
+        assert_eq!(
+            blockhashes[..],
+            [bank0.last_blockhash(), genesis_block.hash()]
+        );
+
+        // Children inherit the accounts and update the clock
+        let bank0 = Arc::new(bank0);
+        let bank1 = new_from_parent(&bank0);
+        let account = bank1.get_account(&clock::id()).unwrap();
+        assert_eq!(clock::Clock::from(&account).unwrap().slot, 1);
+        let account = bank1.get_account(&rent::id()).unwrap();
+        assert_eq!(Rent::from(&account), Some(genesis_block.rent));
+        let account = bank1.get_account(&recent_blockhashes::id()).unwrap();
+        let blockhashes = recent_blockhashes::RecentBlockhashes::from(&account).unwrap();
+        assert_eq!(blockhashes[0], bank1.last_blockhash());
+    }
+
+    fn return_data_processor(
+        program_id: &Pubkey,
+        _keyed_accounts: &mut [KeyedAccount],
+        data: &[u8],
+    ) -> std::result::Result<(), InstructionError> {
+        solana_bpf_loader_api::return_data::set(program_id, data)
+            .map_err(|_| InstructionError::GenericError)
+    }
+
+    #[test]
+    fn test_bank_return_data() {
+        let (genesis_block, mint_keypair) = create_genesis_block(500);
+        let mut bank = Bank::new(&genesis_block);
+        let program_id = Pubkey::new_rand();
+        bank.add_instruction_processor(program_id, return_data_processor);
+        let bank = Arc::new(bank);
+
+        let instruction = Instruction::new(program_id, &[1u8, 2, 3], vec![]);
+        let tx = Transaction::new_signed_instructions(
+            &[&mint_keypair],
+            vec![instruction],
+            bank.last_blockhash(),
+        );
+        let signature = tx.signatures[0];
+        let expected = TransactionReturnData {
+            program_id,
+            data: vec![1, 2, 3],
+        };
+
+        // Simulating leaves no trace in the Bank
+        let instruction_trace = vec![InstructionTraceEntry {
+            instruction_index: 0,
+            depth: 1,
+            program_id,
+        }];
+        assert_eq!(
+            bank.simulate_transaction(&SanitizedTransaction::try_new(tx.clone()).unwrap()),
+            (Ok(()), Some(expected.clone()), instruction_trace)
+        );
+        assert_eq!(bank.get_signature_status(&signature), None);
+        assert_eq!(bank.get_return_data(&signature), None);
+
+        bank.process_transaction(&tx).unwrap();
+        assert_eq!(bank.get_return_data(&signature), Some(expected.clone()));
+
+        // Transactions that set no return data have none
+        let transfer_signature = bank
+            .transfer(1, &mint_keypair, &Pubkey::new_rand())
+            .unwrap();
+        assert_eq!(bank.get_return_data(&transfer_signature), None);
+
+        // Descendants see the return data, even once the Bank is rooted
+        let bank1 = Arc::new(new_from_parent(&bank));
+        let bank2 = Arc::new(new_from_parent(&bank1));
+        bank2.squash();
+        assert_eq!(bank2.get_return_data(&signature), Some(expected.clone()));
+        let bank3 = new_from_parent(&bank2);
+        assert_eq!(bank3.get_return_data(&signature), Some(expected));
+    }
+
+    #[test]
+    fn test_bank_transaction_status_metas() {
+        let (mut genesis_block, mint_keypair) = create_genesis_block(500);
+        genesis_block.fee_calculator.lamports_per_signature = 2;
+        let bank = Arc::new(Bank::new(&genesis_block));
+        let fee = bank.fee_calculator.lamports_per_signature;
+        let key = Pubkey::new_rand();
+
+        let tx = system_transaction::transfer(&mint_keypair, &key, 10, bank.last_blockhash());
+        bank.process_transaction(&tx).unwrap();
+        let meta = bank.get_transaction_status_meta(&tx.signatures[0]).unwrap();
+        assert_eq!(meta.status, Ok(()));
+        assert_eq!(meta.fee, fee);
+        assert_eq!(meta.pre_balances[..2], [500, 0]);
+        assert_eq!(meta.post_balances[..2], [490 - fee, 10]);
+
+        // Transactions whose instructions fail still pay their fee
+        let failed_tx =
+            system_transaction::transfer(&mint_keypair, &key, 1_000, bank.last_blockhash());
+        assert!(bank.process_transaction(&failed_tx).is_err());
+        let failed_meta = bank
+            .get_transaction_status_meta(&failed_tx.signatures[0])
+            .unwrap();
+        assert!(failed_meta.status.is_err());
+        assert_eq!(failed_meta.fee, fee);
+        assert_eq!(failed_meta.pre_balances[..2], [490 - fee, 10]);
+        assert_eq!(failed_meta.post_balances[..2], [490 - 2 * fee, 10]);
+
+        // Transactions that can't be committed have none
+        let expired_tx = system_transaction::transfer(&mint_keypair, &key, 1, Hash::default());
+        assert!(bank.process_transaction(&expired_tx).is_err());
+        assert_eq!(
+            bank.get_transaction_status_meta(&expired_tx.signatures[0]),
+            None
+        );
+        assert_eq!(bank.transaction_status_metas().len(), 2);
+
+        // Both committed transactions are indexed by every account they use, but the program
+        let addresses = bank.take_transaction_addresses();
+        assert_eq!(addresses.len(), 4);
+        assert_eq!(
+            addresses[0],
+            (mint_keypair.pubkey(), tx.signatures[0], true)
+        );
+        assert_eq!(addresses[1], (key, tx.signatures[0], true));
+        assert!(!addresses
+            .iter()
+            .any(|(address, _, _)| *address == system_program::id()));
+        assert!(bank.take_transaction_addresses().is_empty());
+
+        assert_eq!(
+            bank.get_signature_slot(&tx.signatures[0]),
+            Some((0, Ok(())))
+        );
+
+        // Children find the metas of their parents, but only list their own
+        let bank1 = new_from_parent(&bank);
+        assert_eq!(
+            bank1.get_signature_slot(&tx.signatures[0]),
+            Some((0, Ok(())))
+        );
+        assert_eq!(
+            bank1.get_transaction_status_meta(&tx.signatures[0]),
+            Some(meta)
+        );
+        assert!(bank1.transaction_status_metas().is_empty());
+        assert!(bank1.take_transaction_addresses().is_empty());
+    }
+
+    #[test]
+    fn test_bank_compute_budget() {
+        let (genesis_block, mint_keypair) = create_genesis_block(500);
+        let mut bank = Bank::new(&genesis_block);
+        let program_id = Pubkey::new_rand();
+        bank.add_instruction_processor(program_id, return_data_processor);
+        bank.set_compute_budget(ComputeBudget {
+            max_instruction_trace_length: 1,
+            ..ComputeBudget::default()
+        });
+        let bank = Arc::new(bank);
+
+        let instructions = vec![
+            Instruction::new(program_id, &[1u8], vec![]),
+            Instruction::new(program_id, &[2u8], vec![]),
+        ];
+        let tx = Transaction::new_signed_instructions(
+            &[&mint_keypair],
+            instructions,
+            bank.last_blockhash(),
+        );
+        let tx = SanitizedTransaction::try_new(tx).unwrap();
+        let (result, _, instruction_trace) = bank.simulate_transaction(&tx);
+        assert_eq!(
+            result,
+            Err(TransactionError::InstructionError(
+                1,
+                InstructionError::MaxInstructionTraceLengthExceeded
+            ))
+        );
+        assert_eq!(instruction_trace.len(), 1);
+
+        // Descendants keep the budget
+        let bank1 = new_from_parent(&bank);
+        assert_eq!(bank1.compute_budget(), bank.compute_budget());
+    }
+
+    #[test]
+    fn test_bank_execution_observer() {
+        #[derive(Default)]
+        struct OwnerChangeRecorder(Mutex<Vec<(u64, Signature, AccountOwnerChange)>>);
+        impl ExecutionObserver for OwnerChangeRecorder {
+            fn account_owner_changed(
+                &self,
+                slot: u64,
+                signature: &Signature,
+                change: &AccountOwnerChange,
+            ) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push((slot, *signature, change.clone()));
+            }
+        }
+
+        let (genesis_block, mint_keypair) = create_genesis_block(500);
+        let bank = Arc::new(Bank::new(&genesis_block));
+        let recorder = Arc::new(OwnerChangeRecorder::default());
+        bank.set_execution_observer(recorder.clone());
+
+        let alice = Keypair::new();
+        let new_owner = Pubkey::new_rand();
+        bank.transfer(10, &mint_keypair, &alice.pubkey()).unwrap();
+        let tx = system_transaction::assign(&alice, bank.last_blockhash(), &new_owner);
+        bank.process_transaction(&tx).unwrap();
+        let expected_change = AccountOwnerChange {
+            instruction_index: 0,
+            pubkey: alice.pubkey(),
+            old_owner: system_program::id(),
+            new_owner,
+        };
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec![(0, tx.signatures[0], expected_change.clone())]
+        );
+
+        // Failed transactions change no owners
+        let tx = system_transaction::assign(&alice, bank.last_blockhash(), &Pubkey::new_rand());
+        assert!(bank.process_transaction(&tx).is_err());
+        assert_eq!(recorder.0.lock().unwrap().len(), 1);
+
+        // Descendants notify the same observer
+        let bank1 = new_from_parent(&bank);
+        let bob = Keypair::new();
+        bank1.transfer(10, &mint_keypair, &bob.pubkey()).unwrap();
+        let tx = system_transaction::assign(&bob, bank1.last_blockhash(), &new_owner);
+        bank1.process_transaction(&tx).unwrap();
+        assert_eq!(
+            recorder.0.lock().unwrap()[1],
+            (
+                1,
+                tx.signatures[0],
+                AccountOwnerChange {
+                    pubkey: bob.pubkey(),
+                    ..expected_change.clone()
+                }
+            )
+        );
+
+        // An observer set on one Bank reaches the Banks it was already shared with
+        let bank1 = Arc::new(bank1);
+        let bank2 = Bank::new_from_parent(&bank1, &Pubkey::default(), 2);
+        let bank3 = Bank::new_from_parent(&bank1, &Pubkey::default(), 3);
+        let recorder = Arc::new(OwnerChangeRecorder::default());
+        bank2.set_execution_observer(recorder.clone());
+        let carol = Keypair::new();
+        bank3.transfer(10, &mint_keypair, &carol.pubkey()).unwrap();
+        let tx = system_transaction::assign(&carol, bank3.last_blockhash(), &new_owner);
+        bank3.process_transaction(&tx).unwrap();
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec![(
+                3,
+                tx.signatures[0],
+                AccountOwnerChange {
+                    pubkey: carol.pubkey(),
+                    ..expected_change
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_bank_plugin_manager() {
+        #[derive(Debug, PartialEq)]
+        enum Event {
+            Account(u64, Pubkey, u64),
+            Slot(u64, Option<u64>, SlotStatus),
+            Transaction(u64, Signature, Result<()>),
+        }
+        #[derive(Debug)]
+        struct EventRecorder(Arc<Mutex<Vec<Event>>>);
+        impl AccountUpdatePlugin for EventRecorder {
+            fn name(&self) -> &'static str {
+                "event_recorder"
+            }
+            fn update_account(
+                &self,
+                slot: u64,
+                pubkey: &Pubkey,
+                account: &Account,
+            ) -> PluginResult<()> {
+                let event = Event::Account(slot, *pubkey, account.lamports);
+                self.0.lock().unwrap().push(event);
+                Ok(())
+            }
+            fn update_slot_status(
+                &self,
+                slot: u64,
+                parent: Option<u64>,
+                status: SlotStatus,
+            ) -> PluginResult<()> {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(Event::Slot(slot, parent, status));
+                Ok(())
+            }
+            fn notify_transaction(
+                &self,
+                slot: u64,
+                transaction: &Transaction,
+                result: &Result<()>,
+            ) -> PluginResult<()> {
+                let event = Event::Transaction(slot, transaction.signatures[0], result.clone());
+                self.0.lock().unwrap().push(event);
+                Ok(())
+            }
+        }
+
+        let (genesis_block, mint_keypair) = create_genesis_block(500);
+        let bank = Arc::new(Bank::new(&genesis_block));
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut plugin_manager = PluginManager::new();
+        plugin_manager.add_plugin(Box::new(EventRecorder(events.clone())));
+        bank.set_plugin_manager(Arc::new(plugin_manager));
+
+        // The accounts the bank already holds are sent first
+        assert!(events
+            .lock()
+            .unwrap()
+            .contains(&Event::Account(0, mint_keypair.pubkey(), 500)));
+        events.lock().unwrap().clear();
+
+        let alice = Keypair::new();
+        let signature = bank.transfer(10, &mint_keypair, &alice.pubkey()).unwrap();
+        {
+            let events = events.lock().unwrap();
+            assert!(events.contains(&Event::Account(0, alice.pubkey(), 10)));
+            assert_eq!(
+                events.last(),
+                Some(&Event::Transaction(0, signature, Ok(())))
+            );
+        }
+
+        // Failed transactions are committed too
+        let tx =
+            system_transaction::transfer(&alice, &Pubkey::new_rand(), 100, bank.last_blockhash());
+        let result = bank.process_transaction(&tx);
+        assert!(result.is_err());
+        assert_eq!(
+            events.lock().unwrap().last(),
+            Some(&Event::Transaction(0, tx.signatures[0], result))
+        );
+
+        bank.freeze();
+        assert!(events
+            .lock()
+            .unwrap()
+            .contains(&Event::Slot(0, None, SlotStatus::Processed)));
+
+        // Descendants share the plugins
+        let bank1 = new_from_parent(&bank);
+        bank1.freeze();
+        bank1.squash();
+        let events = events.lock().unwrap();
+        assert!(events.contains(&Event::Slot(1, Some(0), SlotStatus::Processed)));
+        assert!(events.contains(&Event::Slot(0, None, SlotStatus::Rooted)));
+    }
 }
@@ -105,6 +105,17 @@ impl BlockhashQueue {
         self.last_hash = Some(*hash);
     }
 
+    /// Hashes that are still valid, most recent first
+    pub fn get_recent_blockhashes(&self) -> Vec<Hash> {
+        let mut recent: Vec<_> = self
+            .ages
+            .iter()
+            .filter(|(_, age)| Self::check_age(self.hash_height, self.max_age, age))
+            .collect();
+        recent.sort_by(|(_, a), (_, b)| b.hash_height.cmp(&a.hash_height));
+        recent.into_iter().map(|(hash, _)| *hash).collect()
+    }
+
     /// Maps a hash height to a timestamp
     pub fn hash_height_to_timestamp(&self, hash_height: u64) -> Option<u64> {
         for age in self.ages.values() {
@@ -150,4 +161,18 @@ mod tests {
         assert_eq!(last_hash, hash_queue.last_hash());
         assert!(hash_queue.check_hash_age(&last_hash, 0));
     }
+
+    #[test]
+    fn test_get_recent_blockhashes() {
+        let mut hash_queue = BlockhashQueue::new(4);
+        assert!(hash_queue.get_recent_blockhashes().is_empty());
+
+        let hashes: Vec<_> = (0..6).map(|i| hash(&serialize(&i).unwrap())).collect();
+        for hash in &hashes {
+            hash_queue.register_hash(hash, &FeeCalculator::default());
+        }
+        // Only hashes within max_age, most recent first
+        let expected: Vec<_> = hashes.iter().rev().take(5).cloned().collect();
+        assert_eq!(hash_queue.get_recent_blockhashes(), expected);
+    }
 }
@@ -0,0 +1,27 @@
+//! The `compute_budget` module limits how much program execution the runtime performs for each
+//! transaction.
+
+/// Programs are loaded through at most this many loaders, deeper call chains fail to load with
+/// `TransactionError::CallChainTooDeep`
+pub const MAX_INVOKE_DEPTH: usize = 5;
+
+pub const DEFAULT_MAX_INSTRUCTION_TRACE_LENGTH: usize = 64;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ComputeBudget {
+    /// Most programs, counting the loaders that run it, one instruction may invoke. Deeper
+    /// instructions fail with `TransactionError::CallChainTooDeep`, and values above
+    /// `MAX_INVOKE_DEPTH` have no effect.
+    pub max_invoke_depth: usize,
+    /// Most program invocations the instruction trace of a transaction may record
+    pub max_instruction_trace_length: usize,
+}
+
+impl Default for ComputeBudget {
+    fn default() -> Self {
+        Self {
+            max_invoke_depth: MAX_INVOKE_DEPTH,
+            max_instruction_trace_length: DEFAULT_MAX_INSTRUCTION_TRACE_LENGTH,
+        }
+    }
+}
@@ -0,0 +1,25 @@
+//! The `execution_observer` module lets a Bank's owner be told about the account changes the Bank
+//! commits, such as an account being handed to a new program, to audit them downstream.
+
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+/// An account whose owner an instruction changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountOwnerChange {
+    /// Index of the message instruction that changed the owner
+    pub instruction_index: u8,
+    pub pubkey: Pubkey,
+    pub old_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+/// Every owner change made while processing a message, in order
+pub type AccountOwnerChanges = Vec<AccountOwnerChange>;
+
+/// Notified of the changes a Bank, or any of its descendants, commits. Observers are called on
+/// the thread committing the transactions, so they should return quickly.
+pub trait ExecutionObserver: Send + Sync {
+    /// Called for each owner change of a committed transaction
+    fn account_owner_changed(&self, slot: u64, signature: &Signature, change: &AccountOwnerChange);
+}
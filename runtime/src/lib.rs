@@ -1,3 +1,4 @@
+pub mod account_update_plugin;
 pub mod accounts;
 pub mod accounts_db;
 pub mod accounts_index;
@@ -6,12 +7,15 @@ pub mod bank;
 pub mod bank_client;
 mod blockhash_queue;
 pub mod bloom;
+pub mod compute_budget;
 pub mod epoch_schedule;
+pub mod execution_observer;
 pub mod genesis_utils;
 pub mod loader_utils;
 pub mod locked_accounts_results;
 pub mod message_processor;
 mod native_loader;
+pub mod plugin_manager;
 mod serde_utils;
 pub mod stakes;
 pub mod status_cache;
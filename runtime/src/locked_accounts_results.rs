@@ -1,11 +1,11 @@
 use crate::bank::Bank;
-use solana_sdk::transaction::{Result, Transaction};
+use solana_sdk::transaction::{Result, SanitizedTransaction};
 
 // Represents the results of trying to lock a set of accounts
 pub struct LockedAccountsResults<'a, 'b> {
     locked_accounts_results: Vec<Result<()>>,
     bank: &'a Bank,
-    transactions: &'b [Transaction],
+    transactions: &'b [SanitizedTransaction],
     pub(crate) needs_unlock: bool,
 }
 
@@ -13,7 +13,7 @@ impl<'a, 'b> LockedAccountsResults<'a, 'b> {
     pub fn new(
         locked_accounts_results: Vec<Result<()>>,
         bank: &'a Bank,
-        transactions: &'b [Transaction],
+        transactions: &'b [SanitizedTransaction],
     ) -> Self {
         Self {
             locked_accounts_results,
@@ -27,7 +27,7 @@ impl<'a, 'b> LockedAccountsResults<'a, 'b> {
         &self.locked_accounts_results
     }
 
-    pub fn transactions(&self) -> &[Transaction] {
+    pub fn transactions(&self) -> &[SanitizedTransaction] {
         self.transactions
     }
 }
@@ -80,7 +80,7 @@ mod tests {
             .all(|x| x.is_ok()));
     }
 
-    fn setup() -> (Bank, Vec<Transaction>) {
+    fn setup() -> (Bank, Vec<SanitizedTransaction>) {
         let dummy_leader_pubkey = Pubkey::new_rand();
         let GenesisBlockInfo {
             genesis_block,
@@ -93,10 +93,11 @@ mod tests {
         let keypair2 = Keypair::new();
         let pubkey2 = Pubkey::new_rand();
 
-        let txs = vec![
+        let txs = SanitizedTransaction::try_new_all(vec![
             system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_block.hash()),
             system_transaction::transfer(&keypair2, &pubkey2, 1, genesis_block.hash()),
-        ];
+        ])
+        .unwrap();
 
         (bank, txs)
     }
@@ -1,25 +1,22 @@
-use crate::native_loader;
+use crate::compute_budget::ComputeBudget;
+use crate::execution_observer::{AccountOwnerChange, AccountOwnerChanges};
+use crate::native_loader::{self, NativeProgram};
 use crate::system_instruction_processor;
 use serde::{Deserialize, Serialize};
 use solana_sdk::account::{
     create_keyed_credit_only_accounts, Account, KeyedAccount, LamportCredit,
 };
+use solana_sdk::bpf_loader;
 use solana_sdk::instruction::{CompiledInstruction, InstructionError};
-use solana_sdk::instruction_processor_utils;
 use solana_sdk::loader_instruction::LoaderInstruction;
 use solana_sdk::message::Message;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::system_program;
-use solana_sdk::transaction::TransactionError;
+use solana_sdk::transaction::{TransactionError, TransactionReturnData};
 use std::collections::HashMap;
 use std::io::Write;
 use std::sync::RwLock;
 
-#[cfg(unix)]
-use libloading::os::unix::*;
-#[cfg(windows)]
-use libloading::os::windows::*;
-
 /// Return true if the slice has any duplicate elements
 pub fn has_duplicates<T: PartialEq>(xs: &[T]) -> bool {
     // Note: This is an O(n^2) algorithm, but requires no heap allocations. The benchmark
@@ -132,7 +129,56 @@ fn get_loader_instruction_data<'a>(
 pub type ProcessInstruction =
     fn(&Pubkey, &mut [KeyedAccount], &[u8]) -> Result<(), InstructionError>;
 
-pub type SymbolCache = RwLock<HashMap<Vec<u8>, Symbol<instruction_processor_utils::Entrypoint>>>;
+pub type SymbolCache = RwLock<HashMap<Vec<u8>, NativeProgram>>;
+
+/// A program invoked while processing a message
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InstructionTraceEntry {
+    /// Index of the message instruction the program was invoked for
+    pub instruction_index: u8,
+    /// 1 for the outermost loader, increasing through the loaders to the instruction's program
+    pub depth: u8,
+    pub program_id: Pubkey,
+}
+
+/// Every program invoked while processing a message, in order
+pub type InstructionTrace = Vec<InstructionTraceEntry>;
+
+/// Record the programs `instruction` invokes in `instruction_trace`, and check both against
+/// `compute_budget`
+fn trace_instruction(
+    instruction_index: u8,
+    program_id: &Pubkey,
+    executable_accounts: &[(Pubkey, Account)],
+    compute_budget: &ComputeBudget,
+    instruction_trace: &mut InstructionTrace,
+) -> Result<(), TransactionError> {
+    // Each executable account is keyed by the loader that runs it, the first by the native
+    // loader, which isn't a program
+    let programs: Vec<_> = executable_accounts
+        .iter()
+        .skip(1)
+        .map(|(loader_id, _)| loader_id)
+        .chain(std::iter::once(program_id))
+        .collect();
+    if programs.len() > compute_budget.max_invoke_depth {
+        return Err(TransactionError::CallChainTooDeep);
+    }
+    for (i, program_id) in programs.into_iter().enumerate() {
+        if instruction_trace.len() >= compute_budget.max_instruction_trace_length {
+            return Err(TransactionError::InstructionError(
+                instruction_index,
+                InstructionError::MaxInstructionTraceLengthExceeded,
+            ));
+        }
+        instruction_trace.push(InstructionTraceEntry {
+            instruction_index,
+            depth: (i + 1) as u8,
+            program_id: *program_id,
+        });
+    }
+    Ok(())
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct MessageProcessor {
@@ -144,10 +190,13 @@ pub struct MessageProcessor {
 
 impl Default for MessageProcessor {
     fn default() -> Self {
-        let instruction_processors: Vec<(Pubkey, ProcessInstruction)> = vec![(
-            system_program::id(),
-            system_instruction_processor::process_instruction,
-        )];
+        let instruction_processors: Vec<(Pubkey, ProcessInstruction)> = vec![
+            (
+                system_program::id(),
+                system_instruction_processor::process_instruction,
+            ),
+            (bpf_loader::id(), solana_bpf_loader_api::process_instruction),
+        ];
 
         Self {
             instruction_processors,
@@ -168,13 +217,15 @@ impl MessageProcessor {
     }
 
     /// Process an instruction
-    /// This method calls the instruction's program entrypoint method
+    /// This method calls the instruction's program entrypoint method, with `return_data`
+    /// swapped in for the program to read and replace
     fn process_instruction(
         &self,
         message: &Message,
         instruction: &CompiledInstruction,
         executable_accounts: &mut [(Pubkey, Account)],
         program_accounts: &mut [&mut Account],
+        return_data: &mut Option<TransactionReturnData>,
     ) -> Result<(), InstructionError> {
         let program_id = instruction.program_id(&message.account_keys);
 
@@ -212,7 +263,10 @@ impl MessageProcessor {
 
         for (id, process_instruction) in &self.instruction_processors {
             if id == program_id {
-                return process_instruction(&program_id, &mut keyed_accounts[1..], &ix_data);
+                solana_bpf_loader_api::return_data::swap(return_data.take());
+                let result = process_instruction(&program_id, &mut keyed_accounts[1..], &ix_data);
+                *return_data = solana_bpf_loader_api::return_data::take();
+                return result;
             }
         }
 
@@ -225,6 +279,7 @@ impl MessageProcessor {
             &program_id,
             &mut keyed_accounts,
             ix_data,
+            return_data,
             &self.symbol_cache,
         )
     }
@@ -233,13 +288,17 @@ impl MessageProcessor {
     /// This method calls the instruction's program entrypoint method and verifies that the result of
     /// the call does not violate the bank's accounting rules.
     /// The accounts are committed back to the bank only if this function returns Ok(_).
+    #[allow(clippy::too_many_arguments)]
     fn execute_instruction(
         &self,
         message: &Message,
+        instruction_index: u8,
         instruction: &CompiledInstruction,
         executable_accounts: &mut [(Pubkey, Account)],
         program_accounts: &mut [&mut Account],
         credits: &mut [&mut LamportCredit],
+        return_data: &mut Option<TransactionReturnData>,
+        owner_changes: &mut AccountOwnerChanges,
     ) -> Result<(), InstructionError> {
         let program_id = instruction.program_id(&message.account_keys);
         assert_eq!(instruction.accounts.len(), program_accounts.len());
@@ -254,7 +313,13 @@ impl MessageProcessor {
             .map(|a| (a.owner, a.lamports, a.data.clone(), a.executable))
             .collect();
 
-        self.process_instruction(message, instruction, executable_accounts, program_accounts)?;
+        self.process_instruction(
+            message,
+            instruction,
+            executable_accounts,
+            program_accounts,
+            return_data,
+        )?;
         // Verify the instruction
         for (
             (pre_program_id, pre_lamports, pre_data, pre_executable),
@@ -278,7 +343,9 @@ impl MessageProcessor {
                 post_account,
             )?;
             if !is_debitable {
-                *credits[i] += post_account.lamports - *pre_lamports;
+                *credits[i] = credits[i]
+                    .checked_add(post_account.lamports - *pre_lamports)
+                    .ok_or(InstructionError::ArithmeticOverflow)?;
             }
         }
         // The total sum of all the lamports in all the accounts cannot change.
@@ -289,24 +356,52 @@ impl MessageProcessor {
         if pre_total != post_total {
             return Err(InstructionError::UnbalancedInstruction);
         }
+
+        for (i, ((pre_program_id, _, _, _), post_account)) in
+            pre_data.iter().zip(program_accounts.iter()).enumerate()
+        {
+            if *pre_program_id != post_account.owner {
+                owner_changes.push(AccountOwnerChange {
+                    instruction_index,
+                    pubkey: message.account_keys[instruction.accounts[i] as usize],
+                    old_owner: *pre_program_id,
+                    new_owner: post_account.owner,
+                });
+            }
+        }
         Ok(())
     }
 
     /// Process a message.
     /// This method calls each instruction in the message over the set of loaded Accounts
     /// The accounts are committed back to the bank only if every instruction succeeds
+    /// The programs invoked are recorded in `instruction_trace`, up to the first that exceeds
+    /// `compute_budget`, the last data a program returned in `return_data`, and the account
+    /// owners changed by successful instructions in `owner_changes`
+    #[allow(clippy::too_many_arguments)]
     pub fn process_message(
         &self,
         message: &Message,
         loaders: &mut [Vec<(Pubkey, Account)>],
         accounts: &mut [Account],
         credits: &mut [LamportCredit],
+        compute_budget: &ComputeBudget,
+        instruction_trace: &mut InstructionTrace,
+        return_data: &mut Option<TransactionReturnData>,
+        owner_changes: &mut AccountOwnerChanges,
     ) -> Result<(), TransactionError> {
         for (instruction_index, instruction) in message.instructions.iter().enumerate() {
             let executable_index = message
                 .program_position(instruction.program_id_index as usize)
                 .ok_or(TransactionError::InvalidAccountIndex)?;
             let executable_accounts = &mut loaders[executable_index];
+            trace_instruction(
+                instruction_index as u8,
+                instruction.program_id(&message.account_keys),
+                executable_accounts,
+                compute_budget,
+                instruction_trace,
+            )?;
             let mut program_accounts = get_subset_unchecked_mut(accounts, &instruction.accounts)
                 .map_err(|err| TransactionError::InstructionError(instruction_index as u8, err))?;
             // TODO: `get_subset_unchecked_mut` panics on an index out of bounds if an executable
@@ -316,10 +411,13 @@ impl MessageProcessor {
                 .map_err(|err| TransactionError::InstructionError(instruction_index as u8, err))?;
             self.execute_instruction(
                 message,
+                instruction_index as u8,
                 instruction,
                 executable_accounts,
                 &mut program_accounts,
                 &mut instruction_credits,
+                return_data,
+                owner_changes,
             )
             .map_err(|err| TransactionError::InstructionError(instruction_index as u8, err))?;
         }
@@ -333,6 +431,7 @@ mod tests {
     use solana_sdk::instruction::{AccountMeta, Instruction, InstructionError};
     use solana_sdk::message::Message;
     use solana_sdk::native_loader::{create_loadable_account, id};
+    use solana_sdk::system_instruction;
 
     #[test]
     fn test_has_duplicates() {
@@ -612,8 +711,16 @@ mod tests {
         )]);
         let mut deltas = vec![0, 0];
 
-        let result =
-            message_processor.process_message(&message, &mut loaders, &mut accounts, &mut deltas);
+        let result = message_processor.process_message(
+            &message,
+            &mut loaders,
+            &mut accounts,
+            &mut deltas,
+            &ComputeBudget::default(),
+            &mut vec![],
+            &mut None,
+            &mut vec![],
+        );
         assert_eq!(result, Ok(()));
         assert_eq!(accounts[0].lamports, 50);
         assert_eq!(accounts[1].lamports, 50);
@@ -626,8 +733,16 @@ mod tests {
         )]);
         let mut deltas = vec![0, 0];
 
-        let result =
-            message_processor.process_message(&message, &mut loaders, &mut accounts, &mut deltas);
+        let result = message_processor.process_message(
+            &message,
+            &mut loaders,
+            &mut accounts,
+            &mut deltas,
+            &ComputeBudget::default(),
+            &mut vec![],
+            &mut None,
+            &mut vec![],
+        );
         assert_eq!(
             result,
             Err(TransactionError::InstructionError(
@@ -643,8 +758,16 @@ mod tests {
         )]);
         let mut deltas = vec![0, 0];
 
-        let result =
-            message_processor.process_message(&message, &mut loaders, &mut accounts, &mut deltas);
+        let result = message_processor.process_message(
+            &message,
+            &mut loaders,
+            &mut accounts,
+            &mut deltas,
+            &ComputeBudget::default(),
+            &mut vec![],
+            &mut None,
+            &mut vec![],
+        );
         assert_eq!(
             result,
             Err(TransactionError::InstructionError(
@@ -686,4 +809,46 @@ mod tests {
         // Note there was an allocation in the input vector.
         assert_eq!(loader_ix_data, expected_ix_data);
     }
+
+    #[test]
+    fn test_process_message_owner_changes() {
+        let message_processor = MessageProcessor::default();
+        let mut loaders = vec![vec![(id(), create_loadable_account("system_program"))]];
+
+        let from_pubkey = Pubkey::new_rand();
+        let to_pubkey = Pubkey::new_rand();
+        let new_owner = Pubkey::new_rand();
+        let message = Message::new(vec![
+            system_instruction::transfer(&from_pubkey, &to_pubkey, 10),
+            system_instruction::assign(&from_pubkey, &new_owner),
+        ]);
+        let mut accounts = vec![
+            Account::new(100, 0, &system_program::id()),
+            Account::new(0, 0, &system_program::id()),
+        ];
+        let mut deltas = vec![0, 0];
+        let mut owner_changes = vec![];
+
+        let result = message_processor.process_message(
+            &message,
+            &mut loaders,
+            &mut accounts,
+            &mut deltas,
+            &ComputeBudget::default(),
+            &mut vec![],
+            &mut None,
+            &mut owner_changes,
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!(accounts[0].owner, new_owner);
+        assert_eq!(
+            owner_changes,
+            vec![AccountOwnerChange {
+                instruction_index: 1,
+                pubkey: from_pubkey,
+                old_owner: system_program::id(),
+                new_owner,
+            }]
+        );
+    }
 }
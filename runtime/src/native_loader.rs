@@ -9,6 +9,7 @@ use solana_sdk::account::KeyedAccount;
 use solana_sdk::instruction::InstructionError;
 use solana_sdk::instruction_processor_utils;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::TransactionReturnData;
 use std::env;
 use std::path::PathBuf;
 use std::str;
@@ -54,28 +55,57 @@ fn create_path(name: &str) -> PathBuf {
 }
 
 #[cfg(windows)]
-fn library_open(path: &PathBuf) -> std::io::Result<Library> {
+pub(crate) fn library_open(path: &PathBuf) -> std::io::Result<Library> {
     Library::new(path)
 }
 
 #[cfg(not(windows))]
-fn library_open(path: &PathBuf) -> std::io::Result<Library> {
+pub(crate) fn library_open(path: &PathBuf) -> std::io::Result<Library> {
     // TODO linux tls bug can cause crash on dlclose(), workaround by never unloading
     Library::open(Some(path), libc::RTLD_NODELETE | libc::RTLD_NOW)
 }
 
+/// The symbols a loaded native program exports
+pub struct NativeProgram {
+    entrypoint: Symbol<instruction_processor_utils::Entrypoint>,
+    swap_return_data: Option<Symbol<instruction_processor_utils::SwapReturnData>>,
+}
+
+impl NativeProgram {
+    /// Calls the entrypoint with the transaction's `return_data` swapped into the program,
+    /// and leaves whatever the program set in its place
+    unsafe fn invoke(
+        &self,
+        program_id: &Pubkey,
+        params: &mut [KeyedAccount],
+        ix_data: &[u8],
+        return_data: &mut Option<TransactionReturnData>,
+    ) -> Result<(), InstructionError> {
+        match &self.swap_return_data {
+            Some(swap_return_data) => {
+                swap_return_data(return_data.take());
+                let ret = (self.entrypoint)(program_id, params, ix_data);
+                *return_data = swap_return_data(None);
+                ret
+            }
+            None => (self.entrypoint)(program_id, params, ix_data),
+        }
+    }
+}
+
 pub fn invoke_entrypoint(
     program_id: &Pubkey,
     keyed_accounts: &mut [KeyedAccount],
     ix_data: &[u8],
+    return_data: &mut Option<TransactionReturnData>,
     symbol_cache: &SymbolCache,
 ) -> Result<(), InstructionError> {
     // dispatch it
     let (names, params) = keyed_accounts.split_at_mut(1);
     let name_vec = &names[0].account.data;
-    if let Some(entrypoint) = symbol_cache.read().unwrap().get(name_vec) {
+    if let Some(program) = symbol_cache.read().unwrap().get(name_vec) {
         unsafe {
-            return entrypoint(program_id, params, ix_data);
+            return program.invoke(program_id, params, ix_data, return_data);
         }
     }
     let name = match str::from_utf8(name_vec) {
@@ -101,11 +131,18 @@ pub fn invoke_entrypoint(
                         return Err(InstructionError::GenericError);
                     }
                 };
-            let ret = entrypoint(program_id, params, ix_data);
+            let swap_return_data = library
+                .get(instruction_processor_utils::SWAP_RETURN_DATA.as_bytes())
+                .ok();
+            let program = NativeProgram {
+                entrypoint,
+                swap_return_data,
+            };
+            let ret = program.invoke(program_id, params, ix_data, return_data);
             symbol_cache
                 .write()
                 .unwrap()
-                .insert(name_vec.to_vec(), entrypoint);
+                .insert(name_vec.to_vec(), program);
             ret
         },
         Err(e) => {
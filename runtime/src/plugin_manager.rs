@@ -0,0 +1,166 @@
+//! The `plugin_manager` module loads `AccountUpdatePlugin`s from the libraries named by their
+//! config files, and passes notifications on to every loaded plugin. A config file is JSON
+//! with the path of the plugin's library under `libpath`, and whatever else the plugin reads:
+//!
+//! ```json
+//! { "libpath": "/path/to/libmy_plugin.so" }
+//! ```
+
+use crate::account_update_plugin::{
+    AccountUpdatePlugin, PluginConstructor, PluginError, Result, SlotStatus,
+};
+use crate::native_loader::library_open;
+#[cfg(unix)]
+use libloading::os::unix::{Library, Symbol};
+#[cfg(windows)]
+use libloading::os::windows::{Library, Symbol};
+use log::*;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::{self, Transaction};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Default)]
+pub struct PluginManager {
+    // Declared before `libraries` so the plugins are dropped before their code is unloaded
+    plugins: Vec<Box<dyn AccountUpdatePlugin>>,
+    libraries: Vec<Library>,
+}
+
+impl PluginManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the plugin described by `config_file`
+    pub fn load_plugin(&mut self, config_file: &str) -> Result<()> {
+        let config = fs::read_to_string(config_file).map_err(PluginError::ConfigFileReadError)?;
+        let config: serde_json::Value = serde_json::from_str(&config)
+            .map_err(|err| PluginError::InvalidConfig(err.to_string()))?;
+        let libpath = config["libpath"]
+            .as_str()
+            .ok_or_else(|| PluginError::InvalidConfig("libpath is missing".to_string()))?;
+
+        let library = library_open(&PathBuf::from(libpath)).map_err(PluginError::LoadError)?;
+        let mut plugin = unsafe {
+            let constructor: Symbol<PluginConstructor> = library
+                .get(b"_create_plugin")
+                .map_err(PluginError::LoadError)?;
+            Box::from_raw(constructor())
+        };
+        plugin.on_load(config_file)?;
+        info!("Loaded plugin {} from {}", plugin.name(), libpath);
+        self.plugins.push(plugin);
+        self.libraries.push(library);
+        Ok(())
+    }
+
+    /// Adds a plugin built into the validator
+    pub fn add_plugin(&mut self, plugin: Box<dyn AccountUpdatePlugin>) {
+        self.plugins.push(plugin);
+    }
+
+    pub fn plugin_names(&self) -> Vec<&'static str> {
+        self.plugins.iter().map(|plugin| plugin.name()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    pub fn notify_account_update(&self, slot: u64, pubkey: &Pubkey, account: &Account) {
+        for plugin in &self.plugins {
+            if let Err(err) = plugin.update_account(slot, pubkey, account) {
+                error!(
+                    "plugin {} failed to update account {} in slot {}: {}",
+                    plugin.name(),
+                    pubkey,
+                    slot,
+                    err
+                );
+            }
+        }
+    }
+
+    pub fn notify_slot_status(&self, slot: u64, parent: Option<u64>, status: SlotStatus) {
+        for plugin in &self.plugins {
+            if let Err(err) = plugin.update_slot_status(slot, parent, status) {
+                error!(
+                    "plugin {} failed to update slot {} to {:?}: {}",
+                    plugin.name(),
+                    slot,
+                    status,
+                    err
+                );
+            }
+        }
+    }
+
+    pub fn notify_transaction(
+        &self,
+        slot: u64,
+        transaction: &Transaction,
+        result: &transaction::Result<()>,
+    ) {
+        for plugin in &self.plugins {
+            if let Err(err) = plugin.notify_transaction(slot, transaction, result) {
+                error!(
+                    "plugin {} failed to notify transaction {} in slot {}: {}",
+                    plugin.name(),
+                    transaction.signatures[0],
+                    slot,
+                    err
+                );
+            }
+        }
+    }
+}
+
+impl fmt::Debug for PluginManager {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PluginManager")
+            .field("plugins", &self.plugins)
+            .finish()
+    }
+}
+
+impl Drop for PluginManager {
+    fn drop(&mut self) {
+        for plugin in &mut self.plugins {
+            plugin.on_unload();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_load_plugin_errors() {
+        let mut plugin_manager = PluginManager::new();
+        let config_file = env::temp_dir().join(format!("plugin-{}.json", Pubkey::new_rand()));
+        let config_file = config_file.to_str().unwrap();
+        match plugin_manager.load_plugin(config_file) {
+            Err(PluginError::ConfigFileReadError(_)) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        fs::write(config_file, r#"{"name": "no libpath"}"#).unwrap();
+        match plugin_manager.load_plugin(config_file) {
+            Err(PluginError::InvalidConfig(_)) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        fs::write(config_file, r#"{"libpath": "/nonexistent/libplugin.so"}"#).unwrap();
+        match plugin_manager.load_plugin(config_file) {
+            Err(PluginError::LoadError(_)) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert!(plugin_manager.is_empty());
+        fs::remove_file(config_file).unwrap();
+    }
+}
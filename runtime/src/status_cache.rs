@@ -1,6 +1,6 @@
 use log::*;
 use rand::{thread_rng, Rng};
-use serde::ser::SerializeSeq;
+use serde::ser::SerializeStruct;
 use serde::{Deserialize, Serialize};
 use solana_sdk::hash::Hash;
 use solana_sdk::signature::Signature;
@@ -9,38 +9,88 @@ use std::collections::{HashMap, HashSet};
 const MAX_CACHE_ENTRIES: usize = solana_sdk::timing::MAX_HASH_AGE_IN_SECONDS;
 const CACHED_SIGNATURE_SIZE: usize = 20;
 
-// Store forks in a single chunk of memory to avoid another lookup.
 pub type ForkId = u64;
-pub type ForkStatus<T> = Vec<(ForkId, T)>;
 type SignatureSlice = [u8; CACHED_SIGNATURE_SIZE];
-type SignatureMap<T> = HashMap<SignatureSlice, ForkStatus<T>>;
-type StatusMap<T> = HashMap<Hash, (ForkId, usize, SignatureMap<T>)>;
+/// The statuses of the signatures a single fork processed
+type SignatureMap<T> = HashMap<SignatureSlice, T>;
 
+/// The statuses of the transactions with one recent blockhash, partitioned by the fork that
+/// processed them, so the statuses of a fork that can no longer be rooted are dropped together
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+struct BlockhashStatus<T> {
+    /// The newest fork that processed a transaction with the blockhash
+    max_fork: ForkId,
+    /// Where the slice of each signature that's cached starts
+    sig_index: usize,
+    forks: HashMap<ForkId, SignatureMap<T>>,
+}
+
+impl<T> BlockhashStatus<T> {
+    fn sig_slice(&self, sig: &Signature) -> SignatureSlice {
+        let mut sig_slice = [0u8; CACHED_SIGNATURE_SIZE];
+        sig_slice.clone_from_slice(
+            &sig.as_ref()[self.sig_index..self.sig_index + CACHED_SIGNATURE_SIZE],
+        );
+        sig_slice
+    }
+}
+
+/// A `BlockhashStatus` holding only some of its forks, serialized in its place
+#[derive(Serialize)]
+struct BlockhashStatusRef<'a, T> {
+    max_fork: ForkId,
+    sig_index: usize,
+    forks: HashMap<ForkId, &'a SignatureMap<T>>,
+}
+
+/// The statuses of recently processed transactions, checked so that a transaction isn't
+/// processed twice by a fork. Memory is bounded by the age of the blockhashes transactions may
+/// use: blockhashes last used before the oldest of the `MAX_CACHE_ENTRIES` most recent roots
+/// are dropped, along with the statuses of forks that fall behind the newest root without being
+/// rooted.
+///
+/// Serializing the cache, for a snapshot, only writes the statuses added since it was last
+/// serialized. Loading a chain of snapshots `append`s each of them.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
 pub struct StatusCache<T: Serialize + Clone> {
-    /// all signatures seen during a hash period
-    #[serde(serialize_with = "serialize_statusmap")]
-    cache: Vec<StatusMap<T>>,
+    cache: HashMap<Hash, BlockhashStatus<T>>,
     roots: HashSet<ForkId>,
+    /// The (blockhash, fork) partitions with statuses added since the cache was last serialized
+    #[serde(skip)]
+    unsnapshotted: HashSet<(Hash, ForkId)>,
 }
 
-fn serialize_statusmap<S, T: Serialize>(x: &[StatusMap<T>], s: S) -> Result<S::Ok, S::Error>
-where
-    T: serde::Serialize + Clone,
-    S: serde::Serializer,
-{
-    let cache0: StatusMap<T> = HashMap::new();
-    let mut seq = s.serialize_seq(Some(x.len()))?;
-    seq.serialize_element(&cache0)?;
-    seq.serialize_element(&x[1])?;
-    seq.end()
+impl<T: Serialize + Clone> Serialize for StatusCache<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut cache: HashMap<Hash, BlockhashStatusRef<T>> = HashMap::new();
+        for (blockhash, fork) in &self.unsnapshotted {
+            let status = &self.cache[blockhash];
+            cache
+                .entry(*blockhash)
+                .or_insert_with(|| BlockhashStatusRef {
+                    max_fork: status.max_fork,
+                    sig_index: status.sig_index,
+                    forks: HashMap::new(),
+                })
+                .forks
+                .insert(*fork, &status.forks[fork]);
+        }
+        let mut state = serializer.serialize_struct("StatusCache", 2)?;
+        state.serialize_field("cache", &cache)?;
+        state.serialize_field("roots", &self.roots)?;
+        state.end()
+    }
 }
 
 impl<T: Serialize + Clone> Default for StatusCache<T> {
     fn default() -> Self {
         Self {
-            cache: vec![HashMap::default(); 2],
+            cache: HashMap::default(),
             roots: HashSet::default(),
+            unsnapshotted: HashSet::default(),
         }
     }
 }
@@ -53,26 +103,24 @@ impl<T: Serialize + Clone> StatusCache<T> {
         transaction_blockhash: &Hash,
         ancestors: &HashMap<ForkId, usize>,
     ) -> Option<(ForkId, T)> {
-        for cache in self.cache.iter() {
-            let map = cache.get(transaction_blockhash);
-            if map.is_none() {
-                continue;
-            }
-            let (_, index, sigmap) = map.unwrap();
-            let mut sig_slice = [0u8; CACHED_SIGNATURE_SIZE];
-            sig_slice.clone_from_slice(&sig.as_ref()[*index..*index + CACHED_SIGNATURE_SIZE]);
-            if let Some(stored_forks) = sigmap.get(&sig_slice) {
-                let res = stored_forks
-                    .iter()
-                    .filter(|(f, _)| ancestors.get(f).is_some() || self.roots.get(f).is_some())
-                    .nth(0)
-                    .cloned();
-                if res.is_some() {
-                    return res;
-                }
-            }
-        }
-        None
+        let status = self.cache.get(transaction_blockhash)?;
+        let sig_slice = status.sig_slice(sig);
+        status
+            .forks
+            .iter()
+            .filter(|(fork, _)| ancestors.contains_key(fork) || self.roots.contains(fork))
+            .find_map(|(fork, sig_map)| sig_map.get(&sig_slice).map(|res| (*fork, res.clone())))
+    }
+
+    /// The fork in the ancestors set that processed `sig`, and its status
+    pub fn get_signature_fork(
+        &self,
+        sig: &Signature,
+        ancestors: &HashMap<ForkId, usize>,
+    ) -> Option<(ForkId, T)> {
+        self.cache
+            .keys()
+            .find_map(|blockhash| self.get_signature_status(sig, blockhash, ancestors))
     }
 
     /// TODO: wallets should send the Transactions recent blockhash as well
@@ -82,12 +130,7 @@ impl<T: Serialize + Clone> StatusCache<T> {
         ancestors: &HashMap<ForkId, usize>,
     ) -> Option<(usize, T)> {
         trace!("get_signature_status_slow");
-        let mut keys = vec![];
-        for cache in self.cache.iter() {
-            let mut val: Vec<_> = cache.iter().map(|(k, _)| *k).collect();
-            keys.append(&mut val);
-        }
-        for blockhash in keys.iter() {
+        for blockhash in self.cache.keys() {
             trace!("get_signature_status_slow: trying {}", blockhash);
             if let Some((forkid, res)) = self.get_signature_status(sig, blockhash, ancestors) {
                 trace!("get_signature_status_slow: got {}", forkid);
@@ -100,88 +143,116 @@ impl<T: Serialize + Clone> StatusCache<T> {
         None
     }
 
-    /// Add a known root fork.  Roots are always valid ancestors.
+    /// Add a known root fork.  Roots are always valid ancestors, and must be added in increasing
+    /// order, so that the statuses of the forks between this root and the previous one that
+    /// weren't rooted can be dropped.
     /// After MAX_CACHE_ENTRIES, roots are removed, and any old signatures are cleared.
     pub fn add_root(&mut self, fork: ForkId) {
+        let dead_forks_start = self.roots.iter().max().map_or(0, |prev_root| prev_root + 1);
         self.roots.insert(fork);
+        let dead_forks: HashSet<_> = self
+            .cache
+            .values()
+            .flat_map(|status| status.forks.keys())
+            .filter(|f| dead_forks_start <= **f && **f < fork && !self.roots.contains(*f))
+            .cloned()
+            .collect();
+        if !dead_forks.is_empty() {
+            for status in self.cache.values_mut() {
+                status.forks.retain(|f, _| !dead_forks.contains(f));
+            }
+            self.cache.retain(|_, status| !status.forks.is_empty());
+            self.purge_unsnapshotted();
+        }
+
         if self.roots.len() > MAX_CACHE_ENTRIES {
             if let Some(min) = self.roots.iter().min().cloned() {
                 self.roots.remove(&min);
-                for cache in self.cache.iter_mut() {
-                    cache.retain(|_, (fork, _, _)| *fork > min);
-                }
+                self.cache.retain(|_, status| status.max_fork > min);
+                self.purge_unsnapshotted();
             }
         }
     }
 
-    /// Insert a new signature for a specific fork.
-    pub fn insert(&mut self, transaction_blockhash: &Hash, sig: &Signature, fork: ForkId, res: T) {
-        let sig_index: usize;
-        if let Some(sig_map) = self.cache[0].get(transaction_blockhash) {
-            sig_index = sig_map.1;
-        } else {
-            sig_index =
-                thread_rng().gen_range(0, std::mem::size_of::<Hash>() - CACHED_SIGNATURE_SIZE);
-        }
-        let sig_map = self.cache[1].entry(*transaction_blockhash).or_insert((
-            fork,
-            sig_index,
-            HashMap::new(),
-        ));
-        sig_map.0 = std::cmp::max(fork, sig_map.0);
-        let index = sig_map.1;
-        let mut sig_slice = [0u8; CACHED_SIGNATURE_SIZE];
-        sig_slice.clone_from_slice(&sig.as_ref()[index..index + CACHED_SIGNATURE_SIZE]);
-        let sig_forks = sig_map.2.entry(sig_slice).or_insert_with(|| vec![]);
-        sig_forks.push((fork, res));
+    /// Forget the partitions dropped from the cache
+    fn purge_unsnapshotted(&mut self) {
+        let cache = &self.cache;
+        self.unsnapshotted.retain(|(blockhash, fork)| {
+            cache
+                .get(blockhash)
+                .map_or(false, |status| status.forks.contains_key(fork))
+        });
     }
 
-    fn insert_entry(
-        &mut self,
-        transaction_blockhash: &Hash,
-        sig_slice: &[u8; CACHED_SIGNATURE_SIZE],
-        status: Vec<(ForkId, T)>,
-        index: usize,
-    ) {
-        let fork = status
-            .iter()
-            .fold(0, |acc, (f, _)| if acc > *f { acc } else { *f });
-        let sig_map =
-            self.cache[0]
-                .entry(*transaction_blockhash)
-                .or_insert((fork, index, HashMap::new()));
-        sig_map.0 = std::cmp::max(fork, sig_map.0);
-        let sig_forks = sig_map.2.entry(*sig_slice).or_insert_with(|| vec![]);
-        sig_forks.extend(status);
+    /// Insert a new signature for a specific fork.
+    pub fn insert(&mut self, transaction_blockhash: &Hash, sig: &Signature, fork: ForkId, res: T) {
+        let status = self
+            .cache
+            .entry(*transaction_blockhash)
+            .or_insert_with(|| BlockhashStatus {
+                max_fork: fork,
+                sig_index: thread_rng()
+                    .gen_range(0, std::mem::size_of::<Hash>() - CACHED_SIGNATURE_SIZE),
+                forks: HashMap::new(),
+            });
+        status.max_fork = std::cmp::max(fork, status.max_fork);
+        let sig_slice = status.sig_slice(sig);
+        status
+            .forks
+            .entry(fork)
+            .or_insert_with(HashMap::new)
+            .insert(sig_slice, res);
+        self.unsnapshotted.insert((*transaction_blockhash, fork));
     }
 
     /// Clear for testing
     pub fn clear_signatures(&mut self) {
-        for cache in self.cache.iter_mut() {
-            for v in cache.values_mut() {
-                v.2 = HashMap::new();
-            }
+        for status in self.cache.values_mut() {
+            status.forks = HashMap::new();
         }
+        self.unsnapshotted.clear();
     }
 
+    /// Add the statuses and roots of a deserialized snapshot of a status cache
     pub fn append(&mut self, status_cache: &StatusCache<T>) {
-        for (hash, sigmap) in status_cache.cache[1].iter() {
-            for (signature, fork_status) in sigmap.2.iter() {
-                self.insert_entry(hash, signature, fork_status.clone(), sigmap.1);
+        for (blockhash, other_status) in &status_cache.cache {
+            let status = self
+                .cache
+                .entry(*blockhash)
+                .or_insert_with(|| BlockhashStatus {
+                    max_fork: other_status.max_fork,
+                    sig_index: other_status.sig_index,
+                    forks: HashMap::new(),
+                });
+            // The partitions of a blockhash must all be sliced the same way, which they are
+            // unless `status_cache` wasn't serialized from this cache
+            if status.sig_index != other_status.sig_index {
+                warn!(
+                    "status cache for blockhash {} was sliced differently, skipping it",
+                    blockhash
+                );
+                continue;
+            }
+            status.max_fork = std::cmp::max(other_status.max_fork, status.max_fork);
+            for (fork, sig_map) in &other_status.forks {
+                status
+                    .forks
+                    .entry(*fork)
+                    .or_insert_with(HashMap::new)
+                    .extend(
+                        sig_map
+                            .iter()
+                            .map(|(sig_slice, res)| (*sig_slice, res.clone())),
+                    );
             }
         }
 
         self.roots = self.roots.union(&status_cache.roots).cloned().collect();
     }
 
-    pub fn merge_caches(&mut self) {
-        let mut cache = HashMap::new();
-        std::mem::swap(&mut cache, &mut self.cache[1]);
-        for (hash, sigmap) in cache.iter() {
-            for (signature, fork_status) in sigmap.2.iter() {
-                self.insert_entry(hash, signature, fork_status.clone(), sigmap.1);
-            }
-        }
+    /// Mark every status as written to a snapshot, once the cache is serialized
+    pub fn mark_snapshotted(&mut self) {
+        self.unsnapshotted.clear();
     }
 }
 
@@ -343,10 +414,12 @@ mod tests {
         let blockhash = hash(Hash::default().as_ref());
         status_cache.clear_signatures();
         status_cache.insert(&blockhash, &sig, 0, ());
-        let (_, index, sig_map) = status_cache.cache[1].get(&blockhash).unwrap();
+        let status = status_cache.cache.get(&blockhash).unwrap();
         let mut sig_slice = [0u8; CACHED_SIGNATURE_SIZE];
-        sig_slice.clone_from_slice(&sig.as_ref()[*index..*index + CACHED_SIGNATURE_SIZE]);
-        assert!(sig_map.get(&sig_slice).is_some());
+        sig_slice.clone_from_slice(
+            &sig.as_ref()[status.sig_index..status.sig_index + CACHED_SIGNATURE_SIZE],
+        );
+        assert!(status.forks[&0].get(&sig_slice).is_some());
     }
 
     #[test]
@@ -376,32 +449,28 @@ mod tests {
             .is_some());
     }
 
-    fn test_serialize(sc: &mut BankStatusCache, blockhash: Vec<Hash>, sig: &Signature) {
+    /// Serializes `sc`, checking that loading it, and appending it to `loaded`, the status
+    /// cache loaded from `sc`'s previous snapshots, yields `sc`
+    fn test_serialize(
+        sc: &mut BankStatusCache,
+        loaded: &mut BankStatusCache,
+        blockhash: Vec<Hash>,
+        sig: &Signature,
+    ) {
         let len = serialized_size(&sc).unwrap();
         let mut buf = vec![0u8; len as usize];
         let mut writer = Cursor::new(&mut buf[..]);
-        let cache0 = sc.cache[0].clone();
         serialize_into(&mut writer, sc).unwrap();
-        for hash in blockhash.iter() {
-            if let Some(map0) = sc.cache[0].get(hash) {
-                if let Some(map1) = sc.cache[1].get(hash) {
-                    assert_eq!(map0.1, map1.1);
-                }
-            }
-        }
-        sc.merge_caches();
+        sc.mark_snapshotted();
         let len = writer.position() as usize;
 
         let mut reader = Cursor::new(&mut buf[..len]);
-        let mut status_cache: BankStatusCache = deserialize_from(&mut reader).unwrap();
-        status_cache.cache[0] = cache0;
-        status_cache.merge_caches();
-        assert!(status_cache.cache[0].len() > 0);
-        assert!(status_cache.cache[1].is_empty());
+        let status_cache: BankStatusCache = deserialize_from(&mut reader).unwrap();
+        loaded.append(&status_cache);
+        assert_eq!(*sc, *loaded);
         let ancestors = vec![(0, 1), (1, 1)].into_iter().collect();
-        assert_eq!(*sc, status_cache);
         for hash in blockhash.iter() {
-            assert!(status_cache
+            assert!(loaded
                 .get_signature_status(&sig, &hash, &ancestors)
                 .is_some());
         }
@@ -414,15 +483,31 @@ mod tests {
         let blockhash0 = hash(Hash::new(&vec![0; 32]).as_ref());
         status_cache.add_root(0);
         status_cache.clear_signatures();
+        let mut loaded = BankStatusCache::default();
         status_cache.insert(&blockhash0, &sig, 0, ());
-        test_serialize(&mut status_cache, vec![blockhash0], &sig);
+        test_serialize(&mut status_cache, &mut loaded, vec![blockhash0], &sig);
 
         status_cache.insert(&blockhash0, &sig, 1, ());
-        test_serialize(&mut status_cache, vec![blockhash0], &sig);
+        test_serialize(&mut status_cache, &mut loaded, vec![blockhash0], &sig);
 
         let blockhash1 = hash(Hash::new(&vec![1; 32]).as_ref());
         status_cache.insert(&blockhash1, &sig, 1, ());
-        test_serialize(&mut status_cache, vec![blockhash0, blockhash1], &sig);
+        test_serialize(
+            &mut status_cache,
+            &mut loaded,
+            vec![blockhash0, blockhash1],
+            &sig,
+        );
+
+        // Only the statuses added since the last snapshot are serialized
+        assert_eq!(
+            serialized_size(&status_cache).unwrap(),
+            serialized_size(&BankStatusCache {
+                roots: status_cache.roots.clone(),
+                ..BankStatusCache::default()
+            })
+            .unwrap()
+        );
 
         let blockhash2 = hash(Hash::new(&vec![2; 32]).as_ref());
         let ancestors = vec![(0, 1), (1, 1)].into_iter().collect();
@@ -430,4 +515,68 @@ mod tests {
             .get_signature_status(&sig, &blockhash2, &ancestors)
             .is_none());
     }
+
+    #[test]
+    fn test_find_sig_on_sibling_forks() {
+        let sig = Signature::default();
+        let mut status_cache = BankStatusCache::default();
+        let blockhash = hash(Hash::default().as_ref());
+        status_cache.insert(&blockhash, &sig, 1, ());
+        let ancestors1 = vec![(1, 0), (0, 1)].into_iter().collect();
+        let ancestors2 = vec![(2, 0), (0, 1)].into_iter().collect();
+        assert_eq!(
+            status_cache.get_signature_status(&sig, &blockhash, &ancestors1),
+            Some((1, ()))
+        );
+        assert_eq!(
+            status_cache.get_signature_status(&sig, &blockhash, &ancestors2),
+            None
+        );
+    }
+
+    #[test]
+    fn test_add_root_purges_dead_forks() {
+        let sig = Signature::default();
+        let mut status_cache = BankStatusCache::default();
+        let blockhash0 = hash(Hash::new(&vec![0; 32]).as_ref());
+        let blockhash1 = hash(Hash::new(&vec![1; 32]).as_ref());
+        status_cache.add_root(0);
+        status_cache.insert(&blockhash0, &sig, 1, ());
+        status_cache.insert(&blockhash0, &sig, 2, ());
+        status_cache.insert(&blockhash1, &sig, 3, ());
+        status_cache.insert(&blockhash1, &sig, 5, ());
+
+        // Forks 1 and 3 aren't ancestors of the new root, 4
+        status_cache.add_root(2);
+        status_cache.add_root(4);
+        assert_eq!(status_cache.cache.len(), 2);
+        assert_eq!(
+            status_cache.cache[&blockhash0]
+                .forks
+                .keys()
+                .collect::<Vec<_>>(),
+            vec![&2]
+        );
+        assert_eq!(
+            status_cache.cache[&blockhash1]
+                .forks
+                .keys()
+                .collect::<Vec<_>>(),
+            vec![&5]
+        );
+        let unsnapshotted: HashSet<_> =
+            vec![(blockhash0, 2), (blockhash1, 5)].into_iter().collect();
+        assert_eq!(status_cache.unsnapshotted, unsnapshotted);
+        let ancestors = vec![(5, 0), (4, 1)].into_iter().collect();
+        assert!(status_cache
+            .get_signature_status(&sig, &blockhash0, &ancestors)
+            .is_some());
+
+        // Blockhashes with no statuses left are dropped
+        status_cache.add_root(6);
+        assert_eq!(status_cache.cache.len(), 1);
+        assert!(status_cache
+            .get_signature_status(&sig, &blockhash1, &ancestors)
+            .is_none());
+    }
 }
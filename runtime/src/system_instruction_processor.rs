@@ -8,13 +8,42 @@ use solana_sdk::sysvar;
 
 const FROM_ACCOUNT_INDEX: usize = 0;
 const TO_ACCOUNT_INDEX: usize = 1;
+const NEW_OWNER_ACCOUNT_INDEX: usize = 1;
+const BASE_ACCOUNT_INDEX: usize = 1;
+const SEED_TO_ACCOUNT_INDEX: usize = 2;
+
+/// Check that keyed_accounts[0] is at the address `base` derives from `seed` for `program_id`,
+/// and that `base` signed for it
+fn check_address_with_seed(
+    keyed_accounts: &[KeyedAccount],
+    base: &Pubkey,
+    seed: &str,
+    program_id: &Pubkey,
+) -> Result<(), InstructionError> {
+    if keyed_accounts[BASE_ACCOUNT_INDEX].signer_key() != Some(base) {
+        debug!("account[base] {} is unsigned", base);
+        Err(InstructionError::MissingRequiredSignature)?;
+    }
+    let address = Pubkey::create_with_seed(base, seed, program_id)
+        .map_err(|_| SystemError::AddressWithSeedMismatch)?;
+    if *keyed_accounts[FROM_ACCOUNT_INDEX].unsigned_key() != address {
+        debug!(
+            "account {} isn't derived from base {} and seed {:?}",
+            keyed_accounts[FROM_ACCOUNT_INDEX].unsigned_key(),
+            base,
+            seed
+        );
+        Err(SystemError::AddressWithSeedMismatch)?;
+    }
+    Ok(())
+}
 
 fn create_system_account(
     keyed_accounts: &mut [KeyedAccount],
     lamports: u64,
     space: u64,
     program_id: &Pubkey,
-) -> Result<(), SystemError> {
+) -> Result<(), InstructionError> {
     if !system_program::check_id(&keyed_accounts[FROM_ACCOUNT_INDEX].account.owner) {
         debug!(
             "CreateAccount: invalid account[from] owner {} ",
@@ -56,8 +85,12 @@ fn create_system_account(
         );
         Err(SystemError::ResultWithNegativeLamports)?;
     }
-    keyed_accounts[FROM_ACCOUNT_INDEX].account.lamports -= lamports;
-    keyed_accounts[TO_ACCOUNT_INDEX].account.lamports += lamports;
+    keyed_accounts[FROM_ACCOUNT_INDEX]
+        .account
+        .checked_sub_lamports(lamports)?;
+    keyed_accounts[TO_ACCOUNT_INDEX]
+        .account
+        .checked_add_lamports(lamports)?;
     keyed_accounts[TO_ACCOUNT_INDEX].account.owner = *program_id;
     keyed_accounts[TO_ACCOUNT_INDEX].account.data = vec![0; space as usize];
     keyed_accounts[TO_ACCOUNT_INDEX].account.executable = false;
@@ -67,14 +100,72 @@ fn create_system_account(
 fn assign_account_to_program(
     keyed_accounts: &mut [KeyedAccount],
     program_id: &Pubkey,
-) -> Result<(), SystemError> {
+) -> Result<(), InstructionError> {
+    if sysvar::check_id(program_id) {
+        debug!(
+            "Assign: invalid argument; program id {} invalid",
+            program_id
+        );
+        Err(SystemError::InvalidProgramId)?;
+    }
+
+    // The new owner would interpret data it never wrote, so it must sign to accept an account
+    // holding any
+    if keyed_accounts[FROM_ACCOUNT_INDEX]
+        .account
+        .data
+        .iter()
+        .any(|byte| *byte != 0)
+    {
+        let owner_signed = keyed_accounts
+            .get(NEW_OWNER_ACCOUNT_INDEX)
+            .map_or(false, |owner| owner.signer_key() == Some(program_id));
+        if !owner_signed {
+            debug!(
+                "Assign: account {} holds data, but {} didn't sign",
+                keyed_accounts[FROM_ACCOUNT_INDEX].unsigned_key(),
+                program_id
+            );
+            Err(SystemError::AccountDataNotZeroed)?;
+        }
+    }
+
+    keyed_accounts[FROM_ACCOUNT_INDEX].account.owner = *program_id;
+    Ok(())
+}
+
+fn allocate_account(
+    keyed_accounts: &mut [KeyedAccount],
+    space: u64,
+    program_id: &Pubkey,
+) -> Result<(), InstructionError> {
+    if !keyed_accounts[FROM_ACCOUNT_INDEX].account.data.is_empty()
+        || !system_program::check_id(&keyed_accounts[FROM_ACCOUNT_INDEX].account.owner)
+    {
+        debug!(
+            "AllocateWithSeed: invalid argument; account {} already in use",
+            keyed_accounts[FROM_ACCOUNT_INDEX].unsigned_key()
+        );
+        Err(SystemError::AccountAlreadyInUse)?;
+    }
+
+    if sysvar::check_id(program_id) {
+        debug!(
+            "AllocateWithSeed: invalid argument; program id {} invalid",
+            program_id
+        );
+        Err(SystemError::InvalidProgramId)?;
+    }
+
+    keyed_accounts[FROM_ACCOUNT_INDEX].account.data = vec![0; space as usize];
     keyed_accounts[FROM_ACCOUNT_INDEX].account.owner = *program_id;
     Ok(())
 }
+
 fn transfer_lamports(
     keyed_accounts: &mut [KeyedAccount],
     lamports: u64,
-) -> Result<(), SystemError> {
+) -> Result<(), InstructionError> {
     if lamports > keyed_accounts[FROM_ACCOUNT_INDEX].account.lamports {
         debug!(
             "Transfer: insufficient lamports ({}, need {})",
@@ -82,8 +173,70 @@ fn transfer_lamports(
         );
         Err(SystemError::ResultWithNegativeLamports)?;
     }
-    keyed_accounts[FROM_ACCOUNT_INDEX].account.lamports -= lamports;
-    keyed_accounts[TO_ACCOUNT_INDEX].account.lamports += lamports;
+    keyed_accounts[FROM_ACCOUNT_INDEX]
+        .account
+        .checked_sub_lamports(lamports)?;
+    keyed_accounts[TO_ACCOUNT_INDEX]
+        .account
+        .checked_add_lamports(lamports)?;
+    Ok(())
+}
+
+fn transfer_lamports_with_seed(
+    keyed_accounts: &mut [KeyedAccount],
+    lamports: u64,
+) -> Result<(), InstructionError> {
+    if keyed_accounts.len() <= SEED_TO_ACCOUNT_INDEX {
+        debug!("TransferWithSeed: no destination account");
+        Err(InstructionError::InvalidArgument)?;
+    }
+    if lamports > keyed_accounts[FROM_ACCOUNT_INDEX].account.lamports {
+        debug!(
+            "TransferWithSeed: insufficient lamports ({}, need {})",
+            keyed_accounts[FROM_ACCOUNT_INDEX].account.lamports, lamports
+        );
+        Err(SystemError::ResultWithNegativeLamports)?;
+    }
+    keyed_accounts[FROM_ACCOUNT_INDEX]
+        .account
+        .checked_sub_lamports(lamports)?;
+    keyed_accounts[SEED_TO_ACCOUNT_INDEX]
+        .account
+        .checked_add_lamports(lamports)?;
+    Ok(())
+}
+
+/// Transfer `lamports[i]` to keyed_accounts[i + 1].  The total is checked against the source's
+/// balance before anything moves.
+fn transfer_lamports_to_many(
+    keyed_accounts: &mut [KeyedAccount],
+    lamports: &[u64],
+) -> Result<(), InstructionError> {
+    if keyed_accounts.len() != lamports.len() + 1 {
+        debug!(
+            "TransferMany: {} amounts for {} destinations",
+            lamports.len(),
+            keyed_accounts.len() - 1
+        );
+        Err(InstructionError::InvalidArgument)?;
+    }
+    let total = lamports
+        .iter()
+        .try_fold(0u64, |total, lamports| total.checked_add(*lamports))
+        .ok_or(InstructionError::ArithmeticOverflow)?;
+    if total > keyed_accounts[FROM_ACCOUNT_INDEX].account.lamports {
+        debug!(
+            "TransferMany: insufficient lamports ({}, need {})",
+            keyed_accounts[FROM_ACCOUNT_INDEX].account.lamports, total
+        );
+        Err(SystemError::ResultWithNegativeLamports)?;
+    }
+    keyed_accounts[FROM_ACCOUNT_INDEX]
+        .account
+        .checked_sub_lamports(total)?;
+    for (to, lamports) in keyed_accounts[TO_ACCOUNT_INDEX..].iter_mut().zip(lamports) {
+        to.account.checked_add_lamports(*lamports)?;
+    }
     Ok(())
 }
 
@@ -96,9 +249,20 @@ pub fn process_instruction(
         trace!("process_instruction: {:?}", instruction);
         trace!("keyed_accounts: {:?}", keyed_accounts);
 
-        // All system instructions require that accounts_keys[0] be a signer
-        if keyed_accounts[FROM_ACCOUNT_INDEX].signer_key().is_none() {
-            debug!("account[from] is unsigned");
+        // All system instructions require that accounts_keys[0] be a signer, except those on
+        // an address derived from a seed, which its base signs for instead
+        let signer_index = match instruction {
+            SystemInstruction::AllocateWithSeed { .. }
+            | SystemInstruction::AssignWithSeed { .. }
+            | SystemInstruction::TransferWithSeed { .. } => BASE_ACCOUNT_INDEX,
+            _ => FROM_ACCOUNT_INDEX,
+        };
+        if keyed_accounts
+            .get(signer_index)
+            .and_then(|keyed_account| keyed_account.signer_key())
+            .is_none()
+        {
+            debug!("account[{}] is unsigned", signer_index);
             Err(InstructionError::MissingRequiredSignature)?;
         }
 
@@ -115,8 +279,40 @@ pub fn process_instruction(
                 assign_account_to_program(keyed_accounts, &program_id)
             }
             SystemInstruction::Transfer { lamports } => transfer_lamports(keyed_accounts, lamports),
+            SystemInstruction::AllocateWithSeed {
+                base,
+                seed,
+                space,
+                program_id,
+            } => {
+                check_address_with_seed(keyed_accounts, &base, &seed, &program_id)?;
+                allocate_account(keyed_accounts, space, &program_id)
+            }
+            SystemInstruction::AssignWithSeed {
+                base,
+                seed,
+                program_id,
+            } => {
+                check_address_with_seed(keyed_accounts, &base, &seed, &program_id)?;
+                if !system_program::check_id(&keyed_accounts[FROM_ACCOUNT_INDEX].account.owner) {
+                    Err(InstructionError::IncorrectProgramId)?;
+                }
+                // keyed_accounts[1] is the base, which can't accept data for the new owner
+                assign_account_to_program(&mut keyed_accounts[..=FROM_ACCOUNT_INDEX], &program_id)
+            }
+            SystemInstruction::TransferWithSeed {
+                lamports,
+                from_seed,
+                from_owner,
+            } => {
+                let base = *keyed_accounts[BASE_ACCOUNT_INDEX].unsigned_key();
+                check_address_with_seed(keyed_accounts, &base, &from_seed, &from_owner)?;
+                transfer_lamports_with_seed(keyed_accounts, lamports)
+            }
+            SystemInstruction::TransferMany { lamports } => {
+                transfer_lamports_to_many(keyed_accounts, &lamports)
+            }
         }
-        .map_err(|e| InstructionError::CustomError(e as u32))
     } else {
         debug!("Invalid instruction data: {:?}", data);
         Err(InstructionError::InvalidInstructionData)
@@ -134,6 +330,7 @@ mod tests {
     use solana_sdk::genesis_block::create_genesis_block;
     use solana_sdk::instruction::{AccountMeta, Instruction, InstructionError};
     use solana_sdk::signature::{Keypair, KeypairUtil};
+    use solana_sdk::system_instruction;
     use solana_sdk::system_program;
     use solana_sdk::transaction::TransactionError;
 
@@ -177,7 +374,7 @@ mod tests {
             KeyedAccount::new(&to, false, &mut to_account),
         ];
         let result = create_system_account(&mut keyed_accounts, 150, 2, &new_program_owner);
-        assert_eq!(result, Err(SystemError::ResultWithNegativeLamports));
+        assert_eq!(result, Err(SystemError::ResultWithNegativeLamports.into()));
         let from_lamports = from_account.lamports;
         assert_eq!(from_lamports, 100);
         assert_eq!(to_account, unchanged_account);
@@ -200,7 +397,7 @@ mod tests {
             KeyedAccount::new(&owned_key, false, &mut owned_account),
         ];
         let result = create_system_account(&mut keyed_accounts, 50, 2, &new_program_owner);
-        assert_eq!(result, Err(SystemError::AccountAlreadyInUse));
+        assert_eq!(result, Err(SystemError::AccountAlreadyInUse.into()));
         let from_lamports = from_account.lamports;
         assert_eq!(from_lamports, 100);
         assert_eq!(owned_account, unchanged_account);
@@ -221,7 +418,7 @@ mod tests {
         ];
         // fail to create a sysvar::id() owned account
         let result = create_system_account(&mut keyed_accounts, 50, 2, &sysvar::id());
-        assert_eq!(result, Err(SystemError::InvalidProgramId));
+        assert_eq!(result, Err(SystemError::InvalidProgramId.into()));
 
         let to = sysvar::fees::id();
         let mut to_account = Account::default();
@@ -232,7 +429,7 @@ mod tests {
         ];
         // fail to create an account with a sysvar id
         let result = create_system_account(&mut keyed_accounts, 50, 2, &system_program::id());
-        assert_eq!(result, Err(SystemError::InvalidAccountId));
+        assert_eq!(result, Err(SystemError::InvalidAccountId.into()));
 
         let from_lamports = from_account.lamports;
         assert_eq!(from_lamports, 100);
@@ -259,7 +456,7 @@ mod tests {
             KeyedAccount::new(&populated_key, false, &mut populated_account),
         ];
         let result = create_system_account(&mut keyed_accounts, 50, 2, &new_program_owner);
-        assert_eq!(result, Err(SystemError::AccountAlreadyInUse));
+        assert_eq!(result, Err(SystemError::AccountAlreadyInUse.into()));
         assert_eq!(from_account.lamports, 100);
         assert_eq!(populated_account, unchanged_account);
     }
@@ -277,7 +474,7 @@ mod tests {
             KeyedAccount::new(&to, false, &mut to_account),
         ];
         let result = create_system_account(&mut keyed_accounts, 50, 2, &other_program);
-        assert_eq!(result, Err(SystemError::SourceNotSystemAccount));
+        assert_eq!(result, Err(SystemError::SourceNotSystemAccount.into()));
     }
 
     #[test]
@@ -303,6 +500,67 @@ mod tests {
         assert_eq!(from_account.owner, new_program_owner);
     }
 
+    #[test]
+    fn test_assign_account_with_data() {
+        let new_program_owner = Pubkey::new(&[9; 32]);
+        let from = Pubkey::new_rand();
+
+        // Zeroed data needs no signature from the new owner
+        let mut from_account = Account::new(100, 2, &system_program::id());
+        let mut keyed_accounts = [KeyedAccount::new(&from, true, &mut from_account)];
+        assign_account_to_program(&mut keyed_accounts, &new_program_owner).unwrap();
+        assert_eq!(from_account.owner, new_program_owner);
+
+        let mut from_account = Account::new(100, 2, &system_program::id());
+        from_account.data[1] = 1;
+        let mut keyed_accounts = [KeyedAccount::new(&from, true, &mut from_account)];
+        assert_eq!(
+            assign_account_to_program(&mut keyed_accounts, &new_program_owner),
+            Err(SystemError::AccountDataNotZeroed.into())
+        );
+
+        // The new owner must be the signer
+        let other = Pubkey::new_rand();
+        let mut other_account = Account::new(1, 0, &Pubkey::default());
+        let mut keyed_accounts = [
+            KeyedAccount::new(&from, true, &mut from_account),
+            KeyedAccount::new(&other, true, &mut other_account),
+        ];
+        assert_eq!(
+            assign_account_to_program(&mut keyed_accounts, &new_program_owner),
+            Err(SystemError::AccountDataNotZeroed.into())
+        );
+
+        let mut owner_account = Account::new(1, 0, &Pubkey::default());
+        let mut keyed_accounts = [
+            KeyedAccount::new(&from, true, &mut from_account),
+            KeyedAccount::new(&new_program_owner, false, &mut owner_account),
+        ];
+        assert_eq!(
+            assign_account_to_program(&mut keyed_accounts, &new_program_owner),
+            Err(SystemError::AccountDataNotZeroed.into())
+        );
+
+        let mut keyed_accounts = [
+            KeyedAccount::new(&from, true, &mut from_account),
+            KeyedAccount::new_credit_only(&new_program_owner, true, &mut owner_account),
+        ];
+        assign_account_to_program(&mut keyed_accounts, &new_program_owner).unwrap();
+        assert_eq!(from_account.owner, new_program_owner);
+    }
+
+    #[test]
+    fn test_assign_sysvar_invalid_id() {
+        let from = Pubkey::new_rand();
+        let mut from_account = Account::new(100, 0, &system_program::id());
+        let mut keyed_accounts = [KeyedAccount::new(&from, true, &mut from_account)];
+        assert_eq!(
+            assign_account_to_program(&mut keyed_accounts, &sysvar::id()),
+            Err(SystemError::InvalidProgramId.into())
+        );
+        assert_eq!(from_account.owner, system_program::id());
+    }
+
     #[test]
     fn test_transfer_lamports() {
         let from = Pubkey::new_rand();
@@ -325,9 +583,271 @@ mod tests {
             KeyedAccount::new_credit_only(&to, false, &mut to_account),
         ];
         let result = transfer_lamports(&mut keyed_accounts, 100);
-        assert_eq!(result, Err(SystemError::ResultWithNegativeLamports));
+        assert_eq!(result, Err(SystemError::ResultWithNegativeLamports.into()));
         assert_eq!(from_account.lamports, 50);
         assert_eq!(to_account.lamports, 51);
+
+        // Attempt to overflow to_account's balance
+        let mut full_account = Account::new(std::u64::MAX, 0, &Pubkey::new(&[3; 32]));
+        keyed_accounts = [
+            KeyedAccount::new(&from, true, &mut from_account),
+            KeyedAccount::new_credit_only(&to, false, &mut full_account),
+        ];
+        let result = transfer_lamports(&mut keyed_accounts, 1);
+        assert_eq!(result, Err(InstructionError::ArithmeticOverflow));
+        assert_eq!(full_account.lamports, std::u64::MAX);
+    }
+
+    #[test]
+    fn test_allocate_with_seed() {
+        let new_program_owner = Pubkey::new(&[9; 32]);
+        let base = Pubkey::new_rand();
+        let mut base_account = Account::new(100, 0, &system_program::id());
+        let address = Pubkey::create_with_seed(&base, "seed", &new_program_owner).unwrap();
+        let mut account = Account::new(50, 0, &system_program::id());
+
+        let instruction = SystemInstruction::AllocateWithSeed {
+            base,
+            seed: "seed".to_string(),
+            space: 2,
+            program_id: new_program_owner,
+        };
+        let data = serialize(&instruction).unwrap();
+
+        // The base must sign
+        let mut keyed_accounts = [
+            KeyedAccount::new(&address, false, &mut account),
+            KeyedAccount::new_credit_only(&base, false, &mut base_account),
+        ];
+        assert_eq!(
+            process_instruction(&system_program::id(), &mut keyed_accounts, &data),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+
+        // The address must be derived from the base and seed
+        let other = Pubkey::new_rand();
+        let mut keyed_accounts = [
+            KeyedAccount::new(&other, false, &mut account),
+            KeyedAccount::new_credit_only(&base, true, &mut base_account),
+        ];
+        assert_eq!(
+            process_instruction(&system_program::id(), &mut keyed_accounts, &data),
+            Err(SystemError::AddressWithSeedMismatch.into())
+        );
+
+        let mut keyed_accounts = [
+            KeyedAccount::new(&address, false, &mut account),
+            KeyedAccount::new_credit_only(&base, true, &mut base_account),
+        ];
+        process_instruction(&system_program::id(), &mut keyed_accounts, &data).unwrap();
+        assert_eq!(account.owner, new_program_owner);
+        assert_eq!(account.data, vec![0, 0]);
+        assert_eq!(account.lamports, 50);
+
+        // Allocating again fails, now that the account is in use
+        let mut keyed_accounts = [
+            KeyedAccount::new(&address, false, &mut account),
+            KeyedAccount::new_credit_only(&base, true, &mut base_account),
+        ];
+        assert_eq!(
+            process_instruction(&system_program::id(), &mut keyed_accounts, &data),
+            Err(SystemError::AccountAlreadyInUse.into())
+        );
+    }
+
+    #[test]
+    fn test_assign_with_seed() {
+        let new_program_owner = Pubkey::new(&[9; 32]);
+        let base = Pubkey::new_rand();
+        let mut base_account = Account::new(100, 0, &system_program::id());
+        let address = Pubkey::create_with_seed(&base, "seed", &new_program_owner).unwrap();
+        let mut account = Account::new(50, 0, &system_program::id());
+
+        let instruction = SystemInstruction::AssignWithSeed {
+            base,
+            seed: "seed".to_string(),
+            program_id: new_program_owner,
+        };
+        let data = serialize(&instruction).unwrap();
+        let mut keyed_accounts = [
+            KeyedAccount::new(&address, false, &mut account),
+            KeyedAccount::new_credit_only(&base, true, &mut base_account),
+        ];
+        process_instruction(&system_program::id(), &mut keyed_accounts, &data).unwrap();
+        assert_eq!(account.owner, new_program_owner);
+
+        // The seed must match the address
+        let instruction = SystemInstruction::AssignWithSeed {
+            base,
+            seed: "other seed".to_string(),
+            program_id: new_program_owner,
+        };
+        let data = serialize(&instruction).unwrap();
+        let mut keyed_accounts = [
+            KeyedAccount::new(&address, false, &mut account),
+            KeyedAccount::new_credit_only(&base, true, &mut base_account),
+        ];
+        assert_eq!(
+            process_instruction(&system_program::id(), &mut keyed_accounts, &data),
+            Err(SystemError::AddressWithSeedMismatch.into())
+        );
+    }
+
+    #[test]
+    fn test_transfer_lamports_with_seed() {
+        let base = Pubkey::new_rand();
+        let mut base_account = Account::new(100, 0, &system_program::id());
+        let from = Pubkey::create_with_seed(&base, "payout", &system_program::id()).unwrap();
+        let mut from_account = Account::new(100, 0, &system_program::id());
+        let to = Pubkey::new_rand();
+        let mut to_account = Account::new(1, 0, &system_program::id());
+
+        let instruction = SystemInstruction::TransferWithSeed {
+            lamports: 50,
+            from_seed: "payout".to_string(),
+            from_owner: system_program::id(),
+        };
+        let data = serialize(&instruction).unwrap();
+        let mut keyed_accounts = [
+            KeyedAccount::new(&from, false, &mut from_account),
+            KeyedAccount::new_credit_only(&base, true, &mut base_account),
+            KeyedAccount::new_credit_only(&to, false, &mut to_account),
+        ];
+        process_instruction(&system_program::id(), &mut keyed_accounts, &data).unwrap();
+        assert_eq!(from_account.lamports, 50);
+        assert_eq!(to_account.lamports, 51);
+        assert_eq!(base_account.lamports, 100);
+
+        // Another base can't sign for the address
+        let other_base = Pubkey::new_rand();
+        let mut keyed_accounts = [
+            KeyedAccount::new(&from, false, &mut from_account),
+            KeyedAccount::new_credit_only(&other_base, true, &mut base_account),
+            KeyedAccount::new_credit_only(&to, false, &mut to_account),
+        ];
+        assert_eq!(
+            process_instruction(&system_program::id(), &mut keyed_accounts, &data),
+            Err(SystemError::AddressWithSeedMismatch.into())
+        );
+
+        // Attempt to move more lamports than remaining in from_account
+        let instruction = SystemInstruction::TransferWithSeed {
+            lamports: 51,
+            from_seed: "payout".to_string(),
+            from_owner: system_program::id(),
+        };
+        let data = serialize(&instruction).unwrap();
+        let mut keyed_accounts = [
+            KeyedAccount::new(&from, false, &mut from_account),
+            KeyedAccount::new_credit_only(&base, true, &mut base_account),
+            KeyedAccount::new_credit_only(&to, false, &mut to_account),
+        ];
+        assert_eq!(
+            process_instruction(&system_program::id(), &mut keyed_accounts, &data),
+            Err(SystemError::ResultWithNegativeLamports.into())
+        );
+        assert_eq!(from_account.lamports, 50);
+        assert_eq!(to_account.lamports, 51);
+    }
+
+    #[test]
+    fn test_transfer_lamports_to_many() {
+        let from = Pubkey::new_rand();
+        let mut from_account = Account::new(100, 0, &system_program::id());
+        let to0 = Pubkey::new_rand();
+        let mut to0_account = Account::new(1, 0, &system_program::id());
+        let to1 = Pubkey::new_rand();
+        let mut to1_account = Account::new(1, 0, &system_program::id());
+
+        let mut keyed_accounts = [
+            KeyedAccount::new(&from, true, &mut from_account),
+            KeyedAccount::new_credit_only(&to0, false, &mut to0_account),
+            KeyedAccount::new_credit_only(&to1, false, &mut to1_account),
+        ];
+        transfer_lamports_to_many(&mut keyed_accounts, &[10, 20]).unwrap();
+
+        // Nothing moves if the total is more than the source has
+        assert_eq!(
+            transfer_lamports_to_many(&mut keyed_accounts, &[10, 61]),
+            Err(SystemError::ResultWithNegativeLamports.into())
+        );
+        assert_eq!(
+            transfer_lamports_to_many(&mut keyed_accounts, &[1, std::u64::MAX]),
+            Err(InstructionError::ArithmeticOverflow)
+        );
+        assert_eq!(
+            transfer_lamports_to_many(&mut keyed_accounts, &[10]),
+            Err(InstructionError::InvalidArgument)
+        );
+        assert_eq!(from_account.lamports, 70);
+        assert_eq!(to0_account.lamports, 11);
+        assert_eq!(to1_account.lamports, 21);
+    }
+
+    #[test]
+    fn test_system_transfer_to_many() {
+        let (genesis_block, alice_keypair) = create_genesis_block(100);
+        let alice_pubkey = alice_keypair.pubkey();
+        let bank = Bank::new(&genesis_block);
+        let bank_client = BankClient::new(bank);
+        let bob_pubkey = Pubkey::new_rand();
+        let carol_pubkey = Pubkey::new_rand();
+
+        let instruction = system_instruction::transfer_to_many(
+            &alice_pubkey,
+            &[(bob_pubkey, 10), (carol_pubkey, 20)],
+        );
+        bank_client
+            .send_instruction(&alice_keypair, instruction)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&bob_pubkey).unwrap(), 10);
+        assert_eq!(bank_client.get_balance(&carol_pubkey).unwrap(), 20);
+
+        let instruction = system_instruction::transfer_to_many(
+            &alice_pubkey,
+            &[(bob_pubkey, 10), (carol_pubkey, 100)],
+        );
+        assert_eq!(
+            bank_client
+                .send_instruction(&alice_keypair, instruction)
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::CustomError(SystemError::ResultWithNegativeLamports as u32)
+            )
+        );
+        assert_eq!(bank_client.get_balance(&bob_pubkey).unwrap(), 10);
+        assert_eq!(bank_client.get_balance(&alice_pubkey).unwrap(), 70);
+    }
+
+    #[test]
+    fn test_system_transfer_with_seed() {
+        let (genesis_block, alice_keypair) = create_genesis_block(100);
+        let alice_pubkey = alice_keypair.pubkey();
+        let bank = Bank::new(&genesis_block);
+        let bank_client = BankClient::new(bank);
+        let from_pubkey =
+            Pubkey::create_with_seed(&alice_pubkey, "payout", &system_program::id()).unwrap();
+        let bob_pubkey = Pubkey::new_rand();
+        bank_client
+            .transfer(50, &alice_keypair, &from_pubkey)
+            .unwrap();
+
+        let instruction = system_instruction::transfer_with_seed(
+            &from_pubkey,
+            &alice_pubkey,
+            "payout",
+            &system_program::id(),
+            &bob_pubkey,
+            20,
+        );
+        bank_client
+            .send_instruction(&alice_keypair, instruction)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&from_pubkey).unwrap(), 30);
+        assert_eq!(bank_client.get_balance(&bob_pubkey).unwrap(), 20);
+        assert_eq!(bank_client.get_balance(&alice_pubkey).unwrap(), 50);
     }
 
     #[test]
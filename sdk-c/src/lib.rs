@@ -4,7 +4,9 @@ use rand_chacha::ChaChaRng;
 use rand_core::SeedableRng;
 use solana_ed25519_dalek::{SignatureError, KEYPAIR_LENGTH, PUBLIC_KEY_LENGTH};
 use solana_sdk::hash::Hash;
+use solana_sdk::instruction::AccountMeta as AccountMetaNative;
 use solana_sdk::instruction::CompiledInstruction as CompiledInstructionNative;
+use solana_sdk::instruction::Instruction as InstructionNative;
 use solana_sdk::message::Message as MessageNative;
 use solana_sdk::message::MessageHeader as MessageHeaderNative;
 use solana_sdk::pubkey::Pubkey;
@@ -12,11 +14,13 @@ use solana_sdk::signature::Signature as SignatureNative;
 use solana_sdk::signature::{Keypair as KeypairNative, KeypairUtil};
 use solana_sdk::transaction::Transaction as TransactionNative;
 use std::convert::TryInto;
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 use std::vec::Vec;
 use std::{fmt, mem, ptr, slice};
 
+pub mod rpc_client;
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct Transaction {
@@ -157,6 +161,69 @@ impl CompiledInstruction {
     }
 }
 
+/// Account metadata for an `Instruction`, built by C code
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct AccountMeta {
+    /// An account's public key
+    pub pubkey: Pubkey,
+
+    /// True if an Instruction requires a Transaction signature matching `pubkey`.
+    pub is_signer: bool,
+
+    /// True if the `pubkey` can be loaded as a credit-debit account.
+    pub is_debitable: bool,
+}
+
+impl AccountMeta {
+    pub fn new_native(&self) -> AccountMetaNative {
+        AccountMetaNative {
+            pubkey: self.pubkey,
+            is_signer: self.is_signer,
+            is_debitable: self.is_debitable,
+        }
+    }
+}
+
+/// An instruction built by C code. Unlike `CVec`s, the arrays it points to are owned by the caller
+/// and are only read.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Instruction {
+    /// Pubkey of the instruction processor that executes this instruction
+    pub program_id: Pubkey,
+
+    /// Metadata for what accounts should be passed to the instruction processor
+    pub accounts: *const AccountMeta,
+    pub accounts_len: size_t,
+
+    /// Opaque data passed to the instruction processor
+    pub data: *const u8,
+    pub data_len: size_t,
+}
+
+impl Instruction {
+    pub unsafe fn new_native(&self) -> InstructionNative {
+        InstructionNative {
+            program_id: self.program_id,
+            accounts: slice_from_raw_parts(self.accounts, self.accounts_len)
+                .iter()
+                .map(AccountMeta::new_native)
+                .collect(),
+            data: slice_from_raw_parts(self.data, self.data_len).to_vec(),
+        }
+    }
+}
+
+// Like `slice::from_raw_parts()`, but allows a `NULL` pointer for an empty slice
+unsafe fn slice_from_raw_parts<'a, T>(data: *const T, len: size_t) -> &'a [T] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data, len)
+    }
+}
+
 #[repr(C)]
 #[derive(Default, Debug, Clone)]
 pub struct MessageHeader {
@@ -311,6 +378,44 @@ pub unsafe extern "C" fn free_c_string(s: *mut c_char) {
     CString::from_raw(s);
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn free_keypair(k: *mut Keypair) {
+    Box::from_raw(k);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn free_pubkey(p: *mut Pubkey) {
+    Box::from_raw(p);
+}
+
+/// Free a byte array returned by `serialize_transaction()` or `base58_decode()`
+#[no_mangle]
+pub unsafe extern "C" fn free_bytes(bytes: *mut u8, len: size_t) {
+    Vec::from_raw_parts(bytes, len, len);
+}
+
+/// Build a `Message` from `num_instructions` `Instruction`s, with `payer` as the first signer. A
+/// `NULL` `payer` leaves the first signer of the first instruction to pay. The recent blockhash is
+/// set when the transaction is signed.
+///
+/// # Undefined Behavior
+///
+/// Causes UB if `instructions` is not a pointer to an array of `Instruction`s of length
+/// `num_instructions`, or if any `Instruction` doesn't point to valid arrays
+#[no_mangle]
+pub unsafe extern "C" fn new_message(
+    instructions: *const Instruction,
+    num_instructions: size_t,
+    payer: *const Pubkey,
+) -> *mut Message {
+    let instructions = slice_from_raw_parts(instructions, num_instructions)
+        .iter()
+        .map(|i| i.new_native())
+        .collect();
+    let message = MessageNative::new_with_payer(instructions, payer.as_ref());
+    Box::into_raw(Box::new(Message::from_native(message)))
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn new_unsigned_transaction(message: *mut Message) -> *mut Transaction {
     let message = Box::from_raw(message);
@@ -450,6 +555,101 @@ pub unsafe extern "C" fn transaction_partial_sign(
     0
 }
 
+/// Sign `len` bytes at `message` with `keypair`, and write the ed25519 signature to `signature`.
+/// Returns `0` for success, other for failure.
+///
+/// # Undefined Behavior
+///
+/// Causes UB if any of the pointers is `NULL`, or if `message` does not point to a valid array of
+/// length `len`
+#[no_mangle]
+pub unsafe extern "C" fn sign_message(
+    keypair: *const Keypair,
+    message: *const u8,
+    len: size_t,
+    signature: *mut Signature,
+) -> c_int {
+    let keypair = if let Ok(k) = (*keypair).new_native() {
+        k
+    } else {
+        return 1;
+    };
+    let message = slice_from_raw_parts(message, len);
+    *signature = Signature::from_native(keypair.sign_message(message));
+    0
+}
+
+/// Verify an ed25519 `signature` of `len` bytes at `message` by `pubkey`. Returns `0` if the
+/// signature is valid, other if it is not.
+///
+/// # Undefined Behavior
+///
+/// Causes UB if any of the pointers is `NULL`, or if `message` does not point to a valid array of
+/// length `len`
+#[no_mangle]
+pub unsafe extern "C" fn verify_signature(
+    pubkey: *const Pubkey,
+    message: *const u8,
+    len: size_t,
+    signature: *const Signature,
+) -> c_int {
+    let message = slice_from_raw_parts(message, len);
+    if (*signature)
+        .new_native()
+        .verify((*pubkey).as_ref(), message)
+    {
+        0
+    } else {
+        1
+    }
+}
+
+/// Encode `len` bytes at `bytes` as a base58 c-string. The returned c-string must be freed with
+/// `free_c_string()`. Returns `NULL` if the conversion fails.
+///
+/// # Undefined Behavior
+///
+/// Causes UB if `bytes` does not point to a valid array of length `len`
+#[no_mangle]
+pub unsafe extern "C" fn base58_encode(bytes: *const u8, len: size_t) -> *mut c_char {
+    let bytes = slice_from_raw_parts(bytes, len);
+    if let Ok(s) = CString::new(bs58::encode(bytes).into_string()) {
+        s.into_raw()
+    } else {
+        ptr::null_mut()
+    }
+}
+
+/// Decode the base58 c-string `s` and save a pointer to the resulting byte array to `decoded`, and
+/// its length to `len`. The byte array must be freed with `free_bytes()`. Returns `0` for success,
+/// other for failure.
+///
+/// # Undefined Behavior
+///
+/// Causes UB if any of the pointers is `NULL`, or if `s` is not a nul-terminated c-string
+#[no_mangle]
+pub unsafe extern "C" fn base58_decode(
+    s: *const c_char,
+    decoded: *mut *mut u8,
+    len: *mut size_t,
+) -> c_int {
+    let s = if let Ok(s) = CStr::from_ptr(s).to_str() {
+        s
+    } else {
+        return 1;
+    };
+    let mut bytes = if let Ok(b) = bs58::decode(s).into_vec() {
+        b
+    } else {
+        return 2;
+    };
+    bytes.shrink_to_fit();
+    *decoded = bytes.as_mut_ptr();
+    *len = bytes.len();
+    mem::forget(bytes);
+    0
+}
+
 /// Get the printable c-string of a Pubkey. The returned c-string must be freed with `free_c_string()`
 /// Returns `NULL` if the conversion fails.
 ///
@@ -473,6 +673,7 @@ mod tests {
     use rand_chacha::ChaChaRng;
     use rand_core::SeedableRng;
     use solana_sdk::signature::{Keypair as KeypairNative, KeypairUtil};
+    use solana_sdk::system_instruction;
     use solana_sdk::system_transaction;
 
     #[test]
@@ -571,4 +772,84 @@ mod tests {
         tx_native.partial_sign(&[&key_native], blockhash);
         assert_eq!(tx_native, tx2);
     }
+
+    #[test]
+    fn test_new_message() {
+        let from = Pubkey::new_rand();
+        let to = Pubkey::new_rand();
+        let ix_native = system_instruction::transfer(&from, &to, 42);
+        let accounts: Vec<_> = ix_native
+            .accounts
+            .iter()
+            .map(|meta| AccountMeta {
+                pubkey: meta.pubkey,
+                is_signer: meta.is_signer,
+                is_debitable: meta.is_debitable,
+            })
+            .collect();
+        let ix = Instruction {
+            program_id: ix_native.program_id,
+            accounts: accounts.as_ptr(),
+            accounts_len: accounts.len(),
+            data: ix_native.data.as_ptr(),
+            data_len: ix_native.data.len(),
+        };
+        let message;
+        unsafe {
+            message = Box::from_raw(new_message(&ix, 1, &from)).into_native();
+        }
+        assert_eq!(
+            message,
+            MessageNative::new_with_payer(vec![ix_native], Some(&from))
+        );
+    }
+
+    #[test]
+    fn test_sign_and_verify_message() {
+        let key_native = KeypairNative::new();
+        let key = Keypair::from_native(&key_native);
+        let pubkey = key_native.pubkey();
+        let message = b"hello";
+        let mut signature = Signature([0u8; 64]);
+        unsafe {
+            assert_eq!(
+                0,
+                sign_message(&key, message.as_ptr(), message.len(), &mut signature)
+            );
+            assert_eq!(
+                0,
+                verify_signature(&pubkey, message.as_ptr(), message.len(), &signature)
+            );
+            assert_ne!(
+                0,
+                verify_signature(&pubkey, message.as_ptr(), 3, &signature)
+            );
+        }
+        assert_eq!(
+            signature.new_native(),
+            key_native.sign_message(&message[..])
+        );
+    }
+
+    #[test]
+    fn test_base58() {
+        let pubkey = Pubkey::new_rand();
+        let bytes = pubkey.as_ref();
+        let mut decoded: *mut u8 = ptr::null_mut();
+        let mut len: size_t = 0;
+        unsafe {
+            let encoded = base58_encode(bytes.as_ptr(), bytes.len());
+            assert_eq!(
+                CStr::from_ptr(encoded).to_str().unwrap(),
+                pubkey.to_string()
+            );
+            assert_eq!(0, base58_decode(encoded, &mut decoded, &mut len));
+            assert_eq!(slice::from_raw_parts(decoded, len), bytes);
+            free_c_string(encoded);
+            free_bytes(decoded, len);
+
+            let bad = CString::new("0OIl").unwrap();
+            assert_ne!(0, base58_decode(bad.as_ptr(), &mut decoded, &mut len));
+        }
+    }
 }
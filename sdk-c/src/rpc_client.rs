@@ -0,0 +1,280 @@
+//! Blocking JSON RPC calls to a Solana node, for C code that doesn't want to speak JSON RPC itself
+
+use crate::Signature;
+use bincode::deserialize;
+use libc::{c_int, size_t};
+use solana_client::rpc_client::RpcClient as RpcClientNative;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature as SignatureNative;
+use solana_sdk::transaction::Transaction as TransactionNative;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::{ptr, slice};
+
+/// A client connected to a node's JSON RPC port. Opaque to C code.
+pub struct RpcClient(RpcClientNative);
+
+// Panics must not unwind into C code
+fn catch_panic<T, F: FnOnce() -> T>(f: F) -> Option<T> {
+    panic::catch_unwind(AssertUnwindSafe(f)).ok()
+}
+
+/// Create a client for the node at `url`, for example "http://127.0.0.1:8899". The client must be
+/// freed with `free_rpc_client()`. Returns `NULL` if `url` is not valid UTF-8.
+///
+/// # Undefined Behavior
+///
+/// Causes UB if `url` is `NULL` or is not a nul-terminated c-string
+#[no_mangle]
+pub unsafe extern "C" fn new_rpc_client(url: *const c_char) -> *mut RpcClient {
+    let url = if let Ok(url) = CStr::from_ptr(url).to_str() {
+        url.to_string()
+    } else {
+        return ptr::null_mut();
+    };
+    Box::into_raw(Box::new(RpcClient(RpcClientNative::new(url))))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn free_rpc_client(client: *mut RpcClient) {
+    Box::from_raw(client);
+}
+
+/// Get the balance of `pubkey` in lamports and save it to `lamports`. Returns `0` for success,
+/// other for failure.
+///
+/// # Undefined Behavior
+///
+/// Causes UB if any of the pointers is `NULL`, or if `client` is not a valid `RpcClient`
+#[no_mangle]
+pub unsafe extern "C" fn rpc_client_get_balance(
+    client: *const RpcClient,
+    pubkey: *const Pubkey,
+    lamports: *mut u64,
+) -> c_int {
+    match catch_panic(|| (*client).0.retry_get_balance(&*pubkey, 0)) {
+        Some(Ok(Some(balance))) => {
+            *lamports = balance;
+            0
+        }
+        _ => 1,
+    }
+}
+
+/// Request an airdrop of `lamports` to `pubkey`, and block until the airdrop is confirmed. Saves
+/// the airdrop transaction's signature to `signature`. Returns `0` for success, other for failure.
+///
+/// # Undefined Behavior
+///
+/// Causes UB if any of the pointers is `NULL`, or if `client` is not a valid `RpcClient`
+#[no_mangle]
+pub unsafe extern "C" fn rpc_client_request_airdrop(
+    client: *const RpcClient,
+    pubkey: *const Pubkey,
+    lamports: u64,
+    signature: *mut Signature,
+) -> c_int {
+    match catch_panic(|| (*client).0.request_airdrop(&*pubkey, lamports)) {
+        Some(Ok(airdrop_signature)) => {
+            *signature = Signature::from_native(airdrop_signature);
+            0
+        }
+        _ => 1,
+    }
+}
+
+/// Send a signed, serialized `Transaction` without waiting for it to be confirmed. Saves the
+/// transaction's signature to `signature`. Returns `0` for success, `1` if the bytes are not a
+/// `Transaction`, and other for failure.
+///
+/// # Undefined Behavior
+///
+/// Causes UB if any of the pointers is `NULL`, if `client` is not a valid `RpcClient`, or if
+/// `bytes` does not point to a valid array of length `len`
+#[no_mangle]
+pub unsafe extern "C" fn rpc_client_send_transaction(
+    client: *const RpcClient,
+    bytes: *const u8,
+    len: size_t,
+    signature: *mut Signature,
+) -> c_int {
+    let tx = if let Ok(tx) = deserialize::<TransactionNative>(slice::from_raw_parts(bytes, len)) {
+        tx
+    } else {
+        return 1;
+    };
+    let sent_signature = catch_panic(|| (*client).0.send_transaction(&tx))
+        .and_then(Result::ok)
+        .and_then(|signature| signature.parse::<SignatureNative>().ok());
+    if let Some(sent_signature) = sent_signature {
+        *signature = Signature::from_native(sent_signature);
+        0
+    } else {
+        2
+    }
+}
+
+/// Block until the transaction with `signature` is confirmed, or until the RPC client gives up.
+/// Returns `0` if the transaction succeeded, `1` if it was not confirmed, `2` if it was confirmed
+/// but failed, and other for failure.
+///
+/// # Undefined Behavior
+///
+/// Causes UB if any of the pointers is `NULL`, or if `client` is not a valid `RpcClient`
+#[no_mangle]
+pub unsafe extern "C" fn rpc_client_confirm_signature(
+    client: *const RpcClient,
+    signature: *const Signature,
+) -> c_int {
+    let client = &(*client).0;
+    let signature = (*signature).new_native();
+    match catch_panic(|| client.poll_for_signature(&signature)) {
+        Some(Ok(())) => (),
+        Some(Err(_)) => return 1,
+        None => return 3,
+    }
+    match catch_panic(|| client.get_signature_status(&signature.to_string())) {
+        Some(Ok(Some(Ok(())))) => 0,
+        Some(Ok(Some(Err(_)))) => 2,
+        Some(Ok(None)) => 1,
+        _ => 3,
+    }
+}
+
+/// Send a signed, serialized `Transaction` and block until it is confirmed. Saves the
+/// transaction's signature to `signature`. Returns `0` if the transaction succeeded, and
+/// otherwise the failure code of `rpc_client_send_transaction()` or
+/// `rpc_client_confirm_signature()`, plus 10 for the latter.
+///
+/// # Undefined Behavior
+///
+/// Causes UB if any of the pointers is `NULL`, if `client` is not a valid `RpcClient`, or if
+/// `bytes` does not point to a valid array of length `len`
+#[no_mangle]
+pub unsafe extern "C" fn rpc_client_send_and_confirm_transaction(
+    client: *const RpcClient,
+    bytes: *const u8,
+    len: size_t,
+    signature: *mut Signature,
+) -> c_int {
+    let result = rpc_client_send_transaction(client, bytes, len, signature);
+    if result != 0 {
+        return result;
+    }
+    match rpc_client_confirm_signature(client, signature) {
+        0 => 0,
+        result => result + 10,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bincode::serialize;
+    use solana_client::mock_rpc_client_request::SIGNATURE;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::signature::{Keypair, KeypairUtil};
+    use solana_sdk::system_transaction;
+    use std::ffi::CString;
+
+    fn new_mock_client(url: &str) -> RpcClient {
+        RpcClient(RpcClientNative::new_mock(url.to_string()))
+    }
+
+    fn serialized_transaction() -> Vec<u8> {
+        let tx =
+            system_transaction::transfer(&Keypair::new(), &Pubkey::new_rand(), 50, Hash::default());
+        serialize(&tx).unwrap()
+    }
+
+    #[test]
+    fn test_new_rpc_client() {
+        let url = CString::new("http://127.0.0.1:8899").unwrap();
+        unsafe {
+            let client = new_rpc_client(url.as_ptr());
+            assert_ne!(client, ptr::null_mut());
+            free_rpc_client(client);
+        }
+    }
+
+    #[test]
+    fn test_rpc_client_get_balance() {
+        let client = new_mock_client("succeeds");
+        let mut lamports = 0;
+        unsafe {
+            assert_eq!(
+                0,
+                rpc_client_get_balance(&client, &Pubkey::new_rand(), &mut lamports)
+            );
+        }
+        assert_eq!(lamports, 50);
+
+        let client = new_mock_client("fails");
+        unsafe {
+            assert_ne!(
+                0,
+                rpc_client_get_balance(&client, &Pubkey::new_rand(), &mut lamports)
+            );
+        }
+    }
+
+    #[test]
+    fn test_rpc_client_request_airdrop() {
+        let client = new_mock_client("succeeds");
+        let mut signature = Signature([0; 64]);
+        unsafe {
+            assert_eq!(
+                0,
+                rpc_client_request_airdrop(&client, &Pubkey::new_rand(), 50, &mut signature)
+            );
+        }
+        assert_eq!(signature.to_string(), SIGNATURE);
+    }
+
+    #[test]
+    fn test_rpc_client_send_and_confirm_transaction() {
+        let tx = serialized_transaction();
+        let client = new_mock_client("succeeds");
+        let mut signature = Signature([0; 64]);
+        unsafe {
+            assert_eq!(
+                0,
+                rpc_client_send_and_confirm_transaction(
+                    &client,
+                    tx.as_ptr(),
+                    tx.len(),
+                    &mut signature
+                )
+            );
+        }
+        assert_eq!(signature.to_string(), SIGNATURE);
+
+        // The transaction was confirmed but failed
+        let client = new_mock_client("account_in_use");
+        unsafe {
+            assert_eq!(
+                12,
+                rpc_client_send_and_confirm_transaction(
+                    &client,
+                    tx.as_ptr(),
+                    tx.len(),
+                    &mut signature
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_rpc_client_send_transaction_bad() {
+        let client = new_mock_client("succeeds");
+        let bytes = vec![0u8; 3];
+        let mut signature = Signature([0; 64]);
+        unsafe {
+            assert_eq!(
+                1,
+                rpc_client_send_transaction(&client, bytes.as_ptr(), bytes.len(), &mut signature)
+            );
+        }
+    }
+}
@@ -69,6 +69,64 @@ void sol_log(const char *);
  */
 void sol_log_64(uint64_t, uint64_t, uint64_t, uint64_t, uint64_t);
 
+/**
+ * Size of the result written by the hashing helpers
+ */
+#define SIZE_HASH 32
+
+/**
+ * Helper function that computes the SHA-256 hash of `len` bytes at `data`
+ * and writes the SIZE_HASH byte result to `result`
+ */
+void sol_sha256_(const uint8_t *data, uint64_t len, uint8_t *result);
+#define sol_sha256 sol_sha256_
+
+/**
+ * Helper function that computes the Keccak-256 hash of `len` bytes at `data`
+ * and writes the SIZE_HASH byte result to `result`
+ */
+void sol_keccak256_(const uint8_t *data, uint64_t len, uint8_t *result);
+#define sol_keccak256 sol_keccak256_
+
+/**
+ * Largest number of bytes a program may set as return data
+ */
+#define MAX_RETURN_DATA 1024
+
+/**
+ * Helper function that sets the return data of the current transaction,
+ * replacing any data set earlier.  `len` must be at most MAX_RETURN_DATA
+ */
+void sol_set_return_data_(const uint8_t *data, uint64_t len);
+#define sol_set_return_data sol_set_return_data_
+
+/**
+ * Helper function that copies up to `len` bytes of the current return data
+ * to `data` and writes the SIZE_PUBKEY byte id of the program that set it to
+ * `program_id`
+ *
+ * @return The full length of the return data, or 0 if there is none
+ */
+uint64_t sol_get_return_data_(uint8_t *data, uint64_t len, uint8_t *program_id);
+#define sol_get_return_data sol_get_return_data_
+
+/**
+ * Returned by sol_request_heap when the request could not be satisfied
+ */
+#define HEAP_REQUEST_DENIED ((uint64_t)-1)
+
+/**
+ * Helper function that requests a program heap of `size` bytes in place of
+ * the loader's default 32KB heap size.  Heaps are capped at 256KB and every
+ * 32KB the heap grows by above the default is charged against the program's
+ * compute budget
+ *
+ * @return The granted heap size, or HEAP_REQUEST_DENIED if the request could
+ * not be satisfied
+ */
+uint64_t sol_request_heap_(uint64_t size);
+#define sol_request_heap sol_request_heap_
+
 
 /**
  * Prefix for all BPF functions
@@ -16,6 +16,21 @@ extern "C" {
     fn sol_alloc_free_(size: u64, ptr: u64) -> *mut u8;
 }
 
+/// Returned by `sol_request_heap_()` when the request could not be satisfied
+const HEAP_REQUEST_DENIED: u64 = core::u64::MAX;
+
+/// Requests a program heap of `size` bytes in place of the loader's default
+/// 32KB heap size.  Heaps are capped at 256KB and every 32KB the heap grows
+/// by above the default is charged against the program's compute budget.
+/// Must be called before the heap is larger than `size`, returns false if
+/// the loader could not grant the request
+pub fn sol_request_heap(size: usize) -> bool {
+    unsafe { sol_request_heap_(size as u64) != HEAP_REQUEST_DENIED }
+}
+extern "C" {
+    fn sol_request_heap_(size: u64) -> u64;
+}
+
 #[alloc_error_handler]
 fn my_alloc_error_handler(_: core::alloc::Layout) -> ! {
     sol_log("alloc_error_handler");
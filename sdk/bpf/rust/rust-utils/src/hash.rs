@@ -0,0 +1,32 @@
+//! @brief Solana Rust-based BPF program hashing helpers
+
+/// Length in bytes of a hash result
+pub const HASH_BYTES: usize = 32;
+
+/// Computes the SHA-256 hash of the given bytes
+///
+/// @param data - Bytes to hash
+pub fn sol_sha256(data: &[u8]) -> [u8; HASH_BYTES] {
+    let mut result = [0u8; HASH_BYTES];
+    unsafe {
+        sol_sha256_(data.as_ptr(), data.len() as u64, result.as_mut_ptr());
+    }
+    result
+}
+extern "C" {
+    fn sol_sha256_(data: *const u8, length: u64, result: *mut u8);
+}
+
+/// Computes the Keccak-256 hash of the given bytes
+///
+/// @param data - Bytes to hash
+pub fn sol_keccak256(data: &[u8]) -> [u8; HASH_BYTES] {
+    let mut result = [0u8; HASH_BYTES];
+    unsafe {
+        sol_keccak256_(data.as_ptr(), data.len() as u64, result.as_mut_ptr());
+    }
+    result
+}
+extern "C" {
+    fn sol_keccak256_(data: *const u8, length: u64, result: *mut u8);
+}
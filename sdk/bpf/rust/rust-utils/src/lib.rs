@@ -3,4 +3,7 @@
 #![no_std]
 
 pub mod entrypoint;
+pub mod hash;
 pub mod log;
+pub mod return_data;
+pub mod sysvar;
@@ -0,0 +1,42 @@
+//! @brief Solana Rust-based BPF program return data helpers
+
+use crate::entrypoint::SolPubkey;
+
+/// Largest number of bytes a program may set as return data
+pub const MAX_RETURN_DATA: usize = 1024;
+
+/// Sets the return data of the current transaction, replacing any data set earlier
+///
+/// @param data - Bytes to return, at most MAX_RETURN_DATA
+pub fn sol_set_return_data(data: &[u8]) {
+    unsafe {
+        sol_set_return_data_(data.as_ptr(), data.len() as u64);
+    }
+}
+extern "C" {
+    fn sol_set_return_data_(data: *const u8, length: u64);
+}
+
+/// Copies as much of the current return data as fits into `buffer`
+///
+/// @param buffer - Receives the return data
+/// @return The program that set the data and the data's full length, or None
+///         if there is no return data
+pub fn sol_get_return_data(buffer: &mut [u8]) -> Option<(SolPubkey, usize)> {
+    let mut program_id = [0u8; 32];
+    let length = unsafe {
+        sol_get_return_data_(
+            buffer.as_mut_ptr(),
+            buffer.len() as u64,
+            program_id.as_mut_ptr(),
+        )
+    };
+    if length == 0 {
+        None
+    } else {
+        Some((program_id, length as usize))
+    }
+}
+extern "C" {
+    fn sol_get_return_data_(data: *mut u8, length: u64, program_id: *mut u8) -> u64;
+}
@@ -0,0 +1,196 @@
+//! @brief Solana Rust-based BPF program sysvar accounts
+//!
+//! The runtime keeps these accounts up to date with the state of the
+//! cluster.  Pass them to a program like any other account and read them
+//! with the `from_keyed_account()` functions below.
+extern crate alloc;
+
+use crate::entrypoint::{SolKeyedAccount, SolPubkey};
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::mem::size_of;
+
+/// Length in bytes of a hash stored in a sysvar account
+pub const HASH_BYTES: usize = 32;
+
+/// Hash stored in a sysvar account
+pub type SolHash = [u8; HASH_BYTES];
+
+/// Reads the little-endian fields a sysvar account is serialized as
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.offset.checked_add(len)?;
+        let bytes = self.data.get(self.offset..end)?;
+        self.offset = end;
+        Some(bytes)
+    }
+
+    fn read_u64(&mut self) -> Option<u64> {
+        let bytes = self.read_bytes(size_of::<u64>())?;
+        Some(u64::from_le_bytes(bytes.try_into().ok()?))
+    }
+
+    fn read_f64(&mut self) -> Option<f64> {
+        self.read_u64().map(f64::from_bits)
+    }
+
+    fn read_hash(&mut self) -> Option<SolHash> {
+        let bytes = self.read_bytes(HASH_BYTES)?;
+        let mut hash = [0u8; HASH_BYTES];
+        hash.copy_from_slice(bytes);
+        Some(hash)
+    }
+
+    fn read_len(&mut self) -> Option<usize> {
+        self.read_u64().map(|len| len as usize)
+    }
+}
+
+/// Returns the account's data if `ka` is the sysvar account `id`
+fn sysvar_data<'a>(ka: &'a SolKeyedAccount, id: &SolPubkey) -> Option<Reader<'a>> {
+    if ka.key == id {
+        Some(Reader::new(ka.data))
+    } else {
+        None
+    }
+}
+
+pub mod clock {
+    use super::*;
+
+    /// "SysvarC1ock11111111111111111111111111111111"
+    pub const ID: SolPubkey = [
+        6, 167, 213, 23, 24, 199, 116, 201, 40, 86, 99, 152, 105, 29, 94, 182, 139, 94, 184, 163,
+        155, 75, 109, 92, 115, 85, 91, 33, 0, 0, 0, 0,
+    ];
+
+    /// The current slot and epoch
+    #[derive(Debug, Default, PartialEq)]
+    pub struct Clock {
+        pub slot: u64,
+        pub segment: u64,
+        pub epoch: u64,
+        pub stakers_epoch: u64,
+    }
+
+    /// Reads the clock, returns None if `ka` is not the clock account
+    pub fn from_keyed_account(ka: &SolKeyedAccount) -> Option<Clock> {
+        let mut reader = sysvar_data(ka, &ID)?;
+        Some(Clock {
+            slot: reader.read_u64()?,
+            segment: reader.read_u64()?,
+            epoch: reader.read_u64()?,
+            stakers_epoch: reader.read_u64()?,
+        })
+    }
+}
+
+pub mod epoch_schedule {
+    use super::*;
+
+    /// "SysvarEpochSchedu1e111111111111111111111111"
+    pub const ID: SolPubkey = [
+        6, 167, 213, 23, 24, 220, 63, 238, 2, 211, 228, 127, 1, 0, 248, 176, 84, 247, 148, 46, 96,
+        89, 30, 63, 80, 135, 25, 168, 5, 0, 0, 0,
+    ];
+
+    /// How the cluster divides slots into epochs
+    #[derive(Debug, Default, PartialEq)]
+    pub struct EpochSchedule {
+        pub slots_per_epoch: u64,
+        pub stakers_slot_offset: u64,
+        pub first_normal_epoch: u64,
+        pub first_normal_slot: u64,
+    }
+
+    /// Reads the epoch schedule, returns None if `ka` is not the epoch
+    /// schedule account
+    pub fn from_keyed_account(ka: &SolKeyedAccount) -> Option<EpochSchedule> {
+        let mut reader = sysvar_data(ka, &ID)?;
+        Some(EpochSchedule {
+            slots_per_epoch: reader.read_u64()?,
+            stakers_slot_offset: reader.read_u64()?,
+            first_normal_epoch: reader.read_u64()?,
+            first_normal_slot: reader.read_u64()?,
+        })
+    }
+}
+
+pub mod rent {
+    use super::*;
+
+    /// "SysvarRent111111111111111111111111111111111"
+    pub const ID: SolPubkey = [
+        6, 167, 213, 23, 25, 44, 92, 81, 33, 140, 201, 76, 61, 74, 241, 127, 88, 218, 238, 8, 155,
+        161, 253, 68, 227, 219, 217, 138, 0, 0, 0, 0,
+    ];
+
+    /// The cluster's rent configuration
+    #[derive(Debug, Default, PartialEq)]
+    pub struct Rent {
+        pub lamports_per_byte_year: u64,
+        pub exemption_threshold: f64,
+    }
+
+    /// Reads the rent configuration, returns None if `ka` is not the rent account
+    pub fn from_keyed_account(ka: &SolKeyedAccount) -> Option<Rent> {
+        let mut reader = sysvar_data(ka, &ID)?;
+        Some(Rent {
+            lamports_per_byte_year: reader.read_u64()?,
+            exemption_threshold: reader.read_f64()?,
+        })
+    }
+}
+
+pub mod slot_hashes {
+    use super::*;
+
+    /// "SysvarS1otHashes111111111111111111111111111"
+    pub const ID: SolPubkey = [
+        6, 167, 213, 23, 25, 47, 10, 175, 198, 242, 101, 227, 251, 119, 204, 122, 218, 130, 197,
+        41, 208, 190, 59, 19, 110, 45, 0, 85, 32, 0, 0, 0,
+    ];
+
+    /// Reads the hashes of recent slots, most recent first, returns None if
+    /// `ka` is not the slot hashes account
+    pub fn from_keyed_account(ka: &SolKeyedAccount) -> Option<Vec<(u64, SolHash)>> {
+        let mut reader = sysvar_data(ka, &ID)?;
+        let len = reader.read_len()?;
+        let mut slot_hashes = Vec::new();
+        for _ in 0..len {
+            slot_hashes.push((reader.read_u64()?, reader.read_hash()?));
+        }
+        Some(slot_hashes)
+    }
+}
+
+pub mod recent_blockhashes {
+    use super::*;
+
+    /// "SysvarRecentB1ockHashes11111111111111111111"
+    pub const ID: SolPubkey = [
+        6, 167, 213, 23, 25, 44, 86, 142, 224, 138, 132, 95, 115, 210, 151, 136, 207, 3, 92, 49,
+        69, 178, 26, 179, 68, 216, 6, 46, 169, 64, 0, 0,
+    ];
+
+    /// Reads the recent blockhashes, most recent first, returns None if `ka`
+    /// is not the recent blockhashes account
+    pub fn from_keyed_account(ka: &SolKeyedAccount) -> Option<Vec<SolHash>> {
+        let mut reader = sysvar_data(ka, &ID)?;
+        let len = reader.read_len()?;
+        let mut blockhashes = Vec::new();
+        for _ in 0..len {
+            blockhashes.push(reader.read_hash()?);
+        }
+        Some(blockhashes)
+    }
+}
@@ -1,3 +1,4 @@
+use crate::instruction::InstructionError;
 use crate::pubkey::Pubkey;
 use std::{cmp, fmt};
 
@@ -70,6 +71,24 @@ impl Account {
         }
         bincode::serialize_into(&mut self.data[..], state)
     }
+
+    /// Credit `lamports` to the account, failing rather than overflowing its balance
+    pub fn checked_add_lamports(&mut self, lamports: u64) -> Result<(), InstructionError> {
+        self.lamports = self
+            .lamports
+            .checked_add(lamports)
+            .ok_or(InstructionError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Debit `lamports` from the account, failing rather than underflowing its balance
+    pub fn checked_sub_lamports(&mut self, lamports: u64) -> Result<(), InstructionError> {
+        self.lamports = self
+            .lamports
+            .checked_sub(lamports)
+            .ok_or(InstructionError::ArithmeticOverflow)?;
+        Ok(())
+    }
 }
 
 pub type LamportCredit = u64;
@@ -160,3 +179,29 @@ pub fn create_keyed_credit_only_accounts(accounts: &mut [(Pubkey, Account)]) ->
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_lamports() {
+        let mut account = Account::new(std::u64::MAX - 1, 0, &Pubkey::default());
+        assert_eq!(account.checked_add_lamports(1), Ok(()));
+        assert_eq!(account.lamports, std::u64::MAX);
+        assert_eq!(
+            account.checked_add_lamports(1),
+            Err(InstructionError::ArithmeticOverflow)
+        );
+        assert_eq!(account.lamports, std::u64::MAX);
+
+        let mut account = Account::new(1, 0, &Pubkey::default());
+        assert_eq!(account.checked_sub_lamports(1), Ok(()));
+        assert_eq!(account.lamports, 0);
+        assert_eq!(
+            account.checked_sub_lamports(1),
+            Err(InstructionError::ArithmeticOverflow)
+        );
+        assert_eq!(account.lamports, 0);
+    }
+}
@@ -31,6 +31,32 @@ pub const DEFAULT_TARGET_SIGNATURES_PER_SLOT: usize =
     710_000 * DEFAULT_TICKS_PER_SLOT as usize / DEFAULT_NUM_TICKS_PER_SECOND as usize;
 pub const DEFAULT_BURN_PERCENT: u8 = 50;
 
+/// How far lamports_per_signature may move away from target_lamports_per_signature, and how
+/// quickly, all as percentages of target_lamports_per_signature
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeRateGovernor {
+    pub min_percent: u64,
+    pub max_percent: u64,
+    // How much lamports_per_signature changes from one slot to the next
+    pub adjustment_percent: u64,
+}
+
+/// TODO: Are these decent limits? Is this fee curve smooth enough or too smooth?
+pub const DEFAULT_MIN_PERCENT: u64 = 50;
+pub const DEFAULT_MAX_PERCENT: u64 = 1000;
+pub const DEFAULT_ADJUSTMENT_PERCENT: u64 = 5;
+
+impl Default for FeeRateGovernor {
+    fn default() -> Self {
+        Self {
+            min_percent: DEFAULT_MIN_PERCENT,
+            max_percent: DEFAULT_MAX_PERCENT,
+            adjustment_percent: DEFAULT_ADJUSTMENT_PERCENT,
+        }
+    }
+}
+
 impl Default for FeeCalculator {
     fn default() -> Self {
         FeeCalculator {
@@ -59,17 +85,30 @@ impl FeeCalculator {
     pub fn new_derived(
         base_fee_calculator: &FeeCalculator,
         latest_signatures_per_slot: usize,
+    ) -> Self {
+        Self::new_derived_with_governor(
+            base_fee_calculator,
+            latest_signatures_per_slot,
+            &FeeRateGovernor::default(),
+        )
+    }
+
+    pub fn new_derived_with_governor(
+        base_fee_calculator: &FeeCalculator,
+        latest_signatures_per_slot: usize,
+        governor: &FeeRateGovernor,
     ) -> Self {
         let mut me = base_fee_calculator.clone();
 
         if me.target_signatures_per_slot > 0 {
-            // lamports_per_signature can range from 50% to 1000% of
-            // target_lamports_per_signature
-            //
-            // TODO: Are these decent limits?
-            //
-            me.min_lamports_per_signature = std::cmp::max(1, me.target_lamports_per_signature / 2);
-            me.max_lamports_per_signature = me.target_lamports_per_signature * 10;
+            // lamports_per_signature can range from governor.min_percent to
+            // governor.max_percent of target_lamports_per_signature
+            me.min_lamports_per_signature = std::cmp::max(
+                1,
+                me.target_lamports_per_signature * governor.min_percent / 100,
+            );
+            me.max_lamports_per_signature =
+                me.target_lamports_per_signature * governor.max_percent / 100;
 
             // What the cluster should charge at `latest_signatures_per_slot`
             let desired_lamports_per_signature =
@@ -92,12 +131,12 @@ impl FeeCalculator {
             if gap == 0 {
                 me.lamports_per_signature = desired_lamports_per_signature;
             } else {
-                // Adjust fee by 5% of target_lamports_per_signature to produce a smooth increase/decrease in fees over time.
-                //
-                // TODO: Is this fee curve smooth enough or too smooth?
-                //
-                let gap_adjust =
-                    std::cmp::max(1, me.target_lamports_per_signature as i64 / 20) * gap.signum();
+                // Adjust fee by governor.adjustment_percent of target_lamports_per_signature to
+                // produce a smooth increase/decrease in fees over time.
+                let gap_adjust = std::cmp::max(
+                    1,
+                    (me.target_lamports_per_signature * governor.adjustment_percent / 100) as i64,
+                ) * gap.signum();
 
                 trace!(
                     "lamports_per_signature gap is {}, adjusting by {}",
@@ -259,4 +298,34 @@ mod tests {
             count += 1;
         }
     }
+
+    #[test]
+    fn test_fee_calculator_derived_governor() {
+        let mut f = FeeCalculator::default();
+        f.target_lamports_per_signature = 100;
+        f.target_signatures_per_slot = 100;
+        f.lamports_per_signature = 100;
+        let governor = FeeRateGovernor {
+            min_percent: 80,
+            max_percent: 120,
+            adjustment_percent: 10,
+        };
+
+        let f_up = FeeCalculator::new_derived_with_governor(&f, std::usize::MAX, &governor);
+        assert_eq!(f_up.min_lamports_per_signature, 80);
+        assert_eq!(f_up.max_lamports_per_signature, 120);
+        assert_eq!(f_up.lamports_per_signature, 110);
+        let f_up = FeeCalculator::new_derived_with_governor(&f_up, std::usize::MAX, &governor);
+        let f_up = FeeCalculator::new_derived_with_governor(&f_up, std::usize::MAX, &governor);
+        assert_eq!(f_up.lamports_per_signature, 120);
+
+        let f_down = FeeCalculator::new_derived_with_governor(&f, 0, &governor);
+        assert_eq!(f_down.lamports_per_signature, 90);
+
+        // The default governor matches new_derived()
+        assert_eq!(
+            FeeCalculator::new_derived_with_governor(&f, 0, &FeeRateGovernor::default()),
+            FeeCalculator::new_derived(&f, 0)
+        );
+    }
 }
@@ -1,7 +1,7 @@
 //! The `genesis_block` module is a library for generating the chain's genesis block.
 
 use crate::account::Account;
-use crate::fee_calculator::FeeCalculator;
+use crate::fee_calculator::{FeeCalculator, FeeRateGovernor};
 use crate::hash::{hash, Hash};
 use crate::inflation::Inflation;
 use crate::poh_config::PohConfig;
@@ -11,11 +11,45 @@ use crate::signature::{Keypair, KeypairUtil};
 use crate::system_program;
 use crate::timing::{DEFAULT_SLOTS_PER_EPOCH, DEFAULT_SLOTS_PER_SEGMENT, DEFAULT_TICKS_PER_SLOT};
 use bincode::{deserialize, serialize};
+use byteorder::{ByteOrder, LittleEndian};
 use memmap::Mmap;
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use serde::Serialize;
 use std::fs::{File, OpenOptions};
-use std::io::Write;
+use std::io::{self, Write};
 use std::path::Path;
 
+/// The genesis format version written by this build. Bumped whenever a field is added, so
+/// `solana-genesis inspect` can tell which release wrote a file.
+pub const GENESIS_VERSION: u32 = 2;
+
+/// Leading bytes of a versioned genesis file. Files without them are version 0, the original
+/// plain bincode encoding of `GenesisBlock`.
+const GENESIS_MAGIC: &[u8; 8] = b"SOLGENBK";
+
+// Each field is written as its tag (u32), the length of its value (u64), and its bincode value
+const FIELD_HEADER_LEN: usize = 12;
+
+/// Identifies each `GenesisBlock` field in the versioned format. Tags are never reused, so older
+/// readers can skip fields added by newer writers.
+#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive)]
+pub enum GenesisField {
+    Accounts = 1,
+    NativeInstructionProcessors = 2,
+    RewardsPools = 3,
+    SlotsPerEpoch = 4,
+    StakersSlotOffset = 5,
+    EpochWarmup = 6,
+    TicksPerSlot = 7,
+    SlotsPerSegment = 8,
+    PohConfig = 9,
+    FeeCalculator = 10,
+    Inflation = 11,
+    Rent = 12,
+    FeeRateGovernor = 13,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GenesisBlock {
     pub accounts: Vec<(Pubkey, Account)>,
@@ -30,6 +64,12 @@ pub struct GenesisBlock {
     pub fee_calculator: FeeCalculator,
     pub inflation: Inflation,
     pub rent: Rent,
+    /// Added in version 2, so skipped by the version 0 encoding
+    #[serde(skip)]
+    pub fee_rate_governor: FeeRateGovernor,
+    /// Fields this build doesn't know, by tag, kept so they are written back and hashed
+    #[serde(skip)]
+    pub unknown_fields: Vec<(u32, Vec<u8>)>,
 }
 
 // useful for basic tests
@@ -62,6 +102,8 @@ impl Default for GenesisBlock {
             inflation: Inflation::default(),
             fee_calculator: FeeCalculator::default(),
             rent: Rent::default(),
+            fee_rate_governor: FeeRateGovernor::default(),
+            unknown_fields: Vec::new(),
         }
     }
 }
@@ -143,6 +185,10 @@ impl Builder {
         self.genesis_block.fee_calculator = fee_calculator;
         self
     }
+    pub fn fee_rate_governor(mut self, fee_rate_governor: FeeRateGovernor) -> Self {
+        self.genesis_block.fee_rate_governor = fee_rate_governor;
+        self
+    }
     pub fn inflation(mut self, inflation: Inflation) -> Self {
         self.genesis_block.inflation = inflation;
         self
@@ -161,9 +207,134 @@ impl GenesisBlock {
         }
     }
 
+    /// Blocks that only use fields of the original format hash as they always have, whichever
+    /// format they were read from, so clusters keep their genesis hash. Others hash the
+    /// versioned encoding, which covers every field, including unknown ones.
     pub fn hash(&self) -> Hash {
-        let serialized = serde_json::to_string(self).unwrap();
-        hash(&serialized.into_bytes())
+        if self.is_version_0() {
+            let serialized = serde_json::to_string(self).unwrap();
+            hash(&serialized.into_bytes())
+        } else {
+            hash(&self.to_bytes())
+        }
+    }
+
+    /// Whether the original format can represent this block
+    fn is_version_0(&self) -> bool {
+        self.fee_rate_governor == FeeRateGovernor::default() && self.unknown_fields.is_empty()
+    }
+
+    /// Encode in the current versioned format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = GENESIS_MAGIC.to_vec();
+        let mut version = [0; 4];
+        LittleEndian::write_u32(&mut version, GENESIS_VERSION);
+        bytes.extend_from_slice(&version);
+
+        write_field(&mut bytes, GenesisField::Accounts, &self.accounts);
+        write_field(
+            &mut bytes,
+            GenesisField::NativeInstructionProcessors,
+            &self.native_instruction_processors,
+        );
+        write_field(&mut bytes, GenesisField::RewardsPools, &self.rewards_pools);
+        write_field(
+            &mut bytes,
+            GenesisField::SlotsPerEpoch,
+            &self.slots_per_epoch,
+        );
+        write_field(
+            &mut bytes,
+            GenesisField::StakersSlotOffset,
+            &self.stakers_slot_offset,
+        );
+        write_field(&mut bytes, GenesisField::EpochWarmup, &self.epoch_warmup);
+        write_field(&mut bytes, GenesisField::TicksPerSlot, &self.ticks_per_slot);
+        write_field(
+            &mut bytes,
+            GenesisField::SlotsPerSegment,
+            &self.slots_per_segment,
+        );
+        write_field(&mut bytes, GenesisField::PohConfig, &self.poh_config);
+        write_field(
+            &mut bytes,
+            GenesisField::FeeCalculator,
+            &self.fee_calculator,
+        );
+        write_field(&mut bytes, GenesisField::Inflation, &self.inflation);
+        write_field(&mut bytes, GenesisField::Rent, &self.rent);
+        write_field(
+            &mut bytes,
+            GenesisField::FeeRateGovernor,
+            &self.fee_rate_governor,
+        );
+        for (tag, value) in &self.unknown_fields {
+            write_field_bytes(&mut bytes, *tag, value);
+        }
+        bytes
+    }
+
+    /// The format version of an encoded genesis block, 0 for the original bincode encoding
+    pub fn version(bytes: &[u8]) -> io::Result<u32> {
+        if !bytes.starts_with(GENESIS_MAGIC) {
+            return Ok(0);
+        }
+        let version = bytes
+            .get(GENESIS_MAGIC.len()..GENESIS_MAGIC.len() + 4)
+            .ok_or_else(|| invalid_data("genesis version is truncated"))?;
+        Ok(LittleEndian::read_u32(version))
+    }
+
+    /// Decode any version. Fields missing from the encoding keep their default values.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if Self::version(bytes)? == 0 {
+            return deserialize(bytes).map_err(|err| invalid_data(&format!("{:?}", err)));
+        }
+
+        let mut genesis_block = GenesisBlock::default();
+        let mut rest = &bytes[GENESIS_MAGIC.len() + 4..];
+        while !rest.is_empty() {
+            if rest.len() < FIELD_HEADER_LEN {
+                return Err(invalid_data("genesis field header is truncated"));
+            }
+            let tag = LittleEndian::read_u32(&rest[..4]);
+            let len = LittleEndian::read_u64(&rest[4..FIELD_HEADER_LEN]) as usize;
+            rest = &rest[FIELD_HEADER_LEN..];
+            if rest.len() < len {
+                return Err(invalid_data(&format!("genesis field {} is truncated", tag)));
+            }
+            let (value, remaining) = rest.split_at(len);
+            rest = remaining;
+
+            match GenesisField::from_u32(tag) {
+                Some(field) => genesis_block
+                    .set_field(field, value)
+                    .map_err(|err| invalid_data(&format!("{:?}: {:?}", field, err)))?,
+                None => genesis_block.unknown_fields.push((tag, value.to_vec())),
+            }
+        }
+        Ok(genesis_block)
+    }
+
+    fn set_field(&mut self, field: GenesisField, value: &[u8]) -> bincode::Result<()> {
+        match field {
+            GenesisField::Accounts => self.accounts = deserialize(value)?,
+            GenesisField::NativeInstructionProcessors => {
+                self.native_instruction_processors = deserialize(value)?
+            }
+            GenesisField::RewardsPools => self.rewards_pools = deserialize(value)?,
+            GenesisField::SlotsPerEpoch => self.slots_per_epoch = deserialize(value)?,
+            GenesisField::StakersSlotOffset => self.stakers_slot_offset = deserialize(value)?,
+            GenesisField::EpochWarmup => self.epoch_warmup = deserialize(value)?,
+            GenesisField::TicksPerSlot => self.ticks_per_slot = deserialize(value)?,
+            GenesisField::SlotsPerSegment => self.slots_per_segment = deserialize(value)?,
+            GenesisField::PohConfig => self.poh_config = deserialize(value)?,
+            GenesisField::FeeCalculator => self.fee_calculator = deserialize(value)?,
+            GenesisField::Inflation => self.inflation = deserialize(value)?,
+            GenesisField::Rent => self.rent = deserialize(value)?,
+            GenesisField::FeeRateGovernor => self.fee_rate_governor = deserialize(value)?,
+        }
+        Ok(())
     }
 
     pub fn load(ledger_path: &str) -> Result<Self, std::io::Error> {
@@ -174,23 +345,35 @@ impl GenesisBlock {
 
         //UNSAFE: Required to create a Mmap
         let mem = unsafe { Mmap::map(&file).expect("failed to map the genesis file") };
-        let genesis_block = deserialize(&mem)
-            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, format!("{:?}", err)))?;
-        Ok(genesis_block)
+        Self::from_bytes(&mem)
     }
 
     pub fn write(&self, ledger_path: &str) -> Result<(), std::io::Error> {
-        let serialized = serialize(&self)
-            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, format!("{:?}", err)))?;
-
         let dir = Path::new(ledger_path);
         std::fs::create_dir_all(&dir)?;
 
         let mut file = File::create(&dir.join("genesis.bin"))?;
-        file.write_all(&serialized)
+        file.write_all(&self.to_bytes())
     }
 }
 
+fn write_field<T: Serialize>(bytes: &mut Vec<u8>, field: GenesisField, value: &T) {
+    let value = serialize(value).expect("serialize genesis field");
+    write_field_bytes(bytes, field as u32, &value);
+}
+
+fn write_field_bytes(bytes: &mut Vec<u8>, tag: u32, value: &[u8]) {
+    let mut header = [0; FIELD_HEADER_LEN];
+    LittleEndian::write_u32(&mut header[..4], tag);
+    LittleEndian::write_u64(&mut header[4..], value.len() as u64);
+    bytes.extend_from_slice(&header);
+    bytes.extend_from_slice(value);
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -234,4 +417,97 @@ mod tests {
         let _ignored = std::fs::remove_file(&path);
     }
 
+    #[test]
+    fn test_genesis_block_bytes() {
+        let block = Builder::new()
+            .account(Pubkey::new_rand(), Account::new(1, 0, &Pubkey::default()))
+            .ticks_per_slot(3)
+            .build();
+        let bytes = block.to_bytes();
+        assert_eq!(GenesisBlock::version(&bytes).unwrap(), GENESIS_VERSION);
+
+        let decoded = GenesisBlock::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.accounts.len(), 1);
+        assert_eq!(decoded.ticks_per_slot, 3);
+        assert_eq!(decoded.hash(), block.hash());
+
+        assert!(GenesisBlock::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_genesis_block_economics() {
+        let mut inflation = Inflation::default();
+        inflation.initial = 0.5;
+        let governor = FeeRateGovernor {
+            min_percent: 10,
+            max_percent: 200,
+            adjustment_percent: 1,
+        };
+        let block = Builder::new()
+            .inflation(inflation.clone())
+            .fee_rate_governor(governor)
+            .build();
+
+        let decoded = GenesisBlock::from_bytes(&block.to_bytes()).unwrap();
+        assert_eq!(decoded.inflation, inflation);
+        assert_eq!(decoded.fee_rate_governor, governor);
+        assert_ne!(block.hash(), GenesisBlock::default().hash());
+        assert_eq!(decoded.hash(), hash(&block.to_bytes()));
+    }
+
+    #[test]
+    fn test_genesis_block_version_0() {
+        let block = Builder::new().ticks_per_slot(3).build();
+        let bytes = serialize(&block).unwrap();
+        assert_eq!(GenesisBlock::version(&bytes).unwrap(), 0);
+
+        let decoded = GenesisBlock::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.ticks_per_slot, 3);
+        assert_eq!(decoded.hash(), block.hash());
+
+        // Hashed as before versioning, however it's encoded
+        let v0_hash = hash(&serde_json::to_string(&block).unwrap().into_bytes());
+        assert_eq!(decoded.hash(), v0_hash);
+        let reencoded = GenesisBlock::from_bytes(&block.to_bytes()).unwrap();
+        assert_eq!(reencoded.hash(), v0_hash);
+    }
+
+    #[test]
+    fn test_genesis_block_unknown_fields() {
+        let block = GenesisBlock::default();
+        let mut bytes = block.to_bytes();
+        write_field_bytes(&mut bytes, 1_000, &[1, 2, 3]);
+
+        let decoded = GenesisBlock::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.unknown_fields, vec![(1_000, vec![1, 2, 3])]);
+        assert_eq!(decoded.to_bytes(), bytes);
+        assert_ne!(decoded.hash(), block.hash());
+    }
+
+    #[test]
+    fn test_genesis_block_missing_fields() {
+        // A writer that only knew about the accounts
+        let accounts = vec![(Pubkey::new_rand(), Account::new(1, 0, &Pubkey::default()))];
+        let mut bytes = GENESIS_MAGIC.to_vec();
+        bytes.extend_from_slice(&[1, 0, 0, 0]);
+        write_field(&mut bytes, GenesisField::Accounts, &accounts);
+
+        let decoded = GenesisBlock::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.accounts.len(), 1);
+        assert_eq!(decoded.rent, Rent::default());
+        assert_eq!(decoded.fee_rate_governor, FeeRateGovernor::default());
+        assert_eq!(decoded.slots_per_epoch, DEFAULT_SLOTS_PER_EPOCH);
+    }
+
+    #[test]
+    fn test_genesis_block_hash_covers_all_fields() {
+        let block = GenesisBlock::default();
+        let mut rent = Rent::default();
+        rent.lamports_per_byte_year += 1;
+        let changed = GenesisBlock {
+            rent,
+            ..GenesisBlock::default()
+        };
+        assert_ne!(block.hash(), changed.hash());
+    }
 }
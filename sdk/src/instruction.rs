@@ -68,6 +68,12 @@ pub enum InstructionError {
     /// them returned by the Solana runtime. A CustomError may be any type that is represented
     /// as or serialized to a u32 integer.
     CustomError(u32),
+
+    /// The transaction invoked more programs than the compute budget allows
+    MaxInstructionTraceLengthExceeded,
+
+    /// An account's lamports would overflow or underflow
+    ArithmeticOverflow,
 }
 
 impl InstructionError {
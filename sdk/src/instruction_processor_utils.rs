@@ -1,6 +1,7 @@
 use crate::account::KeyedAccount;
 use crate::instruction::InstructionError;
 use crate::pubkey::Pubkey;
+use crate::transaction::TransactionReturnData;
 use num_traits::FromPrimitive;
 
 // All native programs export a symbol named process()
@@ -13,6 +14,17 @@ pub type Entrypoint = unsafe extern "C" fn(
     data: &[u8],
 ) -> Result<(), InstructionError>;
 
+// Native programs that produce return data export a symbol named swap_return_data().  The
+// loader calls it to hand the transaction's return data to the program before the
+// entrypoint runs and to take it back afterwards, since the program's copy of any
+// process state is its own.
+pub const SWAP_RETURN_DATA: &str = "swap_return_data";
+
+// Native program SWAP_RETURN_DATA prototype, returns the data it replaced
+pub type SwapReturnData = unsafe extern "C" fn(
+    return_data: Option<TransactionReturnData>,
+) -> Option<TransactionReturnData>;
+
 // Convenience macro to define the native program entrypoint.  Supply a fn to this macro that
 // conforms to the `Entrypoint` type signature.
 #[macro_export]
@@ -2,6 +2,7 @@ pub mod account;
 pub mod account_utils;
 pub mod bpf_loader;
 pub mod client;
+pub mod epoch_schedule;
 pub mod fee_calculator;
 pub mod genesis_block;
 pub mod hash;
@@ -11,6 +12,7 @@ pub mod instruction_processor_utils;
 pub mod loader_instruction;
 pub mod message;
 pub mod native_loader;
+pub mod native_token;
 pub mod packet;
 pub mod poh_config;
 pub mod pubkey;
@@ -0,0 +1,65 @@
+//! The `native_token` module defines the SOL token and its conversions to lamports.
+
+use std::fmt;
+
+/// There are 2^34 lamports in one SOL
+pub const LAMPORTS_PER_SOL: u64 = 17_179_869_184;
+
+/// Number of decimal places a `Sol` is displayed with
+pub const SOL_DECIMALS: usize = 9;
+
+/// Approximately convert fractional native tokens (lamports) into native tokens (SOL)
+pub fn lamports_to_sol(lamports: u64) -> f64 {
+    lamports as f64 / LAMPORTS_PER_SOL as f64
+}
+
+/// Approximately convert native tokens (SOL) into fractional native tokens (lamports)
+pub fn sol_to_lamports(sol: f64) -> u64 {
+    (sol * LAMPORTS_PER_SOL as f64) as u64
+}
+
+/// An amount of lamports that is displayed in SOL, rounded to `SOL_DECIMALS` decimal places
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Sol(pub u64);
+
+impl fmt::Display for Sol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Count in the smallest displayed unit with integer math, so that large amounts don't
+        // lose precision the way an f64 would
+        let scale = 10u128.pow(SOL_DECIMALS as u32);
+        let lamports_per_sol = u128::from(LAMPORTS_PER_SOL);
+        let units = (u128::from(self.0) * scale + lamports_per_sol / 2) / lamports_per_sol;
+        write!(
+            f,
+            "{}.{:0width$} SOL",
+            units / scale,
+            units % scale,
+            width = SOL_DECIMALS
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sol_to_lamports() {
+        assert_eq!(sol_to_lamports(1.0), LAMPORTS_PER_SOL);
+        assert_eq!(sol_to_lamports(1.5), LAMPORTS_PER_SOL / 2 * 3);
+        assert_eq!(lamports_to_sol(LAMPORTS_PER_SOL / 4), 0.25);
+        assert_eq!(lamports_to_sol(sol_to_lamports(42.0)), 42.0);
+    }
+
+    #[test]
+    fn test_sol_display() {
+        assert_eq!(Sol(0).to_string(), "0.000000000 SOL");
+        assert_eq!(Sol(LAMPORTS_PER_SOL / 2 * 3).to_string(), "1.500000000 SOL");
+        assert_eq!(Sol(50).to_string(), "0.000000003 SOL");
+        assert_eq!(Sol(LAMPORTS_PER_SOL - 1).to_string(), "1.000000000 SOL");
+        assert_eq!(
+            Sol(u64::max_value()).to_string(),
+            "1073741824.000000000 SOL"
+        );
+    }
+}
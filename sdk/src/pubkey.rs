@@ -1,3 +1,4 @@
+use crate::hash::hashv;
 use std::convert::TryFrom;
 use std::error;
 use std::fmt;
@@ -7,6 +8,9 @@ use std::mem;
 use std::path::Path;
 use std::str::FromStr;
 
+/// The longest seed an address can be derived from with `Pubkey::create_with_seed`
+pub const MAX_SEED_LEN: usize = 32;
+
 #[repr(transparent)]
 #[derive(Serialize, Deserialize, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct Pubkey([u8; 32]);
@@ -25,6 +29,19 @@ impl fmt::Display for ParsePubkeyError {
 
 impl error::Error for ParsePubkeyError {}
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PubkeyError {
+    MaxSeedLengthExceeded,
+}
+
+impl fmt::Display for PubkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PubkeyError: {:?}", self)
+    }
+}
+
+impl error::Error for PubkeyError {}
+
 impl FromStr for Pubkey {
     type Err = ParsePubkeyError;
 
@@ -51,6 +68,21 @@ impl Pubkey {
     pub fn new_rand() -> Self {
         Self::new(&rand::random::<[u8; 32]>())
     }
+
+    /// Derive an address from `base`, `seed` and the program that will own it.  Only `base`
+    /// can sign for the derived address, which has no private key of its own.
+    pub fn create_with_seed(
+        base: &Pubkey,
+        seed: &str,
+        program_id: &Pubkey,
+    ) -> Result<Pubkey, PubkeyError> {
+        if seed.len() > MAX_SEED_LEN {
+            return Err(PubkeyError::MaxSeedLengthExceeded);
+        }
+        Ok(Pubkey::new(
+            hashv(&[base.as_ref(), seed.as_ref(), program_id.as_ref()]).as_ref(),
+        ))
+    }
 }
 
 impl AsRef<[u8]> for Pubkey {
@@ -167,6 +199,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_create_with_seed() {
+        let base = Pubkey::new_rand();
+        let program_id = Pubkey::new_rand();
+        let address = Pubkey::create_with_seed(&base, "payout", &program_id).unwrap();
+        assert_eq!(
+            Pubkey::create_with_seed(&base, "payout", &program_id),
+            Ok(address)
+        );
+        assert_ne!(
+            Pubkey::create_with_seed(&base, "payout2", &program_id),
+            Ok(address)
+        );
+        assert_ne!(
+            Pubkey::create_with_seed(&base, "payout", &Pubkey::new_rand()),
+            Ok(address)
+        );
+
+        let max_seed = "x".repeat(MAX_SEED_LEN);
+        assert!(Pubkey::create_with_seed(&base, &max_seed, &program_id).is_ok());
+        assert_eq!(
+            Pubkey::create_with_seed(&base, &format!("{}x", max_seed), &program_id),
+            Err(PubkeyError::MaxSeedLengthExceeded)
+        );
+    }
+
     #[test]
     fn test_read_write_pubkey() -> Result<(), Box<dyn error::Error>> {
         let filename = "test_pubkey.json";
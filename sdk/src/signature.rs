@@ -134,7 +134,12 @@ pub fn read_keypair(path: &str) -> Result<Keypair, Box<dyn error::Error>> {
 }
 
 pub fn gen_keypair_file(outfile: &str) -> Result<String, Box<dyn error::Error>> {
-    let keypair_bytes = Keypair::new().to_bytes();
+    write_keypair(&Keypair::new(), outfile)
+}
+
+/// Writes `keypair` to `outfile`, unless it's "-", and returns its serialization
+pub fn write_keypair(keypair: &Keypair, outfile: &str) -> Result<String, Box<dyn error::Error>> {
+    let keypair_bytes = keypair.to_bytes();
     let serialized = serde_json::to_string(&keypair_bytes.to_vec())?;
 
     if outfile != "-" {
@@ -178,6 +183,15 @@ mod tests {
         assert!(!Path::new(&outfile).exists());
     }
 
+    #[test]
+    fn test_write_keypair() {
+        let outfile = tmp_file_path("test_write_keypair.json");
+        let keypair = Keypair::new();
+        write_keypair(&keypair, &outfile).unwrap();
+        assert_eq!(read_keypair(&outfile).unwrap().pubkey(), keypair.pubkey());
+        fs::remove_file(&outfile).unwrap();
+    }
+
     #[test]
     fn test_signature_fromstr() {
         let signature = Keypair::new().sign_message(&[0u8]);
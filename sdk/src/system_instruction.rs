@@ -1,4 +1,4 @@
-use crate::instruction::{AccountMeta, Instruction};
+use crate::instruction::{AccountMeta, Instruction, InstructionError};
 use crate::instruction_processor_utils::DecodeError;
 use crate::pubkey::Pubkey;
 use crate::system_program;
@@ -11,6 +11,8 @@ pub enum SystemError {
     SourceNotSystemAccount,
     InvalidProgramId,
     InvalidAccountId,
+    AccountDataNotZeroed,
+    AddressWithSeedMismatch,
 }
 
 impl<T> DecodeError<T> for SystemError {
@@ -26,6 +28,12 @@ impl std::fmt::Display for SystemError {
 }
 impl std::error::Error for SystemError {}
 
+impl From<SystemError> for InstructionError {
+    fn from(error: SystemError) -> Self {
+        InstructionError::CustomError(error as u32)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum SystemInstruction {
     /// Create a new account
@@ -41,11 +49,46 @@ pub enum SystemInstruction {
     },
     /// Assign account to a program
     /// * Transaction::keys[0] - account to assign
+    /// * Transaction::keys[1] - the program, signing to accept the account, required only if
+    ///   the account's data isn't all zeros
     Assign { program_id: Pubkey },
     /// Transfer lamports
     /// * Transaction::keys[0] - source
     /// * Transaction::keys[1] - destination
     Transfer { lamports: u64 },
+    /// Allocate space for an account at an address derived from a base key and seed, and
+    /// assign it to a program
+    /// * Transaction::keys[0] - account to allocate, at
+    ///   `Pubkey::create_with_seed(base, seed, program_id)`
+    /// * Transaction::keys[1] - base, signing for the derived address
+    AllocateWithSeed {
+        base: Pubkey,
+        seed: String,
+        space: u64,
+        program_id: Pubkey,
+    },
+    /// Assign an account at an address derived from a base key and seed to a program
+    /// * Transaction::keys[0] - account to assign, at
+    ///   `Pubkey::create_with_seed(base, seed, program_id)`
+    /// * Transaction::keys[1] - base, signing for the derived address
+    AssignWithSeed {
+        base: Pubkey,
+        seed: String,
+        program_id: Pubkey,
+    },
+    /// Transfer lamports from an address derived from a base key and seed
+    /// * Transaction::keys[0] - source, at `Pubkey::create_with_seed(base, from_seed, from_owner)`
+    /// * Transaction::keys[1] - base, signing for the derived address
+    /// * Transaction::keys[2] - destination
+    TransferWithSeed {
+        lamports: u64,
+        from_seed: String,
+        from_owner: Pubkey,
+    },
+    /// Transfer lamports to several destinations.  Either every transfer is made or none are.
+    /// * Transaction::keys[0] - source
+    /// * Transaction::keys[1..] - destinations, one for each of `lamports`
+    TransferMany { lamports: Vec<u64> },
 }
 
 pub fn create_account(
@@ -87,6 +130,21 @@ pub fn assign(from_pubkey: &Pubkey, program_id: &Pubkey) -> Instruction {
     )
 }
 
+/// Assign an account that may hold data, which `program_id` accepts by signing
+pub fn assign_with_owner_signature(from_pubkey: &Pubkey, program_id: &Pubkey) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*from_pubkey, true),
+        AccountMeta::new_credit_only(*program_id, true),
+    ];
+    Instruction::new(
+        system_program::id(),
+        &SystemInstruction::Assign {
+            program_id: *program_id,
+        },
+        account_metas,
+    )
+}
+
 pub fn transfer(from_pubkey: &Pubkey, to_pubkey: &Pubkey, lamports: u64) -> Instruction {
     let account_metas = vec![
         AccountMeta::new(*from_pubkey, true),
@@ -99,6 +157,96 @@ pub fn transfer(from_pubkey: &Pubkey, to_pubkey: &Pubkey, lamports: u64) -> Inst
     )
 }
 
+/// Allocate `space` for the account `Pubkey::create_with_seed(base, seed, program_id)` and
+/// assign it to `program_id`
+pub fn allocate_with_seed(
+    address: &Pubkey,
+    base: &Pubkey,
+    seed: &str,
+    space: u64,
+    program_id: &Pubkey,
+) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*address, false),
+        AccountMeta::new_credit_only(*base, true),
+    ];
+    Instruction::new(
+        system_program::id(),
+        &SystemInstruction::AllocateWithSeed {
+            base: *base,
+            seed: seed.to_string(),
+            space,
+            program_id: *program_id,
+        },
+        account_metas,
+    )
+}
+
+/// Assign the account `Pubkey::create_with_seed(base, seed, program_id)` to `program_id`
+pub fn assign_with_seed(
+    address: &Pubkey,
+    base: &Pubkey,
+    seed: &str,
+    program_id: &Pubkey,
+) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*address, false),
+        AccountMeta::new_credit_only(*base, true),
+    ];
+    Instruction::new(
+        system_program::id(),
+        &SystemInstruction::AssignWithSeed {
+            base: *base,
+            seed: seed.to_string(),
+            program_id: *program_id,
+        },
+        account_metas,
+    )
+}
+
+/// Transfer from the account `Pubkey::create_with_seed(from_base, from_seed, from_owner)`
+pub fn transfer_with_seed(
+    from_pubkey: &Pubkey,
+    from_base: &Pubkey,
+    from_seed: &str,
+    from_owner: &Pubkey,
+    to_pubkey: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*from_pubkey, false),
+        AccountMeta::new_credit_only(*from_base, true),
+        AccountMeta::new_credit_only(*to_pubkey, false),
+    ];
+    Instruction::new(
+        system_program::id(),
+        &SystemInstruction::TransferWithSeed {
+            lamports,
+            from_seed: from_seed.to_string(),
+            from_owner: *from_owner,
+        },
+        account_metas,
+    )
+}
+
+/// Transfer to every destination in a single instruction, which fails without transferring
+/// anything if the source can't cover all of them
+pub fn transfer_to_many(from_pubkey: &Pubkey, to_lamports: &[(Pubkey, u64)]) -> Instruction {
+    let mut account_metas = vec![AccountMeta::new(*from_pubkey, true)];
+    account_metas.extend(
+        to_lamports
+            .iter()
+            .map(|(to_pubkey, _)| AccountMeta::new_credit_only(*to_pubkey, false)),
+    );
+    Instruction::new(
+        system_program::id(),
+        &SystemInstruction::TransferMany {
+            lamports: to_lamports.iter().map(|(_, lamports)| *lamports).collect(),
+        },
+        account_metas,
+    )
+}
+
 /// Create and sign new SystemInstruction::Transfer transaction to many destinations
 pub fn transfer_many(from_pubkey: &Pubkey, to_lamports: &[(Pubkey, u64)]) -> Vec<Instruction> {
     to_lamports
@@ -127,4 +275,50 @@ mod tests {
         assert_eq!(get_keys(&instructions[0]), vec![alice_pubkey, bob_pubkey]);
         assert_eq!(get_keys(&instructions[1]), vec![alice_pubkey, carol_pubkey]);
     }
+
+    #[test]
+    fn test_transfer_to_many() {
+        let alice_pubkey = Pubkey::new_rand();
+        let bob_pubkey = Pubkey::new_rand();
+        let carol_pubkey = Pubkey::new_rand();
+        let to_lamports = vec![(bob_pubkey, 1), (carol_pubkey, 2)];
+
+        let instruction = transfer_to_many(&alice_pubkey, &to_lamports);
+        assert_eq!(
+            get_keys(&instruction),
+            vec![alice_pubkey, bob_pubkey, carol_pubkey]
+        );
+        assert!(instruction.accounts[0].is_signer);
+        assert!(!instruction.accounts[1].is_signer);
+        assert_eq!(
+            instruction.data,
+            bincode::serialize(&SystemInstruction::TransferMany {
+                lamports: vec![1, 2]
+            })
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_transfer_with_seed() {
+        let base_pubkey = Pubkey::new_rand();
+        let from_pubkey =
+            Pubkey::create_with_seed(&base_pubkey, "payout", &system_program::id()).unwrap();
+        let to_pubkey = Pubkey::new_rand();
+
+        let instruction = transfer_with_seed(
+            &from_pubkey,
+            &base_pubkey,
+            "payout",
+            &system_program::id(),
+            &to_pubkey,
+            1,
+        );
+        assert_eq!(
+            get_keys(&instruction),
+            vec![from_pubkey, base_pubkey, to_pubkey]
+        );
+        assert!(!instruction.accounts[0].is_signer);
+        assert!(instruction.accounts[1].is_signer);
+    }
 }
@@ -0,0 +1,54 @@
+//! This account contains the cluster's epoch schedule
+//!
+use crate::account::Account;
+pub use crate::epoch_schedule::EpochSchedule;
+use crate::sysvar;
+use bincode::serialized_size;
+
+///  epoch_schedule account pubkey
+const ID: [u8; 32] = [
+    6, 167, 213, 23, 24, 220, 63, 238, 2, 211, 228, 127, 1, 0, 248, 176, 84, 247, 148, 46, 96, 89,
+    30, 63, 80, 135, 25, 168, 5, 0, 0, 0,
+];
+
+crate::solana_name_id!(ID, "SysvarEpochSchedu1e111111111111111111111111");
+
+impl EpochSchedule {
+    pub fn from(account: &Account) -> Option<Self> {
+        account.deserialize_data().ok()
+    }
+    pub fn to(&self, account: &mut Account) -> Option<()> {
+        account.serialize_data(self).ok()
+    }
+
+    pub fn size_of() -> usize {
+        serialized_size(&Self::default()).unwrap() as usize
+    }
+}
+
+pub fn create_account(lamports: u64, epoch_schedule: &EpochSchedule) -> Account {
+    Account::new_data(lamports, epoch_schedule, &sysvar::id()).unwrap()
+}
+
+use crate::account::KeyedAccount;
+use crate::instruction::InstructionError;
+
+pub fn from_keyed_account(account: &KeyedAccount) -> Result<EpochSchedule, InstructionError> {
+    if !check_id(account.unsigned_key()) {
+        return Err(InstructionError::InvalidArgument);
+    }
+    EpochSchedule::from(account.account).ok_or(InstructionError::InvalidAccountData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_account() {
+        let epoch_schedule = EpochSchedule::new(64, 32, true);
+        let account = create_account(1, &epoch_schedule);
+        assert_eq!(account.data.len(), EpochSchedule::size_of());
+        assert_eq!(EpochSchedule::from(&account), Some(epoch_schedule));
+    }
+}
@@ -0,0 +1,96 @@
+//! This account carries the Bank's most recent blockhashes, most recent first
+//!
+use crate::account::Account;
+use crate::hash::Hash;
+use crate::sysvar;
+use bincode::serialized_size;
+use std::ops::Deref;
+
+///  recent_blockhashes account pubkey
+const ID: [u8; 32] = [
+    6, 167, 213, 23, 25, 44, 86, 142, 224, 138, 132, 95, 115, 210, 151, 136, 207, 3, 92, 49, 69,
+    178, 26, 179, 68, 216, 6, 46, 169, 64, 0, 0,
+];
+
+crate::solana_name_id!(ID, "SysvarRecentB1ockHashes11111111111111111111");
+
+pub const MAX_ENTRIES: usize = 32;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Default)]
+pub struct RecentBlockhashes {
+    // non-pub to keep control of size
+    inner: Vec<Hash>,
+}
+
+impl RecentBlockhashes {
+    pub fn from(account: &Account) -> Option<Self> {
+        account.deserialize_data().ok()
+    }
+    pub fn to(&self, account: &mut Account) -> Option<()> {
+        account.serialize_data(self).ok()
+    }
+
+    pub fn size_of() -> usize {
+        serialized_size(&RecentBlockhashes {
+            inner: vec![Hash::default(); MAX_ENTRIES],
+        })
+        .unwrap() as usize
+    }
+
+    /// Keeps the first `MAX_ENTRIES` of `blockhashes`, which should be ordered
+    /// most recent first
+    pub fn new(blockhashes: &[Hash]) -> Self {
+        Self {
+            inner: blockhashes.iter().take(MAX_ENTRIES).cloned().collect(),
+        }
+    }
+}
+
+impl Deref for RecentBlockhashes {
+    type Target = Vec<Hash>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+pub fn create_account(lamports: u64, blockhashes: &[Hash]) -> Account {
+    let mut account = Account::new(lamports, RecentBlockhashes::size_of(), &sysvar::id());
+    RecentBlockhashes::new(blockhashes)
+        .to(&mut account)
+        .unwrap();
+    account
+}
+
+use crate::account::KeyedAccount;
+use crate::instruction::InstructionError;
+
+pub fn from_keyed_account(account: &KeyedAccount) -> Result<RecentBlockhashes, InstructionError> {
+    if !check_id(account.unsigned_key()) {
+        return Err(InstructionError::InvalidArgument);
+    }
+    RecentBlockhashes::from(account.account).ok_or(InstructionError::InvalidAccountData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::hash;
+
+    #[test]
+    fn test_create_account() {
+        let account = create_account(42, &[]);
+        assert_eq!(account.data.len(), RecentBlockhashes::size_of());
+        assert_eq!(
+            RecentBlockhashes::from(&account),
+            Some(RecentBlockhashes::default())
+        );
+
+        let blockhashes: Vec<_> = (0..MAX_ENTRIES + 1)
+            .map(|i| hash(&[(i >> 8) as u8, i as u8]))
+            .collect();
+        let account = create_account(42, &blockhashes);
+        let recent_blockhashes = RecentBlockhashes::from(&account).unwrap();
+        assert_eq!(recent_blockhashes.len(), MAX_ENTRIES);
+        assert_eq!(recent_blockhashes[..], blockhashes[..MAX_ENTRIES]);
+    }
+}
@@ -0,0 +1,57 @@
+//! This account contains the current cluster rent
+//!
+use crate::account::Account;
+pub use crate::rent::Rent;
+use crate::sysvar;
+use bincode::serialized_size;
+
+///  rent account pubkey
+const ID: [u8; 32] = [
+    6, 167, 213, 23, 25, 44, 92, 81, 33, 140, 201, 76, 61, 74, 241, 127, 88, 218, 238, 8, 155, 161,
+    253, 68, 227, 219, 217, 138, 0, 0, 0, 0,
+];
+
+crate::solana_name_id!(ID, "SysvarRent111111111111111111111111111111111");
+
+impl Rent {
+    pub fn from(account: &Account) -> Option<Self> {
+        account.deserialize_data().ok()
+    }
+    pub fn to(&self, account: &mut Account) -> Option<()> {
+        account.serialize_data(self).ok()
+    }
+
+    pub fn size_of() -> usize {
+        serialized_size(&Self::default()).unwrap() as usize
+    }
+}
+
+pub fn create_account(lamports: u64, rent: &Rent) -> Account {
+    Account::new_data(lamports, rent, &sysvar::id()).unwrap()
+}
+
+use crate::account::KeyedAccount;
+use crate::instruction::InstructionError;
+
+pub fn from_keyed_account(account: &KeyedAccount) -> Result<Rent, InstructionError> {
+    if !check_id(account.unsigned_key()) {
+        return Err(InstructionError::InvalidArgument);
+    }
+    Rent::from(account.account).ok_or(InstructionError::InvalidAccountData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_account() {
+        let rent = Rent {
+            lamports_per_byte_year: 7,
+            exemption_threshold: 0.5,
+        };
+        let account = create_account(1, &rent);
+        assert_eq!(account.data.len(), Rent::size_of());
+        assert_eq!(Rent::from(&account), Some(rent));
+    }
+}
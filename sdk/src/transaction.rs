@@ -3,11 +3,13 @@
 use crate::hash::Hash;
 use crate::instruction::{CompiledInstruction, Instruction, InstructionError};
 use crate::message::Message;
+use crate::packet::PACKET_DATA_SIZE;
 use crate::pubkey::Pubkey;
 use crate::short_vec;
 use crate::signature::{KeypairUtil, Signature};
-use bincode::serialize;
-use std::result;
+use bincode::{serialize, serialized_size};
+use std::ops::Deref;
+use std::{error, fmt, io, result};
 
 /// Reasons a transaction might be rejected.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -51,10 +53,72 @@ pub enum TransactionError {
 
     /// Transaction contains an invalid account reference
     InvalidAccountIndex,
+
+    /// Transaction doesn't have the number of signatures its message requires
+    InvalidSignatureCount,
 }
 
 pub type Result<T> = result::Result<T, TransactionError>;
 
+/// Largest number of bytes a program may hand back as return data
+pub const MAX_RETURN_DATA: usize = 1024;
+
+/// The data most recently set by a program with `sol_set_return_data`, which
+/// becomes the return data of the whole transaction
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone)]
+pub struct TransactionReturnData {
+    /// The program that set the data
+    pub program_id: Pubkey,
+    pub data: Vec<u8>,
+}
+
+/// What committing a transaction did, kept so its history can be looked up later
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionStatusMeta {
+    pub status: Result<()>,
+    /// The fee the transaction paid, charged even if one of its instructions failed
+    pub fee: u64,
+    /// The lamports of each of the message's accounts before the transaction, in the order of
+    /// its account keys
+    pub pre_balances: Vec<u64>,
+    /// The lamports of each of the message's accounts after the transaction and its fee
+    pub post_balances: Vec<u64>,
+}
+
+/// A transaction that doesn't fit in a packet, so it would be dropped instead of delivered
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TransactionTooLarge {
+    /// Serialized size of the transaction, in bytes
+    pub size: usize,
+    pub num_signatures: usize,
+    pub num_account_keys: usize,
+}
+
+impl fmt::Display for TransactionTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Transaction is {} bytes, {} bytes over the {} byte packet limit. \
+             Try using fewer accounts (it has {}) or signatures (it has {}), \
+             or splitting it into several transactions",
+            self.size,
+            self.size - PACKET_DATA_SIZE,
+            PACKET_DATA_SIZE,
+            self.num_account_keys,
+            self.num_signatures,
+        )
+    }
+}
+
+impl error::Error for TransactionTooLarge {}
+
+impl From<TransactionTooLarge> for io::Error {
+    fn from(err: TransactionTooLarge) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, err.to_string())
+    }
+}
+
 /// An atomic transaction
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Transaction {
@@ -251,6 +315,24 @@ impl Transaction {
             .all(|signature| *signature != Signature::default())
     }
 
+    /// Number of bytes the transaction takes on the wire
+    pub fn serialized_size(&self) -> usize {
+        serialized_size(self).unwrap() as usize
+    }
+
+    /// Check that the transaction fits in a single packet, as a larger one is never delivered
+    pub fn check_packet_fit(&self) -> result::Result<(), TransactionTooLarge> {
+        let size = self.serialized_size();
+        if size > PACKET_DATA_SIZE {
+            return Err(TransactionTooLarge {
+                size,
+                num_signatures: self.signatures.len(),
+                num_account_keys: self.message.account_keys.len(),
+            });
+        }
+        Ok(())
+    }
+
     /// Verify that references in the instructions are valid
     pub fn verify_refs(&self) -> bool {
         let message = self.message();
@@ -266,6 +348,89 @@ impl Transaction {
         }
         true
     }
+
+    /// Check the structure of the transaction: its signature count, message header, account
+    /// keys and account references
+    pub fn sanitize(&self) -> Result<()> {
+        let header = &self.message.header;
+        let num_account_keys = self.message.account_keys.len();
+        if self.signatures.len() != header.num_required_signatures as usize {
+            return Err(TransactionError::InvalidSignatureCount);
+        }
+        if header.num_required_signatures as usize > num_account_keys
+            || header.num_credit_only_signed_accounts > header.num_required_signatures
+            || header.num_required_signatures as usize
+                + header.num_credit_only_unsigned_accounts as usize
+                > num_account_keys
+        {
+            return Err(TransactionError::InvalidAccountIndex);
+        }
+        let account_keys = &self.message.account_keys;
+        if (1..num_account_keys).any(|i| account_keys[i..].contains(&account_keys[i - 1])) {
+            return Err(TransactionError::AccountLoadedTwice);
+        }
+        if !self.verify_refs() {
+            return Err(TransactionError::InvalidAccountIndex);
+        }
+        Ok(())
+    }
+}
+
+/// A transaction that passed `Transaction::sanitize`. Banks only lock, execute and commit
+/// sanitized transactions, so each entry point sanitizes the transactions it receives once, and
+/// the checks are the same whichever way a transaction arrives.
+#[repr(transparent)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SanitizedTransaction(Transaction);
+
+impl SanitizedTransaction {
+    pub fn try_new(transaction: Transaction) -> Result<Self> {
+        transaction.sanitize()?;
+        Ok(SanitizedTransaction(transaction))
+    }
+
+    /// Sanitize every transaction of `transactions`, failing with the first error
+    pub fn try_new_all<I>(transactions: I) -> Result<Vec<Self>>
+    where
+        I: IntoIterator<Item = Transaction>,
+    {
+        transactions.into_iter().map(Self::try_new).collect()
+    }
+
+    /// Sanitize every transaction of `transactions` in place, failing with the first error, and
+    /// view them as sanitized without copying them
+    pub fn try_from_slice(transactions: &[Transaction]) -> Result<&[Self]> {
+        for transaction in transactions {
+            transaction.sanitize()?;
+        }
+        // Safe because `SanitizedTransaction` is `repr(transparent)` over `Transaction`
+        Ok(unsafe {
+            std::slice::from_raw_parts(transactions.as_ptr() as *const Self, transactions.len())
+        })
+    }
+
+    pub fn into_transaction(self) -> Transaction {
+        self.0
+    }
+
+    /// View `transactions` as the transactions they wrap, for code that only reads them
+    pub fn as_transactions(transactions: &[Self]) -> &[Transaction] {
+        // Safe because `SanitizedTransaction` is `repr(transparent)` over `Transaction`
+        unsafe {
+            std::slice::from_raw_parts(
+                transactions.as_ptr() as *const Transaction,
+                transactions.len(),
+            )
+        }
+    }
+}
+
+impl Deref for SanitizedTransaction {
+    type Target = Transaction;
+
+    fn deref(&self) -> &Transaction {
+        &self.0
+    }
 }
 
 #[cfg(test)]
@@ -353,6 +518,67 @@ mod tests {
         assert!(!tx.verify_refs());
     }
 
+    #[test]
+    fn test_sanitize() {
+        let keypair = Keypair::new();
+        let to = Pubkey::new_rand();
+        let ix = system_instruction::transfer(&keypair.pubkey(), &to, 1);
+        let tx = Transaction::new_signed_instructions(&[&keypair], vec![ix], Hash::default());
+        assert_eq!(tx.sanitize(), Ok(()));
+        let sanitized = SanitizedTransaction::try_new(tx.clone()).unwrap();
+        assert_eq!(*sanitized, tx);
+        assert_eq!(
+            SanitizedTransaction::as_transactions(&[sanitized.clone()]),
+            &[tx.clone()]
+        );
+        assert_eq!(sanitized.into_transaction(), tx);
+
+        let mut too_many_signatures = tx.clone();
+        too_many_signatures.signatures.push(Signature::default());
+        assert_eq!(
+            too_many_signatures.sanitize(),
+            Err(TransactionError::InvalidSignatureCount)
+        );
+
+        let mut duplicate_keys = tx.clone();
+        duplicate_keys.message.account_keys[1] = keypair.pubkey();
+        assert_eq!(
+            duplicate_keys.sanitize(),
+            Err(TransactionError::AccountLoadedTwice)
+        );
+
+        let mut bad_header = tx.clone();
+        bad_header.message.header.num_credit_only_unsigned_accounts = 3;
+        assert_eq!(
+            bad_header.sanitize(),
+            Err(TransactionError::InvalidAccountIndex)
+        );
+
+        let mut bad_index = tx.clone();
+        bad_index.message.instructions[0].accounts[0] = 42;
+        assert_eq!(
+            SanitizedTransaction::try_new(bad_index),
+            Err(TransactionError::InvalidAccountIndex)
+        );
+        assert_eq!(
+            SanitizedTransaction::try_new_all(vec![tx.clone(), too_many_signatures]),
+            Err(TransactionError::InvalidSignatureCount)
+        );
+        assert_eq!(
+            SanitizedTransaction::try_from_slice(&[tx.clone(), too_many_signatures.clone()]),
+            Err(TransactionError::InvalidSignatureCount)
+        );
+        let transactions = [tx.clone()];
+        assert_eq!(
+            SanitizedTransaction::try_from_slice(&transactions),
+            Ok(&[SanitizedTransaction::try_new(tx.clone()).unwrap()][..])
+        );
+        assert_eq!(
+            SanitizedTransaction::try_new_all(vec![tx]).unwrap().len(),
+            1
+        );
+    }
+
     fn create_sample_transaction() -> Transaction {
         let keypair = Keypair::from_bytes(&[
             48, 83, 2, 1, 1, 48, 5, 6, 3, 43, 101, 112, 4, 34, 4, 32, 255, 101, 36, 24, 124, 23,
@@ -539,4 +765,31 @@ mod tests {
         );
         assert!(tx.is_signed());
     }
+
+    #[test]
+    fn test_transaction_check_packet_fit() {
+        let keypair = Keypair::new();
+        let to = Pubkey::new_rand();
+        let ix = system_instruction::transfer(&keypair.pubkey(), &to, 1);
+        let tx = Transaction::new_signed_instructions(&[&keypair], vec![ix], Hash::default());
+        assert_eq!(tx.serialized_size(), serialized_size(&tx).unwrap() as usize);
+        assert_eq!(tx.check_packet_fit(), Ok(()));
+
+        // More account keys than fit in a packet on their own
+        let num_keys = PACKET_DATA_SIZE / size_of::<Pubkey>();
+        let accounts = (0..num_keys)
+            .map(|_| AccountMeta::new(Pubkey::new_rand(), false))
+            .collect();
+        let program_id = Pubkey::new_rand();
+        let ix = Instruction::new(program_id, &(), accounts);
+        let tx = Transaction::new_signed_instructions(&[&keypair], vec![ix], Hash::default());
+        let err = tx.check_packet_fit().unwrap_err();
+        assert_eq!(err.size, tx.serialized_size());
+        assert!(err.size > PACKET_DATA_SIZE);
+        assert_eq!(err.num_signatures, 1);
+        assert_eq!(err.num_account_keys, num_keys + 2);
+        assert!(err
+            .to_string()
+            .contains(&format!("{} bytes over", err.size - PACKET_DATA_SIZE)));
+    }
 }
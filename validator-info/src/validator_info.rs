@@ -6,6 +6,7 @@ use reqwest::Client;
 use serde_derive::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcRequest;
 use solana_config_api::{config_instruction, config_instruction::ConfigKeys, ConfigState};
 use solana_sdk::account::Account;
 use solana_sdk::message::Message;
@@ -19,6 +20,7 @@ pub const MAX_SHORT_FIELD_LENGTH: usize = 70;
 pub const MAX_LONG_FIELD_LENGTH: usize = 300;
 pub const MAX_VALIDATOR_INFO: u64 = 576;
 pub const JSON_RPC_URL: &str = "http://testnet.solana.com:8899";
+pub const LOCAL_JSON_RPC_URL: &str = "http://127.0.0.1:8899";
 
 // Config account key: Va1idator1nfo111111111111111111111111111111
 pub const REGISTER_CONFIG_KEY: [u8; 32] = [
@@ -163,6 +165,38 @@ fn parse_validator_info(
     }
 }
 
+/// How long a stage of a validator's pipeline takes to process a batch, in microseconds, as
+/// reported by the validator's getPipelineLatencies RPC method
+#[derive(Debug, Deserialize, PartialEq)]
+struct StageLatency {
+    stage: String,
+    count: u64,
+    p50: u64,
+    p90: u64,
+    p99: u64,
+}
+
+fn format_stage_latencies(stages: &[StageLatency]) -> String {
+    if stages.is_empty() {
+        return "No pipeline latencies recorded yet".to_string();
+    }
+    let mut report = format!(
+        "{:<12} {:>10} {:>12} {:>12} {:>12}",
+        "Stage", "Batches", "p50 (ms)", "p90 (ms)", "p99 (ms)"
+    );
+    for latency in stages {
+        report.push_str(&format!(
+            "\n{:<12} {:>10} {:>12.3} {:>12.3} {:>12.3}",
+            latency.stage,
+            latency.count,
+            latency.p50 as f64 / 1000.0,
+            latency.p90 as f64 / 1000.0,
+            latency.p99 as f64 / 1000.0
+        ));
+    }
+    report
+}
+
 fn main() -> Result<(), Box<dyn error::Error>> {
     let matches = App::new(crate_name!())
         .about(crate_description!())
@@ -262,6 +296,21 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                         .help("The pubkey of the Validator info account; without this argument, returns all"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("perf")
+                .about("Report the latency of each stage of a local validator's pipeline")
+                .setting(AppSettings::DisableVersion)
+                .arg(
+                    Arg::with_name("json_rpc_url")
+                        .short("u")
+                        .long("url")
+                        .value_name("URL")
+                        .takes_value(true)
+                        .default_value(LOCAL_JSON_RPC_URL)
+                        .validator(is_url)
+                        .help("JSON RPC URL of the validator"),
+                ),
+        )
         .get_matches();
 
     match matches.subcommand() {
@@ -391,6 +440,14 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 }
             }
         }
+        ("perf", Some(matches)) => {
+            let json_rpc_url = matches.value_of("json_rpc_url").unwrap();
+            let rpc_client = RpcClient::new(json_rpc_url.to_string());
+            let response =
+                rpc_client.retry_make_rpc_request(&RpcRequest::GetPipelineLatencies, None, 0)?;
+            let stages: Vec<StageLatency> = serde_json::from_value(response)?;
+            println!("{}", format_stage_latencies(&stages));
+        }
         _ => unreachable!(),
     }
 
@@ -487,6 +544,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_format_stage_latencies() {
+        let stages: Vec<StageLatency> = serde_json::from_str(
+            r#"[{"stage":"banking","count":42,"p50":1500,"p90":2000,"p99":4000}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            format_stage_latencies(&stages),
+            "Stage           Batches     p50 (ms)     p90 (ms)     p99 (ms)\n\
+             banking              42        1.500        2.000        4.000"
+        );
+        assert_eq!(
+            format_stage_latencies(&[]),
+            "No pipeline latencies recorded yet"
+        );
+    }
+
     #[test]
     fn test_validator_info_max_space() {
         // 70-character string
@@ -1,9 +1,13 @@
-use clap::{crate_description, crate_name, crate_version, App, Arg};
+use clap::{crate_description, crate_name, crate_version, value_t, App, Arg};
 use log::*;
-use solana::cluster_info::{Node, FULLNODE_PORT_RANGE};
+use solana::blockstream::new_sink;
+use solana::cluster_info::{Node, NodePortConfig, FULLNODE_PORT_RANGE};
 use solana::contact_info::ContactInfo;
+use solana::io_probe::{self, ACCOUNTS_IO_RECOMMENDATION, LEDGER_IO_RECOMMENDATION};
 use solana::ledger_cleanup_service::DEFAULT_MAX_LEDGER_SLOTS;
 use solana::local_vote_signer_service::LocalVoteSignerService;
+use solana::quic_streamer::QuicTpuConfig;
+use solana::repair_service::{RpcRepairConfig, DEFAULT_RPC_REPAIR_TIMEOUT_SECS};
 use solana::service::Service;
 use solana::socketaddr;
 use solana::validator::{Validator, ValidatorConfig};
@@ -13,6 +17,7 @@ use std::fs::File;
 use std::net::SocketAddr;
 use std::process::exit;
 use std::sync::Arc;
+use std::time::Duration;
 
 fn port_range_validator(port_range: String) -> Result<(), String> {
     if parse_port_range(&port_range).is_some() {
@@ -22,12 +27,54 @@ fn port_range_validator(port_range: String) -> Result<(), String> {
     }
 }
 
+fn port_validator(port: String) -> Result<(), String> {
+    port.parse::<u16>()
+        .map(|_| ())
+        .map_err(|e| format!("{:?}", e))
+}
+
+/// The services that can be given a port of their own, by argument name, flag and help
+const SERVICE_PORTS: &[(&str, &str, &str)] = &[
+    (
+        "tvu_port",
+        "tvu-port",
+        "Port to receive blobs from the cluster on",
+    ),
+    ("tpu_port", "tpu-port", "Port to receive transactions on"),
+    (
+        "tpu_via_blobs_port",
+        "tpu-via-blobs-port",
+        "Port to receive transactions forwarded by other validators on",
+    ),
+    (
+        "tpu_quic_port",
+        "tpu-quic-port",
+        "Port to receive transactions over QUIC on, with --enable-quic-tpu",
+    ),
+    (
+        "repair_port",
+        "repair-port",
+        "Port to send repair requests from",
+    ),
+    (
+        "retransmit_port",
+        "retransmit-port",
+        "Port to retransmit blobs from",
+    ),
+    (
+        "broadcast_port",
+        "broadcast-port",
+        "Port to broadcast blobs from",
+    ),
+];
+
 fn main() {
     solana_logger::setup_with_filter("solana=info");
     solana_metrics::set_panic_hook("validator");
 
     let default_dynamic_port_range =
         &format!("{}-{}", FULLNODE_PORT_RANGE.0, FULLNODE_PORT_RANGE.1);
+    let default_rpc_repair_timeout = &DEFAULT_RPC_REPAIR_TIMEOUT_SECS.to_string();
 
     let matches = App::new(crate_name!()).about(crate_description!())
         .version(crate_version!())
@@ -35,8 +82,10 @@ fn main() {
             Arg::with_name("blockstream")
                 .long("blockstream")
                 .takes_value(true)
-                .value_name("UNIX DOMAIN SOCKET")
-                .help("Open blockstream at this unix domain socket location")
+                .value_name("LOCATION")
+                .validator(|location| new_sink(&location).map(|_| ()))
+                .help("Stream entries and blocks to LOCATION: a unix domain socket path, \
+                       tcp:HOST:PORT, or file:PATH for a set of rotating files")
         )
         .arg(
             Arg::with_name("identity")
@@ -78,6 +127,7 @@ fn main() {
             Arg::with_name("ledger")
                 .short("l")
                 .long("ledger")
+                .alias("ledger-path")
                 .value_name("DIR")
                 .takes_value(true)
                 .required(true)
@@ -97,6 +147,20 @@ fn main() {
                 .takes_value(false)
                 .help("Launch node without voting"),
         )
+        .arg(
+            Arg::with_name("rpc_only")
+                .long("rpc-only")
+                .takes_value(false)
+                .requires("rpc_port")
+                .help("Replay the ledger and serve RPC without a TPU.  Implies --no-voting"),
+        )
+        .arg(
+            Arg::with_name("dev_halt_at_slot")
+                .long("dev-halt-at-slot")
+                .value_name("SLOT")
+                .takes_value(true)
+                .help("Stop processing the ledger past this slot, for debugging"),
+        )
         .arg(
             Arg::with_name("no_sigverify")
                 .short("v")
@@ -117,6 +181,13 @@ fn main() {
                 .takes_value(false)
                 .help("Enable the JSON RPC 'fullnodeExit' API.  Only enable in a debug environment"),
         )
+        .arg(
+            Arg::with_name("prometheus_port")
+                .long("prometheus-port")
+                .value_name("PORT")
+                .takes_value(true)
+                .help("Serve metrics for Prometheus to scrape at /metrics over HTTP on this port"),
+        )
         .arg(
             Arg::with_name("rpc_drone_addr")
                 .long("rpc-drone-address")
@@ -138,6 +209,22 @@ fn main() {
                 .takes_value(true)
                 .help("Comma separated persistent accounts location"),
         )
+        .arg(
+            Arg::with_name("accounts_path")
+                .long("accounts-path")
+                .value_name("DIR")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .conflicts_with("accounts")
+                .help("Persistent accounts location, separate from the ledger. Repeat to spread the accounts across disks"),
+        )
+        .arg(
+            Arg::with_name("no_io_probe")
+                .long("no-io-probe")
+                .takes_value(false)
+                .help("Skip measuring the speed of the accounts and ledger disks at startup"),
+        )
         .arg(
             clap::Arg::with_name("gossip_port")
                 .long("gossip-port")
@@ -154,6 +241,22 @@ fn main() {
                 .validator(port_range_validator)
                 .help("Range to use for dynamically assigned ports"),
         )
+        .arg(
+            clap::Arg::with_name("data_port_range")
+                .long("data-port-range")
+                .value_name("MIN_PORT-MAX_PORT")
+                .takes_value(true)
+                .validator(port_range_validator)
+                .help("Range to use for the tvu, repair, retransmit and broadcast ports, instead of the dynamic port range"),
+        )
+        .args(&SERVICE_PORTS.iter().map(|(name, long, help)| {
+            clap::Arg::with_name(name)
+                .long(long)
+                .value_name("PORT")
+                .takes_value(true)
+                .validator(port_validator)
+                .help(help)
+        }).collect::<Vec<_>>())
         .arg(
             clap::Arg::with_name("snapshot_path")
                 .long("snapshot-path")
@@ -173,6 +276,133 @@ fn main() {
                 .long("skip-ledger-verify")
                 .takes_value(false)
                 .help("Skip ledger verification at node bootup"),
+        )
+        .arg(
+            clap::Arg::with_name("rpc_repair_node")
+                .long("rpc-repair-node")
+                .value_name("HOST:PORT")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Fetch missing blobs from the JSON RPC API at this address when repair over UDP makes no progress"),
+        )
+        .arg(
+            clap::Arg::with_name("rpc_repair_timeout")
+                .long("rpc-repair-timeout")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .default_value(default_rpc_repair_timeout)
+                .requires("rpc_repair_node")
+                .help("How long repair over UDP may make no progress before blobs are fetched over RPC"),
+        )
+        .arg(
+            clap::Arg::with_name("gossip_leader_slots")
+                .long("gossip-leader-slots")
+                .takes_value(false)
+                .help("Publish a summary of this node's rooted and skipped leader slots to gossip"),
+        )
+        .arg(
+            clap::Arg::with_name("storage_identity_keypair")
+                .long("storage-identity-keypair")
+                .value_name("PATH")
+                .takes_value(true)
+                .conflicts_with("no_storage_mining")
+                .help("File containing the keypair that pays for storage mining transactions.  Default is the identity keypair"),
+        )
+        .arg(
+            clap::Arg::with_name("no_storage_mining")
+                .long("no-storage-mining")
+                .takes_value(false)
+                .help("Do not sample the ledger or submit storage proofs for replicators"),
+        )
+        .arg(
+            clap::Arg::with_name("trusted_validators")
+                .long("trusted-validator")
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Compare this node's accounts hash at each epoch boundary with the one gossiped by this validator"),
+        )
+        .arg(
+            clap::Arg::with_name("halt_on_trusted_validators_accounts_hash_mismatch")
+                .long("halt-on-trusted-validators-accounts-hash-mismatch")
+                .takes_value(false)
+                .requires("trusted_validators")
+                .help("Halt the validator when its accounts hash differs from a trusted validator's"),
+        )
+        .arg(
+            clap::Arg::with_name("max_fork_depth")
+                .long("max-fork-depth")
+                .value_name("SLOTS")
+                .takes_value(true)
+                .help("Prune banks more than this many slots past the root"),
+        )
+        .arg(
+            clap::Arg::with_name("max_active_banks")
+                .long("max-active-banks")
+                .value_name("COUNT")
+                .takes_value(true)
+                .help("Keep at most this many banks past the root, evicting the lightest stale forks to make room for new ones"),
+        )
+        .arg(
+            clap::Arg::with_name("account_update_plugin_config")
+                .long("account-update-plugin-config")
+                .value_name("FILE")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Load the account update plugin described by this JSON config file, which names the plugin library with \"libpath\""),
+        )
+        .arg(
+            clap::Arg::with_name("ledger_storage")
+                .long("ledger-storage")
+                .value_name("DIR")
+                .takes_value(true)
+                .help("Serve blocks and transactions the ledger no longer has over JSON RPC from the long-term ledger storage in this directory"),
+        )
+        .arg(
+            clap::Arg::with_name("upload_ledger")
+                .long("upload-ledger")
+                .takes_value(false)
+                .requires("ledger_storage")
+                .help("Upload rooted blocks to the long-term ledger storage"),
+        )
+        .arg(
+            clap::Arg::with_name("enable_quic_tpu")
+                .long("enable-quic-tpu")
+                .takes_value(false)
+                .help("Also accept transactions over QUIC, on a port advertised in gossip [experimental]"),
+        )
+        .arg(
+            clap::Arg::with_name("quic_tpu_max_connections_per_ip")
+                .long("quic-tpu-max-connections-per-ip")
+                .value_name("COUNT")
+                .takes_value(true)
+                .requires("enable_quic_tpu")
+                .help("Refuse QUIC TPU connections from an IP address that already has this many open"),
+        )
+        .arg(
+            clap::Arg::with_name("quic_tpu_max_streams_per_second")
+                .long("quic-tpu-max-streams-per-second")
+                .value_name("COUNT")
+                .takes_value(true)
+                .requires("enable_quic_tpu")
+                .help("Drop the transactions a QUIC TPU connection sends beyond this many a second"),
+        )
+        .arg(
+            clap::Arg::with_name("max_coalesce_ms")
+                .long("max-coalesce-ms")
+                .value_name("MILLISECONDS")
+                .takes_value(true)
+                .help("Wait at most this long for more transactions to batch once one arrives, when signature verification is busiest"),
+        )
+        .arg(
+            clap::Arg::with_name("max_batch_packets")
+                .long("max-batch-packets")
+                .value_name("COUNT")
+                .takes_value(true)
+                .help("Batch at most this many transactions for signature verification, when it's busiest"),
         )
          .get_matches();
 
@@ -213,6 +443,43 @@ fn main() {
     validator_config.sigverify_disabled = matches.is_present("no_sigverify");
 
     validator_config.voting_disabled = matches.is_present("no_voting");
+    validator_config.rpc_only = matches.is_present("rpc_only");
+    if matches.is_present("dev_halt_at_slot") {
+        validator_config.dev_halt_at_slot =
+            Some(value_t!(matches, "dev_halt_at_slot", u64).unwrap_or_else(|err| err.exit()));
+    }
+
+    validator_config.storage_identity_keypair = matches
+        .value_of("storage_identity_keypair")
+        .map(|path| path.to_string());
+    validator_config.storage_mining_disabled = matches.is_present("no_storage_mining");
+
+    validator_config.gossip_leader_slots = matches.is_present("gossip_leader_slots");
+
+    if let Some(trusted_validators) = matches.values_of("trusted_validators") {
+        validator_config.trusted_validators = Some(
+            trusted_validators
+                .map(|pubkey| pubkey.parse().expect("failed to parse trusted validator"))
+                .collect(),
+        );
+    }
+    validator_config.halt_on_trusted_validators_accounts_hash_mismatch =
+        matches.is_present("halt_on_trusted_validators_accounts_hash_mismatch");
+
+    if let Some(config_files) = matches.values_of("account_update_plugin_config") {
+        validator_config.account_update_plugin_config_files =
+            config_files.map(ToString::to_string).collect();
+    }
+
+    if let Some(max_depth) = matches.value_of("max_fork_depth") {
+        validator_config.fork_pruning_config.max_depth =
+            max_depth.parse().expect("failed to parse max-fork-depth");
+    }
+    if let Some(max_active_banks) = matches.value_of("max_active_banks") {
+        validator_config.fork_pruning_config.max_active_banks = max_active_banks
+            .parse()
+            .expect("failed to parse max-active-banks");
+    }
 
     if matches.is_present("enable_rpc_exit") {
         validator_config.rpc_config.enable_fullnode_exit = true;
@@ -220,6 +487,33 @@ fn main() {
     validator_config.rpc_config.drone_addr = matches.value_of("rpc_drone_addr").map(|address| {
         solana_netutil::parse_host_port(address).expect("failed to parse drone address")
     });
+    validator_config.rpc_config.ledger_storage_path =
+        matches.value_of("ledger_storage").map(ToString::to_string);
+    validator_config.upload_ledger = matches.is_present("upload_ledger");
+    if matches.is_present("enable_quic_tpu") {
+        let mut quic_tpu_config = QuicTpuConfig::default();
+        if let Some(max_connections) = matches.value_of("quic_tpu_max_connections_per_ip") {
+            quic_tpu_config.max_connections_per_ip = max_connections
+                .parse()
+                .expect("failed to parse quic-tpu-max-connections-per-ip");
+        }
+        if let Some(max_streams) = matches.value_of("quic_tpu_max_streams_per_second") {
+            quic_tpu_config.max_streams_per_second = max_streams
+                .parse()
+                .expect("failed to parse quic-tpu-max-streams-per-second");
+        }
+        validator_config.quic_tpu_config = Some(quic_tpu_config);
+    }
+    if let Some(max_coalesce_ms) = matches.value_of("max_coalesce_ms") {
+        validator_config.coalesce_config.max_coalesce_ms = max_coalesce_ms
+            .parse()
+            .expect("failed to parse max-coalesce-ms");
+    }
+    if let Some(max_batch_packets) = matches.value_of("max_batch_packets") {
+        validator_config.coalesce_config.max_batch_packets = max_batch_packets
+            .parse()
+            .expect("failed to parse max-batch-packets");
+    }
 
     let dynamic_port_range = parse_port_range(matches.value_of("dynamic_port_range").unwrap())
         .expect("invalid dynamic_port_range");
@@ -236,12 +530,42 @@ fn main() {
     if let Some(paths) = matches.value_of("accounts") {
         validator_config.account_paths = Some(paths.to_string());
     }
+    if let Some(paths) = matches.values_of("accounts_path") {
+        validator_config.account_paths = Some(paths.collect::<Vec<_>>().join(","));
+    }
+    if !matches.is_present("no_io_probe") {
+        if let Some(paths) = &validator_config.account_paths {
+            io_probe::warn_if_slow(
+                "Accounts",
+                &paths.split(',').collect::<Vec<_>>(),
+                &ACCOUNTS_IO_RECOMMENDATION,
+            );
+        }
+        io_probe::warn_if_slow("Ledger", &[ledger_path], &LEDGER_IO_RECOMMENDATION);
+    }
     if let Some(paths) = matches.value_of("snapshot_path") {
         validator_config.snapshot_path = Some(paths.to_string());
     }
     if matches.is_present("limit_ledger_size") {
         validator_config.max_ledger_slots = Some(DEFAULT_MAX_LEDGER_SLOTS);
     }
+    if let Some(rpc_repair_nodes) = matches.values_of("rpc_repair_node") {
+        let rpc_addrs = rpc_repair_nodes
+            .map(|address| {
+                solana_netutil::parse_host_port(address)
+                    .expect("failed to parse rpc repair node address")
+            })
+            .collect();
+        let timeout = matches
+            .value_of("rpc_repair_timeout")
+            .unwrap()
+            .parse()
+            .expect("failed to parse rpc repair timeout");
+        validator_config.rpc_repair_config = Some(RpcRepairConfig {
+            rpc_addrs,
+            timeout: Duration::from_secs(timeout),
+        });
+    }
     let cluster_entrypoint = matches.value_of("entrypoint").map(|entrypoint| {
         let entrypoint_addr = solana_netutil::parse_host_port(entrypoint)
             .expect("failed to parse entrypoint address");
@@ -267,7 +591,23 @@ fn main() {
     validator_config.blockstream = matches.value_of("blockstream").map(ToString::to_string);
 
     let keypair = Arc::new(keypair);
-    let mut node = Node::new_with_external_ip(&keypair.pubkey(), &gossip_addr, dynamic_port_range);
+    let mut port_config = NodePortConfig::new(dynamic_port_range);
+    port_config.data_port_range = matches
+        .value_of("data_port_range")
+        .map(|range| parse_port_range(range).expect("invalid data_port_range"));
+    let service_port = |name| value_t!(matches, name, u16).ok();
+    port_config.tvu_port = service_port("tvu_port");
+    port_config.tpu_port = service_port("tpu_port");
+    port_config.tpu_via_blobs_port = service_port("tpu_via_blobs_port");
+    port_config.tpu_quic_port = service_port("tpu_quic_port");
+    port_config.repair_port = service_port("repair_port");
+    port_config.retransmit_port = service_port("retransmit_port");
+    port_config.broadcast_port = service_port("broadcast_port");
+    let mut node = Node::new_with_port_config(&keypair.pubkey(), &gossip_addr, &port_config)
+        .unwrap_or_else(|err| {
+            eprintln!("Unable to bind the node's ports: {}", err);
+            exit(1);
+        });
     if let Some(port) = matches.value_of("rpc_port") {
         let port_number = port.to_string().parse().expect("integer");
         if port_number == 0 {
@@ -278,6 +618,17 @@ fn main() {
         node.info.rpc_pubsub = SocketAddr::new(gossip_addr.ip(), port_number + 1);
     };
 
+    if let Some(port) = matches.value_of("prometheus_port") {
+        let port_number = port.to_string().parse().expect("integer");
+        solana_metrics::serve_prometheus(socketaddr!(0, port_number)).unwrap_or_else(|err| {
+            eprintln!(
+                "Unable to serve Prometheus metrics on port {}: {}",
+                port, err
+            );
+            exit(1);
+        });
+    }
+
     let verify_ledger = !matches.is_present("skip_ledger_verify");
 
     let validator = Validator::new(
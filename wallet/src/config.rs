@@ -0,0 +1,335 @@
+//! The `config` module persists the wallet's default JSON RPC URL and keypair, in named
+//! profiles, so that they don't need to be passed to every command
+
+use crate::wallet::WalletError;
+use clap::{App, AppSettings, Arg, ArgGroup, ArgMatches, SubCommand};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error;
+use std::fs::{create_dir_all, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+lazy_static! {
+    pub static ref CONFIG_FILE: Option<String> = {
+        dirs::home_dir().map(|mut path| {
+            path.extend(&[".config", "solana", "cli.yml"]);
+            path.to_str().unwrap().to_string()
+        })
+    };
+    pub static ref USER_KEYPAIR: Option<String> = {
+        dirs::home_dir().map(|mut path| {
+            path.extend(&[".config", "solana", "id.json"]);
+            path.to_str().unwrap().to_string()
+        })
+    };
+}
+
+/// The settings a profile provides to every command
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub url: String,
+    /// Path of the keypair file, `USER_KEYPAIR` if `None`
+    pub keypair: Option<String>,
+}
+
+impl Profile {
+    fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            keypair: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Config {
+    /// Name of the profile used unless `--profile` is passed
+    pub profile: String,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let mut profiles = BTreeMap::new();
+        profiles.insert(
+            "devnet".to_string(),
+            Profile::new("http://devnet.solana.com:8899"),
+        );
+        profiles.insert(
+            "localhost".to_string(),
+            Profile::new("http://127.0.0.1:8899"),
+        );
+        profiles.insert(
+            "testnet".to_string(),
+            Profile::new("http://testnet.solana.com:8899"),
+        );
+        Self {
+            profile: "testnet".to_string(),
+            profiles,
+        }
+    }
+}
+
+impl Config {
+    fn _load(config_file: &str) -> Result<Self, io::Error> {
+        let file = File::open(config_file.to_string())?;
+        let config = serde_yaml::from_reader(file)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{:?}", err)))?;
+        Ok(config)
+    }
+
+    /// Load the config at `config_file`, or the default config if there is no such file
+    pub fn load(config_file: &str) -> Result<Self, String> {
+        if !Path::new(config_file).exists() {
+            return Ok(Self::default());
+        }
+        Self::_load(config_file).map_err(|err| format!("Unable to load {}: {:?}", config_file, err))
+    }
+
+    fn _save(&self, config_file: &str) -> Result<(), io::Error> {
+        let serialized = serde_yaml::to_string(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{:?}", err)))?;
+
+        if let Some(outdir) = Path::new(&config_file).parent() {
+            create_dir_all(outdir)?;
+        }
+        let mut file = File::create(config_file)?;
+        file.write_all(&serialized.into_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn save(&self, config_file: &str) -> Result<(), String> {
+        self._save(config_file)
+            .map_err(|err| format!("Unable to save {}: {:?}", config_file, err))
+    }
+
+    /// The profile named `name`, or the active profile if `name` is `None`
+    pub fn profile(&self, name: Option<&str>) -> Result<&Profile, WalletError> {
+        let name = name.unwrap_or(&self.profile);
+        self.profiles
+            .get(name)
+            .ok_or_else(|| WalletError::BadParameter(format!("Unknown profile: {}", name)))
+    }
+}
+
+// Return an error if a url cannot be parsed.
+pub fn is_url(string: String) -> Result<(), String> {
+    match url::Url::parse(&string) {
+        Ok(url) => {
+            if url.has_host() {
+                Ok(())
+            } else {
+                Err("no host provided".to_string())
+            }
+        }
+        Err(err) => Err(format!("{:?}", err)),
+    }
+}
+
+pub fn config_subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("config")
+        .about("Solana wallet configuration settings")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("get")
+                .about("Get the settings of the active profile")
+                .arg(
+                    Arg::with_name("setting")
+                        .index(1)
+                        .value_name("SETTING")
+                        .takes_value(true)
+                        .possible_values(&["url", "keypair", "profile"])
+                        .help("Return a single setting"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set")
+                .about("Set a setting, or switch profiles")
+                .arg(
+                    Arg::with_name("profile")
+                        .long("profile")
+                        .value_name("NAME")
+                        .takes_value(true)
+                        .help("Make this the active profile, creating it if --url is also passed"),
+                )
+                .arg(
+                    Arg::with_name("url")
+                        .short("u")
+                        .long("url")
+                        .value_name("URL")
+                        .takes_value(true)
+                        .validator(is_url)
+                        .help("Set the JSON RPC URL of the profile"),
+                )
+                .arg(
+                    Arg::with_name("keypair")
+                        .short("k")
+                        .long("keypair")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("Set the keypair file of the profile"),
+                )
+                .group(
+                    ArgGroup::with_name("config_settings")
+                        .args(&["profile", "url", "keypair"])
+                        .multiple(true)
+                        .required(true),
+                ),
+        )
+}
+
+fn format_settings(config_file: &str, config: &Config) -> Result<String, WalletError> {
+    let profile = config.profile(None)?;
+    let keypair = profile
+        .keypair
+        .as_ref()
+        .or_else(|| USER_KEYPAIR.as_ref())
+        .map_or("", String::as_str);
+    Ok(format!(
+        "Config File: {}\nProfile: {}\nRPC URL: {}\nKeypair Path: {}\nProfiles: {}",
+        config_file,
+        config.profile,
+        profile.url,
+        keypair,
+        config
+            .profiles
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+/// Process `config get` and `config set`, returning what to print
+pub fn process_config(
+    config_file: &str,
+    matches: &ArgMatches<'_>,
+) -> Result<String, Box<dyn error::Error>> {
+    let mut config = Config::load(config_file)?;
+    match matches.subcommand() {
+        ("get", Some(get_matches)) => {
+            let profile = config.profile(None)?;
+            match get_matches.value_of("setting") {
+                Some("url") => Ok(profile.url.clone()),
+                Some("keypair") => Ok(profile
+                    .keypair
+                    .as_ref()
+                    .or_else(|| USER_KEYPAIR.as_ref())
+                    .cloned()
+                    .unwrap_or_default()),
+                Some("profile") => Ok(config.profile.clone()),
+                _ => Ok(format_settings(config_file, &config)?),
+            }
+        }
+        ("set", Some(set_matches)) => {
+            if let Some(name) = set_matches.value_of("profile") {
+                if !config.profiles.contains_key(name) {
+                    let url = set_matches.value_of("url").ok_or_else(|| {
+                        WalletError::BadParameter(format!(
+                            "Unknown profile: {}, pass --url to create it",
+                            name
+                        ))
+                    })?;
+                    config.profiles.insert(name.to_string(), Profile::new(url));
+                }
+                config.profile = name.to_string();
+            }
+
+            let name = config.profile.clone();
+            let profile = config
+                .profiles
+                .get_mut(&name)
+                .ok_or_else(|| WalletError::BadParameter(format!("Unknown profile: {}", name)))?;
+            if let Some(url) = set_matches.value_of("url") {
+                profile.url = url.to_string();
+            }
+            if let Some(keypair) = set_matches.value_of("keypair") {
+                profile.keypair = Some(keypair.to_string());
+            }
+            config.save(config_file)?;
+            Ok(format_settings(config_file, &config)?)
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+    use std::fs::remove_file;
+
+    fn tmp_config_file(name: &str) -> String {
+        let out_dir = std::env::var("FARF_DIR").unwrap_or_else(|_| "farf".to_string());
+        format!("{}/tmp/{}-{}/cli.yml", out_dir, name, Pubkey::new_rand())
+    }
+
+    fn run_config_command(config_file: &str, args: &[&str]) -> Result<String, String> {
+        let app = App::new("test").subcommand(config_subcommand());
+        let matches = app.get_matches_from(vec!["test", "config"].into_iter().chain(args.to_vec()));
+        process_config(config_file, matches.subcommand_matches("config").unwrap())
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn test_config_default() {
+        let config = Config::load("does-not-exist.yml").unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(
+            config.profile(None).unwrap().url,
+            "http://testnet.solana.com:8899"
+        );
+        assert_eq!(
+            config.profile(Some("localhost")).unwrap().url,
+            "http://127.0.0.1:8899"
+        );
+        assert!(config.profile(Some("devnet")).is_ok());
+        assert!(config.profile(Some("nonexistent")).is_err());
+    }
+
+    #[test]
+    fn test_process_config() {
+        let config_file = tmp_config_file("test_process_config");
+
+        run_config_command(&config_file, &["set", "--profile", "localhost"]).unwrap();
+        assert_eq!(
+            run_config_command(&config_file, &["get", "url"]).unwrap(),
+            "http://127.0.0.1:8899"
+        );
+
+        run_config_command(&config_file, &["set", "--keypair", "my-id.json"]).unwrap();
+        assert_eq!(
+            run_config_command(&config_file, &["get", "keypair"]).unwrap(),
+            "my-id.json"
+        );
+
+        // Switching profiles keeps the settings of the previous one
+        run_config_command(&config_file, &["set", "--profile", "testnet"]).unwrap();
+        let config = Config::load(&config_file).unwrap();
+        assert_eq!(config.profile, "testnet");
+        assert_eq!(
+            config.profile(Some("localhost")).unwrap().keypair,
+            Some("my-id.json".to_string())
+        );
+
+        // A new profile needs a url
+        assert!(run_config_command(&config_file, &["set", "--profile", "mine"]).is_err());
+        run_config_command(
+            &config_file,
+            &["set", "--profile", "mine", "--url", "http://10.0.0.1:8899"],
+        )
+        .unwrap();
+        assert_eq!(
+            run_config_command(&config_file, &["get", "profile"]).unwrap(),
+            "mine"
+        );
+        assert_eq!(
+            run_config_command(&config_file, &["get", "url"]).unwrap(),
+            "http://10.0.0.1:8899"
+        );
+
+        remove_file(&config_file).unwrap();
+    }
+}
@@ -1 +1,5 @@
+#[macro_use]
+extern crate lazy_static;
+
+pub mod config;
 pub mod wallet;
@@ -1,10 +1,20 @@
 use clap::{crate_description, crate_name, crate_version, Arg, ArgMatches};
 use solana_sdk::signature::{gen_keypair_file, read_keypair, KeypairUtil};
-use solana_wallet::wallet::{app, parse_command, process_command, WalletConfig, WalletError};
+use solana_wallet::config::{self, is_url, Config};
+use solana_wallet::wallet::{
+    app, parse_command, parse_signing_config, process_command, WalletConfig, WalletError,
+};
 use std::error;
 
-pub fn parse_args(matches: &ArgMatches<'_>) -> Result<WalletConfig, Box<dyn error::Error>> {
-    let json_rpc_url = matches.value_of("json_rpc_url").unwrap().to_string();
+pub fn parse_args(
+    matches: &ArgMatches<'_>,
+    config: &Config,
+) -> Result<WalletConfig, Box<dyn error::Error>> {
+    let profile = config.profile(matches.value_of("profile"))?;
+    let json_rpc_url = matches
+        .value_of("json_rpc_url")
+        .unwrap_or(&profile.url)
+        .to_string();
 
     let drone_host = if let Some(drone_host) = matches.value_of("drone_host") {
         Some(solana_netutil::parse_host(drone_host).or_else(|err| {
@@ -31,6 +41,8 @@ pub fn parse_args(matches: &ArgMatches<'_>) -> Result<WalletConfig, Box<dyn erro
     let mut path = dirs::home_dir().expect("home directory");
     let id_path = if matches.is_present("keypair") {
         matches.value_of("keypair").unwrap()
+    } else if let Some(keypair) = &profile.keypair {
+        keypair.as_str()
     } else {
         path.extend(&[".config", "solana", "id.json"]);
         if !path.exists() {
@@ -48,6 +60,7 @@ pub fn parse_args(matches: &ArgMatches<'_>) -> Result<WalletConfig, Box<dyn erro
     })?;
 
     let command = parse_command(&keypair.pubkey(), &matches)?;
+    let signing = parse_signing_config(&matches);
 
     Ok(WalletConfig {
         command,
@@ -55,24 +68,12 @@ pub fn parse_args(matches: &ArgMatches<'_>) -> Result<WalletConfig, Box<dyn erro
         drone_port,
         json_rpc_url,
         keypair,
+        lamports_only: matches.is_present("lamports_only"),
         rpc_client: None,
+        signing,
     })
 }
 
-// Return an error if a url cannot be parsed.
-fn is_url(string: String) -> Result<(), String> {
-    match url::Url::parse(&string) {
-        Ok(url) => {
-            if url.has_host() {
-                Ok(())
-            } else {
-                Err("no host provided".to_string())
-            }
-        }
-        Err(err) => Err(format!("{:?}", err)),
-    }
-}
-
 fn main() -> Result<(), Box<dyn error::Error>> {
     solana_logger::setup();
 
@@ -80,15 +81,34 @@ fn main() -> Result<(), Box<dyn error::Error>> {
     let default_drone_port = format!("{}", default.drone_port);
 
     let matches = app(crate_name!(), crate_description!(), crate_version!())
+        .arg({
+            let arg = Arg::with_name("config_file")
+                .short("C")
+                .long("config")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .help("Configuration file to use");
+            match *config::CONFIG_FILE {
+                Some(ref config_file) => arg.default_value(&config_file),
+                None => arg.required(true),
+            }
+        })
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .value_name("NAME")
+                .takes_value(true)
+                .help("Configuration profile to use [default: the active profile]"),
+        )
         .arg(
             Arg::with_name("json_rpc_url")
                 .short("u")
                 .long("url")
                 .value_name("URL")
                 .takes_value(true)
-                .default_value(&default.json_rpc_url)
                 .validator(is_url)
-                .help("JSON RPC URL for the solana cluster"),
+                .help("JSON RPC URL for the solana cluster [default: the url of the profile]"),
         )
         .arg(
             Arg::with_name("drone_host")
@@ -111,11 +131,23 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 .long("keypair")
                 .value_name("PATH")
                 .takes_value(true)
-                .help("/path/to/id.json"),
+                .help("/path/to/id.json [default: the keypair of the profile]"),
+        )
+        .arg(
+            Arg::with_name("lamports_only")
+                .long("lamports")
+                .takes_value(false)
+                .help("Display amounts in lamports only, instead of in SOL and lamports"),
         )
         .get_matches();
 
-    let config = parse_args(&matches)?;
+    let config_file = matches.value_of("config_file").unwrap();
+    if let ("config", Some(config_matches)) = matches.subcommand() {
+        println!("{}", config::process_config(config_file, config_matches)?);
+        return Ok(());
+    }
+
+    let config = parse_args(&matches, &Config::load(config_file)?)?;
     let result = process_command(&config)?;
     println!("{}", result);
     Ok(())
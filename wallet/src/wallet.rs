@@ -1,3 +1,4 @@
+use crate::config::config_subcommand;
 use chrono::prelude::*;
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use log::*;
@@ -5,32 +6,40 @@ use num_traits::FromPrimitive;
 use serde_json;
 use serde_json::json;
 use solana_budget_api;
+use solana_budget_api::budget_expr::Condition;
 use solana_budget_api::budget_instruction;
 use solana_budget_api::budget_state::BudgetError;
 use solana_client::client_error::ClientError;
-use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_client::{ConfirmedTransaction, RpcClient};
 #[cfg(not(test))]
 use solana_drone::drone::request_airdrop_transaction;
 use solana_drone::drone::DRONE_PORT;
 #[cfg(test)]
 use solana_drone::drone_mock::request_airdrop_transaction;
+use solana_memo_api::memo_instruction;
 use solana_sdk::account_utils::State;
 use solana_sdk::bpf_loader;
 use solana_sdk::hash::Hash;
-use solana_sdk::instruction::InstructionError;
+use solana_sdk::instruction::{AccountMeta, Instruction, InstructionError};
 use solana_sdk::instruction_processor_utils::DecodeError;
 use solana_sdk::loader_instruction;
 use solana_sdk::message::Message;
+use solana_sdk::native_token::{sol_to_lamports, Sol, LAMPORTS_PER_SOL};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{read_keypair, Keypair, KeypairUtil, Signature};
-use solana_sdk::system_instruction::SystemError;
+use solana_sdk::system_instruction::{self, SystemError};
 use solana_sdk::system_transaction;
+use solana_sdk::sysvar::clock::{self, Clock};
+use solana_sdk::timing::{Epoch, DEFAULT_NUM_TICKS_PER_SECOND, DEFAULT_TICKS_PER_SLOT};
 use solana_sdk::transaction::{Transaction, TransactionError};
 use solana_stake_api::stake_instruction;
+use solana_stake_api::stake_state::{Stake, StakeState, STAKE_WARMUP_EPOCHS};
 use solana_storage_api::storage_instruction;
+use solana_token_api::token_instruction;
+use solana_token_api::token_state::{TokenError, TokenInfo, TokenState};
 use solana_vote_api::vote_instruction;
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, Read};
 use std::net::{IpAddr, SocketAddr};
 use std::thread::sleep;
 use std::time::Duration;
@@ -38,6 +47,12 @@ use std::{error, fmt};
 
 const USERDATA_CHUNK_SIZE: usize = 229; // Keep program chunks under PACKET_DATA_SIZE
 
+/// The most slots one `getConfirmedSignaturesForAddress` request can search
+const TRANSACTION_HISTORY_SLOT_RANGE: u64 = 10_000;
+
+/// The most signatures one `getConfirmedSignaturesForAddress` request can return
+const TRANSACTION_HISTORY_PAGE_SIZE: usize = 1_000;
+
 #[derive(Debug, PartialEq)]
 #[allow(clippy::large_enum_variant)]
 pub enum WalletCommand {
@@ -48,8 +63,10 @@ pub enum WalletCommand {
     Cancel(Pubkey),
     Confirm(Signature),
     AuthorizeVoter(Pubkey, Keypair, Pubkey),
-    CreateVoteAccount(Pubkey, Pubkey, u8, u64),
+    CreateVoteAccount(Keypair, Pubkey, u8, u64),
     ShowVoteAccount(Pubkey),
+    WithdrawFromVoteAccount(Keypair, Pubkey, u64),
+    CreateStakeAccount(Keypair, u64),
     DelegateStake(Keypair, Pubkey, u64),
     WithdrawStake(Keypair, Pubkey, u64),
     DeactivateStake(Keypair),
@@ -59,21 +76,37 @@ pub enum WalletCommand {
     CreateValidatorStorageAccount(Pubkey, Pubkey),
     ClaimStorageReward(Pubkey, Pubkey),
     ShowStorageAccount(Pubkey),
+    // CreateToken(supply, decimals, name, symbol, mintable)
+    CreateToken(u64, u8, String, String, bool),
+    // CreateTokenAccount(token)
+    CreateTokenAccount(Pubkey),
+    // TransferToken(source, dest, amount)
+    TransferToken(Pubkey, Pubkey, u64),
+    ShowTokenAccount(Pubkey),
     Deploy(String),
     GetTransactionCount,
-    // Pay(lamports, to, timestamp, timestamp_pubkey, witness(es), cancelable)
+    // TransactionHistory(address, from_slot, to_slot)
+    TransactionHistory(Pubkey, u64, Option<u64>),
+    // Pay(lamports, to, timestamp, timestamp_pubkey, witness(es), require_any, cancelable,
+    //     instructions)
     Pay(
         u64,
         Pubkey,
         Option<DateTime<Utc>>,
         Option<Pubkey>,
         Option<Vec<Pubkey>>,
+        bool,
         Option<Pubkey>,
+        Vec<Instruction>,
     ),
+    // PayRecurring(lamports, to, interval, count)
+    PayRecurring(u64, Pubkey, u64, u64),
     // TimeElapsed(to, process_id, timestamp)
     TimeElapsed(Pubkey, Pubkey, DateTime<Utc>),
     // Witness(to, process_id)
     Witness(Pubkey, Pubkey),
+    // ClockElapsed(to, process_id)
+    ClockElapsed(Pubkey, Pubkey),
 }
 
 #[derive(Debug, Clone)]
@@ -101,13 +134,27 @@ impl error::Error for WalletError {
     }
 }
 
+/// Lets a transaction be signed on an offline host and sent from an online one
+#[derive(Debug, Default, PartialEq)]
+pub struct SigningConfig {
+    /// Print the blockhash and signatures instead of sending the transaction
+    pub sign_only: bool,
+    /// Sign with this blockhash instead of fetching a recent one
+    pub blockhash: Option<Hash>,
+    /// Signatures printed by `--sign-only`, to send in place of local ones
+    pub signers: Vec<(Pubkey, Signature)>,
+}
+
 pub struct WalletConfig {
     pub command: WalletCommand,
     pub drone_host: Option<IpAddr>,
     pub drone_port: u16,
     pub json_rpc_url: String,
     pub keypair: Keypair,
+    /// Display amounts in lamports only, instead of in SOL and lamports
+    pub lamports_only: bool,
     pub rpc_client: Option<RpcClient>,
+    pub signing: SigningConfig,
 }
 
 impl Default for WalletConfig {
@@ -118,7 +165,9 @@ impl Default for WalletConfig {
             drone_port: DRONE_PORT,
             json_rpc_url: "http://testnet.solana.com:8899".to_string(),
             keypair: Keypair::new(),
+            lamports_only: false,
             rpc_client: None,
+            signing: SigningConfig::default(),
         }
     }
 }
@@ -163,11 +212,125 @@ where
         .map(|value| value.parse::<T>().unwrap())
 }
 
+// Return the amount of lamports at `name`, see `parse_amount()`
+fn amount_of(matches: &ArgMatches<'_>, name: &str) -> Option<u64> {
+    matches
+        .value_of(name)
+        .map(|value| parse_amount(value).unwrap())
+}
+
 // Return the keypair for an argument with filename `name` or None if not present.
 fn keypair_of(matches: &ArgMatches<'_>, name: &str) -> Option<Keypair> {
     matches.value_of(name).map(|x| read_keypair(x).unwrap())
 }
 
+// Parse a `PUBKEY=SIGNATURE` pair, as printed by `--sign-only`
+fn parse_pubkey_signature(string: &str) -> Result<(Pubkey, Signature), String> {
+    let mut parts = string.splitn(2, '=');
+    let pubkey = parts
+        .next()
+        .unwrap()
+        .parse::<Pubkey>()
+        .map_err(|err| format!("{:?}", err))?;
+    let signature = parts
+        .next()
+        .ok_or_else(|| "expected PUBKEY=SIGNATURE".to_string())?
+        .parse::<Signature>()
+        .map_err(|err| format!("{:?}", err))?;
+    Ok((pubkey, signature))
+}
+
+// Parse an amount of lamports. Amounts are whole lamports, optionally followed by `lamports`,
+// unless they are followed by `SOL`, e.g. `1.5SOL`.
+fn parse_amount(string: &str) -> Result<u64, String> {
+    if string.ends_with("SOL") {
+        let sol = string[..string.len() - "SOL".len()]
+            .trim()
+            .parse::<f64>()
+            .map_err(|err| format!("Invalid amount of SOL: {}: {:?}", string, err))?;
+        if sol < 0.0 || sol * LAMPORTS_PER_SOL as f64 > std::u64::MAX as f64 {
+            Err(format!("Amount out of range: {}", string))?;
+        }
+        Ok(sol_to_lamports(sol))
+    } else {
+        let lamports = if string.ends_with("lamports") {
+            &string[..string.len() - "lamports".len()]
+        } else {
+            string
+        };
+        lamports.trim().parse::<u64>().map_err(|_| {
+            format!(
+                "Invalid amount: {}, amounts are whole lamports unless followed by SOL, e.g. 1.5SOL",
+                string
+            )
+        })
+    }
+}
+
+/// Format an amount of lamports in SOL and lamports, or in lamports only
+pub fn format_lamports(lamports: u64, lamports_only: bool) -> String {
+    let ess = if lamports == 1 { "" } else { "s" };
+    if lamports_only {
+        format!("{} lamport{}", lamports, ess)
+    } else {
+        format!("{} ({} lamport{})", Sol(lamports), lamports, ess)
+    }
+}
+
+// Parse a `PROGRAM_ID:DATA:ACCOUNTS` instruction, where DATA is base58 encoded and ACCOUNTS are
+// comma-separated pubkeys. All the accounts are credit-debit, and `signer` is marked as a signer.
+fn parse_instruction(string: &str, signer: &Pubkey) -> Result<Instruction, String> {
+    let mut parts = string.splitn(3, ':');
+    let program_id = parts
+        .next()
+        .unwrap()
+        .parse::<Pubkey>()
+        .map_err(|err| format!("{:?}", err))?;
+    let data = parts
+        .next()
+        .ok_or_else(|| "expected PROGRAM_ID:DATA:ACCOUNTS".to_string())?;
+    let data = bs58::decode(data)
+        .into_vec()
+        .map_err(|err| format!("{:?}", err))?;
+    let accounts = parts
+        .next()
+        .ok_or_else(|| "expected PROGRAM_ID:DATA:ACCOUNTS".to_string())?
+        .split(',')
+        .filter(|account| !account.is_empty())
+        .map(|account| {
+            account
+                .parse::<Pubkey>()
+                .map(|pubkey| AccountMeta::new(pubkey, pubkey == *signer))
+                .map_err(|err| format!("{:?}", err))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Parse the offline signing arguments of the subcommand in `matches`
+pub fn parse_signing_config(matches: &ArgMatches<'_>) -> SigningConfig {
+    let matches = match matches.subcommand() {
+        (_, Some(matches)) => matches,
+        _ => return SigningConfig::default(),
+    };
+    SigningConfig {
+        sign_only: matches.is_present("sign_only"),
+        blockhash: value_of(matches, "blockhash"),
+        signers: matches
+            .values_of("signer")
+            .map(|signers| {
+                signers
+                    .map(|signer| parse_pubkey_signature(signer).unwrap())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
 pub fn parse_command(
     pubkey: &Pubkey,
     matches: &ArgMatches<'_>,
@@ -176,7 +339,7 @@ pub fn parse_command(
         ("address", Some(_address_matches)) => Ok(WalletCommand::Address),
         ("fees", Some(_fees_matches)) => Ok(WalletCommand::Fees),
         ("airdrop", Some(airdrop_matches)) => {
-            let lamports = airdrop_matches.value_of("lamports").unwrap().parse()?;
+            let lamports = amount_of(airdrop_matches, "lamports").unwrap();
             Ok(WalletCommand::Airdrop(lamports))
         }
         ("balance", Some(balance_matches)) => {
@@ -197,22 +360,22 @@ pub fn parse_command(
             }
         }
         ("create-vote-account", Some(matches)) => {
-            let vote_account_pubkey = value_of(matches, "vote_account_pubkey").unwrap();
+            let vote_account_keypair = keypair_of(matches, "vote_account_keypair_file").unwrap();
             let node_pubkey = value_of(matches, "node_pubkey").unwrap();
             let commission = if let Some(commission) = matches.value_of("commission") {
                 commission.parse()?
             } else {
                 0
             };
-            let lamports = matches.value_of("lamports").unwrap().parse()?;
+            let lamports = amount_of(matches, "lamports").unwrap();
             Ok(WalletCommand::CreateVoteAccount(
-                vote_account_pubkey,
+                vote_account_keypair,
                 node_pubkey,
                 commission,
                 lamports,
             ))
         }
-        ("authorize-voter", Some(matches)) => {
+        ("vote-authorize-voter", Some(matches)) => {
             let vote_account_pubkey = value_of(matches, "vote_account_pubkey").unwrap();
             let authorized_voter_keypair =
                 keypair_of(matches, "authorized_voter_keypair_file").unwrap();
@@ -229,10 +392,29 @@ pub fn parse_command(
             let vote_account_pubkey = value_of(matches, "vote_account_pubkey").unwrap();
             Ok(WalletCommand::ShowVoteAccount(vote_account_pubkey))
         }
+        ("withdraw-from-vote-account", Some(matches)) => {
+            let vote_account_keypair = keypair_of(matches, "vote_account_keypair_file").unwrap();
+            let destination_account_pubkey =
+                value_of(matches, "destination_account_pubkey").unwrap();
+            let lamports = amount_of(matches, "lamports").unwrap();
+            Ok(WalletCommand::WithdrawFromVoteAccount(
+                vote_account_keypair,
+                destination_account_pubkey,
+                lamports,
+            ))
+        }
+        ("create-stake-account", Some(matches)) => {
+            let stake_account_keypair = keypair_of(matches, "stake_account_keypair_file").unwrap();
+            let lamports = amount_of(matches, "lamports").unwrap();
+            Ok(WalletCommand::CreateStakeAccount(
+                stake_account_keypair,
+                lamports,
+            ))
+        }
         ("delegate-stake", Some(matches)) => {
             let stake_account_keypair = keypair_of(matches, "stake_account_keypair_file").unwrap();
             let vote_account_pubkey = value_of(matches, "vote_account_pubkey").unwrap();
-            let lamports_to_stake = matches.value_of("lamports_to_stake").unwrap().parse()?;
+            let lamports_to_stake = amount_of(matches, "lamports_to_stake").unwrap();
             Ok(WalletCommand::DelegateStake(
                 stake_account_keypair,
                 vote_account_pubkey,
@@ -243,7 +425,7 @@ pub fn parse_command(
             let stake_account_keypair = keypair_of(matches, "stake_account_keypair_file").unwrap();
             let destination_account_pubkey =
                 value_of(matches, "destination_account_pubkey").unwrap();
-            let lamports = matches.value_of("lamports").unwrap().parse()?;
+            let lamports = amount_of(matches, "lamports").unwrap();
             Ok(WalletCommand::WithdrawStake(
                 stake_account_keypair,
                 destination_account_pubkey,
@@ -294,6 +476,30 @@ pub fn parse_command(
             let storage_account_pubkey = value_of(matches, "storage_account_pubkey").unwrap();
             Ok(WalletCommand::ShowStorageAccount(storage_account_pubkey))
         }
+        ("create-token", Some(matches)) => {
+            let supply = value_of(matches, "supply").unwrap();
+            let decimals = value_of(matches, "decimals").unwrap();
+            let name = matches.value_of("name").unwrap().to_string();
+            let symbol = matches.value_of("symbol").unwrap().to_string();
+            let mintable = matches.is_present("mintable");
+            Ok(WalletCommand::CreateToken(
+                supply, decimals, name, symbol, mintable,
+            ))
+        }
+        ("create-token-account", Some(matches)) => {
+            let token = value_of(matches, "token").unwrap();
+            Ok(WalletCommand::CreateTokenAccount(token))
+        }
+        ("transfer-token", Some(matches)) => {
+            let source = value_of(matches, "source").unwrap();
+            let dest = value_of(matches, "dest").unwrap();
+            let amount = value_of(matches, "amount").unwrap();
+            Ok(WalletCommand::TransferToken(source, dest, amount))
+        }
+        ("show-token-account", Some(matches)) => {
+            let token_account_pubkey = value_of(matches, "token_account_pubkey").unwrap();
+            Ok(WalletCommand::ShowTokenAccount(token_account_pubkey))
+        }
         ("deploy", Some(deploy_matches)) => Ok(WalletCommand::Deploy(
             deploy_matches
                 .value_of("program_location")
@@ -301,8 +507,16 @@ pub fn parse_command(
                 .to_string(),
         )),
         ("get-transaction-count", Some(_matches)) => Ok(WalletCommand::GetTransactionCount),
+        ("transaction-history", Some(matches)) => {
+            let address = value_of(matches, "address").unwrap();
+            let from_slot = value_of(matches, "from_slot").unwrap();
+            let to_slot = value_of(matches, "to_slot");
+            Ok(WalletCommand::TransactionHistory(
+                address, from_slot, to_slot,
+            ))
+        }
         ("pay", Some(pay_matches)) => {
-            let lamports = pay_matches.value_of("lamports").unwrap().parse()?;
+            let lamports = amount_of(pay_matches, "lamports").unwrap();
             let to = value_of(&pay_matches, "to").unwrap_or(*pubkey);
             let timestamp = if pay_matches.is_present("timestamp") {
                 // Parse input for serde_json
@@ -317,11 +531,21 @@ pub fn parse_command(
             };
             let timestamp_pubkey = value_of(&pay_matches, "timestamp_pubkey");
             let witness_vec = values_of(&pay_matches, "witness");
+            let require_any = pay_matches.is_present("require_any");
             let cancelable = if pay_matches.is_present("cancelable") {
                 Some(*pubkey)
             } else {
                 None
             };
+            let mut instructions = vec![];
+            if let Some(memo) = pay_matches.value_of("memo") {
+                instructions.push(memo_instruction::memo(memo));
+            }
+            if let Some(values) = pay_matches.values_of("instruction") {
+                for value in values {
+                    instructions.push(parse_instruction(value, pubkey)?);
+                }
+            }
 
             Ok(WalletCommand::Pay(
                 lamports,
@@ -329,14 +553,28 @@ pub fn parse_command(
                 timestamp,
                 timestamp_pubkey,
                 witness_vec,
+                require_any,
                 cancelable,
+                instructions,
             ))
         }
+        ("pay-recurring", Some(pay_matches)) => {
+            let lamports = amount_of(pay_matches, "lamports").unwrap();
+            let to = value_of(&pay_matches, "to").unwrap();
+            let interval = value_of(&pay_matches, "interval").unwrap();
+            let count = value_of(&pay_matches, "count").unwrap();
+            Ok(WalletCommand::PayRecurring(lamports, to, interval, count))
+        }
         ("send-signature", Some(sig_matches)) => {
             let to = value_of(&sig_matches, "to").unwrap();
             let process_id = value_of(&sig_matches, "process_id").unwrap();
             Ok(WalletCommand::Witness(to, process_id))
         }
+        ("send-clock", Some(clock_matches)) => {
+            let to = value_of(&clock_matches, "to").unwrap();
+            let process_id = value_of(&clock_matches, "process_id").unwrap();
+            Ok(WalletCommand::ClockElapsed(to, process_id))
+        }
         ("send-timestamp", Some(timestamp_matches)) => {
             let to = value_of(&timestamp_matches, "to").unwrap();
             let process_id = value_of(&timestamp_matches, "process_id").unwrap();
@@ -370,6 +608,118 @@ pub fn parse_command(
 
 type ProcessResult = Result<String, Box<dyn error::Error>>;
 
+// The blockhash given with `--blockhash`, or else a recent one from the cluster
+fn get_blockhash(rpc_client: &RpcClient, config: &WalletConfig) -> io::Result<Hash> {
+    match config.signing.blockhash {
+        Some(blockhash) => Ok(blockhash),
+        None => rpc_client
+            .get_recent_blockhash()
+            .map(|(blockhash, _fee_calculator)| blockhash),
+    }
+}
+
+// The output of `--sign-only`: what the online host passes to `--blockhash` and `--signer`.
+// Fails if the transaction is too large to ever be sent.
+fn return_signers(tx: &Transaction) -> ProcessResult {
+    tx.check_packet_fit()?;
+    let signers: Vec<_> = tx
+        .signatures
+        .iter()
+        .zip(tx.message().account_keys.iter())
+        .filter(|(signature, _)| **signature != Signature::default())
+        .map(|(signature, pubkey)| format!("{}={}", pubkey, signature))
+        .collect();
+    Ok(json!({
+        "blockhash": tx.message().recent_blockhash.to_string(),
+        "signers": signers,
+    })
+    .to_string())
+}
+
+// Put signatures made by `--sign-only` on another host into `tx`
+fn replace_signatures(tx: &mut Transaction, signers: &[(Pubkey, Signature)]) -> io::Result<()> {
+    let message_data = tx.message_data();
+    let num_required_signatures = tx.message().header.num_required_signatures as usize;
+    for (pubkey, signature) in signers {
+        let position = tx.message().account_keys[..num_required_signatures]
+            .iter()
+            .position(|key| key == pubkey)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} is not a signer of this transaction", pubkey),
+                )
+            })?;
+        if !signature.verify(pubkey.as_ref(), &message_data) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Signature from {} doesn't match this transaction, check the blockhash",
+                    pubkey
+                ),
+            ));
+        }
+        tx.signatures[position] = *signature;
+    }
+    if let Some(position) = tx
+        .signatures
+        .iter()
+        .position(|signature| *signature == Signature::default())
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Missing a signature from {}",
+                tx.message().account_keys[position]
+            ),
+        ));
+    }
+    Ok(())
+}
+
+// Who pays for a transaction. When its signatures come from another host, that's the first
+// `--signer`, as `--sign-only` prints the fee payer's signature first, so no local keypair is
+// needed to send it.
+fn fee_payer(config: &WalletConfig) -> Pubkey {
+    config
+        .signing
+        .signers
+        .first()
+        .map(|(pubkey, _)| *pubkey)
+        .unwrap_or_else(|| config.keypair.pubkey())
+}
+
+// Send `tx`, signed by `signers` and any `--signer` signatures. Transactions with signatures from
+// another host are never re-signed, since those signatures can't be remade here, so `signers`
+// only sign when there are no `--signer`s.
+fn send_and_confirm(
+    rpc_client: &RpcClient,
+    config: &WalletConfig,
+    tx: &mut Transaction,
+    signers: &[&Keypair],
+) -> Result<String, ClientError> {
+    if config.signing.signers.is_empty() {
+        return rpc_client.send_and_confirm_transaction(tx, signers);
+    }
+
+    replace_signatures(tx, &config.signing.signers)?;
+    let signature_str = rpc_client.send_transaction(tx)?;
+    for _ in 0..30 {
+        if let Some(result) = rpc_client.get_signature_status(&signature_str)? {
+            result?;
+            return Ok(signature_str);
+        }
+        if cfg!(not(test)) {
+            sleep(Duration::from_millis(500));
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        format!("Transaction {} was not confirmed", signature_str),
+    )
+    .into())
+}
+
 fn process_fees(rpc_client: &RpcClient) -> ProcessResult {
     let (recent_blockhash, fee_calculator) = rpc_client.get_recent_blockhash()?;
 
@@ -385,8 +735,9 @@ fn process_airdrop(
     lamports: u64,
 ) -> ProcessResult {
     println!(
-        "Requesting airdrop of {:?} lamports from {}",
-        lamports, drone_addr
+        "Requesting airdrop of {} from {}",
+        format_lamports(lamports, config.lamports_only),
+        drone_addr
     );
     let previous_balance = match rpc_client.retry_get_balance(&config.keypair.pubkey(), 5)? {
         Some(lamports) => lamports,
@@ -414,16 +765,20 @@ fn process_airdrop(
             lamports
         ))?;
     }
-    Ok(format!("Your balance is: {:?}", current_balance))
+    Ok(format!(
+        "Your balance is: {}",
+        format_lamports(current_balance, config.lamports_only)
+    ))
 }
 
-fn process_balance(pubkey: &Pubkey, rpc_client: &RpcClient) -> ProcessResult {
+fn process_balance(
+    rpc_client: &RpcClient,
+    config: &WalletConfig,
+    pubkey: &Pubkey,
+) -> ProcessResult {
     let balance = rpc_client.retry_get_balance(pubkey, 5)?;
     match balance {
-        Some(lamports) => {
-            let ess = if lamports == 1 { "" } else { "s" };
-            Ok(format!("{:?} lamport{}", lamports, ess))
-        }
+        Some(lamports) => Ok(format_lamports(lamports, config.lamports_only)),
         None => Err(WalletError::RpcRequestError(
             "Received result of an unexpected type".to_string(),
         ))?,
@@ -452,21 +807,35 @@ fn process_confirm(rpc_client: &RpcClient, signature: &Signature) -> ProcessResu
 fn process_create_vote_account(
     rpc_client: &RpcClient,
     config: &WalletConfig,
-    vote_account_pubkey: &Pubkey,
+    vote_account_keypair: &Keypair,
     node_pubkey: &Pubkey,
     commission: u8,
     lamports: u64,
 ) -> ProcessResult {
+    // The vote account signs too, so that no one else can initialize it once it's created
     let ixs = vote_instruction::create_account(
         &config.keypair.pubkey(),
-        vote_account_pubkey,
+        &vote_account_keypair.pubkey(),
         node_pubkey,
         commission,
         lamports,
     );
-    let (recent_blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
-    let mut tx = Transaction::new_signed_instructions(&[&config.keypair], ixs, recent_blockhash);
-    let signature_str = rpc_client.send_and_confirm_transaction(&mut tx, &[&config.keypair])?;
+    let recent_blockhash = get_blockhash(rpc_client, config)?;
+    let mut tx = Transaction::new_signed_with_payer(
+        ixs,
+        Some(&config.keypair.pubkey()),
+        &[&config.keypair, vote_account_keypair],
+        recent_blockhash,
+    );
+    if config.signing.sign_only {
+        return return_signers(&tx);
+    }
+    let signature_str = send_and_confirm(
+        rpc_client,
+        config,
+        &mut tx,
+        &[&config.keypair, vote_account_keypair],
+    )?;
     Ok(signature_str.to_string())
 }
 
@@ -477,7 +846,7 @@ fn process_authorize_voter(
     authorized_voter_keypair: &Keypair,
     new_authorized_voter_pubkey: &Pubkey,
 ) -> ProcessResult {
-    let (recent_blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
+    let recent_blockhash = get_blockhash(rpc_client, config)?;
     let ixs = vec![vote_instruction::authorize_voter(
         vote_account_pubkey,                // vote account to update
         &authorized_voter_keypair.pubkey(), // current authorized voter (often the vote account itself)
@@ -490,14 +859,21 @@ fn process_authorize_voter(
         &[&config.keypair, &authorized_voter_keypair],
         recent_blockhash,
     );
-    let signature_str = rpc_client
-        .send_and_confirm_transaction(&mut tx, &[&config.keypair, &authorized_voter_keypair])?;
+    if config.signing.sign_only {
+        return return_signers(&tx);
+    }
+    let signature_str = send_and_confirm(
+        rpc_client,
+        config,
+        &mut tx,
+        &[&config.keypair, &authorized_voter_keypair],
+    )?;
     Ok(signature_str.to_string())
 }
 
 fn process_show_vote_account(
     rpc_client: &RpcClient,
-    _config: &WalletConfig,
+    config: &WalletConfig,
     vote_account_pubkey: &Pubkey,
 ) -> ProcessResult {
     let vote_account = rpc_client.get_account(vote_account_pubkey)?;
@@ -515,7 +891,10 @@ fn process_show_vote_account(
         )
     })?;
 
-    println!("account lamports: {}", vote_account.lamports);
+    println!(
+        "account balance: {}",
+        format_lamports(vote_account.lamports, config.lamports_only)
+    );
     println!("node id: {}", vote_state.node_pubkey);
     println!(
         "authorized voter pubkey: {}",
@@ -524,7 +903,7 @@ fn process_show_vote_account(
     println!("credits: {}", vote_state.credits());
     println!(
         "commission: {}%",
-        f64::from(vote_state.commission) / f64::from(std::u32::MAX)
+        f64::from(vote_state.commission) * 100.0 / f64::from(std::u8::MAX)
     );
     println!(
         "root slot: {}",
@@ -537,8 +916,11 @@ fn process_show_vote_account(
         println!("recent votes:");
         for vote in &vote_state.votes {
             println!(
-                "- slot: {}\n  confirmation count: {}",
-                vote.slot, vote.confirmation_count
+                "- slot: {}\n  confirmation count: {}\n  lockout: {} slots\n  locked out until slot: {}",
+                vote.slot,
+                vote.confirmation_count,
+                vote.lockout(),
+                vote.expiration_slot()
             );
         }
 
@@ -563,12 +945,44 @@ fn process_show_vote_account(
     Ok("".to_string())
 }
 
+fn process_withdraw_from_vote_account(
+    rpc_client: &RpcClient,
+    config: &WalletConfig,
+    vote_account_keypair: &Keypair,
+    destination_account_pubkey: &Pubkey,
+    lamports: u64,
+) -> ProcessResult {
+    let recent_blockhash = get_blockhash(rpc_client, config)?;
+    let ixs = vec![vote_instruction::withdraw(
+        &vote_account_keypair.pubkey(),
+        lamports,
+        destination_account_pubkey,
+    )];
+
+    let mut tx = Transaction::new_signed_with_payer(
+        ixs,
+        Some(&config.keypair.pubkey()),
+        &[&config.keypair, &vote_account_keypair],
+        recent_blockhash,
+    );
+    if config.signing.sign_only {
+        return return_signers(&tx);
+    }
+    let signature_str = send_and_confirm(
+        rpc_client,
+        config,
+        &mut tx,
+        &[&config.keypair, &vote_account_keypair],
+    )?;
+    Ok(signature_str.to_string())
+}
+
 fn process_deactivate_stake_account(
     rpc_client: &RpcClient,
     config: &WalletConfig,
     stake_account_keypair: &Keypair,
 ) -> ProcessResult {
-    let (recent_blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
+    let recent_blockhash = get_blockhash(rpc_client, config)?;
     let ixs = stake_instruction::deactivate_stake(&stake_account_keypair.pubkey());
     let mut tx = Transaction::new_signed_with_payer(
         vec![ixs],
@@ -576,8 +990,47 @@ fn process_deactivate_stake_account(
         &[&config.keypair, &stake_account_keypair],
         recent_blockhash,
     );
-    let signature_str = rpc_client
-        .send_and_confirm_transaction(&mut tx, &[&config.keypair, &stake_account_keypair])?;
+    if config.signing.sign_only {
+        return return_signers(&tx);
+    }
+    let signature_str = send_and_confirm(
+        rpc_client,
+        config,
+        &mut tx,
+        &[&config.keypair, &stake_account_keypair],
+    )?;
+    Ok(signature_str.to_string())
+}
+
+fn process_create_stake_account(
+    rpc_client: &RpcClient,
+    config: &WalletConfig,
+    stake_account_keypair: &Keypair,
+    lamports: u64,
+) -> ProcessResult {
+    let recent_blockhash = get_blockhash(rpc_client, config)?;
+    let ixs = stake_instruction::create_stake_account(
+        &config.keypair.pubkey(),
+        &stake_account_keypair.pubkey(),
+        lamports,
+    );
+
+    let mut tx = Transaction::new_signed_with_payer(
+        ixs,
+        Some(&config.keypair.pubkey()),
+        &[&config.keypair, &stake_account_keypair],
+        recent_blockhash,
+    );
+
+    if config.signing.sign_only {
+        return return_signers(&tx);
+    }
+    let signature_str = send_and_confirm(
+        rpc_client,
+        config,
+        &mut tx,
+        &[&config.keypair, &stake_account_keypair],
+    )?;
     Ok(signature_str.to_string())
 }
 
@@ -588,14 +1041,13 @@ fn process_delegate_stake(
     vote_account_pubkey: &Pubkey,
     lamports: u64,
 ) -> ProcessResult {
-    let (recent_blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
+    let recent_blockhash = get_blockhash(rpc_client, config)?;
 
-    let ixs = stake_instruction::create_stake_account_and_delegate_stake(
-        &config.keypair.pubkey(),
+    let ixs = vec![stake_instruction::delegate_stake(
         &stake_account_keypair.pubkey(),
         vote_account_pubkey,
         lamports,
-    );
+    )];
 
     let mut tx = Transaction::new_signed_with_payer(
         ixs,
@@ -604,8 +1056,15 @@ fn process_delegate_stake(
         recent_blockhash,
     );
 
-    let signature_str = rpc_client
-        .send_and_confirm_transaction(&mut tx, &[&config.keypair, &stake_account_keypair])?;
+    if config.signing.sign_only {
+        return return_signers(&tx);
+    }
+    let signature_str = send_and_confirm(
+        rpc_client,
+        config,
+        &mut tx,
+        &[&config.keypair, &stake_account_keypair],
+    )?;
     Ok(signature_str.to_string())
 }
 
@@ -616,7 +1075,7 @@ fn process_withdraw_stake(
     destination_account_pubkey: &Pubkey,
     lamports: u64,
 ) -> ProcessResult {
-    let (recent_blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
+    let recent_blockhash = get_blockhash(rpc_client, config)?;
     let ixs = vec![stake_instruction::withdraw(
         &stake_account_keypair.pubkey(),
         destination_account_pubkey,
@@ -630,8 +1089,15 @@ fn process_withdraw_stake(
         recent_blockhash,
     );
 
-    let signature_str = rpc_client
-        .send_and_confirm_transaction(&mut tx, &[&config.keypair, &stake_account_keypair])?;
+    if config.signing.sign_only {
+        return return_signers(&tx);
+    }
+    let signature_str = send_and_confirm(
+        rpc_client,
+        config,
+        &mut tx,
+        &[&config.keypair, &stake_account_keypair],
+    )?;
     Ok(signature_str.to_string())
 }
 
@@ -656,23 +1122,50 @@ fn process_redeem_vote_credits(
     Ok(signature_str.to_string())
 }
 
+// Describe how far `stake` is through its warm-up or cool-down at `epoch`
+fn format_stake_activation(stake: &Stake, epoch: Epoch) -> String {
+    let state = if epoch < stake.activated {
+        "inactive, activates at a later epoch"
+    } else if epoch >= stake.deactivated {
+        "inactive"
+    } else if epoch - stake.activated < STAKE_WARMUP_EPOCHS {
+        "activating"
+    } else if stake.deactivated < std::u64::MAX {
+        "deactivating"
+    } else {
+        "active"
+    };
+    format!(
+        "{} ({} of {} lamports active)",
+        state,
+        stake.stake(epoch),
+        stake.stake
+    )
+}
+
 fn process_show_stake_account(
     rpc_client: &RpcClient,
-    _config: &WalletConfig,
+    config: &WalletConfig,
     stake_account_pubkey: &Pubkey,
 ) -> ProcessResult {
-    use solana_stake_api::stake_state::StakeState;
     let stake_account = rpc_client.get_account(stake_account_pubkey)?;
     if stake_account.owner != solana_stake_api::id() {
         Err(WalletError::RpcRequestError(
             format!("{:?} is not a stake account", stake_account_pubkey).to_string(),
         ))?;
     }
-    match stake_account.state() {
-        Ok(StakeState::Stake(stake)) => {
-            println!("total stake: {}", stake_account.lamports);
+    let stake_state: Option<StakeState> = stake_account.state().ok();
+    match stake_state.as_ref().and_then(StakeState::stake_and_lockup) {
+        Some((stake, lockup)) => {
+            println!(
+                "total stake: {}",
+                format_lamports(stake_account.lamports, config.lamports_only)
+            );
             println!("credits observed: {}", stake.credits_observed);
-            println!("delegated stake: {}", stake.stake);
+            println!(
+                "delegated stake: {}",
+                format_lamports(stake.stake, config.lamports_only)
+            );
             if stake.voter_pubkey != Pubkey::default() {
                 println!("delegated voter pubkey: {}", stake.voter_pubkey);
             }
@@ -680,10 +1173,26 @@ fn process_show_stake_account(
             if stake.deactivated < std::u64::MAX {
                 println!("stake deactivates at epoch: {}", stake.deactivated);
             }
+            if lockup > 0 {
+                println!("stake locked up until slot: {}", lockup);
+            }
+            let clock = rpc_client
+                .get_account(&clock::id())
+                .ok()
+                .and_then(|account| Clock::from(&account));
+            if let Some(clock) = clock {
+                println!("current epoch: {}", clock.epoch);
+                println!(
+                    "stake state: {}",
+                    format_stake_activation(&stake, clock.epoch)
+                );
+            }
             Ok("".to_string())
         }
-        Ok(StakeState::Uninitialized) => Ok("Stake account is uninitialized".to_string()),
-        _ => Err(WalletError::RpcRequestError(
+        None if stake_state == Some(StakeState::Uninitialized) => {
+            Ok("Stake account is uninitialized".to_string())
+        }
+        None => Err(WalletError::RpcRequestError(
             "Account data could not be deserialized to stake state".to_string(),
         ))?,
     }
@@ -745,7 +1254,7 @@ fn process_claim_storage_reward(
 
 fn process_show_storage_account(
     rpc_client: &RpcClient,
-    _config: &WalletConfig,
+    config: &WalletConfig,
     storage_account_pubkey: &Pubkey,
 ) -> ProcessResult {
     let account = rpc_client.get_account(storage_account_pubkey)?;
@@ -763,10 +1272,133 @@ fn process_show_storage_account(
         )
     })?;
     println!("{:#?}", storage_contract);
-    println!("account lamports: {}", account.lamports);
+    println!(
+        "account balance: {}",
+        format_lamports(account.lamports, config.lamports_only)
+    );
     Ok("".to_string())
 }
 
+fn process_create_token(
+    rpc_client: &RpcClient,
+    config: &WalletConfig,
+    token_info: TokenInfo,
+) -> ProcessResult {
+    let token_keypair = Keypair::new();
+    let account_keypair = Keypair::new();
+    let payer = config.keypair.pubkey();
+
+    // Credit the supply to a new account owned by this wallet
+    let mut ixs = token_instruction::create_token_account(
+        &payer,
+        &account_keypair.pubkey(),
+        &payer,
+        &token_keypair.pubkey(),
+        1,
+    );
+    ixs.extend(token_instruction::create_token(
+        &payer,
+        &token_keypair.pubkey(),
+        &account_keypair.pubkey(),
+        token_info,
+        1,
+    ));
+    let (recent_blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
+    let signers = [&config.keypair, &account_keypair, &token_keypair];
+    let mut tx = Transaction::new_signed_with_payer(ixs, Some(&payer), &signers, recent_blockhash);
+    let result = rpc_client.send_and_confirm_transaction(&mut tx, &signers);
+    let signature_str = log_instruction_custom_error::<TokenError>(result)?;
+
+    Ok(json!({
+        "signature": signature_str,
+        "token": format!("{}", token_keypair.pubkey()),
+        "account": format!("{}", account_keypair.pubkey()),
+    })
+    .to_string())
+}
+
+fn process_create_token_account(
+    rpc_client: &RpcClient,
+    config: &WalletConfig,
+    token: &Pubkey,
+) -> ProcessResult {
+    let account_keypair = Keypair::new();
+    let payer = config.keypair.pubkey();
+    let ixs = token_instruction::create_token_account(
+        &payer,
+        &account_keypair.pubkey(),
+        &payer,
+        token,
+        1,
+    );
+    let (recent_blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
+    let signers = [&config.keypair, &account_keypair];
+    let mut tx = Transaction::new_signed_with_payer(ixs, Some(&payer), &signers, recent_blockhash);
+    let result = rpc_client.send_and_confirm_transaction(&mut tx, &signers);
+    let signature_str = log_instruction_custom_error::<TokenError>(result)?;
+
+    Ok(json!({
+        "signature": signature_str,
+        "account": format!("{}", account_keypair.pubkey()),
+    })
+    .to_string())
+}
+
+fn process_transfer_token(
+    rpc_client: &RpcClient,
+    config: &WalletConfig,
+    source: &Pubkey,
+    dest: &Pubkey,
+    amount: u64,
+) -> ProcessResult {
+    let ix = token_instruction::transfer(&config.keypair.pubkey(), source, dest, None, &[], amount);
+    let (recent_blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
+    let mut tx =
+        Transaction::new_signed_instructions(&[&config.keypair], vec![ix], recent_blockhash);
+    let result = rpc_client.send_and_confirm_transaction(&mut tx, &[&config.keypair]);
+    let signature_str = log_instruction_custom_error::<TokenError>(result)?;
+    Ok(signature_str.to_string())
+}
+
+fn process_show_token_account(
+    rpc_client: &RpcClient,
+    token_account_pubkey: &Pubkey,
+) -> ProcessResult {
+    let account = rpc_client.get_account(token_account_pubkey)?;
+
+    if account.owner != solana_token_api::id() {
+        Err(WalletError::RpcRequestError(
+            format!("{:?} is not a token account", token_account_pubkey).to_string(),
+        ))?;
+    }
+
+    match TokenState::deserialize(&account.data) {
+        Ok(TokenState::Account(account_info)) => {
+            println!("token: {}", account_info.token);
+            println!("owner: {}", account_info.owner);
+            println!("amount: {}", account_info.amount);
+            if let Some(delegate_info) = account_info.delegate {
+                println!("delegate for: {}", delegate_info.source);
+                println!("original allowance: {}", delegate_info.original_amount);
+            }
+            Ok("".to_string())
+        }
+        Ok(TokenState::Token(token_info)) => {
+            println!("name: {}", token_info.name);
+            println!("symbol: {}", token_info.symbol);
+            println!("supply: {}", token_info.supply);
+            println!("decimals: {}", token_info.decimals);
+            if let Some(mint_authority) = token_info.mint_authority {
+                println!("mint authority: {}", mint_authority);
+            }
+            Ok("".to_string())
+        }
+        _ => Err(WalletError::RpcRequestError(
+            "Account data could not be deserialized to token state".to_string(),
+        ))?,
+    }
+}
+
 fn process_deploy(
     rpc_client: &RpcClient,
     config: &WalletConfig,
@@ -851,15 +1483,42 @@ fn process_pay(
     timestamp: Option<DateTime<Utc>>,
     timestamp_pubkey: Option<Pubkey>,
     witnesses: &Option<Vec<Pubkey>>,
+    require_any: bool,
     cancelable: Option<Pubkey>,
+    instructions: &[Instruction],
 ) -> ProcessResult {
-    let (blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
+    let blockhash = get_blockhash(rpc_client, config)?;
 
     if timestamp == None && *witnesses == None {
-        let mut tx = system_transaction::transfer(&config.keypair, to, lamports, blockhash);
-        let result = rpc_client.send_and_confirm_transaction(&mut tx, &[&config.keypair]);
+        let mut ixs = vec![system_instruction::transfer(
+            &fee_payer(config),
+            to,
+            lamports,
+        )];
+        ixs.extend_from_slice(instructions);
+        let mut tx = Transaction::new_unsigned_instructions(ixs);
+        if config.signing.signers.is_empty() {
+            tx.sign(&[&config.keypair], blockhash);
+        } else {
+            // Every signature comes from the `--signer`s
+            tx.message.recent_blockhash = blockhash;
+        }
+        if config.signing.sign_only {
+            return return_signers(&tx);
+        }
+        let result = send_and_confirm(rpc_client, config, &mut tx, &[&config.keypair]);
         let signature_str = log_instruction_custom_error::<SystemError>(result)?;
         Ok(signature_str.to_string())
+    } else if !instructions.is_empty() {
+        Err(WalletError::BadParameter(
+            "Memos and extra instructions are only supported on plain transfers".to_string(),
+        ))?
+    } else if config.signing.sign_only || !config.signing.signers.is_empty() {
+        // The contract state account is new each time, so the online host's transaction
+        // wouldn't match the offline one
+        Err(WalletError::BadParameter(
+            "Offline signing only supports plain transfers".to_string(),
+        ))?
     } else if *witnesses == None {
         let dt = timestamp.unwrap();
         let dt_pubkey = match timestamp_pubkey {
@@ -888,7 +1547,7 @@ fn process_pay(
             "processId": format!("{}", contract_state.pubkey()),
         })
         .to_string())
-    } else if timestamp == None {
+    } else if timestamp == None && witnesses.as_ref().map_or(false, |w| w.len() == 1) {
         let (blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
 
         let witness = if let Some(ref witness_vec) = *witnesses {
@@ -920,21 +1579,102 @@ fn process_pay(
         })
         .to_string())
     } else {
-        Ok("Combo transactions not yet handled".to_string())
-    }
-}
+        let mut conditions = vec![];
+        if let Some(dt) = timestamp {
+            let dt_pubkey = timestamp_pubkey.unwrap_or_else(|| config.keypair.pubkey());
+            conditions.push(Condition::Timestamp(dt, dt_pubkey));
+        }
+        if let Some(ref witness_vec) = *witnesses {
+            conditions.extend(
+                witness_vec
+                    .iter()
+                    .map(|witness| Condition::Signature(*witness)),
+            );
+        }
+        let condition = if require_any {
+            Condition::Any(conditions)
+        } else {
+            Condition::All(conditions)
+        };
 
-fn process_cancel(rpc_client: &RpcClient, config: &WalletConfig, pubkey: &Pubkey) -> ProcessResult {
-    let (blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
-    let ix = budget_instruction::apply_signature(
-        &config.keypair.pubkey(),
-        pubkey,
-        &config.keypair.pubkey(),
-    );
-    let mut tx = Transaction::new_signed_instructions(&[&config.keypair], vec![ix], blockhash);
-    let result = rpc_client.send_and_confirm_transaction(&mut tx, &[&config.keypair]);
-    let signature_str = log_instruction_custom_error::<BudgetError>(result)?;
-    Ok(signature_str.to_string())
+        let contract_state = Keypair::new();
+
+        // Initializing contract
+        let ixs = budget_instruction::on_conditions(
+            &config.keypair.pubkey(),
+            to,
+            &contract_state.pubkey(),
+            condition,
+            cancelable,
+            lamports,
+        );
+        let mut tx = Transaction::new_signed_instructions(&[&config.keypair], ixs, blockhash);
+        let result = rpc_client.send_and_confirm_transaction(&mut tx, &[&config.keypair]);
+        let signature_str = log_instruction_custom_error::<BudgetError>(result)?;
+
+        Ok(json!({
+            "signature": signature_str,
+            "processId": format!("{}", contract_state.pubkey()),
+        })
+        .to_string())
+    }
+}
+
+fn process_pay_recurring(
+    rpc_client: &RpcClient,
+    config: &WalletConfig,
+    lamports: u64,
+    to: &Pubkey,
+    interval: u64,
+    count: u64,
+) -> ProcessResult {
+    if interval == 0 || count == 0 {
+        Err(WalletError::BadParameter(
+            "The interval and count of a recurring payment must be positive".to_string(),
+        ))?
+    }
+    let total_lamports = lamports.checked_mul(count).ok_or_else(|| {
+        WalletError::BadParameter("Recurring payment total overflows".to_string())
+    })?;
+    let (blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
+    let start_slot = rpc_client.get_slot()? + interval;
+
+    let contract_state = Keypair::new();
+
+    // Initializing contract
+    let ixs = budget_instruction::recurring(
+        &config.keypair.pubkey(),
+        to,
+        &contract_state.pubkey(),
+        lamports,
+        start_slot,
+        interval,
+        count,
+    );
+    let mut tx = Transaction::new_signed_instructions(&[&config.keypair], ixs, blockhash);
+    let result = rpc_client.send_and_confirm_transaction(&mut tx, &[&config.keypair]);
+    let signature_str = log_instruction_custom_error::<BudgetError>(result)?;
+
+    Ok(json!({
+        "signature": signature_str,
+        "processId": format!("{}", contract_state.pubkey()),
+        "totalLamports": total_lamports,
+        "startSlot": start_slot,
+    })
+    .to_string())
+}
+
+fn process_cancel(rpc_client: &RpcClient, config: &WalletConfig, pubkey: &Pubkey) -> ProcessResult {
+    let (blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
+    let ix = budget_instruction::apply_signature(
+        &config.keypair.pubkey(),
+        pubkey,
+        &config.keypair.pubkey(),
+    );
+    let mut tx = Transaction::new_signed_instructions(&[&config.keypair], vec![ix], blockhash);
+    let result = rpc_client.send_and_confirm_transaction(&mut tx, &[&config.keypair]);
+    let signature_str = log_instruction_custom_error::<BudgetError>(result)?;
+    Ok(signature_str.to_string())
 }
 
 fn process_get_transaction_count(rpc_client: &RpcClient) -> ProcessResult {
@@ -942,10 +1682,106 @@ fn process_get_transaction_count(rpc_client: &RpcClient) -> ProcessResult {
     Ok(transaction_count.to_string())
 }
 
+// Estimate when the block of `slot` was produced, `current_slot` being produced `now`. The
+// ledger doesn't record when blocks were produced, so slots are assumed to take the default
+// slot duration.
+fn estimated_slot_time(slot: u64, current_slot: u64, now: DateTime<Utc>) -> DateTime<Utc> {
+    let slot_ms = 1000 * DEFAULT_TICKS_PER_SLOT / DEFAULT_NUM_TICKS_PER_SECOND;
+    now - chrono::Duration::milliseconds((current_slot.saturating_sub(slot) * slot_ms) as i64)
+}
+
+// A CSV row of the change `confirmed` made to the balance of `address`, its block produced at
+// about `time`.
+fn transaction_history_row(
+    address: &Pubkey,
+    signature: &Signature,
+    confirmed: &ConfirmedTransaction,
+    time: DateTime<Utc>,
+) -> String {
+    let time = time.format("%Y-%m-%dT%H:%M:%SZ");
+    let meta = match &confirmed.meta {
+        Some(meta) => meta,
+        None => return format!("{},{},{},,,", confirmed.slot, time, signature),
+    };
+    let account_keys = &confirmed.transaction.message().account_keys;
+    let deltas: Vec<i128> = meta
+        .pre_balances
+        .iter()
+        .zip(&meta.post_balances)
+        .map(|(pre, post)| i128::from(*post) - i128::from(*pre))
+        .collect();
+    let index = account_keys.iter().position(|key| key == address);
+    let delta = index
+        .and_then(|index| deltas.get(index))
+        .cloned()
+        .unwrap_or(0);
+    // The first account pays the fee, which its delta includes
+    let fee = if index == Some(0) { meta.fee } else { 0 };
+    // The account whose balance moved the most the other way
+    let counterparty = account_keys
+        .iter()
+        .zip(&deltas)
+        .filter(|(key, other_delta)| {
+            *key != address && **other_delta != 0 && other_delta.signum() != delta.signum()
+        })
+        .max_by_key(|(_, other_delta)| other_delta.abs())
+        .map_or_else(String::new, |(key, _)| key.to_string());
+    format!(
+        "{},{},{},{},{},{}",
+        confirmed.slot, time, signature, counterparty, delta, fee
+    )
+}
+
+fn process_transaction_history(
+    rpc_client: &RpcClient,
+    address: &Pubkey,
+    from_slot: u64,
+    to_slot: Option<u64>,
+) -> ProcessResult {
+    let current_slot = rpc_client.get_slot()?;
+    let now = Utc::now();
+    let to_slot = to_slot.unwrap_or(current_slot);
+    let mut rows = vec!["slot,time,signature,counterparty,delta,fee".to_string()];
+    let mut start_slot = from_slot;
+    while start_slot <= to_slot {
+        let end_slot = to_slot.min(start_slot.saturating_add(TRANSACTION_HISTORY_SLOT_RANGE - 1));
+        // A busy address is read a page of signatures at a time
+        let mut after = None;
+        loop {
+            let signatures = rpc_client.get_confirmed_signatures_for_address(
+                address,
+                start_slot,
+                end_slot,
+                after.as_ref(),
+                TRANSACTION_HISTORY_PAGE_SIZE,
+            )?;
+            for signature in &signatures {
+                let confirmed = rpc_client
+                    .get_confirmed_transaction(signature)?
+                    .ok_or_else(|| {
+                        WalletError::RpcRequestError(format!("Transaction {} not found", signature))
+                    })?;
+                let time = estimated_slot_time(confirmed.slot, current_slot, now);
+                rows.push(transaction_history_row(
+                    address, signature, &confirmed, time,
+                ));
+            }
+            if signatures.len() < TRANSACTION_HISTORY_PAGE_SIZE {
+                break;
+            }
+            after = signatures.last().cloned();
+        }
+        if end_slot == to_slot {
+            break;
+        }
+        start_slot = end_slot + 1;
+    }
+    Ok(rows.join("\n"))
+}
+
 fn process_time_elapsed(
     rpc_client: &RpcClient,
     config: &WalletConfig,
-    drone_addr: SocketAddr,
     to: &Pubkey,
     pubkey: &Pubkey,
     dt: DateTime<Utc>,
@@ -953,7 +1789,8 @@ fn process_time_elapsed(
     let balance = rpc_client.retry_get_balance(&config.keypair.pubkey(), 5)?;
 
     if let Some(0) = balance {
-        request_and_confirm_airdrop(&rpc_client, &drone_addr, &config.keypair.pubkey(), 1)?;
+        // Pay the fee with an airdrop from the drone the RPC node knows of
+        rpc_client.request_airdrop(&config.keypair.pubkey(), 1)?;
     }
 
     let (blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
@@ -966,17 +1803,39 @@ fn process_time_elapsed(
     Ok(signature_str.to_string())
 }
 
+fn process_clock_elapsed(
+    rpc_client: &RpcClient,
+    config: &WalletConfig,
+    to: &Pubkey,
+    pubkey: &Pubkey,
+) -> ProcessResult {
+    let balance = rpc_client.retry_get_balance(&config.keypair.pubkey(), 5)?;
+
+    if let Some(0) = balance {
+        // Pay the fee with an airdrop from the drone the RPC node knows of
+        rpc_client.request_airdrop(&config.keypair.pubkey(), 1)?;
+    }
+
+    let (blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
+    let ix = budget_instruction::apply_clock(pubkey, to);
+    let mut tx = Transaction::new_signed_instructions(&[&config.keypair], vec![ix], blockhash);
+    let result = rpc_client.send_and_confirm_transaction(&mut tx, &[&config.keypair]);
+    let signature_str = log_instruction_custom_error::<BudgetError>(result)?;
+
+    Ok(signature_str.to_string())
+}
+
 fn process_witness(
     rpc_client: &RpcClient,
     config: &WalletConfig,
-    drone_addr: SocketAddr,
     to: &Pubkey,
     pubkey: &Pubkey,
 ) -> ProcessResult {
     let balance = rpc_client.retry_get_balance(&config.keypair.pubkey(), 5)?;
 
     if let Some(0) = balance {
-        request_and_confirm_airdrop(&rpc_client, &drone_addr, &config.keypair.pubkey(), 1)?;
+        // Pay the fee with an airdrop from the drone the RPC node knows of
+        rpc_client.request_airdrop(&config.keypair.pubkey(), 1)?;
     }
 
     let (blockhash, _fee_calculator) = rpc_client.get_recent_blockhash()?;
@@ -1017,7 +1876,7 @@ pub fn process_command(config: &WalletConfig) -> ProcessResult {
         }
 
         // Check client balance
-        WalletCommand::Balance(pubkey) => process_balance(&pubkey, &rpc_client),
+        WalletCommand::Balance(pubkey) => process_balance(&rpc_client, config, &pubkey),
 
         // Cancel a contract by contract Pubkey
         WalletCommand::Cancel(pubkey) => process_cancel(&rpc_client, config, &pubkey),
@@ -1027,14 +1886,14 @@ pub fn process_command(config: &WalletConfig) -> ProcessResult {
 
         // Create vote account
         WalletCommand::CreateVoteAccount(
-            vote_account_pubkey,
+            vote_account_keypair,
             node_pubkey,
             commission,
             lamports,
         ) => process_create_vote_account(
             &rpc_client,
             config,
-            &vote_account_pubkey,
+            vote_account_keypair,
             &node_pubkey,
             *commission,
             *lamports,
@@ -1056,6 +1915,22 @@ pub fn process_command(config: &WalletConfig) -> ProcessResult {
             process_show_vote_account(&rpc_client, config, &vote_account_pubkey)
         }
 
+        WalletCommand::WithdrawFromVoteAccount(
+            vote_account_keypair,
+            destination_account_pubkey,
+            lamports,
+        ) => process_withdraw_from_vote_account(
+            &rpc_client,
+            config,
+            &vote_account_keypair,
+            &destination_account_pubkey,
+            *lamports,
+        ),
+
+        WalletCommand::CreateStakeAccount(stake_account_keypair, lamports) => {
+            process_create_stake_account(&rpc_client, config, &stake_account_keypair, *lamports)
+        }
+
         WalletCommand::DelegateStake(stake_account_keypair, vote_account_pubkey, lamports) => {
             process_delegate_stake(
                 &rpc_client,
@@ -1128,6 +2003,34 @@ pub fn process_command(config: &WalletConfig) -> ProcessResult {
             process_show_storage_account(&rpc_client, config, &storage_account_pubkey)
         }
 
+        // Create a token, with its supply credited to a new account owned by this wallet
+        WalletCommand::CreateToken(supply, decimals, name, symbol, mintable) => {
+            let token_info = TokenInfo {
+                supply: *supply,
+                decimals: *decimals,
+                name: name.clone(),
+                symbol: symbol.clone(),
+                mint_authority: if *mintable {
+                    Some(config.keypair.pubkey())
+                } else {
+                    None
+                },
+            };
+            process_create_token(&rpc_client, config, token_info)
+        }
+
+        WalletCommand::CreateTokenAccount(token) => {
+            process_create_token_account(&rpc_client, config, &token)
+        }
+
+        WalletCommand::TransferToken(source, dest, amount) => {
+            process_transfer_token(&rpc_client, config, &source, &dest, *amount)
+        }
+
+        WalletCommand::ShowTokenAccount(token_account_pubkey) => {
+            process_show_token_account(&rpc_client, &token_account_pubkey)
+        }
+
         // Deploy a custom program to the chain
         WalletCommand::Deploy(ref program_location) => {
             process_deploy(&rpc_client, config, program_location)
@@ -1135,6 +2038,10 @@ pub fn process_command(config: &WalletConfig) -> ProcessResult {
 
         WalletCommand::GetTransactionCount => process_get_transaction_count(&rpc_client),
 
+        WalletCommand::TransactionHistory(address, from_slot, to_slot) => {
+            process_transaction_history(&rpc_client, &address, *from_slot, *to_slot)
+        }
+
         // If client has positive balance, pay lamports to another address
         WalletCommand::Pay(
             lamports,
@@ -1142,7 +2049,9 @@ pub fn process_command(config: &WalletConfig) -> ProcessResult {
             timestamp,
             timestamp_pubkey,
             ref witnesses,
+            require_any,
             cancelable,
+            instructions,
         ) => process_pay(
             &rpc_client,
             config,
@@ -1151,17 +2060,27 @@ pub fn process_command(config: &WalletConfig) -> ProcessResult {
             *timestamp,
             *timestamp_pubkey,
             witnesses,
+            *require_any,
             *cancelable,
+            instructions,
         ),
 
+        // Pay lamports to another address on a schedule of slots
+        WalletCommand::PayRecurring(lamports, to, interval, count) => {
+            process_pay_recurring(&rpc_client, config, *lamports, &to, *interval, *count)
+        }
+
         // Apply time elapsed to contract
         WalletCommand::TimeElapsed(to, pubkey, dt) => {
-            process_time_elapsed(&rpc_client, config, drone_addr, &to, &pubkey, *dt)
+            process_time_elapsed(&rpc_client, config, &to, &pubkey, *dt)
         }
 
         // Apply witness signature to contract
-        WalletCommand::Witness(to, pubkey) => {
-            process_witness(&rpc_client, config, drone_addr, &to, &pubkey)
+        WalletCommand::Witness(to, pubkey) => process_witness(&rpc_client, config, &to, &pubkey),
+
+        // Apply the current slot to a recurring payment contract
+        WalletCommand::ClockElapsed(to, pubkey) => {
+            process_clock_elapsed(&rpc_client, config, &to, &pubkey)
         }
     }
 }
@@ -1264,6 +2183,73 @@ fn is_pubkey(string: String) -> Result<(), String> {
     }
 }
 
+// Return an error if a blockhash cannot be parsed.
+fn is_hash(string: String) -> Result<(), String> {
+    match string.parse::<Hash>() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("{:?}", err)),
+    }
+}
+
+// Return an error if a signer cannot be parsed.
+fn is_pubkey_signature(string: String) -> Result<(), String> {
+    parse_pubkey_signature(&string).map(|_| ())
+}
+
+// Return an error if an amount cannot be parsed.
+fn is_amount(string: String) -> Result<(), String> {
+    parse_amount(&string).map(|_| ())
+}
+
+// Return an error if a positive integer cannot be parsed.
+fn is_positive_integer(string: String) -> Result<(), String> {
+    match string.parse::<u64>() {
+        Ok(0) => Err("must be positive".to_string()),
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("{:?}", err)),
+    }
+}
+
+// Return an error if a slot cannot be parsed.
+fn is_slot(string: String) -> Result<(), String> {
+    match string.parse::<u64>() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("{:?}", err)),
+    }
+}
+
+// Return an error if an instruction cannot be parsed.
+fn is_instruction(string: String) -> Result<(), String> {
+    parse_instruction(&string, &Pubkey::default()).map(|_| ())
+}
+
+// Arguments for signing a transaction on an offline host, and for sending it from an online one
+fn offline_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("sign_only")
+            .long("sign-only")
+            .takes_value(false)
+            .requires("blockhash")
+            .help("Sign the transaction and print its signatures instead of sending it"),
+        Arg::with_name("blockhash")
+            .long("blockhash")
+            .value_name("BLOCKHASH")
+            .takes_value(true)
+            .validator(is_hash)
+            .help("Sign with this blockhash instead of fetching a recent one"),
+        Arg::with_name("signer")
+            .long("signer")
+            .value_name("PUBKEY=SIGNATURE")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .requires("blockhash")
+            .conflicts_with("sign_only")
+            .validator(is_pubkey_signature)
+            .help("A signature printed by --sign-only, to send in place of a local one"),
+    ]
+}
+
 pub fn app<'ab, 'v>(name: &str, about: &'ab str, version: &'v str) -> App<'ab, 'v> {
     App::new(name)
         .about(about)
@@ -1277,10 +2263,11 @@ pub fn app<'ab, 'v>(name: &str, about: &'ab str, version: &'v str) -> App<'ab, '
                 .arg(
                     Arg::with_name("lamports")
                         .index(1)
-                        .value_name("NUM")
+                        .value_name("AMOUNT")
                         .takes_value(true)
                         .required(true)
-                        .help("The number of lamports to request"),
+                        .validator(is_amount)
+                        .help("The amount to request, in lamports, or in SOL with a SOL suffix"),
                 ),
         )
         .subcommand(
@@ -1308,6 +2295,7 @@ pub fn app<'ab, 'v>(name: &str, about: &'ab str, version: &'v str) -> App<'ab, '
                         .help("The process id of the transfer to cancel"),
                 ),
         )
+        .subcommand(config_subcommand())
         .subcommand(
             SubCommand::with_name("confirm")
                 .about("Confirm transaction by signature")
@@ -1321,8 +2309,10 @@ pub fn app<'ab, 'v>(name: &str, about: &'ab str, version: &'v str) -> App<'ab, '
                 ),
         )
         .subcommand(
-            SubCommand::with_name("authorize-voter")
+            SubCommand::with_name("vote-authorize-voter")
+                .alias("authorize-voter")
                 .about("Authorize a new vote signing keypair for the given vote account")
+                .args(&offline_args())
                 .arg(
                     Arg::with_name("vote_account_pubkey")
                         .index(1)
@@ -1353,14 +2343,14 @@ pub fn app<'ab, 'v>(name: &str, about: &'ab str, version: &'v str) -> App<'ab, '
         .subcommand(
             SubCommand::with_name("create-vote-account")
                 .about("Create a vote account")
+                .args(&offline_args())
                 .arg(
-                    Arg::with_name("vote_account_pubkey")
+                    Arg::with_name("vote_account_keypair_file")
                         .index(1)
-                        .value_name("PUBKEY")
+                        .value_name("KEYPAIR_FILE")
                         .takes_value(true)
                         .required(true)
-                        .validator(is_pubkey)
-                        .help("Vote account address to fund"),
+                        .help("Keypair file for the vote account to create and fund"),
                 )
                 .arg(
                     Arg::with_name("node_pubkey")
@@ -1374,10 +2364,11 @@ pub fn app<'ab, 'v>(name: &str, about: &'ab str, version: &'v str) -> App<'ab, '
                 .arg(
                     Arg::with_name("lamports")
                         .index(3)
-                        .value_name("NUM")
+                        .value_name("AMOUNT")
                         .takes_value(true)
                         .required(true)
-                        .help("The number of lamports to send to the vote account"),
+                        .validator(is_amount)
+                        .help("The amount to send to the vote account, in lamports, or in SOL with a SOL suffix"),
                 )
                 .arg(
                     Arg::with_name("commission")
@@ -1400,16 +2391,70 @@ pub fn app<'ab, 'v>(name: &str, about: &'ab str, version: &'v str) -> App<'ab, '
                         .help("Vote account pubkey"),
                 )
         )
+        .subcommand(
+            SubCommand::with_name("withdraw-from-vote-account")
+                .about("Withdraw lamports from a vote account")
+                .args(&offline_args())
+                .arg(
+                    Arg::with_name("vote_account_keypair_file")
+                        .index(1)
+                        .value_name("KEYPAIR_FILE")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Keypair file for the vote account"),
+                )
+                .arg(
+                    Arg::with_name("destination_account_pubkey")
+                        .index(2)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_pubkey)
+                        .help("The account to which the lamports should be transferred"),
+                )
+                .arg(
+                    Arg::with_name("lamports")
+                        .index(3)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_amount)
+                        .help("The amount to withdraw, in lamports, or in SOL with a SOL suffix"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("create-stake-account")
+                .about("Create a stake account, to delegate with delegate-stake")
+                .args(&offline_args())
+                .arg(
+                    Arg::with_name("stake_account_keypair_file")
+                        .index(1)
+                        .value_name("KEYPAIR_FILE")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Keypair file for the new stake account"),
+                )
+                .arg(
+                    Arg::with_name("lamports")
+                        .index(2)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_amount)
+                        .help("The amount to fund the stake account with, in lamports, or in SOL with a SOL suffix"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("delegate-stake")
                 .about("Delegate stake to a vote account")
+                .args(&offline_args())
                 .arg(
                     Arg::with_name("stake_account_keypair_file")
                         .index(1)
                         .value_name("KEYPAIR_FILE")
                         .takes_value(true)
                         .required(true)
-                        .help("Keypair file for the new stake account"),
+                        .help("Keypair file for the stake account, created with create-stake-account"),
                 )
                 .arg(
                     Arg::with_name("vote_account_pubkey")
@@ -1423,15 +2468,17 @@ pub fn app<'ab, 'v>(name: &str, about: &'ab str, version: &'v str) -> App<'ab, '
                 .arg(
                     Arg::with_name("lamports_to_stake")
                         .index(3)
-                        .value_name("NUM")
+                        .value_name("AMOUNT")
                         .takes_value(true)
                         .required(true)
-                        .help("The number of lamports to stake"),
+                        .validator(is_amount)
+                        .help("The amount in the stake account to stake, in lamports, or in SOL with a SOL suffix"),
                 ),
         )
         .subcommand(
             SubCommand::with_name("deactivate-stake")
                 .about("Deactivate the delegated stake from the stake account")
+                .args(&offline_args())
                 .arg(
                     Arg::with_name("stake_account_keypair_file")
                         .index(1)
@@ -1444,6 +2491,7 @@ pub fn app<'ab, 'v>(name: &str, about: &'ab str, version: &'v str) -> App<'ab, '
         .subcommand(
             SubCommand::with_name("withdraw-stake")
                 .about("Withdraw the unstaked lamports from the stake account")
+                .args(&offline_args())
                 .arg(
                     Arg::with_name("stake_account_keypair_file")
                         .index(1)
@@ -1464,10 +2512,11 @@ pub fn app<'ab, 'v>(name: &str, about: &'ab str, version: &'v str) -> App<'ab, '
                 .arg(
                     Arg::with_name("lamports")
                         .index(3)
-                        .value_name("NUM")
+                        .value_name("AMOUNT")
                         .takes_value(true)
                         .required(true)
-                        .help("The number of lamports to to withdraw from the stake account."),
+                        .validator(is_amount)
+                        .help("The amount to withdraw from the stake account, in lamports, or in SOL with a SOL suffix"),
                 ),
         )
         .subcommand(
@@ -1529,10 +2578,11 @@ pub fn app<'ab, 'v>(name: &str, about: &'ab str, version: &'v str) -> App<'ab, '
                 .arg(
                     Arg::with_name("lamports")
                         .index(2)
-                        .value_name("NUM")
+                        .value_name("AMOUNT")
                         .takes_value(true)
                         .required(true)
-                        .help("The number of lamports to assign to the storage mining pool account"),
+                        .validator(is_amount)
+                        .help("The amount to assign to the storage mining pool account, in lamports, or in SOL with a SOL suffix"),
                 ),
         )
         .subcommand(
@@ -1611,99 +2661,331 @@ pub fn app<'ab, 'v>(name: &str, about: &'ab str, version: &'v str) -> App<'ab, '
                 )
         )
         .subcommand(
-            SubCommand::with_name("deploy")
-                .about("Deploy a program")
+            SubCommand::with_name("create-token")
+                .about("Create a token, with its supply held by a new token account of this wallet")
                 .arg(
-                    Arg::with_name("program_location")
+                    Arg::with_name("supply")
                         .index(1)
-                        .value_name("PATH")
+                        .value_name("SUPPLY")
                         .takes_value(true)
                         .required(true)
-                        .help("/path/to/program.o"),
-                ), // TODO: Add "loader" argument; current default is bpf_loader
-        )
-        .subcommand(
-            SubCommand::with_name("get-transaction-count")
-                .about("Get current transaction count"),
-        )
-        .subcommand(
-            SubCommand::with_name("pay")
-                .about("Send a payment")
+                        .validator(is_positive_integer)
+                        .help("The number of tokens to create, in the token's smallest unit"),
+                )
                 .arg(
-                    Arg::with_name("to")
-                        .index(1)
-                        .value_name("PUBKEY")
+                    Arg::with_name("name")
+                        .long("name")
+                        .value_name("NAME")
                         .takes_value(true)
                         .required(true)
-                        .validator(is_pubkey)
-                        .help("The pubkey of recipient"),
+                        .help("Descriptive name of the token"),
                 )
                 .arg(
-                    Arg::with_name("lamports")
-                        .index(2)
-                        .value_name("NUM")
+                    Arg::with_name("symbol")
+                        .long("symbol")
+                        .value_name("SYMBOL")
                         .takes_value(true)
                         .required(true)
-                        .help("The number of lamports to send"),
+                        .help("Symbol of the token"),
                 )
                 .arg(
-                    Arg::with_name("timestamp")
-                        .long("after")
-                        .value_name("DATETIME")
+                    Arg::with_name("decimals")
+                        .long("decimals")
+                        .value_name("DECIMALS")
                         .takes_value(true)
-                        .help("A timestamp after which transaction will execute"),
+                        .default_value("0")
+                        .validator(|value| value.parse::<u8>().map(|_| ()).map_err(|err| err.to_string()))
+                        .help("Number of base 10 digits to the right of the decimal place"),
                 )
                 .arg(
-                    Arg::with_name("timestamp_pubkey")
-                        .long("require-timestamp-from")
-                        .value_name("PUBKEY")
-                        .takes_value(true)
-                        .requires("timestamp")
-                        .validator(is_pubkey)
-                        .help("Require timestamp from this third party"),
-                )
+                    Arg::with_name("mintable")
+                        .long("mintable")
+                        .takes_value(false)
+                        .help("Let this wallet mint more of the token later"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("create-token-account")
+                .about("Create a token account owned by this wallet")
                 .arg(
-                    Arg::with_name("witness")
-                        .long("require-signature-from")
-                        .value_name("PUBKEY")
+                    Arg::with_name("token")
+                        .index(1)
+                        .value_name("TOKEN")
                         .takes_value(true)
-                        .multiple(true)
-                        .use_delimiter(true)
+                        .required(true)
                         .validator(is_pubkey)
-                        .help("Any third party signatures required to unlock the lamports"),
-                )
-                .arg(
-                    Arg::with_name("cancelable")
-                        .long("cancelable")
-                        .takes_value(false),
+                        .help("The token the account holds"),
                 ),
         )
         .subcommand(
-            SubCommand::with_name("send-signature")
-                .about("Send a signature to authorize a transfer")
+            SubCommand::with_name("transfer-token")
+                .about("Transfer tokens out of a token account owned by this wallet")
                 .arg(
-                    Arg::with_name("to")
+                    Arg::with_name("source")
                         .index(1)
-                        .value_name("PUBKEY")
+                        .value_name("SOURCE_ACCOUNT")
                         .takes_value(true)
                         .required(true)
                         .validator(is_pubkey)
-                        .help("The pubkey of recipient"),
+                        .help("The token account to transfer from"),
                 )
                 .arg(
-                    Arg::with_name("process_id")
+                    Arg::with_name("dest")
                         .index(2)
-                        .value_name("PROCESS_ID")
+                        .value_name("DESTINATION_ACCOUNT")
                         .takes_value(true)
                         .required(true)
-                        .help("The process id of the transfer to authorize"),
+                        .validator(is_pubkey)
+                        .help("The token account to transfer to"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .index(3)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_positive_integer)
+                        .help("The number of tokens to transfer, in the token's smallest unit"),
                 ),
         )
         .subcommand(
-            SubCommand::with_name("send-timestamp")
-                .about("Send a timestamp to unlock a transfer")
+            SubCommand::with_name("show-token-account")
+                .about("Show the contents of a token or token account")
                 .arg(
-                    Arg::with_name("to")
+                    Arg::with_name("token_account_pubkey")
+                        .index(1)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_pubkey)
+                        .help("Token or token account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("deploy")
+                .about("Deploy a program")
+                .arg(
+                    Arg::with_name("program_location")
+                        .index(1)
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .required(true)
+                        .help("/path/to/program.o"),
+                ), // TODO: Add "loader" argument; current default is bpf_loader
+        )
+        .subcommand(
+            SubCommand::with_name("get-transaction-count")
+                .about("Get current transaction count"),
+        )
+        .subcommand(
+            SubCommand::with_name("transaction-history")
+                .about("Export the balance changes of an address's confirmed transactions")
+                .arg(
+                    Arg::with_name("address")
+                        .index(1)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_pubkey)
+                        .help("The address to export the transactions of"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .takes_value(true)
+                        .possible_values(&["csv"])
+                        .default_value("csv")
+                        .help("Output format"),
+                )
+                .arg(
+                    Arg::with_name("from_slot")
+                        .long("from-slot")
+                        .value_name("SLOT")
+                        .takes_value(true)
+                        .default_value("0")
+                        .validator(is_slot)
+                        .help("The first slot to export"),
+                )
+                .arg(
+                    Arg::with_name("to_slot")
+                        .long("to-slot")
+                        .value_name("SLOT")
+                        .takes_value(true)
+                        .validator(is_slot)
+                        .help("The last slot to export [default: the current slot]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("pay")
+                .about("Send a payment")
+                .args(&offline_args())
+                .arg(
+                    Arg::with_name("to")
+                        .index(1)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_pubkey)
+                        .help("The pubkey of recipient"),
+                )
+                .arg(
+                    Arg::with_name("lamports")
+                        .index(2)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_amount)
+                        .help("The amount to send, in lamports, or in SOL with a SOL suffix"),
+                )
+                .arg(
+                    Arg::with_name("timestamp")
+                        .long("after")
+                        .value_name("DATETIME")
+                        .takes_value(true)
+                        .help("A timestamp after which transaction will execute"),
+                )
+                .arg(
+                    Arg::with_name("timestamp_pubkey")
+                        .long("require-timestamp-from")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .requires("timestamp")
+                        .validator(is_pubkey)
+                        .help("Require timestamp from this third party"),
+                )
+                .arg(
+                    Arg::with_name("witness")
+                        .long("require-signature-from")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .multiple(true)
+                        .use_delimiter(true)
+                        .validator(is_pubkey)
+                        .help("Any third party signatures required to unlock the lamports"),
+                )
+                .arg(
+                    Arg::with_name("require_any")
+                        .long("require-any")
+                        .takes_value(false)
+                        .help(
+                            "Unlock the lamports once any one of the timestamp and signatures \
+                             is received, rather than all of them",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("cancelable")
+                        .long("cancelable")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("memo")
+                        .long("with-memo")
+                        .value_name("MEMO")
+                        .takes_value(true)
+                        .help("Attach a memo to the payment"),
+                )
+                .arg(
+                    Arg::with_name("instruction")
+                        .long("instruction")
+                        .value_name("PROGRAM_ID:DATA:ACCOUNTS")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .validator(is_instruction)
+                        .help(
+                            "Also execute this instruction, in the same transaction. \
+                             DATA is base58 encoded, ACCOUNTS are comma-separated pubkeys",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("pay-recurring")
+                .about("Send a payment in equal installments on a schedule of slots")
+                .arg(
+                    Arg::with_name("to")
+                        .index(1)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_pubkey)
+                        .help("The pubkey of recipient"),
+                )
+                .arg(
+                    Arg::with_name("lamports")
+                        .index(2)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_amount)
+                        .help("The amount of each installment, in lamports, or in SOL with a SOL suffix"),
+                )
+                .arg(
+                    Arg::with_name("interval")
+                        .long("every")
+                        .value_name("SLOTS")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_positive_integer)
+                        .help("The number of slots between installments"),
+                )
+                .arg(
+                    Arg::with_name("count")
+                        .long("times")
+                        .value_name("COUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_positive_integer)
+                        .help("The number of installments"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("send-signature")
+                .about("Send a signature to authorize a transfer")
+                .arg(
+                    Arg::with_name("to")
+                        .index(1)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_pubkey)
+                        .help("The pubkey of recipient"),
+                )
+                .arg(
+                    Arg::with_name("process_id")
+                        .index(2)
+                        .value_name("PROCESS_ID")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The process id of the transfer to authorize"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("send-clock")
+                .about("Send the current slot to release the due installments of a recurring payment")
+                .arg(
+                    Arg::with_name("to")
+                        .index(1)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_pubkey)
+                        .help("The pubkey of recipient"),
+                )
+                .arg(
+                    Arg::with_name("process_id")
+                        .index(2)
+                        .value_name("PROCESS_ID")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The process id of the recurring payment"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("send-timestamp")
+                .about("Send a timestamp to unlock a transfer")
+                .arg(
+                    Arg::with_name("to")
                         .index(1)
                         .value_name("PUBKEY")
                         .takes_value(true)
@@ -1733,7 +3015,7 @@ pub fn app<'ab, 'v>(name: &str, about: &'ab str, version: &'v str) -> App<'ab, '
 mod tests {
     use super::*;
     use serde_json::Value;
-    use solana_client::mock_rpc_client_request::SIGNATURE;
+    use solana_client::mock_rpc_client_request::{PUBKEY, SIGNATURE};
     use solana_sdk::signature::gen_keypair_file;
     use solana_sdk::transaction::TransactionError;
     use std::net::{Ipv4Addr, SocketAddr};
@@ -1759,6 +3041,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(parse_amount("42"), Ok(42));
+        assert_eq!(parse_amount("42lamports"), Ok(42));
+        assert_eq!(parse_amount("42 lamports"), Ok(42));
+        assert_eq!(parse_amount("1SOL"), Ok(LAMPORTS_PER_SOL));
+        assert_eq!(parse_amount("1.5 SOL"), Ok(LAMPORTS_PER_SOL / 2 * 3));
+        assert_eq!(parse_amount("0.5SOL"), Ok(LAMPORTS_PER_SOL / 2));
+
+        // Fractional amounts need a unit
+        assert!(parse_amount("1.5").is_err());
+        assert!(parse_amount("1.5lamports").is_err());
+        assert!(parse_amount("-1SOL").is_err());
+        assert!(parse_amount("1e12SOL").is_err());
+        assert!(parse_amount("SOL").is_err());
+    }
+
+    #[test]
+    fn test_format_lamports() {
+        assert_eq!(format_lamports(1, true), "1 lamport");
+        assert_eq!(format_lamports(42, true), "42 lamports");
+        assert_eq!(
+            format_lamports(LAMPORTS_PER_SOL / 2 * 3, false),
+            format!("1.500000000 SOL ({} lamports)", LAMPORTS_PER_SOL / 2 * 3)
+        );
+    }
+
     #[test]
     fn test_wallet_parse_command() {
         let test_commands = app("test", "desc", "version");
@@ -1815,6 +3124,20 @@ mod tests {
         gen_keypair_file(&keypair_file).unwrap();
         let keypair = read_keypair(&keypair_file).unwrap();
 
+        let test_authorize_voter = test_commands.clone().get_matches_from(vec![
+            "test",
+            "vote-authorize-voter",
+            &pubkey_string,
+            &keypair_file,
+            &pubkey_string,
+        ]);
+        assert_eq!(
+            parse_command(&pubkey, &test_authorize_voter).unwrap(),
+            WalletCommand::AuthorizeVoter(pubkey, keypair, pubkey)
+        );
+
+        // The old name still works
+        let keypair = read_keypair(&keypair_file).unwrap();
         let test_authorize_voter = test_commands.clone().get_matches_from(vec![
             "test",
             "authorize-voter",
@@ -1827,13 +3150,28 @@ mod tests {
             WalletCommand::AuthorizeVoter(pubkey, keypair, pubkey)
         );
 
+        // Test WithdrawFromVoteAccount Subcommand
+        let keypair = read_keypair(&keypair_file).unwrap();
+        let test_withdraw_from_vote_account = test_commands.clone().get_matches_from(vec![
+            "test",
+            "withdraw-from-vote-account",
+            &keypair_file,
+            &pubkey_string,
+            "42",
+        ]);
+        assert_eq!(
+            parse_command(&pubkey, &test_withdraw_from_vote_account).unwrap(),
+            WalletCommand::WithdrawFromVoteAccount(keypair, pubkey, 42)
+        );
+
         // Test CreateVoteAccount SubCommand
         let node_pubkey = Pubkey::new_rand();
         let node_pubkey_string = format!("{}", node_pubkey);
+        let keypair = read_keypair(&keypair_file).unwrap();
         let test_create_vote_account = test_commands.clone().get_matches_from(vec![
             "test",
             "create-vote-account",
-            &pubkey_string,
+            &keypair_file,
             &node_pubkey_string,
             "50",
             "--commission",
@@ -1841,18 +3179,19 @@ mod tests {
         ]);
         assert_eq!(
             parse_command(&pubkey, &test_create_vote_account).unwrap(),
-            WalletCommand::CreateVoteAccount(pubkey, node_pubkey, 10, 50)
+            WalletCommand::CreateVoteAccount(keypair, node_pubkey, 10, 50)
         );
+        let keypair = read_keypair(&keypair_file).unwrap();
         let test_create_vote_account2 = test_commands.clone().get_matches_from(vec![
             "test",
             "create-vote-account",
-            &pubkey_string,
+            &keypair_file,
             &node_pubkey_string,
             "50",
         ]);
         assert_eq!(
             parse_command(&pubkey, &test_create_vote_account2).unwrap(),
-            WalletCommand::CreateVoteAccount(pubkey, node_pubkey, 0, 50)
+            WalletCommand::CreateVoteAccount(keypair, node_pubkey, 0, 50)
         );
 
         // Test DelegateStake Subcommand
@@ -1886,6 +3225,19 @@ mod tests {
             WalletCommand::DelegateStake(keypair, pubkey, 42)
         );
 
+        // Test CreateStakeAccount Subcommand
+        let keypair = read_keypair(&keypair_file).unwrap();
+        let test_create_stake_account = test_commands.clone().get_matches_from(vec![
+            "test",
+            "create-stake-account",
+            &keypair_file,
+            "42",
+        ]);
+        assert_eq!(
+            parse_command(&pubkey, &test_create_stake_account).unwrap(),
+            WalletCommand::CreateStakeAccount(keypair, 42)
+        );
+
         // Test WithdrawStake Subcommand
         let test_withdraw_stake = test_commands.clone().get_matches_from(vec![
             "test",
@@ -1930,7 +3282,7 @@ mod tests {
                 .get_matches_from(vec!["test", "pay", &pubkey_string, "50"]);
         assert_eq!(
             parse_command(&pubkey, &test_pay).unwrap(),
-            WalletCommand::Pay(50, pubkey, None, None, None, None)
+            WalletCommand::Pay(50, pubkey, None, None, None, false, None, vec![])
         );
 
         // Test Pay Subcommand w/ Witness
@@ -1946,7 +3298,16 @@ mod tests {
         ]);
         assert_eq!(
             parse_command(&pubkey, &test_pay_multiple_witnesses).unwrap(),
-            WalletCommand::Pay(50, pubkey, None, None, Some(vec![witness0, witness1]), None)
+            WalletCommand::Pay(
+                50,
+                pubkey,
+                None,
+                None,
+                Some(vec![witness0, witness1]),
+                false,
+                None,
+                vec![]
+            )
         );
         let test_pay_single_witness = test_commands.clone().get_matches_from(vec![
             "test",
@@ -1958,7 +3319,16 @@ mod tests {
         ]);
         assert_eq!(
             parse_command(&pubkey, &test_pay_single_witness).unwrap(),
-            WalletCommand::Pay(50, pubkey, None, None, Some(vec![witness0]), None)
+            WalletCommand::Pay(
+                50,
+                pubkey,
+                None,
+                None,
+                Some(vec![witness0]),
+                false,
+                None,
+                vec![]
+            )
         );
 
         // Test Pay Subcommand w/ Timestamp
@@ -1974,9 +3344,88 @@ mod tests {
         ]);
         assert_eq!(
             parse_command(&pubkey, &test_pay_timestamp).unwrap(),
-            WalletCommand::Pay(50, pubkey, Some(dt), Some(witness0), None, None)
+            WalletCommand::Pay(
+                50,
+                pubkey,
+                Some(dt),
+                Some(witness0),
+                None,
+                false,
+                None,
+                vec![]
+            )
+        );
+
+        // Test Pay Subcommand w/ SOL
+        let test_pay_sol =
+            test_commands
+                .clone()
+                .get_matches_from(vec!["test", "pay", &pubkey_string, "0.5SOL"]);
+        assert_eq!(
+            parse_command(&pubkey, &test_pay_sol).unwrap(),
+            WalletCommand::Pay(
+                LAMPORTS_PER_SOL / 2,
+                pubkey,
+                None,
+                None,
+                None,
+                false,
+                None,
+                vec![]
+            )
         );
 
+        // Test Pay Subcommand w/ Memo and Instruction
+        let program_id = Pubkey::new_rand();
+        let instruction_string = format!(
+            "{}:{}:{},{}",
+            program_id,
+            bs58::encode(&[1, 2, 3]).into_string(),
+            pubkey,
+            witness0
+        );
+        let test_pay_memo = test_commands.clone().get_matches_from(vec![
+            "test",
+            "pay",
+            &pubkey_string,
+            "50",
+            "--with-memo",
+            "thanks",
+            "--instruction",
+            &instruction_string,
+        ]);
+        let instruction = Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(pubkey, true),
+                AccountMeta::new(witness0, false),
+            ],
+            data: vec![1, 2, 3],
+        };
+        assert_eq!(
+            parse_command(&pubkey, &test_pay_memo).unwrap(),
+            WalletCommand::Pay(
+                50,
+                pubkey,
+                None,
+                None,
+                None,
+                false,
+                None,
+                vec![memo_instruction::memo("thanks"), instruction]
+            )
+        );
+        let bad_instruction_string = format!("{}:not-base58:", program_id);
+        let test_pay_bad_instruction = test_commands.clone().get_matches_from_safe(vec![
+            "test",
+            "pay",
+            &pubkey_string,
+            "50",
+            "--instruction",
+            &bad_instruction_string,
+        ]);
+        assert!(test_pay_bad_instruction.is_err());
+
         // Test Send-Signature Subcommand
         let test_send_signature = test_commands.clone().get_matches_from(vec![
             "test",
@@ -2010,9 +3459,162 @@ mod tests {
                 Some(dt),
                 Some(witness0),
                 Some(vec![witness0, witness1]),
-                None
+                false,
+                None,
+                vec![]
             )
         );
+        let test_pay_require_any = test_commands.clone().get_matches_from(vec![
+            "test",
+            "pay",
+            &pubkey_string,
+            "50",
+            "--after",
+            "2018-09-19T17:30:59",
+            "--require-signature-from",
+            &witness0_string,
+            "--require-any",
+        ]);
+        assert_eq!(
+            parse_command(&pubkey, &test_pay_require_any).unwrap(),
+            WalletCommand::Pay(
+                50,
+                pubkey,
+                Some(dt),
+                None,
+                Some(vec![witness0]),
+                true,
+                None,
+                vec![]
+            )
+        );
+
+        // Test Pay-Recurring Subcommand
+        let test_pay_recurring = test_commands.clone().get_matches_from(vec![
+            "test",
+            "pay-recurring",
+            &pubkey_string,
+            "50",
+            "--every",
+            "100",
+            "--times",
+            "3",
+        ]);
+        assert_eq!(
+            parse_command(&pubkey, &test_pay_recurring).unwrap(),
+            WalletCommand::PayRecurring(50, pubkey, 100, 3)
+        );
+        let test_pay_recurring_no_interval = test_commands.clone().get_matches_from_safe(vec![
+            "test",
+            "pay-recurring",
+            &pubkey_string,
+            "50",
+            "--every",
+            "0",
+            "--times",
+            "3",
+        ]);
+        assert!(test_pay_recurring_no_interval.is_err());
+
+        // Test Send-Clock Subcommand
+        let test_send_clock = test_commands.clone().get_matches_from(vec![
+            "test",
+            "send-clock",
+            &pubkey_string,
+            &pubkey_string,
+        ]);
+        assert_eq!(
+            parse_command(&pubkey, &test_send_clock).unwrap(),
+            WalletCommand::ClockElapsed(pubkey, pubkey)
+        );
+
+        // Test Token Subcommands
+        let test_create_token = test_commands.clone().get_matches_from(vec![
+            "test",
+            "create-token",
+            "1000",
+            "--name",
+            "Test Token",
+            "--symbol",
+            "TEST",
+            "--decimals",
+            "2",
+            "--mintable",
+        ]);
+        assert_eq!(
+            parse_command(&pubkey, &test_create_token).unwrap(),
+            WalletCommand::CreateToken(1000, 2, "Test Token".to_string(), "TEST".to_string(), true)
+        );
+        let test_create_token_bad_decimals = test_commands.clone().get_matches_from_safe(vec![
+            "test",
+            "create-token",
+            "1000",
+            "--name",
+            "Test Token",
+            "--symbol",
+            "TEST",
+            "--decimals",
+            "256",
+        ]);
+        assert!(test_create_token_bad_decimals.is_err());
+
+        let test_create_token_account = test_commands.clone().get_matches_from(vec![
+            "test",
+            "create-token-account",
+            &pubkey_string,
+        ]);
+        assert_eq!(
+            parse_command(&pubkey, &test_create_token_account).unwrap(),
+            WalletCommand::CreateTokenAccount(pubkey)
+        );
+
+        let test_transfer_token = test_commands.clone().get_matches_from(vec![
+            "test",
+            "transfer-token",
+            &pubkey_string,
+            &pubkey_string,
+            "50",
+        ]);
+        assert_eq!(
+            parse_command(&pubkey, &test_transfer_token).unwrap(),
+            WalletCommand::TransferToken(pubkey, pubkey, 50)
+        );
+
+        let test_show_token_account = test_commands.clone().get_matches_from(vec![
+            "test",
+            "show-token-account",
+            &pubkey_string,
+        ]);
+        assert_eq!(
+            parse_command(&pubkey, &test_show_token_account).unwrap(),
+            WalletCommand::ShowTokenAccount(pubkey)
+        );
+
+        // Test TransactionHistory Subcommand
+        let test_transaction_history = test_commands.clone().get_matches_from(vec![
+            "test",
+            "transaction-history",
+            &pubkey_string,
+        ]);
+        assert_eq!(
+            parse_command(&pubkey, &test_transaction_history).unwrap(),
+            WalletCommand::TransactionHistory(pubkey, 0, None)
+        );
+        let test_transaction_history = test_commands.clone().get_matches_from(vec![
+            "test",
+            "transaction-history",
+            &pubkey_string,
+            "--format",
+            "csv",
+            "--from-slot",
+            "10",
+            "--to-slot",
+            "20",
+        ]);
+        assert_eq!(
+            parse_command(&pubkey, &test_transaction_history).unwrap(),
+            WalletCommand::TransactionHistory(pubkey, 10, Some(20))
+        );
 
         // Test Send-Timestamp Subcommand
         let test_send_timestamp = test_commands.clone().get_matches_from(vec![
@@ -2051,7 +3653,13 @@ mod tests {
         assert_eq!(process_command(&config).unwrap(), pubkey);
 
         config.command = WalletCommand::Balance(config.keypair.pubkey());
+        assert_eq!(
+            process_command(&config).unwrap(),
+            "0.000000003 SOL (50 lamports)"
+        );
+        config.lamports_only = true;
         assert_eq!(process_command(&config).unwrap(), "50 lamports");
+        config.lamports_only = false;
 
         let process_id = Pubkey::new_rand();
         config.command = WalletCommand::Cancel(process_id);
@@ -2061,9 +3669,10 @@ mod tests {
         config.command = WalletCommand::Confirm(good_signature);
         assert_eq!(process_command(&config).unwrap(), "Confirmed");
 
-        let bob_pubkey = Pubkey::new_rand();
+        let bob_keypair = Keypair::new();
+        let bob_pubkey = bob_keypair.pubkey();
         let node_pubkey = Pubkey::new_rand();
-        config.command = WalletCommand::CreateVoteAccount(bob_pubkey, node_pubkey, 0, 10);
+        config.command = WalletCommand::CreateVoteAccount(bob_keypair, node_pubkey, 0, 10);
         let signature = process_command(&config);
         assert_eq!(signature.unwrap(), SIGNATURE.to_string());
 
@@ -2072,6 +3681,16 @@ mod tests {
         let signature = process_command(&config);
         assert_eq!(signature.unwrap(), SIGNATURE.to_string());
 
+        let bob_keypair = Keypair::new();
+        config.command = WalletCommand::WithdrawFromVoteAccount(bob_keypair, node_pubkey, 10);
+        let signature = process_command(&config);
+        assert_eq!(signature.unwrap(), SIGNATURE.to_string());
+
+        let bob_keypair = Keypair::new();
+        config.command = WalletCommand::CreateStakeAccount(bob_keypair, 100);
+        let signature = process_command(&config);
+        assert_eq!(signature.unwrap(), SIGNATURE.to_string());
+
         let bob_keypair = Keypair::new();
         let node_pubkey = Pubkey::new_rand();
         config.command = WalletCommand::DelegateStake(bob_keypair.into(), node_pubkey, 100);
@@ -2092,7 +3711,36 @@ mod tests {
         config.command = WalletCommand::GetTransactionCount;
         assert_eq!(process_command(&config).unwrap(), "1234");
 
-        config.command = WalletCommand::Pay(10, bob_pubkey, None, None, None, None);
+        // The mock transfers 50 lamports to PUBKEY in every range of slots
+        let address = PUBKEY.parse().unwrap();
+        config.command = WalletCommand::TransactionHistory(address, 0, None);
+        let csv = process_command(&config).unwrap();
+        let rows: Vec<_> = csv.lines().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0], "slot,time,signature,counterparty,delta,fee");
+        let columns: Vec<_> = rows[1].split(',').collect();
+        assert_eq!(columns[0], "1");
+        assert!(DateTime::parse_from_rfc3339(columns[1]).is_ok());
+        assert_eq!(columns[2], SIGNATURE);
+        assert!(rows[1].ends_with(",50,0"));
+        config.command =
+            WalletCommand::TransactionHistory(address, 0, Some(TRANSACTION_HISTORY_SLOT_RANGE));
+        assert_eq!(process_command(&config).unwrap().lines().count(), 3);
+
+        config.command = WalletCommand::Pay(10, bob_pubkey, None, None, None, false, None, vec![]);
+        let signature = process_command(&config);
+        assert_eq!(signature.unwrap(), SIGNATURE.to_string());
+
+        config.command = WalletCommand::Pay(
+            10,
+            bob_pubkey,
+            None,
+            None,
+            None,
+            false,
+            None,
+            vec![memo_instruction::memo("thanks")],
+        );
         let signature = process_command(&config);
         assert_eq!(signature.unwrap(), SIGNATURE.to_string());
 
@@ -2104,7 +3752,9 @@ mod tests {
             Some(dt),
             Some(config.keypair.pubkey()),
             None,
+            false,
             None,
+            vec![],
         );
         let result = process_command(&config);
         let json: Value = serde_json::from_str(&result.unwrap()).unwrap();
@@ -2125,7 +3775,9 @@ mod tests {
             None,
             None,
             Some(vec![witness]),
+            false,
             Some(config.keypair.pubkey()),
+            vec![],
         );
         let result = process_command(&config);
         let json: Value = serde_json::from_str(&result.unwrap()).unwrap();
@@ -2139,6 +3791,44 @@ mod tests {
             SIGNATURE.to_string()
         );
 
+        for require_any in &[false, true] {
+            config.command = WalletCommand::Pay(
+                10,
+                bob_pubkey,
+                Some(dt),
+                None,
+                Some(vec![witness, Pubkey::new_rand()]),
+                *require_any,
+                None,
+                vec![],
+            );
+            let result = process_command(&config);
+            let json: Value = serde_json::from_str(&result.unwrap()).unwrap();
+            assert_eq!(
+                json.as_object()
+                    .unwrap()
+                    .get("signature")
+                    .unwrap()
+                    .as_str()
+                    .unwrap(),
+                SIGNATURE.to_string()
+            );
+        }
+
+        config.command = WalletCommand::PayRecurring(10, bob_pubkey, 100, 3);
+        let result = process_command(&config);
+        let json: Value = serde_json::from_str(&result.unwrap()).unwrap();
+        let json = json.as_object().unwrap();
+        assert_eq!(
+            json.get("signature").unwrap().as_str().unwrap(),
+            SIGNATURE.to_string()
+        );
+        assert_eq!(json.get("totalLamports").unwrap().as_u64().unwrap(), 30);
+        assert_eq!(json.get("startSlot").unwrap().as_u64().unwrap(), 100);
+
+        config.command = WalletCommand::PayRecurring(10, bob_pubkey, 0, 3);
+        assert!(process_command(&config).is_err());
+
         let process_id = Pubkey::new_rand();
         config.command = WalletCommand::TimeElapsed(bob_pubkey, process_id, dt);
         let signature = process_command(&config);
@@ -2149,6 +3839,57 @@ mod tests {
         let signature = process_command(&config);
         assert_eq!(signature.unwrap(), SIGNATURE.to_string());
 
+        config.command = WalletCommand::ClockElapsed(bob_pubkey, process_id);
+        let signature = process_command(&config);
+        assert_eq!(signature.unwrap(), SIGNATURE.to_string());
+
+        config.command = WalletCommand::CreateToken(
+            1000,
+            2,
+            "Test Token".to_string(),
+            "TEST".to_string(),
+            false,
+        );
+        let result = process_command(&config);
+        let json: Value = serde_json::from_str(&result.unwrap()).unwrap();
+        let json = json.as_object().unwrap();
+        assert_eq!(
+            json.get("signature").unwrap().as_str().unwrap(),
+            SIGNATURE.to_string()
+        );
+        assert!(json
+            .get("token")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .parse::<Pubkey>()
+            .is_ok());
+        assert!(json
+            .get("account")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .parse::<Pubkey>()
+            .is_ok());
+
+        let token = Pubkey::new_rand();
+        config.command = WalletCommand::CreateTokenAccount(token);
+        let result = process_command(&config);
+        let json: Value = serde_json::from_str(&result.unwrap()).unwrap();
+        assert_eq!(
+            json.as_object()
+                .unwrap()
+                .get("signature")
+                .unwrap()
+                .as_str()
+                .unwrap(),
+            SIGNATURE.to_string()
+        );
+
+        config.command = WalletCommand::TransferToken(Pubkey::new_rand(), bob_pubkey, 50);
+        let signature = process_command(&config);
+        assert_eq!(signature.unwrap(), SIGNATURE.to_string());
+
         // Need airdrop cases
         config.command = WalletCommand::Airdrop(50);
         assert!(process_command(&config).is_err());
@@ -2190,16 +3931,25 @@ mod tests {
         config.command = WalletCommand::Balance(config.keypair.pubkey());
         assert!(process_command(&config).is_err());
 
-        config.command = WalletCommand::CreateVoteAccount(bob_pubkey, node_pubkey, 0, 10);
+        config.command = WalletCommand::CreateVoteAccount(Keypair::new(), node_pubkey, 0, 10);
         assert!(process_command(&config).is_err());
 
         config.command = WalletCommand::AuthorizeVoter(bob_pubkey, Keypair::new(), bob_pubkey);
         assert!(process_command(&config).is_err());
 
+        config.command = WalletCommand::WithdrawFromVoteAccount(Keypair::new(), bob_pubkey, 10);
+        assert!(process_command(&config).is_err());
+
+        config.command = WalletCommand::CreateStakeAccount(Keypair::new(), 10);
+        assert!(process_command(&config).is_err());
+
         config.command = WalletCommand::GetTransactionCount;
         assert!(process_command(&config).is_err());
 
-        config.command = WalletCommand::Pay(10, bob_pubkey, None, None, None, None);
+        config.command = WalletCommand::TransactionHistory(bob_pubkey, 0, Some(0));
+        assert!(process_command(&config).is_err());
+
+        config.command = WalletCommand::Pay(10, bob_pubkey, None, None, None, false, None, vec![]);
         assert!(process_command(&config).is_err());
 
         config.command = WalletCommand::Pay(
@@ -2208,7 +3958,9 @@ mod tests {
             Some(dt),
             Some(config.keypair.pubkey()),
             None,
+            false,
             None,
+            vec![],
         );
         assert!(process_command(&config).is_err());
 
@@ -2218,7 +3970,9 @@ mod tests {
             None,
             None,
             Some(vec![witness]),
+            false,
             Some(config.keypair.pubkey()),
+            vec![],
         );
         assert!(process_command(&config).is_err());
 
@@ -2226,6 +3980,195 @@ mod tests {
         assert!(process_command(&config).is_err());
     }
 
+    #[test]
+    fn test_format_stake_activation() {
+        let mut stake = Stake {
+            stake: 300,
+            activated: 10,
+            ..Stake::default()
+        };
+        assert_eq!(
+            format_stake_activation(&stake, 9),
+            "inactive, activates at a later epoch (0 of 300 lamports active)"
+        );
+        assert_eq!(
+            format_stake_activation(&stake, 10),
+            "activating (100 of 300 lamports active)"
+        );
+        assert_eq!(
+            format_stake_activation(&stake, 13),
+            "active (300 of 300 lamports active)"
+        );
+
+        stake.deactivated = 20;
+        assert_eq!(
+            format_stake_activation(&stake, 19),
+            "deactivating (100 of 300 lamports active)"
+        );
+        assert_eq!(
+            format_stake_activation(&stake, 20),
+            "inactive (0 of 300 lamports active)"
+        );
+    }
+
+    #[test]
+    fn test_estimated_slot_time() {
+        let now = Utc.ymd(2019, 8, 21).and_hms(14, 0, 0);
+        assert_eq!(estimated_slot_time(10, 10, now), now);
+        let slot_ms = (1000 * DEFAULT_TICKS_PER_SLOT / DEFAULT_NUM_TICKS_PER_SECOND) as i64;
+        assert_eq!(
+            estimated_slot_time(4, 10, now),
+            now - chrono::Duration::milliseconds(6 * slot_ms)
+        );
+        // Slots after the current one are estimated to be produced now
+        assert_eq!(estimated_slot_time(11, 10, now), now);
+    }
+
+    #[test]
+    fn test_wallet_parse_signing_config() {
+        let test_commands = app("test", "desc", "version");
+        let pubkey = Pubkey::new_rand();
+        let pubkey_string = pubkey.to_string();
+        let blockhash = Hash::new(&[1; 32]);
+        let blockhash_string = blockhash.to_string();
+        let signature = Signature::new(&[2; 64]);
+        let signer_string = format!("{}={}", pubkey, signature);
+
+        let test_pay =
+            test_commands
+                .clone()
+                .get_matches_from(vec!["test", "pay", &pubkey_string, "50"]);
+        assert_eq!(parse_signing_config(&test_pay), SigningConfig::default());
+
+        let test_pay = test_commands.clone().get_matches_from(vec![
+            "test",
+            "pay",
+            &pubkey_string,
+            "50",
+            "--sign-only",
+            "--blockhash",
+            &blockhash_string,
+        ]);
+        assert_eq!(
+            parse_signing_config(&test_pay),
+            SigningConfig {
+                sign_only: true,
+                blockhash: Some(blockhash),
+                signers: vec![],
+            }
+        );
+
+        let test_delegate_stake = test_commands.clone().get_matches_from(vec![
+            "test",
+            "delegate-stake",
+            "/tmp/stake.json",
+            &pubkey_string,
+            "42",
+            "--blockhash",
+            &blockhash_string,
+            "--signer",
+            &signer_string,
+            "--signer",
+            &signer_string,
+        ]);
+        assert_eq!(
+            parse_signing_config(&test_delegate_stake),
+            SigningConfig {
+                sign_only: false,
+                blockhash: Some(blockhash),
+                signers: vec![(pubkey, signature), (pubkey, signature)],
+            }
+        );
+
+        // --sign-only and --signer need a blockhash to sign with
+        assert!(test_commands
+            .clone()
+            .get_matches_from_safe(vec!["test", "pay", &pubkey_string, "50", "--sign-only"])
+            .is_err());
+        assert!(test_commands
+            .clone()
+            .get_matches_from_safe(vec![
+                "test",
+                "pay",
+                &pubkey_string,
+                "50",
+                "--signer",
+                &signer_string
+            ])
+            .is_err());
+    }
+
+    #[test]
+    fn test_wallet_offline_signing() {
+        let keypair = Keypair::new();
+        let bob_pubkey = Pubkey::new_rand();
+        let blockhash = Hash::new(&[1; 32]);
+
+        // Sign on the offline host
+        let mut offline_config = WalletConfig::default();
+        offline_config.rpc_client = Some(RpcClient::new_mock("fails".to_string()));
+        offline_config.keypair = Keypair::from_bytes(&keypair.to_bytes()).unwrap();
+        offline_config.command =
+            WalletCommand::Pay(10, bob_pubkey, None, None, None, false, None, vec![]);
+        offline_config.signing = SigningConfig {
+            sign_only: true,
+            blockhash: Some(blockhash),
+            signers: vec![],
+        };
+        let result = process_command(&offline_config).unwrap();
+        let json: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(json["blockhash"], blockhash.to_string());
+        let signers = json["signers"].as_array().unwrap();
+        assert_eq!(signers.len(), 1);
+        let (signer_pubkey, signature) =
+            parse_pubkey_signature(signers[0].as_str().unwrap()).unwrap();
+        assert_eq!(signer_pubkey, keypair.pubkey());
+
+        // Send from the online host, which doesn't hold the keypair
+        let mut online_config = WalletConfig::default();
+        online_config.rpc_client = Some(RpcClient::new_mock("succeeds".to_string()));
+        online_config.command =
+            WalletCommand::Pay(10, bob_pubkey, None, None, None, false, None, vec![]);
+        online_config.signing = SigningConfig {
+            sign_only: false,
+            blockhash: Some(blockhash),
+            signers: vec![(signer_pubkey, signature)],
+        };
+        assert_eq!(process_command(&online_config).unwrap(), SIGNATURE);
+
+        // A signature for a different transaction is rejected
+        online_config.command =
+            WalletCommand::Pay(11, bob_pubkey, None, None, None, false, None, vec![]);
+        assert!(process_command(&online_config).is_err());
+
+        // So is a signature from a key that doesn't sign the transaction
+        online_config.command =
+            WalletCommand::Pay(10, bob_pubkey, None, None, None, false, None, vec![]);
+        online_config.signing.signers = vec![(bob_pubkey, signature)];
+        assert!(process_command(&online_config).is_err());
+
+        // Or a transaction left missing a signature
+        let mut tx = Transaction::new_unsigned_instructions(vec![system_instruction::transfer(
+            &keypair.pubkey(),
+            &bob_pubkey,
+            10,
+        )]);
+        assert!(replace_signatures(&mut tx, &[]).is_err());
+
+        // Budget contracts can't be signed offline
+        offline_config.command = WalletCommand::Pay(
+            10,
+            bob_pubkey,
+            None,
+            None,
+            Some(vec![bob_pubkey]),
+            false,
+            None,
+            vec![],
+        );
+        assert!(process_command(&offline_config).is_err());
+    }
+
     #[test]
     fn test_wallet_deploy() {
         solana_logger::setup();
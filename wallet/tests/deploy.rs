@@ -1,14 +1,13 @@
 use serde_json::{json, Value};
-use solana::validator::new_validator_for_tests;
+use solana::validator::new_validator_with_drone_for_tests;
 use solana_client::rpc_client::RpcClient;
 use solana_client::rpc_request::RpcRequest;
-use solana_drone::drone::run_local_drone;
 use solana_sdk::bpf_loader;
+use solana_sdk::signature::KeypairUtil;
 use solana_wallet::wallet::{process_command, WalletCommand, WalletConfig};
 use std::fs::{remove_dir_all, File};
 use std::io::Read;
 use std::path::PathBuf;
-use std::sync::mpsc::channel;
 
 #[test]
 fn test_wallet_deploy_program() {
@@ -20,19 +19,15 @@ fn test_wallet_deploy_program() {
     pathbuf.push("noop");
     pathbuf.set_extension("so");
 
-    let (server, leader_data, alice, ledger_path) = new_validator_for_tests();
-
-    let (sender, receiver) = channel();
-    run_local_drone(alice, sender, None);
-    let drone_addr = receiver.recv().unwrap();
+    let (server, leader_data, ledger_path) = new_validator_with_drone_for_tests();
 
     let rpc_client = RpcClient::new_socket(leader_data.rpc);
 
     let mut config = WalletConfig::default();
-    config.drone_port = drone_addr.port();
     config.json_rpc_url = format!("http://{}:{}", leader_data.rpc.ip(), leader_data.rpc.port());
-    config.command = WalletCommand::Airdrop(50);
-    process_command(&config).unwrap();
+    rpc_client
+        .request_airdrop(&config.keypair.pubkey(), 50)
+        .unwrap();
 
     config.command = WalletCommand::Deploy(pathbuf.to_str().unwrap().to_string());
 
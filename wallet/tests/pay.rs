@@ -1,17 +1,13 @@
 use chrono::prelude::*;
 use serde_json::Value;
 use solana_client::rpc_client::RpcClient;
-use solana_drone::drone::run_local_drone;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::KeypairUtil;
-use solana_wallet::wallet::{
-    process_command, request_and_confirm_airdrop, WalletCommand, WalletConfig,
-};
+use solana_sdk::signature::{Keypair, KeypairUtil, Signature};
+use solana_wallet::wallet::{process_command, SigningConfig, WalletCommand, WalletConfig};
 use std::fs::remove_dir_all;
-use std::sync::mpsc::channel;
 
 #[cfg(test)]
-use solana::validator::new_validator_for_tests;
+use solana::validator::new_validator_with_drone_for_tests;
 use std::thread::sleep;
 use std::time::Duration;
 
@@ -30,22 +26,16 @@ fn check_balance(expected_balance: u64, client: &RpcClient, pubkey: &Pubkey) {
 
 #[test]
 fn test_wallet_timestamp_tx() {
-    let (server, leader_data, alice, ledger_path) = new_validator_for_tests();
+    let (server, leader_data, ledger_path) = new_validator_with_drone_for_tests();
     let bob_pubkey = Pubkey::new_rand();
 
-    let (sender, receiver) = channel();
-    run_local_drone(alice, sender, None);
-    let drone_addr = receiver.recv().unwrap();
-
     let rpc_client = RpcClient::new_socket(leader_data.rpc);
 
     let mut config_payer = WalletConfig::default();
-    config_payer.drone_port = drone_addr.port();
     config_payer.json_rpc_url =
         format!("http://{}:{}", leader_data.rpc.ip(), leader_data.rpc.port());
 
     let mut config_witness = WalletConfig::default();
-    config_witness.drone_port = config_payer.drone_port;
     config_witness.json_rpc_url = config_payer.json_rpc_url.clone();
 
     assert_ne!(
@@ -53,7 +43,8 @@ fn test_wallet_timestamp_tx() {
         config_witness.keypair.pubkey()
     );
 
-    request_and_confirm_airdrop(&rpc_client, &drone_addr, &config_payer.keypair.pubkey(), 50)
+    rpc_client
+        .request_airdrop(&config_payer.keypair.pubkey(), 50)
         .unwrap();
     check_balance(50, &rpc_client, &config_payer.keypair.pubkey());
 
@@ -66,7 +57,9 @@ fn test_wallet_timestamp_tx() {
         Some(dt),
         Some(config_witness.keypair.pubkey()),
         None,
+        false,
         None,
+        vec![],
     );
     let sig_response = process_command(&config_payer);
 
@@ -95,22 +88,16 @@ fn test_wallet_timestamp_tx() {
 
 #[test]
 fn test_wallet_witness_tx() {
-    let (server, leader_data, alice, ledger_path) = new_validator_for_tests();
+    let (server, leader_data, ledger_path) = new_validator_with_drone_for_tests();
     let bob_pubkey = Pubkey::new_rand();
 
-    let (sender, receiver) = channel();
-    run_local_drone(alice, sender, None);
-    let drone_addr = receiver.recv().unwrap();
-
     let rpc_client = RpcClient::new_socket(leader_data.rpc);
 
     let mut config_payer = WalletConfig::default();
-    config_payer.drone_port = drone_addr.port();
     config_payer.json_rpc_url =
         format!("http://{}:{}", leader_data.rpc.ip(), leader_data.rpc.port());
 
     let mut config_witness = WalletConfig::default();
-    config_witness.drone_port = config_payer.drone_port;
     config_witness.json_rpc_url = config_payer.json_rpc_url.clone();
 
     assert_ne!(
@@ -118,7 +105,8 @@ fn test_wallet_witness_tx() {
         config_witness.keypair.pubkey()
     );
 
-    request_and_confirm_airdrop(&rpc_client, &drone_addr, &config_payer.keypair.pubkey(), 50)
+    rpc_client
+        .request_airdrop(&config_payer.keypair.pubkey(), 50)
         .unwrap();
 
     // Make transaction (from config_payer to bob_pubkey) requiring witness signature from config_witness
@@ -128,7 +116,9 @@ fn test_wallet_witness_tx() {
         None,
         None,
         Some(vec![config_witness.keypair.pubkey()]),
+        false,
         None,
+        vec![],
     );
     let sig_response = process_command(&config_payer);
 
@@ -157,22 +147,16 @@ fn test_wallet_witness_tx() {
 
 #[test]
 fn test_wallet_cancel_tx() {
-    let (server, leader_data, alice, ledger_path) = new_validator_for_tests();
+    let (server, leader_data, ledger_path) = new_validator_with_drone_for_tests();
     let bob_pubkey = Pubkey::new_rand();
 
-    let (sender, receiver) = channel();
-    run_local_drone(alice, sender, None);
-    let drone_addr = receiver.recv().unwrap();
-
     let rpc_client = RpcClient::new_socket(leader_data.rpc);
 
     let mut config_payer = WalletConfig::default();
-    config_payer.drone_port = drone_addr.port();
     config_payer.json_rpc_url =
         format!("http://{}:{}", leader_data.rpc.ip(), leader_data.rpc.port());
 
     let mut config_witness = WalletConfig::default();
-    config_witness.drone_port = config_payer.drone_port;
     config_witness.json_rpc_url = config_payer.json_rpc_url.clone();
 
     assert_ne!(
@@ -180,7 +164,8 @@ fn test_wallet_cancel_tx() {
         config_witness.keypair.pubkey()
     );
 
-    request_and_confirm_airdrop(&rpc_client, &drone_addr, &config_payer.keypair.pubkey(), 50)
+    rpc_client
+        .request_airdrop(&config_payer.keypair.pubkey(), 50)
         .unwrap();
 
     // Make transaction (from config_payer to bob_pubkey) requiring witness signature from config_witness
@@ -190,7 +175,9 @@ fn test_wallet_cancel_tx() {
         None,
         None,
         Some(vec![config_witness.keypair.pubkey()]),
+        false,
         Some(config_payer.keypair.pubkey()),
+        vec![],
     );
     let sig_response = process_command(&config_payer).unwrap();
 
@@ -216,3 +203,66 @@ fn test_wallet_cancel_tx() {
     server.close().unwrap();
     remove_dir_all(ledger_path).unwrap();
 }
+
+#[test]
+fn test_wallet_offline_pay_tx() {
+    let (server, leader_data, ledger_path) = new_validator_with_drone_for_tests();
+    let bob_pubkey = Pubkey::new_rand();
+
+    let rpc_client = RpcClient::new_socket(leader_data.rpc);
+
+    let mut config_offline = WalletConfig::default();
+    config_offline.json_rpc_url =
+        format!("http://{}:{}", leader_data.rpc.ip(), leader_data.rpc.port());
+
+    let mut config_online = WalletConfig::default();
+    config_online.json_rpc_url = config_offline.json_rpc_url.clone();
+    config_online.keypair = Keypair::from_bytes(&config_offline.keypair.to_bytes()).unwrap();
+
+    rpc_client
+        .request_airdrop(&config_offline.keypair.pubkey(), 50)
+        .unwrap();
+    check_balance(50, &rpc_client, &config_offline.keypair.pubkey());
+
+    // Sign without sending
+    let (blockhash, _fee_calculator) = rpc_client.get_recent_blockhash().unwrap();
+    config_offline.command =
+        WalletCommand::Pay(10, bob_pubkey, None, None, None, false, None, vec![]);
+    config_offline.signing = SigningConfig {
+        sign_only: true,
+        blockhash: Some(blockhash),
+        signers: vec![],
+    };
+    let sig_response = process_command(&config_offline).unwrap();
+    check_balance(50, &rpc_client, &config_offline.keypair.pubkey());
+
+    let object: Value = serde_json::from_str(&sig_response).unwrap();
+    let signers: Vec<(Pubkey, Signature)> = object["signers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|signer| {
+            let mut parts = signer.as_str().unwrap().split('=');
+            (
+                parts.next().unwrap().parse().unwrap(),
+                parts.next().unwrap().parse().unwrap(),
+            )
+        })
+        .collect();
+
+    // Send with the offline signatures
+    config_online.command =
+        WalletCommand::Pay(10, bob_pubkey, None, None, None, false, None, vec![]);
+    config_online.signing = SigningConfig {
+        sign_only: false,
+        blockhash: Some(blockhash),
+        signers,
+    };
+    process_command(&config_online).unwrap();
+
+    check_balance(40, &rpc_client, &config_online.keypair.pubkey());
+    check_balance(10, &rpc_client, &bob_pubkey);
+
+    server.close().unwrap();
+    remove_dir_all(ledger_path).unwrap();
+}